@@ -1,21 +1,32 @@
 //! Benchmarking command
+//!
+//! Each scenario runs `--concurrency` workers for `--duration` seconds.
+//! With `--rps`, the workers share one request schedule, and latency is
+//! measured from when a request was scheduled rather than when it was
+//! sent, so a server that falls behind shows up in the percentiles instead
+//! of silently lowering the load (coordinated omission). Scenarios can be
+//! repeated once per Prism profile to compare Classic, Turbo and Hyper.
 
 use anyhow::{Context, Result};
 use bytes::Bytes;
 use clap::Args;
 use futures::stream::{self, StreamExt};
 use hdrhistogram::Histogram;
-use quill_client::QuillClient;
+use quill_client::{HttpProtocol, QuillClient};
+use quill_core::{PrismProfile, ProfilePreference, QuillError};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::Mutex;
+
+/// Highest latency the histograms record, in microseconds (60s)
+const MAX_LATENCY_US: u64 = 60_000_000;
 
 #[derive(Args, Debug)]
 pub struct BenchArgs {
     /// Path to benchmarks.yaml configuration
-    #[arg(short, long, default_value = "benchmarks.yaml")]
+    #[arg(long, default_value = "benchmarks.yaml")]
     pub config: PathBuf,
 
     /// Number of concurrent requests
@@ -26,13 +37,21 @@ pub struct BenchArgs {
     #[arg(short, long, default_value = "10")]
     pub duration: u64,
 
-    /// Target RPS (requests per second)
+    /// Target RPS (requests per second) across all concurrent workers
     #[arg(short, long)]
     pub rps: Option<u64>,
 
+    /// Prism profiles to compare, e.g. classic,turbo,hyper; each scenario runs once per profile
+    #[arg(long, value_delimiter = ',')]
+    pub prism: Vec<PrismProfile>,
+
     /// Output format (text, json)
     #[arg(short, long, default_value = "text")]
     pub output: String,
+
+    /// Also write the JSON report to this file
+    #[arg(long)]
+    pub report: Option<PathBuf>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,12 +71,30 @@ struct BenchmarkScenario {
 #[derive(Debug, Serialize)]
 struct BenchmarkResults {
     scenario: String,
+    /// Prism profile the scenario ran with, if one was selected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<String>,
+    concurrency: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_rps: Option<u64>,
     duration_secs: u64,
     total_requests: u64,
     successful: u64,
     failed: u64,
     rps: f64,
+    /// Response payload bytes received per second
+    throughput_bytes_per_sec: f64,
     latency: LatencyStats,
+    /// UDP offload support of this host, with the `http3` feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    udp_offload: Option<UdpOffloadReport>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct UdpOffloadReport {
+    gso_segments: usize,
+    gro_segments: usize,
+    recv_batch_size: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -70,6 +107,155 @@ struct LatencyStats {
     p999_us: u64,
     max_us: u64,
     mean_us: f64,
+    /// Percentile distribution, halving the distance to 100% at each step
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    histogram: Vec<HistogramBucket>,
+}
+
+/// One step of the latency percentile distribution
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct HistogramBucket {
+    percentile: f64,
+    latency_us: u64,
+    /// Requests with latencies at this step
+    count: u64,
+}
+
+impl LatencyStats {
+    fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        let buckets = histogram
+            .iter_quantiles(1)
+            .map(|step| HistogramBucket {
+                percentile: step.quantile_iterated_to() * 100.0,
+                latency_us: histogram.highest_equivalent(step.value_iterated_to()),
+                count: step.count_since_last_iteration(),
+            })
+            .collect();
+
+        Self {
+            min_us: histogram.min(),
+            p50_us: histogram.value_at_quantile(0.50),
+            p90_us: histogram.value_at_quantile(0.90),
+            p95_us: histogram.value_at_quantile(0.95),
+            p99_us: histogram.value_at_quantile(0.99),
+            p999_us: histogram.value_at_quantile(0.999),
+            max_us: histogram.max(),
+            mean_us: histogram.mean(),
+            histogram: buckets,
+        }
+    }
+}
+
+/// Request schedule shared by all workers of a rate-limited scenario
+struct Pacer {
+    start: Instant,
+    end: Instant,
+    interval: Option<Duration>,
+    next: AtomicU64,
+}
+
+impl Pacer {
+    fn new(start: Instant, duration: Duration, rps: Option<u64>) -> Self {
+        let interval = rps.filter(|rps| *rps > 0).map(|rps| Duration::from_secs(1) / rps as u32);
+        Self { start, end: start + duration, interval, next: AtomicU64::new(0) }
+    }
+
+    /// Wait for the next request slot, returning when the request was due
+    ///
+    /// Returns `None` once the scenario's duration is over.
+    async fn next_slot(&self) -> Option<Instant> {
+        let Some(interval) = self.interval else {
+            let now = Instant::now();
+            return (now < self.end).then_some(now);
+        };
+        let slot = self.next.fetch_add(1, Ordering::Relaxed);
+        let due = self.start + interval * slot as u32;
+        if due >= self.end {
+            return None;
+        }
+        tokio::time::sleep_until(due.into()).await;
+        Some(due)
+    }
+}
+
+/// Client speaking the transport of the selected Prism profile
+enum BenchClient {
+    Http(QuillClient),
+    #[cfg(feature = "http3")]
+    H3(quill_client::QuillH3Client),
+}
+
+impl BenchClient {
+    async fn connect(url: &str, profile: Option<PrismProfile>) -> Result<Self> {
+        let mut builder = QuillClient::builder().base_url(url);
+        match profile {
+            Some(PrismProfile::Classic) => builder = builder.http_protocol(HttpProtocol::Http1),
+            Some(PrismProfile::Turbo) => builder = builder.http2_only(),
+            Some(PrismProfile::Hyper) => return Self::connect_h3(url).await,
+            None => {}
+        }
+        if let Some(profile) = profile {
+            builder = builder.profile_preference(ProfilePreference::new(vec![profile]));
+        }
+        Ok(Self::Http(builder.build().map_err(|e| anyhow::anyhow!(e))?))
+    }
+
+    #[cfg(feature = "http3")]
+    async fn connect_h3(url: &str) -> Result<Self> {
+        let url = url::Url::parse(url).with_context(|| format!("Invalid URL: {}", url))?;
+        let host = url.host_str().context("URL has no host")?;
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addr = tokio::net::lookup_host((host, port))
+            .await
+            .with_context(|| format!("Failed to resolve {}", host))?
+            .next()
+            .with_context(|| format!("No address found for {}", host))?;
+        let client = quill_client::QuillH3Client::builder(addr)
+            .profile_preference(ProfilePreference::new(vec![PrismProfile::Hyper]))
+            .build()?;
+        Ok(Self::H3(client))
+    }
+
+    #[cfg(not(feature = "http3"))]
+    async fn connect_h3(_url: &str) -> Result<Self> {
+        anyhow::bail!("The hyper profile needs quill built with the `http3` feature")
+    }
+
+    async fn call(&self, service: &str, method: &str, request: Bytes) -> Result<Bytes, QuillError> {
+        match self {
+            Self::Http(client) => client.call(service, method, request).await,
+            #[cfg(feature = "http3")]
+            Self::H3(client) => client.call(service, method, request).await,
+        }
+    }
+}
+
+/// Counts and latencies of one worker, merged once the scenario ends
+struct WorkerStats {
+    histogram: Histogram<u64>,
+    successful: u64,
+    failed: u64,
+    bytes_received: u64,
+}
+
+impl WorkerStats {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            histogram: Histogram::new_with_max(MAX_LATENCY_US, 3)
+                .context("Failed to create histogram")?,
+            successful: 0,
+            failed: 0,
+            bytes_received: 0,
+        })
+    }
+
+    fn merge(&mut self, other: &WorkerStats) -> Result<()> {
+        self.histogram.add(&other.histogram).context("Failed to merge histograms")?;
+        self.successful += other.successful;
+        self.failed += other.failed;
+        self.bytes_received += other.bytes_received;
+        Ok(())
+    }
 }
 
 pub async fn run(args: BenchArgs) -> Result<()> {
@@ -80,36 +266,60 @@ pub async fn run(args: BenchArgs) -> Result<()> {
     }
 
     // Parse configuration
-    let config_str = std::fs::read_to_string(&args.config)
-        .context("Failed to read benchmarks.yaml")?;
-    let config: BenchmarkConfig = serde_yaml::from_str(&config_str)
-        .context("Failed to parse benchmarks.yaml")?;
-
+    let config_str =
+        std::fs::read_to_string(&args.config).context("Failed to read benchmarks.yaml")?;
+    let config: BenchmarkConfig =
+        serde_yaml::from_str(&config_str).context("Failed to parse benchmarks.yaml")?;
+
+    let udp_offload = detect_udp_offload();
+    let profiles: Vec<Option<PrismProfile>> = if args.prism.is_empty() {
+        vec![None]
+    } else {
+        args.prism.iter().copied().map(Some).collect()
+    };
     let mut all_results = Vec::new();
 
-    // Run each scenario
+    // Run each scenario; progress goes to stderr so JSON output stays parseable
     for scenario in config.benchmarks {
-        println!("\nRunning scenario: {}", scenario.name);
-        println!("  URL: {}", scenario.url);
-        println!("  Service: {}", scenario.service);
-        println!  ("  Method: {}", scenario.method);
-        println!("  Concurrency: {}", args.concurrency);
-        println!("  Duration: {}s", args.duration);
-
-        let results = run_scenario(&scenario, &args).await?;
-        all_results.push(results);
+        for profile in &profiles {
+            eprintln!("\nRunning scenario: {}", scenario.name);
+            eprintln!("  URL: {}", scenario.url);
+            eprintln!("  Service: {}", scenario.service);
+            eprintln!("  Method: {}", scenario.method);
+            if let Some(profile) = profile {
+                eprintln!("  Profile: {}", profile);
+            }
+            eprintln!("  Concurrency: {}", args.concurrency);
+            eprintln!("  Duration: {}s", args.duration);
+            if let Some(rps) = args.rps {
+                eprintln!("  Target RPS: {}", rps);
+            }
+
+            let mut results = run_scenario(&scenario, *profile, &args).await?;
+            results.udp_offload = udp_offload;
+            all_results.push(results);
+        }
+    }
+
+    if let Some(path) = &args.report {
+        let json = serde_json::to_string_pretty(&all_results)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write report: {}", path.display()))?;
     }
 
     // Output results
     match args.output.as_str() {
         "json" => {
             let json = serde_json::to_string_pretty(&all_results)?;
-            println!("\n{}", json);
+            println!("{}", json);
         }
         _ => {
             for result in &all_results {
                 print_results(result);
             }
+            if profiles.len() > 1 {
+                print_comparison(&all_results);
+            }
         }
     }
 
@@ -118,7 +328,8 @@ pub async fn run(args: BenchArgs) -> Result<()> {
 
 async fn run_adhoc_benchmark(_args: BenchArgs) -> Result<()> {
     println!("No benchmarks.yaml found. Example configuration:");
-    println!(r#"
+    println!(
+        r#"
 benchmarks:
   - name: "Echo Service"
     url: "http://localhost:8080"
@@ -126,141 +337,107 @@ benchmarks:
     method: "Echo"
     payload:
       message: "Hello, World!"
-"#);
+"#
+    );
 
     anyhow::bail!("Please create a benchmarks.yaml configuration file");
 }
 
-async fn run_scenario(scenario: &BenchmarkScenario, args: &BenchArgs) -> Result<BenchmarkResults> {
-    // Create client
-    let client = Arc::new(
-        QuillClient::builder()
-            .base_url(&scenario.url)
-            .build()
-            .map_err(|e| anyhow::anyhow!(e))?
-    );
+async fn run_scenario(
+    scenario: &BenchmarkScenario,
+    profile: Option<PrismProfile>,
+    args: &BenchArgs,
+) -> Result<BenchmarkResults> {
+    let client = Arc::new(BenchClient::connect(&scenario.url, profile).await?);
 
     // Serialize payload
-    let payload_bytes = serde_json::to_vec(&scenario.payload)?;
-
-    // Create histogram for latency tracking
-    let histogram = Arc::new(Mutex::new(
-        Histogram::<u64>::new_with_max(60_000_000, 3)
-            .context("Failed to create histogram")?,
-    ));
-
-    let total_requests = Arc::new(Mutex::new(0u64));
-    let successful = Arc::new(Mutex::new(0u64));
-    let failed = Arc::new(Mutex::new(0u64));
+    let payload = Bytes::from(serde_json::to_vec(&scenario.payload)?);
 
     let start = Instant::now();
-    let duration = Duration::from_secs(args.duration);
+    let pacer = Arc::new(Pacer::new(start, Duration::from_secs(args.duration), args.rps));
 
-    // Calculate delay between requests if RPS is specified
-    let delay_per_request = args.rps.map(|rps| {
-        Duration::from_micros((1_000_000.0 / rps as f64) as u64)
-    });
-
-    // Run concurrent requests
-    stream::iter(0..args.concurrency)
-        .for_each_concurrent(args.concurrency, |_| {
+    // Run concurrent workers, each recording into its own histogram
+    let workers: Vec<Result<WorkerStats>> = stream::iter(0..args.concurrency.max(1))
+        .map(|_| {
             let client = Arc::clone(&client);
-            let service = scenario.service.clone();
-            let method = scenario.method.clone();
-            let payload = Bytes::from(payload_bytes.clone());
-            let histogram = histogram.clone();
-            let total = total_requests.clone();
-            let success = successful.clone();
-            let fail = failed.clone();
-
+            let pacer = Arc::clone(&pacer);
+            let payload = payload.clone();
             async move {
-                let mut last_request = Instant::now();
-
-                while start.elapsed() < duration {
-                    // Rate limiting
-                    if let Some(delay) = delay_per_request {
-                        let elapsed = last_request.elapsed();
-                        if elapsed < delay {
-                            tokio::time::sleep(delay - elapsed).await;
-                        }
-                    }
-
-                    last_request = Instant::now();
-                    let req_start = Instant::now();
-
-                    // Make request
-                    let result = client
-                        .call(&service, &method, payload.clone())
-                        .await;
-
-                    let latency_us = req_start.elapsed().as_micros() as u64;
-
-                    // Record results
-                    {
-                        let mut hist = histogram.lock().await;
-                        let _ = hist.record(latency_us);
-                    }
-
-                    {
-                        let mut total = total.lock().await;
-                        *total += 1;
-                    }
+                let mut stats = WorkerStats::new()?;
+                while let Some(due) = pacer.next_slot().await {
+                    let result =
+                        client.call(&scenario.service, &scenario.method, payload.clone()).await;
+                    let latency_us = due.elapsed().as_micros() as u64;
+                    stats.histogram.saturating_record(latency_us.min(MAX_LATENCY_US));
 
                     match result {
-                        Ok(_) => {
-                            let mut success = success.lock().await;
-                            *success += 1;
-                        }
-                        Err(_) => {
-                            let mut fail = fail.lock().await;
-                            *fail += 1;
+                        Ok(response) => {
+                            stats.successful += 1;
+                            stats.bytes_received += response.len() as u64;
                         }
+                        Err(_) => stats.failed += 1,
                     }
                 }
+                Ok(stats)
             }
         })
+        .buffer_unordered(args.concurrency.max(1))
+        .collect()
         .await;
 
     let elapsed = start.elapsed();
-    let total = *total_requests.lock().await;
-    let success = *successful.lock().await;
-    let fail = *failed.lock().await;
-
-    // Calculate statistics
-    let histogram = histogram.lock().await;
-    let latency = LatencyStats {
-        min_us: histogram.min(),
-        p50_us: histogram.value_at_quantile(0.50),
-        p90_us: histogram.value_at_quantile(0.90),
-        p95_us: histogram.value_at_quantile(0.95),
-        p99_us: histogram.value_at_quantile(0.99),
-        p999_us: histogram.value_at_quantile(0.999),
-        max_us: histogram.max(),
-        mean_us: histogram.mean(),
-    };
-
-    let rps = total as f64 / elapsed.as_secs_f64();
+    let mut totals = WorkerStats::new()?;
+    for worker in workers {
+        totals.merge(&worker?)?;
+    }
+    let total = totals.successful + totals.failed;
 
     Ok(BenchmarkResults {
         scenario: scenario.name.clone(),
+        profile: profile.map(|profile| profile.to_string()),
+        concurrency: args.concurrency,
+        target_rps: args.rps,
         duration_secs: elapsed.as_secs(),
         total_requests: total,
-        successful: success,
-        failed: fail,
-        rps,
-        latency,
+        successful: totals.successful,
+        failed: totals.failed,
+        rps: total as f64 / elapsed.as_secs_f64(),
+        throughput_bytes_per_sec: totals.bytes_received as f64 / elapsed.as_secs_f64(),
+        latency: LatencyStats::from_histogram(&totals.histogram),
+        udp_offload: None,
     })
 }
 
+/// Host UDP offload support, so QUIC throughput can be compared across machines
+#[cfg(feature = "http3")]
+fn detect_udp_offload() -> Option<UdpOffloadReport> {
+    let support = quill_client::UdpOffloadSupport::detect().ok()?;
+    Some(UdpOffloadReport {
+        gso_segments: support.max_gso_segments,
+        gro_segments: support.gro_segments,
+        recv_batch_size: support.recv_batch_size,
+    })
+}
+
+#[cfg(not(feature = "http3"))]
+fn detect_udp_offload() -> Option<UdpOffloadReport> {
+    None
+}
+
 fn print_results(results: &BenchmarkResults) {
     println!("\n========================================");
     println!("Scenario: {}", results.scenario);
+    if let Some(profile) = &results.profile {
+        println!("Profile:  {}", profile);
+    }
     println!("========================================");
     println!("Duration:        {}s", results.duration_secs);
+    println!("Concurrency:     {}", results.concurrency);
     println!("Total Requests:  {}", results.total_requests);
     println!("Successful:      {}", results.successful);
     println!("Failed:          {}", results.failed);
     println!("RPS:             {:.2}", results.rps);
+    println!("Throughput:      {:.2} KiB/s", results.throughput_bytes_per_sec / 1024.0);
     println!();
     println!("Latency Statistics (microseconds):");
     println!("  Min:     {:>10}", results.latency.min_us);
@@ -276,11 +453,53 @@ fn print_results(results: &BenchmarkResults) {
     println!("  p50:     {:>10.2}", results.latency.p50_us as f64 / 1000.0);
     println!("  p95:     {:>10.2}", results.latency.p95_us as f64 / 1000.0);
     println!("  p99:     {:>10.2}", results.latency.p99_us as f64 / 1000.0);
+    if !results.latency.histogram.is_empty() {
+        println!();
+        println!("Latency Distribution:");
+        println!("  {:>10}  {:>12}  {:>10}", "Percentile", "Latency (us)", "Count");
+        for bucket in &results.latency.histogram {
+            println!(
+                "  {:>9.3}%  {:>12}  {:>10}",
+                bucket.percentile, bucket.latency_us, bucket.count
+            );
+        }
+    }
+    if let Some(offload) = &results.udp_offload {
+        println!();
+        println!("UDP Offload (host, 1 = unsupported):");
+        println!("  GSO segments:   {:>6}", offload.gso_segments);
+        println!("  GRO segments:   {:>6}", offload.gro_segments);
+        println!("  Receive batch:  {:>6}", offload.recv_batch_size);
+    }
+}
+
+/// Side-by-side summary of scenarios run with several profiles
+fn print_comparison(results: &[BenchmarkResults]) {
+    println!("\n========================================");
+    println!("Profile Comparison");
+    println!("========================================");
+    println!(
+        "{:<24} {:<8} {:>10} {:>10} {:>10} {:>10} {:>8}",
+        "Scenario", "Profile", "RPS", "p50 (us)", "p99 (us)", "p999 (us)", "Failed"
+    );
+    for result in results {
+        println!(
+            "{:<24} {:<8} {:>10.1} {:>10} {:>10} {:>10} {:>8}",
+            result.scenario,
+            result.profile.as_deref().unwrap_or("-"),
+            result.rps,
+            result.latency.p50_us,
+            result.latency.p99_us,
+            result.latency.p999_us,
+            result.failed
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
 
     #[test]
     fn test_bench_args() {
@@ -289,7 +508,9 @@ mod tests {
             concurrency: 100,
             duration: 30,
             rps: Some(1000),
+            prism: vec![PrismProfile::Classic, PrismProfile::Turbo],
             output: "json".to_string(),
+            report: None,
         };
 
         assert_eq!(args.concurrency, 100);
@@ -297,6 +518,24 @@ mod tests {
         assert_eq!(args.rps, Some(1000));
     }
 
+    #[test]
+    fn test_prism_profiles_parse_as_list() {
+        #[derive(Parser)]
+        struct Cli {
+            #[command(flatten)]
+            bench: BenchArgs,
+        }
+
+        let cli = Cli::try_parse_from(["bench", "--prism", "classic,turbo,hyper", "--rps", "500"])
+            .unwrap();
+        assert_eq!(
+            cli.bench.prism,
+            vec![PrismProfile::Classic, PrismProfile::Turbo, PrismProfile::Hyper]
+        );
+        assert_eq!(cli.bench.rps, Some(500));
+        assert!(Cli::try_parse_from(["bench", "--prism", "warp"]).is_err());
+    }
+
     #[test]
     fn test_latency_stats_serialization() {
         let stats = LatencyStats {
@@ -308,22 +547,72 @@ mod tests {
             p999_us: 999,
             max_us: 1000,
             mean_us: 550.0,
+            histogram: Vec::new(),
         };
 
         let json = serde_json::to_string(&stats).unwrap();
         assert!(json.contains("\"min_us\":100"));
         assert!(json.contains("\"p99_us\":990"));
+        assert!(!json.contains("histogram"));
+    }
+
+    #[test]
+    fn test_latency_distribution() {
+        let mut histogram = Histogram::<u64>::new_with_max(MAX_LATENCY_US, 3).unwrap();
+        for latency in 1..=1000 {
+            histogram.record(latency).unwrap();
+        }
+
+        let stats = LatencyStats::from_histogram(&histogram);
+        assert_eq!(stats.p50_us, 500);
+        assert_eq!(stats.p999_us, 999);
+
+        let buckets = &stats.histogram;
+        assert_eq!(buckets.iter().map(|bucket| bucket.count).sum::<u64>(), 1000);
+        assert!(buckets.windows(2).all(|pair| pair[0].percentile <= pair[1].percentile));
+        assert!(buckets.windows(2).all(|pair| pair[0].latency_us <= pair[1].latency_us));
+        assert_eq!(buckets.last().unwrap().percentile, 100.0);
+        assert_eq!(buckets.last().unwrap().latency_us, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_pacer_spreads_rate_across_workers() {
+        let start = Instant::now();
+        let pacer = Arc::new(Pacer::new(start, Duration::from_millis(200), Some(100)));
+
+        // Ten workers share 20 slots rather than taking 20 each
+        let slots: Vec<Vec<Instant>> = futures::future::join_all((0..10).map(|_| {
+            let pacer = Arc::clone(&pacer);
+            async move {
+                let mut slots = Vec::new();
+                while let Some(due) = pacer.next_slot().await {
+                    slots.push(due);
+                }
+                slots
+            }
+        }))
+        .await;
+
+        let mut slots: Vec<Instant> = slots.into_iter().flatten().collect();
+        assert_eq!(slots.len(), 20);
+        slots.sort();
+        assert_eq!(slots[1] - slots[0], Duration::from_millis(10));
+        assert!(*slots.last().unwrap() < start + Duration::from_millis(200));
     }
 
     #[test]
     fn test_benchmark_results_serialization() {
         let results = BenchmarkResults {
             scenario: "Test".to_string(),
+            profile: None,
+            concurrency: 10,
+            target_rps: None,
             duration_secs: 10,
             total_requests: 1000,
             successful: 990,
             failed: 10,
             rps: 100.0,
+            throughput_bytes_per_sec: 2048.0,
             latency: LatencyStats {
                 min_us: 100,
                 p50_us: 500,
@@ -333,11 +622,35 @@ mod tests {
                 p999_us: 999,
                 max_us: 1000,
                 mean_us: 550.0,
+                histogram: vec![HistogramBucket {
+                    percentile: 100.0,
+                    latency_us: 1000,
+                    count: 1000,
+                }],
             },
+            udp_offload: None,
         };
 
         let json = serde_json::to_string(&results).unwrap();
         assert!(json.contains("\"scenario\":\"Test\""));
         assert!(json.contains("\"rps\":100.0"));
+        assert!(json.contains("\"throughput_bytes_per_sec\":2048.0"));
+        assert!(json
+            .contains("\"histogram\":[{\"percentile\":100.0,\"latency_us\":1000,\"count\":1000}]"));
+        assert!(!json.contains("udp_offload"));
+        assert!(!json.contains("profile"));
+
+        let results = BenchmarkResults {
+            profile: Some("turbo".to_string()),
+            udp_offload: Some(UdpOffloadReport {
+                gso_segments: 64,
+                gro_segments: 64,
+                recv_batch_size: 32,
+            }),
+            ..results
+        };
+        let json = serde_json::to_string(&results).unwrap();
+        assert!(json.contains("\"profile\":\"turbo\""));
+        assert!(json.contains("\"udp_offload\":{\"gso_segments\":64"));
     }
 }
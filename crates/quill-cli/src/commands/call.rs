@@ -8,12 +8,13 @@ use http::header::{HeaderName, HeaderValue, AUTHORIZATION};
 use prost::Message;
 use prost_reflect::{DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor};
 use quill_client::{QuillClient, RequestOptions};
-use quill_core::{PrismProfile, ProfilePreference};
+use quill_core::{PrismProfile, ProfilePreference, QuillError};
 use serde_json::Value;
 use std::env;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 
 #[derive(Debug, Clone, ValueEnum)]
 pub enum InputFormat {
@@ -41,6 +42,8 @@ pub enum OutputFormat {
     Json,
     /// Decode the response to pretty-printed JSON.
     JsonPretty,
+    /// Decode each response message to one line of compact JSON.
+    Ndjson,
     /// Print the response bytes as hexadecimal.
     Hex,
     /// Print the response bytes as base64.
@@ -60,10 +63,14 @@ pub struct CallArgs {
     #[arg(short = 'H', long = "headers", visible_alias = "header")]
     pub headers: Vec<String>,
 
-    /// Enable server streaming mode.
+    /// Enable server streaming mode (detected from descriptors when available).
     #[arg(long)]
     pub stream: bool,
 
+    /// Send one request message per input line as a client stream (detected from descriptors when available).
+    #[arg(long)]
+    pub client_stream: bool,
+
     /// Accept header value.
     #[arg(long, default_value = "application/proto")]
     pub accept: String,
@@ -88,12 +95,16 @@ pub struct CallArgs {
     #[arg(long)]
     pub descriptor_set: Option<PathBuf>,
 
+    /// Fetch descriptors from the server's reflection service instead of --descriptor-set.
+    #[arg(long, conflicts_with = "descriptor_set")]
+    pub reflect: bool,
+
     /// Format for request input.
     #[arg(long, value_enum, default_value = "auto")]
     pub input_format: InputFormat,
 
     /// Format for response output.
-    #[arg(long, value_enum, default_value = "auto")]
+    #[arg(long, visible_alias = "output", value_enum, default_value = "auto")]
     pub output_format: OutputFormat,
 }
 
@@ -108,6 +119,8 @@ struct Endpoint {
 struct MethodDescriptors {
     input: MessageDescriptor,
     output: MessageDescriptor,
+    client_streaming: bool,
+    server_streaming: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -123,12 +136,6 @@ enum RenderedOutput {
 
 pub async fn run(args: CallArgs) -> Result<()> {
     let endpoint = resolve_endpoint(&args.url)?;
-    let descriptors = load_method_descriptors(args.descriptor_set.as_deref(), &endpoint)?;
-    let input = read_input_data(args.input.as_deref(), &args.input_format).await?;
-    let request_bytes = encode_request_payload(&input, &args.input_format, descriptors.as_ref())?;
-
-    let timeout = resolve_timeout(args.timeout)?;
-    let request_options = build_request_options(&args, timeout)?;
 
     let mut client_builder = QuillClient::builder().base_url(&endpoint.base_url);
     if args.compress {
@@ -137,47 +144,98 @@ pub async fn run(args: CallArgs) -> Result<()> {
     let client =
         client_builder.build().map_err(|e| anyhow::anyhow!("Failed to build client: {}", e))?;
 
-    if args.stream {
-        let mut stream = client
-            .call_server_streaming_with_options(
-                &endpoint.service,
-                &endpoint.method,
-                request_bytes,
-                request_options,
-            )
-            .await
-            .context("RPC call failed")?;
-
-        use futures::StreamExt;
-        while let Some(result) = stream.next().await {
-            let bytes = result.context("Stream error")?;
-            let rendered = render_output(
-                &bytes,
-                &args.output_format,
-                args.pretty,
-                descriptors.as_ref().map(|d| &d.output),
-            )?;
-            write_rendered_output(rendered, true).await?;
-        }
+    let descriptors = if args.reflect {
+        Some(reflect_method_descriptors(&client, &endpoint).await?)
     } else {
-        let response = client
-            .call_with_options(&endpoint.service, &endpoint.method, request_bytes, request_options)
-            .await
-            .context("RPC call failed")?;
-
-        let rendered = render_output(
-            &response,
-            &args.output_format,
-            args.pretty,
-            descriptors.as_ref().map(|d| &d.output),
-        )?;
-        let append_newline = !matches!(rendered, RenderedOutput::Binary(_));
-        write_rendered_output(rendered, append_newline).await?;
+        load_method_descriptors(args.descriptor_set.as_deref(), &endpoint)?
+    };
+
+    let timeout = resolve_timeout(args.timeout)?;
+    let request_options = build_request_options(&args, timeout)?;
+
+    let client_streaming =
+        args.client_stream || descriptors.as_ref().is_some_and(|d| d.client_streaming);
+    let server_streaming = args.stream || descriptors.as_ref().is_some_and(|d| d.server_streaming);
+    let output_descriptor = descriptors.as_ref().map(|d| &d.output);
+
+    let mut responses = match (client_streaming, server_streaming) {
+        (false, false) => {
+            let input = read_input_data(args.input.as_deref(), &args.input_format).await?;
+            let request_bytes =
+                encode_request_payload(&input, &args.input_format, descriptors.as_ref())?;
+            let response = client
+                .call_with_options(
+                    &endpoint.service,
+                    &endpoint.method,
+                    request_bytes,
+                    request_options,
+                )
+                .await
+                .context("RPC call failed")?;
+            return write_single_response(&response, &args, output_descriptor).await;
+        }
+        (true, false) => {
+            let requests = request_message_stream(&args, descriptors.clone()).await?;
+            let response = client
+                .call_client_streaming_with_options(
+                    &endpoint.service,
+                    &endpoint.method,
+                    requests,
+                    request_options,
+                )
+                .await
+                .context("RPC call failed")?;
+            return write_single_response(&response, &args, output_descriptor).await;
+        }
+        (false, true) => {
+            let input = read_input_data(args.input.as_deref(), &args.input_format).await?;
+            let request_bytes =
+                encode_request_payload(&input, &args.input_format, descriptors.as_ref())?;
+            client
+                .call_server_streaming_with_options(
+                    &endpoint.service,
+                    &endpoint.method,
+                    request_bytes,
+                    request_options,
+                )
+                .await
+                .context("RPC call failed")?
+        }
+        (true, true) => {
+            let requests = request_message_stream(&args, descriptors.clone()).await?;
+            client
+                .call_bidi_streaming_with_options(
+                    &endpoint.service,
+                    &endpoint.method,
+                    requests,
+                    request_options,
+                )
+                .await
+                .context("RPC call failed")?
+        }
+    };
+
+    // Each message is printed as soon as it arrives
+    use futures::StreamExt;
+    while let Some(result) = responses.next().await {
+        let bytes = result.context("Stream error")?;
+        let rendered = render_output(&bytes, &args.output_format, args.pretty, output_descriptor)?;
+        write_rendered_output(rendered, true).await?;
     }
 
     Ok(())
 }
 
+async fn write_single_response(
+    response: &[u8],
+    args: &CallArgs,
+    descriptor: Option<&MessageDescriptor>,
+) -> Result<()> {
+    let rendered = render_output(response, &args.output_format, args.pretty, descriptor)?;
+    let append_newline = !matches!(rendered, RenderedOutput::Binary(_));
+    write_rendered_output(rendered, append_newline).await
+}
+
 fn resolve_endpoint(endpoint: &str) -> Result<Endpoint> {
     if let Ok(url) = url::Url::parse(endpoint) {
         return parse_absolute_endpoint(&url);
@@ -232,35 +290,49 @@ fn load_method_descriptors(
 
     let bytes = std::fs::read(path)
         .with_context(|| format!("Failed to read descriptor set: {}", path.display()))?;
-    let pool = DescriptorPool::decode(bytes.as_slice())
-        .with_context(|| format!("Failed to parse descriptor set: {}", path.display()))?;
+    let source = format!("descriptor set {}", path.display());
+    find_method_descriptors(&bytes, endpoint, &source).map(Some)
+}
+
+async fn reflect_method_descriptors(
+    client: &QuillClient,
+    endpoint: &Endpoint,
+) -> Result<MethodDescriptors> {
+    let bytes = client
+        .file_descriptor_set(Some(&endpoint.service))
+        .await
+        .context("Failed to fetch descriptors from the server's reflection service")?;
+    find_method_descriptors(&bytes, endpoint, "descriptors reflected by the server")
+}
+
+fn find_method_descriptors(
+    bytes: &[u8],
+    endpoint: &Endpoint,
+    source: &str,
+) -> Result<MethodDescriptors> {
+    let pool =
+        DescriptorPool::decode(bytes).with_context(|| format!("Failed to parse {}", source))?;
 
     let service = pool
         .services()
         .find(|descriptor| {
             descriptor.full_name() == endpoint.service || descriptor.name() == endpoint.service
         })
-        .with_context(|| {
-            format!(
-                "Service '{}' was not found in descriptor set {}",
-                endpoint.service,
-                path.display()
-            )
-        })?;
+        .with_context(|| format!("Service '{}' was not found in {}", endpoint.service, source))?;
 
     let method = service
         .methods()
         .find(|descriptor| descriptor.name() == endpoint.method)
         .with_context(|| {
-            format!(
-                "Method '{}.{}' was not found in descriptor set {}",
-                endpoint.service,
-                endpoint.method,
-                path.display()
-            )
+            format!("Method '{}.{}' was not found in {}", endpoint.service, endpoint.method, source)
         })?;
 
-    Ok(Some(MethodDescriptors { input: method.input(), output: method.output() }))
+    Ok(MethodDescriptors {
+        input: method.input(),
+        output: method.output(),
+        client_streaming: method.is_client_streaming(),
+        server_streaming: method.is_server_streaming(),
+    })
 }
 
 async fn read_input_data(input: Option<&str>, format: &InputFormat) -> Result<InputData> {
@@ -289,6 +361,56 @@ async fn read_input_data(input: Option<&str>, format: &InputFormat) -> Result<In
     Ok(InputData { bytes, text })
 }
 
+/// Request messages of a client stream, one per non-empty input line
+///
+/// Lines from stdin are sent as they are read, so bidirectional calls can
+/// be driven interactively.
+async fn request_message_stream(
+    args: &CallArgs,
+    descriptors: Option<MethodDescriptors>,
+) -> Result<Pin<Box<dyn futures::Stream<Item = Result<Bytes, QuillError>> + Send>>> {
+    use futures::StreamExt;
+
+    let format = args.input_format.clone();
+    let encode = move |line: String| {
+        let input = InputData { bytes: line.clone().into_bytes(), text: Some(line) };
+        encode_request_payload(&input, &format, descriptors.as_ref())
+            .map_err(|e| QuillError::Rpc(format!("Invalid request message: {:#}", e)))
+    };
+
+    let reads_file = matches!(args.input_format, InputFormat::File)
+        || args.input.as_deref().is_some_and(|value| value.starts_with('@'));
+    if args.input.is_some() || reads_file {
+        let input = read_input_data(args.input.as_deref(), &args.input_format).await?;
+        let text = input.text.context("Streamed request input must be UTF-8 lines")?;
+        let lines: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        return Ok(Box::pin(futures::stream::iter(lines).map(encode)));
+    }
+
+    let lines = BufReader::new(tokio::io::stdin()).lines();
+    let lines = futures::stream::unfold(lines, |mut lines| async move {
+        match lines.next_line().await {
+            Ok(Some(line)) => Some((Ok(line), lines)),
+            Ok(None) => None,
+            Err(e) => {
+                Some((Err(QuillError::Transport(format!("Failed to read stdin: {}", e))), lines))
+            }
+        }
+    });
+    let messages = lines
+        .filter(|line| {
+            let skip = matches!(line, Ok(line) if line.trim().is_empty());
+            futures::future::ready(!skip)
+        })
+        .map(move |line| line.and_then(|line| encode(line.trim().to_string())));
+    Ok(Box::pin(messages))
+}
+
 fn encode_request_payload(
     input: &InputData,
     format: &InputFormat,
@@ -306,8 +428,9 @@ fn encode_request_payload(
             Ok(Bytes::from(input.bytes.clone()))
         }
         InputFormat::Json => {
-            let descriptors = descriptors
-                .context("--descriptor-set is required when using --input-format json")?;
+            let descriptors = descriptors.context(
+                "--descriptor-set or --reflect is required when using --input-format json",
+            )?;
             let text = input.text.as_deref().context("JSON input must be valid UTF-8")?;
             encode_json_payload(text, &descriptors.input)
         }
@@ -440,6 +563,7 @@ fn render_output(
         OutputFormat::Base64 => Ok(RenderedOutput::Text(BASE64.encode(response))),
         OutputFormat::Json => render_json_output(response, descriptor, pretty),
         OutputFormat::JsonPretty => render_json_output(response, descriptor, true),
+        OutputFormat::Ndjson => render_json_output(response, descriptor, false),
         OutputFormat::Auto => {
             if let Some(descriptor) = descriptor {
                 if let Some(value) = try_decode_json_value(response, Some(descriptor)) {
@@ -471,7 +595,7 @@ fn render_json_output(
         decode_descriptor_json(response, descriptor)?
     } else {
         serde_json::from_slice::<Value>(response).context(
-            "JSON output requested but the response is not JSON. Provide --descriptor-set to decode protobuf responses.",
+            "JSON output requested but the response is not JSON. Provide --descriptor-set or --reflect to decode protobuf responses.",
         )?
     };
 
@@ -559,4 +683,13 @@ mod tests {
             RenderedOutput::Binary(_) => panic!("expected text output"),
         }
     }
+
+    #[test]
+    fn test_render_ndjson_output_ignores_pretty() {
+        let rendered = render_output(b"{ \"a\": 1 }", &OutputFormat::Ndjson, true, None).unwrap();
+        match rendered {
+            RenderedOutput::Text(text) => assert_eq!(text, r#"{"a":1}"#),
+            RenderedOutput::Binary(_) => panic!("expected text output"),
+        }
+    }
 }
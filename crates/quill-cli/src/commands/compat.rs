@@ -1,27 +1,44 @@
 //! Compatibility checking command
 //!
 //! Uses `buf` CLI for breaking change detection when available,
-//! with fallback to basic proto file comparison.
+//! with fallback to basic proto file comparison. Against a baseline
+//! descriptor set, the built-in semantic rules run instead; see
+//! [`semantic`].
+
+mod semantic;
 
 use anyhow::{Context, Result};
 use clap::Args;
+use semantic::{RuleConfig, Severity};
 use std::path::PathBuf;
 use std::process::Command;
 
 #[derive(Args, Debug)]
 pub struct CompatArgs {
-    /// Reference to compare against (git ref, registry URL, or local path)
-    #[arg(short, long)]
-    pub against: String,
+    /// Reference to compare against (git ref, registry URL, local path, or baseline descriptor set)
+    #[arg(short, long, required_unless_present = "write_baseline")]
+    pub against: Option<String>,
 
-    /// Proto files or directories to check (defaults to current directory)
+    /// Proto files, directories or a descriptor set to check (defaults to current directory)
     #[arg(default_value = ".")]
     pub input: Vec<String>,
 
-    /// Fail on breaking changes (exit code 2)
+    /// Include directories for proto imports when compiling against a baseline
+    #[arg(short = 'I', long = "include")]
+    pub includes: Vec<PathBuf>,
+
+    /// Fail on breaking changes (exit code 2); against a baseline, fail on warnings too
     #[arg(long)]
     pub strict: bool,
 
+    /// Override the severity of a baseline rule, e.g. FIELD_RENAMED=error (off, warning, error)
+    #[arg(long = "rule", value_name = "RULE=SEVERITY")]
+    pub rules: Vec<String>,
+
+    /// Write the input's descriptor set to this file as a baseline and exit
+    #[arg(long, conflicts_with = "against")]
+    pub write_baseline: Option<PathBuf>,
+
     /// Path to buf.yaml config file
     #[arg(long)]
     pub config: Option<PathBuf>,
@@ -45,7 +62,7 @@ fn buf_available() -> bool {
 }
 
 /// Run compatibility check using buf CLI
-fn run_buf_breaking(args: &CompatArgs) -> Result<BreakingResult> {
+fn run_buf_breaking(args: &CompatArgs, against: &str) -> Result<BreakingResult> {
     let mut cmd = Command::new("buf");
     cmd.arg("breaking");
 
@@ -55,7 +72,7 @@ fn run_buf_breaking(args: &CompatArgs) -> Result<BreakingResult> {
     }
 
     // Add against reference
-    cmd.arg("--against").arg(&args.against);
+    cmd.arg("--against").arg(against);
 
     // Add config if specified
     if let Some(config) = &args.config {
@@ -121,6 +138,7 @@ fn parse_json_output(output: &str) -> Result<Vec<BreakingChange>> {
                 column: change.start_column.unwrap_or(0),
                 message: change.message,
                 rule: change.r#type.unwrap_or_default(),
+                severity: Severity::Error,
             });
         }
     }
@@ -146,6 +164,7 @@ fn parse_text_output(stdout: &str, stderr: &str) -> Vec<BreakingChange> {
                 column: parts[2].parse().unwrap_or(0),
                 message: parts[3].trim().to_string(),
                 rule: String::new(),
+                severity: Severity::Error,
             });
         } else if !line.trim().is_empty() {
             changes.push(BreakingChange {
@@ -154,6 +173,7 @@ fn parse_text_output(stdout: &str, stderr: &str) -> Vec<BreakingChange> {
                 column: 0,
                 message: line.to_string(),
                 rule: String::new(),
+                severity: Severity::Error,
             });
         }
     }
@@ -180,6 +200,7 @@ pub struct BreakingChange {
     pub column: u32,
     pub message: String,
     pub rule: String,
+    pub severity: Severity,
 }
 
 #[derive(Debug)]
@@ -190,6 +211,19 @@ pub struct BreakingResult {
 }
 
 pub fn run(args: CompatArgs) -> Result<()> {
+    if let Some(path) = &args.write_baseline {
+        let descriptor_set = current_descriptor_set(&args)?;
+        std::fs::write(path, descriptor_set)
+            .with_context(|| format!("Failed to write baseline: {}", path.display()))?;
+        println!("Wrote baseline of {} to {}", args.input.join(", "), path.display());
+        return Ok(());
+    }
+    let against = args.against.clone().context("Invalid input: missing --against")?;
+
+    if semantic::is_descriptor_set(&against) {
+        return run_semantic(&args, &against);
+    }
+
     if !buf_available() {
         eprintln!("Warning: 'buf' CLI not found. For best results, install buf:");
         eprintln!("  https://buf.build/docs/installation");
@@ -206,7 +240,8 @@ pub fn run(args: CompatArgs) -> Result<()> {
         println!("  - Service/method removals");
         println!("  - Method signature changes");
         println!();
-        println!("To check: {} against {}", args.input.join(", "), args.against);
+        println!("To check: {} against {}", args.input.join(", "), against);
+        println!("Or compare against a baseline descriptor set: --against baseline.binpb");
 
         if args.strict {
             anyhow::bail!("buf CLI required for strict mode");
@@ -216,40 +251,13 @@ pub fn run(args: CompatArgs) -> Result<()> {
 
     println!("Checking compatibility...");
     println!("  Input:   {}", args.input.join(", "));
-    println!("  Against: {}", args.against);
+    println!("  Against: {}", against);
     println!();
 
-    let result = run_buf_breaking(&args)?;
+    let result = run_buf_breaking(&args, &against)?;
 
     if result.has_breaking {
-        if args.format == "json" {
-            // Output as JSON array
-            let json_output: Vec<serde_json::Value> = result
-                .breaking_changes
-                .iter()
-                .map(|c| {
-                    serde_json::json!({
-                        "file": c.file,
-                        "line": c.line,
-                        "column": c.column,
-                        "message": c.message,
-                        "rule": c.rule,
-                    })
-                })
-                .collect();
-            println!("{}", serde_json::to_string_pretty(&json_output)?);
-        } else {
-            println!("Breaking changes detected:");
-            println!();
-            for change in &result.breaking_changes {
-                if !change.file.is_empty() {
-                    print!("  {}:{}:{}: ", change.file, change.line, change.column);
-                } else {
-                    print!("  ");
-                }
-                println!("{}", change.message);
-            }
-        }
+        print_changes(&args, &result.breaking_changes)?;
 
         println!();
         println!(
@@ -267,6 +275,104 @@ pub fn run(args: CompatArgs) -> Result<()> {
     Ok(())
 }
 
+/// Compare the input against a baseline descriptor set with the built-in rules
+fn run_semantic(args: &CompatArgs, against: &str) -> Result<()> {
+    let config = RuleConfig::with_overrides(&args.rules)?;
+    let baseline = semantic::load_descriptor_set(against.as_ref())?;
+    let current = prost_reflect::DescriptorPool::decode(current_descriptor_set(args)?.as_slice())
+        .context("Invalid descriptor set for input")?;
+
+    let mut changes: Vec<BreakingChange> = semantic::check(&baseline, &current, &config)
+        .into_iter()
+        .map(|finding| BreakingChange {
+            file: finding.file,
+            line: 0,
+            column: 0,
+            message: finding.message,
+            rule: finding.rule.id().to_string(),
+            severity: finding.severity,
+        })
+        .collect();
+    if args.error_limit > 0 {
+        changes.truncate(args.error_limit);
+    }
+
+    let errors = changes.iter().filter(|c| c.severity == Severity::Error).count();
+    let warnings = changes.len() - errors;
+    if args.format != "json" {
+        println!("Checking compatibility...");
+        println!("  Input:   {}", args.input.join(", "));
+        println!("  Against: {} (baseline)", against);
+        println!();
+    }
+    if changes.is_empty() {
+        if args.format == "json" {
+            println!("[]");
+        } else {
+            println!("No breaking changes detected.");
+        }
+        return Ok(());
+    }
+
+    print_changes(args, &changes)?;
+    if args.format != "json" {
+        println!();
+        println!("Found {} breaking change(s): {} error(s), {} warning(s)", changes.len(), errors, warnings);
+    }
+    if errors > 0 || (args.strict && warnings > 0) {
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+/// Encoded descriptor set of the input, loaded or compiled with protoc
+fn current_descriptor_set(args: &CompatArgs) -> Result<Vec<u8>> {
+    match args.input.as_slice() {
+        [input] if semantic::is_descriptor_set(input) => {
+            std::fs::read(input).with_context(|| format!("Failed to read {}", input))
+        }
+        inputs => semantic::compile_descriptor_set(inputs, &args.includes),
+    }
+}
+
+fn print_changes(args: &CompatArgs, changes: &[BreakingChange]) -> Result<()> {
+    if args.format == "json" {
+        // Output as JSON array
+        let json_output: Vec<serde_json::Value> = changes
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "file": c.file,
+                    "line": c.line,
+                    "column": c.column,
+                    "message": c.message,
+                    "rule": c.rule,
+                    "severity": c.severity.as_str(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json_output)?);
+    } else {
+        println!("Breaking changes detected:");
+        println!();
+        for change in changes {
+            if change.file.is_empty() {
+                print!("  ");
+            } else if change.line == 0 {
+                print!("  {}: ", change.file);
+            } else {
+                print!("  {}:{}:{}: ", change.file, change.line, change.column);
+            }
+            if change.rule.is_empty() {
+                println!("{}", change.message);
+            } else {
+                println!("{} [{} {}]", change.message, change.severity.as_str(), change.rule);
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,9 +397,12 @@ mod tests {
     #[test]
     fn test_compat_args_defaults() {
         let args = CompatArgs {
-            against: "main".to_string(),
+            against: Some("main".to_string()),
             input: vec![".".to_string()],
+            includes: vec![],
             strict: false,
+            rules: vec![],
+            write_baseline: None,
             config: None,
             format: "text".to_string(),
             error_limit: 0,
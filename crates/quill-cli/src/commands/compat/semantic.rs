@@ -0,0 +1,699 @@
+//! Semantic breaking-change rules against a baseline descriptor set
+//!
+//! Compares two descriptor pools element by element, without `buf`. The
+//! baseline is an encoded `FileDescriptorSet`, e.g. written by
+//! `quill compat --write-baseline` or `buf build -o baseline.binpb`. Each
+//! rule has a default severity that can be overridden per run.
+
+use anyhow::{Context, Result};
+use prost_reflect::{DescriptorPool, FieldDescriptor, Kind, MessageDescriptor};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+/// How a rule's findings are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Not reported
+    Off,
+    /// Reported, fails the check only with `--strict`
+    Warning,
+    /// Reported and fails the check
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "warning" | "warn" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            other => {
+                anyhow::bail!("Invalid input: unknown severity '{}' (off, warning, error)", other)
+            }
+        }
+    }
+}
+
+/// Breaking-change rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    ServiceRemoved,
+    RpcRemoved,
+    RpcRequestTypeChanged,
+    RpcResponseTypeChanged,
+    RpcStreamingChanged,
+    MessageRemoved,
+    FieldRemoved,
+    FieldRenumbered,
+    FieldTypeChanged,
+    FieldCardinalityChanged,
+    FieldRenamed,
+    EnumRemoved,
+    EnumValueRemoved,
+    EnumValueRenumbered,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 14] = [
+        Rule::ServiceRemoved,
+        Rule::RpcRemoved,
+        Rule::RpcRequestTypeChanged,
+        Rule::RpcResponseTypeChanged,
+        Rule::RpcStreamingChanged,
+        Rule::MessageRemoved,
+        Rule::FieldRemoved,
+        Rule::FieldRenumbered,
+        Rule::FieldTypeChanged,
+        Rule::FieldCardinalityChanged,
+        Rule::FieldRenamed,
+        Rule::EnumRemoved,
+        Rule::EnumValueRemoved,
+        Rule::EnumValueRenumbered,
+    ];
+
+    /// Rule ID used in output and severity overrides
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::ServiceRemoved => "SERVICE_REMOVED",
+            Self::RpcRemoved => "RPC_REMOVED",
+            Self::RpcRequestTypeChanged => "RPC_REQUEST_TYPE_CHANGED",
+            Self::RpcResponseTypeChanged => "RPC_RESPONSE_TYPE_CHANGED",
+            Self::RpcStreamingChanged => "RPC_STREAMING_CHANGED",
+            Self::MessageRemoved => "MESSAGE_REMOVED",
+            Self::FieldRemoved => "FIELD_REMOVED",
+            Self::FieldRenumbered => "FIELD_RENUMBERED",
+            Self::FieldTypeChanged => "FIELD_TYPE_CHANGED",
+            Self::FieldCardinalityChanged => "FIELD_CARDINALITY_CHANGED",
+            Self::FieldRenamed => "FIELD_RENAMED",
+            Self::EnumRemoved => "ENUM_REMOVED",
+            Self::EnumValueRemoved => "ENUM_VALUE_REMOVED",
+            Self::EnumValueRenumbered => "ENUM_VALUE_RENUMBERED",
+        }
+    }
+
+    /// Severity unless overridden
+    ///
+    /// Renamed fields keep the wire format and only break JSON and
+    /// generated code, so they warn. Everything else breaks existing peers.
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Self::FieldRenamed => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.id().eq_ignore_ascii_case(s))
+            .with_context(|| format!("Invalid input: unknown compat rule '{}'", s))
+    }
+}
+
+/// Severity of each rule, defaults plus overrides
+#[derive(Debug, Clone, Default)]
+pub struct RuleConfig {
+    overrides: HashMap<Rule, Severity>,
+}
+
+impl RuleConfig {
+    /// Apply `RULE=severity` overrides, e.g. `FIELD_RENAMED=error`
+    pub fn with_overrides(overrides: &[String]) -> Result<Self> {
+        let mut config = Self::default();
+        for item in overrides {
+            let (rule, severity) = item.split_once('=').with_context(|| {
+                format!("Invalid input: expected RULE=severity, got '{}'", item)
+            })?;
+            config.overrides.insert(rule.trim().parse()?, severity.trim().parse()?);
+        }
+        Ok(config)
+    }
+
+    pub fn severity(&self, rule: Rule) -> Severity {
+        self.overrides.get(&rule).copied().unwrap_or_else(|| rule.default_severity())
+    }
+}
+
+/// Breaking change found by a rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// File of the baseline element that broke
+    pub file: String,
+    pub message: String,
+}
+
+/// Compare `current` against `baseline`, returning findings of enabled rules
+pub fn check(
+    baseline: &DescriptorPool,
+    current: &DescriptorPool,
+    config: &RuleConfig,
+) -> Vec<Finding> {
+    let mut checker = Checker { config, findings: Vec::new() };
+    checker.services(baseline, current);
+    checker.messages(baseline, current);
+    checker.enums(baseline, current);
+    checker.findings
+}
+
+struct Checker<'a> {
+    config: &'a RuleConfig,
+    findings: Vec<Finding>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, rule: Rule, file: &str, message: String) {
+        let severity = self.config.severity(rule);
+        if severity != Severity::Off {
+            self.findings.push(Finding { rule, severity, file: file.to_string(), message });
+        }
+    }
+
+    fn services(&mut self, baseline: &DescriptorPool, current: &DescriptorPool) {
+        for service in baseline.services() {
+            let file = service.parent_file().name().to_string();
+            let Some(now) = current.get_service_by_name(service.full_name()) else {
+                self.report(
+                    Rule::ServiceRemoved,
+                    &file,
+                    format!("Service {} was removed", service.full_name()),
+                );
+                continue;
+            };
+            for method in service.methods() {
+                let Some(updated) = now.methods().find(|m| m.name() == method.name()) else {
+                    self.report(
+                        Rule::RpcRemoved,
+                        &file,
+                        format!("RPC {} was removed", method.full_name()),
+                    );
+                    continue;
+                };
+                if method.input().full_name() != updated.input().full_name() {
+                    self.report(
+                        Rule::RpcRequestTypeChanged,
+                        &file,
+                        format!(
+                            "RPC {} request changed from {} to {}",
+                            method.full_name(),
+                            method.input().full_name(),
+                            updated.input().full_name()
+                        ),
+                    );
+                }
+                if method.output().full_name() != updated.output().full_name() {
+                    self.report(
+                        Rule::RpcResponseTypeChanged,
+                        &file,
+                        format!(
+                            "RPC {} response changed from {} to {}",
+                            method.full_name(),
+                            method.output().full_name(),
+                            updated.output().full_name()
+                        ),
+                    );
+                }
+                let before =
+                    streaming_mode(method.is_client_streaming(), method.is_server_streaming());
+                let after =
+                    streaming_mode(updated.is_client_streaming(), updated.is_server_streaming());
+                if before != after {
+                    self.report(
+                        Rule::RpcStreamingChanged,
+                        &file,
+                        format!("RPC {} changed from {} to {}", method.full_name(), before, after),
+                    );
+                }
+            }
+        }
+    }
+
+    fn messages(&mut self, baseline: &DescriptorPool, current: &DescriptorPool) {
+        for message in baseline.all_messages() {
+            let file = message.parent_file().name().to_string();
+            let Some(now) = current.get_message_by_name(message.full_name()) else {
+                self.report(
+                    Rule::MessageRemoved,
+                    &file,
+                    format!("Message {} was removed", message.full_name()),
+                );
+                continue;
+            };
+            for field in message.fields() {
+                self.field(&file, &field, &now);
+            }
+        }
+    }
+
+    fn field(&mut self, file: &str, field: &FieldDescriptor, now: &MessageDescriptor) {
+        let name = field.full_name();
+        let Some(updated) = now.get_field(field.number()) else {
+            match now.get_field_by_name(field.name()) {
+                Some(moved) => self.report(
+                    Rule::FieldRenumbered,
+                    file,
+                    format!(
+                        "Field {} moved from number {} to {}",
+                        name,
+                        field.number(),
+                        moved.number()
+                    ),
+                ),
+                // Reserving the number keeps it from being reused, so removal is safe
+                None if !is_reserved(now, field.number()) => self.report(
+                    Rule::FieldRemoved,
+                    file,
+                    format!(
+                        "Field {} ({}) was removed without reserving its number",
+                        name,
+                        field.number()
+                    ),
+                ),
+                None => {}
+            }
+            return;
+        };
+
+        if updated.name() != field.name() {
+            self.report(
+                Rule::FieldRenamed,
+                file,
+                format!("Field {} ({}) was renamed to {}", name, field.number(), updated.name()),
+            );
+        }
+        let (before, after) = (kind_name(&field.kind()), kind_name(&updated.kind()));
+        if before != after {
+            self.report(
+                Rule::FieldTypeChanged,
+                file,
+                format!(
+                    "Field {} ({}) changed type from {} to {}",
+                    name,
+                    field.number(),
+                    before,
+                    after
+                ),
+            );
+        } else if field.is_list() != updated.is_list() || field.is_map() != updated.is_map() {
+            self.report(
+                Rule::FieldCardinalityChanged,
+                file,
+                format!(
+                    "Field {} ({}) changed from {} to {}",
+                    name,
+                    field.number(),
+                    cardinality(field),
+                    cardinality(&updated)
+                ),
+            );
+        }
+    }
+
+    fn enums(&mut self, baseline: &DescriptorPool, current: &DescriptorPool) {
+        for enum_type in baseline.all_enums() {
+            let file = enum_type.parent_file().name().to_string();
+            let Some(now) = current.get_enum_by_name(enum_type.full_name()) else {
+                self.report(
+                    Rule::EnumRemoved,
+                    &file,
+                    format!("Enum {} was removed", enum_type.full_name()),
+                );
+                continue;
+            };
+            for value in enum_type.values() {
+                if now.get_value(value.number()).is_some() {
+                    continue;
+                }
+                match now.get_value_by_name(value.name()) {
+                    Some(moved) => self.report(
+                        Rule::EnumValueRenumbered,
+                        &file,
+                        format!(
+                            "Enum value {} moved from number {} to {}",
+                            value.full_name(),
+                            value.number(),
+                            moved.number()
+                        ),
+                    ),
+                    None if !now.reserved_ranges().any(|range| range.contains(&value.number())) => {
+                        self.report(
+                            Rule::EnumValueRemoved,
+                            &file,
+                            format!(
+                                "Enum value {} ({}) was removed without reserving its number",
+                                value.full_name(),
+                                value.number()
+                            ),
+                        )
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+fn streaming_mode(client: bool, server: bool) -> &'static str {
+    match (client, server) {
+        (false, false) => "unary",
+        (true, false) => "client streaming",
+        (false, true) => "server streaming",
+        (true, true) => "bidi streaming",
+    }
+}
+
+fn is_reserved(message: &MessageDescriptor, number: u32) -> bool {
+    message.reserved_ranges().any(|range| range.contains(&number))
+}
+
+fn cardinality(field: &FieldDescriptor) -> &'static str {
+    if field.is_map() {
+        "map"
+    } else if field.is_list() {
+        "repeated"
+    } else {
+        "singular"
+    }
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Message(message) => message.full_name().to_string(),
+        Kind::Enum(enum_type) => enum_type.full_name().to_string(),
+        other => format!("{:?}", other).to_ascii_lowercase(),
+    }
+}
+
+/// Whether `path` names an encoded descriptor set rather than a buf input
+pub fn is_descriptor_set(path: &str) -> bool {
+    let path = Path::new(path);
+    path.is_file()
+        && matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("binpb" | "pb" | "desc" | "bin")
+        )
+}
+
+/// Load an encoded `FileDescriptorSet`
+pub fn load_descriptor_set(path: &Path) -> Result<DescriptorPool> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    DescriptorPool::decode(bytes.as_slice())
+        .with_context(|| format!("Invalid descriptor set: {}", path.display()))
+}
+
+/// Compile proto files and directories into an encoded `FileDescriptorSet`
+///
+/// Runs `protoc` from `PROTOC` or `PATH`. Directories are searched for
+/// .proto files and used as include roots when `includes` is empty.
+pub fn compile_descriptor_set(inputs: &[String], includes: &[PathBuf]) -> Result<Vec<u8>> {
+    let mut protos = Vec::new();
+    let mut roots = includes.to_vec();
+    for input in inputs {
+        let path = PathBuf::from(input);
+        if path.is_dir() {
+            collect_protos(&path, &mut protos)?;
+            if includes.is_empty() {
+                roots.push(path);
+            }
+        } else if path.is_file() {
+            protos.push(path);
+        } else {
+            anyhow::bail!("Invalid input: {} not found", input);
+        }
+    }
+    anyhow::ensure!(!protos.is_empty(), "Invalid input: no .proto files in {}", inputs.join(", "));
+    if roots.is_empty() {
+        roots.push(PathBuf::from("."));
+    }
+
+    let out = std::env::temp_dir().join(format!("quill-compat-{}.binpb", std::process::id()));
+    let protoc = std::env::var_os("PROTOC").unwrap_or_else(|| "protoc".into());
+    let mut cmd = Command::new(&protoc);
+    cmd.arg("--include_imports").arg(format!("--descriptor_set_out={}", out.display()));
+    for root in &roots {
+        cmd.arg(format!("-I{}", root.display()));
+    }
+    cmd.args(&protos);
+
+    let output = cmd.output().context(
+        "Failed to run protoc; install it, set PROTOC, or pass a descriptor set as input",
+    )?;
+    if !output.status.success() {
+        anyhow::bail!("Invalid input: protoc failed:\n{}", String::from_utf8_lossy(&output.stderr));
+    }
+    let bytes = std::fs::read(&out).context("Failed to read protoc output")?;
+    let _ = std::fs::remove_file(&out);
+    Ok(bytes)
+}
+
+fn collect_protos(dir: &Path, protos: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_protos(&path, protos)?;
+        } else if path.extension().is_some_and(|ext| ext == "proto") {
+            protos.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost_types::descriptor_proto::ReservedRange;
+    use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+    use prost_reflect::prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
+    };
+
+    fn field(name: &str, number: i32, kind: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(kind as i32),
+            json_name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn message(name: &str, fields: Vec<FieldDescriptorProto>) -> DescriptorProto {
+        DescriptorProto { name: Some(name.to_string()), field: fields, ..Default::default() }
+    }
+
+    fn method(name: &str, input: &str, server_streaming: bool) -> MethodDescriptorProto {
+        MethodDescriptorProto {
+            name: Some(name.to_string()),
+            input_type: Some(format!(".users.v1.{}", input)),
+            output_type: Some(".users.v1.User".to_string()),
+            server_streaming: Some(server_streaming),
+            ..Default::default()
+        }
+    }
+
+    fn pool(
+        messages: Vec<DescriptorProto>,
+        methods: Vec<MethodDescriptorProto>,
+        roles: &[(&str, i32)],
+    ) -> DescriptorPool {
+        let file = FileDescriptorProto {
+            name: Some("users.proto".to_string()),
+            package: Some("users.v1".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: messages,
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Role".to_string()),
+                value: roles
+                    .iter()
+                    .map(|(name, number)| EnumValueDescriptorProto {
+                        name: Some(name.to_string()),
+                        number: Some(*number),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Users".to_string()),
+                method: methods,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] }).unwrap()
+    }
+
+    fn baseline() -> DescriptorPool {
+        pool(
+            vec![
+                message(
+                    "User",
+                    vec![
+                        field("id", 1, Type::String),
+                        field("name", 2, Type::String),
+                        field("age", 3, Type::Int32),
+                    ],
+                ),
+                message("GetUserRequest", vec![field("id", 1, Type::String)]),
+                message("ListUsersRequest", vec![]),
+            ],
+            vec![
+                method("GetUser", "GetUserRequest", false),
+                method("ListUsers", "ListUsersRequest", true),
+            ],
+            &[("ROLE_UNSPECIFIED", 0), ("ROLE_ADMIN", 1)],
+        )
+    }
+
+    fn rules(findings: &[Finding]) -> Vec<&'static str> {
+        findings.iter().map(|finding| finding.rule.id()).collect()
+    }
+
+    #[test]
+    fn test_identical_pools_have_no_findings() {
+        assert!(check(&baseline(), &baseline(), &RuleConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_detects_field_changes() {
+        let current = pool(
+            vec![
+                // name renumbered, age retyped, id renamed
+                message(
+                    "User",
+                    vec![
+                        field("user_id", 1, Type::String),
+                        field("name", 4, Type::String),
+                        field("age", 3, Type::Int64),
+                    ],
+                ),
+                message("GetUserRequest", vec![]),
+                message("ListUsersRequest", vec![]),
+            ],
+            vec![
+                method("GetUser", "GetUserRequest", false),
+                method("ListUsers", "ListUsersRequest", true),
+            ],
+            &[("ROLE_UNSPECIFIED", 0), ("ROLE_ADMIN", 1)],
+        );
+
+        let findings = check(&baseline(), &current, &RuleConfig::default());
+        assert_eq!(
+            rules(&findings),
+            vec!["FIELD_RENAMED", "FIELD_RENUMBERED", "FIELD_TYPE_CHANGED", "FIELD_REMOVED"]
+        );
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[1].message, "Field users.v1.User.name moved from number 2 to 4");
+        assert_eq!(
+            findings[2].message,
+            "Field users.v1.User.age (3) changed type from int32 to int64"
+        );
+        assert_eq!(findings[3].file, "users.proto");
+    }
+
+    #[test]
+    fn test_reserved_numbers_may_be_removed() {
+        let mut request = message("GetUserRequest", vec![]);
+        request.reserved_range.push(ReservedRange { start: Some(1), end: Some(2) });
+        let current = pool(
+            vec![
+                message(
+                    "User",
+                    vec![
+                        field("id", 1, Type::String),
+                        field("name", 2, Type::String),
+                        field("age", 3, Type::Int32),
+                    ],
+                ),
+                request,
+                message("ListUsersRequest", vec![]),
+            ],
+            vec![
+                method("GetUser", "GetUserRequest", false),
+                method("ListUsers", "ListUsersRequest", true),
+            ],
+            &[("ROLE_UNSPECIFIED", 0), ("ROLE_ADMIN", 1)],
+        );
+        assert!(check(&baseline(), &current, &RuleConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_detects_rpc_and_enum_changes() {
+        let current = pool(
+            vec![
+                message(
+                    "User",
+                    vec![
+                        field("id", 1, Type::String),
+                        field("name", 2, Type::String),
+                        field("age", 3, Type::Int32),
+                    ],
+                ),
+                message("GetUserRequest", vec![field("id", 1, Type::String)]),
+                message("ListUsersRequest", vec![]),
+            ],
+            // ListUsers no longer streams, GetUser is gone
+            vec![method("ListUsers", "ListUsersRequest", false)],
+            &[("ROLE_UNSPECIFIED", 0), ("ROLE_ADMIN", 2)],
+        );
+
+        let findings = check(&baseline(), &current, &RuleConfig::default());
+        assert_eq!(
+            rules(&findings),
+            vec!["RPC_REMOVED", "RPC_STREAMING_CHANGED", "ENUM_VALUE_RENUMBERED"]
+        );
+        assert_eq!(
+            findings[1].message,
+            "RPC users.v1.Users.ListUsers changed from server streaming to unary"
+        );
+    }
+
+    #[test]
+    fn test_severity_overrides() {
+        let config = RuleConfig::with_overrides(&[
+            "field_renamed=error".to_string(),
+            "FIELD_REMOVED=off".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(config.severity(Rule::FieldRenamed), Severity::Error);
+        assert_eq!(config.severity(Rule::FieldRemoved), Severity::Off);
+        assert_eq!(config.severity(Rule::RpcRemoved), Severity::Error);
+
+        assert!(RuleConfig::with_overrides(&["FIELD_RENAMED".to_string()]).is_err());
+        assert!(RuleConfig::with_overrides(&["NO_SUCH_RULE=error".to_string()]).is_err());
+        assert!(RuleConfig::with_overrides(&["FIELD_RENAMED=fatal".to_string()]).is_err());
+    }
+}
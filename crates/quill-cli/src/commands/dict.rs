@@ -0,0 +1,131 @@
+//! Compression dictionary commands
+//!
+//! Trains zstd dictionaries from recorded payload samples.
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use quill_core::{CompressionDictionary, DEFAULT_DICTIONARY_SIZE};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Args, Debug)]
+pub struct DictArgs {
+    #[command(subcommand)]
+    pub command: DictCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DictCommand {
+    /// Train a dictionary from payload samples
+    Train(TrainArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct TrainArgs {
+    /// Sample files, or directories whose files are samples
+    #[arg(required = true)]
+    pub samples: Vec<PathBuf>,
+
+    /// File to write the dictionary to
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Identifier clients use to name the dictionary
+    #[arg(long, default_value = "1")]
+    pub id: u32,
+
+    /// Maximum dictionary size in bytes
+    #[arg(long, default_value_t = DEFAULT_DICTIONARY_SIZE)]
+    pub max_size: usize,
+
+    /// Treat every non-empty line of a file as one sample (e.g. JSON Lines)
+    #[arg(long)]
+    pub lines: bool,
+}
+
+pub fn run(args: DictArgs) -> Result<()> {
+    match args.command {
+        DictCommand::Train(args) => train(args),
+    }
+}
+
+fn train(args: TrainArgs) -> Result<()> {
+    let samples = load_samples(&args.samples, args.lines)?;
+    if samples.is_empty() {
+        bail!("Invalid input: no samples found");
+    }
+
+    let dictionary = CompressionDictionary::train(args.id, &samples, args.max_size)
+        .with_context(|| format!("Failed to train on {} samples", samples.len()))?;
+    fs::write(&args.output, dictionary.data())
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+
+    println!(
+        "Trained dictionary {} ({} bytes) from {} samples into {}",
+        dictionary.id(),
+        dictionary.data().len(),
+        samples.len(),
+        args.output.display()
+    );
+    println!("Hash: {}", dictionary.hash());
+    Ok(())
+}
+
+/// Read samples from files and the files of directories, in path order
+fn load_samples(paths: &[PathBuf], lines: bool) -> Result<Vec<Vec<u8>>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.retain(|entry| entry.is_file());
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut samples = Vec::new();
+    for file in &files {
+        let data = read(file)?;
+        if lines {
+            samples.extend(
+                data.split(|byte| *byte == b'\n')
+                    .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+                    .map(<[u8]>::to_vec),
+            );
+        } else if !data.is_empty() {
+            samples.push(data);
+        }
+    }
+    Ok(samples)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Invalid input: cannot read {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_samples_from_lines_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl = dir.path().join("samples.jsonl");
+        fs::write(&jsonl, "{\"a\":1}\n\n{\"a\":2}\n").unwrap();
+        let nested = dir.path().join("payloads");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("b.bin"), b"second").unwrap();
+        fs::write(nested.join("a.bin"), b"first").unwrap();
+
+        let samples = load_samples(std::slice::from_ref(&jsonl), true).unwrap();
+        assert_eq!(samples, vec![b"{\"a\":1}".to_vec(), b"{\"a\":2}".to_vec()]);
+
+        let samples = load_samples(&[nested], false).unwrap();
+        assert_eq!(samples, vec![b"first".to_vec(), b"second".to_vec()]);
+    }
+}
@@ -1,6 +1,9 @@
 //! Payload decoding command
 //!
-//! Decodes protobuf payloads using file descriptor sets for dynamic message introspection.
+//! Decodes protobuf payloads using file descriptor sets for dynamic message introspection,
+//! and captures of the quill-tensor frame protocol with `--tensor`.
+
+mod tensor;
 
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
@@ -36,8 +39,8 @@ pub enum OutputFormat {
 #[derive(Args, Debug)]
 pub struct ExplainArgs {
     /// Path to file descriptor set (.pb or .binpb file)
-    #[arg(short, long)]
-    pub descriptor_set: PathBuf,
+    #[arg(short, long, required_unless_present = "tensor")]
+    pub descriptor_set: Option<PathBuf>,
 
     /// Payload to decode (hex string, base64 string, or file path depending on format)
     #[arg(short, long)]
@@ -63,6 +66,11 @@ pub struct ExplainArgs {
     /// Show field numbers in output
     #[arg(long)]
     pub show_field_numbers: bool,
+
+    /// Decode the payload as tensor frames (TENSOR_META, TENSOR_PAYLOAD, TOKEN_BATCH...),
+    /// either raw or one per message of a captured Quill stream
+    #[arg(long, conflicts_with_all = ["message_type", "list_types"])]
+    pub tensor: bool,
 }
 
 /// Load a file descriptor set from a .pb file
@@ -245,18 +253,39 @@ fn list_message_types(pool: &DescriptorPool) {
     }
 }
 
+/// Decode and print a tensor frame capture
+fn run_tensor(args: &ExplainArgs) -> Result<()> {
+    let payload_bytes = decode_payload(&args.payload, &args.input_format)?;
+    let capture = tensor::decode_capture(&payload_bytes)?;
+
+    let output = match args.output_format {
+        OutputFormat::Json => serde_json::to_string(&capture)?,
+        OutputFormat::JsonPretty => serde_json::to_string_pretty(&capture)?,
+        OutputFormat::Text => tensor::format_text(&capture),
+        OutputFormat::Debug => format!("{:#?}", capture),
+    };
+    println!("{}", output);
+
+    Ok(())
+}
+
 pub fn run(args: ExplainArgs) -> Result<()> {
+    if args.tensor {
+        return run_tensor(&args);
+    }
+
     // Load descriptor set
-    if !args.descriptor_set.exists() {
+    let descriptor_set = args.descriptor_set.as_ref().context("--descriptor-set is required")?;
+    if !descriptor_set.exists() {
         anyhow::bail!(
             "Descriptor set not found: {}\n\n\
             To generate a descriptor set, use:\n\
             protoc --descriptor_set_out=output.pb --include_imports your.proto",
-            args.descriptor_set.display()
+            descriptor_set.display()
         );
     }
 
-    let pool = load_descriptor_pool(&args.descriptor_set)?;
+    let pool = load_descriptor_pool(descriptor_set)?;
 
     // If listing types, just show them and exit
     if args.list_types {
@@ -345,16 +374,34 @@ mod tests {
     #[test]
     fn test_explain_args() {
         let args = ExplainArgs {
-            descriptor_set: PathBuf::from("test.pb"),
+            descriptor_set: Some(PathBuf::from("test.pb")),
             payload: "0a05776f726c64".to_string(),
             message_type: Some("test.Message".to_string()),
             input_format: InputFormat::Hex,
             output_format: OutputFormat::JsonPretty,
             list_types: false,
             show_field_numbers: false,
+            tensor: false,
         };
 
         assert!(matches!(args.input_format, InputFormat::Hex));
         assert!(matches!(args.output_format, OutputFormat::JsonPretty));
     }
+
+    #[test]
+    fn test_tensor_does_not_need_descriptor_set() {
+        use clap::Parser;
+
+        #[derive(Parser)]
+        struct Cli {
+            #[command(flatten)]
+            explain: ExplainArgs,
+        }
+
+        let cli = Cli::try_parse_from(["quill", "--tensor", "-p", "capture.bin"]).unwrap();
+        assert!(cli.explain.tensor);
+        assert!(cli.explain.descriptor_set.is_none());
+        assert!(Cli::try_parse_from(["quill", "-p", "0a00"]).is_err());
+        assert!(Cli::try_parse_from(["quill", "--tensor", "-p", "0a00", "--list-types"]).is_err());
+    }
 }
@@ -0,0 +1,465 @@
+//! Tensor frame capture decoding
+//!
+//! Decodes captures of the quill-tensor frame protocol, either raw tensor
+//! frames back to back or a Quill stream body carrying one tensor frame per
+//! message, as sent by tensor streaming handlers. Runs of TENSOR_PAYLOAD
+//! frames are summarized rather than listed one by one.
+
+use anyhow::{bail, Context, Result};
+use bytes::Bytes;
+use quill_core::FrameParser;
+use quill_tensor::{Device, FrameType, TensorFrame, TensorReceiver, TokenBatch};
+use serde::Serialize;
+use std::fmt::Write;
+
+/// A decoded tensor frame capture
+#[derive(Debug, Serialize)]
+pub struct TensorCapture {
+    /// How frames were found: `tensor` (raw frames) or `quill` (one per message)
+    pub framing: &'static str,
+    /// Number of tensor frames
+    pub frames: usize,
+    /// Frames in order, with TENSOR_PAYLOAD runs merged
+    pub entries: Vec<Entry>,
+    /// One summary per TENSOR_META, with the payload that followed it
+    pub tensors: Vec<TensorSummary>,
+}
+
+/// One frame, or a run of TENSOR_PAYLOAD frames
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Entry {
+    ProtoMsg {
+        index: usize,
+        bytes: usize,
+    },
+    EndStream {
+        index: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        crc32: Option<u32>,
+    },
+    Cancel {
+        index: usize,
+        reason: String,
+    },
+    Credit {
+        index: usize,
+        bytes: u64,
+    },
+    TensorMeta {
+        index: usize,
+        name: Option<String>,
+        shape: Vec<usize>,
+        dtype: String,
+        device: &'static str,
+        byte_size: usize,
+    },
+    TensorPayload {
+        index: usize,
+        frames: usize,
+        bytes: usize,
+        min_chunk: usize,
+        max_chunk: usize,
+    },
+    TensorDelta {
+        index: usize,
+        generation: u32,
+        bytes: usize,
+    },
+    TokenBatch {
+        index: usize,
+        sequence_id: Option<u32>,
+        is_final: bool,
+        tokens: Vec<TokenEntry>,
+    },
+}
+
+/// A token of a TOKEN_BATCH frame
+#[derive(Debug, Serialize)]
+pub struct TokenEntry {
+    pub id: u32,
+    pub position: u32,
+    pub text: Option<String>,
+    pub logprob: Option<f32>,
+    pub special: bool,
+}
+
+/// A tensor announced by TENSOR_META and the payload received for it
+#[derive(Debug, Serialize)]
+pub struct TensorSummary {
+    pub name: Option<String>,
+    pub shape: Vec<usize>,
+    pub dtype: String,
+    pub device: &'static str,
+    pub expected_bytes: usize,
+    pub received_bytes: usize,
+    pub chunks: usize,
+    pub complete: bool,
+}
+
+/// Decode a capture, detecting whether it is raw or Quill-framed
+pub fn decode_capture(data: &[u8]) -> Result<TensorCapture> {
+    let frames = match split_raw(data) {
+        Ok(frames) => ("tensor", frames),
+        Err(raw_err) => match split_quill(data) {
+            Ok(frames) => ("quill", frames),
+            Err(quill_err) => bail!(
+                "Payload is not a tensor frame capture\n  as raw tensor frames: {:#}\n  as a Quill stream: {:#}",
+                raw_err,
+                quill_err
+            ),
+        },
+    };
+    summarize(frames.0, &frames.1)
+}
+
+/// Split raw tensor frames written back to back
+fn split_raw(data: &[u8]) -> Result<Vec<TensorFrame>> {
+    let mut frames = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let (frame, consumed) = TensorFrame::decode(&data[offset..])
+            .with_context(|| format!("frame {} at byte {}", frames.len(), offset))?;
+        frames.push(frame);
+        offset += consumed;
+    }
+    if frames.is_empty() {
+        bail!("no frames");
+    }
+    Ok(frames)
+}
+
+/// Split a Quill stream body whose data messages are tensor frames
+fn split_quill(data: &[u8]) -> Result<Vec<TensorFrame>> {
+    let mut parser = FrameParser::new();
+    parser.feed_bytes(Bytes::copy_from_slice(data));
+    let mut frames = Vec::new();
+    let mut consumed = 0;
+    while let Some(frame) = parser.parse_frame().context("invalid Quill frame")? {
+        consumed += frame.encode_len();
+        if !frame.flags.is_data() {
+            continue;
+        }
+        let (tensor_frame, len) = TensorFrame::decode(&frame.payload)
+            .with_context(|| format!("message {} is not a tensor frame", frames.len()))?;
+        if len != frame.payload.len() {
+            bail!(
+                "message {} has {} bytes after its tensor frame",
+                frames.len(),
+                frame.payload.len() - len
+            );
+        }
+        frames.push(tensor_frame);
+    }
+    if consumed < data.len() {
+        bail!("truncated after {} of {} bytes", consumed, data.len());
+    }
+    if frames.is_empty() {
+        bail!("no data messages");
+    }
+    Ok(frames)
+}
+
+fn summarize(framing: &'static str, frames: &[TensorFrame]) -> Result<TensorCapture> {
+    let mut entries = Vec::new();
+    let mut tensors: Vec<TensorSummary> = Vec::new();
+
+    for (index, frame) in frames.iter().enumerate() {
+        let bytes = frame.payload.len();
+        match frame.frame_type {
+            FrameType::ProtoMsg => entries.push(Entry::ProtoMsg { index, bytes }),
+            FrameType::EndStream => {
+                entries.push(Entry::EndStream { index, crc32: frame.end_stream_checksum() })
+            }
+            FrameType::Cancel => entries.push(Entry::Cancel {
+                index,
+                reason: String::from_utf8_lossy(&frame.payload).into_owned(),
+            }),
+            FrameType::Credit => {
+                let granted: [u8; 8] = frame.payload[..].try_into().map_err(|_| {
+                    anyhow::anyhow!("frame {}: CREDIT payload is {} bytes", index, bytes)
+                })?;
+                entries.push(Entry::Credit { index, bytes: u64::from_le_bytes(granted) });
+            }
+            FrameType::TensorMeta => {
+                let meta = TensorReceiver::decode_meta(&frame.payload)
+                    .with_context(|| format!("frame {}: invalid TENSOR_META", index))?;
+                let device = device_name(meta.device);
+                tensors.push(TensorSummary {
+                    name: meta.name.clone(),
+                    shape: meta.shape.clone(),
+                    dtype: meta.dtype.to_string(),
+                    device,
+                    expected_bytes: meta.storage_byte_size(),
+                    received_bytes: 0,
+                    chunks: 0,
+                    complete: meta.storage_byte_size() == 0,
+                });
+                entries.push(Entry::TensorMeta {
+                    index,
+                    byte_size: meta.storage_byte_size(),
+                    name: meta.name,
+                    shape: meta.shape,
+                    dtype: meta.dtype.to_string(),
+                    device,
+                });
+            }
+            FrameType::TensorPayload => {
+                if let Some(tensor) = tensors.last_mut() {
+                    tensor.received_bytes += bytes;
+                    tensor.chunks += 1;
+                    tensor.complete = tensor.received_bytes == tensor.expected_bytes;
+                }
+                match entries.last_mut() {
+                    Some(Entry::TensorPayload {
+                        frames,
+                        bytes: total,
+                        min_chunk,
+                        max_chunk,
+                        ..
+                    }) => {
+                        *frames += 1;
+                        *total += bytes;
+                        *min_chunk = (*min_chunk).min(bytes);
+                        *max_chunk = (*max_chunk).max(bytes);
+                    }
+                    _ => entries.push(Entry::TensorPayload {
+                        index,
+                        frames: 1,
+                        bytes,
+                        min_chunk: bytes,
+                        max_chunk: bytes,
+                    }),
+                }
+            }
+            FrameType::TensorDelta => entries.push(Entry::TensorDelta {
+                index,
+                generation: u32::from_be_bytes(frame.reserved),
+                bytes,
+            }),
+            FrameType::TokenBatch => {
+                let batch = TokenBatch::decode(&frame.payload)
+                    .with_context(|| format!("frame {}: invalid TOKEN_BATCH", index))?;
+                entries.push(Entry::TokenBatch {
+                    index,
+                    sequence_id: batch.sequence_id,
+                    is_final: batch.is_final,
+                    tokens: batch
+                        .tokens
+                        .into_iter()
+                        .map(|token| TokenEntry {
+                            id: token.id,
+                            position: token.position,
+                            text: token.text,
+                            logprob: token.logprob,
+                            special: token.is_special,
+                        })
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    Ok(TensorCapture { framing, frames: frames.len(), entries, tensors })
+}
+
+fn device_name(device: Device) -> &'static str {
+    match device {
+        Device::Cpu => "cpu",
+        Device::Cuda => "cuda",
+    }
+}
+
+/// Render a capture as human-readable text
+pub fn format_text(capture: &TensorCapture) -> String {
+    let mut out = String::new();
+    let framing = match capture.framing {
+        "quill" => "one per Quill message",
+        _ => "raw",
+    };
+    let _ = writeln!(out, "{} tensor frames ({})", capture.frames, framing);
+    let _ = writeln!(out);
+
+    for entry in &capture.entries {
+        match entry {
+            Entry::ProtoMsg { index, bytes } => {
+                line(&mut out, &index.to_string(), "PROTO_MSG", &format!("{} bytes", bytes))
+            }
+            Entry::EndStream { index, crc32 } => {
+                let detail = crc32.map(|crc| format!("crc32 {:#010x}", crc)).unwrap_or_default();
+                line(&mut out, &index.to_string(), "END_STREAM", &detail)
+            }
+            Entry::Cancel { index, reason } => {
+                line(&mut out, &index.to_string(), "CANCEL", &format!("{:?}", reason))
+            }
+            Entry::Credit { index, bytes } => {
+                line(&mut out, &index.to_string(), "CREDIT", &format!("{} bytes", bytes))
+            }
+            Entry::TensorMeta { index, name, shape, dtype, device, byte_size } => {
+                let name = name.as_deref().unwrap_or("(unnamed)");
+                let detail = format!(
+                    "{} shape={:?} dtype={} device={} ({} bytes)",
+                    name, shape, dtype, device, byte_size
+                );
+                line(&mut out, &index.to_string(), "TENSOR_META", &detail)
+            }
+            Entry::TensorPayload { index, frames, bytes, min_chunk, max_chunk } => {
+                let range = if *frames == 1 {
+                    index.to_string()
+                } else {
+                    format!("{}-{}", index, index + frames - 1)
+                };
+                let detail = if *frames == 1 {
+                    format!("{} bytes", bytes)
+                } else {
+                    format!(
+                        "{} chunks, {} bytes (min {}, max {})",
+                        frames, bytes, min_chunk, max_chunk
+                    )
+                };
+                line(&mut out, &range, "TENSOR_PAYLOAD", &detail)
+            }
+            Entry::TensorDelta { index, generation, bytes } => line(
+                &mut out,
+                &index.to_string(),
+                "TENSOR_DELTA",
+                &format!("{} bytes against generation {}", bytes, generation),
+            ),
+            Entry::TokenBatch { index, sequence_id, is_final, tokens } => {
+                let mut detail = format!("{} tokens", tokens.len());
+                if let Some(sequence_id) = sequence_id {
+                    let _ = write!(detail, ", sequence {}", sequence_id);
+                }
+                if *is_final {
+                    detail.push_str(", final");
+                }
+                line(&mut out, &index.to_string(), "TOKEN_BATCH", &detail);
+                for token in tokens {
+                    let _ = write!(out, "{:24}pos {:<5} id {:<7}", "", token.position, token.id);
+                    match &token.text {
+                        Some(text) => {
+                            let _ = write!(out, " {:<16}", format!("{:?}", text));
+                        }
+                        None => {
+                            let _ = write!(out, " {:<16}", "");
+                        }
+                    }
+                    if let Some(logprob) = token.logprob {
+                        let _ = write!(out, " logprob {:.4}", logprob);
+                    }
+                    if token.special {
+                        out.push_str(" <special>");
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    if !capture.tensors.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Tensors:");
+        for tensor in &capture.tensors {
+            let status = if tensor.complete { "complete" } else { "INCOMPLETE" };
+            let _ = writeln!(
+                out,
+                "  {} {:?} {} {}: {} of {} bytes in {} chunks, {}",
+                tensor.name.as_deref().unwrap_or("(unnamed)"),
+                tensor.shape,
+                tensor.dtype,
+                tensor.device,
+                tensor.received_bytes,
+                tensor.expected_bytes,
+                tensor.chunks,
+                status
+            );
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+fn line(out: &mut String, index: &str, kind: &str, detail: &str) {
+    let _ = writeln!(out, "{:<8}{:<16}{}", format!("#{}", index), kind, detail);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_core::Frame;
+    use quill_tensor::{DType, Tensor, TensorMeta, TensorSender, Token};
+
+    fn tensor_frames() -> Vec<TensorFrame> {
+        let meta = TensorMeta::new(vec![2, 3], DType::Float32).with_name("logits");
+        let tensor = Tensor::from_f32(&meta, &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        let mut frames = TensorSender::with_chunk_size(10).encode_tensor(&tensor);
+        let batch = TokenBatch::final_batch(vec![
+            Token::with_text(1234, "Hello", 0).with_logprob(-0.25),
+            Token::new(2, 1).as_special(),
+        ])
+        .with_sequence_id(7);
+        frames.insert(frames.len() - 1, TensorFrame::token_batch(batch.encode()));
+        frames
+    }
+
+    #[test]
+    fn test_decode_raw_capture() {
+        let data: Vec<u8> = tensor_frames().iter().flat_map(|f| f.encode()).collect();
+        let capture = decode_capture(&data).unwrap();
+        assert_eq!(capture.framing, "tensor");
+        assert_eq!(capture.frames, 6);
+
+        match &capture.entries[1] {
+            Entry::TensorPayload { index, frames, bytes, min_chunk, max_chunk } => {
+                assert_eq!((*index, *frames, *bytes), (1, 3, 24));
+                assert_eq!((*min_chunk, *max_chunk), (4, 10));
+            }
+            other => panic!("expected a payload run, got {:?}", other),
+        }
+        match &capture.entries[2] {
+            Entry::TokenBatch { sequence_id, is_final, tokens, .. } => {
+                assert_eq!(*sequence_id, Some(7));
+                assert!(*is_final);
+                assert_eq!(tokens[0].text.as_deref(), Some("Hello"));
+                assert_eq!(tokens[0].logprob, Some(-0.25));
+                assert!(tokens[1].special);
+            }
+            other => panic!("expected a token batch, got {:?}", other),
+        }
+
+        let tensor = &capture.tensors[0];
+        assert_eq!(tensor.name.as_deref(), Some("logits"));
+        assert_eq!(tensor.shape, vec![2, 3]);
+        assert!(tensor.complete);
+
+        let text = format_text(&capture);
+        assert!(
+            text.contains("#1-3    TENSOR_PAYLOAD  3 chunks, 24 bytes (min 4, max 10)"),
+            "{}",
+            text
+        );
+        assert!(text.contains("\"Hello\""));
+        assert!(text.contains("logits [2, 3] float32 cpu: 24 of 24 bytes in 3 chunks, complete"));
+    }
+
+    #[test]
+    fn test_decode_quill_framed_capture() {
+        let mut data = Vec::new();
+        for frame in tensor_frames().into_iter().take(3) {
+            data.extend_from_slice(&Frame::data(frame.encode()).encode());
+        }
+        data.extend_from_slice(&Frame::end_stream().encode());
+
+        let capture = decode_capture(&data).unwrap();
+        assert_eq!(capture.framing, "quill");
+        assert_eq!(capture.frames, 3);
+        assert!(!capture.tensors[0].complete);
+        assert!(format_text(&capture).contains("20 of 24 bytes in 2 chunks, INCOMPLETE"));
+    }
+
+    #[test]
+    fn test_decode_rejects_other_payloads() {
+        let err = decode_capture(b"\x0a\x05world").unwrap_err().to_string();
+        assert!(err.contains("not a tensor frame capture"), "{}", err);
+    }
+}
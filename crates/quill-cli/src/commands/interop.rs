@@ -0,0 +1,420 @@
+//! Interop conformance command
+//!
+//! Runs a matrix of cases against a server implementing the interop test
+//! service ([`quill_core::interop`]) and reports which ones conform, so
+//! server implementations in other languages can validate themselves
+//! against the Rust reference. With `--serve`, the command runs the
+//! reference server instead, for validating clients.
+
+use anyhow::{ensure, Context, Result};
+use bytes::Bytes;
+use clap::{Args, ValueEnum};
+use futures::stream::{self, StreamExt};
+use quill_client::{HttpProtocol, QuillClient};
+use quill_core::{
+    interop_message, InteropCollectSummary, InteropStreamRequest, PrismProfile, ProfilePreference,
+    QuillError, INTEROP_COLLECT_METHOD, INTEROP_CONVERSE_METHOD, INTEROP_ECHO_METHOD,
+    INTEROP_GENERATE_METHOD, INTEROP_SERVICE, INTEROP_TENSOR_METHOD,
+};
+use quill_server::QuillServer;
+use quill_tensor::stream::ReceiverEvent;
+use quill_tensor::{DType, TensorReceiver};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Conformance case of the interop matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InteropCase {
+    /// Unary echo of small and empty messages
+    Unary,
+    /// Server streaming of generated messages
+    ServerStreaming,
+    /// Client streaming, including empty messages
+    ClientStreaming,
+    /// Bidirectional streaming, one reply before each next message
+    BidiStreaming,
+    /// Cancel a server stream part way, then call again
+    Cancel,
+    /// zstd-compressed unary and streaming calls
+    Compression,
+    /// Unary and streaming messages of --large-size bytes
+    LargePayload,
+    /// Tensor streaming of a float32 tensor
+    Tensor,
+}
+
+impl InteropCase {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Unary => "unary",
+            Self::ServerStreaming => "server-streaming",
+            Self::ClientStreaming => "client-streaming",
+            Self::BidiStreaming => "bidi-streaming",
+            Self::Cancel => "cancel",
+            Self::Compression => "compression",
+            Self::LargePayload => "large-payload",
+            Self::Tensor => "tensor",
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct InteropArgs {
+    /// Base URL of the server under test, e.g. http://localhost:8080
+    #[arg(required_unless_present = "serve")]
+    pub url: Option<String>,
+
+    /// Serve the reference interop service on this address instead of running cases
+    #[arg(long, conflicts_with = "url")]
+    pub serve: Option<SocketAddr>,
+
+    /// Cases to run, comma separated (default: all)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub cases: Vec<InteropCase>,
+
+    /// Prism transport profile to test over (classic or turbo)
+    #[arg(long)]
+    pub prism: Option<PrismProfile>,
+
+    /// Message size of the large-payload case in bytes
+    #[arg(long, default_value = "4194304")]
+    pub large_size: u32,
+
+    /// Timeout of each case in seconds
+    #[arg(long, default_value = "10")]
+    pub timeout: u64,
+
+    /// Output format (text, json)
+    #[arg(short, long, default_value = "text")]
+    pub output: String,
+
+    /// Also write the JSON report to this file
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+struct ConformanceReport {
+    target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<String>,
+    passed: usize,
+    failed: usize,
+    cases: Vec<CaseReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct CaseReport {
+    case: InteropCase,
+    passed: bool,
+    duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub async fn run(args: InteropArgs) -> Result<()> {
+    if let Some(addr) = args.serve {
+        eprintln!("Serving the interop service on {}", addr);
+        return QuillServer::builder()
+            .enable_interop()
+            .build()
+            .serve(addr)
+            .await
+            .map_err(|e| anyhow::anyhow!("Server failed: {}", e));
+    }
+    let url = args.url.clone().context("Invalid input: missing URL")?;
+
+    let cases = if args.cases.is_empty() {
+        InteropCase::value_variants().to_vec()
+    } else {
+        args.cases.clone()
+    };
+    let plain = connect(&url, args.prism, false)?;
+    let compressed = connect(&url, args.prism, true)?;
+    let timeout = Duration::from_secs(args.timeout);
+
+    let mut reports = Vec::new();
+    for case in cases {
+        eprintln!("Running {}...", case.name());
+        let client = if case == InteropCase::Compression { &compressed } else { &plain };
+        let start = Instant::now();
+        let result = match tokio::time::timeout(timeout, run_case(case, client, &args)).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!("Case timed out after {}s", args.timeout)),
+        };
+        reports.push(CaseReport {
+            case,
+            passed: result.is_ok(),
+            duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+            error: result.err().map(|e| format!("{:#}", e)),
+        });
+    }
+
+    let failed = reports.iter().filter(|report| !report.passed).count();
+    let report = ConformanceReport {
+        target: url,
+        profile: args.prism.map(|profile| profile.to_string()),
+        passed: reports.len() - failed,
+        failed,
+        cases: reports,
+    };
+
+    if let Some(path) = &args.report {
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write report: {}", path.display()))?;
+    }
+
+    match args.output.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        _ => print_report(&report),
+    }
+
+    ensure!(failed == 0, "{} of {} interop cases failed", failed, report.cases.len());
+    Ok(())
+}
+
+fn connect(url: &str, profile: Option<PrismProfile>, compression: bool) -> Result<QuillClient> {
+    let mut builder = QuillClient::builder().base_url(url).enable_compression(compression);
+    match profile {
+        Some(PrismProfile::Classic) => builder = builder.http_protocol(HttpProtocol::Http1),
+        Some(PrismProfile::Turbo) => builder = builder.http2_only(),
+        Some(PrismProfile::Hyper) => {
+            anyhow::bail!("Invalid input: interop runs over the classic or turbo profile")
+        }
+        None => {}
+    }
+    if let Some(profile) = profile {
+        builder = builder.profile_preference(ProfilePreference::new(vec![profile]));
+    }
+    builder.build().map_err(|e| anyhow::anyhow!(e))
+}
+
+async fn run_case(case: InteropCase, client: &QuillClient, args: &InteropArgs) -> Result<()> {
+    match case {
+        InteropCase::Unary => unary(client).await,
+        InteropCase::ServerStreaming => server_streaming(client, 100, 64).await,
+        InteropCase::ClientStreaming => client_streaming(client).await,
+        InteropCase::BidiStreaming => bidi_streaming(client).await,
+        InteropCase::Cancel => cancel(client).await,
+        InteropCase::Compression => {
+            echo(client, compressible(64 * 1024)).await?;
+            server_streaming(client, 10, 16 * 1024).await
+        }
+        InteropCase::LargePayload => {
+            echo(client, pattern(args.large_size as usize)).await?;
+            server_streaming(client, 2, args.large_size).await
+        }
+        InteropCase::Tensor => tensor(client, 100_000, 64 * 1024).await,
+    }
+}
+
+async fn echo(client: &QuillClient, message: Bytes) -> Result<()> {
+    let response = client.call(INTEROP_SERVICE, INTEROP_ECHO_METHOD, message.clone()).await?;
+    ensure!(
+        response == message,
+        "Echo of {} bytes answered with {} different bytes",
+        message.len(),
+        response.len()
+    );
+    Ok(())
+}
+
+async fn unary(client: &QuillClient) -> Result<()> {
+    echo(client, Bytes::from_static(b"hello interop")).await?;
+    echo(client, Bytes::new()).await.context("Empty message")
+}
+
+async fn server_streaming(client: &QuillClient, count: u32, size: u32) -> Result<()> {
+    let request = InteropStreamRequest { count, size }.encode();
+    let mut messages =
+        client.call_server_streaming(INTEROP_SERVICE, INTEROP_GENERATE_METHOD, request).await?;
+
+    let mut received = 0;
+    while let Some(message) = messages.next().await {
+        let message = message?;
+        ensure!(received < count, "Stream sent more than {} messages", count);
+        ensure!(
+            message == interop_message(received, size as usize),
+            "Message {} has unexpected content ({} bytes)",
+            received,
+            message.len()
+        );
+        received += 1;
+    }
+    ensure!(received == count, "Stream ended after {} of {} messages", received, count);
+    Ok(())
+}
+
+async fn client_streaming(client: &QuillClient) -> Result<()> {
+    let sizes: Vec<usize> = (0..50).map(|i| (i * 37) % 1000).collect();
+    let expected = InteropCollectSummary {
+        messages: sizes.len() as u64,
+        bytes: sizes.iter().sum::<usize>() as u64,
+    };
+    let messages = stream::iter(sizes)
+        .enumerate()
+        .map(|(index, size)| Ok::<_, QuillError>(interop_message(index as u32, size)));
+
+    let response = client
+        .call_client_streaming(INTEROP_SERVICE, INTEROP_COLLECT_METHOD, Box::pin(messages))
+        .await?;
+    let summary = InteropCollectSummary::decode(&response)
+        .with_context(|| format!("Invalid summary of {} bytes", response.len()))?;
+    ensure!(summary == expected, "Expected {:?}, got {:?}", expected, summary);
+    Ok(())
+}
+
+async fn bidi_streaming(client: &QuillClient) -> Result<()> {
+    let (tx, rx) = mpsc::channel::<Result<Bytes, QuillError>>(1);
+    tx.send(Ok(interop_message(0, 16))).await?;
+    let mut replies = client
+        .call_bidi_streaming(
+            INTEROP_SERVICE,
+            INTEROP_CONVERSE_METHOD,
+            Box::pin(ReceiverStream::new(rx)),
+        )
+        .await?;
+
+    // Each reply must arrive while the request stream is still open
+    for index in 0..10u32 {
+        let reply = replies
+            .next()
+            .await
+            .with_context(|| format!("Stream ended before reply {}", index))??;
+        ensure!(reply == interop_message(index, 16), "Reply {} does not echo its message", index);
+        if index < 9 {
+            tx.send(Ok(interop_message(index + 1, 16))).await?;
+        }
+    }
+    drop(tx);
+
+    ensure!(replies.next().await.is_none(), "Stream continued after the requests ended");
+    Ok(())
+}
+
+async fn cancel(client: &QuillClient) -> Result<()> {
+    let request = InteropStreamRequest { count: u32::MAX, size: 1024 }.encode();
+    let mut messages =
+        client.call_server_streaming(INTEROP_SERVICE, INTEROP_GENERATE_METHOD, request).await?;
+    for index in 0..3 {
+        messages
+            .next()
+            .await
+            .with_context(|| format!("Stream ended before message {}", index))??;
+    }
+    drop(messages);
+
+    // The server must stay usable once the caller goes away
+    echo(client, Bytes::from_static(b"after cancel")).await.context("Call after cancel")
+}
+
+async fn tensor(client: &QuillClient, elements: u32, chunk_size: u32) -> Result<()> {
+    let request = InteropStreamRequest { count: elements, size: chunk_size }.encode();
+    let mut messages =
+        client.call_server_streaming(INTEROP_SERVICE, INTEROP_TENSOR_METHOD, request).await?;
+
+    let mut receiver = TensorReceiver::new();
+    while let Some(message) = messages.next().await {
+        receiver.feed_bytes(message?);
+        loop {
+            match receiver.poll()? {
+                ReceiverEvent::NeedMoreData | ReceiverEvent::End => break,
+                ReceiverEvent::Cancelled(reason) => {
+                    anyhow::bail!("Tensor stream cancelled: {}", reason)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let tensor = receiver.take_tensor().context("Tensor stream ended incomplete")?;
+    ensure!(tensor.shape() == [elements as usize], "Unexpected shape {:?}", tensor.shape());
+    ensure!(tensor.dtype() == DType::Float32, "Unexpected dtype {:?}", tensor.dtype());
+    let mismatch = tensor.as_f32().iter().enumerate().position(|(i, value)| *value != i as f32);
+    ensure!(mismatch.is_none(), "Element {} has an unexpected value", mismatch.unwrap_or_default());
+    Ok(())
+}
+
+/// Repetitive message that zstd compresses well
+fn compressible(size: usize) -> Bytes {
+    Bytes::from(b"quill interop ".iter().copied().cycle().take(size).collect::<Vec<_>>())
+}
+
+/// Message with every byte value, so truncation or reordering shows up
+fn pattern(size: usize) -> Bytes {
+    Bytes::from((0..size).map(|i| (i * 31 % 251) as u8).collect::<Vec<_>>())
+}
+
+fn print_report(report: &ConformanceReport) {
+    println!("\nInterop conformance: {}", report.target);
+    if let Some(profile) = &report.profile {
+        println!("Profile: {}", profile);
+    }
+    println!();
+    for case in &report.cases {
+        let status = if case.passed { "PASS" } else { "FAIL" };
+        println!("  {}  {:<18} {:>9.1} ms", status, case.case.name(), case.duration_ms);
+        if let Some(error) = &case.error {
+            println!("        {}", error);
+        }
+    }
+    println!();
+    println!("{} passed, {} failed", report.passed, report.failed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_names_match_serialization() {
+        for case in InteropCase::value_variants() {
+            let json = serde_json::to_string(case).unwrap();
+            assert_eq!(json, format!("\"{}\"", case.name()));
+            assert_eq!(InteropCase::from_str(case.name(), false).unwrap(), *case);
+        }
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let report = ConformanceReport {
+            target: "http://localhost:8080".to_string(),
+            profile: None,
+            passed: 1,
+            failed: 1,
+            cases: vec![
+                CaseReport {
+                    case: InteropCase::Unary,
+                    passed: true,
+                    duration_ms: 1.5,
+                    error: None,
+                },
+                CaseReport {
+                    case: InteropCase::Cancel,
+                    passed: false,
+                    duration_ms: 10.0,
+                    error: Some("Call after cancel".to_string()),
+                },
+            ],
+        };
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"case\":\"unary\",\"passed\":true,\"duration_ms\":1.5}"));
+        assert!(json.contains("\"error\":\"Call after cancel\""));
+        assert!(!json.contains("profile"));
+    }
+
+    #[test]
+    fn test_pattern_covers_byte_values() {
+        let bytes = pattern(1024);
+        assert_eq!(bytes.len(), 1024);
+        assert_eq!(bytes.iter().collect::<std::collections::HashSet<_>>().len(), 251);
+        assert_eq!(compressible(20).len(), 20);
+    }
+}
@@ -3,5 +3,8 @@
 pub mod gen;
 pub mod call;
 pub mod bench;
+pub mod interop;
 pub mod compat;
 pub mod explain;
+pub mod model;
+pub mod dict;
@@ -0,0 +1,86 @@
+//! Model artifact commands
+//!
+//! Pulls models from a Quill model store into a local cache.
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use quill_client::QuillClient;
+use quill_modelstore::{ModelCache, ModelStoreClient, PullOptions};
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Args, Debug)]
+pub struct ModelArgs {
+    #[command(subcommand)]
+    pub command: ModelCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ModelCommand {
+    /// Download a model into the local cache
+    Pull(PullArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct PullArgs {
+    /// Base URL of the model store server
+    pub url: String,
+
+    /// Name of the model to pull
+    pub model: String,
+
+    /// Local cache directory
+    #[arg(long, default_value = ".quill/models")]
+    pub cache_dir: PathBuf,
+
+    /// Number of shards to download in parallel
+    #[arg(short = 'j', long, default_value = "4")]
+    pub concurrency: usize,
+
+    /// Discard partial downloads instead of resuming them
+    #[arg(long)]
+    pub no_resume: bool,
+}
+
+pub async fn run(args: ModelArgs) -> Result<()> {
+    match args.command {
+        ModelCommand::Pull(args) => pull(args).await,
+    }
+}
+
+async fn pull(args: PullArgs) -> Result<()> {
+    let client = QuillClient::builder()
+        .base_url(&args.url)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to build client: {}", e))?;
+    let client = ModelStoreClient::new(client);
+    let cache = ModelCache::new(&args.cache_dir);
+
+    let options = PullOptions {
+        concurrency: args.concurrency,
+        resume: !args.no_resume,
+    };
+
+    let start = Instant::now();
+    let report = client
+        .pull(&args.model, &cache, options)
+        .await
+        .with_context(|| format!("Failed to pull model {}", args.model))?;
+    let elapsed = start.elapsed();
+
+    println!(
+        "Pulled {} ({} shards, {} cached) into {}",
+        args.model,
+        report.shards,
+        report.cached_shards,
+        cache.model_dir(&args.model)?.display()
+    );
+    println!(
+        "Downloaded {} bytes ({} resumed) in {:.2}s",
+        report.downloaded_bytes,
+        report.resumed_bytes,
+        elapsed.as_secs_f64()
+    );
+
+    Ok(())
+}
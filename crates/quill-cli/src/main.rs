@@ -4,13 +4,16 @@
 //! - gen: Code generation
 //! - call: Making RPC calls (curl-for-proto)
 //! - bench: Benchmarking
+//! - interop: Conformance testing against the interop service
 //! - compat: Breaking change detection
 //! - explain: Payload decoding
+//! - model: Model artifact distribution
+//! - dict: Compression dictionary training
 
 mod commands;
 
 use clap::{Parser, Subcommand};
-use commands::{bench, call, compat, explain, gen};
+use commands::{bench, call, compat, dict, explain, gen, interop, model};
 
 #[derive(Parser)]
 #[command(name = "quill")]
@@ -29,10 +32,16 @@ enum Commands {
     Call(call::CallArgs),
     /// Run benchmarks
     Bench(bench::BenchArgs),
+    /// Run interop conformance cases, or serve the reference interop service
+    Interop(interop::InteropArgs),
     /// Check for breaking changes
     Compat(compat::CompatArgs),
     /// Decode payloads
     Explain(explain::ExplainArgs),
+    /// Manage model artifacts
+    Model(model::ModelArgs),
+    /// Train compression dictionaries
+    Dict(dict::DictArgs),
 }
 
 #[tokio::main]
@@ -43,8 +52,11 @@ async fn main() {
         Commands::Gen(args) => gen::run(args),
         Commands::Call(args) => call::run(args).await,
         Commands::Bench(args) => bench::run(args).await,
+        Commands::Interop(args) => interop::run(args).await,
         Commands::Compat(args) => compat::run(args),
         Commands::Explain(args) => explain::run(args),
+        Commands::Model(args) => model::run(args).await,
+        Commands::Dict(args) => dict::run(args),
     };
 
     if let Err(e) = result {
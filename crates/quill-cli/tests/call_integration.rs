@@ -8,7 +8,7 @@ use hyper::body::{Frame as HyperFrame, Incoming};
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use prost::Message;
-use quill_core::Frame;
+use quill_core::{Frame, FrameParser};
 use std::convert::Infallible;
 use std::future::Future;
 use std::net::SocketAddr;
@@ -119,6 +119,57 @@ async fn quill_call_encodes_and_decodes_json_with_descriptor_set() -> anyhow::Re
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn quill_call_uses_reflected_descriptors() -> anyhow::Result<()> {
+    let (_descriptor_dir, descriptor_set) = generate_descriptor_set(
+        "examples/greeter/proto/greeter.proto",
+        "examples/greeter/proto",
+        "greeter.pb",
+    )?;
+    let descriptor_bytes = Bytes::from(std::fs::read(&descriptor_set)?);
+
+    let server = spawn_server(move |req| {
+        let descriptor_bytes = descriptor_bytes.clone();
+        async move {
+            let path = req.uri().path().to_string();
+            let body = req
+                .into_body()
+                .collect()
+                .await
+                .expect("request body should be readable")
+                .to_bytes();
+            match path.as_str() {
+                "/quill.reflection.v1.Reflection/Descriptors" => {
+                    assert_eq!(body, Bytes::from_static(b"greeter.v1.Greeter"));
+                    proto_response(StatusCode::OK, descriptor_bytes)
+                }
+                "/greeter.v1.Greeter/SayHello" => {
+                    let request = HelloRequest::decode(body).expect("request should decode");
+                    let reply = HelloReply { message: format!("Hello, {}!", request.name) };
+                    proto_response(StatusCode::OK, Bytes::from(reply.encode_to_vec()))
+                }
+                _ => proto_response(StatusCode::NOT_FOUND, Bytes::from_static(b"missing")),
+            }
+        }
+    })
+    .await?;
+
+    let output = Command::cargo_bin("quill")?
+        .arg("call")
+        .arg(server.url("/greeter.v1.Greeter/SayHello"))
+        .arg("--reflect")
+        .arg("--input")
+        .arg(r#"{"name":"Reflection"}"#)
+        .arg("--output-format")
+        .arg("json")
+        .output()?;
+
+    let output = assert_success(output)?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.trim(), r#"{"message":"Hello, Reflection!"}"#);
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn quill_call_streams_descriptor_decoded_messages() -> anyhow::Result<()> {
     let (_descriptor_dir, descriptor_set) = generate_descriptor_set(
@@ -171,6 +222,92 @@ async fn quill_call_streams_descriptor_decoded_messages() -> anyhow::Result<()>
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn quill_call_streams_ndjson_requests_for_bidi_methods() -> anyhow::Result<()> {
+    let (_descriptor_dir, descriptor_set) = generate_descriptor_set(
+        "examples/greeter/proto/greeter.proto",
+        "examples/greeter/proto",
+        "greeter.pb",
+    )?;
+
+    let server = spawn_server(|req| async move {
+        if req.uri().path() != "/greeter.v1.Greeter/Chat" {
+            return proto_response(StatusCode::NOT_FOUND, Bytes::from_static(b"missing"));
+        }
+
+        let body =
+            req.into_body().collect().await.expect("request body should be readable").to_bytes();
+        let mut parser = FrameParser::new();
+        parser.feed(&body);
+
+        let mut chunks = Vec::new();
+        while let Some(frame) = parser.parse_frame().expect("request frames should parse") {
+            if frame.flags.is_end_stream() {
+                break;
+            }
+            let request = HelloRequest::decode(frame.payload).expect("request should decode");
+            let reply = HelloReply { message: format!("Hi, {}!", request.name) };
+            chunks.push(Frame::data(Bytes::from(reply.encode_to_vec())).encode());
+        }
+        chunks.push(Frame::end_stream().encode());
+
+        streaming_proto_response(chunks)
+    })
+    .await?;
+
+    // Streaming is detected from the descriptor; messages come from stdin
+    let output = Command::cargo_bin("quill")?
+        .arg("call")
+        .arg(server.url("/greeter.v1.Greeter/Chat"))
+        .arg("--descriptor-set")
+        .arg(&descriptor_set)
+        .arg("--output")
+        .arg("ndjson")
+        .write_stdin("{\"name\":\"Ada\"}\n\n{\"name\":\"Grace\"}\n")
+        .output()?;
+
+    let output = assert_success(output)?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines, vec![r#"{"message":"Hi, Ada!"}"#, r#"{"message":"Hi, Grace!"}"#]);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn quill_call_sends_input_lines_as_client_stream() -> anyhow::Result<()> {
+    let server = spawn_server(|req| async move {
+        if req.uri().path() != "/echo.v1.EchoService/Join" {
+            return proto_response(StatusCode::NOT_FOUND, Bytes::from_static(b"missing"));
+        }
+
+        let body =
+            req.into_body().collect().await.expect("request body should be readable").to_bytes();
+        let mut parser = FrameParser::new();
+        parser.feed(&body);
+
+        let mut messages = Vec::new();
+        while let Some(frame) = parser.parse_frame().expect("request frames should parse") {
+            if frame.flags.is_data() {
+                messages.push(String::from_utf8(frame.payload.to_vec()).unwrap());
+            }
+        }
+        proto_response(StatusCode::OK, messages.join("+"))
+    })
+    .await?;
+
+    let output = Command::cargo_bin("quill")?
+        .arg("call")
+        .arg(server.url("/echo.v1.EchoService/Join"))
+        .arg("--client-stream")
+        .arg("--input")
+        .arg("one\ntwo\nthree")
+        .output()?;
+
+    let output = assert_success(output)?;
+    assert_eq!(output.stdout, b"one+two+three");
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn quill_call_supports_relative_urls_and_env_auth() -> anyhow::Result<()> {
     let server = spawn_server(|req| async move {
@@ -0,0 +1,137 @@
+use assert_cmd::Command;
+use std::path::Path;
+use tempfile::TempDir;
+
+const BASELINE_PROTO: &str = r#"
+syntax = "proto3";
+package users.v1;
+
+service Users {
+  rpc GetUser(GetUserRequest) returns (User);
+  rpc ListUsers(ListUsersRequest) returns (stream User);
+}
+
+message GetUserRequest { string id = 1; }
+message ListUsersRequest { int32 page_size = 1; }
+message User {
+  string id = 1;
+  string name = 2;
+}
+"#;
+
+fn write_proto(dir: &Path, contents: &str) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir.join("proto"))?;
+    std::fs::write(dir.join("proto/users.proto"), contents)?;
+    Ok(())
+}
+
+fn quill(dir: &TempDir) -> anyhow::Result<Command> {
+    let mut cmd = Command::cargo_bin("quill")?;
+    cmd.current_dir(dir.path()).env("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    Ok(cmd)
+}
+
+fn write_baseline(dir: &TempDir) -> anyhow::Result<()> {
+    write_proto(dir.path(), BASELINE_PROTO)?;
+    let output =
+        quill(dir)?.args(["compat", "--write-baseline", "baseline.binpb", "proto"]).output()?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    Ok(())
+}
+
+#[test]
+fn quill_compat_accepts_compatible_changes() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    write_baseline(&dir)?;
+
+    // New fields and RPCs are compatible; removing a reserved field is too
+    write_proto(
+        dir.path(),
+        &BASELINE_PROTO
+            .replace("int32 page_size = 1;", "reserved 1; string cursor = 2;")
+            .replace("string name = 2;", "string name = 2;\n  string email = 3;")
+            .replace(
+                "returns (User);",
+                "returns (User);\n  rpc DeleteUser(GetUserRequest) returns (User);",
+            ),
+    )?;
+
+    let output = quill(&dir)?.args(["compat", "--against", "baseline.binpb", "proto"]).output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("No breaking changes detected."));
+    Ok(())
+}
+
+#[test]
+fn quill_compat_reports_breaking_changes_against_baseline() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    write_baseline(&dir)?;
+
+    write_proto(
+        dir.path(),
+        &BASELINE_PROTO
+            .replace("returns (stream User)", "returns (User)")
+            .replace("string name = 2;", "string name = 3;")
+            .replace("string id = 1;\n  string", "string user_id = 1;\n  string"),
+    )?;
+
+    let output = quill(&dir)?
+        .args(["compat", "--against", "baseline.binpb", "proto", "--format", "json"])
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let rules: Vec<(&str, &str)> = changes
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| (c["rule"].as_str().unwrap(), c["severity"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            ("RPC_STREAMING_CHANGED", "error"),
+            ("FIELD_RENAMED", "warning"),
+            ("FIELD_RENUMBERED", "error"),
+        ]
+    );
+    assert_eq!(changes[0]["file"], "users.proto");
+    Ok(())
+}
+
+#[test]
+fn quill_compat_rule_severities_gate_the_exit_code() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    write_baseline(&dir)?;
+    write_proto(dir.path(), &BASELINE_PROTO.replace("string name = 2;", "string full_name = 2;"))?;
+
+    // A rename only warns by default
+    let output = quill(&dir)?.args(["compat", "--against", "baseline.binpb", "proto"]).output()?;
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)?.contains("[warning FIELD_RENAMED]"));
+
+    let output = quill(&dir)?
+        .args(["compat", "--against", "baseline.binpb", "proto", "--strict"])
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = quill(&dir)?
+        .args(["compat", "--against", "baseline.binpb", "proto", "--rule", "FIELD_RENAMED=error"])
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = quill(&dir)?
+        .args([
+            "compat",
+            "--against",
+            "baseline.binpb",
+            "proto",
+            "--rule",
+            "FIELD_RENAMED=off",
+            "--strict",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    Ok(())
+}
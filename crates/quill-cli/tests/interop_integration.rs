@@ -0,0 +1,88 @@
+use assert_cmd::Command;
+use bytes::Bytes;
+use quill_server::QuillServer;
+use std::net::SocketAddr;
+use tokio::task::JoinHandle;
+
+struct TestServer {
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl TestServer {
+    fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn spawn(server: QuillServer) -> TestServer {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handle = tokio::spawn(async move {
+        let _ = server.serve_with_listener(listener).await;
+    });
+    TestServer { addr, handle }
+}
+
+fn report(output: &std::process::Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).expect("stdout should be a JSON report")
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn quill_interop_passes_against_reference_server() -> anyhow::Result<()> {
+    let server = spawn(QuillServer::builder().enable_interop().build()).await;
+
+    for prism in ["classic", "turbo"] {
+        let output = Command::cargo_bin("quill")?
+            .arg("interop")
+            .arg(server.url())
+            .args(["--prism", prism, "--large-size", "1048576", "--output", "json"])
+            .output()?;
+
+        let report = report(&output);
+        assert!(output.status.success(), "{}", serde_json::to_string_pretty(&report)?);
+        assert_eq!(report["failed"], 0);
+        assert_eq!(report["passed"], 8);
+        assert_eq!(report["profile"], prism);
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn quill_interop_reports_nonconforming_cases() -> anyhow::Result<()> {
+    // Echo answers with the wrong bytes and nothing else is implemented
+    let server = spawn(
+        QuillServer::builder()
+            .register("quill.interop.v1.Interop/Echo", |_req: Bytes| async move {
+                Ok(Bytes::from_static(b"wrong"))
+            })
+            .build(),
+    )
+    .await;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("report.json");
+
+    let output = Command::cargo_bin("quill")?
+        .arg("interop")
+        .arg(server.url())
+        .args(["--cases", "unary,server-streaming", "--timeout", "5", "--report"])
+        .arg(&path)
+        .output()?;
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)?.contains("2 of 2 interop cases failed"));
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("FAIL  unary"), "{}", stdout);
+
+    let report: serde_json::Value = serde_json::from_slice(&std::fs::read(&path)?)?;
+    assert_eq!(report["failed"], 2);
+    assert_eq!(report["cases"][0]["case"], "unary");
+    assert!(report["cases"][0]["error"].as_str().unwrap().contains("Echo of 13 bytes"));
+    Ok(())
+}
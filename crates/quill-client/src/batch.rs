@@ -0,0 +1,319 @@
+//! Keyed batch get
+//!
+//! This module provides:
+//! - Coalescing of individual `get(key)` calls into batched RPCs
+//! - A short collection window bounded by a maximum batch size
+//! - Splitting batched responses back to the individual callers
+//! - Per-key caching honoring a TTL (the `cache_ttl_ms` RPC hint)
+//!
+//! This is the runtime behind generated `KeyedBatchGet` helpers, the
+//! pattern used by feature stores and embedding lookups where many callers
+//! fetch small values by key at the same time.
+
+use crate::tee::clone_error;
+use quill_core::QuillError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Fetches values for a batch of keys; keys missing from the result are absent
+pub type BatchFetchFn<K, V> = Arc<
+    dyn Fn(Vec<K>) -> Pin<Box<dyn Future<Output = Result<Vec<(K, V)>, QuillError>> + Send>>
+        + Send
+        + Sync,
+>;
+
+type Waiter<V> = oneshot::Sender<Result<Option<V>, QuillError>>;
+
+/// Batching configuration
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// How long to collect keys before sending a batch
+    pub window: Duration,
+    /// Send a batch immediately once this many distinct keys are pending
+    pub max_batch: usize,
+    /// Cache values for this long (None = no caching)
+    pub cache_ttl: Option<Duration>,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(2),
+            max_batch: 128,
+            cache_ttl: None,
+        }
+    }
+}
+
+impl BatchConfig {
+    /// Set the collection window
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the maximum batch size
+    pub fn max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+
+    /// Set the per-key cache TTL from a `cache_ttl_ms` hint (0 disables caching)
+    pub fn cache_ttl_ms(mut self, ttl_ms: u64) -> Self {
+        self.cache_ttl = (ttl_ms > 0).then(|| Duration::from_millis(ttl_ms));
+        self
+    }
+}
+
+struct Pending<K, V> {
+    waiters: HashMap<K, Vec<Waiter<V>>>,
+    flush_scheduled: bool,
+}
+
+struct Inner<K, V> {
+    config: BatchConfig,
+    fetch: BatchFetchFn<K, V>,
+    pending: Mutex<Pending<K, V>>,
+    cache: Mutex<HashMap<K, (V, Instant)>>,
+}
+
+/// Coalesces keyed lookups into batched calls
+pub struct KeyedBatcher<K, V> {
+    inner: Arc<Inner<K, V>>,
+}
+
+impl<K, V> Clone for KeyedBatcher<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<K, V> KeyedBatcher<K, V>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    /// Create a batcher around a batch fetch function
+    pub fn new<F, Fut>(config: BatchConfig, fetch: F) -> Self
+    where
+        F: Fn(Vec<K>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<(K, V)>, QuillError>> + Send + 'static,
+    {
+        let fetch: BatchFetchFn<K, V> = Arc::new(move |keys| Box::pin(fetch(keys)) as Pin<Box<_>>);
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                fetch,
+                pending: Mutex::new(Pending {
+                    waiters: HashMap::new(),
+                    flush_scheduled: false,
+                }),
+                cache: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Look up a single key
+    ///
+    /// Returns `None` if the batch response did not contain the key.
+    pub async fn get(&self, key: K) -> Result<Option<V>, QuillError> {
+        if let Some(value) = self.cached(&key) {
+            return Ok(Some(value));
+        }
+
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut pending = self.inner.pending.lock().unwrap();
+            pending.waiters.entry(key).or_default().push(tx);
+
+            if pending.waiters.len() >= self.inner.config.max_batch {
+                let batch = std::mem::take(&mut pending.waiters);
+                tokio::spawn(Self::flush(Arc::clone(&self.inner), batch));
+            } else if !pending.flush_scheduled {
+                pending.flush_scheduled = true;
+                let inner = Arc::clone(&self.inner);
+                tokio::spawn(async move {
+                    tokio::time::sleep(inner.config.window).await;
+                    let batch = {
+                        let mut pending = inner.pending.lock().unwrap();
+                        pending.flush_scheduled = false;
+                        std::mem::take(&mut pending.waiters)
+                    };
+                    Self::flush(inner, batch).await;
+                });
+            }
+        }
+
+        match rx.await {
+            Ok(result) => result,
+            Err(_) => Err(QuillError::Rpc("Batch dropped before completion".to_string())),
+        }
+    }
+
+    /// Drop a key from the cache
+    pub fn invalidate(&self, key: &K) {
+        self.inner.cache.lock().unwrap().remove(key);
+    }
+
+    /// Drop every cached value
+    pub fn clear_cache(&self) {
+        self.inner.cache.lock().unwrap().clear();
+    }
+
+    fn cached(&self, key: &K) -> Option<V> {
+        let ttl = self.inner.config.cache_ttl?;
+        let mut cache = self.inner.cache.lock().unwrap();
+        match cache.get(key) {
+            Some((value, stored)) if stored.elapsed() < ttl => Some(value.clone()),
+            Some(_) => {
+                cache.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn flush(inner: Arc<Inner<K, V>>, mut batch: HashMap<K, Vec<Waiter<V>>>) {
+        if batch.is_empty() {
+            return;
+        }
+        let keys: Vec<K> = batch.keys().cloned().collect();
+        tracing::trace!("Sending batch of {} keys", keys.len());
+
+        match (inner.fetch)(keys).await {
+            Ok(values) => {
+                if inner.config.cache_ttl.is_some() {
+                    let now = Instant::now();
+                    let mut cache = inner.cache.lock().unwrap();
+                    for (key, value) in &values {
+                        cache.insert(key.clone(), (value.clone(), now));
+                    }
+                }
+                for (key, value) in values {
+                    if let Some(waiters) = batch.remove(&key) {
+                        for waiter in waiters {
+                            let _ = waiter.send(Ok(Some(value.clone())));
+                        }
+                    }
+                }
+                // Keys the server did not return
+                for waiter in batch.into_values().flatten() {
+                    let _ = waiter.send(Ok(None));
+                }
+            }
+            Err(error) => {
+                for waiter in batch.into_values().flatten() {
+                    let _ = waiter.send(Err(clone_error(&error)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Batcher that doubles numeric keys, ignores key 0, and records batch sizes
+    fn doubler(config: BatchConfig) -> (KeyedBatcher<u32, u64>, Arc<Mutex<Vec<usize>>>) {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&batches);
+        let batcher = KeyedBatcher::new(config, move |keys: Vec<u32>| {
+            recorded.lock().unwrap().push(keys.len());
+            async move {
+                Ok(keys.into_iter().filter(|k| *k != 0).map(|k| (k, k as u64 * 2)).collect())
+            }
+        });
+        (batcher, batches)
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_gets_are_coalesced() {
+        let (batcher, batches) = doubler(BatchConfig::default().window(Duration::from_millis(10)));
+
+        let gets = (1..=5).map(|k| {
+            let batcher = batcher.clone();
+            tokio::spawn(async move { batcher.get(k).await })
+        });
+        let mut results = Vec::new();
+        for get in gets.collect::<Vec<_>>() {
+            results.push(get.await.unwrap().unwrap());
+        }
+
+        assert_eq!(results, vec![Some(2), Some(4), Some(6), Some(8), Some(10)]);
+        assert_eq!(*batches.lock().unwrap(), vec![5]);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_keys_share_one_slot() {
+        let (batcher, batches) = doubler(BatchConfig::default().window(Duration::from_millis(10)));
+
+        let (a, b) = tokio::join!(batcher.get(7), batcher.get(7));
+        assert_eq!(a.unwrap(), Some(14));
+        assert_eq!(b.unwrap(), Some(14));
+        assert_eq!(*batches.lock().unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_max_batch_flushes_early() {
+        let config = BatchConfig::default().window(Duration::from_secs(60)).max_batch(3);
+        let (batcher, batches) = doubler(config);
+
+        let (a, b, c) = tokio::time::timeout(
+            Duration::from_secs(1),
+            async { tokio::join!(batcher.get(1), batcher.get(2), batcher.get(3)) },
+        )
+        .await
+        .expect("full batch should not wait for the window");
+        assert_eq!((a.unwrap(), b.unwrap(), c.unwrap()), (Some(2), Some(4), Some(6)));
+        assert_eq!(*batches.lock().unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_key_is_none() {
+        let (batcher, _) = doubler(BatchConfig::default());
+        assert_eq!(batcher.get(0).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cache_honors_ttl() {
+        let config = BatchConfig::default().window(Duration::from_millis(1)).cache_ttl_ms(50);
+        let (batcher, batches) = doubler(config);
+
+        assert_eq!(batcher.get(3).await.unwrap(), Some(6));
+        assert_eq!(batcher.get(3).await.unwrap(), Some(6));
+        assert_eq!(batches.lock().unwrap().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(batcher.get(3).await.unwrap(), Some(6));
+        assert_eq!(batches.lock().unwrap().len(), 2);
+
+        batcher.invalidate(&3);
+        assert_eq!(batcher.get(3).await.unwrap(), Some(6));
+        assert_eq!(batches.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_error_reaches_every_caller() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&calls);
+        let batcher: KeyedBatcher<u32, u64> =
+            KeyedBatcher::new(BatchConfig::default(), move |_keys: Vec<u32>| {
+                counted.fetch_add(1, Ordering::Relaxed);
+                async { Err(QuillError::Transport("unavailable".to_string())) }
+            });
+
+        let (a, b) = tokio::join!(batcher.get(1), batcher.get(2));
+        assert!(matches!(a, Err(QuillError::Transport(_))));
+        assert!(matches!(b, Err(QuillError::Transport(_))));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
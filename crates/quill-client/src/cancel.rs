@@ -0,0 +1,295 @@
+//! Cancellation of in-flight calls
+//!
+//! A [`CancelToken`] attached to a call through
+//! [`RequestOptions::cancel_token`](crate::RequestOptions::cancel_token)
+//! lets another task stop it:
+//! - A pending unary or streaming call resolves with a Problem Details
+//!   error for which [`QuillError::is_cancelled`] is true
+//! - A response stream yields that error next and drops its body, which
+//!   resets the transport stream so the server stops the handler
+//! - A bidirectional request stream sends a CANCEL frame in place of
+//!   END_STREAM, so the handler's request stream fails with the same error
+//!
+//! One token may be shared by several calls to cancel them together.
+//!
+//! The server acknowledges a bidirectional call's CANCEL with a CANCEL_ACK
+//! frame once its handler has stopped. [`CancelToken::acknowledged`] waits
+//! for the acknowledgments, e.g. before reusing GPU memory the call held;
+//! the rest of the response is read and discarded in the meantime. A call
+//! gives up waiting after the client's
+//! [`cancel_ack_timeout`](crate::client::ClientBuilder::cancel_ack_timeout),
+//! and [`QuillClient::cancel_stats`](crate::QuillClient::cancel_stats)
+//! counts the cancels that went unacknowledged.
+
+use quill_core::{ProblemDetails, QuillError};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Default time a cancelled call waits for the server's acknowledgment
+pub const DEFAULT_CANCEL_ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Future resolving once a token is cancelled
+pub(crate) type Cancelled = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Handle cancelling the calls it is attached to
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    acks: Mutex<AckState>,
+    notify: Notify,
+}
+
+/// Calls of a token that may wait for their cancel to be acknowledged
+#[derive(Default)]
+struct AckState {
+    /// Calls whose acknowledgment is still outstanding
+    waiting: usize,
+    /// Cancelled calls that ended without an acknowledgment
+    unacknowledged: usize,
+}
+
+impl CancelToken {
+    /// Create a token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every call using this token, now and in the future
+    ///
+    /// Returns false if the token was already cancelled.
+    pub fn cancel(&self) -> bool {
+        let first = !self.inner.cancelled.swap(true, Ordering::SeqCst);
+        if first {
+            self.inner.notify.notify_waiters();
+        }
+        first
+    }
+
+    /// Whether the token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            // Registered before checking, so a cancel in between is not missed
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Wait until the bidirectional calls this token cancelled are acknowledged
+    ///
+    /// Waits for the token to be cancelled first. Returns true once the
+    /// server of every such call confirmed that its handler stopped, and
+    /// false if any call ended or timed out without that confirmation.
+    /// Other calls are reset on cancel and not counted. A response stream
+    /// only notices the cancel once it is polled or dropped.
+    pub async fn acknowledged(&self) -> bool {
+        loop {
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                let acks = self.inner.acks.lock().unwrap();
+                if acks.waiting == 0 {
+                    return acks.unacknowledged == 0;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// [`cancelled`](Self::cancelled) as an owned future
+    pub(crate) fn cancelled_owned(&self) -> Cancelled {
+        let token = self.clone();
+        Box::pin(async move { token.cancelled().await })
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Acknowledgments of the cancels a client sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CancelStats {
+    /// Cancels the server acknowledged
+    pub acknowledged: u64,
+    /// Cancels that ended without an acknowledgment, including timed out ones
+    pub unacknowledged: u64,
+    /// Cancels whose acknowledgment timed out
+    pub timed_out: u64,
+}
+
+/// Counters behind [`CancelStats`], shared by the calls of a client
+#[derive(Debug, Default)]
+pub(crate) struct CancelCounters {
+    acknowledged: AtomicU64,
+    unacknowledged: AtomicU64,
+    timed_out: AtomicU64,
+}
+
+impl CancelCounters {
+    pub(crate) fn stats(&self) -> CancelStats {
+        CancelStats {
+            acknowledged: self.acknowledged.load(Ordering::Relaxed),
+            unacknowledged: self.unacknowledged.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// How a cancelled call's wait for CANCEL_ACK ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AckOutcome {
+    Acknowledged,
+    Unacknowledged,
+    TimedOut,
+}
+
+/// A call whose cancel, if it comes, awaits the server's acknowledgment
+///
+/// Dropped unresolved, it counts as unacknowledged if the token was
+/// cancelled and is forgotten otherwise.
+pub(crate) struct AckRegistration {
+    token: CancelToken,
+    counters: Arc<CancelCounters>,
+    timeout: Duration,
+    outcome: Option<AckOutcome>,
+}
+
+impl AckRegistration {
+    pub(crate) fn new(token: &CancelToken, counters: Arc<CancelCounters>, timeout: Duration) -> Self {
+        token.inner.acks.lock().unwrap().waiting += 1;
+        Self {
+            token: token.clone(),
+            counters,
+            timeout,
+            outcome: None,
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// How long to wait for the acknowledgment
+    pub(crate) fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub(crate) fn resolve(mut self, outcome: AckOutcome) {
+        self.outcome = Some(outcome);
+    }
+}
+
+impl Drop for AckRegistration {
+    fn drop(&mut self) {
+        let outcome = self
+            .outcome
+            .or_else(|| self.token.is_cancelled().then_some(AckOutcome::Unacknowledged));
+        {
+            let mut acks = self.token.inner.acks.lock().unwrap();
+            acks.waiting -= 1;
+            match outcome {
+                Some(AckOutcome::Acknowledged) => {
+                    self.counters.acknowledged.fetch_add(1, Ordering::Relaxed);
+                }
+                Some(AckOutcome::Unacknowledged) => {
+                    acks.unacknowledged += 1;
+                    self.counters.unacknowledged.fetch_add(1, Ordering::Relaxed);
+                }
+                Some(AckOutcome::TimedOut) => {
+                    acks.unacknowledged += 1;
+                    self.counters.unacknowledged.fetch_add(1, Ordering::Relaxed);
+                    self.counters.timed_out.fetch_add(1, Ordering::Relaxed);
+                }
+                None => {}
+            }
+        }
+        self.token.inner.notify.notify_waiters();
+    }
+}
+
+/// Error of a cancelled call
+pub(crate) fn cancelled_error() -> QuillError {
+    QuillError::ProblemDetails(ProblemDetails::cancelled())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_wakes_waiters() {
+        let token = CancelToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.cancelled().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        assert!(token.cancel());
+        assert!(!token.cancel());
+        tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+
+        // Waiting on a cancelled token returns at once
+        assert!(token.is_cancelled());
+        token.cancelled_owned().await;
+        assert!(cancelled_error().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_acknowledged_waits_for_every_call() {
+        let token = CancelToken::new();
+        let counters = Arc::new(CancelCounters::default());
+        let first = AckRegistration::new(&token, Arc::clone(&counters), DEFAULT_CANCEL_ACK_TIMEOUT);
+        let second = AckRegistration::new(&token, Arc::clone(&counters), DEFAULT_CANCEL_ACK_TIMEOUT);
+        // A call that ended before the cancel is not counted
+        drop(AckRegistration::new(&token, Arc::clone(&counters), DEFAULT_CANCEL_ACK_TIMEOUT));
+
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.acknowledged().await }
+        });
+        token.cancel();
+        first.resolve(AckOutcome::Acknowledged);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        second.resolve(AckOutcome::Acknowledged);
+        assert!(tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap());
+        assert_eq!(counters.stats().acknowledged, 2);
+
+        // A call dropped after the cancel without an acknowledgment
+        drop(AckRegistration::new(&token, Arc::clone(&counters), DEFAULT_CANCEL_ACK_TIMEOUT));
+        assert!(!token.acknowledged().await);
+        assert_eq!(
+            counters.stats(),
+            CancelStats {
+                acknowledged: 2,
+                unacknowledged: 1,
+                timed_out: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Bridges between Quill streams and tokio channels
+//!
+//! This module provides:
+//! - [`stream_to_channel`], draining a response stream into a bounded mpsc channel
+//! - [`channel_to_request`], a request stream fed by a bounded mpsc channel
+//!
+//! A response stream grants the server credit as it is read, so reading it
+//! only when the channel has room turns the channel's capacity into the
+//! call's flow-control window: a consumer that falls behind stops the
+//! server rather than growing a buffer. Request streams are read by the
+//! transport as the server accepts data, so a bounded channel behind one
+//! makes the producer's `send` wait in turn.
+//!
+//! Dropping the receiving end cancels the call, and the first error ends
+//! the stream it travels on.
+
+use bytes::Bytes;
+use quill_core::QuillError;
+use std::pin::Pin;
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, StreamExt};
+
+/// Drain `stream` into a bounded channel holding `capacity` messages
+///
+/// A task reads the stream only once the channel has room. It stops,
+/// dropping (and so cancelling) the stream, when the stream ends, after
+/// forwarding its first error, or once the receiver is dropped.
+pub fn stream_to_channel<S, T>(stream: S, capacity: usize) -> mpsc::Receiver<Result<T, QuillError>>
+where
+    S: Stream<Item = Result<T, QuillError>> + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    tokio::spawn(async move {
+        tokio::pin!(stream);
+        loop {
+            let Ok(permit) = tx.reserve().await else {
+                return;
+            };
+            // Wait for the next message only while someone is still listening
+            let item = tokio::select! {
+                item = stream.next() => item,
+                () = tx.closed() => return,
+            };
+            match item {
+                Some(Ok(message)) => permit.send(Ok(message)),
+                Some(Err(e)) => {
+                    permit.send(Err(e));
+                    return;
+                }
+                None => return,
+            }
+        }
+    });
+    rx
+}
+
+/// Request stream sending the messages of a bounded mpsc channel
+///
+/// The stream ends when every sender is dropped. An error ends it too,
+/// failing the call, and closes the channel so the producer stops.
+pub fn channel_to_request<T, E>(
+    rx: mpsc::Receiver<Result<T, E>>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>
+where
+    T: Into<Bytes> + Send + 'static,
+    E: Into<QuillError> + Send + 'static,
+{
+    Box::pin(futures_util::stream::unfold(Some(rx), |rx| async move {
+        let mut rx = rx?;
+        match rx.recv().await? {
+            Ok(message) => Some((Ok(message.into()), Some(rx))),
+            Err(e) => Some((Err(e.into()), None)),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_stream_to_channel_forwards_first_error() {
+        let source = tokio_stream::iter(vec![
+            Ok(Bytes::from("a")),
+            Err(QuillError::Transport("connection reset".to_string())),
+            Ok(Bytes::from("b")),
+        ]);
+        let mut rx = stream_to_channel(source, 4);
+
+        assert_eq!(rx.recv().await.unwrap().unwrap(), Bytes::from("a"));
+        assert!(matches!(rx.recv().await, Some(Err(QuillError::Transport(_)))));
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_to_channel_stops_when_receiver_drops() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&pulled);
+        let source = tokio_stream::iter(0..100).map(move |n| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, QuillError>(n)
+        });
+
+        let rx = stream_to_channel(source, 3);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(pulled.load(Ordering::SeqCst), 3);
+        drop(rx);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(pulled.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_channel_to_request_closes_channel_on_error() {
+        let (tx, rx) = mpsc::channel::<Result<&'static str, QuillError>>(2);
+        let mut request = channel_to_request(rx);
+
+        tx.send(Ok("a")).await.unwrap();
+        tx.send(Err(QuillError::Rpc("invalid chunk".into()))).await.unwrap();
+        assert_eq!(request.next().await.unwrap().unwrap(), Bytes::from("a"));
+        assert!(request.next().await.unwrap().is_err());
+        assert!(request.next().await.is_none());
+        assert!(tx.send(Ok("b")).await.is_err());
+    }
+}
@@ -1,20 +1,52 @@
 //! Quill client implementation
 
+use crate::cancel::{
+    cancelled_error, AckOutcome, AckRegistration, CancelCounters, CancelStats, CancelToken, Cancelled,
+    DEFAULT_CANCEL_ACK_TIMEOUT,
+};
+use crate::dictionary::DictionaryNegotiation;
+use crate::envelope::EnvelopeEncryption;
+use crate::events::{ClientEvent, ClientEventKind, ClientEvents, EventListener};
+use crate::flow_control::{FlowControlConfig, ReceiveWindow};
+use crate::health::{ConnectionHealth, ConnectionHealthConfig, ConnectionHealthStats};
+use crate::frame_encryption::StreamEncryption;
+use crate::interceptor::{CallInfo, ClientInterceptor, InterceptorChain};
+use crate::offline::{CallOutcome, OfflineQueue, ReplayReport, IDEMPOTENCY_KEY_HEADER};
+use crate::preconnect::{prewarm, PreconnectReport};
 use crate::retry::{CircuitBreaker, RetryPolicy};
-use crate::streaming::encode_request_stream;
+use crate::rtt::{RttEstimator, RttStats};
+use crate::streaming::{
+    deferred_body, encode_sealed_request_stream, full_body, streaming_body, RequestBody,
+};
+use crate::transfer::TransferControl;
+use crate::uds::Connector;
+use crate::upload::{ChunkedUpload, UploadNegotiation};
 use bytes::Bytes;
 use http::header::{
     HeaderName, HeaderValue, ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE,
 };
-use http::{HeaderMap, Method, Request};
-use http_body_util::{BodyExt, Full};
-use hyper_util::client::legacy::{connect::HttpConnector, Client};
-use hyper_util::rt::TokioExecutor;
-use quill_core::{CreditTracker, FrameParser, ProfilePreference, QuillError};
+use http::{HeaderMap, Method, Request, StatusCode};
+use http_body_util::BodyExt;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use quill_core::{
+    decode_batch_response, encode_batch_request, parse_dictionary_id, BatchEntry, BatchResult,
+    CompressionDictionary, CreditTracker, DataKey, Deadline, EnvelopeHeader, FlowControlHeader, FrameCipher,
+    FrameKey, FrameParser,
+    Metadata, PartialStats, ProblemDetails, ProfilePreference, QuillError, StreamCursor, UploadCapability, Usage,
+    UploadManifest, BATCH_METHOD, BATCH_SERVICE, DICTIONARY_HEADER, DICTIONARY_METHOD,
+    DICTIONARY_SERVICE, ENVELOPE_HEADER, FLOW_CONTROL_HEADER, FRAME_ENCRYPTION_HEADER, HEALTH_CHECK_METHOD,
+    HEALTH_SERVICE, HEALTH_WATCH_METHOD, HealthCheckResponse, PING_METHOD, PING_SERVICE, ServingStatus,
+    REFLECTION_METHOD, REFLECTION_SERVICE, RESPONSE_CACHE_INVALIDATE_METHOD, RESPONSE_CACHE_SERVICE,
+    RESUME_TOKEN_HEADER, TIMEOUT_HEADER, UPLOAD_CHUNK_HEADER, UPLOAD_MANIFEST_HEADER,
+};
+#[cfg(feature = "test-util")]
+use quill_transport::sim::SimNetwork;
 use std::fmt;
+use std::path::PathBuf;
 use std::pin::Pin;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio_stream::Stream;
 use tracing::instrument;
 
@@ -56,22 +88,63 @@ pub struct ClientConfig {
     pub http2_keep_alive_interval: Option<Duration>,
     /// HTTP/2 only: keep alive timeout
     pub http2_keep_alive_timeout: Option<Duration>,
+    /// Timeout of calls without their own (None = no timeout)
+    pub timeout: Option<Duration>,
+    /// Time a cancelled bidirectional call waits for the server's acknowledgment
+    pub cancel_ack_timeout: Duration,
     /// Retry policy (None = no retries)
     pub retry_policy: Option<RetryPolicy>,
     /// Circuit breaker (None = no circuit breaking)
     pub circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// Offline queue for calls made while the network is down (None = disabled)
+    pub offline_queue: Option<Arc<OfflineQueue>>,
+    /// Connections to establish and health-check on connect (0 = lazy)
+    pub preconnect: usize,
+    /// Envelope encryption of sensitive requests (None = disabled)
+    pub envelope: Option<Arc<EnvelopeEncryption>>,
+    /// Shared key for end-to-end encryption of stream frames (None = disabled)
+    pub frame_encryption: Option<FrameKey>,
+    /// Listeners for connection lifecycle events
+    pub event_listeners: Vec<EventListener>,
+    /// Interceptors run around every request, in registration order
+    pub interceptors: Vec<Arc<dyn ClientInterceptor>>,
+    /// Credit-based flow control of streaming responses (None = disabled)
+    pub flow_control: Option<FlowControlConfig>,
+    /// Fetch and use the server's compression dictionary (needs compression enabled)
+    pub compression_dictionary: bool,
+    /// Unix domain socket to connect to instead of the base URL's host (None = TCP)
+    pub uds_path: Option<PathBuf>,
+    /// Health scoring and eviction of degraded connections (None = disabled)
+    pub connection_health: Option<ConnectionHealthConfig>,
+    /// Simulated network to connect over instead of TCP (None = real network)
+    #[cfg(feature = "test-util")]
+    pub sim_network: Option<SimNetwork>,
 }
 
 impl fmt::Debug for ClientConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("ClientConfig")
-            .field("http_protocol", &self.http_protocol)
+        let mut f = f.debug_struct("ClientConfig");
+        f.field("http_protocol", &self.http_protocol)
             .field("pool_idle_timeout", &self.pool_idle_timeout)
             .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
             .field("http2_adaptive_window", &self.http2_adaptive_window)
+            .field("timeout", &self.timeout)
+            .field("cancel_ack_timeout", &self.cancel_ack_timeout)
             .field("retry_policy", &self.retry_policy.as_ref().map(|_| "<RetryPolicy>"))
             .field("circuit_breaker", &self.circuit_breaker.as_ref().map(|_| "<CircuitBreaker>"))
-            .finish()
+            .field("offline_queue", &self.offline_queue)
+            .field("preconnect", &self.preconnect)
+            .field("envelope", &self.envelope)
+            .field("frame_encryption", &self.frame_encryption)
+            .field("event_listeners", &self.event_listeners.len())
+            .field("interceptors", &self.interceptors.len())
+            .field("flow_control", &self.flow_control)
+            .field("compression_dictionary", &self.compression_dictionary)
+            .field("uds_path", &self.uds_path)
+            .field("connection_health", &self.connection_health);
+        #[cfg(feature = "test-util")]
+        f.field("sim_network", &self.sim_network);
+        f.finish()
     }
 }
 
@@ -87,8 +160,22 @@ impl Default for ClientConfig {
             http2_max_concurrent_streams: Some(100),
             http2_keep_alive_interval: Some(Duration::from_secs(10)),
             http2_keep_alive_timeout: Some(Duration::from_secs(20)),
+            timeout: None,
+            cancel_ack_timeout: DEFAULT_CANCEL_ACK_TIMEOUT,
             retry_policy: None,
             circuit_breaker: None,
+            offline_queue: None,
+            preconnect: 0,
+            envelope: None,
+            frame_encryption: None,
+            event_listeners: Vec::new(),
+            interceptors: Vec::new(),
+            flow_control: None,
+            compression_dictionary: false,
+            uds_path: None,
+            connection_health: None,
+            #[cfg(feature = "test-util")]
+            sim_network: None,
         }
     }
 }
@@ -97,9 +184,13 @@ impl Default for ClientConfig {
 #[derive(Clone, Debug, Default)]
 pub struct RequestOptions {
     headers: HeaderMap,
+    metadata: Metadata,
     accept: Option<HeaderValue>,
     profile_preference: Option<ProfilePreference>,
     timeout: Option<Duration>,
+    stream_idle_timeout: Option<Duration>,
+    cancel: Option<CancelToken>,
+    pub(crate) chunked_upload: Option<ChunkedUpload>,
 }
 
 impl RequestOptions {
@@ -119,6 +210,20 @@ impl RequestOptions {
         self.headers.insert(name, value);
     }
 
+    /// Attach metadata, replacing metadata attached earlier.
+    ///
+    /// Handlers read it from their request context. Headers added with
+    /// [`header`](Self::header) take precedence over metadata of the same name.
+    pub fn metadata(mut self, value: Metadata) -> Self {
+        self.metadata = value;
+        self
+    }
+
+    /// Attach metadata in place.
+    pub fn set_metadata(&mut self, value: Metadata) {
+        self.metadata = value;
+    }
+
     /// Override the Accept header for this request.
     pub fn accept(mut self, value: HeaderValue) -> Self {
         self.accept = Some(value);
@@ -142,6 +247,11 @@ impl RequestOptions {
     }
 
     /// Apply a timeout to the request operation.
+    ///
+    /// The timeout is sent to the server in the `quill-timeout-ms` header,
+    /// so the handler can see its deadline. On expiry the call fails with a
+    /// Problem Details error for which [`QuillError::is_deadline_exceeded`]
+    /// is true.
     pub fn timeout(mut self, value: Duration) -> Self {
         self.timeout = Some(value);
         self
@@ -151,58 +261,148 @@ impl RequestOptions {
     pub fn set_timeout(&mut self, value: Duration) {
         self.timeout = Some(value);
     }
+
+    /// Fail a streaming response once no message arrives for `value`.
+    ///
+    /// The stream then yields a Problem Details error for which
+    /// [`QuillError::is_stream_idle_timeout`] is true, as it does when the
+    /// server cancels an idle stream.
+    pub fn stream_idle_timeout(mut self, value: Duration) -> Self {
+        self.stream_idle_timeout = Some(value);
+        self
+    }
+
+    /// Fail a streaming response once no message arrives for `value`, in place.
+    pub fn set_stream_idle_timeout(&mut self, value: Duration) {
+        self.stream_idle_timeout = Some(value);
+    }
+
+    /// Cancel the call once `token` is cancelled.
+    ///
+    /// The call, or its response stream, then fails with a Problem Details
+    /// error for which [`QuillError::is_cancelled`] is true. See
+    /// [`crate::cancel`].
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Cancel the call once `token` is cancelled, in place.
+    pub fn set_cancel_token(&mut self, token: CancelToken) {
+        self.cancel = Some(token);
+    }
+
+    /// Split a large unary request across parallel streams if the server supports it.
+    pub fn chunked_upload(mut self, value: ChunkedUpload) -> Self {
+        self.chunked_upload = Some(value);
+        self
+    }
 }
 
+/// HTTP client shared by the calls of a [`QuillClient`]
+pub(crate) type HttpClient = Client<Connector, RequestBody>;
+
 /// Quill RPC client
 pub struct QuillClient {
     base_url: String,
-    client: Client<HttpConnector, Full<Bytes>>,
+    /// Replaced when degraded connections are evicted
+    client: RwLock<HttpClient>,
     profile_preference: ProfilePreference,
     enable_compression: bool,
     compression_level: i32,
     config: ClientConfig,
+    rtt: Arc<RttEstimator>,
+    uploads: UploadNegotiation,
+    dictionaries: DictionaryNegotiation,
+    events: Arc<ClientEvents>,
+    health: Option<Arc<ConnectionHealth>>,
+    cancels: Arc<CancelCounters>,
 }
 
 impl QuillClient {
     /// Create a new client with the given base URL
     pub fn new(base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into();
         let config = ClientConfig::default();
         let client = Self::build_client(&config);
+        let events = Self::build_events(&base_url, &config);
 
         Self {
-            base_url: base_url.into(),
-            client,
+            base_url,
+            client: RwLock::new(client),
             profile_preference: ProfilePreference::default_preference(),
             enable_compression: false,
             compression_level: 3,
+            health: Self::build_health(&config),
             config,
+            rtt: Arc::new(RttEstimator::new()),
+            uploads: UploadNegotiation::default(),
+            dictionaries: DictionaryNegotiation::default(),
+            events,
+            cancels: Arc::default(),
         }
     }
 
     /// Create a new client with custom configuration
     pub fn with_config(base_url: impl Into<String>, config: ClientConfig) -> Self {
+        let base_url = base_url.into();
         let client = Self::build_client(&config);
+        let events = Self::build_events(&base_url, &config);
 
         Self {
-            base_url: base_url.into(),
-            client,
+            base_url,
+            client: RwLock::new(client),
             profile_preference: ProfilePreference::default_preference(),
             enable_compression: false,
             compression_level: 3,
+            health: Self::build_health(&config),
             config,
+            rtt: Arc::new(RttEstimator::new()),
+            uploads: UploadNegotiation::default(),
+            dictionaries: DictionaryNegotiation::default(),
+            events,
+            cancels: Arc::default(),
         }
     }
 
+    /// Build the lifecycle event tracker, reporting circuit breaker openings
+    fn build_events(base_url: &str, config: &ClientConfig) -> Arc<ClientEvents> {
+        let events = Arc::new(ClientEvents::new(base_url, config.event_listeners.clone()));
+        if let (Some(breaker), false) = (&config.circuit_breaker, config.event_listeners.is_empty()) {
+            let events = Arc::clone(&events);
+            breaker.set_on_open(Arc::new(move || events.emit(ClientEventKind::CircuitOpened)));
+        }
+        events
+    }
+
+    /// Build the connection health tracker, if health scoring is enabled
+    fn build_health(config: &ClientConfig) -> Option<Arc<ConnectionHealth>> {
+        config
+            .connection_health
+            .clone()
+            .map(|health| Arc::new(ConnectionHealth::new(health)))
+    }
+
     /// Build an HTTP client based on configuration
-    fn build_client(config: &ClientConfig) -> Client<HttpConnector, Full<Bytes>> {
+    fn build_client(config: &ClientConfig) -> HttpClient {
         let mut builder = Client::builder(TokioExecutor::new());
+        // HTTP/2 keep-alive pings need a timer
+        builder.timer(TokioTimer::new());
 
         // Configure connection pool
         builder.pool_idle_timeout(config.pool_idle_timeout.unwrap_or(Duration::from_secs(90)));
         builder.pool_max_idle_per_host(config.pool_max_idle_per_host);
 
-        // Configure HTTP protocol
-        match config.http_protocol {
+        // Configure HTTP protocol; sockets have no ALPN, so Auto speaks HTTP/2 there
+        #[cfg(feature = "test-util")]
+        let socket = config.uds_path.is_some() || config.sim_network.is_some();
+        #[cfg(not(feature = "test-util"))]
+        let socket = config.uds_path.is_some();
+        let protocol = match config.http_protocol {
+            HttpProtocol::Auto if socket => HttpProtocol::Http2,
+            protocol => protocol,
+        };
+        match protocol {
             HttpProtocol::Http1 => {
                 builder.http2_only(false);
             }
@@ -248,7 +448,10 @@ impl QuillClient {
             }
         }
 
-        builder.build_http()
+        let connector = Connector::new(config.uds_path.clone());
+        #[cfg(feature = "test-util")]
+        let connector = connector.sim(config.sim_network.clone());
+        builder.build(connector)
     }
 
     /// Create a builder for configuring the client
@@ -256,29 +459,214 @@ impl QuillClient {
         ClientBuilder::new()
     }
 
+    /// Base URL this client sends requests to
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Send the built-in ping RPC and record its round-trip time
+    ///
+    /// The measured RTT feeds the estimator behind [`rtt`](Self::rtt).
+    pub async fn ping(&self) -> Result<Duration, QuillError> {
+        let start = Instant::now();
+        self.call(PING_SERVICE, PING_METHOD, Bytes::new()).await?;
+        let elapsed = start.elapsed();
+        self.rtt.record(elapsed);
+        Ok(elapsed)
+    }
+
+    /// Ask the server for the serving status of a service through the built-in health RPC
+    ///
+    /// `None` asks about the server as a whole. Fails with a `404` problem
+    /// for services the server does not know. The server must have health
+    /// checking enabled.
+    pub async fn health_check(&self, service: Option<&str>) -> Result<ServingStatus, QuillError> {
+        let request = Bytes::from(service.unwrap_or_default().to_string());
+        let response = self.call(HEALTH_SERVICE, HEALTH_CHECK_METHOD, request).await?;
+        HealthCheckResponse::from_json(&response)
+            .map(|response| response.status)
+            .map_err(|e| QuillError::Rpc(format!("Invalid health response: {}", e)))
+    }
+
+    /// Stream the serving status of a service: now, then on every change
+    ///
+    /// `None` watches the server as a whole. Unknown services are reported
+    /// as [`ServingStatus::ServiceUnknown`] until they appear.
+    pub async fn watch_health(
+        &self,
+        service: Option<&str>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<ServingStatus, QuillError>> + Send>>, QuillError> {
+        use tokio_stream::StreamExt;
+
+        let request = Bytes::from(service.unwrap_or_default().to_string());
+        let stream = self
+            .call_server_streaming(HEALTH_SERVICE, HEALTH_WATCH_METHOD, request)
+            .await?;
+        Ok(Box::pin(stream.map(|message| {
+            let message = message?;
+            HealthCheckResponse::from_json(&message)
+                .map(|response| response.status)
+                .map_err(|e| QuillError::Rpc(format!("Invalid health response: {}", e)))
+        })))
+    }
+
+    /// Fetch descriptors of the server's services through the built-in reflection RPC
+    ///
+    /// Returns an encoded `FileDescriptorSet` with the files defining every
+    /// served service, or only `service` (e.g. `greeter.v1.Greeter`), and
+    /// their imports. The server must have reflection enabled.
+    pub async fn file_descriptor_set(&self, service: Option<&str>) -> Result<Bytes, QuillError> {
+        let request = Bytes::from(service.unwrap_or_default().to_string());
+        self.call(REFLECTION_SERVICE, REFLECTION_METHOD, request).await
+    }
+
+    /// Drop responses cached by the server through the built-in invalidation RPC
+    ///
+    /// `target` names a method (`pkg.Service/Method`) or a whole service
+    /// (`pkg.Service`); `None` drops every cached response. Returns the
+    /// number of responses dropped. The server must have a response cache
+    /// with remote invalidation enabled and authorize this caller.
+    pub async fn invalidate_response_cache(&self, target: Option<&str>) -> Result<usize, QuillError> {
+        let request = Bytes::from(target.unwrap_or_default().to_string());
+        let response = self
+            .call(RESPONSE_CACHE_SERVICE, RESPONSE_CACHE_INVALIDATE_METHOD, request)
+            .await?;
+        let json: serde_json::Value = serde_json::from_slice(&response)
+            .map_err(|e| QuillError::Rpc(format!("Invalid invalidation response: {}", e)))?;
+        json["invalidated"]
+            .as_u64()
+            .map(|count| count as usize)
+            .ok_or_else(|| QuillError::Rpc("Invalidation response lacks a count".to_string()))
+    }
+
+    /// Send many unary calls in one batch RPC
+    ///
+    /// The server runs the entries concurrently and returns one result per
+    /// entry, in order; a failed entry does not fail the others. The server
+    /// must have batching enabled.
+    pub async fn call_batch(&self, entries: &[BatchEntry]) -> Result<Vec<BatchResult>, QuillError> {
+        self.call_batch_with_options(entries, RequestOptions::default()).await
+    }
+
+    /// Send many unary calls in one batch RPC with per-request options
+    ///
+    /// Every entry sees the batch's metadata and shares its deadline.
+    pub async fn call_batch_with_options(
+        &self,
+        entries: &[BatchEntry],
+        options: RequestOptions,
+    ) -> Result<Vec<BatchResult>, QuillError> {
+        let response = self
+            .call_with_options(BATCH_SERVICE, BATCH_METHOD, encode_batch_request(entries), options)
+            .await?;
+        let results = decode_batch_response(response).map_err(|e| QuillError::Framing(e.to_string()))?;
+        if results.len() != entries.len() {
+            return Err(QuillError::Framing(format!(
+                "Batch response has {} results for {} entries",
+                results.len(),
+                entries.len()
+            )));
+        }
+        Ok(results)
+    }
+
+    /// Smoothed round-trip time to this endpoint, if it has been pinged
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt.smoothed()
+    }
+
+    /// Connection lifecycle events of this client
+    pub fn events(&self) -> &ClientEvents {
+        &self.events
+    }
+
+    /// Round-trip time statistics for this endpoint
+    pub fn rtt_stats(&self) -> RttStats {
+        self.rtt.stats()
+    }
+
+    /// Acknowledgments of the cancels this client sent; see [`crate::cancel`]
+    pub fn cancel_stats(&self) -> CancelStats {
+        self.cancels.stats()
+    }
+
+    /// Health statistics of this client's connections, if scoring is enabled
+    pub fn connection_health(&self) -> Option<ConnectionHealthStats> {
+        let health = self.health.as_ref()?;
+        Some(health.stats(&self.rtt.stats()))
+    }
+
+    /// HTTP client for the next request
+    fn http_client(&self) -> HttpClient {
+        self.client.read().unwrap().clone()
+    }
+
+    /// Drop the connection pool if its health score has degraded
+    ///
+    /// Calls in flight keep their connections; later calls open new ones.
+    fn evict_unhealthy(&self) {
+        let Some(health) = self.health.as_ref() else {
+            return;
+        };
+        let Some(reason) = health.check_eviction(&self.rtt.stats()) else {
+            return;
+        };
+        tracing::warn!("Evicting connections to {}: {}", self.base_url, reason);
+        *self.client.write().unwrap() = Self::build_client(&self.config);
+        // The new connections may take a different path
+        self.rtt.reset();
+        self.events.emit(ClientEventKind::ConnectionEvicted { reason });
+    }
+
+    /// Establish and health-check connections ahead of the first call
+    ///
+    /// Sends `connections` concurrent pings carrying the client's profile
+    /// preference, so connection setup, the HTTP/2 settings exchange and
+    /// profile negotiation are off the first request's critical path.
+    pub async fn preconnect(&self, connections: usize) -> PreconnectReport {
+        prewarm(connections, || self.ping()).await
+    }
+
     /// Compress data using zstd if compression is enabled
-    fn maybe_compress(&self, data: Bytes) -> Result<Bytes, QuillError> {
+    fn maybe_compress(&self, data: Bytes, dictionary: Option<&CompressionDictionary>) -> Result<Bytes, QuillError> {
         if !self.enable_compression {
             return Ok(data);
         }
 
+        if let Some(dictionary) = dictionary {
+            return dictionary
+                .compress(&data, self.compression_level)
+                .map_err(|e| QuillError::Transport(e.to_string()));
+        }
         zstd::encode_all(&data[..], self.compression_level)
             .map(Bytes::from)
             .map_err(|e| QuillError::Transport(format!("Compression failed: {}", e)))
     }
 
     /// Decompress data using zstd if it was compressed
+    ///
+    /// `dictionary_id` names the dictionary a compressed body was compressed with.
     fn maybe_decompress(
         &self,
         data: Bytes,
         content_encoding: Option<&str>,
+        dictionary_id: Option<u32>,
     ) -> Result<Bytes, QuillError> {
-        if let Some("zstd") = content_encoding {
-            zstd::decode_all(&data[..])
+        if content_encoding != Some("zstd") {
+            return Ok(data);
+        }
+        match dictionary_id {
+            Some(id) => {
+                let dictionary = self.dictionaries.get(id).ok_or_else(|| {
+                    QuillError::Transport(format!("Response compressed with unknown dictionary {}", id))
+                })?;
+                dictionary
+                    .decompress(&data)
+                    .map_err(|e| QuillError::Transport(e.to_string()))
+            }
+            None => zstd::decode_all(&data[..])
                 .map(Bytes::from)
-                .map_err(|e| QuillError::Transport(format!("Decompression failed: {}", e)))
-        } else {
-            Ok(data)
+                .map_err(|e| QuillError::Transport(format!("Decompression failed: {}", e))),
         }
     }
 
@@ -287,18 +675,88 @@ impl QuillClient {
         url: &str,
         request: Bytes,
         options: &RequestOptions,
-    ) -> Result<Request<Full<Bytes>>, QuillError> {
+        dictionary: Option<&CompressionDictionary>,
+    ) -> Result<Request<RequestBody>, QuillError> {
         let (request_body, content_encoding) = if self.enable_compression {
-            let compressed = self.maybe_compress(request)?;
+            let compressed = self.maybe_compress(request, dictionary)?;
             (compressed, Some("zstd"))
         } else {
             (request, None)
         };
+        let mut req = self
+            .build_raw_request(url, request_body, content_encoding, options)
+            .map_err(QuillError::Transport)?;
+        if let Some(dictionary) = dictionary.filter(|_| self.enable_compression) {
+            req.headers_mut()
+                .insert(DICTIONARY_HEADER, HeaderValue::from(dictionary.id()));
+        }
+        Ok(req)
+    }
 
+    /// Dictionary to compress requests with, fetching a newly advertised one first
+    ///
+    /// Returns `None` unless compression and dictionaries are enabled. A
+    /// dictionary that cannot be fetched is skipped and calls use plain zstd.
+    async fn compression_dictionary(&self) -> Option<CompressionDictionary> {
+        if !self.enable_compression || !self.config.compression_dictionary {
+            return None;
+        }
+        if let Some(capability) = self.dictionaries.pending() {
+            match self.fetch_dictionary().await {
+                Ok(data) => {
+                    if let Err(e) = self.dictionaries.install(&capability, data) {
+                        tracing::warn!("Ignoring compression dictionary: {}", e);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch compression dictionary {}: {}", capability.id, e);
+                    self.dictionaries.fail(&capability);
+                }
+            }
+        }
+        self.dictionaries.current()
+    }
+
+    /// Fetch the bytes of the server's compression dictionary
+    async fn fetch_dictionary(&self) -> Result<Bytes, QuillError> {
+        let url = format!("{}/{}/{}", self.base_url, DICTIONARY_SERVICE, DICTIONARY_METHOD);
+        let req = self
+            .build_raw_request(&url, Bytes::new(), None, &RequestOptions::default())
+            .map_err(QuillError::Transport)?;
+        let resp = self.send(req, "dictionary request").await?;
+        if !resp.status().is_success() {
+            return Err(QuillError::Rpc(format!("Dictionary fetch failed with status {}", resp.status())));
+        }
+        Ok(resp
+            .into_body()
+            .collect()
+            .await
+            .map_err(|e| QuillError::Transport(format!("Failed to read dictionary: {}", e)))?
+            .to_bytes())
+    }
+
+    /// Build a request around an already encoded body
+    fn build_raw_request(
+        &self,
+        url: &str,
+        request_body: Bytes,
+        content_encoding: Option<&'static str>,
+        options: &RequestOptions,
+    ) -> Result<Request<RequestBody>, String> {
+        self.build_body_request(url, full_body(request_body), content_encoding, options)
+    }
+
+    fn build_body_request(
+        &self,
+        url: &str,
+        request_body: RequestBody,
+        content_encoding: Option<&'static str>,
+        options: &RequestOptions,
+    ) -> Result<Request<RequestBody>, String> {
         let mut req_builder = Request::builder().method(Method::POST).uri(url);
         let headers = req_builder
             .headers_mut()
-            .ok_or_else(|| QuillError::Transport("Failed to build request headers".to_string()))?;
+            .ok_or_else(|| "Failed to build request headers".to_string())?;
 
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/proto"));
         headers.insert(
@@ -311,8 +769,7 @@ impl QuillClient {
             .as_ref()
             .unwrap_or(&self.profile_preference)
             .to_header_value();
-        let prefer = HeaderValue::from_str(&prefer)
-            .map_err(|e| QuillError::Transport(format!("Invalid Prefer header: {}", e)))?;
+        let prefer = HeaderValue::from_str(&prefer).map_err(|e| format!("Invalid Prefer header: {}", e))?;
         headers.insert(HeaderName::from_static("prefer"), prefer);
 
         if self.enable_compression {
@@ -321,32 +778,134 @@ impl QuillClient {
         if let Some(encoding) = content_encoding {
             headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
         }
+        if let Some(timeout) = self.call_timeout(options) {
+            let value = HeaderValue::from_str(&Deadline::after(timeout).to_header_value())
+                .map_err(|e| format!("Invalid timeout header: {}", e))?;
+            headers.insert(HeaderName::from_static(TIMEOUT_HEADER), value);
+        }
 
+        options.metadata.write_to(headers);
         for (name, value) in options.headers.iter() {
             headers.insert(name.clone(), value.clone());
         }
 
         req_builder
-            .body(Full::new(request_body))
-            .map_err(|e| QuillError::Transport(format!("Failed to build request: {}", e)))
+            .body(request_body)
+            .map_err(|e| format!("Failed to build request: {}", e))
+    }
+
+    /// Send a request through the interceptors, tracking connectivity for lifecycle events
+    async fn send(
+        &self,
+        req: Request<RequestBody>,
+        what: &str,
+    ) -> Result<http::Response<hyper::body::Incoming>, QuillError> {
+        if self.config.interceptors.is_empty() {
+            return self.send_raw(req, what).await;
+        }
+
+        let interceptors = InterceptorChain(&self.config.interceptors);
+        let (mut parts, body) = req.into_parts();
+        let mut call = CallInfo::new(parts.uri.clone());
+        interceptors.on_request(&mut call, &mut parts.headers).await?;
+
+        match self.send_raw(Request::from_parts(parts, body), what).await {
+            Ok(resp) => {
+                interceptors.on_response(&call, resp.status(), resp.headers()).await;
+                Ok(resp)
+            }
+            Err(e) => {
+                interceptors.on_error(&call, &e).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Send a request, tracking connectivity for lifecycle events
+    async fn send_raw(
+        &self,
+        req: Request<RequestBody>,
+        what: &str,
+    ) -> Result<http::Response<hyper::body::Incoming>, QuillError> {
+        self.evict_unhealthy();
+        self.events.before_send();
+        match self.http_client().request(req).await {
+            Ok(resp) => {
+                self.events.on_response(resp.headers());
+                if let Some(health) = &self.health {
+                    health.record_success();
+                }
+                Ok(resp)
+            }
+            Err(e) => {
+                let reason = format!("Failed to send {}: {}", what, e);
+                self.events.on_transport_error(&reason);
+                if let Some(health) = &self.health {
+                    health.record_failure();
+                }
+                Err(QuillError::Transport(reason))
+            }
+        }
+    }
+
+    /// Ask the server for flow control of a streaming response, if configured
+    fn request_flow_control(&self, req: &mut Request<RequestBody>) -> Option<String> {
+        let config = self.config.flow_control.as_ref()?;
+        let (id, value) = config.request_header();
+        req.headers_mut().insert(FLOW_CONTROL_HEADER, value);
+        Some(id)
+    }
+
+    /// Start granting credits if the server agreed to flow control
+    fn accept_flow_control(&self, id: Option<String>, headers: &HeaderMap) -> Option<ReceiveWindow> {
+        let config = self.config.flow_control.as_ref()?;
+        let echoed = headers
+            .get(FLOW_CONTROL_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(FlowControlHeader::from_header_value)
+            .filter(|echoed| id.as_ref() == Some(&echoed.id))?;
+        Some(ReceiveWindow::start(config, self.http_client(), &self.base_url, echoed))
+    }
+
+    /// Start end-to-end encryption of a stream's frames, if configured
+    fn start_frame_encryption(&self) -> Result<Option<StreamEncryption>, QuillError> {
+        self.config.frame_encryption.as_ref().map(StreamEncryption::start).transpose()
     }
 
+    /// Timeout of a call: its own, else the client's default
+    fn call_timeout(&self, options: &RequestOptions) -> Option<Duration> {
+        options.timeout.or(self.config.timeout)
+    }
+
+    /// Run a call, cancelling it once its timeout expires or its cancel token fires
     async fn with_request_timeout<F, T>(
         &self,
-        timeout: Option<Duration>,
+        options: &RequestOptions,
         future: F,
     ) -> Result<T, QuillError>
     where
         F: std::future::Future<Output = Result<T, QuillError>>,
     {
-        match timeout {
-            Some(timeout) => tokio::time::timeout(timeout, future).await.map_err(|_| {
-                QuillError::Transport(format!(
-                    "Request timed out after {:.3} seconds",
-                    timeout.as_secs_f64()
-                ))
-            })?,
-            None => future.await,
+        let timed = async {
+            match self.call_timeout(options) {
+                Some(timeout) => tokio::time::timeout(timeout, future).await.map_err(|_| {
+                    // A call that never answers may be on a blackholed path
+                    if let Some(health) = &self.health {
+                        health.record_failure();
+                    }
+                    QuillError::ProblemDetails(ProblemDetails::deadline_exceeded(timeout))
+                })?,
+                None => future.await,
+            }
+        };
+        match &options.cancel {
+            // Dropping the call resets its transport stream
+            Some(token) => tokio::select! {
+                biased;
+                _ = token.cancelled() => Err(cancelled_error()),
+                result = timed => result,
+            },
+            None => timed.await,
         }
     }
 
@@ -414,64 +973,343 @@ impl QuillClient {
         request: Bytes,
         options: RequestOptions,
     ) -> Result<Bytes, QuillError> {
-        // Build the full URL
+        self.call_tracked(service, method, request, options, None).await
+    }
+
+    /// Make a unary RPC call whose request is still being produced
+    ///
+    /// The transport stream is opened and the request headers are sent right
+    /// away; the body follows once `request` resolves. Meant for `real_time`
+    /// methods whose server starts setting the call up before the request
+    /// arrives (see `RpcRouter::register_with_setup` in quill-server). The
+    /// request is compressed with plain zstd if compression is enabled.
+    /// Chunked uploads don't apply, and methods protected by envelope
+    /// encryption are rejected since sealing changes the headers. An error
+    /// from `request` aborts the call.
+    pub async fn call_deferred<F>(
+        &self,
+        service: &str,
+        method: &str,
+        request: F,
+        options: RequestOptions,
+    ) -> Result<Bytes, QuillError>
+    where
+        F: std::future::Future<Output = Result<Bytes, QuillError>> + Send + 'static,
+    {
+        let sealed = self.config.envelope.as_ref().and_then(|envelope| envelope.scope(service, method));
+        if sealed.is_some() {
+            return Err(QuillError::Rpc(format!(
+                "Cannot defer the request of sealed method {}/{}",
+                service, method
+            )));
+        }
+
         let url = format!("{}/{}/{}", self.base_url, service, method);
-        let req = self.build_request(&url, request, &options)?;
+        let level = self.enable_compression.then_some(self.compression_level);
+        let body = async move {
+            let request = request.await?;
+            match level {
+                Some(level) => zstd::encode_all(&request[..], level)
+                    .map(Bytes::from)
+                    .map_err(|e| QuillError::Transport(format!("Compression failed: {}", e))),
+                None => Ok(request),
+            }
+        };
+        let req = self
+            .build_body_request(&url, deferred_body(Box::pin(body)), level.map(|_| "zstd"), &options)
+            .map_err(QuillError::Transport)?;
 
-        self.with_request_timeout(options.timeout, async {
-            // Send the request
-            let resp = self
-                .client
-                .request(req)
-                .await
-                .map_err(|e| QuillError::Transport(format!("Failed to send request: {}", e)))?;
+        self.with_request_timeout(&options, async {
+            let resp = self.send(req, "request").await?;
+            self.read_unary_response(resp, None).await
+        })
+        .await
+    }
 
-            // Check status code
-            let status = resp.status();
-            if !status.is_success() {
-                // Try to parse Problem Details
-                let body_bytes = resp
-                    .into_body()
-                    .collect()
-                    .await
-                    .map_err(|e| {
-                        QuillError::Transport(format!("Failed to read error response: {}", e))
-                    })?
-                    .to_bytes();
+    /// Make a unary RPC call, reporting upload progress to `transfer`
+    pub(crate) async fn call_tracked(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        options: RequestOptions,
+        transfer: Option<&TransferControl>,
+    ) -> Result<Bytes, QuillError> {
+        // Build the full URL
+        let url = format!("{}/{}/{}", self.base_url, service, method);
 
-                // Try to parse as JSON Problem Details
-                if let Ok(pd) = serde_json::from_slice(&body_bytes) {
-                    return Err(QuillError::ProblemDetails(pd));
+        // Seal sensitive requests before they are split or sent
+        let (request, options, envelope_key) = match &self.config.envelope {
+            Some(envelope) => match envelope.seal(service, method, &request).await? {
+                Some(sealed) => {
+                    let mut options = options;
+                    options.insert_header(HeaderName::from_static(ENVELOPE_HEADER), sealed.header);
+                    (sealed.body, options, Some(sealed.key))
                 }
+                None => (request, options, None),
+            },
+            None => (request, options, None),
+        };
+        let envelope_key = envelope_key.as_ref();
+        if let Some(transfer) = transfer {
+            transfer.set_total(request.len() as u64);
+        }
 
-                return Err(QuillError::Rpc(format!(
-                    "RPC failed with status {}: {}",
-                    status,
-                    String::from_utf8_lossy(&body_bytes)
-                )));
+        if let Some(upload) = &options.chunked_upload {
+            if request.len() > upload.chunk_size {
+                let capability = self.upload_capability().await;
+                if let Some((manifest, chunks)) = upload.plan(&request, capability) {
+                    return self
+                        .with_request_timeout(
+                            &options,
+                            self.send_chunks(
+                                &url,
+                                manifest,
+                                chunks,
+                                upload.parallelism,
+                                &options,
+                                envelope_key,
+                                transfer,
+                            ),
+                        )
+                        .await;
+                }
             }
+        }
 
-            // Get content encoding before consuming response
-            let content_encoding = resp
-                .headers()
-                .get(CONTENT_ENCODING)
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string());
+        let request_len = request.len() as u64;
+        let dictionary = self.compression_dictionary().await;
+        let req = self.build_request(&url, request, &options, dictionary.as_ref())?;
 
-            // Read response body
+        self.with_request_timeout(&options, async {
+            if let Some(transfer) = transfer {
+                transfer.checkpoint().await?;
+            }
+            // Send the request
+            let resp = self.send(req, "request").await?;
+
+            let response = self.read_unary_response(resp, envelope_key).await?;
+            if let Some(transfer) = transfer {
+                transfer.advance(request_len);
+            }
+            Ok(response)
+        })
+        .await
+    }
+
+    /// Check the status of a unary response and read its body
+    ///
+    /// Sealed responses are opened with `envelope_key`, the data key of the
+    /// sealed request.
+    async fn read_unary_response(
+        &self,
+        resp: http::Response<hyper::body::Incoming>,
+        envelope_key: Option<&DataKey>,
+    ) -> Result<Bytes, QuillError> {
+        self.uploads.record(resp.headers());
+        self.dictionaries.record(resp.headers());
+
+        // Check status code
+        let status = resp.status();
+        if !status.is_success() {
+            // Try to parse Problem Details
             let body_bytes = resp
                 .into_body()
                 .collect()
                 .await
-                .map_err(|e| QuillError::Transport(format!("Failed to read response: {}", e)))?
+                .map_err(|e| {
+                    QuillError::Transport(format!("Failed to read error response: {}", e))
+                })?
                 .to_bytes();
 
-            // Decompress if needed
-            let body_bytes = self.maybe_decompress(body_bytes, content_encoding.as_deref())?;
+            // Try to parse as JSON Problem Details
+            if let Ok(pd) = serde_json::from_slice(&body_bytes) {
+                return Err(QuillError::ProblemDetails(pd));
+            }
+
+            return Err(QuillError::Rpc(format!(
+                "RPC failed with status {}: {}",
+                status,
+                String::from_utf8_lossy(&body_bytes)
+            )));
+        }
 
-            Ok(body_bytes)
-        })
-        .await
+        // Get content encoding before consuming response
+        let content_encoding = resp
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+        let dictionary_id = resp
+            .headers()
+            .get(DICTIONARY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_dictionary_id);
+        let envelope = match resp.headers().get(ENVELOPE_HEADER) {
+            Some(value) => {
+                let value = value
+                    .to_str()
+                    .map_err(|_| QuillError::Rpc("Invalid envelope header".to_string()))?;
+                Some(EnvelopeHeader::from_header_value(value)?)
+            }
+            None => None,
+        };
+
+        // Read response body
+        let body_bytes = resp
+            .into_body()
+            .collect()
+            .await
+            .map_err(|e| QuillError::Transport(format!("Failed to read response: {}", e)))?
+            .to_bytes();
+
+        // Decompress if needed
+        let body_bytes = self.maybe_decompress(body_bytes, content_encoding.as_deref(), dictionary_id)?;
+
+        match (envelope, envelope_key) {
+            (None, _) => Ok(body_bytes),
+            (Some(envelope), Some(key)) => {
+                Ok(quill_core::envelope::open(key, &envelope.scope, &body_bytes)?)
+            }
+            (Some(_), None) => Err(QuillError::Rpc(
+                "Received a sealed response to an unsealed request".to_string(),
+            )),
+        }
+    }
+
+    /// Whether the server accepts chunked uploads, pinging it if not yet known
+    async fn upload_capability(&self) -> Option<UploadCapability> {
+        if let Some(known) = self.uploads.known() {
+            return known;
+        }
+        // Any response carries the capability; failures are left for the real call to report
+        let url = format!("{}/{}/{}", self.base_url, PING_SERVICE, PING_METHOD);
+        let req = self.build_raw_request(&url, Bytes::new(), None, &RequestOptions::default()).ok()?;
+        let resp = self.send(req, "request").await.ok()?;
+        self.uploads.record(resp.headers())
+    }
+
+    /// Send the chunks of a split request and return the handler's response
+    ///
+    /// The server answers every chunk but the one completing the upload with
+    /// `202 Accepted`. Each chunk waits at `transfer`'s checkpoint before it
+    /// is sent and counts as progress once answered.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_chunks(
+        &self,
+        url: &str,
+        manifest: UploadManifest,
+        chunks: Vec<Bytes>,
+        parallelism: usize,
+        options: &RequestOptions,
+        envelope_key: Option<&DataKey>,
+        transfer: Option<&TransferControl>,
+    ) -> Result<Bytes, QuillError> {
+        let manifest_value = HeaderValue::from_str(&manifest.to_header_value())
+            .map_err(|e| QuillError::Transport(format!("Invalid upload manifest: {}", e)))?;
+        tracing::debug!(
+            "Uploading {} bytes in {} chunks",
+            manifest.total_size,
+            manifest.chunk_count
+        );
+
+        let sends = chunks.into_iter().enumerate().map(|(index, chunk)| {
+            let mut options = options.clone();
+            options.insert_header(HeaderName::from_static(UPLOAD_MANIFEST_HEADER), manifest_value.clone());
+            options.insert_header(HeaderName::from_static(UPLOAD_CHUNK_HEADER), HeaderValue::from(index));
+            async move {
+                if let Some(transfer) = transfer {
+                    transfer.checkpoint().await?;
+                }
+                let chunk_len = chunk.len() as u64;
+                let req = self
+                    .build_raw_request(url, chunk, None, &options)
+                    .map_err(QuillError::Transport)?;
+                let resp = self.send(req, "chunk").await?;
+                let response = if resp.status() == StatusCode::ACCEPTED {
+                    None
+                } else {
+                    Some(self.read_unary_response(resp, envelope_key).await?)
+                };
+                if let Some(transfer) = transfer {
+                    transfer.advance(chunk_len);
+                }
+                Ok::<_, QuillError>(response)
+            }
+        });
+
+        use futures_util::StreamExt;
+        let mut responses = futures_util::stream::iter(sends).buffer_unordered(parallelism);
+        let mut response = None;
+        while let Some(result) = responses.next().await {
+            if let Some(body) = result? {
+                response = Some(body);
+            }
+        }
+        match response {
+            Some(body) => Ok(body),
+            None => Err(QuillError::Rpc("Server did not complete the chunked upload".to_string())),
+        }
+    }
+
+    /// Make a unary RPC call, queueing it if the network is unavailable
+    ///
+    /// Only methods marked queueable in the offline queue configuration are
+    /// queued; others behave like [`call`](Self::call). Every attempt carries
+    /// the same `Idempotency-Key` header so the server can deduplicate replays.
+    pub async fn call_or_enqueue(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+    ) -> Result<CallOutcome, QuillError> {
+        let queue = match &self.config.offline_queue {
+            Some(queue) if queue.config().is_queueable(service, method) => queue,
+            _ => return self.call(service, method, request).await.map(CallOutcome::Completed),
+        };
+
+        let key = crate::offline::generate_idempotency_key();
+        let options = Self::idempotent_options(&key)?;
+
+        match self.call_with_options(service, method, request.clone(), options).await {
+            Ok(response) => Ok(CallOutcome::Completed(response)),
+            Err(QuillError::Transport(e)) => {
+                tracing::debug!("Queueing {}/{} while offline: {}", service, method, e);
+                queue.enqueue_with_key(service, method, request, key).map(CallOutcome::Queued)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replay calls held in the offline queue
+    ///
+    /// Call this when connectivity is restored. Returns an error if no offline
+    /// queue is configured.
+    pub async fn replay_offline_queue(&self) -> Result<ReplayReport, QuillError> {
+        let queue = self
+            .config
+            .offline_queue
+            .as_ref()
+            .ok_or_else(|| QuillError::Rpc("No offline queue configured".to_string()))?;
+
+        Ok(queue
+            .replay(|call| async move {
+                let options = Self::idempotent_options(&call.idempotency_key)?;
+                self.call_with_options(
+                    &call.service,
+                    &call.method,
+                    Bytes::from(call.payload),
+                    options,
+                )
+                .await
+            })
+            .await)
+    }
+
+    fn idempotent_options(key: &str) -> Result<RequestOptions, QuillError> {
+        let value = HeaderValue::from_str(key)
+            .map_err(|e| QuillError::Transport(format!("Invalid idempotency key: {}", e)))?;
+        Ok(RequestOptions::new().header(HeaderName::from_static(IDEMPOTENCY_KEY_HEADER), value))
     }
 
     /// Make a streaming RPC call (client streaming)
@@ -509,10 +1347,16 @@ impl QuillClient {
         service: &str,
         method: &str,
         request: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
-        options: RequestOptions,
+        mut options: RequestOptions,
     ) -> Result<Bytes, QuillError> {
-        // Encode the stream into frames
-        let encoded = encode_request_stream(request).await?;
+        // Encode the stream into frames, sealed if frame encryption is on;
+        // the unary response is not framed and stays as sent
+        let encryption = self.start_frame_encryption()?;
+        if let Some(encryption) = &encryption {
+            options = options.header(HeaderName::from_static(FRAME_ENCRYPTION_HEADER), encryption.header());
+        }
+        let cipher = encryption.map(|encryption| encryption.split().0);
+        let encoded = encode_sealed_request_stream(request, cipher.as_ref()).await?;
 
         // Use regular call with encoded frames
         self.call_with_options(service, method, encoded, options).await
@@ -555,17 +1399,88 @@ impl QuillClient {
         request: Bytes,
         options: RequestOptions,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>, QuillError> {
+        let stream = self.open_server_stream(service, method, request, options).await?;
+        Ok(Box::pin(stream))
+    }
+
+    /// Receive a deadline-bounded streaming response, keeping its PARTIAL trailer
+    ///
+    /// The returned stream reports [`PartialStats`] once the server ended it
+    /// early because its time budget ran out.
+    pub async fn call_server_streaming_partial(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        options: RequestOptions,
+    ) -> Result<PartialStream, QuillError> {
+        let stream = self.open_server_stream(service, method, request, options).await?;
+        Ok(PartialStream { inner: stream })
+    }
+
+    /// Receive a streaming response, keeping its USAGE trailer
+    ///
+    /// The returned stream reports the [`Usage`] the server recorded for
+    /// the call once it has ended.
+    pub async fn call_server_streaming_usage(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        options: RequestOptions,
+    ) -> Result<UsageStream, QuillError> {
+        let stream = self.open_server_stream(service, method, request, options).await?;
+        Ok(UsageStream { inner: stream })
+    }
+
+    /// Receive a cursor stream, resuming after `resume_from` if given
+    ///
+    /// The returned stream keeps the latest cursor the server sent; persist
+    /// it to resume the call later from the message after it. The cursor
+    /// is sent in the `quill-resume-token` header.
+    pub async fn call_cursor_stream(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        resume_from: Option<&StreamCursor>,
+        options: RequestOptions,
+    ) -> Result<CursorStream, QuillError> {
+        let options = match resume_from {
+            Some(cursor) => {
+                let value = HeaderValue::from_str(&cursor.to_header_value()).expect("hex is a valid header value");
+                options.header(HeaderName::from_static(RESUME_TOKEN_HEADER), value)
+            }
+            None => options,
+        };
+        let stream = self.open_server_stream(service, method, request, options).await?;
+        Ok(CursorStream {
+            inner: stream,
+            decode: Box::new(Ok),
+        })
+    }
+
+    async fn open_server_stream(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        options: RequestOptions,
+    ) -> Result<ResponseFrameStream, QuillError> {
         // Build the full URL
         let url = format!("{}/{}/{}", self.base_url, service, method);
-        let req = self.build_request(&url, request, &options)?;
-
-        self.with_request_timeout(options.timeout, async {
+        let dictionary = self.compression_dictionary().await;
+        let mut req = self.build_request(&url, request, &options, dictionary.as_ref())?;
+        let flow_id = self.request_flow_control(&mut req);
+        let decryption = self.start_frame_encryption()?.map(|encryption| {
+            req.headers_mut().insert(FRAME_ENCRYPTION_HEADER, encryption.header());
+            encryption.split().1
+        });
+
+        self.with_request_timeout(&options, async {
             // Send the request
-            let resp = self
-                .client
-                .request(req)
-                .await
-                .map_err(|e| QuillError::Transport(format!("Failed to send request: {}", e)))?;
+            let resp = self.send(req, "request").await?;
+            self.dictionaries.record(resp.headers());
 
             // Check status code
             let status = resp.status();
@@ -591,17 +1506,24 @@ impl QuillClient {
             }
 
             // Create a stream that parses frames from the response
-            let body = resp.into_body();
-            let frame_stream = ResponseFrameStream::new(body);
-
-            Ok(Box::pin(frame_stream)
-                as Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>)
+            let flow = self.accept_flow_control(flow_id, resp.headers());
+            let cipher = decryption.map(|decryption| decryption.accept(resp.headers())).transpose()?;
+            Ok(ResponseFrameStream::new(resp.into_body())
+                .idle_timeout(options.stream_idle_timeout)
+                .health(self.health.clone())
+                .cancel_token(options.cancel.as_ref())
+                .flow_control(flow)
+                .decrypt(cipher))
         })
         .await
     }
 
     /// Make a bidirectional streaming RPC call
     ///
+    /// Request messages are sent as `request` yields them rather than
+    /// buffered up front, so the response stream is available while the
+    /// request stream is still open.
+    ///
     /// # Arguments
     /// * `service` - The service path
     /// * `method` - The method name
@@ -640,17 +1562,26 @@ impl QuillClient {
         // Build the full URL
         let url = format!("{}/{}/{}", self.base_url, service, method);
 
-        // Encode the request stream into frames
-        let encoded = encode_request_stream(request).await?;
-        let req = self.build_request(&url, encoded, &options)?;
+        // Frames are written as the request stream yields messages, so
+        // responses can arrive while the caller is still sending
+        let encryption = self.start_frame_encryption()?;
+        let header = encryption.as_ref().map(StreamEncryption::header);
+        let (request_cipher, decryption) = encryption.map(StreamEncryption::split).unzip();
+        let body = streaming_body(request, options.cancel.as_ref(), request_cipher);
+        let mut req = self.build_body_request(&url, body, None, &options).map_err(QuillError::Transport)?;
+        if let Some(header) = header {
+            req.headers_mut().insert(FRAME_ENCRYPTION_HEADER, header);
+        }
+        let flow_id = self.request_flow_control(&mut req);
+        // Registered before sending, so a cancel while the call is pending counts too
+        let ack = options
+            .cancel
+            .as_ref()
+            .map(|token| AckRegistration::new(token, Arc::clone(&self.cancels), self.config.cancel_ack_timeout));
 
-        self.with_request_timeout(options.timeout, async {
+        self.with_request_timeout(&options, async {
             // Send the request
-            let resp = self
-                .client
-                .request(req)
-                .await
-                .map_err(|e| QuillError::Transport(format!("Failed to send request: {}", e)))?;
+            let resp = self.send(req, "request").await?;
 
             // Check status code
             let status = resp.status();
@@ -676,8 +1607,16 @@ impl QuillClient {
             }
 
             // Create a stream that parses frames from the response
+            let flow = self.accept_flow_control(flow_id, resp.headers());
+            let cipher = decryption.map(|decryption| decryption.accept(resp.headers())).transpose()?;
             let body = resp.into_body();
-            let frame_stream = ResponseFrameStream::new(body);
+            let frame_stream = ResponseFrameStream::new(body)
+                .idle_timeout(options.stream_idle_timeout)
+                .health(self.health.clone())
+                .cancel_token(options.cancel.as_ref())
+                .acknowledge_cancel(ack)
+                .flow_control(flow)
+                .decrypt(cipher);
 
             Ok(Box::pin(frame_stream)
                 as Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>)
@@ -688,39 +1627,114 @@ impl QuillClient {
 
 /// Stream adapter that parses frames from HTTP response body
 struct ResponseFrameStream {
-    body: hyper::body::Incoming,
+    /// Response body, dropped on cancellation to reset the transport stream
+    body: Option<hyper::body::Incoming>,
     parser: FrameParser,
     credits: CreditTracker,
-    messages_received: u32,
+    flow: Option<ReceiveWindow>,
+    /// Opens sealed frames of an encrypted stream
+    cipher: Option<FrameCipher>,
+    partial: Option<PartialStats>,
+    cursor: Option<StreamCursor>,
+    usage: Option<Usage>,
+    idle: Option<IdleTimer>,
+    /// Told how the stream ended, once
+    health: Option<Arc<ConnectionHealth>>,
+    cancel: Option<Cancelled>,
+    /// Wait for CANCEL_ACK once cancelled, instead of resetting the stream
+    ack: Option<AckRegistration>,
+    done: bool,
+}
+
+/// Deadline for the next message of a response stream
+struct IdleTimer {
+    timeout: Duration,
+    sleep: Pin<Box<tokio::time::Sleep>>,
+    /// Whether the caller is waiting; time spent handling a message is not idle
+    waiting: bool,
 }
 
 impl ResponseFrameStream {
     fn new(body: hyper::body::Incoming) -> Self {
         Self {
-            body,
+            body: Some(body),
             parser: FrameParser::new(),
             credits: CreditTracker::with_defaults(),
-            messages_received: 0,
+            flow: None,
+            cipher: None,
+            partial: None,
+            cursor: None,
+            usage: None,
+            idle: None,
+            health: None,
+            cancel: None,
+            ack: None,
+            done: false,
         }
     }
-}
 
-impl Stream for ResponseFrameStream {
-    type Item = Result<Bytes, QuillError>;
+    fn cancel_token(mut self, token: Option<&CancelToken>) -> Self {
+        self.cancel = token.map(CancelToken::cancelled_owned);
+        self
+    }
 
-    fn poll_next(
-        mut self: Pin<&mut Self>,
+    fn acknowledge_cancel(mut self, ack: Option<AckRegistration>) -> Self {
+        self.ack = ack;
+        self
+    }
+
+    fn flow_control(mut self, flow: Option<ReceiveWindow>) -> Self {
+        self.flow = flow;
+        self
+    }
+
+    fn decrypt(mut self, cipher: Option<FrameCipher>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    fn health(mut self, health: Option<Arc<ConnectionHealth>>) -> Self {
+        self.health = health;
+        self
+    }
+
+    fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle = timeout.map(|timeout| IdleTimer {
+            timeout,
+            sleep: Box::pin(tokio::time::sleep(timeout)),
+            waiting: false,
+        });
+        self
+    }
+
+    /// Parse the next message, without the idle timeout
+    fn poll_message(
+        &mut self,
         cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
+    ) -> std::task::Poll<Option<Result<Bytes, QuillError>>> {
         use http_body::Body;
-        use quill_core::DEFAULT_CREDIT_REFILL;
         use std::task::Poll;
 
         loop {
             // Try to parse a frame from buffered data
             match self.parser.parse_frame() {
                 Ok(Some(frame)) => {
+                    let frame = match self.cipher.as_ref() {
+                        Some(cipher) => match cipher.open(frame) {
+                            Ok(frame) => frame,
+                            Err(e) => return Poll::Ready(Some(Err(e.into()))),
+                        },
+                        None => frame,
+                    };
                     if frame.flags.is_end_stream() {
+                        if frame.flags.is_partial() {
+                            // Server ran out of time; keep the completeness trailer
+                            let stats = PartialStats::from_json(&frame.payload).unwrap_or_else(|e| {
+                                tracing::warn!("Invalid PARTIAL trailer: {}", e);
+                                PartialStats::default()
+                            });
+                            self.partial = Some(stats);
+                        }
                         // Stream ended
                         return Poll::Ready(None);
                     }
@@ -733,28 +1747,31 @@ impl Stream for ResponseFrameStream {
                         // Continue to next frame
                         continue;
                     }
+                    if frame.flags.is_cursor() {
+                        // Position after the last message received
+                        self.cursor = Some(StreamCursor::new(frame.payload));
+                        continue;
+                    }
+                    if frame.flags.is_usage() {
+                        // What the call consumed, sent just before the end of the stream
+                        match Usage::from_json(&frame.payload) {
+                            Ok(usage) => self.usage = Some(usage),
+                            Err(e) => tracing::warn!("Invalid USAGE trailer: {}", e),
+                        }
+                        continue;
+                    }
                     if frame.flags.is_data() {
-                        self.messages_received += 1;
-
-                        // In a future HTTP/2 implementation, we would send credit frames
-                        // back to the server here to grant more send credits.
-                        // For now, we just track locally.
-                        if self.messages_received % DEFAULT_CREDIT_REFILL == 0 {
-                            // Would send credit frame to server here
-                            tracing::debug!(
-                                "Would grant {} credits to server (received {} messages)",
-                                DEFAULT_CREDIT_REFILL,
-                                self.messages_received
-                            );
+                        // Grant credits back to the server as messages are consumed
+                        if let Some(flow) = self.flow.as_mut() {
+                            if let Err(e) = flow.on_message(&frame.payload) {
+                                return Poll::Ready(Some(Err(e)));
+                            }
                         }
-
                         return Poll::Ready(Some(Ok(frame.payload)));
                     }
                     if frame.flags.is_cancel() {
                         // Stream was cancelled by server
-                        return Poll::Ready(Some(Err(QuillError::Rpc(
-                            "Stream cancelled by server".to_string(),
-                        ))));
+                        return Poll::Ready(Some(Err(frame.cancel_error())));
                     }
                     // Other frame types, continue
                 }
@@ -767,18 +1784,23 @@ impl Stream for ResponseFrameStream {
             }
 
             // Read more data from body
-            match Pin::new(&mut self.body).poll_frame(cx) {
+            let Some(body) = self.body.as_mut() else {
+                return Poll::Ready(None);
+            };
+            match Pin::new(body).poll_frame(cx) {
                 Poll::Ready(Some(Ok(frame))) => {
                     if let Ok(data) = frame.into_data() {
-                        self.parser.feed(&data);
+                        self.parser.feed_bytes(data);
                     }
                 }
                 Poll::Ready(Some(Err(e))) => {
                     return Poll::Ready(Some(Err(QuillError::Transport(e.to_string()))));
                 }
                 Poll::Ready(None) => {
-                    // Body ended, but we might have buffered data
-                    return Poll::Ready(None);
+                    // Body ended without END_STREAM: the connection was cut short
+                    return Poll::Ready(Some(Err(QuillError::Transport(
+                        "Response stream ended before END_STREAM".to_string(),
+                    ))));
                 }
                 Poll::Pending => {
                     return Poll::Pending;
@@ -788,6 +1810,258 @@ impl Stream for ResponseFrameStream {
     }
 }
 
+impl ResponseFrameStream {
+    /// Stop reading a cancelled stream
+    ///
+    /// A stream awaiting CANCEL_ACK hands the rest of the body to a task
+    /// that waits for it; others drop the body to reset the transport stream.
+    fn release_cancelled(&mut self) {
+        let body = self.body.take();
+        let Some(ack) = self.ack.take() else {
+            return;
+        };
+        let (Some(body), Ok(runtime)) = (body, tokio::runtime::Handle::try_current()) else {
+            ack.resolve(AckOutcome::Unacknowledged);
+            return;
+        };
+        let parser = std::mem::take(&mut self.parser);
+        let cipher = self.cipher.take();
+        let flow = self.flow.take();
+        runtime.spawn(async move {
+            let timeout = ack.timeout();
+            let outcome = match tokio::time::timeout(timeout, await_cancel_ack(body, parser, cipher, flow)).await {
+                Ok(true) => AckOutcome::Acknowledged,
+                Ok(false) => AckOutcome::Unacknowledged,
+                Err(_) => {
+                    tracing::warn!("Server did not acknowledge cancel within {:?}", timeout);
+                    AckOutcome::TimedOut
+                }
+            };
+            ack.resolve(outcome);
+        });
+    }
+
+    /// Tell the health tracker how the stream ended: stalled or not, or
+    /// `None` for a transport failure
+    fn report_end(&mut self, stalled: Option<bool>) {
+        let Some(health) = self.health.take() else {
+            return;
+        };
+        match stalled {
+            Some(stalled) => health.record_stream_end(stalled),
+            None => health.record_failure(),
+        }
+    }
+}
+
+impl Stream for ResponseFrameStream {
+    type Item = Result<Bytes, QuillError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        use std::future::Future;
+        use std::task::Poll;
+
+        let this = &mut *self;
+        if this.done {
+            return Poll::Ready(None);
+        }
+        if let Some(cancel) = this.cancel.as_mut() {
+            if cancel.as_mut().poll(cx).is_ready() {
+                this.done = true;
+                this.release_cancelled();
+                return Poll::Ready(Some(Err(cancelled_error())));
+            }
+        }
+        if let Some(idle) = this.idle.as_mut().filter(|idle| !idle.waiting) {
+            idle.waiting = true;
+            let deadline = tokio::time::Instant::now() + idle.timeout;
+            idle.sleep.as_mut().reset(deadline);
+        }
+
+        let poll = this.poll_message(cx);
+        match &poll {
+            Poll::Ready(None) => {
+                // Nothing left to cancel
+                this.ack = None;
+                this.report_end(Some(false));
+            }
+            Poll::Ready(Some(Err(QuillError::Transport(_)))) => this.report_end(None),
+            _ => {}
+        }
+        let Some(idle) = this.idle.as_mut() else {
+            return poll;
+        };
+        match &poll {
+            Poll::Ready(Some(Ok(_))) => idle.waiting = false,
+            Poll::Pending if idle.sleep.as_mut().poll(cx).is_ready() => {
+                let timeout = idle.timeout;
+                this.done = true;
+                this.report_end(Some(true));
+                return Poll::Ready(Some(Err(QuillError::ProblemDetails(
+                    ProblemDetails::stream_idle_timeout(timeout),
+                ))));
+            }
+            _ => {}
+        }
+        poll
+    }
+}
+
+impl Drop for ResponseFrameStream {
+    fn drop(&mut self) {
+        if self.ack.as_ref().is_some_and(AckRegistration::is_cancelled) {
+            self.release_cancelled();
+        }
+    }
+}
+
+/// Read the rest of a cancelled response until the server acknowledges the cancel
+///
+/// Returns false if the response ends or fails without CANCEL_ACK.
+async fn await_cancel_ack(
+    mut body: hyper::body::Incoming,
+    mut parser: FrameParser,
+    cipher: Option<FrameCipher>,
+    mut flow: Option<ReceiveWindow>,
+) -> bool {
+    loop {
+        loop {
+            let frame = match parser.parse_frame() {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(_) => return false,
+            };
+            let frame = match cipher.as_ref() {
+                Some(cipher) => match cipher.open(frame) {
+                    Ok(frame) => frame,
+                    Err(_) => return false,
+                },
+                None => frame,
+            };
+            if frame.flags.is_cancel_ack() {
+                return true;
+            }
+            if frame.flags.is_end_stream() || frame.flags.is_cancel() {
+                return false;
+            }
+            // Messages still in flight are discarded, but keep the server's credits flowing
+            if let (true, Some(flow)) = (frame.flags.is_data(), flow.as_mut()) {
+                let _ = flow.on_message(&frame.payload);
+            }
+        }
+        match body.frame().await {
+            Some(Ok(frame)) => {
+                if let Ok(data) = frame.into_data() {
+                    parser.feed_bytes(data);
+                }
+            }
+            Some(Err(_)) | None => return false,
+        }
+    }
+}
+
+/// Streaming response that may end early with a PARTIAL trailer
+pub struct PartialStream {
+    inner: ResponseFrameStream,
+}
+
+impl PartialStream {
+    /// Completeness statistics, if the server cut the stream short
+    ///
+    /// Only available once the stream has ended.
+    pub fn partial(&self) -> Option<&PartialStats> {
+        self.inner.partial.as_ref()
+    }
+
+    /// Whether the stream ended early because the server's deadline passed
+    pub fn is_partial(&self) -> bool {
+        self.inner.partial.is_some()
+    }
+}
+
+impl Stream for PartialStream {
+    type Item = Result<Bytes, QuillError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+/// Streaming response that reports what the call consumed
+pub struct UsageStream {
+    inner: ResponseFrameStream,
+}
+
+impl UsageStream {
+    /// Usage the server reported, if it meters the call
+    ///
+    /// Only available once the stream has ended; a cancelled stream has none.
+    pub fn usage(&self) -> Option<&Usage> {
+        self.inner.usage.as_ref()
+    }
+}
+
+impl Stream for UsageStream {
+    type Item = Result<Bytes, QuillError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+/// Streaming response that reports the server's latest stream cursor
+///
+/// Messages are decoded into `T`, raw bytes by default.
+pub struct CursorStream<T = Bytes> {
+    inner: ResponseFrameStream,
+    decode: Box<dyn Fn(Bytes) -> Result<T, QuillError> + Send>,
+}
+
+impl<T: 'static> CursorStream<T> {
+    /// Cursor after the last message covered by a cursor frame
+    ///
+    /// Resuming from it replays no message already received before that
+    /// frame; messages received after it may be sent again.
+    pub fn cursor(&self) -> Option<&StreamCursor> {
+        self.inner.cursor.as_ref()
+    }
+
+    /// Decode every message with `decode`
+    pub fn decode_with<U, F>(self, decode: F) -> CursorStream<U>
+    where
+        F: Fn(T) -> Result<U, QuillError> + Send + 'static,
+    {
+        let inner = self.decode;
+        CursorStream {
+            inner: self.inner,
+            decode: Box::new(move |bytes| inner(bytes).and_then(&decode)),
+        }
+    }
+}
+
+impl<T> Stream for CursorStream<T> {
+    type Item = Result<T, QuillError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        Pin::new(&mut this.inner)
+            .poll_next(cx)
+            .map(|message| message.map(|message| message.and_then(&this.decode)))
+    }
+}
+
 impl fmt::Debug for QuillClient {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("QuillClient").field("base_url", &self.base_url).finish()
@@ -839,6 +2113,14 @@ impl ClientBuilder {
         self
     }
 
+    /// Fetch the server's compression dictionary and compress unary calls with it
+    ///
+    /// Takes effect only with compression enabled. See [`crate::dictionary`].
+    pub fn compression_dictionary(mut self, enable: bool) -> Self {
+        self.config.compression_dictionary = enable;
+        self
+    }
+
     /// Set HTTP protocol version
     pub fn http_protocol(mut self, protocol: HttpProtocol) -> Self {
         self.config.http_protocol = protocol;
@@ -850,6 +2132,26 @@ impl ClientBuilder {
         self.http_protocol(HttpProtocol::Http2)
     }
 
+    /// Connect over the Unix domain socket at `path` instead of TCP
+    ///
+    /// The base URL, `http://localhost` unless set, only supplies the
+    /// authority and path prefix. See [`crate::uds`].
+    pub fn uds_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.uds_path = Some(path.into());
+        self
+    }
+
+    /// Connect over a simulated network instead of TCP
+    ///
+    /// The base URL's host and port name the listener, e.g.
+    /// `http://echo:80` for `network.listen("echo:80")`. Meant for tests on
+    /// a paused tokio clock; see [`quill_transport::sim`].
+    #[cfg(feature = "test-util")]
+    pub fn sim_network(mut self, network: SimNetwork) -> Self {
+        self.config.sim_network = Some(network);
+        self
+    }
+
     /// Set connection pool idle timeout
     pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
         self.config.pool_idle_timeout = Some(timeout);
@@ -923,21 +2225,129 @@ impl ClientBuilder {
         self
     }
 
+    /// Enforce credit-based flow control on streaming responses
+    ///
+    /// See [`crate::flow_control`] for how credits are granted.
+    pub fn flow_control(mut self, config: FlowControlConfig) -> Self {
+        self.config.flow_control = Some(config);
+        self
+    }
+
+    /// Queue calls to annotated methods while the network is down
+    pub fn offline_queue(mut self, queue: Arc<OfflineQueue>) -> Self {
+        self.config.offline_queue = Some(queue);
+        self
+    }
+
+    /// Score connection health and evict connections that degrade
+    ///
+    /// See [`crate::health`] for the signals behind the score. Evictions are
+    /// reported as [`ClientEventKind::ConnectionEvicted`] events.
+    pub fn connection_health(mut self, config: ConnectionHealthConfig) -> Self {
+        self.config.connection_health = Some(config);
+        self
+    }
+
+    /// Register a listener for connection lifecycle events
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ClientEvent) + Send + Sync + 'static,
+    {
+        self.config.event_listeners.push(Arc::new(f));
+        self
+    }
+
+    /// Set the timeout of calls made without one in their [`RequestOptions`]
+    ///
+    /// Like a per-call timeout, it is propagated to the server.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Set how long a cancelled bidirectional call waits for the server to acknowledge it
+    ///
+    /// Defaults to [`DEFAULT_CANCEL_ACK_TIMEOUT`]. See [`crate::cancel`].
+    pub fn cancel_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.config.cancel_ack_timeout = timeout;
+        self
+    }
+
+    /// Add an interceptor run around every request
+    ///
+    /// Request hooks run in the order interceptors are added; response and
+    /// error hooks run in reverse.
+    pub fn interceptor(mut self, interceptor: impl ClientInterceptor + 'static) -> Self {
+        self.config.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Seal requests to sensitive methods with envelope encryption
+    pub fn envelope_encryption(mut self, envelope: EnvelopeEncryption) -> Self {
+        self.config.envelope = Some(Arc::new(envelope));
+        self
+    }
+
+    /// Encrypt the frames of streaming calls end to end with a shared key
+    ///
+    /// Independent of TLS, so frames stay sealed through proxies that
+    /// terminate it. The server must accept the same key; see
+    /// [`frame_encryption`](crate::frame_encryption).
+    pub fn frame_encryption(mut self, key: FrameKey) -> Self {
+        self.config.frame_encryption = Some(key);
+        self
+    }
+
+    /// Establish this many connections when the client connects
+    ///
+    /// Takes effect in [`connect`](Self::connect); [`build`](Self::build)
+    /// stays lazy.
+    pub fn preconnect(mut self, connections: usize) -> Self {
+        self.config.preconnect = connections;
+        self.config.pool_max_idle_per_host = self.config.pool_max_idle_per_host.max(connections);
+        self
+    }
+
+    /// Build the client and prewarm its connections
+    ///
+    /// Fails if preconnecting was requested and no connection passed the
+    /// health check.
+    pub async fn connect(self) -> Result<QuillClient, QuillError> {
+        let client = self.build().map_err(QuillError::Transport)?;
+        let report = client.preconnect(client.config.preconnect).await;
+        if !report.is_healthy() {
+            let error = report.errors.into_iter().next();
+            return Err(error.unwrap_or_else(|| QuillError::Transport("Preconnect failed".to_string())));
+        }
+        Ok(client)
+    }
+
     /// Build the client
     pub fn build(self) -> Result<QuillClient, String> {
-        let base_url = self.base_url.ok_or_else(|| "base_url is required".to_string())?;
+        let base_url = match (self.base_url, &self.config.uds_path) {
+            (Some(base_url), _) => base_url,
+            (None, Some(_)) => "http://localhost".to_string(),
+            (None, None) => return Err("base_url is required".to_string()),
+        };
 
         let client = QuillClient::build_client(&self.config);
+        let events = QuillClient::build_events(&base_url, &self.config);
 
         Ok(QuillClient {
             base_url,
-            client,
+            client: RwLock::new(client),
             profile_preference: self
                 .profile_preference
                 .unwrap_or_else(ProfilePreference::default_preference),
             enable_compression: self.enable_compression,
             compression_level: self.compression_level,
+            health: QuillClient::build_health(&self.config),
             config: self.config,
+            rtt: Arc::new(RttEstimator::new()),
+            uploads: UploadNegotiation::default(),
+            dictionaries: DictionaryNegotiation::default(),
+            events,
+            cancels: Arc::default(),
         })
     }
 }
@@ -959,6 +2369,13 @@ mod tests {
         assert_eq!(client.base_url, "http://localhost:8080");
     }
 
+    #[test]
+    fn test_uds_client_needs_no_base_url() {
+        let client = QuillClient::builder().uds_path("/tmp/quill.sock").build().unwrap();
+        assert_eq!(client.base_url, "http://localhost");
+        assert!(QuillClient::builder().build().is_err());
+    }
+
     #[test]
     fn test_request_options_builder() {
         let options = RequestOptions::new()
@@ -972,4 +2389,148 @@ mod tests {
         assert_eq!(options.profile_preference.unwrap().to_header_value(), "prism=turbo");
         assert_eq!(options.timeout, Some(Duration::from_secs(5)));
     }
+
+    #[tokio::test]
+    async fn test_ping_updates_rtt() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = quill_server::QuillServer::new(quill_server::RpcRouter::new());
+        tokio::spawn(async move {
+            let _ = server.serve_with_listener(listener).await;
+        });
+
+        let client = QuillClient::new(format!("http://{}", addr));
+        assert_eq!(client.rtt(), None);
+
+        let first = client.ping().await.unwrap();
+        client.ping().await.unwrap();
+        let stats = client.rtt_stats();
+        assert_eq!(stats.samples, 2);
+        assert!(stats.min.unwrap() <= first);
+        assert!(client.rtt().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_connect_preconnects() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = quill_server::QuillServer::new(quill_server::RpcRouter::new());
+        tokio::spawn(async move {
+            let _ = server.serve_with_listener(listener).await;
+        });
+
+        let client = QuillClient::builder()
+            .base_url(format!("http://{}", addr))
+            .preconnect(3)
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(client.rtt_stats().samples, 3);
+
+        let report = client.preconnect(2).await;
+        assert_eq!((report.attempted, report.succeeded), (2, 2));
+        assert!(report.min_rtt.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_connect_fails_health_check() {
+        // Nothing listens on port 1, so the connection is refused
+        let result = QuillClient::builder().base_url("http://127.0.0.1:1").preconnect(2).connect().await;
+        assert!(matches!(result, Err(QuillError::Transport(_))));
+
+        let lazy = QuillClient::builder().base_url("http://127.0.0.1:1").connect().await;
+        assert!(lazy.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_call_or_enqueue_queues_when_offline() {
+        use crate::offline::OfflineQueueConfig;
+
+        let queue = Arc::new(OfflineQueue::new(
+            OfflineQueueConfig::new().queue_method("telemetry.v1.Ingest", "Report"),
+        ));
+        // Nothing listens on port 1, so the connection is refused
+        let client = QuillClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .offline_queue(queue.clone())
+            .build()
+            .unwrap();
+
+        let outcome =
+            client.call_or_enqueue("telemetry.v1.Ingest", "Report", Bytes::from("x")).await;
+        assert!(matches!(outcome, Ok(CallOutcome::Queued(_))));
+        assert_eq!(queue.len(), 1);
+
+        // Non-queueable methods surface the transport error directly
+        let outcome = client.call_or_enqueue("telemetry.v1.Ingest", "Query", Bytes::new()).await;
+        assert!(matches!(outcome, Err(QuillError::Transport(_))));
+
+        let report = client.replay_offline_queue().await.unwrap();
+        assert_eq!(report.remaining, 1);
+    }
+
+    #[tokio::test]
+    async fn test_blackholed_connections_are_evicted() {
+        // Accepts connections but never answers, like a path dropped by a NAT
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&seen);
+        let client = QuillClient::builder()
+            .base_url(format!("http://{}", addr))
+            .timeout(Duration::from_millis(50))
+            .connection_health(ConnectionHealthConfig::new().min_samples(3).cooldown(Duration::ZERO))
+            .on_event(move |event| recorded.lock().unwrap().push(event.kind.clone()))
+            .build()
+            .unwrap();
+
+        for _ in 0..4 {
+            assert!(client.call("echo.v1.EchoService", "Echo", Bytes::new()).await.is_err());
+        }
+        let stats = client.connection_health().unwrap();
+        assert_eq!(stats.errors, 4);
+        assert!(stats.score < 0.5, "score = {}", stats.score);
+
+        // The next call starts on fresh connections
+        assert!(client.call("echo.v1.EchoService", "Echo", Bytes::new()).await.is_err());
+        let stats = client.connection_health().unwrap();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.errors, 1);
+        let seen = seen.lock().unwrap();
+        assert!(matches!(seen.as_slice(), [ClientEventKind::ConnectionEvicted { reason }] if reason.contains("error rate")));
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_events() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&seen);
+        let client = QuillClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .enable_circuit_breaker()
+            .on_event(move |event| recorded.lock().unwrap().push(event.kind.clone()))
+            .build()
+            .unwrap();
+
+        assert!(client.call("echo.v1.EchoService", "Echo", Bytes::new()).await.is_err());
+        assert!(client.call("echo.v1.EchoService", "Echo", Bytes::new()).await.is_err());
+        assert_eq!(client.events().is_connected(), Some(false));
+
+        let breaker = client.config.circuit_breaker.clone().unwrap();
+        for _ in 0..5 {
+            breaker.record_failure().await;
+        }
+
+        let seen = seen.lock().unwrap();
+        assert!(matches!(seen[0], ClientEventKind::Disconnected { .. }));
+        assert_eq!(seen[1], ClientEventKind::Reconnecting { attempt: 1 });
+        assert_eq!(seen[2], ClientEventKind::CircuitOpened);
+        assert_eq!(seen.len(), 3);
+    }
 }
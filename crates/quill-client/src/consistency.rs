@@ -0,0 +1,131 @@
+//! Read-your-writes through consistency tokens
+//!
+//! [`ConsistencyTokens`] is an interceptor that remembers the largest
+//! consistency token any response carried and sends it with every later
+//! request, so a replica serving a read knows which writes the caller has
+//! already seen:
+//!
+//! ```ignore
+//! let tokens = ConsistencyTokens::new();
+//! let leader = QuillClient::builder().base_url(leader_url).interceptor(tokens.clone()).build()?;
+//! let follower = QuillClient::builder().base_url(follower_url).interceptor(tokens.clone()).build()?;
+//! leader.call("kv.v1.Kv", "Put", put).await?;
+//! // Carries the token of the Put
+//! follower.call("kv.v1.Kv", "Get", get).await?;
+//! ```
+//!
+//! Share one instance between clients of the same replicated service to
+//! carry tokens across them. A token set explicitly on a request is kept
+//! when it is newer than the tracked one.
+
+use crate::interceptor::{CallInfo, ClientInterceptor, InterceptFuture};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use quill_core::{ConsistencyToken, QuillError, CONSISTENCY_TOKEN_HEADER};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Latest consistency token seen by a group of clients
+///
+/// Cheap to clone; clones track the same token.
+#[derive(Debug, Clone, Default)]
+pub struct ConsistencyTokens {
+    // Zero until a token is observed; token positions start at one
+    latest: Arc<AtomicU64>,
+}
+
+impl ConsistencyTokens {
+    /// Track tokens starting with none
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Latest token seen, if any
+    pub fn latest(&self) -> Option<ConsistencyToken> {
+        match self.latest.load(Ordering::Acquire) {
+            0 => None,
+            position => Some(ConsistencyToken::new(position)),
+        }
+    }
+
+    /// Record `token`, e.g. one persisted by an earlier session
+    ///
+    /// Older tokens than the latest one are ignored.
+    pub fn observe(&self, token: ConsistencyToken) {
+        self.latest.fetch_max(token.position(), Ordering::AcqRel);
+    }
+
+    /// Forget the latest token
+    pub fn reset(&self) {
+        self.latest.store(0, Ordering::Release);
+    }
+}
+
+impl ClientInterceptor for ConsistencyTokens {
+    fn on_request<'a>(
+        &'a self,
+        _call: &'a mut CallInfo,
+        headers: &'a mut HeaderMap,
+    ) -> InterceptFuture<'a, Result<(), QuillError>> {
+        Box::pin(async move {
+            let explicit = headers
+                .get(CONSISTENCY_TOKEN_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(ConsistencyToken::from_header_value);
+            if let Some(latest) = self.latest().filter(|latest| Some(*latest) > explicit) {
+                headers.insert(CONSISTENCY_TOKEN_HEADER, HeaderValue::from(latest.position()));
+            }
+            Ok(())
+        })
+    }
+
+    fn on_response<'a>(
+        &'a self,
+        _call: &'a CallInfo,
+        _status: StatusCode,
+        headers: &'a HeaderMap,
+    ) -> InterceptFuture<'a, ()> {
+        Box::pin(async move {
+            let token = headers
+                .get(CONSISTENCY_TOKEN_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(ConsistencyToken::from_header_value);
+            if let Some(token) = token {
+                self.observe(token);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Uri;
+
+    #[tokio::test]
+    async fn test_echoes_latest_token() {
+        let tokens = ConsistencyTokens::new();
+        let mut call = CallInfo::new(Uri::from_static("http://localhost/kv.v1.Kv/Get"));
+
+        let mut headers = HeaderMap::new();
+        tokens.on_request(&mut call, &mut headers).await.unwrap();
+        assert!(!headers.contains_key(CONSISTENCY_TOKEN_HEADER));
+
+        let mut response = HeaderMap::new();
+        response.insert(CONSISTENCY_TOKEN_HEADER, HeaderValue::from_static("9"));
+        tokens.on_response(&call, StatusCode::OK, &response).await;
+        response.insert(CONSISTENCY_TOKEN_HEADER, HeaderValue::from_static("4"));
+        tokens.clone().on_response(&call, StatusCode::OK, &response).await;
+        assert_eq!(tokens.latest(), Some(ConsistencyToken::new(9)));
+
+        tokens.on_request(&mut call, &mut headers).await.unwrap();
+        assert_eq!(headers[CONSISTENCY_TOKEN_HEADER], "9");
+
+        // A newer token set on the request is left alone
+        headers.insert(CONSISTENCY_TOKEN_HEADER, HeaderValue::from_static("12"));
+        tokens.on_request(&mut call, &mut headers).await.unwrap();
+        assert_eq!(headers[CONSISTENCY_TOKEN_HEADER], "12");
+
+        tokens.reset();
+        assert_eq!(tokens.latest(), None);
+    }
+}
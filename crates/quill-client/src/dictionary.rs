@@ -0,0 +1,160 @@
+//! Compression dictionaries offered by servers
+//!
+//! This module provides:
+//! - Tracking of the dictionary a server advertises
+//! - Verification and caching of fetched dictionaries
+//!
+//! With [`ClientBuilder::compression_dictionary`] and compression enabled,
+//! the client watches responses for the server's dictionary capability.
+//! The next call after a new dictionary is advertised fetches it through
+//! the built-in fetch method and checks it against the advertised hash;
+//! from then on request bodies are compressed with it and responses may be
+//! too. Until a dictionary is held, or if fetching it fails, calls fall
+//! back to plain zstd.
+//!
+//! [`ClientBuilder::compression_dictionary`]: crate::client::ClientBuilder::compression_dictionary
+
+use bytes::Bytes;
+use http::HeaderMap;
+use quill_core::{CompressionDictionary, DictionaryCapability, DictionaryError, DICTIONARY_CAPABILITY_HEADER};
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+struct State {
+    /// Dictionary the server advertised last
+    advertised: Option<DictionaryCapability>,
+    /// Dictionary in use, matching `advertised` once fetched
+    current: Option<CompressionDictionary>,
+    /// Dictionary in use before the last rotation, for responses still in flight
+    previous: Option<CompressionDictionary>,
+    /// Advertised dictionary that could not be fetched, not retried
+    failed: Option<DictionaryCapability>,
+}
+
+/// What is known about a server's compression dictionary
+#[derive(Debug, Default)]
+pub(crate) struct DictionaryNegotiation {
+    state: Mutex<State>,
+}
+
+impl DictionaryNegotiation {
+    /// Learn the advertised dictionary from a server response
+    pub(crate) fn record(&self, headers: &HeaderMap) {
+        let advertised = headers
+            .get(DICTIONARY_CAPABILITY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(DictionaryCapability::from_header_value);
+        self.state.lock().unwrap().advertised = advertised;
+    }
+
+    /// Dictionary to compress requests with, if the server still offers it
+    pub(crate) fn current(&self) -> Option<CompressionDictionary> {
+        let state = self.state.lock().unwrap();
+        let current = state.current.as_ref()?;
+        (state.advertised.as_ref() == Some(&current.capability())).then(|| current.clone())
+    }
+
+    /// Advertised dictionary that still has to be fetched
+    pub(crate) fn pending(&self) -> Option<DictionaryCapability> {
+        let state = self.state.lock().unwrap();
+        let advertised = state.advertised.as_ref()?;
+        let held = state.current.as_ref().is_some_and(|current| current.capability() == *advertised);
+        (!held && state.failed.as_ref() != Some(advertised)).then(|| advertised.clone())
+    }
+
+    /// Install the fetched bytes of an advertised dictionary
+    pub(crate) fn install(
+        &self,
+        capability: &DictionaryCapability,
+        data: Bytes,
+    ) -> Result<CompressionDictionary, DictionaryError> {
+        let mut state = self.state.lock().unwrap();
+        match CompressionDictionary::verified(capability, data) {
+            Ok(dictionary) => {
+                state.previous = state.current.replace(dictionary.clone());
+                Ok(dictionary)
+            }
+            Err(e) => {
+                state.failed = Some(capability.clone());
+                Err(e)
+            }
+        }
+    }
+
+    /// Give up on an advertised dictionary that could not be fetched
+    pub(crate) fn fail(&self, capability: &DictionaryCapability) {
+        self.state.lock().unwrap().failed = Some(capability.clone());
+    }
+
+    /// Held dictionary with `id`, to decompress a response
+    pub(crate) fn get(&self, id: u32) -> Option<CompressionDictionary> {
+        let state = self.state.lock().unwrap();
+        state
+            .current
+            .iter()
+            .chain(state.previous.iter())
+            .find(|dictionary| dictionary.id() == id)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn advertise(negotiation: &DictionaryNegotiation, dictionary: &CompressionDictionary) {
+        let mut headers = HeaderMap::new();
+        let value = dictionary.capability().to_header_value();
+        headers.insert(DICTIONARY_CAPABILITY_HEADER, HeaderValue::from_str(&value).unwrap());
+        negotiation.record(&headers);
+    }
+
+    #[test]
+    fn test_fetches_advertised_dictionary_once() {
+        let negotiation = DictionaryNegotiation::default();
+        let dictionary = CompressionDictionary::new(1, b"first dictionary".to_vec());
+        assert!(negotiation.pending().is_none());
+
+        advertise(&negotiation, &dictionary);
+        let capability = negotiation.pending().unwrap();
+        assert!(negotiation.current().is_none());
+
+        negotiation.install(&capability, dictionary.data().clone()).unwrap();
+        assert!(negotiation.pending().is_none());
+        assert_eq!(negotiation.current(), Some(dictionary));
+
+        // A server that stops advertising is sent plain zstd
+        negotiation.record(&HeaderMap::new());
+        assert!(negotiation.current().is_none());
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_for_responses() {
+        let negotiation = DictionaryNegotiation::default();
+        let first = CompressionDictionary::new(1, b"first dictionary".to_vec());
+        let second = CompressionDictionary::new(2, b"second dictionary".to_vec());
+        advertise(&negotiation, &first);
+        negotiation.install(&first.capability(), first.data().clone()).unwrap();
+
+        advertise(&negotiation, &second);
+        assert!(negotiation.current().is_none());
+        assert_eq!(negotiation.pending(), Some(second.capability()));
+        negotiation.install(&second.capability(), second.data().clone()).unwrap();
+
+        assert_eq!(negotiation.current(), Some(second));
+        assert_eq!(negotiation.get(1), Some(first));
+    }
+
+    #[test]
+    fn test_mismatched_dictionary_is_not_retried() {
+        let negotiation = DictionaryNegotiation::default();
+        let dictionary = CompressionDictionary::new(1, b"real dictionary".to_vec());
+        advertise(&negotiation, &dictionary);
+
+        let capability = negotiation.pending().unwrap();
+        assert!(negotiation.install(&capability, Bytes::from_static(b"tampered")).is_err());
+        assert!(negotiation.pending().is_none());
+        assert!(negotiation.current().is_none());
+    }
+}
@@ -0,0 +1,170 @@
+//! Client-side envelope encryption
+//!
+//! This module provides:
+//! - Configuration of which methods carry sensitive data
+//! - A cached, periodically rotated data key
+//!
+//! Requests to protected methods are sealed under a data key from the
+//! configured [`KeyProvider`] before they leave the client, so relays and
+//! gateways that terminate TLS only see ciphertext. prost does not expose
+//! the `quill.field` and `quill.message` annotations at runtime, so
+//! annotated methods are registered here with the matching
+//! [`EnvelopeScope`]. Only unary calls are sealed.
+
+use bytes::Bytes;
+use http::HeaderValue;
+use quill_core::envelope::seal;
+use quill_core::{DataKey, EnvelopeError, EnvelopeHeader, EnvelopeScope, KeyProvider};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default lifetime of a data key before a new one is requested
+pub const DEFAULT_KEY_ROTATION: Duration = Duration::from_secs(300);
+
+/// A request sealed for sending
+pub(crate) struct SealedRequest {
+    pub(crate) body: Bytes,
+    pub(crate) header: HeaderValue,
+    pub(crate) key: DataKey,
+}
+
+/// Envelope encryption of requests to sensitive methods
+pub struct EnvelopeEncryption {
+    provider: Arc<dyn KeyProvider>,
+    key_id: String,
+    rotate_after: Duration,
+    default_scope: Option<EnvelopeScope>,
+    methods: HashMap<String, EnvelopeScope>,
+    current: Mutex<Option<(DataKey, Instant)>>,
+}
+
+impl EnvelopeEncryption {
+    /// Seal requests under data keys wrapped by the master key `key_id`
+    ///
+    /// No method is protected until registered with
+    /// [`protect_method`](Self::protect_method) or [`protect_all`](Self::protect_all).
+    pub fn new(provider: Arc<dyn KeyProvider>, key_id: impl Into<String>) -> Self {
+        Self {
+            provider,
+            key_id: key_id.into(),
+            rotate_after: DEFAULT_KEY_ROTATION,
+            default_scope: None,
+            methods: HashMap::new(),
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Seal `scope` of every request to `service`/`method`
+    pub fn protect_method(
+        mut self,
+        service: impl Into<String>,
+        method: impl Into<String>,
+        scope: EnvelopeScope,
+    ) -> Self {
+        self.methods
+            .insert(format!("{}/{}", service.into(), method.into()), scope);
+        self
+    }
+
+    /// Seal `scope` of requests to methods without their own registration
+    pub fn protect_all(mut self, scope: EnvelopeScope) -> Self {
+        self.default_scope = Some(scope);
+        self
+    }
+
+    /// Set how long a data key is used before a new one is requested
+    pub fn rotate_after(mut self, rotate_after: Duration) -> Self {
+        self.rotate_after = rotate_after;
+        self
+    }
+
+    /// What part of requests to `service`/`method` is sealed
+    pub fn scope(&self, service: &str, method: &str) -> Option<&EnvelopeScope> {
+        self.methods
+            .get(&format!("{}/{}", service, method))
+            .or(self.default_scope.as_ref())
+    }
+
+    /// Seal a request if its method is protected
+    pub(crate) async fn seal(
+        &self,
+        service: &str,
+        method: &str,
+        request: &Bytes,
+    ) -> Result<Option<SealedRequest>, EnvelopeError> {
+        let Some(scope) = self.scope(service, method) else {
+            return Ok(None);
+        };
+        let key = self.data_key().await?;
+        let body = seal(&key, scope, request)?;
+        let header = EnvelopeHeader::new(&key, scope.clone()).to_header_value();
+        let header = HeaderValue::from_str(&header)
+            .map_err(|_| EnvelopeError::InvalidHeader(format!("key id '{}' is not a valid header", self.key_id)))?;
+        Ok(Some(SealedRequest { body, header, key }))
+    }
+
+    /// Current data key, generating a new one once the old one expires
+    async fn data_key(&self) -> Result<DataKey, EnvelopeError> {
+        if let Some((key, created)) = &*self.current.lock().unwrap() {
+            if created.elapsed() < self.rotate_after {
+                return Ok(key.clone());
+            }
+        }
+        let key = self.provider.generate_data_key(&self.key_id).await?;
+        *self.current.lock().unwrap() = Some((key.clone(), Instant::now()));
+        Ok(key)
+    }
+}
+
+impl fmt::Debug for EnvelopeEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnvelopeEncryption")
+            .field("key_id", &self.key_id)
+            .field("rotate_after", &self.rotate_after)
+            .field("default_scope", &self.default_scope)
+            .field("methods", &self.methods)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_core::envelope::open;
+    use quill_core::LocalKeyProvider;
+
+    fn provider() -> Arc<dyn KeyProvider> {
+        Arc::new(LocalKeyProvider::new().with_random_key("k"))
+    }
+
+    #[tokio::test]
+    async fn test_only_protected_methods_are_sealed() {
+        let envelope = EnvelopeEncryption::new(provider(), "k")
+            .protect_method("bank.Accounts", "Open", EnvelopeScope::Fields(vec![1]));
+        let request = Bytes::from_static(&[0x0a, 3, b'a', b'b', b'c']);
+
+        assert!(envelope.seal("bank.Accounts", "List", &request).await.unwrap().is_none());
+
+        let sealed = envelope.seal("bank.Accounts", "Open", &request).await.unwrap().unwrap();
+        assert_ne!(sealed.body, request);
+        let header = EnvelopeHeader::from_header_value(sealed.header.to_str().unwrap()).unwrap();
+        assert_eq!(header.scope, EnvelopeScope::Fields(vec![1]));
+        assert_eq!(open(&sealed.key, &header.scope, &sealed.body).unwrap(), request);
+
+        let all = EnvelopeEncryption::new(provider(), "k").protect_all(EnvelopeScope::Message);
+        assert_eq!(all.scope("any.Service", "Method"), Some(&EnvelopeScope::Message));
+    }
+
+    #[tokio::test]
+    async fn test_data_key_is_cached_until_rotation() {
+        let envelope = EnvelopeEncryption::new(provider(), "k");
+        let first = envelope.data_key().await.unwrap();
+        assert_eq!(envelope.data_key().await.unwrap().wrapped(), first.wrapped());
+
+        let rotating = EnvelopeEncryption::new(provider(), "k").rotate_after(Duration::ZERO);
+        let first = rotating.data_key().await.unwrap();
+        assert_ne!(rotating.data_key().await.unwrap().wrapped(), first.wrapped());
+    }
+}
@@ -0,0 +1,220 @@
+//! Connection lifecycle events
+//!
+//! This module provides:
+//! - Structured events for connectivity changes of a client's endpoint
+//! - Listener registration via [`ClientBuilder::on_event`](crate::client::ClientBuilder::on_event)
+//!
+//! Connectivity is derived from calls: the first successful response after
+//! a failure (or at startup) reports `Connected`, the first transport error
+//! while connected reports `Disconnected`, and every call attempted while
+//! disconnected reports `Reconnecting`. The HTTP client does not manage TLS
+//! material itself; layers that rotate certificates report
+//! `CertificateRotated` through [`ClientEvents::emit`]. With health scoring
+//! enabled, `ConnectionEvicted` reports connections dropped for degrading.
+
+use http::HeaderMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Response header naming the Prism profile the server selected
+pub const SELECTED_PRISM_HEADER: &str = "selected-prism";
+
+/// Callback invoked for every client event
+pub type EventListener = Arc<dyn Fn(&ClientEvent) + Send + Sync>;
+
+/// What happened to the client's connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientEventKind {
+    /// The endpoint answered a call
+    Connected,
+    /// A call failed at the transport level
+    Disconnected { reason: String },
+    /// A call is being attempted while disconnected
+    Reconnecting { attempt: u32 },
+    /// TLS certificates used for the endpoint were reloaded
+    CertificateRotated,
+    /// The server selected a different Prism profile
+    ProfileNegotiated { profile: String },
+    /// The circuit breaker opened and is rejecting calls
+    CircuitOpened,
+    /// Connections were dropped because their health score degraded
+    ConnectionEvicted { reason: String },
+}
+
+/// A lifecycle event of one client endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientEvent {
+    /// Base URL of the client that emitted the event
+    pub endpoint: String,
+    /// What happened
+    pub kind: ClientEventKind,
+}
+
+#[derive(Debug, Default)]
+struct LinkState {
+    connected: Option<bool>,
+    reconnect_attempts: u32,
+    profile: Option<String>,
+}
+
+/// Tracks a client's connectivity and notifies listeners of changes
+pub struct ClientEvents {
+    endpoint: String,
+    listeners: Vec<EventListener>,
+    state: Mutex<LinkState>,
+}
+
+impl ClientEvents {
+    pub(crate) fn new(endpoint: impl Into<String>, listeners: Vec<EventListener>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            listeners,
+            state: Mutex::new(LinkState::default()),
+        }
+    }
+
+    /// Notify listeners of an event
+    pub fn emit(&self, kind: ClientEventKind) {
+        let event = ClientEvent {
+            endpoint: self.endpoint.clone(),
+            kind,
+        };
+        for listener in &self.listeners {
+            listener(&event);
+        }
+    }
+
+    /// Whether the last call reached the endpoint (`None` before the first call)
+    pub fn is_connected(&self) -> Option<bool> {
+        self.state.lock().unwrap().connected
+    }
+
+    /// Record that a call is about to be sent
+    pub(crate) fn before_send(&self) {
+        let attempt = {
+            let mut state = self.state.lock().unwrap();
+            if state.connected != Some(false) {
+                return;
+            }
+            state.reconnect_attempts += 1;
+            state.reconnect_attempts
+        };
+        self.emit(ClientEventKind::Reconnecting { attempt });
+    }
+
+    /// Record a response from the endpoint
+    pub(crate) fn on_response(&self, headers: &HeaderMap) {
+        let profile = headers
+            .get(SELECTED_PRISM_HEADER)
+            .and_then(|value| value.to_str().ok());
+        let (connected, negotiated) = {
+            let mut state = self.state.lock().unwrap();
+            let connected = state.connected != Some(true);
+            state.connected = Some(true);
+            state.reconnect_attempts = 0;
+            let negotiated = match profile {
+                Some(profile) if state.profile.as_deref() != Some(profile) => {
+                    state.profile = Some(profile.to_string());
+                    Some(profile.to_string())
+                }
+                _ => None,
+            };
+            (connected, negotiated)
+        };
+        if connected {
+            self.emit(ClientEventKind::Connected);
+        }
+        if let Some(profile) = negotiated {
+            self.emit(ClientEventKind::ProfileNegotiated { profile });
+        }
+    }
+
+    /// Record a transport failure
+    pub(crate) fn on_transport_error(&self, reason: &str) {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.connected == Some(false) {
+                return;
+            }
+            state.connected = Some(false);
+        }
+        self.emit(ClientEventKind::Disconnected {
+            reason: reason.to_string(),
+        });
+    }
+}
+
+impl fmt::Debug for ClientEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientEvents")
+            .field("endpoint", &self.endpoint)
+            .field("listeners", &self.listeners.len())
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn recording() -> (ClientEvents, Arc<Mutex<Vec<ClientEventKind>>>) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&seen);
+        let listener: EventListener = Arc::new(move |event: &ClientEvent| {
+            assert_eq!(event.endpoint, "http://api");
+            recorded.lock().unwrap().push(event.kind.clone());
+        });
+        (ClientEvents::new("http://api", vec![listener]), seen)
+    }
+
+    #[test]
+    fn test_connectivity_transitions() {
+        let (events, seen) = recording();
+        events.before_send();
+        events.on_response(&HeaderMap::new());
+        events.on_response(&HeaderMap::new());
+        events.on_transport_error("connection refused");
+        events.on_transport_error("connection refused");
+        events.before_send();
+        events.before_send();
+        events.on_response(&HeaderMap::new());
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ClientEventKind::Connected,
+                ClientEventKind::Disconnected {
+                    reason: "connection refused".to_string()
+                },
+                ClientEventKind::Reconnecting { attempt: 1 },
+                ClientEventKind::Reconnecting { attempt: 2 },
+                ClientEventKind::Connected,
+            ]
+        );
+        assert_eq!(events.is_connected(), Some(true));
+    }
+
+    #[test]
+    fn test_profile_negotiated_once_per_change() {
+        let (events, seen) = recording();
+        let mut headers = HeaderMap::new();
+        headers.insert(SELECTED_PRISM_HEADER, HeaderValue::from_static("turbo"));
+        events.on_response(&headers);
+        events.on_response(&headers);
+        headers.insert(SELECTED_PRISM_HEADER, HeaderValue::from_static("classic"));
+        events.on_response(&headers);
+
+        let profiles: Vec<_> = seen
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|kind| match kind {
+                ClientEventKind::ProfileNegotiated { profile } => Some(profile.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(profiles, vec!["turbo", "classic"]);
+    }
+}
@@ -0,0 +1,450 @@
+//! Warm standby failover for server-streaming calls
+//!
+//! This module provides:
+//! - A client over an ordered list of endpoints (primary first, then standbys)
+//! - Transparent re-establishment of a stream that fails mid-flight
+//! - Application-supplied resume tokens so the next endpoint continues
+//!   where the failed one stopped
+//!
+//! The consumer sees a single stream; transient backend failures are hidden
+//! as long as a standby can take over within the configured failover budget.
+
+use crate::client::{QuillClient, RequestOptions};
+use crate::retry::RetryPolicy;
+use bytes::Bytes;
+use http::header::{HeaderName, HeaderValue};
+use quill_core::{QuillError, StreamCursor};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+pub use quill_core::RESUME_TOKEN_HEADER;
+
+/// Extracts a resume token from a received message
+pub type ResumeTokenFn = Arc<dyn Fn(&Bytes) -> Option<Bytes> + Send + Sync>;
+
+/// Builds the re-issued request from the original request and the last token
+pub type ResumeRequestFn = Arc<dyn Fn(&Bytes, &Bytes) -> Bytes + Send + Sync>;
+
+/// Callback invoked on every failover
+pub type FailoverListener = Arc<dyn Fn(&FailoverEvent) + Send + Sync>;
+
+/// Failover configuration
+#[derive(Debug, Clone)]
+pub struct FailoverConfig {
+    /// Maximum number of failovers per call
+    pub max_failovers: u32,
+    /// Decides which errors trigger failover and how long to wait between endpoints
+    pub retry_policy: RetryPolicy,
+    /// Fail over to the standby with the lowest measured RTT instead of the next in order
+    pub prefer_lowest_rtt: bool,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            max_failovers: 3,
+            retry_policy: RetryPolicy::default(),
+            prefer_lowest_rtt: false,
+        }
+    }
+}
+
+/// Details of a failover from one endpoint to the next
+#[derive(Debug, Clone)]
+pub struct FailoverEvent {
+    /// Base URL of the endpoint that failed
+    pub from: String,
+    /// Base URL of the endpoint taking over
+    pub to: String,
+    /// Error that triggered the failover
+    pub error: String,
+    /// Number of messages delivered before the failure
+    pub messages_delivered: u64,
+    /// Resume token sent to the new endpoint, if any
+    pub resume_token: Option<Bytes>,
+}
+
+/// A server-streaming call that can be resumed on another endpoint
+#[derive(Clone)]
+pub struct ResumableCall {
+    service: String,
+    method: String,
+    request: Bytes,
+    options: RequestOptions,
+    token_fn: Option<ResumeTokenFn>,
+    resume_fn: Option<ResumeRequestFn>,
+}
+
+impl ResumableCall {
+    /// Create a call for the given service method and request
+    pub fn new(service: impl Into<String>, method: impl Into<String>, request: Bytes) -> Self {
+        Self {
+            service: service.into(),
+            method: method.into(),
+            request,
+            options: RequestOptions::default(),
+            token_fn: None,
+            resume_fn: None,
+        }
+    }
+
+    /// Set per-request options used for every attempt
+    pub fn options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Extract the resume token from each received message
+    ///
+    /// The most recent token is sent to the next endpoint on failover, in the
+    /// `quill-resume-token` header unless [`resume_request`](Self::resume_request)
+    /// rewrites the request instead.
+    pub fn resume_token<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Bytes) -> Option<Bytes> + Send + Sync + 'static,
+    {
+        self.token_fn = Some(Arc::new(f));
+        self
+    }
+
+    /// Build the re-issued request from the original request and the last token
+    pub fn resume_request<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Bytes, &Bytes) -> Bytes + Send + Sync + 'static,
+    {
+        self.resume_fn = Some(Arc::new(f));
+        self
+    }
+
+    /// Request and options for an attempt resuming from `token`
+    fn attempt(&self, token: Option<&Bytes>) -> (Bytes, RequestOptions) {
+        let Some(token) = token else {
+            return (self.request.clone(), self.options.clone());
+        };
+
+        if let Some(resume_fn) = &self.resume_fn {
+            return (resume_fn(&self.request, token), self.options.clone());
+        }
+
+        let value = HeaderValue::from_str(&StreamCursor::new(token.clone()).to_header_value())
+            .expect("hex is a valid header value");
+        let options = self
+            .options
+            .clone()
+            .header(HeaderName::from_static(RESUME_TOKEN_HEADER), value);
+        (self.request.clone(), options)
+    }
+}
+
+/// Client that fails over streaming calls between warm standby endpoints
+pub struct FailoverClient {
+    endpoints: Vec<Arc<QuillClient>>,
+    config: FailoverConfig,
+    active: Arc<AtomicUsize>,
+    listener: Option<FailoverListener>,
+}
+
+impl FailoverClient {
+    /// Create a failover client from endpoints in priority order
+    ///
+    /// # Panics
+    ///
+    /// Panics if `endpoints` is empty.
+    pub fn new(endpoints: Vec<QuillClient>) -> Self {
+        Self::with_config(endpoints, FailoverConfig::default())
+    }
+
+    /// Create a failover client with custom configuration
+    pub fn with_config(endpoints: Vec<QuillClient>, config: FailoverConfig) -> Self {
+        assert!(!endpoints.is_empty(), "FailoverClient requires at least one endpoint");
+        Self {
+            endpoints: endpoints.into_iter().map(Arc::new).collect(),
+            config,
+            active: Arc::new(AtomicUsize::new(0)),
+            listener: None,
+        }
+    }
+
+    /// Register a callback invoked on every failover
+    pub fn on_failover<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&FailoverEvent) + Send + Sync + 'static,
+    {
+        self.listener = Some(Arc::new(f));
+        self
+    }
+
+    /// Base URL of the endpoint new calls start on
+    ///
+    /// After a failover the standby stays active until it fails in turn.
+    pub fn active_endpoint(&self) -> &str {
+        self.endpoints[self.active.load(Ordering::Relaxed) % self.endpoints.len()].base_url()
+    }
+
+    /// Ping every endpoint to refresh its RTT estimate
+    ///
+    /// Returns the measured RTT per endpoint, in priority order; endpoints
+    /// that failed to answer are `None`.
+    pub async fn probe_rtt(&self) -> Vec<Option<Duration>> {
+        let mut rtts = Vec::with_capacity(self.endpoints.len());
+        for client in &self.endpoints {
+            rtts.push(client.ping().await.ok());
+        }
+        rtts
+    }
+
+    /// Start a server-streaming call with transparent failover
+    ///
+    /// Errors that the retry policy considers retryable (connection loss,
+    /// 503, ...) move the call to the next endpoint, resuming from the last
+    /// token. Other errors, or exhausting the failover budget, end the
+    /// stream with the error.
+    pub fn call_server_streaming(
+        &self,
+        call: ResumableCall,
+    ) -> Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>> {
+        let (tx, rx) = mpsc::channel(16);
+        let endpoints = self.endpoints.clone();
+        let config = self.config.clone();
+        let active = Arc::clone(&self.active);
+        let listener = self.listener.clone();
+
+        tokio::spawn(async move {
+            let mut index = active.load(Ordering::Relaxed) % endpoints.len();
+            let mut failovers = 0;
+            let mut token: Option<Bytes> = None;
+            let mut delivered = 0u64;
+
+            loop {
+                let client = &endpoints[index];
+                let (request, options) = call.attempt(token.as_ref());
+                let error = match client
+                    .call_server_streaming_with_options(&call.service, &call.method, request, options)
+                    .await
+                {
+                    Ok(mut stream) => loop {
+                        match stream.next().await {
+                            Some(Ok(message)) => {
+                                if let Some(token_fn) = &call.token_fn {
+                                    if let Some(t) = token_fn(&message) {
+                                        token = Some(t);
+                                    }
+                                }
+                                delivered += 1;
+                                if tx.send(Ok(message)).await.is_err() {
+                                    // Consumer dropped the stream
+                                    return;
+                                }
+                            }
+                            Some(Err(e)) => break e,
+                            None => return,
+                        }
+                    },
+                    Err(e) => e,
+                };
+
+                if failovers >= config.max_failovers || !config.retry_policy.is_retryable(&error) {
+                    let _ = tx.send(Err(error)).await;
+                    return;
+                }
+
+                failovers += 1;
+                let next = next_endpoint(&endpoints, index, config.prefer_lowest_rtt);
+                tracing::warn!(
+                    "Streaming call {}/{} failed on {}: {}; failing over to {}",
+                    call.service,
+                    call.method,
+                    client.base_url(),
+                    error,
+                    endpoints[next].base_url()
+                );
+                if let Some(listener) = &listener {
+                    listener(&FailoverEvent {
+                        from: client.base_url().to_string(),
+                        to: endpoints[next].base_url().to_string(),
+                        error: error.to_string(),
+                        messages_delivered: delivered,
+                        resume_token: token.clone(),
+                    });
+                }
+
+                let _ = active.compare_exchange(index, next, Ordering::Relaxed, Ordering::Relaxed);
+                index = next;
+                tokio::time::sleep(config.retry_policy.backoff_duration(failovers - 1)).await;
+            }
+        });
+
+        Box::pin(ReceiverStream::new(rx))
+    }
+}
+
+/// Pick the endpoint to fail over to from `failed`
+///
+/// With `prefer_lowest_rtt`, the standby with the lowest smoothed RTT wins;
+/// unmeasured endpoints rank last and ties keep priority order.
+fn next_endpoint(endpoints: &[Arc<QuillClient>], failed: usize, prefer_lowest_rtt: bool) -> usize {
+    let len = endpoints.len();
+    if !prefer_lowest_rtt {
+        return (failed + 1) % len;
+    }
+    (1..len)
+        .map(|step| (failed + step) % len)
+        .min_by_key(|&i| endpoints[i].rtt().unwrap_or(Duration::MAX))
+        .unwrap_or(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_server::{QuillServer, RpcResponse, RpcRouter};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Streams counters from the offset in the request; fails after `fail_after` messages.
+    fn counter_router(fail_after: Option<u64>) -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register("test.Counter/Count", move |req: Bytes| async move {
+            let start = u64::from_le_bytes(req[..8].try_into().unwrap());
+            let (tx, rx) = mpsc::channel(1);
+            tokio::spawn(async move {
+                for i in start..10 {
+                    let item = if fail_after.is_some_and(|n| i >= start + n) {
+                        // Give the transport time to flush what was already sent
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Err(QuillError::Transport("backend lost".to_string()))
+                    } else {
+                        Ok(Bytes::copy_from_slice(&i.to_le_bytes()))
+                    };
+                    let failed = item.is_err();
+                    if tx.send(item).await.is_err() || failed {
+                        return;
+                    }
+                }
+            });
+            Ok(RpcResponse::streaming(ReceiverStream::new(rx)))
+        });
+        router
+    }
+
+    async fn spawn(router: RpcRouter) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = QuillServer::new(router).serve_with_listener(listener).await;
+        });
+        format!("http://{}", addr)
+    }
+
+    fn fast_config() -> FailoverConfig {
+        FailoverConfig {
+            max_failovers: 2,
+            retry_policy: RetryPolicy::new().initial_backoff(Duration::from_millis(1)),
+            ..FailoverConfig::default()
+        }
+    }
+
+    fn counter_call() -> ResumableCall {
+        ResumableCall::new("test.Counter", "Count", Bytes::copy_from_slice(&0u64.to_le_bytes()))
+            .resume_token(|msg| Some(msg.clone()))
+            .resume_request(|_, token| {
+                let last = u64::from_le_bytes(token[..8].try_into().unwrap());
+                Bytes::copy_from_slice(&(last + 1).to_le_bytes())
+            })
+    }
+
+    async fn collect(
+        mut stream: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
+    ) -> (Vec<u64>, Option<QuillError>) {
+        let mut values = Vec::new();
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(msg) => values.push(u64::from_le_bytes(msg[..8].try_into().unwrap())),
+                Err(e) => return (values, Some(e)),
+            }
+        }
+        (values, None)
+    }
+
+    #[tokio::test]
+    async fn test_resumes_on_standby_after_mid_stream_failure() {
+        let primary = spawn(counter_router(Some(4))).await;
+        let standby = spawn(counter_router(None)).await;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let client = FailoverClient::with_config(
+            vec![QuillClient::new(primary.clone()), QuillClient::new(standby.clone())],
+            fast_config(),
+        )
+        .on_failover(move |event| recorded.lock().unwrap().push(event.clone()));
+
+        let (values, error) = collect(client.call_server_streaming(counter_call())).await;
+        assert!(error.is_none(), "unexpected error: {:?}", error);
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].from, primary);
+        assert_eq!(events[0].to, standby);
+        assert_eq!(events[0].messages_delivered, 4);
+        assert_eq!(client.active_endpoint(), standby);
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_when_primary_unreachable() {
+        let standby = spawn(counter_router(None)).await;
+        let client = FailoverClient::with_config(
+            vec![QuillClient::new("http://127.0.0.1:1"), QuillClient::new(standby)],
+            fast_config(),
+        );
+
+        let (values, error) = collect(client.call_server_streaming(counter_call())).await;
+        assert!(error.is_none());
+        assert_eq!(values.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_budget() {
+        let flaky = spawn(counter_router(Some(1))).await;
+        let client = FailoverClient::with_config(vec![QuillClient::new(flaky)], fast_config());
+
+        let (values, error) = collect(client.call_server_streaming(counter_call())).await;
+        // One message per attempt: the initial call plus two failovers
+        assert_eq!(values, vec![0, 1, 2]);
+        assert!(matches!(error, Some(QuillError::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn test_prefers_measured_standby_when_rtt_preferred() {
+        let live = spawn(counter_router(None)).await;
+        let client = FailoverClient::new(vec![
+            QuillClient::new("http://127.0.0.1:1"),
+            QuillClient::new("http://127.0.0.1:2"),
+            QuillClient::new(live),
+        ]);
+
+        let rtts = client.probe_rtt().await;
+        assert!(rtts[0].is_none() && rtts[1].is_none());
+        assert!(rtts[2].is_some());
+
+        assert_eq!(next_endpoint(&client.endpoints, 0, false), 1);
+        assert_eq!(next_endpoint(&client.endpoints, 0, true), 2);
+    }
+
+    #[test]
+    fn test_resume_token_header() {
+        let call = ResumableCall::new("svc", "m", Bytes::from_static(b"req"))
+            .resume_token(|msg| Some(msg.clone()));
+
+        let (request, options) = call.attempt(Some(&Bytes::from_static(&[0xab, 0x01])));
+        assert_eq!(request, Bytes::from_static(b"req"));
+        let headers = format!("{:?}", options);
+        assert!(headers.contains("quill-resume-token"));
+        assert!(headers.contains("ab01"));
+    }
+}
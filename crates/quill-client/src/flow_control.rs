@@ -0,0 +1,354 @@
+//! Credit-based flow control of streaming responses
+//!
+//! This module provides:
+//! - Initial credits and refill threshold configured on the client builder
+//! - A per-stream receive window granting credits as messages are consumed
+//! - Background posting of CREDIT frames to the server
+//!
+//! A flow-controlled stream asks the server to send at most
+//! `initial_credits` messages ahead of the consumer. The response body is
+//! only read while the consumer polls the stream, and credits are granted
+//! back only for messages handed to the consumer, so a stalled consumer
+//! leaves the server without credits instead of piling up messages.
+//! Grants are batched: once `refill_threshold` messages have been consumed,
+//! their credits are returned in one CREDIT frame posted to
+//! [`FLOW_CREDIT_PATH`]. Servers that do not enforce credits do not echo
+//! the negotiation header, and the stream then runs without flow control.
+//!
+//! A tensor window additionally limits the TENSOR_PAYLOAD bytes the server
+//! sends ahead of the consumer (see [`quill_core::tensor_payload_cost`]).
+//! Consumed payload bytes are granted back in the same way, once
+//! `tensor_refill` bytes have been consumed, to servers that echo the
+//! window.
+
+use crate::client::HttpClient;
+use crate::streaming::full_body;
+use bytes::BytesMut;
+use http::header::CONTENT_TYPE;
+use http::{HeaderValue, Method, Request};
+use quill_core::{
+    max_tensor_refill, tensor_payload_cost, FlowControlHeader, Frame, QuillError,
+    DEFAULT_CREDIT_REFILL, DEFAULT_INITIAL_CREDITS, DEFAULT_TENSOR_LOW_WATER, FLOW_CONTROL_HEADER,
+    FLOW_CREDIT_PATH,
+};
+use tokio::sync::mpsc;
+
+/// Flow control configuration for streaming responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowControlConfig {
+    /// Messages the server may send ahead of the consumer
+    pub initial_credits: u32,
+    /// Consumed messages after which their credits are granted back
+    pub refill_threshold: u32,
+    /// Tensor payload bytes the server may send ahead of the consumer (None = unlimited)
+    pub tensor_window: Option<u64>,
+    /// Consumed tensor payload bytes after which they are granted back
+    pub tensor_refill: u64,
+}
+
+impl Default for FlowControlConfig {
+    fn default() -> Self {
+        Self {
+            initial_credits: DEFAULT_INITIAL_CREDITS,
+            refill_threshold: DEFAULT_CREDIT_REFILL,
+            tensor_window: None,
+            tensor_refill: DEFAULT_TENSOR_LOW_WATER,
+        }
+    }
+}
+
+impl FlowControlConfig {
+    /// Create a configuration granting `initial_credits` up front
+    pub fn new(initial_credits: u32) -> Self {
+        let initial_credits = initial_credits.max(1);
+        Self {
+            initial_credits,
+            refill_threshold: DEFAULT_CREDIT_REFILL.min(initial_credits),
+            ..Self::default()
+        }
+    }
+
+    /// Set the consumed messages after which credits are granted back
+    ///
+    /// Clamped to `1..=initial_credits`.
+    pub fn refill_threshold(mut self, threshold: u32) -> Self {
+        self.refill_threshold = threshold;
+        self
+    }
+
+    fn threshold(&self) -> u32 {
+        self.refill_threshold.clamp(1, self.initial_credits.max(1))
+    }
+
+    /// Limit the tensor payload bytes the server sends ahead of the consumer
+    ///
+    /// [`quill_core::DEFAULT_TENSOR_INITIAL_BYTES`] is a reasonable window. Servers may
+    /// lower it.
+    pub fn tensor_window(mut self, bytes: u64) -> Self {
+        self.tensor_window = Some(bytes.max(1));
+        self
+    }
+
+    /// Set the consumed tensor payload bytes after which they are granted back
+    ///
+    /// Clamped to `1..=window / 2`.
+    pub fn tensor_refill(mut self, bytes: u64) -> Self {
+        self.tensor_refill = bytes;
+        self
+    }
+
+    fn tensor_threshold(&self, window: u64) -> u64 {
+        self.tensor_refill.clamp(1, max_tensor_refill(window))
+    }
+
+    /// Negotiation header for a new stream
+    pub(crate) fn request_header(&self) -> (String, HeaderValue) {
+        let header = FlowControlHeader {
+            id: format!("{:032x}", rand::random::<u128>()),
+            credits: Some(self.initial_credits),
+            bytes: self.tensor_window,
+        };
+        let value = HeaderValue::from_str(&header.to_header_value()).expect("flow control header is ASCII");
+        (header.id, value)
+    }
+}
+
+/// Receive-side credit window of one response stream
+pub(crate) struct ReceiveWindow {
+    /// Whether the server enforces message credits
+    messages: bool,
+    /// Credits the server still holds
+    outstanding: u32,
+    /// Messages consumed since the last grant
+    consumed: u32,
+    threshold: u32,
+    /// Tensor byte window, if the server enforces one
+    bytes: Option<ByteWindow>,
+    grants: mpsc::UnboundedSender<Grant>,
+}
+
+/// Receive-side tensor byte window of one response stream
+struct ByteWindow {
+    window: u64,
+    /// Payload bytes the server may still send
+    outstanding: u64,
+    /// Payload bytes consumed since the last grant
+    consumed: u64,
+    threshold: u64,
+}
+
+/// Credits and tensor bytes to grant back to the server
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Grant {
+    credits: u32,
+    bytes: u64,
+}
+
+impl ReceiveWindow {
+    /// Start granting credits for the stream the server agreed to in `echoed`
+    pub(crate) fn start(
+        config: &FlowControlConfig,
+        client: HttpClient,
+        base_url: &str,
+        echoed: FlowControlHeader,
+    ) -> Self {
+        let url = format!("{}/{}", base_url, FLOW_CREDIT_PATH);
+        // The server may lower the window, never raise it
+        let bytes = config
+            .tensor_window
+            .zip(echoed.bytes)
+            .map(|(requested, echoed)| echoed.min(requested))
+            .map(|window| ByteWindow {
+                window,
+                outstanding: window,
+                consumed: 0,
+                threshold: config.tensor_threshold(window),
+            });
+        Self {
+            messages: echoed.credits.is_some(),
+            outstanding: config.initial_credits,
+            consumed: 0,
+            threshold: config.threshold(),
+            bytes,
+            grants: spawn_grants(client, url, echoed.id),
+        }
+    }
+
+    /// Account for a message handed to the consumer
+    ///
+    /// Fails if the server sent the message without holding a credit, or
+    /// sent more tensor payload than the window allows.
+    pub(crate) fn on_message(&mut self, message: &[u8]) -> Result<(), QuillError> {
+        if self.messages && self.outstanding == 0 {
+            return Err(QuillError::Rpc(
+                "Server sent a message without flow control credit".to_string(),
+            ));
+        }
+        let cost = self
+            .bytes
+            .as_ref()
+            .and_then(|bytes| tensor_payload_cost(message, bytes.window));
+        if let Some((bytes, cost)) = self.bytes.as_mut().zip(cost) {
+            if cost > bytes.outstanding {
+                return Err(QuillError::Rpc(
+                    "Server sent tensor payload beyond its flow control window".to_string(),
+                ));
+            }
+        }
+
+        let mut grant = Grant::default();
+        if self.messages {
+            self.outstanding -= 1;
+            self.consumed += 1;
+            if self.consumed >= self.threshold {
+                grant.credits = self.consumed;
+                self.outstanding += self.consumed;
+                self.consumed = 0;
+            }
+        }
+        if let Some((bytes, cost)) = self.bytes.as_mut().zip(cost) {
+            bytes.outstanding -= cost;
+            bytes.consumed += cost;
+            if bytes.consumed >= bytes.threshold {
+                grant.bytes = bytes.consumed;
+                bytes.outstanding += bytes.consumed;
+                bytes.consumed = 0;
+            }
+        }
+        if grant != Grant::default() {
+            // Fails only once the grant task gave up; the server then stalls
+            let _ = self.grants.send(grant);
+        }
+        Ok(())
+    }
+}
+
+/// Post credits queued by a [`ReceiveWindow`] until its stream is dropped
+fn spawn_grants(client: HttpClient, url: String, id: String) -> mpsc::UnboundedSender<Grant> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Grant>();
+    let header = FlowControlHeader {
+        id,
+        credits: None,
+        bytes: None,
+    }
+    .to_header_value();
+
+    tokio::spawn(async move {
+        while let Some(mut grant) = rx.recv().await {
+            // Coalesce grants queued while the previous one was in flight
+            while let Ok(more) = rx.try_recv() {
+                grant.credits = grant.credits.saturating_add(more.credits);
+                grant.bytes = grant.bytes.saturating_add(more.bytes);
+            }
+            let mut body = BytesMut::new();
+            if grant.credits > 0 {
+                Frame::credit(grant.credits).encode_to(&mut body);
+            }
+            if grant.bytes > 0 {
+                Frame::byte_credit(grant.bytes).encode_to(&mut body);
+            }
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri(&url)
+                .header(CONTENT_TYPE, "application/proto")
+                .header(FLOW_CONTROL_HEADER, &header)
+                .body(full_body(body.freeze()));
+            let req = match req {
+                Ok(req) => req,
+                Err(e) => {
+                    tracing::warn!("Failed to build credit grant: {}", e);
+                    return;
+                }
+            };
+            match client.request(req).await {
+                Ok(resp) if resp.status().is_success() => {}
+                Ok(resp) => {
+                    // The stream is gone on the server side
+                    tracing::debug!("Credit grant rejected with status {}", resp.status());
+                    return;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to send credit grant: {}", e);
+                    return;
+                }
+            }
+        }
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(initial: u32, threshold: u32) -> (ReceiveWindow, mpsc::UnboundedReceiver<Grant>) {
+        let (grants, rx) = mpsc::unbounded_channel();
+        let config = FlowControlConfig::new(initial).refill_threshold(threshold);
+        let window = ReceiveWindow {
+            messages: true,
+            outstanding: config.initial_credits,
+            consumed: 0,
+            threshold: config.threshold(),
+            bytes: None,
+            grants,
+        };
+        (window, rx)
+    }
+
+    fn payload(len: u32) -> Vec<u8> {
+        let mut message = vec![0x11, 0, 0, 0, 0];
+        message.extend_from_slice(&len.to_be_bytes());
+        message.resize(9 + len as usize, 0);
+        message
+    }
+
+    #[test]
+    fn test_grants_after_threshold() {
+        let (mut window, mut grants) = window(4, 2);
+
+        window.on_message(b"x").unwrap();
+        assert!(grants.try_recv().is_err());
+        window.on_message(b"x").unwrap();
+        assert_eq!(grants.try_recv().unwrap(), Grant { credits: 2, bytes: 0 });
+        assert_eq!(window.outstanding, 4);
+    }
+
+    #[test]
+    fn test_rejects_message_without_credit() {
+        let (mut window, _grants) = window(2, 2);
+        window.outstanding = 0;
+        assert!(window.on_message(b"x").is_err());
+    }
+
+    #[test]
+    fn test_grants_tensor_bytes() {
+        let (mut window, mut grants) = window(16, 16);
+        let config = FlowControlConfig::new(16).tensor_window(1000).tensor_refill(300);
+        window.bytes = Some(ByteWindow {
+            window: 1000,
+            outstanding: 1000,
+            consumed: 0,
+            threshold: config.tensor_threshold(1000),
+        });
+
+        window.on_message(&payload(200)).unwrap();
+        assert!(grants.try_recv().is_err());
+        window.on_message(&payload(200)).unwrap();
+        assert_eq!(grants.try_recv().unwrap(), Grant { credits: 0, bytes: 400 });
+
+        // More than the server may have in flight
+        window.bytes.as_mut().unwrap().outstanding = 100;
+        assert!(window.on_message(&payload(200)).is_err());
+    }
+
+    #[test]
+    fn test_threshold_clamped_to_initial_credits() {
+        assert_eq!(FlowControlConfig::new(4).refill_threshold(100).threshold(), 4);
+        assert_eq!(FlowControlConfig::new(4).refill_threshold(0).threshold(), 1);
+        assert_eq!(FlowControlConfig::new(2).refill_threshold, 2);
+
+        let config = FlowControlConfig::new(4).tensor_window(1000);
+        assert_eq!(config.tensor_threshold(1000), 500);
+        assert_eq!(config.tensor_refill(0).tensor_threshold(1000), 1);
+    }
+}
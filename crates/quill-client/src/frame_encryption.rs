@@ -0,0 +1,91 @@
+//! Client-side frame encryption
+//!
+//! With a shared key configured on the client builder, every streaming call
+//! gets a fresh `quill-frame-encryption` header. Request frames are sealed
+//! as they are sent and response frames opened as they are read, so callers
+//! see plaintext. A streaming response the server did not seal, e.g. from a
+//! server without the key, fails the call instead of being read in the
+//! clear. See [`quill_core::frame_encryption`] for the wire format.
+
+use http::{HeaderMap, HeaderValue};
+use quill_core::{
+    FrameCipher, FrameDirection, FrameEncryptionHeader, FrameKey, QuillError,
+    FRAME_ENCRYPTION_HEADER,
+};
+
+/// Ciphers of one stream encrypted end to end
+pub(crate) struct StreamEncryption {
+    header: HeaderValue,
+    request: FrameCipher,
+    response: FrameCipher,
+}
+
+impl StreamEncryption {
+    /// Start a stream encrypted with `key`
+    pub(crate) fn start(key: &FrameKey) -> Result<Self, QuillError> {
+        let header = FrameEncryptionHeader::new(key)?;
+        let value = HeaderValue::from_str(&header.to_header_value())
+            .expect("frame encryption header is ASCII");
+        Ok(Self {
+            header: value,
+            request: FrameCipher::new(key, &header, FrameDirection::Request),
+            response: FrameCipher::new(key, &header, FrameDirection::Response),
+        })
+    }
+
+    /// Header to send with the request
+    pub(crate) fn header(&self) -> HeaderValue {
+        self.header.clone()
+    }
+
+    /// Split into the request cipher and the pending response cipher
+    pub(crate) fn split(self) -> (FrameCipher, ResponseDecryption) {
+        let response = ResponseDecryption {
+            header: self.header,
+            cipher: self.response,
+        };
+        (self.request, response)
+    }
+}
+
+/// Opens the frames of a streaming response once the server sealed them
+pub(crate) struct ResponseDecryption {
+    header: HeaderValue,
+    cipher: FrameCipher,
+}
+
+impl ResponseDecryption {
+    /// Cipher opening the response, if the server echoed the header
+    pub(crate) fn accept(self, headers: &HeaderMap) -> Result<FrameCipher, QuillError> {
+        if headers.get(FRAME_ENCRYPTION_HEADER) != Some(&self.header) {
+            return Err(QuillError::Rpc(
+                "Server did not encrypt the response stream".to_string(),
+            ));
+        }
+        Ok(self.cipher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use quill_core::Frame;
+
+    #[test]
+    fn test_requires_echoed_header() {
+        let key = FrameKey::random("k1").unwrap();
+        let (_, response) = StreamEncryption::start(&key).unwrap().split();
+        assert!(response.accept(&HeaderMap::new()).is_err());
+
+        let stream = StreamEncryption::start(&key).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(FRAME_ENCRYPTION_HEADER, stream.header());
+        let (request, response) = stream.split();
+        let response = response.accept(&headers).unwrap();
+
+        // Each direction has its own nonces
+        let sealed = request.seal(Frame::data(Bytes::from_static(b"hi"))).unwrap();
+        assert!(response.open(sealed).is_err());
+    }
+}
@@ -8,7 +8,10 @@ use bytes::Bytes;
 #[cfg(feature = "http3")]
 use http::{Method, Request};
 #[cfg(feature = "http3")]
-use quill_core::{CreditTracker, FrameParser, ProfilePreference, QuillError};
+use quill_core::{
+    CreditTracker, FrameParser, ProfilePreference, QuillError, UploadCapability, PING_METHOD,
+    PING_SERVICE, UPLOAD_CHUNK_HEADER, UPLOAD_MANIFEST_HEADER,
+};
 #[cfg(feature = "http3")]
 use std::fmt;
 #[cfg(feature = "http3")]
@@ -16,12 +19,20 @@ use std::net::SocketAddr;
 #[cfg(feature = "http3")]
 use std::pin::Pin;
 #[cfg(feature = "http3")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "http3")]
+use quill_transport::{StatsSampler, TransportStats};
+#[cfg(feature = "http3")]
 use tokio_stream::Stream;
 #[cfg(feature = "http3")]
 use tracing::instrument;
 
+#[cfg(feature = "http3")]
+use crate::preconnect::{prewarm, PreconnectReport};
 #[cfg(feature = "http3")]
 use crate::streaming::encode_request_stream;
+#[cfg(feature = "http3")]
+use crate::upload::{ChunkedUpload, UploadNegotiation};
 
 /// HTTP/3 client configuration
 #[cfg(feature = "http3")]
@@ -41,6 +52,12 @@ pub struct H3ClientConfig {
     pub enable_compression: bool,
     /// Compression level (0-22)
     pub compression_level: i32,
+    /// Connections to establish and health-check on connect (0 = lazy)
+    pub preconnect: usize,
+    /// Send several packets per syscall with GSO where supported
+    pub enable_segmentation_offload: bool,
+    /// Largest UDP payload accepted, which sizes the receive buffers
+    pub max_udp_payload_size: u16,
 }
 
 #[cfg(feature = "http3")]
@@ -54,6 +71,9 @@ impl Default for H3ClientConfig {
             idle_timeout_ms: 60000,
             enable_compression: false,
             compression_level: 3,
+            preconnect: 0,
+            enable_segmentation_offload: true,
+            max_udp_payload_size: 1472,
         }
     }
 }
@@ -65,6 +85,7 @@ pub struct QuillH3Client {
     client: quill_transport::H3Client,
     profile_preference: ProfilePreference,
     config: H3ClientConfig,
+    uploads: UploadNegotiation,
 }
 
 #[cfg(feature = "http3")]
@@ -85,6 +106,10 @@ impl QuillH3Client {
             max_datagram_size: 65536,
             keep_alive_interval_ms: 30000,
             idle_timeout_ms: config.idle_timeout_ms,
+            enable_datagram_checksum: false,
+            enable_segmentation_offload: config.enable_segmentation_offload,
+            max_udp_payload_size: config.max_udp_payload_size,
+            max_request_body_size: quill_core::framing::MAX_FRAME_SIZE,
         };
 
         let client = quill_transport::H3Client::new(transport_config)
@@ -95,6 +120,7 @@ impl QuillH3Client {
             client,
             profile_preference: ProfilePreference::default_preference(),
             config,
+            uploads: UploadNegotiation::default(),
         })
     }
 
@@ -188,6 +214,8 @@ impl QuillH3Client {
             .await
             .map_err(|e| QuillError::Transport(format!("HTTP/3 request failed: {}", e)))?;
 
+        self.uploads.record(resp.headers());
+
         // Check status code
         let status = resp.status();
         if !status.is_success() {
@@ -219,6 +247,101 @@ impl QuillH3Client {
         Ok(body_bytes)
     }
 
+    /// Make a unary RPC call, splitting a large request across parallel streams
+    ///
+    /// The request is split only if it is larger than the chunk size and the
+    /// server has advertised chunked upload support (learned from an earlier
+    /// response, or from a ping if nothing is known yet). Otherwise this
+    /// behaves like [`call`](Self::call). Chunks are sent uncompressed.
+    pub async fn call_chunked(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        upload: ChunkedUpload,
+    ) -> Result<Bytes, QuillError> {
+        use futures_util::StreamExt;
+
+        if request.len() <= upload.chunk_size {
+            return self.call(service, method, request).await;
+        }
+        let capability = self.upload_capability().await;
+        let Some((manifest, chunks)) = upload.plan(&request, capability) else {
+            return self.call(service, method, request).await;
+        };
+        tracing::debug!(
+            "Uploading {} bytes in {} chunks over HTTP/3",
+            manifest.total_size, manifest.chunk_count
+        );
+
+        let uri = format!("https://localhost/{}/{}", service, method);
+        let manifest_value = manifest.to_header_value();
+        let sends = chunks.into_iter().enumerate().map(|(index, chunk)| {
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri(&uri)
+                .header("content-type", "application/proto")
+                .header("accept", "application/proto")
+                .header("prefer", self.profile_preference.to_header_value())
+                .header(UPLOAD_MANIFEST_HEADER, manifest_value.as_str())
+                .header(UPLOAD_CHUNK_HEADER, index)
+                .body(chunk);
+            async move {
+                let req = req
+                    .map_err(|e| QuillError::Transport(format!("Failed to build request: {}", e)))?;
+                let resp = self
+                    .client
+                    .send_request(self.server_addr, req)
+                    .await
+                    .map_err(|e| QuillError::Transport(format!("HTTP/3 request failed: {}", e)))?;
+
+                let status = resp.status();
+                if status == http::StatusCode::ACCEPTED {
+                    return Ok(None);
+                }
+                let content_encoding = resp
+                    .headers()
+                    .get("content-encoding")
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string());
+                let body = resp.into_body();
+                if !status.is_success() {
+                    if let Ok(pd) = serde_json::from_slice(&body) {
+                        return Err(QuillError::ProblemDetails(pd));
+                    }
+                    return Err(QuillError::Rpc(format!(
+                        "RPC failed with status {}: {}",
+                        status,
+                        String::from_utf8_lossy(&body)
+                    )));
+                }
+                self.maybe_decompress(body, content_encoding.as_deref()).map(Some)
+            }
+        });
+
+        let mut responses = futures_util::stream::iter(sends).buffer_unordered(upload.parallelism);
+        let mut response = None;
+        while let Some(result) = responses.next().await {
+            if let Some(body) = result? {
+                response = Some(body);
+            }
+        }
+        match response {
+            Some(body) => Ok(body),
+            None => Err(QuillError::Rpc("Server did not complete the chunked upload".to_string())),
+        }
+    }
+
+    /// Whether the server accepts chunked uploads, pinging it if not yet known
+    async fn upload_capability(&self) -> Option<UploadCapability> {
+        if let Some(known) = self.uploads.known() {
+            return known;
+        }
+        // call() records the capability from the response headers
+        let _ = self.ping().await;
+        self.uploads.known().flatten()
+    }
+
     /// Make a client streaming RPC call over HTTP/3
     ///
     /// # Arguments
@@ -278,45 +401,8 @@ impl QuillH3Client {
         method: &str,
         request: Bytes,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>, QuillError> {
-        // Build the URI path
-        let uri = format!("https://localhost/{}/{}", service, method);
-
-        // Build the HTTP request
-        let req = Request::builder()
-            .method(Method::POST)
-            .uri(&uri)
-            .header("content-type", "application/proto")
-            .header("accept", "application/proto")
-            .header("prefer", self.profile_preference.to_header_value())
-            .body(request)
-            .map_err(|e| QuillError::Transport(format!("Failed to build request: {}", e)))?;
-
-        // Send the request over HTTP/3
-        let resp = self
-            .client
-            .send_request(self.server_addr, req)
-            .await
-            .map_err(|e| QuillError::Transport(format!("HTTP/3 request failed: {}", e)))?;
-
-        // Check status code
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.into_body();
-            if let Ok(pd) = serde_json::from_slice(&body) {
-                return Err(QuillError::ProblemDetails(pd));
-            }
-            return Err(QuillError::Rpc(format!(
-                "RPC failed with status {}: {}",
-                status,
-                String::from_utf8_lossy(&body)
-            )));
-        }
-
-        // Parse response body as framed stream
-        let body = resp.into_body();
-        let stream = H3ResponseFrameStream::new(body);
-
-        Ok(Box::pin(stream))
+        let (stream, _stats) = self.send_streaming(service, method, request, None).await?;
+        Ok(stream)
     }
 
     /// Make a bidirectional streaming RPC call over HTTP/3
@@ -348,6 +434,57 @@ impl QuillH3Client {
         // Encode the request stream into frames
         let encoded = encode_request_stream(request).await?;
 
+        let (stream, _stats) = self.send_streaming(service, method, encoded, None).await?;
+        Ok(stream)
+    }
+
+    /// Receive a streaming response and report QUIC transport statistics
+    ///
+    /// Returns the response stream together with a transport snapshot taken
+    /// when the response completed. With a sampler, snapshots are also
+    /// delivered periodically while the call is in flight, so stalls in the
+    /// stream can be lined up against congestion and loss events.
+    pub async fn call_server_streaming_with_stats(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        sampler: Option<StatsSampler>,
+    ) -> Result<
+        (Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>, TransportStats),
+        QuillError,
+    > {
+        self.send_streaming(service, method, request, sampler.as_ref()).await
+    }
+
+    /// Make a bidirectional streaming call and report QUIC transport statistics
+    ///
+    /// See [`call_server_streaming_with_stats`](Self::call_server_streaming_with_stats).
+    pub async fn call_bidi_streaming_with_stats(
+        &self,
+        service: &str,
+        method: &str,
+        request: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
+        sampler: Option<StatsSampler>,
+    ) -> Result<
+        (Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>, TransportStats),
+        QuillError,
+    > {
+        let encoded = encode_request_stream(request).await?;
+        self.send_streaming(service, method, encoded, sampler.as_ref()).await
+    }
+
+    /// Send a streaming request body and parse the framed response
+    async fn send_streaming(
+        &self,
+        service: &str,
+        method: &str,
+        body: Bytes,
+        sampler: Option<&StatsSampler>,
+    ) -> Result<
+        (Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>, TransportStats),
+        QuillError,
+    > {
         // Build the URI path
         let uri = format!("https://localhost/{}/{}", service, method);
 
@@ -358,16 +495,23 @@ impl QuillH3Client {
             .header("content-type", "application/proto")
             .header("accept", "application/proto")
             .header("prefer", self.profile_preference.to_header_value())
-            .body(encoded)
+            .body(body)
             .map_err(|e| QuillError::Transport(format!("Failed to build request: {}", e)))?;
 
         // Send the request over HTTP/3
-        let resp = self
+        let (resp, stats) = self
             .client
-            .send_request(self.server_addr, req)
+            .send_request_with_stats(self.server_addr, req, sampler)
             .await
             .map_err(|e| QuillError::Transport(format!("HTTP/3 request failed: {}", e)))?;
 
+        tracing::debug!(
+            rtt_ms = stats.rtt.as_millis() as u64,
+            cwnd = stats.cwnd,
+            lost_packets = stats.lost_packets,
+            "HTTP/3 streaming call transport stats"
+        );
+
         // Check status code
         let status = resp.status();
         if !status.is_success() {
@@ -386,7 +530,7 @@ impl QuillH3Client {
         let body = resp.into_body();
         let stream = H3ResponseFrameStream::new(body);
 
-        Ok(Box::pin(stream))
+        Ok((Box::pin(stream), stats))
     }
 
     /// Get the server address
@@ -398,6 +542,22 @@ impl QuillH3Client {
     pub fn is_zero_rtt_enabled(&self) -> bool {
         self.config.enable_zero_rtt
     }
+
+    /// Send the built-in ping RPC and return its round-trip time
+    pub async fn ping(&self) -> Result<Duration, QuillError> {
+        let start = Instant::now();
+        self.call(PING_SERVICE, PING_METHOD, Bytes::new()).await?;
+        Ok(start.elapsed())
+    }
+
+    /// Complete QUIC handshakes and health-check the server ahead of the first call
+    ///
+    /// Each ping runs a full QUIC+TLS handshake with the profile preference
+    /// attached, which also seeds the TLS session cache so later calls can
+    /// resume (and use 0-RTT when enabled).
+    pub async fn preconnect(&self, connections: usize) -> PreconnectReport {
+        prewarm(connections, || self.ping()).await
+    }
 }
 
 #[cfg(feature = "http3")]
@@ -471,9 +631,7 @@ impl Stream for H3ResponseFrameStream {
                         return Poll::Ready(Some(Ok(frame.payload)));
                     }
                     if frame.flags.is_cancel() {
-                        return Poll::Ready(Some(Err(QuillError::Rpc(
-                            "Stream cancelled by server".to_string(),
-                        ))));
+                        return Poll::Ready(Some(Err(frame.cancel_error())));
                     }
                 }
                 Ok(None) => {
@@ -555,6 +713,38 @@ impl H3ClientBuilder {
         self
     }
 
+    /// Establish this many connections when the client connects
+    pub fn preconnect(mut self, connections: usize) -> Self {
+        self.config.preconnect = connections;
+        self
+    }
+
+    /// Send several packets per syscall with GSO where supported (default on)
+    pub fn enable_segmentation_offload(mut self, enable: bool) -> Self {
+        self.config.enable_segmentation_offload = enable;
+        self
+    }
+
+    /// Set the largest UDP payload accepted (1200-65527 bytes)
+    pub fn max_udp_payload_size(mut self, size: u16) -> Self {
+        self.config.max_udp_payload_size = size;
+        self
+    }
+
+    /// Build the HTTP/3 client and prewarm its connections
+    ///
+    /// Fails if preconnecting was requested and no connection passed the
+    /// health check.
+    pub async fn connect(self) -> Result<QuillH3Client, QuillError> {
+        let client = self.build()?;
+        let report = client.preconnect(client.config.preconnect).await;
+        if !report.is_healthy() {
+            let error = report.errors.into_iter().next();
+            return Err(error.unwrap_or_else(|| QuillError::Transport("Preconnect failed".to_string())));
+        }
+        Ok(client)
+    }
+
     /// Build the HTTP/3 client
     pub fn build(self) -> Result<QuillH3Client, QuillError> {
         let mut client = QuillH3Client::with_config(self.server_addr, self.config)?;
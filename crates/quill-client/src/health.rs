@@ -0,0 +1,324 @@
+//! Connection health scoring
+//!
+//! This module provides:
+//! - A health score for a client's connections, derived from call outcomes
+//! - Eviction of degraded connections so they are re-established proactively
+//! - A snapshot of health statistics for reporting
+//!
+//! Three signals feed the score, each in `[0, 1]`:
+//! - the error rate, an EWMA over calls where transport errors and timeouts
+//!   count as failures (HTTP error responses do not: the path worked)
+//! - RTT inflation, how far the smoothed ping RTT has drifted above the
+//!   minimum seen (see [`RttEstimator`](crate::rtt::RttEstimator))
+//! - the stall rate, an EWMA over response streams where hitting the
+//!   stream idle timeout counts as a stall
+//!
+//! The score is the product of one minus each signal. When it falls below
+//! the configured threshold the client drops its connection pool before the
+//! next call, instead of waiting for idle or keep-alive timeouts to notice a
+//! path that was silently blackholed (e.g. by a NAT rebinding).
+
+use crate::rtt::RttStats;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Configuration of connection health scoring
+#[derive(Debug, Clone)]
+pub struct ConnectionHealthConfig {
+    /// EWMA weight given to each new call or stream outcome
+    pub alpha: f64,
+    /// Evict connections once the score falls below this
+    pub evict_below: f64,
+    /// Smoothed/min RTT ratio at which the RTT signal reaches 1
+    pub max_rtt_inflation: f64,
+    /// Outcomes recorded before the score may trigger an eviction
+    pub min_samples: u64,
+    /// Minimum time between two evictions
+    pub cooldown: Duration,
+}
+
+impl ConnectionHealthConfig {
+    /// Create a configuration with default thresholds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the EWMA weight of new outcomes, clamped to `(0, 1]`
+    pub fn alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha.clamp(f64::EPSILON, 1.0);
+        self
+    }
+
+    /// Set the score below which connections are evicted
+    pub fn evict_below(mut self, score: f64) -> Self {
+        self.evict_below = score;
+        self
+    }
+
+    /// Set the RTT inflation treated as fully unhealthy (at least 1)
+    pub fn max_rtt_inflation(mut self, ratio: f64) -> Self {
+        self.max_rtt_inflation = ratio.max(1.0);
+        self
+    }
+
+    /// Set the number of outcomes needed before evicting
+    pub fn min_samples(mut self, samples: u64) -> Self {
+        self.min_samples = samples;
+        self
+    }
+
+    /// Set the minimum time between evictions
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+impl Default for ConnectionHealthConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.2,
+            evict_below: 0.5,
+            max_rtt_inflation: 4.0,
+            min_samples: 5,
+            cooldown: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Snapshot of connection health statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ConnectionHealthStats {
+    /// Health score in `[0, 1]`; 1 is healthy
+    pub score: f64,
+    /// EWMA of failed calls
+    pub error_rate: f64,
+    /// Smoothed over minimum ping RTT, if the endpoint has been pinged
+    pub rtt_inflation: Option<f64>,
+    /// EWMA of stalled response streams
+    pub stall_rate: f64,
+    /// Outcomes recorded since the last eviction
+    pub samples: u64,
+    /// Transport errors and timeouts since the last eviction
+    pub errors: u64,
+    /// Stalled streams since the last eviction
+    pub stalled_streams: u64,
+    /// Connection pools evicted so far
+    pub evictions: u64,
+}
+
+#[derive(Debug)]
+struct HealthState {
+    error_rate: f64,
+    stall_rate: f64,
+    samples: u64,
+    errors: u64,
+    stalled_streams: u64,
+    evictions: u64,
+    last_eviction: Option<Instant>,
+}
+
+/// Scores the health of a client's connections from call outcomes
+#[derive(Debug)]
+pub struct ConnectionHealth {
+    config: ConnectionHealthConfig,
+    state: Mutex<HealthState>,
+}
+
+impl ConnectionHealth {
+    /// Create a tracker with the given configuration
+    pub fn new(config: ConnectionHealthConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(HealthState {
+                error_rate: 0.0,
+                stall_rate: 0.0,
+                samples: 0,
+                errors: 0,
+                stalled_streams: 0,
+                evictions: 0,
+                last_eviction: None,
+            }),
+        }
+    }
+
+    /// Record a call that reached the endpoint
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.error_rate = self.smooth(state.error_rate, 0.0);
+        state.samples += 1;
+    }
+
+    /// Record a call that failed at the transport level or timed out
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.error_rate = self.smooth(state.error_rate, 1.0);
+        state.samples += 1;
+        state.errors += 1;
+    }
+
+    /// Record the end of a response stream, `stalled` if it hit its idle timeout
+    pub fn record_stream_end(&self, stalled: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.stall_rate = self.smooth(state.stall_rate, if stalled { 1.0 } else { 0.0 });
+        state.samples += 1;
+        if stalled {
+            state.stalled_streams += 1;
+        }
+    }
+
+    /// Snapshot of the current statistics, given the endpoint's RTT
+    pub fn stats(&self, rtt: &RttStats) -> ConnectionHealthStats {
+        let state = self.state.lock().unwrap();
+        let rtt_inflation = rtt_inflation(rtt);
+        ConnectionHealthStats {
+            score: self.score(&state, rtt_inflation),
+            error_rate: state.error_rate,
+            rtt_inflation,
+            stall_rate: state.stall_rate,
+            samples: state.samples,
+            errors: state.errors,
+            stalled_streams: state.stalled_streams,
+            evictions: state.evictions,
+        }
+    }
+
+    /// Decide whether the connections should be evicted now
+    ///
+    /// Returns why when they should; the statistics then start over, as
+    /// they described the connections being dropped.
+    pub fn check_eviction(&self, rtt: &RttStats) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        if state.samples < self.config.min_samples {
+            return None;
+        }
+        if let Some(last) = state.last_eviction {
+            if last.elapsed() < self.config.cooldown {
+                return None;
+            }
+        }
+        let inflation = rtt_inflation(rtt);
+        let score = self.score(&state, inflation);
+        if score >= self.config.evict_below {
+            return None;
+        }
+
+        let reason = format!(
+            "health score {:.2} (error rate {:.2}, rtt inflation {:.1}x, stall rate {:.2})",
+            score,
+            state.error_rate,
+            inflation.unwrap_or(1.0),
+            state.stall_rate
+        );
+        *state = HealthState {
+            error_rate: 0.0,
+            stall_rate: 0.0,
+            samples: 0,
+            errors: 0,
+            stalled_streams: 0,
+            evictions: state.evictions + 1,
+            last_eviction: Some(Instant::now()),
+        };
+        Some(reason)
+    }
+
+    fn smooth(&self, current: f64, sample: f64) -> f64 {
+        current * (1.0 - self.config.alpha) + sample * self.config.alpha
+    }
+
+    fn score(&self, state: &HealthState, rtt_inflation: Option<f64>) -> f64 {
+        let rtt_penalty = match (rtt_inflation, self.config.max_rtt_inflation) {
+            (Some(inflation), max) if max > 1.0 => ((inflation - 1.0) / (max - 1.0)).clamp(0.0, 1.0),
+            _ => 0.0,
+        };
+        (1.0 - state.error_rate) * (1.0 - rtt_penalty) * (1.0 - state.stall_rate)
+    }
+}
+
+/// Ratio of the smoothed to the minimum RTT
+fn rtt_inflation(rtt: &RttStats) -> Option<f64> {
+    match (rtt.smoothed, rtt.min) {
+        (Some(smoothed), Some(min)) if !min.is_zero() => Some(smoothed.as_secs_f64() / min.as_secs_f64()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_rtt() -> RttStats {
+        RttStats::default()
+    }
+
+    #[test]
+    fn test_healthy_calls_keep_score() {
+        let health = ConnectionHealth::new(ConnectionHealthConfig::new());
+        for _ in 0..20 {
+            health.record_success();
+            health.record_stream_end(false);
+        }
+        let stats = health.stats(&no_rtt());
+        assert_eq!(stats.score, 1.0);
+        assert_eq!(stats.samples, 40);
+        assert_eq!(health.check_eviction(&no_rtt()), None);
+    }
+
+    #[test]
+    fn test_errors_trigger_eviction_and_reset() {
+        let health = ConnectionHealth::new(ConnectionHealthConfig::new().cooldown(Duration::ZERO));
+        for _ in 0..5 {
+            health.record_failure();
+        }
+        let stats = health.stats(&no_rtt());
+        assert!(stats.score < 0.5, "score = {}", stats.score);
+        assert_eq!(stats.errors, 5);
+
+        let reason = health.check_eviction(&no_rtt()).unwrap();
+        assert!(reason.contains("error rate"), "{}", reason);
+        let stats = health.stats(&no_rtt());
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.samples, 0);
+        assert_eq!(stats.score, 1.0);
+    }
+
+    #[test]
+    fn test_min_samples_and_cooldown() {
+        let health = ConnectionHealth::new(ConnectionHealthConfig::new().min_samples(3));
+        health.record_failure();
+        health.record_failure();
+        assert_eq!(health.check_eviction(&no_rtt()), None);
+
+        for _ in 0..4 {
+            health.record_failure();
+        }
+        assert!(health.check_eviction(&no_rtt()).is_some());
+        for _ in 0..6 {
+            health.record_failure();
+        }
+        // Still inside the cooldown of the first eviction
+        assert_eq!(health.check_eviction(&no_rtt()), None);
+    }
+
+    #[test]
+    fn test_rtt_inflation_and_stalls_lower_score() {
+        let health = ConnectionHealth::new(ConnectionHealthConfig::new().max_rtt_inflation(3.0));
+        let rtt = RttStats {
+            samples: 10,
+            last: Some(Duration::from_millis(40)),
+            min: Some(Duration::from_millis(10)),
+            smoothed: Some(Duration::from_millis(20)),
+        };
+        let stats = health.stats(&rtt);
+        assert_eq!(stats.rtt_inflation, Some(2.0));
+        assert!((stats.score - 0.5).abs() < 1e-9, "score = {}", stats.score);
+
+        for _ in 0..5 {
+            health.record_stream_end(true);
+        }
+        let stats = health.stats(&rtt);
+        assert_eq!(stats.stalled_streams, 5);
+        assert!(stats.stall_rate > 0.6);
+        assert!(health.check_eviction(&rtt).unwrap().contains("rtt inflation 2.0x"));
+    }
+}
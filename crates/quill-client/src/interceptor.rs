@@ -0,0 +1,180 @@
+//! Client interceptors
+//!
+//! This module provides:
+//! - The [`ClientInterceptor`] trait with async request, response and error hooks
+//! - Per-call metadata shared between the hooks of one call
+//!
+//! Interceptors registered with [`ClientBuilder::interceptor`] see every
+//! HTTP request the client sends, once per attempt when calls are retried.
+//! `on_request` hooks run in registration order and may add or change
+//! headers, e.g. to attach auth tokens or request IDs; an error aborts the
+//! call before anything is sent. `on_response` and `on_error` hooks run in
+//! reverse order, like unwinding middleware. `on_response` sees every
+//! response, error statuses included, while `on_error` covers calls that
+//! got no response at all.
+//!
+//! [`ClientBuilder::interceptor`]: crate::client::ClientBuilder::interceptor
+
+use http::{Extensions, HeaderMap, StatusCode, Uri};
+use quill_core::QuillError;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Future returned by interceptor hooks
+pub type InterceptFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// One attempt of a call, as seen by interceptors
+#[derive(Debug)]
+pub struct CallInfo {
+    /// Request URI, ending in `/{service}/{method}` for RPCs
+    pub uri: Uri,
+    /// When the attempt started
+    pub started: Instant,
+    /// Values set by one hook for the later hooks of the same attempt
+    pub metadata: Extensions,
+}
+
+impl CallInfo {
+    pub(crate) fn new(uri: Uri) -> Self {
+        Self {
+            uri,
+            started: Instant::now(),
+            metadata: Extensions::new(),
+        }
+    }
+
+    /// Service and method of the call, e.g. `("echo.v1.Echo", "Say")`
+    pub fn rpc(&self) -> Option<(&str, &str)> {
+        let (prefix, method) = self.uri.path().rsplit_once('/')?;
+        let service = prefix.rsplit('/').next()?;
+        (!service.is_empty() && !method.is_empty()).then_some((service, method))
+    }
+}
+
+/// Cross-cutting logic run around every call of a client
+///
+/// All hooks default to doing nothing, so implementations only override
+/// the ones they need.
+pub trait ClientInterceptor: Send + Sync {
+    /// Inspect or modify the headers of an outgoing request
+    ///
+    /// Returning an error fails the call without sending it.
+    fn on_request<'a>(
+        &'a self,
+        call: &'a mut CallInfo,
+        headers: &'a mut HeaderMap,
+    ) -> InterceptFuture<'a, Result<(), QuillError>> {
+        let _ = (call, headers);
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Observe the status and headers of a response
+    fn on_response<'a>(
+        &'a self,
+        call: &'a CallInfo,
+        status: StatusCode,
+        headers: &'a HeaderMap,
+    ) -> InterceptFuture<'a, ()> {
+        let _ = (call, status, headers);
+        Box::pin(async {})
+    }
+
+    /// Observe a call that failed before a response arrived
+    fn on_error<'a>(&'a self, call: &'a CallInfo, error: &'a QuillError) -> InterceptFuture<'a, ()> {
+        let _ = (call, error);
+        Box::pin(async {})
+    }
+}
+
+/// Interceptors of a client, in registration order
+pub(crate) struct InterceptorChain<'a>(pub(crate) &'a [Arc<dyn ClientInterceptor>]);
+
+impl InterceptorChain<'_> {
+    pub(crate) async fn on_request(&self, call: &mut CallInfo, headers: &mut HeaderMap) -> Result<(), QuillError> {
+        for interceptor in self.0 {
+            interceptor.on_request(call, headers).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn on_response(&self, call: &CallInfo, status: StatusCode, headers: &HeaderMap) {
+        for interceptor in self.0.iter().rev() {
+            interceptor.on_response(call, status, headers).await;
+        }
+    }
+
+    pub(crate) async fn on_error(&self, call: &CallInfo, error: &QuillError) {
+        for interceptor in self.0.iter().rev() {
+            interceptor.on_error(call, error).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+    use std::sync::Mutex;
+
+    /// Records hook calls and tags requests with its name
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl ClientInterceptor for Recorder {
+        fn on_request<'a>(
+            &'a self,
+            call: &'a mut CallInfo,
+            headers: &'a mut HeaderMap,
+        ) -> InterceptFuture<'a, Result<(), QuillError>> {
+            Box::pin(async move {
+                self.log.lock().unwrap().push(format!("{} request", self.name));
+                headers.append("x-seen-by", HeaderValue::from_static(self.name));
+                call.metadata.insert(self.name);
+                Ok(())
+            })
+        }
+
+        fn on_response<'a>(
+            &'a self,
+            call: &'a CallInfo,
+            status: StatusCode,
+            _headers: &'a HeaderMap,
+        ) -> InterceptFuture<'a, ()> {
+            Box::pin(async move {
+                let tagged = call.metadata.get::<&'static str>().copied().unwrap_or_default();
+                self.log.lock().unwrap().push(format!("{} response {} {}", self.name, status.as_u16(), tagged));
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_in_onion_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let interceptors: Vec<Arc<dyn ClientInterceptor>> = vec![
+            Arc::new(Recorder { name: "outer", log: Arc::clone(&log) }),
+            Arc::new(Recorder { name: "inner", log: Arc::clone(&log) }),
+        ];
+        let chain = InterceptorChain(&interceptors);
+
+        let mut call = CallInfo::new(Uri::from_static("http://localhost/api/echo.v1.Echo/Say"));
+        let mut headers = HeaderMap::new();
+        chain.on_request(&mut call, &mut headers).await.unwrap();
+        chain.on_response(&call, StatusCode::OK, &HeaderMap::new()).await;
+
+        assert_eq!(headers.get_all("x-seen-by").iter().count(), 2);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "outer request",
+                "inner request",
+                "inner response 200 inner",
+                "outer response 200 inner",
+            ]
+        );
+        assert_eq!(call.rpc(), Some(("echo.v1.Echo", "Say")));
+    }
+}
@@ -0,0 +1,350 @@
+//! Joining of server-streaming calls
+//!
+//! This module provides:
+//! - Several labelled server-streaming calls merged into one stream
+//! - Per-source backpressure: each source reads ahead a bounded buffer
+//! - A termination policy: end when all sources end, or at the first error
+//!
+//! Messages keep their order within a source and interleave across sources
+//! as they arrive, e.g. tailing the logs of every shard at once. Unlike
+//! [`ScatterGather`](crate::ScatterGather), sources are not merged in any
+//! global order and may run different calls on different clients.
+
+use crate::client::{QuillClient, RequestOptions};
+use bytes::Bytes;
+use quill_core::QuillError;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt, StreamMap};
+
+type SourceStream = Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>;
+type OpenSource = Pin<Box<dyn Future<Output = Result<SourceStream, QuillError>> + Send>>;
+
+/// When a joined stream ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JoinPolicy {
+    /// End once every source has ended; a failed source yields its error and stops
+    #[default]
+    AllEnd,
+    /// End at the first error, after yielding it, and cancel the other sources
+    FirstError,
+}
+
+/// Builds a stream joining several server-streaming calls
+pub struct StreamJoin {
+    sources: Vec<(Arc<str>, OpenSource)>,
+    policy: JoinPolicy,
+    buffer: usize,
+}
+
+impl StreamJoin {
+    /// Create an empty join
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            policy: JoinPolicy::default(),
+            buffer: 16,
+        }
+    }
+
+    /// Set when the joined stream ends
+    pub fn policy(mut self, policy: JoinPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set how many messages each source reads ahead of the consumer (default 16)
+    ///
+    /// A source with a full buffer stops reading until the consumer catches
+    /// up, without holding back the other sources.
+    pub fn source_buffer(mut self, messages: usize) -> Self {
+        self.buffer = messages.max(1);
+        self
+    }
+
+    /// Add a server-streaming call whose messages are labelled `label`
+    pub fn call(
+        self,
+        label: impl Into<Arc<str>>,
+        client: Arc<QuillClient>,
+        service: &str,
+        method: &str,
+        request: Bytes,
+    ) -> Self {
+        self.call_with_options(label, client, service, method, request, RequestOptions::default())
+    }
+
+    /// Add a server-streaming call with per-request options
+    pub fn call_with_options(
+        mut self,
+        label: impl Into<Arc<str>>,
+        client: Arc<QuillClient>,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        options: RequestOptions,
+    ) -> Self {
+        let (service, method) = (service.to_string(), method.to_string());
+        let open = async move {
+            client
+                .call_server_streaming_with_options(&service, &method, request, options)
+                .await
+        };
+        self.sources.push((label.into(), Box::pin(open)));
+        self
+    }
+
+    /// Add a stream that is already open, e.g. from another client type
+    pub fn stream<S>(mut self, label: impl Into<Arc<str>>, stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, QuillError>> + Send + 'static,
+    {
+        let stream: SourceStream = Box::pin(stream);
+        self.sources.push((label.into(), Box::pin(async move { Ok(stream) })));
+        self
+    }
+
+    /// Start every source and return the joined stream
+    ///
+    /// Calls are opened concurrently; a call that fails to open yields its
+    /// error like a stream that fails later.
+    pub fn join(self) -> JoinedStream {
+        let mut sources = StreamMap::with_capacity(self.sources.len());
+        let mut tasks = Vec::with_capacity(self.sources.len());
+        for (label, open) in self.sources {
+            let (tx, rx) = mpsc::channel(self.buffer);
+            tasks.push(tokio::spawn(forward(open, tx)));
+            sources.insert(label, ReceiverStream::new(rx));
+        }
+        JoinedStream {
+            sources,
+            tasks,
+            policy: self.policy,
+            finished: false,
+        }
+    }
+}
+
+impl Default for StreamJoin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Open one source and read it into its buffer, stopping after an error
+async fn forward(open: OpenSource, tx: mpsc::Sender<Result<Bytes, QuillError>>) {
+    let mut stream = match open.await {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = tx.send(Err(e)).await;
+            return;
+        }
+    };
+    while let Some(item) = stream.next().await {
+        let failed = item.is_err();
+        if tx.send(item).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Messages of joined calls, each with the label of its source
+pub struct JoinedStream {
+    sources: StreamMap<Arc<str>, ReceiverStream<Result<Bytes, QuillError>>>,
+    tasks: Vec<JoinHandle<()>>,
+    policy: JoinPolicy,
+    finished: bool,
+}
+
+impl JoinedStream {
+    /// Labels of the sources that have not ended yet
+    pub fn active(&self) -> Vec<Arc<str>> {
+        self.sources.keys().cloned().collect()
+    }
+}
+
+impl Stream for JoinedStream {
+    type Item = (Arc<str>, Result<Bytes, QuillError>);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+        match Pin::new(&mut self.sources).poll_next(cx) {
+            Poll::Ready(Some((label, Err(e)))) => {
+                match self.policy {
+                    JoinPolicy::AllEnd => {
+                        // The source stops after its error
+                        self.sources.remove(&label);
+                    }
+                    JoinPolicy::FirstError => {
+                        self.finished = true;
+                        self.sources.clear();
+                        self.tasks.iter().for_each(JoinHandle::abort);
+                    }
+                }
+                Poll::Ready(Some((label, Err(e))))
+            }
+            Poll::Ready(None) => {
+                self.finished = true;
+                Poll::Ready(None)
+            }
+            other => other,
+        }
+    }
+}
+
+impl Drop for JoinedStream {
+    fn drop(&mut self) {
+        // Sources still reading are cancelled with the joined stream
+        self.tasks.iter().for_each(JoinHandle::abort);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_server::{QuillServer, RpcResponse, RpcRouter};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn messages(prefix: &str, n: usize) -> impl Stream<Item = Result<Bytes, QuillError>> + Send + 'static {
+        let prefix = prefix.to_string();
+        tokio_stream::iter((0..n).map(move |i| Ok(Bytes::from(format!("{}{}", prefix, i)))))
+    }
+
+    fn failing(prefix: &str, n: usize) -> impl Stream<Item = Result<Bytes, QuillError>> + Send + 'static {
+        messages(prefix, n).chain(tokio_stream::once(Err(QuillError::Transport("shard crashed".into()))))
+    }
+
+    async fn collect(stream: JoinedStream) -> Vec<(String, Result<String, String>)> {
+        stream
+            .map(|(label, item)| {
+                let item = item
+                    .map(|m| String::from_utf8(m.to_vec()).unwrap())
+                    .map_err(|e| e.to_string());
+                (label.to_string(), item)
+            })
+            .collect()
+            .await
+    }
+
+    fn from(items: &[(String, Result<String, String>)], label: &str) -> Vec<String> {
+        items
+            .iter()
+            .filter(|(l, _)| l == label)
+            .filter_map(|(_, item)| item.clone().ok())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_labels_messages_and_keeps_per_source_order() {
+        let joined = StreamJoin::new()
+            .stream("a", messages("a", 5))
+            .stream("b", messages("b", 3))
+            .join();
+
+        let items = collect(joined).await;
+        assert_eq!(items.len(), 8);
+        assert_eq!(from(&items, "a"), ["a0", "a1", "a2", "a3", "a4"]);
+        assert_eq!(from(&items, "b"), ["b0", "b1", "b2"]);
+    }
+
+    #[tokio::test]
+    async fn test_all_end_policy_reports_errors_and_continues() {
+        let joined = StreamJoin::new()
+            .stream("ok", messages("ok", 4))
+            .stream("bad", failing("bad", 1))
+            .join();
+
+        let items = collect(joined).await;
+        assert_eq!(from(&items, "ok").len(), 4);
+        assert_eq!(from(&items, "bad"), ["bad0"]);
+        let errors: Vec<_> = items.iter().filter(|(_, item)| item.is_err()).collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "bad");
+    }
+
+    #[tokio::test]
+    async fn test_first_error_policy_ends_the_join() {
+        let endless = tokio_stream::iter(0..).map(|i: u64| Ok(Bytes::from(i.to_string())));
+        let joined = StreamJoin::new()
+            .policy(JoinPolicy::FirstError)
+            .stream("endless", endless.throttle(Duration::from_millis(5)))
+            .stream("bad", failing("bad", 0))
+            .join();
+
+        let items = tokio::time::timeout(Duration::from_secs(5), collect(joined)).await.unwrap();
+        let (label, last) = items.last().unwrap();
+        assert_eq!(label, "bad");
+        assert!(last.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_full_buffer_holds_back_only_its_source() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&pulled);
+        let fast = tokio_stream::iter(0..1000).map(move |i: u32| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Bytes::from(i.to_string()))
+        });
+        let mut joined = StreamJoin::new()
+            .source_buffer(4)
+            .stream("fast", fast)
+            .stream("slow", messages("slow", 1))
+            .join();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // The buffer plus the message waiting to be sent
+        assert!(pulled.load(Ordering::SeqCst) <= 5, "pulled {}", pulled.load(Ordering::SeqCst));
+
+        let mut slow_seen = false;
+        for _ in 0..6 {
+            let (label, _) = joined.next().await.unwrap();
+            slow_seen |= &*label == "slow";
+        }
+        assert!(slow_seen);
+        assert_eq!(joined.active().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_joins_calls_to_several_servers() {
+        let mut clients = Vec::new();
+        for shard in 0..3 {
+            let mut router = RpcRouter::new();
+            router.register("logs.v1.Logs/Tail", move |_req: Bytes| async move {
+                Ok(RpcResponse::streaming(messages(&format!("shard{}-", shard), 3)))
+            });
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let _ = QuillServer::new(router).serve_with_listener(listener).await;
+            });
+            clients.push(Arc::new(QuillClient::new(format!("http://{}", addr))));
+        }
+
+        let mut join = StreamJoin::new();
+        for (shard, client) in clients.into_iter().enumerate() {
+            join = join.call(format!("shard{}", shard), client, "logs.v1.Logs", "Tail", Bytes::new());
+        }
+        join = join.call(
+            "missing",
+            Arc::new(QuillClient::new("http://127.0.0.1:1")),
+            "logs.v1.Logs",
+            "Tail",
+            Bytes::new(),
+        );
+
+        let items = collect(join.join()).await;
+        for shard in 0..3 {
+            let label = format!("shard{}", shard);
+            assert_eq!(from(&items, &label), (0..3).map(|i| format!("{}-{}", label, i)).collect::<Vec<_>>());
+        }
+        assert!(items.iter().any(|(label, item)| label == "missing" && item.is_err()));
+    }
+}
@@ -3,18 +3,89 @@
 //! This crate provides client-side components:
 //! - Client builder and connection management
 //! - Unary and streaming calls
+//! - Cancellation of in-flight calls
+//! - Cursor streams resumable from a persisted position
+//! - Usage reported by metered streams
+//! - Keyed batch get with per-key caching
+//! - Connection prewarming at startup
+//! - Connection lifecycle event hooks
+//! - Connection health scoring and eviction of degraded connections
+//! - Interceptors for auth, request IDs and metrics
+//! - Read-your-writes through consistency tokens
 //! - Retry logic
+//! - Chunked upload of large unary requests
+//! - Compression dictionaries fetched from the server
+//! - Envelope encryption of sensitive requests
+//! - End-to-end encryption of stream frames
+//! - Ping and round-trip time estimation
+//! - Warm standby failover for streaming calls
+//! - Ordered fan-in for scatter/gather calls
+//! - Joining labelled server-streaming calls into one stream
+//! - Offline call queueing and replay
 //! - Backpressure handling
+//! - Backpressure-aware bridges between streams and tokio channels
+//! - Credit-based flow control of streaming responses
+//! - Teeing a response stream to multiple consumers
+//! - Progress, pause/resume and cancel for large transfers
+//! - Unix domain socket transport for sidecars
 //! - HTTP/3 support (with `http3` feature)
 
+pub mod batch;
+pub mod cancel;
+pub mod channel;
 pub mod client;
+pub mod consistency;
+pub mod dictionary;
+pub mod envelope;
+pub mod events;
+pub mod failover;
+pub mod frame_encryption;
+pub mod health;
+pub mod interceptor;
+pub mod join;
+pub mod flow_control;
 #[cfg(feature = "http3")]
 pub mod h3_client;
+pub mod offline;
+pub mod preconnect;
 pub mod retry;
+pub mod rtt;
+pub mod scatter;
 pub mod streaming;
+pub mod tee;
+pub mod transfer;
+pub mod uds;
+pub mod upload;
 
-pub use client::{ClientConfig, HttpProtocol, QuillClient, RequestOptions};
+pub use batch::{BatchConfig, KeyedBatcher};
+pub use channel::{channel_to_request, stream_to_channel};
+pub use cancel::{CancelStats, CancelToken, DEFAULT_CANCEL_ACK_TIMEOUT};
+pub use client::{
+    ClientConfig, CursorStream, HttpProtocol, PartialStream, QuillClient, RequestOptions, UsageStream,
+};
+pub use consistency::ConsistencyTokens;
+pub use envelope::EnvelopeEncryption;
+pub use events::{ClientEvent, ClientEventKind, ClientEvents, EventListener};
+pub use flow_control::FlowControlConfig;
+pub use health::{ConnectionHealth, ConnectionHealthConfig, ConnectionHealthStats};
+pub use interceptor::{CallInfo, ClientInterceptor, InterceptFuture};
+pub use join::{JoinPolicy, JoinedStream, StreamJoin};
+pub use failover::{
+    FailoverClient, FailoverConfig, FailoverEvent, ResumableCall, RESUME_TOKEN_HEADER,
+};
 #[cfg(feature = "http3")]
 pub use h3_client::{H3ClientBuilder, H3ClientConfig, QuillH3Client};
+#[cfg(feature = "http3")]
+pub use quill_transport::{StatsSampler, TransportStats, UdpOffloadSupport};
+pub use offline::{
+    CallOutcome, FileQueueStore, MemoryQueueStore, OfflineQueue, OfflineQueueConfig, QueueEvent,
+    QueueStore, QueuedCall, ReplayReport,
+};
+pub use preconnect::PreconnectReport;
 pub use retry::{CircuitBreaker, CircuitBreakerConfig, CircuitState, RetryPolicy};
+pub use rtt::{RttEstimator, RttStats, DEFAULT_RTT_ALPHA};
+pub use scatter::{PartialFailurePolicy, ScatterConfig, ScatterGather, ShardFailure};
 pub use streaming::RpcRequest;
+pub use tee::{StreamTee, TeePolicy, TeeStream};
+pub use transfer::{TransferHandle, TransferProgress, TransferState};
+pub use upload::ChunkedUpload;
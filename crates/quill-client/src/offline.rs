@@ -0,0 +1,556 @@
+//! Offline call queue for intermittently connected clients
+//!
+//! This module provides:
+//! - A durable outbound queue for unary calls made while the network is down
+//! - Pluggable persistence (in-memory or one-file-per-call on disk)
+//! - Replay with idempotency keys once connectivity returns
+//! - Queue size and TTL policies with status callbacks
+
+use bytes::Bytes;
+use quill_core::QuillError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Header carrying the idempotency key of a queued call
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Offline queue configuration
+#[derive(Debug, Clone)]
+pub struct OfflineQueueConfig {
+    /// Maximum number of queued calls (oldest are dropped when full)
+    pub max_entries: usize,
+    /// How long a queued call stays eligible for replay
+    pub ttl: Duration,
+    /// Methods eligible for queueing, as "service/method" paths
+    pub methods: HashSet<String>,
+}
+
+impl Default for OfflineQueueConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1000,
+            ttl: Duration::from_secs(24 * 60 * 60),
+            methods: HashSet::new(),
+        }
+    }
+}
+
+impl OfflineQueueConfig {
+    /// Create a new configuration with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set maximum number of queued calls
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    /// Set the time-to-live of queued calls
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Mark a method as eligible for offline queueing
+    pub fn queue_method(mut self, service: &str, method: &str) -> Self {
+        self.methods.insert(format!("{}/{}", service, method));
+        self
+    }
+
+    /// Check if a method is eligible for offline queueing
+    pub fn is_queueable(&self, service: &str, method: &str) -> bool {
+        self.methods.contains(&format!("{}/{}", service, method))
+    }
+}
+
+/// A unary call waiting to be replayed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedCall {
+    /// Queue-local identifier
+    pub id: u64,
+    /// Service path (e.g., "echo.v1.EchoService")
+    pub service: String,
+    /// Method name (e.g., "Echo")
+    pub method: String,
+    /// Protobuf-encoded request bytes
+    pub payload: Vec<u8>,
+    /// Idempotency key sent with every attempt of this call
+    pub idempotency_key: String,
+    /// Enqueue time in milliseconds since the Unix epoch
+    pub enqueued_at_ms: u64,
+    /// Number of replay attempts made so far
+    pub attempts: u32,
+}
+
+impl QueuedCall {
+    /// Check if this call has outlived the given TTL
+    pub fn is_expired(&self, ttl: Duration, now_ms: u64) -> bool {
+        now_ms.saturating_sub(self.enqueued_at_ms) > ttl.as_millis() as u64
+    }
+}
+
+/// Persistence backend for queued calls
+pub trait QueueStore: Send + Sync {
+    /// Load all persisted calls
+    fn load(&self) -> Result<Vec<QueuedCall>, QuillError>;
+
+    /// Persist a call (insert or update)
+    fn put(&self, call: &QueuedCall) -> Result<(), QuillError>;
+
+    /// Remove a persisted call
+    fn remove(&self, id: u64) -> Result<(), QuillError>;
+}
+
+/// Non-durable store; queued calls are lost when the process exits
+#[derive(Debug, Clone, Default)]
+pub struct MemoryQueueStore;
+
+impl QueueStore for MemoryQueueStore {
+    fn load(&self) -> Result<Vec<QueuedCall>, QuillError> {
+        Ok(Vec::new())
+    }
+
+    fn put(&self, _call: &QueuedCall) -> Result<(), QuillError> {
+        Ok(())
+    }
+
+    fn remove(&self, _id: u64) -> Result<(), QuillError> {
+        Ok(())
+    }
+}
+
+/// Durable store that keeps one JSON file per queued call in a directory
+#[derive(Debug, Clone)]
+pub struct FileQueueStore {
+    dir: PathBuf,
+}
+
+impl FileQueueStore {
+    /// Open (and create if needed) a queue directory
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, QuillError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| {
+            QuillError::Transport(format!("Failed to create queue directory: {}", e))
+        })?;
+        Ok(Self { dir })
+    }
+
+    fn path_for(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{:020}.json", id))
+    }
+}
+
+impl QueueStore for FileQueueStore {
+    fn load(&self) -> Result<Vec<QueuedCall>, QuillError> {
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| QuillError::Transport(format!("Failed to read queue directory: {}", e)))?;
+
+        let mut calls = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match fs::read(&path).map(|data| serde_json::from_slice::<QueuedCall>(&data)) {
+                Ok(Ok(call)) => calls.push(call),
+                _ => tracing::warn!("Skipping unreadable queued call {}", path.display()),
+            }
+        }
+
+        calls.sort_by_key(|c| c.id);
+        Ok(calls)
+    }
+
+    fn put(&self, call: &QueuedCall) -> Result<(), QuillError> {
+        let data = serde_json::to_vec(call)
+            .map_err(|e| QuillError::Transport(format!("Failed to encode queued call: {}", e)))?;
+
+        // Write to a temp file and rename so a crash never leaves a torn entry
+        let path = self.path_for(call.id);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data)
+            .and_then(|_| fs::rename(&tmp, &path))
+            .map_err(|e| QuillError::Transport(format!("Failed to persist queued call: {}", e)))
+    }
+
+    fn remove(&self, id: u64) -> Result<(), QuillError> {
+        match fs::remove_file(self.path_for(id)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(QuillError::Transport(format!("Failed to remove queued call: {}", e))),
+        }
+    }
+}
+
+/// Status notifications emitted by the queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueEvent {
+    /// A call was queued for later replay
+    Enqueued { id: u64, idempotency_key: String },
+    /// A queued call was replayed successfully
+    Replayed { id: u64 },
+    /// A queued call was rejected by the server and removed
+    ReplayFailed { id: u64, error: String },
+    /// A queued call outlived its TTL and was removed
+    Expired { id: u64 },
+    /// A queued call was evicted because the queue was full
+    Dropped { id: u64 },
+}
+
+/// Result of a replay pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Calls delivered successfully
+    pub replayed: usize,
+    /// Calls rejected by the server
+    pub failed: usize,
+    /// Calls removed because they expired
+    pub expired: usize,
+    /// Calls still waiting (network went down again mid-replay)
+    pub remaining: usize,
+}
+
+/// Outcome of a call that may be queued
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallOutcome {
+    /// The call completed with this response
+    Completed(Bytes),
+    /// The network was unavailable; the call was queued with this id
+    Queued(u64),
+}
+
+type QueueListener = Arc<dyn Fn(&QueueEvent) + Send + Sync>;
+
+/// Durable outbound queue for unary calls
+pub struct OfflineQueue {
+    config: OfflineQueueConfig,
+    store: Arc<dyn QueueStore>,
+    entries: Mutex<VecDeque<QueuedCall>>,
+    next_id: AtomicU64,
+    replay_lock: tokio::sync::Mutex<()>,
+    listener: Option<QueueListener>,
+}
+
+impl OfflineQueue {
+    /// Create a non-durable queue
+    pub fn new(config: OfflineQueueConfig) -> Self {
+        Self {
+            config,
+            store: Arc::new(MemoryQueueStore),
+            entries: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1),
+            replay_lock: tokio::sync::Mutex::new(()),
+            listener: None,
+        }
+    }
+
+    /// Create a queue backed by the given store, restoring persisted calls
+    pub fn with_store(
+        config: OfflineQueueConfig,
+        store: Arc<dyn QueueStore>,
+    ) -> Result<Self, QuillError> {
+        let persisted = store.load()?;
+        let next_id = persisted.iter().map(|c| c.id).max().unwrap_or(0) + 1;
+
+        Ok(Self {
+            config,
+            store,
+            entries: Mutex::new(persisted.into()),
+            next_id: AtomicU64::new(next_id),
+            replay_lock: tokio::sync::Mutex::new(()),
+            listener: None,
+        })
+    }
+
+    /// Register a callback for queue status events
+    pub fn on_event<F>(mut self, listener: F) -> Self
+    where
+        F: Fn(&QueueEvent) + Send + Sync + 'static,
+    {
+        self.listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &OfflineQueueConfig {
+        &self.config
+    }
+
+    /// Number of queued calls
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Check if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Snapshot of the queued calls in replay order
+    pub fn pending(&self) -> Vec<QueuedCall> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Queue a call with a freshly generated idempotency key
+    pub fn enqueue(&self, service: &str, method: &str, payload: Bytes) -> Result<u64, QuillError> {
+        self.enqueue_with_key(service, method, payload, generate_idempotency_key())
+    }
+
+    /// Queue a call with an existing idempotency key
+    ///
+    /// Use this when a first attempt was already sent with `idempotency_key`,
+    /// so the server can deduplicate if that attempt actually landed.
+    pub fn enqueue_with_key(
+        &self,
+        service: &str,
+        method: &str,
+        payload: Bytes,
+        idempotency_key: String,
+    ) -> Result<u64, QuillError> {
+        self.purge_expired();
+
+        let call = QueuedCall {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            service: service.to_string(),
+            method: method.to_string(),
+            payload: payload.to_vec(),
+            idempotency_key,
+            enqueued_at_ms: now_ms(),
+            attempts: 0,
+        };
+        self.store.put(&call)?;
+
+        let mut dropped = Vec::new();
+        {
+            let mut entries = self.entries.lock().unwrap();
+            entries.push_back(call.clone());
+            while entries.len() > self.config.max_entries {
+                if let Some(old) = entries.pop_front() {
+                    dropped.push(old.id);
+                }
+            }
+        }
+
+        for id in dropped {
+            let _ = self.store.remove(id);
+            self.emit(QueueEvent::Dropped { id });
+        }
+        self.emit(QueueEvent::Enqueued {
+            id: call.id,
+            idempotency_key: call.idempotency_key,
+        });
+
+        Ok(call.id)
+    }
+
+    /// Remove calls that have outlived the TTL
+    ///
+    /// Returns the number of calls removed.
+    pub fn purge_expired(&self) -> usize {
+        let now = now_ms();
+        let expired: Vec<u64> = {
+            let mut entries = self.entries.lock().unwrap();
+            let (expired, live): (Vec<_>, Vec<_>) =
+                entries.drain(..).partition(|c| c.is_expired(self.config.ttl, now));
+            entries.extend(live);
+            expired.into_iter().map(|c| c.id).collect()
+        };
+
+        for &id in &expired {
+            let _ = self.store.remove(id);
+            self.emit(QueueEvent::Expired { id });
+        }
+        expired.len()
+    }
+
+    /// Replay queued calls in order using `send`
+    ///
+    /// Transport errors mean the network is still down: the call stays at the
+    /// head of the queue and the pass stops. Any other error is treated as a
+    /// server rejection and the call is removed.
+    pub async fn replay<F, Fut>(&self, send: F) -> ReplayReport
+    where
+        F: Fn(QueuedCall) -> Fut,
+        Fut: Future<Output = Result<Bytes, QuillError>>,
+    {
+        let _guard = self.replay_lock.lock().await;
+        let mut report = ReplayReport {
+            expired: self.purge_expired(),
+            ..Default::default()
+        };
+
+        loop {
+            let Some(mut call) = self.entries.lock().unwrap().pop_front() else {
+                break;
+            };
+            call.attempts += 1;
+
+            match send(call.clone()).await {
+                Ok(_) => {
+                    let _ = self.store.remove(call.id);
+                    report.replayed += 1;
+                    self.emit(QueueEvent::Replayed { id: call.id });
+                }
+                Err(QuillError::Transport(e)) => {
+                    tracing::debug!("Replay of queued call {} deferred: {}", call.id, e);
+                    let _ = self.store.put(&call);
+                    self.entries.lock().unwrap().push_front(call);
+                    break;
+                }
+                Err(e) => {
+                    let _ = self.store.remove(call.id);
+                    report.failed += 1;
+                    self.emit(QueueEvent::ReplayFailed {
+                        id: call.id,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        report.remaining = self.len();
+        report
+    }
+
+    fn emit(&self, event: QueueEvent) {
+        if let Some(listener) = &self.listener {
+            listener(&event);
+        }
+    }
+}
+
+impl fmt::Debug for OfflineQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OfflineQueue")
+            .field("config", &self.config)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Generate a random idempotency key
+pub fn generate_idempotency_key() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("quill-offline-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_config_queueable_methods() {
+        let config = OfflineQueueConfig::new().queue_method("telemetry.v1.Ingest", "Report");
+
+        assert!(config.is_queueable("telemetry.v1.Ingest", "Report"));
+        assert!(!config.is_queueable("telemetry.v1.Ingest", "Query"));
+    }
+
+    #[test]
+    fn test_enqueue_drops_oldest_when_full() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        let queue = OfflineQueue::new(OfflineQueueConfig::new().max_entries(2))
+            .on_event(move |e| events_clone.lock().unwrap().push(e.clone()));
+
+        let first = queue.enqueue("svc", "M", Bytes::from("1")).unwrap();
+        queue.enqueue("svc", "M", Bytes::from("2")).unwrap();
+        queue.enqueue("svc", "M", Bytes::from("3")).unwrap();
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pending()[0].payload, b"2".to_vec());
+        assert!(events.lock().unwrap().contains(&QueueEvent::Dropped { id: first }));
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let queue = OfflineQueue::new(OfflineQueueConfig::new().ttl(Duration::from_secs(60)));
+        queue.enqueue("svc", "M", Bytes::from("fresh")).unwrap();
+        queue.entries.lock().unwrap()[0].enqueued_at_ms -= 120_000;
+
+        assert_eq!(queue.purge_expired(), 1);
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_stops_on_transport_error() {
+        let queue = OfflineQueue::new(OfflineQueueConfig::new());
+        queue.enqueue("svc", "A", Bytes::from("a")).unwrap();
+        queue.enqueue("svc", "B", Bytes::from("b")).unwrap();
+        queue.enqueue("svc", "C", Bytes::from("c")).unwrap();
+
+        let report = queue
+            .replay(|call| async move {
+                match call.method.as_str() {
+                    "A" => Ok(Bytes::new()),
+                    "B" => Err(QuillError::Rpc("rejected".to_string())),
+                    _ => Err(QuillError::Transport("offline".to_string())),
+                }
+            })
+            .await;
+
+        assert_eq!(report.replayed, 1);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.remaining, 1);
+
+        let pending = queue.pending();
+        assert_eq!(pending[0].method, "C");
+        assert_eq!(pending[0].attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_reuses_idempotency_key() {
+        let queue = OfflineQueue::new(OfflineQueueConfig::new());
+        queue.enqueue_with_key("svc", "M", Bytes::from("x"), "key-1".to_string()).unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+        queue
+            .replay(move |call| {
+                seen_clone.lock().unwrap().push(call.idempotency_key);
+                async { Ok(Bytes::new()) }
+            })
+            .await;
+
+        assert_eq!(*seen.lock().unwrap(), vec!["key-1".to_string()]);
+    }
+
+    #[test]
+    fn test_file_store_survives_restart() {
+        let dir = temp_dir("restart");
+        let _ = fs::remove_dir_all(&dir);
+
+        {
+            let store = Arc::new(FileQueueStore::open(&dir).unwrap());
+            let queue = OfflineQueue::with_store(OfflineQueueConfig::new(), store).unwrap();
+            queue.enqueue("svc", "M", Bytes::from("persisted")).unwrap();
+        }
+
+        let store = Arc::new(FileQueueStore::open(&dir).unwrap());
+        let queue = OfflineQueue::with_store(OfflineQueueConfig::new(), store).unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pending()[0].payload, b"persisted".to_vec());
+
+        // New ids continue after the restored ones
+        let id = queue.enqueue("svc", "M", Bytes::from("next")).unwrap();
+        assert_eq!(id, 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,109 @@
+//! Connection prewarming
+//!
+//! This module provides:
+//! - A report of connections established and health-checked at startup
+//! - Concurrent warm-up pings shared by the HTTP/2 and HTTP/3 clients
+//!
+//! Warm-up uses the built-in ping RPC, so the transport handshake (TCP+TLS
+//! or QUIC), the HTTP settings exchange and the `Prefer` profile header all
+//! happen before the first real request instead of inside it.
+
+use futures_util::future::join_all;
+use quill_core::QuillError;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Outcome of prewarming a client's connections
+#[derive(Debug, Default)]
+pub struct PreconnectReport {
+    /// Number of warm-up pings sent
+    pub attempted: usize,
+    /// Number of warm-up pings that succeeded
+    pub succeeded: usize,
+    /// Fastest successful round trip
+    pub min_rtt: Option<Duration>,
+    /// Wall time spent prewarming
+    pub elapsed: Duration,
+    /// Errors from failed pings
+    pub errors: Vec<QuillError>,
+}
+
+impl PreconnectReport {
+    /// Whether at least one connection passed the health check
+    ///
+    /// An empty report (nothing attempted) counts as healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.attempted == 0 || self.succeeded > 0
+    }
+}
+
+/// Send `connections` pings concurrently and collect the results
+///
+/// Concurrent requests force the pool to open one connection per ping
+/// on HTTP/1.1 and pipeline them over a single connection on HTTP/2.
+pub(crate) async fn prewarm<F, Fut>(connections: usize, ping: F) -> PreconnectReport
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Duration, QuillError>>,
+{
+    let start = Instant::now();
+    let results = join_all((0..connections).map(|_| ping())).await;
+
+    let mut report = PreconnectReport {
+        attempted: connections,
+        ..Default::default()
+    };
+    for result in results {
+        match result {
+            Ok(rtt) => {
+                report.succeeded += 1;
+                report.min_rtt = Some(report.min_rtt.map_or(rtt, |min| min.min(rtt)));
+            }
+            Err(e) => report.errors.push(e),
+        }
+    }
+    report.elapsed = start.elapsed();
+
+    tracing::debug!(
+        "Prewarmed {}/{} connections in {:?}",
+        report.succeeded,
+        report.attempted,
+        report.elapsed
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_prewarm_collects_results() {
+        let calls = AtomicUsize::new(0);
+        let report = prewarm(3, || {
+            let n = calls.fetch_add(1, Ordering::Relaxed);
+            async move {
+                if n == 1 {
+                    Err(QuillError::Transport("refused".to_string()))
+                } else {
+                    Ok(Duration::from_millis(10 + n as u64))
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(report.attempted, 3);
+        assert_eq!(report.succeeded, 2);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.min_rtt, Some(Duration::from_millis(10)));
+        assert!(report.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_all_failures_is_unhealthy() {
+        let report = prewarm(2, || async { Err(QuillError::Transport("refused".to_string())) }).await;
+        assert!(!report.is_healthy());
+        assert!(PreconnectReport::default().is_healthy());
+    }
+}
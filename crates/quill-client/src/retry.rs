@@ -5,7 +5,7 @@
 //! - Circuit breaker pattern for fault tolerance
 //! - Idempotency key support for safe retries
 
-use quill_core::QuillError;
+use quill_core::{ErrorCode, QuillError};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -24,7 +24,9 @@ pub struct RetryPolicy {
     pub backoff_multiplier: f64,
     /// Add random jitter to backoff (0.0 to 1.0)
     pub jitter: f64,
-    /// Only retry on specific errors
+    /// Error codes to retry
+    pub retryable_codes: Vec<ErrorCode>,
+    /// HTTP statuses to retry instead of `retryable_codes` (empty = use codes)
     pub retryable_status_codes: Vec<u16>,
 }
 
@@ -36,14 +38,8 @@ impl Default for RetryPolicy {
             max_backoff: Duration::from_secs(30),
             backoff_multiplier: 2.0,
             jitter: 0.1,
-            retryable_status_codes: vec![
-                408, // Request Timeout
-                429, // Too Many Requests
-                500, // Internal Server Error
-                502, // Bad Gateway
-                503, // Service Unavailable
-                504, // Gateway Timeout
-            ],
+            retryable_codes: ErrorCode::ALL.into_iter().filter(ErrorCode::is_retryable).collect(),
+            retryable_status_codes: Vec::new(),
         }
     }
 }
@@ -84,20 +80,30 @@ impl RetryPolicy {
         self
     }
 
-    /// Set retryable status codes
+    /// Set retryable error codes
+    pub fn retryable_codes(mut self, codes: Vec<ErrorCode>) -> Self {
+        self.retryable_codes = codes;
+        self
+    }
+
+    /// Retry Problem Details responses by HTTP status instead of error code
     pub fn retryable_status_codes(mut self, codes: Vec<u16>) -> Self {
         self.retryable_status_codes = codes;
         self
     }
 
     /// Check if an error is retryable
+    ///
+    /// By default an error is retried when its [`ErrorCode`] is retryable:
+    /// transport failures, unavailable or rate-limited servers, timeouts and
+    /// aborted calls. Setting `retryable_status_codes` matches Problem
+    /// Details by status instead.
     pub fn is_retryable(&self, error: &QuillError) -> bool {
         match error {
-            QuillError::ProblemDetails(details) => {
+            QuillError::ProblemDetails(details) if !self.retryable_status_codes.is_empty() => {
                 self.retryable_status_codes.contains(&details.status)
             }
-            QuillError::Transport(_) => true, // Network errors are retryable
-            _ => false,
+            _ => self.retryable_codes.contains(&error.code()),
         }
     }
 
@@ -165,6 +171,7 @@ impl Default for CircuitBreakerConfig {
 pub struct CircuitBreaker {
     config: CircuitBreakerConfig,
     state: Arc<RwLock<CircuitBreakerState>>,
+    on_open: std::sync::Mutex<Option<Arc<dyn Fn() + Send + Sync>>>,
 }
 
 #[derive(Debug)]
@@ -188,9 +195,15 @@ impl CircuitBreaker {
                 last_failure_time: None,
                 opened_at: None,
             })),
+            on_open: std::sync::Mutex::new(None),
         }
     }
 
+    /// Invoke `f` whenever the circuit opens
+    pub fn set_on_open(&self, f: Arc<dyn Fn() + Send + Sync>) {
+        *self.on_open.lock().unwrap() = Some(f);
+    }
+
     /// Check if a request can proceed
     pub async fn allow_request(&self) -> Result<(), QuillError> {
         let mut state = self.state.write().await;
@@ -264,6 +277,7 @@ impl CircuitBreaker {
 
         state.last_failure_time = Some(now);
 
+        let was_open = state.current_state == CircuitState::Open;
         match state.current_state {
             CircuitState::Closed => {
                 state.failure_count += 1;
@@ -283,6 +297,14 @@ impl CircuitBreaker {
                 // Already open, nothing to do
             }
         }
+
+        let opened = !was_open && state.current_state == CircuitState::Open;
+        drop(state);
+        if opened {
+            if let Some(on_open) = self.on_open.lock().unwrap().clone() {
+                on_open();
+            }
+        }
     }
 
     /// Get the current circuit state
@@ -392,6 +414,8 @@ mod tests {
             instance: None,
             quill_proto_type: None,
             quill_proto_detail_base64: None,
+            quill_code: None,
+            debug: None,
         });
         assert!(policy.is_retryable(&retryable_error));
 
@@ -403,10 +427,39 @@ mod tests {
             instance: None,
             quill_proto_type: None,
             quill_proto_detail_base64: None,
+            quill_code: None,
+            debug: None,
         });
         assert!(!policy.is_retryable(&non_retryable_error));
     }
 
+    #[test]
+    fn test_is_retryable_by_error_code() {
+        use quill_core::ProblemDetails;
+        use http::StatusCode;
+        let policy = RetryPolicy::default();
+
+        // Internal errors may have had side effects, so are not retried by default
+        let internal = ProblemDetails::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal error");
+        assert!(!policy.is_retryable(&QuillError::ProblemDetails(internal.clone())));
+        assert!(!policy.is_retryable(&QuillError::Framing("bad frame".to_string())));
+
+        // The carried code wins over the status
+        let aborted = internal.clone().with_code(ErrorCode::Aborted);
+        assert!(policy.is_retryable(&QuillError::ProblemDetails(aborted)));
+        let throttled = ProblemDetails::new(StatusCode::TOO_MANY_REQUESTS, "Slow down");
+        assert!(policy.is_retryable(&QuillError::ProblemDetails(throttled.clone())));
+
+        let only_internal = RetryPolicy::new().retryable_codes(vec![ErrorCode::Internal]);
+        assert!(only_internal.is_retryable(&QuillError::ProblemDetails(internal.clone())));
+        assert!(!only_internal.is_retryable(&QuillError::Transport("reset".to_string())));
+
+        let by_status = RetryPolicy::new().retryable_status_codes(vec![500]);
+        assert!(by_status.is_retryable(&QuillError::ProblemDetails(internal)));
+        assert!(!by_status.is_retryable(&QuillError::ProblemDetails(throttled)));
+        assert!(by_status.is_retryable(&QuillError::Transport("reset".to_string())));
+    }
+
     #[tokio::test]
     async fn test_circuit_breaker_closed_to_open() {
         let config = CircuitBreakerConfig {
@@ -0,0 +1,139 @@
+//! Round-trip time estimation
+//!
+//! This module provides:
+//! - An EWMA round-trip time estimator, one per endpoint
+//! - A snapshot of RTT statistics for reporting
+//!
+//! Samples come from the built-in ping RPC (see [`QuillClient::ping`]) so
+//! they reflect the same transport regular calls use.
+//!
+//! [`QuillClient::ping`]: crate::QuillClient::ping
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Default EWMA weight given to a new sample (as in TCP's SRTT)
+pub const DEFAULT_RTT_ALPHA: f64 = 0.125;
+
+/// Snapshot of round-trip time statistics for one endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RttStats {
+    /// Number of samples recorded
+    pub samples: u64,
+    /// Most recent sample
+    pub last: Option<Duration>,
+    /// Smallest sample seen
+    pub min: Option<Duration>,
+    /// Exponentially weighted moving average of the samples
+    pub smoothed: Option<Duration>,
+}
+
+/// EWMA round-trip time estimator
+#[derive(Debug)]
+pub struct RttEstimator {
+    alpha: f64,
+    stats: Mutex<RttStats>,
+}
+
+impl RttEstimator {
+    /// Create an estimator with the default smoothing factor
+    pub fn new() -> Self {
+        Self::with_alpha(DEFAULT_RTT_ALPHA)
+    }
+
+    /// Create an estimator with a custom smoothing factor
+    ///
+    /// `alpha` is clamped to `(0, 1]`; higher values react faster to change.
+    pub fn with_alpha(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(f64::EPSILON, 1.0),
+            stats: Mutex::new(RttStats::default()),
+        }
+    }
+
+    /// Record a round-trip time sample
+    pub fn record(&self, sample: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.samples += 1;
+        stats.last = Some(sample);
+        stats.min = Some(stats.min.map_or(sample, |min| min.min(sample)));
+        stats.smoothed = Some(match stats.smoothed {
+            None => sample,
+            Some(prev) => {
+                let secs = prev.as_secs_f64() * (1.0 - self.alpha) + sample.as_secs_f64() * self.alpha;
+                Duration::from_secs_f64(secs)
+            }
+        });
+    }
+
+    /// Current smoothed round-trip time, if any sample has been recorded
+    pub fn smoothed(&self) -> Option<Duration> {
+        self.stats.lock().unwrap().smoothed
+    }
+
+    /// Snapshot of the current statistics
+    pub fn stats(&self) -> RttStats {
+        *self.stats.lock().unwrap()
+    }
+
+    /// Discard all samples
+    pub fn reset(&self) {
+        *self.stats.lock().unwrap() = RttStats::default();
+    }
+}
+
+impl Default for RttEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_sample_seeds_average() {
+        let rtt = RttEstimator::new();
+        assert_eq!(rtt.smoothed(), None);
+
+        rtt.record(Duration::from_millis(40));
+        let stats = rtt.stats();
+        assert_eq!(stats.samples, 1);
+        assert_eq!(stats.smoothed, Some(Duration::from_millis(40)));
+        assert_eq!(stats.min, Some(Duration::from_millis(40)));
+    }
+
+    #[test]
+    fn test_ewma_moves_toward_new_samples() {
+        let rtt = RttEstimator::with_alpha(0.5);
+        rtt.record(Duration::from_millis(100));
+        rtt.record(Duration::from_millis(20));
+        let smoothed = rtt.smoothed().unwrap().as_secs_f64();
+        assert!((smoothed - 0.060).abs() < 1e-6, "smoothed = {}", smoothed);
+
+        let stats = rtt.stats();
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.last, Some(Duration::from_millis(20)));
+        assert_eq!(stats.min, Some(Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn test_single_outlier_is_damped() {
+        let rtt = RttEstimator::new();
+        for _ in 0..10 {
+            rtt.record(Duration::from_millis(10));
+        }
+        rtt.record(Duration::from_millis(500));
+        let smoothed = rtt.smoothed().unwrap();
+        assert!(smoothed < Duration::from_millis(80), "smoothed = {:?}", smoothed);
+    }
+
+    #[test]
+    fn test_reset() {
+        let rtt = RttEstimator::new();
+        rtt.record(Duration::from_millis(5));
+        rtt.reset();
+        assert_eq!(rtt.stats(), RttStats::default());
+    }
+}
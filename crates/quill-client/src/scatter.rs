@@ -0,0 +1,442 @@
+//! Ordered fan-in for scatter/gather RPCs
+//!
+//! This module provides:
+//! - Fan-out of one server-streaming request to N shard endpoints
+//! - A k-way merge of the per-shard streams by a user-supplied comparator
+//! - Per-shard timeouts and a partial-failure policy
+//!
+//! Each shard is expected to return its results already ordered by the same
+//! comparator (e.g. nearest neighbours by distance); the merged stream is
+//! then globally ordered.
+
+use crate::client::{QuillClient, RequestOptions};
+use bytes::Bytes;
+use quill_core::QuillError;
+use std::cmp::Ordering;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Compares two messages for merge ordering
+pub type MergeComparator = Arc<dyn Fn(&Bytes, &Bytes) -> Ordering + Send + Sync>;
+
+/// Callback invoked when a shard fails and is dropped from the merge
+pub type ShardFailureListener = Arc<dyn Fn(&ShardFailure) + Send + Sync>;
+
+/// How to handle shards that fail or time out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialFailurePolicy {
+    /// Fail the merged stream as soon as any shard fails
+    FailFast,
+    /// Drop failed shards and keep merging the rest
+    BestEffort,
+    /// Keep merging while at least this many shards are healthy
+    Quorum(usize),
+}
+
+/// A shard that was dropped from the merge
+#[derive(Debug, Clone)]
+pub struct ShardFailure {
+    /// Index of the shard in the endpoint list
+    pub shard: usize,
+    /// Base URL of the shard endpoint
+    pub endpoint: String,
+    /// Error that caused the failure
+    pub error: String,
+}
+
+/// Scatter/gather configuration
+#[derive(Debug, Clone)]
+pub struct ScatterConfig {
+    /// Maximum time to wait for each shard's next message (including the first)
+    pub shard_timeout: Duration,
+    /// Partial-failure policy
+    pub policy: PartialFailurePolicy,
+    /// Stop after this many merged messages (e.g. top-k)
+    pub limit: Option<usize>,
+    /// Per-shard buffer of messages read ahead of the merge
+    pub shard_buffer: usize,
+}
+
+impl Default for ScatterConfig {
+    fn default() -> Self {
+        Self {
+            shard_timeout: Duration::from_secs(10),
+            policy: PartialFailurePolicy::FailFast,
+            limit: None,
+            shard_buffer: 16,
+        }
+    }
+}
+
+/// Fans server-streaming calls out to shards and merges the results
+pub struct ScatterGather {
+    shards: Vec<Arc<QuillClient>>,
+    config: ScatterConfig,
+    comparator: MergeComparator,
+    listener: Option<ShardFailureListener>,
+}
+
+impl ScatterGather {
+    /// Create a scatter/gather client merging by `comparator`
+    pub fn new<F>(shards: Vec<QuillClient>, comparator: F) -> Self
+    where
+        F: Fn(&Bytes, &Bytes) -> Ordering + Send + Sync + 'static,
+    {
+        Self {
+            shards: shards.into_iter().map(Arc::new).collect(),
+            config: ScatterConfig::default(),
+            comparator: Arc::new(comparator),
+            listener: None,
+        }
+    }
+
+    /// Set the per-shard timeout
+    pub fn shard_timeout(mut self, timeout: Duration) -> Self {
+        self.config.shard_timeout = timeout;
+        self
+    }
+
+    /// Set the partial-failure policy
+    pub fn policy(mut self, policy: PartialFailurePolicy) -> Self {
+        self.config.policy = policy;
+        self
+    }
+
+    /// Stop after `limit` merged messages
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.config.limit = Some(limit);
+        self
+    }
+
+    /// Register a callback invoked when a shard is dropped
+    pub fn on_shard_failure<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ShardFailure) + Send + Sync + 'static,
+    {
+        self.listener = Some(Arc::new(f));
+        self
+    }
+
+    /// Send `request` to every shard and return the merged response stream
+    pub fn call(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+    ) -> Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>> {
+        self.call_with_options(service, method, request, RequestOptions::default())
+    }
+
+    /// Send `request` to every shard with per-request options
+    pub fn call_with_options(
+        &self,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        options: RequestOptions,
+    ) -> Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>> {
+        let receivers: Vec<_> = self
+            .shards
+            .iter()
+            .map(|client| {
+                spawn_shard(
+                    Arc::clone(client),
+                    service.to_string(),
+                    method.to_string(),
+                    request.clone(),
+                    options.clone(),
+                    self.config.shard_timeout,
+                    self.config.shard_buffer.max(1),
+                )
+            })
+            .collect();
+
+        let (tx, rx) = mpsc::channel(self.config.shard_buffer.max(1));
+        let merge = Merge {
+            endpoints: self.shards.iter().map(|c| c.base_url().to_string()).collect(),
+            receivers,
+            config: self.config.clone(),
+            comparator: Arc::clone(&self.comparator),
+            listener: self.listener.clone(),
+        };
+        tokio::spawn(merge.run(tx));
+
+        Box::pin(ReceiverStream::new(rx))
+    }
+}
+
+/// Reads one shard's stream into a channel, enforcing the shard timeout
+fn spawn_shard(
+    client: Arc<QuillClient>,
+    service: String,
+    method: String,
+    request: Bytes,
+    options: RequestOptions,
+    timeout: Duration,
+    buffer: usize,
+) -> mpsc::Receiver<Result<Bytes, QuillError>> {
+    let (tx, rx) = mpsc::channel(buffer);
+    tokio::spawn(async move {
+        let timed_out = || QuillError::Transport(format!("shard {} timed out", client.base_url()));
+
+        let call = client.call_server_streaming_with_options(&service, &method, request, options);
+        let mut stream = match tokio::time::timeout(timeout, call).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx.send(Err(timed_out())).await;
+                return;
+            }
+        };
+
+        loop {
+            let item = match tokio::time::timeout(timeout, stream.next()).await {
+                Ok(Some(item)) => item,
+                Ok(None) => return,
+                Err(_) => Err(timed_out()),
+            };
+            let failed = item.is_err();
+            if tx.send(item).await.is_err() || failed {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+/// K-way merge state
+struct Merge {
+    endpoints: Vec<String>,
+    receivers: Vec<mpsc::Receiver<Result<Bytes, QuillError>>>,
+    config: ScatterConfig,
+    comparator: MergeComparator,
+    listener: Option<ShardFailureListener>,
+}
+
+impl Merge {
+    async fn run(mut self, tx: mpsc::Sender<Result<Bytes, QuillError>>) {
+        let shards = self.receivers.len();
+        let mut heads: Vec<Option<Bytes>> = vec![None; shards];
+        let mut healthy = shards;
+
+        // Prime every shard's head
+        for shard in 0..shards {
+            if let Some(e) = self.advance(shard, &mut heads, &mut healthy).await {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        }
+
+        let mut emitted = 0;
+        while self.config.limit.map_or(true, |limit| emitted < limit) {
+            let next = (0..shards).filter(|&i| heads[i].is_some()).min_by(|&a, &b| {
+                (self.comparator)(heads[a].as_ref().unwrap(), heads[b].as_ref().unwrap())
+                    .then(a.cmp(&b))
+            });
+            let Some(shard) = next else {
+                return;
+            };
+
+            let message = heads[shard].take().unwrap();
+            if tx.send(Ok(message)).await.is_err() {
+                return;
+            }
+            emitted += 1;
+
+            if let Some(e) = self.advance(shard, &mut heads, &mut healthy).await {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        }
+    }
+
+    /// Fetch the next head for `shard`, returning an error if the policy
+    /// says the merged stream must fail
+    async fn advance(
+        &mut self,
+        shard: usize,
+        heads: &mut [Option<Bytes>],
+        healthy: &mut usize,
+    ) -> Option<QuillError> {
+        match self.receivers[shard].recv().await {
+            Some(Ok(message)) => {
+                heads[shard] = Some(message);
+                None
+            }
+            None => None,
+            Some(Err(error)) => {
+                *healthy -= 1;
+                tracing::warn!("Shard {} ({}) failed: {}", shard, self.endpoints[shard], error);
+                if let Some(listener) = &self.listener {
+                    listener(&ShardFailure {
+                        shard,
+                        endpoint: self.endpoints[shard].clone(),
+                        error: error.to_string(),
+                    });
+                }
+
+                match self.config.policy {
+                    PartialFailurePolicy::FailFast => Some(error),
+                    PartialFailurePolicy::BestEffort => None,
+                    PartialFailurePolicy::Quorum(required) if *healthy < required => {
+                        Some(QuillError::Rpc(format!(
+                            "Scatter quorum lost: {} of {} shards healthy, {} required (last error: {})",
+                            healthy,
+                            self.receivers.len(),
+                            required,
+                            error
+                        )))
+                    }
+                    PartialFailurePolicy::Quorum(_) => None,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_server::{QuillServer, RpcResponse, RpcRouter};
+    use std::sync::Mutex;
+
+    enum Behavior {
+        Values(Vec<u32>),
+        FailAfter(Vec<u32>),
+        Stall,
+    }
+
+    async fn spawn(behavior: Behavior) -> QuillClient {
+        let behavior = Arc::new(behavior);
+        let mut router = RpcRouter::new();
+        router.register("test.Search/Query", move |_req: Bytes| {
+            let behavior = Arc::clone(&behavior);
+            async move {
+                let (tx, rx) = mpsc::channel(1);
+                tokio::spawn(async move {
+                    let (values, fail) = match &*behavior {
+                        Behavior::Values(v) => (v.clone(), false),
+                        Behavior::FailAfter(v) => (v.clone(), true),
+                        Behavior::Stall => {
+                            tokio::time::sleep(Duration::from_secs(30)).await;
+                            return;
+                        }
+                    };
+                    for v in values {
+                        if tx.send(Ok(Bytes::copy_from_slice(&v.to_be_bytes()))).await.is_err() {
+                            return;
+                        }
+                    }
+                    if fail {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        let _ = tx.send(Err(QuillError::Transport("shard crashed".into()))).await;
+                    }
+                });
+                Ok(RpcResponse::streaming(ReceiverStream::new(rx)))
+            }
+        });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = QuillServer::new(router).serve_with_listener(listener).await;
+        });
+        QuillClient::new(format!("http://{}", addr))
+    }
+
+    fn ascending(shards: Vec<QuillClient>) -> ScatterGather {
+        ScatterGather::new(shards, |a, b| a.cmp(b)).shard_timeout(Duration::from_millis(500))
+    }
+
+    async fn collect(
+        mut stream: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
+    ) -> (Vec<u32>, Option<QuillError>) {
+        let mut values = Vec::new();
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(msg) => values.push(u32::from_be_bytes(msg[..4].try_into().unwrap())),
+                Err(e) => return (values, Some(e)),
+            }
+        }
+        (values, None)
+    }
+
+    #[tokio::test]
+    async fn test_merges_in_order() {
+        let shards = vec![
+            spawn(Behavior::Values(vec![1, 4, 7])).await,
+            spawn(Behavior::Values(vec![2, 5, 8])).await,
+            spawn(Behavior::Values(vec![3, 6, 9])).await,
+        ];
+
+        let (values, error) = collect(ascending(shards).call("test.Search", "Query", Bytes::new())).await;
+        assert!(error.is_none());
+        assert_eq!(values, (1..=9).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_limit() {
+        let shards = vec![
+            spawn(Behavior::Values(vec![10, 30])).await,
+            spawn(Behavior::Values(vec![20, 40])).await,
+        ];
+
+        let scatter = ascending(shards).limit(3);
+        let (values, _) = collect(scatter.call("test.Search", "Query", Bytes::new())).await;
+        assert_eq!(values, vec![10, 20, 30]);
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast() {
+        let shards = vec![
+            spawn(Behavior::Values(vec![1, 2, 3])).await,
+            spawn(Behavior::FailAfter(vec![])).await,
+        ];
+
+        let (_, error) = collect(ascending(shards).call("test.Search", "Query", Bytes::new())).await;
+        assert!(error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_best_effort_drops_failed_and_stalled_shards() {
+        let shards = vec![
+            spawn(Behavior::Values(vec![1, 3, 5])).await,
+            spawn(Behavior::FailAfter(vec![2])).await,
+            spawn(Behavior::Stall).await,
+        ];
+
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&failures);
+        let scatter = ascending(shards)
+            .policy(PartialFailurePolicy::BestEffort)
+            .on_shard_failure(move |f| recorded.lock().unwrap().push(f.shard));
+
+        let (values, error) = collect(scatter.call("test.Search", "Query", Bytes::new())).await;
+        assert!(error.is_none());
+        assert_eq!(values, vec![1, 2, 3, 5]);
+
+        let mut failed = failures.lock().unwrap().clone();
+        failed.sort();
+        assert_eq!(failed, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_quorum() {
+        let shards = vec![
+            spawn(Behavior::Values(vec![1])).await,
+            spawn(Behavior::FailAfter(vec![])).await,
+            spawn(Behavior::FailAfter(vec![])).await,
+        ];
+
+        let scatter = ascending(shards).policy(PartialFailurePolicy::Quorum(2));
+        let (_, error) = collect(scatter.call("test.Search", "Query", Bytes::new())).await;
+        assert!(matches!(error, Some(QuillError::Rpc(msg)) if msg.contains("quorum")));
+    }
+}
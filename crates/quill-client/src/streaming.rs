@@ -1,9 +1,18 @@
 //! Client-side streaming support
 
-use bytes::Bytes;
-use quill_core::{Frame, QuillError};
+use bytes::{Bytes, BytesMut};
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::Frame as HyperFrame;
+use crate::cancel::CancelToken;
+use quill_core::{Frame, FrameCipher, ProblemDetails, QuillError};
+use std::future::Future;
 use std::pin::Pin;
-use tokio_stream::Stream;
+use std::sync::Arc;
+use tokio_stream::{Stream, StreamExt};
+
+/// HTTP request body, either buffered or streamed
+pub(crate) type RequestBody = UnsyncBoxBody<Bytes, QuillError>;
 
 /// Request type that can be either unary or streaming
 pub enum RpcRequest {
@@ -28,26 +37,97 @@ impl RpcRequest {
     }
 }
 
+/// Request body sending `bytes` in one piece
+pub(crate) fn full_body(bytes: Bytes) -> RequestBody {
+    Full::new(bytes).map_err(|never| match never {}).boxed_unsync()
+}
+
+/// Request body sending `request` in one piece once it resolves
+///
+/// Headers go out before the body is ready. An error from `request` aborts
+/// the request.
+pub(crate) fn deferred_body(
+    request: Pin<Box<dyn Future<Output = Result<Bytes, QuillError>> + Send>>,
+) -> RequestBody {
+    StreamBody::new(futures_util::stream::once(request).map(|data| data.map(HyperFrame::data))).boxed_unsync()
+}
+
+/// Request body sending each message as a frame as soon as it is produced
+///
+/// END_STREAM follows the last message. An error from `stream` aborts the
+/// request instead of ending it cleanly. Once `cancel` fires, no further
+/// message is sent and a CANCEL frame ends the body instead. Frames are
+/// sealed with `cipher` if the stream is encrypted end to end.
+pub(crate) fn streaming_body(
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
+    cancel: Option<&CancelToken>,
+    cipher: Option<FrameCipher>,
+) -> RequestBody {
+    let cipher = cipher.map(Arc::new);
+    let sealer = cipher.clone();
+    let messages = stream.map(move |message| {
+        let frame = message.and_then(|data| seal_frame(Frame::data(data), sealer.as_deref()))?;
+        Ok(frame.encode())
+    });
+    let frames = match cancel {
+        Some(token) => {
+            let token = token.clone();
+            let messages = futures_util::StreamExt::take_until(messages, token.cancelled_owned());
+            let last = futures_util::stream::once(async move {
+                let frame = if token.is_cancelled() {
+                    Frame::cancel_with_problem(&ProblemDetails::cancelled())
+                } else {
+                    Frame::end_stream()
+                };
+                seal_frame(frame, cipher.as_deref()).map(|frame| frame.encode())
+            });
+            Box::pin(messages.chain(last)) as Pin<Box<dyn Stream<Item = _> + Send>>
+        }
+        None => {
+            // Sealed only once the messages are, so it takes the last nonce
+            let last = futures_util::stream::once(async move {
+                seal_frame(Frame::end_stream(), cipher.as_deref()).map(|frame| frame.encode())
+            });
+            Box::pin(messages.chain(last))
+        }
+    };
+    StreamBody::new(frames.map(|frame| frame.map(HyperFrame::data))).boxed_unsync()
+}
+
 /// Encode a stream of messages into frames
 pub async fn encode_request_stream(
-    mut stream: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
 ) -> Result<Bytes, QuillError> {
-    use tokio_stream::StreamExt;
+    encode_sealed_request_stream(stream, None).await
+}
 
-    let mut encoded = Vec::new();
+/// Encode a stream of messages into frames sealed with `cipher`, if given
+pub(crate) async fn encode_sealed_request_stream(
+    mut stream: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
+    cipher: Option<&FrameCipher>,
+) -> Result<Bytes, QuillError> {
+    let mut encoded = BytesMut::new();
 
     // Encode each message as a frame
     while let Some(result) = stream.next().await {
         let data = result?;
-        let frame = Frame::data(data);
-        encoded.extend_from_slice(&frame.encode());
+        let frame = seal_frame(Frame::data(data), cipher)?;
+        encoded.reserve(frame.encode_len());
+        frame.encode_to(&mut encoded);
     }
 
     // Add END_STREAM frame
-    let end_frame = Frame::end_stream();
-    encoded.extend_from_slice(&end_frame.encode());
+    seal_frame(Frame::end_stream(), cipher)?.encode_to(&mut encoded);
 
-    Ok(Bytes::from(encoded))
+    Ok(encoded.freeze())
+}
+
+/// Seal `frame` with `cipher`, if given
+fn seal_frame(frame: Frame, cipher: Option<&FrameCipher>) -> Result<Frame, QuillError> {
+    match cipher {
+        Some(cipher) => Ok(cipher.seal(frame)?),
+        None => Ok(frame),
+    }
 }
 
 #[cfg(test)]
@@ -67,4 +147,13 @@ mod tests {
         // Should have 2 data frames + 1 end frame
         assert!(encoded.len() > 0);
     }
+
+    #[tokio::test]
+    async fn test_streaming_body_matches_buffered_encoding() {
+        let messages = || iter(vec![Ok(Bytes::from("hello")), Ok(Bytes::from("world"))]);
+        let encoded = encode_request_stream(Box::pin(messages())).await.unwrap();
+
+        let streamed = streaming_body(Box::pin(messages()), None, None).collect().await.unwrap().to_bytes();
+        assert_eq!(streamed, encoded);
+    }
 }
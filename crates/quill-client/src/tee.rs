@@ -0,0 +1,284 @@
+//! Streaming response tee
+//!
+//! This module provides:
+//! - Splitting one response stream into several independent consumers
+//! - Per-consumer backpressure: slowest-wins or bounded lag with drop
+//! - Zero-copy fan-out: consumers share the same `Bytes` payloads
+//!
+//! A typical use is streaming generated tokens to the user while also
+//! writing them to an audit journal that must never slow the user down.
+
+use bytes::Bytes;
+use quill_core::QuillError;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::{Stream, StreamExt};
+
+type SourceStream = Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>;
+
+/// Backpressure policy for a tee consumer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeePolicy {
+    /// Slowest wins: the source waits until this consumer has buffer space
+    Block {
+        /// Messages buffered ahead of the consumer
+        buffer: usize,
+    },
+    /// Bounded lag: messages beyond `max_lag` unread are dropped for this consumer
+    BoundedLag {
+        /// Maximum number of unread messages
+        max_lag: usize,
+    },
+}
+
+/// Per-consumer state shared between the driver and the consumer stream
+#[derive(Default)]
+struct Shared {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    error: Mutex<Option<QuillError>>,
+}
+
+struct Output {
+    policy: TeePolicy,
+    tx: mpsc::Sender<Bytes>,
+    shared: Arc<Shared>,
+}
+
+/// Splits a response stream into multiple consumers
+pub struct StreamTee {
+    source: SourceStream,
+    outputs: Vec<Output>,
+}
+
+impl StreamTee {
+    /// Create a tee over a response stream
+    pub fn new(source: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>) -> Self {
+        Self {
+            source,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Add a consumer with the given backpressure policy
+    pub fn consumer(&mut self, policy: TeePolicy) -> TeeStream {
+        let capacity = match policy {
+            TeePolicy::Block { buffer } => buffer,
+            TeePolicy::BoundedLag { max_lag } => max_lag,
+        };
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        let shared = Arc::new(Shared::default());
+        self.outputs.push(Output {
+            policy,
+            tx,
+            shared: Arc::clone(&shared),
+        });
+        TeeStream {
+            rx,
+            shared,
+            finished: false,
+        }
+    }
+
+    /// Start forwarding the source to all consumers
+    ///
+    /// The driver stops when the source ends or every consumer has been dropped.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+
+    async fn run(mut self) {
+        while let Some(item) = self.source.next().await {
+            match item {
+                Ok(message) => {
+                    for output in &self.outputs {
+                        output.deliver(&message).await;
+                    }
+                    self.outputs.retain(|output| !output.tx.is_closed());
+                    if self.outputs.is_empty() {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    for output in &self.outputs {
+                        *output.shared.error.lock().unwrap() = Some(clone_error(&error));
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Output {
+    async fn deliver(&self, message: &Bytes) {
+        let sent = match self.policy {
+            TeePolicy::Block { .. } => self.tx.send(message.clone()).await.is_ok(),
+            TeePolicy::BoundedLag { .. } => match self.tx.try_send(message.clone()) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    false
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            },
+        };
+        if sent {
+            self.shared.delivered.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// One consumer's view of a teed stream
+pub struct TeeStream {
+    rx: mpsc::Receiver<Bytes>,
+    shared: Arc<Shared>,
+    finished: bool,
+}
+
+impl TeeStream {
+    /// Number of messages delivered to this consumer
+    pub fn delivered(&self) -> u64 {
+        self.shared.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages dropped because this consumer lagged
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Stream for TeeStream {
+    type Item = Result<Bytes, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+        match self.rx.poll_recv(cx) {
+            Poll::Ready(Some(message)) => Poll::Ready(Some(Ok(message))),
+            Poll::Ready(None) => {
+                // Buffered messages are drained; surface the source error, if any
+                self.finished = true;
+                Poll::Ready(self.shared.error.lock().unwrap().take().map(Err))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+pub(crate) fn clone_error(error: &QuillError) -> QuillError {
+    match error {
+        QuillError::Rpc(msg) => QuillError::Rpc(msg.clone()),
+        QuillError::Transport(msg) => QuillError::Transport(msg.clone()),
+        QuillError::Framing(msg) => QuillError::Framing(msg.clone()),
+        QuillError::ProblemDetails(pd) => QuillError::ProblemDetails(pd.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn source(n: u32) -> SourceStream {
+        Box::pin(tokio_stream::iter((0..n).map(|i| Ok(Bytes::from(i.to_string())))))
+    }
+
+    #[tokio::test]
+    async fn test_all_consumers_receive_same_payloads() {
+        let mut tee = StreamTee::new(source(5));
+        let mut a = tee.consumer(TeePolicy::Block { buffer: 2 });
+        let mut b = tee.consumer(TeePolicy::Block { buffer: 2 });
+        tee.spawn();
+
+        let (mut got_a, mut got_b) = (Vec::new(), Vec::new());
+        loop {
+            tokio::select! {
+                Some(m) = a.next() => got_a.push(m.unwrap()),
+                Some(m) = b.next() => got_b.push(m.unwrap()),
+                else => break,
+            }
+        }
+        assert_eq!(got_a.len(), 5);
+        assert_eq!(got_a, got_b);
+        // Payloads are shared, not copied
+        assert_eq!(got_a[0].as_ptr(), got_b[0].as_ptr());
+    }
+
+    #[tokio::test]
+    async fn test_bounded_lag_drops_for_slow_consumer_only() {
+        let mut tee = StreamTee::new(source(100));
+        let fast = tee.consumer(TeePolicy::Block { buffer: 1 });
+        let mut slow = tee.consumer(TeePolicy::BoundedLag { max_lag: 4 });
+        let driver = tee.spawn();
+
+        let fast_messages: Vec<_> = fast.collect().await;
+        assert_eq!(fast_messages.len(), 100);
+        driver.await.unwrap();
+
+        let mut slow_messages = Vec::new();
+        while let Some(m) = slow.next().await {
+            slow_messages.push(m.unwrap());
+        }
+        assert_eq!(slow_messages.len(), 4);
+        assert_eq!(slow.delivered(), 4);
+        assert_eq!(slow.dropped(), 96);
+    }
+
+    #[tokio::test]
+    async fn test_slowest_wins_applies_backpressure() {
+        let mut tee = StreamTee::new(source(10));
+        let mut slow = tee.consumer(TeePolicy::Block { buffer: 1 });
+        let mut fast = tee.consumer(TeePolicy::Block { buffer: 16 });
+        tee.spawn();
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        // The fast consumer cannot run ahead of the slow one's buffer
+        assert!(fast.delivered() <= 2);
+
+        let mut count = 0;
+        while let Some(m) = slow.next().await {
+            m.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, 10);
+        while fast.next().await.is_some() {}
+        assert_eq!(fast.delivered(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_error_reaches_every_consumer_after_buffered_messages() {
+        let items = vec![
+            Ok(Bytes::from_static(b"a")),
+            Err(QuillError::Transport("connection reset".to_string())),
+        ];
+        let mut tee = StreamTee::new(Box::pin(tokio_stream::iter(items)));
+        let a = tee.consumer(TeePolicy::Block { buffer: 4 });
+        let b = tee.consumer(TeePolicy::BoundedLag { max_lag: 4 });
+        tee.spawn().await.unwrap();
+
+        for consumer in [a, b] {
+            let items: Vec<_> = consumer.collect().await;
+            assert_eq!(items.len(), 2);
+            assert!(items[0].is_ok());
+            assert!(matches!(items[1], Err(QuillError::Transport(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropping_all_consumers_stops_driver() {
+        let (tx, rx) = mpsc::channel::<Result<Bytes, QuillError>>(1);
+        let mut tee = StreamTee::new(Box::pin(tokio_stream::wrappers::ReceiverStream::new(rx)));
+        let consumer = tee.consumer(TeePolicy::Block { buffer: 1 });
+        let driver = tee.spawn();
+
+        drop(consumer);
+        tx.send(Ok(Bytes::from_static(b"x"))).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), driver).await.unwrap().unwrap();
+    }
+}
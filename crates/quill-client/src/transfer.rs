@@ -0,0 +1,426 @@
+//! Progress-reporting handles for large transfers
+//!
+//! This module provides:
+//! - [`TransferHandle`], a future for an upload or download that can report
+//!   progress and be paused, resumed or cancelled
+//! - [`TransferProgress`] snapshots with byte counts, rate and ETA
+//! - `QuillClient::upload` and `QuillClient::download`
+//!
+//! Uploads report progress per chunk, so they are split with
+//! [`ChunkedUpload`](crate::ChunkedUpload) when the server accepts chunked
+//! uploads; otherwise the request is sent whole and progress jumps to done
+//! when the server answers. Downloads are server-streaming calls whose
+//! messages are concatenated, reporting progress per message. Pausing takes
+//! effect between chunks or messages; while a download is paused the
+//! response is not read, so flow control holds back the sender.
+
+use crate::client::{QuillClient, RequestOptions};
+use crate::upload::ChunkedUpload;
+use bytes::{Bytes, BytesMut};
+use quill_core::QuillError;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+/// Lifecycle state of a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferState {
+    /// Bytes are moving
+    Running,
+    /// Held between chunks or messages until resumed
+    Paused,
+    /// Finished successfully
+    Completed,
+    /// Finished with an error
+    Failed,
+    /// Cancelled by the caller
+    Cancelled,
+}
+
+impl TransferState {
+    /// Whether the transfer has finished
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Snapshot of a transfer's progress
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferProgress {
+    /// Bytes sent or received so far
+    pub bytes_transferred: u64,
+    /// Total size, if known
+    pub total_bytes: Option<u64>,
+    /// Time spent transferring, excluding pauses
+    pub elapsed: Duration,
+    /// Average rate while not paused
+    pub bytes_per_second: f64,
+    /// Estimated time to completion at the current rate, if the total is known
+    pub eta: Option<Duration>,
+    /// Current state
+    pub state: TransferState,
+}
+
+impl TransferProgress {
+    /// Completed fraction in `[0, 1]`, if the total is known
+    pub fn fraction(&self) -> Option<f64> {
+        self.total_bytes.map(|total| match total {
+            0 => 1.0,
+            total => (self.bytes_transferred as f64 / total as f64).min(1.0),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Counters {
+    bytes: u64,
+    total: Option<u64>,
+    started: Instant,
+    paused_since: Option<Instant>,
+    paused_for: Duration,
+    finished_at: Option<Instant>,
+}
+
+impl Counters {
+    fn active_elapsed(&self) -> Duration {
+        let end = self.finished_at.or(self.paused_since).unwrap_or_else(Instant::now);
+        end.duration_since(self.started).saturating_sub(self.paused_for)
+    }
+}
+
+/// State shared between a transfer task and its handle
+#[derive(Debug)]
+pub(crate) struct TransferControl {
+    state: watch::Sender<TransferState>,
+    counters: Mutex<Counters>,
+}
+
+impl TransferControl {
+    pub(crate) fn new(total: Option<u64>) -> Self {
+        Self {
+            state: watch::channel(TransferState::Running).0,
+            counters: Mutex::new(Counters {
+                bytes: 0,
+                total,
+                started: Instant::now(),
+                paused_since: None,
+                paused_for: Duration::ZERO,
+                finished_at: None,
+            }),
+        }
+    }
+
+    /// Record the total size once it is known
+    pub(crate) fn set_total(&self, total: u64) {
+        self.counters.lock().unwrap().total = Some(total);
+    }
+
+    /// Record bytes sent or received
+    pub(crate) fn advance(&self, bytes: u64) {
+        self.counters.lock().unwrap().bytes += bytes;
+    }
+
+    /// Wait while paused; fails once the transfer is cancelled
+    pub(crate) async fn checkpoint(&self) -> Result<(), QuillError> {
+        let mut state = self.state.subscribe();
+        loop {
+            match *state.borrow_and_update() {
+                TransferState::Paused => {}
+                TransferState::Cancelled => return Err(cancelled()),
+                _ => return Ok(()),
+            }
+            if state.changed().await.is_err() {
+                return Err(cancelled());
+            }
+        }
+    }
+
+    fn state(&self) -> TransferState {
+        *self.state.borrow()
+    }
+
+    /// Move from `from` to `to`; returns false if the transfer was in another state
+    fn transition(&self, from: TransferState, to: TransferState) -> bool {
+        let changed = self.state.send_if_modified(|state| {
+            if *state != from {
+                return false;
+            }
+            *state = to;
+            true
+        });
+        if changed {
+            let now = Instant::now();
+            let mut counters = self.counters.lock().unwrap();
+            if from == TransferState::Paused {
+                if let Some(since) = counters.paused_since.take() {
+                    counters.paused_for += now.duration_since(since);
+                }
+            }
+            match to {
+                TransferState::Paused => counters.paused_since = Some(now),
+                state if state.is_finished() => counters.finished_at = Some(now),
+                _ => {}
+            }
+        }
+        changed
+    }
+
+    fn finish(&self, result: &Result<impl Sized, QuillError>) {
+        let to = match result {
+            Ok(_) => TransferState::Completed,
+            Err(_) => TransferState::Failed,
+        };
+        if !self.transition(TransferState::Running, to) {
+            self.transition(TransferState::Paused, to);
+        }
+    }
+
+    fn progress(&self) -> TransferProgress {
+        let state = self.state();
+        let counters = self.counters.lock().unwrap();
+        let elapsed = counters.active_elapsed();
+        let bytes_per_second = match elapsed.as_secs_f64() {
+            secs if secs > 0.0 => counters.bytes as f64 / secs,
+            _ => 0.0,
+        };
+        let eta = match (counters.total, state) {
+            (_, state) if state.is_finished() => None,
+            (Some(total), _) if bytes_per_second > 0.0 => Some(Duration::from_secs_f64(
+                total.saturating_sub(counters.bytes) as f64 / bytes_per_second,
+            )),
+            _ => None,
+        };
+        TransferProgress {
+            bytes_transferred: counters.bytes,
+            total_bytes: counters.total,
+            elapsed,
+            bytes_per_second,
+            eta,
+            state,
+        }
+    }
+}
+
+fn cancelled() -> QuillError {
+    QuillError::Rpc("Transfer cancelled".to_string())
+}
+
+/// A running upload or download
+///
+/// Await the handle for the result. Dropping it does not stop the
+/// transfer; call [`cancel`](Self::cancel) for that.
+#[derive(Debug)]
+pub struct TransferHandle<T> {
+    control: Arc<TransferControl>,
+    task: JoinHandle<Result<T, QuillError>>,
+}
+
+impl<T: Send + 'static> TransferHandle<T> {
+    /// Run `transfer` in the background
+    pub(crate) fn spawn<F, Fut>(total: Option<u64>, transfer: F) -> Self
+    where
+        F: FnOnce(Arc<TransferControl>) -> Fut,
+        Fut: Future<Output = Result<T, QuillError>> + Send + 'static,
+    {
+        let control = Arc::new(TransferControl::new(total));
+        let running = transfer(Arc::clone(&control));
+        let finished = Arc::clone(&control);
+        let task = tokio::spawn(async move {
+            let result = running.await;
+            finished.finish(&result);
+            result
+        });
+        Self { control, task }
+    }
+}
+
+impl<T> TransferHandle<T> {
+    /// Current progress
+    pub fn progress(&self) -> TransferProgress {
+        self.control.progress()
+    }
+
+    /// Current state
+    pub fn state(&self) -> TransferState {
+        self.control.state()
+    }
+
+    /// Hold the transfer after the chunk or message in flight
+    ///
+    /// Returns false if the transfer is not running.
+    pub fn pause(&self) -> bool {
+        self.control.transition(TransferState::Running, TransferState::Paused)
+    }
+
+    /// Continue a paused transfer
+    ///
+    /// Returns false if the transfer is not paused.
+    pub fn resume(&self) -> bool {
+        self.control.transition(TransferState::Paused, TransferState::Running)
+    }
+
+    /// Stop the transfer, dropping any request or response in flight
+    ///
+    /// Returns false if the transfer had already finished.
+    pub fn cancel(&self) -> bool {
+        let cancelled = self.control.transition(TransferState::Running, TransferState::Cancelled)
+            || self.control.transition(TransferState::Paused, TransferState::Cancelled);
+        if cancelled {
+            self.task.abort();
+        }
+        cancelled
+    }
+}
+
+impl<T> Future for TransferHandle<T> {
+    type Output = Result<T, QuillError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let joined = match Pin::new(&mut self.task).poll(cx) {
+            Poll::Ready(joined) => joined,
+            Poll::Pending => return Poll::Pending,
+        };
+        Poll::Ready(match joined {
+            Ok(result) => result,
+            Err(e) if e.is_cancelled() => Err(cancelled()),
+            Err(e) => Err(QuillError::Rpc(format!("Transfer task failed: {}", e))),
+        })
+    }
+}
+
+impl QuillClient {
+    /// Upload a large unary request, reporting progress per chunk
+    ///
+    /// Uses the default [`ChunkedUpload`] options unless `options` sets its
+    /// own.
+    pub fn upload(
+        self: &Arc<Self>,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        options: RequestOptions,
+    ) -> TransferHandle<Bytes> {
+        let client = Arc::clone(self);
+        let (service, method) = (service.to_string(), method.to_string());
+        let options = match options.chunked_upload {
+            Some(_) => options,
+            None => options.chunked_upload(ChunkedUpload::new()),
+        };
+        TransferHandle::spawn(Some(request.len() as u64), move |control| async move {
+            client
+                .call_tracked(&service, &method, request, options, Some(&control))
+                .await
+        })
+    }
+
+    /// Download a server-streaming response, concatenating its messages
+    ///
+    /// `expected_size` enables the completed fraction and ETA.
+    pub fn download(
+        self: &Arc<Self>,
+        service: &str,
+        method: &str,
+        request: Bytes,
+        options: RequestOptions,
+        expected_size: Option<u64>,
+    ) -> TransferHandle<Bytes> {
+        let client = Arc::clone(self);
+        let (service, method) = (service.to_string(), method.to_string());
+        TransferHandle::spawn(expected_size, move |control| async move {
+            control.checkpoint().await?;
+            let mut stream = client
+                .call_server_streaming_with_options(&service, &method, request, options)
+                .await?;
+            let mut body = BytesMut::new();
+            loop {
+                control.checkpoint().await?;
+                match stream.next().await {
+                    Some(message) => {
+                        let message = message?;
+                        control.advance(message.len() as u64);
+                        body.extend_from_slice(&message);
+                    }
+                    None => return Ok(body.freeze()),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_progress_and_completion() {
+        let handle = TransferHandle::spawn(Some(300), |control| async move {
+            for _ in 0..3 {
+                control.checkpoint().await?;
+                control.advance(100);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            Ok(42)
+        });
+
+        let progress = handle.progress();
+        assert_eq!(progress.total_bytes, Some(300));
+        assert_eq!(progress.state, TransferState::Running);
+
+        let control = Arc::clone(&handle.control);
+        assert_eq!(handle.await.unwrap(), 42);
+
+        let progress = control.progress();
+        assert_eq!(progress.bytes_transferred, 300);
+        assert_eq!(progress.fraction(), Some(1.0));
+        assert_eq!(progress.state, TransferState::Completed);
+        assert!(progress.bytes_per_second > 0.0);
+        assert_eq!(progress.eta, None);
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume() {
+        let (step_tx, mut step_rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+        let handle = TransferHandle::spawn(Some(200), move |control| async move {
+            for _ in 0..2 {
+                control.checkpoint().await?;
+                control.advance(100);
+                let _ = step_tx.send(());
+            }
+            Ok(())
+        });
+
+        assert!(handle.pause());
+        assert!(!handle.pause());
+        // The task may have finished its first step before the pause took hold
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(handle.state(), TransferState::Paused);
+        assert!(handle.progress().bytes_transferred < 200);
+
+        assert!(handle.resume());
+        handle.await.unwrap();
+        assert!(step_rx.recv().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let handle = TransferHandle::<()>::spawn(None, |control| async move {
+            loop {
+                control.checkpoint().await?;
+                control.advance(1);
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(handle.cancel());
+        assert!(!handle.cancel());
+        assert_eq!(handle.state(), TransferState::Cancelled);
+        assert!(handle.progress().eta.is_none());
+        assert!(matches!(handle.await, Err(QuillError::Rpc(msg)) if msg == "Transfer cancelled"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Unix domain socket transport
+//!
+//! This module provides:
+//! - A connector dialing either TCP or a Unix domain socket
+//! - The connection type shared by both transports
+//!
+//! With [`ClientBuilder::uds_path`], every connection goes to the socket
+//! instead of the host in the base URL, which then only supplies the
+//! `:authority` and path prefix of requests. Useful for sidecars, e.g. a
+//! gateway and an inference server on the same host: no TCP overhead, and
+//! access is governed by the socket's file permissions. Connections speak
+//! HTTP/2 unless HTTP/1.1 is forced, so every streaming mode is available.
+//!
+//! With the `test-util` feature, [`ClientBuilder::sim_network`] dials a
+//! simulated network instead (see [`quill_transport::sim`]).
+//!
+//! [`ClientBuilder::uds_path`]: crate::client::ClientBuilder::uds_path
+//! [`ClientBuilder::sim_network`]: crate::client::ClientBuilder::sim_network
+
+use http::Uri;
+use hyper::rt::{Read, ReadBufCursor, Write};
+use hyper_util::client::legacy::connect::{Connected, Connection, HttpConnector};
+use hyper_util::rt::TokioIo;
+#[cfg(feature = "test-util")]
+use quill_transport::sim::{SimNetwork, SimStream};
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Dials the host of each request, or the configured socket
+#[derive(Clone)]
+pub(crate) struct Connector {
+    http: HttpConnector,
+    uds_path: Option<Arc<PathBuf>>,
+    #[cfg(feature = "test-util")]
+    sim: Option<SimNetwork>,
+}
+
+impl Connector {
+    pub(crate) fn new(uds_path: Option<PathBuf>) -> Self {
+        Self {
+            http: HttpConnector::new(),
+            uds_path: uds_path.map(Arc::new),
+            #[cfg(feature = "test-util")]
+            sim: None,
+        }
+    }
+
+    /// Dial `network` instead of TCP or a socket
+    #[cfg(feature = "test-util")]
+    pub(crate) fn sim(mut self, network: Option<SimNetwork>) -> Self {
+        self.sim = network;
+        self
+    }
+}
+
+impl tower::Service<Uri> for Connector {
+    type Response = Stream;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Stream, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        #[cfg(feature = "test-util")]
+        if let Some(network) = &self.sim {
+            let network = network.clone();
+            return Box::pin(async move { connect_sim(&network, &uri).await });
+        }
+        match &self.uds_path {
+            Some(path) => {
+                let path = Arc::clone(path);
+                Box::pin(async move { connect_uds(&path).await })
+            }
+            None => {
+                let connecting = self.http.call(uri);
+                Box::pin(async move { Ok(Stream::Tcp(connecting.await?)) })
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn connect_uds(path: &Path) -> Result<Stream, BoxError> {
+    let stream = UnixStream::connect(path)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", path.display(), e))?;
+    Ok(Stream::Unix(TokioIo::new(stream)))
+}
+
+#[cfg(not(unix))]
+async fn connect_uds(_path: &Path) -> Result<Stream, BoxError> {
+    Err("Unix domain sockets are not supported on this platform".into())
+}
+
+/// Connect to the listener named by `uri`'s host and port
+#[cfg(feature = "test-util")]
+async fn connect_sim(network: &SimNetwork, uri: &Uri) -> Result<Stream, BoxError> {
+    let host = uri.host().ok_or("URI has no host")?;
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("https") { 443 } else { 80 });
+    let addr = format!("{}:{}", host, port);
+    let stream = network
+        .connect(&addr)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+    Ok(Stream::Sim(TokioIo::new(stream)))
+}
+
+/// Connection over TCP, a Unix domain socket or a simulated network
+pub(crate) enum Stream {
+    Tcp(TokioIo<TcpStream>),
+    #[cfg(unix)]
+    Unix(TokioIo<UnixStream>),
+    #[cfg(feature = "test-util")]
+    Sim(TokioIo<SimStream>),
+}
+
+impl Connection for Stream {
+    fn connected(&self) -> Connected {
+        match self {
+            Stream::Tcp(stream) => stream.connected(),
+            #[cfg(unix)]
+            Stream::Unix(_) => Connected::new(),
+            #[cfg(feature = "test-util")]
+            Stream::Sim(_) => Connected::new(),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: ReadBufCursor<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "test-util")]
+            Stream::Sim(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "test-util")]
+            Stream::Sim(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "test-util")]
+            Stream::Sim(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "test-util")]
+            Stream::Sim(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Stream::Tcp(stream) => stream.is_write_vectored(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.is_write_vectored(),
+            #[cfg(feature = "test-util")]
+            Stream::Sim(stream) => stream.is_write_vectored(),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Stream::Tcp(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            #[cfg(feature = "test-util")]
+            Stream::Sim(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+}
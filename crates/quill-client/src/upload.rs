@@ -0,0 +1,147 @@
+//! Chunked upload of large unary requests
+//!
+//! This module provides:
+//! - Per-call options for splitting a large request across parallel streams
+//! - Tracking of whether the server accepts chunked uploads
+//!
+//! Splitting is opt-in per call via [`RequestOptions::chunked_upload`] and
+//! only happens once the server has advertised support; otherwise the
+//! request is sent whole. On high bandwidth-delay links several streams
+//! fill the pipe faster than one.
+//!
+//! [`RequestOptions::chunked_upload`]: crate::RequestOptions::chunked_upload
+
+use http::HeaderMap;
+use quill_core::{UploadCapability, UploadManifest, UPLOAD_CAPABILITY_HEADER};
+use std::sync::Mutex;
+
+/// Default chunk size (1 MiB)
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Default number of chunks in flight at once
+pub const DEFAULT_UPLOAD_PARALLELISM: usize = 4;
+
+/// Options for splitting a large unary request into chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkedUpload {
+    /// Size of each chunk; requests no larger than this are sent whole
+    pub chunk_size: usize,
+    /// Number of chunks in flight at once
+    pub parallelism: usize,
+}
+
+impl Default for ChunkedUpload {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            parallelism: DEFAULT_UPLOAD_PARALLELISM,
+        }
+    }
+}
+
+impl ChunkedUpload {
+    /// Create options with the default chunk size and parallelism
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the chunk size
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set the number of chunks in flight at once
+    pub fn parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Split `request` if it is large enough and the server accepts the result
+    pub(crate) fn plan(
+        &self,
+        request: &bytes::Bytes,
+        capability: Option<UploadCapability>,
+    ) -> Option<(UploadManifest, Vec<bytes::Bytes>)> {
+        let capability = capability?;
+        if request.len() <= self.chunk_size {
+            return None;
+        }
+        let upload_id = format!("{:032x}", rand::random::<u128>());
+        let (manifest, chunks) = UploadManifest::split(upload_id, request, self.chunk_size);
+        capability.allows(&manifest).then_some((manifest, chunks))
+    }
+}
+
+/// What is known about a server's chunked upload support
+#[derive(Debug, Default)]
+pub(crate) struct UploadNegotiation {
+    /// `None` until a response from the server has been seen
+    state: Mutex<Option<Option<UploadCapability>>>,
+}
+
+impl UploadNegotiation {
+    /// Capability learned so far; `None` if nothing is known yet
+    pub(crate) fn known(&self) -> Option<Option<UploadCapability>> {
+        *self.state.lock().unwrap()
+    }
+
+    /// Learn the capability from a server response
+    pub(crate) fn record(&self, headers: &HeaderMap) -> Option<UploadCapability> {
+        let capability = headers
+            .get(UPLOAD_CAPABILITY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(UploadCapability::from_header_value);
+        *self.state.lock().unwrap() = Some(capability);
+        capability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use http::HeaderValue;
+
+    #[test]
+    fn test_plan_requires_capability_and_size() {
+        let upload = ChunkedUpload::new().chunk_size(4);
+        let capability = UploadCapability {
+            max_size: 1024,
+            max_chunks: 8,
+        };
+        let small = Bytes::from_static(b"abc");
+        let large = Bytes::from_static(b"abcdefghij");
+
+        assert!(upload.plan(&large, None).is_none());
+        assert!(upload.plan(&small, Some(capability)).is_none());
+
+        let (manifest, chunks) = upload.plan(&large, Some(capability)).unwrap();
+        assert_eq!(manifest.chunk_count, 3);
+        assert_eq!(chunks.len(), 3);
+
+        let tight = UploadCapability {
+            max_size: 1024,
+            max_chunks: 2,
+        };
+        assert!(upload.plan(&large, Some(tight)).is_none());
+    }
+
+    #[test]
+    fn test_negotiation_records_capability() {
+        let negotiation = UploadNegotiation::default();
+        assert_eq!(negotiation.known(), None);
+
+        negotiation.record(&HeaderMap::new());
+        assert_eq!(negotiation.known(), Some(None));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            UPLOAD_CAPABILITY_HEADER,
+            HeaderValue::from_static("chunked; max-size=100; max-chunks=4"),
+        );
+        let capability = negotiation.record(&headers).unwrap();
+        assert_eq!(capability.max_chunks, 4);
+        assert_eq!(negotiation.known(), Some(Some(capability)));
+    }
+}
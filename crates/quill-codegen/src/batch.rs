@@ -0,0 +1,310 @@
+//! Keyed batch get code generation
+//!
+//! For RPCs using the `KeyedBatchGet` pattern, this module generates a
+//! client helper that coalesces single-key `get(key)` calls into batched
+//! RPCs and splits the responses back per key, caching values for the
+//! RPC's `cache_ttl_ms`.
+//!
+//! prost does not expose custom method options to service generators, so
+//! annotated methods are registered on [`QuillConfig`] with
+//! [`QuillConfig::with_batch_get`], typically via [`BatchGetMethod::from_options`].
+
+use crate::QuillConfig;
+use heck::ToSnakeCase;
+use prost_build::{Method, Service};
+use quill_proto::{KeyedBatchGet, RpcOptions};
+use quote::{format_ident, quote};
+
+/// A method using the keyed batch get pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchGetMethod {
+    /// Service name (without package)
+    pub service: String,
+    /// Method name
+    pub method: String,
+    /// Message type of each returned value
+    pub value_type: String,
+    /// Repeated request field holding the keys
+    pub keys_field: String,
+    /// Repeated response field holding the values
+    pub values_field: String,
+    /// Field of each value carrying its key
+    pub key_field: String,
+    /// Rust type of the key
+    pub key_type: String,
+    /// Collection window in milliseconds
+    pub window_ms: u64,
+    /// Maximum number of keys per batch
+    pub max_batch: usize,
+    /// Per-key cache TTL in milliseconds (0 = no caching)
+    pub cache_ttl_ms: u64,
+}
+
+impl BatchGetMethod {
+    /// Create a batch get method with default field names
+    pub fn new(
+        service: impl Into<String>,
+        method: impl Into<String>,
+        value_type: impl Into<String>,
+    ) -> Self {
+        Self {
+            service: service.into(),
+            method: method.into(),
+            value_type: value_type.into(),
+            keys_field: "keys".to_string(),
+            values_field: "values".to_string(),
+            key_field: "key".to_string(),
+            key_type: "String".to_string(),
+            window_ms: 2,
+            max_batch: 128,
+            cache_ttl_ms: 0,
+        }
+    }
+
+    /// Build from `(quill.rpc)` options; `None` if the RPC has no `batch_get`
+    pub fn from_options(
+        service: impl Into<String>,
+        method: impl Into<String>,
+        opts: &RpcOptions,
+    ) -> Option<Self> {
+        let batch: &KeyedBatchGet = opts.batch_get.as_ref()?;
+        let mut m = Self::new(service, method, batch.value_type.clone());
+        if let Some(field) = &batch.keys_field {
+            m.keys_field = field.clone();
+        }
+        if let Some(field) = &batch.values_field {
+            m.values_field = field.clone();
+        }
+        if let Some(field) = &batch.key_field {
+            m.key_field = field.clone();
+        }
+        if let Some(ty) = &batch.key_type {
+            m.key_type = ty.clone();
+        }
+        if let Some(window) = batch.window_ms {
+            m.window_ms = window as u64;
+        }
+        if let Some(max) = batch.max_batch {
+            m.max_batch = max as usize;
+        }
+        m.cache_ttl_ms = opts.cache_ttl_ms.unwrap_or(0).max(0) as u64;
+        Some(m)
+    }
+
+    /// Set the request keys field
+    pub fn keys_field(mut self, field: impl Into<String>) -> Self {
+        self.keys_field = field.into();
+        self
+    }
+
+    /// Set the response values field
+    pub fn values_field(mut self, field: impl Into<String>) -> Self {
+        self.values_field = field.into();
+        self
+    }
+
+    /// Set the field of each value carrying its key
+    pub fn key_field(mut self, field: impl Into<String>) -> Self {
+        self.key_field = field.into();
+        self
+    }
+
+    /// Set the Rust key type
+    pub fn key_type(mut self, ty: impl Into<String>) -> Self {
+        self.key_type = ty.into();
+        self
+    }
+
+    /// Set the collection window
+    pub fn window_ms(mut self, window_ms: u64) -> Self {
+        self.window_ms = window_ms;
+        self
+    }
+
+    /// Set the maximum batch size
+    pub fn max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch;
+        self
+    }
+
+    /// Set the per-key cache TTL
+    pub fn cache_ttl_ms(mut self, cache_ttl_ms: u64) -> Self {
+        self.cache_ttl_ms = cache_ttl_ms;
+        self
+    }
+
+    fn matches(&self, service: &Service, method: &Method) -> bool {
+        self.service == service.name && self.method == method.name
+    }
+}
+
+/// Generate batch get helpers for a service's annotated unary methods
+pub fn generate_batchers(service: &Service, config: &QuillConfig) -> proc_macro2::TokenStream {
+    let mut batchers = proc_macro2::TokenStream::new();
+
+    for method in &service.methods {
+        if method.client_streaming || method.server_streaming {
+            continue;
+        }
+        if let Some(batch) = config.batch_get_methods.iter().find(|b| b.matches(service, method)) {
+            batchers.extend(generate_batcher(service, method, batch));
+        }
+    }
+
+    batchers
+}
+
+fn generate_batcher(
+    service: &Service,
+    method: &Method,
+    batch: &BatchGetMethod,
+) -> proc_macro2::TokenStream {
+    let batcher_name = format_ident!("{}Batcher", method.name);
+    let input_type: proc_macro2::TokenStream = format!("super::{}", method.input_type).parse().unwrap();
+    let output_type: proc_macro2::TokenStream = format!("super::{}", method.output_type).parse().unwrap();
+    let value_type: proc_macro2::TokenStream = format!("super::{}", batch.value_type).parse().unwrap();
+    let key_type: proc_macro2::TokenStream = batch.key_type.parse().unwrap();
+    let keys_field = format_ident!("{}", batch.keys_field.to_snake_case());
+    let values_field = format_ident!("{}", batch.values_field.to_snake_case());
+    let key_field = format_ident!("{}", batch.key_field.to_snake_case());
+
+    let service_name = &service.name;
+    let rpc_method = &method.name;
+    let window_ms = batch.window_ms;
+    let max_batch = batch.max_batch;
+    let cache_ttl_ms = batch.cache_ttl_ms;
+
+    quote! {
+        /// Batched get helper for #rpc_method
+        ///
+        /// Coalesces concurrent `get` calls into batched RPCs.
+        #[derive(Clone)]
+        pub struct #batcher_name {
+            batcher: quill_client::KeyedBatcher<#key_type, #value_type>,
+        }
+
+        impl #batcher_name {
+            /// Create a batcher with the settings from the proto annotation
+            pub fn new(client: std::sync::Arc<QuillClient>) -> Self {
+                Self::with_config(client, Self::default_config())
+            }
+
+            /// Batch settings from the proto annotation
+            pub fn default_config() -> quill_client::BatchConfig {
+                quill_client::BatchConfig::default()
+                    .window(std::time::Duration::from_millis(#window_ms))
+                    .max_batch(#max_batch)
+                    .cache_ttl_ms(#cache_ttl_ms)
+            }
+
+            /// Create a batcher with custom settings
+            pub fn with_config(
+                client: std::sync::Arc<QuillClient>,
+                config: quill_client::BatchConfig,
+            ) -> Self {
+                let batcher = quill_client::KeyedBatcher::new(config, move |keys: Vec<#key_type>| {
+                    let client = std::sync::Arc::clone(&client);
+                    async move {
+                        let request = #input_type {
+                            #keys_field: keys,
+                            ..Default::default()
+                        };
+                        let response_bytes = client.call(
+                            #service_name,
+                            #rpc_method,
+                            Bytes::from(request.encode_to_vec()),
+                        ).await?;
+                        let response = #output_type::decode(&response_bytes[..])
+                            .map_err(|e| QuillError::Rpc(format!("Failed to decode response: {}", e)))?;
+                        Ok(response
+                            .#values_field
+                            .into_iter()
+                            .map(|value| (value.#key_field.clone(), value))
+                            .collect())
+                    }
+                });
+                Self { batcher }
+            }
+
+            /// Get the value for a single key, batched with concurrent calls
+            pub async fn get(&self, key: #key_type) -> Result<Option<#value_type>, QuillError> {
+                self.batcher.get(key).await
+            }
+
+            /// Drop a key from the cache
+            pub fn invalidate(&self, key: &#key_type) {
+                self.batcher.invalidate(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_service() -> Service {
+        Service {
+            name: "FeatureStore".to_string(),
+            proto_name: "FeatureStore".to_string(),
+            package: "features.v1".to_string(),
+            comments: Default::default(),
+            options: Default::default(),
+            methods: vec![Method {
+                name: "BatchGetFeatures".to_string(),
+                proto_name: "BatchGetFeatures".to_string(),
+                comments: Default::default(),
+                input_type: "BatchGetFeaturesRequest".to_string(),
+                output_type: "BatchGetFeaturesResponse".to_string(),
+                input_proto_type: "BatchGetFeaturesRequest".to_string(),
+                output_proto_type: "BatchGetFeaturesResponse".to_string(),
+                options: Default::default(),
+                client_streaming: false,
+                server_streaming: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_from_options() {
+        let opts = RpcOptions {
+            cache_ttl_ms: Some(5000),
+            batch_get: Some(KeyedBatchGet {
+                value_type: "FeatureRow".to_string(),
+                key_field: Some("entity_id".to_string()),
+                window_ms: Some(5),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let batch = BatchGetMethod::from_options("FeatureStore", "BatchGetFeatures", &opts).unwrap();
+        assert_eq!(batch.value_type, "FeatureRow");
+        assert_eq!(batch.keys_field, "keys");
+        assert_eq!(batch.key_field, "entity_id");
+        assert_eq!(batch.window_ms, 5);
+        assert_eq!(batch.cache_ttl_ms, 5000);
+
+        assert!(BatchGetMethod::from_options("FeatureStore", "Other", &RpcOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_generate_batcher() {
+        let service = make_service();
+        let config = QuillConfig::new().with_batch_get(
+            BatchGetMethod::new("FeatureStore", "BatchGetFeatures", "FeatureRow").cache_ttl_ms(250),
+        );
+        let code = generate_batchers(&service, &config).to_string();
+
+        assert!(code.contains("BatchGetFeaturesBatcher"));
+        assert!(code.contains("KeyedBatcher < String , super :: FeatureRow >"));
+        assert!(code.contains("cache_ttl_ms (250u64)"));
+        assert!(code.contains("value . key . clone ()"));
+    }
+
+    #[test]
+    fn test_unannotated_methods_get_no_batcher() {
+        let service = make_service();
+        let code = generate_batchers(&service, &QuillConfig::new()).to_string();
+        assert!(code.is_empty());
+    }
+}
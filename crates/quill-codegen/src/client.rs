@@ -1,5 +1,7 @@
 //! Client code generation for Quill services
 
+use crate::cursor::CursorMethod;
+use crate::hooks::HookTokens;
 use crate::{method_type, MethodType, QuillConfig};
 use heck::ToSnakeCase;
 use prost_build::{Method, Service};
@@ -12,6 +14,25 @@ pub fn generate_client(service: &Service, config: &QuillConfig) -> Option<String
 
     let _service_name = &service.name;
     let methods = generate_methods(service, config);
+    let batchers = crate::batch::generate_batchers(service, config);
+    let hooks = HookTokens::new(service, config);
+
+    let (hooks_field, hooks_init, hooks_builder) = if hooks.enabled() {
+        let default_hooks = hooks.default_hooks();
+        (
+            quote! { hooks: Option<std::sync::Arc<dyn quill_core::SerializerHooks>>, },
+            quote! { hooks: #default_hooks, },
+            quote! {
+                /// Replace the serializer hooks run on every message
+                pub fn with_hooks(mut self, hooks: std::sync::Arc<dyn quill_core::SerializerHooks>) -> Self {
+                    self.hooks = Some(hooks);
+                    self
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
 
     let code = quote! {
         /// Generated client for #service_name service
@@ -26,14 +47,17 @@ pub fn generate_client(service: &Service, config: &QuillConfig) -> Option<String
             /// Client for the #service_name service
             pub struct #client_name {
                 client: QuillClient,
+                #hooks_field
             }
 
             impl #client_name {
                 /// Create a new client with the given QuillClient
                 pub fn new(client: QuillClient) -> Self {
-                    Self { client }
+                    Self { client, #hooks_init }
                 }
 
+                #hooks_builder
+
                 /// Create a new client with a base URL
                 pub fn connect(url: impl Into<String>) -> Result<Self, QuillError> {
                     let client = QuillClient::builder()
@@ -45,6 +69,8 @@ pub fn generate_client(service: &Service, config: &QuillConfig) -> Option<String
 
                 #methods
             }
+
+            #batchers
         }
     };
 
@@ -67,7 +93,7 @@ fn generate_methods(service: &Service, config: &QuillConfig) -> proc_macro2::Tok
 fn generate_method(
     service: &Service,
     method: &Method,
-    _config: &QuillConfig,
+    config: &QuillConfig,
 ) -> proc_macro2::TokenStream {
     let method_name = format_ident!("{}", method.name.to_snake_case());
 
@@ -80,6 +106,18 @@ fn generate_method(
     let service_name = &service.name;
     let rpc_method = &method.name;
 
+    let hooks = HookTokens::new(service, config);
+    let capture_hooks = hooks.capture(quote! { self.hooks });
+    let recapture_hooks = hooks.capture(quote! { hooks });
+    let encode_request = hooks.encode(rpc_method, "request", "request_bytes");
+    let encode_message = hooks.encode(rpc_method, "request", "bytes");
+    let decode_response = hooks.decode(rpc_method, "response", "response_bytes");
+    let decode_message = hooks.decode(rpc_method, "response", "bytes");
+
+    if CursorMethod::find(config, service, method).is_some() {
+        return crate::cursor::client_method(service, method, &hooks);
+    }
+
     match method_type(method) {
         MethodType::Unary => {
             quote! {
@@ -88,12 +126,15 @@ fn generate_method(
                     &self,
                     request: &#input_type,
                 ) -> Result<#output_type, QuillError> {
+                    #capture_hooks
                     let request_bytes = request.encode_to_vec();
+                    #encode_request
                     let response_bytes = self.client.call(
                         #service_name,
                         #rpc_method,
                         Bytes::from(request_bytes),
                     ).await?;
+                    #decode_response
 
                     #output_type::decode(&response_bytes[..])
                         .map_err(|e| QuillError::Rpc(format!("Failed to decode response: {}", e)))
@@ -109,15 +150,18 @@ fn generate_method(
                 ) -> Result<Pin<Box<dyn Stream<Item = Result<#output_type, QuillError>> + Send>>, QuillError> {
                     use futures::StreamExt;
 
+                    #capture_hooks
                     let request_bytes = request.encode_to_vec();
+                    #encode_request
                     let stream = self.client.call_server_streaming(
                         #service_name,
                         #rpc_method,
                         Bytes::from(request_bytes),
                     ).await?;
 
-                    let mapped_stream = stream.map(|result| {
+                    let mapped_stream = stream.map(move |result| {
                         result.and_then(|bytes| {
+                            #decode_message
                             #output_type::decode(&bytes[..])
                                 .map_err(|e| QuillError::Rpc(format!("Failed to decode response: {}", e)))
                         })
@@ -136,17 +180,24 @@ fn generate_method(
                 ) -> Result<#output_type, QuillError> {
                     use futures::StreamExt;
 
-                    let byte_stream = request_stream.map(|result| {
-                        result.and_then(|msg| {
-                            Ok(Bytes::from(msg.encode_to_vec()))
+                    #capture_hooks
+                    let byte_stream = {
+                        #recapture_hooks
+                        request_stream.map(move |result| {
+                            result.and_then(|msg| {
+                                let bytes = Bytes::from(msg.encode_to_vec());
+                                #encode_message
+                                Ok(bytes)
+                            })
                         })
-                    });
+                    };
 
                     let response_bytes = self.client.call_client_streaming(
                         #service_name,
                         #rpc_method,
                         Box::pin(byte_stream),
                     ).await?;
+                    #decode_response
 
                     #output_type::decode(&response_bytes[..])
                         .map_err(|e| QuillError::Rpc(format!("Failed to decode response: {}", e)))
@@ -162,11 +213,17 @@ fn generate_method(
                 ) -> Result<Pin<Box<dyn Stream<Item = Result<#output_type, QuillError>> + Send>>, QuillError> {
                     use futures::StreamExt;
 
-                    let byte_stream = request_stream.map(|result| {
-                        result.and_then(|msg| {
-                            Ok(Bytes::from(msg.encode_to_vec()))
+                    #capture_hooks
+                    let byte_stream = {
+                        #recapture_hooks
+                        request_stream.map(move |result| {
+                            result.and_then(|msg| {
+                                let bytes = Bytes::from(msg.encode_to_vec());
+                                #encode_message
+                                Ok(bytes)
+                            })
                         })
-                    });
+                    };
 
                     let stream = self.client.call_bidi_streaming(
                         #service_name,
@@ -174,10 +231,11 @@ fn generate_method(
                         Box::pin(byte_stream),
                     ).await?;
 
-                    let mapped_stream = stream.map(|result| {
+                    let mapped_stream = stream.map(move |result| {
                         result.and_then(|bytes| {
+                            #decode_message
                             #output_type::decode(&bytes[..])
-                                .map_err(|e| QuillError::Decode(e.to_string()))
+                                .map_err(|e| QuillError::Rpc(format!("Failed to decode response: {}", e)))
                         })
                     });
 
@@ -322,6 +380,8 @@ mod tests {
         assert!(code.contains("call_bidi_streaming"));
         // Verify request_stream parameter
         assert!(code.contains("request_stream"));
+        // Decode failures use an error variant QuillError has
+        assert!(!code.contains("QuillError :: Decode"));
     }
 
     #[test]
@@ -383,4 +443,23 @@ mod tests {
         assert!(code.contains("call_client_streaming"));
         assert!(code.contains("call_bidi_streaming"));
     }
+
+    #[test]
+    fn test_generate_client_with_serializer_hooks() {
+        let service = make_test_service();
+        let config = QuillConfig::default().with_serializer_hooks(
+            crate::SerializerHookService::new("TestService").with_default("crate::Canonicalize"),
+        );
+        let code = generate_client(&service, &config).unwrap();
+
+        assert!(code.contains("fn with_hooks"));
+        assert!(code.contains("crate :: Canonicalize"));
+        assert!(code.contains("run_encode"));
+        assert!(code.contains("run_decode"));
+
+        // Other services are unaffected
+        let plain = generate_client(&service, &QuillConfig::default()).unwrap();
+        assert!(!plain.contains("with_hooks"));
+        assert!(!plain.contains("run_encode"));
+    }
 }
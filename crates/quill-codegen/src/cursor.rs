@@ -0,0 +1,243 @@
+//! Stream cursor code generation
+//!
+//! For server-streaming RPCs annotated with `cursor`, the generated server
+//! trait method receives the cursor a call resumes from and yields each
+//! message paired with the cursor just after it; the generated route sends
+//! a cursor frame every `every` messages. The generated client method takes
+//! the cursor to resume from and returns a `quill_client::CursorStream`
+//! reporting the latest cursor received.
+//!
+//! Like keyed batch get, annotated methods are registered on [`QuillConfig`]
+//! with [`QuillConfig::with_cursor_stream`], typically via
+//! [`CursorMethod::from_options`].
+
+use crate::hooks::HookTokens;
+use crate::QuillConfig;
+use heck::ToSnakeCase;
+use prost_build::{Method, Service};
+use quill_proto::{CursorOptions, RpcOptions};
+use quote::{format_ident, quote};
+
+/// A server-streaming method resumable from a stream cursor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorMethod {
+    /// Service name (without package)
+    pub service: String,
+    /// Method name
+    pub method: String,
+    /// Messages between two cursor frames
+    pub every: usize,
+}
+
+impl CursorMethod {
+    /// Create a cursor method sending a cursor every 100 messages
+    pub fn new(service: impl Into<String>, method: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+            method: method.into(),
+            every: 100,
+        }
+    }
+
+    /// Build from `(quill.rpc)` options; `None` if the RPC has no `cursor`
+    pub fn from_options(
+        service: impl Into<String>,
+        method: impl Into<String>,
+        opts: &RpcOptions,
+    ) -> Option<Self> {
+        let cursor: &CursorOptions = opts.cursor.as_ref()?;
+        let mut m = Self::new(service, method);
+        if let Some(every) = cursor.every {
+            m.every = every as usize;
+        }
+        Some(m)
+    }
+
+    /// Set the number of messages between two cursor frames
+    pub fn every(mut self, every: usize) -> Self {
+        self.every = every;
+        self
+    }
+
+    /// Cursor settings of `method`, if it is a registered server-streaming method
+    pub(crate) fn find<'a>(config: &'a QuillConfig, service: &Service, method: &Method) -> Option<&'a Self> {
+        if method.client_streaming || !method.server_streaming {
+            return None;
+        }
+        config
+            .cursor_methods
+            .iter()
+            .find(|cursor| cursor.service == service.name && cursor.method == method.name)
+    }
+}
+
+/// Generate the client method of a cursor stream
+pub(crate) fn client_method(service: &Service, method: &Method, hooks: &HookTokens) -> proc_macro2::TokenStream {
+    let method_name = format_ident!("{}", method.name.to_snake_case());
+    let input_type: proc_macro2::TokenStream = format!("super::{}", method.input_type).parse().unwrap();
+    let output_type: proc_macro2::TokenStream = format!("super::{}", method.output_type).parse().unwrap();
+    let service_name = &service.name;
+    let rpc_method = &method.name;
+
+    let capture_hooks = hooks.capture(quote! { self.hooks });
+    let encode_request = hooks.encode(rpc_method, "request", "request_bytes");
+    let decode_message = hooks.decode(rpc_method, "response", "bytes");
+
+    quote! {
+        /// Cursor streaming RPC: #rpc_method
+        ///
+        /// Pass the last cursor received to resume after it.
+        pub async fn #method_name(
+            &self,
+            request: &#input_type,
+            resume_from: Option<&quill_core::StreamCursor>,
+        ) -> Result<quill_client::CursorStream<#output_type>, QuillError> {
+            #capture_hooks
+            let request_bytes = request.encode_to_vec();
+            #encode_request
+            let stream = self.client.call_cursor_stream(
+                #service_name,
+                #rpc_method,
+                Bytes::from(request_bytes),
+                resume_from,
+                quill_client::RequestOptions::default(),
+            ).await?;
+
+            Ok(stream.decode_with(move |bytes| {
+                #decode_message
+                #output_type::decode(&bytes[..])
+                    .map_err(|e| QuillError::Rpc(format!("Failed to decode response: {}", e)))
+            }))
+        }
+    }
+}
+
+/// Generate the server trait method of a cursor stream
+pub(crate) fn trait_method(method: &Method) -> proc_macro2::TokenStream {
+    let method_name = format_ident!("{}", method.name.to_snake_case());
+    let input_type: proc_macro2::TokenStream = format!("super::{}", method.input_type).parse().unwrap();
+    let output_type: proc_macro2::TokenStream = format!("super::{}", method.output_type).parse().unwrap();
+    let method_doc = format!(
+        "Handle {} RPC, resuming after `cursor` if given; yield each message with the cursor after it",
+        method.name
+    );
+
+    quote! {
+        #[doc = #method_doc]
+        async fn #method_name(
+            &self,
+            request: #input_type,
+            cursor: Option<quill_core::StreamCursor>,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<(#output_type, quill_core::StreamCursor), QuillError>> + Send>>, QuillError>;
+    }
+}
+
+/// Generate the route handler of a cursor stream
+pub(crate) fn route_handler(
+    service_name: &str,
+    method: &Method,
+    cursor: &CursorMethod,
+    hooks: &HookTokens,
+) -> proc_macro2::TokenStream {
+    let method_name = format_ident!("{}", method.name.to_snake_case());
+    let input_type: proc_macro2::TokenStream = format!("super::{}", method.input_type).parse().unwrap();
+    let rpc_method = &method.name;
+    let path = format!("{}/{}", service_name, rpc_method);
+    let every = cursor.every;
+
+    let capture_hooks = hooks.capture(quote! { hooks });
+    let decode_request = hooks.decode(rpc_method, "request", "request_bytes");
+    let encode_message = hooks.encode(rpc_method, "response", "bytes");
+
+    quote! {
+        {
+            let service = service.clone();
+            #capture_hooks
+            builder = builder.register_cursor_streaming(
+                #path,
+                #every,
+                move |request_bytes: Bytes, cursor: Option<quill_core::StreamCursor>| {
+                    let service = service.clone();
+                    #capture_hooks
+                    async move {
+                        #decode_request
+                        let request = #input_type::decode(&request_bytes[..])
+                            .map_err(|e| QuillError::Rpc(format!("Failed to decode: {}", e)))?;
+
+                        let response_stream = service.#method_name(request, cursor).await?;
+
+                        use futures::StreamExt;
+                        Ok(response_stream.map(move |result| {
+                            result.and_then(|(msg, cursor)| {
+                                let bytes = Bytes::from(msg.encode_to_vec());
+                                #encode_message
+                                Ok((bytes, cursor))
+                            })
+                        }))
+                    }
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::generate_client;
+    use crate::server::generate_server;
+
+    fn make_service() -> Service {
+        Service {
+            name: "Catalog".to_string(),
+            proto_name: "Catalog".to_string(),
+            package: "catalog.v1".to_string(),
+            comments: Default::default(),
+            options: Default::default(),
+            methods: vec![Method {
+                name: "ListItems".to_string(),
+                proto_name: "ListItems".to_string(),
+                comments: Default::default(),
+                input_type: "ListItemsRequest".to_string(),
+                output_type: "Item".to_string(),
+                input_proto_type: "ListItemsRequest".to_string(),
+                output_proto_type: "Item".to_string(),
+                options: Default::default(),
+                client_streaming: false,
+                server_streaming: true,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_from_options() {
+        let opts = RpcOptions {
+            cursor: Some(CursorOptions { every: Some(25) }),
+            ..Default::default()
+        };
+        let cursor = CursorMethod::from_options("Catalog", "ListItems", &opts).unwrap();
+        assert_eq!(cursor, CursorMethod::new("Catalog", "ListItems").every(25));
+
+        assert!(CursorMethod::from_options("Catalog", "ListItems", &RpcOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_generate_cursor_stubs() {
+        let service = make_service();
+        let config = QuillConfig::new().with_cursor_stream(CursorMethod::new("Catalog", "ListItems").every(10));
+
+        let server = generate_server(&service, &config).unwrap();
+        assert!(server.contains("register_cursor_streaming"));
+        assert!(server.contains("10usize"));
+        assert!(server.contains("cursor : Option < quill_core :: StreamCursor >"));
+
+        let client = generate_client(&service, &config).unwrap();
+        assert!(client.contains("call_cursor_stream"));
+        assert!(client.contains("CursorStream < super :: Item >"));
+
+        // Unannotated streams keep the plain signatures
+        let plain = generate_server(&service, &QuillConfig::new()).unwrap();
+        assert!(!plain.contains("register_cursor_streaming"));
+        assert!(plain.contains("register_streaming"));
+    }
+}
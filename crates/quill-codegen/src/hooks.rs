@@ -0,0 +1,154 @@
+//! Serializer hook code generation
+//!
+//! Services registered with [`QuillConfig::with_serializer_hooks`] get
+//! stubs that pass every encoded message through a
+//! `quill_core::SerializerHooks` implementation: after prost encoding on
+//! the sending side and before prost decoding on the receiving side.
+//!
+//! Generated clients gain a `with_hooks` builder and generated servers an
+//! `add_service_with_hooks` function, so hooks can be swapped at runtime.
+//! A default hooks type can be named at codegen time; it must implement
+//! `SerializerHooks + Default`. Keyed batch helpers call the underlying
+//! `QuillClient` directly and do not run hooks.
+
+use crate::QuillConfig;
+use prost_build::Service;
+use quote::{format_ident, quote};
+
+/// A service whose generated stubs run serializer hooks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerializerHookService {
+    /// Service name (without package)
+    pub service: String,
+    /// Path of a `SerializerHooks + Default` type installed by default
+    pub default_hooks: Option<String>,
+}
+
+impl SerializerHookService {
+    /// Enable hooks for a service, with none installed by default
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+            default_hooks: None,
+        }
+    }
+
+    /// Install `type_path` (e.g. `crate::crypto::Encryptor`) by default
+    pub fn with_default(mut self, type_path: impl Into<String>) -> Self {
+        self.default_hooks = Some(type_path.into());
+        self
+    }
+}
+
+/// Tokens for running hooks in the stubs of one service
+pub(crate) struct HookTokens<'a> {
+    service: &'a Service,
+    config: Option<&'a SerializerHookService>,
+}
+
+impl<'a> HookTokens<'a> {
+    pub(crate) fn new(service: &'a Service, config: &'a QuillConfig) -> Self {
+        Self {
+            service,
+            config: config
+                .serializer_hooks
+                .iter()
+                .find(|hooks| hooks.service == service.name),
+        }
+    }
+
+    /// Whether hooks are enabled for the service
+    pub(crate) fn enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// Expression for the hooks installed by default
+    pub(crate) fn default_hooks(&self) -> proc_macro2::TokenStream {
+        match self.config.and_then(|hooks| hooks.default_hooks.as_deref()) {
+            Some(path) => {
+                let path: proc_macro2::TokenStream = path.parse().expect("valid hooks type path");
+                quote! {
+                    Some(std::sync::Arc::new(<#path>::default()) as std::sync::Arc<dyn quill_core::SerializerHooks>)
+                }
+            }
+            None => quote! { None },
+        }
+    }
+
+    /// Bind a local `hooks` cloned from `source`
+    pub(crate) fn capture(&self, source: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        if !self.enabled() {
+            return quote! {};
+        }
+        quote! { let hooks = #source.clone(); }
+    }
+
+    /// Rebind `var` to the output of the encode hook
+    pub(crate) fn encode(&self, method: &str, kind: &str, var: &str) -> proc_macro2::TokenStream {
+        self.run("run_encode", method, kind, var)
+    }
+
+    /// Rebind `var` to the output of the decode hook
+    pub(crate) fn decode(&self, method: &str, kind: &str, var: &str) -> proc_macro2::TokenStream {
+        self.run("run_decode", method, kind, var)
+    }
+
+    fn run(&self, func: &str, method: &str, kind: &str, var: &str) -> proc_macro2::TokenStream {
+        if !self.enabled() {
+            return quote! {};
+        }
+        let func = format_ident!("{}", func);
+        let kind = format_ident!("{}", kind);
+        let var = format_ident!("{}", var);
+        let service_name = &self.service.name;
+        quote! {
+            let #var = quill_core::codec::#func(
+                hooks.as_deref(),
+                &quill_core::CodecContext::#kind(#service_name, #method),
+                Bytes::from(#var),
+            )?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_service(name: &str) -> Service {
+        Service {
+            name: name.to_string(),
+            proto_name: name.to_string(),
+            package: "test.v1".to_string(),
+            comments: Default::default(),
+            options: Default::default(),
+            methods: vec![],
+        }
+    }
+
+    #[test]
+    fn test_disabled_emits_nothing() {
+        let service = make_service("Plain");
+        let config = QuillConfig::default().with_serializer_hooks(SerializerHookService::new("Other"));
+        let hooks = HookTokens::new(&service, &config);
+
+        assert!(!hooks.enabled());
+        assert!(hooks.encode("Get", "request", "bytes").is_empty());
+        assert!(hooks.capture(quote! { self.hooks }).is_empty());
+        assert_eq!(hooks.default_hooks().to_string(), "None");
+    }
+
+    #[test]
+    fn test_enabled_with_default() {
+        let service = make_service("Payments");
+        let config = QuillConfig::default()
+            .with_serializer_hooks(SerializerHookService::new("Payments").with_default("crate::crypto::Encryptor"));
+        let hooks = HookTokens::new(&service, &config);
+
+        assert!(hooks.enabled());
+        let code = hooks.decode("Charge", "response", "bytes").to_string();
+        assert!(code.contains("run_decode"));
+        assert!(code.contains("CodecContext :: response (\"Payments\" , \"Charge\")"));
+        assert!(hooks.default_hooks().to_string().contains("crate :: crypto :: Encryptor"));
+    }
+}
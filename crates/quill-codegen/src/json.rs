@@ -0,0 +1,58 @@
+//! JSON and reflection support code generation.
+//!
+//! This module generates, once per package:
+//! - The encoded file descriptor set of the compiled protos, when serde or
+//!   reflection support is enabled
+//! - A constructor for the REST gateway's `MessageConverter`, when serde
+//!   support is enabled
+//!
+//! The messages themselves get their serde derives from prost-build.
+
+/// Default file name of the descriptor set written to `OUT_DIR`
+pub const DEFAULT_DESCRIPTOR_SET_FILE: &str = "quill_descriptor_set.bin";
+
+/// Generate the descriptor set constant of a package.
+///
+/// `descriptor_set_file` is the file name prost-build wrote the descriptor
+/// set to, relative to `OUT_DIR`. Pass the constant to
+/// `ServerBuilder::file_descriptor_set` to serve it by reflection.
+pub fn generate_descriptor_set(descriptor_set_file: &str) -> proc_macro2::TokenStream {
+    let path = format!("/{}", descriptor_set_file);
+
+    quote::quote! {
+        /// Encoded `FileDescriptorSet` of the protos this package was compiled from
+        pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), #path));
+    }
+}
+
+/// Generate the converter constructor of a package.
+///
+/// The constructor reads the constant from [`generate_descriptor_set`].
+pub fn generate_json_support() -> proc_macro2::TokenStream {
+    quote::quote! {
+        /// JSON ↔ Protobuf converter for the messages of this package
+        ///
+        /// Pass it to `RestGatewayBuilder::with_converter` to map REST
+        /// requests and responses without a hand-built descriptor set.
+        pub fn message_converter(
+        ) -> quill_rest_gateway::GatewayResult<quill_rest_gateway::MessageConverter> {
+            quill_rest_gateway::MessageConverter::from_bytes(FILE_DESCRIPTOR_SET)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_json_support() {
+        let code = generate_descriptor_set(DEFAULT_DESCRIPTOR_SET_FILE).to_string();
+        assert!(code.contains("FILE_DESCRIPTOR_SET"));
+        assert!(code.contains("\"/quill_descriptor_set.bin\""));
+
+        let code = generate_json_support().to_string();
+        assert!(code.contains("fn message_converter"));
+        assert!(code.contains("MessageConverter :: from_bytes"));
+    }
+}
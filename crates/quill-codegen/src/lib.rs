@@ -3,11 +3,21 @@
 //! This crate provides code generation for Quill services from .proto files,
 //! generating type-safe client and server stubs.
 
+pub mod batch;
 pub mod client;
+pub mod cursor;
+pub mod hooks;
+pub mod json;
+pub mod paths;
 pub mod playground;
 pub mod server;
 pub mod service;
 
+pub use batch::BatchGetMethod;
+pub use cursor::CursorMethod;
+pub use hooks::SerializerHookService;
+pub use paths::CratePaths;
+
 use prost_build::{Config, Method, Service};
 use std::io::Result;
 use std::path::Path;
@@ -24,6 +34,18 @@ pub struct QuillConfig {
     pub package_prefix: Option<String>,
     /// Generate playground support (ToDebugJson trait, method metadata)
     pub generate_playground: bool,
+    /// Methods using the keyed batch get pattern
+    pub batch_get_methods: Vec<BatchGetMethod>,
+    /// Server-streaming methods resumable from a stream cursor
+    pub cursor_methods: Vec<CursorMethod>,
+    /// Services whose stubs run serializer hooks
+    pub serializer_hooks: Vec<SerializerHookService>,
+    /// Derive serde traits on messages and generate REST gateway JSON support
+    pub generate_serde: bool,
+    /// Generate the descriptor set constant served by reflection
+    pub generate_reflection: bool,
+    /// Crates named by generated code
+    pub crate_paths: CratePaths,
 }
 
 impl Default for QuillConfig {
@@ -33,6 +55,12 @@ impl Default for QuillConfig {
             generate_server: true,
             package_prefix: None,
             generate_playground: false,
+            batch_get_methods: Vec::new(),
+            cursor_methods: Vec::new(),
+            serializer_hooks: Vec::new(),
+            generate_serde: false,
+            generate_reflection: false,
+            crate_paths: CratePaths::Direct,
         }
     }
 }
@@ -48,6 +76,12 @@ impl QuillConfig {
             generate_server: false,
             package_prefix: None,
             generate_playground: false,
+            batch_get_methods: Vec::new(),
+            cursor_methods: Vec::new(),
+            serializer_hooks: Vec::new(),
+            generate_serde: false,
+            generate_reflection: false,
+            crate_paths: CratePaths::Direct,
         }
     }
 
@@ -57,6 +91,12 @@ impl QuillConfig {
             generate_server: true,
             package_prefix: None,
             generate_playground: false,
+            batch_get_methods: Vec::new(),
+            cursor_methods: Vec::new(),
+            serializer_hooks: Vec::new(),
+            generate_serde: false,
+            generate_reflection: false,
+            crate_paths: CratePaths::Direct,
         }
     }
 
@@ -65,6 +105,46 @@ impl QuillConfig {
         self
     }
 
+    /// Generate a keyed batch get helper for a method.
+    ///
+    /// The generated `{Method}Batcher` coalesces single-key lookups into
+    /// batched calls and caches values per key.
+    pub fn with_batch_get(mut self, method: BatchGetMethod) -> Self {
+        self.batch_get_methods.push(method);
+        self
+    }
+
+    /// Make a server-streaming method resumable from a stream cursor.
+    ///
+    /// The generated server method receives the cursor to resume from and
+    /// pairs each message with its cursor; the generated client method
+    /// returns a `quill_client::CursorStream`.
+    pub fn with_cursor_stream(mut self, method: CursorMethod) -> Self {
+        self.cursor_methods.push(method);
+        self
+    }
+
+    /// Run serializer hooks in the stubs of a service.
+    ///
+    /// Generated stubs pass encoded messages through a
+    /// `quill_core::SerializerHooks` implementation, e.g. to encrypt or
+    /// canonicalize payloads.
+    pub fn with_serializer_hooks(mut self, hooks: SerializerHookService) -> Self {
+        self.serializer_hooks.push(hooks);
+        self
+    }
+
+    /// Enable reflection support generation.
+    ///
+    /// When enabled, the descriptor set is written to `OUT_DIR` and every
+    /// package with services gets a `FILE_DESCRIPTOR_SET` constant, to be
+    /// passed to `ServerBuilder::file_descriptor_set`. Serde support
+    /// generates the constant as well.
+    pub fn with_reflection(mut self, enabled: bool) -> Self {
+        self.generate_reflection = enabled;
+        self
+    }
+
     /// Enable playground support generation.
     ///
     /// When enabled, generates:
@@ -74,6 +154,32 @@ impl QuillConfig {
         self.generate_playground = enabled;
         self
     }
+
+    /// Enable serde/JSON support generation.
+    ///
+    /// When enabled:
+    /// - Messages derive `serde::Serialize` and `serde::Deserialize`
+    /// - The descriptor set is written to `OUT_DIR`, and every package with
+    ///   services gets a `FILE_DESCRIPTOR_SET` constant and a
+    ///   `message_converter()` function returning a
+    ///   `quill_rest_gateway::MessageConverter` for its messages
+    ///
+    /// The crate including the generated code must depend on `serde` and
+    /// `quill-rest-gateway`.
+    pub fn with_serde(mut self, enabled: bool) -> Self {
+        self.generate_serde = enabled;
+        self
+    }
+
+    /// Set the crates named by generated code.
+    ///
+    /// [`CratePaths::Facade`] makes stubs name the modules of the `quill`
+    /// crate instead of `quill_core`, `quill_client`, `quill_server` and
+    /// `quill_rest_gateway`. `quill::codegen::compile_protos` sets it.
+    pub fn with_crate_paths(mut self, paths: CratePaths) -> Self {
+        self.crate_paths = paths;
+        self
+    }
 }
 
 /// Generate Quill RPC code from protobuf files
@@ -99,12 +205,20 @@ pub fn compile_protos(
 
     let mut prost_config = Config::new();
 
-    // When playground is enabled, derive serde traits for ToDebugJson support
-    if config.generate_playground {
+    // Playground needs serde traits for ToDebugJson support
+    if config.generate_playground || config.generate_serde {
         // Add serde derives to all message types for JSON serialization
         prost_config.type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]");
     }
 
+    // JSON and reflection support embed the descriptor set prost-build writes
+    if config.generate_serde || config.generate_reflection {
+        let out_dir = env::var_os("OUT_DIR")
+            .ok_or_else(|| io::Error::other("OUT_DIR must be set to embed the descriptor set"))?;
+        prost_config
+            .file_descriptor_set_path(Path::new(&out_dir).join(json::DEFAULT_DESCRIPTOR_SET_FILE));
+    }
+
     // Configure prost to generate code
     prost_config.service_generator(Box::new(QuillServiceGenerator::new(config)));
 
@@ -134,6 +248,18 @@ impl QuillServiceGenerator {
     fn new(config: QuillConfig) -> Self {
         Self { config }
     }
+
+    /// Append generated code, rewritten to the configured crate paths
+    fn emit(&self, buf: &mut String, code: &str) {
+        match self.config.crate_paths {
+            CratePaths::Direct => buf.push_str(code),
+            CratePaths::Facade => {
+                let tokens = code.parse().expect("generated code is valid Rust");
+                buf.push_str(&paths::to_facade(tokens).to_string());
+            }
+        }
+        buf.push('\n');
+    }
 }
 
 impl prost_build::ServiceGenerator for QuillServiceGenerator {
@@ -141,24 +267,32 @@ impl prost_build::ServiceGenerator for QuillServiceGenerator {
         // Generate client code
         if self.config.generate_client {
             if let Some(client_code) = client::generate_client(&service, &self.config) {
-                buf.push_str(&client_code);
-                buf.push('\n');
+                self.emit(buf, &client_code);
             }
         }
 
         // Generate server code
         if self.config.generate_server {
             if let Some(server_code) = server::generate_server(&service, &self.config) {
-                buf.push_str(&server_code);
-                buf.push('\n');
+                self.emit(buf, &server_code);
             }
         }
 
         // Generate playground metadata
         if self.config.generate_playground {
             let playground_code = playground::generate_playground_metadata(&service, &self.config);
-            buf.push_str(&playground_code.to_string());
-            buf.push('\n');
+            self.emit(buf, &playground_code.to_string());
+        }
+    }
+
+    fn finalize_package(&mut self, _package: &str, buf: &mut String) {
+        // Generate the descriptor set and JSON support once per package
+        if self.config.generate_serde || self.config.generate_reflection {
+            let descriptor_code = json::generate_descriptor_set(json::DEFAULT_DESCRIPTOR_SET_FILE);
+            self.emit(buf, &descriptor_code.to_string());
+        }
+        if self.config.generate_serde {
+            self.emit(buf, &json::generate_json_support().to_string());
         }
     }
 }
@@ -204,6 +338,21 @@ mod tests {
         assert!(config.generate_server);
         assert!(config.package_prefix.is_none());
         assert!(!config.generate_playground);
+        assert!(config.batch_get_methods.is_empty());
+        assert!(config.serializer_hooks.is_empty());
+        assert!(!config.generate_serde);
+        assert!(!config.generate_reflection);
+        assert_eq!(config.crate_paths, CratePaths::Direct);
+    }
+
+    #[test]
+    fn test_config_with_serde() {
+        let config = QuillConfig::new().with_serde(true);
+        assert!(config.generate_serde);
+        assert!(!config.generate_playground);
+
+        let config_disabled = config.with_serde(false);
+        assert!(!config_disabled.generate_serde);
     }
 
     #[test]
@@ -0,0 +1,82 @@
+//! Crate paths named by generated code
+//!
+//! Generators name `quill_core`, `quill_client`, `quill_server` and
+//! `quill_rest_gateway` directly. With [`CratePaths::Facade`] the generated
+//! tokens are rewritten to the matching modules of the `quill` facade
+//! crate, so crates depending only on `quill` compile the stubs.
+
+use proc_macro2::{Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Crates named by generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CratePaths {
+    /// `quill_core`, `quill_client`, `quill_server` and `quill_rest_gateway`
+    #[default]
+    Direct,
+    /// `quill::core`, `quill::client`, `quill::server` and `quill::gateway`
+    Facade,
+}
+
+/// Facade module of a crate named by generated code
+fn facade_module(ident: &Ident) -> Option<&'static str> {
+    match ident.to_string().as_str() {
+        "quill_core" => Some("core"),
+        "quill_client" => Some("client"),
+        "quill_server" => Some("server"),
+        "quill_rest_gateway" => Some("gateway"),
+        _ => None,
+    }
+}
+
+/// Rewrite the crate paths of `tokens` to the `quill` facade
+pub(crate) fn to_facade(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .flat_map(|tree| match tree {
+            TokenTree::Ident(ident) => match facade_module(&ident) {
+                Some(module) => facade_path(module, ident.span()),
+                None => vec![TokenTree::Ident(ident)],
+            },
+            TokenTree::Group(group) => {
+                let mut rewritten = Group::new(group.delimiter(), to_facade(group.stream()));
+                rewritten.set_span(group.span());
+                vec![TokenTree::Group(rewritten)]
+            }
+            tree => vec![tree],
+        })
+        .collect()
+}
+
+/// `::quill::<module>`
+fn facade_path(module: &str, span: Span) -> Vec<TokenTree> {
+    let mut tokens = Vec::new();
+    for segment in ["quill", module] {
+        tokens.push(TokenTree::Punct(Punct::new(':', Spacing::Joint)));
+        tokens.push(TokenTree::Punct(Punct::new(':', Spacing::Alone)));
+        tokens.push(TokenTree::Ident(Ident::new(segment, span)));
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_to_facade_rewrites_crate_paths() {
+        let tokens = quote! {
+            use quill_client::QuillClient;
+            fn f(hooks: Arc<dyn quill_core::SerializerHooks>) -> quill_rest_gateway::GatewayResult<()> {
+                quill_server::router::RequestStream::default()
+            }
+        };
+        let expected = quote! {
+            use ::quill::client::QuillClient;
+            fn f(hooks: Arc<dyn ::quill::core::SerializerHooks>) -> ::quill::gateway::GatewayResult<()> {
+                ::quill::server::router::RequestStream::default()
+            }
+        };
+        assert_eq!(to_facade(tokens).to_string(), expected.to_string());
+    }
+}
@@ -1,18 +1,63 @@
 //! Server code generation for Quill services
 
+use crate::cursor::CursorMethod;
+use crate::hooks::HookTokens;
 use crate::{method_type, MethodType, QuillConfig};
 use heck::ToSnakeCase;
 use prost_build::{Method, Service};
 use quote::{format_ident, quote};
 
 /// Generate server code for a service
-pub fn generate_server(service: &Service, _config: &QuillConfig) -> Option<String> {
+pub fn generate_server(service: &Service, config: &QuillConfig) -> Option<String> {
     let trait_name = format_ident!("{}", service.name);
     let server_mod_name = format_ident!("{}_server", service.name.to_snake_case());
 
     let _service_name = &service.name;
-    let trait_methods = generate_trait_methods(service);
-    let route_handlers = generate_route_handlers(service);
+    let trait_methods = generate_trait_methods(service, config);
+    let hooks = HookTokens::new(service, config);
+    let route_handlers = generate_route_handlers(service, config, &hooks);
+
+    let registration = if hooks.enabled() {
+        let default_hooks = hooks.default_hooks();
+        quote! {
+            /// Register the service implementation with a ServerBuilder
+            pub fn add_service<S: #trait_name>(
+                builder: ServerBuilder,
+                service: S,
+            ) -> ServerBuilder {
+                add_service_with_hooks(builder, service, #default_hooks)
+            }
+
+            /// Register the service implementation, running `hooks` on every message
+            pub fn add_service_with_hooks<S: #trait_name>(
+                builder: ServerBuilder,
+                service: S,
+                hooks: Option<Arc<dyn quill_core::SerializerHooks>>,
+            ) -> ServerBuilder {
+                let service = Arc::new(service);
+                let mut builder = builder;
+
+                #route_handlers
+
+                builder
+            }
+        }
+    } else {
+        quote! {
+            /// Register the service implementation with a ServerBuilder
+            pub fn add_service<S: #trait_name>(
+                builder: ServerBuilder,
+                service: S,
+            ) -> ServerBuilder {
+                let service = Arc::new(service);
+                let mut builder = builder;
+
+                #route_handlers
+
+                builder
+            }
+        }
+    };
 
     let code = quote! {
         /// Generated server for #service_name service
@@ -31,18 +76,7 @@ pub fn generate_server(service: &Service, _config: &QuillConfig) -> Option<Strin
                 #trait_methods
             }
 
-            /// Register the service implementation with a ServerBuilder
-            pub fn add_service<S: #trait_name>(
-                builder: ServerBuilder,
-                service: S,
-            ) -> ServerBuilder {
-                let service = Arc::new(service);
-                let mut builder = builder;
-
-                #route_handlers
-
-                builder
-            }
+            #registration
         }
     };
 
@@ -50,11 +84,14 @@ pub fn generate_server(service: &Service, _config: &QuillConfig) -> Option<Strin
 }
 
 /// Generate trait methods for all RPCs in the service
-fn generate_trait_methods(service: &Service) -> proc_macro2::TokenStream {
+fn generate_trait_methods(service: &Service, config: &QuillConfig) -> proc_macro2::TokenStream {
     let mut methods = proc_macro2::TokenStream::new();
 
     for method in &service.methods {
-        let method_code = generate_trait_method(method);
+        let method_code = match CursorMethod::find(config, service, method) {
+            Some(_) => crate::cursor::trait_method(method),
+            None => generate_trait_method(method),
+        };
         methods.extend(method_code);
     }
 
@@ -114,13 +151,16 @@ fn generate_trait_method(method: &Method) -> proc_macro2::TokenStream {
 }
 
 /// Generate route handlers for all RPCs
-fn generate_route_handlers(service: &Service) -> proc_macro2::TokenStream {
+fn generate_route_handlers(service: &Service, config: &QuillConfig, hooks: &HookTokens) -> proc_macro2::TokenStream {
     let mut handlers = proc_macro2::TokenStream::new();
 
     let service_name = &service.name;
 
     for method in &service.methods {
-        let handler_code = generate_route_handler(service_name, method);
+        let handler_code = match CursorMethod::find(config, service, method) {
+            Some(cursor) => crate::cursor::route_handler(service_name, method, cursor, hooks),
+            None => generate_route_handler(service_name, method, hooks),
+        };
         handlers.extend(handler_code);
     }
 
@@ -128,7 +168,11 @@ fn generate_route_handlers(service: &Service) -> proc_macro2::TokenStream {
 }
 
 /// Generate a single route handler based on streaming type
-fn generate_route_handler(service_name: &str, method: &Method) -> proc_macro2::TokenStream {
+fn generate_route_handler(
+    service_name: &str,
+    method: &Method,
+    hooks: &HookTokens,
+) -> proc_macro2::TokenStream {
     let method_name = format_ident!("{}", method.name.to_snake_case());
 
     // Use super:: to reference message types from parent module
@@ -141,19 +185,30 @@ fn generate_route_handler(service_name: &str, method: &Method) -> proc_macro2::T
 
     let path = format!("{}/{}", service_name, rpc_method);
 
+    let capture_hooks = hooks.capture(quote! { hooks });
+    let decode_request = hooks.decode(rpc_method, "request", "request_bytes");
+    let decode_message = hooks.decode(rpc_method, "request", "bytes");
+    let encode_response = hooks.encode(rpc_method, "response", "response_bytes");
+    let encode_message = hooks.encode(rpc_method, "response", "bytes");
+
     match method_type(method) {
         MethodType::Unary => {
             quote! {
                 {
                     let service = service.clone();
+                    #capture_hooks
                     builder = builder.register(#path, move |request_bytes: Bytes| {
                         let service = service.clone();
+                        #capture_hooks
                         async move {
+                            #decode_request
                             let request = #input_type::decode(&request_bytes[..])
                                 .map_err(|e| QuillError::Rpc(format!("Failed to decode: {}", e)))?;
 
                             let response = service.#method_name(request).await?;
-                            Ok(Bytes::from(response.encode_to_vec()))
+                            let response_bytes = response.encode_to_vec();
+                            #encode_response
+                            Ok(Bytes::from(response_bytes))
                         }
                     });
                 }
@@ -163,20 +218,25 @@ fn generate_route_handler(service_name: &str, method: &Method) -> proc_macro2::T
             quote! {
                 {
                     let service = service.clone();
+                    #capture_hooks
                     builder = builder.register_streaming(
                         #path,
                         move |request_bytes: Bytes| {
                             let service = service.clone();
+                            #capture_hooks
                             async move {
+                                #decode_request
                                 let request = #input_type::decode(&request_bytes[..])
                                     .map_err(|e| QuillError::Rpc(format!("Failed to decode: {}", e)))?;
 
                                 let response_stream = service.#method_name(request).await?;
 
                                 use futures::StreamExt;
-                                let byte_stream = response_stream.map(|result| {
+                                let byte_stream = response_stream.map(move |result| {
                                     result.and_then(|msg| {
-                                        Ok(Bytes::from(msg.encode_to_vec()))
+                                        let bytes = Bytes::from(msg.encode_to_vec());
+                                        #encode_message
+                                        Ok(bytes)
                                     })
                                 });
 
@@ -191,24 +251,32 @@ fn generate_route_handler(service_name: &str, method: &Method) -> proc_macro2::T
             quote! {
                 {
                     let service = service.clone();
+                    #capture_hooks
                     builder = builder.register_client_streaming(
                         #path,
                         move |request_stream: quill_server::router::RequestStream| {
                             let service = service.clone();
+                            #capture_hooks
                             async move {
                                 use futures::StreamExt;
                                 use prost::Message;
 
                                 // Map the byte stream to typed messages
-                                let typed_stream = request_stream.map(|result| {
-                                    result.and_then(|bytes| {
-                                        #input_type::decode(&bytes[..])
-                                            .map_err(|e| QuillError::Rpc(format!("Failed to decode: {}", e)))
+                                let typed_stream = {
+                                    #capture_hooks
+                                    request_stream.map(move |result| {
+                                        result.and_then(|bytes| {
+                                            #decode_message
+                                            #input_type::decode(&bytes[..])
+                                                .map_err(|e| QuillError::Rpc(format!("Failed to decode: {}", e)))
+                                        })
                                     })
-                                });
+                                };
 
                                 let response = service.#method_name(Box::pin(typed_stream)).await?;
-                                Ok(RpcResponse::Unary(Bytes::from(response.encode_to_vec())))
+                                let response_bytes = response.encode_to_vec();
+                                #encode_response
+                                Ok(RpcResponse::Unary(Bytes::from(response_bytes)))
                             }
                         },
                     );
@@ -219,28 +287,36 @@ fn generate_route_handler(service_name: &str, method: &Method) -> proc_macro2::T
             quote! {
                 {
                     let service = service.clone();
+                    #capture_hooks
                     builder = builder.register_bidi_streaming(
                         #path,
                         move |request_stream: quill_server::router::RequestStream| {
                             let service = service.clone();
+                            #capture_hooks
                             async move {
                                 use futures::StreamExt;
                                 use prost::Message;
 
                                 // Map the byte stream to typed messages
-                                let typed_stream = request_stream.map(|result| {
-                                    result.and_then(|bytes| {
-                                        #input_type::decode(&bytes[..])
-                                            .map_err(|e| QuillError::Rpc(format!("Failed to decode: {}", e)))
+                                let typed_stream = {
+                                    #capture_hooks
+                                    request_stream.map(move |result| {
+                                        result.and_then(|bytes| {
+                                            #decode_message
+                                            #input_type::decode(&bytes[..])
+                                                .map_err(|e| QuillError::Rpc(format!("Failed to decode: {}", e)))
+                                        })
                                     })
-                                });
+                                };
 
                                 let response_stream = service.#method_name(Box::pin(typed_stream)).await?;
 
                                 // Map typed responses back to bytes
-                                let byte_stream = response_stream.map(|result| {
+                                let byte_stream = response_stream.map(move |result| {
                                     result.and_then(|msg| {
-                                        Ok(Bytes::from(msg.encode_to_vec()))
+                                        let bytes = Bytes::from(msg.encode_to_vec());
+                                        #encode_message
+                                        Ok(bytes)
                                     })
                                 });
 
@@ -440,4 +516,20 @@ mod tests {
         assert!(code.contains("register_client_streaming"));
         assert!(code.contains("register_bidi_streaming"));
     }
+
+    #[test]
+    fn test_generate_server_with_serializer_hooks() {
+        let service = make_test_service();
+        let config = QuillConfig::default()
+            .with_serializer_hooks(crate::SerializerHookService::new("TestService"));
+        let code = generate_server(&service, &config).unwrap();
+
+        assert!(code.contains("fn add_service_with_hooks"));
+        assert!(code.contains("add_service_with_hooks (builder , service , None)"));
+        assert!(code.contains("run_decode"));
+        assert!(code.contains("run_encode"));
+
+        let plain = generate_server(&service, &QuillConfig::default()).unwrap();
+        assert!(!plain.contains("add_service_with_hooks"));
+    }
 }
@@ -1,6 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
-use quill_core::{Frame, FrameParser};
-use bytes::Bytes;
+use quill_core::{encode_varint, Frame, FrameParser};
+use bytes::{Bytes, BytesMut};
 
 fn bench_frame_encoding(c: &mut Criterion) {
     let mut group = c.benchmark_group("frame_encoding");
@@ -25,6 +25,18 @@ fn bench_frame_encoding(c: &mut Criterion) {
         })
     });
 
+    // Benchmark encoding into a reused buffer
+    group.throughput(Throughput::Bytes(100));
+    group.bench_function("encode_to_reused_100b", |b| {
+        let mut buf = BytesMut::with_capacity(4096);
+        b.iter(|| {
+            buf.clear();
+            let frame = Frame::data(black_box(small_payload.clone()));
+            frame.encode_to(&mut buf);
+            black_box(&buf);
+        })
+    });
+
     // Benchmark large payload (1MB)
     let large_payload = Bytes::from(vec![0u8; 1024 * 1024]);
     group.throughput(Throughput::Bytes(1024 * 1024));
@@ -132,6 +144,18 @@ fn bench_varint_encoding(c: &mut Criterion) {
         })
     });
 
+    // Varints alone, into a reused buffer
+    for (name, value) in [("raw_1_byte", 100u64), ("raw_2_byte", 10_000), ("raw_5_byte", 1 << 32)] {
+        group.bench_function(name, |b| {
+            let mut buf = BytesMut::with_capacity(16);
+            b.iter(|| {
+                buf.clear();
+                encode_varint(black_box(value), &mut buf);
+                black_box(&buf);
+            })
+        });
+    }
+
     group.finish();
 }
 
@@ -0,0 +1,213 @@
+//! Batch RPC envelope
+//!
+//! A batch carries many unary calls in one request to the built-in batch
+//! method ([`BATCH_PATH`]). The server runs the entries concurrently and
+//! answers with one result per entry, in request order.
+//!
+//! Both messages are varint length-prefixed:
+//! - Request: entry count, then per entry the method path and its payload
+//! - Response: result count, then per result the HTTP status and its
+//!   payload (the response message on success, Problem Details JSON otherwise)
+
+use crate::error::ProblemDetails;
+use crate::framing::{decode_varint, encode_varint};
+use bytes::{Buf, Bytes, BytesMut};
+
+/// Service name of the built-in batch RPC
+pub const BATCH_SERVICE: &str = "quill.batch.v1.Batch";
+
+/// Method name of the built-in batch RPC
+pub const BATCH_METHOD: &str = "Call";
+
+/// Full route path of the built-in batch RPC
+pub const BATCH_PATH: &str = "quill.batch.v1.Batch/Call";
+
+/// Errors decoding a batch envelope
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum BatchError {
+    #[error("Batch envelope truncated")]
+    Truncated,
+
+    #[error("Batch entry {0} has an invalid method path")]
+    InvalidPath(usize),
+
+    #[error("Batch entry {0} has an invalid status")]
+    InvalidStatus(usize),
+
+    #[error("Batch envelope has {0} trailing bytes")]
+    TrailingBytes(usize),
+}
+
+/// One unary call in a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchEntry {
+    /// Method path: `{package}.{Service}/{Method}`
+    pub path: String,
+    /// Request message
+    pub payload: Bytes,
+}
+
+impl BatchEntry {
+    /// Create an entry calling `service`/`method`
+    pub fn new(service: &str, method: &str, payload: Bytes) -> Self {
+        Self {
+            path: format!("{}/{}", service, method),
+            payload,
+        }
+    }
+}
+
+/// Outcome of one batch entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchResult {
+    /// HTTP status the call would have had on its own
+    pub status: u16,
+    /// Response message, or Problem Details JSON for a failed call
+    pub payload: Bytes,
+}
+
+impl BatchResult {
+    /// A successful call
+    pub fn ok(payload: Bytes) -> Self {
+        Self { status: 200, payload }
+    }
+
+    /// A failed call
+    pub fn problem(pd: &ProblemDetails) -> Self {
+        let json = pd.to_json().unwrap_or_else(|_| "{}".to_string());
+        Self {
+            status: pd.status,
+            payload: Bytes::from(json),
+        }
+    }
+
+    /// Check if the call succeeded
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Convert into the response message or the call's error
+    pub fn into_result(self) -> Result<Bytes, Box<ProblemDetails>> {
+        if self.is_success() {
+            return Ok(self.payload);
+        }
+        let pd: ProblemDetails = serde_json::from_slice(&self.payload).unwrap_or_else(|_| {
+            ProblemDetails::new(
+                http::StatusCode::from_u16(self.status).unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR),
+                "Batch entry failed",
+            )
+        });
+        Err(Box::new(pd))
+    }
+}
+
+/// Encode batch entries as a request body
+pub fn encode_batch_request(entries: &[BatchEntry]) -> Bytes {
+    let mut buf = BytesMut::new();
+    encode_varint(entries.len() as u64, &mut buf);
+    for entry in entries {
+        put_bytes(&mut buf, entry.path.as_bytes());
+        put_bytes(&mut buf, &entry.payload);
+    }
+    buf.freeze()
+}
+
+/// Decode a batch request body
+pub fn decode_batch_request(mut data: Bytes) -> Result<Vec<BatchEntry>, BatchError> {
+    let count = decode_varint(&mut data).ok_or(BatchError::Truncated)?;
+    let mut entries = Vec::new();
+    for index in 0..count as usize {
+        let path = take_bytes(&mut data)?;
+        let path = String::from_utf8(path.to_vec()).map_err(|_| BatchError::InvalidPath(index))?;
+        let payload = take_bytes(&mut data)?;
+        entries.push(BatchEntry { path, payload });
+    }
+    finish(&data)?;
+    Ok(entries)
+}
+
+/// Encode batch results as a response body
+pub fn encode_batch_response(results: &[BatchResult]) -> Bytes {
+    let mut buf = BytesMut::new();
+    encode_varint(results.len() as u64, &mut buf);
+    for result in results {
+        encode_varint(result.status as u64, &mut buf);
+        put_bytes(&mut buf, &result.payload);
+    }
+    buf.freeze()
+}
+
+/// Decode a batch response body
+pub fn decode_batch_response(mut data: Bytes) -> Result<Vec<BatchResult>, BatchError> {
+    let count = decode_varint(&mut data).ok_or(BatchError::Truncated)?;
+    let mut results = Vec::new();
+    for index in 0..count as usize {
+        let status = decode_varint(&mut data).ok_or(BatchError::Truncated)?;
+        let status = u16::try_from(status).map_err(|_| BatchError::InvalidStatus(index))?;
+        let payload = take_bytes(&mut data)?;
+        results.push(BatchResult { status, payload });
+    }
+    finish(&data)?;
+    Ok(results)
+}
+
+fn put_bytes(buf: &mut BytesMut, bytes: &[u8]) {
+    encode_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
+
+fn take_bytes(data: &mut Bytes) -> Result<Bytes, BatchError> {
+    let len = decode_varint(data).ok_or(BatchError::Truncated)?;
+    if len > data.remaining() as u64 {
+        return Err(BatchError::Truncated);
+    }
+    Ok(data.split_to(len as usize))
+}
+
+fn finish(data: &Bytes) -> Result<(), BatchError> {
+    match data.remaining() {
+        0 => Ok(()),
+        n => Err(BatchError::TrailingBytes(n)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_round_trip() {
+        let entries = vec![
+            BatchEntry::new("echo.v1.Echo", "Echo", Bytes::from_static(b"hello")),
+            BatchEntry::new("echo.v1.Echo", "Echo", Bytes::new()),
+        ];
+        let decoded = decode_batch_request(encode_batch_request(&entries)).unwrap();
+        assert_eq!(decoded, entries);
+        assert_eq!(decoded[0].path, "echo.v1.Echo/Echo");
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let pd = ProblemDetails::new(http::StatusCode::NOT_FOUND, "Method not found");
+        let results = vec![BatchResult::ok(Bytes::from_static(b"world")), BatchResult::problem(&pd)];
+        let decoded = decode_batch_response(encode_batch_response(&results)).unwrap();
+        assert_eq!(decoded, results);
+
+        assert_eq!(decoded[0].clone().into_result().unwrap(), Bytes::from_static(b"world"));
+        let error = decoded[1].clone().into_result().unwrap_err();
+        assert_eq!(error.status, 404);
+        assert_eq!(error.title, "Method not found");
+    }
+
+    #[test]
+    fn test_malformed_envelopes() {
+        let body = encode_batch_request(&[BatchEntry::new("a.B", "C", Bytes::from_static(b"xyz"))]);
+        assert_eq!(decode_batch_request(body.slice(..body.len() - 1)), Err(BatchError::Truncated));
+
+        let mut padded = BytesMut::from(&body[..]);
+        padded.extend_from_slice(b"!!");
+        assert_eq!(decode_batch_request(padded.freeze()), Err(BatchError::TrailingBytes(2)));
+
+        assert_eq!(decode_batch_request(Bytes::new()), Err(BatchError::Truncated));
+    }
+}
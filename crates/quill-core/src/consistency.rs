@@ -0,0 +1,63 @@
+//! Consistency tokens for read-your-writes across replicas
+//!
+//! This module provides:
+//! - [`ConsistencyToken`], an ordered position in a replicated log
+//! - Its encoding in [`CONSISTENCY_TOKEN_HEADER`]
+//!
+//! A replica answering a write attaches the token of that write, e.g. the
+//! index of the log entry it committed. Clients echo the latest token they
+//! have seen on later calls, so a replica serving a read can wait until it
+//! has applied at least that position before answering. Tokens only grow:
+//! of two tokens, the larger one covers every write the smaller one does.
+
+use std::fmt;
+
+/// Header carrying a consistency token in both directions
+pub const CONSISTENCY_TOKEN_HEADER: &str = "quill-consistency-token";
+
+/// Position in a replicated log a call must observe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConsistencyToken(u64);
+
+impl ConsistencyToken {
+    /// Token for log position `position`
+    pub fn new(position: u64) -> Self {
+        Self(position)
+    }
+
+    /// Log position of the token
+    pub fn position(&self) -> u64 {
+        self.0
+    }
+
+    /// Format as a [`CONSISTENCY_TOKEN_HEADER`] value
+    pub fn to_header_value(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Parse a [`CONSISTENCY_TOKEN_HEADER`] value
+    pub fn from_header_value(value: &str) -> Option<Self> {
+        value.trim().parse().ok().map(Self)
+    }
+}
+
+impl fmt::Display for ConsistencyToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_value_roundtrip() {
+        let token = ConsistencyToken::new(42);
+        assert_eq!(token.to_header_value(), "42");
+        assert_eq!(ConsistencyToken::from_header_value(" 42 "), Some(token));
+        assert_eq!(ConsistencyToken::from_header_value("-1"), None);
+        assert_eq!(ConsistencyToken::from_header_value("abc"), None);
+        assert!(ConsistencyToken::new(7) < token);
+    }
+}
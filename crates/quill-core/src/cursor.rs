@@ -0,0 +1,87 @@
+//! Stream cursors for resumable list-style streams
+//!
+//! This module provides:
+//! - Opaque cursors marking a position in a server stream
+//! - Their encoding in the resume token header
+//!
+//! A server streaming a long listing interleaves CURSOR frames with its
+//! messages, each carrying the cursor after the last message sent. A client
+//! that persists the latest cursor can later restart the call from that
+//! point: the cursor travels back in [`RESUME_TOKEN_HEADER`], the same
+//! header failover uses to hand a resume token to a standby, so cursor
+//! streams resume transparently across failovers too.
+
+use bytes::Bytes;
+use std::fmt;
+
+/// Header carrying the resume token (hex-encoded) on re-issued requests
+pub const RESUME_TOKEN_HEADER: &str = "quill-resume-token";
+
+/// Default number of messages between two cursor frames
+pub const DEFAULT_CURSOR_INTERVAL: usize = 100;
+
+/// Opaque position in a server stream
+///
+/// The server chooses the encoding, e.g. the last key sent; clients only
+/// store cursors and hand them back.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct StreamCursor(Bytes);
+
+impl StreamCursor {
+    /// Wrap cursor bytes
+    pub fn new(cursor: impl Into<Bytes>) -> Self {
+        Self(cursor.into())
+    }
+
+    /// Cursor bytes
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.0
+    }
+
+    /// Take the cursor bytes
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+
+    /// Format as a [`RESUME_TOKEN_HEADER`] value (lowercase hex)
+    pub fn to_header_value(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Parse a [`RESUME_TOKEN_HEADER`] value
+    pub fn from_header_value(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.len() % 2 != 0 {
+            return None;
+        }
+        let bytes = (0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        Some(Self(Bytes::from(bytes)))
+    }
+}
+
+impl fmt::Debug for StreamCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "StreamCursor({})", self.to_header_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_value_roundtrip() {
+        let cursor = StreamCursor::new(Bytes::from_static(b"\x00key-42\xff"));
+        let value = cursor.to_header_value();
+        assert_eq!(value, "006b65792d3432ff");
+        assert_eq!(StreamCursor::from_header_value(&value), Some(cursor));
+        assert_eq!(StreamCursor::from_header_value(""), Some(StreamCursor::new(Bytes::new())));
+
+        assert!(StreamCursor::from_header_value("abc").is_none());
+        assert!(StreamCursor::from_header_value("zz").is_none());
+        assert!(StreamCursor::from_header_value("é").is_none());
+    }
+}
@@ -0,0 +1,94 @@
+//! Call deadlines propagated from client to server
+//!
+//! This module provides:
+//! - The header carrying a call's timeout to the server
+//! - [`Deadline`], the point in time a call must finish by
+//!
+//! The client sends the time it has left, not an absolute time, so clock
+//! skew between the two machines does not matter. The server turns the
+//! timeout back into a deadline when the request arrives; network latency
+//! makes that deadline slightly later than the client's, so the client
+//! always gives up first.
+
+use std::time::{Duration, Instant};
+
+/// Header carrying the call's timeout in whole milliseconds
+pub const TIMEOUT_HEADER: &str = "quill-timeout-ms";
+
+/// Longest timeout honoured, so deadlines never overflow `Instant`
+const MAX_TIMEOUT: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Point in time by which a call must finish
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// Deadline `timeout` from now
+    pub fn after(timeout: Duration) -> Self {
+        Self(Instant::now() + timeout.min(MAX_TIMEOUT))
+    }
+
+    /// Deadline at `instant`
+    pub fn at(instant: Instant) -> Self {
+        Self(instant)
+    }
+
+    /// The instant of the deadline
+    pub fn instant(&self) -> Instant {
+        self.0
+    }
+
+    /// Time left until the deadline, zero once it passed
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    /// Whether the deadline has passed
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.0
+    }
+
+    /// Encode the time left for [`TIMEOUT_HEADER`]
+    ///
+    /// Rounds up, so a deadline with time left never encodes as zero.
+    pub fn to_header_value(&self) -> String {
+        let remaining = self.remaining();
+        let millis = remaining.as_millis() + u128::from(remaining.subsec_nanos() % 1_000_000 != 0);
+        millis.to_string()
+    }
+
+    /// Deadline from a [`TIMEOUT_HEADER`] value, counted from now
+    pub fn from_header_value(value: &str) -> Option<Self> {
+        let millis: u64 = value.trim().parse().ok()?;
+        Some(Self::after(Duration::from_millis(millis)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_round_trip() {
+        let deadline = Deadline::after(Duration::from_millis(1500));
+        let value = deadline.to_header_value();
+        let millis: u64 = value.parse().unwrap();
+        assert!((1490..=1500).contains(&millis), "{}", millis);
+
+        let parsed = Deadline::from_header_value(&value).unwrap();
+        assert!(parsed.remaining() <= Duration::from_millis(1500));
+        assert!(!parsed.is_expired());
+
+        assert!(Deadline::from_header_value("soon").is_none());
+        assert!(Deadline::from_header_value("-5").is_none());
+        assert!(Deadline::from_header_value(&u64::MAX.to_string()).is_some());
+    }
+
+    #[test]
+    fn test_expired_deadline() {
+        let deadline = Deadline::after(Duration::ZERO);
+        assert!(deadline.is_expired());
+        assert_eq!(deadline.remaining(), Duration::ZERO);
+        assert_eq!(deadline.to_header_value(), "0");
+    }
+}
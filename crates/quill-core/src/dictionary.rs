@@ -0,0 +1,271 @@
+//! Compression dictionaries for small messages
+//!
+//! This module provides:
+//! - zstd dictionaries identified by an id and a content hash
+//! - Training a dictionary from recorded payload samples
+//! - Compression and decompression with a dictionary
+//! - The capability a server advertises when it offers a dictionary
+//!
+//! Small JSON or protobuf messages barely compress on their own because
+//! each one is too short to build up a useful history. A dictionary
+//! trained on representative payloads primes the compressor with their
+//! common structure. A server advertises its dictionary on every response
+//! with [`DICTIONARY_CAPABILITY_HEADER`]; clients fetch it once through the
+//! built-in [`DICTIONARY_PATH`] method, check it against the advertised
+//! hash, and from then on name it in [`DICTIONARY_HEADER`] on requests
+//! compressed with it. The same header on a request tells the server the
+//! client can decompress responses with that dictionary.
+
+use bytes::Bytes;
+use ring::digest::{digest, SHA256};
+use std::fmt;
+use std::io::{Read, Write};
+
+/// Header naming the dictionary a zstd body was compressed with
+pub const DICTIONARY_HEADER: &str = "quill-dictionary";
+
+/// Response header advertising the server's dictionary
+pub const DICTIONARY_CAPABILITY_HEADER: &str = "quill-accept-dictionary";
+
+/// Service name of the built-in dictionary fetch RPC
+pub const DICTIONARY_SERVICE: &str = "quill.dictionary.v1.Dictionary";
+
+/// Method name of the built-in dictionary fetch RPC
+pub const DICTIONARY_METHOD: &str = "Fetch";
+
+/// Full route path of the built-in dictionary fetch RPC
+pub const DICTIONARY_PATH: &str = "quill.dictionary.v1.Dictionary/Fetch";
+
+/// Default maximum size of a trained dictionary (16 KiB)
+pub const DEFAULT_DICTIONARY_SIZE: usize = 16 * 1024;
+
+/// Compression dictionary errors
+#[derive(Debug, thiserror::Error)]
+pub enum DictionaryError {
+    #[error("Dictionary training failed: {0}")]
+    Training(String),
+
+    #[error("Compression failed: {0}")]
+    Compress(String),
+
+    #[error("Decompression failed: {0}")]
+    Decompress(String),
+
+    #[error("Dictionary {id} does not match hash {expected}")]
+    HashMismatch { id: u32, expected: String },
+}
+
+/// A zstd dictionary shared by client and server
+#[derive(Clone, PartialEq, Eq)]
+pub struct CompressionDictionary {
+    id: u32,
+    hash: String,
+    data: Bytes,
+}
+
+impl CompressionDictionary {
+    /// Wrap dictionary bytes under `id`
+    ///
+    /// Any bytes work as a dictionary; trained ones compress best.
+    pub fn new(id: u32, data: impl Into<Bytes>) -> Self {
+        let data = data.into();
+        Self {
+            id,
+            hash: content_hash(&data),
+            data,
+        }
+    }
+
+    /// Train a dictionary of at most `max_size` bytes from payload samples
+    ///
+    /// zstd needs many samples to train on; a few hundred representative
+    /// messages are a reasonable minimum.
+    pub fn train<S: AsRef<[u8]>>(id: u32, samples: &[S], max_size: usize) -> Result<Self, DictionaryError> {
+        let data = zstd::dict::from_samples(samples, max_size)
+            .map_err(|e| DictionaryError::Training(e.to_string()))?;
+        Ok(Self::new(id, data))
+    }
+
+    /// Wrap dictionary bytes received for `capability`, checking their hash
+    pub fn verified(capability: &DictionaryCapability, data: impl Into<Bytes>) -> Result<Self, DictionaryError> {
+        let dictionary = Self::new(capability.id, data);
+        if dictionary.hash != capability.hash {
+            return Err(DictionaryError::HashMismatch {
+                id: capability.id,
+                expected: capability.hash.clone(),
+            });
+        }
+        Ok(dictionary)
+    }
+
+    /// Identifier of this dictionary
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Hex-encoded SHA-256 of the dictionary bytes
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Dictionary bytes
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Capability advertising this dictionary
+    pub fn capability(&self) -> DictionaryCapability {
+        DictionaryCapability {
+            id: self.id,
+            hash: self.hash.clone(),
+        }
+    }
+
+    /// Compress `data` with this dictionary
+    pub fn compress(&self, data: &[u8], level: i32) -> Result<Bytes, DictionaryError> {
+        let mut encoder = zstd::stream::write::Encoder::with_dictionary(Vec::new(), level, &self.data)
+            .map_err(|e| DictionaryError::Compress(e.to_string()))?;
+        encoder
+            .write_all(data)
+            .map_err(|e| DictionaryError::Compress(e.to_string()))?;
+        encoder
+            .finish()
+            .map(Bytes::from)
+            .map_err(|e| DictionaryError::Compress(e.to_string()))
+    }
+
+    /// Decompress `data` compressed with this dictionary
+    pub fn decompress(&self, data: &[u8]) -> Result<Bytes, DictionaryError> {
+        let mut decoder = zstd::stream::read::Decoder::with_dictionary(data, &self.data)
+            .map_err(|e| DictionaryError::Decompress(e.to_string()))?;
+        let mut decompressed = Vec::new();
+        decoder
+            .read_to_end(&mut decompressed)
+            .map_err(|e| DictionaryError::Decompress(e.to_string()))?;
+        Ok(Bytes::from(decompressed))
+    }
+}
+
+impl fmt::Debug for CompressionDictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressionDictionary")
+            .field("id", &self.id)
+            .field("hash", &self.hash)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// Dictionary a server offers, as advertised in [`DICTIONARY_CAPABILITY_HEADER`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictionaryCapability {
+    /// Identifier of the dictionary
+    pub id: u32,
+    /// Hex-encoded SHA-256 of the dictionary bytes
+    pub hash: String,
+}
+
+impl DictionaryCapability {
+    /// Format as a header value: `id=<id>; hash=<hex>`
+    pub fn to_header_value(&self) -> String {
+        format!("id={}; hash={}", self.id, self.hash)
+    }
+
+    /// Parse a capability header value
+    pub fn from_header_value(value: &str) -> Option<Self> {
+        let mut id = None;
+        let mut hash = None;
+        for part in value.split(';') {
+            let (key, val) = part.trim().split_once('=')?;
+            match key {
+                "id" => id = Some(val.parse().ok()?),
+                "hash" => hash = Some(val.to_string()),
+                _ => {}
+            }
+        }
+        Some(Self {
+            id: id?,
+            hash: hash.filter(|hash| !hash.is_empty())?,
+        })
+    }
+}
+
+/// Parse a [`DICTIONARY_HEADER`] value into a dictionary id
+pub fn parse_dictionary_id(value: &str) -> Option<u32> {
+    value.trim().parse().ok()
+}
+
+fn content_hash(data: &[u8]) -> String {
+    digest(&SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<Vec<u8>> {
+        (0..1000)
+            .map(|i| {
+                format!(
+                    r#"{{"user_id":{},"name":"user-{}","email":"user{}@example.com","active":{},"roles":["reader","writer"]}}"#,
+                    i,
+                    i * 7,
+                    i % 13,
+                    i % 2 == 0
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_trained_dictionary_shrinks_small_messages() {
+        let samples = samples();
+        let dictionary = CompressionDictionary::train(1, &samples, DEFAULT_DICTIONARY_SIZE).unwrap();
+        let message = br#"{"user_id":4242,"name":"user-99","email":"user5@example.com","active":true,"roles":["reader","writer"]}"#;
+
+        let plain = zstd::encode_all(&message[..], 3).unwrap();
+        let compressed = dictionary.compress(message, 3).unwrap();
+        assert!(compressed.len() * 2 < plain.len(), "{} vs {}", compressed.len(), plain.len());
+        assert_eq!(dictionary.decompress(&compressed).unwrap(), Bytes::from_static(message));
+    }
+
+    #[test]
+    fn test_decompress_requires_matching_dictionary() {
+        let dictionary = CompressionDictionary::new(1, b"some shared message structure".to_vec());
+        let other = CompressionDictionary::new(2, b"entirely different content".to_vec());
+        let message = Bytes::from_static(b"some shared message structure, again");
+        let compressed = dictionary.compress(&message, 3).unwrap();
+
+        assert_ne!(other.decompress(&compressed).ok(), Some(message.clone()));
+        assert_eq!(dictionary.decompress(&compressed).unwrap(), message);
+    }
+
+    #[test]
+    fn test_verified_checks_hash() {
+        let dictionary = CompressionDictionary::new(7, b"dictionary bytes".to_vec());
+        let capability = dictionary.capability();
+        assert_eq!(capability.hash.len(), 64);
+
+        assert_eq!(CompressionDictionary::verified(&capability, b"dictionary bytes".to_vec()).unwrap(), dictionary);
+        assert!(matches!(
+            CompressionDictionary::verified(&capability, b"tampered bytes".to_vec()),
+            Err(DictionaryError::HashMismatch { id: 7, .. })
+        ));
+    }
+
+    #[test]
+    fn test_capability_header_roundtrip() {
+        let capability = CompressionDictionary::new(3, b"abc".to_vec()).capability();
+        let parsed = DictionaryCapability::from_header_value(&capability.to_header_value()).unwrap();
+        assert_eq!(parsed, capability);
+
+        assert!(DictionaryCapability::from_header_value("id=3").is_none());
+        assert!(DictionaryCapability::from_header_value("id=x; hash=abc").is_none());
+        assert_eq!(parse_dictionary_id(" 3 "), Some(3));
+    }
+}
@@ -0,0 +1,535 @@
+//! Envelope encryption of messages
+//!
+//! This module provides:
+//! - A key provider trait for KMS-backed data keys
+//! - An in-process key provider for tests and development
+//! - Sealing of whole messages or selected fields with AES-256-GCM
+//! - The `quill-envelope` header describing a sealed message
+//!
+//! TLS protects a message only up to the first hop that terminates it,
+//! such as a relay or gateway. With envelope encryption the client seals
+//! sensitive data under a data key that only the KMS can unwrap, so
+//! intermediaries forward ciphertext and only the destination server reads
+//! it. The wrapped data key travels in the `quill-envelope` header.
+//!
+//! Sealing selected fields works on the protobuf wire format: the payload
+//! of each listed top-level length-delimited field (`string`, `bytes` or an
+//! embedded message) is replaced by its ciphertext, and all other fields
+//! stay readable for routing.
+
+use crate::error::QuillError;
+use bytes::Bytes;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Header carrying the envelope of a sealed message
+pub const ENVELOPE_HEADER: &str = "quill-envelope";
+
+/// Length of a data key in bytes (AES-256)
+pub const DATA_KEY_LEN: usize = 32;
+
+/// Envelope encryption errors
+#[derive(Debug, thiserror::Error)]
+pub enum EnvelopeError {
+    #[error("Key provider error: {0}")]
+    KeyProvider(String),
+
+    #[error("Unknown master key: {0}")]
+    UnknownKey(String),
+
+    #[error("Invalid envelope header: {0}")]
+    InvalidHeader(String),
+
+    #[error("Malformed message: {0}")]
+    Malformed(String),
+
+    #[error("Field {0} is not length-delimited and cannot be sealed")]
+    UnsupportedField(u32),
+
+    #[error("Decryption failed")]
+    Decrypt,
+}
+
+impl From<EnvelopeError> for QuillError {
+    fn from(e: EnvelopeError) -> Self {
+        QuillError::Rpc(format!("Envelope encryption failed: {}", e))
+    }
+}
+
+/// A data key and its form wrapped by the KMS master key
+#[derive(Clone)]
+pub struct DataKey {
+    key_id: String,
+    plaintext: [u8; DATA_KEY_LEN],
+    wrapped: Vec<u8>,
+}
+
+impl DataKey {
+    /// Create a data key from its plaintext and wrapped forms
+    pub fn new(key_id: impl Into<String>, plaintext: [u8; DATA_KEY_LEN], wrapped: Vec<u8>) -> Self {
+        Self {
+            key_id: key_id.into(),
+            plaintext,
+            wrapped,
+        }
+    }
+
+    /// Identifier of the master key that wrapped this key
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// The data key wrapped by the master key
+    pub fn wrapped(&self) -> &[u8] {
+        &self.wrapped
+    }
+}
+
+impl fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataKey")
+            .field("key_id", &self.key_id)
+            .field("plaintext", &"<redacted>")
+            .field("wrapped", &self.wrapped.len())
+            .finish()
+    }
+}
+
+/// Future returned by a [`KeyProvider`]
+pub type KeyFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, EnvelopeError>> + Send + 'a>>;
+
+/// Source of data keys, typically a KMS
+pub trait KeyProvider: Send + Sync {
+    /// Generate a fresh data key wrapped by the master key `key_id`
+    fn generate_data_key<'a>(&'a self, key_id: &'a str) -> KeyFuture<'a, DataKey>;
+
+    /// Unwrap a data key previously generated under `key_id`
+    fn decrypt_data_key<'a>(&'a self, key_id: &'a str, wrapped: &'a [u8]) -> KeyFuture<'a, DataKey>;
+}
+
+/// Key provider holding master keys in process
+///
+/// Intended for tests and development; production deployments should keep
+/// master keys in a KMS.
+#[derive(Default)]
+pub struct LocalKeyProvider {
+    master_keys: HashMap<String, [u8; DATA_KEY_LEN]>,
+}
+
+impl LocalKeyProvider {
+    /// Create a provider without master keys
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a master key
+    pub fn with_key(mut self, key_id: impl Into<String>, key: [u8; DATA_KEY_LEN]) -> Self {
+        self.master_keys.insert(key_id.into(), key);
+        self
+    }
+
+    /// Add a randomly generated master key
+    pub fn with_random_key(self, key_id: impl Into<String>) -> Self {
+        let key = random_key().expect("system random source available");
+        self.with_key(key_id, key)
+    }
+
+    fn master_key(&self, key_id: &str) -> Result<&[u8; DATA_KEY_LEN], EnvelopeError> {
+        self.master_keys
+            .get(key_id)
+            .ok_or_else(|| EnvelopeError::UnknownKey(key_id.to_string()))
+    }
+}
+
+impl fmt::Debug for LocalKeyProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalKeyProvider")
+            .field("master_keys", &self.master_keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl KeyProvider for LocalKeyProvider {
+    fn generate_data_key<'a>(&'a self, key_id: &'a str) -> KeyFuture<'a, DataKey> {
+        Box::pin(async move {
+            let master = self.master_key(key_id)?;
+            let plaintext = random_key()?;
+            let wrapped = encrypt(master, key_id.as_bytes(), &plaintext)?;
+            Ok(DataKey::new(key_id, plaintext, wrapped))
+        })
+    }
+
+    fn decrypt_data_key<'a>(&'a self, key_id: &'a str, wrapped: &'a [u8]) -> KeyFuture<'a, DataKey> {
+        Box::pin(async move {
+            let master = self.master_key(key_id)?;
+            let plaintext: [u8; DATA_KEY_LEN] = decrypt(master, key_id.as_bytes(), wrapped)?
+                .try_into()
+                .map_err(|_| EnvelopeError::Decrypt)?;
+            Ok(DataKey::new(key_id, plaintext, wrapped.to_vec()))
+        })
+    }
+}
+
+/// What part of a message is sealed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeScope {
+    /// The whole message
+    Message,
+    /// The listed top-level fields (by field number)
+    Fields(Vec<u32>),
+}
+
+impl EnvelopeScope {
+    fn to_header_value(&self) -> String {
+        match self {
+            EnvelopeScope::Message => "message".to_string(),
+            EnvelopeScope::Fields(fields) => {
+                let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+                format!("fields:{}", fields.join(","))
+            }
+        }
+    }
+
+    fn from_header_value(value: &str) -> Result<Self, EnvelopeError> {
+        if value == "message" {
+            return Ok(EnvelopeScope::Message);
+        }
+        let fields = value
+            .strip_prefix("fields:")
+            .ok_or_else(|| EnvelopeError::InvalidHeader(format!("unknown scope '{}'", value)))?;
+        fields
+            .split(',')
+            .map(|f| {
+                f.trim()
+                    .parse::<u32>()
+                    .map_err(|_| EnvelopeError::InvalidHeader(format!("invalid field number '{}'", f)))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(EnvelopeScope::Fields)
+    }
+}
+
+/// Contents of the `quill-envelope` header
+///
+/// Format: `v1; key=<key id>; dek=<hex wrapped data key>; scope=<scope>`,
+/// where scope is `message` or `fields:<n>,<n>...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeHeader {
+    /// Master key identifier
+    pub key_id: String,
+    /// Wrapped data key
+    pub wrapped_key: Vec<u8>,
+    /// What part of the message is sealed
+    pub scope: EnvelopeScope,
+}
+
+impl EnvelopeHeader {
+    /// Describe a message sealed with `key`
+    pub fn new(key: &DataKey, scope: EnvelopeScope) -> Self {
+        Self {
+            key_id: key.key_id.clone(),
+            wrapped_key: key.wrapped.clone(),
+            scope,
+        }
+    }
+
+    /// Encode as a header value
+    pub fn to_header_value(&self) -> String {
+        format!(
+            "v1; key={}; dek={}; scope={}",
+            self.key_id,
+            to_hex(&self.wrapped_key),
+            self.scope.to_header_value()
+        )
+    }
+
+    /// Parse a header value
+    pub fn from_header_value(value: &str) -> Result<Self, EnvelopeError> {
+        let mut parts = value.split(';').map(str::trim);
+        if parts.next() != Some("v1") {
+            return Err(EnvelopeError::InvalidHeader("unsupported version".to_string()));
+        }
+
+        let (mut key_id, mut wrapped_key, mut scope) = (None, None, None);
+        for part in parts {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| EnvelopeError::InvalidHeader(format!("invalid parameter '{}'", part)))?;
+            match name {
+                "key" => key_id = Some(value.to_string()),
+                "dek" => wrapped_key = Some(from_hex(value)?),
+                "scope" => scope = Some(EnvelopeScope::from_header_value(value)?),
+                _ => {}
+            }
+        }
+
+        let missing = |name: &str| EnvelopeError::InvalidHeader(format!("missing {}", name));
+        Ok(Self {
+            key_id: key_id.ok_or_else(|| missing("key"))?,
+            wrapped_key: wrapped_key.ok_or_else(|| missing("dek"))?,
+            scope: scope.ok_or_else(|| missing("scope"))?,
+        })
+    }
+}
+
+/// Seal `scope` of an encoded message with `key`
+pub fn seal(key: &DataKey, scope: &EnvelopeScope, message: &[u8]) -> Result<Bytes, EnvelopeError> {
+    match scope {
+        EnvelopeScope::Message => encrypt(&key.plaintext, &message_aad(), message).map(Bytes::from),
+        EnvelopeScope::Fields(fields) => rewrite_fields(message, fields, |field, payload| {
+            encrypt(&key.plaintext, &field_aad(field), payload)
+        }),
+    }
+}
+
+/// Open `scope` of a message sealed with `key`
+pub fn open(key: &DataKey, scope: &EnvelopeScope, message: &[u8]) -> Result<Bytes, EnvelopeError> {
+    match scope {
+        EnvelopeScope::Message => decrypt(&key.plaintext, &message_aad(), message).map(Bytes::from),
+        EnvelopeScope::Fields(fields) => rewrite_fields(message, fields, |field, payload| {
+            decrypt(&key.plaintext, &field_aad(field), payload)
+        }),
+    }
+}
+
+fn message_aad() -> Vec<u8> {
+    b"quill-envelope/v1/message".to_vec()
+}
+
+fn field_aad(field: u32) -> Vec<u8> {
+    format!("quill-envelope/v1/field/{}", field).into_bytes()
+}
+
+fn random_key() -> Result<[u8; DATA_KEY_LEN], EnvelopeError> {
+    let mut key = [0u8; DATA_KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| EnvelopeError::KeyProvider("random source unavailable".to_string()))?;
+    Ok(key)
+}
+
+/// AES-256-GCM encrypt; output is nonce || ciphertext || tag
+fn encrypt(key: &[u8; DATA_KEY_LEN], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, EnvelopeError> {
+    let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).map_err(|_| EnvelopeError::Decrypt)?);
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| EnvelopeError::KeyProvider("random source unavailable".to_string()))?;
+
+    let mut sealed = plaintext.to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad), &mut sealed)
+        .map_err(|_| EnvelopeError::Decrypt)?;
+
+    let mut out = Vec::with_capacity(NONCE_LEN + sealed.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Inverse of [`encrypt`]
+fn decrypt(key: &[u8; DATA_KEY_LEN], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, EnvelopeError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(EnvelopeError::Decrypt);
+    }
+    let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).map_err(|_| EnvelopeError::Decrypt)?);
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| EnvelopeError::Decrypt)?;
+
+    let mut buf = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(aad), &mut buf)
+        .map_err(|_| EnvelopeError::Decrypt)?;
+    Ok(plaintext.to_vec())
+}
+
+/// Replace the payload of listed length-delimited fields with `f(field, payload)`
+fn rewrite_fields(
+    message: &[u8],
+    fields: &[u32],
+    mut f: impl FnMut(u32, &[u8]) -> Result<Vec<u8>, EnvelopeError>,
+) -> Result<Bytes, EnvelopeError> {
+    let mut out = Vec::with_capacity(message.len());
+    let mut pos = 0;
+
+    while pos < message.len() {
+        let start = pos;
+        let tag = read_varint(message, &mut pos)?;
+        let field = (tag >> 3) as u32;
+        let wire_type = tag & 0x7;
+        let listed = fields.contains(&field);
+
+        match wire_type {
+            // Length-delimited
+            2 => {
+                let len_start = pos;
+                let len = read_varint(message, &mut pos)? as usize;
+                let end = pos
+                    .checked_add(len)
+                    .filter(|end| *end <= message.len())
+                    .ok_or_else(|| EnvelopeError::Malformed(format!("field {} overruns message", field)))?;
+                if listed {
+                    let payload = f(field, &message[pos..end])?;
+                    out.extend_from_slice(&message[start..len_start]);
+                    write_varint(&mut out, payload.len() as u64);
+                    out.extend_from_slice(&payload);
+                } else {
+                    out.extend_from_slice(&message[start..end]);
+                }
+                pos = end;
+                continue;
+            }
+            _ if listed => return Err(EnvelopeError::UnsupportedField(field)),
+            // Varint
+            0 => {
+                read_varint(message, &mut pos)?;
+            }
+            // 64-bit
+            1 => pos += 8,
+            // 32-bit
+            5 => pos += 4,
+            _ => {
+                return Err(EnvelopeError::Malformed(format!(
+                    "unsupported wire type {} for field {}",
+                    wire_type, field
+                )))
+            }
+        }
+
+        if pos > message.len() {
+            return Err(EnvelopeError::Malformed(format!("field {} overruns message", field)));
+        }
+        out.extend_from_slice(&message[start..pos]);
+    }
+
+    Ok(Bytes::from(out))
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64, EnvelopeError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf
+            .get(*pos)
+            .ok_or_else(|| EnvelopeError::Malformed("truncated varint".to_string()))?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(EnvelopeError::Malformed("varint too long".to_string()))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(value: &str) -> Result<Vec<u8>, EnvelopeError> {
+    if value.len() % 2 != 0 {
+        return Err(EnvelopeError::InvalidHeader("odd-length hex".to_string()));
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&value[i..i + 2], 16)
+                .map_err(|_| EnvelopeError::InvalidHeader("invalid hex".to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a message with field 1 = "alice" (string), 2 = 42 (varint), 3 = card (string)
+    fn encoded_message() -> Vec<u8> {
+        let mut msg = vec![0x0a, 5];
+        msg.extend_from_slice(b"alice");
+        msg.extend_from_slice(&[0x10, 42]);
+        msg.extend_from_slice(&[0x1a, 16]);
+        msg.extend_from_slice(b"4242424242424242");
+        msg
+    }
+
+    #[tokio::test]
+    async fn test_data_key_roundtrip_through_provider() {
+        let provider = LocalKeyProvider::new().with_random_key("payments");
+        let key = provider.generate_data_key("payments").await.unwrap();
+        let unwrapped = provider.decrypt_data_key("payments", key.wrapped()).await.unwrap();
+        assert_eq!(unwrapped.plaintext, key.plaintext);
+
+        assert!(matches!(
+            provider.generate_data_key("other").await,
+            Err(EnvelopeError::UnknownKey(_))
+        ));
+        assert!(!format!("{:?}", key).contains(&format!("{:?}", key.plaintext)));
+    }
+
+    #[tokio::test]
+    async fn test_seal_whole_message() {
+        let provider = LocalKeyProvider::new().with_random_key("k");
+        let key = provider.generate_data_key("k").await.unwrap();
+        let message = encoded_message();
+
+        let sealed = seal(&key, &EnvelopeScope::Message, &message).unwrap();
+        assert!(!sealed.windows(5).any(|w| w == b"alice"));
+        assert_eq!(open(&key, &EnvelopeScope::Message, &sealed).unwrap(), message);
+
+        let mut tampered = sealed.to_vec();
+        tampered[NONCE_LEN] ^= 1;
+        assert!(matches!(
+            open(&key, &EnvelopeScope::Message, &tampered),
+            Err(EnvelopeError::Decrypt)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_seal_selected_fields() {
+        let provider = LocalKeyProvider::new().with_random_key("k");
+        let key = provider.generate_data_key("k").await.unwrap();
+        let message = encoded_message();
+        let scope = EnvelopeScope::Fields(vec![3]);
+
+        let sealed = seal(&key, &scope, &message).unwrap();
+        // Unlisted fields stay readable
+        assert_eq!(&sealed[..9], &message[..9]);
+        assert!(!sealed.windows(16).any(|w| w == b"4242424242424242"));
+        assert_eq!(open(&key, &scope, &sealed).unwrap(), message);
+
+        // Fields cannot be moved to another field number
+        let moved = EnvelopeScope::Fields(vec![1]);
+        let sealed_first = seal(&key, &moved, &message).unwrap();
+        let mut swapped = sealed_first.to_vec();
+        swapped[0] = 0x1a;
+        assert!(open(&key, &EnvelopeScope::Fields(vec![3]), &swapped).is_err());
+
+        assert!(matches!(
+            seal(&key, &EnvelopeScope::Fields(vec![2]), &message),
+            Err(EnvelopeError::UnsupportedField(2))
+        ));
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let key = DataKey::new("arn:kms:key/1", [7; DATA_KEY_LEN], vec![0xde, 0xad, 0x01]);
+        let header = EnvelopeHeader::new(&key, EnvelopeScope::Fields(vec![1, 4]));
+        let value = header.to_header_value();
+        assert_eq!(value, "v1; key=arn:kms:key/1; dek=dead01; scope=fields:1,4");
+        assert_eq!(EnvelopeHeader::from_header_value(&value).unwrap(), header);
+
+        assert!(EnvelopeHeader::from_header_value("v2; key=a; dek=00; scope=message").is_err());
+        assert!(EnvelopeHeader::from_header_value("v1; key=a; scope=message").is_err());
+        assert!(EnvelopeHeader::from_header_value("v1; key=a; dek=0; scope=message").is_err());
+    }
+}
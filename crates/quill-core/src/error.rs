@@ -3,6 +3,17 @@
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Problem type of a stream cancelled because no frame was exchanged in time
+pub const STREAM_IDLE_TIMEOUT_TYPE: &str = "urn:quill:stream-idle-timeout";
+
+/// Problem type of a call that did not finish before its deadline
+pub const DEADLINE_EXCEEDED_TYPE: &str = "urn:quill:deadline-exceeded";
+
+/// Problem type of a call cancelled by the client
+pub const CANCELLED_TYPE: &str = "urn:quill:cancelled";
 
 /// Quill error type
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +31,215 @@ pub enum QuillError {
     ProblemDetails(ProblemDetails),
 }
 
+impl QuillError {
+    /// The stable error code of this error
+    ///
+    /// Transport errors are [`ErrorCode::Unavailable`], framing errors
+    /// [`ErrorCode::Internal`] and untyped RPC errors [`ErrorCode::Unknown`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            QuillError::Rpc(_) => ErrorCode::Unknown,
+            QuillError::Transport(_) => ErrorCode::Unavailable,
+            QuillError::Framing(_) => ErrorCode::Internal,
+            QuillError::ProblemDetails(pd) => pd.code(),
+        }
+    }
+
+    /// Whether the call may be safely retried, judged by its error code
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+
+    /// Whether a stream was cancelled for being idle, by either side
+    pub fn is_stream_idle_timeout(&self) -> bool {
+        matches!(self, QuillError::ProblemDetails(pd) if pd.type_uri == STREAM_IDLE_TIMEOUT_TYPE)
+    }
+
+    /// Whether a call ran past its deadline, on either side
+    pub fn is_deadline_exceeded(&self) -> bool {
+        matches!(self, QuillError::ProblemDetails(pd) if pd.type_uri == DEADLINE_EXCEEDED_TYPE)
+    }
+
+    /// Whether the client cancelled the call
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, QuillError::ProblemDetails(pd) if pd.type_uri == CANCELLED_TYPE)
+    }
+}
+
+/// Stable, transport-independent error codes
+///
+/// Codes mirror the canonical gRPC codes so they map losslessly through the
+/// gRPC bridge, and are carried in Problem Details as `quill_code`. Unknown
+/// code names from newer peers parse as [`ErrorCode::Unknown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "&'static str", from = "String")]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// The caller cancelled the call
+    Cancelled,
+    /// An error with no more specific code
+    Unknown,
+    /// The request is malformed and will fail however often it is sent
+    InvalidArgument,
+    /// The call did not finish before its deadline
+    DeadlineExceeded,
+    /// The requested entity does not exist
+    NotFound,
+    /// The entity the caller tried to create already exists
+    AlreadyExists,
+    /// The caller is not allowed to make the call
+    PermissionDenied,
+    /// A quota or rate limit was hit
+    ResourceExhausted,
+    /// The system is not in a state the call requires
+    FailedPrecondition,
+    /// The call was aborted by a concurrency conflict
+    Aborted,
+    /// The request addressed a range past the valid one
+    OutOfRange,
+    /// The method is not implemented
+    Unimplemented,
+    /// An invariant of the server was broken
+    Internal,
+    /// The service is temporarily unreachable or overloaded
+    Unavailable,
+    /// Data was lost or corrupted
+    DataLoss,
+    /// The caller has no valid credentials
+    Unauthenticated,
+}
+
+impl ErrorCode {
+    /// Every error code, in canonical gRPC order
+    pub const ALL: [ErrorCode; 16] = [
+        Self::Cancelled,
+        Self::Unknown,
+        Self::InvalidArgument,
+        Self::DeadlineExceeded,
+        Self::NotFound,
+        Self::AlreadyExists,
+        Self::PermissionDenied,
+        Self::ResourceExhausted,
+        Self::FailedPrecondition,
+        Self::Aborted,
+        Self::OutOfRange,
+        Self::Unimplemented,
+        Self::Internal,
+        Self::Unavailable,
+        Self::DataLoss,
+        Self::Unauthenticated,
+    ];
+
+    /// Get the code name, e.g. `UNAVAILABLE`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cancelled => "CANCELLED",
+            Self::Unknown => "UNKNOWN",
+            Self::InvalidArgument => "INVALID_ARGUMENT",
+            Self::DeadlineExceeded => "DEADLINE_EXCEEDED",
+            Self::NotFound => "NOT_FOUND",
+            Self::AlreadyExists => "ALREADY_EXISTS",
+            Self::PermissionDenied => "PERMISSION_DENIED",
+            Self::ResourceExhausted => "RESOURCE_EXHAUSTED",
+            Self::FailedPrecondition => "FAILED_PRECONDITION",
+            Self::Aborted => "ABORTED",
+            Self::OutOfRange => "OUT_OF_RANGE",
+            Self::Unimplemented => "UNIMPLEMENTED",
+            Self::Internal => "INTERNAL",
+            Self::Unavailable => "UNAVAILABLE",
+            Self::DataLoss => "DATA_LOSS",
+            Self::Unauthenticated => "UNAUTHENTICATED",
+        }
+    }
+
+    /// Whether a call failing with this code may be retried unchanged
+    ///
+    /// Only codes for transient conditions are retryable: `UNAVAILABLE`,
+    /// `DEADLINE_EXCEEDED`, `RESOURCE_EXHAUSTED` and `ABORTED`.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Unavailable | Self::DeadlineExceeded | Self::ResourceExhausted | Self::Aborted
+        )
+    }
+
+    /// The HTTP status a response with this code is sent with
+    pub fn http_status(&self) -> StatusCode {
+        match self {
+            Self::Cancelled => StatusCode::from_u16(499).unwrap(), // Client Closed Request
+            Self::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidArgument => StatusCode::BAD_REQUEST,
+            Self::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::AlreadyExists => StatusCode::CONFLICT,
+            Self::PermissionDenied => StatusCode::FORBIDDEN,
+            Self::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+            Self::FailedPrecondition => StatusCode::BAD_REQUEST,
+            Self::Aborted => StatusCode::CONFLICT,
+            Self::OutOfRange => StatusCode::BAD_REQUEST,
+            Self::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Unauthenticated => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    /// The code implied by an HTTP status, for problems without a `quill_code`
+    pub fn from_http_status(status: u16) -> Self {
+        match status {
+            400 | 413 | 422 => Self::InvalidArgument,
+            401 => Self::Unauthenticated,
+            403 => Self::PermissionDenied,
+            404 => Self::NotFound,
+            408 => Self::DeadlineExceeded,
+            409 => Self::AlreadyExists,
+            412 => Self::FailedPrecondition,
+            416 => Self::OutOfRange,
+            429 => Self::ResourceExhausted,
+            499 => Self::Cancelled,
+            500 => Self::Internal,
+            501 => Self::Unimplemented,
+            502 | 503 => Self::Unavailable,
+            504 => Self::DeadlineExceeded,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = ErrorCodeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|code| code.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ErrorCodeParseError(s.to_string()))
+    }
+}
+
+impl From<ErrorCode> for &'static str {
+    fn from(code: ErrorCode) -> Self {
+        code.as_str()
+    }
+}
+
+impl From<String> for ErrorCode {
+    fn from(name: String) -> Self {
+        name.parse().unwrap_or(Self::Unknown)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown error code: {0}")]
+pub struct ErrorCodeParseError(String);
+
 /// Problem Details per RFC 7807
 /// Used for structured error responses in Quill
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,10 +269,37 @@ pub struct ProblemDetails {
     /// Quill-specific: base64-encoded protobuf bytes
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quill_proto_detail_base64: Option<String>,
+
+    /// Quill-specific: stable error code, derived from `status` when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quill_code: Option<ErrorCode>,
+
+    /// Quill-specific: redacted debug context, only sent to entitled callers
+    ///
+    /// Boxed to keep `QuillError` small on the success path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<Box<DebugContext>>,
+}
+
+/// Debug context attached to Problem Details for diagnosing failures
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugContext {
+    /// What failed: "error" or "panic"
+    pub kind: String,
+
+    /// Redacted error or panic message
+    pub message: String,
+
+    /// Redacted backtrace frames, innermost first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backtrace: Vec<String>,
 }
 
 impl ProblemDetails {
     /// Create a new Problem Details with the given status and title
+    ///
+    /// The error code is derived from `status`; use [`Self::with_code`] to
+    /// send a more specific one.
     pub fn new(status: StatusCode, title: impl Into<String>) -> Self {
         Self {
             type_uri: format!("urn:quill:error:{}", status.as_u16()),
@@ -62,15 +309,66 @@ impl ProblemDetails {
             instance: None,
             quill_proto_type: None,
             quill_proto_detail_base64: None,
+            quill_code: Some(ErrorCode::from_http_status(status.as_u16())),
+            debug: None,
         }
     }
 
+    /// Create a Problem Details for `code`, sent with its HTTP status
+    pub fn from_code(code: ErrorCode, title: impl Into<String>) -> Self {
+        Self::new(code.http_status(), title).with_code(code)
+    }
+
+    /// A `499` problem for a call the client cancelled
+    pub fn cancelled() -> Self {
+        Self {
+            type_uri: CANCELLED_TYPE.to_string(),
+            ..Self::from_code(ErrorCode::Cancelled, "Cancelled")
+        }
+        .with_detail("The call was cancelled by the client")
+    }
+
+    /// A `408` problem for a stream with no frame exchanged for `idle`
+    pub fn stream_idle_timeout(idle: Duration) -> Self {
+        Self {
+            type_uri: STREAM_IDLE_TIMEOUT_TYPE.to_string(),
+            ..Self::new(StatusCode::REQUEST_TIMEOUT, "Stream idle timeout")
+        }
+        .with_detail(format!("No frame exchanged for {:.3} seconds", idle.as_secs_f64()))
+    }
+
+    /// A `504` problem for a call that did not finish within `timeout`
+    pub fn deadline_exceeded(timeout: Duration) -> Self {
+        Self {
+            type_uri: DEADLINE_EXCEEDED_TYPE.to_string(),
+            ..Self::new(StatusCode::GATEWAY_TIMEOUT, "Deadline exceeded")
+        }
+        .with_detail(format!("Request timed out after {:.3} seconds", timeout.as_secs_f64()))
+    }
+
+    /// Set the error code
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.quill_code = Some(code);
+        self
+    }
+
+    /// The error code: `quill_code` if set, otherwise derived from `status`
+    pub fn code(&self) -> ErrorCode {
+        self.quill_code.unwrap_or_else(|| ErrorCode::from_http_status(self.status))
+    }
+
     /// Set the detail field
     pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
         self.detail = Some(detail.into());
         self
     }
 
+    /// Attach a debug context
+    pub fn with_debug(mut self, debug: DebugContext) -> Self {
+        self.debug = Some(Box::new(debug));
+        self
+    }
+
     /// Convert to JSON string
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(self)
@@ -99,5 +397,106 @@ mod tests {
         let json = pd.to_json().unwrap();
         assert!(json.contains("\"status\":404"));
         assert!(json.contains("\"title\":\"Resource not found\""));
+        assert!(!json.contains("debug"));
+    }
+
+    #[test]
+    fn test_problem_details_debug_round_trip() {
+        let pd = ProblemDetails::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+            .with_debug(DebugContext {
+                kind: "panic".to_string(),
+                message: "index out of bounds".to_string(),
+                backtrace: vec!["my_service::handler (handler.rs:42)".to_string()],
+            });
+
+        let json = pd.to_json().unwrap();
+        let parsed: ProblemDetails = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.debug, pd.debug);
+    }
+
+    #[test]
+    fn test_stream_idle_timeout_is_distinct() {
+        let pd = ProblemDetails::stream_idle_timeout(Duration::from_millis(1500));
+        assert_eq!(pd.status, 408);
+        assert_eq!(pd.detail.as_deref(), Some("No frame exchanged for 1.500 seconds"));
+
+        let parsed: ProblemDetails = serde_json::from_str(&pd.to_json().unwrap()).unwrap();
+        assert!(QuillError::ProblemDetails(parsed).is_stream_idle_timeout());
+        let other = ProblemDetails::new(StatusCode::REQUEST_TIMEOUT, "Request timeout");
+        assert!(!QuillError::ProblemDetails(other).is_stream_idle_timeout());
+
+        let deadline = QuillError::ProblemDetails(ProblemDetails::deadline_exceeded(Duration::from_secs(2)));
+        assert!(deadline.is_deadline_exceeded());
+        assert!(!deadline.is_stream_idle_timeout());
+        assert!(!QuillError::Transport("timed out".to_string()).is_stream_idle_timeout());
+    }
+
+    #[test]
+    fn test_cancelled_problem() {
+        let pd = ProblemDetails::cancelled();
+        assert_eq!(pd.status, 499);
+        assert_eq!(pd.code(), ErrorCode::Cancelled);
+
+        let parsed: ProblemDetails = serde_json::from_str(&pd.to_json().unwrap()).unwrap();
+        let error = QuillError::ProblemDetails(parsed);
+        assert!(error.is_cancelled());
+        assert!(!error.is_retryable());
+        assert!(!QuillError::Rpc("Stream cancelled by server".to_string()).is_cancelled());
+    }
+
+    #[test]
+    fn test_error_code_names_round_trip() {
+        for code in ErrorCode::ALL {
+            assert_eq!(code.as_str().parse::<ErrorCode>().unwrap(), code);
+            let json = serde_json::to_string(&code).unwrap();
+            assert_eq!(json, format!("\"{}\"", code.as_str()));
+            assert_eq!(serde_json::from_str::<ErrorCode>(&json).unwrap(), code);
+        }
+        assert!("NOPE".parse::<ErrorCode>().is_err());
+        let future: ErrorCode = serde_json::from_str("\"FROM_THE_FUTURE\"").unwrap();
+        assert_eq!(future, ErrorCode::Unknown);
+    }
+
+    #[test]
+    fn test_error_code_http_mapping() {
+        for code in ErrorCode::ALL {
+            let back = ErrorCode::from_http_status(code.http_status().as_u16());
+            match code {
+                ErrorCode::Unknown | ErrorCode::DataLoss => assert_eq!(back, ErrorCode::Internal),
+                ErrorCode::FailedPrecondition | ErrorCode::OutOfRange => {
+                    assert_eq!(back, ErrorCode::InvalidArgument)
+                }
+                ErrorCode::Aborted => assert_eq!(back, ErrorCode::AlreadyExists),
+                _ => assert_eq!(back, code),
+            }
+        }
+        assert_eq!(ErrorCode::from_http_status(502), ErrorCode::Unavailable);
+        assert_eq!(ErrorCode::from_http_status(418), ErrorCode::Unknown);
+    }
+
+    #[test]
+    fn test_problem_details_carry_code() {
+        let pd = ProblemDetails::from_code(ErrorCode::Aborted, "Write conflict");
+        assert_eq!(pd.status, 409);
+        let json = pd.to_json().unwrap();
+        assert!(json.contains("\"quill_code\":\"ABORTED\""));
+
+        // The explicit code wins over the one implied by the status
+        let parsed: ProblemDetails = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.code(), ErrorCode::Aborted);
+        assert!(QuillError::ProblemDetails(parsed).is_retryable());
+
+        // Problems from peers without codes fall back to the status
+        let legacy: ProblemDetails =
+            serde_json::from_str(r#"{"type":"about:blank","title":"Busy","status":503}"#).unwrap();
+        assert_eq!(legacy.quill_code, None);
+        assert_eq!(legacy.code(), ErrorCode::Unavailable);
+
+        let deadline = ProblemDetails::deadline_exceeded(Duration::from_secs(1));
+        assert_eq!(deadline.code(), ErrorCode::DeadlineExceeded);
+        let bad = ProblemDetails::new(StatusCode::BAD_REQUEST, "Bad request");
+        assert!(!QuillError::ProblemDetails(bad).is_retryable());
+        assert!(QuillError::Transport("reset".to_string()).is_retryable());
+        assert!(!QuillError::Framing("bad varint".to_string()).is_retryable());
     }
 }
@@ -82,6 +82,99 @@ impl Default for CreditTracker {
     }
 }
 
+// ============================================================================
+// Receiver-Granted Credits for Streaming Responses
+// ============================================================================
+
+/// Header negotiating credit-based flow control of a streaming response
+///
+/// The client sends `id=<id>; credits=<n>` with the streaming request; a
+/// server that enforces credits echoes it on the response. The client then
+/// grants more credits by posting CREDIT frames to [`FLOW_CREDIT_PATH`]
+/// with `id=<id>` in this header. A `bytes=<n>` parameter additionally
+/// asks for a byte window on tensor payloads (see [`tensor_payload_cost`]),
+/// which is echoed, possibly lowered, by servers that enforce it.
+pub const FLOW_CONTROL_HEADER: &str = "quill-flow-control";
+
+/// Service name of the built-in credit grant RPC
+pub const FLOW_CREDIT_SERVICE: &str = "quill.flow.v1.Flow";
+
+/// Method name of the built-in credit grant RPC
+pub const FLOW_CREDIT_METHOD: &str = "Credit";
+
+/// Full route path of the built-in credit grant RPC
+pub const FLOW_CREDIT_PATH: &str = "quill.flow.v1.Flow/Credit";
+
+/// Flow control parameters carried in [`FLOW_CONTROL_HEADER`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowControlHeader {
+    /// Identifier of the response stream
+    pub id: String,
+    /// Messages the server may send before waiting for a grant (absent on grants)
+    pub credits: Option<u32>,
+    /// Tensor payload bytes the server may send before waiting for a grant
+    pub bytes: Option<u64>,
+}
+
+impl FlowControlHeader {
+    /// Format as a header value: `id=<id>`, followed by `; credits=<n>` and `; bytes=<n>` if set
+    pub fn to_header_value(&self) -> String {
+        let mut value = format!("id={}", self.id);
+        if let Some(credits) = self.credits {
+            value.push_str(&format!("; credits={}", credits));
+        }
+        if let Some(bytes) = self.bytes {
+            value.push_str(&format!("; bytes={}", bytes));
+        }
+        value
+    }
+
+    /// Parse a header value
+    pub fn from_header_value(value: &str) -> Option<Self> {
+        let mut id = None;
+        let mut credits = None;
+        let mut bytes = None;
+        for part in value.split(';') {
+            let (key, val) = part.trim().split_once('=')?;
+            match key {
+                "id" => id = Some(val.to_string()),
+                "credits" => credits = Some(val.parse().ok()?),
+                "bytes" => bytes = Some(val.parse().ok()?),
+                _ => {}
+            }
+        }
+        id.filter(|id| !id.is_empty()).map(|id| Self { id, credits, bytes })
+    }
+}
+
+/// Frame type byte of a TENSOR_PAYLOAD frame
+const TENSOR_PAYLOAD_FRAME_TYPE: u8 = 0x11;
+
+/// Size of a tensor frame header: type, reserved bytes and payload length
+const TENSOR_FRAME_HEADER_SIZE: usize = 9;
+
+/// Bytes a message costs against a tensor byte window of `window` bytes
+///
+/// A message starting with a TENSOR_PAYLOAD frame costs the payload length
+/// in its header, up to half the window; other messages (metadata, token
+/// batches) are free. As receivers grant bytes back at least every half
+/// window, the sender always regains room for the next payload.
+///
+/// Only meaningful for tensor streams: a plain protobuf message may start
+/// with the same byte, so the window is negotiated per tensor method.
+pub fn tensor_payload_cost(message: &[u8], window: u64) -> Option<u64> {
+    if message.len() < TENSOR_FRAME_HEADER_SIZE || message[0] != TENSOR_PAYLOAD_FRAME_TYPE {
+        return None;
+    }
+    let len = u32::from_be_bytes([message[5], message[6], message[7], message[8]]);
+    Some((len as u64).min(max_tensor_refill(window)))
+}
+
+/// Largest number of consumed bytes a receiver may hold before granting them back
+pub fn max_tensor_refill(window: u64) -> u64 {
+    (window / 2).max(1)
+}
+
 // ============================================================================
 // Tensor Flow Control
 // ============================================================================
@@ -299,6 +392,46 @@ impl Clone for TensorCreditTracker {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_flow_control_header_round_trip() {
+        let header = FlowControlHeader {
+            id: "abc".to_string(),
+            credits: Some(16),
+            bytes: None,
+        };
+        assert_eq!(header.to_header_value(), "id=abc; credits=16");
+        assert_eq!(FlowControlHeader::from_header_value("id=abc; credits=16"), Some(header));
+
+        let tensor = FlowControlHeader {
+            id: "abc".to_string(),
+            credits: Some(16),
+            bytes: Some(65536),
+        };
+        assert_eq!(tensor.to_header_value(), "id=abc; credits=16; bytes=65536");
+        assert_eq!(FlowControlHeader::from_header_value(&tensor.to_header_value()), Some(tensor));
+
+        let grant = FlowControlHeader::from_header_value("id=abc").unwrap();
+        assert_eq!(grant.credits, None);
+        assert_eq!(grant.bytes, None);
+        assert_eq!(FlowControlHeader::from_header_value("credits=4"), None);
+        assert_eq!(FlowControlHeader::from_header_value("id=abc; credits=x"), None);
+        assert_eq!(FlowControlHeader::from_header_value("id=abc; bytes=-1"), None);
+    }
+
+    #[test]
+    fn test_tensor_payload_cost() {
+        let mut payload = vec![0x11, 0, 0, 0, 0];
+        payload.extend_from_slice(&1000u32.to_be_bytes());
+        payload.extend_from_slice(&[0; 16]);
+        assert_eq!(tensor_payload_cost(&payload, 4096), Some(1000));
+        assert_eq!(tensor_payload_cost(&payload, 1024), Some(512));
+
+        // TENSOR_META and truncated headers cost nothing
+        payload[0] = 0x10;
+        assert_eq!(tensor_payload_cost(&payload, 4096), None);
+        assert_eq!(tensor_payload_cost(&[0x11, 0, 0], 4096), None);
+    }
+
     #[test]
     fn test_credit_consumption() {
         let tracker = CreditTracker::new(5);
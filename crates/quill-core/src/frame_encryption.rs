@@ -0,0 +1,335 @@
+//! End-to-end encryption of frame payloads
+//!
+//! This module provides:
+//! - Shared keys distributed out of band
+//! - The `quill-frame-encryption` header negotiating encryption of a stream
+//! - Sealing and opening of frame payloads with XChaCha20-Poly1305
+//!
+//! TLS only protects a stream up to the first hop that terminates it. When
+//! relays or gateways sit in between, the client can encrypt the payloads
+//! of a stream's frames under a key shared with the destination server, so
+//! intermediaries forward ciphertext. Unlike [envelope encryption], which
+//! seals unary messages, this works frame by frame on streaming bodies.
+//!
+//! Each stream gets a random salt, sent in the header. The nonce of a frame
+//! is the salt followed by the frame's position in its direction, so frames
+//! that are dropped, reordered or replayed from another stream fail to
+//! open. DATA and END_STREAM payloads are sealed, with the frame flags as
+//! associated data; control frames such as CANCEL and CREDIT stay in the
+//! clear.
+//!
+//! [envelope encryption]: crate::envelope
+
+use crate::error::QuillError;
+use crate::framing::Frame;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Header negotiating frame encryption of a stream
+///
+/// The client sends it with the request; a server that opened the request
+/// and seals its response echoes it.
+pub const FRAME_ENCRYPTION_HEADER: &str = "quill-frame-encryption";
+
+/// Length of a frame encryption key in bytes
+pub const FRAME_KEY_LEN: usize = 32;
+
+/// Length of a stream's salt in bytes
+pub const STREAM_SALT_LEN: usize = 16;
+
+/// Name of the only supported algorithm in the header
+const ALGORITHM: &str = "xchacha20poly1305";
+
+/// Associated data prefix binding sealed payloads to this scheme
+const AAD_PREFIX: &[u8] = b"quill-frame/v1";
+
+/// Frame encryption errors
+#[derive(Debug, thiserror::Error)]
+pub enum FrameEncryptionError {
+    #[error("Invalid frame encryption header: {0}")]
+    InvalidHeader(String),
+
+    #[error("Unknown frame encryption key: {0}")]
+    UnknownKey(String),
+
+    #[error("Random source unavailable")]
+    Random,
+
+    #[error("Payload too large to encrypt")]
+    Encrypt,
+
+    #[error("Frame decryption failed")]
+    Decrypt,
+}
+
+impl From<FrameEncryptionError> for QuillError {
+    fn from(e: FrameEncryptionError) -> Self {
+        QuillError::Rpc(format!("Frame encryption failed: {}", e))
+    }
+}
+
+/// A key shared by the client and the destination server
+#[derive(Clone)]
+pub struct FrameKey {
+    id: String,
+    key: [u8; FRAME_KEY_LEN],
+}
+
+impl FrameKey {
+    /// Create a key from its identifier and bytes
+    pub fn new(id: impl Into<String>, key: [u8; FRAME_KEY_LEN]) -> Self {
+        Self { id: id.into(), key }
+    }
+
+    /// Create a random key, e.g. to distribute to both peers
+    pub fn random(id: impl Into<String>) -> Result<Self, FrameEncryptionError> {
+        let mut key = [0u8; FRAME_KEY_LEN];
+        SystemRandom::new().fill(&mut key).map_err(|_| FrameEncryptionError::Random)?;
+        Ok(Self::new(id, key))
+    }
+
+    /// Identifier of this key, sent in the header
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl fmt::Debug for FrameKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameKey")
+            .field("id", &self.id)
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Contents of the `quill-frame-encryption` header
+///
+/// Format: `xchacha20poly1305; key=<key id>; salt=<hex salt>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameEncryptionHeader {
+    /// Identifier of the shared key
+    pub key_id: String,
+    /// Random salt of the stream
+    pub salt: [u8; STREAM_SALT_LEN],
+}
+
+impl FrameEncryptionHeader {
+    /// Describe a new stream encrypted with `key`, with a fresh salt
+    pub fn new(key: &FrameKey) -> Result<Self, FrameEncryptionError> {
+        let mut salt = [0u8; STREAM_SALT_LEN];
+        SystemRandom::new().fill(&mut salt).map_err(|_| FrameEncryptionError::Random)?;
+        Ok(Self {
+            key_id: key.id.clone(),
+            salt,
+        })
+    }
+
+    /// Encode as a header value
+    pub fn to_header_value(&self) -> String {
+        let salt: String = self.salt.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}; key={}; salt={}", ALGORITHM, self.key_id, salt)
+    }
+
+    /// Parse a header value
+    pub fn from_header_value(value: &str) -> Result<Self, FrameEncryptionError> {
+        let invalid = |detail: &str| FrameEncryptionError::InvalidHeader(detail.to_string());
+        let mut parts = value.split(';').map(str::trim);
+        if parts.next() != Some(ALGORITHM) {
+            return Err(invalid("unsupported algorithm"));
+        }
+
+        let (mut key_id, mut salt) = (None, None);
+        for part in parts {
+            let (name, value) = part.split_once('=').ok_or_else(|| invalid("invalid parameter"))?;
+            match name {
+                "key" => key_id = Some(value.to_string()),
+                "salt" => salt = Some(parse_salt(value).ok_or_else(|| invalid("invalid salt"))?),
+                _ => {}
+            }
+        }
+        Ok(Self {
+            key_id: key_id.filter(|id| !id.is_empty()).ok_or_else(|| invalid("missing key"))?,
+            salt: salt.ok_or_else(|| invalid("missing salt"))?,
+        })
+    }
+}
+
+fn parse_salt(value: &str) -> Option<[u8; STREAM_SALT_LEN]> {
+    if value.len() != STREAM_SALT_LEN * 2 || !value.is_ascii() {
+        return None;
+    }
+    let mut salt = [0u8; STREAM_SALT_LEN];
+    for (i, byte) in salt.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(salt)
+}
+
+/// Direction of the frames a [`FrameCipher`] handles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDirection {
+    /// Frames of the request body, sent by the client
+    Request,
+    /// Frames of the response body, sent by the server
+    Response,
+}
+
+/// Seals or opens the frames of one direction of a stream
+///
+/// Frames must be sealed and opened in the order they are sent.
+pub struct FrameCipher {
+    cipher: XChaCha20Poly1305,
+    salt: [u8; STREAM_SALT_LEN],
+    direction: FrameDirection,
+    /// Position of the next sealed frame
+    sequence: AtomicU64,
+}
+
+impl FrameCipher {
+    /// Create a cipher for one direction of the stream described by `header`
+    pub fn new(key: &FrameKey, header: &FrameEncryptionHeader, direction: FrameDirection) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(&key.key.into()),
+            salt: header.salt,
+            direction,
+            sequence: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether `frame` carries a sealed payload
+    pub fn seals(frame: &Frame) -> bool {
+        frame.flags.is_data() || frame.flags.is_end_stream()
+    }
+
+    /// Seal the payload of a DATA or END_STREAM frame; other frames are returned as-is
+    pub fn seal(&self, frame: Frame) -> Result<Frame, FrameEncryptionError> {
+        if !Self::seals(&frame) {
+            return Ok(frame);
+        }
+        let (flags, payload) = frame.into_parts();
+        let nonce = self.next_nonce();
+        let aad = Self::aad(flags.bits());
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: &payload, aad: &aad })
+            .map_err(|_| FrameEncryptionError::Encrypt)?;
+        Ok(Frame::from_parts(flags, sealed.into()))
+    }
+
+    /// Open the payload of a DATA or END_STREAM frame; other frames are returned as-is
+    pub fn open(&self, frame: Frame) -> Result<Frame, FrameEncryptionError> {
+        if !Self::seals(&frame) {
+            return Ok(frame);
+        }
+        let (flags, payload) = frame.into_parts();
+        let nonce = self.next_nonce();
+        let aad = Self::aad(flags.bits());
+        let opened = self
+            .cipher
+            .decrypt(&nonce, Payload { msg: &payload, aad: &aad })
+            .map_err(|_| FrameEncryptionError::Decrypt)?;
+        Ok(Frame::from_parts(flags, opened.into()))
+    }
+
+    /// Salt followed by the frame's position, with the top bit set for responses
+    fn next_nonce(&self) -> XNonce {
+        let mut position = self.sequence.fetch_add(1, Ordering::Relaxed);
+        if self.direction == FrameDirection::Response {
+            position |= 1 << 63;
+        }
+        let mut nonce = XNonce::default();
+        nonce[..STREAM_SALT_LEN].copy_from_slice(&self.salt);
+        nonce[STREAM_SALT_LEN..].copy_from_slice(&position.to_be_bytes());
+        nonce
+    }
+
+    fn aad(flags: u8) -> Vec<u8> {
+        let mut aad = AAD_PREFIX.to_vec();
+        aad.push(flags);
+        aad
+    }
+}
+
+impl fmt::Debug for FrameCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameCipher")
+            .field("direction", &self.direction)
+            .field("sequence", &self.sequence.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    fn ciphers(direction: FrameDirection) -> (FrameCipher, FrameCipher) {
+        let key = FrameKey::new("k1", [7u8; FRAME_KEY_LEN]);
+        let header = FrameEncryptionHeader::new(&key).unwrap();
+        (FrameCipher::new(&key, &header, direction), FrameCipher::new(&key, &header, direction))
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let key = FrameKey::random("k1").unwrap();
+        let header = FrameEncryptionHeader::new(&key).unwrap();
+        let value = header.to_header_value();
+        assert!(value.starts_with("xchacha20poly1305; key=k1; salt="));
+        assert_eq!(FrameEncryptionHeader::from_header_value(&value).unwrap(), header);
+
+        assert!(FrameEncryptionHeader::from_header_value("aes256gcm; key=k1; salt=00").is_err());
+        assert!(FrameEncryptionHeader::from_header_value("xchacha20poly1305; key=k1").is_err());
+        assert!(FrameEncryptionHeader::from_header_value("xchacha20poly1305; key=k1; salt=zz").is_err());
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let (sender, receiver) = ciphers(FrameDirection::Response);
+        let frames = [Frame::data(Bytes::from_static(b"hello")), Frame::end_stream()];
+
+        for frame in frames {
+            let sealed = sender.seal(frame.clone()).unwrap();
+            assert_ne!(sealed.payload, frame.payload);
+            assert_eq!(sealed.flags, frame.flags);
+            let opened = receiver.open(sealed).unwrap();
+            assert_eq!((opened.flags, opened.payload), (frame.flags, frame.payload));
+        }
+
+        // Control frames are left alone
+        let credit = Frame::credit(4);
+        assert_eq!(sender.seal(credit.clone()).unwrap().payload, credit.payload);
+    }
+
+    #[test]
+    fn test_rejects_reordered_and_altered_frames() {
+        let (sender, receiver) = ciphers(FrameDirection::Request);
+        // The first frame never arrives
+        sender.seal(Frame::data(Bytes::from_static(b"first"))).unwrap();
+        let second = sender.seal(Frame::data(Bytes::from_static(b"second"))).unwrap();
+        assert!(matches!(receiver.open(second), Err(FrameEncryptionError::Decrypt)));
+
+        // Turning a DATA frame into END_STREAM breaks the tag
+        let (sender, receiver) = ciphers(FrameDirection::Request);
+        let sealed = sender.seal(Frame::data(Bytes::from_static(b"first"))).unwrap();
+        let altered = Frame::from_parts(Frame::end_stream().flags, sealed.payload);
+        assert!(receiver.open(altered).is_err());
+    }
+
+    #[test]
+    fn test_directions_use_distinct_nonces() {
+        let key = FrameKey::new("k1", [7u8; FRAME_KEY_LEN]);
+        let header = FrameEncryptionHeader::new(&key).unwrap();
+        let request = FrameCipher::new(&key, &header, FrameDirection::Request);
+        let response = FrameCipher::new(&key, &header, FrameDirection::Response);
+
+        let message = Bytes::from_static(b"same message");
+        let a = request.seal(Frame::data(message.clone())).unwrap();
+        let b = response.seal(Frame::data(message)).unwrap();
+        assert_ne!(a.payload, b.payload);
+    }
+}
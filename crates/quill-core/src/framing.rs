@@ -1,13 +1,24 @@
 //! Stream framing for Quill RPC.
 //!
 //! Frame format: [length varint][flags byte][payload bytes]
-//! Flags: DATA(bit 0), END_STREAM(bit 1), CANCEL(bit 2), CREDIT(bit 3)
+//! Flags: DATA(bit 0), END_STREAM(bit 1), CANCEL(bit 2), CREDIT(bit 3), PARTIAL(bit 4), DROPPABLE(bit 5), CURSOR(bit 6), USAGE(bit 7)
+//! CANCEL_ACK is END_STREAM and CANCEL set together.
 
+use crate::error::{ProblemDetails, QuillError};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 /// Maximum frame size (4MB)
 pub const MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
 
+/// Maximum length of an encoded varint
+pub const MAX_VARINT_LEN: usize = 10;
+
+/// Payload size from which forwarded frames are written as separate header and payload chunks
+///
+/// Smaller payloads are cheaper to copy next to their header than to send
+/// as a chunk of their own.
+pub const SPLIT_PAYLOAD_THRESHOLD: usize = 16 * 1024;
+
 /// Frame flags
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FrameFlags(u8);
@@ -16,7 +27,17 @@ impl FrameFlags {
     pub const DATA: u8 = 0b0000_0001;
     pub const END_STREAM: u8 = 0b0000_0010;
     pub const CANCEL: u8 = 0b0000_0100;
+    /// END_STREAM and CANCEL together: the stream ended because the peer's cancel was observed
+    pub const CANCEL_ACK: u8 = Self::END_STREAM | Self::CANCEL;
     pub const CREDIT: u8 = 0b0000_1000;
+    /// Set with END_STREAM when the stream was cut short by its deadline
+    pub const PARTIAL: u8 = 0b0001_0000;
+    /// Set with DATA on auxiliary messages a server may drop under backpressure
+    pub const DROPPABLE: u8 = 0b0010_0000;
+    /// Set alone on frames carrying a stream cursor
+    pub const CURSOR: u8 = 0b0100_0000;
+    /// Set alone on frames carrying a usage trailer
+    pub const USAGE: u8 = 0b1000_0000;
 
     pub fn new(flags: u8) -> Self {
         Self(flags)
@@ -26,6 +47,11 @@ impl FrameFlags {
         Self(0)
     }
 
+    /// Raw flag bits
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
     pub fn is_data(&self) -> bool {
         self.0 & Self::DATA != 0
     }
@@ -38,10 +64,30 @@ impl FrameFlags {
         self.0 & Self::CANCEL != 0
     }
 
+    pub fn is_cancel_ack(&self) -> bool {
+        self.0 & Self::CANCEL_ACK == Self::CANCEL_ACK
+    }
+
     pub fn is_credit(&self) -> bool {
         self.0 & Self::CREDIT != 0
     }
 
+    pub fn is_partial(&self) -> bool {
+        self.0 & Self::PARTIAL != 0
+    }
+
+    pub fn is_droppable(&self) -> bool {
+        self.0 & Self::DROPPABLE != 0
+    }
+
+    pub fn is_cursor(&self) -> bool {
+        self.0 & Self::CURSOR != 0
+    }
+
+    pub fn is_usage(&self) -> bool {
+        self.0 & Self::USAGE != 0
+    }
+
     pub fn as_u8(&self) -> u8 {
         self.0
     }
@@ -55,6 +101,19 @@ pub struct Frame {
 }
 
 impl Frame {
+    /// Reassemble a frame from its flags and payload
+    ///
+    /// The payload is kept as-is, so frames taken apart with
+    /// [`into_parts`](Self::into_parts) can be forwarded without copying.
+    pub fn from_parts(flags: FrameFlags, payload: Bytes) -> Self {
+        Self { flags, payload }
+    }
+
+    /// Take the frame apart into its flags and payload
+    pub fn into_parts(self) -> (FrameFlags, Bytes) {
+        (self.flags, self.payload)
+    }
+
     /// Create a new data frame
     pub fn data(payload: Bytes) -> Self {
         Self {
@@ -63,6 +122,17 @@ impl Frame {
         }
     }
 
+    /// Create a data frame carrying auxiliary data (e.g. logprobs, debug events)
+    ///
+    /// Servers may drop droppable frames to keep up with a slow consumer.
+    /// Receivers that don't understand DROPPABLE see a normal data frame.
+    pub fn droppable(payload: Bytes) -> Self {
+        Self {
+            flags: FrameFlags::new(FrameFlags::DATA | FrameFlags::DROPPABLE),
+            payload,
+        }
+    }
+
     /// Create an end-of-stream frame
     pub fn end_stream() -> Self {
         Self {
@@ -71,6 +141,38 @@ impl Frame {
         }
     }
 
+    /// Create a PARTIAL end-of-stream frame carrying a trailer
+    ///
+    /// Receivers that don't understand PARTIAL see a normal end of stream.
+    pub fn partial(trailer: Bytes) -> Self {
+        Self {
+            flags: FrameFlags::new(FrameFlags::END_STREAM | FrameFlags::PARTIAL),
+            payload: trailer,
+        }
+    }
+
+    /// Create a cursor frame marking the position after the last data frame
+    ///
+    /// Receivers that don't understand CURSOR skip it like any other
+    /// non-data frame.
+    pub fn cursor(cursor: Bytes) -> Self {
+        Self {
+            flags: FrameFlags::new(FrameFlags::CURSOR),
+            payload: cursor,
+        }
+    }
+
+    /// Create a usage frame reporting what the call consumed
+    ///
+    /// Sent just before END_STREAM. Receivers that don't understand USAGE
+    /// skip it like any other non-data frame.
+    pub fn usage(trailer: Bytes) -> Self {
+        Self {
+            flags: FrameFlags::new(FrameFlags::USAGE),
+            payload: trailer,
+        }
+    }
+
     /// Create a cancel frame
     pub fn cancel() -> Self {
         Self {
@@ -79,9 +181,39 @@ impl Frame {
         }
     }
 
+    /// Create a cancel frame explaining why the stream was cancelled
+    pub fn cancel_with_problem(problem: &ProblemDetails) -> Self {
+        Self {
+            flags: FrameFlags::new(FrameFlags::CANCEL),
+            payload: Bytes::from(serde_json::to_vec(problem).unwrap_or_default()),
+        }
+    }
+
+    /// Error to report for a cancel frame
+    ///
+    /// Cancel frames may carry Problem Details JSON; bare ones report a
+    /// generic cancellation.
+    pub fn cancel_error(&self) -> QuillError {
+        match serde_json::from_slice::<ProblemDetails>(&self.payload) {
+            Ok(problem) => QuillError::ProblemDetails(problem),
+            Err(_) => QuillError::Rpc("Stream cancelled by server".to_string()),
+        }
+    }
+
+    /// Create a cancel acknowledgment, ending a stream whose peer sent CANCEL
+    ///
+    /// Sent once the cancelled work has stopped. Receivers that don't
+    /// understand CANCEL_ACK see a normal end of stream.
+    pub fn cancel_ack() -> Self {
+        Self {
+            flags: FrameFlags::new(FrameFlags::CANCEL_ACK),
+            payload: Bytes::new(),
+        }
+    }
+
     /// Create a credit frame with the specified number of credits
     pub fn credit(credits: u32) -> Self {
-        let mut buf = BytesMut::new();
+        let mut buf = BytesMut::with_capacity(varint_len(credits as u64));
         encode_varint(credits as u64, &mut buf);
         Self {
             flags: FrameFlags::new(FrameFlags::CREDIT),
@@ -98,83 +230,205 @@ impl Frame {
         decode_varint(&mut cursor).map(|v| v as u32)
     }
 
-    /// Encode this frame to bytes
-    pub fn encode(&self) -> Bytes {
-        let payload_len = self.payload.len();
-        let mut buf = BytesMut::new();
+    /// Create a credit frame granting tensor payload bytes
+    ///
+    /// The byte count follows a zero message credit, so receivers that only
+    /// know message credits read it as granting nothing.
+    pub fn byte_credit(bytes: u64) -> Self {
+        let mut buf = BytesMut::with_capacity(1 + varint_len(bytes));
+        encode_varint(0, &mut buf);
+        encode_varint(bytes, &mut buf);
+        Self {
+            flags: FrameFlags::new(FrameFlags::CREDIT),
+            payload: buf.freeze(),
+        }
+    }
+
+    /// Decode the tensor payload bytes granted by a credit frame
+    pub fn decode_byte_credit(&self) -> Option<u64> {
+        if !self.flags.is_credit() {
+            return None;
+        }
+        let mut cursor = std::io::Cursor::new(&self.payload[..]);
+        decode_varint(&mut cursor)?;
+        decode_varint(&mut cursor)
+    }
+
+    /// Length of the encoded header: the length varint and the flags byte
+    #[inline]
+    pub fn header_len(&self) -> usize {
+        varint_len(self.payload.len() as u64) + 1
+    }
 
-        // Encode length as varint
-        encode_varint(payload_len as u64, &mut buf);
+    /// Exact length of [`encode`](Self::encode)'s output
+    ///
+    /// Use it to size buffers passed to [`encode_to`](Self::encode_to).
+    #[inline]
+    pub fn encode_len(&self) -> usize {
+        self.header_len() + self.payload.len()
+    }
 
-        // Encode flags
+    /// Write the header (length varint and flags) to `buf`
+    ///
+    /// Lets callers send the payload separately, e.g. in a vectored write,
+    /// without copying it.
+    #[inline]
+    pub fn encode_header<B: BufMut + ?Sized>(&self, buf: &mut B) {
+        encode_varint(self.payload.len() as u64, buf);
         buf.put_u8(self.flags.as_u8());
+    }
 
-        // Encode payload
+    /// Write this frame to `buf`
+    ///
+    /// Bytes go straight into the buffer's spare capacity, so frames encoded
+    /// into one reused `BytesMut` allocate nothing once it is large enough.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` cannot grow and has less than
+    /// [`encode_len`](Self::encode_len) bytes of room, like any `BufMut`.
+    #[inline]
+    pub fn encode_to<B: BufMut + ?Sized>(&self, buf: &mut B) {
+        self.encode_header(buf);
         buf.put_slice(&self.payload);
+    }
 
+    /// Encode this frame to bytes
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(self.encode_len());
+        self.encode_to(&mut buf);
         buf.freeze()
     }
+
+    /// Encode this frame as chunks to write in order, without copying large payloads
+    ///
+    /// Payloads of at least [`SPLIT_PAYLOAD_THRESHOLD`] bytes follow their
+    /// header as a chunk of their own, sharing the payload's buffer; smaller
+    /// frames come out as one chunk like [`encode`](Self::encode). Relays
+    /// use it to pass parsed frames on with their payloads untouched.
+    pub fn into_chunks(self) -> FrameChunks {
+        if self.payload.len() < SPLIT_PAYLOAD_THRESHOLD {
+            return FrameChunks { header: Some(self.encode()), payload: None };
+        }
+        let mut header = BytesMut::with_capacity(self.header_len());
+        self.encode_header(&mut header);
+        FrameChunks { header: Some(header.freeze()), payload: Some(self.payload) }
+    }
+}
+
+/// Encoded chunks of one frame, from [`Frame::into_chunks`]
+#[derive(Debug)]
+pub struct FrameChunks {
+    header: Option<Bytes>,
+    payload: Option<Bytes>,
+}
+
+impl Iterator for FrameChunks {
+    type Item = Bytes;
+
+    fn next(&mut self) -> Option<Bytes> {
+        self.header.take().or_else(|| self.payload.take())
+    }
 }
 
 /// Frame parser for decoding frames from a byte stream
+///
+/// Parsed payloads share the parser's buffers rather than being copied out
+/// of them. Data given to [`feed_bytes`](Self::feed_bytes) is not copied
+/// either unless a frame straddles it and the next chunk.
 pub struct FrameParser {
     buffer: BytesMut,
+    /// Chunk fed as `Bytes` and not yet parsed; set only while `buffer` is empty
+    chunk: Bytes,
 }
 
 impl FrameParser {
     pub fn new() -> Self {
         Self {
             buffer: BytesMut::new(),
+            chunk: Bytes::new(),
         }
     }
 
     /// Add data to the parser buffer
     pub fn feed(&mut self, data: &[u8]) {
+        self.spill();
         self.buffer.extend_from_slice(data);
     }
 
+    /// Add a received chunk, keeping its frames' payloads in place
+    pub fn feed_bytes(&mut self, data: Bytes) {
+        if self.buffer.is_empty() && self.chunk.is_empty() {
+            self.chunk = data;
+        } else {
+            self.spill();
+            self.buffer.extend_from_slice(&data);
+        }
+    }
+
     /// Try to parse a complete frame from the buffer
     pub fn parse_frame(&mut self) -> Result<Option<Frame>, FrameError> {
-        // Need at least 2 bytes (min varint + flags)
-        if self.buffer.len() < 2 {
-            return Ok(None);
+        if !self.chunk.is_empty() {
+            let Some((flags_at, total_len)) = frame_bounds(&self.chunk)? else {
+                // The frame continues in a later chunk
+                self.spill();
+                return Ok(None);
+            };
+            let frame = self.chunk.split_to(total_len);
+            return Ok(Some(Frame {
+                flags: FrameFlags::new(frame[flags_at]),
+                payload: frame.slice(flags_at + 1..),
+            }));
         }
 
-        let mut cursor = std::io::Cursor::new(&self.buffer[..]);
-
-        // Decode length varint
-        let payload_len = match decode_varint(&mut cursor) {
-            Some(len) => len as usize,
-            None => return Ok(None), // Need more data
+        let Some((flags_at, total_len)) = frame_bounds(&self.buffer)? else {
+            return Ok(None);
         };
+        let frame = self.buffer.split_to(total_len).freeze();
+        Ok(Some(Frame {
+            flags: FrameFlags::new(frame[flags_at]),
+            payload: frame.slice(flags_at + 1..),
+        }))
+    }
 
-        if payload_len > MAX_FRAME_SIZE {
-            return Err(FrameError::FrameTooLarge(payload_len));
+    /// Move the unparsed chunk into the buffer, so later data can follow it
+    fn spill(&mut self) {
+        if !self.chunk.is_empty() {
+            self.buffer.extend_from_slice(&self.chunk);
+            self.chunk = Bytes::new();
         }
+    }
+}
 
-        let header_len = cursor.position() as usize;
+/// Offset of the flags byte and total length of the frame at the start of `data`
+///
+/// Returns `None` until the whole frame is there.
+fn frame_bounds(data: &[u8]) -> Result<Option<(usize, usize)>, FrameError> {
+    // Need at least 2 bytes (min varint + flags)
+    if data.len() < 2 {
+        return Ok(None);
+    }
 
-        // Check if we have the full frame
-        let total_len = header_len + 1 + payload_len; // +1 for flags byte
-        if self.buffer.len() < total_len {
-            return Ok(None); // Need more data
-        }
+    let mut cursor = std::io::Cursor::new(data);
 
-        // Parse flags
-        let flags = FrameFlags::new(self.buffer[header_len]);
+    // Decode length varint
+    let payload_len = match decode_varint(&mut cursor) {
+        Some(len) => len as usize,
+        None => return Ok(None), // Need more data
+    };
 
-        // Extract payload
-        let payload_start = header_len + 1;
-        let payload = self.buffer[payload_start..payload_start + payload_len].to_vec();
+    if payload_len > MAX_FRAME_SIZE {
+        return Err(FrameError::FrameTooLarge(payload_len));
+    }
 
-        // Advance buffer
-        self.buffer.advance(total_len);
+    let header_len = cursor.position() as usize;
 
-        Ok(Some(Frame {
-            flags,
-            payload: Bytes::from(payload),
-        }))
+    // Check if we have the full frame
+    let total_len = header_len + 1 + payload_len; // +1 for flags byte
+    if data.len() < total_len {
+        return Ok(None); // Need more data
     }
+    Ok(Some((header_len, total_len)))
 }
 
 impl Default for FrameParser {
@@ -192,17 +446,40 @@ pub enum FrameError {
     InvalidVarint,
 }
 
+/// Number of bytes `value` takes as a protobuf varint
+#[inline]
+pub const fn varint_len(value: u64) -> usize {
+    // 7 bits per byte; zero still takes one byte
+    let bits = 64 - (value | 1).leading_zeros() as usize;
+    bits.div_ceil(7)
+}
+
 /// Encode a u64 as a protobuf varint
-pub fn encode_varint(mut value: u64, buf: &mut BytesMut) {
-    loop {
-        if value < 0x80 {
-            buf.put_u8(value as u8);
-            break;
-        } else {
-            buf.put_u8(((value & 0x7F) | 0x80) as u8);
-            value >>= 7;
-        }
+///
+/// One- and two-byte values, which cover frames up to 16KB, take a single
+/// write.
+#[inline]
+pub fn encode_varint<B: BufMut + ?Sized>(value: u64, buf: &mut B) {
+    if value < 0x80 {
+        buf.put_u8(value as u8);
+    } else if value < 0x4000 {
+        buf.put_slice(&[(value as u8) | 0x80, (value >> 7) as u8]);
+    } else {
+        encode_varint_slow(value, buf);
+    }
+}
+
+#[cold]
+fn encode_varint_slow<B: BufMut + ?Sized>(mut value: u64, buf: &mut B) {
+    let mut bytes = [0u8; MAX_VARINT_LEN];
+    let mut len = 0;
+    while value >= 0x80 {
+        bytes[len] = (value as u8) | 0x80;
+        value >>= 7;
+        len += 1;
     }
+    bytes[len] = value as u8;
+    buf.put_slice(&bytes[..=len]);
 }
 
 /// Decode a protobuf varint from a cursor
@@ -243,6 +520,99 @@ mod tests {
         assert_eq!(decoded, 150);
     }
 
+    #[test]
+    fn test_varint_lengths() {
+        let values = [0, 1, 127, 128, 16_383, 16_384, 2_097_151, 2_097_152, u32::MAX as u64, u64::MAX];
+        for value in values {
+            let mut buf = BytesMut::new();
+            encode_varint(value, &mut buf);
+            assert_eq!(buf.len(), varint_len(value), "length of {}", value);
+
+            let mut cursor = std::io::Cursor::new(&buf[..]);
+            assert_eq!(decode_varint(&mut cursor), Some(value));
+        }
+        assert_eq!(varint_len(u64::MAX), MAX_VARINT_LEN);
+    }
+
+    #[test]
+    fn test_encode_to_caller_buffer() {
+        let frames = [
+            Frame::data(Bytes::from("hello")),
+            Frame::data(Bytes::from(vec![7u8; 300])),
+            Frame::end_stream(),
+        ];
+
+        // Frames share one pre-sized buffer
+        let total: usize = frames.iter().map(Frame::encode_len).sum();
+        let mut buf = BytesMut::with_capacity(total);
+        for frame in &frames {
+            assert_eq!(frame.encode_len(), frame.encode().len());
+            frame.encode_to(&mut buf);
+        }
+        assert_eq!(buf.len(), total);
+        assert_eq!(buf.capacity(), total);
+
+        let mut parser = FrameParser::new();
+        parser.feed(&buf);
+        assert_eq!(parser.parse_frame().unwrap().unwrap().payload, frames[0].payload);
+        assert_eq!(parser.parse_frame().unwrap().unwrap().payload, frames[1].payload);
+        assert!(parser.parse_frame().unwrap().unwrap().flags.is_end_stream());
+
+        // Headers fit a fixed stack buffer
+        let mut header = [0u8; MAX_VARINT_LEN + 1];
+        let mut slice = &mut header[..];
+        frames[1].encode_header(&mut slice);
+        let written = MAX_VARINT_LEN + 1 - slice.len();
+        assert_eq!(written, frames[1].header_len());
+        assert_eq!(&header[..written], &frames[1].encode()[..written]);
+    }
+
+    #[test]
+    fn test_parts_roundtrip_without_copying() {
+        let payload = Bytes::from(vec![3u8; 64]);
+        let (flags, parts_payload) = Frame::droppable(payload.clone()).into_parts();
+        assert!(flags.is_droppable());
+        assert_eq!(parts_payload.as_ptr(), payload.as_ptr());
+
+        let frame = Frame::from_parts(flags, parts_payload);
+        assert_eq!(frame.payload.as_ptr(), payload.as_ptr());
+        assert_eq!(frame.encode(), Frame::droppable(payload).encode());
+    }
+
+    #[test]
+    fn test_fed_chunks_are_parsed_in_place() {
+        let large = Frame::data(Bytes::from(vec![9u8; SPLIT_PAYLOAD_THRESHOLD]));
+        let small = Frame::data(Bytes::from_static(b"tiny"));
+        let mut wire = BytesMut::new();
+        large.encode_to(&mut wire);
+        small.encode_to(&mut wire);
+        let wire = wire.freeze();
+
+        // Frames parsed from a fed chunk point into that chunk
+        let mut parser = FrameParser::new();
+        parser.feed_bytes(wire.clone());
+        let parsed = parser.parse_frame().unwrap().unwrap();
+        assert_eq!(parsed.payload, large.payload);
+        assert_eq!(parsed.payload.as_ptr(), wire[large.header_len()..].as_ptr());
+        assert_eq!(parser.parse_frame().unwrap().unwrap().payload, small.payload);
+        assert!(parser.parse_frame().unwrap().is_none());
+
+        // A frame split across chunks is still parsed
+        let split = 5;
+        parser.feed_bytes(wire.slice(..split));
+        assert!(parser.parse_frame().unwrap().is_none());
+        parser.feed_bytes(wire.slice(split..));
+        assert_eq!(parser.parse_frame().unwrap().unwrap().payload, large.payload);
+        assert_eq!(parser.parse_frame().unwrap().unwrap().payload, small.payload);
+
+        // Forwarding sends the large payload's buffer on as its own chunk
+        let chunks: Vec<Bytes> = parsed.clone().into_chunks().collect();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].as_ptr(), parsed.payload.as_ptr());
+        assert_eq!(chunks.concat(), large.encode());
+        assert_eq!(small.clone().into_chunks().collect::<Vec<_>>(), vec![small.encode()]);
+    }
+
     #[test]
     fn test_frame_roundtrip() {
         let original = Frame::data(Bytes::from("hello"));
@@ -256,6 +626,51 @@ mod tests {
         assert_eq!(decoded.flags.as_u8(), original.flags.as_u8());
     }
 
+    #[test]
+    fn test_partial_frame() {
+        let frame = Frame::partial(Bytes::from_static(b"{}"));
+        assert!(frame.flags.is_end_stream());
+        assert!(frame.flags.is_partial());
+        assert!(!Frame::end_stream().flags.is_partial());
+    }
+
+    #[test]
+    fn test_droppable_frame() {
+        let frame = Frame::droppable(Bytes::from_static(b"logprobs"));
+        assert!(frame.flags.is_data());
+        assert!(frame.flags.is_droppable());
+        assert!(!Frame::data(Bytes::new()).flags.is_droppable());
+
+        let mut parser = FrameParser::new();
+        parser.feed(&frame.encode());
+        assert!(parser.parse_frame().unwrap().unwrap().flags.is_droppable());
+    }
+
+    #[test]
+    fn test_cursor_frame() {
+        let frame = Frame::cursor(Bytes::from_static(b"key-42"));
+        assert!(frame.flags.is_cursor());
+        assert!(!frame.flags.is_data());
+
+        let mut parser = FrameParser::new();
+        parser.feed(&frame.encode());
+        let decoded = parser.parse_frame().unwrap().unwrap();
+        assert!(decoded.flags.is_cursor());
+        assert_eq!(decoded.payload, Bytes::from_static(b"key-42"));
+    }
+
+    #[test]
+    fn test_usage_frame() {
+        let frame = Frame::usage(Bytes::from_static(b"{}"));
+        assert!(frame.flags.is_usage());
+        assert!(!frame.flags.is_data());
+        assert!(!frame.flags.is_end_stream());
+
+        let mut parser = FrameParser::new();
+        parser.feed(&frame.encode());
+        assert!(parser.parse_frame().unwrap().unwrap().flags.is_usage());
+    }
+
     #[test]
     fn test_frame_flags() {
         let flags = FrameFlags::new(FrameFlags::DATA | FrameFlags::END_STREAM);
@@ -265,6 +680,18 @@ mod tests {
         assert!(!flags.is_credit());
     }
 
+    #[test]
+    fn test_cancel_ack_frame() {
+        let mut parser = FrameParser::new();
+        parser.feed(&Frame::cancel_ack().encode());
+
+        let decoded = parser.parse_frame().unwrap().unwrap();
+        assert!(decoded.flags.is_cancel_ack());
+        assert!(decoded.flags.is_end_stream());
+        assert!(!Frame::cancel().flags.is_cancel_ack());
+        assert!(!Frame::end_stream().flags.is_cancel_ack());
+    }
+
     #[test]
     fn test_credit_frame() {
         let credits = 42u32;
@@ -287,4 +714,25 @@ mod tests {
         assert!(decoded.flags.is_credit());
         assert_eq!(decoded.decode_credit(), Some(100));
     }
+
+    #[test]
+    fn test_byte_credit_frame() {
+        let mut parser = FrameParser::new();
+        parser.feed(&Frame::byte_credit(1 << 20).encode());
+
+        let decoded = parser.parse_frame().unwrap().unwrap();
+        assert_eq!(decoded.decode_credit(), Some(0));
+        assert_eq!(decoded.decode_byte_credit(), Some(1 << 20));
+        assert_eq!(Frame::credit(4).decode_byte_credit(), None);
+    }
+
+    #[test]
+    fn test_cancel_with_problem() {
+        let problem = ProblemDetails::new(http::StatusCode::SERVICE_UNAVAILABLE, "Slow consumer");
+        match Frame::cancel_with_problem(&problem).cancel_error() {
+            QuillError::ProblemDetails(pd) => assert_eq!(pd.title, "Slow consumer"),
+            other => panic!("expected problem details, got {:?}", other),
+        }
+        assert!(matches!(Frame::cancel().cancel_error(), QuillError::Rpc(_)));
+    }
 }
@@ -0,0 +1,97 @@
+//! Built-in health checking RPCs
+//!
+//! Servers with health checking enabled answer two methods:
+//! - `Check` (unary) reports the serving status of a service
+//! - `Watch` (server streaming) sends the status, then a message each time
+//!   it changes, until the caller hangs up
+//!
+//! The request body names a service (e.g. `echo.v1.EchoService`); an empty
+//! body asks about the server as a whole. Responses are JSON
+//! [`HealthCheckResponse`] objects, e.g. `{"status":"SERVING"}`. `Check`
+//! answers `404` for services the server does not know, while `Watch`
+//! reports them as `SERVICE_UNKNOWN` so callers can wait for them to appear.
+//!
+//! The statuses follow the gRPC health checking protocol, so existing
+//! probes map onto them directly.
+
+use serde::{Deserialize, Serialize};
+
+/// Service name of the built-in health RPCs
+pub const HEALTH_SERVICE: &str = "quill.health.v1.Health";
+
+/// Method name of the built-in health check RPC
+pub const HEALTH_CHECK_METHOD: &str = "Check";
+
+/// Method name of the built-in health watch RPC
+pub const HEALTH_WATCH_METHOD: &str = "Watch";
+
+/// Full route path of the built-in health check RPC
+pub const HEALTH_CHECK_PATH: &str = "quill.health.v1.Health/Check";
+
+/// Full route path of the built-in health watch RPC
+pub const HEALTH_WATCH_PATH: &str = "quill.health.v1.Health/Watch";
+
+/// Serving status of a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ServingStatus {
+    /// Status not yet known
+    Unknown,
+    /// Ready to take calls
+    Serving,
+    /// Alive but not taking calls (e.g. draining or missing a dependency)
+    NotServing,
+    /// The server does not know the service (only sent by `Watch`)
+    ServiceUnknown,
+}
+
+impl ServingStatus {
+    /// Whether calls to the service should succeed
+    pub fn is_serving(self) -> bool {
+        self == Self::Serving
+    }
+}
+
+/// Response of the health check and watch RPCs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheckResponse {
+    /// Serving status of the service asked about
+    pub status: ServingStatus,
+}
+
+impl HealthCheckResponse {
+    /// Response reporting `status`
+    pub fn new(status: ServingStatus) -> Self {
+        Self { status }
+    }
+
+    /// Encode as a response payload
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Decode a response payload
+    pub fn from_json(data: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_paths_match_service_and_methods() {
+        assert_eq!(HEALTH_CHECK_PATH, format!("{}/{}", HEALTH_SERVICE, HEALTH_CHECK_METHOD));
+        assert_eq!(HEALTH_WATCH_PATH, format!("{}/{}", HEALTH_SERVICE, HEALTH_WATCH_METHOD));
+    }
+
+    #[test]
+    fn test_response_json_roundtrip() {
+        let response = HealthCheckResponse::new(ServingStatus::NotServing);
+        let json = response.to_json().unwrap();
+        assert_eq!(json, r#"{"status":"NOT_SERVING"}"#);
+        assert_eq!(HealthCheckResponse::from_json(json.as_bytes()).unwrap(), response);
+        assert!(!response.status.is_serving());
+    }
+}
@@ -0,0 +1,138 @@
+//! Interop test service contract
+//!
+//! Implementations in other languages validate themselves against the Rust
+//! reference by serving or calling this service. Messages are raw bytes
+//! rather than protobuf, so a conformance server needs no generated code:
+//!
+//! - `Echo` (unary) answers with its request body unchanged
+//! - `Generate` (server streaming) takes an [`InteropStreamRequest`] and
+//!   sends `count` messages of `size` bytes, see [`interop_message`]
+//! - `Collect` (client streaming) answers with an [`InteropCollectSummary`]
+//!   of the messages it received
+//! - `Converse` (bidi streaming) answers each message with the same bytes,
+//!   as soon as it arrives
+//! - `Tensor` (tensor streaming) takes an [`InteropStreamRequest`] and
+//!   sends a 1-D float32 tensor of `count` elements valued `0.0, 1.0, ...`
+//!   in payload chunks of at most `size` bytes
+//!
+//! Integers are big-endian.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// Service name of the interop test service
+pub const INTEROP_SERVICE: &str = "quill.interop.v1.Interop";
+
+/// Unary method answering with its request body
+pub const INTEROP_ECHO_METHOD: &str = "Echo";
+
+/// Server streaming method sending generated messages
+pub const INTEROP_GENERATE_METHOD: &str = "Generate";
+
+/// Client streaming method summarizing the messages it received
+pub const INTEROP_COLLECT_METHOD: &str = "Collect";
+
+/// Bidirectional streaming method echoing each message
+pub const INTEROP_CONVERSE_METHOD: &str = "Converse";
+
+/// Tensor streaming method sending a generated tensor
+pub const INTEROP_TENSOR_METHOD: &str = "Tensor";
+
+/// Request of the `Generate` and `Tensor` methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteropStreamRequest {
+    /// Messages to send, or tensor elements
+    pub count: u32,
+    /// Bytes per message, or largest tensor payload chunk
+    pub size: u32,
+}
+
+impl InteropStreamRequest {
+    /// Encoded length of a request
+    pub const LEN: usize = 8;
+
+    /// Encode as `count` then `size`
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(Self::LEN);
+        buf.put_u32(self.count);
+        buf.put_u32(self.size);
+        buf.freeze()
+    }
+
+    /// Decode a request, or `None` if `data` is not exactly 8 bytes
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let data: &[u8; Self::LEN] = data.try_into().ok()?;
+        Some(Self {
+            count: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            size: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+        })
+    }
+}
+
+/// Response of the `Collect` method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InteropCollectSummary {
+    /// Messages received
+    pub messages: u64,
+    /// Total bytes of the messages received
+    pub bytes: u64,
+}
+
+impl InteropCollectSummary {
+    /// Encoded length of a summary
+    pub const LEN: usize = 16;
+
+    /// Encode as `messages` then `bytes`
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(Self::LEN);
+        buf.put_u64(self.messages);
+        buf.put_u64(self.bytes);
+        buf.freeze()
+    }
+
+    /// Decode a summary, or `None` if `data` is not exactly 16 bytes
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN {
+            return None;
+        }
+        let (messages, bytes) = data.split_at(8);
+        Some(Self {
+            messages: u64::from_be_bytes(messages.try_into().ok()?),
+            bytes: u64::from_be_bytes(bytes.try_into().ok()?),
+        })
+    }
+}
+
+/// Message `index` of a `Generate` stream: `size` bytes of `index % 256`
+pub fn interop_message(index: u32, size: usize) -> Bytes {
+    Bytes::from(vec![index as u8; size])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_request_roundtrip() {
+        let request = InteropStreamRequest { count: 3, size: 1 << 20 };
+        let encoded = request.encode();
+        assert_eq!(&encoded[..], &[0, 0, 0, 3, 0, 0x10, 0, 0]);
+        assert_eq!(InteropStreamRequest::decode(&encoded), Some(request));
+        assert_eq!(InteropStreamRequest::decode(&encoded[..7]), None);
+        assert_eq!(InteropStreamRequest::decode(&[]), None);
+    }
+
+    #[test]
+    fn test_collect_summary_roundtrip() {
+        let summary = InteropCollectSummary { messages: 2, bytes: 300 };
+        let encoded = summary.encode();
+        assert_eq!(encoded.len(), InteropCollectSummary::LEN);
+        assert_eq!(InteropCollectSummary::decode(&encoded), Some(summary));
+        assert_eq!(InteropCollectSummary::decode(&encoded[1..]), None);
+    }
+
+    #[test]
+    fn test_generated_messages() {
+        assert_eq!(interop_message(2, 3), Bytes::from_static(&[2, 2, 2]));
+        assert_eq!(interop_message(257, 1), Bytes::from_static(&[1]));
+    }
+}
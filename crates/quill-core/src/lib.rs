@@ -2,25 +2,109 @@
 //!
 //! This crate provides the foundation types used across all Quill components:
 //! - Stream framing (varint encoding, frame parsing)
-//! - Problem Details error model
+//! - Problem Details error model with stable, retry-aware error codes
 //! - Prism transport profiles
+//! - Call deadlines propagated to the server
+//! - Per-call metadata sent as headers
 //! - Flow control primitives
+//! - Built-in ping, health, reflection and response cache RPC constants
+//! - Interop test service contract for conformance testing
+//! - Batch envelope for many unary calls in one request
+//! - Partial result trailers for deadline-bounded streams
+//! - Stream cursors for resumable list-style streams
+//! - Consistency tokens for read-your-writes across replicas
+//! - Usage trailers for metered streams
+//! - Chunked upload manifests for large unary requests
+//! - zstd compression dictionaries for small messages
+//! - Serializer hooks for generated stubs
+//! - Envelope encryption of sensitive messages and fields
+//! - End-to-end encryption of frame payloads
 //! - Streaming utilities
 
+pub mod batch;
+pub mod codec;
+pub mod consistency;
+pub mod cursor;
+pub mod deadline;
+pub mod dictionary;
+pub mod envelope;
 pub mod error;
 pub mod flow_control;
+pub mod frame_encryption;
 pub mod framing;
+pub mod health;
+pub mod interop;
+pub mod metadata;
+pub mod partial;
+pub mod ping;
 pub mod playground;
 pub mod profile;
+pub mod reflection;
+pub mod response_cache;
 pub mod stream;
+pub mod upload;
+pub mod usage;
 
-pub use error::{ProblemDetails, QuillError};
-pub use flow_control::{CreditTracker, DEFAULT_CREDIT_REFILL, DEFAULT_INITIAL_CREDITS};
-pub use framing::{decode_varint, encode_varint, Frame, FrameFlags, FrameParser};
+pub use batch::{
+    decode_batch_request, decode_batch_response, encode_batch_request, encode_batch_response,
+    BatchEntry, BatchError, BatchResult, BATCH_METHOD, BATCH_PATH, BATCH_SERVICE,
+};
+pub use codec::{CodecContext, HookError, MessageKind, SerializerHooks};
+pub use consistency::{ConsistencyToken, CONSISTENCY_TOKEN_HEADER};
+pub use cursor::{StreamCursor, DEFAULT_CURSOR_INTERVAL, RESUME_TOKEN_HEADER};
+pub use deadline::{Deadline, TIMEOUT_HEADER};
+pub use dictionary::{
+    parse_dictionary_id, CompressionDictionary, DictionaryCapability, DictionaryError,
+    DEFAULT_DICTIONARY_SIZE, DICTIONARY_CAPABILITY_HEADER, DICTIONARY_HEADER, DICTIONARY_METHOD,
+    DICTIONARY_PATH, DICTIONARY_SERVICE,
+};
+pub use envelope::{
+    DataKey, EnvelopeError, EnvelopeHeader, EnvelopeScope, KeyFuture, KeyProvider, LocalKeyProvider,
+    ENVELOPE_HEADER,
+};
+pub use error::{
+    DebugContext, ErrorCode, ErrorCodeParseError, ProblemDetails, QuillError, CANCELLED_TYPE,
+    DEADLINE_EXCEEDED_TYPE, STREAM_IDLE_TIMEOUT_TYPE,
+};
+pub use flow_control::{
+    max_tensor_refill, tensor_payload_cost, CreditTracker, FlowControlHeader, TensorCreditTracker,
+    DEFAULT_CREDIT_REFILL, DEFAULT_INITIAL_CREDITS, DEFAULT_TENSOR_INITIAL_BYTES,
+    DEFAULT_TENSOR_LOW_WATER, FLOW_CONTROL_HEADER, FLOW_CREDIT_METHOD, FLOW_CREDIT_PATH,
+    FLOW_CREDIT_SERVICE,
+};
+pub use frame_encryption::{
+    FrameCipher, FrameDirection, FrameEncryptionError, FrameEncryptionHeader, FrameKey,
+    FRAME_ENCRYPTION_HEADER, FRAME_KEY_LEN,
+};
+pub use framing::{
+    decode_varint, encode_varint, varint_len, Frame, FrameChunks, FrameFlags, FrameParser,
+    SPLIT_PAYLOAD_THRESHOLD,
+};
+pub use health::{
+    HealthCheckResponse, ServingStatus, HEALTH_CHECK_METHOD, HEALTH_CHECK_PATH, HEALTH_SERVICE,
+    HEALTH_WATCH_METHOD, HEALTH_WATCH_PATH,
+};
+pub use interop::{
+    interop_message, InteropCollectSummary, InteropStreamRequest, INTEROP_COLLECT_METHOD,
+    INTEROP_CONVERSE_METHOD, INTEROP_ECHO_METHOD, INTEROP_GENERATE_METHOD, INTEROP_SERVICE,
+    INTEROP_TENSOR_METHOD,
+};
+pub use metadata::{Metadata, MetadataError};
+pub use partial::PartialStats;
+pub use ping::{PING_METHOD, PING_PATH, PING_SERVICE};
 pub use playground::{
     ClockDirection, ClockDriftConfig, InterceptContext, LatencyRule, PartitionBehavior,
     PartitionError, PartitionRule, PlaygroundConfig, PlaygroundEvent, RuleSchedule,
     TelemetryConfig, ToDebugJson,
 };
 pub use profile::{PrismProfile, ProfilePreference};
+pub use reflection::{REFLECTION_METHOD, REFLECTION_PATH, REFLECTION_SERVICE};
+pub use response_cache::{
+    RESPONSE_CACHE_INVALIDATE_METHOD, RESPONSE_CACHE_INVALIDATE_PATH, RESPONSE_CACHE_SERVICE,
+};
 pub use stream::{FrameStream, StreamWriter};
+pub use upload::{
+    parse_chunk_index, ChunkAssembly, UploadCapability, UploadError, UploadManifest,
+    UPLOAD_CAPABILITY_HEADER, UPLOAD_CHUNK_HEADER, UPLOAD_MANIFEST_HEADER,
+};
+pub use usage::Usage;
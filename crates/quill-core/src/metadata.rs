@@ -0,0 +1,197 @@
+//! Per-call metadata sent alongside a message
+//!
+//! This module provides:
+//! - [`Metadata`], a case-insensitive map of ASCII key/value pairs
+//! - Its translation to and from HTTP headers
+//!
+//! Metadata travels as ordinary request headers. Headers that belong to the
+//! protocol are never metadata: framing and negotiation headers such as
+//! `content-type` or `prefer`, hop-by-hop headers, and every header starting
+//! with `quill-`, which is reserved for the framework itself. Binary values
+//! are sent base64-encoded under keys ending in `-bin`, as gRPC does.
+
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use std::fmt;
+
+/// Prefix of headers reserved for the framework
+const RESERVED_PREFIX: &str = "quill-";
+
+/// Protocol headers that are never metadata
+const RESERVED_HEADERS: &[&str] = &[
+    "accept",
+    "accept-encoding",
+    "connection",
+    "content-encoding",
+    "content-length",
+    "content-type",
+    "host",
+    "keep-alive",
+    "prefer",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Errors building metadata
+#[derive(Debug, thiserror::Error)]
+pub enum MetadataError {
+    #[error("Invalid metadata key '{0}'")]
+    InvalidKey(String),
+
+    #[error("Invalid value for metadata key '{0}'")]
+    InvalidValue(String),
+
+    #[error("Metadata key '{0}' is reserved")]
+    Reserved(String),
+}
+
+/// Case-insensitive map of per-call metadata
+#[derive(Clone, Default, PartialEq)]
+pub struct Metadata {
+    entries: HeaderMap,
+}
+
+impl Metadata {
+    /// Create empty metadata
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Metadata carried by `headers`, leaving out protocol headers
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut entries = HeaderMap::new();
+        for (name, value) in headers {
+            if !is_reserved(name.as_str()) && value.to_str().is_ok() {
+                entries.append(name.clone(), value.clone());
+            }
+        }
+        Self { entries }
+    }
+
+    /// Set `key` to `value`, replacing earlier values
+    pub fn insert(&mut self, key: &str, value: &str) -> Result<(), MetadataError> {
+        let (name, value) = entry(key, value)?;
+        self.entries.insert(name, value);
+        Ok(())
+    }
+
+    /// Add `value` to `key`, keeping earlier values
+    pub fn append(&mut self, key: &str, value: &str) -> Result<(), MetadataError> {
+        let (name, value) = entry(key, value)?;
+        self.entries.append(name, value);
+        Ok(())
+    }
+
+    /// Builder form of [`insert`](Self::insert)
+    pub fn with(mut self, key: &str, value: &str) -> Result<Self, MetadataError> {
+        self.insert(key, value)?;
+        Ok(self)
+    }
+
+    /// First value of `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    /// All values of `key`
+    pub fn get_all<'a>(&'a self, key: &str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries.get_all(key).into_iter().filter_map(|value| value.to_str().ok())
+    }
+
+    /// Remove `key`, returning its first value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.entries.remove(key).and_then(|value| value.to_str().ok().map(str::to_string))
+    }
+
+    /// Whether `key` is set
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Key/value pairs, one per value
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?)))
+    }
+
+    /// Number of values
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no metadata is set
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the metadata into request headers, replacing headers of the same name
+    pub fn write_to(&self, headers: &mut HeaderMap) {
+        for name in self.entries.keys() {
+            headers.remove(name);
+        }
+        for (name, value) in &self.entries {
+            headers.append(name.clone(), value.clone());
+        }
+    }
+}
+
+impl fmt::Debug for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Whether a header belongs to the protocol rather than to metadata
+fn is_reserved(name: &str) -> bool {
+    name.starts_with(RESERVED_PREFIX) || RESERVED_HEADERS.contains(&name)
+}
+
+fn entry(key: &str, value: &str) -> Result<(HeaderName, HeaderValue), MetadataError> {
+    let name = HeaderName::from_bytes(key.as_bytes())
+        .map_err(|_| MetadataError::InvalidKey(key.to_string()))?;
+    if is_reserved(name.as_str()) {
+        return Err(MetadataError::Reserved(key.to_string()));
+    }
+    let value =
+        HeaderValue::from_str(value).map_err(|_| MetadataError::InvalidValue(key.to_string()))?;
+    Ok((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let mut metadata = Metadata::new().with("X-Request-Id", "abc").unwrap();
+        metadata.append("x-tag", "a").unwrap();
+        metadata.append("x-tag", "b").unwrap();
+        assert_eq!(metadata.get("x-request-id"), Some("abc"));
+        assert_eq!(metadata.get_all("x-tag").collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(metadata.len(), 3);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("application/proto"));
+        headers.insert("x-tag", HeaderValue::from_static("stale"));
+        metadata.write_to(&mut headers);
+        assert_eq!(headers.get_all("x-tag").iter().count(), 2);
+
+        headers.insert("quill-timeout-ms", HeaderValue::from_static("100"));
+        assert_eq!(Metadata::from_headers(&headers), metadata);
+        assert_eq!(metadata.remove("x-request-id").as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_metadata_rejects_protocol_headers() {
+        let mut metadata = Metadata::new();
+        assert!(matches!(metadata.insert("quill-envelope", "x"), Err(MetadataError::Reserved(_))));
+        assert!(matches!(metadata.insert("Content-Type", "x"), Err(MetadataError::Reserved(_))));
+        assert!(matches!(metadata.insert("bad key", "x"), Err(MetadataError::InvalidKey(_))));
+        assert!(matches!(
+            metadata.insert("x-ok", "line\nbreak"),
+            Err(MetadataError::InvalidValue(_))
+        ));
+        assert!(metadata.is_empty());
+    }
+}
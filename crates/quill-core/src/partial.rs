@@ -0,0 +1,86 @@
+//! Partial results for deadline-bounded streams
+//!
+//! A server that runs out of time ends the stream with a PARTIAL frame
+//! (END_STREAM | PARTIAL) whose payload is a JSON [`PartialStats`] trailer,
+//! telling the caller how complete the results it received are.
+
+use serde::{Deserialize, Serialize};
+
+/// Completeness statistics sent in a PARTIAL trailer
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PartialStats {
+    /// Messages sent before the deadline
+    pub emitted: u64,
+    /// Messages the server expected to send, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<u64>,
+    /// Sources (shards, indexes, ...) that finished before the deadline
+    pub sources_completed: u32,
+    /// Sources the server queried, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sources_total: Option<u32>,
+    /// Time spent before the stream was cut off, in milliseconds
+    pub elapsed_ms: u64,
+    /// Time budget of the stream, in milliseconds
+    pub budget_ms: u64,
+}
+
+impl PartialStats {
+    /// Fraction of the expected results delivered, in `[0, 1]`
+    ///
+    /// Based on sources when known, otherwise on expected messages.
+    pub fn completeness(&self) -> Option<f64> {
+        let ratio = match (self.sources_total, self.expected) {
+            (Some(total), _) if total > 0 => self.sources_completed as f64 / total as f64,
+            (_, Some(expected)) if expected > 0 => self.emitted as f64 / expected as f64,
+            _ => return None,
+        };
+        Some(ratio.min(1.0))
+    }
+
+    /// Encode as a trailer payload
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Decode a trailer payload
+    pub fn from_json(data: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completeness() {
+        let mut stats = PartialStats {
+            emitted: 5,
+            expected: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(stats.completeness(), Some(0.25));
+
+        stats.sources_total = Some(4);
+        stats.sources_completed = 3;
+        assert_eq!(stats.completeness(), Some(0.75));
+
+        assert_eq!(PartialStats::default().completeness(), None);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let stats = PartialStats {
+            emitted: 7,
+            sources_completed: 2,
+            sources_total: Some(3),
+            elapsed_ms: 250,
+            budget_ms: 250,
+            ..Default::default()
+        };
+        let json = stats.to_json().unwrap();
+        assert!(!json.contains("expected"));
+        assert_eq!(PartialStats::from_json(json.as_bytes()).unwrap(), stats);
+    }
+}
@@ -0,0 +1,24 @@
+//! Built-in ping RPC
+//!
+//! Every Quill server answers a lightweight ping method that echoes its
+//! request body. Clients use it to measure round-trip time over the same
+//! transport as regular calls.
+
+/// Service name of the built-in ping RPC
+pub const PING_SERVICE: &str = "quill.ping.v1.Ping";
+
+/// Method name of the built-in ping RPC
+pub const PING_METHOD: &str = "Ping";
+
+/// Full route path of the built-in ping RPC
+pub const PING_PATH: &str = "quill.ping.v1.Ping/Ping";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_path_matches_service_and_method() {
+        assert_eq!(PING_PATH, format!("{}/{}", PING_SERVICE, PING_METHOD));
+    }
+}
@@ -0,0 +1,27 @@
+//! Built-in reflection RPC
+//!
+//! Servers with reflection enabled describe the services they serve. The
+//! reflection method answers with an encoded `google.protobuf.FileDescriptorSet`
+//! holding the files that define those services and everything they import,
+//! so tools can build requests and decode responses without local .proto
+//! files. An empty request asks for every service; a request naming a
+//! service (e.g. `greeter.v1.Greeter`) asks for that service alone.
+
+/// Service name of the built-in reflection RPC
+pub const REFLECTION_SERVICE: &str = "quill.reflection.v1.Reflection";
+
+/// Method name of the built-in reflection RPC
+pub const REFLECTION_METHOD: &str = "Descriptors";
+
+/// Full route path of the built-in reflection RPC
+pub const REFLECTION_PATH: &str = "quill.reflection.v1.Reflection/Descriptors";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflection_path_matches_service_and_method() {
+        assert_eq!(REFLECTION_PATH, format!("{}/{}", REFLECTION_SERVICE, REFLECTION_METHOD));
+    }
+}
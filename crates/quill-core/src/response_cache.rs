@@ -0,0 +1,34 @@
+//! Built-in response cache invalidation RPC
+//!
+//! Servers with a response cache answer cached methods from pre-serialized
+//! bytes without running their handlers. The invalidation method drops
+//! cached responses so the next call runs the handler again. Its request
+//! body names a method (`pkg.Service/Method`) or a whole service
+//! (`pkg.Service`); an empty body drops every cached response. The server
+//! answers with a JSON object counting the dropped entries, e.g.
+//! `{"invalidated":2}`.
+//!
+//! Invalidation is an administrative call; deployments exposing the server
+//! to untrusted clients should restrict it with their auth middleware.
+
+/// Service name of the built-in response cache RPC
+pub const RESPONSE_CACHE_SERVICE: &str = "quill.cache.v1.ResponseCache";
+
+/// Method name of the built-in response cache invalidation RPC
+pub const RESPONSE_CACHE_INVALIDATE_METHOD: &str = "Invalidate";
+
+/// Full route path of the built-in response cache invalidation RPC
+pub const RESPONSE_CACHE_INVALIDATE_PATH: &str = "quill.cache.v1.ResponseCache/Invalidate";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidate_path_matches_service_and_method() {
+        assert_eq!(
+            RESPONSE_CACHE_INVALIDATE_PATH,
+            format!("{}/{}", RESPONSE_CACHE_SERVICE, RESPONSE_CACHE_INVALIDATE_METHOD)
+        );
+    }
+}
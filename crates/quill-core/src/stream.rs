@@ -30,6 +30,11 @@ impl StreamWriter {
         self.frames.push(Frame::data(data));
     }
 
+    /// Send a data frame the server may drop under backpressure
+    pub fn send_droppable(&mut self, data: Bytes) {
+        self.frames.push(Frame::droppable(data));
+    }
+
     /// End the stream
     pub fn end(&mut self) {
         self.frames.push(Frame::end_stream());
@@ -67,4 +72,17 @@ mod tests {
         assert!(frames[1].flags.is_data());
         assert!(frames[2].flags.is_end_stream());
     }
+
+    #[test]
+    fn test_stream_writer_droppable() {
+        let mut writer = StreamWriter::new();
+        writer.send(Bytes::from("hello"));
+        writer.send_droppable(Bytes::from("debug"));
+
+        let frames = writer.into_frames();
+        assert_eq!(frames.len(), 3); // 2 data + 1 end
+        assert!(!frames[0].flags.is_droppable());
+        assert!(frames[1].flags.is_data() && frames[1].flags.is_droppable());
+        assert!(frames[2].flags.is_end_stream());
+    }
 }
@@ -0,0 +1,323 @@
+//! Chunked upload of large unary requests
+//!
+//! This module provides:
+//! - The upload manifest sent with every chunk of a split request
+//! - Reassembly of chunks received in any order
+//! - The capability a server advertises when it accepts chunked uploads
+//!
+//! A large unary request can be split into fixed-size chunks and sent on
+//! parallel request streams. Each chunk carries the manifest
+//! ([`UPLOAD_MANIFEST_HEADER`]) and its index ([`UPLOAD_CHUNK_HEADER`]); the
+//! server reassembles the body and invokes the handler once, answering the
+//! chunk that completed the upload. Servers advertise support with
+//! [`UPLOAD_CAPABILITY_HEADER`] and clients only split after seeing it.
+
+use bytes::{Bytes, BytesMut};
+
+/// Header carrying the upload manifest on every chunk
+pub const UPLOAD_MANIFEST_HEADER: &str = "quill-upload-manifest";
+
+/// Header carrying the index of the chunk in the request body
+pub const UPLOAD_CHUNK_HEADER: &str = "quill-upload-chunk";
+
+/// Response header advertising chunked upload support
+pub const UPLOAD_CAPABILITY_HEADER: &str = "quill-accept-upload";
+
+/// Errors from chunked upload handling
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum UploadError {
+    #[error("Invalid upload manifest: {0}")]
+    InvalidManifest(String),
+
+    #[error("Invalid chunk index: {0}")]
+    InvalidChunkIndex(String),
+
+    #[error("Chunk {index} out of range for {count} chunks")]
+    ChunkOutOfRange { index: u32, count: u32 },
+
+    #[error("Chunk {index} has {actual} bytes, expected {expected}")]
+    ChunkSize { index: u32, expected: u64, actual: u64 },
+
+    #[error("Chunk manifest does not match the upload in progress")]
+    ManifestMismatch,
+}
+
+/// Describes how a request body was split into chunks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadManifest {
+    /// Identifier shared by all chunks of one request
+    pub upload_id: String,
+    /// Size of the reassembled body in bytes
+    pub total_size: u64,
+    /// Size of every chunk but the last
+    pub chunk_size: u64,
+    /// Number of chunks
+    pub chunk_count: u32,
+}
+
+impl UploadManifest {
+    /// Describe a body of `total_size` bytes split into `chunk_size` chunks
+    pub fn new(upload_id: impl Into<String>, total_size: u64, chunk_size: u64) -> Self {
+        let chunk_size = chunk_size.max(1);
+        Self {
+            upload_id: upload_id.into(),
+            total_size,
+            chunk_size,
+            chunk_count: total_size.div_ceil(chunk_size).max(1) as u32,
+        }
+    }
+
+    /// Split a body into chunks, returning the manifest and the chunks in order
+    pub fn split(upload_id: impl Into<String>, body: &Bytes, chunk_size: usize) -> (Self, Vec<Bytes>) {
+        let manifest = Self::new(upload_id, body.len() as u64, chunk_size as u64);
+        let chunks = (0..manifest.chunk_count)
+            .map(|index| {
+                let start = (index as u64 * manifest.chunk_size) as usize;
+                let end = start + manifest.expected_len(index) as usize;
+                body.slice(start..end)
+            })
+            .collect();
+        (manifest, chunks)
+    }
+
+    /// Expected length of the chunk at `index`
+    pub fn expected_len(&self, index: u32) -> u64 {
+        if index + 1 < self.chunk_count {
+            self.chunk_size
+        } else {
+            self.total_size - self.chunk_size * (self.chunk_count as u64 - 1)
+        }
+    }
+
+    /// Check that chunk `index` is in range and has its expected length
+    pub fn check_chunk(&self, index: u32, chunk: &Bytes) -> Result<(), UploadError> {
+        if index >= self.chunk_count {
+            return Err(UploadError::ChunkOutOfRange {
+                index,
+                count: self.chunk_count,
+            });
+        }
+        let expected = self.expected_len(index);
+        if chunk.len() as u64 != expected {
+            return Err(UploadError::ChunkSize {
+                index,
+                expected,
+                actual: chunk.len() as u64,
+            });
+        }
+        Ok(())
+    }
+
+    /// Format as a header value: `id=<id>; size=<n>; chunk-size=<n>; chunks=<n>`
+    pub fn to_header_value(&self) -> String {
+        format!(
+            "id={}; size={}; chunk-size={}; chunks={}",
+            self.upload_id, self.total_size, self.chunk_size, self.chunk_count
+        )
+    }
+
+    /// Parse a manifest header value
+    pub fn from_header_value(value: &str) -> Result<Self, UploadError> {
+        let mut upload_id = None;
+        let mut total_size = None;
+        let mut chunk_size = None;
+        let mut chunk_count = None;
+
+        for part in value.split(';') {
+            let (key, val) = part
+                .trim()
+                .split_once('=')
+                .ok_or_else(|| UploadError::InvalidManifest(value.to_string()))?;
+            let number = || val.parse::<u64>().map_err(|_| UploadError::InvalidManifest(value.to_string()));
+            match key {
+                "id" => upload_id = Some(val.to_string()),
+                "size" => total_size = Some(number()?),
+                "chunk-size" => chunk_size = Some(number()?),
+                "chunks" => chunk_count = Some(number()?),
+                _ => {}
+            }
+        }
+
+        match (upload_id, total_size, chunk_size, chunk_count) {
+            (Some(id), Some(total), Some(size), Some(count)) if !id.is_empty() && size > 0 => {
+                let manifest = Self::new(id, total, size);
+                if manifest.chunk_count as u64 != count {
+                    return Err(UploadError::InvalidManifest(value.to_string()));
+                }
+                Ok(manifest)
+            }
+            _ => Err(UploadError::InvalidManifest(value.to_string())),
+        }
+    }
+}
+
+/// Parse a chunk index header value
+pub fn parse_chunk_index(value: &str) -> Result<u32, UploadError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| UploadError::InvalidChunkIndex(value.to_string()))
+}
+
+/// Collects the chunks of one upload until the body is complete
+#[derive(Debug)]
+pub struct ChunkAssembly {
+    manifest: UploadManifest,
+    chunks: Vec<Option<Bytes>>,
+    received: u32,
+}
+
+impl ChunkAssembly {
+    /// Start collecting chunks for a manifest
+    pub fn new(manifest: UploadManifest) -> Self {
+        Self {
+            chunks: vec![None; manifest.chunk_count as usize],
+            manifest,
+            received: 0,
+        }
+    }
+
+    /// Manifest of this upload
+    pub fn manifest(&self) -> &UploadManifest {
+        &self.manifest
+    }
+
+    /// Add a chunk; duplicates of an already received chunk are ignored
+    pub fn insert(&mut self, index: u32, chunk: Bytes) -> Result<(), UploadError> {
+        self.manifest.check_chunk(index, &chunk)?;
+        let slot = &mut self.chunks[index as usize];
+        if slot.is_none() {
+            *slot = Some(chunk);
+            self.received += 1;
+        }
+        Ok(())
+    }
+
+    /// Number of distinct chunks received
+    pub fn received(&self) -> u32 {
+        self.received
+    }
+
+    /// Whether every chunk has been received
+    pub fn is_complete(&self) -> bool {
+        self.received == self.manifest.chunk_count
+    }
+
+    /// Concatenate the chunks into the original body
+    ///
+    /// Returns `None` if chunks are still missing.
+    pub fn assemble(self) -> Option<Bytes> {
+        if !self.is_complete() {
+            return None;
+        }
+        let mut body = BytesMut::with_capacity(self.manifest.total_size as usize);
+        for chunk in self.chunks.into_iter().flatten() {
+            body.extend_from_slice(&chunk);
+        }
+        Some(body.freeze())
+    }
+}
+
+/// Limits a server accepts for chunked uploads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadCapability {
+    /// Largest reassembled body in bytes
+    pub max_size: u64,
+    /// Most chunks per upload
+    pub max_chunks: u32,
+}
+
+impl UploadCapability {
+    /// Whether an upload described by `manifest` is within these limits
+    pub fn allows(&self, manifest: &UploadManifest) -> bool {
+        manifest.total_size <= self.max_size && manifest.chunk_count <= self.max_chunks
+    }
+
+    /// Format as a header value: `chunked; max-size=<n>; max-chunks=<n>`
+    pub fn to_header_value(&self) -> String {
+        format!("chunked; max-size={}; max-chunks={}", self.max_size, self.max_chunks)
+    }
+
+    /// Parse a capability header value; `None` if chunked uploads are not offered
+    pub fn from_header_value(value: &str) -> Option<Self> {
+        let mut parts = value.split(';').map(str::trim);
+        if parts.next() != Some("chunked") {
+            return None;
+        }
+        let mut capability = Self {
+            max_size: u64::MAX,
+            max_chunks: u32::MAX,
+        };
+        for part in parts {
+            match part.split_once('=') {
+                Some(("max-size", v)) => capability.max_size = v.parse().ok()?,
+                Some(("max-chunks", v)) => capability.max_chunks = v.parse().ok()?,
+                _ => {}
+            }
+        }
+        Some(capability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_assemble_out_of_order() {
+        let body = Bytes::from((0..250u32).map(|i| i as u8).collect::<Vec<_>>());
+        let (manifest, chunks) = UploadManifest::split("u1", &body, 100);
+        assert_eq!(manifest.chunk_count, 3);
+        assert_eq!(chunks.iter().map(Bytes::len).collect::<Vec<_>>(), vec![100, 100, 50]);
+
+        let mut assembly = ChunkAssembly::new(manifest);
+        assembly.insert(2, chunks[2].clone()).unwrap();
+        assembly.insert(0, chunks[0].clone()).unwrap();
+        assembly.insert(0, chunks[0].clone()).unwrap();
+        assert_eq!(assembly.received(), 2);
+        assert!(!assembly.is_complete());
+
+        assembly.insert(1, chunks[1].clone()).unwrap();
+        assert_eq!(assembly.assemble().unwrap(), body);
+    }
+
+    #[test]
+    fn test_chunk_validation() {
+        let mut assembly = ChunkAssembly::new(UploadManifest::new("u1", 10, 4));
+        assert_eq!(
+            assembly.insert(3, Bytes::from_static(b"xx")),
+            Err(UploadError::ChunkOutOfRange { index: 3, count: 3 })
+        );
+        assert_eq!(
+            assembly.insert(0, Bytes::from_static(b"xx")),
+            Err(UploadError::ChunkSize { index: 0, expected: 4, actual: 2 })
+        );
+        assert!(assembly.insert(2, Bytes::from_static(b"xx")).is_ok());
+    }
+
+    #[test]
+    fn test_manifest_header_roundtrip() {
+        let manifest = UploadManifest::new("abc", 3_000_000, 1 << 20);
+        assert_eq!(manifest.chunk_count, 3);
+        let parsed = UploadManifest::from_header_value(&manifest.to_header_value()).unwrap();
+        assert_eq!(parsed, manifest);
+
+        assert!(UploadManifest::from_header_value("id=abc; size=10").is_err());
+        assert!(UploadManifest::from_header_value("id=abc; size=10; chunk-size=4; chunks=2").is_err());
+        assert_eq!(parse_chunk_index(" 7 "), Ok(7));
+        assert!(parse_chunk_index("x").is_err());
+    }
+
+    #[test]
+    fn test_capability_header() {
+        let capability = UploadCapability {
+            max_size: 1024,
+            max_chunks: 4,
+        };
+        let parsed = UploadCapability::from_header_value(&capability.to_header_value()).unwrap();
+        assert_eq!(parsed, capability);
+        assert!(capability.allows(&UploadManifest::new("u", 1024, 256)));
+        assert!(!capability.allows(&UploadManifest::new("u", 1024, 100)));
+        assert_eq!(UploadCapability::from_header_value("none"), None);
+    }
+}
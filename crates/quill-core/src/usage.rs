@@ -0,0 +1,89 @@
+//! Usage trailers for metered streams
+//!
+//! A server that meters its work (tokens generated, compute spent) reports
+//! it in a USAGE frame sent just before END_STREAM. The payload is a JSON
+//! [`Usage`] trailer. Receivers that don't understand USAGE skip it like
+//! any other non-data frame.
+
+use serde::{Deserialize, Serialize};
+
+/// Resources a call consumed, sent in a USAGE trailer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Input units processed (e.g. prompt tokens)
+    pub input_tokens: u64,
+    /// Output units produced (e.g. generated tokens)
+    pub output_tokens: u64,
+    /// Time spent serving the call, in milliseconds
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub compute_ms: u64,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+impl Usage {
+    /// Usage of `input_tokens` in and `output_tokens` out
+    pub fn new(input_tokens: u64, output_tokens: u64) -> Self {
+        Self {
+            input_tokens,
+            output_tokens,
+            compute_ms: 0,
+        }
+    }
+
+    /// Input and output units together
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    /// Whether nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Add another call's usage to this one
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.compute_ms += other.compute_ms;
+    }
+
+    /// Encode as a trailer payload
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Decode a trailer payload
+    pub fn from_json(data: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_total() {
+        let mut usage = Usage::new(12, 30);
+        usage.add(&Usage {
+            input_tokens: 3,
+            output_tokens: 5,
+            compute_ms: 40,
+        });
+        assert_eq!(usage.total_tokens(), 50);
+        assert_eq!(usage.compute_ms, 40);
+        assert!(!usage.is_empty());
+        assert!(Usage::default().is_empty());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let usage = Usage::new(7, 21);
+        let json = usage.to_json().unwrap();
+        assert!(!json.contains("compute_ms"));
+        assert_eq!(Usage::from_json(json.as_bytes()).unwrap(), usage);
+    }
+}
@@ -1,10 +1,14 @@
 //! gRPC to Quill bridge implementation
 
-use crate::status::{grpc_to_problem_details, problem_details_to_grpc_status};
+use crate::metadata::grpc_metadata_to_http_headers;
+use crate::proxy::MethodType;
+use crate::status::{grpc_to_problem_details, problem_details_to_grpc_status, quill_error_to_status};
 use bytes::Bytes;
-use quill_client::QuillClient;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::Metadata;
 use std::sync::Arc;
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::MetadataMap;
 use tonic::{Request, Response, Status};
 use tracing::info;
 
@@ -15,8 +19,10 @@ pub struct GrpcBridgeConfig {
     pub quill_base_url: String,
     /// Enable request/response logging
     pub enable_logging: bool,
-    /// Forward all metadata/headers
+    /// Forward gRPC metadata to Quill handlers as call metadata
     pub forward_metadata: bool,
+    /// gRPC backend that [`GrpcBridge::serve`] proxies Quill calls to
+    pub grpc_backend_url: String,
 }
 
 impl Default for GrpcBridgeConfig {
@@ -25,6 +31,7 @@ impl Default for GrpcBridgeConfig {
             quill_base_url: "http://localhost:8080".to_string(),
             enable_logging: true,
             forward_metadata: true,
+            grpc_backend_url: "http://localhost:50051".to_string(),
         }
     }
 }
@@ -32,9 +39,12 @@ impl Default for GrpcBridgeConfig {
 /// gRPC to Quill protocol bridge
 ///
 /// Translates gRPC requests to Quill RPC calls and responses back to gRPC format.
+/// In the other direction, [`GrpcBridge::serve`] proxies Quill clients to a
+/// gRPC backend.
 pub struct GrpcBridge {
-    config: GrpcBridgeConfig,
+    pub(crate) config: GrpcBridgeConfig,
     client: Arc<QuillClient>,
+    pub(crate) methods: Vec<(String, MethodType)>,
 }
 
 impl GrpcBridge {
@@ -49,9 +59,19 @@ impl GrpcBridge {
         Ok(Self {
             config,
             client: Arc::new(client),
+            methods: Vec::new(),
         })
     }
 
+    /// Request options carrying the call's gRPC metadata, if forwarded
+    fn request_options(&self, metadata: &MetadataMap) -> RequestOptions {
+        let options = RequestOptions::new();
+        if !self.config.forward_metadata {
+            return options;
+        }
+        options.metadata(Metadata::from_headers(&grpc_metadata_to_http_headers(metadata)))
+    }
+
     /// Bridge a unary gRPC call to Quill
     ///
     /// # Arguments
@@ -80,6 +100,7 @@ impl GrpcBridge {
         }
 
         // Extract and encode the request message
+        let options = self.request_options(request.metadata());
         let message = request.into_inner();
         let mut request_bytes = Vec::new();
         message
@@ -89,7 +110,7 @@ impl GrpcBridge {
         // Make Quill RPC call
         let response_bytes = self
             .client
-            .call(service, method, Bytes::from(request_bytes))
+            .call_with_options(service, method, Bytes::from(request_bytes), options)
             .await
             .map_err(|e| self.quill_error_to_grpc_status(e))?;
 
@@ -102,15 +123,7 @@ impl GrpcBridge {
 
     /// Convert Quill error to gRPC status
     fn quill_error_to_grpc_status(&self, error: quill_core::QuillError) -> Status {
-        match error {
-            quill_core::QuillError::ProblemDetails(details) => {
-                let (code, message) = problem_details_to_grpc_status(&details);
-                Status::new(code, message)
-            }
-            quill_core::QuillError::Transport(msg) => Status::unavailable(msg),
-            quill_core::QuillError::Framing(msg) => Status::internal(msg),
-            quill_core::QuillError::Rpc(msg) => Status::unknown(msg),
-        }
+        quill_error_to_status(error)
     }
 
     /// Convert gRPC status to Quill error
@@ -141,6 +154,7 @@ impl GrpcBridge {
         }
 
         // Encode request
+        let options = self.request_options(request.metadata());
         let message = request.into_inner();
         let mut request_bytes = Vec::new();
         message
@@ -150,7 +164,7 @@ impl GrpcBridge {
         // Make Quill server streaming call
         let mut quill_stream = self
             .client
-            .call_server_streaming(&service, &method, Bytes::from(request_bytes))
+            .call_server_streaming_with_options(service, method, Bytes::from(request_bytes), options)
             .await
             .map_err(|e| self.quill_error_to_grpc_status(e))?;
 
@@ -225,6 +239,7 @@ impl GrpcBridge {
             );
         }
 
+        let options = self.request_options(request.metadata());
         let mut grpc_stream = request.into_inner();
 
         // Create a Quill stream from the gRPC stream
@@ -254,7 +269,7 @@ impl GrpcBridge {
         // Make Quill client streaming call
         let response_bytes = self
             .client
-            .call_client_streaming(&service, &method, Box::pin(quill_stream))
+            .call_client_streaming_with_options(service, method, Box::pin(quill_stream), options)
             .await
             .map_err(|e| self.quill_error_to_grpc_status(e))?;
 
@@ -284,6 +299,7 @@ impl GrpcBridge {
             );
         }
 
+        let options = self.request_options(request.metadata());
         let mut grpc_stream = request.into_inner();
 
         // Create a Quill stream from the gRPC stream
@@ -313,7 +329,7 @@ impl GrpcBridge {
         // Make Quill bidirectional streaming call
         let mut quill_response_stream = self
             .client
-            .call_bidi_streaming(&service, &method, Box::pin(quill_stream))
+            .call_bidi_streaming_with_options(service, method, Box::pin(quill_stream), options)
             .await
             .map_err(|e| self.quill_error_to_grpc_status(e))?;
 
@@ -395,6 +411,7 @@ mod tests {
         assert_eq!(config.quill_base_url, "http://localhost:8080");
         assert!(config.enable_logging);
         assert!(config.forward_metadata);
+        assert_eq!(config.grpc_backend_url, "http://localhost:50051");
     }
 
     #[test]
@@ -435,6 +452,7 @@ mod tests {
             quill_base_url: "http://localhost:8080".to_string(),
             enable_logging: true,
             forward_metadata: true,
+            grpc_backend_url: "http://localhost:50051".to_string(),
         };
 
         let bridge = GrpcBridge::new(config);
@@ -447,6 +465,7 @@ mod tests {
             quill_base_url: "http://custom-server:9090".to_string(),
             enable_logging: false,
             forward_metadata: false,
+            grpc_backend_url: "http://localhost:50051".to_string(),
         };
 
         let bridge = GrpcBridge::new(config);
@@ -487,6 +506,8 @@ mod tests {
             instance: None,
             quill_proto_type: None,
             quill_proto_detail_base64: None,
+            quill_code: None,
+            debug: None,
         };
 
         let quill_err = quill_core::QuillError::ProblemDetails(problem);
@@ -502,6 +523,7 @@ mod tests {
             quill_base_url: "http://localhost:8080".to_string(),
             enable_logging: false,
             forward_metadata: true,
+            grpc_backend_url: "http://localhost:50051".to_string(),
         };
 
         assert!(!config.enable_logging);
@@ -0,0 +1,465 @@
+//! gRPC-Web compatibility layer
+//!
+//! This module provides:
+//! - [`GrpcWeb`], which serves browser gRPC-Web clients from Quill handlers
+//! - Unwrapping of the length-prefixed `application/grpc-web(+proto)` request message
+//! - Re-framing of responses with the status sent as a trailer frame in the body
+//! - CORS preflight answers and headers for browser origins
+//!
+//! Calls are dispatched in-process to an [`RpcRouter`], so the same handlers
+//! serve Quill and gRPC-Web clients. Requests that are not gRPC-Web are passed
+//! to the router unchanged. gRPC-Web has no request streams, so only unary
+//! and server-streaming methods can be called; the streaming mode is not on
+//! the wire, so server-streaming methods are declared:
+//!
+//! ```ignore
+//! let grpc_web = GrpcWeb::new(router, GrpcWebConfig::default())
+//!     .server_streaming("inventory.v1.Inventory/Watch");
+//!
+//! grpc_web.serve("0.0.0.0:8080".parse()?).await?;
+//! ```
+//!
+//! A call that fails before its first message is answered trailers-only,
+//! with `grpc-status` and `grpc-message` as headers. Otherwise each Quill
+//! DATA frame becomes a gRPC-Web message and the stream ends with a trailer
+//! frame: `OK` for END_STREAM, and the status of the Problem Details for a
+//! CANCEL. `grpc-timeout` is passed on as the Quill call deadline.
+
+use crate::status::{http_to_grpc_status, problem_details_to_grpc_status, quill_error_to_status};
+use bytes::{BufMut, Bytes, BytesMut};
+use http::header::{HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, ORIGIN, VARY};
+use http::{HeaderMap, Method, Request, Response, StatusCode};
+use http_body::Body;
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Empty, Full, StreamBody};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use quill_core::{FrameParser, ProblemDetails, QuillError, TIMEOUT_HEADER};
+use quill_server::RpcRouter;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_stream::Stream;
+use tonic::{Code, Status};
+use tracing::{error, info};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type ResponseBody = UnsyncBoxBody<Bytes, QuillError>;
+
+/// Flag byte of a message frame
+const MESSAGE_FLAG: u8 = 0x00;
+/// Flag bit of a compressed message, which is not supported
+const COMPRESSED_FLAG: u8 = 0x01;
+/// Flag byte of the trailer frame ending a response
+const TRAILER_FLAG: u8 = 0x80;
+
+/// Request headers gRPC-Web clients send, allowed in every preflight
+const GRPC_WEB_HEADERS: &str = "content-type, x-grpc-web, x-user-agent, grpc-timeout";
+
+/// Configuration for the gRPC-Web layer
+#[derive(Debug, Clone)]
+pub struct GrpcWebConfig {
+    /// Origins browsers may call from; `"*"` allows any origin
+    pub allowed_origins: Vec<String>,
+    /// Request headers allowed besides the gRPC-Web ones, e.g. `authorization`
+    pub allowed_headers: Vec<String>,
+    /// How long browsers may cache a preflight answer
+    pub max_age: Duration,
+    /// Enable request logging
+    pub enable_logging: bool,
+}
+
+impl Default for GrpcWebConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            allowed_headers: Vec::new(),
+            max_age: Duration::from_secs(3600),
+            enable_logging: true,
+        }
+    }
+}
+
+/// Serves gRPC-Web clients from the handlers of an [`RpcRouter`]
+pub struct GrpcWeb {
+    router: Arc<RpcRouter>,
+    config: GrpcWebConfig,
+    server_streaming: HashSet<String>,
+}
+
+impl GrpcWeb {
+    /// Create a gRPC-Web layer in front of `router`
+    pub fn new(router: RpcRouter, config: GrpcWebConfig) -> Self {
+        Self { router: Arc::new(router), config, server_streaming: HashSet::new() }
+    }
+
+    /// Declare `path` ("{package}.{Service}/{Method}") a server-streaming method
+    ///
+    /// Other methods are answered as unary.
+    pub fn server_streaming(mut self, path: impl Into<String>) -> Self {
+        self.server_streaming.insert(path.into());
+        self
+    }
+
+    /// Handle one HTTP request
+    ///
+    /// Answers CORS preflights, translates gRPC-Web calls and passes any
+    /// other request to the router.
+    pub async fn handle<B>(&self, req: Request<B>) -> Response<ResponseBody>
+    where
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let origin = req.headers().get(ORIGIN).cloned();
+        let mut response = if req.method() == Method::OPTIONS {
+            self.preflight()
+        } else {
+            match grpc_web_content_type(req.headers()) {
+                Some(Ok(content_type)) => self.call(req, content_type).await,
+                Some(Err(())) => plain_response(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+                None => self.router.route(req).await,
+            }
+        };
+        self.add_cors_headers(origin.as_ref(), response.headers_mut());
+        response
+    }
+
+    /// Accept HTTP/1.1 and HTTP/2 connections on `addr`
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
+        self.serve_with_listener(TcpListener::bind(addr).await?).await
+    }
+
+    /// Accept HTTP/1.1 and HTTP/2 connections on an already bound `listener`
+    pub async fn serve_with_listener(
+        self,
+        listener: TcpListener,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.config.enable_logging {
+            info!(addr = %listener.local_addr()?, "Serving gRPC-Web clients");
+        }
+
+        let grpc_web = Arc::new(self);
+        loop {
+            let (stream, remote_addr) = listener.accept().await?;
+            let grpc_web = Arc::clone(&grpc_web);
+            tokio::spawn(async move {
+                let service =
+                    hyper::service::service_fn(move |req: Request<hyper::body::Incoming>| {
+                        let grpc_web = Arc::clone(&grpc_web);
+                        async move { Ok::<_, Infallible>(grpc_web.handle(req).await) }
+                    });
+                let builder = auto::Builder::new(TokioExecutor::new());
+                if let Err(e) = builder.serve_connection(TokioIo::new(stream), service).await {
+                    error!("Error serving gRPC-Web connection from {}: {:?}", remote_addr, e);
+                }
+            });
+        }
+    }
+
+    /// Run a gRPC-Web call against the router
+    async fn call<B>(&self, req: Request<B>, content_type: HeaderValue) -> Response<ResponseBody>
+    where
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let path = req.uri().path().trim_start_matches('/').to_string();
+        if self.config.enable_logging {
+            info!(method = %path, "Serving gRPC-Web call");
+        }
+
+        let (mut parts, body) = req.into_parts();
+        let message = match body.collect().await {
+            Ok(body) => decode_request(body.to_bytes()),
+            Err(e) => Err(Status::internal(format!("Failed to read request: {}", e.into()))),
+        };
+        let message =
+            match message.and_then(|message| quill_headers(&mut parts.headers).map(|()| message)) {
+                Ok(message) => message,
+                Err(status) => return trailers_only(content_type, &status),
+            };
+
+        let response = self.router.route(Request::from_parts(parts, Full::new(message))).await;
+        let (parts, body) = response.into_parts();
+        if !parts.status.is_success() {
+            let status = match body.collect().await {
+                Ok(body) => problem_status(parts.status, &body.to_bytes()),
+                Err(e) => quill_error_to_status(e),
+            };
+            return trailers_only(content_type, &status);
+        }
+
+        if self.server_streaming.contains(&path) {
+            let body = StreamBody::new(relay(body)).boxed_unsync();
+            return grpc_web_response(content_type, body);
+        }
+        match body.collect().await {
+            Ok(body) => {
+                let mut buf = BytesMut::new();
+                put_message(&mut buf, MESSAGE_FLAG, &body.to_bytes());
+                put_trailer(&mut buf, &Status::new(Code::Ok, ""));
+                grpc_web_response(content_type, full(buf.freeze()))
+            }
+            Err(e) => trailers_only(content_type, &quill_error_to_status(e)),
+        }
+    }
+
+    /// Answer a CORS preflight
+    fn preflight(&self) -> Response<ResponseBody> {
+        let mut allowed_headers = GRPC_WEB_HEADERS.to_string();
+        for header in &self.config.allowed_headers {
+            allowed_headers.push_str(", ");
+            allowed_headers.push_str(header);
+        }
+
+        let mut response = plain_response(StatusCode::NO_CONTENT);
+        let headers = response.headers_mut();
+        headers.insert("access-control-allow-methods", HeaderValue::from_static("POST, OPTIONS"));
+        if let Ok(value) = HeaderValue::from_str(&allowed_headers) {
+            headers.insert("access-control-allow-headers", value);
+        }
+        headers.insert("access-control-max-age", HeaderValue::from(self.config.max_age.as_secs()));
+        response
+    }
+
+    /// Let browsers at an allowed origin read the response and its status
+    fn add_cors_headers(&self, origin: Option<&HeaderValue>, headers: &mut HeaderMap) {
+        let Some(origin) = origin else {
+            return;
+        };
+        let allowed = &self.config.allowed_origins;
+        if allowed.iter().any(|allowed| allowed == "*") {
+            headers.insert("access-control-allow-origin", HeaderValue::from_static("*"));
+        } else if allowed.iter().any(|allowed| allowed.as_bytes() == origin.as_bytes()) {
+            headers.insert("access-control-allow-origin", origin.clone());
+            headers.append(VARY, HeaderValue::from_static("origin"));
+        } else {
+            return;
+        }
+        headers.insert(
+            "access-control-expose-headers",
+            HeaderValue::from_static("grpc-status, grpc-message"),
+        );
+    }
+}
+
+/// Content type of a gRPC-Web request
+///
+/// `None` for other requests, and `Err` for gRPC-Web formats other than
+/// binary protobuf, such as `application/grpc-web-text`.
+fn grpc_web_content_type(headers: &HeaderMap) -> Option<Result<HeaderValue, ()>> {
+    let value = headers.get(CONTENT_TYPE)?;
+    let media_type = value.to_str().ok()?.split(';').next()?.trim().to_ascii_lowercase();
+    if !media_type.starts_with("application/grpc-web") {
+        return None;
+    }
+    match media_type.as_str() {
+        "application/grpc-web" | "application/grpc-web+proto" => Some(Ok(value.clone())),
+        _ => Some(Err(())),
+    }
+}
+
+/// The one message of a gRPC-Web request body
+fn decode_request(mut body: Bytes) -> Result<Bytes, Status> {
+    if body.len() < 5 {
+        return Err(Status::invalid_argument("Request must hold one length-prefixed message"));
+    }
+    let flag = body[0];
+    let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+    if flag & COMPRESSED_FLAG != 0 {
+        return Err(Status::unimplemented("Compressed messages are not supported"));
+    }
+    if body.len() - 5 != len {
+        return Err(Status::invalid_argument("Request must hold one length-prefixed message"));
+    }
+    Ok(body.split_off(5))
+}
+
+/// Rewrite gRPC-Web request headers for the Quill router
+fn quill_headers(headers: &mut HeaderMap) -> Result<(), Status> {
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/proto"));
+    headers.remove(CONTENT_LENGTH);
+    if let Some(timeout) = headers.remove("grpc-timeout") {
+        let millis = timeout
+            .to_str()
+            .ok()
+            .and_then(grpc_timeout_millis)
+            .ok_or_else(|| Status::invalid_argument("Invalid grpc-timeout"))?;
+        headers.insert(HeaderName::from_static(TIMEOUT_HEADER), HeaderValue::from(millis));
+    }
+    Ok(())
+}
+
+/// Milliseconds of a `grpc-timeout` value such as "250m" or "5S", rounded up
+fn grpc_timeout_millis(value: &str) -> Option<u64> {
+    let unit = value.chars().last()?;
+    let amount: u128 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let nanos_per_unit: u128 = match unit {
+        'H' => 3_600_000_000_000,
+        'M' => 60_000_000_000,
+        'S' => 1_000_000_000,
+        'm' => 1_000_000,
+        'u' => 1_000,
+        'n' => 1,
+        _ => return None,
+    };
+    u64::try_from((amount * nanos_per_unit).div_ceil(1_000_000)).ok()
+}
+
+/// gRPC status of a failed Quill response
+fn problem_status(status: StatusCode, body: &[u8]) -> Status {
+    match serde_json::from_slice::<ProblemDetails>(body) {
+        Ok(problem) => {
+            let (code, message) = problem_details_to_grpc_status(&problem);
+            Status::new(code, message)
+        }
+        Err(_) => {
+            Status::new(http_to_grpc_status(status), status.canonical_reason().unwrap_or_default())
+        }
+    }
+}
+
+/// gRPC-Web body for the Quill frames of a streaming response
+fn relay(
+    mut body: ResponseBody,
+) -> impl Stream<Item = Result<http_body::Frame<Bytes>, QuillError>> + Send + 'static {
+    async_stream::stream! {
+        let mut parser = FrameParser::new();
+        let status = 'relay: loop {
+            loop {
+                match parser.parse_frame() {
+                    Ok(Some(frame)) if frame.flags.is_cancel() && !frame.flags.is_end_stream() => {
+                        break 'relay quill_error_to_status(frame.cancel_error());
+                    }
+                    Ok(Some(frame)) => {
+                        if frame.flags.is_data() {
+                            let mut buf = BytesMut::new();
+                            put_message(&mut buf, MESSAGE_FLAG, &frame.payload);
+                            yield Ok(http_body::Frame::data(buf.freeze()));
+                        }
+                        if frame.flags.is_end_stream() {
+                            break 'relay Status::new(Code::Ok, "");
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => break 'relay Status::internal(format!("Invalid response frame: {}", e)),
+                }
+            }
+            match body.frame().await {
+                Some(Ok(frame)) => {
+                    if let Ok(data) = frame.into_data() {
+                        parser.feed_bytes(data);
+                    }
+                }
+                Some(Err(e)) => break quill_error_to_status(e),
+                None => break Status::internal("Response stream ended before END_STREAM"),
+            }
+        };
+        let mut buf = BytesMut::new();
+        put_trailer(&mut buf, &status);
+        yield Ok(http_body::Frame::data(buf.freeze()));
+    }
+}
+
+/// Append a length-prefixed frame
+fn put_message(buf: &mut BytesMut, flag: u8, payload: &[u8]) {
+    buf.reserve(5 + payload.len());
+    buf.put_u8(flag);
+    buf.put_u32(payload.len() as u32);
+    buf.put_slice(payload);
+}
+
+/// Append the trailer frame carrying `status`
+fn put_trailer(buf: &mut BytesMut, status: &Status) {
+    let mut trailers = format!("grpc-status:{}\r\n", status.code() as i32);
+    if !status.message().is_empty() {
+        trailers.push_str(&format!("grpc-message:{}\r\n", percent_encode(status.message())));
+    }
+    put_message(buf, TRAILER_FLAG, trailers.as_bytes());
+}
+
+/// Percent-encode a `grpc-message` value
+fn percent_encode(message: &str) -> String {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        match byte {
+            b' '..=b'~' if byte != b'%' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn full(bytes: Bytes) -> ResponseBody {
+    Full::new(bytes).map_err(|never| match never {}).boxed_unsync()
+}
+
+fn plain_response(status: StatusCode) -> Response<ResponseBody> {
+    let mut response = Response::new(Empty::new().map_err(|never| match never {}).boxed_unsync());
+    *response.status_mut() = status;
+    response
+}
+
+fn grpc_web_response(content_type: HeaderValue, body: ResponseBody) -> Response<ResponseBody> {
+    let mut response = Response::new(body);
+    response.headers_mut().insert(CONTENT_TYPE, content_type);
+    response
+}
+
+/// Response carrying only a status, in its headers
+fn trailers_only(content_type: HeaderValue, status: &Status) -> Response<ResponseBody> {
+    let mut response = plain_response(StatusCode::OK);
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, content_type);
+    headers.insert("grpc-status", HeaderValue::from(status.code() as i32));
+    if let Ok(message) = HeaderValue::from_str(&percent_encode(status.message())) {
+        headers.insert("grpc-message", message);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_request() {
+        let mut buf = BytesMut::new();
+        put_message(&mut buf, MESSAGE_FLAG, b"hello");
+        assert_eq!(decode_request(buf.freeze()).unwrap(), "hello");
+
+        let mut buf = BytesMut::new();
+        put_message(&mut buf, COMPRESSED_FLAG, b"hello");
+        assert_eq!(decode_request(buf.freeze()).unwrap_err().code(), Code::Unimplemented);
+
+        let mut buf = BytesMut::new();
+        put_message(&mut buf, MESSAGE_FLAG, b"a");
+        put_message(&mut buf, MESSAGE_FLAG, b"b");
+        assert_eq!(decode_request(buf.freeze()).unwrap_err().code(), Code::InvalidArgument);
+        assert_eq!(
+            decode_request(Bytes::from_static(b"\0\0")).unwrap_err().code(),
+            Code::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn test_grpc_timeout_millis() {
+        assert_eq!(grpc_timeout_millis("250m"), Some(250));
+        assert_eq!(grpc_timeout_millis("5S"), Some(5000));
+        assert_eq!(grpc_timeout_millis("2M"), Some(120_000));
+        assert_eq!(grpc_timeout_millis("1500u"), Some(2));
+        assert_eq!(grpc_timeout_millis("1n"), Some(1));
+        assert_eq!(grpc_timeout_millis("5"), None);
+        assert_eq!(grpc_timeout_millis("m"), None);
+        assert_eq!(grpc_timeout_millis("5x"), None);
+    }
+
+    #[test]
+    fn test_trailer_frame() {
+        let mut buf = BytesMut::new();
+        put_trailer(&mut buf, &Status::not_found("no 100% match: café"));
+        assert_eq!(buf[0], TRAILER_FLAG);
+        assert_eq!(&buf[5..], b"grpc-status:5\r\ngrpc-message:no 100%25 match: caf%C3%A9\r\n");
+    }
+}
@@ -5,16 +5,25 @@
 //!
 //! # Features
 //!
-//! - gRPC status code to HTTP status / Problem Details mapping
+//! - gRPC status code to Quill error code, HTTP status and Problem Details mapping
 //! - Metadata to HTTP header translation
 //! - All streaming modes supported (unary, server, client, bidirectional)
+//! - A proxy runtime serving Quill clients from a gRPC backend (`GrpcBridge::serve`)
+//! - A gRPC-Web layer serving browser clients from Quill handlers, with CORS (`GrpcWeb`)
 //! - Transparent protobuf message passing
 //! - Tracing and observability integration
 
 pub mod status;
 pub mod metadata;
 pub mod bridge;
+pub mod proxy;
+pub mod grpc_web;
 
-pub use status::{grpc_to_http_status, grpc_to_problem_details, http_to_grpc_status};
+pub use status::{
+    error_code_to_grpc, grpc_to_error_code, grpc_to_http_status, grpc_to_problem_details,
+    http_to_grpc_status,
+};
 pub use metadata::{grpc_metadata_to_http_headers, http_headers_to_grpc_metadata};
 pub use bridge::{GrpcBridge, GrpcBridgeConfig};
+pub use proxy::{BytesCodec, MethodType};
+pub use grpc_web::{GrpcWeb, GrpcWebConfig};
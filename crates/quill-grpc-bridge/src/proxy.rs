@@ -0,0 +1,341 @@
+//! Quill to gRPC proxy runtime
+//!
+//! This module provides:
+//! - [`GrpcBridge::serve`], which accepts Quill calls and proxies them to a gRPC backend
+//! - Passthrough of all four streaming modes without decoding messages
+//! - Translation of `grpc-status`/`grpc-message` into Problem Details and END_STREAM frames
+//!
+//! The streaming mode of a method is not on the wire, so each proxied
+//! method is registered with its [`MethodType`]:
+//!
+//! ```ignore
+//! let bridge = GrpcBridge::new(GrpcBridgeConfig {
+//!     grpc_backend_url: "http://inventory:50051".to_string(),
+//!     ..Default::default()
+//! })?
+//! .route("inventory.v1.Inventory/Get", MethodType::Unary)
+//! .route("inventory.v1.Inventory/Watch", MethodType::ServerStreaming);
+//!
+//! bridge.serve("0.0.0.0:8080".parse()?).await?;
+//! ```
+//!
+//! A failed call is answered with the Problem Details of its gRPC status
+//! when it fails before any response message, and otherwise ends its
+//! response stream with a CANCEL frame carrying them; a stream the backend
+//! ends with `OK` ends with END_STREAM. Call metadata is forwarded to the
+//! backend when `forward_metadata` is set, and the caller's deadline is
+//! sent as `grpc-timeout`. When the client cancels a call with a request
+//! stream, the backend call is reset instead of half-closed, so the backend
+//! never mistakes a cancelled request stream for a complete one.
+
+use crate::bridge::GrpcBridge;
+use crate::metadata::http_headers_to_grpc_metadata;
+use crate::status::grpc_to_problem_details;
+use bytes::{Buf, BufMut, Bytes};
+use quill_core::{Frame, QuillError};
+use quill_server::router::RequestStream;
+use quill_server::{QuillServer, RequestContext, RpcResponse, RpcRouter};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio_stream::{Stream, StreamExt};
+use tonic::client::Grpc;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Status, Streaming};
+use tracing::info;
+
+/// Streaming mode of a proxied method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodType {
+    /// One request, one response
+    Unary,
+    /// One request, a stream of responses
+    ServerStreaming,
+    /// A stream of requests, one response
+    ClientStreaming,
+    /// Streams in both directions
+    BidirectionalStreaming,
+}
+
+/// Codec passing gRPC messages through as undecoded bytes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BytesCodec;
+
+impl Codec for BytesCodec {
+    type Encode = Bytes;
+    type Decode = Bytes;
+    type Encoder = BytesCodec;
+    type Decoder = BytesCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        BytesCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        BytesCodec
+    }
+}
+
+impl Encoder for BytesCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn encode(&mut self, item: Bytes, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        dst.put(item);
+        Ok(())
+    }
+}
+
+impl Decoder for BytesCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Bytes>, Status> {
+        Ok(Some(src.copy_to_bytes(src.remaining())))
+    }
+}
+
+/// Codec for request streams that may fail part way
+///
+/// A failed message fails the request body, so the backend call is reset
+/// instead of half-closed.
+#[derive(Debug, Clone, Copy, Default)]
+struct RequestStreamCodec;
+
+impl Codec for RequestStreamCodec {
+    type Encode = Result<Bytes, Status>;
+    type Decode = Bytes;
+    type Encoder = RequestStreamCodec;
+    type Decoder = BytesCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        RequestStreamCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        BytesCodec
+    }
+}
+
+impl Encoder for RequestStreamCodec {
+    type Item = Result<Bytes, Status>;
+    type Error = Status;
+
+    fn encode(&mut self, item: Result<Bytes, Status>, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        dst.put(item?);
+        Ok(())
+    }
+}
+
+impl GrpcBridge {
+    /// Proxy calls to `path` ("{package}.{Service}/{Method}") to the gRPC backend
+    pub fn route(mut self, path: impl Into<String>, method_type: MethodType) -> Self {
+        self.methods.push((path.into(), method_type));
+        self
+    }
+
+    /// Router proxying the registered methods, for mounting in a Quill server
+    ///
+    /// Connects to the backend lazily, so it must be called within a Tokio
+    /// runtime but does not need the backend to be up.
+    pub fn router(&self) -> Result<RpcRouter, String> {
+        let channel = Endpoint::from_shared(self.config.grpc_backend_url.clone())
+            .map_err(|e| format!("Invalid gRPC backend URL: {}", e))?
+            .connect_lazy();
+        let backend = Arc::new(Backend {
+            grpc: Grpc::new(channel),
+            forward_metadata: self.config.forward_metadata,
+            enable_logging: self.config.enable_logging,
+        });
+
+        let mut router = RpcRouter::new();
+        for (path, method_type) in &self.methods {
+            let path_and_query = PathAndQuery::try_from(format!("/{}", path))
+                .map_err(|e| format!("Invalid method path '{}': {}", path, e))?;
+            let backend = Arc::clone(&backend);
+            match method_type {
+                MethodType::Unary => router.register_unary(path.clone(), move |req: Bytes| {
+                    let (backend, path) = (Arc::clone(&backend), path_and_query.clone());
+                    async move { backend.unary(path, req).await }
+                }),
+                MethodType::ServerStreaming => router.register(path.clone(), move |req: Bytes| {
+                    let (backend, path) = (Arc::clone(&backend), path_and_query.clone());
+                    async move { backend.server_streaming(path, req).await }
+                }),
+                MethodType::ClientStreaming => {
+                    router.register_client_streaming(path.clone(), move |requests: RequestStream| {
+                        let (backend, path) = (Arc::clone(&backend), path_and_query.clone());
+                        async move { backend.client_streaming(path, requests).await }
+                    })
+                }
+                MethodType::BidirectionalStreaming => {
+                    router.register_bidi_streaming(path.clone(), move |requests: RequestStream| {
+                        let (backend, path) = (Arc::clone(&backend), path_and_query.clone());
+                        async move { backend.bidi_streaming(path, requests).await }
+                    })
+                }
+            }
+        }
+        Ok(router)
+    }
+
+    /// Accept Quill calls on `addr` and proxy them to the gRPC backend
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
+        self.serve_with_listener(TcpListener::bind(addr).await?).await
+    }
+
+    /// Accept Quill calls on an already bound `listener` and proxy them to the gRPC backend
+    pub async fn serve_with_listener(
+        self,
+        listener: TcpListener,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let router = self.router()?;
+        if self.config.enable_logging {
+            info!(
+                addr = %listener.local_addr()?,
+                backend = %self.config.grpc_backend_url,
+                methods = self.methods.len(),
+                "Proxying Quill calls to gRPC backend"
+            );
+        }
+        QuillServer::new(router).serve_with_listener(listener).await
+    }
+}
+
+/// Connection to the gRPC backend shared by the proxied methods
+struct Backend {
+    grpc: Grpc<Channel>,
+    forward_metadata: bool,
+    enable_logging: bool,
+}
+
+impl Backend {
+    /// A ready client and the backend request for the current call
+    async fn prepare<T>(&self, path: &PathAndQuery, message: T) -> Result<(Grpc<Channel>, Request<T>), QuillError> {
+        if self.enable_logging {
+            info!(method = path.as_str(), "Proxying Quill call to gRPC");
+        }
+        let mut request = Request::new(message);
+        if let Some(context) = RequestContext::current() {
+            if self.forward_metadata {
+                let mut headers = http::HeaderMap::new();
+                context.metadata().write_to(&mut headers);
+                *request.metadata_mut() = http_headers_to_grpc_metadata(&headers);
+            }
+            if let Some(remaining) = context.remaining() {
+                request.set_timeout(remaining);
+            }
+        }
+
+        let mut grpc = self.grpc.clone();
+        grpc.ready()
+            .await
+            .map_err(|e| QuillError::Transport(format!("gRPC backend unavailable: {}", e)))?;
+        Ok((grpc, request))
+    }
+
+    async fn unary(&self, path: PathAndQuery, message: Bytes) -> Result<Bytes, QuillError> {
+        let (mut grpc, request) = self.prepare(&path, message).await?;
+        let response = grpc.unary(request, path, BytesCodec).await.map_err(status_error)?;
+        Ok(response.into_inner())
+    }
+
+    async fn server_streaming(&self, path: PathAndQuery, message: Bytes) -> Result<RpcResponse, QuillError> {
+        let (mut grpc, request) = self.prepare(&path, message).await?;
+        let response = grpc.server_streaming(request, path, BytesCodec).await.map_err(status_error)?;
+        Ok(RpcResponse::framed(relay(response.into_inner(), None)))
+    }
+
+    async fn client_streaming(&self, path: PathAndQuery, requests: RequestStream) -> Result<RpcResponse, QuillError> {
+        let (messages, mut failed) = outgoing(requests);
+        let (mut grpc, request) = self.prepare(&path, messages).await?;
+        match grpc.client_streaming(request, path, RequestStreamCodec).await {
+            Ok(response) => Ok(RpcResponse::unary(response.into_inner())),
+            Err(status) => Err(failed.try_recv().unwrap_or_else(|_| status_error(status))),
+        }
+    }
+
+    async fn bidi_streaming(&self, path: PathAndQuery, requests: RequestStream) -> Result<RpcResponse, QuillError> {
+        let (messages, mut failed) = outgoing(requests);
+        let (mut grpc, request) = self.prepare(&path, messages).await?;
+        match grpc.streaming(request, path, RequestStreamCodec).await {
+            Ok(response) => Ok(RpcResponse::framed(relay(response.into_inner(), Some(failed)))),
+            Err(status) => Err(failed.try_recv().unwrap_or_else(|_| status_error(status))),
+        }
+    }
+}
+
+/// Error for a failed gRPC call, carrying its status as Problem Details
+fn status_error(status: Status) -> QuillError {
+    QuillError::ProblemDetails(grpc_to_problem_details(status.code(), status.message().to_string()))
+}
+
+/// Messages of a Quill request stream, to send to the backend
+///
+/// The first error, e.g. the client's CANCEL, fails the request body so the
+/// backend call is reset, and is sent on the returned receiver for the
+/// caller to report in place of the reset's status.
+fn outgoing(
+    mut requests: RequestStream,
+) -> (impl Stream<Item = Result<Bytes, Status>> + Send + 'static, oneshot::Receiver<QuillError>) {
+    let (tx, rx) = oneshot::channel();
+    let messages = async_stream::stream! {
+        while let Some(item) = requests.next().await {
+            match item {
+                Ok(message) => yield Ok(message),
+                Err(e) => {
+                    let status = Status::cancelled(e.to_string());
+                    // Sent first, so it is there once the reset fails the call
+                    let _ = tx.send(e);
+                    yield Err(status);
+                    break;
+                }
+            }
+        }
+    };
+    (messages, rx)
+}
+
+/// Quill frames for a backend response stream
+///
+/// The stream ends with END_STREAM when the backend's status is `OK` and
+/// with a CANCEL frame carrying its Problem Details otherwise. A failure
+/// of the request stream ends it with that error.
+fn relay(
+    mut messages: Streaming<Bytes>,
+    mut failed: Option<oneshot::Receiver<QuillError>>,
+) -> impl Stream<Item = Result<Frame, QuillError>> + Send + 'static {
+    async_stream::stream! {
+        loop {
+            let next = match failed.as_mut() {
+                Some(request_error) => tokio::select! {
+                    biased;
+                    error = request_error => Err(error.ok()),
+                    message = messages.message() => Ok(message),
+                },
+                None => Ok(messages.message().await),
+            };
+            match next {
+                Ok(Ok(Some(message))) => yield Ok(Frame::data(message)),
+                Ok(Ok(None)) => {
+                    yield Ok(Frame::end_stream());
+                    break;
+                }
+                Ok(Err(status)) => {
+                    let problem = grpc_to_problem_details(status.code(), status.message().to_string());
+                    yield Ok(Frame::cancel_with_problem(&problem));
+                    break;
+                }
+                Err(Some(error)) => {
+                    yield Err(error);
+                    break;
+                }
+                // The request stream ended normally
+                Err(None) => failed = None,
+            }
+        }
+    }
+}
@@ -1,58 +1,77 @@
 //! gRPC status code to HTTP status and Problem Details mapping
 
 use http::StatusCode;
-use quill_core::ProblemDetails;
-use tonic::Code;
+use quill_core::{ErrorCode, ProblemDetails, QuillError};
+use tonic::{Code, Status};
+
+/// Convert a gRPC status code to a Quill error code
+///
+/// Returns `None` for `Code::Ok`, which is not an error.
+pub fn grpc_to_error_code(code: Code) -> Option<ErrorCode> {
+    Some(match code {
+        Code::Ok => return None,
+        Code::Cancelled => ErrorCode::Cancelled,
+        Code::Unknown => ErrorCode::Unknown,
+        Code::InvalidArgument => ErrorCode::InvalidArgument,
+        Code::DeadlineExceeded => ErrorCode::DeadlineExceeded,
+        Code::NotFound => ErrorCode::NotFound,
+        Code::AlreadyExists => ErrorCode::AlreadyExists,
+        Code::PermissionDenied => ErrorCode::PermissionDenied,
+        Code::ResourceExhausted => ErrorCode::ResourceExhausted,
+        Code::FailedPrecondition => ErrorCode::FailedPrecondition,
+        Code::Aborted => ErrorCode::Aborted,
+        Code::OutOfRange => ErrorCode::OutOfRange,
+        Code::Unimplemented => ErrorCode::Unimplemented,
+        Code::Internal => ErrorCode::Internal,
+        Code::Unavailable => ErrorCode::Unavailable,
+        Code::DataLoss => ErrorCode::DataLoss,
+        Code::Unauthenticated => ErrorCode::Unauthenticated,
+    })
+}
+
+/// Convert a Quill error code to a gRPC status code
+pub fn error_code_to_grpc(code: ErrorCode) -> Code {
+    match code {
+        ErrorCode::Cancelled => Code::Cancelled,
+        ErrorCode::InvalidArgument => Code::InvalidArgument,
+        ErrorCode::DeadlineExceeded => Code::DeadlineExceeded,
+        ErrorCode::NotFound => Code::NotFound,
+        ErrorCode::AlreadyExists => Code::AlreadyExists,
+        ErrorCode::PermissionDenied => Code::PermissionDenied,
+        ErrorCode::ResourceExhausted => Code::ResourceExhausted,
+        ErrorCode::FailedPrecondition => Code::FailedPrecondition,
+        ErrorCode::Aborted => Code::Aborted,
+        ErrorCode::OutOfRange => Code::OutOfRange,
+        ErrorCode::Unimplemented => Code::Unimplemented,
+        ErrorCode::Internal => Code::Internal,
+        ErrorCode::Unavailable => Code::Unavailable,
+        ErrorCode::DataLoss => Code::DataLoss,
+        ErrorCode::Unauthenticated => Code::Unauthenticated,
+        _ => Code::Unknown,
+    }
+}
 
 /// Convert gRPC status code to HTTP status code
 ///
 /// Maps gRPC canonical error codes to appropriate HTTP status codes
 /// following common REST API conventions.
 pub fn grpc_to_http_status(code: Code) -> StatusCode {
-    match code {
-        Code::Ok => StatusCode::OK,
-        Code::Cancelled => StatusCode::from_u16(499).unwrap(), // Client Closed Request
-        Code::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
-        Code::InvalidArgument => StatusCode::BAD_REQUEST,
-        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
-        Code::NotFound => StatusCode::NOT_FOUND,
-        Code::AlreadyExists => StatusCode::CONFLICT,
-        Code::PermissionDenied => StatusCode::FORBIDDEN,
-        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
-        Code::FailedPrecondition => StatusCode::BAD_REQUEST,
-        Code::Aborted => StatusCode::CONFLICT,
-        Code::OutOfRange => StatusCode::BAD_REQUEST,
-        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
-        Code::Internal => StatusCode::INTERNAL_SERVER_ERROR,
-        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
-        Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
-        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
-    }
+    grpc_to_error_code(code).map_or(StatusCode::OK, |code| code.http_status())
 }
 
 /// Convert HTTP status code to gRPC status code
 pub fn http_to_grpc_status(status: StatusCode) -> Code {
-    match status.as_u16() {
-        200..=299 => Code::Ok,
-        400 => Code::InvalidArgument,
-        401 => Code::Unauthenticated,
-        403 => Code::PermissionDenied,
-        404 => Code::NotFound,
-        409 => Code::AlreadyExists,
-        412 => Code::FailedPrecondition,
-        429 => Code::ResourceExhausted,
-        499 => Code::Cancelled,
-        500 => Code::Internal,
-        501 => Code::Unimplemented,
-        503 => Code::Unavailable,
-        504 => Code::DeadlineExceeded,
-        _ => Code::Unknown,
+    if status.is_success() {
+        return Code::Ok;
     }
+    error_code_to_grpc(ErrorCode::from_http_status(status.as_u16()))
 }
 
 /// Convert gRPC status to Quill Problem Details
 ///
 /// Creates a Problem Details structure following RFC 7807 from a gRPC status.
+/// The gRPC code is carried as `quill_code`, so codes sharing an HTTP status
+/// (such as `ABORTED` and `ALREADY_EXISTS`) survive the round trip.
 pub fn grpc_to_problem_details(code: Code, message: String) -> ProblemDetails {
     let http_status = grpc_to_http_status(code);
 
@@ -64,14 +83,16 @@ pub fn grpc_to_problem_details(code: Code, message: String) -> ProblemDetails {
         instance: None,
         quill_proto_type: None,
         quill_proto_detail_base64: None,
+        quill_code: grpc_to_error_code(code),
+        debug: None,
     }
 }
 
 /// Convert HTTP status and Problem Details to gRPC status
+///
+/// Uses the problem's error code, falling back to its HTTP status.
 pub fn problem_details_to_grpc_status(details: &ProblemDetails) -> (Code, String) {
-    let code = http_to_grpc_status(
-        StatusCode::from_u16(details.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
-    );
+    let code = error_code_to_grpc(details.code());
 
     let message = details.detail.clone()
         .unwrap_or_else(|| details.title.clone());
@@ -79,6 +100,20 @@ pub fn problem_details_to_grpc_status(details: &ProblemDetails) -> (Code, String
     (code, message)
 }
 
+/// Convert a Quill error to the gRPC status reporting it
+pub(crate) fn quill_error_to_status(error: QuillError) -> Status {
+    let code = error_code_to_grpc(error.code());
+    match error {
+        QuillError::ProblemDetails(details) => {
+            let (_, message) = problem_details_to_grpc_status(&details);
+            Status::new(code, message)
+        }
+        QuillError::Transport(msg) | QuillError::Framing(msg) | QuillError::Rpc(msg) => {
+            Status::new(code, msg)
+        }
+    }
+}
+
 fn code_to_string(code: Code) -> &'static str {
     match code {
         Code::Ok => "OK",
@@ -169,6 +204,8 @@ mod tests {
             instance: None,
             quill_proto_type: None,
             quill_proto_detail_base64: None,
+            quill_code: None,
+            debug: None,
         };
 
         let (code, message) = problem_details_to_grpc_status(&details);
@@ -184,4 +221,26 @@ mod tests {
 
         assert_eq!(original_code, converted_code);
     }
+
+    #[test]
+    fn test_error_code_table_is_lossless() {
+        assert_eq!(grpc_to_error_code(Code::Ok), None);
+        for code in ErrorCode::ALL {
+            assert_eq!(grpc_to_error_code(error_code_to_grpc(code)), Some(code));
+        }
+    }
+
+    #[test]
+    fn test_problem_details_keep_grpc_code() {
+        // ABORTED shares 409 with ALREADY_EXISTS, but the code is carried along
+        let details = grpc_to_problem_details(Code::Aborted, "Write conflict".to_string());
+        assert_eq!(details.status, 409);
+        assert_eq!(details.quill_code, Some(ErrorCode::Aborted));
+        assert_eq!(problem_details_to_grpc_status(&details).0, Code::Aborted);
+
+        // Problems without a code fall back to the HTTP status
+        let details = ProblemDetails { quill_code: None, ..details };
+        assert_eq!(problem_details_to_grpc_status(&details).0, Code::AlreadyExists);
+        assert_eq!(http_to_grpc_status(StatusCode::BAD_GATEWAY), Code::Unavailable);
+    }
 }
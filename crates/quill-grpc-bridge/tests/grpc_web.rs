@@ -0,0 +1,215 @@
+//! End-to-end tests for serving gRPC-Web clients from Quill handlers
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http::{Method, Request, Response, StatusCode};
+use http_body_util::{BodyExt, Full};
+use quill_core::{Frame, ProblemDetails, QuillError};
+use quill_grpc_bridge::{GrpcWeb, GrpcWebConfig};
+use quill_server::{RpcResponse, RpcRouter};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn router() -> RpcRouter {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.v1.Web/Echo", |req: Bytes| async move {
+        match &req[..] {
+            b"missing" => Err(QuillError::ProblemDetails(
+                ProblemDetails::new(StatusCode::NOT_FOUND, "Not Found").with_detail("no such item"),
+            )),
+            b"slow" => {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                Ok(req)
+            }
+            _ => Ok(req),
+        }
+    });
+    router.register("test.v1.Web/Count", |req: Bytes| async move {
+        let n: usize = String::from_utf8(req.to_vec()).unwrap().parse().unwrap();
+        let messages = (0..n).map(|i| Ok(Bytes::from(i.to_string())));
+        Ok(RpcResponse::streaming(tokio_stream::iter(messages)))
+    });
+    router.register("test.v1.Web/Quota", |_req: Bytes| async move {
+        let problem = ProblemDetails::new(StatusCode::TOO_MANY_REQUESTS, "Too Many Requests")
+            .with_detail("quota exceeded");
+        let frames = vec![
+            Ok(Frame::data(Bytes::from_static(b"first"))),
+            Ok(Frame::cancel_with_problem(&problem)),
+        ];
+        Ok(RpcResponse::framed(tokio_stream::iter(frames)))
+    });
+    router
+}
+
+fn grpc_web(config: GrpcWebConfig) -> GrpcWeb {
+    GrpcWeb::new(router(), GrpcWebConfig { enable_logging: false, ..config })
+        .server_streaming("test.v1.Web/Count")
+        .server_streaming("test.v1.Web/Quota")
+}
+
+fn framed(message: &[u8]) -> Bytes {
+    let mut buf = BytesMut::new();
+    buf.put_u8(0);
+    buf.put_u32(message.len() as u32);
+    buf.put_slice(message);
+    buf.freeze()
+}
+
+fn request(path: &str) -> http::request::Builder {
+    Request::builder()
+        .method(Method::POST)
+        .uri(format!("/{}", path))
+        .header("content-type", "application/grpc-web+proto")
+        .header("x-grpc-web", "1")
+}
+
+async fn call(
+    grpc_web: &GrpcWeb,
+    builder: http::request::Builder,
+    message: &[u8],
+) -> (Response<()>, Bytes) {
+    let response = grpc_web.handle(builder.body(Full::new(framed(message))).unwrap()).await;
+    let (parts, body) = response.into_parts();
+    (Response::from_parts(parts, ()), body.collect().await.unwrap().to_bytes())
+}
+
+/// Messages and trailers of a gRPC-Web response body
+fn decode(mut body: Bytes) -> (Vec<Bytes>, String) {
+    let mut messages = Vec::new();
+    let mut trailers = String::new();
+    while !body.is_empty() {
+        let flag = body[0];
+        let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+        let payload = body.split_to(5 + len).split_off(5);
+        if flag & 0x80 != 0 {
+            trailers = String::from_utf8(payload.to_vec()).unwrap();
+        } else {
+            messages.push(payload);
+        }
+    }
+    (messages, trailers)
+}
+
+fn header<'a>(response: &'a Response<()>, name: &str) -> Option<&'a str> {
+    response.headers().get(name).map(|value| value.to_str().unwrap())
+}
+
+#[tokio::test]
+async fn test_unary_calls_are_reframed_with_trailers() {
+    let grpc_web = grpc_web(GrpcWebConfig::default());
+
+    let (response, body) = call(&grpc_web, request("test.v1.Web/Echo"), b"hello").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "content-type"), Some("application/grpc-web+proto"));
+    let (messages, trailers) = decode(body);
+    assert_eq!(messages, ["hello"]);
+    assert_eq!(trailers, "grpc-status:0\r\n");
+
+    // Failures before a message are sent trailers-only
+    let (response, body) = call(&grpc_web, request("test.v1.Web/Echo"), b"missing").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "grpc-status"), Some("5"));
+    assert_eq!(header(&response, "grpc-message"), Some("no such item"));
+    assert!(body.is_empty());
+
+    // grpc-timeout becomes the call's deadline
+    let slow = request("test.v1.Web/Echo").header("grpc-timeout", "50m");
+    let (response, _) = call(&grpc_web, slow, b"slow").await;
+    assert_eq!(header(&response, "grpc-status"), Some("4"));
+}
+
+#[tokio::test]
+async fn test_server_streams_end_with_a_trailer_frame() {
+    let grpc_web = grpc_web(GrpcWebConfig::default());
+
+    let (_, body) = call(&grpc_web, request("test.v1.Web/Count"), b"3").await;
+    let (messages, trailers) = decode(body);
+    assert_eq!(messages, ["0", "1", "2"]);
+    assert_eq!(trailers, "grpc-status:0\r\n");
+
+    let (_, body) = call(&grpc_web, request("test.v1.Web/Quota"), b"").await;
+    let (messages, trailers) = decode(body);
+    assert_eq!(messages, ["first"]);
+    assert_eq!(trailers, "grpc-status:8\r\ngrpc-message:quota exceeded\r\n");
+}
+
+#[tokio::test]
+async fn test_cors_and_other_requests() {
+    let grpc_web = grpc_web(GrpcWebConfig {
+        allowed_origins: vec!["https://app.example.com".to_string()],
+        allowed_headers: vec!["authorization".to_string()],
+        ..Default::default()
+    });
+
+    let preflight = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/test.v1.Web/Echo")
+        .header("origin", "https://app.example.com")
+        .header("access-control-request-method", "POST")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let response = grpc_web.handle(preflight).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let headers = response.headers();
+    assert_eq!(headers["access-control-allow-origin"], "https://app.example.com");
+    assert_eq!(headers["access-control-allow-methods"], "POST, OPTIONS");
+    let allowed = headers["access-control-allow-headers"].to_str().unwrap();
+    assert!(allowed.contains("x-grpc-web") && allowed.ends_with("authorization"), "{}", allowed);
+    assert_eq!(headers["access-control-max-age"], "3600");
+
+    let allowed = request("test.v1.Web/Echo").header("origin", "https://app.example.com");
+    let (response, _) = call(&grpc_web, allowed, b"hi").await;
+    assert_eq!(
+        header(&response, "access-control-expose-headers"),
+        Some("grpc-status, grpc-message")
+    );
+
+    let other = request("test.v1.Web/Echo").header("origin", "https://evil.example.com");
+    let (response, _) = call(&grpc_web, other, b"hi").await;
+    assert_eq!(header(&response, "access-control-allow-origin"), None);
+
+    // Only binary gRPC-Web is supported
+    let text = Request::builder()
+        .method(Method::POST)
+        .uri("/test.v1.Web/Echo")
+        .header("content-type", "application/grpc-web-text")
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    assert_eq!(grpc_web.handle(text).await.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    // Quill calls pass through unchanged
+    let quill = Request::builder()
+        .method(Method::POST)
+        .uri("/test.v1.Web/Echo")
+        .header("content-type", "application/proto")
+        .body(Full::new(Bytes::from_static(b"raw")))
+        .unwrap();
+    let body = grpc_web.handle(quill).await.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, "raw");
+}
+
+#[tokio::test]
+async fn test_serve_over_http1() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = grpc_web(GrpcWebConfig::default()).serve_with_listener(listener).await;
+    });
+
+    let message = framed(b"hello");
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let head = format!(
+        "POST /test.v1.Web/Echo HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/grpc-web\r\n\
+         Origin: https://app.example.com\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        message.len()
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(&message).await.unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+
+    let response = String::from_utf8_lossy(&response).to_lowercase();
+    assert!(response.starts_with("http/1.1 200 ok"), "{}", response);
+    assert!(response.contains("content-type: application/grpc-web\r\n"), "{}", response);
+    assert!(response.contains("access-control-allow-origin: *"), "{}", response);
+    assert!(response.contains("hello") && response.contains("grpc-status:0"), "{}", response);
+}
@@ -0,0 +1,244 @@
+//! End-to-end tests for proxying Quill clients to a gRPC backend
+
+use bytes::Bytes;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{Metadata, QuillError};
+use quill_grpc_bridge::{BytesCodec, GrpcBridge, GrpcBridgeConfig, MethodType};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_stream::StreamExt;
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, BoxStream, Context, Poll, Service};
+use tonic::server::{ClientStreamingService, NamedService, ServerStreamingService, StreamingService, UnaryService};
+use tonic::transport::Body;
+use tonic::{Request, Response, Status, Streaming};
+
+/// gRPC backend speaking raw bytes, like a tonic service would
+#[derive(Clone)]
+struct Backend;
+
+impl NamedService for Backend {
+    const NAME: &'static str = "test.v1.Backend";
+}
+
+impl Service<http::Request<Body>> for Backend {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(BytesCodec);
+            Ok(match req.uri().path() {
+                "/test.v1.Backend/Echo" => grpc.unary(Echo, req).await,
+                "/test.v1.Backend/Count" => grpc.server_streaming(Count, req).await,
+                "/test.v1.Backend/Join" => grpc.client_streaming(Join, req).await,
+                "/test.v1.Backend/Shout" => grpc.streaming(Shout, req).await,
+                _ => Status::unimplemented("no such method").to_http(),
+            })
+        })
+    }
+}
+
+/// Returns the request; "whoami" returns the caller's `x-user` metadata
+struct Echo;
+
+impl UnaryService<Bytes> for Echo {
+    type Response = Bytes;
+    type Future = BoxFuture<Response<Bytes>, Status>;
+
+    fn call(&mut self, request: Request<Bytes>) -> Self::Future {
+        Box::pin(async move {
+            let user = request.metadata().get("x-user").map(|v| v.to_str().unwrap().to_string());
+            match &request.into_inner()[..] {
+                b"whoami" => Ok(Response::new(Bytes::from(user.unwrap_or_default()))),
+                b"missing" => Err(Status::not_found("no such item")),
+                other => Ok(Response::new(Bytes::copy_from_slice(other))),
+            }
+        })
+    }
+}
+
+/// Counts up to the request; a trailing '!' fails after the last message
+struct Count;
+
+impl ServerStreamingService<Bytes> for Count {
+    type Response = Bytes;
+    type ResponseStream = BoxStream<Bytes>;
+    type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, request: Request<Bytes>) -> Self::Future {
+        Box::pin(async move {
+            let text = String::from_utf8(request.into_inner().to_vec()).unwrap();
+            let fail = text.ends_with('!');
+            let n: usize = text.trim_end_matches('!').parse().unwrap();
+            let messages = tokio_stream::iter((0..n).map(|i| Ok(Bytes::from(i.to_string()))));
+            // tonic drops messages still buffered when the stream fails, so
+            // the status comes separately
+            let status = async_stream::stream! {
+                if fail {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    yield Err(Status::resource_exhausted("quota exceeded"));
+                }
+            };
+            let stream: Self::ResponseStream = Box::pin(messages.chain(status));
+            Ok(Response::new(stream))
+        })
+    }
+}
+
+/// Joins the request messages with commas
+struct Join;
+
+impl ClientStreamingService<Bytes> for Join {
+    type Response = Bytes;
+    type Future = BoxFuture<Response<Bytes>, Status>;
+
+    fn call(&mut self, request: Request<Streaming<Bytes>>) -> Self::Future {
+        Box::pin(async move {
+            let mut requests = request.into_inner();
+            let mut parts = Vec::new();
+            while let Some(message) = requests.message().await? {
+                parts.push(String::from_utf8(message.to_vec()).unwrap());
+            }
+            Ok(Response::new(Bytes::from(parts.join(","))))
+        })
+    }
+}
+
+/// Answers each request message in upper case
+struct Shout;
+
+impl StreamingService<Bytes> for Shout {
+    type Response = Bytes;
+    type ResponseStream = BoxStream<Bytes>;
+    type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, request: Request<Streaming<Bytes>>) -> Self::Future {
+        Box::pin(async move {
+            let replies = request
+                .into_inner()
+                .map(|message| message.map(|m| Bytes::from(m.to_ascii_uppercase())));
+            let stream: Self::ResponseStream = Box::pin(replies);
+            Ok(Response::new(stream))
+        })
+    }
+}
+
+/// Start the gRPC backend and a bridge in front of it
+async fn spawn() -> QuillClient {
+    let backend = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let backend_addr = backend.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = tonic::transport::Server::builder()
+            .add_service(Backend)
+            .serve_with_incoming(TcpListenerStream::new(backend))
+            .await;
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bridge_addr = listener.local_addr().unwrap();
+    let bridge = GrpcBridge::new(GrpcBridgeConfig {
+        grpc_backend_url: format!("http://{}", backend_addr),
+        enable_logging: false,
+        ..Default::default()
+    })
+    .unwrap()
+    .route("test.v1.Backend/Echo", MethodType::Unary)
+    .route("test.v1.Backend/Count", MethodType::ServerStreaming)
+    .route("test.v1.Backend/Join", MethodType::ClientStreaming)
+    .route("test.v1.Backend/Shout", MethodType::BidirectionalStreaming)
+    .route("test.v1.Backend/Missing", MethodType::Unary);
+    tokio::spawn(async move {
+        let _ = bridge.serve_with_listener(listener).await;
+    });
+
+    QuillClient::builder()
+        .base_url(format!("http://{}", bridge_addr))
+        .http2_only()
+        .build()
+        .unwrap()
+}
+
+type RequestStream = std::pin::Pin<Box<dyn tokio_stream::Stream<Item = Result<Bytes, QuillError>> + Send>>;
+
+fn requests(messages: &[&'static str]) -> RequestStream {
+    let messages: Vec<_> = messages.iter().map(|m| Ok(Bytes::from_static(m.as_bytes()))).collect();
+    Box::pin(tokio_stream::iter(messages))
+}
+
+fn problem_status(error: QuillError) -> (u16, String) {
+    match error {
+        QuillError::ProblemDetails(problem) => (problem.status, problem.detail.unwrap_or_default()),
+        other => panic!("Expected Problem Details, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_unary_calls_pass_through_with_metadata_and_statuses() {
+    let client = spawn().await;
+
+    let reply = client.call("test.v1.Backend", "Echo", Bytes::from_static(b"hello")).await.unwrap();
+    assert_eq!(reply, "hello");
+
+    let options = RequestOptions::new().metadata(Metadata::new().with("x-user", "ada").unwrap());
+    let reply = client
+        .call_with_options("test.v1.Backend", "Echo", Bytes::from_static(b"whoami"), options)
+        .await
+        .unwrap();
+    assert_eq!(reply, "ada");
+
+    let error = client.call("test.v1.Backend", "Echo", Bytes::from_static(b"missing")).await.unwrap_err();
+    let (status, detail) = problem_status(error);
+    assert_eq!(status, 404);
+    assert!(detail.contains("no such item"), "{}", detail);
+
+    // Statuses sent without a response, as trailers-only responses
+    let error = client.call("test.v1.Backend", "Missing", Bytes::new()).await.unwrap_err();
+    assert_eq!(problem_status(error).0, 501);
+}
+
+#[tokio::test]
+async fn test_server_streams_end_with_the_backend_status() {
+    let client = spawn().await;
+
+    let stream = client
+        .call_server_streaming("test.v1.Backend", "Count", Bytes::from_static(b"3"))
+        .await
+        .unwrap();
+    let messages: Vec<_> = stream.map(|m| m.unwrap()).collect().await;
+    assert_eq!(messages, ["0", "1", "2"]);
+
+    let mut stream = client
+        .call_server_streaming("test.v1.Backend", "Count", Bytes::from_static(b"2!"))
+        .await
+        .unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), "0");
+    assert_eq!(stream.next().await.unwrap().unwrap(), "1");
+    let (status, detail) = problem_status(stream.next().await.unwrap().unwrap_err());
+    assert_eq!(status, 429);
+    assert!(detail.contains("quota exceeded"), "{}", detail);
+}
+
+#[tokio::test]
+async fn test_request_streams_pass_through() {
+    let client = spawn().await;
+
+    let reply = client
+        .call_client_streaming("test.v1.Backend", "Join", requests(&["a", "b", "c"]))
+        .await
+        .unwrap();
+    assert_eq!(reply, "a,b,c");
+
+    let stream = client
+        .call_bidi_streaming("test.v1.Backend", "Shout", requests(&["hi", "there"]))
+        .await
+        .unwrap();
+    let replies: Vec<_> = stream.map(|m| m.unwrap()).collect().await;
+    assert_eq!(replies, ["HI", "THERE"]);
+}
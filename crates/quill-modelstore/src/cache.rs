@@ -0,0 +1,169 @@
+//! Local model cache
+//!
+//! Shards are stored as `<root>/<model>/<shard>`. Downloads in progress are
+//! written to `<shard>.partial` and renamed once their digest verifies, so a
+//! shard file under its final name is always complete.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{validate_name, ModelStoreResult};
+use crate::manifest::{sha256_file, Manifest, ShardInfo};
+
+/// File name of the cached manifest inside a model directory.
+const MANIFEST_FILE: &str = ".manifest.json";
+
+/// Suffix for shards that are still downloading.
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Local cache of downloaded models.
+#[derive(Debug, Clone)]
+pub struct ModelCache {
+    root: PathBuf,
+}
+
+impl ModelCache {
+    /// Create a cache rooted at `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Root directory of the cache
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory holding a model's shards
+    pub fn model_dir(&self, model: &str) -> ModelStoreResult<PathBuf> {
+        validate_name(model)?;
+        Ok(self.root.join(model))
+    }
+
+    /// Final path of a verified shard
+    pub fn shard_path(&self, model: &str, shard: &str) -> ModelStoreResult<PathBuf> {
+        validate_name(shard)?;
+        Ok(self.model_dir(model)?.join(shard))
+    }
+
+    /// Path of a shard that is still downloading
+    pub fn partial_path(&self, model: &str, shard: &str) -> ModelStoreResult<PathBuf> {
+        validate_name(shard)?;
+        Ok(self.model_dir(model)?.join(format!("{}{}", shard, PARTIAL_SUFFIX)))
+    }
+
+    /// Number of bytes already downloaded for a shard
+    pub fn partial_len(&self, model: &str, shard: &str) -> ModelStoreResult<u64> {
+        match std::fs::metadata(self.partial_path(model, shard)?) {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Check that a cached shard exists with the expected size and digest
+    pub fn verify_shard(&self, model: &str, shard: &ShardInfo) -> ModelStoreResult<bool> {
+        let path = self.shard_path(model, &shard.name)?;
+        match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.len() == shard.size => {
+                Ok(sha256_file(&path)? == shard.sha256)
+            }
+            Ok(_) => Ok(false),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Return the shards of `manifest` that are missing or fail verification
+    pub fn missing_shards<'a>(&self, manifest: &'a Manifest) -> ModelStoreResult<Vec<&'a ShardInfo>> {
+        let mut missing = Vec::new();
+        for shard in &manifest.shards {
+            if !self.verify_shard(&manifest.model, shard)? {
+                missing.push(shard);
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Load the manifest stored alongside a cached model
+    pub fn load_manifest(&self, model: &str) -> ModelStoreResult<Option<Manifest>> {
+        let path = self.model_dir(model)?.join(MANIFEST_FILE);
+        match std::fs::read(&path) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store a manifest alongside the cached model
+    pub fn save_manifest(&self, manifest: &Manifest) -> ModelStoreResult<()> {
+        let dir = self.model_dir(&manifest.model)?;
+        std::fs::create_dir_all(&dir)?;
+
+        let tmp = dir.join(format!("{}{}", MANIFEST_FILE, PARTIAL_SUFFIX));
+        std::fs::write(&tmp, serde_json::to_vec_pretty(manifest)?)?;
+        std::fs::rename(&tmp, dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shard(name: &str, data: &[u8], sha256: &str) -> ShardInfo {
+        ShardInfo {
+            name: name.to_string(),
+            size: data.len() as u64,
+            sha256: sha256.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_verify_shard() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModelCache::new(dir.path());
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let info = shard("a.bin", b"abc", abc);
+        assert!(!cache.verify_shard("tiny", &info).unwrap());
+
+        std::fs::create_dir_all(cache.model_dir("tiny").unwrap()).unwrap();
+        std::fs::write(cache.shard_path("tiny", "a.bin").unwrap(), b"abd").unwrap();
+        assert!(!cache.verify_shard("tiny", &info).unwrap());
+
+        std::fs::write(cache.shard_path("tiny", "a.bin").unwrap(), b"abc").unwrap();
+        assert!(cache.verify_shard("tiny", &info).unwrap());
+
+        let manifest = Manifest {
+            model: "tiny".to_string(),
+            shards: vec![info, shard("b.bin", b"x", "00")],
+        };
+        let missing = cache.missing_shards(&manifest).unwrap();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].name, "b.bin");
+    }
+
+    #[test]
+    fn test_manifest_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModelCache::new(dir.path());
+        assert!(cache.load_manifest("tiny").unwrap().is_none());
+
+        let manifest = Manifest {
+            model: "tiny".to_string(),
+            shards: vec![shard("a.bin", b"abc", "00")],
+        };
+        cache.save_manifest(&manifest).unwrap();
+        assert_eq!(cache.load_manifest("tiny").unwrap(), Some(manifest));
+    }
+
+    #[test]
+    fn test_paths_reject_traversal() {
+        let cache = ModelCache::new("/tmp/cache");
+        assert!(cache.shard_path("tiny", "../../etc/passwd").is_err());
+        assert!(cache.model_dir("..").is_err());
+        assert_eq!(
+            cache.partial_path("tiny", "a.bin").unwrap(),
+            PathBuf::from("/tmp/cache/tiny/a.bin.partial")
+        );
+    }
+}
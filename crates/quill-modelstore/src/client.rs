@@ -0,0 +1,247 @@
+//! Model store client
+//!
+//! Downloads model shards in parallel into a [`ModelCache`], resuming any
+//! partially downloaded shard from where it stopped.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_tensor::stream::TensorStreamError;
+use quill_tensor::{FrameType, TensorFrameParser};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_stream::StreamExt;
+
+use crate::cache::ModelCache;
+use crate::error::{ModelStoreError, ModelStoreResult};
+use crate::manifest::{sha256_file, FetchShardRequest, Manifest, ManifestRequest, ShardInfo};
+use crate::{METHOD_FETCH_SHARD, METHOD_GET_MANIFEST, SERVICE_NAME};
+
+/// Options for [`ModelStoreClient::pull`].
+#[derive(Debug, Clone)]
+pub struct PullOptions {
+    /// Maximum number of shards downloaded concurrently
+    pub concurrency: usize,
+    /// Resume partial downloads instead of starting over
+    pub resume: bool,
+}
+
+impl Default for PullOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            resume: true,
+        }
+    }
+}
+
+/// Summary of a completed pull.
+#[derive(Debug, Clone, Default)]
+pub struct PullReport {
+    /// Total number of shards in the manifest
+    pub shards: usize,
+    /// Shards already present and verified in the cache
+    pub cached_shards: usize,
+    /// Bytes fetched over the network
+    pub downloaded_bytes: u64,
+    /// Bytes reused from partial downloads
+    pub resumed_bytes: u64,
+}
+
+/// Result of downloading a single shard.
+#[derive(Debug, Default)]
+struct ShardTransfer {
+    downloaded: u64,
+    resumed: u64,
+}
+
+/// Client for a remote [`ModelStore`](crate::ModelStore).
+#[derive(Clone)]
+pub struct ModelStoreClient {
+    client: Arc<QuillClient>,
+}
+
+impl ModelStoreClient {
+    /// Create a client using the given Quill client
+    pub fn new(client: QuillClient) -> Self {
+        Self::from_arc(Arc::new(client))
+    }
+
+    /// Create a client sharing an existing Quill client
+    pub fn from_arc(client: Arc<QuillClient>) -> Self {
+        Self { client }
+    }
+
+    /// Fetch the manifest for a model
+    pub async fn manifest(&self, model: &str) -> ModelStoreResult<Manifest> {
+        let req = serde_json::to_vec(&ManifestRequest { model: model.to_string() })?;
+        let resp = self.client.call(SERVICE_NAME, METHOD_GET_MANIFEST, Bytes::from(req)).await?;
+        Ok(serde_json::from_slice(&resp)?)
+    }
+
+    /// Download a model into the cache
+    ///
+    /// Shards already present with a matching digest are skipped; the rest
+    /// are fetched in parallel and verified before being moved into place.
+    pub async fn pull(
+        &self,
+        model: &str,
+        cache: &ModelCache,
+        options: PullOptions,
+    ) -> ModelStoreResult<PullReport> {
+        let manifest = self.manifest(model).await?;
+        std::fs::create_dir_all(cache.model_dir(model)?)?;
+
+        let mut report = PullReport {
+            shards: manifest.shards.len(),
+            ..Default::default()
+        };
+
+        let missing: Vec<ShardInfo> =
+            cache.missing_shards(&manifest)?.into_iter().cloned().collect();
+        report.cached_shards = report.shards - missing.len();
+
+        let semaphore = Arc::new(Semaphore::new(options.concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        for shard in missing {
+            let this = self.clone();
+            let cache = cache.clone();
+            let model = model.to_string();
+            let semaphore = Arc::clone(&semaphore);
+            let resume = options.resume;
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                this.download_shard(&model, &shard, &cache, resume).await
+            });
+        }
+
+        while let Some(result) = tasks.join_next().await {
+            let transfer = result.map_err(|e| {
+                ModelStoreError::Io(std::io::Error::other(format!("download task failed: {}", e)))
+            })??;
+            report.downloaded_bytes += transfer.downloaded;
+            report.resumed_bytes += transfer.resumed;
+        }
+
+        cache.save_manifest(&manifest)?;
+        Ok(report)
+    }
+
+    async fn download_shard(
+        &self,
+        model: &str,
+        shard: &ShardInfo,
+        cache: &ModelCache,
+        resume: bool,
+    ) -> ModelStoreResult<ShardTransfer> {
+        let partial = cache.partial_path(model, &shard.name)?;
+        let mut offset = if resume { cache.partial_len(model, &shard.name)? } else { 0 };
+        if offset > shard.size {
+            offset = 0;
+        }
+
+        let file = tokio::fs::OpenOptions::new().create(true).append(true).open(&partial).await?;
+        file.set_len(offset).await?;
+        let mut file = tokio::io::BufWriter::new(file);
+        let mut transfer = ShardTransfer {
+            downloaded: 0,
+            resumed: offset,
+        };
+
+        if offset < shard.size {
+            tracing::debug!("Fetching {}/{} from offset {}", model, shard.name, offset);
+            let result = self.fetch_into(model, shard, offset, &mut file).await;
+            // Persist whatever arrived so an interrupted transfer can resume
+            file.flush().await?;
+            transfer.downloaded = result?;
+        }
+        file.get_ref().sync_all().await?;
+        drop(file);
+
+        let partial_clone = partial.clone();
+        let actual = tokio::task::spawn_blocking(move || sha256_file(&partial_clone))
+            .await
+            .map_err(|e| std::io::Error::other(format!("digest task failed: {}", e)))??;
+        if actual != shard.sha256 {
+            // Start from scratch next time rather than resuming corrupt data
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(ModelStoreError::DigestMismatch {
+                shard: shard.name.clone(),
+                expected: shard.sha256.clone(),
+                actual,
+            });
+        }
+
+        tokio::fs::rename(&partial, cache.shard_path(model, &shard.name)?).await?;
+        Ok(transfer)
+    }
+
+    /// Stream a shard from `offset` and append its payload to `out`
+    async fn fetch_into<W>(
+        &self,
+        model: &str,
+        shard: &ShardInfo,
+        offset: u64,
+        out: &mut W,
+    ) -> ModelStoreResult<u64>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        let req = serde_json::to_vec(&FetchShardRequest {
+            model: model.to_string(),
+            shard: shard.name.clone(),
+            offset,
+        })?;
+        let mut stream = self
+            .client
+            .call_server_streaming(SERVICE_NAME, METHOD_FETCH_SHARD, Bytes::from(req))
+            .await?;
+
+        let expected = shard.size - offset;
+        let mut parser = TensorFrameParser::new();
+        let mut written = 0u64;
+        while let Some(msg) = stream.next().await {
+            parser.feed_bytes(msg?);
+            while let Some(frame) = parser.parse_frame().map_err(TensorStreamError::from)? {
+                match frame.frame_type {
+                    FrameType::TensorMeta => {}
+                    FrameType::TensorPayload => {
+                        out.write_all(&frame.payload).await?;
+                        written += frame.payload.len() as u64;
+                    }
+                    FrameType::EndStream => {
+                        if written != expected {
+                            return Err(TensorStreamError::SizeMismatch {
+                                expected: expected as usize,
+                                actual: written as usize,
+                            }
+                            .into());
+                        }
+                        return Ok(written);
+                    }
+                    FrameType::Cancel => {
+                        let reason = String::from_utf8_lossy(&frame.payload).into_owned();
+                        return Err(TensorStreamError::Cancelled(reason).into());
+                    }
+                    other => {
+                        return Err(TensorStreamError::UnexpectedFrame {
+                            expected: "TENSOR_META, TENSOR_PAYLOAD, END_STREAM, or CANCEL",
+                            actual: other.name(),
+                        }
+                        .into());
+                    }
+                }
+            }
+        }
+
+        // Stream ended without END_STREAM; keep what we have for the next resume
+        Err(TensorStreamError::SizeMismatch {
+            expected: expected as usize,
+            actual: written as usize,
+        }
+        .into())
+    }
+}
+
@@ -0,0 +1,86 @@
+//! Error types for the model store
+
+use quill_core::QuillError;
+use quill_tensor::stream::TensorStreamError;
+use thiserror::Error;
+
+/// Model store errors
+#[derive(Debug, Error)]
+pub enum ModelStoreError {
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
+    #[error("Shard not found: {model}/{shard}")]
+    ShardNotFound { model: String, shard: String },
+
+    #[error("Invalid name: {0:?}")]
+    InvalidName(String),
+
+    #[error("Digest mismatch for shard {shard}: expected {expected}, got {actual}")]
+    DigestMismatch {
+        shard: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Tensor stream error: {0}")]
+    Stream(#[from] TensorStreamError),
+
+    #[error("RPC error: {0}")]
+    Rpc(Box<QuillError>),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Result type for model store operations
+pub type ModelStoreResult<T> = Result<T, ModelStoreError>;
+
+impl From<QuillError> for ModelStoreError {
+    fn from(err: QuillError) -> Self {
+        ModelStoreError::Rpc(Box::new(err))
+    }
+}
+
+impl From<ModelStoreError> for QuillError {
+    fn from(err: ModelStoreError) -> Self {
+        match err {
+            ModelStoreError::Rpc(err) => *err,
+            other => QuillError::Rpc(other.to_string()),
+        }
+    }
+}
+
+/// Validates a model or shard name for use as a single path component.
+///
+/// Rejects empty names, separators and relative components so remote input
+/// can never escape the store or cache root.
+pub(crate) fn validate_name(name: &str) -> ModelStoreResult<()> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\', '\0'])
+    {
+        return Err(ModelStoreError::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("llama-7b").is_ok());
+        assert!(validate_name("model-00001.safetensors").is_ok());
+
+        assert!(validate_name("").is_err());
+        assert!(validate_name("..").is_err());
+        assert!(validate_name(".hidden").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("a\\b").is_err());
+    }
+}
@@ -0,0 +1,52 @@
+//! Model artifact distribution for the Quill RPC framework.
+//!
+//! This crate packages tensor streaming, resumable transfers and local
+//! verification into the workflow of distributing model weights:
+//! - A `ModelStore` service that serves model directories as shards
+//! - A manifest RPC listing shards with their sizes and SHA-256 digests
+//! - Parallel shard download over tensor streams, resuming partial files
+//! - A local `ModelCache` that verifies shards before reusing them
+//!
+//! # Layout
+//!
+//! The server root contains one directory per model, with one file per shard:
+//!
+//! ```text
+//! models/
+//!   llama-7b/
+//!     model-00001.safetensors
+//!     model-00002.safetensors
+//! ```
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use quill_client::QuillClient;
+//! use quill_modelstore::{ModelCache, ModelStoreClient, PullOptions};
+//!
+//! let client = ModelStoreClient::new(QuillClient::new("http://models.internal:8080"));
+//! let cache = ModelCache::new("/var/cache/quill/models");
+//! let report = client.pull("llama-7b", &cache, PullOptions::default()).await?;
+//! println!("downloaded {} bytes", report.downloaded_bytes);
+//! ```
+
+pub mod cache;
+pub mod client;
+pub mod error;
+pub mod manifest;
+pub mod server;
+
+pub use cache::ModelCache;
+pub use client::{ModelStoreClient, PullOptions, PullReport};
+pub use error::{ModelStoreError, ModelStoreResult};
+pub use manifest::{FetchShardRequest, Manifest, ManifestRequest, ShardInfo};
+pub use server::ModelStore;
+
+/// Fully-qualified service name of the model store.
+pub const SERVICE_NAME: &str = "quill.modelstore.v1.ModelStore";
+
+/// Method returning a model's manifest.
+pub const METHOD_GET_MANIFEST: &str = "GetManifest";
+
+/// Method streaming a shard as tensor frames.
+pub const METHOD_FETCH_SHARD: &str = "FetchShard";
@@ -0,0 +1,112 @@
+//! Manifest and request messages
+//!
+//! Messages are JSON-encoded; shard data itself travels as tensor frames.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::ModelStoreResult;
+
+/// A single shard of a model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardInfo {
+    /// Shard file name
+    pub name: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the shard contents
+    pub sha256: String,
+}
+
+/// The list of shards making up a model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Model name
+    pub model: String,
+    /// Shards, sorted by name
+    pub shards: Vec<ShardInfo>,
+}
+
+impl Manifest {
+    /// Total size of all shards in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.shards.iter().map(|s| s.size).sum()
+    }
+
+    /// Looks up a shard by name.
+    pub fn shard(&self, name: &str) -> Option<&ShardInfo> {
+        self.shards.iter().find(|s| s.name == name)
+    }
+}
+
+/// Request for `GetManifest`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestRequest {
+    /// Model name
+    pub model: String,
+}
+
+/// Request for `FetchShard`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchShardRequest {
+    /// Model name
+    pub model: String,
+    /// Shard name
+    pub shard: String,
+    /// Byte offset to resume from
+    #[serde(default)]
+    pub offset: u64,
+}
+
+/// Computes the lowercase hex SHA-256 of a file.
+pub fn sha256_file(path: &Path) -> ModelStoreResult<String> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let manifest = Manifest {
+            model: "tiny".to_string(),
+            shards: vec![
+                ShardInfo { name: "a.bin".to_string(), size: 3, sha256: "00".to_string() },
+                ShardInfo { name: "b.bin".to_string(), size: 5, sha256: "11".to_string() },
+            ],
+        };
+
+        let json = serde_json::to_vec(&manifest).unwrap();
+        let decoded: Manifest = serde_json::from_slice(&json).unwrap();
+        assert_eq!(decoded, manifest);
+        assert_eq!(decoded.total_size(), 8);
+        assert_eq!(decoded.shard("b.bin").unwrap().size, 5);
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shard");
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Model store service
+//!
+//! Serves model directories as manifests and shard tensor streams.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use quill_core::QuillError;
+use quill_server::{RpcResponse, RpcRouter};
+use quill_tensor::{DType, TensorFrame, TensorMeta, TensorSender};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+
+use crate::error::{validate_name, ModelStoreError, ModelStoreResult};
+use crate::manifest::{sha256_file, FetchShardRequest, Manifest, ManifestRequest, ShardInfo};
+use crate::{METHOD_FETCH_SHARD, METHOD_GET_MANIFEST, SERVICE_NAME};
+
+/// Cached digest, keyed by path and invalidated when size or mtime change.
+#[derive(Debug, Clone)]
+struct CachedDigest {
+    size: u64,
+    modified: Option<SystemTime>,
+    sha256: String,
+}
+
+struct Inner {
+    root: PathBuf,
+    chunk_size: usize,
+    digests: Mutex<HashMap<PathBuf, CachedDigest>>,
+}
+
+/// Model store serving `<root>/<model>/<shard>` files.
+#[derive(Clone)]
+pub struct ModelStore {
+    inner: Arc<Inner>,
+}
+
+impl ModelStore {
+    /// Create a store serving models under `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                root: root.into(),
+                chunk_size: TensorSender::DEFAULT_CHUNK_SIZE,
+                digests: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Set the payload chunk size used when streaming shards
+    pub fn with_chunk_size(self, chunk_size: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                root: self.inner.root.clone(),
+                chunk_size: chunk_size.max(1),
+                digests: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Root directory of the store
+    pub fn root(&self) -> &Path {
+        &self.inner.root
+    }
+
+    /// Register the `GetManifest` and `FetchShard` methods on a router
+    pub fn register(&self, router: &mut RpcRouter) {
+        let store = self.clone();
+        router.register_unary(format!("{}/{}", SERVICE_NAME, METHOD_GET_MANIFEST), move |req| {
+            let store = store.clone();
+            async move {
+                let req: ManifestRequest = serde_json::from_slice(&req)
+                    .map_err(|e| QuillError::Rpc(format!("Invalid manifest request: {}", e)))?;
+                let manifest = tokio::task::spawn_blocking(move || store.manifest(&req.model))
+                    .await
+                    .map_err(|e| QuillError::Rpc(format!("Manifest task failed: {}", e)))??;
+                let body = serde_json::to_vec(&manifest).map_err(ModelStoreError::from)?;
+                Ok(Bytes::from(body))
+            }
+        });
+
+        let store = self.clone();
+        router.register(format!("{}/{}", SERVICE_NAME, METHOD_FETCH_SHARD), move |req| {
+            let store = store.clone();
+            async move {
+                let req: FetchShardRequest = serde_json::from_slice(&req)
+                    .map_err(|e| QuillError::Rpc(format!("Invalid fetch request: {}", e)))?;
+                let stream = store.fetch_shard(req).await?;
+                Ok(RpcResponse::streaming(stream))
+            }
+        });
+    }
+
+    /// Build the manifest for a model
+    ///
+    /// Digests are computed on first use and cached until the file changes.
+    pub fn manifest(&self, model: &str) -> ModelStoreResult<Manifest> {
+        validate_name(model)?;
+        let dir = self.inner.root.join(model);
+        if !dir.is_dir() {
+            return Err(ModelStoreError::ModelNotFound(model.to_string()));
+        }
+
+        let mut shards = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() || validate_name(&name).is_err() {
+                continue;
+            }
+
+            let sha256 = self.digest(&entry.path(), &metadata)?;
+            shards.push(ShardInfo {
+                name,
+                size: metadata.len(),
+                sha256,
+            });
+        }
+        shards.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Manifest {
+            model: model.to_string(),
+            shards,
+        })
+    }
+
+    /// Stream a shard starting at `offset` as tensor frames
+    ///
+    /// The stream is `TENSOR_META → TENSOR_PAYLOAD* → END_STREAM`, one encoded
+    /// frame per message, describing the remaining bytes as a `UInt8` tensor.
+    pub async fn fetch_shard(
+        &self,
+        req: FetchShardRequest,
+    ) -> ModelStoreResult<impl Stream<Item = Result<Bytes, QuillError>> + Send + 'static> {
+        validate_name(&req.model)?;
+        validate_name(&req.shard)?;
+
+        let path = self.inner.root.join(&req.model).join(&req.shard);
+        let mut file = tokio::fs::File::open(&path).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ModelStoreError::ShardNotFound {
+                model: req.model.clone(),
+                shard: req.shard.clone(),
+            },
+            _ => ModelStoreError::Io(e),
+        })?;
+        let size = file.metadata().await?.len();
+        if req.offset > size {
+            return Err(ModelStoreError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("offset {} is past the end of {} ({} bytes)", req.offset, req.shard, size),
+            )));
+        }
+        file.seek(std::io::SeekFrom::Start(req.offset)).await?;
+
+        let remaining = (size - req.offset) as usize;
+        let meta = TensorMeta::new(vec![remaining], DType::UInt8).with_name(req.shard.clone());
+        let meta_payload = TensorSender::new().encode_meta(&meta);
+        let chunk_size = self.inner.chunk_size;
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            if tx.send(Ok(TensorFrame::tensor_meta(meta_payload).encode())).await.is_err() {
+                return;
+            }
+
+            let mut buf = vec![0u8; chunk_size];
+            let mut sent = 0;
+            while sent < remaining {
+                let want = chunk_size.min(remaining - sent);
+                let n = match file.read(&mut buf[..want]).await {
+                    Ok(0) => {
+                        let _ = tx
+                            .send(Err(QuillError::Rpc(format!("Shard {} was truncated", req.shard))))
+                            .await;
+                        return;
+                    }
+                    Ok(n) => n,
+                    Err(e) => {
+                        let _ = tx.send(Err(QuillError::Rpc(format!("Read failed: {}", e)))).await;
+                        return;
+                    }
+                };
+                sent += n;
+
+                let frame = TensorFrame::tensor_payload(Bytes::copy_from_slice(&buf[..n]));
+                if tx.send(Ok(frame.encode())).await.is_err() {
+                    tracing::debug!("Client disconnected while streaming {}", req.shard);
+                    return;
+                }
+            }
+
+            let _ = tx.send(Ok(TensorFrame::end_stream().encode())).await;
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    fn digest(&self, path: &Path, metadata: &std::fs::Metadata) -> ModelStoreResult<String> {
+        let modified = metadata.modified().ok();
+        if let Some(cached) = self.inner.digests.lock().unwrap().get(path) {
+            if cached.size == metadata.len() && cached.modified == modified {
+                return Ok(cached.sha256.clone());
+            }
+        }
+
+        let sha256 = sha256_file(path)?;
+        self.inner.digests.lock().unwrap().insert(
+            path.to_path_buf(),
+            CachedDigest {
+                size: metadata.len(),
+                modified,
+                sha256: sha256.clone(),
+            },
+        );
+        Ok(sha256)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_tensor::stream::{ReceiverEvent, TensorReceiver};
+    use tokio_stream::StreamExt;
+
+    fn store_with_model() -> (tempfile::TempDir, ModelStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("tiny");
+        std::fs::create_dir(&model).unwrap();
+        std::fs::write(model.join("b.bin"), vec![2u8; 300]).unwrap();
+        std::fs::write(model.join("a.bin"), b"abc").unwrap();
+        std::fs::write(model.join(".partial"), b"ignored").unwrap();
+        let store = ModelStore::new(dir.path()).with_chunk_size(128);
+        (dir, store)
+    }
+
+    #[test]
+    fn test_manifest_lists_shards() {
+        let (_dir, store) = store_with_model();
+        let manifest = store.manifest("tiny").unwrap();
+
+        let names: Vec<_> = manifest.shards.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["a.bin", "b.bin"]);
+        assert_eq!(manifest.total_size(), 303);
+        assert_eq!(
+            manifest.shard("a.bin").unwrap().sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        assert!(matches!(store.manifest("missing"), Err(ModelStoreError::ModelNotFound(_))));
+        assert!(matches!(store.manifest(".."), Err(ModelStoreError::InvalidName(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_shard_from_offset() {
+        let (_dir, store) = store_with_model();
+        let req = FetchShardRequest {
+            model: "tiny".to_string(),
+            shard: "b.bin".to_string(),
+            offset: 100,
+        };
+
+        let mut stream = Box::pin(store.fetch_shard(req).await.unwrap());
+        let mut receiver = TensorReceiver::new();
+        let mut frames = 0;
+        while let Some(msg) = stream.next().await {
+            receiver.feed(&msg.unwrap());
+            frames += 1;
+        }
+        // META + two 128-byte payloads (200 bytes remaining) + END
+        assert_eq!(frames, 4);
+
+        while !matches!(receiver.poll().unwrap(), ReceiverEvent::End) {}
+        let tensor = receiver.take_tensor().unwrap();
+        assert_eq!(tensor.meta.name.as_deref(), Some("b.bin"));
+        assert_eq!(&tensor.data[..], &[2u8; 200][..]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_shard_rejects_traversal() {
+        let (_dir, store) = store_with_model();
+        let req = FetchShardRequest {
+            model: "tiny".to_string(),
+            shard: "../tiny/a.bin".to_string(),
+            offset: 0,
+        };
+        assert!(matches!(store.fetch_shard(req).await, Err(ModelStoreError::InvalidName(_))));
+    }
+}
@@ -0,0 +1,104 @@
+//! End-to-end tests for pulling models from a model store server
+
+use quill_client::QuillClient;
+use quill_modelstore::{ModelCache, ModelStore, ModelStoreClient, ModelStoreError, PullOptions};
+use quill_server::{QuillServer, RpcRouter};
+
+struct Fixture {
+    _store_dir: tempfile::TempDir,
+    cache_dir: tempfile::TempDir,
+    client: ModelStoreClient,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+fn shard_data(seed: u8, len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed)).collect()
+}
+
+async fn start() -> Fixture {
+    let store_dir = tempfile::tempdir().unwrap();
+    let model_dir = store_dir.path().join("tiny");
+    std::fs::create_dir(&model_dir).unwrap();
+    std::fs::write(model_dir.join("model-00001.bin"), shard_data(1, 200_000)).unwrap();
+    std::fs::write(model_dir.join("model-00002.bin"), shard_data(2, 150_000)).unwrap();
+    std::fs::write(model_dir.join("config.json"), br#"{"layers":2}"#).unwrap();
+
+    let mut router = RpcRouter::new();
+    ModelStore::new(store_dir.path()).with_chunk_size(16 * 1024).register(&mut router);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+
+    let client = ModelStoreClient::new(QuillClient::new(format!("http://{}", addr)));
+
+    Fixture {
+        _store_dir: store_dir,
+        cache_dir: tempfile::tempdir().unwrap(),
+        client,
+        server,
+    }
+}
+
+#[tokio::test]
+async fn test_pull_downloads_and_verifies() {
+    let fixture = start().await;
+    let cache = ModelCache::new(fixture.cache_dir.path());
+
+    let report = fixture.client.pull("tiny", &cache, PullOptions::default()).await.unwrap();
+    assert_eq!(report.shards, 3);
+    assert_eq!(report.cached_shards, 0);
+    assert_eq!(report.downloaded_bytes, 200_000 + 150_000 + 12);
+
+    let shard = std::fs::read(cache.shard_path("tiny", "model-00002.bin").unwrap()).unwrap();
+    assert_eq!(shard, shard_data(2, 150_000));
+    assert!(cache.load_manifest("tiny").unwrap().is_some());
+
+    // A second pull reuses the verified cache
+    let report = fixture.client.pull("tiny", &cache, PullOptions::default()).await.unwrap();
+    assert_eq!(report.cached_shards, 3);
+    assert_eq!(report.downloaded_bytes, 0);
+}
+
+#[tokio::test]
+async fn test_pull_resumes_partial_shard() {
+    let fixture = start().await;
+    let cache = ModelCache::new(fixture.cache_dir.path());
+
+    std::fs::create_dir_all(cache.model_dir("tiny").unwrap()).unwrap();
+    let partial = cache.partial_path("tiny", "model-00001.bin").unwrap();
+    std::fs::write(&partial, &shard_data(1, 200_000)[..120_000]).unwrap();
+
+    let report = fixture.client.pull("tiny", &cache, PullOptions::default()).await.unwrap();
+    assert_eq!(report.resumed_bytes, 120_000);
+    assert_eq!(report.downloaded_bytes, 80_000 + 150_000 + 12);
+    assert!(!partial.exists());
+
+    let shard = std::fs::read(cache.shard_path("tiny", "model-00001.bin").unwrap()).unwrap();
+    assert_eq!(shard, shard_data(1, 200_000));
+}
+
+#[tokio::test]
+async fn test_corrupt_partial_is_discarded() {
+    let fixture = start().await;
+    let cache = ModelCache::new(fixture.cache_dir.path());
+
+    std::fs::create_dir_all(cache.model_dir("tiny").unwrap()).unwrap();
+    let partial = cache.partial_path("tiny", "model-00001.bin").unwrap();
+    std::fs::write(&partial, vec![0u8; 1000]).unwrap();
+
+    let err = fixture.client.pull("tiny", &cache, PullOptions::default()).await.unwrap_err();
+    assert!(matches!(err, ModelStoreError::DigestMismatch { .. }));
+    assert!(!partial.exists());
+
+    // The retry starts from scratch and succeeds
+    fixture.client.pull("tiny", &cache, PullOptions::default()).await.unwrap();
+}
@@ -1,7 +1,9 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
 
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
     let mut config = prost_build::Config::new();
+    config.file_descriptor_set_path(out_dir.join("quill_descriptor.bin"));
 
     // Include the proto directory
     config.compile_protos(&["../../proto/quill/annotations.proto"], &["../../proto"])?;
@@ -13,6 +13,9 @@ pub mod annotations {
 
 pub use annotations::*;
 
+/// Encoded `FileDescriptorSet` of the Quill annotations and their imports
+pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/quill_descriptor.bin"));
+
 /// Utilities for working with Quill RPC options
 pub mod options {
     use super::*;
@@ -32,6 +35,42 @@ pub mod options {
         opts.cache_ttl_ms
     }
 
+    /// Get the keyed batch get settings, if the RPC uses the pattern
+    pub fn batch_get(opts: &RpcOptions) -> Option<&KeyedBatchGet> {
+        opts.batch_get.as_ref()
+    }
+
+    /// Get the stream cursor settings, if the RPC is resumable from a cursor
+    pub fn cursor(opts: &RpcOptions) -> Option<&CursorOptions> {
+        opts.cursor.as_ref()
+    }
+
+    /// Get the HTTP method and path template of an HTTP binding
+    ///
+    /// Returns `None` if the rule sets no pattern.
+    pub fn http_pattern(rule: &HttpRule) -> Option<(&str, &str)> {
+        use http_rule::Pattern;
+
+        match rule.pattern.as_ref()? {
+            Pattern::Get(path) => Some(("GET", path)),
+            Pattern::Put(path) => Some(("PUT", path)),
+            Pattern::Post(path) => Some(("POST", path)),
+            Pattern::Delete(path) => Some(("DELETE", path)),
+            Pattern::Patch(path) => Some(("PATCH", path)),
+            Pattern::Custom(custom) => Some((&custom.kind, &custom.path)),
+        }
+    }
+
+    /// Check if a field is marked for envelope encryption
+    pub fn is_sensitive_field(opts: &FieldOptions) -> bool {
+        opts.sensitive
+    }
+
+    /// Check if a message is marked for envelope encryption
+    pub fn is_sensitive_message(opts: &MessageOptions) -> bool {
+        opts.sensitive
+    }
+
     /// Get the list of error types this RPC may throw
     pub fn throws(opts: &RpcOptions) -> &[String] {
         &opts.throws
@@ -4,7 +4,10 @@ use bytes::Bytes;
 use pyo3::exceptions::{PyConnectionError, PyRuntimeError, PyTimeoutError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
-use quill_client::QuillClient;
+use crate::token::PyTokenBatch;
+use crate::token_stream::{latency_dict, stream_error, PyTokenStream, TokenFrameReader};
+use crate::transfer::PyTransfer;
+use quill_client::{ChunkedUpload, QuillClient, RequestOptions};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
@@ -176,6 +179,138 @@ impl PyQuillClient {
         Ok(result.unbind())
     }
 
+    /// Upload a large request in the background, reporting progress.
+    ///
+    /// The request is split into chunks when the server accepts chunked
+    /// uploads. Transfers are not bound by `timeout_ms`; pass a timeout to
+    /// `Transfer.wait()` instead.
+    ///
+    /// Args:
+    ///     service: The service name
+    ///     method: The method name
+    ///     request: The request payload as bytes
+    ///     chunk_size: Chunk size in bytes (default: 1 MiB)
+    ///
+    /// Returns:
+    ///     Transfer handle
+    #[pyo3(signature = (service, method, request, chunk_size=None))]
+    fn upload(&self, service: &str, method: &str, request: &[u8], chunk_size: Option<usize>) -> PyResult<PyTransfer> {
+        let client = Arc::new(self.build_client()?);
+        let upload = match chunk_size {
+            Some(size) => ChunkedUpload::new().chunk_size(size),
+            None => ChunkedUpload::new(),
+        };
+        let options = RequestOptions::new().chunked_upload(upload);
+
+        let _runtime = self.runtime.enter();
+        let handle = client.upload(service, method, Bytes::copy_from_slice(request), options);
+        Ok(PyTransfer::new(handle, Arc::clone(&self.runtime)))
+    }
+
+    /// Download a server-streaming response in the background, reporting progress.
+    ///
+    /// Messages are concatenated into the result. Transfers are not bound
+    /// by `timeout_ms`; pass a timeout to `Transfer.wait()` instead.
+    ///
+    /// Args:
+    ///     service: The service name
+    ///     method: The method name
+    ///     request: The request payload as bytes
+    ///     expected_size: Expected response size in bytes, for fraction and ETA
+    ///
+    /// Returns:
+    ///     Transfer handle
+    #[pyo3(signature = (service, method, request, expected_size=None))]
+    fn download(
+        &self,
+        service: &str,
+        method: &str,
+        request: &[u8],
+        expected_size: Option<u64>,
+    ) -> PyResult<PyTransfer> {
+        let client = Arc::new(self.build_client()?);
+
+        let _runtime = self.runtime.enter();
+        let handle = client.download(
+            service,
+            method,
+            Bytes::copy_from_slice(request),
+            RequestOptions::new(),
+            expected_size,
+        );
+        Ok(PyTransfer::new(handle, Arc::clone(&self.runtime)))
+    }
+
+    /// Stream token batches from a generation RPC as TOKEN_BATCH frames arrive.
+    ///
+    /// Without a callback, returns a `TokenStream` to iterate over. With a
+    /// callback, calls it with each `TokenBatch` and returns the token
+    /// latency summary (see `TokenStream.latency()`) once the stream ends;
+    /// return False from the callback to stop early. `timeout_ms`
+    /// bounds the wait for each batch rather than the whole generation.
+    ///
+    /// Args:
+    ///     service: The service name
+    ///     method: The method name
+    ///     request: The request payload as bytes
+    ///     callback: Optional callable receiving each TokenBatch
+    ///
+    /// Returns:
+    ///     TokenStream, or the latency summary dict when a callback is given
+    #[pyo3(signature = (service, method, request, callback=None))]
+    fn call_generate_stream(
+        &self,
+        py: Python<'_>,
+        service: &str,
+        method: &str,
+        request: &[u8],
+        callback: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let client = self.build_client()?;
+        let request_bytes = Bytes::copy_from_slice(request);
+        let timeout = Duration::from_millis(self.timeout_ms);
+        let options = RequestOptions::new().stream_idle_timeout(timeout);
+
+        let opened = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                tokio::time::timeout(
+                    timeout,
+                    client.call_server_streaming_with_options(service, method, request_bytes, options),
+                )
+                .await
+            })
+        });
+        let messages = match opened {
+            Ok(Ok(messages)) => messages,
+            Ok(Err(e)) => return Err(stream_error(e, self.timeout_ms)),
+            Err(_) => {
+                return Err(PyTimeoutError::new_err(format!(
+                    "Request timed out after {}ms",
+                    self.timeout_ms
+                )))
+            }
+        };
+        let mut reader = TokenFrameReader::new(messages);
+
+        let Some(callback) = callback else {
+            let stream = PyTokenStream::new(reader, Arc::clone(&self.runtime), self.timeout_ms);
+            return Ok(stream.into_py(py));
+        };
+        loop {
+            let next = py.allow_threads(|| self.runtime.block_on(reader.next_batch()));
+            let batch = match next {
+                Ok(Some(batch)) => batch,
+                Ok(None) => break,
+                Err(e) => return Err(stream_error(e, self.timeout_ms)),
+            };
+            let keep_going = callback.call1(py, (PyTokenBatch::from_inner(batch),))?;
+            if matches!(keep_going.extract::<bool>(py), Ok(false)) {
+                break;
+            }
+        }
+        Ok(latency_dict(py, &reader.latency())?.into_py(py))
+    }
+
     /// Check if the server is healthy.
     ///
     /// Returns:
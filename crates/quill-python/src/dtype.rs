@@ -15,6 +15,10 @@ use quill_tensor::DType;
 /// - `Int64`: 64-bit signed integer
 /// - `UInt8`: 8-bit unsigned integer
 /// - `Bool`: Boolean
+/// - `Float8E4M3`: 8-bit floating point, E4M3FN (`ml_dtypes.float8_e4m3fn`)
+/// - `Float8E5M2`: 8-bit floating point, E5M2 (`ml_dtypes.float8_e5m2`)
+/// - `Int4`: 4-bit signed integer, packed two per byte
+/// - `UInt4`: 4-bit unsigned integer, packed two per byte
 #[pyclass(name = "DType")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PyDType {
@@ -77,51 +81,74 @@ impl PyDType {
         Self { inner: DType::Bool }
     }
 
-    /// Get the size of one element in bytes
+    /// Create Float8 E4M3 dtype (OCP E4M3FN)
+    #[staticmethod]
+    fn float8_e4m3() -> Self {
+        Self { inner: DType::Float8E4M3 }
+    }
+
+    /// Create Float8 E5M2 dtype
+    #[staticmethod]
+    fn float8_e5m2() -> Self {
+        Self { inner: DType::Float8E5M2 }
+    }
+
+    /// Create Int4 dtype (packed two per byte)
+    #[staticmethod]
+    fn int4() -> Self {
+        Self { inner: DType::Int4 }
+    }
+
+    /// Create UInt4 dtype (packed two per byte)
+    #[staticmethod]
+    fn uint4() -> Self {
+        Self { inner: DType::UInt4 }
+    }
+
+    /// Get the size of one element in bytes (1 for packed 4-bit types)
     #[getter]
     fn element_size(&self) -> usize {
         self.inner.element_size()
     }
 
+    /// Get the size of one element in bits
+    #[getter]
+    fn bit_width(&self) -> usize {
+        self.inner.bit_width()
+    }
+
+    /// Check if several elements share each byte
+    fn is_packed(&self) -> bool {
+        self.inner.is_packed()
+    }
+
+    /// Get the number of bytes holding `numel` elements
+    fn byte_size(&self, numel: usize) -> usize {
+        self.inner.byte_size(numel)
+    }
+
     /// Get the name of this dtype
     #[getter]
     pub fn name(&self) -> &'static str {
-        match self.inner {
-            DType::Float32 => "float32",
-            DType::Float64 => "float64",
-            DType::Float16 => "float16",
-            DType::BFloat16 => "bfloat16",
-            DType::Int8 => "int8",
-            DType::Int32 => "int32",
-            DType::Int64 => "int64",
-            DType::UInt8 => "uint8",
-            DType::Bool => "bool",
-        }
+        self.inner.name()
     }
 
     /// Check if this dtype is a floating point type
     fn is_float(&self) -> bool {
-        matches!(
-            self.inner,
-            DType::Float32 | DType::Float64 | DType::Float16 | DType::BFloat16
-        )
+        self.inner.is_floating_point()
     }
 
     /// Check if this dtype is an integer type
     fn is_integer(&self) -> bool {
         matches!(
             self.inner,
-            DType::Int8 | DType::Int32 | DType::Int64 | DType::UInt8
+            DType::Int8 | DType::Int32 | DType::Int64 | DType::UInt8 | DType::Int4 | DType::UInt4
         )
     }
 
     /// Check if this dtype is a signed type
     fn is_signed(&self) -> bool {
-        matches!(
-            self.inner,
-            DType::Float32 | DType::Float64 | DType::Float16 | DType::BFloat16 |
-            DType::Int8 | DType::Int32 | DType::Int64
-        )
+        self.inner.is_signed()
     }
 
     fn __repr__(&self) -> String {
@@ -217,4 +244,21 @@ mod tests {
         assert!(f16.is_float());
         assert!(bf16.is_float());
     }
+
+    #[test]
+    fn test_dtype_quantized() {
+        let fp8 = PyDType::float8_e4m3();
+        assert_eq!(fp8.name(), "float8_e4m3");
+        assert_eq!(fp8.element_size(), 1);
+        assert!(fp8.is_float());
+        assert!(!fp8.is_packed());
+
+        let int4 = PyDType::int4();
+        assert_eq!(int4.name(), "int4");
+        assert_eq!(int4.bit_width(), 4);
+        assert_eq!(int4.byte_size(5), 3);
+        assert!(int4.is_integer());
+        assert!(int4.is_signed());
+        assert!(!PyDType::uint4().is_signed());
+    }
 }
@@ -6,10 +6,20 @@
 //! - DLPack protocol for PyTorch/JAX interop
 //! - CUDA Array Interface for CuPy/Numba interop
 
-use pyo3::exceptions::PyRuntimeError;
+use pyo3::exceptions::{PyAttributeError, PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::ffi;
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
-use quill_tensor::{DLPackCapsule, GpuStatus, TensorBuffer, TensorMeta};
+use pyo3::types::{PyBytes, PyDict};
+use quill_tensor::dlpack::{CudaArrayInterface, DLDeviceType};
+use quill_tensor::{DLPackCapsule, DLPackError, DType, GpuStatus, TensorBuffer, TensorMeta};
+use std::os::raw::c_char;
+use std::sync::Arc;
+
+/// Name of an unconsumed DLPack capsule.
+const DLTENSOR_NAME: &[u8] = b"dltensor\0";
+
+/// Name a consumer gives a capsule once it owns the tensor.
+const USED_DLTENSOR_NAME: &[u8] = b"used_dltensor\0";
 
 /// GPU availability status.
 ///
@@ -79,11 +89,15 @@ impl PyGpuStatus {
 
 /// A tensor buffer that can reside on CPU or GPU.
 ///
-/// Use `TensorBuffer.cpu()` or `TensorBuffer.gpu()` to create buffers.
+/// Use `TensorBuffer.cpu_zeros()` or `TensorBuffer.allocate_gpu()` to create
+/// buffers. GPU buffers implement `__cuda_array_interface__` and `__dlpack__`
+/// as flat `uint8` arrays, so CuPy, Numba or PyTorch can use them without a
+/// copy.
 #[pyclass(name = "TensorBuffer")]
 #[derive(Clone)]
 pub struct PyTensorBuffer {
-    inner: TensorBuffer,
+    /// Shared with DLPack capsules exported from this buffer
+    inner: Arc<TensorBuffer>,
 }
 
 #[pymethods]
@@ -97,9 +111,7 @@ impl PyTensorBuffer {
     ///     TensorBuffer on CPU
     #[staticmethod]
     fn cpu_zeros(size: usize) -> Self {
-        Self {
-            inner: TensorBuffer::cpu_zeros(size),
-        }
+        Self::from_inner(TensorBuffer::cpu_zeros(size))
     }
 
     /// Creates a CPU buffer from bytes.
@@ -111,9 +123,7 @@ impl PyTensorBuffer {
     ///     TensorBuffer on CPU
     #[staticmethod]
     fn from_bytes(data: &[u8]) -> Self {
-        Self {
-            inner: TensorBuffer::cpu(bytes::Bytes::copy_from_slice(data)),
-        }
+        Self::from_inner(TensorBuffer::cpu(bytes::Bytes::copy_from_slice(data)))
     }
 
     /// Allocates a GPU buffer (falls back to CPU if unavailable).
@@ -129,7 +139,28 @@ impl PyTensorBuffer {
     fn try_allocate_gpu(size: usize, device_id: usize) -> PyResult<Self> {
         let buffer = TensorBuffer::try_allocate_gpu(size, device_id)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-        Ok(Self { inner: buffer })
+        Ok(Self::from_inner(buffer))
+    }
+
+    /// Allocates a zeroed GPU buffer.
+    ///
+    /// Unlike `try_allocate_gpu()`, this never falls back to CPU memory.
+    ///
+    /// Args:
+    ///     size: Size in bytes
+    ///     device_id: GPU device ID (default: 0)
+    ///
+    /// Returns:
+    ///     TensorBuffer on GPU
+    ///
+    /// Raises:
+    ///     RuntimeError: If CUDA is not compiled or the allocation fails
+    #[staticmethod]
+    #[pyo3(signature = (size, device_id=0))]
+    fn allocate_gpu(size: usize, device_id: usize) -> PyResult<Self> {
+        let buffer = TensorBuffer::allocate_gpu(size, device_id)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(Self::from_inner(buffer))
     }
 
     /// Returns buffer size in bytes.
@@ -150,6 +181,12 @@ impl PyTensorBuffer {
         self.inner.is_cpu()
     }
 
+    /// Returns the GPU device ID, or None for CPU buffers.
+    #[getter]
+    fn device_id(&self) -> Option<usize> {
+        self.inner.device_id()
+    }
+
     /// Copies buffer contents to CPU memory.
     ///
     /// Returns:
@@ -165,17 +202,19 @@ impl PyTensorBuffer {
     /// Moves buffer to GPU.
     ///
     /// Args:
-    ///     device_id: GPU device ID
+    ///     device_id: GPU device ID (default: 0)
     ///
     /// Returns:
     ///     New TensorBuffer on GPU
+    #[pyo3(signature = (device_id=0))]
     fn to_gpu(&self, device_id: usize) -> PyResult<Self> {
-        let buffer = self
-            .inner
-            .clone()
+        if self.inner.device_id() == Some(device_id) {
+            return Ok(self.clone());
+        }
+        let buffer = TensorBuffer::clone(&self.inner)
             .to_gpu(device_id)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-        Ok(Self { inner: buffer })
+        Ok(Self::from_inner(buffer))
     }
 
     /// Moves buffer to CPU.
@@ -183,17 +222,82 @@ impl PyTensorBuffer {
     /// Returns:
     ///     New TensorBuffer on CPU
     fn to_cpu(&self) -> PyResult<Self> {
-        let buffer = self
+        if self.inner.is_cpu() {
+            return Ok(self.clone());
+        }
+        let data = self
             .inner
-            .clone()
-            .to_cpu()
+            .to_host()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(Self::from_inner(TensorBuffer::cpu(data)))
+    }
+
+    /// CUDA Array Interface (version 3) of a GPU buffer.
+    ///
+    /// Describes the buffer as a read-write 1-D `uint8` array. Raises
+    /// AttributeError for CPU buffers, so `hasattr()` reports GPU buffers
+    /// only.
+    #[getter]
+    fn __cuda_array_interface__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let meta = TensorMeta::new(vec![self.inner.len()], DType::UInt8);
+        let interface = CudaArrayInterface::from_buffer(&self.inner, &meta).ok_or_else(|| {
+            PyAttributeError::new_err("CPU buffers have no __cuda_array_interface__")
+        })?;
+
+        let dict = PyDict::new_bound(py);
+        dict.set_item("shape", pyo3::types::PyTuple::new_bound(py, &interface.shape))?;
+        dict.set_item("typestr", interface.typestr)?;
+        dict.set_item("data", interface.data)?;
+        dict.set_item("version", interface.version)?;
+        dict.set_item("strides", py.None())?;
+        // Copies into the buffer are synchronous, so no stream to wait on
+        dict.set_item("stream", py.None())?;
+        Ok(dict)
+    }
+
+    /// Exports the buffer as a DLPack capsule without copying.
+    ///
+    /// The capsule describes a 1-D `uint8` tensor on the buffer's device
+    /// and keeps the buffer alive until the consumer releases it. Data is
+    /// ready on return, so `stream` is accepted but not waited on.
+    ///
+    /// Example:
+    ///     >>> buf = quill.TensorBuffer.allocate_gpu(1024)
+    ///     >>> t = torch.from_dlpack(buf)
+    #[pyo3(signature = (*, stream=None, max_version=None, dl_device=None, copy=None))]
+    fn __dlpack__(
+        &self,
+        py: Python<'_>,
+        stream: Option<PyObject>,
+        max_version: Option<PyObject>,
+        dl_device: Option<PyObject>,
+        copy: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let _ = (stream, max_version, dl_device);
+        if copy == Some(true) {
+            return Err(PyValueError::new_err("TensorBuffer.__dlpack__ does not copy"));
+        }
+        let meta = TensorMeta::new(vec![self.inner.len()], DType::UInt8);
+        let capsule = DLPackCapsule::from_buffer(self.inner.clone(), &meta)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-        Ok(Self { inner: buffer })
+        PyDLPackCapsule::into_pycapsule(py, capsule)
+    }
+
+    /// Returns the DLPack `(device_type, device_id)` of the buffer.
+    fn __dlpack_device__(&self) -> (i32, usize) {
+        match self.inner.device_id() {
+            Some(device_id) => (DLDeviceType::Cuda as i32, device_id),
+            None => (DLDeviceType::Cpu as i32, 0),
+        }
     }
 
     fn __repr__(&self) -> String {
-        let location = if self.inner.is_gpu() { "GPU" } else { "CPU" };
-        format!("TensorBuffer({} bytes on {})", self.inner.len(), location)
+        match self.inner.device_id() {
+            Some(device_id) => {
+                format!("TensorBuffer({} bytes on GPU {})", self.inner.len(), device_id)
+            }
+            None => format!("TensorBuffer({} bytes on CPU)", self.inner.len()),
+        }
     }
 
     fn __len__(&self) -> usize {
@@ -207,7 +311,9 @@ impl PyTensorBuffer {
     }
 
     pub fn from_inner(inner: TensorBuffer) -> Self {
-        Self { inner }
+        Self {
+            inner: Arc::new(inner),
+        }
     }
 }
 
@@ -257,6 +363,80 @@ impl PyDLPackCapsule {
 
         Ok(Self::new(capsule))
     }
+
+    /// Wraps a capsule in a `PyCapsule` named "dltensor".
+    ///
+    /// If no consumer renames the capsule to "used_dltensor", the tensor is
+    /// released when the `PyCapsule` is garbage collected.
+    pub fn into_pycapsule(py: Python<'_>, capsule: DLPackCapsule) -> PyResult<PyObject> {
+        let name = DLTENSOR_NAME.as_ptr() as *const c_char;
+        let managed = capsule.into_raw();
+        unsafe {
+            let obj = ffi::PyCapsule_New(managed.cast(), name, Some(dlpack_capsule_destructor));
+            if obj.is_null() {
+                drop(DLPackCapsule::from_raw(managed));
+                return Err(PyErr::fetch(py));
+            }
+            Ok(PyObject::from_owned_ptr(py, obj))
+        }
+    }
+
+    /// Takes ownership of the DLPack tensor behind a Python object.
+    ///
+    /// Accepts a Quill `DLPackCapsule`, a `PyCapsule` named "dltensor", or
+    /// any object implementing `__dlpack__` (PyTorch, JAX, CuPy, ...). Raw
+    /// capsules are renamed to "used_dltensor" so the producer no longer
+    /// frees them; the returned capsule runs the deleter instead.
+    pub fn consume(obj: &Bound<'_, PyAny>) -> PyResult<DLPackCapsule> {
+        if let Ok(capsule) = obj.downcast::<Self>() {
+            return capsule
+                .borrow_mut()
+                .take()
+                .ok_or_else(|| PyValueError::new_err("DLPack capsule was already consumed"));
+        }
+
+        let capsule = if obj.hasattr("__dlpack__")? {
+            obj.call_method0("__dlpack__")?
+        } else {
+            obj.clone()
+        };
+
+        let name = DLTENSOR_NAME.as_ptr() as *const c_char;
+        unsafe {
+            if ffi::PyCapsule_IsValid(capsule.as_ptr(), name) != 1 {
+                return Err(PyTypeError::new_err(
+                    "Expected an unconsumed DLPack capsule or an object implementing __dlpack__",
+                ));
+            }
+            let managed = ffi::PyCapsule_GetPointer(capsule.as_ptr(), name);
+            if managed.is_null() {
+                return Err(PyErr::fetch(obj.py()));
+            }
+            let used = USED_DLTENSOR_NAME.as_ptr() as *const c_char;
+            if ffi::PyCapsule_SetName(capsule.as_ptr(), used) != 0 {
+                return Err(PyErr::fetch(obj.py()));
+            }
+            Ok(DLPackCapsule::from_raw(managed.cast()))
+        }
+    }
+}
+
+/// Releases an exported tensor that no consumer took.
+unsafe extern "C" fn dlpack_capsule_destructor(capsule: *mut ffi::PyObject) {
+    let name = DLTENSOR_NAME.as_ptr() as *const c_char;
+    if ffi::PyCapsule_IsValid(capsule, name) == 1 {
+        let managed = ffi::PyCapsule_GetPointer(capsule, name);
+        drop(DLPackCapsule::from_raw(managed.cast()));
+    }
+}
+
+/// Maps a DLPack import error to a Python exception.
+pub fn dlpack_error(err: DLPackError) -> PyErr {
+    match err {
+        DLPackError::UnsupportedDataType { .. } => PyTypeError::new_err(err.to_string()),
+        DLPackError::Gpu(_) => PyRuntimeError::new_err(err.to_string()),
+        _ => PyValueError::new_err(err.to_string()),
+    }
 }
 
 #[cfg(all(test, feature = "python-tests"))]
@@ -287,4 +467,20 @@ mod tests {
         let buf = PyTensorBuffer::from_bytes(&data);
         assert_eq!(buf.size(), 4);
     }
+
+    #[test]
+    fn test_tensor_buffer_cpu_interop() {
+        let buf = PyTensorBuffer::from_bytes(&[1, 2, 3, 4]);
+        assert_eq!(buf.device_id(), None);
+        assert_eq!(buf.__dlpack_device__(), (DLDeviceType::Cpu as i32, 0));
+
+        Python::with_gil(|py| {
+            assert!(buf.__cuda_array_interface__(py).is_err());
+
+            // The capsule is importable like any other producer's
+            let capsule = buf.__dlpack__(py, None, None, None, None).unwrap();
+            let tensor = PyDLPackCapsule::consume(capsule.bind(py)).unwrap().to_tensor().unwrap();
+            assert_eq!(tensor.data.as_ref(), &[1, 2, 3, 4]);
+        });
+    }
 }
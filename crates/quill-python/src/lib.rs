@@ -38,12 +38,16 @@ mod dtype;
 mod gpu;
 mod tensor;
 mod token;
+mod token_stream;
+mod transfer;
 
 pub use client::PyQuillClient;
 pub use dtype::PyDType;
 pub use gpu::{PyDLPackCapsule, PyGpuStatus, PyTensorBuffer};
 pub use tensor::{PyTensor, PyTensorMeta};
 pub use token::{PyToken, PyTokenBatch};
+pub use token_stream::PyTokenStream;
+pub use transfer::PyTransfer;
 
 /// Quill Python module
 #[pymodule]
@@ -63,9 +67,11 @@ fn quill(m: &Bound<'_, PyModule>) -> PyResult<()> {
     // Token types for LLM inference
     m.add_class::<PyToken>()?;
     m.add_class::<PyTokenBatch>()?;
+    m.add_class::<PyTokenStream>()?;
 
     // Client
     m.add_class::<PyQuillClient>()?;
+    m.add_class::<PyTransfer>()?;
 
     // Version info
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
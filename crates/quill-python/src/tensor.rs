@@ -1,11 +1,13 @@
 //! Python bindings for Tensor types with NumPy integration.
 
 use crate::dtype::PyDType;
+use bytes::Bytes;
 use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::ffi;
 use pyo3::prelude::*;
-use pyo3::types::PyTuple;
-use quill_tensor::{DType, TensorMeta};
-use std::sync::Arc;
+use pyo3::types::{PyBytes, PyTuple};
+use quill_tensor::{pack_int4, pack_uint4, DType, Tensor, TensorMeta};
+use std::os::raw::{c_int, c_void};
 
 /// Tensor metadata describing shape and data type.
 #[pyclass(name = "TensorMeta")]
@@ -22,14 +24,36 @@ impl PyTensorMeta {
     ///     shape: Tensor dimensions as a list
     ///     dtype: Data type (DType)
     ///     name: Optional tensor name/identifier
+    ///     strides: Optional strides in elements, one per dimension
+    ///     requires_grad: Whether the tensor requires gradients
+    ///
+    /// Raises:
+    ///     ValueError: If strides doesn't have one entry per dimension
     #[new]
-    #[pyo3(signature = (shape, dtype, name=None))]
-    fn new(shape: Vec<usize>, dtype: PyDType, name: Option<String>) -> Self {
-        let mut meta = TensorMeta::new(shape, dtype.inner());
+    #[pyo3(signature = (shape, dtype, name=None, strides=None, requires_grad=false))]
+    fn new(
+        shape: Vec<usize>,
+        dtype: PyDType,
+        name: Option<String>,
+        strides: Option<Vec<usize>>,
+        requires_grad: bool,
+    ) -> PyResult<Self> {
+        let mut meta = TensorMeta::new(shape, dtype.inner()).with_requires_grad(requires_grad);
         if let Some(n) = name {
             meta = meta.with_name(n);
         }
-        Self { inner: meta }
+        if let Some(strides) = strides {
+            if strides.len() != meta.shape.len() {
+                return Err(PyValueError::new_err(format!(
+                    "Expected {} strides for shape {:?}, got {}",
+                    meta.shape.len(),
+                    meta.shape,
+                    strides.len()
+                )));
+            }
+            meta = meta.with_strides(strides);
+        }
+        Ok(Self { inner: meta })
     }
 
     /// Get tensor name (if set)
@@ -68,14 +92,40 @@ impl PyTensorMeta {
         self.inner.shape.len()
     }
 
+    /// Get strides in elements (None for row-major layout)
+    #[getter]
+    fn strides(&self) -> Option<Vec<usize>> {
+        self.inner.strides.clone()
+    }
+
+    /// Whether the layout is row-major contiguous
+    #[getter]
+    fn is_contiguous(&self) -> bool {
+        self.inner.is_contiguous()
+    }
+
+    /// Whether the tensor requires gradients
+    #[getter]
+    fn requires_grad(&self) -> bool {
+        self.inner.requires_grad
+    }
+
     fn __repr__(&self) -> String {
         let name_str = self.inner.name.as_deref().unwrap_or("unnamed");
-        format!(
-            "TensorMeta(name='{}', shape={:?}, dtype={})",
+        let mut repr = format!(
+            "TensorMeta(name='{}', shape={:?}, dtype={}",
             name_str,
             self.inner.shape,
             PyDType::from_inner(self.inner.dtype).name()
-        )
+        );
+        if let Some(strides) = self.inner.strides.as_ref().filter(|_| !self.inner.is_contiguous()) {
+            repr.push_str(&format!(", strides={:?}", strides));
+        }
+        if self.inner.requires_grad {
+            repr.push_str(", requires_grad=True");
+        }
+        repr.push(')');
+        repr
     }
 }
 
@@ -93,11 +143,15 @@ impl PyTensorMeta {
 ///
 /// Tensors can be created from NumPy arrays and converted back to NumPy.
 /// This provides efficient interop for ML inference.
+///
+/// The data is immutable and exported read-only through the buffer
+/// protocol, so `memoryview(tensor)` and `to_numpy()` share it without
+/// copying.
 #[pyclass(name = "Tensor")]
 #[derive(Clone)]
 pub struct PyTensor {
     meta: TensorMeta,
-    data: Arc<Vec<u8>>,
+    data: Bytes,
 }
 
 #[pymethods]
@@ -105,7 +159,8 @@ impl PyTensor {
     /// Create a tensor from a NumPy array.
     ///
     /// Args:
-    ///     array: NumPy array (supports float32, float64, int32, int64, uint8, bool)
+    ///     array: NumPy array (supports float32, float64, int32, int64, uint8, bool,
+    ///         and the ml_dtypes float8_e4m3fn, float8_e5m2, int4 and uint4)
     ///     name: Optional tensor name (defaults to "tensor")
     ///
     /// Returns:
@@ -131,17 +186,32 @@ impl PyTensor {
             "int64" => DType::Int64,
             "uint8" => DType::UInt8,
             "bool" => DType::Bool,
+            "float8_e4m3fn" => DType::Float8E4M3,
+            "float8_e5m2" => DType::Float8E5M2,
+            "int4" => DType::Int4,
+            "uint4" => DType::UInt4,
             other => {
                 return Err(PyTypeError::new_err(format!(
-                    "Unsupported numpy dtype: {}. Supported: float32, float64, float16, int8, int32, int64, uint8, bool",
+                    "Unsupported numpy dtype: {}. Supported: float32, float64, float16, int8, int32, int64, uint8, bool, \
+                     float8_e4m3fn, float8_e5m2, int4, uint4",
                     other
                 )));
             }
         };
 
-        // Get raw bytes from the array
-        let tobytes = array.call_method0("tobytes")?;
-        let data: Vec<u8> = tobytes.extract()?;
+        // Get raw bytes from the array; ml_dtypes keeps 4-bit values one per
+        // byte, so widen them and pack two per byte
+        let data: Vec<u8> = match dtype {
+            DType::Int4 => {
+                let bytes: Vec<u8> = array.call_method1("astype", ("int8",))?.call_method0("tobytes")?.extract()?;
+                pack_int4(&bytes.iter().map(|&b| b as i8).collect::<Vec<_>>())
+            }
+            DType::UInt4 => {
+                let bytes: Vec<u8> = array.call_method1("astype", ("uint8",))?.call_method0("tobytes")?.extract()?;
+                pack_uint4(&bytes)
+            }
+            _ => array.call_method0("tobytes")?.extract()?,
+        };
 
         let mut meta = TensorMeta::new(shape, dtype);
         if let Some(n) = name {
@@ -150,7 +220,7 @@ impl PyTensor {
 
         Ok(Self {
             meta,
-            data: Arc::new(data),
+            data: Bytes::from(data),
         })
     }
 
@@ -172,7 +242,7 @@ impl PyTensor {
 
         Self {
             meta,
-            data: Arc::new(data),
+            data: Bytes::from(data),
         }
     }
 
@@ -203,20 +273,34 @@ impl PyTensor {
 
         Ok(Self {
             meta,
-            data: Arc::new(data),
+            data: Bytes::from(data),
         })
     }
 
     /// Convert tensor to a NumPy array.
     ///
+    /// By default the array is a read-only view of the tensor's buffer: no
+    /// data is copied, and the tensor stays alive as long as the array does.
+    /// Pass `copy=True`, or call `.copy()` on the view, for an array that
+    /// owns its data and can be written to.
+    ///
+    /// FP8 and 4-bit tensors come back as ml_dtypes arrays, which requires
+    /// the `ml_dtypes` package. 4-bit values are unpacked to one per byte,
+    /// so those arrays are always copies.
+    ///
+    /// Args:
+    ///     copy: Return an owned, writable copy instead of a view
+    ///
     /// Returns:
     ///     NumPy array with the tensor data
-    fn to_numpy<'py>(&self, py: Python<'py>) -> PyResult<PyObject> {
+    #[pyo3(signature = (copy=false))]
+    fn to_numpy(slf: &Bound<'_, Self>, copy: bool) -> PyResult<PyObject> {
+        let py = slf.py();
+        let tensor = slf.borrow();
         let numpy = py.import_bound("numpy")?;
-        let frombuffer = numpy.getattr("frombuffer")?;
 
         // Map quill dtype to numpy dtype string
-        let np_dtype = match self.meta.dtype {
+        let np_dtype = match tensor.meta.dtype {
             DType::Float32 => "float32",
             DType::Float64 => "float64",
             DType::Float16 => "float16",
@@ -225,29 +309,89 @@ impl PyTensor {
             DType::Int64 => "int64",
             DType::UInt8 => "uint8",
             DType::Bool => "bool",
+            DType::Float8E4M3 => "float8_e4m3fn",
+            DType::Float8E5M2 => "float8_e5m2",
+            DType::Int4 => "int4",
+            DType::UInt4 => "uint4",
             DType::BFloat16 => {
                 return Err(PyTypeError::new_err(
                     "bfloat16 is not directly supported by NumPy. Use view as uint16 instead."
                 ));
             }
         };
+        let np_dtype = match tensor.meta.dtype {
+            DType::Float8E4M3 | DType::Float8E5M2 | DType::Int4 | DType::UInt4 => {
+                let ml_dtypes = py.import_bound("ml_dtypes").map_err(|_| {
+                    PyTypeError::new_err(format!(
+                        "{} arrays require the ml_dtypes package (pip install ml_dtypes)",
+                        np_dtype
+                    ))
+                })?;
+                ml_dtypes.getattr(np_dtype)?
+            }
+            _ => numpy.call_method1("dtype", (np_dtype,))?,
+        };
 
-        // Create numpy array from bytes
-        let data_bytes = pyo3::types::PyBytes::new_bound(py, &self.data);
-        let array = frombuffer.call1((data_bytes, np_dtype))?;
-
-        // Reshape to original shape
         let shape_tuple = PyTuple::new_bound(
             py,
-            self.meta.shape.iter().map(|&x| x as i64),
+            tensor.meta.shape.iter().map(|&x| x as i64),
         );
-        let reshaped = array.call_method1("reshape", (shape_tuple,))?;
+        // frombuffer rejects empty buffers on older NumPy releases
+        if tensor.data.is_empty() {
+            return Ok(numpy.call_method1("empty", (shape_tuple, np_dtype))?.unbind());
+        }
+
+        if tensor.meta.dtype.is_packed() {
+            // Unpack to one value per byte, in row-major order
+            let packed = Tensor::new(tensor.meta.clone(), tensor.data.clone());
+            let (unpacked, carrier) = match tensor.meta.dtype {
+                DType::Int4 => (packed.to_int4().into_iter().map(|v| v as u8).collect(), "int8"),
+                _ => (packed.to_uint4(), "uint8"),
+            };
+            let array = numpy.call_method1("frombuffer", (PyBytes::new_bound(py, &unpacked), carrier))?;
+            let array = array.call_method1("astype", (np_dtype,))?;
+            return Ok(array.call_method1("reshape", (shape_tuple,))?.unbind());
+        }
+
+        // View the tensor through the buffer protocol; the array keeps it alive
+        let array = numpy.call_method1("frombuffer", (slf, np_dtype))?;
+        let reshaped = match tensor.meta.strides.as_ref().filter(|_| !tensor.meta.is_contiguous()) {
+            Some(strides) => {
+                // Strided layouts (e.g. transposes received with their strides) stay views too
+                let itemsize = tensor.meta.dtype.element_size();
+                let byte_strides =
+                    PyTuple::new_bound(py, strides.iter().map(|&s| (s * itemsize) as i64));
+                let stride_tricks = py.import_bound("numpy.lib.stride_tricks")?;
+                let kwargs = pyo3::types::PyDict::new_bound(py);
+                kwargs.set_item("shape", shape_tuple)?;
+                kwargs.set_item("strides", byte_strides)?;
+                kwargs.set_item("writeable", false)?;
+                stride_tricks.call_method("as_strided", (array,), Some(&kwargs))?
+            }
+            None => array.call_method1("reshape", (shape_tuple,))?,
+        };
+        if copy {
+            return Ok(reshaped.call_method0("copy")?.unbind());
+        }
+        Ok(reshaped.unbind())
+    }
 
-        // Return a copy to ensure memory safety
-        let copied = reshaped.call_method0("copy")?;
-        Ok(copied.unbind())
+    /// Export the tensor data as a read-only, one-dimensional byte buffer
+    unsafe fn __getbuffer__(slf: Bound<'_, Self>, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        let (buf, len) = {
+            let tensor = slf.borrow();
+            (tensor.data.as_ptr(), tensor.data.len())
+        };
+        // The data is never replaced, so the pointer stays valid while the
+        // view holds its reference to the tensor; writable requests are refused
+        if ffi::PyBuffer_FillInfo(view, slf.as_ptr(), buf as *mut c_void, len as ffi::Py_ssize_t, 1, flags) == -1 {
+            return Err(PyErr::fetch(slf.py()));
+        }
+        Ok(())
     }
 
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+
     /// Get tensor metadata
     #[getter]
     fn meta(&self) -> PyTensorMeta {
@@ -311,6 +455,46 @@ impl PyTensor {
         crate::gpu::PyDLPackCapsule::from_tensor_data(&self.meta, &self.data)
     }
 
+    /// Imports a tensor from DLPack.
+    ///
+    /// Accepts any object implementing `__dlpack__` (PyTorch, JAX, CuPy, ...),
+    /// a raw DLPack capsule, or a `DLPackCapsule` from `to_dlpack()`. CPU
+    /// tensors are shared without copying and released through the
+    /// producer's deleter once the Quill tensor is dropped; CUDA tensors are
+    /// copied to host memory (requires the `cuda` feature). Tensors must be
+    /// contiguous.
+    ///
+    /// Args:
+    ///     obj: DLPack-compatible tensor or capsule
+    ///     name: Optional tensor name
+    ///
+    /// Returns:
+    ///     Tensor owning the imported data
+    ///
+    /// Raises:
+    ///     TypeError: If obj is not a DLPack source or the dtype is unsupported
+    ///     ValueError: If the tensor is non-contiguous or malformed
+    ///     RuntimeError: If a CUDA tensor cannot be copied to the host
+    ///
+    /// Example:
+    ///     >>> torch_tensor = torch.arange(6, dtype=torch.float32).reshape(2, 3)
+    ///     >>> tensor = quill.Tensor.from_dlpack(torch_tensor)
+    #[staticmethod]
+    #[pyo3(signature = (obj, name=None))]
+    fn from_dlpack(obj: &Bound<'_, PyAny>, name: Option<String>) -> PyResult<Self> {
+        let capsule = crate::gpu::PyDLPackCapsule::consume(obj)?;
+        let tensor = capsule.to_tensor().map_err(crate::gpu::dlpack_error)?;
+
+        let mut meta = tensor.meta;
+        if let Some(n) = name {
+            meta = meta.with_name(n);
+        }
+        Ok(Self {
+            meta,
+            data: tensor.data,
+        })
+    }
+
     fn __repr__(&self) -> String {
         let name_str = self.meta.name.as_deref().unwrap_or("unnamed");
         format!(
@@ -338,7 +522,7 @@ impl PyTensor {
     pub fn from_parts(meta: TensorMeta, data: Vec<u8>) -> Self {
         Self {
             meta,
-            data: Arc::new(data),
+            data: Bytes::from(data),
         }
     }
 }
@@ -353,13 +537,33 @@ mod tests {
             vec![2, 3, 4],
             PyDType::from_inner(DType::Float32),
             Some("test".to_string()),
-        );
+            None,
+            false,
+        )
+        .unwrap();
 
         assert_eq!(meta.name(), Some("test"));
         assert_eq!(meta.shape(), vec![2, 3, 4]);
         assert_eq!(meta.num_elements(), 24);
         assert_eq!(meta.size_bytes(), 96); // 24 * 4 bytes
         assert_eq!(meta.ndim(), 3);
+        assert_eq!(meta.strides(), None);
+        assert!(meta.is_contiguous());
+        assert!(!meta.requires_grad());
+    }
+
+    #[test]
+    fn test_tensor_meta_strides_and_grad() {
+        let dtype = || PyDType::from_inner(DType::Float32);
+        let meta = PyTensorMeta::new(vec![2, 3], dtype(), None, Some(vec![1, 2]), true).unwrap();
+        assert_eq!(meta.strides(), Some(vec![1, 2]));
+        assert!(!meta.is_contiguous());
+        assert!(meta.requires_grad());
+        let repr = meta.__repr__();
+        assert!(repr.contains("strides=[1, 2]"));
+        assert!(repr.contains("requires_grad=True"));
+
+        assert!(PyTensorMeta::new(vec![2, 3], dtype(), None, Some(vec![1]), false).is_err());
     }
 
     #[test]
@@ -404,13 +608,49 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_tensor_buffer_is_read_only_view() {
+        Python::with_gil(|py| {
+            let tensor = Bound::new(
+                py,
+                PyTensor::from_parts(TensorMeta::new(vec![4], DType::UInt8), vec![1, 2, 3, 4]),
+            )
+            .unwrap();
+            let buffer = pyo3::buffer::PyBuffer::<u8>::get_bound(tensor.as_any()).unwrap();
+
+            // The buffer is the tensor's own data, not a copy
+            assert_eq!(buffer.buf_ptr() as *const u8, tensor.borrow().data.as_ptr());
+            assert!(buffer.readonly());
+            assert_eq!(buffer.to_vec(py).unwrap(), vec![1, 2, 3, 4]);
+        });
+    }
+
+    #[test]
+    fn test_tensor_dlpack_roundtrip() {
+        Python::with_gil(|py| {
+            let tensor =
+                PyTensor::from_parts(TensorMeta::new(vec![2, 2], DType::UInt8), vec![1, 2, 3, 4]);
+            let capsule = Bound::new(py, tensor.to_dlpack().unwrap()).unwrap();
+
+            let imported = PyTensor::from_dlpack(capsule.as_any(), None).unwrap();
+            assert_eq!(imported.meta.shape, vec![2, 2]);
+            assert_eq!(imported.data(), &[1, 2, 3, 4]);
+
+            // A capsule can only be consumed once
+            assert!(PyTensor::from_dlpack(capsule.as_any(), None).is_err());
+        });
+    }
+
     #[test]
     fn test_tensor_meta_repr() {
         let meta = PyTensorMeta::new(
             vec![768],
             PyDType::from_inner(DType::Float16),
             Some("embedding".to_string()),
-        );
+            None,
+            false,
+        )
+        .unwrap();
 
         let repr = meta.__repr__();
         assert!(repr.contains("embedding"));
@@ -75,6 +75,12 @@ impl PyToken {
         self.inner.is_special
     }
 
+    /// Get generation time in microseconds since the stream started (if stamped)
+    #[getter]
+    fn timestamp_us(&self) -> Option<u64> {
+        self.inner.timestamp_us
+    }
+
     /// Check if this is an end-of-sequence token (by convention, id 0, 1, or 2)
     fn is_eos(&self) -> bool {
         self.inner.is_special && matches!(self.inner.id, 0 | 1 | 2)
@@ -93,6 +99,7 @@ impl PyToken {
         dict.set_item("logprob", self.inner.logprob)?;
         dict.set_item("position", self.inner.position)?;
         dict.set_item("is_special", self.inner.is_special)?;
+        dict.set_item("timestamp_us", self.inner.timestamp_us)?;
         Ok(dict)
     }
 
@@ -281,6 +288,7 @@ impl PyTokenBatch {
                 dict.set_item("logprob", t.logprob)?;
                 dict.set_item("position", t.position)?;
                 dict.set_item("is_special", t.is_special)?;
+                dict.set_item("timestamp_us", t.timestamp_us)?;
                 Ok(dict)
             })
             .collect()
@@ -303,6 +311,19 @@ impl PyTokenBatch {
     }
 }
 
+impl PyTokenBatch {
+    pub fn inner(&self) -> &TokenBatch {
+        &self.inner
+    }
+
+    pub fn from_inner(inner: TokenBatch) -> Self {
+        Self {
+            inner,
+            metadata: HashMap::new(),
+        }
+    }
+}
+
 /// Iterator for TokenBatch
 #[pyclass]
 pub struct PyTokenBatchIterator {
@@ -0,0 +1,264 @@
+//! Python bindings for incrementally received token streams.
+
+use crate::token::PyTokenBatch;
+use bytes::Bytes;
+use pyo3::exceptions::{PyRuntimeError, PyTimeoutError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use quill_core::QuillError;
+use quill_tensor::{
+    FrameType, TensorFrameParser, TokenBatch, TokenLatencySummary, TokenLatencyTracker,
+};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio_stream::{Stream, StreamExt};
+
+type MessageStream = Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>;
+
+/// Decodes TOKEN_BATCH frames from a streaming response as messages arrive.
+pub(crate) struct TokenFrameReader {
+    messages: MessageStream,
+    parser: TensorFrameParser,
+    finished: bool,
+    latency: TokenLatencyTracker,
+}
+
+impl TokenFrameReader {
+    pub(crate) fn new(messages: MessageStream) -> Self {
+        Self {
+            messages,
+            parser: TensorFrameParser::new(),
+            finished: false,
+            latency: TokenLatencyTracker::new(),
+        }
+    }
+
+    /// Latency of the timestamped tokens received so far.
+    pub(crate) fn latency(&self) -> TokenLatencySummary {
+        self.latency.summary()
+    }
+
+    /// Wait for the next token batch, or `None` once the stream has ended.
+    pub(crate) async fn next_batch(&mut self) -> Result<Option<TokenBatch>, QuillError> {
+        while !self.finished {
+            match self.parser.parse_frame() {
+                Ok(Some(frame)) => match frame.frame_type {
+                    FrameType::TokenBatch => {
+                        let batch = TokenBatch::decode(&frame.payload)
+                            .ok_or_else(|| self.fail(QuillError::Framing("Invalid TOKEN_BATCH payload".to_string())))?;
+                        self.latency.record(&batch);
+                        return Ok(Some(batch));
+                    }
+                    FrameType::EndStream => self.finished = true,
+                    FrameType::Cancel => {
+                        let reason = String::from_utf8_lossy(&frame.payload).into_owned();
+                        return Err(self.fail(QuillError::Rpc(format!("Cancelled: {}", reason))));
+                    }
+                    _ => continue,
+                },
+                Ok(None) => match self.messages.next().await {
+                    Some(Ok(message)) => self.parser.feed_bytes(message),
+                    Some(Err(e)) => return Err(self.fail(e)),
+                    None => self.finished = true,
+                },
+                Err(e) => return Err(self.fail(QuillError::Framing(e.to_string()))),
+            }
+        }
+        Ok(None)
+    }
+
+    fn fail(&mut self, error: QuillError) -> QuillError {
+        self.finished = true;
+        error
+    }
+}
+
+/// Convert a stream error into the matching Python exception.
+pub(crate) fn stream_error(error: QuillError, timeout_ms: u64) -> PyErr {
+    if error.is_stream_idle_timeout() {
+        PyTimeoutError::new_err(format!("No tokens received for {}ms", timeout_ms))
+    } else {
+        PyRuntimeError::new_err(format!("RPC error: {}", error))
+    }
+}
+
+/// Convert a token latency summary into a dictionary of seconds.
+pub(crate) fn latency_dict<'py>(py: Python<'py>, summary: &TokenLatencySummary) -> PyResult<Bound<'py, PyDict>> {
+    let seconds = |duration: Option<std::time::Duration>| duration.map(|d| d.as_secs_f64());
+    let dict = PyDict::new_bound(py);
+    dict.set_item("tokens", summary.tokens)?;
+    dict.set_item("time_to_first_token_seconds", seconds(summary.time_to_first_token))?;
+    dict.set_item("generation_seconds", seconds(summary.generation_time))?;
+    dict.set_item("mean_inter_token_seconds", seconds(summary.mean_inter_token))?;
+    dict.set_item("p50_inter_token_seconds", seconds(summary.p50_inter_token))?;
+    dict.set_item("p99_inter_token_seconds", seconds(summary.p99_inter_token))?;
+    dict.set_item("max_inter_token_seconds", seconds(summary.max_inter_token))?;
+    dict.set_item("tokens_per_second", summary.tokens_per_second())?;
+    Ok(dict)
+}
+
+/// Token batches of a generation, yielded as they arrive.
+///
+/// Iteration blocks until the next TOKEN_BATCH frame is received, with the
+/// GIL released. Stopping early (or calling `close()`) cancels the call.
+/// `latency()` summarizes the timestamped tokens received so far.
+///
+/// Example:
+/// ```python
+/// for batch in client.call_generate_stream("llm.v1.LLM", "Generate", request):
+///     print(batch.text(), end="", flush=True)
+/// print(stream.latency()["time_to_first_token_seconds"])
+/// ```
+#[pyclass(name = "TokenStream")]
+pub struct PyTokenStream {
+    reader: Option<TokenFrameReader>,
+    runtime: Arc<Runtime>,
+    timeout_ms: u64,
+    /// Latency as of when the reader was dropped
+    latency: TokenLatencySummary,
+}
+
+impl PyTokenStream {
+    pub(crate) fn new(reader: TokenFrameReader, runtime: Arc<Runtime>, timeout_ms: u64) -> Self {
+        Self {
+            reader: Some(reader),
+            runtime,
+            timeout_ms,
+            latency: TokenLatencySummary::default(),
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(reader) = self.reader.take() {
+            self.latency = reader.latency();
+        }
+    }
+}
+
+#[pymethods]
+impl PyTokenStream {
+    /// Check if the stream has ended or been closed
+    #[getter]
+    fn closed(&self) -> bool {
+        self.reader.is_none()
+    }
+
+    /// Stop receiving and cancel the call.
+    fn close(&mut self) {
+        self.finish();
+    }
+
+    /// Time-to-first-token and inter-token latency of the timestamped tokens
+    /// received so far, in seconds (None until enough tokens arrived).
+    fn latency<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let summary = self.reader.as_ref().map_or(self.latency, TokenFrameReader::latency);
+        latency_dict(py, &summary)
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyTokenBatch>> {
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(None);
+        };
+        let runtime = &self.runtime;
+        match py.allow_threads(|| runtime.block_on(reader.next_batch())) {
+            Ok(Some(batch)) => Ok(Some(PyTokenBatch::from_inner(batch))),
+            Ok(None) => {
+                self.finish();
+                Ok(None)
+            }
+            Err(e) => {
+                self.finish();
+                Err(stream_error(e, self.timeout_ms))
+            }
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("TokenStream(closed={})", self.closed())
+    }
+}
+
+#[cfg(all(test, feature = "python-tests"))]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use quill_tensor::{TensorFrame, Token};
+
+    fn reader(messages: Vec<Result<Bytes, QuillError>>) -> TokenFrameReader {
+        TokenFrameReader::new(Box::pin(tokio_stream::iter(messages)))
+    }
+
+    fn batch_frame(text: &str) -> Bytes {
+        let mut batch = TokenBatch::new();
+        batch.push(Token::with_text(1, text, 0));
+        let mut buf = BytesMut::new();
+        TensorFrame::token_batch(batch.encode()).encode_into(&mut buf);
+        buf.freeze()
+    }
+
+    fn end_frame() -> Bytes {
+        let mut buf = BytesMut::new();
+        TensorFrame::end_stream().encode_into(&mut buf);
+        buf.freeze()
+    }
+
+    #[tokio::test]
+    async fn test_reader_yields_batches_per_message() {
+        let mut reader = reader(vec![Ok(batch_frame("Hello")), Ok(batch_frame(" world")), Ok(end_frame())]);
+
+        let first = reader.next_batch().await.unwrap().unwrap();
+        assert_eq!(first.tokens[0].text.as_deref(), Some("Hello"));
+        let second = reader.next_batch().await.unwrap().unwrap();
+        assert_eq!(second.tokens[0].text.as_deref(), Some(" world"));
+        assert!(reader.next_batch().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reader_handles_frames_split_across_messages() {
+        let mut joined = BytesMut::new();
+        joined.extend_from_slice(&batch_frame("a"));
+        joined.extend_from_slice(&batch_frame("b"));
+        joined.extend_from_slice(&end_frame());
+        let joined = joined.freeze();
+        let mut reader = reader(vec![Ok(joined.slice(..3)), Ok(joined.slice(3..))]);
+
+        assert!(reader.next_batch().await.unwrap().is_some());
+        assert!(reader.next_batch().await.unwrap().is_some());
+        assert!(reader.next_batch().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reader_stops_after_error() {
+        let mut reader = reader(vec![
+            Ok(batch_frame("a")),
+            Err(QuillError::Rpc("boom".to_string())),
+            Ok(batch_frame("b")),
+        ]);
+
+        assert!(reader.next_batch().await.unwrap().is_some());
+        assert!(reader.next_batch().await.is_err());
+        assert!(reader.next_batch().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reader_tracks_token_latency() {
+        let stamped = |ms: u64| {
+            let batch = TokenBatch::with_tokens(vec![Token::new(1, 0).with_timestamp_us(ms * 1000)]);
+            let mut buf = BytesMut::new();
+            TensorFrame::token_batch(batch.encode()).encode_into(&mut buf);
+            Ok(buf.freeze())
+        };
+        let mut reader = reader(vec![stamped(80), stamped(100), Ok(end_frame())]);
+
+        while reader.next_batch().await.unwrap().is_some() {}
+        let latency = reader.latency();
+        assert_eq!(latency.tokens, 2);
+        assert_eq!(latency.time_to_first_token, Some(std::time::Duration::from_millis(80)));
+        assert_eq!(latency.mean_inter_token, Some(std::time::Duration::from_millis(20)));
+    }
+}
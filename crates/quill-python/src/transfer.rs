@@ -0,0 +1,160 @@
+//! Python bindings for progress-reporting transfers.
+
+use bytes::Bytes;
+use pyo3::exceptions::{PyRuntimeError, PyTimeoutError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use quill_client::{TransferHandle, TransferState};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+/// A running upload or download.
+///
+/// Example:
+/// ```python
+/// transfer = client.download("files.v1.FileService", "Get", b"model.bin", expected_size=size)
+/// while not transfer.done:
+///     p = transfer.progress()
+///     print(f"{p['bytes_transferred']} / {p['total_bytes']} bytes, ETA {p['eta_seconds']}s")
+///     time.sleep(1)
+/// data = transfer.wait()
+/// ```
+#[pyclass(name = "Transfer")]
+pub struct PyTransfer {
+    handle: TransferHandle<Bytes>,
+    outcome: Option<Result<Bytes, String>>,
+    runtime: Arc<Runtime>,
+}
+
+impl PyTransfer {
+    pub(crate) fn new(handle: TransferHandle<Bytes>, runtime: Arc<Runtime>) -> Self {
+        Self {
+            handle,
+            outcome: None,
+            runtime,
+        }
+    }
+}
+
+fn state_name(state: TransferState) -> &'static str {
+    match state {
+        TransferState::Running => "running",
+        TransferState::Paused => "paused",
+        TransferState::Completed => "completed",
+        TransferState::Failed => "failed",
+        TransferState::Cancelled => "cancelled",
+    }
+}
+
+#[pymethods]
+impl PyTransfer {
+    /// Get the current progress.
+    ///
+    /// Returns:
+    ///     Dict with bytes_transferred, total_bytes, fraction, bytes_per_second,
+    ///     elapsed_seconds, eta_seconds and state (None where unknown)
+    fn progress<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let progress = self.handle.progress();
+        let dict = PyDict::new_bound(py);
+        dict.set_item("bytes_transferred", progress.bytes_transferred)?;
+        dict.set_item("total_bytes", progress.total_bytes)?;
+        dict.set_item("fraction", progress.fraction())?;
+        dict.set_item("bytes_per_second", progress.bytes_per_second)?;
+        dict.set_item("elapsed_seconds", progress.elapsed.as_secs_f64())?;
+        dict.set_item("eta_seconds", progress.eta.map(|eta| eta.as_secs_f64()))?;
+        dict.set_item("state", state_name(progress.state))?;
+        Ok(dict)
+    }
+
+    /// Get the current state ("running", "paused", "completed", "failed" or "cancelled")
+    #[getter]
+    fn state(&self) -> &'static str {
+        state_name(self.handle.state())
+    }
+
+    /// Check if the transfer has finished
+    #[getter]
+    fn done(&self) -> bool {
+        self.handle.state().is_finished()
+    }
+
+    /// Hold the transfer after the chunk or message in flight.
+    ///
+    /// Returns:
+    ///     True if the transfer was running
+    fn pause(&self) -> bool {
+        self.handle.pause()
+    }
+
+    /// Continue a paused transfer.
+    ///
+    /// Returns:
+    ///     True if the transfer was paused
+    fn resume(&self) -> bool {
+        self.handle.resume()
+    }
+
+    /// Stop the transfer.
+    ///
+    /// Returns:
+    ///     True if the transfer had not finished yet
+    fn cancel(&self) -> bool {
+        self.handle.cancel()
+    }
+
+    /// Wait for the transfer to finish.
+    ///
+    /// Args:
+    ///     timeout_ms: Maximum time to wait; the transfer keeps running if it expires
+    ///
+    /// Returns:
+    ///     Response bytes
+    #[pyo3(signature = (timeout_ms=None))]
+    fn wait<'py>(&mut self, py: Python<'py>, timeout_ms: Option<u64>) -> PyResult<Bound<'py, pyo3::types::PyBytes>> {
+        if self.outcome.is_none() {
+            let handle = &mut self.handle;
+            let runtime = &self.runtime;
+            let result = py.allow_threads(|| {
+                runtime.block_on(async {
+                    match timeout_ms {
+                        Some(ms) => tokio::time::timeout(Duration::from_millis(ms), handle).await.ok(),
+                        None => Some(handle.await),
+                    }
+                })
+            });
+            match result {
+                Some(result) => self.outcome = Some(result.map_err(|e| e.to_string())),
+                None => {
+                    return Err(PyTimeoutError::new_err(format!(
+                        "Transfer still running after {}ms",
+                        timeout_ms.unwrap_or_default()
+                    )))
+                }
+            }
+        }
+
+        match &self.outcome {
+            Some(Ok(body)) => Ok(pyo3::types::PyBytes::new_bound(py, body)),
+            Some(Err(e)) => Err(PyRuntimeError::new_err(format!("Transfer error: {}", e))),
+            None => unreachable!("outcome is set above"),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        let progress = self.handle.progress();
+        match progress.total_bytes {
+            Some(total) => format!(
+                "Transfer(state='{}', bytes={}/{})",
+                state_name(progress.state),
+                progress.bytes_transferred,
+                total
+            ),
+            None => format!(
+                "Transfer(state='{}', bytes={})",
+                state_name(progress.state),
+                progress.bytes_transferred
+            ),
+        }
+    }
+}
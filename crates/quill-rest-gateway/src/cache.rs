@@ -0,0 +1,322 @@
+//! Response caching for idempotent GET routes
+//!
+//! This module provides:
+//! - Gateway-wide cache configuration with per-route TTL overrides
+//! - Request `Cache-Control` handling (`no-cache`, `no-store`)
+//! - Stale-while-revalidate bookkeeping for background refreshes
+//!
+//! Entries are keyed by path, query and `Authorization` header, so responses
+//! are never shared between callers presenting different credentials.
+
+use crate::mapping::RouteMapping;
+use http::HeaderMap;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Response header reporting how a request was served (`HIT`, `STALE`, `MISS`)
+pub const CACHE_STATUS_HEADER: &str = "quill-cache";
+
+/// Default maximum number of cached responses
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// Gateway response cache configuration
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// TTL for routes without their own `cache_ttl` (`None` caches only those routes)
+    pub default_ttl: Option<Duration>,
+    /// How long past the TTL a stale response may be served while refreshing
+    pub stale_while_revalidate: Duration,
+    /// Maximum number of cached responses
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl: None,
+            stale_while_revalidate: Duration::ZERO,
+            max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Create a cache that only caches routes with their own TTL
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache every GET route for `ttl` unless the route overrides it
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    /// Serve stale responses for `window` past the TTL while refreshing them
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.stale_while_revalidate = window;
+        self
+    }
+
+    /// Set the maximum number of cached responses
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+}
+
+/// Effective caching policy for a route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CachePolicy {
+    pub(crate) ttl: Duration,
+    pub(crate) stale_while_revalidate: Duration,
+}
+
+impl CachePolicy {
+    /// `Cache-Control` value advertised to downstream caches
+    pub(crate) fn header_value(&self) -> String {
+        if self.stale_while_revalidate.is_zero() {
+            format!("max-age={}", self.ttl.as_secs())
+        } else {
+            format!(
+                "max-age={}, stale-while-revalidate={}",
+                self.ttl.as_secs(),
+                self.stale_while_revalidate.as_secs()
+            )
+        }
+    }
+}
+
+/// Request `Cache-Control` directives the cache honors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct RequestDirectives {
+    /// Skip the lookup but store the fresh response
+    pub(crate) no_cache: bool,
+    /// Neither read from nor write to the cache
+    pub(crate) no_store: bool,
+}
+
+impl RequestDirectives {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let mut directives = Self::default();
+        let values = headers
+            .get_all(http::header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+        for directive in values {
+            match directive.trim().to_ascii_lowercase().as_str() {
+                "no-cache" | "max-age=0" => directives.no_cache = true,
+                "no-store" => directives.no_store = true,
+                _ => {}
+            }
+        }
+        directives
+    }
+}
+
+/// Result of a cache lookup
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Lookup {
+    /// Fresh entry and its age
+    Fresh(Value, Duration),
+    /// Stale entry within the revalidation window; `refresh` is set for the
+    /// one request that should trigger the background refresh
+    Stale { body: Value, age: Duration, refresh: bool },
+    Miss,
+}
+
+struct CacheEntry {
+    body: Value,
+    stored: Instant,
+    policy: CachePolicy,
+    refreshing: bool,
+}
+
+impl CacheEntry {
+    fn expired(&self, now: Instant) -> bool {
+        now.duration_since(self.stored) > self.policy.ttl + self.policy.stale_while_revalidate
+    }
+}
+
+/// In-memory cache of JSON responses
+pub(crate) struct ResponseCache {
+    config: CacheConfig,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Caching policy for `route`, or `None` if its responses are not cached
+    pub(crate) fn policy(&self, route: &RouteMapping) -> Option<CachePolicy> {
+        let ttl = route.cache_ttl.or(self.config.default_ttl)?;
+        if ttl.is_zero() {
+            return None;
+        }
+        Some(CachePolicy {
+            ttl,
+            stale_while_revalidate: route
+                .stale_while_revalidate
+                .unwrap_or(self.config.stale_while_revalidate),
+        })
+    }
+
+    /// Cache key for a request
+    pub(crate) fn key(path: &str, query: Option<&str>, headers: &HeaderMap) -> String {
+        let auth = headers
+            .get(http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        format!("{}?{}#{}", path, query.unwrap_or(""), auth)
+    }
+
+    pub(crate) fn lookup(&self, key: &str) -> Lookup {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get_mut(key) else {
+            return Lookup::Miss;
+        };
+
+        let age = now.duration_since(entry.stored);
+        if age <= entry.policy.ttl {
+            return Lookup::Fresh(entry.body.clone(), age);
+        }
+        if entry.expired(now) {
+            entries.remove(key);
+            return Lookup::Miss;
+        }
+
+        let refresh = !entry.refreshing;
+        entry.refreshing = true;
+        Lookup::Stale {
+            body: entry.body.clone(),
+            age,
+            refresh,
+        }
+    }
+
+    pub(crate) fn store(&self, key: String, body: Value, policy: CachePolicy) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, entry| !entry.expired(now));
+            if entries.len() >= self.config.max_entries {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.stored)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                body,
+                stored: now,
+                policy,
+                refreshing: false,
+            },
+        );
+    }
+
+    /// Allow another refresh after a failed background refresh
+    pub(crate) fn refresh_failed(&self, key: &str) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(key) {
+            entry.refreshing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy(ttl_ms: u64, swr_ms: u64) -> CachePolicy {
+        CachePolicy {
+            ttl: Duration::from_millis(ttl_ms),
+            stale_while_revalidate: Duration::from_millis(swr_ms),
+        }
+    }
+
+    #[test]
+    fn test_route_policy() {
+        let cache = ResponseCache::new(CacheConfig::new().stale_while_revalidate(Duration::from_secs(30)));
+        let route = RouteMapping::new("svc.Dashboard", "Stats");
+        assert_eq!(cache.policy(&route), None);
+
+        let route = route.with_cache_ttl_ms(5_000);
+        assert_eq!(
+            cache.policy(&route).unwrap().header_value(),
+            "max-age=5, stale-while-revalidate=30"
+        );
+
+        let cache = ResponseCache::new(CacheConfig::new().default_ttl(Duration::from_secs(60)));
+        assert!(cache.policy(&RouteMapping::new("svc.Dashboard", "Stats")).is_some());
+        assert_eq!(cache.policy(&RouteMapping::new("svc.Dashboard", "Stats").with_cache_ttl_ms(0)), None);
+    }
+
+    #[test]
+    fn test_request_directives() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(RequestDirectives::from_headers(&headers), RequestDirectives::default());
+
+        headers.insert(http::header::CACHE_CONTROL, "No-Cache, no-store".parse().unwrap());
+        let directives = RequestDirectives::from_headers(&headers);
+        assert!(directives.no_cache && directives.no_store);
+    }
+
+    #[test]
+    fn test_key_varies_on_authorization() {
+        let mut headers = HeaderMap::new();
+        let anonymous = ResponseCache::key("/api/stats", Some("day=1"), &headers);
+        headers.insert(http::header::AUTHORIZATION, "Bearer a".parse().unwrap());
+        assert_ne!(ResponseCache::key("/api/stats", Some("day=1"), &headers), anonymous);
+    }
+
+    #[test]
+    fn test_stale_while_revalidate() {
+        let cache = ResponseCache::new(CacheConfig::new());
+        assert_eq!(cache.lookup("k"), Lookup::Miss);
+
+        cache.store("k".into(), json!({"n": 1}), policy(0, 60_000));
+        std::thread::sleep(Duration::from_millis(2));
+
+        // Only the first stale hit triggers a refresh
+        assert!(matches!(cache.lookup("k"), Lookup::Stale { refresh: true, .. }));
+        assert!(matches!(cache.lookup("k"), Lookup::Stale { refresh: false, .. }));
+        cache.refresh_failed("k");
+        assert!(matches!(cache.lookup("k"), Lookup::Stale { refresh: true, .. }));
+
+        cache.store("k".into(), json!({"n": 2}), policy(60_000, 0));
+        assert!(matches!(cache.lookup("k"), Lookup::Fresh(body, _) if body == json!({"n": 2})));
+    }
+
+    #[test]
+    fn test_expired_and_evicted() {
+        let cache = ResponseCache::new(CacheConfig::new().max_entries(2));
+        cache.store("a".into(), json!(1), policy(0, 0));
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(cache.lookup("a"), Lookup::Miss);
+
+        cache.store("a".into(), json!(1), policy(60_000, 0));
+        std::thread::sleep(Duration::from_millis(1));
+        cache.store("b".into(), json!(2), policy(60_000, 0));
+        cache.store("c".into(), json!(3), policy(60_000, 0));
+        assert_eq!(cache.lookup("a"), Lookup::Miss);
+        assert!(matches!(cache.lookup("c"), Lookup::Fresh(..)));
+    }
+}
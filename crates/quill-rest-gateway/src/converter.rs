@@ -4,13 +4,23 @@
 //! using dynamic message reflection via prost-reflect.
 
 use crate::error::{GatewayError, GatewayResult};
+use crate::mapping::{HttpMethodMapping, RouteMapping};
+use crate::schema::{message_schema, validate};
 use bytes::Bytes;
+use heck::ToSnakeCase;
 use prost::Message;
-use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, DeserializeOptions};
+use quill_client::QuillClient;
+use quill_proto::HttpRule;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor, MethodDescriptor,
+};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Method options holding HTTP annotations, in order of precedence
+const HTTP_EXTENSIONS: [&str; 2] = ["quill.http", "google.api.http"];
+
 /// Message converter for JSON ↔ Protobuf conversion
 #[derive(Clone)]
 pub struct MessageConverter {
@@ -33,33 +43,24 @@ impl MessageConverter {
         Ok(Self::new(pool))
     }
 
+    /// Create a message converter from the descriptors a server reflects
+    ///
+    /// The server must have reflection enabled; see
+    /// [`QuillClient::file_descriptor_set`].
+    pub async fn from_reflection(client: &QuillClient) -> GatewayResult<Self> {
+        let descriptor_bytes = client.file_descriptor_set(None).await.map_err(|e| {
+            GatewayError::RpcCall(format!("Failed to fetch descriptors by reflection: {}", e))
+        })?;
+        Self::from_bytes(&descriptor_bytes)
+    }
+
     /// Get message descriptor for a service method's input type
     pub fn get_input_descriptor(
         &self,
         service: &str,
         method: &str,
     ) -> GatewayResult<MessageDescriptor> {
-        // Find the service
-        let service_desc = self
-            .pool
-            .services()
-            .find(|s| s.full_name() == service || s.name() == service)
-            .ok_or_else(|| {
-                GatewayError::RpcNotFound(format!("Service '{}' not found", service))
-            })?;
-
-        // Find the method
-        let method_desc = service_desc
-            .methods()
-            .find(|m| m.name() == method)
-            .ok_or_else(|| {
-                GatewayError::RpcNotFound(format!(
-                    "Method '{}' not found in service '{}'",
-                    method, service
-                ))
-            })?;
-
-        Ok(method_desc.input())
+        Ok(self.get_method(service, method)?.input())
     }
 
     /// Get message descriptor for a service method's output type
@@ -68,6 +69,15 @@ impl MessageConverter {
         service: &str,
         method: &str,
     ) -> GatewayResult<MessageDescriptor> {
+        Ok(self.get_method(service, method)?.output())
+    }
+
+    /// Find a service method
+    ///
+    /// Methods are matched by their proto name (`SayHello`) or by the
+    /// snake_case name quill-codegen stubs register (`say_hello`).
+    fn get_method(&self, service: &str, method: &str) -> GatewayResult<MethodDescriptor> {
+        // Find the service
         let service_desc = self
             .pool
             .services()
@@ -76,9 +86,10 @@ impl MessageConverter {
                 GatewayError::RpcNotFound(format!("Service '{}' not found", service))
             })?;
 
+        // Find the method
         let method_desc = service_desc
             .methods()
-            .find(|m| m.name() == method)
+            .find(|m| method_matches(m.name(), method))
             .ok_or_else(|| {
                 GatewayError::RpcNotFound(format!(
                     "Method '{}' not found in service '{}'",
@@ -86,7 +97,51 @@ impl MessageConverter {
                 ))
             })?;
 
-        Ok(method_desc.output())
+        Ok(method_desc)
+    }
+
+    /// Routes for every RPC with an HTTP annotation
+    ///
+    /// Reads the `quill.http` method option, or `google.api.http` when the
+    /// descriptors carry it instead. Unannotated RPCs get no routes.
+    pub fn annotated_routes(&self) -> GatewayResult<Vec<RouteMapping>> {
+        let extensions: Vec<_> = HTTP_EXTENSIONS
+            .iter()
+            .filter_map(|name| self.pool.get_extension_by_name(name))
+            .collect();
+
+        let mut routes = Vec::new();
+        for service in self.pool.services() {
+            for method in service.methods() {
+                let options = method.options();
+                let Some(extension) = extensions.iter().find(|ext| options.has_extension(ext)) else {
+                    continue;
+                };
+                let rule: HttpRule = options
+                    .get_extension(extension)
+                    .as_message()
+                    .and_then(|rule| rule.transcode_to().ok())
+                    .ok_or_else(|| {
+                        GatewayError::InvalidConfig(format!(
+                            "Invalid HTTP annotation on {}",
+                            method.full_name()
+                        ))
+                    })?;
+
+                let mut route = RouteMapping::new(service.full_name(), method.name());
+                for mapping in HttpMethodMapping::from_http_rule(&rule)? {
+                    route = route.add_http_mapping(mapping);
+                }
+                route = match (method.is_client_streaming(), method.is_server_streaming()) {
+                    (false, false) => route,
+                    (false, true) => route.server_streaming(),
+                    (true, false) => route.client_streaming(),
+                    (true, true) => route.bidirectional_streaming(),
+                };
+                routes.push(route);
+            }
+        }
+        Ok(routes)
     }
 
     /// Convert JSON to Protobuf bytes
@@ -100,12 +155,29 @@ impl MessageConverter {
         self.json_to_proto_with_descriptor(&descriptor, json)
     }
 
+    /// JSON Schema of a service method's input type
+    pub fn request_schema(&self, service: &str, method: &str) -> GatewayResult<Value> {
+        Ok(message_schema(&self.get_input_descriptor(service, method)?))
+    }
+
     /// Convert JSON to Protobuf bytes using a specific descriptor
+    ///
+    /// The JSON is validated against the descriptor first; mismatches are
+    /// reported together as [`GatewayError::SchemaValidation`].
     pub fn json_to_proto_with_descriptor(
         &self,
         descriptor: &MessageDescriptor,
         json: &Value,
     ) -> GatewayResult<Bytes> {
+        let violations = validate(descriptor, json);
+        if !violations.is_empty() {
+            return Err(GatewayError::SchemaValidation {
+                message: descriptor.full_name().to_string(),
+                violations,
+                schema: message_schema(descriptor),
+            });
+        }
+
         // Use prost-reflect's deserialize support to convert JSON to DynamicMessage
         let json_string = json.to_string();
         let mut deserializer = serde_json::Deserializer::from_str(&json_string);
@@ -160,29 +232,56 @@ impl MessageConverter {
 }
 
 /// Merge path parameters into a JSON object
+///
+/// Dotted names (`book.id`) set fields of nested messages.
 pub fn merge_path_params(
     json: &mut Value,
     params: &HashMap<String, String>,
 ) -> GatewayResult<()> {
-    match json {
-        Value::Object(map) => {
-            for (key, value) in params {
-                map.insert(key.clone(), Value::String(value.clone()));
-            }
-            Ok(())
-        }
-        Value::Null => {
-            // If body is null/empty, create object from params
-            let mut map = serde_json::Map::new();
-            for (key, value) in params {
-                map.insert(key.clone(), Value::String(value.clone()));
-            }
-            *json = Value::Object(map);
-            Ok(())
-        }
-        _ => Err(GatewayError::InvalidRequestBody(
+    if json.is_null() {
+        // If body is null/empty, create object from params
+        *json = Value::Object(serde_json::Map::new());
+    }
+    let Value::Object(map) = json else {
+        return Err(GatewayError::InvalidRequestBody(
             "Request body must be a JSON object to merge path parameters".to_string(),
-        )),
+        ));
+    };
+    for (key, value) in params {
+        set_field(map, key, Value::String(value.clone()))?;
+    }
+    Ok(())
+}
+
+/// Nest a body mapped to the request field `field` (dotted for nested messages)
+pub fn nest_body(field: &str, body: Value) -> Value {
+    if body.is_null() {
+        return body;
+    }
+    field
+        .rsplit('.')
+        .fold(body, |value, name| Value::Object(serde_json::Map::from_iter([(name.to_string(), value)])))
+}
+
+/// Set the field at a dotted `path`, creating parent objects as needed
+fn set_field(
+    map: &mut serde_json::Map<String, Value>,
+    path: &str,
+    value: Value,
+) -> GatewayResult<()> {
+    let Some((parent, rest)) = path.split_once('.') else {
+        map.insert(path.to_string(), value);
+        return Ok(());
+    };
+    let child = map
+        .entry(parent.to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()));
+    match child {
+        Value::Object(child) => set_field(child, rest, value),
+        _ => Err(GatewayError::InvalidRequestBody(format!(
+            "Field '{}' must be a JSON object to set '{}'",
+            parent, rest
+        ))),
     }
 }
 
@@ -228,10 +327,24 @@ fn urlencoding_decode(s: &str) -> String {
     result
 }
 
+/// Whether `requested` names the method `proto_name`, as is or in snake_case
+fn method_matches(proto_name: &str, requested: &str) -> bool {
+    proto_name == requested || proto_name.to_snake_case() == requested
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_method_matches_proto_and_snake_case_names() {
+        assert!(method_matches("SayHello", "SayHello"));
+        assert!(method_matches("SayHello", "say_hello"));
+        assert!(method_matches("GetHTTPStatus", "get_http_status"));
+        assert!(!method_matches("SayHello", "sayhello"));
+        assert!(!method_matches("SayHello", "SayHelloStream"));
+    }
+
     #[test]
     fn test_merge_path_params_into_object() {
         let mut json = serde_json::json!({
@@ -257,6 +370,28 @@ mod tests {
         assert_eq!(json["id"], "456");
     }
 
+    #[test]
+    fn test_merge_dotted_path_params() {
+        let mut json = serde_json::json!({ "book": { "title": "Dune" } });
+        let mut params = HashMap::new();
+        params.insert("book.id".to_string(), "7".to_string());
+
+        merge_path_params(&mut json, &params).unwrap();
+
+        assert_eq!(json, serde_json::json!({ "book": { "id": "7", "title": "Dune" } }));
+    }
+
+    #[test]
+    fn test_nest_body() {
+        let body = serde_json::json!({ "title": "Dune" });
+        assert_eq!(nest_body("book", body.clone()), serde_json::json!({ "book": { "title": "Dune" } }));
+        assert_eq!(
+            nest_body("update.book", body),
+            serde_json::json!({ "update": { "book": { "title": "Dune" } } })
+        );
+        assert_eq!(nest_body("book", Value::Null), Value::Null);
+    }
+
     #[test]
     fn test_merge_path_params_into_array_fails() {
         let mut json = serde_json::json!([1, 2, 3]);
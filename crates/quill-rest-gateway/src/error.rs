@@ -1,6 +1,7 @@
 //! Error types for REST gateway
 
-use quill_core::ProblemDetails;
+use crate::schema::SchemaViolation;
+use quill_core::{ErrorCode, ProblemDetails};
 use thiserror::Error;
 
 /// REST gateway errors
@@ -41,6 +42,16 @@ pub enum GatewayError {
 
     #[error("No converter configured for JSON/Protobuf conversion")]
     NoConverter,
+
+    #[error("Invalid gateway configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Request does not match message '{message}': {} violation(s)", violations.len())]
+    SchemaValidation {
+        message: String,
+        violations: Vec<SchemaViolation>,
+        schema: serde_json::Value,
+    },
 }
 
 /// Result type for gateway operations
@@ -58,6 +69,8 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::NotFound),
+                debug: None,
             },
             GatewayError::MethodNotAllowed { method, path } => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:method-not-allowed".to_string(),
@@ -67,6 +80,8 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::Unimplemented),
+                debug: None,
             },
             GatewayError::InvalidRequestBody(msg) => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:invalid-request".to_string(),
@@ -76,6 +91,8 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::InvalidArgument),
+                debug: None,
             },
             GatewayError::InvalidPathParam(msg) => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:invalid-path-param".to_string(),
@@ -85,6 +102,8 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::InvalidArgument),
+                debug: None,
             },
             GatewayError::MissingField(field) => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:missing-field".to_string(),
@@ -94,6 +113,27 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::InvalidArgument),
+                debug: None,
+            },
+            GatewayError::SchemaValidation { message, violations, .. } => ProblemDetails {
+                type_uri: "urn:quill:rest-gateway:schema-validation".to_string(),
+                title: "Request Schema Violation".to_string(),
+                status: 400,
+                detail: Some(format!(
+                    "Request does not match message '{}': {}",
+                    message,
+                    violations
+                        .iter()
+                        .map(|v| format!("{} {}", if v.pointer.is_empty() { "/" } else { &v.pointer }, v.message))
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                )),
+                instance: None,
+                quill_proto_type: None,
+                quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::InvalidArgument),
+                debug: None,
             },
             GatewayError::RpcCall(msg) => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:rpc-error".to_string(),
@@ -103,6 +143,8 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::Internal),
+                debug: None,
             },
             GatewayError::RpcNotFound(msg) => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:rpc-not-found".to_string(),
@@ -112,6 +154,8 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::NotFound),
+                debug: None,
             },
             GatewayError::InternalError(msg) => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:internal-error".to_string(),
@@ -121,6 +165,8 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::Internal),
+                debug: None,
             },
             GatewayError::NoConverter => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:no-converter".to_string(),
@@ -130,6 +176,8 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::Internal),
+                debug: None,
             },
             _ => ProblemDetails {
                 type_uri: "urn:quill:rest-gateway:internal-error".to_string(),
@@ -139,10 +187,25 @@ impl GatewayError {
                 instance: None,
                 quill_proto_type: None,
                 quill_proto_detail_base64: None,
+                quill_code: Some(ErrorCode::Internal),
+                debug: None,
             },
         }
     }
 
+    /// Problem Details JSON body, with extension members where the error has them
+    ///
+    /// Schema violations add `errors` (JSON pointer and message per violation)
+    /// and `schema` (the JSON Schema of the expected message).
+    pub fn to_problem_json(&self) -> serde_json::Value {
+        let mut body = serde_json::to_value(self.to_problem_details()).unwrap_or_default();
+        if let (GatewayError::SchemaValidation { violations, schema, .. }, Some(object)) = (self, body.as_object_mut()) {
+            object.insert("errors".to_string(), serde_json::json!(violations));
+            object.insert("schema".to_string(), schema.clone());
+        }
+        body
+    }
+
     /// Get HTTP status code for this error
     pub fn status_code(&self) -> u16 {
         self.to_problem_details().status
@@ -176,4 +239,22 @@ mod tests {
         let err = GatewayError::InvalidRequestBody("Invalid JSON".to_string());
         assert_eq!(err.status_code(), 400);
     }
+
+    #[test]
+    fn test_schema_validation_error() {
+        let err = GatewayError::SchemaValidation {
+            message: "shop.Order".to_string(),
+            violations: vec![SchemaViolation {
+                pointer: "/items/0/quantity".to_string(),
+                message: "expected an integer".to_string(),
+            }],
+            schema: serde_json::json!({"$ref": "#/$defs/shop.Order"}),
+        };
+        assert_eq!(err.status_code(), 400);
+
+        let body = err.to_problem_json();
+        assert_eq!(body["errors"][0]["pointer"], "/items/0/quantity");
+        assert_eq!(body["schema"]["$ref"], "#/$defs/shop.Order");
+        assert!(body["detail"].as_str().unwrap().contains("/items/0/quantity expected an integer"));
+    }
 }
@@ -0,0 +1,117 @@
+//! Liveness and readiness endpoints
+//!
+//! This module provides:
+//! - `GET /healthz`: liveness; `200` while the backend answers health checks
+//! - `GET /readyz`: readiness; `200` while the backend is SERVING, else `503`
+//!
+//! Both call the backend's built-in health RPC through the gateway's default
+//! client, so the backend must have health checking enabled. `/readyz`
+//! accepts a `service` query parameter to check one service instead of the
+//! server as a whole. Bodies are JSON, e.g. `{"status":"SERVING"}`.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use quill_client::QuillClient;
+use quill_core::{QuillError, ServingStatus};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Router serving `/healthz` and `/readyz` from `client`'s backend
+pub(crate) fn health_router(client: Arc<QuillClient>) -> Router {
+    Router::new()
+        .route("/healthz", get(liveness))
+        .route("/readyz", get(readiness))
+        .with_state(client)
+}
+
+async fn liveness(State(client): State<Arc<QuillClient>>) -> Response {
+    match client.health_check(None).await {
+        // A backend that answers is alive, whether or not it is serving
+        Ok(status) => (StatusCode::OK, Json(json!({ "status": status }))).into_response(),
+        Err(e) => unreachable_response(&e),
+    }
+}
+
+async fn readiness(
+    State(client): State<Arc<QuillClient>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let service = params.get("service").map(String::as_str);
+    match client.health_check(service).await {
+        Ok(status) if status.is_serving() => (StatusCode::OK, Json(json!({ "status": status }))).into_response(),
+        Ok(status) => (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "status": status }))).into_response(),
+        Err(QuillError::ProblemDetails(pd)) if pd.status == StatusCode::NOT_FOUND.as_u16() => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "status": ServingStatus::ServiceUnknown })),
+        )
+            .into_response(),
+        Err(e) => unreachable_response(&e),
+    }
+}
+
+fn unreachable_response(error: &QuillError) -> Response {
+    let body = json!({ "status": ServingStatus::Unknown, "error": error.to_string() });
+    (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use quill_server::{HealthReporter, QuillServer};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn get_json(router: &Router, uri: &str) -> (StatusCode, Value) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_liveness_and_readiness_follow_backend() {
+        let health = HealthReporter::new();
+        let server = QuillServer::builder()
+            .enable_health(health.clone())
+            .register("echo.v1.Echo/Echo", |req: bytes::Bytes| async move { Ok(req) })
+            .build();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = server.serve_with_listener(listener).await;
+        });
+
+        let router = health_router(Arc::new(QuillClient::new(format!("http://{}", addr))));
+        assert_eq!(get_json(&router, "/readyz").await, (StatusCode::OK, json!({ "status": "SERVING" })));
+
+        health.set_serving("echo.v1.Echo", false);
+        let (status, body) = get_json(&router, "/readyz?service=echo.v1.Echo").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "NOT_SERVING");
+        let (status, _) = get_json(&router, "/readyz?service=missing.v1.Missing").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        health.set_serving("", false);
+        assert_eq!(get_json(&router, "/healthz").await, (StatusCode::OK, json!({ "status": "NOT_SERVING" })));
+        assert_eq!(get_json(&router, "/readyz").await.0, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_backend_is_not_alive() {
+        let router = health_router(Arc::new(QuillClient::new("http://127.0.0.1:1")));
+        let (status, body) = get_json(&router, "/healthz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "UNKNOWN");
+        assert!(body["error"].is_string());
+    }
+}
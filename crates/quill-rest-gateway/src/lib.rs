@@ -5,27 +5,39 @@
 //! - Clean REST URLs (e.g., `/api/v1/users/123`)
 //! - HTTP method routing (GET/POST/PUT/PATCH/DELETE)
 //! - JSON request/response conversion
-//! - OpenAPI 3.0 specification generation
+//! - OpenAPI 3.0 specification generation, with per-route examples
+//! - Mock mode serving route examples for unavailable backends
+//! - Path-prefix routing to multiple upstream Quill backends
+//! - Response caching for GET routes with stale-while-revalidate
+//! - Request validation against schemas derived from protobuf descriptors
 //! - Problem Details error responses
-//! - Authentication, CORS, and rate limiting middleware
+//! - Authentication, CORS, and rate limiting middleware, with hot reload
+//! - Liveness and readiness endpoints backed by the health RPC
 //! - Server-Sent Events (SSE) for server-streaming RPCs
 //! - NDJSON streaming for server and client streams
 
+pub mod cache;
 pub mod converter;
 pub mod error;
+pub mod health;
 pub mod mapping;
 pub mod middleware;
 pub mod openapi;
 pub mod router;
+pub mod schema;
 pub mod streaming;
+pub mod upstream;
 
+pub use cache::{CacheConfig, CACHE_STATUS_HEADER};
 pub use converter::MessageConverter;
 pub use error::{GatewayError, GatewayResult};
-pub use mapping::{HttpMethod, HttpMethodMapping, RouteMapping, StreamingMode, UrlTemplate};
+pub use mapping::{BodyMapping, HttpMethod, HttpMethodMapping, RouteExample, RouteMapping, StreamingMode, UrlTemplate};
 pub use middleware::{AuthConfig, AuthMiddleware, CorsConfig, CorsMiddleware, RateLimitConfig, RateLimitMiddleware};
 pub use openapi::OpenApiSpec;
-pub use router::{RestGateway, RestGatewayBuilder};
+pub use router::{MockMode, RestGateway, RestGatewayBuilder, MOCK_HEADER};
+pub use schema::SchemaViolation;
 pub use streaming::{
     ChunkedRequestReader, ContentType, MultipartChunk, NdjsonReader, NdjsonStream, SseEvent,
     SseStream, StreamingConfig, StreamingFormat, StreamingResponse,
 };
+pub use upstream::Upstream;
@@ -2,7 +2,10 @@
 
 use crate::error::{GatewayError, GatewayResult};
 use crate::streaming::StreamingConfig;
+use quill_proto::{options, HttpRule};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// HTTP methods supported by the gateway
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -127,6 +130,48 @@ impl UrlTemplate {
         &self.template
     }
 
+    /// Path in axum's route syntax, with `:name` for each parameter
+    pub fn axum_path(&self) -> String {
+        let path: String = self
+            .segments
+            .iter()
+            .map(|seg| match seg {
+                UrlSegment::Static(part) => format!("/{}", part),
+                UrlSegment::Parameter(name) => format!("/:{}", name),
+            })
+            .collect();
+        if path.is_empty() {
+            "/".to_string()
+        } else {
+            path
+        }
+    }
+
+    /// Path in axum's route syntax with positional parameter names
+    ///
+    /// Templates that differ only in parameter names (`/books/{id}` and
+    /// `/books/{book.id}`) share a route; parameters are then read with
+    /// [`match_path`](Self::match_path).
+    pub fn route_path(&self) -> String {
+        let mut index = 0;
+        let path: String = self
+            .segments
+            .iter()
+            .map(|seg| match seg {
+                UrlSegment::Static(part) => format!("/{}", part),
+                UrlSegment::Parameter(_) => {
+                    index += 1;
+                    format!("/:p{}", index)
+                }
+            })
+            .collect();
+        if path.is_empty() {
+            "/".to_string()
+        } else {
+            path
+        }
+    }
+
     /// Get parameter names from the template
     pub fn parameter_names(&self) -> Vec<String> {
         self.segments
@@ -144,6 +189,29 @@ impl UrlTemplate {
     }
 }
 
+/// Part of the request message carried by the HTTP body
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum BodyMapping {
+    /// The body is the whole request message (query parameters are merged for GET)
+    #[default]
+    Request,
+    /// The body is the named request field; other fields come from the query string
+    Field(String),
+    /// No body; request fields come from the query string
+    Query,
+}
+
+impl BodyMapping {
+    /// Parse the `body` of an HTTP annotation (`*`, a field name, or empty)
+    pub fn from_annotation(body: &str) -> Self {
+        match body {
+            "" => BodyMapping::Query,
+            "*" => BodyMapping::Request,
+            field => BodyMapping::Field(field.to_string()),
+        }
+    }
+}
+
 /// HTTP method mapping for a specific RPC method
 #[derive(Debug, Clone)]
 pub struct HttpMethodMapping {
@@ -151,6 +219,8 @@ pub struct HttpMethodMapping {
     pub http_method: HttpMethod,
     /// URL template with path parameters
     pub url_template: UrlTemplate,
+    /// Part of the request carried by the body
+    pub body: BodyMapping,
 }
 
 impl HttpMethodMapping {
@@ -159,8 +229,44 @@ impl HttpMethodMapping {
         Ok(Self {
             http_method,
             url_template: UrlTemplate::new(url_template)?,
+            body: BodyMapping::default(),
         })
     }
+
+    /// Create the mappings of an HTTP annotation and its additional bindings
+    pub fn from_http_rule(rule: &HttpRule) -> GatewayResult<Vec<Self>> {
+        let mut mappings = Vec::new();
+        if let Some((method, path)) = options::http_pattern(rule) {
+            let http_method = HttpMethod::from_str(method).ok_or_else(|| {
+                GatewayError::InvalidConfig(format!("Unsupported HTTP method '{}' in annotation", method))
+            })?;
+            mappings.push(Self::new(http_method, path)?.with_body(BodyMapping::from_annotation(&rule.body)));
+        }
+        for binding in &rule.additional_bindings {
+            mappings.extend(Self::from_http_rule(binding)?);
+        }
+        Ok(mappings)
+    }
+
+    /// Set the part of the request carried by the body
+    pub fn with_body(mut self, body: BodyMapping) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// Whether requests to this mapping carry a JSON body
+    pub fn has_body(&self) -> bool {
+        match self.body {
+            BodyMapping::Request => !matches!(self.http_method, HttpMethod::Get | HttpMethod::Delete),
+            BodyMapping::Field(_) => true,
+            BodyMapping::Query => false,
+        }
+    }
+
+    /// Whether request fields are also read from the query string
+    pub fn reads_query(&self) -> bool {
+        self.body != BodyMapping::Request || self.http_method == HttpMethod::Get
+    }
 }
 
 /// Streaming mode for RPC methods
@@ -177,6 +283,46 @@ pub enum StreamingMode {
     Bidirectional,
 }
 
+/// Example request/response payloads for a route
+///
+/// Examples are published in the OpenAPI spec and served by the gateway's
+/// mock mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteExample {
+    /// Example name, unique per route
+    pub name: String,
+    /// Short description of the example
+    pub summary: Option<String>,
+    /// Example JSON request body
+    pub request: Option<Value>,
+    /// Example JSON response body
+    pub response: Value,
+}
+
+impl RouteExample {
+    /// Create an example with the given response body
+    pub fn new(name: &str, response: Value) -> Self {
+        Self {
+            name: name.to_string(),
+            summary: None,
+            request: None,
+            response,
+        }
+    }
+
+    /// Set the example request body
+    pub fn with_request(mut self, request: Value) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    /// Set the example summary
+    pub fn with_summary(mut self, summary: &str) -> Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+}
+
 /// Route mapping from REST to RPC
 #[derive(Debug, Clone)]
 pub struct RouteMapping {
@@ -190,6 +336,12 @@ pub struct RouteMapping {
     pub streaming_mode: StreamingMode,
     /// Streaming configuration (for SSE, NDJSON, etc.)
     pub streaming_config: Option<StreamingConfig>,
+    /// Example payloads (published in OpenAPI, served in mock mode)
+    pub examples: Vec<RouteExample>,
+    /// Response cache TTL overriding the gateway default (zero disables caching)
+    pub cache_ttl: Option<Duration>,
+    /// Stale-while-revalidate window overriding the gateway default
+    pub stale_while_revalidate: Option<Duration>,
 }
 
 impl RouteMapping {
@@ -201,6 +353,9 @@ impl RouteMapping {
             http_mappings: Vec::new(),
             streaming_mode: StreamingMode::Unary,
             streaming_config: None,
+            examples: Vec::new(),
+            cache_ttl: None,
+            stale_while_revalidate: None,
         }
     }
 
@@ -210,12 +365,32 @@ impl RouteMapping {
         Ok(self)
     }
 
+    /// Add a prepared HTTP method mapping
+    pub fn add_http_mapping(mut self, mapping: HttpMethodMapping) -> Self {
+        self.http_mappings.push(mapping);
+        self
+    }
+
     /// Set streaming mode for this route
     pub fn with_streaming_mode(mut self, mode: StreamingMode) -> Self {
         self.streaming_mode = mode;
         self
     }
 
+    /// Cache GET responses for `cache_ttl_ms` (mirrors the `quill.rpc` option)
+    ///
+    /// Zero disables caching for this route even if the gateway caches by default.
+    pub fn with_cache_ttl_ms(mut self, cache_ttl_ms: u64) -> Self {
+        self.cache_ttl = Some(Duration::from_millis(cache_ttl_ms));
+        self
+    }
+
+    /// Serve stale responses for `window` past the TTL while refreshing them
+    pub fn with_stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.stale_while_revalidate = Some(window);
+        self
+    }
+
     /// Set server-side streaming with default SSE config
     pub fn server_streaming(mut self) -> Self {
         self.streaming_mode = StreamingMode::ServerStreaming;
@@ -243,6 +418,20 @@ impl RouteMapping {
         self
     }
 
+    /// Add an example payload
+    pub fn with_example(mut self, example: RouteExample) -> Self {
+        self.examples.push(example);
+        self
+    }
+
+    /// Find an example by name, or the first example if `name` is `None`
+    pub fn example(&self, name: Option<&str>) -> Option<&RouteExample> {
+        match name {
+            Some(name) => self.examples.iter().find(|e| e.name == name),
+            None => self.examples.first(),
+        }
+    }
+
     /// Check if this is a streaming route
     pub fn is_streaming(&self) -> bool {
         self.streaming_mode != StreamingMode::Unary
@@ -316,6 +505,72 @@ mod tests {
         assert_eq!(names, vec!["user_id", "post_id"]);
     }
 
+    #[test]
+    fn test_url_template_axum_path() {
+        let template = UrlTemplate::new("/api/v1/users/{user_id}/posts/{post_id}").unwrap();
+        assert_eq!(template.axum_path(), "/api/v1/users/:user_id/posts/:post_id");
+        assert_eq!(UrlTemplate::new("/").unwrap().axum_path(), "/");
+    }
+
+    #[test]
+    fn test_url_template_route_path() {
+        let by_id = UrlTemplate::new("/v1/books/{id}").unwrap();
+        let by_book_id = UrlTemplate::new("/v1/books/{book.id}").unwrap();
+        assert_eq!(by_id.route_path(), "/v1/books/:p1");
+        assert_eq!(by_id.route_path(), by_book_id.route_path());
+        assert_eq!(UrlTemplate::new("/").unwrap().route_path(), "/");
+    }
+
+    #[test]
+    fn test_mappings_from_http_rule() {
+        use quill_proto::http_rule::Pattern;
+
+        let binding = HttpRule {
+            pattern: Some(Pattern::Put("/v1/books/{book.id}".to_string())),
+            body: "book".to_string(),
+            ..Default::default()
+        };
+        let rule = HttpRule {
+            pattern: Some(Pattern::Patch("/v1/books/{book.id}".to_string())),
+            body: "*".to_string(),
+            additional_bindings: vec![binding],
+        };
+
+        let mappings = HttpMethodMapping::from_http_rule(&rule).unwrap();
+        assert_eq!(mappings.len(), 2);
+        assert_eq!(mappings[0].http_method, HttpMethod::Patch);
+        assert_eq!(mappings[0].body, BodyMapping::Request);
+        assert!(!mappings[0].reads_query());
+        assert_eq!(mappings[1].http_method, HttpMethod::Put);
+        assert_eq!(mappings[1].body, BodyMapping::Field("book".to_string()));
+        assert_eq!(mappings[1].url_template.parameter_names(), vec!["book.id"]);
+
+        let rule = HttpRule {
+            pattern: Some(Pattern::Get("/v1/books".to_string())),
+            ..Default::default()
+        };
+        let mappings = HttpMethodMapping::from_http_rule(&rule).unwrap();
+        assert!(!mappings[0].has_body());
+        assert!(mappings[0].reads_query());
+    }
+
+    #[test]
+    fn test_http_rule_with_unsupported_method() {
+        use quill_proto::{http_rule::Pattern, CustomHttpPattern};
+
+        let rule = HttpRule {
+            pattern: Some(Pattern::Custom(CustomHttpPattern {
+                kind: "HEAD".to_string(),
+                path: "/v1/books".to_string(),
+            })),
+            ..Default::default()
+        };
+        assert!(matches!(
+            HttpMethodMapping::from_http_rule(&rule),
+            Err(GatewayError::InvalidConfig(_))
+        ));
+    }
+
     #[test]
     fn test_route_mapping() {
         let mapping = RouteMapping::new("users.v1.UserService", "GetUser")
@@ -332,6 +587,24 @@ mod tests {
         assert!(mapping.find_mapping(HttpMethod::Post, "/api/v1/users/123").is_none());
     }
 
+    #[test]
+    fn test_route_examples() {
+        let mapping = RouteMapping::new("users.v1.UserService", "GetUser")
+            .with_example(RouteExample::new("alice", serde_json::json!({"id": "1", "name": "Alice"})))
+            .with_example(
+                RouteExample::new("bob", serde_json::json!({"id": "2", "name": "Bob"}))
+                    .with_request(serde_json::json!({"id": "2"}))
+                    .with_summary("A second user"),
+            );
+
+        assert_eq!(mapping.example(None).unwrap().name, "alice");
+        let bob = mapping.example(Some("bob")).unwrap();
+        assert_eq!(bob.request, Some(serde_json::json!({"id": "2"})));
+        assert_eq!(bob.summary.as_deref(), Some("A second user"));
+        assert!(mapping.example(Some("carol")).is_none());
+        assert!(RouteMapping::new("a.S", "M").example(None).is_none());
+    }
+
     #[test]
     fn test_streaming_mode_default() {
         let mapping = RouteMapping::new("events.v1.EventService", "ListEvents");
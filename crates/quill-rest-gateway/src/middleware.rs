@@ -3,6 +3,7 @@
 pub mod auth;
 pub mod cors;
 pub mod ratelimit;
+pub(crate) mod reload;
 
 pub use auth::{AuthMiddleware, AuthScheme, AuthConfig};
 pub use cors::{CorsMiddleware, CorsConfig};
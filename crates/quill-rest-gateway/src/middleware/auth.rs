@@ -8,7 +8,7 @@ use axum::{
     Json,
 };
 use base64::Engine;
-use quill_core::ProblemDetails;
+use quill_core::{ErrorCode, ProblemDetails};
 use std::sync::Arc;
 
 /// Authentication scheme
@@ -162,22 +162,29 @@ impl AuthMiddleware {
         next: Next,
     ) -> Result<Response, Response> {
         let middleware = AuthMiddleware::new((*config).clone());
+        middleware.authorize(&request)?;
+        Ok(next.run(request).await)
+    }
 
-        if !middleware.validate(request.headers()) {
-            let problem = ProblemDetails {
-                type_uri: "urn:quill:rest-gateway:unauthorized".to_string(),
-                title: "Unauthorized".to_string(),
-                status: 401,
-                detail: Some("Authentication required".to_string()),
-                instance: None,
-                quill_proto_type: None,
-                quill_proto_detail_base64: None,
-            };
-
-            return Err((StatusCode::UNAUTHORIZED, Json(problem)).into_response());
+    /// Reject `request` with `401 Unauthorized` unless it authenticates
+    pub(crate) fn authorize(&self, request: &Request) -> Result<(), Response> {
+        if self.validate(request.headers()) {
+            return Ok(());
         }
 
-        Ok(next.run(request).await)
+        let problem = ProblemDetails {
+            type_uri: "urn:quill:rest-gateway:unauthorized".to_string(),
+            title: "Unauthorized".to_string(),
+            status: 401,
+            detail: Some("Authentication required".to_string()),
+            instance: None,
+            quill_proto_type: None,
+            quill_proto_detail_base64: None,
+            quill_code: Some(ErrorCode::Unauthenticated),
+            debug: None,
+        };
+
+        Err((StatusCode::UNAUTHORIZED, Json(problem)).into_response())
     }
 }
 
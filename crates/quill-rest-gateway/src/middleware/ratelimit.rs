@@ -7,7 +7,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use quill_core::ProblemDetails;
+use quill_core::{ErrorCode, ProblemDetails};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
@@ -147,15 +147,21 @@ impl RateLimitMiddleware {
         request: Request,
         next: Next,
     ) -> Result<Response, Response> {
+        middleware.admit(&request)?;
+        Ok(next.run(request).await)
+    }
+
+    /// Reject `request` with `429 Too Many Requests` once its key is over the limit
+    pub(crate) fn admit(&self, request: &Request) -> Result<(), Response> {
         // Extract key (default to "global" if no key function)
-        let key = if let Some(key_fn) = &middleware.config.key_fn {
-            key_fn(&request).unwrap_or_else(|| "anonymous".to_string())
+        let key = if let Some(key_fn) = &self.config.key_fn {
+            key_fn(request).unwrap_or_else(|| "anonymous".to_string())
         } else {
             "global".to_string()
         };
 
-        match middleware.check_limit(&key) {
-            Ok(()) => Ok(next.run(request).await),
+        match self.check_limit(&key) {
+            Ok(()) => Ok(()),
             Err((remaining, retry_after)) => {
                 let problem = ProblemDetails {
                     type_uri: "urn:quill:rest-gateway:rate-limit-exceeded".to_string(),
@@ -168,6 +174,8 @@ impl RateLimitMiddleware {
                     instance: None,
                     quill_proto_type: None,
                     quill_proto_detail_base64: None,
+                    quill_code: Some(ErrorCode::ResourceExhausted),
+                    debug: None,
                 };
 
                 let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(problem)).into_response();
@@ -180,7 +188,7 @@ impl RateLimitMiddleware {
                 // Add X-RateLimit headers
                 response.headers_mut().insert(
                     "x-ratelimit-limit",
-                    middleware.config.max_requests.into(),
+                    self.config.max_requests.into(),
                 );
                 if let Ok(value) = (remaining as u32).to_string().parse() {
                     response.headers_mut().insert("x-ratelimit-remaining", value);
@@ -0,0 +1,98 @@
+//! Middleware configuration swapped while the gateway runs
+
+use super::{AuthConfig, AuthMiddleware, RateLimitConfig, RateLimitMiddleware};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use quill_transport::ConfigSource;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+/// A middleware replaced by a background task as its configuration changes
+pub(crate) struct Reloadable<T> {
+    current: RwLock<Option<Arc<T>>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<T: Send + Sync + 'static> Reloadable<T> {
+    /// Build from `source`'s current configuration and follow its changes
+    ///
+    /// The slot stays empty while the initial configuration fails to load.
+    pub(crate) fn follow<C>(name: &'static str, mut source: ConfigSource<C>, build: fn(C) -> T) -> Arc<Self>
+    where
+        C: Clone + Send + Sync + 'static,
+    {
+        let initial = match source.current() {
+            Ok(config) => Some(Arc::new(build(config))),
+            Err(e) => {
+                error!("Failed to load {} configuration: {}", name, e);
+                None
+            }
+        };
+        let reloadable = Arc::new(Self {
+            current: RwLock::new(initial),
+            task: Mutex::new(None),
+        });
+        if source.is_fixed() {
+            return reloadable;
+        }
+
+        // The task holds a weak reference so dropping the gateway stops it
+        let target = Arc::downgrade(&reloadable);
+        let task = tokio::spawn(async move {
+            while let Some(config) = source.next().await {
+                let Some(target) = target.upgrade() else { break };
+                *target.current.write().unwrap() = Some(Arc::new(build(config)));
+                info!("Reloaded {} configuration", name);
+            }
+        });
+        *reloadable.task.lock().unwrap() = Some(task);
+        reloadable
+    }
+
+    fn get(&self) -> Option<Arc<T>> {
+        self.current.read().unwrap().clone()
+    }
+}
+
+impl<T> Drop for Reloadable<T> {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.get_mut().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+pub(crate) fn auth(source: ConfigSource<AuthConfig>) -> Arc<Reloadable<AuthMiddleware>> {
+    Reloadable::follow("auth", source, AuthMiddleware::new)
+}
+
+pub(crate) fn rate_limit(source: ConfigSource<RateLimitConfig>) -> Arc<Reloadable<RateLimitMiddleware>> {
+    Reloadable::follow("rate limit", source, RateLimitMiddleware::new)
+}
+
+/// Authenticate with the current configuration, rejecting all requests until one loads
+pub(crate) async fn authenticate(
+    State(auth): State<Arc<Reloadable<AuthMiddleware>>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    let middleware = auth.get().unwrap_or_else(|| Arc::new(AuthMiddleware::new(AuthConfig::new())));
+    middleware.authorize(&request)?;
+    Ok(next.run(request).await)
+}
+
+/// Rate limit with the current configuration, if one has loaded
+pub(crate) async fn limit_rate(
+    State(limiter): State<Arc<Reloadable<RateLimitMiddleware>>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    if let Some(middleware) = limiter.get() {
+        middleware.admit(&request)?;
+    }
+    Ok(next.run(request).await)
+}
@@ -1,6 +1,6 @@
 //! OpenAPI 3.0 specification generation
 
-use crate::mapping::{HttpMethod, RouteMapping};
+use crate::mapping::{HttpMethod, RouteExample, RouteMapping};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -90,6 +90,15 @@ pub struct OpenApiResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenApiMediaType {
     pub schema: OpenApiSchema,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub examples: HashMap<String, OpenApiExample>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenApiExample {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub value: serde_json::Value,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -187,7 +196,7 @@ impl OpenApiSpecBuilder {
                             },
                         })
                         .collect(),
-                    request_body: if http_mapping.http_method != HttpMethod::Get && http_mapping.http_method != HttpMethod::Delete {
+                    request_body: if http_mapping.has_body() {
                         Some(OpenApiRequestBody {
                             description: Some("Request body".to_string()),
                             required: true,
@@ -201,6 +210,7 @@ impl OpenApiSpecBuilder {
                                             format: None,
                                             description: None,
                                         },
+                                        examples: examples(route, |e| e.request.as_ref()),
                                     },
                                 );
                                 content
@@ -225,6 +235,7 @@ impl OpenApiSpecBuilder {
                                                 format: None,
                                                 description: None,
                                             },
+                                            examples: examples(route, |e| Some(&e.response)),
                                         },
                                     );
                                     content
@@ -245,6 +256,7 @@ impl OpenApiSpecBuilder {
                                                 format: None,
                                                 description: Some("RFC 7807 Problem Details".to_string()),
                                             },
+                                            examples: HashMap::new(),
                                         },
                                     );
                                     content
@@ -280,6 +292,28 @@ impl OpenApiSpecBuilder {
     }
 }
 
+/// Collect the named example payloads of a route
+fn examples(
+    route: &RouteMapping,
+    payload: impl Fn(&RouteExample) -> Option<&serde_json::Value>,
+) -> HashMap<String, OpenApiExample> {
+    route
+        .examples
+        .iter()
+        .filter_map(|example| {
+            payload(example).map(|value| {
+                (
+                    example.name.clone(),
+                    OpenApiExample {
+                        summary: example.summary.clone(),
+                        value: value.clone(),
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
 impl OpenApiSpec {
     /// Convert to JSON string
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
@@ -333,4 +367,31 @@ mod tests {
         assert!(json_str.contains("\"openapi\": \"3.0.0\""));
         assert!(json_str.contains("/api/v1/users/{id}"));
     }
+
+    #[test]
+    fn test_openapi_route_examples() {
+        let route = RouteMapping::new("users.v1.UserService", "CreateUser")
+            .add_mapping(HttpMethod::Post, "/api/v1/users")
+            .unwrap()
+            .with_example(
+                RouteExample::new("alice", serde_json::json!({"id": "1", "name": "Alice"}))
+                    .with_request(serde_json::json!({"name": "Alice"}))
+                    .with_summary("Create Alice"),
+            )
+            .with_example(RouteExample::new("empty", serde_json::json!({})));
+
+        let spec = OpenApiSpecBuilder::new("My API", "1.0.0").routes(vec![route]).build();
+        let operation = spec.paths["/api/v1/users"].post.as_ref().unwrap();
+
+        let request = &operation.request_body.as_ref().unwrap().content["application/json"];
+        assert_eq!(request.examples.len(), 1);
+        assert_eq!(request.examples["alice"].value, serde_json::json!({"name": "Alice"}));
+
+        let response = &operation.responses["200"].content.as_ref().unwrap()["application/json"];
+        assert_eq!(response.examples.len(), 2);
+        assert_eq!(response.examples["alice"].summary.as_deref(), Some("Create Alice"));
+
+        let json = spec.to_json().unwrap();
+        assert!(json.contains("\"examples\""));
+    }
 }
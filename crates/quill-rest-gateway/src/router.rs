@@ -1,30 +1,61 @@
 //! REST gateway router
 
-use crate::converter::{merge_path_params, parse_query_params, MessageConverter};
+use crate::cache::{CacheConfig, CachePolicy, Lookup, RequestDirectives, ResponseCache, CACHE_STATUS_HEADER};
+use crate::converter::{merge_path_params, nest_body, parse_query_params, MessageConverter};
 use crate::error::{GatewayError, GatewayResult};
-use crate::mapping::{HttpMethod, RouteMapping};
+use crate::health::health_router;
+use crate::mapping::{BodyMapping, HttpMethod, HttpMethodMapping, RouteExample, RouteMapping, StreamingMode};
+use crate::middleware::{reload, AuthConfig, RateLimitConfig};
 use crate::openapi::{OpenApiSpec, OpenApiSpecBuilder};
+use crate::streaming::{SseEvent, StreamingFormat, StreamingResponse};
+use crate::upstream::{Upstream, UpstreamTable};
 use axum::{
     body::Body,
-    extract::{Path, State},
+    extract::State,
     http::{Request, StatusCode},
     response::{IntoResponse, Response},
     routing::{get, MethodRouter},
     Json, Router,
 };
+use bytes::Bytes;
+use futures_util::StreamExt;
 use http_body_util::BodyExt;
-use quill_client::QuillClient;
+use quill_client::{QuillClient, RequestOptions, UsageStream};
+use quill_core::{Metadata, QuillError};
+use quill_transport::ConfigSource;
 use serde_json::Value;
-use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Header selecting a named example in mock mode (`Prefer: example=<name>`)
+pub const PREFER_HEADER: &str = "prefer";
+
+/// Response header naming the example served in place of a backend response
+pub const MOCK_HEADER: &str = "quill-mock";
+
+/// When the gateway serves route examples instead of calling the backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MockMode {
+    /// Always call the backend
+    #[default]
+    Off,
+    /// Serve examples when the backend is unavailable
+    Fallback,
+    /// Serve examples without calling the backend
+    Always,
+}
 
 /// REST gateway state
 #[derive(Clone)]
 struct GatewayState {
     client: Arc<QuillClient>,
+    upstreams: Arc<UpstreamTable>,
     routes: Arc<Vec<RouteMapping>>,
     converter: Option<Arc<MessageConverter>>,
+    mock_mode: MockMode,
+    cache: Option<Arc<ResponseCache>>,
+    forward_metadata: bool,
 }
 
 /// REST gateway for Quill RPC services
@@ -53,12 +84,19 @@ impl RestGateway {
 /// REST gateway builder
 pub struct RestGatewayBuilder {
     client: Arc<QuillClient>,
+    upstreams: Vec<(String, Upstream)>,
     routes: Vec<RouteMapping>,
     title: String,
     version: String,
     description: Option<String>,
     base_path: String,
     converter: Option<MessageConverter>,
+    mock_mode: MockMode,
+    cache: Option<CacheConfig>,
+    forward_metadata: bool,
+    health_endpoints: bool,
+    auth: Option<ConfigSource<AuthConfig>>,
+    rate_limit: Option<ConfigSource<RateLimitConfig>>,
 }
 
 impl RestGatewayBuilder {
@@ -66,15 +104,33 @@ impl RestGatewayBuilder {
     pub fn new(client: QuillClient) -> Self {
         Self {
             client: Arc::new(client),
+            upstreams: Vec::new(),
             routes: Vec::new(),
             title: "Quill REST API".to_string(),
             version: "1.0.0".to_string(),
             description: None,
             base_path: "/api".to_string(),
             converter: None,
+            mock_mode: MockMode::Off,
+            cache: None,
+            forward_metadata: true,
+            health_endpoints: false,
+            auth: None,
+            rate_limit: None,
         }
     }
 
+    /// Create a builder with routes generated from HTTP annotations
+    ///
+    /// The descriptors set the message converter, and every RPC annotated
+    /// with `quill.http` (or `google.api.http`) gets a route for each of its
+    /// bindings. See [`MessageConverter::annotated_routes`].
+    pub fn from_descriptors(client: QuillClient, descriptor_bytes: &[u8]) -> GatewayResult<Self> {
+        let converter = MessageConverter::from_bytes(descriptor_bytes)?;
+        let routes = converter.annotated_routes()?;
+        Ok(Self::new(client).with_converter(converter).routes(routes))
+    }
+
     /// Set message converter from descriptor bytes
     ///
     /// This enables JSON ↔ Protobuf conversion for RPC calls.
@@ -114,6 +170,84 @@ impl RestGatewayBuilder {
         self
     }
 
+    /// Serve route examples instead of backend responses
+    ///
+    /// Only routes with examples are mocked; a request picks an example with
+    /// `Prefer: example=<name>` and otherwise gets the route's first one.
+    pub fn mock_mode(mut self, mode: MockMode) -> Self {
+        self.mock_mode = mode;
+        self
+    }
+
+    /// Cache responses of GET routes
+    ///
+    /// Routes are cached for their own `cache_ttl`, or the configured default.
+    pub fn response_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Forward request headers to the backend as call metadata (default: on)
+    ///
+    /// Protocol headers are never forwarded. Cached responses are shared by
+    /// callers whose metadata differs in anything but `Authorization`.
+    pub fn forward_metadata(mut self, enabled: bool) -> Self {
+        self.forward_metadata = enabled;
+        self
+    }
+
+    /// Serve `/healthz` and `/readyz` from the backend's health RPC
+    ///
+    /// The backend must have health checking enabled. See [`crate::health`].
+    pub fn health_endpoints(mut self, enabled: bool) -> Self {
+        self.health_endpoints = enabled;
+        self
+    }
+
+    /// Require authentication on the API routes
+    ///
+    /// `/openapi.json` and the health endpoints stay public.
+    pub fn auth(self, config: AuthConfig) -> Self {
+        self.auth_source(ConfigSource::fixed(config))
+    }
+
+    /// Require authentication, swapping in credentials as `source` changes
+    ///
+    /// Use this to rotate API keys or tokens without a restart. Requests
+    /// are rejected while the initial configuration fails to load; later
+    /// failures keep the current credentials.
+    pub fn auth_source(mut self, source: ConfigSource<AuthConfig>) -> Self {
+        self.auth = Some(source);
+        self
+    }
+
+    /// Rate limit the API routes
+    ///
+    /// Requests are limited before authentication, so rejected credentials
+    /// count against the limit too.
+    pub fn rate_limit(self, config: RateLimitConfig) -> Self {
+        self.rate_limit_source(ConfigSource::fixed(config))
+    }
+
+    /// Rate limit, swapping in limits as `source` changes
+    ///
+    /// Each new configuration starts with full buckets. Requests are not
+    /// limited while the initial configuration fails to load.
+    pub fn rate_limit_source(mut self, source: ConfigSource<RateLimitConfig>) -> Self {
+        self.rate_limit = Some(source);
+        self
+    }
+
+    /// Send requests under `prefix` (e.g. `/v1/orders`) to `upstream`
+    ///
+    /// Prefixes are relative to the base path and match whole path segments;
+    /// the longest matching prefix wins. Requests matching no prefix use the
+    /// gateway's default client.
+    pub fn upstream(mut self, prefix: &str, upstream: Upstream) -> Self {
+        self.upstreams.push((prefix.to_string(), upstream));
+        self
+    }
+
     /// Add a route mapping
     pub fn route(mut self, route: RouteMapping) -> Self {
         self.routes.push(route);
@@ -127,11 +261,20 @@ impl RestGatewayBuilder {
     }
 
     /// Build the REST gateway
+    ///
+    /// # Panics
+    ///
+    /// Panics outside a Tokio runtime if an auth or rate limit source can
+    /// change, since reloading runs in a background task.
     pub fn build(self) -> RestGateway {
         let state = GatewayState {
             client: self.client.clone(),
+            upstreams: Arc::new(self.upstream_table()),
             routes: Arc::new(self.routes.clone()),
             converter: self.converter.clone().map(Arc::new),
+            mock_mode: self.mock_mode,
+            cache: self.cache.clone().map(|config| Arc::new(ResponseCache::new(config))),
+            forward_metadata: self.forward_metadata,
         };
 
         // Build router with all routes
@@ -147,15 +290,37 @@ impl RestGatewayBuilder {
 
         router = router.merge(openapi_router);
 
+        if self.health_endpoints {
+            router = router.merge(health_router(self.client.clone()));
+        }
+
         // Add routes
+        let mut api = Router::new();
         for route in &self.routes {
             for http_mapping in &route.http_mappings {
-                let path_template = format!("{}{}", self.base_path, http_mapping.url_template.template());
+                let path_template = format!("{}{}", self.base_path, http_mapping.url_template.route_path());
                 let method_router = create_method_router(http_mapping.http_method, state.clone());
 
-                router = router.route(&path_template, method_router);
+                api = api.route(&path_template, method_router);
+            }
+        }
+
+        // Layers added last run first: rate limiting, then authentication
+        if api.has_routes() {
+            if let Some(source) = self.auth {
+                api = api.route_layer(axum::middleware::from_fn_with_state(
+                    reload::auth(source),
+                    reload::authenticate,
+                ));
+            }
+            if let Some(source) = self.rate_limit {
+                api = api.route_layer(axum::middleware::from_fn_with_state(
+                    reload::rate_limit(source),
+                    reload::limit_rate,
+                ));
             }
         }
+        router = router.merge(api);
 
         RestGateway {
             router,
@@ -163,6 +328,14 @@ impl RestGatewayBuilder {
         }
     }
 
+    fn upstream_table(&self) -> UpstreamTable {
+        let mut table = UpstreamTable::default();
+        for (prefix, upstream) in &self.upstreams {
+            table.insert(&format!("{}{}", self.base_path, prefix), upstream.clone());
+        }
+        table
+    }
+
     fn build_openapi_spec(&self) -> OpenApiSpec {
         let mut builder = OpenApiSpecBuilder::new(&self.title, &self.version);
 
@@ -188,72 +361,81 @@ fn create_method_router(http_method: HttpMethod, state: GatewayState) -> MethodR
 /// Handle GET requests
 async fn handle_get(
     State(state): State<GatewayState>,
-    Path(params): Path<HashMap<String, String>>,
     req: Request<Body>,
 ) -> Result<Response, GatewayResponse> {
-    handle_request(state, HttpMethod::Get, params, req).await
+    handle_request(state, HttpMethod::Get, req).await
 }
 
 /// Handle POST requests
 async fn handle_post(
     State(state): State<GatewayState>,
-    Path(params): Path<HashMap<String, String>>,
     req: Request<Body>,
 ) -> Result<Response, GatewayResponse> {
-    handle_request(state, HttpMethod::Post, params, req).await
+    handle_request(state, HttpMethod::Post, req).await
 }
 
 /// Handle PUT requests
 async fn handle_put(
     State(state): State<GatewayState>,
-    Path(params): Path<HashMap<String, String>>,
     req: Request<Body>,
 ) -> Result<Response, GatewayResponse> {
-    handle_request(state, HttpMethod::Put, params, req).await
+    handle_request(state, HttpMethod::Put, req).await
 }
 
 /// Handle PATCH requests
 async fn handle_patch(
     State(state): State<GatewayState>,
-    Path(params): Path<HashMap<String, String>>,
     req: Request<Body>,
 ) -> Result<Response, GatewayResponse> {
-    handle_request(state, HttpMethod::Patch, params, req).await
+    handle_request(state, HttpMethod::Patch, req).await
 }
 
 /// Handle DELETE requests
 async fn handle_delete(
     State(state): State<GatewayState>,
-    Path(params): Path<HashMap<String, String>>,
     req: Request<Body>,
 ) -> Result<Response, GatewayResponse> {
-    handle_request(state, HttpMethod::Delete, params, req).await
+    handle_request(state, HttpMethod::Delete, req).await
 }
 
 /// Handle request and route to RPC
 async fn handle_request(
     state: GatewayState,
     http_method: HttpMethod,
-    params: HashMap<String, String>,
     req: Request<Body>,
 ) -> Result<Response, GatewayResponse> {
     let path = req.uri().path().to_string();
     let query = req.uri().query().map(|s| s.to_string());
 
+    // Find matching route
+    let (route, mapping) = find_matching_route(&state.routes, &path, http_method)?;
+    let params = mapping.url_template.match_path(&path).unwrap_or_default();
+
     debug!(
         "Handling {} request to {} with params: {:?}",
         http_method.as_str(),
         path,
         params
     );
-
-    // Find matching route
-    let route = find_matching_route(&state.routes, &path, http_method)?;
     let service = &route.service;
     let method = &route.method;
 
     info!("Routing to {}/{}", service, method);
 
+    let example_name = preferred_example(req.headers());
+    let format = streaming_format(route, req.headers());
+    let cache_directives = RequestDirectives::from_headers(req.headers());
+    let cache_key = ResponseCache::key(&path, query.as_deref(), req.headers());
+    let metadata = match state.forward_metadata {
+        true => Metadata::from_headers(req.headers()),
+        false => Metadata::new(),
+    };
+    if state.mock_mode == MockMode::Always {
+        if let Some(example) = route.example(example_name.as_deref()) {
+            return Ok(mock_response(example));
+        }
+    }
+
     // Get the converter (required for JSON ↔ Protobuf conversion)
     let converter = state
         .converter
@@ -268,7 +450,7 @@ async fn handle_request(
         .map_err(|e| GatewayError::InvalidRequestBody(format!("Failed to read body: {}", e)))?
         .to_bytes();
 
-    let mut json_body: Value = if body_bytes.is_empty() {
+    let mut json_body: Value = if body_bytes.is_empty() || mapping.body == BodyMapping::Query {
         Value::Null
     } else {
         serde_json::from_slice(&body_bytes).map_err(|e| {
@@ -276,11 +458,16 @@ async fn handle_request(
         })?
     };
 
+    // Place a field-mapped body under its request field
+    if let BodyMapping::Field(field) = &mapping.body {
+        json_body = nest_body(field, json_body);
+    }
+
     // Merge path parameters into JSON body
     merge_path_params(&mut json_body, &params)?;
 
-    // Merge query parameters for GET requests
-    if http_method == HttpMethod::Get {
+    // Merge query parameters for GET requests and annotated bindings
+    if mapping.reads_query() {
         let query_params = parse_query_params(query.as_deref());
         merge_path_params(&mut json_body, &query_params)?;
     }
@@ -290,32 +477,251 @@ async fn handle_request(
     // Convert JSON to Protobuf
     let request_bytes = converter.json_to_proto(service, method, &json_body)?;
 
-    // Make RPC call
-    let response_bytes = state
-        .client
-        .call(service, method, request_bytes)
-        .await
-        .map_err(|e| GatewayError::RpcCall(e.to_string()))?;
+    if route.streaming_mode == StreamingMode::ServerStreaming {
+        return stream_upstream(&state, route, &path, request_bytes, metadata, format).await;
+    }
+
+    // Serve idempotent GETs from the cache when possible
+    let cache = match (&state.cache, http_method) {
+        (Some(cache), HttpMethod::Get) if !cache_directives.no_store => {
+            cache.policy(route).map(|policy| (Arc::clone(cache), policy))
+        }
+        _ => None,
+    };
+    if let Some((cache, policy)) = &cache {
+        if !cache_directives.no_cache {
+            match cache.lookup(&cache_key) {
+                Lookup::Fresh(body, age) => return Ok(cached_response(body, policy, age, "HIT")),
+                Lookup::Stale { body, age, refresh } => {
+                    if refresh {
+                        let cache = Arc::clone(cache);
+                        spawn_refresh(&state, route, &path, request_bytes, metadata, cache, cache_key, *policy);
+                    }
+                    return Ok(cached_response(body, policy, age, "STALE"));
+                }
+                Lookup::Miss => {}
+            }
+        }
+    }
+
+    // Make RPC call on the upstream serving this path
+    let response_bytes = match call_upstream(&state, route, &path, request_bytes, metadata).await {
+        Ok(response_bytes) => response_bytes,
+        Err(e) => {
+            if state.mock_mode == MockMode::Fallback && backend_unavailable(&e) {
+                if let Some(example) = route.example(example_name.as_deref()) {
+                    warn!("Backend for {}/{} unavailable, serving example '{}'", service, method, example.name);
+                    return Ok(mock_response(example));
+                }
+            }
+            return Err(GatewayError::RpcCall(e.to_string()).into());
+        }
+    };
 
     // Convert Protobuf response to JSON
     let response_json = converter.proto_to_json(service, method, &response_bytes)?;
 
     debug!("Response JSON: {:?}", response_json);
 
+    if let Some((cache, policy)) = cache {
+        cache.store(cache_key, response_json.clone(), policy);
+        return Ok(cached_response(response_json, &policy, Duration::ZERO, "MISS"));
+    }
+
     // Return JSON response
     Ok(Json(response_json).into_response())
 }
 
+/// Call the RPC on the upstream serving `path`
+async fn call_upstream(
+    state: &GatewayState,
+    route: &RouteMapping,
+    path: &str,
+    request_bytes: Bytes,
+    metadata: Metadata,
+) -> Result<Bytes, QuillError> {
+    match state.upstreams.select(path) {
+        Some(upstream) => {
+            debug!("Using upstream '{}' for {}", upstream.name(), path);
+            let options = upstream.request_options().metadata(metadata);
+            upstream.client().call_with_options(&route.service, &route.method, request_bytes, options).await
+        }
+        None => {
+            let options = RequestOptions::new().metadata(metadata);
+            state.client.call_with_options(&route.service, &route.method, request_bytes, options).await
+        }
+    }
+}
+
+/// Serve a server-streaming RPC as SSE or NDJSON
+///
+/// Each message becomes an event. The stream ends with a `usage` event if
+/// the server reported usage, or an `error` event carrying Problem Details
+/// if the call failed midway. A client disconnecting drops the upstream
+/// stream, which cancels the call.
+async fn stream_upstream(
+    state: &GatewayState,
+    route: &RouteMapping,
+    path: &str,
+    request_bytes: Bytes,
+    metadata: Metadata,
+    format: StreamingFormat,
+) -> Result<Response, GatewayResponse> {
+    let opened = match state.upstreams.select(path) {
+        Some(upstream) => {
+            debug!("Using upstream '{}' for {}", upstream.name(), path);
+            let options = upstream.request_options().metadata(metadata);
+            upstream
+                .client()
+                .call_server_streaming_usage(&route.service, &route.method, request_bytes, options)
+                .await
+        }
+        None => {
+            let options = RequestOptions::new().metadata(metadata);
+            state
+                .client
+                .call_server_streaming_usage(&route.service, &route.method, request_bytes, options)
+                .await
+        }
+    };
+    let stream = opened.map_err(|e| GatewayError::RpcCall(e.to_string()))?;
+    let converter = state.converter.clone().ok_or(GatewayError::NoConverter)?;
+    let (service, method) = (route.service.clone(), route.method.clone());
+
+    let events = futures_util::stream::unfold(Some(stream), move |stream: Option<UsageStream>| {
+        let converter = Arc::clone(&converter);
+        let (service, method) = (service.clone(), method.clone());
+        async move {
+            let mut stream = stream?;
+            match stream.next().await {
+                Some(Ok(bytes)) => match converter.proto_to_json(&service, &method, &bytes) {
+                    Ok(json) => Some((SseEvent::new(json), Some(stream))),
+                    Err(e) => Some((SseEvent::new(e.to_problem_json()).with_event("error"), None)),
+                },
+                Some(Err(e)) => Some((SseEvent::new(rpc_problem_json(e)).with_event("error"), None)),
+                None => {
+                    let usage = stream.usage().and_then(|usage| serde_json::to_value(usage).ok())?;
+                    Some((SseEvent::new(usage).with_event("usage"), None))
+                }
+            }
+        }
+    });
+
+    let mut response = StreamingResponse::new(format);
+    if let Some(secs) = route.streaming_config.as_ref().and_then(|config| config.keep_alive_secs) {
+        response = response.with_keep_alive(secs);
+    }
+    Ok(response.build_events(events))
+}
+
+/// Format for a streaming route, from the Accept header or the route's default
+fn streaming_format(route: &RouteMapping, headers: &http::HeaderMap) -> StreamingFormat {
+    let accept = headers
+        .get(http::header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    match StreamingFormat::from_accept(accept) {
+        StreamingFormat::JsonArray => route
+            .streaming_config
+            .as_ref()
+            .and_then(|config| config.default_format)
+            .unwrap_or(StreamingFormat::Sse),
+        format => format,
+    }
+}
+
+/// Problem Details JSON for an RPC that failed mid-stream
+fn rpc_problem_json(err: QuillError) -> Value {
+    match err {
+        QuillError::ProblemDetails(pd) => serde_json::to_value(pd).unwrap_or_default(),
+        other => GatewayError::RpcCall(other.to_string()).to_problem_json(),
+    }
+}
+
+/// Refresh a stale cache entry in the background
+#[allow(clippy::too_many_arguments)]
+fn spawn_refresh(
+    state: &GatewayState,
+    route: &RouteMapping,
+    path: &str,
+    request_bytes: Bytes,
+    metadata: Metadata,
+    cache: Arc<ResponseCache>,
+    key: String,
+    policy: CachePolicy,
+) {
+    let state = state.clone();
+    let route = route.clone();
+    let path = path.to_string();
+    tokio::spawn(async move {
+        let refreshed = match call_upstream(&state, &route, &path, request_bytes, metadata).await {
+            Ok(bytes) => state
+                .converter
+                .as_ref()
+                .and_then(|converter| converter.proto_to_json(&route.service, &route.method, &bytes).ok()),
+            Err(e) => {
+                warn!("Background refresh of {} failed: {}", path, e);
+                None
+            }
+        };
+        match refreshed {
+            Some(body) => cache.store(key, body, policy),
+            None => cache.refresh_failed(&key),
+        }
+    });
+}
+
+/// JSON response with cache headers
+fn cached_response(body: Value, policy: &CachePolicy, age: Duration, status: &'static str) -> Response {
+    let mut response = Json(body).into_response();
+    let headers = response.headers_mut();
+    if let Ok(value) = http::HeaderValue::from_str(&policy.header_value()) {
+        headers.insert(http::header::CACHE_CONTROL, value);
+    }
+    headers.insert(http::header::AGE, http::HeaderValue::from(age.as_secs()));
+    headers.insert(CACHE_STATUS_HEADER, http::HeaderValue::from_static(status));
+    response
+}
+
+/// Name of the example requested with `Prefer: example=<name>`
+fn preferred_example(headers: &http::HeaderMap) -> Option<String> {
+    headers
+        .get_all(PREFER_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|preference| preference.trim().strip_prefix("example="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+/// Whether an RPC failure means the backend could not be reached
+fn backend_unavailable(err: &QuillError) -> bool {
+    match err {
+        QuillError::Transport(_) => true,
+        QuillError::ProblemDetails(pd) => matches!(pd.status, 502..=504),
+        _ => false,
+    }
+}
+
+/// Serve an example in place of a backend response
+fn mock_response(example: &RouteExample) -> Response {
+    let mut response = Json(example.response.clone()).into_response();
+    if let Ok(name) = http::HeaderValue::from_str(&example.name) {
+        response.headers_mut().insert(MOCK_HEADER, name);
+    }
+    response
+}
+
 /// Find matching route for the given path and HTTP method
 fn find_matching_route<'a>(
     routes: &'a [RouteMapping],
     path: &str,
     http_method: HttpMethod,
-) -> GatewayResult<&'a RouteMapping> {
+) -> GatewayResult<(&'a RouteMapping, &'a HttpMethodMapping)> {
     for route in routes {
         for mapping in &route.http_mappings {
             if mapping.http_method == http_method && mapping.url_template.matches(path) {
-                return Ok(route);
+                return Ok((route, mapping));
             }
         }
     }
@@ -332,10 +738,9 @@ struct GatewayResponse(GatewayError);
 
 impl IntoResponse for GatewayResponse {
     fn into_response(self) -> Response {
-        let problem = self.0.to_problem_details();
-        let status = StatusCode::from_u16(problem.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let status = StatusCode::from_u16(self.0.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
-        (status, Json(problem)).into_response()
+        (status, Json(self.0.to_problem_json())).into_response()
     }
 }
 
@@ -350,6 +755,9 @@ impl From<GatewayError> for GatewayResponse {
 mod tests {
     use super::*;
     use quill_client::client::ClientBuilder;
+    use quill_core::ProblemDetails;
+    use serde_json::json;
+    use tower::ServiceExt;
 
     #[test]
     fn test_gateway_builder() {
@@ -373,6 +781,75 @@ mod tests {
         assert_eq!(gateway.openapi_spec().info.version, "1.0.0");
     }
 
+    /// Descriptors for a books service with `quill.http` annotations
+    fn annotated_descriptors() -> Vec<u8> {
+        use prost::Message;
+
+        let pool = prost_reflect::DescriptorPool::decode(quill_proto::FILE_DESCRIPTOR_SET).unwrap();
+        let set = pool.get_message_by_name("google.protobuf.FileDescriptorSet").unwrap();
+        let books = json!({ "file": [{
+            "name": "books.proto",
+            "package": "books.v1",
+            "dependency": ["quill/annotations.proto"],
+            "syntax": "proto3",
+            "messageType": [
+                { "name": "Book", "field": [
+                    { "name": "id", "number": 1, "label": "LABEL_OPTIONAL", "type": "TYPE_STRING" },
+                    { "name": "title", "number": 2, "label": "LABEL_OPTIONAL", "type": "TYPE_STRING" },
+                ]},
+                { "name": "UpdateBookRequest", "field": [
+                    { "name": "book", "number": 1, "label": "LABEL_OPTIONAL", "type": "TYPE_MESSAGE", "typeName": ".books.v1.Book" },
+                ]},
+            ],
+            "service": [{ "name": "BookService", "method": [
+                { "name": "GetBook", "inputType": ".books.v1.Book", "outputType": ".books.v1.Book",
+                  "options": { "[quill.http]": { "get": "/v1/books/{id}" } } },
+                { "name": "UpdateBook", "inputType": ".books.v1.UpdateBookRequest", "outputType": ".books.v1.Book",
+                  "options": { "[quill.http]": {
+                      "patch": "/v1/books/{book.id}",
+                      "body": "book",
+                      "additionalBindings": [{ "put": "/v1/books/{book.id}", "body": "book" }],
+                  } } },
+                { "name": "WatchBooks", "inputType": ".books.v1.Book", "outputType": ".books.v1.Book",
+                  "serverStreaming": true,
+                  "options": { "[quill.http]": { "get": "/v1/books/watch" } } },
+                { "name": "Reindex", "inputType": ".books.v1.Book", "outputType": ".books.v1.Book" },
+            ]}],
+        }]});
+        let books = prost_reflect::DynamicMessage::deserialize(set, books).unwrap();
+        [quill_proto::FILE_DESCRIPTOR_SET, &books.encode_to_vec()].concat()
+    }
+
+    #[test]
+    fn test_routes_from_annotated_descriptors() {
+        let converter = MessageConverter::from_bytes(&annotated_descriptors()).unwrap();
+        let routes = converter.annotated_routes().unwrap();
+        assert_eq!(routes.len(), 3);
+
+        let update = routes.iter().find(|r| r.method == "UpdateBook").unwrap();
+        assert_eq!(update.service, "books.v1.BookService");
+        let (mapping, params) = update.find_mapping(HttpMethod::Put, "/v1/books/7").unwrap();
+        assert_eq!(mapping.body, BodyMapping::Field("book".to_string()));
+        assert_eq!(params["book.id"], "7");
+        assert!(update.find_mapping(HttpMethod::Patch, "/v1/books/7").is_some());
+
+        let watch = routes.iter().find(|r| r.method == "WatchBooks").unwrap();
+        assert_eq!(watch.streaming_mode, StreamingMode::ServerStreaming);
+        assert!(routes.iter().all(|r| r.method != "Reindex"));
+
+        let client = ClientBuilder::new()
+            .base_url("http://localhost:8080")
+            .build()
+            .unwrap();
+        let gateway = RestGatewayBuilder::from_descriptors(client, &annotated_descriptors())
+            .unwrap()
+            .build();
+        let paths = &gateway.openapi_spec().paths;
+        assert!(paths["/v1/books/{id}"].get.is_some());
+        let update = &paths["/v1/books/{book.id}"];
+        assert!(update.patch.is_some() && update.put.is_some());
+    }
+
     #[test]
     fn test_openapi_json_generation() {
         let client = ClientBuilder::new()
@@ -410,20 +887,20 @@ mod tests {
         // Test matching GET /v1/users/123
         let result = find_matching_route(&routes, "/v1/users/123", HttpMethod::Get);
         assert!(result.is_ok());
-        let route = result.unwrap();
+        let (route, _) = result.unwrap();
         assert_eq!(route.service, "users.v1.UserService");
         assert_eq!(route.method, "GetUser");
 
         // Test matching POST /v1/users
         let result = find_matching_route(&routes, "/v1/users", HttpMethod::Post);
         assert!(result.is_ok());
-        let route = result.unwrap();
+        let (route, _) = result.unwrap();
         assert_eq!(route.method, "CreateUser");
 
         // Test matching GET /v1/posts/456
         let result = find_matching_route(&routes, "/v1/posts/456", HttpMethod::Get);
         assert!(result.is_ok());
-        let route = result.unwrap();
+        let (route, _) = result.unwrap();
         assert_eq!(route.service, "posts.v1.PostService");
     }
 
@@ -506,4 +983,101 @@ mod tests {
         let problem = err.to_problem_details();
         assert_eq!(problem.status, 500);
     }
+
+    #[test]
+    fn test_preferred_example() {
+        let mut headers = http::HeaderMap::new();
+        assert_eq!(preferred_example(&headers), None);
+
+        headers.insert(PREFER_HEADER, "respond-async, example=\"missing\"".parse().unwrap());
+        assert_eq!(preferred_example(&headers).as_deref(), Some("missing"));
+    }
+
+    #[test]
+    fn test_backend_unavailable() {
+        assert!(backend_unavailable(&QuillError::Transport("connection refused".into())));
+        assert!(backend_unavailable(&QuillError::ProblemDetails(ProblemDetails::new(StatusCode::SERVICE_UNAVAILABLE, "Unavailable"))));
+        assert!(!backend_unavailable(&QuillError::ProblemDetails(ProblemDetails::new(StatusCode::NOT_FOUND, "Not Found"))));
+        assert!(!backend_unavailable(&QuillError::Rpc("bad request".into())));
+    }
+
+    #[tokio::test]
+    async fn test_mock_mode_serves_examples() {
+        let client = ClientBuilder::new()
+            .base_url("http://127.0.0.1:1")
+            .build()
+            .unwrap();
+
+        let route = RouteMapping::new("users.v1.UserService", "GetUser")
+            .add_mapping(HttpMethod::Get, "/v1/users/me")
+            .unwrap()
+            .with_example(RouteExample::new("alice", json!({"id": "1", "name": "Alice"})))
+            .with_example(RouteExample::new("bob", json!({"id": "2", "name": "Bob"})));
+
+        let router = RestGatewayBuilder::new(client)
+            .base_path("")
+            .route(route)
+            .mock_mode(MockMode::Always)
+            .build()
+            .router();
+
+        let request = Request::builder()
+            .uri("/v1/users/me")
+            .header(PREFER_HEADER, "example=bob")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[MOCK_HEADER], "bob");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["name"], "Bob");
+    }
+
+    #[tokio::test]
+    async fn test_auth_and_rate_limit_reload_from_source() {
+        let client = ClientBuilder::new()
+            .base_url("http://127.0.0.1:1")
+            .build()
+            .unwrap();
+        let route = RouteMapping::new("users.v1.UserService", "GetUser")
+            .add_mapping(HttpMethod::Get, "/v1/users/me")
+            .unwrap()
+            .with_example(RouteExample::new("alice", json!({"id": "1", "name": "Alice"})));
+
+        let (keys, key_updates) = tokio::sync::watch::channel(AuthConfig::new().api_key("x-api-key", "old"));
+        let (limits, limit_updates) =
+            tokio::sync::watch::channel(RateLimitConfig::new(100, Duration::from_secs(60)));
+        let router = RestGatewayBuilder::new(client)
+            .base_path("")
+            .route(route)
+            .mock_mode(MockMode::Always)
+            .auth_source(ConfigSource::channel(key_updates))
+            .rate_limit_source(ConfigSource::channel(limit_updates))
+            .build()
+            .router();
+
+        let get = |key: &'static str| {
+            let request = Request::builder()
+                .uri("/v1/users/me")
+                .header("x-api-key", key)
+                .body(Body::empty())
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+        assert_eq!(get("old").await.unwrap().status(), StatusCode::OK);
+
+        keys.send(AuthConfig::new().api_key("x-api-key", "new")).unwrap();
+        limits.send(RateLimitConfig::new(1, Duration::from_secs(60))).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(get("old").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        // The rejected request used up the new limit
+        assert_eq!(get("new").await.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // The spec stays public
+        let request = Request::builder().uri("/openapi.json").body(Body::empty()).unwrap();
+        assert_eq!(router.clone().oneshot(request).await.unwrap().status(), StatusCode::OK);
+    }
 }
@@ -0,0 +1,389 @@
+//! JSON Schema derivation and request validation
+//!
+//! This module provides:
+//! - JSON Schemas (draft 2020-12) derived from protobuf message descriptors
+//! - Validation of JSON request bodies against protobuf descriptors
+//!
+//! Validation follows the proto3 JSON mapping: fields may use their JSON or
+//! original names, 64-bit integers and floats may be strings, enums may be
+//! names or numbers, and `null` means the default value. Every violation is
+//! reported with the JSON pointer of the offending value.
+
+use prost_reflect::{EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// JSON Schema dialect of derived schemas
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A value in a request body that does not match the message schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value (e.g. `/items/0/price`)
+    pub pointer: String,
+    /// What is wrong with the value
+    pub message: String,
+}
+
+impl SchemaViolation {
+    fn new(pointer: &str, message: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Derive the JSON Schema of a message
+///
+/// Nested messages are emitted once under `$defs` and referenced by full
+/// name, so recursive messages produce finite schemas.
+pub fn message_schema(descriptor: &MessageDescriptor) -> Value {
+    let mut defs = Map::new();
+    collect_defs(descriptor, &mut defs);
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$ref": def_ref(descriptor),
+        "$defs": defs,
+    })
+}
+
+/// Validate a JSON value against a message descriptor
+pub fn validate(descriptor: &MessageDescriptor, value: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    validate_message(descriptor, value, "", &mut violations);
+    violations
+}
+
+fn def_ref(descriptor: &MessageDescriptor) -> String {
+    format!("#/$defs/{}", descriptor.full_name())
+}
+
+fn collect_defs(descriptor: &MessageDescriptor, defs: &mut Map<String, Value>) {
+    if defs.contains_key(descriptor.full_name()) {
+        return;
+    }
+    if let Some(schema) = well_known_schema(descriptor) {
+        defs.insert(descriptor.full_name().to_string(), schema);
+        return;
+    }
+    // Reserve the slot before recursing so cycles terminate
+    defs.insert(descriptor.full_name().to_string(), Value::Null);
+
+    let mut properties = Map::new();
+    for field in descriptor.fields() {
+        properties.insert(field.json_name().to_string(), field_schema(&field, defs));
+    }
+    defs.insert(
+        descriptor.full_name().to_string(),
+        json!({
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": false,
+        }),
+    );
+}
+
+fn field_schema(field: &FieldDescriptor, defs: &mut Map<String, Value>) -> Value {
+    if field.is_map() {
+        let Kind::Message(entry) = field.kind() else {
+            unreachable!("map fields are messages");
+        };
+        let value = kind_schema(&entry.map_entry_value_field().kind(), defs);
+        return json!({ "type": "object", "additionalProperties": value });
+    }
+    let item = kind_schema(&field.kind(), defs);
+    if field.is_list() {
+        json!({ "type": "array", "items": item })
+    } else {
+        item
+    }
+}
+
+fn kind_schema(kind: &Kind, defs: &mut Map<String, Value>) -> Value {
+    match kind {
+        Kind::Bool => json!({ "type": "boolean" }),
+        Kind::String => json!({ "type": "string" }),
+        Kind::Bytes => json!({ "type": "string", "contentEncoding": "base64" }),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 | Kind::Uint32 | Kind::Fixed32 => {
+            json!({ "type": "integer" })
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 | Kind::Uint64 | Kind::Fixed64 => {
+            json!({ "type": ["integer", "string"] })
+        }
+        Kind::Float | Kind::Double => json!({ "type": ["number", "string"] }),
+        Kind::Enum(descriptor) => {
+            let mut values: Vec<Value> = descriptor.values().map(|v| json!(v.name())).collect();
+            values.extend(descriptor.values().map(|v| json!(v.number())));
+            json!({ "enum": values })
+        }
+        Kind::Message(descriptor) => {
+            collect_defs(descriptor, defs);
+            json!({ "$ref": def_ref(descriptor) })
+        }
+    }
+}
+
+/// Schemas of well-known types with special JSON mappings
+fn well_known_schema(descriptor: &MessageDescriptor) -> Option<Value> {
+    let name = descriptor.full_name().strip_prefix("google.protobuf.")?;
+    Some(match name {
+        "Timestamp" => json!({ "type": "string", "format": "date-time" }),
+        "Duration" | "FieldMask" => json!({ "type": "string" }),
+        "BoolValue" => json!({ "type": "boolean" }),
+        "StringValue" => json!({ "type": "string" }),
+        "BytesValue" => json!({ "type": "string", "contentEncoding": "base64" }),
+        "Int32Value" | "UInt32Value" => json!({ "type": "integer" }),
+        "Int64Value" | "UInt64Value" => json!({ "type": ["integer", "string"] }),
+        "FloatValue" | "DoubleValue" => json!({ "type": ["number", "string"] }),
+        "Struct" => json!({ "type": "object" }),
+        "ListValue" => json!({ "type": "array" }),
+        _ => json!({}),
+    })
+}
+
+fn validate_message(
+    descriptor: &MessageDescriptor,
+    value: &Value,
+    pointer: &str,
+    violations: &mut Vec<SchemaViolation>,
+) {
+    if value.is_null() || descriptor.full_name().starts_with("google.protobuf.") {
+        // Well-known types have their own JSON forms; prost-reflect checks them
+        return;
+    }
+    let Value::Object(object) = value else {
+        violations.push(SchemaViolation::new(
+            pointer,
+            format!("expected an object for message '{}'", descriptor.full_name()),
+        ));
+        return;
+    };
+
+    let mut oneofs: HashMap<String, &str> = HashMap::new();
+    for (key, value) in object {
+        let field_pointer = format!("{}/{}", pointer, escape_pointer(key));
+        let field = descriptor
+            .get_field_by_json_name(key)
+            .or_else(|| descriptor.get_field_by_name(key));
+        let Some(field) = field else {
+            violations.push(SchemaViolation::new(&field_pointer, "unknown field"));
+            continue;
+        };
+        if value.is_null() {
+            continue;
+        }
+        if let Some(oneof) = field.containing_oneof() {
+            if let Some(other) = oneofs.insert(oneof.name().to_string(), key) {
+                violations.push(SchemaViolation::new(
+                    &field_pointer,
+                    format!("oneof '{}' is already set by '{}'", oneof.name(), other),
+                ));
+            }
+        }
+        validate_field(&field, value, &field_pointer, violations);
+    }
+}
+
+fn validate_field(field: &FieldDescriptor, value: &Value, pointer: &str, violations: &mut Vec<SchemaViolation>) {
+    if field.is_map() {
+        let Kind::Message(entry) = field.kind() else {
+            return;
+        };
+        let Value::Object(object) = value else {
+            violations.push(SchemaViolation::new(pointer, "expected an object"));
+            return;
+        };
+        let value_kind = entry.map_entry_value_field().kind();
+        for (key, value) in object {
+            validate_kind(&value_kind, value, &format!("{}/{}", pointer, escape_pointer(key)), violations);
+        }
+    } else if field.is_list() {
+        let Value::Array(items) = value else {
+            violations.push(SchemaViolation::new(pointer, "expected an array"));
+            return;
+        };
+        for (i, item) in items.iter().enumerate() {
+            validate_kind(&field.kind(), item, &format!("{}/{}", pointer, i), violations);
+        }
+    } else {
+        validate_kind(&field.kind(), value, pointer, violations);
+    }
+}
+
+fn validate_kind(kind: &Kind, value: &Value, pointer: &str, violations: &mut Vec<SchemaViolation>) {
+    let problem = match kind {
+        Kind::Message(descriptor) => {
+            validate_message(descriptor, value, pointer, violations);
+            return;
+        }
+        Kind::Enum(descriptor) => check_enum(descriptor, value),
+        Kind::Bool => (!value.is_boolean()).then(|| "expected a boolean".to_string()),
+        Kind::String => (!value.is_string()).then(|| "expected a string".to_string()),
+        Kind::Bytes => (!value.is_string()).then(|| "expected a base64 string".to_string()),
+        Kind::Float | Kind::Double => check_float(value),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => check_integer(value, i32::MIN as i128, i32::MAX as i128),
+        Kind::Uint32 | Kind::Fixed32 => check_integer(value, 0, u32::MAX as i128),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => check_integer(value, i64::MIN as i128, i64::MAX as i128),
+        Kind::Uint64 | Kind::Fixed64 => check_integer(value, 0, u64::MAX as i128),
+    };
+    if let Some(message) = problem {
+        violations.push(SchemaViolation::new(pointer, message));
+    }
+}
+
+fn check_enum(descriptor: &EnumDescriptor, value: &Value) -> Option<String> {
+    let known = match value {
+        Value::String(name) => descriptor.get_value_by_name(name).is_some(),
+        Value::Number(number) => number
+            .as_i64()
+            .and_then(|n| i32::try_from(n).ok())
+            .is_some(),
+        _ => false,
+    };
+    (!known).then(|| format!("expected a value of enum '{}'", descriptor.full_name()))
+}
+
+fn check_float(value: &Value) -> Option<String> {
+    let valid = match value {
+        Value::Number(_) => true,
+        Value::String(s) => matches!(s.as_str(), "NaN" | "Infinity" | "-Infinity") || s.parse::<f64>().is_ok(),
+        _ => false,
+    };
+    (!valid).then(|| "expected a number".to_string())
+}
+
+fn check_integer(value: &Value, min: i128, max: i128) -> Option<String> {
+    let parsed = match value {
+        Value::Number(number) => number
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| number.as_u64().map(i128::from))
+            .or_else(|| number.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i128)),
+        Value::String(s) => s.parse::<i128>().ok(),
+        _ => None,
+    };
+    match parsed {
+        None => Some("expected an integer".to_string()),
+        Some(n) if n < min || n > max => Some(format!("integer {} is out of range [{}, {}]", n, min, max)),
+        Some(_) => None,
+    }
+}
+
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::DescriptorPool;
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+        FileDescriptorSet,
+    };
+
+    fn field(name: &str, number: i32, ty: Type, label: Label, type_name: Option<&str>) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(ty as i32),
+            type_name: type_name.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    /// `shop.Order { string order_id; repeated Item items; Status status; }`
+    fn order() -> MessageDescriptor {
+        let item = DescriptorProto {
+            name: Some("Item".to_string()),
+            field: vec![
+                field("sku", 1, Type::String, Label::Optional, None),
+                field("quantity", 2, Type::Uint32, Label::Optional, None),
+                field("price_cents", 3, Type::Int64, Label::Optional, None),
+            ],
+            ..Default::default()
+        };
+        let order = DescriptorProto {
+            name: Some("Order".to_string()),
+            field: vec![
+                field("order_id", 1, Type::String, Label::Optional, None),
+                field("items", 2, Type::Message, Label::Repeated, Some(".shop.Item")),
+                field("status", 3, Type::Enum, Label::Optional, Some(".shop.Status")),
+                field("parent", 4, Type::Message, Label::Optional, Some(".shop.Order")),
+            ],
+            ..Default::default()
+        };
+        let status = EnumDescriptorProto {
+            name: Some("Status".to_string()),
+            value: vec![
+                EnumValueDescriptorProto {
+                    name: Some("PENDING".to_string()),
+                    number: Some(0),
+                    ..Default::default()
+                },
+                EnumValueDescriptorProto {
+                    name: Some("PAID".to_string()),
+                    number: Some(1),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("shop.proto".to_string()),
+            package: Some("shop".to_string()),
+            message_type: vec![item, order],
+            enum_type: vec![status],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        };
+        let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] }).unwrap();
+        pool.get_message_by_name("shop.Order").unwrap()
+    }
+
+    #[test]
+    fn test_valid_request() {
+        let body = json!({
+            "orderId": "o-1",
+            "items": [{ "sku": "A", "quantity": 2, "price_cents": "1999" }],
+            "status": "PAID",
+            "parent": null,
+        });
+        assert_eq!(validate(&order(), &body), vec![]);
+    }
+
+    #[test]
+    fn test_violations_have_pointers() {
+        let body = json!({
+            "orderId": 7,
+            "items": [{ "sku": "A", "quantity": -1 }, "oops"],
+            "status": "SHIPPED",
+            "coupon": "FREE",
+        });
+        let mut pointers: Vec<_> = validate(&order(), &body).into_iter().map(|v| v.pointer).collect();
+        pointers.sort();
+        assert_eq!(pointers, vec!["/coupon", "/items/0/quantity", "/items/1", "/orderId", "/status"]);
+
+        let violations = validate(&order(), &json!([1]));
+        assert_eq!(violations[0].pointer, "");
+    }
+
+    #[test]
+    fn test_message_schema() {
+        let schema = message_schema(&order());
+        assert_eq!(schema["$ref"], "#/$defs/shop.Order");
+
+        let order = &schema["$defs"]["shop.Order"];
+        assert_eq!(order["properties"]["items"]["items"]["$ref"], "#/$defs/shop.Item");
+        assert_eq!(order["properties"]["parent"]["$ref"], "#/$defs/shop.Order");
+        assert_eq!(order["properties"]["status"]["enum"], json!(["PENDING", "PAID", 0, 1]));
+        assert_eq!(
+            schema["$defs"]["shop.Item"]["properties"]["priceCents"]["type"],
+            json!(["integer", "string"])
+        );
+    }
+}
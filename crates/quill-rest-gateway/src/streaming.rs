@@ -699,6 +699,42 @@ impl StreamingResponse {
             .body(body)
             .unwrap()
     }
+
+    /// Build response from a stream of events, keeping their types
+    ///
+    /// SSE sends the events as they are. NDJSON has no event types, so
+    /// typed events are written as `{"<type>": data}` lines.
+    pub fn build_events<S>(self, events: S) -> Response
+    where
+        S: Stream<Item = SseEvent> + Send + 'static,
+    {
+        match self.format {
+            StreamingFormat::Sse => {
+                let mapped = events.map(|event| {
+                    let sse_event = AxumSseEvent::from_event(event).unwrap();
+                    Ok::<_, Infallible>(sse_event.into())
+                });
+                let sse = Sse::new(mapped);
+                match self.keep_alive_secs {
+                    Some(secs) => sse
+                        .keep_alive(
+                            axum::response::sse::KeepAlive::new()
+                                .interval(Duration::from_secs(secs))
+                                .text("ping"),
+                        )
+                        .into_response(),
+                    None => sse.into_response(),
+                }
+            }
+            StreamingFormat::Ndjson | StreamingFormat::JsonArray => {
+                let values = events.map(|event| match event.event {
+                    Some(event_type) => serde_json::json!({ event_type: event.data }),
+                    None => event.data,
+                });
+                self.build_ndjson(values)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,165 @@
+//! Multi-backend upstream selection
+//!
+//! This module provides:
+//! - Upstream Quill endpoints with their own client, auth headers and timeout
+//! - Longest-prefix selection of the upstream serving a request path
+//!
+//! Each upstream wraps its own `QuillClient`, so connection pools, retries
+//! and circuit breakers are independent per backend. Requests whose path
+//! matches no registered prefix go to the gateway's default client.
+
+use crate::error::{GatewayError, GatewayResult};
+use http::{HeaderName, HeaderValue};
+use quill_client::{QuillClient, RequestOptions};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// An upstream Quill endpoint
+#[derive(Clone)]
+pub struct Upstream {
+    name: String,
+    client: Arc<QuillClient>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    timeout: Option<Duration>,
+}
+
+impl Upstream {
+    /// Create an upstream served by `client`
+    pub fn new(name: impl Into<String>, client: QuillClient) -> Self {
+        Self {
+            name: name.into(),
+            client: Arc::new(client),
+            headers: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    /// Add a header sent with every call to this upstream
+    pub fn header(mut self, name: &str, value: &str) -> GatewayResult<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| GatewayError::InvalidConfig(format!("Invalid header name '{}': {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| GatewayError::InvalidConfig(format!("Invalid value for header '{}': {}", name, e)))?;
+        self.headers.push((name, value));
+        Ok(self)
+    }
+
+    /// Authenticate calls to this upstream with a bearer token
+    pub fn bearer_token(self, token: &str) -> GatewayResult<Self> {
+        self.header("authorization", &format!("Bearer {}", token))
+    }
+
+    /// Set the timeout for calls to this upstream
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Upstream name, used in logs
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Client used for calls to this upstream
+    pub fn client(&self) -> &QuillClient {
+        &self.client
+    }
+
+    /// Request options carrying this upstream's headers and timeout
+    pub fn request_options(&self) -> RequestOptions {
+        let mut options = RequestOptions::new();
+        for (name, value) in &self.headers {
+            options.insert_header(name.clone(), value.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            options.set_timeout(timeout);
+        }
+        options
+    }
+}
+
+impl std::fmt::Debug for Upstream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Upstream")
+            .field("name", &self.name)
+            .field("base_url", &self.client.base_url())
+            .field("headers", &self.headers.iter().map(|(name, _)| name).collect::<Vec<_>>())
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// Upstreams keyed by path prefix
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UpstreamTable {
+    prefixes: Vec<(String, Arc<Upstream>)>,
+}
+
+impl UpstreamTable {
+    /// Register `upstream` for paths under `prefix`
+    pub(crate) fn insert(&mut self, prefix: &str, upstream: Upstream) {
+        let prefix = prefix.trim_end_matches('/').to_string();
+        self.prefixes.retain(|(existing, _)| *existing != prefix);
+        self.prefixes.push((prefix, Arc::new(upstream)));
+        // Longest prefix first, so the most specific upstream wins
+        self.prefixes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    }
+
+    /// Upstream serving `path`, if any prefix matches
+    pub(crate) fn select(&self, path: &str) -> Option<&Arc<Upstream>> {
+        self.prefixes
+            .iter()
+            .find(|(prefix, _)| {
+                prefix.is_empty()
+                    || path
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .map(|(_, upstream)| upstream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upstream(name: &str) -> Upstream {
+        let client = QuillClient::builder()
+            .base_url(format!("http://{}.internal:8080", name))
+            .build()
+            .unwrap();
+        Upstream::new(name, client)
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let mut table = UpstreamTable::default();
+        table.insert("/api/v1/users", upstream("users"));
+        table.insert("/api/v1/users/admin/", upstream("admin"));
+        table.insert("/api/v1/orders", upstream("orders"));
+
+        let name = |path| table.select(path).map(|u| u.name().to_string());
+        assert_eq!(name("/api/v1/users/42").as_deref(), Some("users"));
+        assert_eq!(name("/api/v1/users").as_deref(), Some("users"));
+        assert_eq!(name("/api/v1/users/admin/roles").as_deref(), Some("admin"));
+        assert_eq!(name("/api/v1/orders/7").as_deref(), Some("orders"));
+        // Prefixes match whole segments only
+        assert_eq!(name("/api/v1/usersettings"), None);
+        assert_eq!(name("/api/v2/users/1"), None);
+    }
+
+    #[test]
+    fn test_upstream_headers() {
+        let upstream = upstream("billing")
+            .bearer_token("secret")
+            .unwrap()
+            .header("x-tenant", "acme")
+            .unwrap()
+            .timeout(Duration::from_secs(2));
+
+        let debug = format!("{:?}", upstream);
+        assert!(debug.contains("billing"));
+        assert!(!debug.contains("secret"));
+        assert!(upstream.header("bad header", "x").is_err());
+    }
+}
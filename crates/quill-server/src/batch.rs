@@ -0,0 +1,43 @@
+//! Batch RPC execution limits
+//!
+//! When enabled, the router answers the built-in batch method
+//! ([`quill_core::BATCH_PATH`]) by running every entry against the unary
+//! handler registered for its path, up to a concurrency cap, and returning
+//! one result per entry in request order. A failing entry does not fail the
+//! batch: its result carries the status and Problem Details it would have
+//! had on its own. Streaming methods cannot be batched. Like a unary call,
+//! each entry runs inside a [`crate::RequestContext`] carrying the batch's
+//! metadata and consistency token, and is cut off at the batch's deadline
+//! with `504`. See [`quill_core::batch`] for the wire format.
+
+/// Limits for batch RPCs
+#[derive(Debug, Clone)]
+pub struct BatchRpcConfig {
+    /// Most entries per batch; larger batches are rejected with 413
+    pub max_entries: usize,
+    /// Most entries of one batch running at the same time
+    pub max_concurrency: usize,
+}
+
+impl Default for BatchRpcConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 256,
+            max_concurrency: 16,
+        }
+    }
+}
+
+impl BatchRpcConfig {
+    /// Set the most entries per batch
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Set the most entries running at the same time
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+}
@@ -0,0 +1,224 @@
+//! Acknowledgment of client cancels
+//!
+//! This module provides:
+//! - [`ClientCancel`], set when the client ends a request stream with CANCEL
+//! - A CANCEL_ACK frame ending the response once the cancel is acknowledged
+//!
+//! A client cancelling a bidirectional call sends CANCEL in place of
+//! END_STREAM and waits for CANCEL_ACK, so it knows when the handler has
+//! stopped, e.g. before reusing the GPU memory it held. Handlers reach the
+//! cancel through [`RequestContext::cancellation`](crate::RequestContext::cancellation):
+//!
+//! ```ignore
+//! let cancel = RequestContext::current().unwrap_or_default().cancellation().clone();
+//! tokio::spawn(async move {
+//!     cancel.cancelled().await;
+//!     release_kv_cache(request_id).await;
+//!     cancel.acknowledge();
+//! });
+//! ```
+//!
+//! The cancel is seen once the handler reads its request stream up to the
+//! CANCEL frame. Without an explicit acknowledgment it is acknowledged when
+//! the handler's response stream ends, whether with an error or not, so a
+//! handler holding resources until its stream ends needs no code at all.
+//! Acknowledging ends the response at once and drops the handler's stream.
+//! Each cancel is acknowledged at most once. Calls that fail before their
+//! response starts, and calls without a request stream, are never
+//! acknowledged: the client gives up waiting after its acknowledgment
+//! timeout.
+
+use crate::BoxFrameStream;
+use quill_core::{Frame, QuillError};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::Notify;
+use tokio_stream::Stream;
+
+/// The client's cancel of a call with a request stream
+#[derive(Clone, Default)]
+pub struct ClientCancel {
+    state: Arc<CancelState>,
+}
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    acknowledged: AtomicBool,
+    notify: Notify,
+}
+
+impl ClientCancel {
+    /// Whether the client cancelled the call
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the client cancels the call
+    ///
+    /// Never completes if the request stream ends normally.
+    pub async fn cancelled(&self) {
+        self.wait(|state| state.cancelled.load(Ordering::SeqCst)).await
+    }
+
+    /// Acknowledge the cancel now instead of when the response stream ends
+    ///
+    /// Returns false if the client has not cancelled or the cancel was
+    /// already acknowledged.
+    pub fn acknowledge(&self) -> bool {
+        if !self.is_cancelled() || self.state.acknowledged.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        self.state.notify.notify_waiters();
+        true
+    }
+
+    /// Whether the cancel was acknowledged
+    pub fn is_acknowledged(&self) -> bool {
+        self.state.acknowledged.load(Ordering::SeqCst)
+    }
+
+    /// Record that the request stream ended with CANCEL
+    pub(crate) fn cancel(&self) {
+        if !self.state.cancelled.swap(true, Ordering::SeqCst) {
+            self.state.notify.notify_waiters();
+        }
+    }
+
+    async fn wait(&self, done: fn(&CancelState) -> bool) {
+        loop {
+            // Registered before checking, so a change in between is not missed
+            let notified = self.state.notify.notified();
+            if done(&self.state) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    fn acknowledged_owned(&self) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let cancel = self.clone();
+        Box::pin(async move { cancel.wait(|state| state.acknowledged.load(Ordering::SeqCst)).await })
+    }
+}
+
+impl fmt::Debug for ClientCancel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientCancel")
+            .field("cancelled", &self.is_cancelled())
+            .field("acknowledged", &self.is_acknowledged())
+            .finish()
+    }
+}
+
+/// End `frames` with CANCEL_ACK once the client's cancel is acknowledged
+///
+/// After a cancel, the handler's terminal frame or error is replaced with
+/// CANCEL_ACK. Streams that are not cancelled pass through unchanged.
+pub(crate) fn acknowledge_cancel(frames: BoxFrameStream, cancel: ClientCancel) -> BoxFrameStream {
+    Box::pin(AckStream {
+        acknowledged: cancel.acknowledged_owned(),
+        inner: frames,
+        cancel,
+        done: false,
+    })
+}
+
+struct AckStream {
+    inner: BoxFrameStream,
+    cancel: ClientCancel,
+    acknowledged: Pin<Box<dyn Future<Output = ()> + Send>>,
+    done: bool,
+}
+
+impl AckStream {
+    fn ack(&mut self) -> Poll<Option<Result<Frame, QuillError>>> {
+        self.done = true;
+        self.cancel.state.acknowledged.store(true, Ordering::SeqCst);
+        tracing::debug!("Acknowledging client cancel");
+        Poll::Ready(Some(Ok(Frame::cancel_ack())))
+    }
+}
+
+impl Stream for AckStream {
+    type Item = Result<Frame, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        if self.acknowledged.as_mut().poll(cx).is_ready() {
+            return self.ack();
+        }
+        match self.inner.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(frame))) if frame.flags.is_end_stream() || frame.flags.is_cancel() => {
+                if self.cancel.is_cancelled() {
+                    return self.ack();
+                }
+                self.done = true;
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Poll::Ready(Some(Err(_)) | None) if self.cancel.is_cancelled() => self.ack(),
+            poll => poll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_cancelled_stream_ends_with_ack() {
+        let cancel = ClientCancel::default();
+        assert!(!cancel.acknowledge());
+
+        // The handler fails once its request stream reports the cancel
+        let handler = tokio_stream::iter(vec![
+            Ok(Frame::data(Bytes::from_static(b"a"))),
+            Err(QuillError::Rpc("Stream cancelled by client".to_string())),
+        ]);
+        let mut frames = acknowledge_cancel(Box::pin(handler), cancel.clone());
+        assert!(frames.next().await.unwrap().unwrap().flags.is_data());
+
+        cancel.cancel();
+        assert!(cancel.is_cancelled());
+        let last = frames.next().await.unwrap().unwrap();
+        assert!(last.flags.is_cancel_ack());
+        assert!(frames.next().await.is_none());
+        assert!(cancel.is_acknowledged());
+        assert!(!cancel.acknowledge());
+    }
+
+    #[tokio::test]
+    async fn test_explicit_ack_ends_pending_stream() {
+        let cancel = ClientCancel::default();
+        let mut frames = acknowledge_cancel(Box::pin(tokio_stream::pending()), cancel.clone());
+
+        let waiter = tokio::spawn({
+            let cancel = cancel.clone();
+            async move { cancel.cancelled().await }
+        });
+        cancel.cancel();
+        waiter.await.unwrap();
+        assert!(cancel.acknowledge());
+        assert!(frames.next().await.unwrap().unwrap().flags.is_cancel_ack());
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_uncancelled_stream_is_unchanged() {
+        let cancel = ClientCancel::default();
+        let handler = tokio_stream::iter(vec![Ok(Frame::data(Bytes::from_static(b"a"))), Ok(Frame::end_stream())]);
+        let frames: Vec<_> = acknowledge_cancel(Box::pin(handler), cancel.clone()).collect().await;
+        assert_eq!(frames.len(), 2);
+        assert!(!frames[1].as_ref().unwrap().flags.is_cancel_ack());
+        assert!(!cancel.is_acknowledged());
+    }
+}
@@ -0,0 +1,194 @@
+//! Bridges between tokio channels and Quill streams
+//!
+//! This module provides:
+//! - [`channel_to_response`], a streaming response fed by a bounded mpsc channel
+//! - [`broadcast_to_response`], a streaming response following a broadcast channel
+//! - [`stream_to_channel`], draining a request stream into a bounded mpsc channel
+//!
+//! The router only polls a response stream while the client has credit
+//! for more messages, so a bounded channel behind it holds at most its
+//! capacity: once it fills up, the producer's `send` waits until the client
+//! reads. In the other direction, [`stream_to_channel`] reads a request
+//! frame only after reserving room for it, so a slow handler stops reading
+//! and the client's stream stalls behind it.
+//!
+//! Both directions end cleanly. When the client goes away the response
+//! stream is dropped with its receiver, so the producer's next `send`
+//! fails and [`mpsc::Sender::closed`] resolves. The first error ends the
+//! stream it travels on, closing the channel behind it.
+
+use crate::streaming::RpcResponse;
+use bytes::Bytes;
+use http::StatusCode;
+use quill_core::{ProblemDetails, QuillError};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::{Stream, StreamExt};
+
+/// What a broadcast-backed response does when its client falls behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LagPolicy {
+    /// Skip the messages the channel dropped and carry on
+    #[default]
+    Skip,
+    /// End the response with `503`, so the client can resubscribe
+    Fail,
+}
+
+/// Streaming response sending the messages of a bounded mpsc channel
+///
+/// The response ends when every sender is dropped, or after the first
+/// error, which is sent to the client.
+pub fn channel_to_response<T, E>(rx: mpsc::Receiver<Result<T, E>>) -> RpcResponse
+where
+    T: Into<Bytes> + Send + 'static,
+    E: Into<QuillError> + Send + 'static,
+{
+    let messages = futures_util::stream::unfold(Some(rx), |rx| async move {
+        let mut rx = rx?;
+        match rx.recv().await? {
+            Ok(message) => Some((Ok(message.into()), Some(rx))),
+            // Dropping the receiver tells the producer to stop
+            Err(e) => Some((Err(e.into()), None)),
+        }
+    });
+    RpcResponse::streaming(messages)
+}
+
+/// Streaming response following a broadcast channel
+///
+/// A broadcast sender never waits for its receivers, so a client slower
+/// than the producer lags behind; `lag` decides what happens then. The
+/// response ends when every sender is dropped.
+pub fn broadcast_to_response<T>(rx: broadcast::Receiver<T>, lag: LagPolicy) -> RpcResponse
+where
+    T: Into<Bytes> + Clone + Send + 'static,
+{
+    let messages = futures_util::stream::unfold(Some(rx), move |rx| async move {
+        let mut rx = rx?;
+        loop {
+            match rx.recv().await {
+                Ok(message) => return Some((Ok(message.into()), Some(rx))),
+                Err(broadcast::error::RecvError::Closed) => return None,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => match lag {
+                    LagPolicy::Skip => {
+                        tracing::debug!("Broadcast response skipped {} messages", skipped);
+                    }
+                    LagPolicy::Fail => {
+                        let problem = ProblemDetails::new(StatusCode::SERVICE_UNAVAILABLE, "Slow consumer")
+                            .with_detail(format!("Fell {} messages behind the broadcast", skipped));
+                        return Some((Err(QuillError::ProblemDetails(problem)), None));
+                    }
+                },
+            }
+        }
+    });
+    RpcResponse::streaming(messages)
+}
+
+/// Drain `stream` into a bounded channel holding `capacity` messages
+///
+/// A task reads the stream only once the channel has room, so the channel
+/// bound becomes the stream's backpressure. The task stops, dropping the
+/// stream, when the stream ends, after forwarding its first error, or once
+/// the receiver is dropped.
+pub fn stream_to_channel<S, T>(stream: S, capacity: usize) -> mpsc::Receiver<Result<T, QuillError>>
+where
+    S: Stream<Item = Result<T, QuillError>> + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    tokio::spawn(async move {
+        tokio::pin!(stream);
+        loop {
+            let Ok(permit) = tx.reserve().await else {
+                return;
+            };
+            let item = tokio::select! {
+                item = stream.next() => item,
+                () = tx.closed() => return,
+            };
+            match item {
+                Some(Ok(message)) => permit.send(Ok(message)),
+                Some(Err(e)) => {
+                    permit.send(Err(e));
+                    return;
+                }
+                None => return,
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn expect_stream(response: RpcResponse) -> impl Stream<Item = Result<Bytes, QuillError>> {
+        match response {
+            RpcResponse::Streaming(stream) => stream,
+            _ => panic!("expected a streaming response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_channel_response_ends_at_first_error() {
+        let (tx, rx) = mpsc::channel::<Result<&'static str, QuillError>>(4);
+        let mut stream = expect_stream(channel_to_response(rx));
+
+        tx.send(Ok("a")).await.unwrap();
+        tx.send(Err(QuillError::Rpc("boom".into()))).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from("a"));
+        assert!(matches!(stream.next().await, Some(Err(QuillError::Rpc(_)))));
+        assert!(stream.next().await.is_none());
+        tokio::time::timeout(Duration::from_secs(1), tx.closed()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_response_lag_policies() {
+        let (tx, rx) = broadcast::channel::<&'static str>(2);
+        let failing = tx.subscribe();
+        let mut skipping = expect_stream(broadcast_to_response(rx, LagPolicy::Skip));
+        let mut failing = expect_stream(broadcast_to_response(failing, LagPolicy::Fail));
+        for message in ["a", "b", "c"] {
+            tx.send(message).unwrap();
+        }
+        drop(tx);
+
+        assert_eq!(skipping.next().await.unwrap().unwrap(), Bytes::from("b"));
+        assert_eq!(skipping.next().await.unwrap().unwrap(), Bytes::from("c"));
+        assert!(skipping.next().await.is_none());
+
+        match failing.next().await {
+            Some(Err(QuillError::ProblemDetails(pd))) => assert_eq!(pd.status, 503),
+            other => panic!("expected a lag failure, got {:?}", other.map(|r| r.map(|_| ()))),
+        }
+        assert!(failing.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_to_channel_reads_only_with_room() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&pulled);
+        let source = tokio_stream::iter(0..100).map(move |n| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, QuillError>(n)
+        });
+
+        let mut rx = stream_to_channel(source, 2);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(pulled.load(Ordering::SeqCst), 2);
+
+        assert_eq!(rx.recv().await.unwrap().unwrap(), 0);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(pulled.load(Ordering::SeqCst), 3);
+
+        // Dropping the receiver stops the task before the stream runs out
+        drop(rx);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(pulled.load(Ordering::SeqCst), 3);
+    }
+}
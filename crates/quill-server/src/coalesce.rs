@@ -0,0 +1,277 @@
+//! Coalescing of small response frames into fewer writes
+//!
+//! This module provides:
+//! - A default coalescing budget (delay and size) with per-method overrides
+//! - A response stream that batches encoded frames within that budget
+//!
+//! Token streaming produces many tiny frames, and each one otherwise
+//! becomes a separate body chunk, transport write and, without Nagle, a
+//! packet. With coalescing enabled, frames that arrive while earlier ones
+//! wait are appended to the same chunk. A chunk is written once it reaches
+//! the size budget, once its first frame has waited for the delay budget,
+//! or when the stream ends, so no frame is held back longer than the delay.
+//! Frame boundaries are unchanged: peers parse a coalesced chunk like any
+//! other run of frames.
+
+use crate::overrides::MethodOverrides;
+use crate::BoxFrameStream;
+use bytes::{Bytes, BytesMut};
+use quill_core::QuillError;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Sleep;
+use tokio_stream::Stream;
+
+/// Default longest time a frame waits for others to share its write
+pub const DEFAULT_COALESCE_DELAY: Duration = Duration::from_millis(1);
+
+/// Default chunk size at which coalesced frames are written right away
+pub const DEFAULT_COALESCE_BYTES: usize = 4 * 1024;
+
+/// How long and how much a response stream may batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoalesceBudget {
+    /// Longest time the first frame of a chunk waits for more frames
+    pub max_delay: Duration,
+    /// Chunk size at which frames are written without waiting
+    pub max_bytes: usize,
+}
+
+impl Default for CoalesceBudget {
+    fn default() -> Self {
+        Self {
+            max_delay: DEFAULT_COALESCE_DELAY,
+            max_bytes: DEFAULT_COALESCE_BYTES,
+        }
+    }
+}
+
+impl CoalesceBudget {
+    /// Batch frames for up to `max_delay` or `max_bytes`, whichever comes first
+    pub fn new(max_delay: Duration, max_bytes: usize) -> Self {
+        Self { max_delay, max_bytes }
+    }
+
+    /// Budget that writes every frame on its own
+    pub fn disabled() -> Self {
+        Self::new(Duration::ZERO, 0)
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.max_delay.is_zero() && self.max_bytes > 0
+    }
+}
+
+/// Frame coalescing configuration
+#[derive(Debug, Clone, Default)]
+pub struct FrameCoalescingConfig {
+    /// Budget for methods without their own
+    pub budget: CoalesceBudget,
+    budgets: MethodOverrides<CoalesceBudget>,
+}
+
+impl FrameCoalescingConfig {
+    /// Coalesce the response frames of every streaming method within `budget`
+    pub fn new(budget: CoalesceBudget) -> Self {
+        Self {
+            budget,
+            budgets: MethodOverrides::default(),
+        }
+    }
+
+    /// Set the budget of a method (`pkg.Service/Method`) or a whole service (`pkg.Service`)
+    ///
+    /// Use [`CoalesceBudget::disabled`] to write a method's frames one by one.
+    pub fn method_budget(mut self, path: impl Into<String>, budget: CoalesceBudget) -> Self {
+        self.budgets.insert(path, budget);
+        self
+    }
+
+    /// Budget of `method`, or `None` if coalescing is disabled for it
+    pub(crate) fn budget(&self, method: &str) -> Option<CoalesceBudget> {
+        let budget = self.budgets.get(method).copied().unwrap_or(self.budget);
+        budget.is_enabled().then_some(budget)
+    }
+}
+
+/// Encoded response frames, batched into chunks within a budget
+pub(crate) struct CoalescedFrames {
+    frames: BoxFrameStream,
+    budget: CoalesceBudget,
+    /// Encoded frames not yet written
+    buffer: BytesMut,
+    /// Fires when the oldest buffered frame has waited `max_delay`
+    timer: Option<Pin<Box<Sleep>>>,
+    /// Error to report once the frames before it are written
+    error: Option<QuillError>,
+    /// Whether the frames ended, so nothing follows the buffer
+    finished: bool,
+}
+
+impl CoalescedFrames {
+    pub(crate) fn new(frames: BoxFrameStream, budget: CoalesceBudget) -> Self {
+        Self {
+            frames,
+            budget,
+            buffer: BytesMut::new(),
+            timer: None,
+            error: None,
+            finished: false,
+        }
+    }
+
+    /// Take the buffered frames as one chunk
+    fn flush(&mut self) -> Poll<Option<Result<Bytes, QuillError>>> {
+        self.timer = None;
+        Poll::Ready(Some(Ok(self.buffer.split().freeze())))
+    }
+}
+
+impl Stream for CoalescedFrames {
+    type Item = Result<Bytes, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if let Some(error) = this.error.take() {
+            return Poll::Ready(Some(Err(error)));
+        }
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match this.frames.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(frame))) => {
+                    // Nothing follows a terminal frame, so don't wait for more
+                    let terminal = frame.flags.is_end_stream() || frame.flags.is_cancel();
+                    this.buffer.reserve(frame.encode_len());
+                    frame.encode_to(&mut this.buffer);
+                    if terminal {
+                        this.finished = true;
+                        return this.flush();
+                    }
+                    if this.buffer.len() >= this.budget.max_bytes {
+                        return this.flush();
+                    }
+                    if this.timer.is_none() {
+                        this.timer = Some(Box::pin(tokio::time::sleep(this.budget.max_delay)));
+                    }
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    this.finished = true;
+                    if this.buffer.is_empty() {
+                        return Poll::Ready(Some(Err(error)));
+                    }
+                    this.error = Some(error);
+                    return this.flush();
+                }
+                Poll::Ready(None) => {
+                    this.finished = true;
+                    if this.buffer.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return this.flush();
+                }
+                Poll::Pending => {
+                    let expired = match this.timer.as_mut() {
+                        Some(timer) => timer.as_mut().poll(cx).is_ready(),
+                        None => false,
+                    };
+                    if expired {
+                        return this.flush();
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_core::{Frame, FrameParser};
+    use tokio_stream::StreamExt;
+
+    /// Data frames of `size` bytes, each `gap` after the previous one, then END_STREAM
+    fn ticking(count: usize, size: usize, gap: Duration) -> BoxFrameStream {
+        let items = (0..count)
+            .map(move |i| Frame::data(Bytes::from(vec![i as u8; size])))
+            .chain(std::iter::once(Frame::end_stream()))
+            .map(Ok::<_, QuillError>);
+        Box::pin(tokio_stream::iter(items).then(move |item| async move {
+            if !gap.is_zero() {
+                tokio::time::sleep(gap).await;
+            }
+            item
+        }))
+    }
+
+    fn parse(chunks: &[Bytes]) -> Vec<Frame> {
+        let mut parser = FrameParser::new();
+        for chunk in chunks {
+            parser.feed(chunk);
+        }
+        std::iter::from_fn(|| parser.parse_frame().unwrap()).collect()
+    }
+
+    async fn coalesce(frames: BoxFrameStream, budget: CoalesceBudget) -> Vec<Bytes> {
+        CoalescedFrames::new(frames, budget).map(|chunk| chunk.unwrap()).collect().await
+    }
+
+    #[test]
+    fn test_method_budgets() {
+        let tokens = CoalesceBudget::new(Duration::from_millis(5), 1024);
+        let config = FrameCoalescingConfig::default()
+            .method_budget("llm.v1.Generate", tokens)
+            .method_budget("llm.v1.Generate/Embed", CoalesceBudget::disabled());
+
+        assert_eq!(config.budget("echo.v1.Echo/Stream"), Some(CoalesceBudget::default()));
+        assert_eq!(config.budget("llm.v1.Generate/Stream"), Some(tokens));
+        assert_eq!(config.budget("llm.v1.Generate/Embed"), None);
+    }
+
+    #[tokio::test]
+    async fn test_ready_frames_share_a_chunk() {
+        // 100 ready 10-byte frames fill 12-byte encodings into 4KB chunks
+        let chunks = coalesce(ticking(100, 10, Duration::ZERO), CoalesceBudget::default()).await;
+        assert!(chunks.len() < 5, "{} chunks", chunks.len());
+        assert!(chunks.iter().all(|chunk| chunk.len() <= DEFAULT_COALESCE_BYTES + 12));
+
+        let frames = parse(&chunks);
+        assert_eq!(frames.len(), 101);
+        assert_eq!(frames[42].payload, Bytes::from(vec![42u8; 10]));
+        assert!(frames[100].flags.is_end_stream());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delay_bounds_batching() {
+        // Frames 400µs apart: a 1ms budget holds two or three of them
+        let chunks = coalesce(ticking(10, 1, Duration::from_micros(400)), CoalesceBudget::default()).await;
+        assert!(chunks.len() > 1 && chunks.len() < 10, "{} chunks", chunks.len());
+        assert_eq!(parse(&chunks).len(), 11);
+
+        // Frames further apart than the budget are written one by one
+        let chunks = coalesce(ticking(3, 1, Duration::from_millis(5)), CoalesceBudget::default()).await;
+        assert_eq!(chunks.len(), 4);
+        assert!(parse(&chunks[3..])[0].flags.is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn test_error_follows_buffered_frames() {
+        let items = vec![
+            Ok(Frame::data(Bytes::from_static(b"a"))),
+            Ok(Frame::data(Bytes::from_static(b"b"))),
+            Err(QuillError::Rpc("handler failed".to_string())),
+        ];
+        let frames: BoxFrameStream = Box::pin(tokio_stream::iter(items));
+        let mut coalesced = CoalescedFrames::new(frames, CoalesceBudget::default());
+
+        let chunk = coalesced.next().await.unwrap().unwrap();
+        assert_eq!(parse(&[chunk]).len(), 2);
+        assert!(coalesced.next().await.unwrap().is_err());
+        assert!(coalesced.next().await.is_none());
+    }
+}
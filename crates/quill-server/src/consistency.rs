@@ -0,0 +1,198 @@
+//! Consistency tokens for read-your-writes on replicated services
+//!
+//! Every call gets a [`CallConsistency`] in its [`RequestContext`]. It holds
+//! the token the caller echoed, the latest write it has observed, and the
+//! token the handler attaches to its response:
+//!
+//! ```ignore
+//! let ctx = RequestContext::current().unwrap_or_default();
+//! // Reads wait until this replica has applied the caller's writes
+//! applied.wait_for(&ctx).await?;
+//! // Writes tell the caller which position to ask for next
+//! let index = log.append(entry).await?;
+//! ctx.consistency().set_response_token(ConsistencyToken::new(index));
+//! ```
+//!
+//! The router sends the response token in [`CONSISTENCY_TOKEN_HEADER`] on
+//! successful responses. Streaming handlers must set it before returning
+//! their stream, since headers go out first.
+//!
+//! [`RequestContext`]: crate::context::RequestContext
+//! [`CONSISTENCY_TOKEN_HEADER`]: quill_core::CONSISTENCY_TOKEN_HEADER
+
+use crate::context::RequestContext;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use quill_core::{ConsistencyToken, ProblemDetails, QuillError, CONSISTENCY_TOKEN_HEADER};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Consistency tokens of one call
+///
+/// Cheap to clone; clones share the response token.
+#[derive(Debug, Clone, Default)]
+pub struct CallConsistency {
+    requested: Option<ConsistencyToken>,
+    response: Arc<Mutex<Option<ConsistencyToken>>>,
+}
+
+impl CallConsistency {
+    /// Tokens of a call from its request headers
+    ///
+    /// A malformed token is ignored rather than failing the call.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let requested = headers
+            .get(CONSISTENCY_TOKEN_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(ConsistencyToken::from_header_value);
+        Self {
+            requested,
+            response: Arc::default(),
+        }
+    }
+
+    /// Token the caller sent, covering the latest write it has observed
+    pub fn requested(&self) -> Option<ConsistencyToken> {
+        self.requested
+    }
+
+    /// Attach `token` to the response
+    ///
+    /// Keeps the largest token when set more than once.
+    pub fn set_response_token(&self, token: ConsistencyToken) {
+        let mut response = self.response.lock().unwrap();
+        *response = (*response).max(Some(token));
+    }
+
+    /// Token attached to the response so far
+    pub fn response_token(&self) -> Option<ConsistencyToken> {
+        *self.response.lock().unwrap()
+    }
+
+    /// Write the response token into response headers
+    pub(crate) fn write_to(&self, headers: &mut HeaderMap) {
+        if let Some(token) = self.response_token() {
+            headers.insert(CONSISTENCY_TOKEN_HEADER, HeaderValue::from(token.position()));
+        }
+    }
+}
+
+/// Log position a replica has applied, for reads that must see earlier writes
+///
+/// The replica advances it as it applies log entries; handlers wait on it
+/// before serving a read. Cheap to clone; clones share the position.
+#[derive(Debug, Clone)]
+pub struct AppliedPosition {
+    sender: Arc<watch::Sender<u64>>,
+}
+
+impl AppliedPosition {
+    /// Start at log position `position`
+    pub fn new(position: u64) -> Self {
+        Self {
+            sender: Arc::new(watch::Sender::new(position)),
+        }
+    }
+
+    /// Position applied so far
+    pub fn current(&self) -> ConsistencyToken {
+        ConsistencyToken::new(*self.sender.borrow())
+    }
+
+    /// Record that entries up to `position` are applied
+    ///
+    /// Positions never move backwards.
+    pub fn advance(&self, position: u64) {
+        self.sender.send_if_modified(|applied| {
+            let advanced = position > *applied;
+            *applied = (*applied).max(position);
+            advanced
+        });
+    }
+
+    /// Wait until `token` is applied
+    pub async fn wait(&self, token: ConsistencyToken) {
+        let mut receiver = self.sender.subscribe();
+        // The sender lives as long as `self`, so waiting cannot fail
+        let _ = receiver.wait_for(|applied| *applied >= token.position()).await;
+    }
+
+    /// Wait until the token the caller of `context` sent is applied
+    ///
+    /// Returns at once for calls without a token. Fails with `503` when the
+    /// call's deadline passes first, so the caller can retry elsewhere.
+    pub async fn wait_for(&self, context: &RequestContext) -> Result<(), QuillError> {
+        let Some(token) = context.consistency().requested() else {
+            return Ok(());
+        };
+        let Some(remaining) = context.remaining() else {
+            self.wait(token).await;
+            return Ok(());
+        };
+        tokio::time::timeout(remaining, self.wait(token)).await.map_err(|_| {
+            QuillError::ProblemDetails(
+                ProblemDetails::new(StatusCode::SERVICE_UNAVAILABLE, "Replica behind")
+                    .with_detail(format!("Position {} not applied before the deadline", token)),
+            )
+        })
+    }
+}
+
+impl Default for AppliedPosition {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_tokens_from_headers_and_response() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONSISTENCY_TOKEN_HEADER, HeaderValue::from_static("12"));
+        let call = CallConsistency::from_headers(&headers);
+        assert_eq!(call.requested(), Some(ConsistencyToken::new(12)));
+
+        let clone = call.clone();
+        clone.set_response_token(ConsistencyToken::new(20));
+        call.set_response_token(ConsistencyToken::new(15));
+        assert_eq!(call.response_token(), Some(ConsistencyToken::new(20)));
+
+        let mut response = HeaderMap::new();
+        call.write_to(&mut response);
+        assert_eq!(response[CONSISTENCY_TOKEN_HEADER], "20");
+
+        headers.insert(CONSISTENCY_TOKEN_HEADER, HeaderValue::from_static("latest"));
+        assert!(CallConsistency::from_headers(&headers).requested().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_applied_position() {
+        let applied = AppliedPosition::new(5);
+        applied.advance(3);
+        assert_eq!(applied.current(), ConsistencyToken::new(5));
+
+        let waiter = {
+            let applied = applied.clone();
+            tokio::spawn(async move { applied.wait(ConsistencyToken::new(8)).await })
+        };
+        applied.advance(8);
+        tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONSISTENCY_TOKEN_HEADER, HeaderValue::from_static("10"));
+        headers.insert(quill_core::TIMEOUT_HEADER, HeaderValue::from_static("20"));
+        let ctx = RequestContext::from_headers("kv.v1.Kv/Get", &headers).unwrap();
+        match applied.wait_for(&ctx).await {
+            Err(QuillError::ProblemDetails(pd)) => assert_eq!(pd.status, 503),
+            other => panic!("expected a deadline failure, got {:?}", other),
+        }
+
+        applied.advance(10);
+        assert!(applied.wait_for(&ctx).await.is_ok());
+        let no_token = RequestContext::new("kv.v1.Kv/Get", None);
+        assert!(applied.wait_for(&no_token).await.is_ok());
+    }
+}
@@ -0,0 +1,207 @@
+//! Per-call context visible to handlers
+//!
+//! This module provides:
+//! - [`RequestContext`], the method, deadline and metadata of the call being handled
+//! - The call's [`UsageRecorder`], reported to the caller in a usage trailer
+//! - The client's cancel of a call with a request stream, see [`crate::cancel_ack`]
+//! - The call's consistency tokens, see [`crate::consistency`]
+//! - Parsing of the caller's timeout from [`TIMEOUT_HEADER`]
+//!
+//! The router runs every handler inside its call's context, so handler code
+//! can ask for the time it has left or read the caller's metadata without
+//! threading them through arguments:
+//!
+//! ```ignore
+//! let ctx = RequestContext::current().unwrap_or_default();
+//! if ctx.remaining().is_some_and(|left| left < Duration::from_millis(50)) {
+//!     return Err(QuillError::ProblemDetails(ProblemDetails::new(
+//!         StatusCode::SERVICE_UNAVAILABLE,
+//!         "Not enough time left",
+//!     )));
+//! }
+//! ```
+//!
+//! Once a handler runs past its deadline the router drops its future and
+//! answers `504` with a problem of type [`DEADLINE_EXCEEDED_TYPE`]. A
+//! streaming handler is bounded only until it returns its stream.
+//!
+//! The context is task-local: it is set while the handler's future runs,
+//! but not inside tasks it spawns or streams it returns. Capture it with
+//! [`RequestContext::current`] before handing work off.
+//!
+//! [`TIMEOUT_HEADER`]: quill_core::TIMEOUT_HEADER
+//! [`DEADLINE_EXCEEDED_TYPE`]: quill_core::DEADLINE_EXCEEDED_TYPE
+
+use http::HeaderMap;
+use crate::cancel_ack::ClientCancel;
+use crate::consistency::CallConsistency;
+use crate::usage::UsageRecorder;
+use quill_core::{Deadline, Metadata, TIMEOUT_HEADER};
+use std::future::Future;
+use std::time::Duration;
+
+tokio::task_local! {
+    static CONTEXT: RequestContext;
+}
+
+/// Method, deadline and metadata of the call being handled
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    method: String,
+    timeout: Option<Duration>,
+    deadline: Option<Deadline>,
+    metadata: Metadata,
+    usage: UsageRecorder,
+    cancellation: ClientCancel,
+    consistency: CallConsistency,
+}
+
+impl RequestContext {
+    /// Context of the call `method` (e.g. `echo.v1.EchoService/Echo`)
+    /// with the caller's timeout, if it sent one
+    pub fn new(method: impl Into<String>, timeout: Option<Duration>) -> Self {
+        Self {
+            method: method.into(),
+            timeout,
+            deadline: timeout.map(Deadline::after),
+            metadata: Metadata::new(),
+            usage: UsageRecorder::default(),
+            cancellation: ClientCancel::default(),
+            consistency: CallConsistency::default(),
+        }
+    }
+
+    /// Attach the caller's metadata
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Context of a request from its headers
+    ///
+    /// Fails with a description when the timeout header is malformed.
+    pub(crate) fn from_headers(method: &str, headers: &HeaderMap) -> Result<Self, String> {
+        let timeout = match headers.get(TIMEOUT_HEADER) {
+            Some(value) => {
+                let millis = value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .ok_or_else(|| {
+                        format!("{} must be a whole number of milliseconds", TIMEOUT_HEADER)
+                    })?;
+                Some(Duration::from_millis(millis))
+            }
+            None => None,
+        };
+        let mut context = Self::new(method, timeout).with_metadata(Metadata::from_headers(headers));
+        context.consistency = CallConsistency::from_headers(headers);
+        Ok(context)
+    }
+
+    /// Context of `method` run as part of this call, e.g. an entry of a batch
+    ///
+    /// Shares the deadline, metadata and consistency tokens of this call.
+    pub(crate) fn for_method(&self, method: &str) -> Self {
+        Self {
+            method: method.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// Context of the call the current task is handling
+    ///
+    /// `None` outside a handler.
+    pub fn current() -> Option<Self> {
+        CONTEXT.try_with(Clone::clone).ok()
+    }
+
+    /// Run `future` with this context as the current one
+    pub fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        CONTEXT.scope(self, future)
+    }
+
+    /// Method being called
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Timeout the caller sent
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Metadata the caller sent
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Usage of the call, sent to the caller when its response stream ends
+    ///
+    /// Clone it into streams the handler returns to keep recording.
+    pub fn usage(&self) -> &UsageRecorder {
+        &self.usage
+    }
+
+    /// Cancel the client may send on the call's request stream
+    ///
+    /// Never cancelled for calls without a request stream, which the client
+    /// cancels by resetting them.
+    pub fn cancellation(&self) -> &ClientCancel {
+        &self.cancellation
+    }
+
+    /// Consistency token the caller sent and the one sent back to it
+    pub fn consistency(&self) -> &CallConsistency {
+        &self.consistency
+    }
+
+    /// Deadline of the call, if the caller sent a timeout
+    pub fn deadline(&self) -> Option<Deadline> {
+        self.deadline
+    }
+
+    /// Time left until the deadline, `None` without one
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.remaining())
+    }
+
+    /// Whether the deadline has passed; never true without one
+    pub fn is_expired(&self) -> bool {
+        self.deadline.is_some_and(|deadline| deadline.is_expired())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    #[tokio::test]
+    async fn test_context_is_scoped_to_the_call() {
+        let mut headers = HeaderMap::new();
+        headers.insert(TIMEOUT_HEADER, HeaderValue::from_static("2000"));
+        headers.insert("x-request-id", HeaderValue::from_static("abc"));
+        let ctx = RequestContext::from_headers("test.Svc/Method", &headers).unwrap();
+        assert_eq!(ctx.timeout(), Some(Duration::from_secs(2)));
+        assert_eq!(ctx.metadata().get("x-request-id"), Some("abc"));
+        assert_eq!(ctx.metadata().len(), 1);
+
+        assert!(RequestContext::current().is_none());
+        let remaining = ctx
+            .scope(async {
+                let current = RequestContext::current().unwrap();
+                assert_eq!(current.method(), "test.Svc/Method");
+                assert!(!current.is_expired());
+                current.remaining().unwrap()
+            })
+            .await;
+        assert!(remaining <= Duration::from_secs(2));
+
+        let none = RequestContext::from_headers("test.Svc/Method", &HeaderMap::new()).unwrap();
+        assert!(none.deadline().is_none() && !none.is_expired());
+
+        headers.insert(TIMEOUT_HEADER, HeaderValue::from_static("1.5s"));
+        assert!(RequestContext::from_headers("test.Svc/Method", &headers).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Resumable list-style streaming responses
+//!
+//! This module provides:
+//! - A streaming response that interleaves CURSOR frames with its messages
+//! - Extraction of the cursor a call resumes from
+//!
+//! Handlers registered with [`RpcRouter::register_cursor_streaming`] yield
+//! each message together with the cursor just after it. A cursor frame is
+//! sent after every `every` messages and once more before the end of the
+//! stream, so a client always learns the position of the last message it
+//! received. Calls resuming a stream carry the cursor in the
+//! `quill-resume-token` header; the handler receives it decoded.
+//!
+//! [`RpcRouter::register_cursor_streaming`]: crate::router::RpcRouter::register_cursor_streaming
+
+use crate::streaming::RpcResponse;
+use bytes::Bytes;
+use http::{HeaderMap, StatusCode};
+use quill_core::{Frame, QuillError, StreamCursor, RESUME_TOKEN_HEADER};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio_stream::Stream;
+
+/// Type alias for cursor streaming handlers (takes the request and the cursor to resume from)
+pub type CursorHandlerFn = Arc<
+    dyn Fn(Bytes, Option<StreamCursor>) -> Pin<Box<dyn Future<Output = Result<RpcResponse, QuillError>> + Send>>
+        + Send
+        + Sync,
+>;
+
+impl RpcResponse {
+    /// Create a streaming response sending a cursor frame every `every` messages
+    ///
+    /// Each item pairs a message with the cursor just after it. The last
+    /// cursor is always sent before the stream ends.
+    pub fn cursor_stream<S>(items: S, every: usize) -> Self
+    where
+        S: Stream<Item = Result<(Bytes, StreamCursor), QuillError>> + Send + 'static,
+    {
+        Self::framed(CursorFrameStream {
+            inner: Box::pin(items),
+            every: every.max(1),
+            since_cursor: 0,
+            last: None,
+            pending: None,
+            done: false,
+        })
+    }
+}
+
+/// Cursor a call resumes from, decoded from its resume token header
+///
+/// Returns the status and detail to send if the header is not valid hex.
+pub(crate) fn resume_cursor(headers: &HeaderMap) -> Result<Option<StreamCursor>, (StatusCode, String)> {
+    let Some(value) = headers.get(RESUME_TOKEN_HEADER) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(StreamCursor::from_header_value)
+        .map(Some)
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid {} header: expected hex", RESUME_TOKEN_HEADER),
+            )
+        })
+}
+
+/// Messages paired with the cursor just after each
+type CursorItems = Pin<Box<dyn Stream<Item = Result<(Bytes, StreamCursor), QuillError>> + Send>>;
+
+struct CursorFrameStream {
+    inner: CursorItems,
+    every: usize,
+    /// Messages sent since the last cursor frame
+    since_cursor: usize,
+    /// Cursor of the last message sent
+    last: Option<StreamCursor>,
+    /// Item to send before polling for more messages
+    pending: Option<Result<Frame, QuillError>>,
+    done: bool,
+}
+
+impl CursorFrameStream {
+    /// Cursor frame for the last message, if any was sent since the previous one
+    fn take_cursor(&mut self) -> Option<Frame> {
+        if self.since_cursor == 0 {
+            return None;
+        }
+        self.since_cursor = 0;
+        self.last.clone().map(|cursor| Frame::cursor(cursor.into_bytes()))
+    }
+
+    /// Send the outstanding cursor, if any, then `last`
+    fn finish(&mut self, last: Result<Frame, QuillError>) -> Poll<Option<Result<Frame, QuillError>>> {
+        match self.take_cursor() {
+            Some(cursor) => {
+                self.pending = Some(last);
+                Poll::Ready(Some(Ok(cursor)))
+            }
+            None => Poll::Ready(Some(last)),
+        }
+    }
+}
+
+impl Stream for CursorFrameStream {
+    type Item = Result<Frame, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(item) = self.pending.take() {
+            return Poll::Ready(Some(item));
+        }
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        match self.inner.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok((message, cursor)))) => {
+                self.last = Some(cursor);
+                self.since_cursor += 1;
+                if self.since_cursor >= self.every {
+                    self.pending = self.take_cursor().map(Ok);
+                }
+                Poll::Ready(Some(Ok(Frame::data(message))))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                // Send the cursor first so the client can resume after the failure
+                self.done = true;
+                self.finish(Err(e))
+            }
+            Poll::Ready(None) => {
+                self.done = true;
+                self.finish(Ok(Frame::end_stream()))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+    use tokio_stream::StreamExt;
+
+    async fn collect(response: RpcResponse) -> Vec<Result<Frame, QuillError>> {
+        match response {
+            RpcResponse::Framed(stream) => stream.collect().await,
+            _ => panic!("expected a framed response"),
+        }
+    }
+
+    fn items(range: std::ops::Range<u32>) -> impl Stream<Item = Result<(Bytes, StreamCursor), QuillError>> + Send {
+        tokio_stream::iter(range.map(|i| Ok((Bytes::from(i.to_string()), StreamCursor::new(i.to_be_bytes().to_vec())))))
+    }
+
+    fn cursor_at(frame: &Frame) -> u32 {
+        assert!(frame.flags.is_cursor());
+        u32::from_be_bytes(frame.payload[..].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_cursor_frames_follow_every_n_messages() {
+        let frames: Vec<Frame> = collect(RpcResponse::cursor_stream(items(0..5), 2))
+            .await
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        // data, data, cursor(1), data, data, cursor(3), data, cursor(4), end
+        assert_eq!(frames.len(), 9);
+        assert_eq!(cursor_at(&frames[2]), 1);
+        assert_eq!(cursor_at(&frames[5]), 3);
+        assert_eq!(cursor_at(&frames[7]), 4);
+        assert_eq!(frames.iter().filter(|f| f.flags.is_data()).count(), 5);
+        assert!(frames[8].flags.is_end_stream());
+
+        // No trailing cursor repeats the last periodic one
+        let frames = collect(RpcResponse::cursor_stream(items(0..2), 2)).await;
+        assert_eq!(frames.len(), 4);
+        assert!(frames[3].as_ref().unwrap().flags.is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn test_cursor_precedes_error() {
+        let failing = items(0..3).chain(tokio_stream::once(Err(QuillError::Rpc("backend gone".to_string()))));
+        let frames = collect(RpcResponse::cursor_stream(failing, 10)).await;
+
+        assert_eq!(frames.len(), 5);
+        assert_eq!(cursor_at(frames[3].as_ref().unwrap()), 2);
+        assert!(frames[4].is_err());
+    }
+
+    #[test]
+    fn test_resume_cursor_from_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(resume_cursor(&headers).unwrap(), None);
+
+        headers.insert(RESUME_TOKEN_HEADER, HeaderValue::from_static("0000002a"));
+        let cursor = resume_cursor(&headers).unwrap().unwrap();
+        assert_eq!(cursor.as_bytes()[..], [0, 0, 0, 42]);
+
+        headers.insert(RESUME_TOKEN_HEADER, HeaderValue::from_static("not-hex"));
+        assert_eq!(resume_cursor(&headers).unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,306 @@
+//! Debug context for error responses
+//!
+//! This module provides:
+//! - A server debug policy gating debug context on a caller entitlement
+//! - Capture of handler panics with a redacted backtrace
+//! - Redaction of credentials and filesystem paths from debug text
+//!
+//! Debug context is attached to the `debug` extension of Problem Details.
+//! It is off unless the server installs a [`DebugPolicy`], and even then it
+//! is only sent to callers the policy entitles, so staging callers get
+//! actionable failures while production callers never see internals.
+
+use http::HeaderMap;
+use quill_core::{DebugContext, QuillError};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, Once};
+
+/// Header a caller sends to request debug context
+pub const DEBUG_HEADER: &str = "quill-debug";
+
+/// Default maximum number of backtrace frames in a debug context
+pub const DEFAULT_MAX_FRAMES: usize = 16;
+
+/// Decides whether a caller is entitled to debug context
+pub type EntitlementFn = Arc<dyn Fn(&HeaderMap) -> bool + Send + Sync>;
+
+/// Frames from these crates are runtime plumbing and are left out
+const SKIPPED_FRAME_PREFIXES: &[&str] = &[
+    "std::",
+    "core::",
+    "alloc::",
+    "tokio::",
+    "hyper::",
+    "hyper_util::",
+    "futures_util::",
+    "quill_server::debug::",
+    "quill_server::router::",
+    "<",
+    "__rust",
+    "rust_begin_unwind",
+];
+
+/// Words that mark the value of a `key=value` pair as secret
+pub(crate) const SECRET_KEYS: &[&str] = &["password", "passwd", "secret", "token", "key", "credential"];
+
+thread_local! {
+    static PANIC_BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
+/// Server policy for attaching debug context to Problem Details
+#[derive(Clone)]
+pub struct DebugPolicy {
+    entitlement: EntitlementFn,
+    max_frames: usize,
+}
+
+impl DebugPolicy {
+    /// Create a policy with a custom caller entitlement check
+    pub fn new<F>(entitlement: F) -> Self
+    where
+        F: Fn(&HeaderMap) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            entitlement: Arc::new(entitlement),
+            max_frames: DEFAULT_MAX_FRAMES,
+        }
+    }
+
+    /// Entitle callers that send `quill-debug: <token>`
+    pub fn with_token(token: impl Into<String>) -> Self {
+        let token = token.into();
+        Self::new(move |headers| {
+            headers
+                .get(DEBUG_HEADER)
+                .is_some_and(|value| constant_time_eq(value.as_bytes(), token.as_bytes()))
+        })
+    }
+
+    /// Set the maximum number of backtrace frames
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// Whether the caller sending these headers may receive debug context
+    pub fn allows(&self, headers: &HeaderMap) -> bool {
+        (self.entitlement)(headers)
+    }
+
+    /// Debug context for an error returned by a handler
+    pub fn error_context(&self, error: &QuillError) -> DebugContext {
+        DebugContext {
+            kind: "error".to_string(),
+            message: redact(&format!("{:?}", error)),
+            backtrace: Vec::new(),
+        }
+    }
+
+    /// Debug context for a handler panic
+    ///
+    /// Uses the backtrace recorded by the panic hook on this thread, if any.
+    pub fn panic_context(&self, payload: &(dyn Any + Send)) -> DebugContext {
+        let backtrace = PANIC_BACKTRACE
+            .with(|slot| slot.borrow_mut().take())
+            .map(|bt| redact_backtrace(&bt.to_string(), self.max_frames))
+            .unwrap_or_default();
+        DebugContext {
+            kind: "panic".to_string(),
+            message: redact(&panic_message(payload)),
+            backtrace,
+        }
+    }
+
+    /// Record a backtrace for every panic so it can be reported afterwards
+    ///
+    /// Chains to the previously installed hook; installed at most once.
+    pub(crate) fn install_panic_hook() {
+        PANIC_HOOK.call_once(|| {
+            let previous = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                PANIC_BACKTRACE.with(|slot| *slot.borrow_mut() = Some(Backtrace::force_capture()));
+                previous(info);
+            }));
+        });
+    }
+}
+
+impl fmt::Debug for DebugPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugPolicy")
+            .field("max_frames", &self.max_frames)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Extract the message from a panic payload
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        (*msg).to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "Handler panicked".to_string()
+    }
+}
+
+/// Redact credentials and absolute paths from debug text
+///
+/// Bearer tokens and the values of secret-looking `key=value` pairs are
+/// replaced with `[redacted]`; absolute paths are reduced to the file name.
+pub fn redact(text: &str) -> String {
+    let mut out = Vec::new();
+    let mut after_bearer = false;
+    for word in text.split(' ') {
+        if after_bearer && !word.is_empty() {
+            out.push("[redacted]".to_string());
+            after_bearer = false;
+            continue;
+        }
+        after_bearer = word.eq_ignore_ascii_case("bearer");
+        out.push(redact_word(word));
+    }
+    out.join(" ")
+}
+
+fn redact_word(word: &str) -> String {
+    if let Some((key, _)) = word.split_once(['=', ':']) {
+        let lower = key.trim_start_matches(|c: char| !c.is_alphanumeric()).to_ascii_lowercase();
+        if !lower.is_empty() && SECRET_KEYS.iter().any(|secret| lower.contains(secret)) {
+            let sep = &word[key.len()..key.len() + 1];
+            return format!("{}{}[redacted]", key, sep);
+        }
+    }
+    let trimmed = word.trim_start_matches(['"', '(', '\'']);
+    if trimmed.starts_with('/') && trimmed[1..].contains('/') {
+        let prefix = &word[..word.len() - trimmed.len()];
+        let file = trimmed.rsplit('/').next().unwrap_or(trimmed);
+        return format!("{}{}", prefix, file);
+    }
+    word.to_string()
+}
+
+/// Turn a `std::backtrace::Backtrace` rendering into redacted frames
+///
+/// Runtime frames are dropped and locations are reduced to `file:line`.
+pub fn redact_backtrace(rendered: &str, max_frames: usize) -> Vec<String> {
+    let mut frames: Vec<String> = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in rendered.lines() {
+        let line = line.trim();
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(symbol) = current.take() {
+                let file = location.rsplit('/').next().unwrap_or(location);
+                let file = file.rsplit_once(':').map_or(file, |(file_line, _col)| file_line);
+                frames.push(format!("{} ({})", symbol, file));
+            }
+            continue;
+        }
+        if let Some(symbol) = current.take() {
+            frames.push(symbol);
+        }
+        if let Some((index, symbol)) = line.split_once(": ") {
+            if index.chars().all(|c| c.is_ascii_digit()) && !is_runtime_frame(symbol) {
+                current = Some(strip_hash(symbol).to_string());
+            }
+        }
+    }
+    if let Some(symbol) = current {
+        frames.push(symbol);
+    }
+
+    frames.truncate(max_frames);
+    frames
+}
+
+fn is_runtime_frame(symbol: &str) -> bool {
+    SKIPPED_FRAME_PREFIXES.iter().any(|prefix| symbol.starts_with(prefix))
+}
+
+/// Strip the trailing `::h0123abcd` symbol hash
+fn strip_hash(symbol: &str) -> &str {
+    match symbol.rsplit_once("::h") {
+        Some((path, hash)) if hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            path
+        }
+        _ => symbol,
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    #[test]
+    fn test_token_entitlement() {
+        let policy = DebugPolicy::with_token("s3cret");
+
+        let mut headers = HeaderMap::new();
+        assert!(!policy.allows(&headers));
+
+        headers.insert(DEBUG_HEADER, HeaderValue::from_static("wrong"));
+        assert!(!policy.allows(&headers));
+
+        headers.insert(DEBUG_HEADER, HeaderValue::from_static("s3cret"));
+        assert!(policy.allows(&headers));
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("auth failed: Bearer abc.def for user"),
+            "auth failed: Bearer [redacted] for user"
+        );
+        assert_eq!(redact("connect db password=hunter2 host=db"), "connect db password=[redacted] host=db");
+        assert_eq!(redact("open /home/alice/app/config.toml failed"), "open config.toml failed");
+        assert_eq!(redact("index out of bounds"), "index out of bounds");
+    }
+
+    #[test]
+    fn test_redact_backtrace() {
+        let rendered = "\
+   0: std::backtrace::Backtrace::force_capture
+             at /rustc/abc/library/std/src/backtrace.rs:312:13
+   1: my_service::handlers::get_user::h0123456789abcdef
+             at /home/alice/my-service/src/handlers.rs:42:9
+   2: tokio::runtime::task::core::Core<T,S>::poll
+             at /cargo/registry/tokio/src/runtime/task/core.rs:331:17
+   3: my_service::main
+";
+        let frames = redact_backtrace(rendered, 8);
+        assert_eq!(frames, vec!["my_service::handlers::get_user (handlers.rs:42)", "my_service::main"]);
+        assert_eq!(redact_backtrace(rendered, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_panic_context_uses_hook_backtrace() {
+        DebugPolicy::install_panic_hook();
+        let policy = DebugPolicy::new(|_| true);
+
+        let payload = std::panic::catch_unwind(|| panic!("token=abc leaked")).unwrap_err();
+        let ctx = policy.panic_context(payload.as_ref());
+        assert_eq!(ctx.kind, "panic");
+        assert_eq!(ctx.message, "token=[redacted] leaked");
+        assert!(ctx.backtrace.iter().all(|frame| !frame.starts_with("std::")));
+    }
+
+    #[test]
+    fn test_error_context() {
+        let policy = DebugPolicy::new(|_| true);
+        let ctx = policy.error_context(&QuillError::Rpc("db at /var/lib/db/data failed".to_string()));
+        assert_eq!(ctx.kind, "error");
+        assert!(ctx.message.contains("data failed"));
+        assert!(!ctx.message.contains("/var/lib"));
+    }
+}
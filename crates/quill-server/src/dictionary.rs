@@ -0,0 +1,224 @@
+//! Dictionary compression of unary calls
+//!
+//! This module provides:
+//! - Configuration of the dictionary a server offers to clients
+//! - Decompression of requests compressed with a dictionary
+//! - Dictionary compression of unary responses
+//!
+//! Once enabled, every response advertises the current dictionary and the
+//! built-in fetch method returns its bytes. Requests with
+//! `Content-Encoding: zstd` are decompressed before the handler runs, with
+//! the dictionary named in their `quill-dictionary` header if any. Retired
+//! dictionaries can stay accepted on requests while clients catch up. A
+//! unary response is compressed with the current dictionary when the
+//! request accepts zstd and names that dictionary. See
+//! [`quill_core::dictionary`] for the handshake.
+
+use bytes::Bytes;
+use http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use quill_core::{
+    parse_dictionary_id, CompressionDictionary, DictionaryError, DICTIONARY_CAPABILITY_HEADER,
+    DICTIONARY_HEADER,
+};
+use std::collections::HashMap;
+
+/// Default zstd level of dictionary-compressed responses
+pub const DEFAULT_DICTIONARY_LEVEL: i32 = 3;
+
+/// Headers describing a dictionary-compressed body
+type DictionaryHeaders = [(&'static str, HeaderValue); 2];
+
+/// Configuration of the dictionary offered to clients
+#[derive(Debug, Clone)]
+pub struct DictionaryCompression {
+    /// Dictionary advertised and used for responses
+    pub dictionary: CompressionDictionary,
+    /// Retired dictionaries still accepted on requests
+    pub previous: Vec<CompressionDictionary>,
+    /// zstd level of compressed responses
+    pub level: i32,
+}
+
+impl DictionaryCompression {
+    /// Offer `dictionary` to clients
+    pub fn new(dictionary: CompressionDictionary) -> Self {
+        Self {
+            dictionary,
+            previous: Vec::new(),
+            level: DEFAULT_DICTIONARY_LEVEL,
+        }
+    }
+
+    /// Keep accepting requests compressed with a retired dictionary
+    pub fn previous(mut self, dictionary: CompressionDictionary) -> Self {
+        self.previous.push(dictionary);
+        self
+    }
+
+    /// Set the zstd level of compressed responses
+    pub fn level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+}
+
+/// Compresses and decompresses bodies with the configured dictionaries
+pub(crate) struct DictionaryCodec {
+    current: CompressionDictionary,
+    accepted: HashMap<u32, CompressionDictionary>,
+    level: i32,
+    capability: HeaderValue,
+    id: HeaderValue,
+}
+
+impl DictionaryCodec {
+    pub(crate) fn new(config: DictionaryCompression) -> Self {
+        let capability = HeaderValue::from_str(&config.dictionary.capability().to_header_value())
+            .expect("capability header is ASCII");
+        let id = HeaderValue::from(config.dictionary.id());
+        let accepted = config
+            .previous
+            .into_iter()
+            .chain(std::iter::once(config.dictionary.clone()))
+            .map(|dictionary| (dictionary.id(), dictionary))
+            .collect();
+        Self {
+            current: config.dictionary,
+            accepted,
+            level: config.level,
+            capability,
+            id,
+        }
+    }
+
+    /// Value of the capability header sent on every response
+    pub(crate) fn capability_header(&self) -> &HeaderValue {
+        &self.capability
+    }
+
+    /// Bytes of the current dictionary and the headers describing it
+    pub(crate) fn fetch(&self) -> (Bytes, DictionaryHeaders) {
+        (
+            self.current.data().clone(),
+            [
+                (DICTIONARY_HEADER, self.id.clone()),
+                (DICTIONARY_CAPABILITY_HEADER, self.capability.clone()),
+            ],
+        )
+    }
+
+    /// Decompress a request body sent with `Content-Encoding: zstd`
+    ///
+    /// Bodies without that encoding are returned unchanged. Returns the
+    /// status and detail to send if the body cannot be decompressed.
+    pub(crate) fn decode_request(&self, headers: &HeaderMap, body: Bytes) -> Result<Bytes, (StatusCode, String)> {
+        if headers.get(CONTENT_ENCODING).map_or(true, |encoding| encoding != "zstd") {
+            return Ok(body);
+        }
+        let Some(id) = headers.get(DICTIONARY_HEADER) else {
+            return zstd::decode_all(&body[..])
+                .map(Bytes::from)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Decompression failed: {}", e)));
+        };
+        let dictionary = id
+            .to_str()
+            .ok()
+            .and_then(parse_dictionary_id)
+            .and_then(|id| self.accepted.get(&id))
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown compression dictionary {:?}", id),
+                )
+            })?;
+        dictionary
+            .decompress(&body)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+    }
+
+    /// Whether a unary response to a request with `headers` is compressed
+    ///
+    /// The request must accept zstd and name the current dictionary.
+    pub(crate) fn compresses_response(&self, headers: &HeaderMap) -> bool {
+        let accepts_zstd = headers
+            .get(ACCEPT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("zstd"));
+        let holds_current = headers
+            .get(DICTIONARY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_dictionary_id)
+            == Some(self.current.id());
+        accepts_zstd && holds_current
+    }
+
+    /// Compress a unary response with the current dictionary
+    ///
+    /// Returns the headers to add and the compressed body, or `None` for
+    /// an empty body, which is sent as-is.
+    pub(crate) fn encode_response(&self, body: &Bytes) -> Option<Result<(DictionaryHeaders, Bytes), DictionaryError>> {
+        if body.is_empty() {
+            return None;
+        }
+        Some(self.current.compress(body, self.level).map(|compressed| {
+            let headers = [
+                (CONTENT_ENCODING.as_str(), HeaderValue::from_static("zstd")),
+                (DICTIONARY_HEADER, self.id.clone()),
+            ];
+            (headers, compressed)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_decodes_current_previous_and_plain_zstd() {
+        let old = CompressionDictionary::new(1, b"old dictionary content".to_vec());
+        let current = CompressionDictionary::new(2, b"current dictionary content".to_vec());
+        let codec = DictionaryCodec::new(DictionaryCompression::new(current.clone()).previous(old.clone()));
+        let body = Bytes::from_static(b"dictionary content message");
+
+        let with_current = current.compress(&body, 3).unwrap();
+        let decoded = codec.decode_request(&headers(&[("content-encoding", "zstd"), ("quill-dictionary", "2")]), with_current);
+        assert_eq!(decoded.unwrap(), body);
+
+        let with_old = old.compress(&body, 3).unwrap();
+        let decoded = codec.decode_request(&headers(&[("content-encoding", "zstd"), ("quill-dictionary", "1")]), with_old);
+        assert_eq!(decoded.unwrap(), body);
+
+        let plain = Bytes::from(zstd::encode_all(&body[..], 3).unwrap());
+        assert_eq!(codec.decode_request(&headers(&[("content-encoding", "zstd")]), plain).unwrap(), body);
+        assert_eq!(codec.decode_request(&HeaderMap::new(), body.clone()).unwrap(), body);
+
+        let unknown = codec.decode_request(&headers(&[("content-encoding", "zstd"), ("quill-dictionary", "9")]), body);
+        assert_eq!(unknown.unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_compresses_responses_for_holders_of_current_dictionary() {
+        let current = CompressionDictionary::new(2, b"current dictionary content".to_vec());
+        let codec = DictionaryCodec::new(DictionaryCompression::new(current.clone()));
+        let body = Bytes::from_static(b"dictionary content reply");
+
+        assert!(codec.compresses_response(&headers(&[("accept-encoding", "zstd"), ("quill-dictionary", "2")])));
+        assert!(!codec.compresses_response(&headers(&[("accept-encoding", "zstd"), ("quill-dictionary", "1")])));
+        assert!(!codec.compresses_response(&headers(&[("quill-dictionary", "2")])));
+
+        let (response_headers, compressed) = codec.encode_response(&body).unwrap().unwrap();
+        assert_eq!(response_headers[1].1, "2");
+        assert_eq!(current.decompress(&compressed).unwrap(), body);
+        assert!(codec.encode_response(&Bytes::new()).is_none());
+    }
+}
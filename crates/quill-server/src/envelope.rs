@@ -0,0 +1,182 @@
+//! Server-side envelope decryption
+//!
+//! This module provides:
+//! - Configuration for opening envelope-encrypted requests
+//! - A cache of unwrapped data keys
+//!
+//! Requests carrying the `quill-envelope` header are opened before the
+//! handler runs, so handlers always see plaintext. Unary responses to such
+//! requests are sealed with the same data key by default, keeping the reply
+//! protected through relays and gateways as well. See
+//! [`quill_core::envelope`] for the wire format.
+
+use bytes::Bytes;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use quill_core::envelope::{open, seal};
+use quill_core::{DataKey, EnvelopeError, EnvelopeHeader, EnvelopeScope, KeyProvider, ENVELOPE_HEADER};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Default number of unwrapped data keys kept in memory
+pub const DEFAULT_KEY_CACHE_SIZE: usize = 1024;
+
+/// Configuration for opening envelope-encrypted requests
+#[derive(Clone)]
+pub struct EnvelopeDecryption {
+    /// Unwraps the data keys sent by clients
+    pub provider: Arc<dyn KeyProvider>,
+    /// Seal unary responses to sealed requests with the request's data key
+    pub encrypt_responses: bool,
+    /// Unwrapped data keys kept in memory, to avoid a KMS call per request
+    pub key_cache_size: usize,
+}
+
+impl EnvelopeDecryption {
+    /// Open sealed requests with data keys unwrapped by `provider`
+    pub fn new(provider: Arc<dyn KeyProvider>) -> Self {
+        Self {
+            provider,
+            encrypt_responses: true,
+            key_cache_size: DEFAULT_KEY_CACHE_SIZE,
+        }
+    }
+
+    /// Set whether responses to sealed requests are sealed
+    pub fn encrypt_responses(mut self, enabled: bool) -> Self {
+        self.encrypt_responses = enabled;
+        self
+    }
+
+    /// Set the number of unwrapped data keys kept in memory
+    pub fn key_cache_size(mut self, size: usize) -> Self {
+        self.key_cache_size = size;
+        self
+    }
+}
+
+impl fmt::Debug for EnvelopeDecryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnvelopeDecryption")
+            .field("encrypt_responses", &self.encrypt_responses)
+            .field("key_cache_size", &self.key_cache_size)
+            .finish()
+    }
+}
+
+/// Opens sealed requests and seals their responses
+pub(crate) struct EnvelopeOpener {
+    config: EnvelopeDecryption,
+    keys: Mutex<HashMap<(String, Vec<u8>), DataKey>>,
+}
+
+impl EnvelopeOpener {
+    pub(crate) fn new(config: EnvelopeDecryption) -> Self {
+        Self {
+            config,
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Open a sealed request body
+    ///
+    /// Returns the plaintext and the data key, or the status and detail to
+    /// send if the request cannot be opened.
+    pub(crate) async fn open(
+        &self,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> Result<(Bytes, DataKey), (StatusCode, String)> {
+        let header = headers
+            .get(ENVELOPE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid envelope header".to_string()))?;
+        let header = EnvelopeHeader::from_header_value(header)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+        let key = self.data_key(&header).await?;
+        let plaintext = open(&key, &header.scope, &body).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        Ok((plaintext, key))
+    }
+
+    /// Seal a unary response with the request's data key, if enabled
+    pub(crate) fn seal_response(&self, key: &DataKey, body: &Bytes) -> Option<Result<(HeaderValue, Bytes), EnvelopeError>> {
+        if !self.config.encrypt_responses {
+            return None;
+        }
+        let scope = EnvelopeScope::Message;
+        Some(seal(key, &scope, body).map(|sealed| {
+            let header = EnvelopeHeader::new(key, scope).to_header_value();
+            let header = HeaderValue::from_str(&header).expect("envelope header is ASCII");
+            (header, sealed)
+        }))
+    }
+
+    async fn data_key(&self, header: &EnvelopeHeader) -> Result<DataKey, (StatusCode, String)> {
+        let cache_key = (header.key_id.clone(), header.wrapped_key.clone());
+        if let Some(key) = self.keys.lock().unwrap().get(&cache_key) {
+            return Ok(key.clone());
+        }
+
+        let key = self
+            .config
+            .provider
+            .decrypt_data_key(&header.key_id, &header.wrapped_key)
+            .await
+            .map_err(|e| match e {
+                EnvelopeError::UnknownKey(_) | EnvelopeError::Decrypt => (StatusCode::BAD_REQUEST, e.to_string()),
+                _ => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+            })?;
+
+        if self.config.key_cache_size > 0 {
+            let mut keys = self.keys.lock().unwrap();
+            if keys.len() >= self.config.key_cache_size {
+                keys.clear();
+            }
+            keys.insert(cache_key, key.clone());
+        }
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_core::LocalKeyProvider;
+
+    fn sealed_request(key: &DataKey, body: &[u8]) -> (HeaderMap, Bytes) {
+        let mut headers = HeaderMap::new();
+        let header = EnvelopeHeader::new(key, EnvelopeScope::Message).to_header_value();
+        headers.insert(ENVELOPE_HEADER, HeaderValue::from_str(&header).unwrap());
+        (headers, seal(key, &EnvelopeScope::Message, body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_open_and_seal_response() {
+        let provider = Arc::new(LocalKeyProvider::new().with_random_key("k"));
+        let key = provider.generate_data_key("k").await.unwrap();
+        let opener = EnvelopeOpener::new(EnvelopeDecryption::new(provider));
+
+        let (headers, body) = sealed_request(&key, b"secret");
+        let (plaintext, opened_key) = opener.open(&headers, body).await.unwrap();
+        assert_eq!(plaintext, Bytes::from_static(b"secret"));
+        assert_eq!(opener.keys.lock().unwrap().len(), 1);
+
+        let (header, sealed) = opener.seal_response(&opened_key, &Bytes::from_static(b"reply")).unwrap().unwrap();
+        let header = EnvelopeHeader::from_header_value(header.to_str().unwrap()).unwrap();
+        assert_eq!(open(&key, &header.scope, &sealed).unwrap(), Bytes::from_static(b"reply"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_foreign_keys() {
+        let provider = Arc::new(LocalKeyProvider::new().with_random_key("k"));
+        let other = LocalKeyProvider::new().with_random_key("k");
+        let key = other.generate_data_key("k").await.unwrap();
+        let opener = EnvelopeOpener::new(EnvelopeDecryption::new(provider).encrypt_responses(false));
+
+        let (headers, body) = sealed_request(&key, b"secret");
+        let (status, _) = opener.open(&headers, body).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(opener.seal_response(&key, &Bytes::new()).is_none());
+    }
+}
@@ -0,0 +1,296 @@
+//! Credit-based flow control of streaming responses
+//!
+//! A client that sends [`FLOW_CONTROL_HEADER`] with a streaming request
+//! grants the server an initial number of messages. With flow control
+//! enabled, the server echoes the header, sends DATA frames only while it
+//! holds credits and otherwise waits for the client to post CREDIT frames
+//! to [`quill_core::FLOW_CREDIT_PATH`]. Terminal frames (END_STREAM,
+//! CANCEL) are never held back.
+//!
+//! Requests without the header are streamed as before, so clients and
+//! servers that do not support flow control keep working.
+//!
+//! With tensor flow control enabled, a client calling a method registered
+//! with [`crate::RpcRouter::register_tensor_streaming`] may also ask for a
+//! byte window (`bytes=<n>`): messages carrying TENSOR_PAYLOAD frames are then
+//! sent only while their payload fits the bytes the client has granted
+//! (see [`quill_core::tensor_payload_cost`]), and byte grants arrive in
+//! the same CREDIT frames. The server lowers windows above its configured
+//! maximum and echoes the window it enforces. Other methods ignore the byte
+//! window and never echo it, so their messages are not mistaken for tensor
+//! payloads.
+
+use crate::BoxFrameStream;
+use quill_core::{
+    tensor_payload_cost, CreditTracker, FlowControlHeader, TensorCreditTracker, FLOW_CONTROL_HEADER,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio_stream::StreamExt;
+
+/// Credits a client has granted to one response stream
+struct CreditWindow {
+    credits: Option<CreditTracker>,
+    /// Tensor payload bytes, and the size of the window
+    bytes: Option<(TensorCreditTracker, u64)>,
+    notify: Notify,
+}
+
+impl CreditWindow {
+    /// Wait until the client's windows admit `message`, and consume from them
+    async fn acquire(&self, message: &[u8]) {
+        if let Some(credits) = &self.credits {
+            self.wait_for(|| credits.try_consume()).await;
+        }
+        if let Some((bytes, window)) = &self.bytes {
+            if let Some(cost) = tensor_payload_cost(message, *window) {
+                self.wait_for(|| bytes.try_consume(cost)).await;
+            }
+        }
+    }
+
+    async fn wait_for(&self, mut try_consume: impl FnMut() -> bool) {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if try_consume() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    fn grant(&self, credits: u32, bytes: u64) {
+        if let Some(tracker) = &self.credits {
+            tracker.grant(credits);
+        }
+        if let Some((tracker, _)) = &self.bytes {
+            tracker.grant(bytes);
+        }
+        self.notify.notify_waiters();
+    }
+}
+
+/// Credit windows of the flow-controlled streams in progress, by stream id
+pub(crate) struct FlowControlRegistry {
+    windows: Mutex<HashMap<String, Arc<CreditWindow>>>,
+    /// Whether message credits are enforced
+    messages: bool,
+    /// Largest tensor byte window, if byte windows are enforced
+    max_tensor_window: Option<u64>,
+}
+
+impl FlowControlRegistry {
+    /// Create a registry enforcing message credits and/or tensor byte windows
+    pub(crate) fn new(messages: bool, max_tensor_window: Option<u64>) -> Self {
+        Self {
+            windows: Mutex::default(),
+            messages,
+            max_tensor_window,
+        }
+    }
+
+    /// Whether message credits are enforced
+    pub(crate) fn messages(&self) -> bool {
+        self.messages
+    }
+
+    /// Largest tensor byte window, if byte windows are enforced
+    pub(crate) fn max_tensor_window(&self) -> Option<u64> {
+        self.max_tensor_window
+    }
+
+    /// Parse the flow control header of a streaming request, keeping the windows enforced here
+    ///
+    /// Byte windows are only kept for `tensor` methods.
+    pub(crate) fn requested(&self, headers: &http::HeaderMap, tensor: bool) -> Option<FlowControlHeader> {
+        let mut header = headers
+            .get(FLOW_CONTROL_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(FlowControlHeader::from_header_value)?;
+        header.credits = header.credits.filter(|_| self.messages);
+        header.bytes = header
+            .bytes
+            .filter(|bytes| tensor && *bytes > 0)
+            .zip(self.max_tensor_window)
+            .map(|(bytes, max)| bytes.min(max.max(1)));
+        (header.credits.is_some() || header.bytes.is_some()).then_some(header)
+    }
+
+    /// Hold back DATA frames of `frames` until the client has granted credits
+    pub(crate) fn gate(
+        self: &Arc<Self>,
+        header: &FlowControlHeader,
+        frames: BoxFrameStream,
+    ) -> BoxFrameStream {
+        let window = Arc::new(CreditWindow {
+            credits: header.credits.map(CreditTracker::new),
+            // Sending only waits for budget, so the water marks are the whole window
+            bytes: header
+                .bytes
+                .map(|bytes| (TensorCreditTracker::with_settings(bytes, bytes, 0), bytes)),
+            notify: Notify::new(),
+        });
+        self.windows
+            .lock()
+            .unwrap()
+            .insert(header.id.clone(), Arc::clone(&window));
+        let registration = Registration {
+            registry: Arc::clone(self),
+            id: header.id.clone(),
+            window,
+        };
+
+        Box::pin(futures_util::stream::unfold(
+            (frames, registration),
+            |(mut frames, registration)| async move {
+                let frame = frames.next().await?;
+                if let Ok(frame) = &frame {
+                    if frame.flags.is_data() {
+                        registration.window.acquire(&frame.payload).await;
+                    }
+                }
+                Some((frame, (frames, registration)))
+            },
+        ))
+    }
+
+    /// Grant credits and tensor bytes to a stream; false if no such stream is in progress
+    pub(crate) fn grant(&self, id: &str, credits: u32, bytes: u64) -> bool {
+        let window = self.windows.lock().unwrap().get(id).cloned();
+        match window {
+            Some(window) => {
+                window.grant(credits, bytes);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Removes a stream's window once its response stream is dropped
+struct Registration {
+    registry: Arc<FlowControlRegistry>,
+    id: String,
+    window: Arc<CreditWindow>,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let mut windows = self.registry.windows.lock().unwrap();
+        // A later stream may have reused the id
+        if windows.get(&self.id).is_some_and(|window| Arc::ptr_eq(window, &self.window)) {
+            windows.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use quill_core::Frame;
+    use std::time::Duration;
+
+    fn header(credits: u32) -> FlowControlHeader {
+        FlowControlHeader {
+            id: "stream-1".to_string(),
+            credits: Some(credits),
+            bytes: None,
+        }
+    }
+
+    fn registry() -> Arc<FlowControlRegistry> {
+        Arc::new(FlowControlRegistry::new(true, Some(1024)))
+    }
+
+    fn data_frames(count: usize) -> BoxFrameStream {
+        let frames: Vec<_> = (0..count)
+            .map(|_| Ok(Frame::data(Bytes::from_static(b"x"))))
+            .chain(std::iter::once(Ok(Frame::end_stream())))
+            .collect();
+        Box::pin(tokio_stream::iter(frames))
+    }
+
+    #[tokio::test]
+    async fn test_gate_waits_for_grants() {
+        let registry = registry();
+        let mut frames = registry.gate(&header(2), data_frames(3));
+
+        assert!(frames.next().await.unwrap().unwrap().flags.is_data());
+        assert!(frames.next().await.unwrap().unwrap().flags.is_data());
+        assert!(tokio::time::timeout(Duration::from_millis(50), frames.next()).await.is_err());
+
+        assert!(registry.grant("stream-1", 1, 0));
+        assert!(frames.next().await.unwrap().unwrap().flags.is_data());
+        // END_STREAM needs no credit
+        assert!(frames.next().await.unwrap().unwrap().flags.is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn test_window_removed_with_stream() {
+        let registry = registry();
+        let frames = registry.gate(&header(1), data_frames(1));
+        assert!(registry.grant("stream-1", 1, 0));
+
+        drop(frames);
+        assert!(!registry.grant("stream-1", 1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_gate_waits_for_tensor_bytes() {
+        let payload = |len: u32| {
+            let mut message = vec![0x11, 0, 0, 0, 0];
+            message.extend_from_slice(&len.to_be_bytes());
+            message.resize(9 + len as usize, 0);
+            Ok(Frame::data(Bytes::from(message)))
+        };
+        let frames: Vec<_> = vec![
+            Ok(Frame::data(Bytes::from_static(b"meta"))),
+            payload(400),
+            payload(400),
+            payload(400),
+            Ok(Frame::end_stream()),
+        ];
+        let registry = registry();
+        let header = FlowControlHeader {
+            id: "stream-1".to_string(),
+            credits: None,
+            bytes: Some(1000),
+        };
+        let mut frames = registry.gate(&header, Box::pin(tokio_stream::iter(frames)));
+
+        // Messages other than payloads cost nothing
+        for _ in 0..3 {
+            assert!(frames.next().await.unwrap().unwrap().flags.is_data());
+        }
+        assert!(tokio::time::timeout(Duration::from_millis(50), frames.next()).await.is_err());
+
+        assert!(registry.grant("stream-1", 0, 200));
+        assert!(frames.next().await.unwrap().unwrap().flags.is_data());
+        assert!(frames.next().await.unwrap().unwrap().flags.is_end_stream());
+    }
+
+    #[test]
+    fn test_requested_keeps_enforced_windows() {
+        let registry = registry();
+        let mut headers = http::HeaderMap::new();
+        headers.insert(FLOW_CONTROL_HEADER, "id=abc".parse().unwrap());
+        assert!(registry.requested(&headers, true).is_none());
+
+        headers.insert(FLOW_CONTROL_HEADER, "id=abc; credits=4".parse().unwrap());
+        assert_eq!(registry.requested(&headers, true).unwrap().credits, Some(4));
+
+        // Byte windows are capped at the configured maximum
+        headers.insert(FLOW_CONTROL_HEADER, "id=abc; bytes=4096".parse().unwrap());
+        assert_eq!(registry.requested(&headers, true).unwrap().bytes, Some(1024));
+
+        let messages_only = FlowControlRegistry::new(true, None);
+        assert!(messages_only.requested(&headers, true).is_none());
+
+        // Methods other than tensor streams never get a byte window
+        assert!(registry.requested(&headers, false).is_none());
+    }
+}
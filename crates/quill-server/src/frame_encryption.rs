@@ -0,0 +1,132 @@
+//! Server-side frame encryption
+//!
+//! This module provides:
+//! - The shared keys a server accepts for frame encryption
+//! - Opening of encrypted request streams and sealing of response streams
+//!
+//! A request carrying the `quill-frame-encryption` header names one of the
+//! configured keys. Its request frames are opened before the handler reads
+//! them and the frames of a streaming response are sealed after the handler
+//! produced them, so handlers always see plaintext. The header is echoed on
+//! sealed streaming responses. Unary bodies are not framed; use
+//! [envelope encryption](crate::envelope) for them. See
+//! [`quill_core::frame_encryption`] for the wire format.
+
+use crate::BoxFrameStream;
+use http::{HeaderMap, HeaderValue};
+use quill_core::{
+    FrameCipher, FrameDirection, FrameEncryptionError, FrameEncryptionHeader, FrameKey, QuillError,
+    FRAME_ENCRYPTION_HEADER,
+};
+use std::collections::HashMap;
+use std::fmt;
+use tokio_stream::StreamExt;
+
+/// Shared keys accepted for frame encryption, by key id
+#[derive(Clone, Default)]
+pub struct FrameEncryption {
+    keys: HashMap<String, FrameKey>,
+}
+
+impl FrameEncryption {
+    /// Create a configuration without keys
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept streams encrypted with `key`
+    pub fn with_key(mut self, key: FrameKey) -> Self {
+        self.keys.insert(key.id().to_string(), key);
+        self
+    }
+
+    /// Ciphers for the stream of a request asking for frame encryption
+    ///
+    /// Returns `None` for plaintext requests, or the detail to send if the
+    /// header is invalid or names an unknown key.
+    pub(crate) fn accept(&self, headers: &HeaderMap) -> Result<Option<StreamEncryption>, String> {
+        let Some(value) = headers.get(FRAME_ENCRYPTION_HEADER) else {
+            return Ok(None);
+        };
+        let header = value
+            .to_str()
+            .map_err(|_| "Invalid frame encryption header".to_string())
+            .and_then(|value| FrameEncryptionHeader::from_header_value(value).map_err(|e| e.to_string()))?;
+        let key = self
+            .keys
+            .get(&header.key_id)
+            .ok_or_else(|| FrameEncryptionError::UnknownKey(header.key_id.clone()).to_string())?;
+        Ok(Some(StreamEncryption {
+            request: FrameCipher::new(key, &header, FrameDirection::Request),
+            response: ResponseEncryption {
+                header: value.clone(),
+                cipher: FrameCipher::new(key, &header, FrameDirection::Response),
+            },
+        }))
+    }
+}
+
+impl fmt::Debug for FrameEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameEncryption")
+            .field("keys", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Ciphers of one encrypted stream
+pub(crate) struct StreamEncryption {
+    /// Opens the request frames
+    pub(crate) request: FrameCipher,
+    /// Seals the response frames
+    pub(crate) response: ResponseEncryption,
+}
+
+/// Seals the frames of a streaming response
+pub(crate) struct ResponseEncryption {
+    header: HeaderValue,
+    cipher: FrameCipher,
+}
+
+impl ResponseEncryption {
+    /// Seal `frames`, returning the header value to echo and the sealed stream
+    pub(crate) fn seal(self, frames: BoxFrameStream) -> (HeaderValue, BoxFrameStream) {
+        let cipher = self.cipher;
+        let sealed = frames.map(move |frame| frame.and_then(|frame| cipher.seal(frame).map_err(QuillError::from)));
+        (self.header, Box::pin(sealed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use quill_core::Frame;
+
+    #[tokio::test]
+    async fn test_accepts_known_keys() {
+        let key = FrameKey::random("k1").unwrap();
+        let config = FrameEncryption::new().with_key(key.clone());
+        let header = FrameEncryptionHeader::new(&key).unwrap();
+
+        let mut headers = HeaderMap::new();
+        assert!(config.accept(&headers).unwrap().is_none());
+
+        headers.insert(FRAME_ENCRYPTION_HEADER, header.to_header_value().parse().unwrap());
+        let stream = config.accept(&headers).unwrap().unwrap();
+
+        // The client's response cipher opens what the server sealed
+        let frames: BoxFrameStream =
+            Box::pin(tokio_stream::iter(vec![Ok(Frame::data(Bytes::from_static(b"hi")))]));
+        let (echo, mut sealed) = stream.response.seal(frames);
+        assert_eq!(echo, header.to_header_value());
+        let client = FrameCipher::new(&key, &header, FrameDirection::Response);
+        let opened = client.open(sealed.next().await.unwrap().unwrap()).unwrap();
+        assert_eq!(opened.payload, Bytes::from_static(b"hi"));
+
+        let other = FrameKey::random("k2").unwrap();
+        let header = FrameEncryptionHeader::new(&other).unwrap();
+        headers.insert(FRAME_ENCRYPTION_HEADER, header.to_header_value().parse().unwrap());
+        assert!(matches!(config.accept(&headers), Err(detail) if detail.contains("k2")));
+    }
+}
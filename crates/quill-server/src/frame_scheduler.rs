@@ -0,0 +1,475 @@
+//! Fair scheduling of response writes across the streams of a connection
+//!
+//! This module provides:
+//! - A default write quantum with per-method overrides
+//! - A per-connection scheduler taking turns between streams with data to send
+//! - Starvation counters across all connections
+//!
+//! Without scheduling, one stream writing large chunks (a tensor, say)
+//! reserves the connection's whole HTTP/2 flow-control window for each
+//! chunk, and every other stream on the connection waits until it has been
+//! flushed. With scheduling enabled, response chunks are split into pieces
+//! of at most the stream's quantum, and the streams of a connection take
+//! turns: a stream may run at most one quantum ahead of the least served
+//! stream still waiting to write. Streams therefore share the connection in
+//! proportion to their quanta, and a method can be weighted by giving it a
+//! larger quantum.
+//!
+//! A stream that stops taking its turn (its own consumer is not reading)
+//! holds the others back for at most the stall timeout. Splitting is
+//! zero-copy and keeps frame boundaries intact for the peer's parser.
+
+use crate::overrides::MethodOverrides;
+use bytes::Bytes;
+use quill_core::QuillError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+use tokio::time::Sleep;
+use tokio_stream::Stream;
+
+/// Default bytes a stream writes per turn
+pub const DEFAULT_WRITE_QUANTUM: usize = 64 * 1024;
+
+/// Default time a stream not taking its turn holds back the others
+pub const DEFAULT_STALL_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Default wait after which a chunk counts as starved
+pub const DEFAULT_STARVATION_THRESHOLD: Duration = Duration::from_millis(250);
+
+/// Response chunks of one stream, before they become body frames
+pub(crate) type ChunkStream = Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>;
+
+/// Frame scheduling configuration
+#[derive(Debug, Clone)]
+pub struct FrameSchedulingConfig {
+    /// Bytes a stream writes per turn, for methods without their own quantum
+    pub quantum: usize,
+    /// Time a stream not taking its turn holds back the others
+    pub stall_timeout: Duration,
+    /// Wait after which a chunk counts as starved
+    pub starvation_threshold: Duration,
+    quanta: MethodOverrides<usize>,
+}
+
+impl Default for FrameSchedulingConfig {
+    fn default() -> Self {
+        Self {
+            quantum: DEFAULT_WRITE_QUANTUM,
+            stall_timeout: DEFAULT_STALL_TIMEOUT,
+            starvation_threshold: DEFAULT_STARVATION_THRESHOLD,
+            quanta: MethodOverrides::default(),
+        }
+    }
+}
+
+impl FrameSchedulingConfig {
+    /// Schedule with the default quantum
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the bytes a stream writes per turn
+    pub fn quantum(mut self, bytes: usize) -> Self {
+        self.quantum = bytes.max(1);
+        self
+    }
+
+    /// Set the quantum of a method (`pkg.Service/Method`) or a whole service (`pkg.Service`)
+    ///
+    /// Under contention, a stream with twice the quantum gets twice the
+    /// share of the connection.
+    pub fn method_quantum(mut self, path: impl Into<String>, bytes: usize) -> Self {
+        self.quanta.insert(path, bytes.max(1));
+        self
+    }
+
+    /// Set how long a stream not taking its turn holds back the others
+    pub fn stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = timeout;
+        self
+    }
+
+    /// Set the wait after which a chunk counts as starved
+    pub fn starvation_threshold(mut self, threshold: Duration) -> Self {
+        self.starvation_threshold = threshold;
+        self
+    }
+
+    /// Quantum of `method`
+    fn quantum_of(&self, method: &str) -> usize {
+        self.quanta.get(method).copied().unwrap_or(self.quantum)
+    }
+}
+
+/// Frame scheduling counters across all connections
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameSchedulerStats {
+    /// Streams currently scheduled
+    pub active_streams: usize,
+    /// Chunks written
+    pub chunks: u64,
+    /// Times a stream gave up its turn to a less served one
+    pub deferrals: u64,
+    /// Chunks that waited longer than the starvation threshold
+    pub starved: u64,
+    /// Longest wait of a chunk, in milliseconds
+    pub max_wait_ms: u64,
+}
+
+/// Configuration and counters shared by the schedulers of all connections
+pub(crate) struct FrameScheduling {
+    config: FrameSchedulingConfig,
+    active_streams: AtomicUsize,
+    chunks: AtomicU64,
+    deferrals: AtomicU64,
+    starved: AtomicU64,
+    max_wait_ms: AtomicU64,
+}
+
+impl FrameScheduling {
+    pub(crate) fn new(config: FrameSchedulingConfig) -> Self {
+        Self {
+            config,
+            active_streams: AtomicUsize::new(0),
+            chunks: AtomicU64::new(0),
+            deferrals: AtomicU64::new(0),
+            starved: AtomicU64::new(0),
+            max_wait_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Scheduler for the streams of one new connection
+    pub(crate) fn connection(self: &Arc<Self>) -> ConnectionScheduler {
+        ConnectionScheduler {
+            shared: Arc::clone(self),
+            state: Arc::new(Mutex::new(ConnectionState::default())),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> FrameSchedulerStats {
+        FrameSchedulerStats {
+            active_streams: self.active_streams.load(Ordering::Relaxed),
+            chunks: self.chunks.load(Ordering::Relaxed),
+            deferrals: self.deferrals.load(Ordering::Relaxed),
+            starved: self.starved.load(Ordering::Relaxed),
+            max_wait_ms: self.max_wait_ms.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record_wait(&self, method: &str, wait: Duration) {
+        self.chunks.fetch_add(1, Ordering::Relaxed);
+        let wait_ms = wait.as_millis() as u64;
+        self.max_wait_ms.fetch_max(wait_ms, Ordering::Relaxed);
+        if wait >= self.config.starvation_threshold {
+            self.starved.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(method = %method, wait_ms, "Response stream starved for write turns");
+        }
+    }
+}
+
+/// Turn-taking state of one stream
+struct StreamTurns {
+    /// Bytes written, in quanta of the stream
+    served: f64,
+    /// Whether the stream has a chunk waiting to be written
+    backlogged: bool,
+    /// Last time the stream asked for a turn
+    last_poll: Instant,
+    /// Woken once the stream may be allowed to write again
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct ConnectionState {
+    streams: HashMap<u64, StreamTurns>,
+    next_id: u64,
+    /// Service of the latest turn, where streams that start writing join
+    clock: f64,
+}
+
+impl ConnectionState {
+    /// Least service among backlogged streams still taking their turns
+    fn least_served(&self, now: Instant, stall_timeout: Duration) -> Option<f64> {
+        self.streams
+            .values()
+            .filter(|turns| turns.backlogged && now.duration_since(turns.last_poll) < stall_timeout)
+            .map(|turns| turns.served)
+            .reduce(f64::min)
+    }
+
+    fn wake_deferred(&mut self) {
+        for turns in self.streams.values_mut() {
+            if let Some(waker) = turns.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Takes turns between the response streams of one connection
+#[derive(Clone)]
+pub(crate) struct ConnectionScheduler {
+    shared: Arc<FrameScheduling>,
+    state: Arc<Mutex<ConnectionState>>,
+}
+
+impl ConnectionScheduler {
+    /// Write the chunks of a response to `method` in turns with the connection's other streams
+    pub(crate) fn schedule(&self, method: &str, chunks: ChunkStream) -> ChunkStream {
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            let clock = state.clock;
+            state.streams.insert(
+                id,
+                StreamTurns {
+                    served: clock,
+                    backlogged: false,
+                    last_poll: Instant::now(),
+                    waker: None,
+                },
+            );
+            id
+        };
+        self.shared.active_streams.fetch_add(1, Ordering::Relaxed);
+        Box::pin(ScheduledChunks {
+            chunks,
+            scheduler: self.clone(),
+            id,
+            method: method.to_string(),
+            quantum: self.shared.config.quantum_of(method),
+            pending: None,
+            stall: None,
+        })
+    }
+
+    /// Ask for a turn to write `len` bytes, given the stream's `quantum`
+    fn poll_turn(&self, id: u64, len: usize, quantum: usize, cx: &mut Context<'_>) -> Poll<()> {
+        let stall_timeout = self.shared.config.stall_timeout;
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let clock = state.clock;
+        let Some(turns) = state.streams.get_mut(&id) else {
+            return Poll::Ready(());
+        };
+        if !turns.backlogged {
+            // Streams that were idle join at the current turn rather than
+            // catching up on the service they did not ask for
+            turns.backlogged = true;
+            turns.served = turns.served.max(clock);
+        }
+        turns.last_poll = now;
+        let served = turns.served;
+
+        let least = state.least_served(now, stall_timeout).unwrap_or(served);
+        if served >= least + 1.0 {
+            let turns = state.streams.get_mut(&id).expect("stream is scheduled");
+            if turns.waker.is_none() {
+                self.shared.deferrals.fetch_add(1, Ordering::Relaxed);
+            }
+            turns.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        state.clock = state.clock.max(served);
+        let turns = state.streams.get_mut(&id).expect("stream is scheduled");
+        turns.served += len as f64 / quantum as f64;
+        turns.waker = None;
+        state.wake_deferred();
+        Poll::Ready(())
+    }
+
+    /// Note that the stream has nothing left to write for now
+    fn idle(&self, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(turns) = state.streams.get_mut(&id) {
+            if turns.backlogged {
+                turns.backlogged = false;
+                state.wake_deferred();
+            }
+        }
+    }
+
+    fn remove(&self, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        if state.streams.remove(&id).is_some() {
+            state.wake_deferred();
+        }
+        self.shared.active_streams.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Chunks of one stream, split to its quantum and written in turns
+struct ScheduledChunks {
+    chunks: ChunkStream,
+    scheduler: ConnectionScheduler,
+    id: u64,
+    method: String,
+    quantum: usize,
+    /// Chunk waiting for a turn, and since when
+    pending: Option<(Bytes, Instant)>,
+    /// Wakes a deferred stream to re-check for stalled streams ahead of it
+    stall: Option<Pin<Box<Sleep>>>,
+}
+
+impl Stream for ScheduledChunks {
+    type Item = Result<Bytes, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if this.pending.is_none() {
+                match this.chunks.as_mut().poll_next(cx) {
+                    Poll::Ready(Some(Ok(chunk))) if chunk.is_empty() => continue,
+                    Poll::Ready(Some(Ok(chunk))) => this.pending = Some((chunk, Instant::now())),
+                    Poll::Ready(other) => {
+                        this.scheduler.idle(this.id);
+                        return Poll::Ready(other);
+                    }
+                    Poll::Pending => {
+                        this.scheduler.idle(this.id);
+                        return Poll::Pending;
+                    }
+                }
+            }
+
+            let (chunk, _) = this.pending.as_ref().expect("a chunk is pending");
+            let len = chunk.len().min(this.quantum);
+            if this.scheduler.poll_turn(this.id, len, this.quantum, cx).is_pending() {
+                let stall_timeout = this.scheduler.shared.config.stall_timeout;
+                let stall = this
+                    .stall
+                    .get_or_insert_with(|| Box::pin(tokio::time::sleep(stall_timeout)));
+                if stall.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                // The streams ahead may have stalled; check again once they count as such
+                this.stall = None;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            this.stall = None;
+
+            let (mut chunk, since) = this.pending.take().expect("a chunk is pending");
+            let piece = chunk.split_to(len);
+            this.scheduler.shared.record_wait(&this.method, since.elapsed());
+            if !chunk.is_empty() {
+                // The rest is ready now and waits for the next turn
+                this.pending = Some((chunk, Instant::now()));
+            }
+            return Poll::Ready(Some(Ok(piece)));
+        }
+    }
+}
+
+impl Drop for ScheduledChunks {
+    fn drop(&mut self) {
+        self.scheduler.remove(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::task::noop_waker;
+
+    fn scheduling(config: FrameSchedulingConfig) -> ConnectionScheduler {
+        Arc::new(FrameScheduling::new(config)).connection()
+    }
+
+    fn chunks(sizes: &[usize]) -> ChunkStream {
+        let chunks: Vec<_> = sizes.iter().map(|&size| Ok(Bytes::from(vec![0u8; size]))).collect();
+        Box::pin(tokio_stream::iter(chunks))
+    }
+
+    fn poll(stream: &mut ChunkStream) -> Poll<Option<usize>> {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        stream.as_mut().poll_next(&mut cx).map(|chunk| chunk.map(|chunk| chunk.unwrap().len()))
+    }
+
+    #[test]
+    fn test_large_chunks_are_split_to_the_quantum() {
+        let connection = scheduling(FrameSchedulingConfig::new().quantum(4));
+        let mut stream = connection.schedule("test.Svc/Big", chunks(&[10, 3]));
+
+        let mut pieces = Vec::new();
+        while let Poll::Ready(Some(len)) = poll(&mut stream) {
+            pieces.push(len);
+        }
+        assert_eq!(pieces, vec![4, 4, 2, 3]);
+        assert_eq!(connection.shared.stats().chunks, 4);
+    }
+
+    #[tokio::test]
+    async fn test_streams_take_turns() {
+        let connection = scheduling(FrameSchedulingConfig::new().quantum(4));
+        let mut big = connection.schedule("test.Svc/Big", chunks(&[40]));
+        let mut small = connection.schedule("test.Svc/Small", chunks(&[40]));
+
+        // Both streams are backlogged once they hold a chunk
+        assert_eq!(poll(&mut big), Poll::Ready(Some(4)));
+        assert_eq!(poll(&mut small), Poll::Ready(Some(4)));
+
+        // A stream a turn ahead waits for the other
+        assert_eq!(poll(&mut big), Poll::Ready(Some(4)));
+        assert_eq!(poll(&mut big), Poll::Pending);
+        assert_eq!(poll(&mut small), Poll::Ready(Some(4)));
+        assert_eq!(poll(&mut big), Poll::Ready(Some(4)));
+        assert_eq!(connection.shared.stats().deferrals, 1);
+
+        // Ending a stream lets the other write on its own
+        drop(small);
+        for _ in 0..7 {
+            assert_eq!(poll(&mut big), Poll::Ready(Some(4)));
+        }
+        assert_eq!(poll(&mut big), Poll::Ready(None));
+        drop(big);
+        assert_eq!(connection.shared.stats().active_streams, 0);
+    }
+
+    #[tokio::test]
+    async fn test_larger_quanta_get_larger_shares() {
+        let connection = scheduling(FrameSchedulingConfig::new().quantum(4).method_quantum("test.Svc/Heavy", 8));
+        let mut heavy = connection.schedule("test.Svc/Heavy", chunks(&[80]));
+        let mut light = connection.schedule("test.Svc/Light", chunks(&[80]));
+
+        let (mut heavy_bytes, mut light_bytes) = (0, 0);
+        for _ in 0..10 {
+            if let Poll::Ready(Some(len)) = poll(&mut heavy) {
+                heavy_bytes += len;
+            }
+            if let Poll::Ready(Some(len)) = poll(&mut light) {
+                light_bytes += len;
+            }
+        }
+        assert_eq!(heavy_bytes, 2 * light_bytes);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_stops_holding_back_others() {
+        let connection = scheduling(
+            FrameSchedulingConfig::new()
+                .quantum(4)
+                .stall_timeout(Duration::from_millis(20)),
+        );
+        let mut busy = connection.schedule("test.Svc/Busy", chunks(&[40]));
+        let mut stalled = connection.schedule("test.Svc/Stalled", chunks(&[40]));
+
+        assert_eq!(poll(&mut stalled), Poll::Ready(Some(4)));
+        assert_eq!(poll(&mut busy), Poll::Ready(Some(4)));
+        assert_eq!(poll(&mut busy), Poll::Ready(Some(4)));
+        assert_eq!(poll(&mut busy), Poll::Pending);
+
+        // The stalled stream is never polled again, so after the timeout
+        // the busy one writes without it
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(poll(&mut busy), Poll::Ready(Some(4)));
+    }
+}
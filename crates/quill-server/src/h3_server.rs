@@ -8,9 +8,11 @@ use bytes::Bytes;
 #[cfg(feature = "http3")]
 use http::{Request, Response, StatusCode};
 #[cfg(feature = "http3")]
-use quill_core::{ProblemDetails, QuillError};
+use futures_util::TryStreamExt;
 #[cfg(feature = "http3")]
-use quill_transport::{BoxFuture, H3Service};
+use quill_core::QuillError;
+#[cfg(feature = "http3")]
+use quill_transport::{BoxFuture, ConfigSource, H3Body, H3Service, H3TlsConfig, HyperError};
 #[cfg(feature = "http3")]
 use std::future::Future;
 #[cfg(feature = "http3")]
@@ -18,7 +20,9 @@ use std::net::SocketAddr;
 #[cfg(feature = "http3")]
 use std::sync::Arc;
 #[cfg(feature = "http3")]
-use tracing::{debug, info, instrument};
+use http_body_util::{BodyExt, Full};
+#[cfg(feature = "http3")]
+use tracing::{debug, error, info, instrument};
 
 #[cfg(feature = "http3")]
 use crate::router::RpcRouter;
@@ -39,6 +43,20 @@ pub struct H3ServerConfig {
     pub idle_timeout_ms: u64,
     /// Keep-alive interval in milliseconds
     pub keep_alive_interval_ms: u64,
+    /// Server certificates; self-signed for `localhost` if unset
+    pub tls: H3TlsConfig,
+    /// Replacement certificates, swapped in as they arrive
+    pub tls_source: Option<ConfigSource<H3TlsConfig>>,
+    /// Kernel receive buffer size of the UDP socket (None = OS default)
+    pub udp_recv_buffer_size: Option<usize>,
+    /// Kernel send buffer size of the UDP socket (None = OS default)
+    pub udp_send_buffer_size: Option<usize>,
+    /// Send several packets per syscall with GSO where supported
+    pub enable_segmentation_offload: bool,
+    /// Largest UDP payload accepted, which sizes the receive buffers
+    pub max_udp_payload_size: u16,
+    /// Largest request body read; larger requests get `413`
+    pub max_request_body_size: usize,
 }
 
 #[cfg(feature = "http3")]
@@ -50,6 +68,13 @@ impl Default for H3ServerConfig {
             max_concurrent_streams: 100,
             idle_timeout_ms: 60000,
             keep_alive_interval_ms: 30000,
+            tls: H3TlsConfig::default(),
+            tls_source: None,
+            udp_recv_buffer_size: None,
+            udp_send_buffer_size: None,
+            enable_segmentation_offload: true,
+            max_udp_payload_size: 1472,
+            max_request_body_size: quill_core::framing::MAX_FRAME_SIZE,
         }
     }
 }
@@ -106,17 +131,37 @@ impl QuillH3Server {
             max_datagram_size: 65536,
             keep_alive_interval_ms: self.config.keep_alive_interval_ms,
             idle_timeout_ms: self.config.idle_timeout_ms,
+            enable_datagram_checksum: false,
+            enable_segmentation_offload: self.config.enable_segmentation_offload,
+            max_udp_payload_size: self.config.max_udp_payload_size,
+            max_request_body_size: self.config.max_request_body_size,
         };
 
         // Create H3 server
-        let h3_server = quill_transport::H3ServerBuilder::new(self.bind_addr)
+        let mut h3_builder = quill_transport::H3ServerBuilder::new(self.bind_addr)
             .enable_zero_rtt(transport_config.enable_zero_rtt)
             .enable_datagrams(transport_config.enable_datagrams)
             .max_concurrent_streams(transport_config.max_concurrent_streams)
             .idle_timeout_ms(transport_config.idle_timeout_ms)
+            .enable_segmentation_offload(transport_config.enable_segmentation_offload)
+            .max_udp_payload_size(transport_config.max_udp_payload_size)
+            .max_request_body_size(transport_config.max_request_body_size)
+            .tls(self.config.tls.clone());
+        if let Some(source) = self.config.tls_source.clone() {
+            h3_builder = h3_builder.tls_source(source);
+        }
+        if let Some(size) = self.config.udp_recv_buffer_size {
+            h3_builder = h3_builder.udp_recv_buffer_size(size);
+        }
+        if let Some(size) = self.config.udp_send_buffer_size {
+            h3_builder = h3_builder.udp_send_buffer_size(size);
+        }
+        let h3_server = h3_builder
             .build()
             .map_err(|e| QuillError::Transport(format!("Failed to create HTTP/3 server: {}", e)))?;
 
+        self.router.start_scheduler();
+
         // Create the service
         let service = QuillH3Service {
             router: self.router,
@@ -139,45 +184,19 @@ struct QuillH3Service {
 
 #[cfg(feature = "http3")]
 impl H3Service for QuillH3Service {
-    fn call(&self, req: Request<()>) -> BoxFuture<Result<Response<Bytes>, StatusCode>> {
-        let _router = Arc::clone(&self.router);
+    fn call(&self, req: Request<Bytes>) -> BoxFuture<Result<Response<H3Body>, StatusCode>> {
+        let router = Arc::clone(&self.router);
 
         Box::pin(async move {
-            // Parse the path
-            let path = req.uri().path();
-            let method = req.method();
-
-            debug!("HTTP/3 request: {} {}", method, path);
-
-            // Validate HTTP method
-            if method != http::Method::POST {
-                let pd = ProblemDetails::new(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
-                    .with_detail("Only POST is supported for RPC calls");
-                let json = pd.to_json().unwrap_or_else(|_| "{}".to_string());
-                return Ok(Response::builder()
-                    .status(StatusCode::METHOD_NOT_ALLOWED)
-                    .header("content-type", "application/problem+json")
-                    .body(Bytes::from(json))
-                    .unwrap());
-            }
-
-            // Strip leading slash
-            let _path = path.strip_prefix('/').unwrap_or(path);
-
-            // For HTTP/3 we receive the request body separately, so we create an empty Bytes
-            // The full request/response handling will be done in the transport layer
-            // Here we just validate the route exists
-
-            // Note: In a full implementation, the H3Server would pass the body to this service
-            // For now, we return OK to indicate the route is valid
-            // The actual body handling happens in the transport layer
-
-            // Build response with OK status to indicate route exists
-            Ok(Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "application/proto")
-                .body(Bytes::new())
-                .unwrap())
+            debug!("HTTP/3 request: {} {}", req.method(), req.uri().path());
+
+            // Frames of streaming responses go out as the handler produces them
+            let (parts, body) = router.route(req.map(Full::new)).await.into_parts();
+            let body: H3Body = Box::pin(body.into_data_stream().map_err(|e| {
+                error!("HTTP/3 response failed: {}", e);
+                HyperError::H3Stream(format!("Response body failed: {}", e))
+            }));
+            Ok(Response::from_parts(parts, body))
         })
     }
 }
@@ -231,6 +250,87 @@ impl H3ServerBuilder {
         self
     }
 
+    /// Set the kernel receive and send buffer sizes of the UDP socket
+    pub fn udp_buffer_sizes(mut self, recv: usize, send: usize) -> Self {
+        self.config.udp_recv_buffer_size = Some(recv);
+        self.config.udp_send_buffer_size = Some(send);
+        self
+    }
+
+    /// Send several packets per syscall with GSO where supported (default on)
+    ///
+    /// quinn falls back to one packet per syscall when the host lacks GSO;
+    /// see `quill_transport::UdpOffloadSupport::detect`.
+    pub fn enable_segmentation_offload(mut self, enable: bool) -> Self {
+        self.config.enable_segmentation_offload = enable;
+        self
+    }
+
+    /// Set the largest UDP payload accepted (1200-65527 bytes)
+    ///
+    /// Each receive syscall reads a batch of datagrams into buffers of this
+    /// size; raise it on loopback or jumbo-frame links.
+    pub fn max_udp_payload_size(mut self, size: u16) -> Self {
+        self.config.max_udp_payload_size = size;
+        self
+    }
+
+    /// Set the largest request body read; larger requests get `413`
+    pub fn max_request_body_size(mut self, size: usize) -> Self {
+        self.config.max_request_body_size = size;
+        self
+    }
+
+    /// Set the server certificates
+    pub fn tls(mut self, tls: H3TlsConfig) -> Self {
+        self.config.tls = tls;
+        self
+    }
+
+    /// Swap in new server certificates whenever `source` yields them
+    ///
+    /// Use [`H3TlsConfig::watch`] to follow the configured PEM files.
+    pub fn tls_source(mut self, source: ConfigSource<H3TlsConfig>) -> Self {
+        self.config.tls_source = Some(source);
+        self
+    }
+
+    /// Load the certificate chain from a PEM file
+    pub fn tls_cert_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.tls = self.config.tls.cert_path(path);
+        self
+    }
+
+    /// Load the private key from a PEM file
+    pub fn tls_key_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.tls = self.config.tls.key_path(path);
+        self
+    }
+
+    /// Use a PEM-encoded certificate chain
+    pub fn tls_cert_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.config.tls = self.config.tls.cert_pem(pem);
+        self
+    }
+
+    /// Use a PEM-encoded private key
+    pub fn tls_key_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.config.tls = self.config.tls.key_pem(pem);
+        self
+    }
+
+    /// Require client certificates signed by CAs from a PEM file
+    pub fn tls_client_ca_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.tls = self.config.tls.client_ca_path(path);
+        self
+    }
+
+    /// Require client certificates signed by PEM-encoded CAs
+    pub fn tls_client_ca_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.config.tls = self.config.tls.client_ca_pem(pem);
+        self
+    }
+
     /// Register a unary handler for an RPC method
     pub fn register<F, Fut>(mut self, path: impl Into<String>, handler: F) -> Self
     where
@@ -310,10 +410,20 @@ mod tests {
             max_concurrent_streams: 150,
             idle_timeout_ms: 45000,
             keep_alive_interval_ms: 15000,
+            tls: H3TlsConfig::default().cert_pem("cert").key_pem("key"),
+            tls_source: None,
+            udp_recv_buffer_size: Some(4 << 20),
+            udp_send_buffer_size: None,
+            enable_segmentation_offload: false,
+            max_udp_payload_size: 9000,
+            max_request_body_size: 1 << 20,
         };
 
         let server = QuillH3Server::with_config(RpcRouter::new(), addr, config);
         assert!(server.config.enable_zero_rtt);
+        assert_eq!(server.config.max_udp_payload_size, 9000);
+        assert_eq!(server.config.max_request_body_size, 1 << 20);
         assert_eq!(server.config.max_concurrent_streams, 150);
+        assert!(server.config.tls.has_certificate());
     }
 }
@@ -1,5 +1,6 @@
 //! RPC handler trait
 
+use crate::context::RequestContext;
 use bytes::Bytes;
 use quill_core::QuillError;
 use std::future::Future;
@@ -7,9 +8,12 @@ use std::future::Future;
 /// Trait for RPC handlers
 pub trait RpcHandler: Send + Sync + 'static {
     /// Handle a unary RPC call
+    ///
+    /// `context` carries the caller's deadline and metadata.
     fn handle_unary(
         &self,
         method: &str,
         request: Bytes,
+        context: &RequestContext,
     ) -> impl Future<Output = Result<Bytes, QuillError>> + Send;
 }
@@ -0,0 +1,149 @@
+//! Health checking service
+//!
+//! This module provides:
+//! - A reporter the application sets serving statuses through
+//! - Resolution of the status reported for a service
+//! - Status streams backing the built-in watch RPC
+//!
+//! The server as a whole has a status under the empty service name,
+//! SERVING until set otherwise. Registered services without a status of
+//! their own report the server's status; services the router does not know
+//! are SERVICE_UNKNOWN. An [`ObservabilityCollector`] sets the server's
+//! status from its health checks, see
+//! [`ObservabilityCollector::health_reporter`].
+//!
+//! The wire protocol is described in [`quill_core::health`].
+//!
+//! [`ObservabilityCollector`]: crate::observability::ObservabilityCollector
+//! [`ObservabilityCollector::health_reporter`]: crate::observability::ObservabilityCollector::health_reporter
+
+use quill_core::ServingStatus;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio_stream::Stream;
+
+/// Explicitly set statuses by service name
+type Statuses = HashMap<String, ServingStatus>;
+
+/// Serving statuses reported by the health RPCs
+///
+/// Clones share the same statuses.
+#[derive(Debug, Clone)]
+pub struct HealthReporter {
+    statuses: Arc<watch::Sender<Statuses>>,
+}
+
+impl HealthReporter {
+    /// Create a reporter with the server SERVING
+    pub fn new() -> Self {
+        let statuses = HashMap::from([(String::new(), ServingStatus::Serving)]);
+        Self {
+            statuses: Arc::new(watch::channel(statuses).0),
+        }
+    }
+
+    /// Set the status of `service`, or of the server for an empty name
+    pub fn set_status(&self, service: impl Into<String>, status: ServingStatus) {
+        let service = service.into();
+        self.statuses
+            .send_if_modified(|statuses| statuses.insert(service, status) != Some(status));
+    }
+
+    /// Set `service` SERVING or NOT_SERVING
+    pub fn set_serving(&self, service: impl Into<String>, serving: bool) {
+        let status = if serving {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+        self.set_status(service, status);
+    }
+
+    /// Let `service` report the server's status again
+    pub fn clear_status(&self, service: &str) {
+        self.statuses.send_if_modified(|statuses| statuses.remove(service).is_some());
+    }
+
+    /// Status explicitly set for `service`
+    pub fn status(&self, service: &str) -> Option<ServingStatus> {
+        self.statuses.borrow().get(service).copied()
+    }
+
+    /// Status reported for `service`, which the router serves if `registered`
+    pub(crate) fn resolve(&self, service: &str, registered: bool) -> ServingStatus {
+        resolve(&self.statuses.borrow(), service, registered)
+    }
+
+    /// Status reported for `service` now and after every change to it
+    pub(crate) fn watch(&self, service: String, registered: bool) -> impl Stream<Item = ServingStatus> + Send {
+        let receiver = self.statuses.subscribe();
+        futures_util::stream::unfold((receiver, None), move |(mut receiver, last)| {
+            let service = service.clone();
+            async move {
+                loop {
+                    let status = resolve(&receiver.borrow_and_update(), &service, registered);
+                    if last != Some(status) {
+                        return Some((status, (receiver, Some(status))));
+                    }
+                    // Ends once every reporter is dropped
+                    receiver.changed().await.ok()?;
+                }
+            }
+        })
+    }
+}
+
+impl Default for HealthReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn resolve(statuses: &Statuses, service: &str, registered: bool) -> ServingStatus {
+    if let Some(status) = statuses.get(service) {
+        return *status;
+    }
+    if service.is_empty() || registered {
+        return statuses.get("").copied().unwrap_or(ServingStatus::Unknown);
+    }
+    ServingStatus::ServiceUnknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[test]
+    fn test_services_inherit_server_status() {
+        let health = HealthReporter::new();
+        assert_eq!(health.resolve("", false), ServingStatus::Serving);
+        assert_eq!(health.resolve("echo.v1.Echo", true), ServingStatus::Serving);
+        assert_eq!(health.resolve("missing.v1.Missing", false), ServingStatus::ServiceUnknown);
+
+        health.set_serving("", false);
+        assert_eq!(health.resolve("echo.v1.Echo", true), ServingStatus::NotServing);
+
+        health.set_serving("echo.v1.Echo", true);
+        assert_eq!(health.resolve("echo.v1.Echo", true), ServingStatus::Serving);
+        health.clear_status("echo.v1.Echo");
+        assert_eq!(health.status("echo.v1.Echo"), None);
+        assert_eq!(health.resolve("echo.v1.Echo", true), ServingStatus::NotServing);
+    }
+
+    #[tokio::test]
+    async fn test_watch_sends_changes_only() {
+        let health = HealthReporter::new();
+        let mut watch = Box::pin(health.watch("echo.v1.Echo".to_string(), true));
+        assert_eq!(watch.next().await, Some(ServingStatus::Serving));
+
+        // Changes to other services are not sent
+        health.set_serving("other.v1.Other", false);
+        health.set_serving("echo.v1.Echo", false);
+        assert_eq!(watch.next().await, Some(ServingStatus::NotServing));
+
+        health.set_status("echo.v1.Echo", ServingStatus::Serving);
+        assert_eq!(watch.next().await, Some(ServingStatus::Serving));
+    }
+}
@@ -0,0 +1,443 @@
+//! Idle timeouts for streaming RPCs
+//!
+//! This module provides:
+//! - A default stream idle timeout with per-method overrides
+//! - Cancellation of response streams that deliver no frame in time
+//! - Failure of request streams that receive no frame in time
+//!
+//! A response stream is idle both while the handler produces nothing and
+//! while the peer stops reading, so stalled handlers and abandoned
+//! consumers are caught alike. Once the timeout passes, a watchdog drops
+//! the handler's stream, releasing whatever it holds, and the peer receives
+//! a `408` cancel frame of type [`STREAM_IDLE_TIMEOUT_TYPE`] if it is still
+//! reading. Request streams count only the time the handler spends waiting
+//! for the next message; the handler sees the same problem as an error.
+//!
+//! [`STREAM_IDLE_TIMEOUT_TYPE`]: quill_core::STREAM_IDLE_TIMEOUT_TYPE
+
+use crate::overrides::MethodOverrides;
+use crate::router::RequestStream;
+use crate::BoxFrameStream;
+use quill_core::{Frame, ProblemDetails, QuillError};
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio::time::Sleep;
+use tokio_stream::Stream;
+
+/// Default time without a frame after which a stream is cancelled
+pub const DEFAULT_STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Callback invoked when a stream times out
+pub type StreamIdleListener = Arc<dyn Fn(&StreamIdleEvent) + Send + Sync>;
+
+/// Which half of a call went idle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamSide {
+    /// The client sent no request message
+    Request,
+    /// No response frame was produced or accepted
+    Response,
+}
+
+/// A stream cancelled for being idle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamIdleEvent {
+    /// Method path (e.g. `llm.v1.Generate/Stream`)
+    pub method: String,
+    /// Half of the call that went idle
+    pub side: StreamSide,
+    /// Idle timeout that was exceeded
+    pub idle_timeout: Duration,
+}
+
+/// Stream idle timeout configuration
+#[derive(Clone)]
+pub struct StreamIdleConfig {
+    /// Idle timeout for methods without their own
+    pub idle_timeout: Duration,
+    timeouts: MethodOverrides<Duration>,
+    listener: Option<StreamIdleListener>,
+}
+
+impl Default for StreamIdleConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout: DEFAULT_STREAM_IDLE_TIMEOUT,
+            timeouts: MethodOverrides::default(),
+            listener: None,
+        }
+    }
+}
+
+impl StreamIdleConfig {
+    /// Cancel streams idle for `idle_timeout`
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            ..Self::default()
+        }
+    }
+
+    /// Set the idle timeout of a method (`pkg.Service/Method`) or a whole service (`pkg.Service`)
+    ///
+    /// A zero timeout disables idle cancellation for the method.
+    pub fn method_timeout(mut self, path: impl Into<String>, timeout: Duration) -> Self {
+        self.timeouts.insert(path, timeout);
+        self
+    }
+
+    /// Register a listener for idle-timeout events
+    pub fn on_idle_timeout<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&StreamIdleEvent) + Send + Sync + 'static,
+    {
+        self.listener = Some(Arc::new(f));
+        self
+    }
+
+    /// Idle timeout of `method`, or `None` if disabled
+    fn timeout(&self, method: &str) -> Option<Duration> {
+        let timeout = self.timeouts.get(method).copied().unwrap_or(self.idle_timeout);
+        (!timeout.is_zero()).then_some(timeout)
+    }
+}
+
+impl fmt::Debug for StreamIdleConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamIdleConfig")
+            .field("idle_timeout", &self.idle_timeout)
+            .field("timeouts", &self.timeouts)
+            .finish()
+    }
+}
+
+/// Applies idle timeouts to the streams of a call
+pub(crate) struct StreamIdleGuard {
+    config: StreamIdleConfig,
+}
+
+impl StreamIdleGuard {
+    pub(crate) fn new(config: StreamIdleConfig) -> Self {
+        Self { config }
+    }
+
+    /// Cancel `frames` once no frame has been delivered for the method's timeout
+    pub(crate) fn guard_response(
+        self: &Arc<Self>,
+        method: &str,
+        frames: BoxFrameStream,
+    ) -> BoxFrameStream {
+        let Some(timeout) = self.config.timeout(method) else {
+            return frames;
+        };
+        let state = Arc::new(Mutex::new(ResponseState {
+            frames: Some(frames),
+            last_frame: Instant::now(),
+            timed_out: false,
+            ended: false,
+            consumer: None,
+        }));
+        let watchdog = tokio::spawn(Arc::clone(self).watchdog(method.to_string(), timeout, Arc::clone(&state)));
+        Box::pin(IdleResponseStream {
+            state,
+            timeout,
+            watchdog,
+            done: false,
+        })
+    }
+
+    /// Fail `requests` once the handler has waited the method's timeout for a message
+    pub(crate) fn guard_request(self: &Arc<Self>, method: &str, requests: RequestStream) -> RequestStream {
+        let Some(timeout) = self.config.timeout(method) else {
+            return requests;
+        };
+        Box::pin(IdleRequestStream {
+            requests,
+            guard: Arc::clone(self),
+            method: method.to_string(),
+            timeout,
+            sleep: Box::pin(tokio::time::sleep(timeout)),
+            waiting: false,
+            done: false,
+        })
+    }
+
+    async fn watchdog(self: Arc<Self>, method: String, timeout: Duration, state: Arc<Mutex<ResponseState>>) {
+        loop {
+            let deadline = {
+                let state = state.lock().unwrap();
+                if state.ended {
+                    return;
+                }
+                state.last_frame + timeout
+            };
+            tokio::time::sleep_until(deadline.into()).await;
+
+            let frames = {
+                let mut state = state.lock().unwrap();
+                if state.ended || state.last_frame.elapsed() < timeout {
+                    continue;
+                }
+                state.timed_out = true;
+                if let Some(waker) = state.consumer.take() {
+                    waker.wake();
+                }
+                state.frames.take()
+            };
+            // Dropped outside the lock: this stops the handler
+            drop(frames);
+            self.report(&method, StreamSide::Response, timeout);
+            return;
+        }
+    }
+
+    fn report(&self, method: &str, side: StreamSide, idle_timeout: Duration) {
+        tracing::warn!("Stream of {} idle for {:?} ({:?}), cancelling", method, idle_timeout, side);
+        if let Some(listener) = &self.config.listener {
+            listener(&StreamIdleEvent {
+                method: method.to_string(),
+                side,
+                idle_timeout,
+            });
+        }
+    }
+}
+
+/// Response stream shared between the consumer and the watchdog
+struct ResponseState {
+    /// The handler's frames, taken by the watchdog on timeout
+    frames: Option<BoxFrameStream>,
+    /// When a frame was last handed to the consumer
+    last_frame: Instant,
+    timed_out: bool,
+    /// Whether the stream finished, so the watchdog can stop
+    ended: bool,
+    consumer: Option<Waker>,
+}
+
+/// Consumer side of an idle-guarded response stream
+struct IdleResponseStream {
+    state: Arc<Mutex<ResponseState>>,
+    timeout: Duration,
+    watchdog: JoinHandle<()>,
+    done: bool,
+}
+
+impl Stream for IdleResponseStream {
+    type Item = Result<Frame, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.timed_out {
+            drop(state);
+            self.done = true;
+            let problem = ProblemDetails::stream_idle_timeout(self.timeout);
+            return Poll::Ready(Some(Ok(Frame::cancel_with_problem(&problem))));
+        }
+        let Some(frames) = state.frames.as_mut() else {
+            return Poll::Ready(None);
+        };
+        match frames.as_mut().poll_next(cx) {
+            Poll::Ready(Some(item)) => {
+                state.last_frame = Instant::now();
+                // Nothing follows a terminal frame, so stop watching
+                state.ended = match &item {
+                    Ok(frame) => frame.flags.is_end_stream() || frame.flags.is_cancel(),
+                    Err(_) => true,
+                };
+                Poll::Ready(Some(item))
+            }
+            Poll::Ready(None) => {
+                state.ended = true;
+                drop(state);
+                self.done = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => {
+                state.consumer = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for IdleResponseStream {
+    fn drop(&mut self) {
+        self.watchdog.abort();
+    }
+}
+
+/// Request stream failing once the handler waits too long for a message
+struct IdleRequestStream {
+    requests: RequestStream,
+    guard: Arc<StreamIdleGuard>,
+    method: String,
+    timeout: Duration,
+    sleep: Pin<Box<Sleep>>,
+    /// Whether the handler is waiting for a message; time spent handling one is not idle
+    waiting: bool,
+    done: bool,
+}
+
+impl Stream for IdleRequestStream {
+    type Item = Result<bytes::Bytes, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        if !self.waiting {
+            self.waiting = true;
+            let deadline = tokio::time::Instant::now() + self.timeout;
+            self.sleep.as_mut().reset(deadline);
+        }
+        match self.requests.as_mut().poll_next(cx) {
+            Poll::Ready(Some(item)) => {
+                self.waiting = false;
+                Poll::Ready(Some(item))
+            }
+            Poll::Ready(None) => {
+                self.done = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => {
+                if self.sleep.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                self.done = true;
+                self.guard.report(&self.method, StreamSide::Request, self.timeout);
+                let problem = ProblemDetails::stream_idle_timeout(self.timeout);
+                Poll::Ready(Some(Err(QuillError::ProblemDetails(problem))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use tokio_stream::StreamExt;
+
+    fn guard(config: StreamIdleConfig) -> Arc<StreamIdleGuard> {
+        Arc::new(StreamIdleGuard::new(config))
+    }
+
+    /// Frames spaced `gap` apart, followed by END_STREAM
+    fn ticking(count: usize, gap: Duration) -> BoxFrameStream {
+        let items = (0..count)
+            .map(|i| Frame::data(Bytes::from(vec![i as u8])))
+            .chain(std::iter::once(Frame::end_stream()))
+            .map(Ok::<_, QuillError>);
+        Box::pin(tokio_stream::iter(items).then(move |item| async move {
+            tokio::time::sleep(gap).await;
+            item
+        }))
+    }
+
+    #[test]
+    fn test_method_timeouts() {
+        let config = StreamIdleConfig::new(Duration::from_secs(30))
+            .method_timeout("llm.v1.Generate", Duration::from_secs(300))
+            .method_timeout("llm.v1.Generate/Ping", Duration::ZERO);
+
+        assert_eq!(config.timeout("echo.v1.Echo/Stream"), Some(Duration::from_secs(30)));
+        assert_eq!(config.timeout("llm.v1.Generate/Stream"), Some(Duration::from_secs(300)));
+        assert_eq!(config.timeout("llm.v1.Generate/Ping"), None);
+    }
+
+    #[tokio::test]
+    async fn test_active_stream_is_not_cancelled() {
+        let guard = guard(StreamIdleConfig::new(Duration::from_millis(100)));
+        let frames: Vec<Frame> = guard
+            .guard_response("svc/Stream", ticking(10, Duration::from_millis(20)))
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(frames.len(), 11);
+        assert!(frames[10].flags.is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn test_idle_handler_is_cancelled() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let guard = guard(
+            StreamIdleConfig::new(Duration::from_millis(50))
+                .on_idle_timeout(move |event| recorded.lock().unwrap().push(event.clone())),
+        );
+        let frames: Vec<Frame> = guard
+            .guard_response("svc/Stream", ticking(3, Duration::from_millis(200)))
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].cancel_error().is_stream_idle_timeout());
+        assert_eq!(
+            events.lock().unwrap()[0],
+            StreamIdleEvent {
+                method: "svc/Stream".to_string(),
+                side: StreamSide::Response,
+                idle_timeout: Duration::from_millis(50),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_consumer_releases_handler() {
+        struct Released(Arc<Mutex<bool>>);
+        impl Drop for Released {
+            fn drop(&mut self) {
+                *self.0.lock().unwrap() = true;
+            }
+        }
+
+        let released = Arc::new(Mutex::new(false));
+        let held = Released(Arc::clone(&released));
+        let frames: BoxFrameStream = Box::pin(tokio_stream::iter(0..u8::MAX).map(move |i| {
+            let _ = &held;
+            Ok(Frame::data(Bytes::from(vec![i])))
+        }));
+        let guard = guard(StreamIdleConfig::new(Duration::from_millis(50)));
+        let mut stream = guard.guard_response("svc/Stream", frames);
+
+        // The consumer reads once, then stops without dropping the stream
+        stream.next().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        assert!(*released.lock().unwrap());
+        let frame = stream.next().await.unwrap().unwrap();
+        assert!(frame.cancel_error().is_stream_idle_timeout());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_idle_request_stream_fails() {
+        let guard = guard(StreamIdleConfig::new(Duration::from_millis(50)));
+        let requests: RequestStream = Box::pin(
+            tokio_stream::iter(vec![Ok::<_, QuillError>(Bytes::from_static(b"a"))]).chain(tokio_stream::pending()),
+        );
+        let mut stream = guard.guard_request("svc/Upload", requests);
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"a"));
+        // Time spent handling a message does not count as idle
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let started = Instant::now();
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(error.is_stream_idle_timeout());
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,125 @@
+//! Reference implementation of the interop test service
+//!
+//! Servers with the interop service enabled answer every method of
+//! [`quill_core::interop`], so clients in other languages can check their
+//! framing, streaming, cancellation, compression and tensor handling
+//! against the Rust implementation, and `quill interop` can be pointed at
+//! a Rust server to validate the driver itself.
+//!
+//! Message sizes are capped at [`MAX_INTEROP_MESSAGE_SIZE`] and tensors at
+//! [`MAX_INTEROP_TENSOR_ELEMENTS`]. `Generate` streams are produced lazily,
+//! so a caller may ask for `u32::MAX` messages and cancel part way.
+//!
+//! `Tensor` is only served with the `tensor` feature.
+
+use crate::router::{RequestStream, RpcRouter};
+use crate::streaming::RpcResponse;
+use bytes::Bytes;
+use http::StatusCode;
+use quill_core::{
+    interop_message, InteropCollectSummary, InteropStreamRequest, ProblemDetails, QuillError,
+    INTEROP_COLLECT_METHOD, INTEROP_CONVERSE_METHOD, INTEROP_ECHO_METHOD, INTEROP_GENERATE_METHOD,
+    INTEROP_SERVICE,
+};
+#[cfg(feature = "tensor")]
+use quill_core::INTEROP_TENSOR_METHOD;
+#[cfg(feature = "tensor")]
+use quill_tensor::{DType, Tensor, TensorMeta, TensorSender};
+use tokio_stream::StreamExt;
+
+/// Largest message `Generate` sends and `Tensor` payload chunk, in bytes
+pub const MAX_INTEROP_MESSAGE_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Largest tensor `Tensor` sends, in elements (256 MiB of float32)
+pub const MAX_INTEROP_TENSOR_ELEMENTS: u32 = 64 * 1024 * 1024;
+
+/// Register the interop methods on `router`
+pub(crate) fn register(router: &mut RpcRouter) {
+    router.register_unary(path(INTEROP_ECHO_METHOD), |request: Bytes| async move { Ok(request) });
+
+    router.register(path(INTEROP_GENERATE_METHOD), |request: Bytes| async move {
+        let request = decode_request(&request)?;
+        let size = request.size as usize;
+        let messages = tokio_stream::iter(0..request.count)
+            .map(move |index| Ok::<_, QuillError>(interop_message(index, size)));
+        Ok(RpcResponse::streaming(messages))
+    });
+
+    router.register_client_streaming(
+        path(INTEROP_COLLECT_METHOD),
+        |mut requests: RequestStream| async move {
+            let mut summary = InteropCollectSummary::default();
+            while let Some(message) = requests.next().await {
+                summary.messages += 1;
+                summary.bytes += message?.len() as u64;
+            }
+            Ok(RpcResponse::unary(summary.encode()))
+        },
+    );
+
+    router.register_bidi_streaming(
+        path(INTEROP_CONVERSE_METHOD),
+        |requests: RequestStream| async move { Ok(RpcResponse::streaming(requests)) },
+    );
+
+    #[cfg(feature = "tensor")]
+    router.register_tensor_streaming(path(INTEROP_TENSOR_METHOD), |request: Bytes| async move {
+        let request = decode_request(&request)?;
+        if request.count > MAX_INTEROP_TENSOR_ELEMENTS {
+            return Err(invalid(format!(
+                "Tensor of {} elements exceeds the limit of {}",
+                request.count, MAX_INTEROP_TENSOR_ELEMENTS
+            )));
+        }
+        let values: Vec<f32> = (0..request.count).map(|i| i as f32).collect();
+        let tensor = Tensor::from_f32(
+            &TensorMeta::new(vec![request.count as usize], DType::Float32),
+            &values,
+        );
+        let frames =
+            TensorSender::with_chunk_size(request.size.max(1) as usize).encode_tensor(&tensor);
+        Ok(tokio_stream::iter(frames.into_iter().map(Ok::<_, QuillError>)))
+    });
+}
+
+fn path(method: &str) -> String {
+    format!("{}/{}", INTEROP_SERVICE, method)
+}
+
+/// Decode a `Generate` or `Tensor` request, checking its size
+fn decode_request(request: &[u8]) -> Result<InteropStreamRequest, QuillError> {
+    let request = InteropStreamRequest::decode(request).ok_or_else(|| {
+        invalid(format!("Expected {} bytes, got {}", InteropStreamRequest::LEN, request.len()))
+    })?;
+    if request.size > MAX_INTEROP_MESSAGE_SIZE {
+        return Err(invalid(format!(
+            "Message size {} exceeds the limit of {}",
+            request.size, MAX_INTEROP_MESSAGE_SIZE
+        )));
+    }
+    Ok(request)
+}
+
+fn invalid(detail: String) -> QuillError {
+    QuillError::ProblemDetails(
+        ProblemDetails::new(StatusCode::BAD_REQUEST, "Invalid interop request").with_detail(detail),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_request_limits() {
+        let request = InteropStreamRequest { count: u32::MAX, size: 1024 };
+        assert_eq!(decode_request(&request.encode()).unwrap(), request);
+
+        let oversized = InteropStreamRequest { count: 1, size: MAX_INTEROP_MESSAGE_SIZE + 1 };
+        match decode_request(&oversized.encode()) {
+            Err(QuillError::ProblemDetails(pd)) => assert_eq!(pd.status, 400),
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+        assert!(decode_request(b"short").is_err());
+    }
+}
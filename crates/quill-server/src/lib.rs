@@ -3,35 +3,152 @@
 //! This crate provides server-side components:
 //! - HTTP router for RPC methods
 //! - Handler traits
+//! - Request context with the caller's deadline
 //! - Middleware (Problem Details, compression, tracing)
-//! - Server runtime
+//! - Server runtime, core pinning and listener socket tuning
+//! - io_uring connection I/O (with `io-uring` feature on Linux)
+//! - Debug context for error responses
 //! - Streaming support
+//! - Backpressure-aware bridges between streams and tokio channels
+//! - Acknowledgment of client cancels on bidirectional calls
+//! - Deadline-bounded partial results
+//! - Usage trailers for metered streams
+//! - Cursor streams resumable from a persisted position
+//! - Slow-consumer detection for streaming responses
+//! - Credit-based flow control of streaming responses
+//! - End-to-end encryption of stream frames
+//! - Idle timeouts for streaming RPCs
+//! - Collection of idle streams and detection of stream leaks
+//! - Coalescing of small response frames into fewer writes
+//! - Fair scheduling of response writes across the streams of a connection
+//! - Sampling of redacted payloads into traces
+//! - Reassembly of chunked uploads
+//! - Dictionary compression of unary calls
+//! - Batch RPCs carrying many unary calls
+//! - Incremental tensor streaming responses (with `tensor` feature)
+//! - Scheduled invocation of registered methods
+//! - Reflection of registered services for runtime discovery
+//! - JSON transcoding of described methods for debugging with curl
+//! - Health checking with liveness, readiness and watch streams
+//! - Pre-serialized responses for hot static methods
+//! - Reference interop test service for conformance testing
+//! - Per-tenant isolation of stream and bandwidth limits
+//! - Session stores for conversation state kept across calls
+//! - Consistency tokens for read-your-writes on replicated services
+//! - OpenTelemetry export of traces and metrics (with `otel` feature)
+//! - WASM handler plugins (with `wasm` feature)
 //! - HTTP/3 support (with `http3` feature)
 
 #[cfg(feature = "http3")]
 pub mod h3_server;
+pub mod batch;
+pub mod cancel_ack;
+pub mod channel;
+pub mod coalesce;
+pub mod consistency;
+pub mod context;
+pub mod cursor;
+pub mod debug;
+pub mod dictionary;
+pub mod envelope;
+pub mod flow_control;
+pub mod frame_encryption;
+pub mod frame_scheduler;
 pub mod handler;
+pub mod health;
+pub mod idle_timeout;
+pub mod interop;
 pub mod middleware;
 pub mod negotiation;
 pub mod observability;
+#[cfg(feature = "otel")]
+pub mod otel;
+mod overrides;
+pub mod partial;
+#[cfg(feature = "wasm")]
+pub mod plugin;
+pub mod reflection;
 pub mod request_stream;
+pub mod response_cache;
 pub mod router;
+pub mod runtime;
+pub mod sampling;
+pub mod schedule;
 pub mod security;
 pub mod server;
+pub mod session;
+pub mod slow_consumer;
+pub mod stream_gc;
 pub mod streaming;
+pub mod tenant;
+#[cfg(feature = "tensor")]
+pub mod tensor;
+pub mod transcode;
+pub mod upload;
+pub mod usage;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub mod uring;
 
 #[cfg(feature = "http3")]
 pub use h3_server::{H3ServerBuilder, H3ServerConfig, QuillH3Server};
+pub use batch::BatchRpcConfig;
+pub use cancel_ack::ClientCancel;
+pub use channel::{broadcast_to_response, channel_to_response, stream_to_channel, LagPolicy};
+pub use coalesce::{CoalesceBudget, FrameCoalescingConfig};
+pub use consistency::{AppliedPosition, CallConsistency};
+pub use context::RequestContext;
+pub use debug::{DebugPolicy, DEBUG_HEADER};
+pub use dictionary::{DictionaryCompression, DEFAULT_DICTIONARY_LEVEL};
+pub use envelope::EnvelopeDecryption;
+pub use frame_encryption::FrameEncryption;
+pub use frame_scheduler::{FrameSchedulerStats, FrameSchedulingConfig};
 pub use handler::RpcHandler;
+pub use health::HealthReporter;
+pub use idle_timeout::{StreamIdleConfig, StreamIdleEvent, StreamSide};
 pub use negotiation::{
     negotiate_profile, NegotiationResult, ProfileSupport, PREFER_HEADER, SELECTED_PRISM_HEADER,
 };
-pub use observability::{check_dependency, DependencyStatus, HealthStatus, ObservabilityCollector};
+pub use observability::{
+    check_dependency, ConnectionGuard, DependencyStatus, HealthStatus, ObservabilityCollector,
+    LATENCY_BUCKETS, PROMETHEUS_CONTENT_TYPE,
+};
+#[cfg(feature = "otel")]
+pub use otel::{OtelConfig, OtelError, OtelExporter, RpcMetrics};
+pub use partial::{PartialProgress, PartialResponse};
+#[cfg(feature = "wasm")]
+pub use plugin::{PluginConfig, PluginError, PluginHost};
 pub use request_stream::RequestFrameStream;
+pub use response_cache::{CachedResponse, InvalidationAuthFn, ResponseCache, ResponseCacheConfig};
 pub use router::{parse_rpc_path, RpcRouter};
+pub use runtime::{CorePinning, InferenceRuntime, IoBackend, RuntimeConfig, SocketConfig};
+pub use sampling::{PayloadDirection, PayloadSample, PayloadSamplingConfig, PAYLOAD_TARGET};
+pub use schedule::{CronError, CronSchedule, JobRun, OverlapPolicy, RunOutcome, ScheduledJob};
 pub use security::{
     is_early_data_request, CompressionExclusions, IdempotencyChecker, EARLY_DATA_HEADER,
     STATUS_TOO_EARLY,
 };
 pub use server::{HttpVersion, QuillServer, ServerBuilder, ServerConfig};
-pub use streaming::{FramedResponseStream, RpcResponse};
+pub use session::{
+    new_session_id, session_id, InMemorySessionStore, SessionStore, DEFAULT_MAX_SESSIONS,
+    SESSION_METADATA_KEY,
+};
+pub use slow_consumer::{LagStats, SlowConsumerAction, SlowConsumerConfig, SlowConsumerEvent};
+pub use stream_gc::{
+    StreamGcConfig, StreamGcStats, StreamLeak, STREAM_GC_LEAKS_METHOD, STREAM_GC_LEAKS_PATH,
+    STREAM_GC_SERVICE,
+};
+pub use streaming::{CancelGuard, CancelSignal, FramedResponseStream, ResponseSender, RpcResponse};
+pub use tenant::{
+    TenantIsolationConfig, TenantLimits, TenantStats, ANONYMOUS_TENANT, DEFAULT_TENANT_MAX_STREAMS,
+    DEFAULT_TENANT_QUEUE_TIMEOUT,
+};
+#[cfg(feature = "tensor")]
+pub use tensor::{tensor_channel, TensorFrameSink, TensorFrameStream, TokenSink};
+pub use transcode::JSON_CONTENT_TYPE;
+pub use upload::ChunkedUploadConfig;
+pub use usage::UsageRecorder;
+
+/// Boxed stream of response frames
+pub(crate) type BoxFrameStream = std::pin::Pin<
+    Box<dyn tokio_stream::Stream<Item = Result<quill_core::Frame, quill_core::QuillError>> + Send>,
+>;
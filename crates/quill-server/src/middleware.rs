@@ -255,7 +255,7 @@ pub enum AuthScheme {
 }
 
 /// Extract bearer token from Authorization header
-pub fn extract_bearer_token(req: &Request<Incoming>) -> Option<String> {
+pub fn extract_bearer_token<B>(req: &Request<B>) -> Option<String> {
     req.headers()
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
@@ -269,7 +269,7 @@ pub fn extract_bearer_token(req: &Request<Incoming>) -> Option<String> {
 }
 
 /// Extract API key from header
-pub fn extract_api_key(req: &Request<Incoming>, header_name: &str) -> Option<String> {
+pub fn extract_api_key<B>(req: &Request<B>, header_name: &str) -> Option<String> {
     req.headers()
         .get(header_name)
         .and_then(|v| v.to_str().ok())
@@ -277,7 +277,7 @@ pub fn extract_api_key(req: &Request<Incoming>, header_name: &str) -> Option<Str
 }
 
 /// Extract basic auth credentials
-pub fn extract_basic_auth(req: &Request<Incoming>) -> Option<(String, String)> {
+pub fn extract_basic_auth<B>(req: &Request<B>) -> Option<(String, String)> {
     req.headers()
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
@@ -358,7 +358,7 @@ impl AuthLayer {
     }
 
     /// Authenticate a request
-    pub fn authenticate(&self, req: &Request<Incoming>) -> AuthResult {
+    pub fn authenticate<B>(&self, req: &Request<B>) -> AuthResult {
         match &self.scheme {
             AuthScheme::Bearer => {
                 if let Some(token) = extract_bearer_token(req) {
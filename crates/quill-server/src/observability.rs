@@ -2,12 +2,23 @@
 //!
 //! Provides comprehensive metrics, health checks, and monitoring capabilities
 
-use std::collections::HashMap;
+use crate::health::HealthReporter;
+use crate::BoxFrameStream;
+use futures_util::StreamExt;
+use http::StatusCode;
+use quill_core::{Frame, QuillError};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// Content type of the Prometheus text format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Upper bounds, in seconds, of the request latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 12] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
 /// Prometheus-compatible metrics collector
 #[derive(Clone)]
 pub struct ObservabilityCollector {
@@ -28,22 +39,45 @@ struct ObservabilityInner {
     response_bytes_total: AtomicU64,
     request_bytes_total: AtomicU64,
 
+    // Connection and stream tracking
+    connections_total: AtomicU64,
+    connections_active: AtomicU64,
+    streams_active: AtomicU64,
+    stream_frames_total: AtomicU64,
+    tensor_bytes_total: AtomicU64,
+
     // Per-endpoint metrics
     endpoint_metrics: RwLock<HashMap<String, EndpointMetrics>>,
 
     // Health status
     health_status: RwLock<HealthStatus>,
+    health: HealthReporter,
 
     // Start time
     start_time: Instant,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 struct EndpointMetrics {
     requests: u64,
     errors: u64,
     latency_sum_ms: u64,
     latency_count: u64,
+    latency_sum_us: u64,
+    /// Requests per latency bucket, not cumulative
+    latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    /// Responses per status code
+    statuses: BTreeMap<u16, u64>,
+}
+
+impl EndpointMetrics {
+    fn record_latency(&mut self, duration: Duration) {
+        self.latency_sum_us += duration.as_micros() as u64;
+        let seconds = duration.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&bound| seconds <= bound) {
+            self.latency_buckets[bucket] += 1;
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -73,12 +107,18 @@ impl ObservabilityCollector {
                 latency_count: AtomicU64::new(0),
                 response_bytes_total: AtomicU64::new(0),
                 request_bytes_total: AtomicU64::new(0),
+                connections_total: AtomicU64::new(0),
+                connections_active: AtomicU64::new(0),
+                streams_active: AtomicU64::new(0),
+                stream_frames_total: AtomicU64::new(0),
+                tensor_bytes_total: AtomicU64::new(0),
                 endpoint_metrics: RwLock::new(HashMap::new()),
                 health_status: RwLock::new(HealthStatus {
                     healthy: true,
                     dependencies: HashMap::new(),
                     last_check: Instant::now(),
                 }),
+                health: HealthReporter::new(),
                 start_time: Instant::now(),
             }),
         }
@@ -98,6 +138,31 @@ impl ObservabilityCollector {
         duration: Duration,
         response_bytes: usize,
         success: bool,
+    ) {
+        self.complete(endpoint, duration, response_bytes, success, None).await;
+    }
+
+    /// Record a request completion with the status code of its response
+    ///
+    /// Responses with a status outside `2xx` count as failed.
+    pub async fn record_response(
+        &self,
+        endpoint: &str,
+        duration: Duration,
+        response_bytes: usize,
+        status: StatusCode,
+    ) {
+        self.complete(endpoint, duration, response_bytes, status.is_success(), Some(status))
+            .await;
+    }
+
+    async fn complete(
+        &self,
+        endpoint: &str,
+        duration: Duration,
+        response_bytes: usize,
+        success: bool,
+        status: Option<StatusCode>,
     ) {
         self.inner.requests_in_flight.fetch_sub(1, Ordering::Relaxed);
         self.inner.response_bytes_total.fetch_add(response_bytes as u64, Ordering::Relaxed);
@@ -112,27 +177,66 @@ impl ObservabilityCollector {
 
         // Update per-endpoint metrics
         let mut metrics = self.inner.endpoint_metrics.write().await;
-        let endpoint_metric = metrics.entry(endpoint.to_string()).or_insert(EndpointMetrics {
-            requests: 0,
-            errors: 0,
-            latency_sum_ms: 0,
-            latency_count: 0,
-        });
+        let endpoint_metric = metrics.entry(endpoint.to_string()).or_default();
 
         endpoint_metric.requests += 1;
         endpoint_metric.latency_sum_ms += latency_ms;
         endpoint_metric.latency_count += 1;
+        endpoint_metric.record_latency(duration);
         if !success {
             endpoint_metric.errors += 1;
         }
+        if let Some(status) = status {
+            *endpoint_metric.statuses.entry(status.as_u16()).or_default() += 1;
+        }
+    }
+
+    /// Record an accepted connection, until the returned guard is dropped
+    pub fn track_connection(&self) -> ConnectionGuard {
+        self.inner.connections_total.fetch_add(1, Ordering::Relaxed);
+        self.inner.connections_active.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    /// Count the frames of a streaming response as they are sent
+    ///
+    /// Message bytes of `tensor` streams count as tensor bytes transferred.
+    pub(crate) fn observe_stream(&self, frames: BoxFrameStream, tensor: bool) -> BoxFrameStream {
+        self.inner.streams_active.fetch_add(1, Ordering::Relaxed);
+        let active = ActiveStream {
+            inner: Arc::clone(&self.inner),
+        };
+        Box::pin(frames.inspect(move |frame: &Result<Frame, QuillError>| {
+            if let Ok(frame) = frame {
+                active.inner.stream_frames_total.fetch_add(1, Ordering::Relaxed);
+                if tensor {
+                    active
+                        .inner
+                        .tensor_bytes_total
+                        .fetch_add(frame.payload.len() as u64, Ordering::Relaxed);
+                }
+            }
+        }))
     }
 
     /// Update health status
+    ///
+    /// Also sets the server's status in [`health_reporter`](Self::health_reporter).
     pub async fn update_health(&self, healthy: bool, dependencies: HashMap<String, DependencyStatus>) {
         let mut health = self.inner.health_status.write().await;
         health.healthy = healthy;
         health.dependencies = dependencies;
         health.last_check = Instant::now();
+        self.inner.health.set_serving("", healthy);
+    }
+
+    /// Reporter answering the health RPCs with this collector's health status
+    ///
+    /// Pass it to [`RpcRouter::enable_health`](crate::router::RpcRouter::enable_health).
+    pub fn health_reporter(&self) -> HealthReporter {
+        self.inner.health.clone()
     }
 
     /// Get current health status
@@ -194,6 +298,41 @@ impl ObservabilityCollector {
             self.inner.response_bytes_total.load(Ordering::Relaxed)
         ));
 
+        output.push_str("# HELP quill_connections_total Total number of accepted connections\n");
+        output.push_str("# TYPE quill_connections_total counter\n");
+        output.push_str(&format!(
+            "quill_connections_total {}\n",
+            self.inner.connections_total.load(Ordering::Relaxed)
+        ));
+
+        output.push_str("# HELP quill_connections_active Current number of open connections\n");
+        output.push_str("# TYPE quill_connections_active gauge\n");
+        output.push_str(&format!(
+            "quill_connections_active {}\n",
+            self.inner.connections_active.load(Ordering::Relaxed)
+        ));
+
+        output.push_str("# HELP quill_streams_active Current number of streaming responses being sent\n");
+        output.push_str("# TYPE quill_streams_active gauge\n");
+        output.push_str(&format!(
+            "quill_streams_active {}\n",
+            self.inner.streams_active.load(Ordering::Relaxed)
+        ));
+
+        output.push_str("# HELP quill_stream_frames_total Total frames of streaming responses sent\n");
+        output.push_str("# TYPE quill_stream_frames_total counter\n");
+        output.push_str(&format!(
+            "quill_stream_frames_total {}\n",
+            self.inner.stream_frames_total.load(Ordering::Relaxed)
+        ));
+
+        output.push_str("# HELP quill_tensor_bytes_total Total bytes of tensor stream messages sent\n");
+        output.push_str("# TYPE quill_tensor_bytes_total counter\n");
+        output.push_str(&format!(
+            "quill_tensor_bytes_total {}\n",
+            self.inner.tensor_bytes_total.load(Ordering::Relaxed)
+        ));
+
         // Uptime
         let uptime_seconds = self.inner.start_time.elapsed().as_secs();
         output.push_str("# HELP quill_uptime_seconds Server uptime in seconds\n");
@@ -234,6 +373,43 @@ impl ObservabilityCollector {
                     endpoint, avg
                 ));
             }
+
+            output.push_str("# HELP quill_request_duration_seconds Request latency per endpoint\n");
+            output.push_str("# TYPE quill_request_duration_seconds histogram\n");
+            for (endpoint, metrics) in endpoint_metrics.iter() {
+                let mut cumulative = 0;
+                for (bound, count) in LATENCY_BUCKETS.iter().zip(metrics.latency_buckets) {
+                    cumulative += count;
+                    output.push_str(&format!(
+                        "quill_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"{}\"}} {}\n",
+                        endpoint, bound, cumulative
+                    ));
+                }
+                output.push_str(&format!(
+                    "quill_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"+Inf\"}} {}\n",
+                    endpoint, metrics.latency_count
+                ));
+                output.push_str(&format!(
+                    "quill_request_duration_seconds_sum{{endpoint=\"{}\"}} {:.6}\n",
+                    endpoint,
+                    metrics.latency_sum_us as f64 / 1_000_000.0
+                ));
+                output.push_str(&format!(
+                    "quill_request_duration_seconds_count{{endpoint=\"{}\"}} {}\n",
+                    endpoint, metrics.latency_count
+                ));
+            }
+
+            output.push_str("# HELP quill_responses_total Responses per endpoint and status code\n");
+            output.push_str("# TYPE quill_responses_total counter\n");
+            for (endpoint, metrics) in endpoint_metrics.iter() {
+                for (code, count) in &metrics.statuses {
+                    output.push_str(&format!(
+                        "quill_responses_total{{endpoint=\"{}\",code=\"{}\"}} {}\n",
+                        endpoint, code, count
+                    ));
+                }
+            }
         }
 
         // Health status
@@ -313,6 +489,28 @@ impl ObservabilityCollector {
     }
 }
 
+/// Keeps a connection counted as active until dropped
+pub struct ConnectionGuard {
+    inner: Arc<ObservabilityInner>,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.inner.connections_active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Keeps a streaming response counted as active until dropped
+struct ActiveStream {
+    inner: Arc<ObservabilityInner>,
+}
+
+impl Drop for ActiveStream {
+    fn drop(&mut self) {
+        self.inner.streams_active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 impl Default for ObservabilityCollector {
     fn default() -> Self {
         Self::new()
@@ -379,6 +577,50 @@ mod tests {
         assert_eq!(json["endpoints"].as_array().unwrap().len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_latency_histogram_and_statuses() {
+        let collector = ObservabilityCollector::new();
+
+        collector.record_request_start("svc/Fast", 10);
+        collector
+            .record_response("svc/Fast", Duration::from_millis(3), 20, StatusCode::OK)
+            .await;
+        collector.record_request_start("svc/Fast", 10);
+        collector
+            .record_response("svc/Fast", Duration::from_millis(300), 20, StatusCode::NOT_FOUND)
+            .await;
+
+        let prometheus = collector.export_prometheus().await;
+        assert!(prometheus.contains("quill_request_duration_seconds_bucket{endpoint=\"svc/Fast\",le=\"0.005\"} 1"));
+        assert!(prometheus.contains("quill_request_duration_seconds_bucket{endpoint=\"svc/Fast\",le=\"0.5\"} 2"));
+        assert!(prometheus.contains("quill_request_duration_seconds_count{endpoint=\"svc/Fast\"} 2"));
+        assert!(prometheus.contains("quill_responses_total{endpoint=\"svc/Fast\",code=\"404\"} 1"));
+        assert!(prometheus.contains("quill_requests_failed_total 1"));
+    }
+
+    #[tokio::test]
+    async fn test_connections_and_streams() {
+        let collector = ObservabilityCollector::new();
+
+        let connection = collector.track_connection();
+        let frames: BoxFrameStream = Box::pin(futures_util::stream::iter(vec![
+            Ok(Frame::data(bytes::Bytes::from_static(b"tensor"))),
+            Ok(Frame::end_stream()),
+        ]));
+        let mut stream = collector.observe_stream(frames, true);
+        assert!(collector.export_prometheus().await.contains("quill_streams_active 1"));
+        while stream.next().await.is_some() {}
+        drop(stream);
+        drop(connection);
+
+        let prometheus = collector.export_prometheus().await;
+        assert!(prometheus.contains("quill_connections_total 1"));
+        assert!(prometheus.contains("quill_connections_active 0"));
+        assert!(prometheus.contains("quill_streams_active 0"));
+        assert!(prometheus.contains("quill_stream_frames_total 2"));
+        assert!(prometheus.contains("quill_tensor_bytes_total 6"));
+    }
+
     #[tokio::test]
     async fn test_health_status() {
         let collector = ObservabilityCollector::new();
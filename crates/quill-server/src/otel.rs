@@ -0,0 +1,435 @@
+//! OpenTelemetry export of RPC traces and metrics (with `otel` feature)
+//!
+//! This module provides:
+//! - [`OtelExporter`], which installs OTLP exporters for spans and metrics
+//! - [`RpcMetrics`], the RPC instruments the router records into
+//!
+//! Every call is recorded with `rpc.system`, `rpc.service` and `rpc.method`
+//! attributes, so dashboards can break metrics down per method. Calls to
+//! unregistered paths are labelled `_OTHER` to keep cardinality bounded.
+//!
+//! | Instrument | Kind | Unit |
+//! |------------|------|------|
+//! | `rpc.server.requests` | Counter, also labelled with the HTTP status | `{request}` |
+//! | `rpc.server.duration` | Histogram, until the response headers are sent | `ms` |
+//! | `rpc.server.active_streams` | UpDownCounter of streaming responses | `{stream}` |
+//! | `rpc.server.frames` | Counter of response frames sent | `{frame}` |
+//! | `rpc.server.request.size` | Counter, from `Content-Length` | `By` |
+//! | `rpc.server.response.size` | Counter, including streamed payloads | `By` |
+//!
+//! Each call also runs in an `rpc.request` span, parented to the caller's
+//! W3C `traceparent` when it sends one:
+//!
+//! ```ignore
+//! let exporter = OtelExporter::install(OtelConfig::new("chat-service"))?;
+//! tracing_subscriber::registry().with(exporter.tracing_layer()).init();
+//!
+//! let server = QuillServer::builder()
+//!     .otel_metrics(exporter.metrics())
+//!     .register("chat.v1.Chat/Send", send)
+//!     .build();
+//! ```
+
+use crate::middleware::create_rpc_span;
+use crate::router::parse_rpc_path;
+use crate::BoxFrameStream;
+use futures_util::StreamExt;
+use http::{HeaderMap, StatusCode};
+use opentelemetry::global;
+use opentelemetry::metrics::{Counter, Histogram, Meter, MeterProvider, MetricsError, UpDownCounter};
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::{TraceError, TracerProvider as _};
+use opentelemetry::{Key, KeyValue, Value};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Tracer, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use quill_core::{Frame, QuillError};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Span;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+
+/// Default OTLP/gRPC collector endpoint
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4317";
+
+/// Default interval between two metric exports
+pub const DEFAULT_EXPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Name of the instrumentation scope of spans and metrics
+const SCOPE: &str = "quill-server";
+
+/// Method label of calls to unregistered paths
+const OTHER_METHOD: &str = "_OTHER";
+
+/// Errors installing the exporters
+#[derive(Debug, thiserror::Error)]
+pub enum OtelError {
+    #[error("Failed to install trace exporter: {0}")]
+    Trace(#[from] TraceError),
+
+    #[error("Failed to install metrics exporter: {0}")]
+    Metrics(#[from] MetricsError),
+}
+
+/// Where and as what service telemetry is exported
+#[derive(Debug, Clone)]
+pub struct OtelConfig {
+    /// Reported as the `service.name` resource attribute
+    pub service_name: String,
+    /// OTLP/gRPC collector endpoint
+    pub endpoint: String,
+    /// Further resource attributes, e.g. `deployment.environment`
+    pub resource_attributes: Vec<KeyValue>,
+    /// Interval between two metric exports
+    pub export_interval: Duration,
+}
+
+impl OtelConfig {
+    /// Export as `service_name` to the default collector endpoint
+    pub fn new(service_name: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+            endpoint: DEFAULT_OTLP_ENDPOINT.to_string(),
+            resource_attributes: Vec::new(),
+            export_interval: DEFAULT_EXPORT_INTERVAL,
+        }
+    }
+
+    /// Export to the collector at `endpoint`
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Add a resource attribute to all exported telemetry
+    pub fn with_resource_attribute(mut self, key: impl Into<Key>, value: impl Into<Value>) -> Self {
+        self.resource_attributes.push(KeyValue::new(key, value));
+        self
+    }
+
+    /// Set the interval between two metric exports
+    pub fn with_export_interval(mut self, interval: Duration) -> Self {
+        self.export_interval = interval;
+        self
+    }
+
+    /// Resource describing the service
+    pub fn resource(&self) -> Resource {
+        let mut attributes = vec![KeyValue::new("service.name", self.service_name.clone())];
+        attributes.extend(self.resource_attributes.iter().cloned());
+        Resource::default().merge(&Resource::new(attributes))
+    }
+}
+
+/// Installed OTLP exporters for spans and metrics
+///
+/// Installing also makes them the global providers and W3C Trace Context
+/// the global propagator, so clients in the same process join the traces.
+pub struct OtelExporter {
+    tracer_provider: TracerProvider,
+    meter_provider: SdkMeterProvider,
+    metrics: RpcMetrics,
+}
+
+impl OtelExporter {
+    /// Install batching OTLP/gRPC exporters described by `config`
+    ///
+    /// Must be called from within a Tokio runtime, which runs the exports.
+    pub fn install(config: OtelConfig) -> Result<Self, OtelError> {
+        let resource = config.resource();
+
+        let tracer_provider = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(&config.endpoint))
+            .with_trace_config(opentelemetry_sdk::trace::Config::default().with_resource(resource.clone()))
+            .install_batch(runtime::Tokio)?;
+
+        let meter_provider = opentelemetry_otlp::new_pipeline()
+            .metrics(runtime::Tokio)
+            .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(&config.endpoint))
+            .with_resource(resource)
+            .with_period(config.export_interval)
+            .build()?;
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        global::set_tracer_provider(tracer_provider.clone());
+        global::set_meter_provider(meter_provider.clone());
+
+        let metrics = RpcMetrics::new(&meter_provider.meter(SCOPE));
+        Ok(Self {
+            tracer_provider,
+            meter_provider,
+            metrics,
+        })
+    }
+
+    /// Layer exporting `tracing` spans, to add to the subscriber
+    pub fn tracing_layer<S>(&self) -> OpenTelemetryLayer<S, Tracer>
+    where
+        S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.tracer_provider.tracer(SCOPE))
+    }
+
+    /// RPC instruments exported by this exporter
+    pub fn metrics(&self) -> RpcMetrics {
+        self.metrics.clone()
+    }
+
+    /// Export what is still buffered and stop exporting
+    pub fn shutdown(&self) -> Result<(), OtelError> {
+        self.tracer_provider.shutdown()?;
+        self.meter_provider.shutdown()?;
+        Ok(())
+    }
+}
+
+/// RPC instruments recorded by the router
+///
+/// Cheap to clone; clones record into the same instruments.
+#[derive(Clone)]
+pub struct RpcMetrics {
+    requests: Counter<u64>,
+    duration: Histogram<f64>,
+    active_streams: UpDownCounter<i64>,
+    frames: Counter<u64>,
+    request_bytes: Counter<u64>,
+    response_bytes: Counter<u64>,
+}
+
+impl RpcMetrics {
+    /// Create the instruments on `meter`
+    pub fn new(meter: &Meter) -> Self {
+        Self {
+            requests: meter
+                .u64_counter("rpc.server.requests")
+                .with_description("RPCs received")
+                .with_unit("{request}")
+                .init(),
+            duration: meter
+                .f64_histogram("rpc.server.duration")
+                .with_description("Time until the response headers were sent")
+                .with_unit("ms")
+                .init(),
+            active_streams: meter
+                .i64_up_down_counter("rpc.server.active_streams")
+                .with_description("Streaming responses in flight")
+                .with_unit("{stream}")
+                .init(),
+            frames: meter
+                .u64_counter("rpc.server.frames")
+                .with_description("Frames of streaming responses sent")
+                .with_unit("{frame}")
+                .init(),
+            request_bytes: meter
+                .u64_counter("rpc.server.request.size")
+                .with_description("Request bytes received")
+                .with_unit("By")
+                .init(),
+            response_bytes: meter
+                .u64_counter("rpc.server.response.size")
+                .with_description("Response bytes sent")
+                .with_unit("By")
+                .init(),
+        }
+    }
+
+    /// Record a call to `path` whose response headers are being sent
+    pub(crate) fn record_request(
+        &self,
+        path: &str,
+        registered: bool,
+        duration: Duration,
+        status: StatusCode,
+        request_bytes: usize,
+        response_bytes: usize,
+    ) {
+        let mut attributes = method_attributes(path, registered);
+        self.request_bytes.add(request_bytes as u64, &attributes);
+        self.response_bytes.add(response_bytes as u64, &attributes);
+        self.duration.record(duration.as_secs_f64() * 1000.0, &attributes);
+        attributes.push(KeyValue::new("http.response.status_code", i64::from(status.as_u16())));
+        self.requests.add(1, &attributes);
+    }
+
+    /// Count the frames of a streaming response to `path` as they are sent
+    ///
+    /// The stream counts as active until `frames` is dropped.
+    pub(crate) fn observe_stream(&self, path: &str, frames: BoxFrameStream) -> BoxFrameStream {
+        let attributes: Arc<[KeyValue]> = method_attributes(path, true).into();
+        self.active_streams.add(1, &attributes);
+        let active = ActiveStream {
+            streams: self.active_streams.clone(),
+            attributes: Arc::clone(&attributes),
+        };
+        let counted = self.frames.clone();
+        let bytes = self.response_bytes.clone();
+        Box::pin(frames.inspect(move |frame: &Result<Frame, QuillError>| {
+            let _active = &active;
+            if let Ok(frame) = frame {
+                counted.add(1, &attributes);
+                bytes.add(frame.payload.len() as u64, &attributes);
+            }
+        }))
+    }
+}
+
+/// Ends an active stream when dropped
+struct ActiveStream {
+    streams: UpDownCounter<i64>,
+    attributes: Arc<[KeyValue]>,
+}
+
+impl Drop for ActiveStream {
+    fn drop(&mut self) {
+        self.streams.add(-1, &self.attributes);
+    }
+}
+
+/// Attributes identifying the method of a call
+fn method_attributes(path: &str, registered: bool) -> Vec<KeyValue> {
+    let (service, method) = parse_rpc_path(path)
+        .filter(|_| registered)
+        .unwrap_or_else(|| (OTHER_METHOD.to_string(), OTHER_METHOD.to_string()));
+    vec![
+        KeyValue::new("rpc.system", "quill"),
+        KeyValue::new("rpc.service", service),
+        KeyValue::new("rpc.method", method),
+    ]
+}
+
+/// Span of a call to `path`, parented to the caller's trace context
+pub(crate) fn request_span(path: &str, registered: bool, headers: &HeaderMap) -> Span {
+    let (service, method) = parse_rpc_path(path)
+        .filter(|_| registered)
+        .unwrap_or_else(|| (OTHER_METHOD.to_string(), OTHER_METHOD.to_string()));
+    let span = create_rpc_span(&service, &method);
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    span.set_parent(parent);
+    span
+}
+
+/// Reads trace context from request headers
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_sdk::metrics::data::{ResourceMetrics, Sum};
+    use opentelemetry_sdk::metrics::reader::{AggregationSelector, MetricReader, TemporalitySelector};
+    use opentelemetry_sdk::metrics::{Aggregation, InstrumentKind, ManualReader, Pipeline};
+    use opentelemetry_sdk::metrics::data::Temporality;
+    use std::sync::Weak;
+
+    /// Manual reader the test keeps a handle to after handing it to the provider
+    #[derive(Debug, Clone)]
+    struct SharedReader(Arc<ManualReader>);
+
+    impl TemporalitySelector for SharedReader {
+        fn temporality(&self, kind: InstrumentKind) -> Temporality {
+            self.0.temporality(kind)
+        }
+    }
+
+    impl AggregationSelector for SharedReader {
+        fn aggregation(&self, kind: InstrumentKind) -> Aggregation {
+            self.0.aggregation(kind)
+        }
+    }
+
+    impl MetricReader for SharedReader {
+        fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+            self.0.register_pipeline(pipeline)
+        }
+
+        fn collect(&self, rm: &mut ResourceMetrics) -> opentelemetry::metrics::Result<()> {
+            self.0.collect(rm)
+        }
+
+        fn force_flush(&self) -> opentelemetry::metrics::Result<()> {
+            self.0.force_flush()
+        }
+
+        fn shutdown(&self) -> opentelemetry::metrics::Result<()> {
+            self.0.shutdown()
+        }
+    }
+
+    fn sum_of<T: Copy + std::ops::Add<Output = T> + Default + 'static>(rm: &ResourceMetrics, name: &str) -> T {
+        rm.scope_metrics
+            .iter()
+            .flat_map(|scope| &scope.metrics)
+            .filter(|metric| metric.name == name)
+            .filter_map(|metric| metric.data.as_any().downcast_ref::<Sum<T>>())
+            .flat_map(|sum| &sum.data_points)
+            .fold(T::default(), |total, point| total + point.value)
+    }
+
+    #[tokio::test]
+    async fn test_stream_frames_and_activity() {
+        let reader = SharedReader(Arc::new(ManualReader::builder().build()));
+        let provider = SdkMeterProvider::builder().with_reader(reader.clone()).build();
+        let metrics = RpcMetrics::new(&provider.meter(SCOPE));
+
+        let frames: BoxFrameStream = Box::pin(futures_util::stream::iter(vec![
+            Ok(Frame::data(bytes::Bytes::from_static(b"hello"))),
+            Ok(Frame::data(bytes::Bytes::from_static(b"world!"))),
+            Ok(Frame::end_stream()),
+        ]));
+        let mut stream = metrics.observe_stream("chat.v1.Chat/Send", frames);
+        metrics.record_request("chat.v1.Chat/Send", true, Duration::from_millis(3), StatusCode::OK, 7, 0);
+
+        let mut rm = ResourceMetrics {
+            resource: Resource::empty(),
+            scope_metrics: Vec::new(),
+        };
+        reader.collect(&mut rm).unwrap();
+        assert_eq!(sum_of::<i64>(&rm, "rpc.server.active_streams"), 1);
+        assert_eq!(sum_of::<u64>(&rm, "rpc.server.requests"), 1);
+        assert_eq!(sum_of::<u64>(&rm, "rpc.server.request.size"), 7);
+
+        while stream.next().await.is_some() {}
+        drop(stream);
+        reader.collect(&mut rm).unwrap();
+        assert_eq!(sum_of::<i64>(&rm, "rpc.server.active_streams"), 0);
+        assert_eq!(sum_of::<u64>(&rm, "rpc.server.frames"), 3);
+        assert_eq!(sum_of::<u64>(&rm, "rpc.server.response.size"), 11);
+    }
+
+    #[test]
+    fn test_unregistered_paths_share_a_label() {
+        let attributes = method_attributes("chat.v1.Chat/Send", true);
+        assert!(attributes.contains(&KeyValue::new("rpc.service", "chat.v1.Chat")));
+        assert!(attributes.contains(&KeyValue::new("rpc.method", "Send")));
+
+        let attributes = method_attributes("random/probe", false);
+        assert!(attributes.contains(&KeyValue::new("rpc.method", OTHER_METHOD)));
+    }
+
+    #[test]
+    fn test_resource_names_the_service() {
+        let resource = OtelConfig::new("chat-service")
+            .with_resource_attribute("deployment.environment", "test")
+            .resource();
+        assert_eq!(
+            resource.get(Key::new("service.name")),
+            Some(Value::from("chat-service"))
+        );
+        assert_eq!(
+            resource.get(Key::new("deployment.environment")),
+            Some(Value::from("test"))
+        );
+    }
+}
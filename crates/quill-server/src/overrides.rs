@@ -0,0 +1,34 @@
+//! Per-method configuration overrides
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Values keyed by method (`pkg.Service/Method`) or whole service (`pkg.Service`)
+///
+/// Lookups prefer the method's own value over its service's.
+#[derive(Clone)]
+pub(crate) struct MethodOverrides<T>(HashMap<String, T>);
+
+impl<T> MethodOverrides<T> {
+    pub(crate) fn insert(&mut self, path: impl Into<String>, value: T) {
+        self.0.insert(path.into(), value);
+    }
+
+    pub(crate) fn get(&self, method: &str) -> Option<&T> {
+        self.0
+            .get(method)
+            .or_else(|| method.split_once('/').and_then(|(service, _)| self.0.get(service)))
+    }
+}
+
+impl<T> Default for MethodOverrides<T> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MethodOverrides<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -0,0 +1,236 @@
+//! Time-budgeted partial results for streaming responses
+//!
+//! This module provides:
+//! - A streaming response builder that tracks the request deadline
+//! - A PARTIAL trailer with completeness statistics when time runs out
+//! - A progress handle for reporting finished sources (shards, indexes, ...)
+//!
+//! Useful for search and retrieval services where returning what is ready
+//! by the deadline beats failing the whole call.
+
+use crate::streaming::RpcResponse;
+use bytes::Bytes;
+use quill_core::{Frame, PartialStats, QuillError};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{Instant, Sleep};
+use tokio_stream::Stream;
+
+/// Reports progress of a deadline-bounded response
+///
+/// Cheap to clone; hand one to each source worker.
+#[derive(Debug, Clone, Default)]
+pub struct PartialProgress {
+    sources_completed: Arc<AtomicU32>,
+}
+
+impl PartialProgress {
+    /// Mark one source as finished
+    pub fn source_completed(&self) {
+        self.sources_completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of sources finished so far
+    pub fn sources_completed(&self) -> u32 {
+        self.sources_completed.load(Ordering::Relaxed)
+    }
+}
+
+/// Builder for a streaming response bounded by a deadline
+///
+/// Messages are forwarded until the inner stream ends or the deadline
+/// passes. In the latter case the stream ends with a PARTIAL trailer
+/// instead of an error.
+#[derive(Debug, Clone)]
+pub struct PartialResponse {
+    start: Instant,
+    deadline: Instant,
+    expected: Option<u64>,
+    sources_total: Option<u32>,
+    progress: PartialProgress,
+}
+
+impl PartialResponse {
+    /// Bound the response by a time budget starting now
+    pub fn with_budget(budget: Duration) -> Self {
+        let start = Instant::now();
+        Self::bounded(start, start + budget)
+    }
+
+    /// Bound the response by an absolute deadline
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self::bounded(Instant::now(), deadline)
+    }
+
+    fn bounded(start: Instant, deadline: Instant) -> Self {
+        Self {
+            start,
+            deadline,
+            expected: None,
+            sources_total: None,
+            progress: PartialProgress::default(),
+        }
+    }
+
+    /// Number of messages a complete response would contain
+    pub fn expected(mut self, expected: u64) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Number of sources contributing to the response
+    pub fn sources(mut self, total: u32) -> Self {
+        self.sources_total = Some(total);
+        self
+    }
+
+    /// Handle for reporting finished sources
+    pub fn progress(&self) -> PartialProgress {
+        self.progress.clone()
+    }
+
+    /// Time left before the deadline
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// Wrap a message stream into a deadline-bounded response
+    pub fn stream<S>(self, inner: S) -> RpcResponse
+    where
+        S: Stream<Item = Result<Bytes, QuillError>> + Send + 'static,
+    {
+        RpcResponse::framed(DeadlineStream {
+            inner: Box::pin(inner),
+            sleep: Box::pin(tokio::time::sleep_until(self.deadline)),
+            config: self,
+            emitted: 0,
+            done: false,
+        })
+    }
+}
+
+struct DeadlineStream {
+    inner: Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>,
+    sleep: Pin<Box<Sleep>>,
+    config: PartialResponse,
+    emitted: u64,
+    done: bool,
+}
+
+impl DeadlineStream {
+    fn trailer(&self) -> Frame {
+        let stats = PartialStats {
+            emitted: self.emitted,
+            expected: self.config.expected,
+            sources_completed: self.config.progress.sources_completed(),
+            sources_total: self.config.sources_total,
+            elapsed_ms: self.config.start.elapsed().as_millis() as u64,
+            budget_ms: self.config.deadline.saturating_duration_since(self.config.start).as_millis() as u64,
+        };
+        tracing::debug!(
+            "Deadline reached after {} messages; ending stream with PARTIAL trailer",
+            stats.emitted
+        );
+        let json = stats.to_json().unwrap_or_else(|_| "{}".to_string());
+        Frame::partial(Bytes::from(json))
+    }
+}
+
+impl Stream for DeadlineStream {
+    type Item = Result<Frame, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        // Ready messages win over the deadline so nothing already produced is lost
+        match self.inner.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(message))) => {
+                self.emitted += 1;
+                return Poll::Ready(Some(Ok(Frame::data(message))));
+            }
+            Poll::Ready(Some(Err(e))) => {
+                self.done = true;
+                return Poll::Ready(Some(Err(e)));
+            }
+            Poll::Ready(None) => {
+                self.done = true;
+                return Poll::Ready(Some(Ok(Frame::end_stream())));
+            }
+            Poll::Pending => {}
+        }
+
+        match self.sleep.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                self.done = true;
+                Poll::Ready(Some(Ok(self.trailer())))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    async fn collect(response: RpcResponse) -> Vec<Frame> {
+        match response {
+            RpcResponse::Framed(stream) => stream.map(|f| f.unwrap()).collect().await,
+            _ => panic!("expected a framed response"),
+        }
+    }
+
+    /// One message every 10ms
+    fn ticking(n: u64) -> impl Stream<Item = Result<Bytes, QuillError>> + Send {
+        tokio_stream::iter(0..n).then(|i| async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(Bytes::from(i.to_string()))
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_complete_stream_ends_normally() {
+        let frames = collect(PartialResponse::with_budget(Duration::from_secs(1)).stream(ticking(3))).await;
+        assert_eq!(frames.len(), 4);
+        assert!(frames[..3].iter().all(|f| f.flags.is_data()));
+        assert!(frames[3].flags.is_end_stream());
+        assert!(!frames[3].flags.is_partial());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline_emits_partial_trailer() {
+        let response = PartialResponse::with_budget(Duration::from_millis(35)).expected(10);
+        let frames = collect(response.stream(ticking(10))).await;
+
+        let (trailer, data) = frames.split_last().unwrap();
+        assert_eq!(data.len(), 3);
+        assert!(trailer.flags.is_end_stream() && trailer.flags.is_partial());
+
+        let stats = PartialStats::from_json(&trailer.payload).unwrap();
+        assert_eq!(stats.emitted, 3);
+        assert_eq!(stats.expected, Some(10));
+        assert_eq!(stats.budget_ms, 35);
+        assert_eq!(stats.completeness(), Some(0.3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_progress_reports_sources() {
+        let response = PartialResponse::with_budget(Duration::from_millis(5)).sources(4);
+        let progress = response.progress();
+        progress.source_completed();
+        progress.source_completed();
+
+        let frames = collect(response.stream(tokio_stream::pending())).await;
+        let stats = PartialStats::from_json(&frames[0].payload).unwrap();
+        assert_eq!(stats.sources_completed, 2);
+        assert_eq!(stats.sources_total, Some(4));
+        assert_eq!(stats.completeness(), Some(0.5));
+    }
+}
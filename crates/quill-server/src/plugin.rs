@@ -0,0 +1,470 @@
+//! WASM handler plugins
+//!
+//! This module provides:
+//! - Sandbox limits for plugin modules (memory, fuel)
+//! - `PluginHost`, which compiles WASM modules and replaces them in place
+//!   when reloaded, without restarting the server
+//! - Registration of plugin-backed unary and server-streaming methods on
+//!   an [`RpcRouter`]
+//!
+//! # ABI
+//!
+//! A plugin is a core WASM module (e.g. built for `wasm32-wasip1`). Each
+//! call runs in a fresh instance with WASI preview1 available, but without
+//! preopened directories, environment or arguments. The module exports:
+//!
+//! - `memory`
+//! - `quill_alloc(len: i32) -> i32`: allocate `len` bytes for the host
+//! - `quill_handle(method_ptr: i32, method_len: i32, req_ptr: i32, req_len: i32) -> i32`:
+//!   handle a request for the method name (e.g. `Echo`); `0` on success
+//! - `_initialize` (optional): run once per instance before `quill_handle`
+//!
+//! and may import from module `quill`:
+//!
+//! - `emit(ptr: i32, len: i32)`: send one response message. Unary methods
+//!   emit exactly one; streaming methods emit any number, each delivered to
+//!   the client as it is emitted
+//! - `fail(ptr: i32, len: i32)`: set the error message returned when
+//!   `quill_handle` returns non-zero
+
+use crate::router::RpcRouter;
+use crate::streaming::RpcResponse;
+use bytes::Bytes;
+use quill_core::QuillError;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use wasmtime::{Caller, Engine, Extern, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::WasiCtxBuilder;
+
+/// Messages buffered between a streaming plugin and its client
+const STREAM_BUFFER: usize = 16;
+
+/// Plugin errors
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("Failed to read plugin {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid plugin module: {0}")]
+    Module(String),
+
+    #[error("Plugin '{0}' is not loaded")]
+    NotLoaded(String),
+
+    #[error("Plugin trapped: {0}")]
+    Trap(String),
+
+    #[error("Plugin failed: {0}")]
+    Failed(String),
+}
+
+impl From<PluginError> for QuillError {
+    fn from(error: PluginError) -> Self {
+        QuillError::Rpc(error.to_string())
+    }
+}
+
+/// Sandbox limits applied to every plugin call
+#[derive(Debug, Clone)]
+pub struct PluginConfig {
+    /// Largest linear memory of one instance in bytes
+    pub max_memory_bytes: usize,
+    /// Fuel (roughly, WASM instructions) available to one call; unlimited if `None`
+    pub fuel: Option<u64>,
+    /// Forward plugin stdout/stderr to the server's
+    pub inherit_stdio: bool,
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: 64 * 1024 * 1024,
+            fuel: Some(1_000_000_000),
+            inherit_stdio: true,
+        }
+    }
+}
+
+/// A compiled plugin module
+struct Plugin {
+    module: Module,
+    path: Option<PathBuf>,
+    generation: u64,
+}
+
+/// Per-call store state
+struct CallState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+    output: Output,
+    failure: Option<String>,
+}
+
+/// Where emitted messages go
+enum Output {
+    Collect(Vec<Bytes>),
+    Stream(mpsc::Sender<Result<Bytes, QuillError>>),
+}
+
+/// Compiles, holds and runs WASM handler plugins
+pub struct PluginHost {
+    engine: Engine,
+    linker: Linker<CallState>,
+    config: PluginConfig,
+    plugins: RwLock<HashMap<String, Arc<Plugin>>>,
+}
+
+impl PluginHost {
+    /// Create a host with the given sandbox limits
+    pub fn new(config: PluginConfig) -> Result<Self, PluginError> {
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(config.fuel.is_some());
+        let engine = Engine::new(&engine_config).map_err(module_error)?;
+
+        let mut linker = Linker::new(&engine);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut CallState| &mut state.wasi).map_err(module_error)?;
+        linker
+            .func_wrap("quill", "emit", |mut caller: Caller<'_, CallState>, ptr: i32, len: i32| {
+                let message = Bytes::from(read_guest(&mut caller, ptr, len)?);
+                match &mut caller.data_mut().output {
+                    Output::Collect(messages) => messages.push(message),
+                    Output::Stream(tx) => tx
+                        .blocking_send(Ok(message))
+                        .map_err(|_| wasmtime::Error::msg("response stream closed"))?,
+                }
+                Ok(())
+            })
+            .map_err(module_error)?;
+        linker
+            .func_wrap("quill", "fail", |mut caller: Caller<'_, CallState>, ptr: i32, len: i32| {
+                let message = read_guest(&mut caller, ptr, len)?;
+                caller.data_mut().failure = Some(String::from_utf8_lossy(&message).into_owned());
+                Ok(())
+            })
+            .map_err(module_error)?;
+
+        Ok(Self {
+            engine,
+            linker,
+            config,
+            plugins: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Compile the module at `path` and install it as `name`, replacing any previous version
+    pub fn load(&self, name: &str, path: &Path) -> Result<u64, PluginError> {
+        let bytes = std::fs::read(path).map_err(|source| PluginError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        self.install(name, &bytes, Some(path.to_path_buf()))
+    }
+
+    /// Compile a module (binary or text format) and install it as `name`
+    pub fn load_bytes(&self, name: &str, bytes: &[u8]) -> Result<u64, PluginError> {
+        self.install(name, bytes, None)
+    }
+
+    /// Recompile `name` from the file it was loaded from
+    ///
+    /// The previous version keeps serving if compilation fails. Calls
+    /// already in progress finish on the version they started with.
+    pub fn reload(&self, name: &str) -> Result<u64, PluginError> {
+        let path = self
+            .plugin(name)?
+            .path
+            .clone()
+            .ok_or_else(|| PluginError::Module(format!("plugin '{}' was not loaded from a file", name)))?;
+        self.load(name, &path)
+    }
+
+    /// Remove a plugin; its methods fail until it is loaded again
+    pub fn unload(&self, name: &str) -> bool {
+        self.plugins.write().unwrap().remove(name).is_some()
+    }
+
+    /// Names of the loaded plugins
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.plugins.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// How many times `name` has been (re)loaded
+    pub fn generation(&self, name: &str) -> Option<u64> {
+        self.plugins.read().unwrap().get(name).map(|plugin| plugin.generation)
+    }
+
+    /// Register a unary method served by plugin `name`
+    ///
+    /// The plugin is looked up on every call, so reloads take effect
+    /// without re-registering.
+    pub fn register_unary(self: &Arc<Self>, router: &mut RpcRouter, path: &str, name: &str) {
+        let host = Arc::clone(self);
+        let name = name.to_string();
+        let method = method_name(path);
+        router.register_unary(path.to_string(), move |request: Bytes| {
+            let host = Arc::clone(&host);
+            let name = name.clone();
+            let method = method.clone();
+            async move {
+                let messages = tokio::task::spawn_blocking(move || {
+                    host.call(&name, &method, &request, Output::Collect(Vec::new()))
+                })
+                .await
+                .map_err(|e| QuillError::Rpc(format!("Plugin task failed: {}", e)))??;
+                match <[Bytes; 1]>::try_from(messages) {
+                    Ok([message]) => Ok(message),
+                    Err(messages) => Err(PluginError::Failed(format!(
+                        "unary method emitted {} messages, expected 1",
+                        messages.len()
+                    ))
+                    .into()),
+                }
+            }
+        });
+    }
+
+    /// Register a server-streaming method served by plugin `name`
+    pub fn register_streaming(self: &Arc<Self>, router: &mut RpcRouter, path: &str, name: &str) {
+        let host = Arc::clone(self);
+        let name = name.to_string();
+        let method = method_name(path);
+        router.register(path.to_string(), move |request: Bytes| {
+            let host = Arc::clone(&host);
+            let name = name.clone();
+            let method = method.clone();
+            async move {
+                let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+                tokio::task::spawn_blocking(move || {
+                    if let Err(e) = host.call(&name, &method, &request, Output::Stream(tx.clone())) {
+                        let _ = tx.blocking_send(Err(e.into()));
+                    }
+                });
+                Ok(RpcResponse::Streaming(Box::pin(ReceiverStream::new(rx))))
+            }
+        });
+    }
+
+    fn plugin(&self, name: &str) -> Result<Arc<Plugin>, PluginError> {
+        self.plugins
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| PluginError::NotLoaded(name.to_string()))
+    }
+
+    fn install(&self, name: &str, bytes: &[u8], path: Option<PathBuf>) -> Result<u64, PluginError> {
+        let module = Module::new(&self.engine, bytes).map_err(module_error)?;
+        for export in ["memory", "quill_alloc", "quill_handle"] {
+            if module.get_export(export).is_none() {
+                return Err(PluginError::Module(format!("missing export '{}'", export)));
+            }
+        }
+
+        let mut plugins = self.plugins.write().unwrap();
+        let generation = plugins.get(name).map_or(1, |previous| previous.generation + 1);
+        plugins.insert(
+            name.to_string(),
+            Arc::new(Plugin {
+                module,
+                path,
+                generation,
+            }),
+        );
+        Ok(generation)
+    }
+
+    /// Run one call to completion on the calling (blocking) thread
+    fn call(&self, name: &str, method: &str, request: &[u8], output: Output) -> Result<Vec<Bytes>, PluginError> {
+        let plugin = self.plugin(name)?;
+
+        let mut wasi = WasiCtxBuilder::new();
+        if self.config.inherit_stdio {
+            wasi.inherit_stdout().inherit_stderr();
+        }
+        let state = CallState {
+            wasi: wasi.build_p1(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.config.max_memory_bytes)
+                .build(),
+            output,
+            failure: None,
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        if let Some(fuel) = self.config.fuel {
+            store.set_fuel(fuel).map_err(trap_error)?;
+        }
+
+        let instance = self.linker.instantiate(&mut store, &plugin.module).map_err(trap_error)?;
+        if let Ok(initialize) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+            initialize.call(&mut store, ()).map_err(trap_error)?;
+        }
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "quill_alloc")
+            .map_err(module_error)?;
+        let handle = instance
+            .get_typed_func::<(i32, i32, i32, i32), i32>(&mut store, "quill_handle")
+            .map_err(module_error)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| PluginError::Module("export 'memory' is not a memory".to_string()))?;
+
+        let place = |store: &mut Store<CallState>, data: &[u8]| -> Result<(i32, i32), PluginError> {
+            let len = i32::try_from(data.len()).map_err(|_| PluginError::Failed("request too large".to_string()))?;
+            let ptr = alloc.call(&mut *store, len).map_err(trap_error)?;
+            memory
+                .write(&mut *store, ptr as u32 as usize, data)
+                .map_err(|e| PluginError::Trap(e.to_string()))?;
+            Ok((ptr, len))
+        };
+        let (method_ptr, method_len) = place(&mut store, method.as_bytes())?;
+        let (req_ptr, req_len) = place(&mut store, request)?;
+
+        let status = handle
+            .call(&mut store, (method_ptr, method_len, req_ptr, req_len))
+            .map_err(trap_error)?;
+        let state = store.into_data();
+        if status != 0 {
+            return Err(PluginError::Failed(
+                state.failure.unwrap_or_else(|| format!("{} returned status {}", method, status)),
+            ));
+        }
+        match state.output {
+            Output::Collect(messages) => Ok(messages),
+            Output::Stream(_) => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Copy `len` bytes at `ptr` out of the caller's memory
+fn read_guest(caller: &mut Caller<'_, CallState>, ptr: i32, len: i32) -> wasmtime::Result<Vec<u8>> {
+    let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+        return Err(wasmtime::Error::msg("plugin does not export memory"));
+    };
+    let start = ptr as u32 as usize;
+    let end = start
+        .checked_add(len as u32 as usize)
+        .filter(|end| *end <= memory.data_size(&*caller))
+        .ok_or_else(|| wasmtime::Error::msg("message out of bounds"))?;
+    Ok(memory.data(&*caller)[start..end].to_vec())
+}
+
+/// Method name of an RPC path (`pkg.Service/Method` → `Method`)
+fn method_name(path: &str) -> String {
+    path.rsplit('/').next().unwrap_or(path).to_string()
+}
+
+fn module_error(error: wasmtime::Error) -> PluginError {
+    PluginError::Module(format!("{:#}", error))
+}
+
+fn trap_error(error: wasmtime::Error) -> PluginError {
+    PluginError::Trap(format!("{:#}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Echoes the request, or fails if it is empty
+    const ECHO: &str = r#"
+        (module
+          (import "quill" "emit" (func $emit (param i32 i32)))
+          (import "quill" "fail" (func $fail (param i32 i32)))
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (data (i32.const 0) "boom")
+          (func (export "quill_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "quill_handle") (param $m i32) (param $ml i32) (param $r i32) (param $rl i32) (result i32)
+            (if (i32.eqz (local.get $rl))
+              (then
+                (call $fail (i32.const 0) (i32.const 4))
+                (return (i32.const 1))))
+            (call $emit (local.get $r) (local.get $rl))
+            (i32.const 0)))
+    "#;
+
+    /// Never returns
+    const SPIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "quill_alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "quill_handle") (param i32 i32 i32 i32) (result i32)
+            (loop $l (br $l))
+            (i32.const 0)))
+    "#;
+
+    #[test]
+    fn test_call_and_failure() {
+        let host = PluginHost::new(PluginConfig::default()).unwrap();
+        assert_eq!(host.load_bytes("echo", ECHO.as_bytes()).unwrap(), 1);
+
+        let messages = host.call("echo", "Echo", b"hello", Output::Collect(Vec::new())).unwrap();
+        assert_eq!(messages, vec![Bytes::from_static(b"hello")]);
+
+        let err = host.call("echo", "Echo", b"", Output::Collect(Vec::new())).unwrap_err();
+        assert!(matches!(err, PluginError::Failed(ref message) if message == "boom"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_exports_rejected() {
+        let host = PluginHost::new(PluginConfig::default()).unwrap();
+        let err = host.load_bytes("empty", b"(module (memory (export \"memory\") 1))").unwrap_err();
+        assert!(err.to_string().contains("quill_alloc"), "{}", err);
+        assert!(host.names().is_empty());
+    }
+
+    #[test]
+    fn test_fuel_limit_stops_runaway_plugin() {
+        let host = PluginHost::new(PluginConfig {
+            fuel: Some(100_000),
+            ..PluginConfig::default()
+        })
+        .unwrap();
+        host.load_bytes("spin", SPIN.as_bytes()).unwrap();
+
+        let err = host.call("spin", "Spin", b"", Output::Collect(Vec::new())).unwrap_err();
+        assert!(matches!(err, PluginError::Trap(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_reload_replaces_module() {
+        let dir = std::env::temp_dir().join(format!("quill-plugin-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("plugin.wat");
+        std::fs::write(&path, SPIN).unwrap();
+
+        let host = PluginHost::new(PluginConfig::default()).unwrap();
+        host.load("p", &path).unwrap();
+        std::fs::write(&path, ECHO).unwrap();
+        assert_eq!(host.reload("p").unwrap(), 2);
+        assert_eq!(host.generation("p"), Some(2));
+
+        let messages = host.call("p", "Echo", b"v2", Output::Collect(Vec::new())).unwrap();
+        assert_eq!(messages, vec![Bytes::from_static(b"v2")]);
+
+        std::fs::write(&path, "(module").unwrap();
+        assert!(host.reload("p").is_err());
+        assert_eq!(host.generation("p"), Some(2));
+
+        assert!(host.unload("p"));
+        assert!(matches!(host.reload("p"), Err(PluginError::NotLoaded(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+//! Reflection of registered services
+//!
+//! This module provides:
+//! - A registry of the file descriptors a server was given
+//! - The descriptor set answered by the built-in reflection method
+//!
+//! Routes carry no schema, so descriptors come from the application, e.g.
+//! the `FILE_DESCRIPTOR_SET` constant generated by quill-codegen. Only files
+//! defining a service with registered methods are served, together with
+//! the files they import; descriptors of anything else stay private. See
+//! [`quill_core::reflection`] for the request and response format.
+
+use bytes::Bytes;
+use prost::Message;
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use quill_core::QuillError;
+use std::collections::HashSet;
+
+/// File descriptors of the services a server may describe
+#[derive(Debug, Default)]
+pub(crate) struct ReflectionRegistry {
+    /// Files in the order they were added, unique by name
+    files: Vec<FileDescriptorProto>,
+}
+
+impl ReflectionRegistry {
+    /// Add the files of an encoded `FileDescriptorSet`
+    ///
+    /// Files already known by name are skipped.
+    pub(crate) fn add(&mut self, descriptor_set: &[u8]) -> Result<(), QuillError> {
+        let set = FileDescriptorSet::decode(descriptor_set)
+            .map_err(|e| QuillError::Rpc(format!("Invalid file descriptor set: {}", e)))?;
+        for file in set.file {
+            if !self.files.iter().any(|known| known.name == file.name) {
+                self.files.push(file);
+            }
+        }
+        Ok(())
+    }
+
+    /// Encoded descriptor set describing the served services
+    ///
+    /// `served` holds the service part of every registered route, either
+    /// fully qualified (`greeter.v1.Greeter`) or bare (`Greeter`). With
+    /// `requested`, only the file defining that service is included. Files
+    /// come after the files they import.
+    pub(crate) fn descriptor_set(
+        &self,
+        served: &HashSet<&str>,
+        requested: Option<&str>,
+    ) -> Result<Bytes, String> {
+        let mut roots = Vec::new();
+        for file in &self.files {
+            let package = file.package();
+            let matches = file.service.iter().any(|service| {
+                let full_name = qualified(package, service.name());
+                let is_served =
+                    served.contains(full_name.as_str()) || served.contains(service.name());
+                let is_requested =
+                    requested.map_or(true, |name| name == full_name || name == service.name());
+                is_served && is_requested
+            });
+            if matches {
+                roots.push(file.name());
+            }
+        }
+        if let (Some(name), true) = (requested, roots.is_empty()) {
+            return Err(format!("Service '{}' is not served or has no descriptor", name));
+        }
+
+        let mut included = HashSet::new();
+        let mut set = FileDescriptorSet::default();
+        for root in roots {
+            self.include(root, &mut included, &mut set);
+        }
+        Ok(Bytes::from(set.encode_to_vec()))
+    }
+
+    /// Append `name` to `set` after its imports, unless already included
+    fn include<'a>(
+        &'a self,
+        name: &'a str,
+        included: &mut HashSet<&'a str>,
+        set: &mut FileDescriptorSet,
+    ) {
+        if !included.insert(name) {
+            return;
+        }
+        // Imports missing from the registry are left to the caller's own pool
+        let Some(file) = self.files.iter().find(|file| file.name() == name) else {
+            return;
+        };
+        for dependency in &file.dependency {
+            self.include(dependency, included, set);
+        }
+        set.file.push(file.clone());
+    }
+}
+
+fn qualified(package: &str, name: &str) -> String {
+    if package.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", package, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_types::{DescriptorProto, ServiceDescriptorProto};
+
+    fn file(
+        name: &str,
+        package: &str,
+        services: &[&str],
+        dependencies: &[&str],
+    ) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_string()),
+            package: Some(package.to_string()),
+            dependency: dependencies.iter().map(|d| d.to_string()).collect(),
+            message_type: vec![DescriptorProto {
+                name: Some("Message".to_string()),
+                ..Default::default()
+            }],
+            service: services
+                .iter()
+                .map(|service| ServiceDescriptorProto {
+                    name: Some(service.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn registry() -> ReflectionRegistry {
+        let set = FileDescriptorSet {
+            file: vec![
+                file("common.proto", "common.v1", &[], &[]),
+                file("greeter.proto", "greeter.v1", &["Greeter"], &["common.proto"]),
+                file("admin.proto", "admin.v1", &["Admin"], &["common.proto"]),
+            ],
+        };
+        let mut registry = ReflectionRegistry::default();
+        registry.add(&set.encode_to_vec()).unwrap();
+        // Files already known are not added twice
+        registry.add(&set.encode_to_vec()).unwrap();
+        registry
+    }
+
+    fn file_names(bytes: Bytes) -> Vec<String> {
+        FileDescriptorSet::decode(bytes)
+            .unwrap()
+            .file
+            .into_iter()
+            .map(|f| f.name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_only_served_services_are_described() {
+        let registry = registry();
+
+        // Bare names, as generated stubs register them, count as well
+        let served = HashSet::from(["Greeter"]);
+        let set = registry.descriptor_set(&served, None).unwrap();
+        assert_eq!(file_names(set), vec!["common.proto", "greeter.proto"]);
+
+        let served = HashSet::from(["greeter.v1.Greeter", "admin.v1.Admin"]);
+        let set = registry.descriptor_set(&served, None).unwrap();
+        assert_eq!(file_names(set), vec!["common.proto", "greeter.proto", "admin.proto"]);
+    }
+
+    #[test]
+    fn test_requested_service() {
+        let registry = registry();
+        let served = HashSet::from(["greeter.v1.Greeter", "admin.v1.Admin"]);
+
+        let set = registry.descriptor_set(&served, Some("admin.v1.Admin")).unwrap();
+        assert_eq!(file_names(set), vec!["common.proto", "admin.proto"]);
+
+        assert!(registry.descriptor_set(&served, Some("billing.v1.Billing")).is_err());
+        let served = HashSet::from(["greeter.v1.Greeter"]);
+        assert!(registry.descriptor_set(&served, Some("admin.v1.Admin")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_descriptor_set() {
+        let mut registry = ReflectionRegistry::default();
+        assert!(registry.add(b"\xff\xff\xff").is_err());
+    }
+}
@@ -1,42 +1,71 @@
 //! Server-side request streaming support
 
+use crate::cancel_ack::ClientCancel;
 use bytes::Bytes;
 use hyper::body::Incoming;
-use quill_core::{CreditTracker, FrameParser, QuillError};
+use quill_core::{CreditTracker, FrameCipher, FrameParser, ProblemDetails, QuillError};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use tokio_stream::Stream;
 
 /// Stream adapter that parses frames from incoming request body
-pub struct RequestFrameStream {
-    body: Incoming,
+pub struct RequestFrameStream<B = Incoming> {
+    body: B,
     parser: FrameParser,
     credits: CreditTracker,
     messages_received: u32,
+    /// Opens frames of an encrypted request stream
+    cipher: Option<FrameCipher>,
+    /// Told when the client sends CANCEL
+    cancel: Option<ClientCancel>,
 }
 
-impl RequestFrameStream {
-    pub fn new(body: Incoming) -> Self {
+impl<B> RequestFrameStream<B> {
+    pub fn new(body: B) -> Self {
         Self {
             body,
             parser: FrameParser::new(),
             credits: CreditTracker::with_defaults(),
             messages_received: 0,
+            cipher: None,
+            cancel: None,
         }
     }
+
+    /// Open the frames of the stream with `cipher`
+    pub(crate) fn decrypt(mut self, cipher: Option<FrameCipher>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Record a CANCEL from the client in `cancel`
+    pub(crate) fn on_cancel(mut self, cancel: ClientCancel) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
 }
 
-impl Stream for RequestFrameStream {
+impl<B> Stream for RequestFrameStream<B>
+where
+    B: http_body::Body<Data = Bytes> + Unpin,
+    B::Error: std::fmt::Display,
+{
     type Item = Result<Bytes, QuillError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        use http_body::Body;
         use quill_core::DEFAULT_CREDIT_REFILL;
 
         loop {
             // Try to parse a frame from buffered data
             match self.parser.parse_frame() {
                 Ok(Some(frame)) => {
+                    let frame = match &self.cipher {
+                        Some(cipher) => match cipher.open(frame) {
+                            Ok(frame) => frame,
+                            Err(e) => return Poll::Ready(Some(Err(e.into()))),
+                        },
+                        None => frame,
+                    };
                     if frame.flags.is_end_stream() {
                         // Stream ended
                         return Poll::Ready(None);
@@ -68,10 +97,15 @@ impl Stream for RequestFrameStream {
                         return Poll::Ready(Some(Ok(frame.payload)));
                     }
                     if frame.flags.is_cancel() {
-                        // Stream was cancelled by client
-                        return Poll::Ready(Some(Err(QuillError::Rpc(
-                            "Stream cancelled by client".to_string()
-                        ))));
+                        // Stream was cancelled by client, possibly saying why
+                        if let Some(cancel) = &self.cancel {
+                            cancel.cancel();
+                        }
+                        let error = match serde_json::from_slice::<ProblemDetails>(&frame.payload) {
+                            Ok(problem) => QuillError::ProblemDetails(problem),
+                            Err(_) => QuillError::Rpc("Stream cancelled by client".to_string()),
+                        };
+                        return Poll::Ready(Some(Err(error)));
                     }
                     // Other frame types, continue
                 }
@@ -87,7 +121,7 @@ impl Stream for RequestFrameStream {
             match Pin::new(&mut self.body).poll_frame(cx) {
                 Poll::Ready(Some(Ok(frame))) => {
                     if let Ok(data) = frame.into_data() {
-                        self.parser.feed(&data);
+                        self.parser.feed_bytes(data);
                     }
                 }
                 Poll::Ready(Some(Err(e))) => {
@@ -0,0 +1,259 @@
+//! Pre-serialized responses for hot static methods
+//!
+//! This module provides:
+//! - Cached responses, optionally pre-compressed per content encoding
+//! - A shared cache that handlers and the application fill and invalidate
+//!
+//! Methods returning effectively static data (model metadata, vocab lists)
+//! spend most of each call serializing and compressing the same bytes. Once
+//! a response is in the cache, the router answers the method's unary calls
+//! with it without reading the request or running the handler, picking the
+//! first pre-compressed body the caller accepts. Sealed envelopes and
+//! chunked uploads always reach the handler. Since the request is never
+//! read, cached calls are not payload-sampled, their responses are not
+//! dictionary-compressed, and they never carry debug context.
+//!
+//! Entries stay until invalidated through [`ResponseCache`]. The built-in
+//! invalidation RPC ([`quill_core::RESPONSE_CACHE_INVALIDATE_PATH`]) is only
+//! served when [`ResponseCacheConfig::remote_invalidation`] authorizes it.
+
+use crate::middleware::compress_zstd;
+use bytes::Bytes;
+use http::HeaderMap;
+use quill_core::QuillError;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Check whether a caller, by its request headers, may invalidate cached responses
+pub type InvalidationAuthFn = Arc<dyn Fn(&HeaderMap) -> bool + Send + Sync>;
+
+/// Pre-serialized response of a method
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    body: Bytes,
+    /// Pre-compressed bodies by content encoding, in order of preference
+    encodings: Vec<(String, Bytes)>,
+}
+
+impl CachedResponse {
+    /// Cache an encoded response message
+    pub fn new(body: impl Into<Bytes>) -> Self {
+        Self { body: body.into(), encodings: Vec::new() }
+    }
+
+    /// Add a body already compressed with `encoding` (e.g. `zstd`)
+    ///
+    /// Callers accepting several encodings get the first one added.
+    pub fn with_encoding(mut self, encoding: impl Into<String>, body: impl Into<Bytes>) -> Self {
+        let encoding = encoding.into();
+        self.encodings.retain(|(known, _)| *known != encoding);
+        self.encodings.push((encoding, body.into()));
+        self
+    }
+
+    /// Add a zstd-compressed body, compressed once at `level`
+    pub fn with_zstd(self, level: i32) -> Result<Self, QuillError> {
+        let compressed = compress_zstd(&self.body, level)?;
+        Ok(self.with_encoding("zstd", compressed))
+    }
+
+    /// Uncompressed response message
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Body to send to a caller with these request headers, and its content encoding
+    pub(crate) fn negotiate(&self, headers: &http::HeaderMap) -> (Option<&str>, &Bytes) {
+        let accepted: Vec<&str> = headers
+            .get_all(http::header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|coding| {
+                let mut params = coding.split(';').map(str::trim);
+                let name = params.next()?;
+                let refused = params.any(|param| {
+                    param
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| q <= 0.0)
+                });
+                (!refused).then_some(name)
+            })
+            .collect();
+
+        self.encodings
+            .iter()
+            .find(|(encoding, _)| accepted.iter().any(|name| name.eq_ignore_ascii_case(encoding)))
+            .map_or((None, &self.body), |(encoding, body)| (Some(encoding.as_str()), body))
+    }
+}
+
+/// Cached responses by method path, shared by the router and its handlers
+///
+/// Clones refer to the same entries, so a handler holding a clone can cache
+/// its own response for later calls.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCache {
+    entries: Arc<RwLock<HashMap<String, Arc<CachedResponse>>>>,
+}
+
+impl ResponseCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer calls of `path` (`pkg.Service/Method`) with `response` until invalidated
+    pub fn insert(&self, path: impl Into<String>, response: CachedResponse) {
+        self.entries.write().unwrap().insert(path.into(), Arc::new(response));
+    }
+
+    /// Cached response of `path`, if any
+    pub fn get(&self, path: &str) -> Option<Arc<CachedResponse>> {
+        self.entries.read().unwrap().get(path).cloned()
+    }
+
+    /// Drop the cached responses of a method or a whole service
+    ///
+    /// `target` is a method (`pkg.Service/Method`) or a service
+    /// (`pkg.Service`). Returns the number of responses dropped.
+    pub fn invalidate(&self, target: &str) -> usize {
+        let mut entries = self.entries.write().unwrap();
+        let before = entries.len();
+        entries.retain(|path, _| {
+            path != target && path.split_once('/').map_or(true, |(service, _)| service != target)
+        });
+        before - entries.len()
+    }
+
+    /// Drop every cached response, returning how many were dropped
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.write().unwrap();
+        let dropped = entries.len();
+        entries.clear();
+        dropped
+    }
+
+    /// Number of cached responses
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    /// Whether no responses are cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How the router serves a [`ResponseCache`]
+///
+/// A bare [`ResponseCache`] converts into a configuration without remote
+/// invalidation.
+#[derive(Clone)]
+pub struct ResponseCacheConfig {
+    cache: ResponseCache,
+    invalidation: Option<InvalidationAuthFn>,
+}
+
+impl ResponseCacheConfig {
+    /// Serve `cache`, without the built-in invalidation RPC
+    pub fn new(cache: ResponseCache) -> Self {
+        Self { cache, invalidation: None }
+    }
+
+    /// Serve the built-in invalidation RPC to callers `authorize` accepts
+    ///
+    /// Other callers get `403 Forbidden`. Without this the method is not
+    /// served at all.
+    pub fn remote_invalidation<F>(mut self, authorize: F) -> Self
+    where
+        F: Fn(&HeaderMap) -> bool + Send + Sync + 'static,
+    {
+        self.invalidation = Some(Arc::new(authorize));
+        self
+    }
+
+    /// The served cache
+    pub fn cache(&self) -> &ResponseCache {
+        &self.cache
+    }
+
+    /// Whether the caller sending these headers may invalidate, or `None`
+    /// when remote invalidation is off
+    pub(crate) fn authorizes_invalidation(&self, headers: &HeaderMap) -> Option<bool> {
+        self.invalidation.as_ref().map(|authorize| authorize(headers))
+    }
+}
+
+impl From<ResponseCache> for ResponseCacheConfig {
+    fn from(cache: ResponseCache) -> Self {
+        Self::new(cache)
+    }
+}
+
+impl fmt::Debug for ResponseCacheConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCacheConfig")
+            .field("cache", &self.cache)
+            .field("remote_invalidation", &self.invalidation.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::ACCEPT_ENCODING;
+
+    fn accepting(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_negotiates_pre_compressed_body() {
+        let response = CachedResponse::new(Bytes::from(vec![7u8; 4096]))
+            .with_zstd(3)
+            .unwrap()
+            .with_encoding("br", Bytes::from_static(b"brotli"));
+
+        let (encoding, body) = response.negotiate(&HeaderMap::new());
+        assert_eq!(encoding, None);
+        assert_eq!(body.len(), 4096);
+
+        let (encoding, body) = response.negotiate(&accepting("gzip, zstd"));
+        assert_eq!(encoding, Some("zstd"));
+        assert!(body.len() < 4096);
+        assert_eq!(compress_zstd(response.body(), 3).unwrap(), *body);
+
+        let (encoding, _) = response.negotiate(&accepting("zstd;q=0, br"));
+        assert_eq!(encoding, Some("br"));
+        let (encoding, _) = response.negotiate(&accepting("gzip"));
+        assert_eq!(encoding, None);
+    }
+
+    #[test]
+    fn test_invalidate_method_or_service() {
+        let cache = ResponseCache::new();
+        cache.insert("models.v1.Models/List", CachedResponse::new(Bytes::from_static(b"list")));
+        cache.insert("models.v1.Models/Vocab", CachedResponse::new(Bytes::from_static(b"vocab")));
+        cache.insert("models.v1.ModelsAdmin/Get", CachedResponse::new(Bytes::from_static(b"get")));
+
+        assert_eq!(cache.invalidate("models.v1.Models/List"), 1);
+        assert!(cache.get("models.v1.Models/List").is_none());
+        assert_eq!(cache.invalidate("models.v1.Models/List"), 0);
+
+        // Service names match whole services, not prefixes
+        assert_eq!(cache.invalidate("models.v1.Models"), 1);
+        assert_eq!(
+            cache.get("models.v1.ModelsAdmin/Get").unwrap().body(),
+            &Bytes::from_static(b"get")
+        );
+
+        assert_eq!(cache.clone().clear(), 1);
+        assert!(cache.is_empty());
+    }
+}
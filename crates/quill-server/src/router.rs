@@ -3,18 +3,57 @@
 //! Routes match the pattern: /{package}.{Service}/{Method}
 
 use bytes::Bytes;
-use futures_util::stream::StreamExt as FuturesStreamExt;
+use futures_util::future::FutureExt;
+use futures_util::stream::{StreamExt as FuturesStreamExt, TryStreamExt};
+use http::header::CONTENT_ENCODING;
 use http::{Method, Request, Response, StatusCode};
 use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full, StreamBody};
-use hyper::body::{Frame as HyperFrame, Incoming};
-use quill_core::{Frame, ProblemDetails, QuillError};
+use hyper::body::Frame as HyperFrame;
+use quill_core::{
+    decode_batch_request, encode_batch_response, BatchEntry, BatchResult, FlowControlHeader, Frame,
+    FrameParser, ProblemDetails, QuillError, StreamCursor, BATCH_PATH, DICTIONARY_CAPABILITY_HEADER, DICTIONARY_PATH,
+    ENVELOPE_HEADER, FLOW_CONTROL_HEADER, FLOW_CREDIT_PATH, FRAME_ENCRYPTION_HEADER, HEALTH_CHECK_PATH, HEALTH_SERVICE,
+    HEALTH_WATCH_PATH, PING_PATH, REFLECTION_PATH, RESPONSE_CACHE_INVALIDATE_PATH,
+    HealthCheckResponse, ServingStatus, UPLOAD_CAPABILITY_HEADER, UPLOAD_MANIFEST_HEADER,
+};
+use crate::batch::BatchRpcConfig;
+use crate::cancel_ack::{acknowledge_cancel, ClientCancel};
+use crate::coalesce::{CoalescedFrames, FrameCoalescingConfig};
+use crate::context::RequestContext;
+use crate::cursor::{resume_cursor, CursorHandlerFn};
+use crate::debug::{panic_message, DebugPolicy};
+use crate::dictionary::{DictionaryCodec, DictionaryCompression};
+use crate::envelope::{EnvelopeDecryption, EnvelopeOpener};
+use crate::flow_control::FlowControlRegistry;
+use crate::frame_scheduler::{ChunkStream, ConnectionScheduler, FrameScheduling, FrameSchedulerStats, FrameSchedulingConfig};
+use crate::frame_encryption::{FrameEncryption, ResponseEncryption};
+use crate::health::HealthReporter;
+use crate::idle_timeout::{StreamIdleConfig, StreamIdleGuard};
+use crate::middleware::decompress_zstd;
+use crate::observability::{ObservabilityCollector, PROMETHEUS_CONTENT_TYPE};
+#[cfg(feature = "otel")]
+use crate::otel::RpcMetrics;
+use crate::reflection::ReflectionRegistry;
 use crate::request_stream::RequestFrameStream;
+use crate::response_cache::{CachedResponse, ResponseCache, ResponseCacheConfig};
+use crate::sampling::{PayloadDirection, PayloadSampler, PayloadSamplingConfig};
+use crate::schedule::{JobRun, ScheduledJob, Scheduler, SCHEDULER_HISTORY_PATH};
+use crate::slow_consumer::{LagStats, SlowConsumerConfig, SlowConsumerDetector};
+use crate::stream_gc::{StreamGc, StreamGcConfig, StreamGcStats, StreamLeak, STREAM_GC_LEAKS_PATH};
 use crate::streaming::RpcResponse;
-use std::collections::HashMap;
+use crate::tenant::{TenantIsolationConfig, TenantPermit, TenantRegistry, TenantStats};
+use crate::transcode::{self, JsonTranscoder, JSON_CONTENT_TYPE};
+use crate::upload::{ChunkOutcome, ChunkedUploadConfig, UploadStore};
+use crate::BoxFrameStream;
+#[cfg(feature = "tensor")]
+use quill_tensor::TensorFrame;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
 use std::sync::Arc;
 use tokio_stream::Stream;
+use tracing::Instrument;
 
 /// Type alias for request stream (for client streaming)
 pub type RequestStream = Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>;
@@ -31,29 +70,427 @@ pub type ClientStreamingHandlerFn =
 pub type BidiStreamingHandlerFn =
     Arc<dyn Fn(RequestStream) -> Pin<Box<dyn Future<Output = Result<RpcResponse, QuillError>> + Send>> + Send + Sync>;
 
+/// Handler prepared by a setup function, called with the collected request
+pub type PreparedHandlerFn =
+    Box<dyn FnOnce(Bytes) -> Pin<Box<dyn Future<Output = Result<RpcResponse, QuillError>> + Send>> + Send>;
+
+/// Type alias for setup functions run before the request body arrives
+pub type SetupHandlerFn = Arc<
+    dyn Fn(RequestContext) -> Pin<Box<dyn Future<Output = Result<PreparedHandlerFn, QuillError>> + Send>>
+        + Send
+        + Sync,
+>;
+
 /// Handler type enum for different streaming modes
 enum Handler {
     /// Unary or server-streaming (request is collected upfront)
     Unary(HandlerFn),
+    /// Unary or server-streaming whose setup runs while the request is collected
+    Setup(SetupHandlerFn),
+    /// Server streaming resumable from a cursor (request is collected upfront)
+    Cursor(CursorHandlerFn),
     /// Client streaming (request is a stream)
     ClientStreaming(ClientStreamingHandlerFn),
     /// Bidirectional streaming (request is a stream, response is a stream)
     Bidi(BidiStreamingHandlerFn),
 }
 
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Setup of a call started before its request arrived, aborted if the call is dropped
+struct SetupTask {
+    handle: tokio::task::JoinHandle<Result<PreparedHandlerFn, QuillError>>,
+    outcome: Option<Result<Result<PreparedHandlerFn, QuillError>, tokio::task::JoinError>>,
+}
+
+impl SetupTask {
+    fn spawn<F>(setup: F) -> Self
+    where
+        F: Future<Output = Result<PreparedHandlerFn, QuillError>> + Send + 'static,
+    {
+        Self { handle: tokio::spawn(setup), outcome: None }
+    }
+
+    /// Wait for the setup to finish, keeping its outcome
+    async fn finished(&mut self) {
+        if self.outcome.is_none() {
+            self.outcome = Some((&mut self.handle).await);
+        }
+    }
+
+    /// Problem of a setup that finished with an error or panic
+    fn failure(&mut self, debug: Option<&DebugPolicy>) -> Option<ProblemDetails> {
+        match self.outcome.take()? {
+            Ok(Ok(prepared)) => {
+                self.outcome = Some(Ok(Ok(prepared)));
+                None
+            }
+            Ok(Err(e)) => Some(RpcRouter::handler_problem(e, debug)),
+            Err(e) => Some(match e.try_into_panic() {
+                Ok(payload) => RpcRouter::panic_problem(payload.as_ref(), debug),
+                Err(e) => RpcRouter::handler_problem(QuillError::Rpc(format!("Call setup failed: {}", e)), debug),
+            }),
+        }
+    }
+
+    /// Handler prepared by the setup
+    async fn prepared(mut self) -> Result<PreparedHandlerFn, QuillError> {
+        self.finished().await;
+        match self.outcome.take().expect("setup finished") {
+            Ok(prepared) => prepared,
+            Err(e) => match e.try_into_panic() {
+                // Reported like a panic of the handler itself
+                Ok(payload) => std::panic::resume_unwind(payload),
+                Err(e) => Err(QuillError::Rpc(format!("Call setup failed: {}", e))),
+            },
+        }
+    }
+}
+
+impl Drop for SetupTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Request body once the router took it over from the connection
+type RouteBody = UnsyncBoxBody<Bytes, BoxError>;
+
+/// Call that passed the pre-admission stage of the router
+struct Admission {
+    /// Deadline and metadata of the call
+    context: RequestContext,
+    /// Stream slot of the caller's tenant, if tenants are isolated
+    tenant: Option<TenantPermit>,
+}
+
 /// RPC Router
 pub struct RpcRouter {
     routes: HashMap<String, Handler>,
+    debug: Option<DebugPolicy>,
+    uploads: Option<UploadStore>,
+    envelopes: Option<EnvelopeOpener>,
+    slow_consumers: Option<Arc<SlowConsumerDetector>>,
+    observability: Option<ObservabilityCollector>,
+    metrics_path: Option<String>,
+    tensor_methods: HashSet<String>,
+    #[cfg(feature = "otel")]
+    otel: Option<RpcMetrics>,
+    sampler: Option<PayloadSampler>,
+    idle: Option<Arc<StreamIdleGuard>>,
+    stream_gc: Option<Arc<StreamGc>>,
+    batch: Option<BatchRpcConfig>,
+    flow: Option<Arc<FlowControlRegistry>>,
+    frame_encryption: Option<FrameEncryption>,
+    scheduler: Option<Scheduler>,
+    tenants: Option<Arc<TenantRegistry>>,
+    dictionaries: Option<DictionaryCodec>,
+    coalescing: Option<FrameCoalescingConfig>,
+    frame_scheduling: Option<Arc<FrameScheduling>>,
+    descriptors: ReflectionRegistry,
+    json: JsonTranscoder,
+    reflection: bool,
+    health: Option<HealthReporter>,
+    response_cache: Option<ResponseCacheConfig>,
 }
 
 impl RpcRouter {
     /// Create a new router
+    ///
+    /// The built-in ping method ([`PING_PATH`]) is registered by default and
+    /// echoes its request body back to the caller.
     pub fn new() -> Self {
-        Self {
+        let mut router = Self {
             routes: HashMap::new(),
+            debug: None,
+            uploads: None,
+            envelopes: None,
+            slow_consumers: None,
+            observability: None,
+            metrics_path: None,
+            tensor_methods: HashSet::new(),
+            #[cfg(feature = "otel")]
+            otel: None,
+            sampler: None,
+            idle: None,
+            stream_gc: None,
+            batch: None,
+            flow: None,
+            frame_encryption: None,
+            scheduler: None,
+            tenants: None,
+            dictionaries: None,
+            coalescing: None,
+            frame_scheduling: None,
+            descriptors: ReflectionRegistry::default(),
+            json: JsonTranscoder::default(),
+            reflection: false,
+            health: None,
+            response_cache: None,
+        };
+        router.register_unary(PING_PATH, |req: Bytes| async move { Ok(req) });
+        router
+    }
+
+    /// Attach redacted debug context to error responses for entitled callers
+    ///
+    /// Also records backtraces of handler panics so they can be reported.
+    pub fn set_debug_policy(&mut self, policy: DebugPolicy) {
+        DebugPolicy::install_panic_hook();
+        self.debug = Some(policy);
+    }
+
+    /// Accept unary requests split into chunks on parallel streams
+    ///
+    /// Support is advertised on every response so clients know they may
+    /// split large requests.
+    pub fn enable_chunked_upload(&mut self, config: ChunkedUploadConfig) {
+        self.uploads = Some(UploadStore::new(config));
+    }
+
+    /// Offer a compression dictionary and use it for unary calls
+    ///
+    /// The dictionary is advertised on every response and served by the
+    /// built-in fetch method ([`DICTIONARY_PATH`]). See [`crate::dictionary`].
+    pub fn enable_compression_dictionary(&mut self, config: DictionaryCompression) {
+        self.dictionaries = Some(DictionaryCodec::new(config));
+    }
+
+    /// Open envelope-encrypted unary requests before they reach handlers
+    ///
+    /// Requests without the envelope header are passed through unchanged.
+    pub fn enable_envelope_decryption(&mut self, config: EnvelopeDecryption) {
+        self.envelopes = Some(EnvelopeOpener::new(config));
+    }
+
+    /// Open and seal the frames of streams encrypted end to end
+    ///
+    /// Streams without the frame encryption header are passed through
+    /// unchanged. See [`crate::frame_encryption`].
+    pub fn enable_frame_encryption(&mut self, config: FrameEncryption) {
+        self.frame_encryption = Some(config);
+    }
+
+    /// Detect streaming responses whose consumer falls behind
+    ///
+    /// Responses are drained into a bounded buffer so lag is measurable;
+    /// the configured action is applied once a consumer counts as slow.
+    pub fn enable_slow_consumer_detection(&mut self, config: SlowConsumerConfig) {
+        self.slow_consumers = Some(Arc::new(SlowConsumerDetector::new(config)));
+    }
+
+    /// Record request counts, sizes and latencies in `collector`
+    ///
+    /// Sizes come from `Content-Length` and are zero for streamed bodies;
+    /// streaming calls count as complete once their response headers are sent.
+    pub fn set_observability(&mut self, collector: ObservabilityCollector) {
+        self.observability = Some(collector);
+    }
+
+    /// Serve the recorded metrics in Prometheus text format on `GET path`
+    ///
+    /// Starts recording into a new collector unless one was set with
+    /// [`set_observability`](Self::set_observability). Scrapes are not
+    /// recorded themselves.
+    pub fn enable_metrics(&mut self, path: impl Into<String>) {
+        let path = path.into();
+        let path = if path.starts_with('/') { path } else { format!("/{}", path) };
+        self.metrics_path = Some(path);
+        self.observability.get_or_insert_with(ObservabilityCollector::new);
+    }
+
+    /// Collector recording this router's metrics, if any
+    pub fn observability(&self) -> Option<&ObservabilityCollector> {
+        self.observability.as_ref()
+    }
+
+    /// Record RPC metrics in `metrics` and run each call in an exported span
+    ///
+    /// See [`crate::otel`] for the instruments and their attributes.
+    #[cfg(feature = "otel")]
+    pub fn set_otel_metrics(&mut self, metrics: RpcMetrics) {
+        self.otel = Some(metrics);
+    }
+
+    /// Attach sampled, redacted unary payloads to the current span
+    ///
+    /// See [`crate::sampling`] for what is sampled and how it is redacted.
+    pub fn enable_payload_sampling(&mut self, config: PayloadSamplingConfig) {
+        self.sampler = Some(PayloadSampler::new(config));
+    }
+
+    /// Cancel streams that exchange no frame within an idle timeout
+    ///
+    /// Covers response streams and the request streams of client and
+    /// bidirectional streaming calls. See [`crate::idle_timeout`].
+    pub fn enable_stream_idle_timeout(&mut self, config: StreamIdleConfig) {
+        self.idle = Some(Arc::new(StreamIdleGuard::new(config)));
+    }
+
+    /// Track active streams, listing and cancelling those that make no progress
+    ///
+    /// Covers response streams and the request streams of client and
+    /// bidirectional streaming calls; suspected leaks are listed by the
+    /// built-in [`STREAM_GC_LEAKS_PATH`] method. See [`crate::stream_gc`].
+    pub fn enable_stream_gc(&mut self, config: StreamGcConfig) {
+        self.stream_gc = Some(Arc::new(StreamGc::new(config)));
+    }
+
+    /// Batch small response frames into fewer writes
+    ///
+    /// Frames wait at most the method's delay budget. See [`crate::coalesce`].
+    pub fn enable_frame_coalescing(&mut self, config: FrameCoalescingConfig) {
+        self.coalescing = Some(config);
+    }
+
+    /// Take turns between the response streams of each connection
+    ///
+    /// Response chunks are split to the method's quantum so no stream holds
+    /// the connection for long. See [`crate::frame_scheduler`].
+    pub fn enable_frame_scheduling(&mut self, config: FrameSchedulingConfig) {
+        self.frame_scheduling = Some(Arc::new(FrameScheduling::new(config)));
+    }
+
+    /// Describe the registered services through the built-in reflection method ([`REFLECTION_PATH`])
+    ///
+    /// Only services whose descriptors were added with
+    /// [`add_file_descriptor_set`](Self::add_file_descriptor_set) can be
+    /// described. See [`crate::reflection`].
+    pub fn enable_reflection(&mut self) {
+        self.reflection = true;
+    }
+
+    /// Answer the built-in health RPCs ([`HEALTH_CHECK_PATH`] and [`HEALTH_WATCH_PATH`])
+    ///
+    /// Keep a clone of `reporter` to set serving statuses, or use
+    /// [`ObservabilityCollector::health_reporter`] to report the collector's
+    /// health status. See [`crate::health`].
+    pub fn enable_health(&mut self, reporter: HealthReporter) {
+        self.health = Some(reporter);
+    }
+
+    /// Add descriptors of registered services from an encoded `FileDescriptorSet`
+    ///
+    /// Described methods also accept JSON requests; see [`crate::transcode`].
+    /// A set that refers to types it lacks is still used for reflection.
+    pub fn add_file_descriptor_set(&mut self, descriptor_set: &[u8]) -> Result<(), QuillError> {
+        self.descriptors.add(descriptor_set)?;
+        if let Err(e) = self.json.add(descriptor_set) {
+            tracing::warn!("JSON transcoding unavailable for a descriptor set: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Serve the interop test service ([`quill_core::INTEROP_SERVICE`])
+    ///
+    /// Its methods are registered like any others. See [`crate::interop`].
+    pub fn enable_interop(&mut self) {
+        crate::interop::register(self);
+    }
+
+    /// Answer unary calls of cached methods without running their handlers
+    ///
+    /// Keep a clone of the cache to fill it. The built-in invalidation
+    /// method ([`RESPONSE_CACHE_INVALIDATE_PATH`]) is served only if the
+    /// configuration authorizes callers with
+    /// [`ResponseCacheConfig::remote_invalidation`]. See [`crate::response_cache`].
+    pub fn enable_response_cache(&mut self, config: impl Into<ResponseCacheConfig>) {
+        self.response_cache = Some(config.into());
+    }
+
+    /// Answer batch RPCs ([`BATCH_PATH`]) carrying many unary calls
+    ///
+    /// See [`crate::batch`] for how entries are run.
+    pub fn enable_batch(&mut self, config: BatchRpcConfig) {
+        self.batch = Some(config);
+    }
+
+    /// Honor credits granted by clients that request flow control
+    ///
+    /// Streaming responses to such clients send messages only while they
+    /// hold credits. See [`crate::flow_control`].
+    pub fn enable_flow_control(&mut self) {
+        let max_tensor_window = self.flow.as_ref().and_then(|flow| flow.max_tensor_window());
+        self.flow = Some(Arc::new(FlowControlRegistry::new(true, max_tensor_window)));
+    }
+
+    /// Honor tensor byte windows granted by clients that request them
+    ///
+    /// Responses of methods registered with [`Self::register_tensor_streaming`]
+    /// send TENSOR_PAYLOAD messages only while the client has granted room
+    /// for their payload, up to `max_window` bytes in flight. See
+    /// [`crate::flow_control`].
+    pub fn enable_tensor_flow_control(&mut self, max_window: u64) {
+        let messages = self.flow.as_ref().is_some_and(|flow| flow.messages());
+        self.flow = Some(Arc::new(FlowControlRegistry::new(messages, Some(max_window))));
+    }
+
+    /// Invoke a registered unary method on a schedule
+    ///
+    /// Jobs start once the server is serving. Their recent runs are returned
+    /// by the built-in history method. See [`crate::schedule`].
+    pub fn schedule(&mut self, job: ScheduledJob) {
+        self.scheduler.get_or_insert_with(Scheduler::default).add(job);
+    }
+
+    /// Recent scheduled runs, oldest first
+    pub fn schedule_history(&self) -> Vec<JobRun> {
+        self.scheduler.as_ref().map(Scheduler::history).unwrap_or_default()
+    }
+
+    /// Start the scheduled jobs, which stop once the router is dropped
+    pub(crate) fn start_scheduler(self: &Arc<Self>) {
+        if let Some(scheduler) = &self.scheduler {
+            scheduler.start(Arc::downgrade(self));
         }
     }
 
+    /// Limit the streams and bandwidth of each authenticated tenant
+    ///
+    /// Applies to calls of registered methods. See [`crate::tenant`].
+    pub fn enable_tenant_isolation(&mut self, config: TenantIsolationConfig) {
+        self.tenants = Some(Arc::new(TenantRegistry::new(config)));
+    }
+
+    /// Streams per tenant, if tenant isolation is enabled
+    pub fn tenant_stats(&self) -> HashMap<String, TenantStats> {
+        self.tenants
+            .as_ref()
+            .map(|registry| registry.stats())
+            .unwrap_or_default()
+    }
+
+    /// Streaming lag statistics per method path, if detection is enabled
+    pub fn slow_consumer_stats(&self) -> HashMap<String, LagStats> {
+        self.slow_consumers
+            .as_ref()
+            .map(|detector| detector.stats())
+            .unwrap_or_default()
+    }
+
+    /// Stream garbage collection counters, if enabled
+    pub fn stream_gc_stats(&self) -> StreamGcStats {
+        self.stream_gc.as_ref().map(|gc| gc.stats()).unwrap_or_default()
+    }
+
+    /// Frame scheduling counters across all connections
+    pub fn frame_scheduler_stats(&self) -> FrameSchedulerStats {
+        self.frame_scheduling
+            .as_ref()
+            .map(|scheduling| scheduling.stats())
+            .unwrap_or_default()
+    }
+
+    /// Scheduler for the response streams of a new connection, if frame scheduling is enabled
+    pub(crate) fn connection_scheduler(&self) -> Option<ConnectionScheduler> {
+        self.frame_scheduling.as_ref().map(|scheduling| scheduling.connection())
+    }
+
+    /// Streams currently suspected of leaking, oldest first, if stream GC is enabled
+    pub fn suspected_stream_leaks(&self) -> Vec<StreamLeak> {
+        self.stream_gc.as_ref().map(|gc| gc.leaks()).unwrap_or_default()
+    }
+
     /// Register a handler for a specific service method
     /// Path format: "{package}.{Service}/{Method}"
     pub fn register<F, Fut>(&mut self, path: impl Into<String>, handler: F)
@@ -81,6 +518,82 @@ impl RpcRouter {
         });
     }
 
+    /// Register a handler whose setup starts before the request body arrives
+    ///
+    /// `setup` runs as soon as the request headers are in, concurrently with
+    /// reading the body, for per-call work that doesn't need the request such
+    /// as authentication or loading a session. `handler` then gets its
+    /// result along with the request. Together with
+    /// `QuillClient::call_deferred`, which sends the headers while the client
+    /// is still producing the request, this takes the setup out of the
+    /// latency of `real_time` methods. A failing `setup` fails the call.
+    pub fn register_with_setup<S, SFut, T, F, Fut>(&mut self, path: impl Into<String>, setup: S, handler: F)
+    where
+        S: Fn(RequestContext) -> SFut + Send + Sync + 'static,
+        SFut: Future<Output = Result<T, QuillError>> + Send + 'static,
+        T: Send + 'static,
+        F: Fn(T, Bytes) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<RpcResponse, QuillError>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let setup: SetupHandlerFn = Arc::new(move |context: RequestContext| {
+            let setup = setup(context);
+            let handler = Arc::clone(&handler);
+            Box::pin(async move {
+                let state = setup.await?;
+                let prepared: PreparedHandlerFn = Box::new(move |req: Bytes| Box::pin(handler(state, req)));
+                Ok(prepared)
+            }) as Pin<Box<_>>
+        });
+        self.routes.insert(path.into(), Handler::Setup(setup));
+    }
+
+    /// Register a tensor streaming handler
+    ///
+    /// The handler returns a stream of tensor frames, each sent as one
+    /// message as soon as it is produced. See [`crate::tensor`].
+    #[cfg(feature = "tensor")]
+    pub fn register_tensor_streaming<F, Fut, S>(&mut self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(Bytes) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<S, QuillError>> + Send + 'static,
+        S: Stream<Item = Result<TensorFrame, QuillError>> + Send + 'static,
+    {
+        let path = path.into();
+        self.tensor_methods.insert(path.clone());
+        let handler = Arc::new(handler);
+        self.register(path, move |req: Bytes| {
+            let handler = Arc::clone(&handler);
+            async move {
+                let frames = handler(req).await?;
+                Ok(RpcResponse::tensor_stream(frames))
+            }
+        });
+    }
+
+    /// Register a cursor streaming handler
+    ///
+    /// The handler receives the request and the cursor the call resumes
+    /// from, and returns a stream of messages each paired with the cursor
+    /// just after it. A cursor frame is sent every `every` messages. See
+    /// [`crate::cursor`].
+    pub fn register_cursor_streaming<F, Fut, S>(&mut self, path: impl Into<String>, every: usize, handler: F)
+    where
+        F: Fn(Bytes, Option<StreamCursor>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<S, QuillError>> + Send + 'static,
+        S: Stream<Item = Result<(Bytes, StreamCursor), QuillError>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let handler: CursorHandlerFn = Arc::new(move |req: Bytes, cursor: Option<StreamCursor>| {
+            let handler = Arc::clone(&handler);
+            Box::pin(async move {
+                let items = handler(req, cursor).await?;
+                Ok(RpcResponse::cursor_stream(items, every))
+            }) as Pin<Box<_>>
+        });
+        self.routes.insert(path.into(), Handler::Cursor(handler));
+    }
+
     /// Register a client streaming handler
     ///
     /// The handler receives a stream of request messages and returns a single response.
@@ -110,10 +623,91 @@ impl RpcRouter {
     }
 
     /// Route an incoming request
-    pub async fn route(&self, req: Request<Incoming>) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+    ///
+    /// Accepts any request body, so the same router serves HTTP/1.1, HTTP/2
+    /// and HTTP/3 connections.
+    pub async fn route<B>(&self, req: Request<B>) -> Response<UnsyncBoxBody<Bytes, QuillError>>
+    where
+        B: http_body::Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let req = req.map(|body| body.map_err(Into::into).boxed_unsync());
+        let scrape = self.metrics_path.as_deref() == Some(req.uri().path());
+        let observed = self.observability.as_ref().filter(|_| !scrape).map(|collector| {
+            let endpoint = req.uri().path().trim_start_matches('/').to_string();
+            collector.record_request_start(&endpoint, content_length(req.headers()));
+            (collector, endpoint, std::time::Instant::now())
+        });
+
+        // Span the call runs in, exported when OpenTelemetry is set up
+        let span = tracing::Span::none();
+        #[cfg(feature = "otel")]
+        let (span, otel) = match &self.otel {
+            Some(metrics) => {
+                let path = req.uri().path().trim_start_matches('/').to_string();
+                let registered = self.routes.contains_key(&path);
+                let span = crate::otel::request_span(&path, registered, req.headers());
+                let request_bytes = content_length(req.headers());
+                (span, Some((metrics, path, registered, request_bytes, std::time::Instant::now())))
+            }
+            None => (span, None),
+        };
+
+        let mut response = self.dispatch(req).instrument(span).await;
+
+        #[cfg(feature = "otel")]
+        if let Some((metrics, path, registered, request_bytes, started)) = otel {
+            metrics.record_request(
+                &path,
+                registered,
+                started.elapsed(),
+                response.status(),
+                request_bytes,
+                content_length(response.headers()),
+            );
+        }
+        if let Some((collector, endpoint, started)) = observed {
+            collector
+                .record_response(
+                    &endpoint,
+                    started.elapsed(),
+                    content_length(response.headers()),
+                    response.status(),
+                )
+                .await;
+        }
+        if let Some(uploads) = &self.uploads {
+            response
+                .headers_mut()
+                .insert(UPLOAD_CAPABILITY_HEADER, uploads.capability_header().clone());
+        }
+        if let Some(dictionaries) = &self.dictionaries {
+            response
+                .headers_mut()
+                .insert(DICTIONARY_CAPABILITY_HEADER, dictionaries.capability_header().clone());
+        }
+        response
+    }
+
+    async fn dispatch(&self, req: Request<RouteBody>) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
         // Parse the path
         let path = req.uri().path();
 
+        // Scrapes are not RPC calls, so they are served without admission
+        if req.method() == Method::GET && self.metrics_path.as_deref() == Some(path) {
+            if let Some(collector) = &self.observability {
+                return Response::builder()
+                    .status(StatusCode::OK)
+                    .header("Content-Type", PROMETHEUS_CONTENT_TYPE)
+                    .body(
+                        Full::new(Bytes::from(collector.export_prometheus().await))
+                            .map_err(|never| match never {})
+                            .boxed_unsync(),
+                    )
+                    .unwrap();
+            }
+        }
+
         // Validate HTTP method (should be POST for RPC)
         if req.method() != Method::POST {
             return Self::error_response(
@@ -124,7 +718,124 @@ impl RpcRouter {
         }
 
         // Strip leading slash
-        let path = path.strip_prefix('/').unwrap_or(path);
+        let path = path.strip_prefix('/').unwrap_or(path).to_string();
+
+        // Built-in paths and registered methods alike are only routed once admitted
+        let admission = match self.admit(&path, &req).await {
+            Ok(admission) => admission,
+            Err(response) => return response,
+        };
+        self.dispatch_admitted(&path, admission, req).await
+    }
+
+    /// Pre-admission stage of every RPC: the caller's deadline and tenant
+    ///
+    /// Fails with `400` on a malformed timeout and, with tenant isolation,
+    /// `401` when authentication fails or the status of a refused slot.
+    /// Credits posted for a flow-controlled stream are authenticated but
+    /// take no slot, since the stream they feed already holds one.
+    fn admit(
+        &self,
+        path: &str,
+        req: &Request<RouteBody>,
+    ) -> impl Future<Output = Result<Admission, Response<UnsyncBoxBody<Bytes, QuillError>>>> + Send + '_ {
+        // The caller's deadline starts counting as soon as the request arrives
+        let context = RequestContext::from_headers(path, req.headers());
+        // Identified before waiting for a slot, which the request cannot be borrowed across
+        let tenant = match &self.tenants {
+            Some(registry) => registry.identify(req).map(Some).map_err(Self::problem_response),
+            None => Ok(None),
+        };
+        let takes_slot = !(path == FLOW_CREDIT_PATH && self.flow.is_some());
+
+        async move {
+            let context = context
+                .map_err(|detail| Self::error_response(StatusCode::BAD_REQUEST, "Invalid timeout", Some(&detail)))?;
+            let tenant = match self.tenants.as_ref().zip(tenant?) {
+                Some((registry, tenant)) if takes_slot => {
+                    Some(registry.admit(tenant).await.map_err(Self::problem_response)?)
+                }
+                _ => None,
+            };
+            Ok(Admission { context, tenant })
+        }
+    }
+
+    /// Route-table stage: built-in paths first, then registered methods
+    ///
+    /// Built-in paths keep the tenant's slot until their response is built.
+    async fn dispatch_admitted(
+        &self,
+        path: &str,
+        admission: Admission,
+        req: Request<RouteBody>,
+    ) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        let Admission { context, tenant } = admission;
+
+        if path == BATCH_PATH {
+            if let Some(config) = &self.batch {
+                return self.dispatch_batch(config, context, req).await;
+            }
+        }
+        if path == SCHEDULER_HISTORY_PATH {
+            if let Some(scheduler) = &self.scheduler {
+                return Response::builder()
+                    .status(StatusCode::OK)
+                    .header("Content-Type", "application/json")
+                    .body(Full::new(scheduler.history_json()).map_err(|never| match never {}).boxed_unsync())
+                    .unwrap();
+            }
+        }
+        if path == STREAM_GC_LEAKS_PATH {
+            if let Some(gc) = &self.stream_gc {
+                return Response::builder()
+                    .status(StatusCode::OK)
+                    .header("Content-Type", "application/json")
+                    .body(Full::new(gc.leaks_json()).map_err(|never| match never {}).boxed_unsync())
+                    .unwrap();
+            }
+        }
+        if path == DICTIONARY_PATH {
+            if let Some(dictionaries) = &self.dictionaries {
+                let (dictionary, headers) = dictionaries.fetch();
+                let mut builder = Response::builder().status(StatusCode::OK);
+                for (name, value) in headers {
+                    builder = builder.header(name, value);
+                }
+                return builder
+                    .body(Full::new(dictionary).map_err(|never| match never {}).boxed_unsync())
+                    .unwrap();
+            }
+        }
+        if path == FLOW_CREDIT_PATH {
+            if let Some(flow) = &self.flow {
+                return Self::dispatch_credit(flow, req).await;
+            }
+        }
+        if path == REFLECTION_PATH && self.reflection {
+            return self.dispatch_reflection(req).await;
+        }
+        if path == HEALTH_CHECK_PATH || path == HEALTH_WATCH_PATH {
+            if let Some(health) = &self.health {
+                let watch = path == HEALTH_WATCH_PATH;
+                return self.dispatch_health(health, watch, req).await;
+            }
+        }
+        if path == RESPONSE_CACHE_INVALIDATE_PATH {
+            if let Some(config) = &self.response_cache {
+                match config.authorizes_invalidation(req.headers()) {
+                    Some(true) => return Self::dispatch_cache_invalidation(config.cache(), req).await,
+                    Some(false) => {
+                        return Self::error_response(
+                            StatusCode::FORBIDDEN,
+                            "Invalidation not permitted",
+                            Some("The caller may not invalidate cached responses"),
+                        );
+                    }
+                    None => {}
+                }
+            }
+        }
 
         // Find handler
         let handler = match self.routes.get(path) {
@@ -137,13 +848,118 @@ impl RpcRouter {
                 )
             }
         };
+        self.dispatch_method(path, handler, context, tenant, req).await
+    }
+
+    /// Call the handler registered for `path`
+    ///
+    /// `tenant` is the stream slot of the caller's tenant, held until the
+    /// response is sent.
+    async fn dispatch_method(
+        &self,
+        path: &str,
+        handler: &Handler,
+        context: RequestContext,
+        tenant: Option<TenantPermit>,
+        req: Request<RouteBody>,
+    ) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        let method_path = path.to_string();
+
+        // Described methods called with JSON are transcoded to and from protobuf
+        let json_method = self.json.method(path, req.headers());
+
+        // Static responses are served as cached, without reading the request,
+        // so they skip sampling and dictionary compression below
+        let cache = self.response_cache.as_ref().map(ResponseCacheConfig::cache);
+        if let (Handler::Unary(_), Some(cache)) = (handler, cache) {
+            let plain = json_method.is_none()
+                && !req.headers().contains_key(ENVELOPE_HEADER)
+                && !req.headers().contains_key(UPLOAD_MANIFEST_HEADER);
+            if let Some(cached) = cache.get(path).filter(|_| plain) {
+                return Self::cached_response(&cached, req.headers(), tenant.as_ref()).await;
+            }
+        }
+
+        // Decide before the request is consumed whether the caller may see debug context
+        let debug = self.debug.as_ref().filter(|policy| policy.allows(req.headers()));
+
+        // Data key of a sealed request, used to seal its response
+        let mut envelope_key = None;
+
+        // Set when this call's payloads are sampled
+        let mut sampler = None;
+
+        // Set for calls with a request stream, which the client may cancel
+        let mut client_cancel: Option<ClientCancel> = None;
+
+        // Dictionary that compresses the unary response, if the caller holds it
+        let response_dictionary = self
+            .dictionaries
+            .as_ref()
+            .filter(|dictionaries| dictionaries.compresses_response(req.headers()));
+
+        // Turns of the connection's response streams, set per connection by the server
+        let connection = req.extensions().get::<ConnectionScheduler>().cloned();
+
+        // Credits the client grants to a streaming response
+        let flow = self
+            .flow
+            .as_ref()
+            .and_then(|flow| flow.requested(req.headers(), self.tensor_methods.contains(&method_path)));
+
+        // Ciphers of a stream encrypted end to end
+        let encryption = match &self.frame_encryption {
+            Some(config) => match config.accept(req.headers()) {
+                Ok(encryption) => encryption,
+                Err(detail) => {
+                    return Self::error_response(StatusCode::BAD_REQUEST, "Invalid frame encryption", Some(&detail))
+                }
+            },
+            None if req.headers().contains_key(FRAME_ENCRYPTION_HEADER) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Frame encryption not supported",
+                    Some("This server does not accept encrypted frames"),
+                );
+            }
+            None => None,
+        };
+        let (request_cipher, response_encryption) =
+            encryption.map(|encryption| (encryption.request, encryption.response)).unzip();
 
         // Dispatch based on handler type
-        let result = match handler {
-            Handler::Unary(handler) => {
+        let call = match handler {
+            Handler::Unary(_) | Handler::Cursor(_) | Handler::Setup(_) => {
+                let path = path.to_string();
+                let (parts, body) = req.into_parts();
+
+                // Set the call up while its request is still arriving
+                let mut setup = match handler {
+                    Handler::Setup(setup) => {
+                        let prepared = context.clone().scope(setup(context.clone()));
+                        Some(SetupTask::spawn(prepared))
+                    }
+                    _ => None,
+                };
+
                 // Read entire request body for unary/server-streaming
-                match Self::read_body(req.into_body()).await {
-                    Ok(body) => handler(body).await,
+                let read = Self::read_body(body);
+                let read = match setup.as_mut() {
+                    Some(setup) => {
+                        // A failed setup ends the call without waiting for the rest of the request
+                        tokio::pin!(read);
+                        tokio::select! {
+                            body = &mut read => body,
+                            () = setup.finished() => match setup.failure(debug) {
+                                Some(pd) => return Self::problem_response(pd),
+                                None => read.await,
+                            },
+                        }
+                    }
+                    None => read.await,
+                };
+                let body = match read {
+                    Ok(body) => body,
                     Err(e) => {
                         return Self::error_response(
                             StatusCode::BAD_REQUEST,
@@ -151,73 +967,683 @@ impl RpcRouter {
                             Some(&e.to_string()),
                         );
                     }
+                };
+
+                let body = match &self.dictionaries {
+                    Some(dictionaries) => match dictionaries.decode_request(&parts.headers, body) {
+                        Ok(body) => body,
+                        Err((status, detail)) => {
+                            return Self::error_response(status, "Invalid compressed request", Some(&detail));
+                        }
+                    },
+                    None if parts.headers.get(CONTENT_ENCODING).is_some_and(|encoding| encoding == "zstd") => {
+                        match decompress_zstd(&body) {
+                            Ok(body) => body,
+                            Err(e) => {
+                                return Self::error_response(
+                                    StatusCode::BAD_REQUEST,
+                                    "Invalid compressed request",
+                                    Some(&e.to_string()),
+                                );
+                            }
+                        }
+                    }
+                    None => body,
+                };
+
+                let body = if !parts.headers.contains_key(UPLOAD_MANIFEST_HEADER) {
+                    body
+                } else {
+                    // One chunk of a split request; run the handler once all chunks are in
+                    let Some(uploads) = &self.uploads else {
+                        return Self::error_response(
+                            StatusCode::BAD_REQUEST,
+                            "Chunked upload not supported",
+                            None,
+                        );
+                    };
+                    match uploads.accept(&path, &parts.headers, body) {
+                        Ok(ChunkOutcome::Complete(body)) => body,
+                        Ok(ChunkOutcome::Pending) => return Self::accepted_response(),
+                        Err((status, detail)) => {
+                            return Self::error_response(status, "Invalid chunked upload", Some(&detail));
+                        }
+                    }
+                };
+
+                let body = if !parts.headers.contains_key(ENVELOPE_HEADER) {
+                    body
+                } else {
+                    let Some(envelopes) = &self.envelopes else {
+                        return Self::error_response(
+                            StatusCode::BAD_REQUEST,
+                            "Envelope encryption not supported",
+                            None,
+                        );
+                    };
+                    match envelopes.open(&parts.headers, body).await {
+                        Ok((body, key)) => {
+                            envelope_key = Some(key);
+                            body
+                        }
+                        Err((status, detail)) => {
+                            return Self::error_response(status, "Invalid envelope", Some(&detail));
+                        }
+                    }
+                };
+
+                let body = match &json_method {
+                    Some(method) => match transcode::json_to_proto(&method.input(), &body) {
+                        Ok(body) => body,
+                        Err(detail) => {
+                            return Self::error_response(StatusCode::BAD_REQUEST, "Invalid JSON request", Some(&detail));
+                        }
+                    },
+                    None => body,
+                };
+
+                // Opened envelopes stay out of traces
+                if envelope_key.is_none() {
+                    sampler = self.sampler.as_ref().filter(|sampler| sampler.should_sample(&path));
+                }
+                if let Some(sampler) = sampler {
+                    sampler.record(&path, PayloadDirection::Request, &body);
+                }
+
+                match handler {
+                    Handler::Cursor(handler) => match resume_cursor(&parts.headers) {
+                        Ok(cursor) => handler(body, cursor),
+                        Err((status, detail)) => {
+                            return Self::error_response(status, "Invalid resume cursor", Some(&detail));
+                        }
+                    },
+                    Handler::Unary(handler) => handler(body),
+                    Handler::Setup(_) => {
+                        let setup = setup.take().expect("setup is spawned for setup handlers");
+                        Box::pin(async move {
+                            let prepared = setup.prepared().await?;
+                            prepared(body).await
+                        })
+                    }
+                    Handler::ClientStreaming(_) | Handler::Bidi(_) => unreachable!("request streams are not collected"),
                 }
             }
+            Handler::ClientStreaming(_) | Handler::Bidi(_) if json_method.is_some() => {
+                return Self::error_response(
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    "JSON not supported",
+                    Some("Methods with request streams only accept protobuf frames"),
+                );
+            }
             Handler::ClientStreaming(handler) | Handler::Bidi(handler) => {
-                // Create request stream for client/bidi streaming
-                let request_stream = RequestFrameStream::new(req.into_body());
-                let boxed_stream: RequestStream = Box::pin(request_stream);
-                handler(boxed_stream).await
+                // Create request stream for client/bidi streaming; a CANCEL on
+                // it is acknowledged at the end of the response
+                let cancel = context.cancellation().clone();
+                client_cancel = Some(cancel.clone());
+                let request_stream = RequestFrameStream::new(req.into_body())
+                    .decrypt(request_cipher)
+                    .on_cancel(cancel);
+                let mut boxed_stream: RequestStream = Box::pin(request_stream);
+                if let Some(idle) = &self.idle {
+                    boxed_stream = idle.guard_request(&method_path, boxed_stream);
+                }
+                if let Some(gc) = &self.stream_gc {
+                    boxed_stream = gc.track_request(&method_path, boxed_stream);
+                }
+                handler(boxed_stream)
             }
         };
 
+        // A panicking handler fails this call only, not the connection
+        let usage = context.usage().clone();
+        let consistency = context.consistency().clone();
+        let deadline = context.deadline();
+        let timeout = context.timeout().unwrap_or_default();
+        let call = AssertUnwindSafe(context.scope(call)).catch_unwind();
+        let outcome = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline.instant().into(), call).await {
+                Ok(outcome) => outcome,
+                Err(_) => return Self::problem_response(ProblemDetails::deadline_exceeded(timeout)),
+            },
+            None => call.await,
+        };
+        let result = match outcome {
+            Ok(result) => result,
+            Err(payload) => Err(QuillError::ProblemDetails(Self::panic_problem(payload.as_ref(), debug))),
+        };
+        let result = match &json_method {
+            Some(method) => result.map(|response| transcode::stream_to_json(method.output(), response)),
+            None => result,
+        };
+
         // Handle result
-        match result {
+        let mut response = match result {
             Ok(RpcResponse::Unary(response_bytes)) => {
                 // Unary response
-                Response::builder()
+                let mut response_bytes = response_bytes;
+                if let Some(sampler) = sampler {
+                    sampler.record(&method_path, PayloadDirection::Response, &response_bytes);
+                }
+                let mut content_type = "application/proto";
+                if let Some(method) = &json_method {
+                    response_bytes = match transcode::proto_to_json(&method.output(), &response_bytes) {
+                        Ok(json) => json,
+                        Err(e) => {
+                            tracing::error!("Failed to transcode response of {}: {}", method_path, e);
+                            return Self::error_response(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                "Internal server error",
+                                Some("Failed to transcode response to JSON"),
+                            );
+                        }
+                    };
+                    content_type = JSON_CONTENT_TYPE;
+                }
+                let mut builder = Response::builder()
                     .status(StatusCode::OK)
-                    .header("Content-Type", "application/proto")
+                    .header("Content-Type", content_type);
+
+                let sealed = envelope_key
+                    .as_ref()
+                    .zip(self.envelopes.as_ref())
+                    .and_then(|(key, envelopes)| envelopes.seal_response(key, &response_bytes));
+                match sealed {
+                    Some(Ok((header, sealed))) => {
+                        builder = builder.header(ENVELOPE_HEADER, header);
+                        response_bytes = sealed;
+                    }
+                    Some(Err(e)) => {
+                        tracing::error!("Failed to seal response: {}", e);
+                        return Self::error_response(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "Internal server error",
+                            Some("Failed to seal response"),
+                        );
+                    }
+                    None => {}
+                }
+                match response_dictionary.and_then(|dictionaries| dictionaries.encode_response(&response_bytes)) {
+                    Some(Ok((headers, compressed))) => {
+                        for (name, value) in headers {
+                            builder = builder.header(name, value);
+                        }
+                        response_bytes = compressed;
+                    }
+                    Some(Err(e)) => tracing::warn!("Sending response uncompressed: {}", e),
+                    None => {}
+                }
+                if let Some(permit) = &tenant {
+                    permit.throttle(response_bytes.len()).await;
+                }
+
+                builder
                     .body(Full::new(response_bytes).map_err(|never| match never {}).boxed_unsync())
                     .unwrap()
             }
             Ok(RpcResponse::Streaming(stream)) => {
-                // Streaming response - encode each message as a frame
-                let frame_stream = stream.map(|result| match result {
-                    Ok(data) => {
-                        let frame = Frame::data(data);
-                        Ok(HyperFrame::data(frame.encode()))
-                    }
-                    Err(e) => Err(e),
-                });
+                // Streaming response - encode each message as a frame, then report
+                // usage recorded by the time the messages ran out and end the stream
+                let trailer = futures_util::stream::once(async move { usage.trailer() })
+                    .filter_map(|trailer| async move { trailer.map(Ok) });
+                let mut frames: BoxFrameStream = Box::pin(
+                    stream
+                        .map_ok(Frame::data)
+                        .chain(trailer)
+                        .chain(futures_util::stream::once(async { Ok(Frame::end_stream()) })),
+                );
+                if let Some(cancel) = client_cancel {
+                    frames = acknowledge_cancel(frames, cancel);
+                }
+                self.streaming_response(&method_path, frames, flow.as_ref(), response_encryption, tenant, connection)
+            }
+            Ok(RpcResponse::Framed(mut stream)) => {
+                // Frames are sent as-is, including the stream's own terminal frame
+                if let Some(cancel) = client_cancel {
+                    stream = acknowledge_cancel(stream, cancel);
+                }
+                self.streaming_response(&method_path, stream, flow.as_ref(), response_encryption, tenant, connection)
+            }
+            Err(e) => return Self::problem_response(Self::handler_problem(e, debug)),
+        };
+        consistency.write_to(response.headers_mut());
+        response
+    }
 
-                // Create the end frame stream
-                let with_end = frame_stream.chain(futures_util::stream::once(async {
-                    let end_frame = Frame::end_stream();
-                    Ok(HyperFrame::data(end_frame.encode()))
-                }));
+    /// Run the entries of a batch RPC and answer with their results
+    ///
+    /// Entries run in `context`, so they share the batch's deadline.
+    async fn dispatch_batch(
+        &self,
+        config: &BatchRpcConfig,
+        context: RequestContext,
+        req: Request<RouteBody>,
+    ) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        let debug = self.debug.as_ref().filter(|policy| policy.allows(req.headers()));
 
-                Response::builder()
-                    .status(StatusCode::OK)
-                    .header("Content-Type", "application/proto")
-                    .header("Transfer-Encoding", "chunked")
-                    .body(StreamBody::new(with_end).boxed_unsync())
-                    .unwrap()
+        let body = match Self::read_body(req.into_body()).await {
+            Ok(body) => body,
+            Err(e) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Failed to read request body",
+                    Some(&e.to_string()),
+                );
             }
-            Err(QuillError::ProblemDetails(pd)) => {
-                // Return Problem Details as JSON
-                let json = pd.to_json().unwrap_or_else(|_| "{}".to_string());
-                Response::builder()
-                    .status(StatusCode::from_u16(pd.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))
-                    .header("Content-Type", "application/problem+json")
-                    .body(Full::new(Bytes::from(json)).map_err(|never| match never {}).boxed_unsync())
-                    .unwrap()
+        };
+        let entries = match decode_batch_request(body) {
+            Ok(entries) => entries,
+            Err(e) => return Self::error_response(StatusCode::BAD_REQUEST, "Invalid batch", Some(&e.to_string())),
+        };
+        if entries.len() > config.max_entries {
+            return Self::error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Batch too large",
+                Some(&format!("{} entries exceed the limit of {}", entries.len(), config.max_entries)),
+            );
+        }
+
+        // Results keep request order even though entries finish in any order
+        let results: Vec<BatchResult> = futures_util::stream::iter(entries)
+            .map(|entry| self.call_batch_entry(entry, &context, debug))
+            .buffered(config.max_concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut response = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/proto")
+            .body(
+                Full::new(encode_batch_response(&results))
+                    .map_err(|never| match never {})
+                    .boxed_unsync(),
+            )
+            .unwrap();
+        context.consistency().write_to(response.headers_mut());
+        response
+    }
+
+    /// Run one batch entry against its unary handler
+    ///
+    /// The entry runs in the context of the call carrying it, under its own
+    /// method, and is bounded by that call's deadline.
+    pub(crate) async fn call_batch_entry(
+        &self,
+        entry: BatchEntry,
+        context: &RequestContext,
+        debug: Option<&DebugPolicy>,
+    ) -> BatchResult {
+        let not_batchable = || {
+            BatchResult::problem(
+                &ProblemDetails::new(StatusCode::BAD_REQUEST, "Method cannot be batched")
+                    .with_detail(format!("/{} is a streaming method", entry.path)),
+            )
+        };
+        let context = context.for_method(&entry.path);
+        let call: Pin<Box<dyn Future<Output = Result<RpcResponse, QuillError>> + Send>> =
+            match self.routes.get(&entry.path) {
+                Some(Handler::Unary(handler)) => handler(entry.payload),
+                Some(Handler::Setup(setup)) => {
+                    let setup = setup(context.clone());
+                    let payload = entry.payload;
+                    Box::pin(async move {
+                        let prepared = setup.await?;
+                        prepared(payload).await
+                    })
+                }
+                Some(_) => return not_batchable(),
+                None => {
+                    return BatchResult::problem(
+                        &ProblemDetails::new(StatusCode::NOT_FOUND, "Method not found")
+                            .with_detail(format!("No handler registered for path: /{}", entry.path)),
+                    )
+                }
+            };
+
+        let deadline = context.deadline();
+        let timeout = context.timeout().unwrap_or_default();
+        let call = AssertUnwindSafe(context.scope(call)).catch_unwind();
+        let outcome = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline.instant().into(), call).await {
+                Ok(outcome) => outcome,
+                Err(_) => return BatchResult::problem(&ProblemDetails::deadline_exceeded(timeout)),
+            },
+            None => call.await,
+        };
+        match outcome {
+            Ok(Ok(RpcResponse::Unary(response))) => BatchResult::ok(response),
+            Ok(Ok(_)) => not_batchable(),
+            Ok(Err(e)) => BatchResult::problem(&Self::handler_problem(e, debug)),
+            Err(payload) => BatchResult::problem(&Self::panic_problem(payload.as_ref(), debug)),
+        }
+    }
+
+    /// Problem Details for a handler that panicked
+    fn panic_problem(payload: &(dyn std::any::Any + Send), debug: Option<&DebugPolicy>) -> ProblemDetails {
+        tracing::error!("Handler panicked: {}", panic_message(payload));
+        let mut pd = ProblemDetails::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+            .with_detail("Handler panicked");
+        if let Some(policy) = debug {
+            pd = pd.with_debug(policy.panic_context(payload));
+        }
+        pd
+    }
+
+    /// Problem Details for a handler error
+    fn handler_problem(error: QuillError, debug: Option<&DebugPolicy>) -> ProblemDetails {
+        match error {
+            QuillError::ProblemDetails(mut pd) => {
+                // Never leak debug context to callers that are not entitled to it
+                if debug.is_none() {
+                    pd.debug = None;
+                }
+                pd
+            }
+            e => {
+                let pd = ProblemDetails::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+                    .with_detail(e.to_string());
+                match debug {
+                    Some(policy) => pd.with_debug(policy.error_context(&e)),
+                    None => pd,
+                }
+            }
+        }
+    }
+
+    /// Build the response for a stream of frames
+    fn streaming_response(
+        &self,
+        method: &str,
+        frames: BoxFrameStream,
+        flow: Option<&FlowControlHeader>,
+        encryption: Option<ResponseEncryption>,
+        tenant: Option<TenantPermit>,
+        connection: Option<ConnectionScheduler>,
+    ) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        #[cfg(feature = "otel")]
+        let frames = match &self.otel {
+            Some(metrics) => metrics.observe_stream(method, frames),
+            None => frames,
+        };
+        let frames = match &self.observability {
+            Some(collector) => collector.observe_stream(frames, self.tensor_methods.contains(method)),
+            None => frames,
+        };
+        let mut frames = self.watch_consumer(method, frames);
+        let mut builder = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/proto")
+            .header("Transfer-Encoding", "chunked");
+        if let Some((registry, header)) = self.flow.as_ref().zip(flow) {
+            frames = registry.gate(header, frames);
+            builder = builder.header(FLOW_CONTROL_HEADER, header.to_header_value());
+        }
+        if let Some(permit) = tenant {
+            frames = permit.limit(frames);
+        }
+        // Flow control above charges plaintext payloads
+        if let Some(encryption) = encryption {
+            let (header, sealed) = encryption.seal(frames);
+            frames = sealed;
+            builder = builder.header(FRAME_ENCRYPTION_HEADER, header);
+        }
+
+        let chunks: ChunkStream = match self.coalescing.as_ref().and_then(|config| config.budget(method)) {
+            Some(budget) => Box::pin(CoalescedFrames::new(frames, budget)),
+            None => {
+                // Large payloads go out in their own chunk rather than being copied
+                Box::pin(
+                    frames
+                        .map_ok(|frame| futures_util::stream::iter(frame.into_chunks().map(Ok)))
+                        .try_flatten(),
+                )
+            }
+        };
+        let chunks = match connection {
+            Some(connection) => connection.schedule(method, chunks),
+            None => chunks,
+        };
+        builder.body(StreamBody::new(chunks.map_ok(HyperFrame::data)).boxed_unsync()).unwrap()
+    }
+
+    /// Answer the built-in reflection method with descriptors of the served services
+    async fn dispatch_reflection(&self, req: Request<RouteBody>) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        let body = match Self::read_body(req.into_body()).await {
+            Ok(body) => body,
+            Err(e) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Failed to read request body",
+                    Some(&e.to_string()),
+                );
+            }
+        };
+        let requested = match std::str::from_utf8(&body) {
+            Ok(name) => Some(name.trim()).filter(|name| !name.is_empty()),
+            Err(_) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid reflection request",
+                    Some("Service name must be UTF-8"),
+                );
+            }
+        };
+
+        let served = self.routes.keys().filter_map(|path| path.split_once('/')).map(|(service, _)| service).collect();
+        match self.descriptors.descriptor_set(&served, requested) {
+            Ok(descriptor_set) => Response::builder()
+                .status(StatusCode::OK)
+                .header("Content-Type", "application/proto")
+                .body(Full::new(descriptor_set).map_err(|never| match never {}).boxed_unsync())
+                .unwrap(),
+            Err(detail) => Self::error_response(StatusCode::NOT_FOUND, "Service not found", Some(&detail)),
+        }
+    }
+
+    /// Answer a unary call with a cached response, pre-compressed if the caller accepts it
+    async fn cached_response(
+        cached: &CachedResponse,
+        headers: &http::HeaderMap,
+        tenant: Option<&TenantPermit>,
+    ) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        let (encoding, body) = cached.negotiate(headers);
+        if let Some(permit) = tenant {
+            permit.throttle(body.len()).await;
+        }
+        let mut builder = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/proto");
+        if let Some(encoding) = encoding {
+            builder = builder.header(CONTENT_ENCODING, encoding);
+        }
+        builder
+            .body(Full::new(body.clone()).map_err(|never| match never {}).boxed_unsync())
+            .unwrap()
+    }
+
+    /// Answer a health check, or stream the status of a health watch
+    async fn dispatch_health(
+        &self,
+        health: &HealthReporter,
+        watch: bool,
+        req: Request<RouteBody>,
+    ) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        let connection = req.extensions().get::<ConnectionScheduler>().cloned();
+        let body = match Self::read_body(req.into_body()).await {
+            Ok(body) => body,
+            Err(e) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Failed to read request body",
+                    Some(&e.to_string()),
+                );
+            }
+        };
+        let service = match std::str::from_utf8(&body) {
+            Ok(name) => name.trim().to_string(),
+            Err(_) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid health request",
+                    Some("Service name must be UTF-8"),
+                );
+            }
+        };
+        let registered = service == HEALTH_SERVICE
+            || self
+                .routes
+                .keys()
+                .filter_map(|path| path.split_once('/'))
+                .any(|(served, _)| served == service);
+
+        if watch {
+            let statuses = health.watch(service, registered).map(|status| {
+                let json = HealthCheckResponse::new(status).to_json().unwrap_or_default();
+                Ok(Frame::data(Bytes::from(json)))
+            });
+            return self.streaming_response(HEALTH_WATCH_PATH, Box::pin(statuses), None, None, None, connection);
+        }
+
+        let status = health.resolve(&service, registered);
+        if status == ServingStatus::ServiceUnknown {
+            return Self::error_response(
+                StatusCode::NOT_FOUND,
+                "Service not found",
+                Some(&format!("No health status for service: {}", service)),
+            );
+        }
+        let json = HealthCheckResponse::new(status).to_json().unwrap_or_default();
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(Full::new(Bytes::from(json)).map_err(|never| match never {}).boxed_unsync())
+            .unwrap()
+    }
+
+    /// Drop the cached responses named by a call to the built-in invalidation method
+    async fn dispatch_cache_invalidation(
+        cache: &ResponseCache,
+        req: Request<RouteBody>,
+    ) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        let body = match Self::read_body(req.into_body()).await {
+            Ok(body) => body,
+            Err(e) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Failed to read request body",
+                    Some(&e.to_string()),
+                );
+            }
+        };
+        let invalidated = match std::str::from_utf8(&body).map(str::trim) {
+            Ok("") => cache.clear(),
+            Ok(target) => cache.invalidate(target),
+            Err(_) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid invalidation request",
+                    Some("Method or service name must be UTF-8"),
+                );
             }
-            Err(e) => Self::error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error",
-                Some(&e.to_string()),
-            ),
+        };
+        tracing::info!(invalidated, "Invalidated cached responses");
+
+        let json = serde_json::json!({ "invalidated": invalidated });
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(Full::new(Bytes::from(json.to_string())).map_err(|never| match never {}).boxed_unsync())
+            .unwrap()
+    }
+
+    /// Apply credits a client posted to the built-in credit method
+    async fn dispatch_credit(
+        flow: &FlowControlRegistry,
+        req: Request<RouteBody>,
+    ) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        let header = req
+            .headers()
+            .get(FLOW_CONTROL_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(FlowControlHeader::from_header_value);
+        let Some(header) = header else {
+            return Self::error_response(
+                StatusCode::BAD_REQUEST,
+                "Invalid credit grant",
+                Some("Missing flow control stream id"),
+            );
+        };
+        let body = match Self::read_body(req.into_body()).await {
+            Ok(body) => body,
+            Err(e) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Failed to read request body",
+                    Some(&e.to_string()),
+                );
+            }
+        };
+
+        let mut parser = FrameParser::new();
+        parser.feed(&body);
+        let mut credits = 0u32;
+        let mut bytes = 0u64;
+        loop {
+            match parser.parse_frame() {
+                Ok(Some(frame)) => {
+                    credits = credits.saturating_add(frame.decode_credit().unwrap_or(0));
+                    bytes = bytes.saturating_add(frame.decode_byte_credit().unwrap_or(0));
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    return Self::error_response(StatusCode::BAD_REQUEST, "Invalid credit grant", Some(&e.to_string()))
+                }
+            }
+        }
+
+        if !flow.grant(&header.id, credits, bytes) {
+            return Self::error_response(
+                StatusCode::NOT_FOUND,
+                "Unknown stream",
+                Some(&format!("No flow-controlled stream with id {}", header.id)),
+            );
+        }
+        Response::builder()
+            .status(StatusCode::OK)
+            .body(Full::new(Bytes::new()).map_err(|never| match never {}).boxed_unsync())
+            .unwrap()
+    }
+
+    /// Apply slow-consumer detection, idle timeouts and stream GC to a response stream, if enabled
+    fn watch_consumer(&self, method: &str, frames: BoxFrameStream) -> BoxFrameStream {
+        let frames = match &self.slow_consumers {
+            Some(detector) => detector.watch(method, frames),
+            None => frames,
+        };
+        let frames = match &self.idle {
+            Some(idle) => idle.guard_response(method, frames),
+            None => frames,
+        };
+        match &self.stream_gc {
+            Some(gc) => gc.track_response(method, frames),
+            None => frames,
         }
     }
 
     /// Helper to read body bytes
-    async fn read_body(body: Incoming) -> Result<Bytes, Box<dyn std::error::Error + Send + Sync>> {
+    async fn read_body(body: RouteBody) -> Result<Bytes, BoxError> {
         use http_body_util::BodyExt;
         let collected = body.collect().await?;
         Ok(collected.to_bytes())
     }
 
+    /// Helper to acknowledge a chunk of an incomplete upload
+    fn accepted_response() -> Response<UnsyncBoxBody<Bytes, QuillError>> {
+        Response::builder()
+            .status(StatusCode::ACCEPTED)
+            .body(Full::new(Bytes::new()).map_err(|never| match never {}).boxed_unsync())
+            .unwrap()
+    }
+
     /// Helper to create error responses
     fn error_response(status: StatusCode, title: &str, detail: Option<&str>) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
         let mut pd = ProblemDetails::new(status, title);
@@ -225,10 +1651,15 @@ impl RpcRouter {
             pd = pd.with_detail(d);
         }
 
+        Self::problem_response(pd)
+    }
+
+    /// Helper to return Problem Details as JSON
+    fn problem_response(pd: ProblemDetails) -> Response<UnsyncBoxBody<Bytes, QuillError>> {
         let json = pd.to_json().unwrap_or_else(|_| "{}".to_string());
 
         Response::builder()
-            .status(status)
+            .status(StatusCode::from_u16(pd.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))
             .header("Content-Type", "application/problem+json")
             .body(Full::new(Bytes::from(json)).map_err(|never| match never {}).boxed_unsync())
             .unwrap()
@@ -259,6 +1690,14 @@ pub fn parse_rpc_path(path: &str) -> Option<(String, String)> {
     Some((service, method))
 }
 
+fn content_length(headers: &http::HeaderMap) -> usize {
+    headers
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,4 +1714,10 @@ mod tests {
 
         assert!(parse_rpc_path("/invalid").is_none());
     }
+
+    #[test]
+    fn test_ping_registered_by_default() {
+        let router = RpcRouter::new();
+        assert!(matches!(router.routes.get(PING_PATH), Some(Handler::Unary(_))));
+    }
 }
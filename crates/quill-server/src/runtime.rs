@@ -0,0 +1,367 @@
+//! Runtime and socket tuning for high-throughput servers
+//!
+//! This module provides:
+//! - Worker thread count and core pinning of the server runtime
+//! - A separate runtime for blocking inference work
+//! - Listener socket options: `SO_REUSEPORT` with multiple acceptors,
+//!   accept backlog, `TCP_NODELAY` and kernel buffer sizes
+//! - The I/O backend of accepted connections, including io_uring
+//!
+//! Runtime settings apply when the server creates its own runtime with
+//! [`QuillServer::run`](crate::server::QuillServer::run); under
+//! `#[tokio::main]` the runtime already exists and only the socket options
+//! take effect. Keeping inference on an [`InferenceRuntime`] stops
+//! long-running model calls from starving the I/O workers that accept
+//! connections and move frames.
+
+use quill_core::QuillError;
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::runtime::{Builder, Handle, Runtime};
+
+/// Which CPU cores runtime threads are pinned to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CorePinning {
+    /// Let the OS schedule threads freely
+    #[default]
+    None,
+    /// Pin threads round-robin over all cores
+    AllCores,
+    /// Pin threads round-robin over the given core IDs, e.g. one NUMA node
+    Cores(Vec<usize>),
+}
+
+impl CorePinning {
+    /// Core IDs to pin to, `None` when pinning is off or unsupported
+    fn core_ids(&self) -> Option<Vec<core_affinity::CoreId>> {
+        match self {
+            CorePinning::None => None,
+            CorePinning::AllCores => core_affinity::get_core_ids().filter(|ids| !ids.is_empty()),
+            CorePinning::Cores(ids) if ids.is_empty() => None,
+            CorePinning::Cores(ids) => Some(ids.iter().map(|&id| core_affinity::CoreId { id }).collect()),
+        }
+    }
+}
+
+/// Configuration of a multi-threaded tokio runtime
+#[derive(Debug, Clone)]
+pub struct RuntimeConfig {
+    /// Worker threads (None = one per core)
+    pub worker_threads: Option<usize>,
+    /// Cores the worker threads are pinned to
+    pub core_pinning: CorePinning,
+    /// Name prefix of the runtime's threads
+    pub thread_name: String,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            worker_threads: None,
+            core_pinning: CorePinning::None,
+            thread_name: "quill-worker".to_string(),
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Build a runtime with this configuration
+    ///
+    /// Blocking-pool threads are pinned along with the workers.
+    pub fn build(&self) -> io::Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all().thread_name(self.thread_name.clone());
+        if let Some(threads) = self.worker_threads {
+            builder.worker_threads(threads);
+        }
+        if let Some(cores) = self.core_pinning.core_ids() {
+            let next = AtomicUsize::new(0);
+            builder.on_thread_start(move || {
+                let core = cores[next.fetch_add(1, Ordering::Relaxed) % cores.len()];
+                if !core_affinity::set_for_current(core) {
+                    tracing::warn!("Failed to pin runtime thread to core {}", core.id);
+                }
+            });
+        }
+        builder.build()
+    }
+}
+
+/// Runtime dedicated to blocking or CPU-heavy inference work
+///
+/// Cheap to clone; handlers capture a clone and hand model calls to it.
+/// The runtime shuts down without waiting once the last clone is dropped.
+#[derive(Clone)]
+pub struct InferenceRuntime {
+    runtime: Arc<OwnedRuntime>,
+}
+
+/// Runtime that may be dropped from async code
+struct OwnedRuntime(Option<Runtime>);
+
+impl Drop for OwnedRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl InferenceRuntime {
+    /// Create an inference runtime with `threads` worker threads
+    pub fn new(threads: usize) -> io::Result<Self> {
+        Self::with_config(RuntimeConfig {
+            worker_threads: Some(threads),
+            ..Self::default_config()
+        })
+    }
+
+    /// Create an inference runtime with custom configuration
+    pub fn with_config(config: RuntimeConfig) -> io::Result<Self> {
+        Ok(Self { runtime: Arc::new(OwnedRuntime(Some(config.build()?))) })
+    }
+
+    /// Default configuration, naming threads `quill-inference`
+    pub fn default_config() -> RuntimeConfig {
+        RuntimeConfig { thread_name: "quill-inference".to_string(), ..RuntimeConfig::default() }
+    }
+
+    /// Handle of the underlying runtime
+    pub fn handle(&self) -> &Handle {
+        self.runtime.0.as_ref().expect("inference runtime is running").handle()
+    }
+
+    /// Run a future on the inference runtime and wait for its output
+    pub fn run<F>(&self, future: F) -> impl Future<Output = Result<F::Output, QuillError>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let task = self.handle().spawn(future);
+        async move { task.await.map_err(|e| QuillError::Rpc(format!("Inference task failed: {}", e))) }
+    }
+
+    /// Run a blocking closure on the inference runtime's blocking pool
+    pub fn run_blocking<F, R>(&self, f: F) -> impl Future<Output = Result<R, QuillError>>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let task = self.handle().spawn_blocking(f);
+        async move { task.await.map_err(|e| QuillError::Rpc(format!("Inference task failed: {}", e))) }
+    }
+}
+
+impl std::fmt::Debug for InferenceRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InferenceRuntime").finish_non_exhaustive()
+    }
+}
+
+/// I/O backend serving accepted TCP connections
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IoBackend {
+    /// tokio's readiness-based I/O (epoll, kqueue)
+    #[default]
+    Tokio,
+    /// io_uring reads and writes on one ring per acceptor thread
+    ///
+    /// Needs Linux and the `io-uring` feature; elsewhere, or when the
+    /// kernel refuses to create a ring, the server falls back to
+    /// [`IoBackend::Tokio`]. HTTP/3 keeps using quinn's UDP socket.
+    IoUring,
+}
+
+impl IoBackend {
+    /// Whether this backend can be used in this build and on this host
+    pub fn is_available(self) -> bool {
+        match self {
+            IoBackend::Tokio => true,
+            IoBackend::IoUring => io_uring_available(),
+        }
+    }
+
+    /// This backend if it is available, otherwise [`IoBackend::Tokio`]
+    pub fn resolve(self) -> Self {
+        if self.is_available() {
+            return self;
+        }
+        tracing::warn!("{:?} I/O backend unavailable, falling back to tokio", self);
+        IoBackend::Tokio
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+fn io_uring_available() -> bool {
+    crate::uring::is_available()
+}
+
+#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+fn io_uring_available() -> bool {
+    false
+}
+
+/// Options of the listening sockets
+#[derive(Debug, Clone)]
+pub struct SocketConfig {
+    /// Bind one listener per acceptor with `SO_REUSEPORT`, letting the
+    /// kernel balance connections between them (Unix only)
+    pub reuse_port: bool,
+    /// Tasks accepting connections
+    pub acceptors: usize,
+    /// Pending connection queue length
+    pub backlog: u32,
+    /// Disable Nagle's algorithm on accepted connections
+    pub tcp_nodelay: bool,
+    /// Kernel receive buffer size in bytes (None = OS default)
+    pub recv_buffer_size: Option<usize>,
+    /// Kernel send buffer size in bytes (None = OS default)
+    pub send_buffer_size: Option<usize>,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        Self {
+            reuse_port: false,
+            acceptors: 1,
+            backlog: 1024,
+            tcp_nodelay: false,
+            recv_buffer_size: None,
+            send_buffer_size: None,
+        }
+    }
+}
+
+impl SocketConfig {
+    /// Bind the listeners for `addr`, one per acceptor with `SO_REUSEPORT`
+    ///
+    /// Must be called from within a tokio runtime.
+    pub(crate) fn bind_tcp(&self, addr: SocketAddr) -> io::Result<Vec<Arc<TcpListener>>> {
+        self.bind_std(addr)?
+            .into_iter()
+            .map(|listener| TcpListener::from_std(listener).map(Arc::new))
+            .collect()
+    }
+
+    /// Bind non-blocking std listeners, not yet registered with a runtime
+    pub(crate) fn bind_std(&self, addr: SocketAddr) -> io::Result<Vec<std::net::TcpListener>> {
+        let first = self.bind_one(addr)?;
+        if !self.reuse_port {
+            return Ok(vec![first]);
+        }
+
+        // Later listeners join the port the first one got, which matters for port 0
+        let addr = first.local_addr()?;
+        let mut listeners = vec![first];
+        for _ in 1..self.acceptors.max(1) {
+            listeners.push(self.bind_one(addr)?);
+        }
+        Ok(listeners)
+    }
+
+    /// Apply the backlog and buffer sizes to a listener bound by the caller
+    ///
+    /// Listening again only updates the queue length, and accepted
+    /// connections inherit the buffer sizes.
+    pub(crate) fn configure_bound(&self, listener: &TcpListener) -> io::Result<()> {
+        let socket = SockRef::from(listener);
+        apply_buffer_sizes(&socket, self.recv_buffer_size, self.send_buffer_size)?;
+        socket.listen(self.backlog.min(i32::MAX as u32) as i32)
+    }
+
+    fn bind_one(&self, addr: SocketAddr) -> io::Result<std::net::TcpListener> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        #[cfg(not(windows))]
+        socket.set_reuse_address(true)?;
+        if self.reuse_port {
+            set_reuse_port(&socket)?;
+        }
+        apply_buffer_sizes(&socket, self.recv_buffer_size, self.send_buffer_size)?;
+        socket.set_nonblocking(true)?;
+        socket.bind(&addr.into())?;
+        socket.listen(self.backlog.min(i32::MAX as u32) as i32)?;
+        Ok(socket.into())
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+fn set_reuse_port(socket: &Socket) -> io::Result<()> {
+    socket.set_reuse_port(true)
+}
+
+#[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+fn set_reuse_port(_socket: &Socket) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "SO_REUSEPORT is not supported on this platform"))
+}
+
+/// Set the kernel buffer sizes of a socket, where given
+pub(crate) fn apply_buffer_sizes(socket: &Socket, recv: Option<usize>, send: Option<usize>) -> io::Result<()> {
+    if let Some(size) = recv {
+        socket.set_recv_buffer_size(size)?;
+    }
+    if let Some(size) = send {
+        socket.set_send_buffer_size(size)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_runtime_runs_tasks() {
+        let config = RuntimeConfig {
+            worker_threads: Some(2),
+            core_pinning: CorePinning::Cores(vec![0]),
+            ..RuntimeConfig::default()
+        };
+        let runtime = config.build().unwrap();
+        assert_eq!(runtime.block_on(async { tokio::spawn(async { 21 * 2 }).await.unwrap() }), 42);
+        assert!(CorePinning::Cores(Vec::new()).core_ids().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_inference_runtime_off_the_io_threads() {
+        let inference = InferenceRuntime::new(1).unwrap();
+        let name = inference
+            .run_blocking(|| std::thread::current().name().map(str::to_string))
+            .await
+            .unwrap();
+        assert_eq!(name.as_deref(), Some("quill-inference"));
+        assert_eq!(inference.run(async { 7 }).await.unwrap(), 7);
+
+        // Dropping the last clone inside async code must not panic
+        drop(inference);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reuse_port_listeners_share_port() {
+        let config = SocketConfig { reuse_port: true, acceptors: 3, ..SocketConfig::default() };
+        let listeners = config.bind_tcp("127.0.0.1:0".parse().unwrap()).unwrap();
+        assert_eq!(listeners.len(), 3);
+        let port = listeners[0].local_addr().unwrap().port();
+        assert!(listeners.iter().all(|l| l.local_addr().unwrap().port() == port));
+
+        // Without SO_REUSEPORT the port can't be bound twice
+        let single = SocketConfig::default().bind_tcp("127.0.0.1:0".parse().unwrap()).unwrap();
+        assert_eq!(single.len(), 1);
+        assert!(SocketConfig::default().bind_tcp(single[0].local_addr().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_io_backend_falls_back() {
+        assert_eq!(IoBackend::Tokio.resolve(), IoBackend::Tokio);
+        let resolved = IoBackend::IoUring.resolve();
+        assert_eq!(resolved == IoBackend::IoUring, IoBackend::IoUring.is_available());
+        if cfg!(not(all(target_os = "linux", feature = "io-uring"))) {
+            assert_eq!(resolved, IoBackend::Tokio);
+        }
+    }
+}
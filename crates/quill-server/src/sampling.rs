@@ -0,0 +1,346 @@
+//! Payload sampling for production debugging
+//!
+//! This module provides:
+//! - Per-method sample rates for attaching payloads to traces
+//! - Truncation of sampled payloads to a size cap
+//! - Redaction of secret-looking JSON fields and text, plus a custom hook
+//!
+//! Sampled unary request and response payloads are emitted as `tracing`
+//! events (target [`PAYLOAD_TARGET`]) inside the current span, so with
+//! `tracing-opentelemetry` installed they become events of the active
+//! span. Rates are applied deterministically: a method sampled at `0.1`
+//! records exactly every tenth call, with the request and response of a
+//! call sampled together. Envelope-encrypted calls are never sampled, and
+//! streaming messages are not sampled.
+
+use crate::debug::{redact, SECRET_KEYS};
+use crate::overrides::MethodOverrides;
+use bytes::Bytes;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// `tracing` target of payload sample events
+pub const PAYLOAD_TARGET: &str = "quill::payload";
+
+/// Default size cap of a sampled payload in bytes
+pub const DEFAULT_MAX_PAYLOAD_BYTES: usize = 512;
+
+/// Rewrites a payload before it is rendered, e.g. to strip protobuf fields
+pub type RedactorFn = Arc<dyn Fn(&str, &Bytes) -> Bytes + Send + Sync>;
+
+/// Callback invoked for every recorded sample
+pub type PayloadSampleListener = Arc<dyn Fn(&PayloadSample) + Send + Sync>;
+
+/// Which side of a call a sample shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadDirection {
+    /// Request sent by the client
+    Request,
+    /// Response returned by the handler
+    Response,
+}
+
+impl PayloadDirection {
+    /// Name used in sample events
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayloadDirection::Request => "request",
+            PayloadDirection::Response => "response",
+        }
+    }
+}
+
+/// A recorded payload sample
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadSample {
+    /// Method path (`pkg.Service/Method`)
+    pub method: String,
+    /// Request or response
+    pub direction: PayloadDirection,
+    /// Size of the full payload in bytes
+    pub size: usize,
+    /// Whether the rendered payload was cut at the size cap
+    pub truncated: bool,
+    /// Redacted payload: JSON or text as-is, binary as `hex:<bytes>`
+    pub payload: String,
+}
+
+/// Payload sampling configuration
+#[derive(Clone)]
+pub struct PayloadSamplingConfig {
+    /// Fraction of calls sampled for methods without their own rate
+    pub default_rate: f64,
+    /// Size cap of a sampled payload in bytes
+    pub max_payload_bytes: usize,
+    rates: MethodOverrides<f64>,
+    redact_fields: Vec<String>,
+    redactor: Option<RedactorFn>,
+    listener: Option<PayloadSampleListener>,
+}
+
+impl Default for PayloadSamplingConfig {
+    fn default() -> Self {
+        Self {
+            default_rate: 0.0,
+            max_payload_bytes: DEFAULT_MAX_PAYLOAD_BYTES,
+            rates: MethodOverrides::default(),
+            redact_fields: Vec::new(),
+            redactor: None,
+            listener: None,
+        }
+    }
+}
+
+impl PayloadSamplingConfig {
+    /// Sample `default_rate` of all calls (`0.0`–`1.0`)
+    pub fn new(default_rate: f64) -> Self {
+        Self {
+            default_rate: default_rate.clamp(0.0, 1.0),
+            ..Self::default()
+        }
+    }
+
+    /// Set the rate of a method (`pkg.Service/Method`) or a whole service (`pkg.Service`)
+    pub fn method_rate(mut self, path: impl Into<String>, rate: f64) -> Self {
+        self.rates.insert(path, rate.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Set the size cap of a sampled payload
+    pub fn max_payload_bytes(mut self, bytes: usize) -> Self {
+        self.max_payload_bytes = bytes;
+        self
+    }
+
+    /// Redact a JSON field by name, in addition to secret-looking names
+    pub fn redact_field(mut self, name: impl Into<String>) -> Self {
+        self.redact_fields.push(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Rewrite payloads before they are rendered
+    ///
+    /// The hook receives the method path and the full payload. Use it for
+    /// binary formats the built-in JSON and text redaction cannot see into.
+    pub fn redactor<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, &Bytes) -> Bytes + Send + Sync + 'static,
+    {
+        self.redactor = Some(Arc::new(f));
+        self
+    }
+
+    /// Register a listener for recorded samples
+    pub fn on_sample<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&PayloadSample) + Send + Sync + 'static,
+    {
+        self.listener = Some(Arc::new(f));
+        self
+    }
+
+    fn rate(&self, method: &str) -> f64 {
+        self.rates.get(method).copied().unwrap_or(self.default_rate)
+    }
+}
+
+impl fmt::Debug for PayloadSamplingConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadSamplingConfig")
+            .field("default_rate", &self.default_rate)
+            .field("max_payload_bytes", &self.max_payload_bytes)
+            .field("rates", &self.rates)
+            .field("redact_fields", &self.redact_fields)
+            .field("redactor", &self.redactor.is_some())
+            .finish()
+    }
+}
+
+/// Decides which calls are sampled and records their payloads
+pub(crate) struct PayloadSampler {
+    config: PayloadSamplingConfig,
+    calls: Mutex<HashMap<String, u64>>,
+}
+
+impl PayloadSampler {
+    pub(crate) fn new(config: PayloadSamplingConfig) -> Self {
+        Self {
+            config,
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a call to `method` and decide whether to sample it
+    pub(crate) fn should_sample(&self, method: &str) -> bool {
+        let rate = self.config.rate(method);
+        if rate <= 0.0 {
+            return false;
+        }
+        let mut calls = self.calls.lock().unwrap();
+        let n = calls.entry(method.to_string()).or_insert(0);
+        let sampled = ((*n + 1) as f64 * rate).floor() > (*n as f64 * rate).floor();
+        *n += 1;
+        sampled
+    }
+
+    /// Emit a sample event for a payload of a sampled call
+    pub(crate) fn record(&self, method: &str, direction: PayloadDirection, payload: &Bytes) {
+        let sample = self.sample(method, direction, payload);
+        tracing::event!(
+            target: PAYLOAD_TARGET,
+            tracing::Level::INFO,
+            rpc.method = %sample.method,
+            payload.direction = sample.direction.as_str(),
+            payload.size = sample.size,
+            payload.truncated = sample.truncated,
+            payload = %sample.payload,
+            "payload sample"
+        );
+        if let Some(listener) = &self.config.listener {
+            listener(&sample);
+        }
+    }
+
+    fn sample(&self, method: &str, direction: PayloadDirection, payload: &Bytes) -> PayloadSample {
+        let (rendered, truncated) = match &self.config.redactor {
+            Some(redactor) => self.render(&redactor(method, payload)),
+            None => self.render(payload),
+        };
+
+        PayloadSample {
+            method: method.to_string(),
+            direction,
+            size: payload.len(),
+            truncated,
+            payload: rendered,
+        }
+    }
+
+    /// Redacted rendering capped at the size limit, and whether it was cut
+    ///
+    /// JSON has secret fields masked, other text is redacted, and binary
+    /// payloads are hex-encoded.
+    fn render(&self, payload: &[u8]) -> (String, bool) {
+        let max = self.config.max_payload_bytes;
+        let Ok(text) = std::str::from_utf8(payload) else {
+            let shown = payload.len().min(max.saturating_sub(4) / 2);
+            let mut hex = String::with_capacity(4 + shown * 2);
+            hex.push_str("hex:");
+            for byte in &payload[..shown] {
+                let _ = write!(hex, "{:02x}", byte);
+            }
+            return (hex, shown < payload.len());
+        };
+
+        let mut rendered = match serde_json::from_str::<Value>(text) {
+            Ok(mut json @ (Value::Object(_) | Value::Array(_))) => {
+                self.redact_json(&mut json);
+                json.to_string()
+            }
+            _ => redact(text),
+        };
+        if rendered.len() <= max {
+            return (rendered, false);
+        }
+        let mut end = max;
+        while !rendered.is_char_boundary(end) {
+            end -= 1;
+        }
+        rendered.truncate(end);
+        (rendered, true)
+    }
+
+    fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.is_secret(key) {
+                        *value = Value::String("[redacted]".to_string());
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            Value::String(text) => *text = redact(text),
+            _ => {}
+        }
+    }
+
+    fn is_secret(&self, key: &str) -> bool {
+        let key = key.to_ascii_lowercase();
+        SECRET_KEYS.iter().any(|secret| key.contains(secret)) || self.config.redact_fields.contains(&key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sampler(config: PayloadSamplingConfig) -> PayloadSampler {
+        PayloadSampler::new(config)
+    }
+
+    #[test]
+    fn test_rates_are_exact_and_per_method() {
+        let sampler = sampler(
+            PayloadSamplingConfig::new(0.25)
+                .method_rate("users.v1.Users", 0.5)
+                .method_rate("users.v1.Users/Login", 0.0),
+        );
+
+        let count = |method: &str| (0..100).filter(|_| sampler.should_sample(method)).count();
+        assert_eq!(count("echo.v1.Echo/Echo"), 25);
+        assert_eq!(count("users.v1.Users/Get"), 50);
+        assert_eq!(count("users.v1.Users/Login"), 0);
+    }
+
+    #[test]
+    fn test_json_redaction() {
+        let sampler = sampler(PayloadSamplingConfig::new(1.0).redact_field("SSN"));
+        let payload = Bytes::from_static(
+            br#"{"user":"ada","password":"hunter2","profile":{"ssn":"123","note":"Bearer abc"},"ids":[1,2]}"#,
+        );
+
+        let sample = sampler.sample("users.v1.Users/Create", PayloadDirection::Request, &payload);
+        let json: Value = serde_json::from_str(&sample.payload).unwrap();
+        assert_eq!(json["user"], "ada");
+        assert_eq!(json["password"], "[redacted]");
+        assert_eq!(json["profile"]["ssn"], "[redacted]");
+        assert_eq!(json["profile"]["note"], "Bearer [redacted]");
+        assert_eq!(json["ids"], serde_json::json!([1, 2]));
+        assert!(!sample.truncated);
+        assert_eq!(sample.size, payload.len());
+    }
+
+    #[test]
+    fn test_truncation_and_binary() {
+        let sampler = sampler(PayloadSamplingConfig::new(1.0).max_payload_bytes(10));
+
+        let text = sampler.sample("a.B/C", PayloadDirection::Response, &Bytes::from("é".repeat(20)));
+        assert!(text.truncated);
+        assert!(text.payload.len() <= 10);
+        assert_eq!(text.size, 40);
+
+        let binary = sampler.sample("a.B/C", PayloadDirection::Response, &Bytes::from_static(&[0xff, 0x00, 0x01]));
+        assert_eq!(binary.payload, "hex:ff0001");
+        assert!(!binary.truncated);
+
+        let long = sampler.sample("a.B/C", PayloadDirection::Response, &Bytes::from(vec![0xffu8; 100]));
+        assert_eq!(long.payload, "hex:ffffff");
+        assert!(long.truncated);
+    }
+
+    #[test]
+    fn test_custom_redactor() {
+        let sampler = sampler(PayloadSamplingConfig::new(1.0).redactor(|method, payload| {
+            assert_eq!(method, "a.B/C");
+            Bytes::from(format!("{} bytes", payload.len()))
+        }));
+        let sample = sampler.sample("a.B/C", PayloadDirection::Request, &Bytes::from_static(&[0xff; 7]));
+        assert_eq!(sample.payload, "7 bytes");
+        assert_eq!(sample.size, 7);
+    }
+}
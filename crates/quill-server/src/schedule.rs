@@ -0,0 +1,485 @@
+//! Scheduled invocation of registered methods
+//!
+//! Jobs call a unary handler of the router on a cron schedule or at a fixed
+//! interval, e.g. to refresh caches, reload models or run self-health checks.
+//! Each job has an [`OverlapPolicy`] for runs that come due while the
+//! previous one is still in progress, and an optional jitter that spreads
+//! runs of many servers sharing a schedule.
+//!
+//! Cron expressions have five fields (minute, hour, day of month, month,
+//! day of week) evaluated in UTC. Fields accept `*`, single values, ranges
+//! (`1-5`), steps (`*/15`, `0-30/10`) and comma-separated lists. As in cron,
+//! a restricted day of month and day of week match when either does.
+//!
+//! Recent runs are kept in memory and returned as JSON by the built-in
+//! history method ([`SCHEDULER_HISTORY_PATH`]).
+
+use crate::context::RequestContext;
+use crate::router::RpcRouter;
+use bytes::Bytes;
+use quill_core::{BatchEntry, ProblemDetails};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+/// Service name of the built-in scheduler admin service
+pub const SCHEDULER_SERVICE: &str = "quill.scheduler.v1.Scheduler";
+
+/// Method returning recent scheduled runs
+pub const SCHEDULER_HISTORY_METHOD: &str = "History";
+
+/// Path of the built-in history method, without a leading slash
+pub const SCHEDULER_HISTORY_PATH: &str = "quill.scheduler.v1.Scheduler/History";
+
+/// Runs kept in the history
+const MAX_HISTORY: usize = 256;
+
+/// Years searched for the next match of a cron expression
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// Errors from parsing a cron expression
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CronError {
+    #[error("Expected 5 cron fields, got {0}")]
+    FieldCount(usize),
+
+    #[error("Invalid cron {field} field: {value}")]
+    InvalidField { field: &'static str, value: String },
+}
+
+/// A five-field cron expression evaluated in UTC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(CronError::FieldCount(fields.len()));
+        }
+        // Day of week 7 is Sunday, like 0
+        let weekdays = parse_field(fields[4], "day of week", 0, 7)?;
+        Ok(Self {
+            minutes: parse_field(fields[0], "minute", 0, 59)?,
+            hours: parse_field(fields[1], "hour", 0, 23)?,
+            days: parse_field(fields[2], "day of month", 1, 31)?,
+            months: parse_field(fields[3], "month", 1, 12)?,
+            weekdays: (weekdays | (weekdays >> 7)) & 0x7f,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+}
+
+impl CronSchedule {
+    /// First matching minute strictly after `after`, if any within five years
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let secs = after.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+        let mut minute = secs / 60 + 1;
+        let last_day = minute / 1440 + MAX_SEARCH_DAYS;
+
+        while minute / 1440 <= last_day {
+            let day = minute / 1440;
+            if !self.matches_day(day) {
+                minute = (day + 1) * 1440;
+                continue;
+            }
+            let hour = minute % 1440 / 60;
+            if self.hours & (1 << hour) == 0 {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+            if self.minutes & (1 << (minute % 60)) == 0 {
+                minute += 1;
+                continue;
+            }
+            return Some(UNIX_EPOCH + Duration::from_secs(minute as u64 * 60));
+        }
+        None
+    }
+
+    fn matches_day(&self, day: i64) -> bool {
+        let (month, day_of_month) = month_and_day(day);
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        // 1970-01-01 was a Thursday
+        let weekday = (day + 4).rem_euclid(7);
+        let day_matches = self.days & (1 << day_of_month) != 0;
+        let weekday_matches = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day_matches || weekday_matches,
+            _ => day_matches && weekday_matches,
+        }
+    }
+}
+
+/// Parse one cron field into a bit set of the values it matches
+fn parse_field(field: &str, name: &'static str, min: u32, max: u32) -> Result<u64, CronError> {
+    let invalid = || CronError::InvalidField {
+        field: name,
+        value: field.to_string(),
+    };
+    let value = |s: &str| {
+        s.parse::<u32>()
+            .ok()
+            .filter(|v| (min..=max).contains(v))
+            .ok_or_else(invalid)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0).ok_or_else(invalid)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/10` steps from 5 to the end of the range
+                None if part.contains('/') => (value(range)?, max),
+                None => {
+                    let v = value(range)?;
+                    (v, v)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for v in (start..=end).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+/// Month (1-12) and day of month of a day counted from 1970-01-01
+fn month_and_day(day: i64) -> (u32, u32) {
+    // Howard Hinnant's civil_from_days
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day_of_month = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month as u32, day_of_month as u32)
+}
+
+/// What to do when a run comes due while the previous one is in progress
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Skip the new run (default)
+    #[default]
+    Skip,
+    /// Start the new run alongside the previous one
+    Allow,
+    /// Abort the previous run and start the new one
+    Replace,
+}
+
+#[derive(Debug, Clone)]
+enum Trigger {
+    Cron(CronSchedule),
+    Every(Duration),
+}
+
+impl Trigger {
+    /// Time until the next run, None if the schedule never fires again
+    fn next_delay(&self) -> Option<Duration> {
+        match self {
+            Trigger::Cron(schedule) => {
+                let now = SystemTime::now();
+                let next = schedule.next_after(now)?;
+                Some(next.duration_since(now).unwrap_or_default())
+            }
+            Trigger::Every(interval) => Some(*interval),
+        }
+    }
+}
+
+/// A registered method invoked on a schedule
+#[derive(Debug, Clone)]
+pub struct ScheduledJob {
+    name: String,
+    path: String,
+    payload: Bytes,
+    trigger: Trigger,
+    overlap: OverlapPolicy,
+    jitter: Duration,
+}
+
+impl ScheduledJob {
+    /// Invoke the unary method at `path` on a cron schedule
+    pub fn cron(name: impl Into<String>, path: impl Into<String>, expr: &str) -> Result<Self, CronError> {
+        Ok(Self::new(name, path, Trigger::Cron(expr.parse()?)))
+    }
+
+    /// Invoke the unary method at `path` every `interval`
+    pub fn every(name: impl Into<String>, path: impl Into<String>, interval: Duration) -> Self {
+        Self::new(name, path, Trigger::Every(interval.max(Duration::from_millis(1))))
+    }
+
+    fn new(name: impl Into<String>, path: impl Into<String>, trigger: Trigger) -> Self {
+        let path = path.into();
+        Self {
+            name: name.into(),
+            path: path.strip_prefix('/').unwrap_or(&path).to_string(),
+            payload: Bytes::new(),
+            trigger,
+            overlap: OverlapPolicy::default(),
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Set the request body passed to the method
+    pub fn payload(mut self, payload: Bytes) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    /// Set what happens when a run comes due while the previous one is in progress
+    pub fn overlap(mut self, policy: OverlapPolicy) -> Self {
+        self.overlap = policy;
+        self
+    }
+
+    /// Delay each run by a random duration of up to `jitter`
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Name identifying the job in the run history
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Outcome of one scheduled run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// The method returned a response
+    Succeeded,
+    /// The method failed with the given status
+    Failed { code: u16, detail: Option<String> },
+    /// The previous run was still in progress
+    Skipped,
+    /// The run was aborted in favor of a newer one
+    Replaced,
+}
+
+/// One run in the scheduler history
+#[derive(Debug, Clone, Serialize)]
+pub struct JobRun {
+    /// Job name
+    pub job: String,
+    /// Method path
+    pub path: String,
+    /// Start time in milliseconds since the Unix epoch
+    pub started_at_ms: u64,
+    /// Run duration; zero for skipped runs
+    pub duration_ms: u64,
+    /// How the run ended
+    #[serde(flatten)]
+    pub outcome: RunOutcome,
+}
+
+#[derive(Serialize)]
+struct History<'a> {
+    runs: &'a VecDeque<JobRun>,
+}
+
+/// Jobs of a router and the history of their runs
+#[derive(Default)]
+pub(crate) struct Scheduler {
+    jobs: Vec<ScheduledJob>,
+    history: Arc<Mutex<VecDeque<JobRun>>>,
+}
+
+impl Scheduler {
+    pub(crate) fn add(&mut self, job: ScheduledJob) {
+        self.jobs.push(job);
+    }
+
+    /// Recent runs, oldest first
+    pub(crate) fn history(&self) -> Vec<JobRun> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Recent runs as the JSON body of the history method
+    pub(crate) fn history_json(&self) -> Bytes {
+        let history = self.history.lock().unwrap();
+        let json = serde_json::to_vec(&History { runs: &history }).unwrap_or_default();
+        Bytes::from(json)
+    }
+
+    /// Spawn a task per job that runs until the router is dropped
+    pub(crate) fn start(&self, router: Weak<RpcRouter>) {
+        for job in &self.jobs {
+            let job = job.clone();
+            let router = router.clone();
+            let history = Arc::clone(&self.history);
+            tokio::spawn(run_job(job, router, history));
+        }
+    }
+}
+
+async fn run_job(job: ScheduledJob, router: Weak<RpcRouter>, history: Arc<Mutex<VecDeque<JobRun>>>) {
+    let mut previous: Option<(JoinHandle<()>, Instant, u64)> = None;
+
+    while let Some(delay) = job.trigger.next_delay() {
+        tokio::time::sleep(delay + jitter(job.jitter)).await;
+        let Some(router) = router.upgrade() else {
+            return;
+        };
+
+        if let Some((handle, started, started_at_ms)) = previous.take() {
+            if !handle.is_finished() {
+                match job.overlap {
+                    OverlapPolicy::Skip => {
+                        record(&history, &job, now_ms(), Duration::ZERO, RunOutcome::Skipped);
+                        previous = Some((handle, started, started_at_ms));
+                        continue;
+                    }
+                    OverlapPolicy::Allow => {}
+                    OverlapPolicy::Replace => {
+                        handle.abort();
+                        record(&history, &job, started_at_ms, started.elapsed(), RunOutcome::Replaced);
+                    }
+                }
+            }
+        }
+
+        let started = Instant::now();
+        let started_at_ms = now_ms();
+        let entry = BatchEntry {
+            path: job.path.clone(),
+            payload: job.payload.clone(),
+        };
+        let (run_job, run_history) = (job.clone(), Arc::clone(&history));
+        let handle = tokio::spawn(async move {
+            let result = router.call_batch_entry(entry, &RequestContext::default(), None).await;
+            let outcome = match result.into_result() {
+                Ok(_) => RunOutcome::Succeeded,
+                Err(pd) => failed(*pd),
+            };
+            if let RunOutcome::Failed { code, detail } = &outcome {
+                tracing::warn!("Scheduled job {} failed with {}: {:?}", run_job.name, code, detail);
+            }
+            record(&run_history, &run_job, started_at_ms, started.elapsed(), outcome);
+        });
+        previous = Some((handle, started, started_at_ms));
+    }
+}
+
+fn failed(pd: ProblemDetails) -> RunOutcome {
+    RunOutcome::Failed {
+        code: pd.status,
+        detail: pd.detail.or(Some(pd.title)),
+    }
+}
+
+fn jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    max.mul_f64(rand::random::<f64>())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn record(
+    history: &Mutex<VecDeque<JobRun>>,
+    job: &ScheduledJob,
+    started_at_ms: u64,
+    duration: Duration,
+    outcome: RunOutcome,
+) {
+    let mut history = history.lock().unwrap();
+    if history.len() == MAX_HISTORY {
+        history.pop_front();
+    }
+    history.push_back(JobRun {
+        job: job.name.clone(),
+        path: job.path.clone(),
+        started_at_ms,
+        duration_ms: duration.as_millis() as u64,
+        outcome,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds since the epoch of a UTC date and time
+    fn at(days: i64, hour: u64, minute: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(days as u64 * 86_400 + hour * 3600 + minute * 60)
+    }
+
+    // 2024-01-04 (a Thursday) is day 19726
+    const THURSDAY: i64 = 19_726;
+
+    #[test]
+    fn test_month_and_day() {
+        assert_eq!(month_and_day(0), (1, 1));
+        assert_eq!(month_and_day(THURSDAY), (1, 4));
+        // 2024-02-29
+        assert_eq!(month_and_day(THURSDAY + 56), (2, 29));
+    }
+
+    #[test]
+    fn test_next_after() {
+        let every_15: CronSchedule = "*/15 * * * *".parse().unwrap();
+        assert_eq!(every_15.next_after(at(THURSDAY, 10, 7)), Some(at(THURSDAY, 10, 15)));
+        assert_eq!(every_15.next_after(at(THURSDAY, 10, 45)), Some(at(THURSDAY, 11, 0)));
+
+        // Mondays at 09:00, next one four days later
+        let monday: CronSchedule = "0 9 * * 1".parse().unwrap();
+        assert_eq!(monday.next_after(at(THURSDAY, 12, 0)), Some(at(THURSDAY + 4, 9, 0)));
+
+        // The 1st of the month or any Sunday (7), whichever comes first
+        let either: CronSchedule = "30 6 1 * 7".parse().unwrap();
+        assert_eq!(either.next_after(at(THURSDAY, 0, 0)), Some(at(THURSDAY + 3, 6, 30)));
+
+        // February 30th never happens
+        let never: CronSchedule = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(never.next_after(at(THURSDAY, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("* * * *".parse::<CronSchedule>(), Err(CronError::FieldCount(4)));
+        assert!(matches!(
+            "60 * * * *".parse::<CronSchedule>(),
+            Err(CronError::InvalidField { field: "minute", .. })
+        ));
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+        assert!("* 5-2 * * *".parse::<CronSchedule>().is_err());
+        assert!("0,30 8-18/2 1 1-6 MON".parse::<CronSchedule>().is_err());
+        assert!("0,30 8-18/2 1 1-6 1-5".parse::<CronSchedule>().is_ok());
+    }
+}
@@ -1,18 +1,31 @@
 //! Quill server implementation
 
+use crate::context::RequestContext;
 use crate::router::{RequestStream, RpcRouter};
+use crate::runtime::{CorePinning, InferenceRuntime, IoBackend, RuntimeConfig, SocketConfig};
 use crate::streaming::RpcResponse;
 use bytes::Bytes;
 use http::Request;
 use hyper::body::Incoming;
-use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
 use hyper_util::server::conn::auto;
-use quill_core::QuillError;
+use quill_core::{QuillError, StreamCursor};
+#[cfg(feature = "tensor")]
+use quill_tensor::TensorFrame;
+#[cfg(feature = "test-util")]
+use quill_transport::sim::SimListener;
 use std::future::Future;
+use std::io;
 use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio_stream::Stream;
 use tracing::{error, info};
 
 /// HTTP version configuration
@@ -43,6 +56,12 @@ pub struct ServerConfig {
     pub http2_keep_alive_timeout: Option<Duration>,
     /// HTTP/2 max frame size
     pub http2_max_frame_size: Option<u32>,
+    /// Runtime created by [`QuillServer::run`]
+    pub runtime: RuntimeConfig,
+    /// Options of the listening sockets
+    pub socket: SocketConfig,
+    /// I/O backend of accepted TCP connections
+    pub io_backend: IoBackend,
 }
 
 impl Default for ServerConfig {
@@ -55,6 +74,9 @@ impl Default for ServerConfig {
             http2_keep_alive_interval: Some(Duration::from_secs(10)),
             http2_keep_alive_timeout: Some(Duration::from_secs(20)),
             http2_max_frame_size: Some(16 * 1024), // 16KB
+            runtime: RuntimeConfig::default(),
+            socket: SocketConfig::default(),
+            io_backend: IoBackend::default(),
         }
     }
 }
@@ -63,6 +85,7 @@ impl Default for ServerConfig {
 pub struct QuillServer {
     router: Arc<RpcRouter>,
     config: ServerConfig,
+    inference: Option<InferenceRuntime>,
 }
 
 impl QuillServer {
@@ -71,6 +94,7 @@ impl QuillServer {
         Self {
             router: Arc::new(router),
             config: ServerConfig::default(),
+            inference: None,
         }
     }
 
@@ -79,6 +103,7 @@ impl QuillServer {
         Self {
             router: Arc::new(router),
             config,
+            inference: None,
         }
     }
 
@@ -87,106 +112,304 @@ impl QuillServer {
         ServerBuilder::new()
     }
 
+    /// Runtime for inference work set with [`ServerBuilder::inference_runtime`]
+    pub fn inference_runtime(&self) -> Option<&InferenceRuntime> {
+        self.inference.as_ref()
+    }
+
+    /// Build the configured runtime and serve on the given address until an error
+    ///
+    /// Call from a plain `fn main`, not from within a runtime: worker
+    /// threads and core pinning can only be chosen when the runtime is
+    /// created.
+    pub fn run(self, addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
+        let runtime = self.config.runtime.build()?;
+        runtime.block_on(self.serve(addr))
+    }
+
     /// Serve the server on the given address
     pub async fn serve(self, addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
-        let listener = TcpListener::bind(addr).await?;
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if self.config.io_backend.resolve() == IoBackend::IoUring {
+            let listeners = self.config.socket.bind_std(addr)?;
+            return self.serve_uring(listeners).await;
+        }
+
+        let listeners = self.config.socket.bind_tcp(addr)?;
+        self.serve_tcp(listeners).await
+    }
+
+    /// Serve connections accepted on an already bound `listener`
+    ///
+    /// Binding first gives the caller the address, e.g. of port `0`, before
+    /// serving starts, with no window for another socket to take it. The
+    /// backlog, buffer sizes, acceptors and I/O backend are applied as by
+    /// [`serve`](Self::serve); `SO_REUSEPORT` needs a listener per acceptor
+    /// bound with it, so it is rejected here.
+    pub async fn serve_with_listener(
+        self,
+        listener: TcpListener,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.config.socket.reuse_port {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "reuse_port needs the listeners bound by `serve`",
+            )
+            .into());
+        }
+        self.config.socket.configure_bound(&listener)?;
+
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if self.config.io_backend.resolve() == IoBackend::IoUring {
+            return self.serve_uring(vec![listener.into_std()?]).await;
+        }
+
+        self.serve_tcp(vec![Arc::new(listener)]).await
+    }
+
+    /// Run the configured acceptors over tokio TCP listeners
+    async fn serve_tcp(self, listeners: Vec<Arc<TcpListener>>) -> Result<(), Box<dyn std::error::Error>> {
+        info!(
+            "Quill server listening on {} (HTTP version: {:?}, acceptors: {})",
+            listeners[0].local_addr()?,
+            self.config.http_version,
+            self.config.socket.acceptors.max(1)
+        );
+
+        let config = Arc::new(self.config);
+        self.router.start_scheduler();
+
+        // With SO_REUSEPORT each acceptor owns a listener, otherwise they share one
+        let mut acceptors = tokio::task::JoinSet::new();
+        for i in 0..config.socket.acceptors.max(1) {
+            let listener = Arc::clone(&listeners[i % listeners.len()]);
+            acceptors.spawn(accept_loop(listener, Arc::clone(&self.router), Arc::clone(&config)));
+        }
+        match acceptors.join_next().await {
+            Some(Ok(result)) => result.map_err(Into::into),
+            Some(Err(e)) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Serve TCP connections with io_uring reads and writes
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    async fn serve_uring(
+        self,
+        listeners: Vec<std::net::TcpListener>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let acceptors = self.config.socket.acceptors.max(1);
+        info!(
+            "Quill server listening on {} (HTTP version: {:?}, acceptors: {}, I/O: io_uring)",
+            listeners[0].local_addr()?,
+            self.config.http_version,
+            acceptors
+        );
+
+        let config = Arc::new(self.config);
+        self.router.start_scheduler();
+        crate::uring::serve(listeners, acceptors, self.router, config).await.map_err(Into::into)
+    }
+
+    /// Serve the server on a Unix domain socket at `path`
+    ///
+    /// For sidecars sharing a host with their clients: no TCP overhead, and
+    /// access is governed by the socket's file permissions. A stale socket
+    /// left at `path` by a previous run is replaced. Clients connect with
+    /// `ClientBuilder::uds_path` and speak HTTP/2, so every streaming mode
+    /// works as over TCP.
+    #[cfg(unix)]
+    pub async fn serve_uds(self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let path = path.as_ref();
+        if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
         info!(
             "Quill server listening on {} (HTTP version: {:?})",
-            addr, self.config.http_version
+            path.display(),
+            self.config.http_version
         );
 
         let config = Arc::new(self.config);
+        self.router.start_scheduler();
 
         loop {
-            let (stream, remote_addr) = listener.accept().await?;
+            let (stream, _) = listener.accept().await?;
             let router = Arc::clone(&self.router);
             let config = Arc::clone(&config);
+            let path = path.to_path_buf();
 
             tokio::spawn(async move {
-                let io = TokioIo::new(stream);
-
-                let service = hyper::service::service_fn(move |req: Request<Incoming>| {
-                    let router = Arc::clone(&router);
-                    async move { Ok::<_, hyper::Error>(router.route(req).await) }
-                });
-
-                // Configure connection based on HTTP version setting
-                let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = match config.http_version {
-                    HttpVersion::Http1Only => {
-                        // HTTP/1.1 only
-                        let mut builder = auto::Builder::new(TokioExecutor::new());
-                        // Disable HTTP/2, keep HTTP/1
-                        builder.http1();
-                        builder.serve_connection(io, service).await.map_err(Into::into)
-                    }
-                    HttpVersion::Http2Only => {
-                        // HTTP/2 only - use direct h2 module
-                        use hyper::server::conn::http2;
-                        let mut builder = http2::Builder::new(TokioExecutor::new());
-
-                        if let Some(window_size) = config.http2_initial_connection_window_size {
-                            builder.initial_connection_window_size(window_size);
-                        }
-                        if let Some(window_size) = config.http2_initial_stream_window_size {
-                            builder.initial_stream_window_size(window_size);
-                        }
-                        if let Some(max_streams) = config.http2_max_concurrent_streams {
-                            builder.max_concurrent_streams(max_streams);
-                        }
-                        if let Some(interval) = config.http2_keep_alive_interval {
-                            builder.keep_alive_interval(interval);
-                        }
-                        if let Some(timeout) = config.http2_keep_alive_timeout {
-                            builder.keep_alive_timeout(timeout);
-                        }
-                        if let Some(frame_size) = config.http2_max_frame_size {
-                            builder.max_frame_size(frame_size);
-                        }
-
-                        builder.serve_connection(io, service).await.map_err(Into::into)
-                    }
-                    HttpVersion::Auto => {
-                        // Auto-negotiate HTTP/1.1 or HTTP/2
-                        let mut builder = auto::Builder::new(TokioExecutor::new());
-
-                        // Configure HTTP/2 settings for when HTTP/2 is negotiated
-                        let mut http2 = builder.http2();
-                        if let Some(window_size) = config.http2_initial_connection_window_size {
-                            http2.initial_connection_window_size(window_size);
-                        }
-                        if let Some(window_size) = config.http2_initial_stream_window_size {
-                            http2.initial_stream_window_size(window_size);
-                        }
-                        if let Some(max_streams) = config.http2_max_concurrent_streams {
-                            http2.max_concurrent_streams(max_streams);
-                        }
-                        if let Some(interval) = config.http2_keep_alive_interval {
-                            http2.keep_alive_interval(interval);
-                        }
-                        if let Some(timeout) = config.http2_keep_alive_timeout {
-                            http2.keep_alive_timeout(timeout);
-                        }
-                        if let Some(frame_size) = config.http2_max_frame_size {
-                            http2.max_frame_size(frame_size);
-                        }
-                        drop(http2);
-
-                        builder.serve_connection(io, service).await.map_err(Into::into)
-                    }
-                };
-
-                if let Err(err) = result {
-                    error!("Error serving connection from {}: {:?}", remote_addr, err);
+                if let Err(err) = serve_connection(router, config, stream, None).await {
+                    error!("Error serving connection on {}: {:?}", path.display(), err);
                 }
             });
         }
     }
+
+    /// Serve connections made to `listener` on a simulated network
+    ///
+    /// For deterministic tests of latency, loss and partitions, usually on a
+    /// paused tokio clock. Clients connect with `ClientBuilder::sim_network`
+    /// and speak HTTP/2. See [`quill_transport::sim`].
+    #[cfg(feature = "test-util")]
+    pub async fn serve_sim(self, mut listener: SimListener) -> Result<(), Box<dyn std::error::Error>> {
+        info!(
+            "Quill server listening on simulated {} (HTTP version: {:?})",
+            listener.local_addr(),
+            self.config.http_version
+        );
+
+        let config = Arc::new(self.config);
+        self.router.start_scheduler();
+
+        loop {
+            let stream = listener.accept().await?;
+            let router = Arc::clone(&self.router);
+            let config = Arc::clone(&config);
+            let addr = listener.local_addr().to_string();
+
+            tokio::spawn(async move {
+                if let Err(err) = serve_connection(router, config, stream, None).await {
+                    error!("Error serving connection on simulated {}: {:?}", addr, err);
+                }
+            });
+        }
+    }
+}
+
+/// Accept TCP connections and serve each on its own task
+async fn accept_loop(
+    listener: Arc<TcpListener>,
+    router: Arc<RpcRouter>,
+    config: Arc<ServerConfig>,
+) -> std::io::Result<()> {
+    loop {
+        let (stream, remote_addr) = listener.accept().await?;
+        if config.socket.tcp_nodelay {
+            stream.set_nodelay(true)?;
+        }
+        let router = Arc::clone(&router);
+        let config = Arc::clone(&config);
+
+        tokio::spawn(async move {
+            if let Err(err) = serve_connection(router, config, stream, None).await {
+                error!("Error serving connection from {}: {:?}", remote_addr, err);
+            }
+        });
+    }
+}
+
+/// Serve one accepted connection with the router
+///
+/// Handlers run on the current task unless `handlers` names the runtime
+/// to spawn them on.
+pub(crate) async fn serve_connection<S>(
+    router: Arc<RpcRouter>,
+    config: Arc<ServerConfig>,
+    stream: S,
+    handlers: Option<tokio::runtime::Handle>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + 'static,
+{
+    let io = TokioIo::new(stream);
+    let _connection = router.observability().map(|collector| collector.track_connection());
+
+    // Response streams of this connection take turns writing
+    let scheduler = router.connection_scheduler();
+    let service = hyper::service::service_fn(move |mut req: Request<Incoming>| {
+        let router = Arc::clone(&router);
+        let handlers = handlers.clone();
+        if let Some(scheduler) = &scheduler {
+            req.extensions_mut().insert(scheduler.clone());
+        }
+        async move {
+            match handlers {
+                Some(handlers) => handlers.spawn(async move { router.route(req).await }).await,
+                None => Ok(router.route(req).await),
+            }
+        }
+    });
+
+    // Configure connection based on HTTP version setting
+    match config.http_version {
+        HttpVersion::Http1Only => {
+            // HTTP/1.1 only
+            let mut builder = auto::Builder::new(TokioExecutor::new());
+            // Disable HTTP/2, keep HTTP/1
+            builder.http1();
+            builder.serve_connection(io, service).await
+        }
+        HttpVersion::Http2Only => {
+            // HTTP/2 only - use direct h2 module
+            use hyper::server::conn::http2;
+            let mut builder = http2::Builder::new(TokioExecutor::new());
+            // Keep-alive pings need a timer
+            builder.timer(TokioTimer::new());
+
+            if let Some(window_size) = config.http2_initial_connection_window_size {
+                builder.initial_connection_window_size(window_size);
+            }
+            if let Some(window_size) = config.http2_initial_stream_window_size {
+                builder.initial_stream_window_size(window_size);
+            }
+            if let Some(max_streams) = config.http2_max_concurrent_streams {
+                builder.max_concurrent_streams(max_streams);
+            }
+            if let Some(interval) = config.http2_keep_alive_interval {
+                builder.keep_alive_interval(interval);
+            }
+            if let Some(timeout) = config.http2_keep_alive_timeout {
+                builder.keep_alive_timeout(timeout);
+            }
+            if let Some(frame_size) = config.http2_max_frame_size {
+                builder.max_frame_size(frame_size);
+            }
+
+            builder.serve_connection(io, service).await.map_err(Into::into)
+        }
+        HttpVersion::Auto => {
+            // Auto-negotiate HTTP/1.1 or HTTP/2
+            let mut builder = auto::Builder::new(TokioExecutor::new());
+
+            // Configure HTTP/2 settings for when HTTP/2 is negotiated
+            {
+                let mut http2 = builder.http2();
+                http2.timer(TokioTimer::new());
+                if let Some(window_size) = config.http2_initial_connection_window_size {
+                    http2.initial_connection_window_size(window_size);
+                }
+                if let Some(window_size) = config.http2_initial_stream_window_size {
+                    http2.initial_stream_window_size(window_size);
+                }
+                if let Some(max_streams) = config.http2_max_concurrent_streams {
+                    http2.max_concurrent_streams(max_streams);
+                }
+                if let Some(interval) = config.http2_keep_alive_interval {
+                    http2.keep_alive_interval(interval);
+                }
+                if let Some(timeout) = config.http2_keep_alive_timeout {
+                    http2.keep_alive_timeout(timeout);
+                }
+                if let Some(frame_size) = config.http2_max_frame_size {
+                    http2.max_frame_size(frame_size);
+                }
+            }
+
+            builder.serve_connection(io, service).await
+        }
+    }
 }
 
 /// Builder for creating a Quill server
 pub struct ServerBuilder {
     router: RpcRouter,
     config: ServerConfig,
+    inference: Option<InferenceRuntime>,
 }
 
 impl ServerBuilder {
@@ -195,6 +418,7 @@ impl ServerBuilder {
         Self {
             router: RpcRouter::new(),
             config: ServerConfig::default(),
+            inference: None,
         }
     }
 
@@ -267,6 +491,56 @@ impl ServerBuilder {
         self
     }
 
+    /// Register a tensor streaming handler
+    ///
+    /// The handler returns a stream of tensor frames (TENSOR_META,
+    /// TENSOR_PAYLOAD, ...) that are sent incrementally, one per message.
+    /// Path format: "{package}.{Service}/{Method}"
+    #[cfg(feature = "tensor")]
+    pub fn register_tensor_streaming<F, Fut, S>(mut self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Bytes) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<S, QuillError>> + Send + 'static,
+        S: Stream<Item = Result<TensorFrame, QuillError>> + Send + 'static,
+    {
+        self.router.register_tensor_streaming(path, handler);
+        self
+    }
+
+    /// Register a handler whose setup starts before the request body arrives
+    ///
+    /// `setup` runs on the request headers while the body is still being
+    /// read, and `handler` gets its result along with the request. See
+    /// [`RpcRouter::register_with_setup`].
+    /// Path format: "{package}.{Service}/{Method}"
+    pub fn register_with_setup<S, SFut, T, F, Fut>(mut self, path: impl Into<String>, setup: S, handler: F) -> Self
+    where
+        S: Fn(RequestContext) -> SFut + Send + Sync + 'static,
+        SFut: Future<Output = Result<T, QuillError>> + Send + 'static,
+        T: Send + 'static,
+        F: Fn(T, Bytes) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<RpcResponse, QuillError>> + Send + 'static,
+    {
+        self.router.register_with_setup(path, setup, handler);
+        self
+    }
+
+    /// Register a cursor streaming handler
+    ///
+    /// The handler receives the request and the cursor a resumed call
+    /// starts from, and returns messages paired with the cursor after each.
+    /// A cursor frame is sent every `every` messages.
+    /// Path format: "{package}.{Service}/{Method}"
+    pub fn register_cursor_streaming<F, Fut, S>(mut self, path: impl Into<String>, every: usize, handler: F) -> Self
+    where
+        F: Fn(Bytes, Option<StreamCursor>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<S, QuillError>> + Send + 'static,
+        S: Stream<Item = Result<(Bytes, StreamCursor), QuillError>> + Send + 'static,
+    {
+        self.router.register_cursor_streaming(path, every, handler);
+        self
+    }
+
     /// Register a client streaming handler
     ///
     /// The handler receives a stream of request messages and returns a single response.
@@ -293,9 +567,218 @@ impl ServerBuilder {
         self
     }
 
+    /// Record RPC metrics in `metrics` and export a span per call
+    #[cfg(feature = "otel")]
+    pub fn otel_metrics(mut self, metrics: crate::otel::RpcMetrics) -> Self {
+        self.router.set_otel_metrics(metrics);
+        self
+    }
+
+    /// Attach redacted debug context to error responses for entitled callers
+    pub fn debug_policy(mut self, policy: crate::debug::DebugPolicy) -> Self {
+        self.router.set_debug_policy(policy);
+        self
+    }
+
+    /// Accept large unary requests split into chunks on parallel streams
+    pub fn chunked_upload(mut self, config: crate::upload::ChunkedUploadConfig) -> Self {
+        self.router.enable_chunked_upload(config);
+        self
+    }
+
+    /// Open envelope-encrypted requests before they reach handlers
+    pub fn envelope_decryption(mut self, config: crate::envelope::EnvelopeDecryption) -> Self {
+        self.router.enable_envelope_decryption(config);
+        self
+    }
+
+    /// Open and seal the frames of streams encrypted end to end
+    pub fn frame_encryption(mut self, config: crate::frame_encryption::FrameEncryption) -> Self {
+        self.router.enable_frame_encryption(config);
+        self
+    }
+
+    /// Attach sampled, redacted unary payloads to the current span
+    pub fn payload_sampling(mut self, config: crate::sampling::PayloadSamplingConfig) -> Self {
+        self.router.enable_payload_sampling(config);
+        self
+    }
+
+    /// Cancel streams that exchange no frame within an idle timeout
+    pub fn stream_idle_timeout(mut self, config: crate::idle_timeout::StreamIdleConfig) -> Self {
+        self.router.enable_stream_idle_timeout(config);
+        self
+    }
+
+    /// Track active streams, listing and cancelling those that make no progress
+    pub fn stream_gc(mut self, config: crate::stream_gc::StreamGcConfig) -> Self {
+        self.router.enable_stream_gc(config);
+        self
+    }
+
+    /// Batch small response frames into fewer writes
+    pub fn frame_coalescing(mut self, config: crate::coalesce::FrameCoalescingConfig) -> Self {
+        self.router.enable_frame_coalescing(config);
+        self
+    }
+
+    /// Serve request metrics in Prometheus text format on `GET path`, e.g. `/metrics`
+    pub fn enable_metrics(mut self, path: impl Into<String>) -> Self {
+        self.router.enable_metrics(path);
+        self
+    }
+
+    /// Answer the built-in health check and watch RPCs from `reporter`
+    pub fn enable_health(mut self, reporter: crate::health::HealthReporter) -> Self {
+        self.router.enable_health(reporter);
+        self
+    }
+
+    /// Take turns between the response streams of each connection
+    pub fn frame_scheduling(mut self, config: crate::frame_scheduler::FrameSchedulingConfig) -> Self {
+        self.router.enable_frame_scheduling(config);
+        self
+    }
+
+    /// Honor credits granted by clients that request flow control
+    pub fn flow_control(mut self) -> Self {
+        self.router.enable_flow_control();
+        self
+    }
+
+    /// Honor tensor byte windows of up to `max_window` bytes granted by clients
+    pub fn tensor_flow_control(mut self, max_window: u64) -> Self {
+        self.router.enable_tensor_flow_control(max_window);
+        self
+    }
+
+    /// Serve descriptors of the registered services through the built-in reflection method
+    ///
+    /// Add the descriptors with [`file_descriptor_set`](Self::file_descriptor_set).
+    pub fn enable_reflection(mut self) -> Self {
+        self.router.enable_reflection();
+        self
+    }
+
+    /// Serve the interop test service that conformance drivers such as `quill interop` call
+    pub fn enable_interop(mut self) -> Self {
+        self.router.enable_interop();
+        self
+    }
+
+    /// Add descriptors of registered services from an encoded `FileDescriptorSet`
+    ///
+    /// Fails if the bytes are not a valid descriptor set.
+    pub fn file_descriptor_set(mut self, descriptor_set: &[u8]) -> Result<Self, QuillError> {
+        self.router.add_file_descriptor_set(descriptor_set)?;
+        Ok(self)
+    }
+
+    /// Answer unary calls of cached methods without running their handlers
+    ///
+    /// Takes a [`ResponseCache`](crate::ResponseCache), or a
+    /// [`ResponseCacheConfig`](crate::ResponseCacheConfig) to also serve
+    /// remote invalidation. Keep a clone of the cache to fill and invalidate
+    /// it; see [`crate::response_cache`].
+    pub fn response_cache(mut self, config: impl Into<crate::response_cache::ResponseCacheConfig>) -> Self {
+        self.router.enable_response_cache(config);
+        self
+    }
+
+    /// Answer batch RPCs carrying many unary calls
+    pub fn batch(mut self, config: crate::batch::BatchRpcConfig) -> Self {
+        self.router.enable_batch(config);
+        self
+    }
+
+    /// Offer a compression dictionary and use it for unary calls
+    pub fn compression_dictionary(mut self, config: crate::dictionary::DictionaryCompression) -> Self {
+        self.router.enable_compression_dictionary(config);
+        self
+    }
+
+    /// Limit the streams and bandwidth of each authenticated tenant
+    pub fn tenant_isolation(mut self, config: crate::tenant::TenantIsolationConfig) -> Self {
+        self.router.enable_tenant_isolation(config);
+        self
+    }
+
+    /// Invoke a registered unary method on a schedule
+    pub fn schedule(mut self, job: crate::schedule::ScheduledJob) -> Self {
+        self.router.schedule(job);
+        self
+    }
+
+    /// Set the number of runtime worker threads used by [`QuillServer::run`]
+    pub fn worker_threads(mut self, threads: usize) -> Self {
+        self.config.runtime.worker_threads = Some(threads);
+        self
+    }
+
+    /// Pin the runtime threads used by [`QuillServer::run`] to CPU cores
+    pub fn core_pinning(mut self, pinning: CorePinning) -> Self {
+        self.config.runtime.core_pinning = pinning;
+        self
+    }
+
+    /// Keep a separate runtime for blocking inference work
+    ///
+    /// Handlers capture clones of the same runtime; the server holds one so
+    /// it lives as long as the server.
+    pub fn inference_runtime(mut self, runtime: InferenceRuntime) -> Self {
+        self.inference = Some(runtime);
+        self
+    }
+
+    /// Accept connections on `acceptors` listeners bound with `SO_REUSEPORT`
+    pub fn reuse_port(mut self, acceptors: usize) -> Self {
+        self.config.socket.reuse_port = true;
+        self.config.socket.acceptors = acceptors;
+        self
+    }
+
+    /// Set the length of the pending connection queue
+    pub fn listen_backlog(mut self, backlog: u32) -> Self {
+        self.config.socket.backlog = backlog;
+        self
+    }
+
+    /// Disable Nagle's algorithm on accepted connections
+    pub fn tcp_nodelay(mut self, nodelay: bool) -> Self {
+        self.config.socket.tcp_nodelay = nodelay;
+        self
+    }
+
+    /// Set the kernel receive and send buffer sizes of the listening sockets
+    pub fn socket_buffer_sizes(mut self, recv: usize, send: usize) -> Self {
+        self.config.socket.recv_buffer_size = Some(recv);
+        self.config.socket.send_buffer_size = Some(send);
+        self
+    }
+
+    /// Choose the I/O backend of accepted TCP connections
+    ///
+    /// [`IoBackend::IoUring`] falls back to tokio with a warning when the
+    /// `io-uring` feature is off or the kernel does not allow it.
+    pub fn io_backend(mut self, backend: IoBackend) -> Self {
+        self.config.io_backend = backend;
+        self
+    }
+
     /// Build the server
     pub fn build(self) -> QuillServer {
-        QuillServer::with_config(self.router, self.config)
+        let mut server = QuillServer::with_config(self.router, self.config);
+        server.inference = self.inference;
+        server
+    }
+
+    /// Take the router with every registered method and feature
+    ///
+    /// Lets services registered on a builder, e.g. by generated
+    /// `add_service` functions, be served over another transport such as
+    /// `QuillH3Server`. Server settings are dropped.
+    pub fn into_router(self) -> RpcRouter {
+        self.router
     }
 }
 
@@ -0,0 +1,252 @@
+//! Conversation state kept across calls
+//!
+//! This module provides:
+//! - The [`SessionStore`] trait, keyed by session ID
+//! - [`InMemorySessionStore`], with idle expiry and a size bound
+//! - The session a call belongs to, from its `session-id` metadata
+//!
+//! Stateful services (chat, multi-step agents) resume a conversation from
+//! the session ID the caller sends with every call:
+//!
+//! ```ignore
+//! let ctx = RequestContext::current().unwrap_or_default();
+//! let id = session_id(&ctx).map(str::to_string).unwrap_or_else(new_session_id);
+//! let history = store.update(&id, |history: &mut Vec<String>| history.push(prompt));
+//! ```
+
+use crate::context::RequestContext;
+use rand::RngCore;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Metadata key carrying the session a call belongs to
+pub const SESSION_METADATA_KEY: &str = "session-id";
+
+/// Default number of sessions an [`InMemorySessionStore`] keeps
+pub const DEFAULT_MAX_SESSIONS: usize = 10_000;
+
+/// Storage for per-session state of type `T`
+pub trait SessionStore<T>: Send + Sync {
+    /// State of session `id`, if it exists
+    fn load(&self, id: &str) -> Option<T>;
+
+    /// Store the state of session `id`, replacing earlier state
+    fn save(&self, id: &str, session: T);
+
+    /// Delete session `id`, returning its state
+    fn remove(&self, id: &str) -> Option<T>;
+
+    /// Apply `f` to the state of session `id`, creating it if needed
+    ///
+    /// Returns the updated state. The default loads and saves; stores that
+    /// can should make it atomic.
+    fn update(&self, id: &str, f: &mut dyn FnMut(&mut T)) -> T
+    where
+        T: Default + Clone,
+    {
+        let mut session = self.load(id).unwrap_or_default();
+        f(&mut session);
+        self.save(id, session.clone());
+        session
+    }
+}
+
+/// Session a call belongs to, from its `session-id` metadata
+pub fn session_id(context: &RequestContext) -> Option<&str> {
+    context.metadata().get(SESSION_METADATA_KEY).filter(|id| !id.is_empty())
+}
+
+/// Random 128-bit session ID, hex-encoded
+pub fn new_session_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+struct Entry<T> {
+    session: T,
+    touched: Instant,
+}
+
+/// Session store kept in process memory
+///
+/// Sessions idle for longer than the TTL are dropped when next looked up
+/// or by [`purge_expired`](Self::purge_expired). Once full, saving a new
+/// session evicts the one idle the longest.
+pub struct InMemorySessionStore<T> {
+    entries: Mutex<HashMap<String, Entry<T>>>,
+    ttl: Option<Duration>,
+    max_sessions: usize,
+}
+
+impl<T> InMemorySessionStore<T> {
+    /// Create a store without expiry, holding up to [`DEFAULT_MAX_SESSIONS`]
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl: None,
+            max_sessions: DEFAULT_MAX_SESSIONS,
+        }
+    }
+
+    /// Drop sessions idle for longer than `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Keep at most `max_sessions` sessions
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions.max(1);
+        self
+    }
+
+    /// Number of sessions held, including expired ones not yet purged
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no session is held
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop expired sessions, returning how many were dropped
+    pub fn purge_expired(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|_, entry| !self.expired(entry));
+        before - entries.len()
+    }
+
+    fn expired(&self, entry: &Entry<T>) -> bool {
+        self.ttl.is_some_and(|ttl| entry.touched.elapsed() > ttl)
+    }
+
+    /// Make room for a new session
+    fn evict(&self, entries: &mut HashMap<String, Entry<T>>) {
+        entries.retain(|_, entry| !self.expired(entry));
+        while entries.len() >= self.max_sessions {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.touched)
+                .map(|(id, _)| id.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+    }
+}
+
+impl<T> Default for InMemorySessionStore<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Send> SessionStore<T> for InMemorySessionStore<T> {
+    fn load(&self, id: &str) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(id)?;
+        if self.expired(entry) {
+            entries.remove(id);
+            return None;
+        }
+        entry.touched = Instant::now();
+        Some(entry.session.clone())
+    }
+
+    fn save(&self, id: &str, session: T) {
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(id) {
+            self.evict(&mut entries);
+        }
+        entries.insert(id.to_string(), Entry { session, touched: Instant::now() });
+    }
+
+    fn remove(&self, id: &str) -> Option<T> {
+        self.entries.lock().unwrap().remove(id).map(|entry| entry.session)
+    }
+
+    fn update(&self, id: &str, f: &mut dyn FnMut(&mut T)) -> T
+    where
+        T: Default + Clone,
+    {
+        let mut entries = self.entries.lock().unwrap();
+        let live = entries.get(id).is_some_and(|entry| !self.expired(entry));
+        if !live {
+            entries.remove(id);
+            self.evict(&mut entries);
+        }
+        let entry = entries.entry(id.to_string()).or_insert_with(|| Entry {
+            session: T::default(),
+            touched: Instant::now(),
+        });
+        f(&mut entry.session);
+        entry.touched = Instant::now();
+        entry.session.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quill_core::Metadata;
+
+    #[test]
+    fn test_update_creates_and_appends() {
+        let store = InMemorySessionStore::<Vec<String>>::new();
+        assert!(store.load("s1").is_none());
+
+        store.update("s1", &mut |history| history.push("hi".to_string()));
+        let history = store.update("s1", &mut |history| history.push("again".to_string()));
+        assert_eq!(history, vec!["hi", "again"]);
+        assert_eq!(store.load("s1").unwrap().len(), 2);
+
+        assert_eq!(store.remove("s1").unwrap().len(), 2);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_full_store_evicts_the_oldest() {
+        let store = InMemorySessionStore::new().with_max_sessions(2);
+        store.save("a", 1);
+        std::thread::sleep(Duration::from_millis(2));
+        store.save("b", 2);
+        std::thread::sleep(Duration::from_millis(2));
+        // Reading "a" makes "b" the session idle the longest
+        store.load("a");
+        store.save("c", 3);
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.load("a"), Some(1));
+        assert_eq!(store.load("b"), None);
+        assert_eq!(store.load("c"), Some(3));
+    }
+
+    #[test]
+    fn test_idle_sessions_expire() {
+        let store = InMemorySessionStore::new().with_ttl(Duration::from_millis(20));
+        store.save("a", 1);
+        store.save("b", 2);
+        std::thread::sleep(Duration::from_millis(40));
+
+        assert_eq!(store.load("a"), None);
+        assert_eq!(store.purge_expired(), 1);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_session_id_from_metadata() {
+        let metadata = Metadata::new().with(SESSION_METADATA_KEY, "abc").unwrap();
+        let ctx = RequestContext::new("chat.v1.Chat/Send", None).with_metadata(metadata);
+        assert_eq!(session_id(&ctx), Some("abc"));
+        assert_eq!(session_id(&RequestContext::default()), None);
+
+        let id = new_session_id();
+        assert_eq!(id.len(), 32);
+        assert_ne!(id, new_session_id());
+    }
+}
@@ -0,0 +1,521 @@
+//! Slow-consumer detection for streaming responses
+//!
+//! This module provides:
+//! - Per-stream tracking of unsent buffered bytes and consumer stall time
+//! - A configurable policy for slow consumers: warn, degrade or cancel
+//! - Shedding of droppable frames (DROPPABLE flag) before essential ones
+//! - Per-method lag statistics
+//!
+//! Streaming responses normally advance only as fast as the peer reads, so
+//! a slow consumer silently stalls the handler. With detection enabled, the
+//! handler's stream is drained into a bounded buffer by a background task,
+//! which makes the gap between what the handler produced and what the peer
+//! accepted observable and lets the server act on it.
+
+use crate::BoxFrameStream;
+use bytes::Bytes;
+use http::StatusCode;
+use quill_core::{Frame, ProblemDetails, QuillError};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio_stream::{Stream, StreamExt};
+
+/// Default unsent bytes after which a consumer counts as slow
+pub const DEFAULT_MAX_BUFFERED_BYTES: usize = 1024 * 1024;
+
+/// Default time without a read after which a consumer counts as slow
+pub const DEFAULT_MAX_STALL: Duration = Duration::from_secs(10);
+
+/// Decides whether a response message may be dropped when degrading
+pub type DroppableFn = Arc<dyn Fn(&Bytes) -> bool + Send + Sync>;
+
+/// Callback invoked when a stream is first detected as slow
+pub type SlowConsumerListener = Arc<dyn Fn(&SlowConsumerEvent) + Send + Sync>;
+
+/// What to do with a stream whose consumer falls behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowConsumerAction {
+    /// Report the stream and keep applying backpressure to the handler
+    Warn,
+    /// Drop optional messages while the consumer is behind
+    ///
+    /// Droppable messages already waiting are shed first to make room for
+    /// essential ones, then new droppable messages are skipped.
+    Degrade,
+    /// End the stream with a `503` Problem Details cancel frame
+    Cancel,
+}
+
+/// Slow-consumer detection configuration
+#[derive(Clone)]
+pub struct SlowConsumerConfig {
+    /// Unsent bytes after which a consumer counts as slow (also the buffer limit)
+    pub max_buffered_bytes: usize,
+    /// Time without a read, while messages wait, after which a consumer counts as slow
+    pub max_stall: Duration,
+    /// What to do with slow consumers
+    pub action: SlowConsumerAction,
+    droppable: Option<DroppableFn>,
+    listener: Option<SlowConsumerListener>,
+}
+
+impl Default for SlowConsumerConfig {
+    fn default() -> Self {
+        Self {
+            max_buffered_bytes: DEFAULT_MAX_BUFFERED_BYTES,
+            max_stall: DEFAULT_MAX_STALL,
+            action: SlowConsumerAction::Warn,
+            droppable: None,
+            listener: None,
+        }
+    }
+}
+
+impl SlowConsumerConfig {
+    /// Warn about slow consumers with the default thresholds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the unsent bytes after which a consumer counts as slow
+    pub fn max_buffered_bytes(mut self, bytes: usize) -> Self {
+        self.max_buffered_bytes = bytes;
+        self
+    }
+
+    /// Set the stall time after which a consumer counts as slow
+    pub fn max_stall(mut self, stall: Duration) -> Self {
+        self.max_stall = stall;
+        self
+    }
+
+    /// Set what to do with slow consumers
+    pub fn action(mut self, action: SlowConsumerAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Mark additional messages as optional for [`SlowConsumerAction::Degrade`]
+    ///
+    /// Frames with the DROPPABLE flag are always optional; the classifier
+    /// covers handlers that send plain data frames.
+    pub fn droppable<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Bytes) -> bool + Send + Sync + 'static,
+    {
+        self.droppable = Some(Arc::new(f));
+        self
+    }
+
+    /// Register a listener for slow-consumer events
+    pub fn on_slow_consumer<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&SlowConsumerEvent) + Send + Sync + 'static,
+    {
+        self.listener = Some(Arc::new(f));
+        self
+    }
+}
+
+impl fmt::Debug for SlowConsumerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlowConsumerConfig")
+            .field("max_buffered_bytes", &self.max_buffered_bytes)
+            .field("max_stall", &self.max_stall)
+            .field("action", &self.action)
+            .field("droppable", &self.droppable.is_some())
+            .finish()
+    }
+}
+
+/// A stream whose consumer fell behind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowConsumerEvent {
+    /// Method path (e.g. `llm.v1.Generate/Stream`)
+    pub method: String,
+    /// Bytes produced but not yet accepted by the peer
+    pub buffered_bytes: usize,
+    /// Time since the peer last accepted a message
+    pub stalled_for: Duration,
+    /// Action applied to the stream
+    pub action: SlowConsumerAction,
+}
+
+/// Lag statistics of one method's streaming responses
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LagStats {
+    /// Streams watched
+    pub streams: u64,
+    /// Streams whose consumer fell behind
+    pub slow_streams: u64,
+    /// Optional messages dropped while degrading
+    pub dropped_messages: u64,
+    /// Streams cancelled for a slow consumer
+    pub cancelled_streams: u64,
+    /// Largest number of unsent bytes seen
+    pub max_buffered_bytes: usize,
+    /// Longest stall seen
+    pub max_stall: Duration,
+}
+
+/// Watches streaming responses and applies the slow-consumer policy
+pub(crate) struct SlowConsumerDetector {
+    config: SlowConsumerConfig,
+    stats: Mutex<HashMap<String, LagStats>>,
+}
+
+impl SlowConsumerDetector {
+    pub(crate) fn new(config: SlowConsumerConfig) -> Self {
+        Self {
+            config,
+            stats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Lag statistics per method
+    pub(crate) fn stats(&self) -> HashMap<String, LagStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Drain `frames` in the background, applying the policy as the consumer lags
+    pub(crate) fn watch(self: &Arc<Self>, method: &str, frames: BoxFrameStream) -> BoxFrameStream {
+        self.update(method, |stats| stats.streams += 1);
+        let shared = Arc::new(Shared::default());
+        let pump = tokio::spawn(Arc::clone(self).pump(method.to_string(), frames, Arc::clone(&shared)));
+        Box::pin(WatchedStream { shared, pump })
+    }
+
+    fn update(&self, method: &str, f: impl FnOnce(&mut LagStats)) {
+        f(self.stats.lock().unwrap().entry(method.to_string()).or_default());
+    }
+
+    async fn pump(
+        self: Arc<Self>,
+        method: String,
+        mut frames: BoxFrameStream,
+        shared: Arc<Shared>,
+    ) {
+        let mut reported = false;
+        'frames: while let Some(item) = frames.next().await {
+            let end = item.is_err();
+            loop {
+                let (buffered, stalled) = shared.lag();
+                self.update(&method, |stats| {
+                    stats.max_buffered_bytes = stats.max_buffered_bytes.max(buffered);
+                    stats.max_stall = stats.max_stall.max(stalled);
+                });
+                let full = buffered >= self.config.max_buffered_bytes;
+                if !full && stalled < self.config.max_stall {
+                    break;
+                }
+
+                if !reported {
+                    reported = true;
+                    self.report(&method, buffered, stalled);
+                }
+                match self.config.action {
+                    SlowConsumerAction::Cancel => {
+                        self.update(&method, |stats| stats.cancelled_streams += 1);
+                        shared.cancel(&method);
+                        return;
+                    }
+                    SlowConsumerAction::Degrade if self.is_droppable(&item) => {
+                        self.update(&method, |stats| stats.dropped_messages += 1);
+                        continue 'frames;
+                    }
+                    SlowConsumerAction::Degrade if full => {
+                        // Make room for an essential message by shedding optional ones
+                        let shed = shared.shed(|item| self.is_droppable(item));
+                        if shed > 0 {
+                            self.update(&method, |stats| stats.dropped_messages += shed);
+                            continue;
+                        }
+                    }
+                    _ => {}
+                }
+                if !full {
+                    break;
+                }
+
+                // Wait for the consumer to make room, re-checking the stall deadline
+                let wait = self.config.max_stall.saturating_sub(stalled).max(Duration::from_millis(10));
+                tokio::select! {
+                    _ = shared.space.notified() => {}
+                    _ = tokio::time::sleep(wait) => {}
+                }
+            }
+
+            shared.push(item);
+            if end {
+                break;
+            }
+        }
+        shared.finish();
+    }
+
+    fn is_droppable(&self, item: &Result<Frame, QuillError>) -> bool {
+        let Ok(frame) = item else {
+            return false;
+        };
+        frame.flags.is_data()
+            && (frame.flags.is_droppable()
+                || self.config.droppable.as_ref().is_some_and(|droppable| droppable(&frame.payload)))
+    }
+
+    fn report(&self, method: &str, buffered_bytes: usize, stalled_for: Duration) {
+        self.update(method, |stats| stats.slow_streams += 1);
+        tracing::warn!(
+            "Slow consumer on {}: {} bytes unsent, stalled for {:?} ({:?})",
+            method,
+            buffered_bytes,
+            stalled_for,
+            self.config.action
+        );
+        if let Some(listener) = &self.config.listener {
+            listener(&SlowConsumerEvent {
+                method: method.to_string(),
+                buffered_bytes,
+                stalled_for,
+                action: self.config.action,
+            });
+        }
+    }
+}
+
+/// Frames produced by the handler and not yet taken by the consumer
+struct Buffer {
+    items: VecDeque<Result<Frame, QuillError>>,
+    bytes: usize,
+    /// When the oldest waiting message started waiting for the consumer
+    waiting_since: Instant,
+    done: bool,
+    consumer: Option<Waker>,
+}
+
+struct Shared {
+    buffer: Mutex<Buffer>,
+    space: Notify,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Self {
+            buffer: Mutex::new(Buffer {
+                items: VecDeque::new(),
+                bytes: 0,
+                waiting_since: Instant::now(),
+                done: false,
+                consumer: None,
+            }),
+            space: Notify::new(),
+        }
+    }
+}
+
+impl Shared {
+    /// Unsent bytes and how long the consumer has left messages waiting
+    fn lag(&self) -> (usize, Duration) {
+        let buffer = self.buffer.lock().unwrap();
+        let stalled = if buffer.items.is_empty() {
+            Duration::ZERO
+        } else {
+            buffer.waiting_since.elapsed()
+        };
+        (buffer.bytes, stalled)
+    }
+
+    fn push(&self, item: Result<Frame, QuillError>) {
+        let mut buffer = self.buffer.lock().unwrap();
+        if buffer.items.is_empty() {
+            buffer.waiting_since = Instant::now();
+        }
+        buffer.bytes += item.as_ref().map(|frame| frame.payload.len()).unwrap_or(0);
+        buffer.items.push_back(item);
+        if let Some(waker) = buffer.consumer.take() {
+            waker.wake();
+        }
+    }
+
+    /// Remove unsent messages matching `droppable`, returning how many were removed
+    fn shed(&self, droppable: impl Fn(&Result<Frame, QuillError>) -> bool) -> u64 {
+        let mut buffer = self.buffer.lock().unwrap();
+        let before = buffer.items.len();
+        buffer.items.retain(|item| !droppable(item));
+        buffer.bytes = buffer
+            .items
+            .iter()
+            .map(|item| item.as_ref().map(|frame| frame.payload.len()).unwrap_or(0))
+            .sum();
+        (before - buffer.items.len()) as u64
+    }
+
+    /// Replace unsent messages with a cancel frame and end the stream
+    fn cancel(&self, method: &str) {
+        let problem = ProblemDetails::new(StatusCode::SERVICE_UNAVAILABLE, "Slow consumer")
+            .with_detail(format!("Stream of {} cancelled because the consumer fell behind", method));
+        {
+            let mut buffer = self.buffer.lock().unwrap();
+            buffer.items.clear();
+            buffer.bytes = 0;
+        }
+        self.push(Ok(Frame::cancel_with_problem(&problem)));
+        self.finish();
+    }
+
+    fn finish(&self) {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.done = true;
+        if let Some(waker) = buffer.consumer.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Consumer side of a watched stream
+struct WatchedStream {
+    shared: Arc<Shared>,
+    pump: JoinHandle<()>,
+}
+
+impl Stream for WatchedStream {
+    type Item = Result<Frame, QuillError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut buffer = self.shared.buffer.lock().unwrap();
+        if let Some(item) = buffer.items.pop_front() {
+            buffer.bytes -= item.as_ref().map(|frame| frame.payload.len()).unwrap_or(0);
+            buffer.waiting_since = Instant::now();
+            drop(buffer);
+            self.shared.space.notify_one();
+            return Poll::Ready(Some(item));
+        }
+        if buffer.done {
+            return Poll::Ready(None);
+        }
+        buffer.consumer = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for WatchedStream {
+    fn drop(&mut self) {
+        // The peer went away; stop running the handler
+        self.pump.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(count: usize, size: usize) -> BoxFrameStream {
+        let items: Vec<Result<Frame, QuillError>> = (0..count)
+            .map(|i| Frame::data(Bytes::from(vec![i as u8; size])))
+            .chain(std::iter::once(Frame::end_stream()))
+            .map(Ok)
+            .collect();
+        Box::pin(tokio_stream::iter(items))
+    }
+
+    async fn collect(mut stream: BoxFrameStream) -> Vec<Frame> {
+        let mut out = Vec::new();
+        while let Some(item) = stream.next().await {
+            out.push(item.unwrap());
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn test_fast_consumer_sees_everything() {
+        let detector = Arc::new(SlowConsumerDetector::new(SlowConsumerConfig::new()));
+        let received = collect(detector.watch("svc/Stream", frames(20, 100))).await;
+
+        assert_eq!(received.len(), 21);
+        assert!(received.last().unwrap().flags.is_end_stream());
+        let stats = &detector.stats()["svc/Stream"];
+        assert_eq!(stats.streams, 1);
+        assert_eq!(stats.slow_streams, 0);
+    }
+
+    #[tokio::test]
+    async fn test_degrade_drops_optional_messages() {
+        let config = SlowConsumerConfig::new()
+            .max_buffered_bytes(250)
+            .action(SlowConsumerAction::Degrade)
+            .droppable(|payload| payload[0] % 2 == 1);
+        let detector = Arc::new(SlowConsumerDetector::new(config));
+        let stream = detector.watch("svc/Stream", frames(10, 100));
+
+        // Let the producer run ahead of a consumer that has not started reading
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let received = collect(stream).await;
+
+        let data: Vec<u8> = received.iter().filter(|f| f.flags.is_data()).map(|f| f.payload[0]).collect();
+        assert!(!data.contains(&3), "optional message sent while behind: {:?}", data);
+        assert_eq!(data.iter().filter(|i| *i % 2 == 0).count(), 5);
+        let stats = &detector.stats()["svc/Stream"];
+        assert_eq!(stats.slow_streams, 1);
+        assert!(stats.dropped_messages > 0);
+    }
+
+    #[tokio::test]
+    async fn test_degrade_sheds_droppable_frames_first() {
+        let config = SlowConsumerConfig::new()
+            .max_buffered_bytes(250)
+            .action(SlowConsumerAction::Degrade);
+        let detector = Arc::new(SlowConsumerDetector::new(config));
+        // Token text on even indices, auxiliary data (e.g. logprobs) on odd ones
+        let items: Vec<Result<Frame, QuillError>> = (0..10u8)
+            .map(|i| match i % 2 {
+                0 => Frame::data(Bytes::from(vec![i; 100])),
+                _ => Frame::droppable(Bytes::from(vec![i; 100])),
+            })
+            .chain(std::iter::once(Frame::end_stream()))
+            .map(Ok)
+            .collect();
+        let stream = detector.watch("svc/Stream", Box::pin(tokio_stream::iter(items)));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let received = collect(stream).await;
+
+        let data: Vec<u8> = received.iter().filter(|f| f.flags.is_data()).map(|f| f.payload[0]).collect();
+        // Frame 1 was already buffered and got shed to make room for token text
+        assert!(!data.contains(&1) && !data.contains(&3), "droppable frames sent while behind: {:?}", data);
+        assert_eq!(data.iter().filter(|i| *i % 2 == 0).count(), 5);
+        assert!(detector.stats()["svc/Stream"].dropped_messages >= 3);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stalled_consumer() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let config = SlowConsumerConfig::new()
+            .max_stall(Duration::from_millis(20))
+            .action(SlowConsumerAction::Cancel)
+            .on_slow_consumer(move |event| recorded.lock().unwrap().push(event.clone()));
+        let detector = Arc::new(SlowConsumerDetector::new(config));
+        // A handler that keeps producing while the consumer does not read
+        let ticks = frames(1000, 10).then(|item| async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            item
+        });
+        let stream = detector.watch("svc/Stream", Box::pin(ticks));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let received = collect(stream).await;
+
+        assert_eq!(received.len(), 1);
+        match received[0].cancel_error() {
+            QuillError::ProblemDetails(pd) => assert_eq!(pd.status, 503),
+            other => panic!("expected problem details, got {:?}", other),
+        }
+        assert_eq!(events.lock().unwrap()[0].action, SlowConsumerAction::Cancel);
+        assert_eq!(detector.stats()["svc/Stream"].cancelled_streams, 1);
+    }
+}
@@ -0,0 +1,658 @@
+//! Idle stream collection and leak detection
+//!
+//! This module provides:
+//! - A registry of the server's active response streams and request parsers
+//! - A periodic scan flagging streams with no progress as suspected leaks
+//! - Cancellation of streams making no progress past a configured threshold
+//! - A built-in method listing suspected leaks ([`STREAM_GC_LEAKS_PATH`])
+//!
+//! Unlike [`crate::idle_timeout`], which arms a timer per stream, streams
+//! here are only counted and timestamped as they make progress; a single
+//! background task walks the registry every
+//! [`scan_interval`](StreamGcConfig::scan_interval). It is meant as a
+//! safety net against handler bugs that never terminate a stream, so its
+//! thresholds are typically far longer than any legitimate pause. A
+//! cancelled response ends with a `408` cancel frame of type
+//! [`STREAM_IDLE_TIMEOUT_TYPE`], and a cancelled request stream fails with
+//! the same problem.
+//!
+//! [`STREAM_IDLE_TIMEOUT_TYPE`]: quill_core::STREAM_IDLE_TIMEOUT_TYPE
+
+use crate::idle_timeout::StreamSide;
+use crate::router::RequestStream;
+use crate::BoxFrameStream;
+use bytes::Bytes;
+use quill_core::{Frame, ProblemDetails, QuillError};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
+
+/// Service of the built-in leak listing method
+pub const STREAM_GC_SERVICE: &str = "quill.streams.v1.StreamGc";
+
+/// Method listing suspected stream leaks
+pub const STREAM_GC_LEAKS_METHOD: &str = "Leaks";
+
+/// Path of the built-in leak listing method, without a leading slash
+pub const STREAM_GC_LEAKS_PATH: &str = "quill.streams.v1.StreamGc/Leaks";
+
+/// Default time between scans
+pub const DEFAULT_SCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default time without progress after which a stream is a suspected leak
+pub const DEFAULT_SUSPECT_AFTER: Duration = Duration::from_secs(60);
+
+/// Default time without progress after which a stream is cancelled
+pub const DEFAULT_CANCEL_AFTER: Duration = Duration::from_secs(600);
+
+/// Callback invoked when a stream is cancelled
+pub type StreamGcListener = Arc<dyn Fn(&StreamLeak) + Send + Sync>;
+
+/// Stream garbage collection configuration
+#[derive(Clone)]
+pub struct StreamGcConfig {
+    /// Time between scans of the active streams
+    pub scan_interval: Duration,
+    /// Time without progress after which a stream is listed as a suspected leak
+    pub suspect_after: Duration,
+    /// Time without progress after which a stream is cancelled; zero only reports
+    pub cancel_after: Duration,
+    listener: Option<StreamGcListener>,
+}
+
+impl Default for StreamGcConfig {
+    fn default() -> Self {
+        Self {
+            scan_interval: DEFAULT_SCAN_INTERVAL,
+            suspect_after: DEFAULT_SUSPECT_AFTER,
+            cancel_after: DEFAULT_CANCEL_AFTER,
+            listener: None,
+        }
+    }
+}
+
+impl StreamGcConfig {
+    /// Default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the time between scans
+    pub fn scan_interval(mut self, interval: Duration) -> Self {
+        self.scan_interval = interval;
+        self
+    }
+
+    /// Set the time without progress after which a stream is a suspected leak
+    pub fn suspect_after(mut self, after: Duration) -> Self {
+        self.suspect_after = after;
+        self
+    }
+
+    /// Set the time without progress after which a stream is cancelled
+    ///
+    /// A zero duration disables cancellation; suspected leaks are still listed.
+    pub fn cancel_after(mut self, after: Duration) -> Self {
+        self.cancel_after = after;
+        self
+    }
+
+    /// Register a listener for cancelled streams
+    pub fn on_cancel<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&StreamLeak) + Send + Sync + 'static,
+    {
+        self.listener = Some(Arc::new(f));
+        self
+    }
+}
+
+impl fmt::Debug for StreamGcConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamGcConfig")
+            .field("scan_interval", &self.scan_interval)
+            .field("suspect_after", &self.suspect_after)
+            .field("cancel_after", &self.cancel_after)
+            .finish()
+    }
+}
+
+/// A stream without progress, as listed by [`STREAM_GC_LEAKS_PATH`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StreamLeak {
+    /// Registry id, unique for the router's lifetime
+    pub id: u64,
+    /// Method path (e.g. `llm.v1.Generate/Stream`)
+    pub method: String,
+    /// Half of the call the stream carries
+    pub side: StreamSide,
+    /// Creation time in milliseconds since the Unix epoch
+    pub created_at_ms: u64,
+    /// Time since creation, in milliseconds
+    pub age_ms: u64,
+    /// Payload bytes passed through so far
+    pub bytes: u64,
+    /// Frames (responses) or messages (requests) passed through so far
+    pub frames: u64,
+    /// Time of the last progress in milliseconds since the Unix epoch
+    pub last_activity_ms: u64,
+    /// Time since the last progress, in milliseconds
+    pub idle_ms: u64,
+}
+
+/// Stream garbage collection counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamGcStats {
+    /// Streams currently tracked
+    pub active: usize,
+    /// Streams currently without progress past the suspect threshold
+    pub suspected: usize,
+    /// Streams ever flagged as suspected leaks
+    pub suspected_total: u64,
+    /// Streams ever cancelled for lack of progress
+    pub cancelled_total: u64,
+}
+
+/// Registry of the active streams of a router
+pub(crate) struct StreamGc {
+    config: StreamGcConfig,
+    streams: Mutex<HashMap<u64, Arc<dyn GcEntry>>>,
+    next_id: AtomicU64,
+    suspected_total: AtomicU64,
+    cancelled_total: AtomicU64,
+    scanner: OnceLock<JoinHandle<()>>,
+}
+
+impl StreamGc {
+    pub(crate) fn new(config: StreamGcConfig) -> Self {
+        Self {
+            config,
+            streams: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            suspected_total: AtomicU64::new(0),
+            cancelled_total: AtomicU64::new(0),
+            scanner: OnceLock::new(),
+        }
+    }
+
+    /// Track a response stream
+    pub(crate) fn track_response(
+        self: &Arc<Self>,
+        method: &str,
+        frames: BoxFrameStream,
+    ) -> BoxFrameStream {
+        Box::pin(self.track(method, StreamSide::Response, frames))
+    }
+
+    /// Track a request stream
+    pub(crate) fn track_request(self: &Arc<Self>, method: &str, requests: RequestStream) -> RequestStream {
+        Box::pin(self.track(method, StreamSide::Request, requests))
+    }
+
+    fn track<S>(self: &Arc<Self>, method: &str, side: StreamSide, inner: S) -> TrackedStream<S>
+    where
+        S: Stream + Unpin + Send + 'static,
+        S::Item: GcItem,
+    {
+        self.start_scanner();
+        let now = Instant::now();
+        let state = Arc::new(Tracked {
+            method: method.to_string(),
+            side,
+            created_at: now,
+            created_at_ms: now_ms(),
+            state: Mutex::new(TrackedState {
+                inner: Some(inner),
+                bytes: 0,
+                frames: 0,
+                last_activity: now,
+                suspected: false,
+                cancelled: None,
+                consumer: None,
+            }),
+        });
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.streams.lock().unwrap().insert(id, Arc::clone(&state) as Arc<dyn GcEntry>);
+        TrackedStream {
+            gc: Arc::downgrade(self),
+            id,
+            state,
+            done: false,
+        }
+    }
+
+    /// Spawn the periodic scan on first use, when a runtime is available
+    fn start_scanner(self: &Arc<Self>) {
+        if self.scanner.get().is_some() || self.config.scan_interval.is_zero() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let gc = Arc::downgrade(self);
+        let interval = self.config.scan_interval;
+        // Losing a race spawns a scanner that exits with the registry
+        let _ = self.scanner.set(runtime.spawn(scan_loop(gc, interval)));
+    }
+
+    /// Flag and cancel streams without progress
+    pub(crate) fn scan(&self) {
+        let entries: Vec<(u64, Arc<dyn GcEntry>)> = self
+            .streams
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, entry)| (*id, Arc::clone(entry)))
+            .collect();
+        let cancel_after = self.config.cancel_after;
+        for (id, entry) in entries {
+            let leak = entry.snapshot(id);
+            let idle = Duration::from_millis(leak.idle_ms);
+            if !cancel_after.is_zero() && idle >= cancel_after {
+                if entry.cancel(cancel_after) {
+                    self.streams.lock().unwrap().remove(&id);
+                    self.cancelled_total.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!(
+                        "Stream {} of {} made no progress for {:?} ({:?}), cancelling",
+                        id,
+                        leak.method,
+                        idle,
+                        leak.side
+                    );
+                    if let Some(listener) = &self.config.listener {
+                        listener(&leak);
+                    }
+                }
+            } else if idle >= self.config.suspect_after && entry.mark_suspected() {
+                self.suspected_total.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    "Stream {} of {} made no progress for {:?} ({:?}), possible leak",
+                    id,
+                    leak.method,
+                    idle,
+                    leak.side
+                );
+            }
+        }
+    }
+
+    /// Streams currently without progress past the suspect threshold, oldest first
+    pub(crate) fn leaks(&self) -> Vec<StreamLeak> {
+        let mut leaks: Vec<StreamLeak> = self
+            .streams
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, entry)| entry.snapshot(*id))
+            .filter(|leak| Duration::from_millis(leak.idle_ms) >= self.config.suspect_after)
+            .collect();
+        leaks.sort_by_key(|leak| (leak.created_at_ms, leak.id));
+        leaks
+    }
+
+    pub(crate) fn stats(&self) -> StreamGcStats {
+        let suspected = self.leaks().len();
+        let active = self.streams.lock().unwrap().len();
+        StreamGcStats {
+            active,
+            suspected,
+            suspected_total: self.suspected_total.load(Ordering::Relaxed),
+            cancelled_total: self.cancelled_total.load(Ordering::Relaxed),
+        }
+    }
+
+    /// JSON body of the leak listing method
+    pub(crate) fn leaks_json(&self) -> Bytes {
+        let stats = self.stats();
+        let json = serde_json::to_vec(&Leaks {
+            active: stats.active,
+            suspected_total: stats.suspected_total,
+            cancelled_total: stats.cancelled_total,
+            leaks: self.leaks(),
+        })
+        .unwrap_or_default();
+        Bytes::from(json)
+    }
+
+    fn untrack(&self, id: u64) {
+        self.streams.lock().unwrap().remove(&id);
+    }
+}
+
+impl Drop for StreamGc {
+    fn drop(&mut self) {
+        if let Some(scanner) = self.scanner.get() {
+            scanner.abort();
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Leaks {
+    active: usize,
+    suspected_total: u64,
+    cancelled_total: u64,
+    leaks: Vec<StreamLeak>,
+}
+
+async fn scan_loop(gc: Weak<StreamGc>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let Some(gc) = gc.upgrade() else {
+            return;
+        };
+        gc.scan();
+    }
+}
+
+/// Item of a tracked stream
+trait GcItem: Send {
+    /// Payload bytes carried by the item
+    fn size(&self) -> usize;
+    /// Whether nothing follows the item
+    fn is_terminal(&self) -> bool;
+    /// Item delivered in place of the stream once it is cancelled
+    fn cancelled(after: Duration) -> Self;
+}
+
+impl GcItem for Result<Frame, QuillError> {
+    fn size(&self) -> usize {
+        self.as_ref().map_or(0, |frame| frame.payload.len())
+    }
+
+    fn is_terminal(&self) -> bool {
+        match self {
+            Ok(frame) => frame.flags.is_end_stream() || frame.flags.is_cancel(),
+            Err(_) => true,
+        }
+    }
+
+    fn cancelled(after: Duration) -> Self {
+        Ok(Frame::cancel_with_problem(&ProblemDetails::stream_idle_timeout(after)))
+    }
+}
+
+impl GcItem for Result<Bytes, QuillError> {
+    fn size(&self) -> usize {
+        self.as_ref().map_or(0, |message| message.len())
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.is_err()
+    }
+
+    fn cancelled(after: Duration) -> Self {
+        Err(QuillError::ProblemDetails(ProblemDetails::stream_idle_timeout(after)))
+    }
+}
+
+/// Registry view of a tracked stream, independent of its item type
+trait GcEntry: Send + Sync {
+    fn snapshot(&self, id: u64) -> StreamLeak;
+    /// Mark the stream as suspected, returning whether it was not already
+    fn mark_suspected(&self) -> bool;
+    /// Cancel the stream, returning whether it was still running
+    fn cancel(&self, after: Duration) -> bool;
+}
+
+/// A tracked stream, shared between the registry and its consumer
+struct Tracked<S> {
+    method: String,
+    side: StreamSide,
+    created_at: Instant,
+    created_at_ms: u64,
+    state: Mutex<TrackedState<S>>,
+}
+
+struct TrackedState<S> {
+    /// The wrapped stream, taken on cancellation
+    inner: Option<S>,
+    bytes: u64,
+    frames: u64,
+    last_activity: Instant,
+    suspected: bool,
+    /// Threshold the stream was cancelled after, if it was
+    cancelled: Option<Duration>,
+    consumer: Option<Waker>,
+}
+
+impl<S: Send> GcEntry for Tracked<S> {
+    fn snapshot(&self, id: u64) -> StreamLeak {
+        let state = self.state.lock().unwrap();
+        let since_created = state.last_activity.duration_since(self.created_at);
+        StreamLeak {
+            id,
+            method: self.method.clone(),
+            side: self.side,
+            created_at_ms: self.created_at_ms,
+            age_ms: self.created_at.elapsed().as_millis() as u64,
+            bytes: state.bytes,
+            frames: state.frames,
+            last_activity_ms: self.created_at_ms + since_created.as_millis() as u64,
+            idle_ms: state.last_activity.elapsed().as_millis() as u64,
+        }
+    }
+
+    fn mark_suspected(&self) -> bool {
+        !std::mem::replace(&mut self.state.lock().unwrap().suspected, true)
+    }
+
+    fn cancel(&self, after: Duration) -> bool {
+        let inner = {
+            let mut state = self.state.lock().unwrap();
+            if state.cancelled.is_some() || state.inner.is_none() {
+                return false;
+            }
+            state.cancelled = Some(after);
+            if let Some(waker) = state.consumer.take() {
+                waker.wake();
+            }
+            state.inner.take()
+        };
+        // Dropped outside the lock: this stops the handler or releases the body
+        drop(inner);
+        true
+    }
+}
+
+/// Consumer side of a tracked stream
+struct TrackedStream<S> {
+    gc: Weak<StreamGc>,
+    id: u64,
+    state: Arc<Tracked<S>>,
+    done: bool,
+}
+
+impl<S> TrackedStream<S> {
+    fn finish(&mut self) {
+        self.done = true;
+        if let Some(gc) = self.gc.upgrade() {
+            gc.untrack(self.id);
+        }
+    }
+}
+
+impl<S> Stream for TrackedStream<S>
+where
+    S: Stream + Unpin,
+    S::Item: GcItem,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let tracked = Arc::clone(&self.state);
+        let mut state = tracked.state.lock().unwrap();
+        if let Some(after) = state.cancelled {
+            drop(state);
+            self.finish();
+            return Poll::Ready(Some(S::Item::cancelled(after)));
+        }
+        let Some(inner) = state.inner.as_mut() else {
+            drop(state);
+            self.finish();
+            return Poll::Ready(None);
+        };
+        match Pin::new(inner).poll_next(cx) {
+            Poll::Ready(Some(item)) => {
+                state.last_activity = Instant::now();
+                state.bytes += item.size() as u64;
+                state.frames += 1;
+                state.suspected = false;
+                let terminal = item.is_terminal();
+                drop(state);
+                if terminal {
+                    self.finish();
+                }
+                Poll::Ready(Some(item))
+            }
+            Poll::Ready(None) => {
+                drop(state);
+                self.finish();
+                Poll::Ready(None)
+            }
+            Poll::Pending => {
+                state.consumer = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<S> Drop for TrackedStream<S> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.finish();
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    fn gc(config: StreamGcConfig) -> Arc<StreamGc> {
+        Arc::new(StreamGc::new(config))
+    }
+
+    fn stalled() -> BoxFrameStream {
+        Box::pin(
+            tokio_stream::iter(vec![Ok::<_, QuillError>(Frame::data(Bytes::from_static(b"hello")))])
+                .chain(tokio_stream::pending()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_finished_streams_are_untracked() {
+        let gc = gc(StreamGcConfig::new());
+        let frames: BoxFrameStream = Box::pin(tokio_stream::iter(vec![
+            Ok(Frame::data(Bytes::from_static(b"a"))),
+            Ok(Frame::end_stream()),
+        ]));
+        let mut stream = gc.track_response("svc/Stream", frames);
+        let abandoned = gc.track_response("svc/Stream", stalled());
+        assert_eq!(gc.stats().active, 2);
+
+        stream.next().await.unwrap().unwrap();
+        assert!(stream.next().await.unwrap().unwrap().flags.is_end_stream());
+        assert_eq!(gc.stats().active, 1);
+
+        drop(abandoned);
+        assert_eq!(gc.stats().active, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_is_listed_then_cancelled() {
+        let cancelled = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&cancelled);
+        let gc = gc(StreamGcConfig::new()
+            .scan_interval(Duration::ZERO)
+            .suspect_after(Duration::from_millis(30))
+            .cancel_after(Duration::from_millis(100))
+            .on_cancel(move |leak| recorded.lock().unwrap().push(leak.clone())));
+        let mut stream = gc.track_response("svc/Stream", stalled());
+        stream.next().await.unwrap().unwrap();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        gc.scan();
+        let leaks = gc.leaks();
+        assert_eq!(leaks.len(), 1);
+        assert_eq!(leaks[0].method, "svc/Stream");
+        assert_eq!(leaks[0].side, StreamSide::Response);
+        assert_eq!((leaks[0].bytes, leaks[0].frames), (5, 1));
+        assert!(leaks[0].idle_ms >= 30);
+        assert_eq!(gc.stats().suspected_total, 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        gc.scan();
+        assert_eq!(
+            gc.stats(),
+            StreamGcStats {
+                active: 0,
+                suspected: 0,
+                suspected_total: 1,
+                cancelled_total: 1,
+            }
+        );
+        assert_eq!(cancelled.lock().unwrap()[0].id, leaks[0].id);
+
+        let frame = stream.next().await.unwrap().unwrap();
+        assert!(frame.cancel_error().is_stream_idle_timeout());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scanner_cancels_stalled_request_stream() {
+        let gc = gc(StreamGcConfig::new()
+            .scan_interval(Duration::from_millis(20))
+            .suspect_after(Duration::from_millis(20))
+            .cancel_after(Duration::from_millis(50)));
+        let requests: RequestStream = Box::pin(tokio_stream::pending());
+        let mut stream = gc.track_request("svc/Upload", requests);
+
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(error.is_stream_idle_timeout());
+        assert!(stream.next().await.is_none());
+        assert_eq!(gc.stats().cancelled_total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_report_only_keeps_streams() {
+        let gc = gc(StreamGcConfig::new()
+            .scan_interval(Duration::ZERO)
+            .suspect_after(Duration::from_millis(10))
+            .cancel_after(Duration::ZERO));
+        let _stream = gc.track_response("svc/Stream", stalled());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        gc.scan();
+        gc.scan();
+        let stats = gc.stats();
+        assert_eq!((stats.active, stats.suspected), (1, 1));
+        assert_eq!((stats.suspected_total, stats.cancelled_total), (1, 0));
+
+        let json: serde_json::Value = serde_json::from_slice(&gc.leaks_json()).unwrap();
+        assert_eq!(json["leaks"][0]["side"], "response");
+        assert_eq!(json["leaks"][0]["method"], "svc/Stream");
+    }
+}
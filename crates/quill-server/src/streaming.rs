@@ -3,16 +3,26 @@
 use bytes::Bytes;
 use hyper::body::Frame as HyperFrame;
 use quill_core::{Frame, QuillError};
+use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::AbortHandle;
 use tokio_stream::Stream;
 
+/// Sending half of a channel-backed streaming response
+pub type ResponseSender = mpsc::Sender<Result<Bytes, QuillError>>;
+
 /// Response type that can be either unary or streaming
 pub enum RpcResponse {
     /// Unary response (single message)
     Unary(Bytes),
     /// Streaming response (multiple messages)
     Streaming(Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>),
+    /// Pre-framed streaming response; the stream supplies its own terminal frame
+    Framed(Pin<Box<dyn Stream<Item = Result<Frame, QuillError>> + Send>>),
 }
 
 impl RpcResponse {
@@ -28,6 +38,157 @@ impl RpcResponse {
     {
         Self::Streaming(Box::pin(stream))
     }
+
+    /// Create a pre-framed streaming response
+    pub fn framed<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Frame, QuillError>> + Send + 'static,
+    {
+        Self::Framed(Box::pin(stream))
+    }
+
+    /// Create a streaming response from messages pushed into a channel
+    ///
+    /// The response ends once every sender is dropped. When the response
+    /// stream is dropped first, e.g. because the client disconnected,
+    /// further sends fail; use [`from_channel_with_guard`](Self::from_channel_with_guard)
+    /// to also signal the producer.
+    pub fn from_channel(rx: mpsc::Receiver<Result<Bytes, QuillError>>) -> Self {
+        Self::streaming(ChannelStream { rx, guard: None })
+    }
+
+    /// Create a streaming response from a channel, firing `guard` if the stream is cancelled
+    ///
+    /// The guard fires when the response stream is dropped before the
+    /// channel is exhausted: on client disconnect, server-side cancellation
+    /// or a handler error.
+    pub fn from_channel_with_guard(rx: mpsc::Receiver<Result<Bytes, QuillError>>, guard: CancelGuard) -> Self {
+        Self::streaming(ChannelStream { rx, guard: Some(guard) })
+    }
+
+    /// Create a streaming response fed by a spawned producer task
+    ///
+    /// The producer receives the sending half of a channel holding
+    /// `buffer` messages and a [`CancelSignal`] that fires if the response
+    /// stream goes away before the producer is done.
+    pub fn spawn_producer<F, Fut>(buffer: usize, producer: F) -> Self
+    where
+        F: FnOnce(ResponseSender, CancelSignal) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(buffer.max(1));
+        let (guard, signal) = CancelGuard::new();
+        tokio::spawn(producer(tx, signal));
+        Self::from_channel_with_guard(rx, guard)
+    }
+}
+
+/// Fires a [`CancelSignal`] when dropped before the response stream completes
+pub struct CancelGuard {
+    state: Arc<CancelState>,
+    tasks: Vec<AbortHandle>,
+    completed: bool,
+}
+
+impl CancelGuard {
+    /// Create a guard and the signal it fires
+    pub fn new() -> (Self, CancelSignal) {
+        let state = Arc::new(CancelState::default());
+        let guard = Self {
+            state: Arc::clone(&state),
+            tasks: Vec::new(),
+            completed: false,
+        };
+        (guard, CancelSignal { state })
+    }
+
+    /// Also abort `task` when the guard fires
+    ///
+    /// For producers that cannot check the signal, e.g. while awaiting
+    /// upstream work.
+    pub fn abort_on_cancel<T>(mut self, task: &tokio::task::JoinHandle<T>) -> Self {
+        self.tasks.push(task.abort_handle());
+        self
+    }
+
+    /// Disarm the guard; the stream was consumed to the end
+    fn complete(&mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.notify.notify_waiters();
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// Tells a producer that its response stream was cancelled
+#[derive(Clone)]
+pub struct CancelSignal {
+    state: Arc<CancelState>,
+}
+
+impl CancelSignal {
+    /// Whether the response stream was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the response stream is cancelled
+    ///
+    /// Never completes if the stream is consumed to the end.
+    pub async fn cancelled(&self) {
+        let notified = self.state.notify.notified();
+        tokio::pin!(notified);
+        // Register before checking, so a cancel in between is not missed
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+impl std::fmt::Debug for CancelSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancelSignal")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Response stream reading from a channel
+struct ChannelStream {
+    rx: mpsc::Receiver<Result<Bytes, QuillError>>,
+    guard: Option<CancelGuard>,
+}
+
+impl Stream for ChannelStream {
+    type Item = Result<Bytes, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = self.rx.poll_recv(cx);
+        if let Poll::Ready(None) = poll {
+            if let Some(guard) = self.guard.as_mut() {
+                guard.complete();
+            }
+        }
+        poll
+    }
 }
 
 /// Stream adapter that wraps Quill frames in HTTP frames
@@ -101,4 +262,74 @@ mod tests {
 
         assert!(end.is_none());
     }
+
+    fn stream_of(response: RpcResponse) -> Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>> {
+        match response {
+            RpcResponse::Streaming(stream) => stream,
+            _ => panic!("expected a streaming response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_channel_consumed_to_end_does_not_cancel() {
+        use tokio_stream::StreamExt;
+
+        let (tx, rx) = mpsc::channel(4);
+        let (guard, signal) = CancelGuard::new();
+        tx.send(Ok(Bytes::from("a"))).await.unwrap();
+        drop(tx);
+
+        let messages: Vec<_> = stream_of(RpcResponse::from_channel_with_guard(rx, guard)).collect().await;
+        assert_eq!(messages.len(), 1);
+        assert!(!signal.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_stream_signals_producer() {
+        use tokio_stream::StreamExt;
+
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+        let response = RpcResponse::spawn_producer(1, |tx, signal| async move {
+            let mut sent = 0;
+            loop {
+                tokio::select! {
+                    _ = signal.cancelled() => break,
+                    result = tx.send(Ok(Bytes::from("tick"))) => {
+                        if result.is_err() {
+                            break;
+                        }
+                        sent += 1;
+                    }
+                }
+            }
+            let _ = done_tx.send((sent, signal.is_cancelled()));
+        });
+
+        let mut stream = stream_of(response);
+        stream.next().await.unwrap().unwrap();
+        stream.next().await.unwrap().unwrap();
+        drop(stream);
+
+        let (sent, cancelled) = tokio::time::timeout(std::time::Duration::from_secs(1), done_rx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(cancelled);
+        assert!(sent >= 2);
+    }
+
+    #[tokio::test]
+    async fn test_guard_aborts_task() {
+        let (tx, rx) = mpsc::channel::<Result<Bytes, QuillError>>(1);
+        let task = tokio::spawn(async move {
+            let _tx = tx;
+            std::future::pending::<()>().await;
+        });
+        let (guard, signal) = CancelGuard::new();
+        let response = RpcResponse::from_channel_with_guard(rx, guard.abort_on_cancel(&task));
+
+        drop(response);
+        assert!(signal.is_cancelled());
+        assert!(task.await.unwrap_err().is_cancelled());
+    }
 }
@@ -0,0 +1,518 @@
+//! Per-tenant isolation of stream and bandwidth limits
+//!
+//! This module provides:
+//! - Tenant identification through an [`AuthLayer`]
+//! - Per-tenant caps on concurrent streams, tracked across all connections
+//! - Per-tenant response bandwidth limits
+//! - Weighted fair admission between tenants once the server is at capacity
+//!
+//! Every call to a registered method holds one stream slot of its tenant
+//! until its response has been sent, and so do built-in methods such as
+//! batches until theirs is built. A tenant at its own cap is turned away
+//! with `429`. When the server-wide cap is reached, calls wait and each
+//! freed slot goes to the waiting tenant with the fewest active streams
+//! relative to its weight, oldest call first, so one noisy tenant cannot
+//! starve the others. Calls still waiting after the queue timeout fail with
+//! `503`. Response bytes of a tenant share one token bucket, so its
+//! bandwidth limit holds however many streams it spreads them over.
+
+use crate::middleware::{AuthLayer, AuthResult};
+use crate::BoxFrameStream;
+use futures_util::StreamExt;
+use http::{Request, StatusCode};
+use quill_core::{Frame, ProblemDetails, QuillError};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Default concurrent streams per tenant
+pub const DEFAULT_TENANT_MAX_STREAMS: usize = 100;
+
+/// Default time a call waits for a stream slot on a server at capacity
+pub const DEFAULT_TENANT_QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tenant of requests the auth layer accepts without an identity
+pub const ANONYMOUS_TENANT: &str = "anonymous";
+
+/// Limits applied to one tenant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TenantLimits {
+    /// Streams the tenant may have in flight, including queued calls
+    pub max_concurrent_streams: usize,
+    /// Response bytes per second, unlimited if `None`
+    pub max_bytes_per_second: Option<u64>,
+    /// Share of the server's streams relative to other tenants
+    pub weight: u32,
+}
+
+impl Default for TenantLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_streams: DEFAULT_TENANT_MAX_STREAMS,
+            max_bytes_per_second: None,
+            weight: 1,
+        }
+    }
+}
+
+impl TenantLimits {
+    /// Set the streams the tenant may have in flight
+    pub fn max_concurrent_streams(mut self, max: usize) -> Self {
+        self.max_concurrent_streams = max;
+        self
+    }
+
+    /// Limit the tenant's response bytes per second
+    pub fn max_bytes_per_second(mut self, bytes: u64) -> Self {
+        self.max_bytes_per_second = Some(bytes.max(1));
+        self
+    }
+
+    /// Set the tenant's share of the server's streams (at least 1)
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = weight.max(1);
+        self
+    }
+}
+
+/// Tenant isolation configuration
+pub struct TenantIsolationConfig {
+    /// Limits of tenants without an override
+    pub default_limits: TenantLimits,
+    /// Streams of all tenants together, unlimited if `None`
+    pub max_concurrent_streams: Option<usize>,
+    /// Time a call waits for a stream slot on a server at capacity
+    pub queue_timeout: Duration,
+    auth: AuthLayer,
+    overrides: HashMap<String, TenantLimits>,
+}
+
+impl TenantIsolationConfig {
+    /// Identify tenants by the identity `auth` authenticates
+    ///
+    /// Requests failing authentication are rejected with `401`; requests
+    /// an optional layer lets through without credentials share the
+    /// [`ANONYMOUS_TENANT`].
+    pub fn new(auth: AuthLayer) -> Self {
+        Self {
+            default_limits: TenantLimits::default(),
+            max_concurrent_streams: None,
+            queue_timeout: DEFAULT_TENANT_QUEUE_TIMEOUT,
+            auth,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Set the limits of tenants without an override
+    pub fn default_limits(mut self, limits: TenantLimits) -> Self {
+        self.default_limits = limits;
+        self
+    }
+
+    /// Override the limits of one tenant
+    pub fn tenant(mut self, tenant: impl Into<String>, limits: TenantLimits) -> Self {
+        self.overrides.insert(tenant.into(), limits);
+        self
+    }
+
+    /// Cap the streams of all tenants together
+    pub fn max_concurrent_streams(mut self, max: usize) -> Self {
+        self.max_concurrent_streams = Some(max.max(1));
+        self
+    }
+
+    /// Set the time a call waits for a stream slot
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = timeout;
+        self
+    }
+
+    fn limits(&self, tenant: &str) -> TenantLimits {
+        self.overrides.get(tenant).copied().unwrap_or(self.default_limits)
+    }
+}
+
+/// Streams of one tenant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TenantStats {
+    /// Calls holding a stream slot
+    pub active_streams: usize,
+    /// Calls waiting for a stream slot
+    pub waiting: usize,
+}
+
+/// Token bucket shared by all streams of a tenant
+struct Bandwidth {
+    bytes_per_second: f64,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl Bandwidth {
+    fn new(bytes_per_second: u64) -> Self {
+        let bytes_per_second = bytes_per_second as f64;
+        Self {
+            bytes_per_second,
+            bucket: Mutex::new((bytes_per_second, Instant::now())),
+        }
+    }
+
+    /// Take `bytes` from the bucket and return how long to wait before sending them
+    ///
+    /// The bucket may go into debt, which later reservations wait out.
+    fn reserve(&self, bytes: usize) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let (tokens, refilled) = &mut *bucket;
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * self.bytes_per_second)
+            .min(self.bytes_per_second);
+        *refilled = now;
+        *tokens -= bytes as f64;
+        if *tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-*tokens / self.bytes_per_second)
+        }
+    }
+}
+
+struct Tenant {
+    active: usize,
+    weight: u32,
+    /// Queued calls by arrival order
+    waiting: VecDeque<(u64, oneshot::Sender<TenantPermit>)>,
+    bandwidth: Option<Arc<Bandwidth>>,
+}
+
+impl Tenant {
+    fn new(limits: &TenantLimits) -> Self {
+        Self {
+            active: 0,
+            weight: limits.weight.max(1),
+            waiting: VecDeque::new(),
+            bandwidth: limits.max_bytes_per_second.map(|rate| Arc::new(Bandwidth::new(rate))),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Tenants are kept once seen, so their bandwidth budget carries over between calls
+    tenants: HashMap<String, Tenant>,
+    active: usize,
+    next_seq: u64,
+}
+
+/// Stream slots and bandwidth of every tenant on the server
+pub(crate) struct TenantRegistry {
+    config: TenantIsolationConfig,
+    state: Mutex<State>,
+}
+
+impl TenantRegistry {
+    pub(crate) fn new(config: TenantIsolationConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Tenant a request belongs to
+    pub(crate) fn identify<B>(&self, req: &Request<B>) -> Result<String, ProblemDetails> {
+        match self.config.auth.authenticate(req) {
+            AuthResult::Authenticated(identity) => Ok(identity),
+            AuthResult::None => Ok(ANONYMOUS_TENANT.to_string()),
+            AuthResult::Failed(reason) => {
+                Err(ProblemDetails::new(StatusCode::UNAUTHORIZED, "Unauthenticated").with_detail(reason))
+            }
+        }
+    }
+
+    /// Take a stream slot for a call of `tenant`, waiting while the server is at capacity
+    pub(crate) async fn admit(self: &Arc<Self>, tenant: String) -> Result<TenantPermit, ProblemDetails> {
+        let limits = self.config.limits(&tenant);
+        let (seq, mut rx) = {
+            let mut state = self.state.lock().unwrap();
+            let State { tenants, active, next_seq } = &mut *state;
+            let entry = tenants.entry(tenant.clone()).or_insert_with(|| Tenant::new(&limits));
+            if entry.active + entry.waiting.len() >= limits.max_concurrent_streams {
+                return Err(ProblemDetails::new(StatusCode::TOO_MANY_REQUESTS, "Tenant stream limit exceeded")
+                    .with_detail(format!(
+                        "Tenant {} already has {} concurrent streams",
+                        tenant, limits.max_concurrent_streams
+                    )));
+            }
+            // Slots are handed to waiting calls as they free up, so none wait below capacity
+            if self.config.max_concurrent_streams.map_or(true, |max| *active < max) {
+                entry.active += 1;
+                *active += 1;
+                return Ok(TenantPermit::new(self, tenant, entry.bandwidth.clone()));
+            }
+            let seq = *next_seq;
+            *next_seq += 1;
+            let (tx, rx) = oneshot::channel();
+            entry.waiting.push_back((seq, tx));
+            (seq, rx)
+        };
+
+        if let Ok(Ok(permit)) = tokio::time::timeout(self.config.queue_timeout, &mut rx).await {
+            return Ok(permit);
+        }
+        let dequeued = {
+            let mut state = self.state.lock().unwrap();
+            let waiting = &mut state.tenants.get_mut(&tenant).expect("tenants are never removed").waiting;
+            let queued = waiting.len();
+            waiting.retain(|(waiter, _)| *waiter != seq);
+            queued == waiting.len()
+        };
+        // Slots are handed out under the lock, so a dequeued call already holds one
+        match rx.try_recv() {
+            Ok(permit) if dequeued => Ok(permit),
+            _ => Err(ProblemDetails::new(StatusCode::SERVICE_UNAVAILABLE, "Server at capacity").with_detail(
+                format!("No stream slot freed up within {:?}", self.config.queue_timeout),
+            )),
+        }
+    }
+
+    /// Return a slot of `tenant` and hand freed slots to waiting calls
+    fn release(self: &Arc<Self>, tenant: &str) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        if let Some(entry) = state.tenants.get_mut(tenant) {
+            entry.active -= 1;
+        }
+        state.active -= 1;
+
+        while self.config.max_concurrent_streams.map_or(true, |max| state.active < max) {
+            // Fewest active streams per unit of weight first, then the oldest call
+            let next = state
+                .tenants
+                .iter()
+                .filter_map(|(id, entry)| entry.waiting.front().map(|(seq, _)| (id, entry, *seq)))
+                .min_by(|(_, a, a_seq), (_, b, b_seq)| {
+                    let a_share = a.active as u64 * u64::from(b.weight);
+                    let b_share = b.active as u64 * u64::from(a.weight);
+                    a_share.cmp(&b_share).then(a_seq.cmp(b_seq))
+                })
+                .map(|(id, _, _)| id.clone());
+            let Some(id) = next else {
+                break;
+            };
+
+            let entry = state.tenants.get_mut(&id).expect("tenant was just found");
+            let (_, tx) = entry.waiting.pop_front().expect("tenant has a waiting call");
+            let permit = TenantPermit::new(self, id, entry.bandwidth.clone());
+            match tx.send(permit) {
+                Ok(()) => {
+                    entry.active += 1;
+                    state.active += 1;
+                }
+                // The call gave up waiting; its slot was never counted
+                Err(mut permit) => permit.counted = false,
+            }
+        }
+    }
+
+    /// Streams per tenant seen so far
+    pub(crate) fn stats(&self) -> HashMap<String, TenantStats> {
+        let state = self.state.lock().unwrap();
+        state
+            .tenants
+            .iter()
+            .map(|(id, entry)| {
+                let stats = TenantStats {
+                    active_streams: entry.active,
+                    waiting: entry.waiting.len(),
+                };
+                (id.clone(), stats)
+            })
+            .collect()
+    }
+}
+
+/// Stream slot held by one call, returned when dropped
+pub(crate) struct TenantPermit {
+    registry: Arc<TenantRegistry>,
+    tenant: String,
+    bandwidth: Option<Arc<Bandwidth>>,
+    counted: bool,
+}
+
+impl TenantPermit {
+    fn new(registry: &Arc<TenantRegistry>, tenant: String, bandwidth: Option<Arc<Bandwidth>>) -> Self {
+        Self {
+            registry: Arc::clone(registry),
+            tenant,
+            bandwidth,
+            counted: true,
+        }
+    }
+
+    fn delay(&self, bytes: usize) -> Duration {
+        self.bandwidth
+            .as_ref()
+            .map_or(Duration::ZERO, |bandwidth| bandwidth.reserve(bytes))
+    }
+
+    /// Wait until the tenant's bandwidth allows sending `bytes`
+    pub(crate) async fn throttle(&self, bytes: usize) {
+        let delay = self.delay(bytes);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Pace `frames` to the tenant's bandwidth and hold the slot until the stream is dropped
+    pub(crate) fn limit(self, frames: BoxFrameStream) -> BoxFrameStream {
+        Box::pin(frames.then(move |frame: Result<Frame, QuillError>| {
+            let delay = match &frame {
+                Ok(frame) => self.delay(frame.payload.len()),
+                Err(_) => Duration::ZERO,
+            };
+            async move {
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+                frame
+            }
+        }))
+    }
+}
+
+impl Drop for TenantPermit {
+    fn drop(&mut self) {
+        if self.counted {
+            self.registry.release(&self.tenant);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::{ApiKeyValidator, AuthScheme};
+
+    fn registry(config: impl FnOnce(TenantIsolationConfig) -> TenantIsolationConfig) -> Arc<TenantRegistry> {
+        let validator = ApiKeyValidator::new().with_key("key-a".to_string(), "a".to_string());
+        let auth = AuthLayer::new(
+            AuthScheme::ApiKey {
+                header_name: "x-api-key".to_string(),
+            },
+            Arc::new(validator),
+        )
+        .optional();
+        Arc::new(TenantRegistry::new(config(TenantIsolationConfig::new(auth))))
+    }
+
+    #[test]
+    fn test_identifies_tenant_by_auth_identity() {
+        let registry = registry(|config| config);
+        let req = |key: Option<&str>| {
+            let mut builder = Request::builder();
+            if let Some(key) = key {
+                builder = builder.header("x-api-key", key);
+            }
+            builder.body(()).unwrap()
+        };
+
+        assert_eq!(registry.identify(&req(Some("key-a"))).unwrap(), "a");
+        assert_eq!(registry.identify(&req(None)).unwrap(), ANONYMOUS_TENANT);
+        assert_eq!(registry.identify(&req(Some("wrong"))).unwrap_err().status, 401);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_calls_over_tenant_limit() {
+        let registry = registry(|config| {
+            config.tenant("a", TenantLimits::default().max_concurrent_streams(2))
+        });
+
+        let first = registry.admit("a".to_string()).await.unwrap();
+        let _second = registry.admit("a".to_string()).await.unwrap();
+        let rejected = registry.admit("a".to_string()).await.err().unwrap();
+        assert_eq!(rejected.status, 429);
+        // Other tenants keep the default limit
+        let _other = registry.admit("b".to_string()).await.unwrap();
+
+        drop(first);
+        let _third = registry.admit("a".to_string()).await.unwrap();
+        assert_eq!(registry.stats()["a"].active_streams, 2);
+    }
+
+    #[tokio::test]
+    async fn test_freed_slot_goes_to_least_served_tenant() {
+        let registry = registry(|config| config.max_concurrent_streams(2));
+        let first = registry.admit("a".to_string()).await.unwrap();
+        let _second = registry.admit("a".to_string()).await.unwrap();
+
+        let queued_a = tokio::spawn({
+            let registry = Arc::clone(&registry);
+            async move { registry.admit("a".to_string()).await }
+        });
+        tokio::task::yield_now().await;
+        let queued_b = tokio::spawn({
+            let registry = Arc::clone(&registry);
+            async move { registry.admit("b".to_string()).await }
+        });
+        while registry.stats().get("b").map_or(true, |stats| stats.waiting == 0) {
+            tokio::task::yield_now().await;
+        }
+
+        // Tenant a queued first, but b has no streams yet
+        drop(first);
+        let _b = queued_b.await.unwrap().unwrap();
+        assert_eq!(registry.stats()["a"].waiting, 1);
+        assert!(!queued_a.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_weight_scales_share() {
+        let registry = registry(|config| {
+            config
+                .max_concurrent_streams(3)
+                .tenant("a", TenantLimits::default().weight(2))
+        });
+        let _a = registry.admit("a".to_string()).await.unwrap();
+        let _b = registry.admit("b".to_string()).await.unwrap();
+        let held = registry.admit("b".to_string()).await.unwrap();
+
+        let queued_b = tokio::spawn({
+            let registry = Arc::clone(&registry);
+            async move { registry.admit("b".to_string()).await }
+        });
+        tokio::task::yield_now().await;
+        let queued_a = tokio::spawn({
+            let registry = Arc::clone(&registry);
+            async move { registry.admit("a".to_string()).await }
+        });
+        while registry.stats().get("a").map_or(true, |stats| stats.waiting == 0) {
+            tokio::task::yield_now().await;
+        }
+
+        // One stream of weight 2 is a smaller share than one stream of weight 1
+        drop(held);
+        let _a2 = queued_a.await.unwrap().unwrap();
+        assert!(!queued_b.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_queue_timeout() {
+        let registry = registry(|config| {
+            config
+                .max_concurrent_streams(1)
+                .queue_timeout(Duration::from_millis(20))
+        });
+        let _held = registry.admit("a".to_string()).await.unwrap();
+
+        let error = registry.admit("b".to_string()).await.err().unwrap();
+        assert_eq!(error.status, 503);
+        assert_eq!(registry.stats()["b"], TenantStats::default());
+    }
+
+    #[test]
+    fn test_bandwidth_debt_is_waited_out() {
+        let bandwidth = Bandwidth::new(1000);
+        assert_eq!(bandwidth.reserve(500), Duration::ZERO);
+        let delay = bandwidth.reserve(1500);
+        assert!(delay > Duration::from_millis(900) && delay <= Duration::from_secs(1), "{:?}", delay);
+    }
+}
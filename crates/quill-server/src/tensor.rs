@@ -0,0 +1,356 @@
+//! Tensor streaming responses
+//!
+//! Tensor handlers answer with a stream of [`TensorFrame`]s rather than one
+//! buffered message. Every tensor frame is sent as its own message, so a
+//! client can feed messages into a `TensorReceiver` as they arrive and
+//! pre-allocate on TENSOR_META before the payload is complete.
+//!
+//! Handlers that produce data incrementally can push frames through a
+//! [`TensorFrameSink`] from [`tensor_channel`] and return its stream.
+//! Generated tokens go through a [`TokenSink`], which batches them into
+//! TOKEN_BATCH frames without holding back the first token or a slow one.
+//!
+//! Relays forward tensor streams from another service with
+//! [`RpcResponse::tensor_passthrough`], which checks the frames without
+//! assembling the tensor or copying its payload.
+
+use crate::streaming::RpcResponse;
+use bytes::Bytes;
+use futures_util::stream::TryStreamExt;
+use http::StatusCode;
+use quill_core::{Frame, ProblemDetails, QuillError};
+use quill_tensor::{
+    Tensor, TensorFrame, TensorMeta, TensorPassthrough, TensorSender, Token, TokenBatch,
+    TokenBatchBuilder,
+};
+use std::pin::Pin;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Stream of tensor frames returned by a tensor streaming handler
+pub type TensorFrameStream = Pin<Box<dyn Stream<Item = Result<TensorFrame, QuillError>> + Send>>;
+
+impl RpcResponse {
+    /// Create a streaming response sending each tensor frame as one message
+    pub fn tensor_stream<S>(frames: S) -> Self
+    where
+        S: Stream<Item = Result<TensorFrame, QuillError>> + Send + 'static,
+    {
+        Self::streaming(frames.map_ok(|frame| frame.encode()))
+    }
+
+    /// Create a streaming response relaying the tensor stream `messages`
+    ///
+    /// Each message is checked by `passthrough` and forwarded as it is,
+    /// without assembling the tensor or copying its payload. Instead of a
+    /// message that breaks the stream, or if `messages` ends in the middle of
+    /// a frame, a CANCEL frame with a 502 problem ends the response.
+    pub fn tensor_passthrough<S>(messages: S, passthrough: TensorPassthrough) -> Self
+    where
+        S: Stream<Item = Result<Bytes, QuillError>> + Send + 'static,
+    {
+        let invalid = |detail: String| {
+            let problem =
+                ProblemDetails::new(StatusCode::BAD_GATEWAY, "Invalid tensor stream").with_detail(detail);
+            Frame::cancel_with_problem(&problem)
+        };
+        let frames = futures_util::stream::unfold(
+            (Box::pin(messages), Some(passthrough)),
+            move |(mut messages, passthrough)| async move {
+                let mut passthrough = passthrough?;
+                let frame = match messages.next().await {
+                    Some(Ok(message)) => match passthrough.forward(message) {
+                        Ok(message) => Ok(Frame::data(message)),
+                        Err(e) => Ok(invalid(e.to_string())),
+                    },
+                    Some(Err(e)) => Err(e),
+                    None if passthrough.at_frame_boundary() => Ok(Frame::end_stream()),
+                    None => Ok(invalid("stream ended mid-frame".to_string())),
+                };
+                // Nothing follows a terminal frame or an error
+                let more = matches!(&frame, Ok(frame) if frame.flags.is_data());
+                Some((frame, (messages, more.then_some(passthrough))))
+            },
+        );
+        Self::framed(frames)
+    }
+}
+
+/// Create a sink and the tensor frame stream it feeds
+///
+/// The stream holds up to `buffer` frames; sends wait while it is full and
+/// fail once the stream has been dropped, e.g. because the client went away.
+pub fn tensor_channel(sender: TensorSender, buffer: usize) -> (TensorFrameSink, TensorFrameStream) {
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+    (TensorFrameSink { tx, sender }, Box::pin(ReceiverStream::new(rx)))
+}
+
+/// Pushes tensor frames into a [`tensor_channel`] stream
+///
+/// Payload chunks follow the chunk size of the [`TensorSender`].
+pub struct TensorFrameSink {
+    tx: mpsc::Sender<Result<TensorFrame, QuillError>>,
+    sender: TensorSender,
+}
+
+impl TensorFrameSink {
+    /// Send one frame
+    pub async fn send_frame(&self, frame: TensorFrame) -> Result<(), QuillError> {
+        self.tx
+            .send(Ok(frame))
+            .await
+            .map_err(|_| QuillError::Rpc("Tensor stream closed".to_string()))
+    }
+
+    /// Send a whole tensor: TENSOR_META, its payload chunks and END_STREAM
+    pub async fn send_tensor(&self, tensor: &Tensor) -> Result<(), QuillError> {
+        for frame in self.sender.encode_tensor(tensor) {
+            self.send_frame(frame).await?;
+        }
+        Ok(())
+    }
+
+    /// Start a tensor whose payload follows in [`send_payload`](Self::send_payload) calls
+    pub async fn send_meta(&self, meta: &TensorMeta) -> Result<(), QuillError> {
+        self.send_frame(TensorFrame::tensor_meta(self.sender.encode_meta(meta))).await
+    }
+
+    /// Send payload bytes of the current tensor, split into chunks
+    pub async fn send_payload(&self, data: bytes::Bytes) -> Result<(), QuillError> {
+        let chunk_size = self.sender.chunk_size().max(1);
+        let mut offset = 0;
+        while offset < data.len() {
+            let end = (offset + chunk_size).min(data.len());
+            self.send_frame(TensorFrame::tensor_payload(data.slice(offset..end))).await?;
+            offset = end;
+        }
+        Ok(())
+    }
+
+    /// Send a batch of tokens as a TOKEN_BATCH frame
+    pub async fn send_token_batch(&self, batch: &TokenBatch) -> Result<(), QuillError> {
+        self.send_frame(TensorFrame::token_batch(batch.encode())).await
+    }
+
+    /// Batch tokens pushed through the returned [`TokenSink`] with `builder`
+    ///
+    /// The first token is always sent on its own right away, and batches
+    /// are flushed when their oldest token reaches the builder's max age even
+    /// if no further token arrives.
+    pub fn token_sink(self, builder: TokenBatchBuilder) -> TokenSink {
+        let (tx, rx) = mpsc::channel(TOKEN_SINK_BUFFER);
+        let task = tokio::spawn(run_token_sink(self, builder.with_immediate_first_token(true), rx));
+        TokenSink { tx, task }
+    }
+
+    /// End the tensor stream
+    pub async fn send_end(&self) -> Result<(), QuillError> {
+        self.send_frame(TensorFrame::end_stream()).await
+    }
+
+    /// Fail the response with `error`
+    pub async fn send_error(&self, error: QuillError) -> Result<(), QuillError> {
+        self.tx
+            .send(Err(error))
+            .await
+            .map_err(|_| QuillError::Rpc("Tensor stream closed".to_string()))
+    }
+
+    /// Check if the stream has been dropped
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+/// Tokens a [`TokenSink`] holds before `send` waits
+const TOKEN_SINK_BUFFER: usize = 64;
+
+enum TokenCommand {
+    Token(Token),
+    Flush,
+    Finish,
+}
+
+/// Batches tokens into TOKEN_BATCH frames of a tensor stream
+///
+/// Created by [`TensorFrameSink::token_sink`]. A background task owns the
+/// batch builder, so time-based flushes happen while the producer is busy
+/// generating the next token.
+pub struct TokenSink {
+    tx: mpsc::Sender<TokenCommand>,
+    task: JoinHandle<Result<(), QuillError>>,
+}
+
+impl TokenSink {
+    /// Queue a token for the current batch
+    pub async fn send(&self, token: Token) -> Result<(), QuillError> {
+        self.command(TokenCommand::Token(token)).await
+    }
+
+    /// Send the pending tokens now
+    pub async fn flush(&self) -> Result<(), QuillError> {
+        self.command(TokenCommand::Flush).await
+    }
+
+    /// Send the remaining tokens as the final batch and end the stream
+    pub async fn finish(self) -> Result<(), QuillError> {
+        // A send error means the task ended; its result says why
+        let _ = self.tx.send(TokenCommand::Finish).await;
+        self.task
+            .await
+            .map_err(|e| QuillError::Rpc(format!("Token sink task failed: {}", e)))?
+    }
+
+    async fn command(&self, command: TokenCommand) -> Result<(), QuillError> {
+        self.tx
+            .send(command)
+            .await
+            .map_err(|_| QuillError::Rpc("Tensor stream closed".to_string()))
+    }
+}
+
+async fn run_token_sink(
+    sink: TensorFrameSink,
+    mut builder: TokenBatchBuilder,
+    mut rx: mpsc::Receiver<TokenCommand>,
+) -> Result<(), QuillError> {
+    loop {
+        let deadline = builder.deadline();
+        let command = tokio::select! {
+            command = rx.recv() => command,
+            _ = sleep_until(deadline), if deadline.is_some() => {
+                if let Some(batch) = builder.flush_due() {
+                    sink.send_token_batch(&batch).await?;
+                }
+                continue;
+            }
+        };
+
+        match command {
+            Some(TokenCommand::Token(token)) => {
+                if let Some(batch) = builder.push(token) {
+                    sink.send_token_batch(&batch).await?;
+                }
+            }
+            Some(TokenCommand::Flush) => {
+                if builder.has_pending() {
+                    sink.send_token_batch(&builder.flush()).await?;
+                }
+            }
+            Some(TokenCommand::Finish) => {
+                sink.send_token_batch(&builder.finish()).await?;
+                return sink.send_end().await;
+            }
+            // Dropped without finishing: send what is left, but don't end
+            None => {
+                if builder.has_pending() {
+                    sink.send_token_batch(&builder.flush()).await?;
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn sleep_until(deadline: Option<std::time::Instant>) {
+    if let Some(deadline) = deadline {
+        tokio::time::sleep_until(deadline.into()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use quill_tensor::{DType, FrameType};
+    use std::time::Duration;
+    use tokio_stream::StreamExt;
+
+    async fn next_batch(frames: &mut TensorFrameStream) -> TokenBatch {
+        let frame = frames.next().await.unwrap().unwrap();
+        assert_eq!(frame.frame_type, FrameType::TokenBatch);
+        TokenBatch::decode(&frame.payload).unwrap()
+    }
+
+    fn ids(batch: &TokenBatch) -> Vec<u32> {
+        batch.iter().map(|t| t.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_token_sink_batches_after_first_token() {
+        let (sink, mut frames) = tensor_channel(TensorSender::new(), 16);
+        let tokens = sink.token_sink(TokenBatchBuilder::with_max_size(2));
+        for id in 0..4 {
+            tokens.send(Token::new(id, id)).await.unwrap();
+        }
+        tokens.flush().await.unwrap();
+        tokens.send(Token::new(4, 4)).await.unwrap();
+        tokens.finish().await.unwrap();
+
+        assert_eq!(ids(&next_batch(&mut frames).await), vec![0]);
+        assert_eq!(ids(&next_batch(&mut frames).await), vec![1, 2]);
+        assert_eq!(ids(&next_batch(&mut frames).await), vec![3]);
+        let last = next_batch(&mut frames).await;
+        assert_eq!(ids(&last), vec![4]);
+        assert!(last.is_final);
+        let end = frames.next().await.unwrap().unwrap();
+        assert_eq!(end.frame_type, FrameType::EndStream);
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_token_sink_flushes_by_age() {
+        let (sink, mut frames) = tensor_channel(TensorSender::new(), 16);
+        let builder = TokenBatchBuilder::with_max_size(32).with_max_age(Duration::from_millis(20));
+        let tokens = sink.token_sink(builder);
+        tokens.send(Token::new(0, 0)).await.unwrap();
+        tokens.send(Token::new(1, 1)).await.unwrap();
+        tokens.send(Token::new(2, 2)).await.unwrap();
+        assert_eq!(ids(&next_batch(&mut frames).await), vec![0]);
+
+        // No further token arrives, the age limit flushes the pending ones
+        let batch = tokio::time::timeout(Duration::from_secs(1), next_batch(&mut frames))
+            .await
+            .unwrap();
+        assert_eq!(ids(&batch), vec![1, 2]);
+        assert!(!batch.is_final);
+        tokens.finish().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sink_chunks_payload() {
+        let (sink, mut frames) = tensor_channel(TensorSender::with_chunk_size(4), 16);
+        let meta = TensorMeta::new(vec![10], DType::UInt8);
+        tokio::spawn(async move {
+            sink.send_meta(&meta).await.unwrap();
+            sink.send_payload(Bytes::from(vec![7u8; 10])).await.unwrap();
+            sink.send_end().await.unwrap();
+        });
+
+        let mut kinds = Vec::new();
+        while let Some(frame) = frames.next().await {
+            let frame = frame.unwrap();
+            kinds.push((frame.frame_type, frame.payload.len()));
+        }
+        assert_eq!(kinds[0].0, FrameType::TensorMeta);
+        assert_eq!(
+            &kinds[1..],
+            &[
+                (FrameType::TensorPayload, 4),
+                (FrameType::TensorPayload, 4),
+                (FrameType::TensorPayload, 2),
+                (FrameType::EndStream, 0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sink_fails_once_stream_dropped() {
+        let (sink, frames) = tensor_channel(TensorSender::new(), 1);
+        drop(frames);
+        assert!(sink.is_closed());
+        assert!(sink.send_end().await.is_err());
+    }
+}
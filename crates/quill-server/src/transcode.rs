@@ -0,0 +1,189 @@
+//! JSON transcoding of described methods
+//!
+//! This module provides:
+//! - Detection of JSON requests (`Content-Type: application/json`)
+//! - Conversion of JSON requests to protobuf and of responses back to JSON
+//!
+//! A method is transcoded when descriptors of its service were added with
+//! [`RpcRouter::add_file_descriptor_set`](crate::RpcRouter::add_file_descriptor_set),
+//! so a described server can be called with curl:
+//!
+//! ```text
+//! curl -X POST http://localhost:8080/greeter.v1.Greeter/SayHello \
+//!     -H 'Content-Type: application/json' -d '{"name": "Ada"}'
+//! ```
+//!
+//! JSON follows the proto3 JSON mapping. Unary responses are JSON bodies;
+//! streaming responses keep their framing with one JSON message per frame.
+//! Pre-framed responses are sent as they are, and methods with request
+//! streams do not accept JSON. Requests to undescribed methods reach their
+//! handlers unchanged, whatever their content type.
+
+use crate::streaming::RpcResponse;
+use bytes::Bytes;
+use futures_util::stream::StreamExt;
+use heck::ToSnakeCase;
+use http::header::CONTENT_TYPE;
+use http::HeaderMap;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor};
+use quill_core::QuillError;
+
+/// Content type of transcoded requests and responses
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Descriptors of the methods that accept JSON
+#[derive(Debug, Default)]
+pub(crate) struct JsonTranscoder {
+    pool: DescriptorPool,
+}
+
+impl JsonTranscoder {
+    /// Add the files of an encoded `FileDescriptorSet`
+    ///
+    /// Fails if the set refers to types it does not define and that were
+    /// not added before.
+    pub(crate) fn add(&mut self, descriptor_set: &[u8]) -> Result<(), String> {
+        self.pool.decode_file_descriptor_set(descriptor_set).map_err(|e| e.to_string())
+    }
+
+    /// Method to transcode for a request to `path`, if it is JSON and described
+    pub(crate) fn method(&self, path: &str, headers: &HeaderMap) -> Option<MethodDescriptor> {
+        if !is_json(headers) {
+            return None;
+        }
+        let (service, method) = path.split_once('/')?;
+        // Routes name services fully qualified or bare, and methods as in
+        // the proto or in snake_case as quill-codegen stubs register them
+        self.pool
+            .services()
+            .filter(|s| s.full_name() == service || s.name() == service)
+            .find_map(|s| {
+                s.methods()
+                    .find(|m| m.name() == method || m.name().to_snake_case() == method)
+            })
+    }
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(JSON_CONTENT_TYPE))
+}
+
+/// Encode a JSON request as `descriptor`; an empty body is an empty message
+pub(crate) fn json_to_proto(descriptor: &MessageDescriptor, json: &[u8]) -> Result<Bytes, String> {
+    if json.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Bytes::new());
+    }
+    let mut deserializer = serde_json::Deserializer::from_slice(json);
+    let message = DynamicMessage::deserialize(descriptor.clone(), &mut deserializer)
+        .and_then(|message| deserializer.end().map(|()| message))
+        .map_err(|e| format!("Not a valid {} message: {}", descriptor.full_name(), e))?;
+    Ok(Bytes::from(message.encode_to_vec()))
+}
+
+/// Decode a response as `descriptor` and render it as JSON
+pub(crate) fn proto_to_json(descriptor: &MessageDescriptor, proto: &[u8]) -> Result<Bytes, String> {
+    let message = DynamicMessage::decode(descriptor.clone(), proto)
+        .map_err(|e| format!("Response is not a valid {} message: {}", descriptor.full_name(), e))?;
+    serde_json::to_vec(&message)
+        .map(Bytes::from)
+        .map_err(|e| format!("Failed to render {} as JSON: {}", descriptor.full_name(), e))
+}
+
+/// Render each message of a streaming response as JSON
+pub(crate) fn stream_to_json(descriptor: MessageDescriptor, response: RpcResponse) -> RpcResponse {
+    match response {
+        RpcResponse::Streaming(stream) => RpcResponse::Streaming(Box::pin(stream.map(move |message| {
+            message.and_then(|proto| proto_to_json(&descriptor, &proto).map_err(QuillError::Rpc))
+        }))),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+    use prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        MethodDescriptorProto, ServiceDescriptorProto,
+    };
+
+    fn descriptor_set() -> Vec<u8> {
+        let field = |name: &str, number, kind: Type| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(kind as i32),
+            json_name: Some(name.to_string()),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("greeter.proto".to_string()),
+            package: Some("greeter.v1".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Hello".to_string()),
+                field: vec![field("name", 1, Type::String), field("count", 2, Type::Int32)],
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Greeter".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("SayHello".to_string()),
+                    input_type: Some(".greeter.v1.Hello".to_string()),
+                    output_type: Some(".greeter.v1.Hello".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        FileDescriptorSet { file: vec![file] }.encode_to_vec()
+    }
+
+    fn json_headers(content_type: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        headers
+    }
+
+    #[test]
+    fn test_methods_match_json_requests_to_described_routes() {
+        let mut transcoder = JsonTranscoder::default();
+        transcoder.add(&descriptor_set()).unwrap();
+        let json = json_headers("application/json; charset=utf-8");
+
+        for path in ["greeter.v1.Greeter/SayHello", "Greeter/SayHello", "greeter.v1.Greeter/say_hello"] {
+            assert!(transcoder.method(path, &json).is_some(), "{}", path);
+        }
+        assert!(transcoder.method("greeter.v1.Greeter/Missing", &json).is_none());
+        assert!(transcoder.method("other.v1.Other/SayHello", &json).is_none());
+        assert!(transcoder.method("greeter.v1.Greeter/SayHello", &json_headers("application/proto")).is_none());
+        assert!(transcoder.method("greeter.v1.Greeter/SayHello", &HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_json_round_trips_through_proto() {
+        let mut transcoder = JsonTranscoder::default();
+        transcoder.add(&descriptor_set()).unwrap();
+        let method = transcoder.method("greeter.v1.Greeter/SayHello", &json_headers("application/json")).unwrap();
+
+        let proto = json_to_proto(&method.input(), br#"{"name": "Ada", "count": 2}"#).unwrap();
+        let json = proto_to_json(&method.output(), &proto).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({"name": "Ada", "count": 2})
+        );
+
+        assert_eq!(json_to_proto(&method.input(), b" ").unwrap(), Bytes::new());
+        assert!(json_to_proto(&method.input(), br#"{"name": 7}"#).is_err());
+        assert!(json_to_proto(&method.input(), br#"{"name": "Ada"} trailing"#).is_err());
+        assert!(proto_to_json(&method.output(), b"\xff").is_err());
+    }
+}
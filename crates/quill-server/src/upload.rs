@@ -0,0 +1,241 @@
+//! Reassembly of chunked uploads
+//!
+//! This module provides:
+//! - Limits for chunked uploads of large unary requests
+//! - A store collecting chunks until the request body is complete
+//!
+//! Chunks of one upload may arrive on parallel request streams in any
+//! order. Every chunk but the last is answered with `202 Accepted`; the
+//! chunk completing the upload invokes the handler with the reassembled
+//! body and carries its response. See [`quill_core::upload`] for the wire
+//! format.
+
+use bytes::Bytes;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use quill_core::{
+    parse_chunk_index, ChunkAssembly, UploadCapability, UploadManifest, UPLOAD_CHUNK_HEADER,
+    UPLOAD_MANIFEST_HEADER,
+};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Limits for chunked uploads
+#[derive(Debug, Clone)]
+pub struct ChunkedUploadConfig {
+    /// Largest reassembled body in bytes
+    pub max_size: u64,
+    /// Most chunks per upload
+    pub max_chunks: u32,
+    /// Most uploads collected at the same time
+    pub max_pending: usize,
+    /// Discard an incomplete upload after this long without a new chunk
+    pub timeout: Duration,
+}
+
+impl Default for ChunkedUploadConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 64 * 1024 * 1024,
+            max_chunks: 256,
+            max_pending: 1024,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ChunkedUploadConfig {
+    /// Set the largest reassembled body
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Set the most chunks per upload
+    pub fn max_chunks(mut self, max_chunks: u32) -> Self {
+        self.max_chunks = max_chunks;
+        self
+    }
+
+    /// Set the most uploads collected at the same time
+    pub fn max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    /// Set the incomplete upload timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Capability advertised to clients
+    pub fn capability(&self) -> UploadCapability {
+        UploadCapability {
+            max_size: self.max_size,
+            max_chunks: self.max_chunks,
+        }
+    }
+}
+
+/// Result of accepting one chunk
+#[derive(Debug)]
+pub(crate) enum ChunkOutcome {
+    /// More chunks are needed
+    Pending,
+    /// The upload is complete
+    Complete(Bytes),
+}
+
+struct PendingUpload {
+    assembly: ChunkAssembly,
+    last_chunk: Instant,
+}
+
+/// Collects chunks of in-flight uploads
+pub(crate) struct UploadStore {
+    config: ChunkedUploadConfig,
+    capability_header: HeaderValue,
+    pending: Mutex<HashMap<String, PendingUpload>>,
+}
+
+impl UploadStore {
+    pub(crate) fn new(config: ChunkedUploadConfig) -> Self {
+        let capability_header = HeaderValue::from_str(&config.capability().to_header_value())
+            .expect("capability header is ASCII");
+        Self {
+            config,
+            capability_header,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Header value advertising this store's limits
+    pub(crate) fn capability_header(&self) -> &HeaderValue {
+        &self.capability_header
+    }
+
+    /// Add a chunk of an upload to `path`
+    ///
+    /// Fails with the status and detail to send if the chunk is rejected.
+    pub(crate) fn accept(
+        &self,
+        path: &str,
+        headers: &HeaderMap,
+        chunk: Bytes,
+    ) -> Result<ChunkOutcome, (StatusCode, String)> {
+        let bad_request = |detail: String| (StatusCode::BAD_REQUEST, detail);
+
+        let manifest = headers
+            .get(UPLOAD_MANIFEST_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| bad_request("Missing upload manifest".to_string()))?;
+        let manifest = UploadManifest::from_header_value(manifest).map_err(|e| bad_request(e.to_string()))?;
+        let index = headers
+            .get(UPLOAD_CHUNK_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| bad_request("Missing upload chunk index".to_string()))?;
+        let index = parse_chunk_index(index).map_err(|e| bad_request(e.to_string()))?;
+
+        if !self.config.capability().allows(&manifest) {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "Upload of {} bytes in {} chunks exceeds the server limits",
+                    manifest.total_size, manifest.chunk_count
+                ),
+            ));
+        }
+
+        // Validate before the chunk can open an upload that would never complete
+        manifest.check_chunk(index, &chunk).map_err(|e| bad_request(e.to_string()))?;
+
+        let key = format!("{}\n{}", path, manifest.upload_id);
+        let mut pending = self.pending.lock().unwrap();
+        let now = Instant::now();
+        pending.retain(|_, upload| now.duration_since(upload.last_chunk) < self.config.timeout);
+
+        if !pending.contains_key(&key) && pending.len() >= self.config.max_pending {
+            return Err((StatusCode::SERVICE_UNAVAILABLE, "Too many uploads in progress".to_string()));
+        }
+        let upload = pending.entry(key.clone()).or_insert_with(|| PendingUpload {
+            assembly: ChunkAssembly::new(manifest.clone()),
+            last_chunk: now,
+        });
+        if upload.assembly.manifest() != &manifest {
+            return Err(bad_request("Chunk manifest does not match the upload in progress".to_string()));
+        }
+        upload.assembly.insert(index, chunk).map_err(|e| bad_request(e.to_string()))?;
+        upload.last_chunk = now;
+
+        if !upload.assembly.is_complete() {
+            return Ok(ChunkOutcome::Pending);
+        }
+        let upload = pending.remove(&key).expect("upload present");
+        tracing::debug!(
+            "Reassembled upload {} ({} bytes in {} chunks)",
+            manifest.upload_id,
+            manifest.total_size,
+            manifest.chunk_count
+        );
+        Ok(ChunkOutcome::Complete(upload.assembly.assemble().expect("complete upload")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_headers(manifest: &UploadManifest, index: u32) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(UPLOAD_MANIFEST_HEADER, HeaderValue::from_str(&manifest.to_header_value()).unwrap());
+        headers.insert(UPLOAD_CHUNK_HEADER, HeaderValue::from(index));
+        headers
+    }
+
+    #[test]
+    fn test_reassembles_out_of_order() {
+        let store = UploadStore::new(ChunkedUploadConfig::default());
+        let body = Bytes::from_static(b"hello chunked world");
+        let (manifest, chunks) = UploadManifest::split("u1", &body, 8);
+
+        for index in [2, 0] {
+            let outcome = store.accept("svc/M", &chunk_headers(&manifest, index), chunks[index as usize].clone());
+            assert!(matches!(outcome, Ok(ChunkOutcome::Pending)));
+        }
+        match store.accept("svc/M", &chunk_headers(&manifest, 1), chunks[1].clone()) {
+            Ok(ChunkOutcome::Complete(assembled)) => assert_eq!(assembled, body),
+            other => panic!("expected completed upload, got {:?}", other),
+        }
+        assert!(store.pending.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_uploads_over_limits() {
+        let store = UploadStore::new(ChunkedUploadConfig::default().max_size(10));
+        let manifest = UploadManifest::new("big", 20, 10);
+        let (status, _) = store.accept("svc/M", &chunk_headers(&manifest, 0), Bytes::from(vec![0; 10])).unwrap_err();
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_rejected_chunk_opens_no_upload() {
+        let store = UploadStore::new(ChunkedUploadConfig::default().max_pending(1));
+        let manifest = UploadManifest::new("u1", 20, 10);
+        for (index, len) in [(2, 10), (0, 4)] {
+            let (status, _) = store.accept("svc/M", &chunk_headers(&manifest, index), Bytes::from(vec![0; len])).unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        assert!(store.pending.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expires_stale_uploads() {
+        let store = UploadStore::new(ChunkedUploadConfig::default().timeout(Duration::ZERO).max_pending(1));
+        let first = UploadManifest::new("a", 20, 10);
+        let second = UploadManifest::new("b", 20, 10);
+        assert!(store.accept("svc/M", &chunk_headers(&first, 0), Bytes::from(vec![0; 10])).is_ok());
+        // The stale first upload no longer counts against max_pending
+        assert!(store.accept("svc/M", &chunk_headers(&second, 0), Bytes::from(vec![0; 10])).is_ok());
+    }
+}
@@ -0,0 +1,221 @@
+//! io_uring I/O backend for TCP connections (Linux, `io-uring` feature)
+//!
+//! This module provides:
+//! - Probing whether the kernel allows io_uring
+//! - A stream adapter submitting a connection's reads and writes to a ring
+//! - Acceptor threads, each driving its own ring
+//!
+//! Every acceptor runs on a dedicated thread with a `tokio-uring` runtime.
+//! Connections are accepted from the configured listeners, so all
+//! [`SocketConfig`](crate::runtime::SocketConfig) options still apply; their
+//! reads and writes then complete on the thread's ring instead of being
+//! polled for readiness, which saves syscalls at high packet rates.
+//! Handlers keep running on the runtime that called
+//! [`QuillServer::serve`](crate::server::QuillServer::serve), exactly as
+//! with the tokio backend.
+//!
+//! Only TCP is served this way; HTTP/3 keeps using quinn's UDP socket.
+
+use crate::router::RpcRouter;
+use crate::server::{serve_connection, ServerConfig};
+use std::future::Future;
+use std::io;
+use std::net::Shutdown;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, watch};
+use tracing::error;
+
+/// Capacity of the buffer each read fills
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+type ReadOp = Pin<Box<dyn Future<Output = (io::Result<usize>, Vec<u8>)>>>;
+type WriteOp = Pin<Box<dyn Future<Output = (io::Result<()>, Vec<u8>)>>>;
+
+/// Whether this process can create an io_uring
+///
+/// Kernels before 5.6, seccomp profiles and `io_uring_disabled` all refuse
+/// it. Probed once.
+pub(crate) fn is_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        // The runtime installs its driver in a thread-local, so probe on a thread of its own
+        std::thread::spawn(|| tokio_uring::Runtime::new(&tokio_uring::builder()).is_ok())
+            .join()
+            .unwrap_or(false)
+    })
+}
+
+/// Serve connections accepted from `listeners` on `acceptors` io_uring threads
+///
+/// Returns once an acceptor fails; dropping the future stops every thread.
+pub(crate) async fn serve(
+    listeners: Vec<std::net::TcpListener>,
+    acceptors: usize,
+    router: Arc<RpcRouter>,
+    config: Arc<ServerConfig>,
+) -> io::Result<()> {
+    let handlers = Handle::current();
+    let (shutdown, _) = watch::channel(());
+    let (exited_tx, mut exited) = mpsc::channel(1);
+
+    for i in 0..acceptors.max(1) {
+        let listener = listeners[i % listeners.len()].try_clone()?;
+        let router = Arc::clone(&router);
+        let config = Arc::clone(&config);
+        let handlers = handlers.clone();
+        let mut shutdown = shutdown.subscribe();
+        let exited = exited_tx.clone();
+
+        std::thread::Builder::new().name(format!("quill-uring-{}", i)).spawn(move || {
+            let result = tokio_uring::start(async move {
+                tokio::select! {
+                    result = accept_loop(listener, router, config, handlers) => result,
+                    _ = shutdown.changed() => Ok(()),
+                }
+            });
+            let _ = exited.blocking_send(result);
+        })?;
+    }
+    drop(exited_tx);
+
+    exited.recv().await.unwrap_or(Ok(()))
+}
+
+/// Accept connections and serve each on a task of this thread's ring
+async fn accept_loop(
+    listener: std::net::TcpListener,
+    router: Arc<RpcRouter>,
+    config: Arc<ServerConfig>,
+    handlers: Handle,
+) -> io::Result<()> {
+    // Accepting stays readiness-based; reads and writes go to the ring
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    loop {
+        let (stream, remote_addr) = listener.accept().await?;
+        if config.socket.tcp_nodelay {
+            stream.set_nodelay(true)?;
+        }
+        let stream = stream.into_std()?;
+        stream.set_nonblocking(false)?;
+        let stream = UringStream::new(tokio_uring::net::TcpStream::from_std(stream));
+
+        let router = Arc::clone(&router);
+        let config = Arc::clone(&config);
+        let handlers = handlers.clone();
+        tokio_uring::spawn(async move {
+            if let Err(err) = serve_connection(router, config, stream, Some(handlers)).await {
+                error!("Error serving connection from {}: {:?}", remote_addr, err);
+            }
+        });
+    }
+}
+
+/// TCP stream whose reads and writes are io_uring operations
+///
+/// Writes are buffered: `poll_write` submits a copy of the data and
+/// completes at once, and the next write, flush or shutdown waits for it.
+pub(crate) struct UringStream {
+    stream: Rc<tokio_uring::net::TcpStream>,
+    /// Bytes received but not yet handed to the reader
+    received: Vec<u8>,
+    consumed: usize,
+    read: Option<ReadOp>,
+    write: Option<WriteOp>,
+    /// Buffer of the last completed write, reused by the next one
+    spare: Vec<u8>,
+}
+
+impl UringStream {
+    pub(crate) fn new(stream: tokio_uring::net::TcpStream) -> Self {
+        Self {
+            stream: Rc::new(stream),
+            received: Vec::new(),
+            consumed: 0,
+            read: None,
+            write: None,
+            spare: Vec::new(),
+        }
+    }
+
+    /// Wait for the write in flight, if any
+    fn poll_write_done(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(op) = self.write.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let (result, buffer) = ready!(op.as_mut().poll(cx));
+        self.write = None;
+        self.spare = buffer;
+        Poll::Ready(result)
+    }
+}
+
+impl AsyncRead for UringStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.consumed < this.received.len() {
+                let n = buf.remaining().min(this.received.len() - this.consumed);
+                buf.put_slice(&this.received[this.consumed..this.consumed + n]);
+                this.consumed += n;
+                return Poll::Ready(Ok(()));
+            }
+
+            let op = this.read.get_or_insert_with(|| {
+                let stream = Rc::clone(&this.stream);
+                let mut buffer = std::mem::take(&mut this.received);
+                buffer.clear();
+                buffer.reserve(READ_BUFFER_SIZE);
+                Box::pin(async move { stream.read(buffer).await })
+            });
+            let (result, buffer) = ready!(op.as_mut().poll(cx));
+            this.read = None;
+            this.received = buffer;
+            this.consumed = 0;
+            if result? == 0 {
+                // End of stream
+                return Poll::Ready(Ok(()));
+            }
+        }
+    }
+}
+
+impl AsyncWrite for UringStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        ready!(this.poll_write_done(cx))?;
+
+        let stream = Rc::clone(&this.stream);
+        let mut buffer = std::mem::take(&mut this.spare);
+        buffer.clear();
+        buffer.extend_from_slice(data);
+        this.write = Some(Box::pin(async move { stream.write_all(buffer).await }));
+
+        // Poll once so the write is submitted now rather than at the next flush
+        if let Poll::Ready(Err(e)) = this.poll_write_done(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(data.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_write_done(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_done(cx))?;
+        Poll::Ready(self.stream.shutdown(Shutdown::Write))
+    }
+}
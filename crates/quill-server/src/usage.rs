@@ -0,0 +1,93 @@
+//! Usage accounting for streaming responses
+//!
+//! Every call gets a [`UsageRecorder`] in its [`RequestContext`]. Handlers
+//! add what they consume to it, including from the streams they return,
+//! and the router sends the total in a USAGE frame just before END_STREAM:
+//!
+//! ```ignore
+//! let usage = RequestContext::current().unwrap_or_default().usage().clone();
+//! usage.record_input(prompt_tokens);
+//! let tokens = generate(prompt).inspect(move |_| usage.record_output(1));
+//! Ok(RpcResponse::streaming(tokens))
+//! ```
+//!
+//! Nothing is sent for calls that record no usage. Pre-framed responses
+//! supply their own trailer with [`Frame::usage`].
+//!
+//! [`RequestContext`]: crate::context::RequestContext
+//! [`Frame::usage`]: quill_core::Frame::usage
+
+use bytes::Bytes;
+use quill_core::{Frame, Usage};
+use std::sync::{Arc, Mutex};
+
+/// Accumulates the usage of one call
+///
+/// Cheap to clone; clones add to the same total.
+#[derive(Debug, Clone, Default)]
+pub struct UsageRecorder {
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl UsageRecorder {
+    /// Add input units (e.g. prompt tokens)
+    pub fn record_input(&self, tokens: u64) {
+        self.usage.lock().unwrap().input_tokens += tokens;
+    }
+
+    /// Add output units (e.g. generated tokens)
+    pub fn record_output(&self, tokens: u64) {
+        self.usage.lock().unwrap().output_tokens += tokens;
+    }
+
+    /// Add compute time, in milliseconds
+    pub fn record_compute_ms(&self, millis: u64) {
+        self.usage.lock().unwrap().compute_ms += millis;
+    }
+
+    /// Add a usage report
+    pub fn record(&self, usage: &Usage) {
+        self.usage.lock().unwrap().add(usage);
+    }
+
+    /// Usage recorded so far
+    pub fn usage(&self) -> Usage {
+        *self.usage.lock().unwrap()
+    }
+
+    /// USAGE frame with the total, `None` if nothing was recorded
+    pub(crate) fn trailer(&self) -> Option<Frame> {
+        let usage = self.usage();
+        if usage.is_empty() {
+            return None;
+        }
+        match usage.to_json() {
+            Ok(json) => Some(Frame::usage(Bytes::from(json))),
+            Err(e) => {
+                tracing::warn!("Failed to encode usage trailer: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_the_total() {
+        let recorder = UsageRecorder::default();
+        assert!(recorder.trailer().is_none());
+
+        let clone = recorder.clone();
+        recorder.record_input(10);
+        clone.record_output(3);
+        clone.record(&Usage::new(0, 2));
+        assert_eq!(recorder.usage(), Usage::new(10, 5));
+
+        let trailer = recorder.trailer().unwrap();
+        assert!(trailer.flags.is_usage());
+        assert_eq!(Usage::from_json(&trailer.payload).unwrap(), Usage::new(10, 5));
+    }
+}
@@ -0,0 +1,137 @@
+//! End-to-end tests for batch RPCs
+
+use bytes::Bytes;
+use http::{HeaderName, HeaderValue};
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{BatchEntry, Metadata, ProblemDetails, QuillError, TIMEOUT_HEADER};
+use quill_server::{BatchRpcConfig, QuillServer, RequestContext, RpcResponse, RpcRouter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn spawn(batch: Option<BatchRpcConfig>, in_flight: Arc<(AtomicUsize, AtomicUsize)>) -> QuillClient {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Echo/Echo", |req: Bytes| async move { Ok(req) });
+    router.register_unary("test.Echo/Slow", move |req: Bytes| {
+        let in_flight = Arc::clone(&in_flight);
+        async move {
+            let (current, peak) = &*in_flight;
+            let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            current.fetch_sub(1, Ordering::SeqCst);
+            Ok(req)
+        }
+    });
+    router.register_unary("test.Echo/Fail", |_req: Bytes| async move {
+        Err::<Bytes, _>(QuillError::ProblemDetails(ProblemDetails::new(
+            http::StatusCode::UNPROCESSABLE_ENTITY,
+            "Rejected",
+        )))
+    });
+    router.register_unary("test.Echo/Context", |_req: Bytes| async move {
+        let ctx = RequestContext::current().expect("batch entries run in a request context");
+        let request_id = ctx.metadata().get("x-request-id").unwrap_or_default();
+        Ok(Bytes::from(format!("{} {}", ctx.method(), request_id)))
+    });
+    router.register_with_setup(
+        "test.Echo/Prepared",
+        |ctx: RequestContext| async move { Ok(ctx.method().to_string()) },
+        |method: String, req: Bytes| async move {
+            Ok(RpcResponse::unary(Bytes::from(format!("{} {}", method, String::from_utf8_lossy(&req)))))
+        },
+    );
+    router.register_unary("test.Echo/Hang", |req: Bytes| async move {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(req)
+    });
+    router.register("test.Echo/Stream", |_req: Bytes| async move {
+        Ok(RpcResponse::streaming(tokio_stream::iter(vec![Ok(Bytes::new())])))
+    });
+    if let Some(config) = batch {
+        router.enable_batch(config);
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_batch_returns_per_entry_results() {
+    let client = spawn(Some(BatchRpcConfig::default()), Default::default()).await;
+
+    let entries = vec![
+        BatchEntry::new("test.Echo", "Echo", Bytes::from_static(b"one")),
+        BatchEntry::new("test.Echo", "Fail", Bytes::new()),
+        BatchEntry::new("test.Echo", "Missing", Bytes::new()),
+        BatchEntry::new("test.Echo", "Stream", Bytes::new()),
+        BatchEntry::new("test.Echo", "Echo", Bytes::from_static(b"two")),
+    ];
+    let results = client.call_batch(&entries).await.unwrap();
+
+    let statuses: Vec<u16> = results.iter().map(|result| result.status).collect();
+    assert_eq!(statuses, vec![200, 422, 404, 400, 200]);
+    assert_eq!(results[0].payload, Bytes::from_static(b"one"));
+    assert_eq!(results[4].payload, Bytes::from_static(b"two"));
+    assert_eq!(results[1].clone().into_result().unwrap_err().title, "Rejected");
+}
+
+#[tokio::test]
+async fn test_batch_respects_concurrency_cap() {
+    let in_flight = Arc::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+    let client = spawn(
+        Some(BatchRpcConfig::default().max_concurrency(3)),
+        Arc::clone(&in_flight),
+    )
+    .await;
+
+    let entries: Vec<_> = (0..9u8)
+        .map(|i| BatchEntry::new("test.Echo", "Slow", Bytes::from(vec![i])))
+        .collect();
+    let results = client.call_batch(&entries).await.unwrap();
+
+    for (i, result) in results.iter().enumerate() {
+        assert_eq!(result.payload, Bytes::from(vec![i as u8]));
+    }
+    assert_eq!(in_flight.1.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_batch_limits_and_disabled_batching() {
+    let client = spawn(Some(BatchRpcConfig::default().max_entries(2)), Default::default()).await;
+    let entries = vec![BatchEntry::new("test.Echo", "Echo", Bytes::new()); 3];
+    match client.call_batch(&entries).await {
+        Err(QuillError::ProblemDetails(pd)) => assert_eq!(pd.status, 413),
+        other => panic!("expected 413, got {:?}", other),
+    }
+
+    let client = spawn(None, Default::default()).await;
+    match client.call_batch(&entries[..1]).await {
+        Err(QuillError::ProblemDetails(pd)) => assert_eq!(pd.status, 404),
+        other => panic!("expected 404, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_batch_entries_run_in_call_context() {
+    let client = spawn(Some(BatchRpcConfig::default()), Default::default()).await;
+
+    let entries = vec![
+        BatchEntry::new("test.Echo", "Context", Bytes::new()),
+        BatchEntry::new("test.Echo", "Prepared", Bytes::from_static(b"payload")),
+        BatchEntry::new("test.Echo", "Hang", Bytes::new()),
+    ];
+    let options = RequestOptions::new()
+        .metadata(Metadata::new().with("x-request-id", "req-42").unwrap())
+        .header(HeaderName::from_static(TIMEOUT_HEADER), HeaderValue::from_static("200"));
+    let results = client.call_batch_with_options(&entries, options).await.unwrap();
+
+    assert_eq!(results[0].payload, Bytes::from_static(b"test.Echo/Context req-42"));
+    assert_eq!(results[1].payload, Bytes::from_static(b"test.Echo/Prepared payload"));
+    // The slow entry is cut off at the batch's deadline
+    assert_eq!(results[2].status, 504);
+}
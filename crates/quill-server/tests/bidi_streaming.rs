@@ -0,0 +1,72 @@
+//! End-to-end tests for incremental bidirectional streaming
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::QuillError;
+use quill_server::router::RequestStream;
+use quill_server::{QuillServer, RpcResponse, RpcRouter};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+
+async fn spawn() -> SocketAddr {
+    let mut router = RpcRouter::new();
+    router.register_bidi_streaming("test.Chat/Echo", |requests: RequestStream| async move {
+        Ok(RpcResponse::streaming(requests.map(|message| {
+            message.map(|message| Bytes::from([b"echo: ", &message[..]].concat()))
+        })))
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    addr
+}
+
+/// Send each message only after the reply to the previous one arrived
+async fn converse(client: QuillClient) {
+    let (tx, rx) = mpsc::channel::<Result<Bytes, QuillError>>(1);
+    tx.send(Ok(Bytes::from("hello"))).await.unwrap();
+
+    let mut responses = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.call_bidi_streaming("test.Chat", "Echo", Box::pin(ReceiverStream::new(rx))),
+    )
+    .await
+    .expect("response must not wait for the request stream to end")
+    .unwrap();
+
+    for next in ["second", "third"] {
+        let reply = tokio::time::timeout(Duration::from_secs(5), responses.next())
+            .await
+            .expect("reply must arrive while the request stream is open");
+        assert!(reply.unwrap().unwrap().starts_with(b"echo: "));
+        tx.send(Ok(Bytes::from(next))).await.unwrap();
+    }
+    drop(tx);
+
+    let rest: Vec<_> = responses.collect().await;
+    assert_eq!(rest.len(), 1);
+    assert_eq!(rest[0].as_ref().unwrap(), &Bytes::from("echo: third"));
+}
+
+#[tokio::test]
+async fn test_bidi_interleaves_over_http1() {
+    let addr = spawn().await;
+    converse(QuillClient::new(format!("http://{}", addr))).await;
+}
+
+#[tokio::test]
+async fn test_bidi_interleaves_over_http2() {
+    let addr = spawn().await;
+    let client = QuillClient::builder()
+        .base_url(format!("http://{}", addr))
+        .http2_only()
+        .build()
+        .unwrap();
+    converse(client).await;
+}
@@ -0,0 +1,97 @@
+//! End-to-end tests for call setup running before the request body arrives
+
+use bytes::Bytes;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{Metadata, QuillError};
+use quill_server::{QuillServer, RequestContext, RpcResponse, RpcRouter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+async fn spawn(setup_started: Arc<Notify>) -> QuillClient {
+    let mut router = RpcRouter::new();
+    router.register_with_setup(
+        "test.Chat/Complete",
+        move |ctx: RequestContext| {
+            let started = Arc::clone(&setup_started);
+            async move {
+                started.notify_one();
+                let user = ctx.metadata().get("x-user").unwrap_or("anonymous").to_string();
+                Ok::<_, QuillError>(format!("session:{}", user))
+            }
+        },
+        |session: String, req: Bytes| async move {
+            let mut response = session.into_bytes();
+            response.push(b'|');
+            response.extend_from_slice(&req);
+            Ok(RpcResponse::Unary(Bytes::from(response)))
+        },
+    );
+    router.register_with_setup(
+        "test.Chat/Denied",
+        |_ctx: RequestContext| async move {
+            Err::<(), _>(QuillError::Rpc("not authorized".to_string()))
+        },
+        |_: (), _req: Bytes| async move { Ok(RpcResponse::Unary(Bytes::new())) },
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::builder().base_url(format!("http://{}", addr)).http2_only().build().unwrap()
+}
+
+#[tokio::test]
+async fn test_setup_runs_before_deferred_request_is_sent() {
+    let setup_started = Arc::new(Notify::new());
+    let client = spawn(Arc::clone(&setup_started)).await;
+
+    // The request is only produced once the server started the call setup,
+    // which needs the headers to have been sent ahead of the body
+    let request = async move {
+        setup_started.notified().await;
+        Ok(Bytes::from_static(b"hello"))
+    };
+    let metadata = Metadata::new().with("x-user", "ada").unwrap();
+    let options = RequestOptions::new().metadata(metadata);
+    let response = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.call_deferred("test.Chat", "Complete", request, options),
+    )
+    .await
+    .expect("setup did not start before the request body")
+    .unwrap();
+    assert_eq!(response, Bytes::from_static(b"session:ada|hello"));
+}
+
+#[tokio::test]
+async fn test_setup_runs_for_regular_calls() {
+    let client = spawn(Arc::new(Notify::new())).await;
+
+    let response = client.call("test.Chat", "Complete", Bytes::from_static(b"hi")).await.unwrap();
+    assert_eq!(response, Bytes::from_static(b"session:anonymous|hi"));
+}
+
+#[tokio::test]
+async fn test_failed_setup_fails_the_call() {
+    let client = spawn(Arc::new(Notify::new())).await;
+    let produced = Arc::new(Mutex::new(false));
+
+    let flag = Arc::clone(&produced);
+    let request = async move {
+        // Never finishes producing the request
+        *flag.lock().unwrap() = true;
+        std::future::pending::<Result<Bytes, QuillError>>().await
+    };
+    let error = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.call_deferred("test.Chat", "Denied", request, RequestOptions::new()),
+    )
+    .await
+    .expect("failed setup waited for the request")
+    .unwrap_err();
+    assert!(error.to_string().contains("not authorized"), "{}", error);
+    assert!(*produced.lock().unwrap());
+}
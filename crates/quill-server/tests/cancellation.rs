@@ -0,0 +1,303 @@
+//! End-to-end tests for client cancellation of in-flight calls
+
+use bytes::Bytes;
+use quill_client::{CancelToken, QuillClient, RequestOptions, DEFAULT_CANCEL_ACK_TIMEOUT};
+use quill_core::QuillError;
+use quill_server::router::RequestStream;
+use quill_server::{QuillServer, RequestContext, RpcResponse, RpcRouter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+
+/// Sets its flag when the handler holding it is dropped
+struct Released(Arc<Mutex<bool>>);
+
+impl Drop for Released {
+    fn drop(&mut self) {
+        *self.0.lock().unwrap() = true;
+    }
+}
+
+#[derive(Clone, Default)]
+struct Observed {
+    unary_released: Arc<Mutex<bool>>,
+    stream_released: Arc<Mutex<bool>>,
+    request_errors: Arc<Mutex<Vec<QuillError>>>,
+    gpu_released: Arc<Mutex<bool>>,
+}
+
+async fn spawn(observed: Observed) -> QuillClient {
+    spawn_with(observed, DEFAULT_CANCEL_ACK_TIMEOUT).await
+}
+
+async fn spawn_with(observed: Observed, cancel_ack_timeout: Duration) -> QuillClient {
+    let mut router = RpcRouter::new();
+    let released = observed.unary_released.clone();
+    router.register_unary("test.Slow/Wait", move |_req: Bytes| {
+        let held = Released(Arc::clone(&released));
+        async move {
+            let _held = held;
+            std::future::pending::<()>().await;
+            Ok(Bytes::new())
+        }
+    });
+    let released = observed.stream_released.clone();
+    router.register("test.Feed/Watch", move |_req: Bytes| {
+        let held = Released(Arc::clone(&released));
+        async move {
+            let first = tokio_stream::iter(vec![Ok::<_, QuillError>(Bytes::from_static(b"first"))]);
+            let rest = tokio_stream::pending().map(move |item| {
+                let _ = &held;
+                item
+            });
+            Ok(RpcResponse::streaming(first.chain(rest)))
+        }
+    });
+    let errors = observed.request_errors.clone();
+    router.register_bidi_streaming("test.Chat/Echo", move |mut requests: RequestStream| {
+        let errors = Arc::clone(&errors);
+        async move {
+            let (tx, rx) = mpsc::channel(4);
+            tokio::spawn(async move {
+                while let Some(message) = requests.next().await {
+                    match message {
+                        Ok(message) => {
+                            let _ = tx.send(Ok::<_, QuillError>(message)).await;
+                        }
+                        Err(e) => errors.lock().unwrap().push(e),
+                    }
+                }
+            });
+            Ok(RpcResponse::streaming(ReceiverStream::new(rx)))
+        }
+    });
+    // Keeps its response open and acknowledges a cancel once it released its memory
+    let released = observed.gpu_released.clone();
+    router.register_bidi_streaming("test.Gpu/Generate", move |mut requests: RequestStream| {
+        let released = Arc::clone(&released);
+        async move {
+            let cancel = RequestContext::current().unwrap().cancellation().clone();
+            // The cancel arrives on the request stream, so it must be read
+            tokio::spawn(async move { while requests.next().await.is_some() {} });
+            tokio::spawn(async move {
+                cancel.cancelled().await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                *released.lock().unwrap() = true;
+                cancel.acknowledge();
+            });
+            Ok(RpcResponse::streaming(tokio_stream::pending()))
+        }
+    });
+    // Never stops, so a cancel is never acknowledged
+    router.register_bidi_streaming("test.Stuck/Generate", |requests: RequestStream| async move {
+        let _requests = requests;
+        Ok(RpcResponse::streaming(tokio_stream::pending()))
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::builder()
+        .base_url(format!("http://{}", addr))
+        .http2_only()
+        .cancel_ack_timeout(cancel_ack_timeout)
+        .build()
+        .unwrap()
+}
+
+/// Open a bidirectional call cancelled by `token`, with an open request stream
+async fn open_bidi(
+    client: &QuillClient,
+    service: &str,
+    token: &CancelToken,
+) -> (
+    mpsc::Sender<Result<Bytes, QuillError>>,
+    std::pin::Pin<Box<dyn tokio_stream::Stream<Item = Result<Bytes, QuillError>> + Send>>,
+) {
+    let (tx, rx) = mpsc::channel::<Result<Bytes, QuillError>>(1);
+    let responses = client
+        .call_bidi_streaming_with_options(
+            service,
+            "Generate",
+            Box::pin(ReceiverStream::new(rx)),
+            RequestOptions::new().cancel_token(token.clone()),
+        )
+        .await
+        .unwrap();
+    (tx, responses)
+}
+
+/// Wait up to a second for `flag` to be set
+async fn wait_for(flag: &Mutex<bool>) -> bool {
+    for _ in 0..100 {
+        if *flag.lock().unwrap() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_cancel_unary_call() {
+    let observed = Observed::default();
+    let client = spawn(observed.clone()).await;
+    let token = CancelToken::new();
+
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+    let error = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.call_with_options(
+            "test.Slow",
+            "Wait",
+            Bytes::new(),
+            RequestOptions::new().cancel_token(token.clone()),
+        ),
+    )
+    .await
+    .expect("cancelled call did not resolve")
+    .unwrap_err();
+    assert!(error.is_cancelled(), "{:?}", error);
+    assert!(wait_for(&observed.unary_released).await, "handler was not stopped");
+
+    // A cancelled token fails new calls at once
+    let error = client
+        .call_with_options(
+            "test.Slow",
+            "Wait",
+            Bytes::new(),
+            RequestOptions::new().cancel_token(token),
+        )
+        .await
+        .unwrap_err();
+    assert!(error.is_cancelled());
+}
+
+#[tokio::test]
+async fn test_cancel_server_stream() {
+    let observed = Observed::default();
+    let client = spawn(observed.clone()).await;
+    let token = CancelToken::new();
+
+    let mut stream = client
+        .call_server_streaming_with_options(
+            "test.Feed",
+            "Watch",
+            Bytes::new(),
+            RequestOptions::new().cancel_token(token.clone()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"first"));
+
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+    let error = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("cancelled stream did not end")
+        .unwrap()
+        .unwrap_err();
+    assert!(error.is_cancelled(), "{:?}", error);
+    assert!(stream.next().await.is_none());
+    assert!(wait_for(&observed.stream_released).await, "handler stream was not dropped");
+}
+
+#[tokio::test]
+async fn test_cancel_sends_cancel_frame_to_bidi_handler() {
+    let observed = Observed::default();
+    let client = spawn(observed.clone()).await;
+    let token = CancelToken::new();
+    let (tx, rx) = mpsc::channel::<Result<Bytes, QuillError>>(1);
+    tx.send(Ok(Bytes::from("hello"))).await.unwrap();
+
+    let mut responses = client
+        .call_bidi_streaming_with_options(
+            "test.Chat",
+            "Echo",
+            Box::pin(ReceiverStream::new(rx)),
+            RequestOptions::new().cancel_token(token.clone()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(responses.next().await.unwrap().unwrap(), Bytes::from("hello"));
+
+    token.cancel();
+    let mut received = false;
+    for _ in 0..100 {
+        if !observed.request_errors.lock().unwrap().is_empty() {
+            received = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(received, "handler did not see the CANCEL frame");
+    assert!(observed.request_errors.lock().unwrap()[0].is_cancelled());
+    assert!(responses.next().await.unwrap().unwrap_err().is_cancelled());
+    drop(tx);
+}
+
+#[tokio::test]
+async fn test_bidi_cancel_is_acknowledged_when_handler_stream_ends() {
+    let observed = Observed::default();
+    let client = spawn(observed.clone()).await;
+    let token = CancelToken::new();
+    let (tx, rx) = mpsc::channel::<Result<Bytes, QuillError>>(1);
+    tx.send(Ok(Bytes::from("hello"))).await.unwrap();
+
+    let mut responses = client
+        .call_bidi_streaming_with_options(
+            "test.Chat",
+            "Echo",
+            Box::pin(ReceiverStream::new(rx)),
+            RequestOptions::new().cancel_token(token.clone()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(responses.next().await.unwrap().unwrap(), Bytes::from("hello"));
+
+    token.cancel();
+    assert!(responses.next().await.unwrap().unwrap_err().is_cancelled());
+    let acknowledged = tokio::time::timeout(Duration::from_secs(5), token.acknowledged()).await;
+    assert!(acknowledged.expect("acknowledgment did not resolve"));
+    assert_eq!(client.cancel_stats().acknowledged, 1);
+    drop(tx);
+}
+
+#[tokio::test]
+async fn test_bidi_cancel_is_acknowledged_after_handler_releases_resources() {
+    let observed = Observed::default();
+    let client = spawn(observed.clone()).await;
+    let token = CancelToken::new();
+    let (_tx, responses) = open_bidi(&client, "test.Gpu", &token).await;
+
+    token.cancel();
+    drop(responses);
+    let acknowledged = tokio::time::timeout(Duration::from_secs(5), token.acknowledged()).await;
+    assert!(acknowledged.expect("acknowledgment did not resolve"));
+    assert!(*observed.gpu_released.lock().unwrap(), "acknowledged before the handler released");
+}
+
+#[tokio::test]
+async fn test_unacknowledged_bidi_cancel_times_out() {
+    let client = spawn_with(Observed::default(), Duration::from_millis(100)).await;
+    let token = CancelToken::new();
+    let (_tx, mut responses) = open_bidi(&client, "test.Stuck", &token).await;
+
+    token.cancel();
+    assert!(responses.next().await.unwrap().unwrap_err().is_cancelled());
+    let acknowledged = tokio::time::timeout(Duration::from_secs(5), token.acknowledged()).await;
+    assert!(!acknowledged.expect("acknowledgment wait did not time out"));
+    let stats = client.cancel_stats();
+    assert_eq!((stats.acknowledged, stats.unacknowledged, stats.timed_out), (0, 1, 1));
+}
@@ -0,0 +1,112 @@
+//! End-to-end tests for channel-backed streaming responses
+
+use bytes::Bytes;
+use http::StatusCode;
+use quill_client::{stream_to_channel, QuillClient};
+use quill_core::{ProblemDetails, QuillError};
+use quill_server::{channel_to_response, QuillServer, RpcResponse, RpcRouter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+#[tokio::test]
+async fn test_client_disconnect_cancels_producer() {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let observed = Arc::clone(&cancelled);
+
+    let mut router = RpcRouter::new();
+    router.register("test.Ticker/Watch", move |_req: Bytes| {
+        let observed = Arc::clone(&observed);
+        async move {
+            Ok(RpcResponse::spawn_producer(1, move |tx, signal| async move {
+                loop {
+                    tokio::select! {
+                        _ = signal.cancelled() => break,
+                        _ = tokio::time::sleep(Duration::from_millis(10)) => {}
+                    }
+                    if tx.send(Ok(Bytes::from_static(b"tick"))).await.is_err() {
+                        break;
+                    }
+                }
+                observed.store(signal.is_cancelled(), Ordering::SeqCst);
+            }))
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    let client = QuillClient::new(format!("http://{}", addr));
+
+    let mut stream = client
+        .call_server_streaming("test.Ticker", "Watch", Bytes::new())
+        .await
+        .unwrap();
+    for _ in 0..3 {
+        assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"tick"));
+    }
+    drop(stream);
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while !cancelled.load(Ordering::SeqCst) {
+        assert!(tokio::time::Instant::now() < deadline, "producer was not cancelled");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_channel_bridges_propagate_errors_and_cancellation() {
+    let producer_stopped = Arc::new(AtomicBool::new(false));
+    let observed = Arc::clone(&producer_stopped);
+
+    let mut router = RpcRouter::new();
+    router.register("test.Feed/Fail", |_req: Bytes| async move {
+        let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, QuillError>>(1);
+        tokio::spawn(async move {
+            let _ = tx.send(Ok(Bytes::from_static(b"first"))).await;
+            let problem = ProblemDetails::new(StatusCode::CONFLICT, "Feed reset");
+            let _ = tx.send(Err(QuillError::ProblemDetails(problem))).await;
+        });
+        Ok(channel_to_response(rx))
+    });
+    router.register("test.Feed/Endless", move |_req: Bytes| {
+        let observed = Arc::clone(&observed);
+        async move {
+            let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, QuillError>>(1);
+            tokio::spawn(async move {
+                while tx.send(Ok(Bytes::from_static(b"item"))).await.is_ok() {}
+                observed.store(true, Ordering::SeqCst);
+            });
+            Ok(channel_to_response(rx))
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    let client = QuillClient::new(format!("http://{}", addr));
+
+    // The producer's error reaches the channel on the client and ends it
+    let stream = client.call_server_streaming("test.Feed", "Fail", Bytes::new()).await.unwrap();
+    let mut rx = stream_to_channel(stream, 1);
+    assert_eq!(rx.recv().await.unwrap().unwrap(), Bytes::from_static(b"first"));
+    assert!(rx.recv().await.unwrap().is_err());
+    assert!(rx.recv().await.is_none());
+
+    // Dropping the client's receiver cancels the call and stops the producer
+    let stream = client.call_server_streaming("test.Feed", "Endless", Bytes::new()).await.unwrap();
+    let mut rx = stream_to_channel(stream, 1);
+    assert_eq!(rx.recv().await.unwrap().unwrap(), Bytes::from_static(b"item"));
+    drop(rx);
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while !producer_stopped.load(Ordering::SeqCst) {
+        assert!(tokio::time::Instant::now() < deadline, "producer was not stopped");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
@@ -0,0 +1,96 @@
+//! End-to-end tests for chunked upload of large unary requests
+
+use bytes::Bytes;
+use quill_client::{ChunkedUpload, QuillClient, RequestOptions};
+use quill_core::QuillError;
+use quill_server::{ChunkedUploadConfig, QuillServer, RpcRouter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Serve a handler that checksums its request; returns the client and the handler call count
+async fn spawn(upload: Option<ChunkedUploadConfig>) -> (QuillClient, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = Arc::clone(&calls);
+
+    let mut router = RpcRouter::new();
+    if let Some(config) = upload {
+        router.enable_chunked_upload(config);
+    }
+    router.register_unary("test.Images/Upload", move |req: Bytes| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        async move {
+            let sum: u64 = req.iter().enumerate().map(|(i, b)| i as u64 * *b as u64).sum();
+            Ok(Bytes::from(format!("{}:{}", req.len(), sum)))
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    (QuillClient::new(format!("http://{}", addr)), calls)
+}
+
+fn image(len: usize) -> Bytes {
+    Bytes::from((0..len).map(|i| (i % 251) as u8).collect::<Vec<_>>())
+}
+
+fn expected(body: &Bytes) -> Bytes {
+    let sum: u64 = body.iter().enumerate().map(|(i, b)| i as u64 * *b as u64).sum();
+    Bytes::from(format!("{}:{}", body.len(), sum))
+}
+
+#[tokio::test]
+async fn test_large_request_is_reassembled_before_handler() {
+    let (client, calls) = spawn(Some(ChunkedUploadConfig::default())).await;
+    let body = image(100_000);
+
+    let options = RequestOptions::new().chunked_upload(ChunkedUpload::new().chunk_size(8 * 1024).parallelism(4));
+    let response = client.call_with_options("test.Images", "Upload", body.clone(), options).await.unwrap();
+
+    assert_eq!(response, expected(&body));
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_server_without_support_gets_whole_request() {
+    let (client, calls) = spawn(None).await;
+    let body = image(50_000);
+
+    let options = RequestOptions::new().chunked_upload(ChunkedUpload::new().chunk_size(4 * 1024));
+    let response = client.call_with_options("test.Images", "Upload", body.clone(), options).await.unwrap();
+
+    assert_eq!(response, expected(&body));
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_upload_over_server_limit_is_sent_whole() {
+    // The advertised limit is below the request size, so the client does not split
+    let (client, calls) = spawn(Some(ChunkedUploadConfig::default().max_chunks(2))).await;
+    let body = image(20_000);
+
+    let options = RequestOptions::new().chunked_upload(ChunkedUpload::new().chunk_size(4 * 1024));
+    let response = client.call_with_options("test.Images", "Upload", body.clone(), options).await.unwrap();
+
+    assert_eq!(response, expected(&body));
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_unsupported_chunk_is_rejected() {
+    let (client, _) = spawn(None).await;
+    let options = RequestOptions::new()
+        .header(
+            http::HeaderName::from_static(quill_core::UPLOAD_MANIFEST_HEADER),
+            http::HeaderValue::from_static("id=x; size=8; chunk-size=4; chunks=2"),
+        )
+        .header(http::HeaderName::from_static(quill_core::UPLOAD_CHUNK_HEADER), http::HeaderValue::from(0));
+
+    let result = client.call_with_options("test.Images", "Upload", Bytes::from_static(b"abcd"), options).await;
+    match result {
+        Err(QuillError::ProblemDetails(pd)) => assert_eq!(pd.status, 400),
+        other => panic!("expected a 400, got {:?}", other),
+    }
+}
@@ -0,0 +1,122 @@
+//! End-to-end tests for client interceptors
+
+use bytes::Bytes;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use quill_client::{CallInfo, ClientInterceptor, InterceptFuture, QuillClient};
+use quill_core::QuillError;
+use quill_server::{QuillServer, RpcRouter};
+use std::sync::{Arc, Mutex};
+
+/// Adds a bearer token, failing calls when none is configured
+struct Auth(Option<&'static str>);
+
+impl ClientInterceptor for Auth {
+    fn on_request<'a>(
+        &'a self,
+        _call: &'a mut CallInfo,
+        headers: &'a mut HeaderMap,
+    ) -> InterceptFuture<'a, Result<(), QuillError>> {
+        Box::pin(async move {
+            let token = self.0.ok_or_else(|| QuillError::Rpc("no credentials".to_string()))?;
+            headers.insert(http::header::AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+            Ok(())
+        })
+    }
+}
+
+/// Records the outcome of every call and the credentials it was sent with
+#[derive(Clone, Default)]
+struct Metrics(Arc<Mutex<Vec<String>>>);
+
+impl ClientInterceptor for Metrics {
+    fn on_request<'a>(
+        &'a self,
+        _call: &'a mut CallInfo,
+        headers: &'a mut HeaderMap,
+    ) -> InterceptFuture<'a, Result<(), QuillError>> {
+        Box::pin(async move {
+            if let Some(auth) = headers.get(http::header::AUTHORIZATION) {
+                self.0.lock().unwrap().push(auth.to_str().unwrap().to_string());
+            }
+            Ok(())
+        })
+    }
+
+    fn on_response<'a>(
+        &'a self,
+        call: &'a CallInfo,
+        status: StatusCode,
+        _headers: &'a HeaderMap,
+    ) -> InterceptFuture<'a, ()> {
+        Box::pin(async move {
+            let (_, method) = call.rpc().unwrap();
+            self.0.lock().unwrap().push(format!("{} {}", method, status.as_u16()));
+        })
+    }
+
+    fn on_error<'a>(&'a self, call: &'a CallInfo, _error: &'a QuillError) -> InterceptFuture<'a, ()> {
+        Box::pin(async move {
+            let (_, method) = call.rpc().unwrap();
+            self.0.lock().unwrap().push(format!("{} failed", method));
+        })
+    }
+}
+
+fn router() -> RpcRouter {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Auth/WhoAmI", |_req: Bytes| async move { Ok(Bytes::new()) });
+    router
+}
+
+async fn spawn() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router()).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_interceptors_see_every_call() {
+    let url = spawn().await;
+    let metrics = Metrics::default();
+    let client = QuillClient::builder()
+        .base_url(&url)
+        .interceptor(Auth(Some("secret")))
+        .interceptor(metrics.clone())
+        .build()
+        .unwrap();
+
+    client.call("test.Auth", "WhoAmI", Bytes::new()).await.unwrap();
+    assert!(client.call("test.Auth", "Missing", Bytes::new()).await.is_err());
+    assert_eq!(
+        *metrics.0.lock().unwrap(),
+        vec!["Bearer secret", "WhoAmI 200", "Bearer secret", "Missing 404"]
+    );
+
+    // Transport errors reach on_error
+    let offline = QuillClient::builder()
+        .base_url("http://127.0.0.1:1")
+        .interceptor(metrics.clone())
+        .build()
+        .unwrap();
+    assert!(offline.call("test.Auth", "WhoAmI", Bytes::new()).await.is_err());
+    assert_eq!(metrics.0.lock().unwrap().last().unwrap(), "WhoAmI failed");
+}
+
+#[tokio::test]
+async fn test_request_hook_error_aborts_call() {
+    let url = spawn().await;
+    let metrics = Metrics::default();
+    let client = QuillClient::builder()
+        .base_url(&url)
+        .interceptor(metrics.clone())
+        .interceptor(Auth(None))
+        .build()
+        .unwrap();
+
+    let error = client.call("test.Auth", "WhoAmI", Bytes::new()).await.unwrap_err();
+    assert!(matches!(error, QuillError::Rpc(ref message) if message == "no credentials"));
+    assert!(metrics.0.lock().unwrap().is_empty());
+}
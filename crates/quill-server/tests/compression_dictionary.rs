@@ -0,0 +1,121 @@
+//! End-to-end tests for dictionary compression of unary calls
+
+use bytes::Bytes;
+use http::{HeaderName, HeaderValue, Request};
+use http_body_util::{BodyExt, Full};
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{
+    CompressionDictionary, DictionaryCapability, QuillError, DEFAULT_DICTIONARY_SIZE,
+    DICTIONARY_CAPABILITY_HEADER, DICTIONARY_HEADER, DICTIONARY_PATH,
+};
+use quill_server::{DictionaryCompression, QuillServer, RpcRouter};
+use std::sync::{Arc, Mutex};
+
+fn message(i: usize) -> Bytes {
+    Bytes::from(format!(
+        r#"{{"request_id":"req-{}","model":"small-{}","temperature":0.{},"stream":false}}"#,
+        i,
+        i % 5,
+        i % 10
+    ))
+}
+
+fn dictionary() -> CompressionDictionary {
+    let samples: Vec<Bytes> = (0..1000).map(message).collect();
+    CompressionDictionary::train(7, &samples, DEFAULT_DICTIONARY_SIZE).unwrap()
+}
+
+/// Router with an echo handler that records the bodies it sees
+fn router(dictionary: &CompressionDictionary) -> (RpcRouter, Arc<Mutex<Vec<Bytes>>>) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&seen);
+
+    let mut router = RpcRouter::new();
+    router.enable_compression_dictionary(DictionaryCompression::new(dictionary.clone()));
+    router.register_unary("llm.Completions/Create", move |req: Bytes| {
+        recorded.lock().unwrap().push(req.clone());
+        async move { Ok(req) }
+    });
+    (router, seen)
+}
+
+async fn spawn(router: RpcRouter) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_router_decodes_and_encodes_with_dictionary() {
+    let dictionary = dictionary();
+    let (router, seen) = router(&dictionary);
+
+    // The fetch method serves the advertised dictionary
+    let fetch = Request::post(format!("/{}", DICTIONARY_PATH))
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    let response = router.route(fetch).await;
+    let capability = response.headers()[DICTIONARY_CAPABILITY_HEADER].to_str().unwrap();
+    let capability = DictionaryCapability::from_header_value(capability).unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(CompressionDictionary::verified(&capability, body).unwrap(), dictionary);
+
+    let request = Request::post("/llm.Completions/Create")
+        .header("content-encoding", "zstd")
+        .header("accept-encoding", "zstd")
+        .header(DICTIONARY_HEADER, "7")
+        .body(Full::new(dictionary.compress(&message(4242), 3).unwrap()))
+        .unwrap();
+    let response = router.route(request).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-encoding"], "zstd");
+    assert_eq!(response.headers()[DICTIONARY_HEADER], "7");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(dictionary.decompress(&body).unwrap(), message(4242));
+    assert_eq!(seen.lock().unwrap().as_slice(), &[message(4242)]);
+
+    let unknown = Request::post("/llm.Completions/Create")
+        .header("content-encoding", "zstd")
+        .header(DICTIONARY_HEADER, "8")
+        .body(Full::new(dictionary.compress(&message(1), 3).unwrap()))
+        .unwrap();
+    assert_eq!(router.route(unknown).await.status(), 400);
+}
+
+#[tokio::test]
+async fn test_client_fetches_and_uses_dictionary() {
+    let dictionary = dictionary();
+    let (router, seen) = router(&dictionary);
+    let url = spawn(router).await;
+    let client = QuillClient::builder()
+        .base_url(&url)
+        .enable_compression(true)
+        .compression_dictionary(true)
+        .build()
+        .unwrap();
+
+    // The first call learns about the dictionary, later ones fetch and use it
+    for i in 0..3 {
+        let response = client
+            .call("llm.Completions", "Create", message(i))
+            .await
+            .unwrap();
+        assert_eq!(response, message(i));
+    }
+    assert_eq!(seen.lock().unwrap().as_slice(), &[message(0), message(1), message(2)]);
+
+    // Requests naming a dictionary the server does not know are rejected
+    let options = RequestOptions::new().header(
+        HeaderName::from_static(DICTIONARY_HEADER),
+        HeaderValue::from_static("99"),
+    );
+    let plain = QuillClient::builder().base_url(&url).enable_compression(true).build().unwrap();
+    let error = plain
+        .call_with_options("llm.Completions", "Create", message(3), options)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, QuillError::ProblemDetails(pd) if pd.status == 400));
+}
@@ -0,0 +1,70 @@
+//! End-to-end tests for read-your-writes consistency tokens
+
+use bytes::Bytes;
+use quill_client::{ConsistencyTokens, QuillClient};
+use quill_core::ConsistencyToken;
+use quill_server::{AppliedPosition, QuillServer, RequestContext, RpcRouter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A log whose writes return their index and whose reads wait for the caller's token
+fn router(log: Arc<AtomicU64>, applied: AppliedPosition) -> RpcRouter {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Log/Append", move |_req: Bytes| {
+        let log = Arc::clone(&log);
+        async move {
+            let ctx = RequestContext::current().expect("handlers run in a request context");
+            let index = log.fetch_add(1, Ordering::SeqCst) + 1;
+            ctx.consistency().set_response_token(ConsistencyToken::new(index));
+            Ok(Bytes::new())
+        }
+    });
+    router.register_unary("test.Log/Read", move |_req: Bytes| {
+        let applied = applied.clone();
+        async move {
+            let ctx = RequestContext::current().expect("handlers run in a request context");
+            applied.wait_for(&ctx).await?;
+            let requested = ctx.consistency().requested().map(|token| token.to_string());
+            Ok(Bytes::from(requested.unwrap_or_default()))
+        }
+    });
+    router
+}
+
+async fn spawn(router: RpcRouter) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_reads_wait_for_the_callers_writes() {
+    let applied = AppliedPosition::new(0);
+    let url = spawn(router(Arc::new(AtomicU64::new(0)), applied.clone())).await;
+    let tokens = ConsistencyTokens::new();
+    let client = QuillClient::builder()
+        .base_url(&url)
+        .interceptor(tokens.clone())
+        .build()
+        .unwrap();
+
+    // Reads before any write carry no token
+    let read = client.call("test.Log", "Read", Bytes::new()).await.unwrap();
+    assert!(read.is_empty());
+
+    client.call("test.Log", "Append", Bytes::new()).await.unwrap();
+    client.call("test.Log", "Append", Bytes::new()).await.unwrap();
+    assert_eq!(tokens.latest(), Some(ConsistencyToken::new(2)));
+
+    // The read is held until the replica applies the second write
+    let read = tokio::spawn(async move { client.call("test.Log", "Read", Bytes::new()).await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!read.is_finished());
+    applied.advance(2);
+    let read = tokio::time::timeout(Duration::from_secs(5), read).await.unwrap().unwrap().unwrap();
+    assert_eq!(read, Bytes::from("2"));
+}
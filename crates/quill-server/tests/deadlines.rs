@@ -0,0 +1,104 @@
+//! End-to-end tests for deadline propagation
+
+use bytes::Bytes;
+use http::{HeaderName, HeaderValue};
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{QuillError, TIMEOUT_HEADER};
+use quill_server::{QuillServer, RequestContext, RpcRouter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// `Remaining` answers with the milliseconds left; `Work` takes two seconds
+/// and sets `finished` if it is not cancelled first
+fn router(finished: Arc<AtomicBool>) -> RpcRouter {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Slow/Remaining", |_req: Bytes| async move {
+        let ctx = RequestContext::current().expect("handlers run in a request context");
+        let remaining =
+            ctx.remaining().map(|left| left.as_millis().to_string()).unwrap_or_default();
+        Ok(Bytes::from(remaining))
+    });
+    router.register_unary("test.Slow/Work", move |_req: Bytes| {
+        let finished = finished.clone();
+        async move {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            finished.store(true, Ordering::SeqCst);
+            Ok(Bytes::new())
+        }
+    });
+    router
+}
+
+async fn spawn(finished: Arc<AtomicBool>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router(finished)).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_timeout_reaches_handler() {
+    let url = spawn(Arc::default()).await;
+    let client =
+        QuillClient::builder().base_url(&url).timeout(Duration::from_secs(5)).build().unwrap();
+
+    let remaining = client.call("test.Slow", "Remaining", Bytes::new()).await.unwrap();
+    let millis: u64 = std::str::from_utf8(&remaining).unwrap().parse().unwrap();
+    assert!(millis > 0 && millis <= 5000, "{}", millis);
+
+    // A per-call timeout overrides the client's
+    let options = RequestOptions::new().timeout(Duration::from_millis(800));
+    let remaining =
+        client.call_with_options("test.Slow", "Remaining", Bytes::new(), options).await.unwrap();
+    let millis: u64 = std::str::from_utf8(&remaining).unwrap().parse().unwrap();
+    assert!(millis <= 800, "{}", millis);
+
+    // Without a timeout the handler has no deadline
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+    let remaining = client.call("test.Slow", "Remaining", Bytes::new()).await.unwrap();
+    assert!(remaining.is_empty());
+}
+
+#[tokio::test]
+async fn test_client_cancels_at_deadline() {
+    let url = spawn(Arc::default()).await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+
+    let started = Instant::now();
+    let options = RequestOptions::new().timeout(Duration::from_millis(200));
+    let error =
+        client.call_with_options("test.Slow", "Work", Bytes::new(), options).await.unwrap_err();
+    assert!(error.is_deadline_exceeded(), "{:?}", error);
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_server_enforces_deadline() {
+    let finished = Arc::new(AtomicBool::new(false));
+    let url = spawn(finished.clone()).await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+
+    // Only the header is sent, so the client itself never times out
+    let options = RequestOptions::new()
+        .header(HeaderName::from_static(TIMEOUT_HEADER), HeaderValue::from_static("100"));
+    let started = Instant::now();
+    let error =
+        client.call_with_options("test.Slow", "Work", Bytes::new(), options).await.unwrap_err();
+    assert!(error.is_deadline_exceeded(), "{:?}", error);
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    // The handler was dropped rather than left running
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+    assert!(!finished.load(Ordering::SeqCst));
+
+    let options = RequestOptions::new()
+        .header(HeaderName::from_static(TIMEOUT_HEADER), HeaderValue::from_static("soon"));
+    let error = client
+        .call_with_options("test.Slow", "Remaining", Bytes::new(), options)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, QuillError::ProblemDetails(ref pd) if pd.status == 400), "{:?}", error);
+}
@@ -0,0 +1,79 @@
+//! End-to-end tests for the Problem Details `debug` extension
+
+use bytes::Bytes;
+use http::header::{HeaderName, HeaderValue};
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{ProblemDetails, QuillError};
+use quill_server::{DebugPolicy, QuillServer, RpcRouter, DEBUG_HEADER};
+
+async fn spawn(debug: Option<DebugPolicy>) -> QuillClient {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Debug/Fail", |_req: Bytes| async move {
+        Err(QuillError::Rpc("lookup failed: password=hunter2".to_string()))
+    });
+    router.register_unary("test.Debug/Panic", |req: Bytes| async move {
+        if req.is_empty() {
+            panic!("empty request");
+        }
+        Ok(req)
+    });
+    if let Some(policy) = debug {
+        router.set_debug_policy(policy);
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+fn entitled(token: &'static str) -> RequestOptions {
+    RequestOptions::new().header(HeaderName::from_static(DEBUG_HEADER), HeaderValue::from_static(token))
+}
+
+async fn problem(client: &QuillClient, method: &str, options: RequestOptions) -> ProblemDetails {
+    match client.call_with_options("test.Debug", method, Bytes::new(), options).await {
+        Err(QuillError::ProblemDetails(pd)) => pd,
+        other => panic!("expected Problem Details, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_entitled_caller_gets_redacted_error_context() {
+    let client = spawn(Some(DebugPolicy::with_token("staging"))).await;
+
+    let pd = problem(&client, "Fail", entitled("staging")).await;
+    assert_eq!(pd.status, 500);
+    let debug = pd.debug.expect("debug context");
+    assert_eq!(debug.kind, "error");
+    assert!(debug.message.contains("password=[redacted]"));
+    assert!(!debug.message.contains("hunter2"));
+}
+
+#[tokio::test]
+async fn test_panic_is_reported_with_debug_context() {
+    let client = spawn(Some(DebugPolicy::with_token("staging"))).await;
+
+    let pd = problem(&client, "Panic", entitled("staging")).await;
+    assert_eq!(pd.status, 500);
+    assert_eq!(pd.detail.as_deref(), Some("Handler panicked"));
+    let debug = pd.debug.expect("debug context");
+    assert_eq!(debug.kind, "panic");
+    assert_eq!(debug.message, "empty request");
+
+    // The connection survives the panic
+    let echoed = client.call("test.Debug", "Panic", Bytes::from_static(b"ok")).await.unwrap();
+    assert_eq!(&echoed[..], b"ok");
+}
+
+#[tokio::test]
+async fn test_debug_context_withheld_without_entitlement() {
+    let client = spawn(Some(DebugPolicy::with_token("staging"))).await;
+    assert!(problem(&client, "Fail", RequestOptions::new()).await.debug.is_none());
+    assert!(problem(&client, "Panic", entitled("guess")).await.debug.is_none());
+
+    let client = spawn(None).await;
+    assert!(problem(&client, "Panic", entitled("staging")).await.debug.is_none());
+}
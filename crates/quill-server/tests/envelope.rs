@@ -0,0 +1,99 @@
+//! End-to-end tests for envelope encryption of sensitive requests
+
+use bytes::Bytes;
+use quill_client::{ChunkedUpload, EnvelopeEncryption, QuillClient, RequestOptions};
+use quill_core::{EnvelopeScope, KeyProvider, LocalKeyProvider, QuillError};
+use quill_server::{ChunkedUploadConfig, EnvelopeDecryption, QuillServer, RpcRouter};
+use std::sync::{Arc, Mutex};
+
+/// Serve an echo handler that records the bodies it sees
+async fn spawn(envelope: Option<EnvelopeDecryption>) -> (String, Arc<Mutex<Vec<Bytes>>>) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&seen);
+
+    let mut router = RpcRouter::new();
+    if let Some(config) = envelope {
+        router.enable_envelope_decryption(config);
+    }
+    router.enable_chunked_upload(ChunkedUploadConfig::default());
+    router.register_unary("bank.Accounts/Open", move |req: Bytes| {
+        recorded.lock().unwrap().push(req.clone());
+        async move { Ok(req) }
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    (format!("http://{}", addr), seen)
+}
+
+fn client(url: &str, provider: Arc<dyn KeyProvider>, scope: EnvelopeScope) -> QuillClient {
+    let envelope = EnvelopeEncryption::new(provider, "accounts").protect_method("bank.Accounts", "Open", scope);
+    QuillClient::builder()
+        .base_url(url)
+        .envelope_encryption(envelope)
+        .build()
+        .unwrap()
+}
+
+/// Field 1 = "alice", field 2 = SSN
+fn request() -> Bytes {
+    let mut msg = vec![0x0a, 5];
+    msg.extend_from_slice(b"alice");
+    msg.extend_from_slice(&[0x12, 11]);
+    msg.extend_from_slice(b"123-45-6789");
+    Bytes::from(msg)
+}
+
+#[tokio::test]
+async fn test_sealed_request_is_opened_for_handler() {
+    let provider: Arc<dyn KeyProvider> = Arc::new(LocalKeyProvider::new().with_random_key("accounts"));
+    let (url, seen) = spawn(Some(EnvelopeDecryption::new(Arc::clone(&provider)))).await;
+
+    for scope in [EnvelopeScope::Message, EnvelopeScope::Fields(vec![2])] {
+        let client = client(&url, Arc::clone(&provider), scope);
+        let response = client.call("bank.Accounts", "Open", request()).await.unwrap();
+        assert_eq!(response, request());
+    }
+    assert_eq!(*seen.lock().unwrap(), vec![request(), request()]);
+}
+
+#[tokio::test]
+async fn test_sealed_chunked_upload() {
+    let provider: Arc<dyn KeyProvider> = Arc::new(LocalKeyProvider::new().with_random_key("accounts"));
+    let (url, seen) = spawn(Some(EnvelopeDecryption::new(Arc::clone(&provider)))).await;
+    let client = client(&url, provider, EnvelopeScope::Message);
+
+    let body = Bytes::from(vec![7u8; 50_000]);
+    let options = RequestOptions::new().chunked_upload(ChunkedUpload::new().chunk_size(8 * 1024));
+    let response = client.call_with_options("bank.Accounts", "Open", body.clone(), options).await.unwrap();
+
+    assert_eq!(response, body);
+    assert_eq!(*seen.lock().unwrap(), vec![body]);
+}
+
+#[tokio::test]
+async fn test_server_without_decryption_rejects_sealed_requests() {
+    let provider: Arc<dyn KeyProvider> = Arc::new(LocalKeyProvider::new().with_random_key("accounts"));
+    let (url, seen) = spawn(None).await;
+    let client = client(&url, provider, EnvelopeScope::Message);
+
+    match client.call("bank.Accounts", "Open", request()).await {
+        Err(QuillError::ProblemDetails(pd)) => assert_eq!(pd.status, 400),
+        other => panic!("expected a 400, got {:?}", other),
+    }
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_unknown_master_key_is_rejected() {
+    let server_provider = Arc::new(LocalKeyProvider::new().with_random_key("accounts"));
+    let client_provider: Arc<dyn KeyProvider> = Arc::new(LocalKeyProvider::new().with_random_key("accounts"));
+    let (url, seen) = spawn(Some(EnvelopeDecryption::new(server_provider))).await;
+    let client = client(&url, client_provider, EnvelopeScope::Message);
+
+    assert!(client.call("bank.Accounts", "Open", request()).await.is_err());
+    assert!(seen.lock().unwrap().is_empty());
+}
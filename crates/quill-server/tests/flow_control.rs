@@ -0,0 +1,171 @@
+//! End-to-end tests for credit-based flow control of streaming responses
+
+use bytes::Bytes;
+use quill_client::{FlowControlConfig, QuillClient};
+use quill_core::QuillError;
+use quill_server::{QuillServer, RpcResponse, RpcRouter};
+use quill_tensor::{DType, FrameType, TensorFrame, TensorMeta, TensorSender};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+const MESSAGES: usize = 50;
+
+/// Payload chunks of the tensor stream, and their size
+const CHUNKS: usize = 32;
+const CHUNK_BYTES: usize = 16 * 1024;
+
+async fn spawn(flow_control: bool, produced: Arc<AtomicUsize>) -> String {
+    let mut router = RpcRouter::new();
+    let payloads = Arc::clone(&produced);
+    let fixed = Arc::clone(&produced);
+    router.register("test.Numbers/Count", move |_req: Bytes| {
+        let produced = Arc::clone(&produced);
+        async move {
+            let messages = (0..MESSAGES).map(move |i| {
+                produced.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, QuillError>(Bytes::from(i.to_string()))
+            });
+            Ok(RpcResponse::streaming(tokio_stream::iter(messages)))
+        }
+    });
+    router.register("test.Numbers/Fixed", move |_req: Bytes| {
+        let produced = Arc::clone(&fixed);
+        async move {
+            // `fixed64 value = 2;`, whose encoding starts like a TENSOR_PAYLOAD frame
+            let messages = (0..MESSAGES).map(move |i| {
+                produced.fetch_add(1, Ordering::SeqCst);
+                let mut message = vec![0x11];
+                message.extend_from_slice(&(u64::MAX - i as u64).to_le_bytes());
+                Ok::<_, QuillError>(Bytes::from(message))
+            });
+            Ok(RpcResponse::streaming(tokio_stream::iter(messages)))
+        }
+    });
+    router.register_tensor_streaming("test.Numbers/Tensor", move |_req: Bytes| {
+        let produced = Arc::clone(&payloads);
+        async move {
+            let meta = TensorMeta::new(vec![CHUNKS * CHUNK_BYTES], DType::UInt8);
+            let payloads = (0..CHUNKS).map(move |_| {
+                produced.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, QuillError>(TensorFrame::tensor_payload(Bytes::from(vec![7u8; CHUNK_BYTES])))
+            });
+            let frames = std::iter::once(Ok(TensorFrame::tensor_meta(TensorSender::new().encode_meta(&meta))))
+                .chain(payloads)
+                .chain(std::iter::once(Ok(TensorFrame::end_stream())));
+            Ok(tokio_stream::iter(frames))
+        }
+    });
+    if flow_control {
+        router.enable_flow_control();
+        router.enable_tensor_flow_control(1024 * 1024);
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+fn flow_controlled_client(base_url: String) -> QuillClient {
+    QuillClient::builder()
+        .base_url(base_url)
+        .flow_control(FlowControlConfig::new(4).refill_threshold(2))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_server_waits_for_credits() {
+    let produced = Arc::new(AtomicUsize::new(0));
+    let client = flow_controlled_client(spawn(true, Arc::clone(&produced)).await);
+
+    let mut stream = client
+        .call_server_streaming("test.Numbers", "Count", Bytes::new())
+        .await
+        .unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from("0"));
+
+    // The consumer stalls: the server holds at most one message beyond its credits
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(produced.load(Ordering::SeqCst) <= 5);
+
+    let mut received = 1;
+    while let Some(message) = stream.next().await {
+        assert_eq!(message.unwrap(), Bytes::from(received.to_string()));
+        received += 1;
+    }
+    assert_eq!(received, MESSAGES);
+}
+
+#[tokio::test]
+async fn test_falls_back_without_server_support() {
+    let produced = Arc::new(AtomicUsize::new(0));
+    let client = flow_controlled_client(spawn(false, Arc::clone(&produced)).await);
+
+    let stream = client
+        .call_server_streaming("test.Numbers", "Count", Bytes::new())
+        .await
+        .unwrap();
+    let messages: Vec<_> = stream.collect().await;
+    assert_eq!(messages.len(), MESSAGES);
+    assert!(messages.iter().all(|message| message.is_ok()));
+}
+
+#[tokio::test]
+async fn test_server_waits_for_tensor_bytes() {
+    let produced = Arc::new(AtomicUsize::new(0));
+    let client = QuillClient::builder()
+        .base_url(spawn(true, Arc::clone(&produced)).await)
+        .flow_control(FlowControlConfig::new(1024).tensor_window(4 * CHUNK_BYTES as u64))
+        .build()
+        .unwrap();
+
+    let mut stream = client
+        .call_server_streaming("test.Numbers", "Tensor", Bytes::new())
+        .await
+        .unwrap();
+    let meta = TensorFrame::decode(&stream.next().await.unwrap().unwrap()).unwrap().0;
+    assert_eq!(meta.frame_type, FrameType::TensorMeta);
+
+    // The consumer stalls: the server holds at most one chunk beyond the window
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(produced.load(Ordering::SeqCst) <= 5);
+
+    let mut bytes = 0;
+    while let Some(message) = stream.next().await {
+        let (frame, _) = TensorFrame::decode(&message.unwrap()).unwrap();
+        bytes += frame.payload.len();
+    }
+    assert_eq!(bytes, CHUNKS * CHUNK_BYTES);
+}
+
+#[tokio::test]
+async fn test_byte_window_ignores_plain_messages() {
+    let produced = Arc::new(AtomicUsize::new(0));
+    let client = QuillClient::builder()
+        .base_url(spawn(true, Arc::clone(&produced)).await)
+        .flow_control(FlowControlConfig::new(1024).tensor_window(64))
+        .build()
+        .unwrap();
+
+    let mut stream = client
+        .call_server_streaming("test.Numbers", "Fixed", Bytes::new())
+        .await
+        .unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap().len(), 9);
+
+    // Only message credits apply, so a stalled consumer does not hold the server back
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(produced.load(Ordering::SeqCst), MESSAGES);
+
+    let mut received = 1;
+    while let Some(message) = stream.next().await {
+        assert_eq!(message.unwrap()[0], 0x11);
+        received += 1;
+    }
+    assert_eq!(received, MESSAGES);
+}
@@ -0,0 +1,72 @@
+//! End-to-end tests for frame coalescing
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::QuillError;
+use quill_server::{CoalesceBudget, FrameCoalescingConfig, QuillServer, RpcResponse, RpcRouter};
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+async fn spawn(config: FrameCoalescingConfig) -> QuillClient {
+    let mut router = RpcRouter::new();
+    router.register("test.Llm/Generate", |_req: Bytes| async move {
+        let tokens = (0..500).map(|i| Ok::<_, QuillError>(Bytes::from(format!("tok{}", i))));
+        Ok(RpcResponse::streaming(tokio_stream::iter(tokens)))
+    });
+    // Sends one message, then hangs without ending the stream
+    router.register("test.Llm/Stall", |_req: Bytes| async move {
+        let first = tokio_stream::iter(vec![Ok::<_, QuillError>(Bytes::from_static(b"first"))]);
+        Ok(RpcResponse::streaming(first.chain(tokio_stream::pending())))
+    });
+    router.enable_frame_coalescing(config);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_coalesced_tokens_arrive_in_order() {
+    let client = spawn(FrameCoalescingConfig::default()).await;
+
+    let tokens: Vec<Bytes> = client
+        .call_server_streaming("test.Llm", "Generate", Bytes::new())
+        .await
+        .unwrap()
+        .map(|token| token.unwrap())
+        .collect()
+        .await;
+    assert_eq!(tokens.len(), 500);
+    assert_eq!(tokens[0], Bytes::from_static(b"tok0"));
+    assert_eq!(tokens[499], Bytes::from_static(b"tok499"));
+}
+
+#[tokio::test]
+async fn test_delay_budget_releases_waiting_frames() {
+    let client = spawn(
+        FrameCoalescingConfig::new(CoalesceBudget::new(Duration::from_millis(20), 64 * 1024))
+            .method_budget("test.Llm/Generate", CoalesceBudget::disabled()),
+    )
+    .await;
+
+    // The first message is written once its delay budget runs out
+    let mut stream = client.call_server_streaming("test.Llm", "Stall", Bytes::new()).await.unwrap();
+    let first = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("coalesced frame was held back")
+        .unwrap()
+        .unwrap();
+    assert_eq!(first, Bytes::from_static(b"first"));
+
+    // Methods with coalescing disabled still stream normally
+    let tokens: Vec<_> = client
+        .call_server_streaming("test.Llm", "Generate", Bytes::new())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(tokens.len(), 500);
+}
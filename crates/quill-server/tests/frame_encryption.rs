@@ -0,0 +1,109 @@
+//! End-to-end tests for frame-level encryption of streams
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::{FrameKey, QuillError};
+use quill_server::router::RequestStream;
+use quill_server::{FrameEncryption, QuillServer, RpcResponse, RpcRouter};
+use std::pin::Pin;
+use tokio_stream::{Stream, StreamExt};
+
+async fn spawn(encryption: Option<FrameEncryption>) -> String {
+    let mut router = RpcRouter::new();
+    router.register("test.Secrets/List", |req: Bytes| async move {
+        let count: usize = std::str::from_utf8(&req).unwrap().parse().unwrap();
+        let messages = (0..count).map(|i| Ok::<_, QuillError>(Bytes::from(format!("secret {}", i))));
+        Ok(RpcResponse::streaming(tokio_stream::iter(messages)))
+    });
+    router.register_client_streaming("test.Secrets/Join", |requests: RequestStream| async move {
+        let parts: Vec<Bytes> = requests.collect::<Result<_, _>>().await?;
+        Ok(RpcResponse::unary(Bytes::from(parts.concat())))
+    });
+    router.register_bidi_streaming("test.Secrets/Echo", |requests: RequestStream| async move {
+        Ok(RpcResponse::streaming(requests.map(|message| {
+            message.map(|message| Bytes::from([b"echo: ", &message[..]].concat()))
+        })))
+    });
+    if let Some(encryption) = encryption {
+        router.enable_frame_encryption(encryption);
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+fn client(base_url: &str, key: FrameKey) -> QuillClient {
+    QuillClient::builder().base_url(base_url).frame_encryption(key).build().unwrap()
+}
+
+type Requests = Pin<Box<dyn Stream<Item = Result<Bytes, QuillError>> + Send>>;
+
+fn requests(parts: &[&'static str]) -> Requests {
+    let messages: Vec<_> = parts.iter().map(|part| Ok(Bytes::from_static(part.as_bytes()))).collect();
+    Box::pin(tokio_stream::iter(messages))
+}
+
+#[tokio::test]
+async fn test_encrypted_streams_round_trip() {
+    let key = FrameKey::random("k1").unwrap();
+    let base_url = spawn(Some(FrameEncryption::new().with_key(key.clone()))).await;
+    let client = client(&base_url, key);
+
+    let stream = client.call_server_streaming("test.Secrets", "List", Bytes::from("3")).await.unwrap();
+    let messages: Vec<Bytes> = stream.map(Result::unwrap).collect().await;
+    assert_eq!(messages, vec!["secret 0", "secret 1", "secret 2"]);
+
+    let joined = client
+        .call_client_streaming("test.Secrets", "Join", requests(&["a", "b", "c"]))
+        .await
+        .unwrap();
+    assert_eq!(joined, Bytes::from("abc"));
+
+    let stream = client.call_bidi_streaming("test.Secrets", "Echo", requests(&["x", "y"])).await.unwrap();
+    let messages: Vec<Bytes> = stream.map(Result::unwrap).collect().await;
+    assert_eq!(messages, vec!["echo: x", "echo: y"]);
+}
+
+#[tokio::test]
+async fn test_plaintext_streams_still_accepted() {
+    let key = FrameKey::random("k1").unwrap();
+    let base_url = spawn(Some(FrameEncryption::new().with_key(key))).await;
+
+    let stream = QuillClient::new(&base_url)
+        .call_server_streaming("test.Secrets", "List", Bytes::from("2"))
+        .await
+        .unwrap();
+    assert_eq!(stream.map(Result::unwrap).collect::<Vec<_>>().await.len(), 2);
+}
+
+#[tokio::test]
+async fn test_unknown_key_rejected() {
+    let base_url = spawn(Some(FrameEncryption::new().with_key(FrameKey::random("k1").unwrap()))).await;
+    let client = client(&base_url, FrameKey::random("k2").unwrap());
+
+    let err = client.call_server_streaming("test.Secrets", "List", Bytes::from("1")).await.err().unwrap();
+    match err {
+        QuillError::ProblemDetails(pd) => {
+            assert_eq!(pd.status, 400);
+            assert!(pd.detail.unwrap().contains("k2"));
+        }
+        other => panic!("expected problem details, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_server_without_encryption_rejects() {
+    let base_url = spawn(None).await;
+    let client = client(&base_url, FrameKey::random("k1").unwrap());
+
+    // Sealed frames must not reach a handler that cannot open them
+    let err = client
+        .call_client_streaming("test.Secrets", "Join", requests(&["a"]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, QuillError::ProblemDetails(pd) if pd.status == 400));
+}
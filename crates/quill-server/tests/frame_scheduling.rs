@@ -0,0 +1,84 @@
+//! End-to-end tests for fair frame scheduling
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::QuillError;
+use quill_server::{FrameSchedulingConfig, QuillServer, RpcResponse, RpcRouter};
+use tokio_stream::StreamExt;
+
+const BLOCK_SIZE: usize = 256 * 1024;
+
+async fn spawn(config: FrameSchedulingConfig) -> QuillClient {
+    let mut router = RpcRouter::new();
+    // Large blocks, each several quanta long
+    router.register("test.Tensor/Download", |_req: Bytes| async move {
+        let blocks = (0..16u8).map(|i| Ok::<_, QuillError>(Bytes::from(vec![i; BLOCK_SIZE])));
+        Ok(RpcResponse::streaming(tokio_stream::iter(blocks)))
+    });
+    router.register("test.Llm/Generate", |_req: Bytes| async move {
+        let tokens = (0..100).map(|i| Ok::<_, QuillError>(Bytes::from(format!("tok{}", i))));
+        Ok(RpcResponse::streaming(tokio_stream::iter(tokens)))
+    });
+    router.enable_frame_scheduling(config);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_split_frames_arrive_whole_alongside_other_streams() {
+    let client = spawn(FrameSchedulingConfig::new().quantum(16 * 1024)).await;
+
+    // Both streams share the client's connection
+    let download = async {
+        client
+            .call_server_streaming("test.Tensor", "Download", Bytes::new())
+            .await
+            .unwrap()
+            .map(|block| block.unwrap())
+            .collect::<Vec<_>>()
+            .await
+    };
+    let generate = async {
+        client
+            .call_server_streaming("test.Llm", "Generate", Bytes::new())
+            .await
+            .unwrap()
+            .map(|token| token.unwrap())
+            .collect::<Vec<_>>()
+            .await
+    };
+    let (blocks, tokens) = tokio::join!(download, generate);
+
+    assert_eq!(blocks.len(), 16);
+    for (i, block) in blocks.iter().enumerate() {
+        assert_eq!(block.len(), BLOCK_SIZE);
+        assert!(block.iter().all(|&byte| byte == i as u8));
+    }
+    assert_eq!(tokens.len(), 100);
+    assert_eq!(tokens[99], Bytes::from_static(b"tok99"));
+}
+
+#[tokio::test]
+async fn test_method_quantum_keeps_streams_intact() {
+    let client = spawn(
+        FrameSchedulingConfig::new()
+            .quantum(4 * 1024)
+            .method_quantum("test.Tensor", 128 * 1024),
+    )
+    .await;
+
+    let blocks: Vec<_> = client
+        .call_server_streaming("test.Tensor", "Download", Bytes::new())
+        .await
+        .unwrap()
+        .map(|block| block.unwrap())
+        .collect()
+        .await;
+    assert_eq!(blocks.len(), 16);
+    assert_eq!(blocks[15], Bytes::from(vec![15u8; BLOCK_SIZE]));
+}
@@ -0,0 +1,70 @@
+//! End-to-end tests for the built-in health service
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::{QuillError, ServingStatus};
+use quill_server::{HealthReporter, ObservabilityCollector, QuillServer};
+use std::collections::HashMap;
+use tokio_stream::StreamExt;
+
+async fn spawn(health: HealthReporter) -> QuillClient {
+    let server = QuillServer::builder()
+        .enable_health(health)
+        .register("echo.v1.Echo/Echo", |req: Bytes| async move { Ok(req) })
+        .build();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = server.serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_check_reports_services() {
+    let health = HealthReporter::new();
+    let client = spawn(health.clone()).await;
+
+    assert_eq!(client.health_check(None).await.unwrap(), ServingStatus::Serving);
+    assert_eq!(client.health_check(Some("echo.v1.Echo")).await.unwrap(), ServingStatus::Serving);
+    match client.health_check(Some("missing.v1.Missing")).await {
+        Err(QuillError::ProblemDetails(pd)) => assert_eq!(pd.status, 404),
+        other => panic!("expected a 404, got {:?}", other),
+    }
+
+    health.set_serving("echo.v1.Echo", false);
+    assert_eq!(client.health_check(Some("echo.v1.Echo")).await.unwrap(), ServingStatus::NotServing);
+    assert_eq!(client.health_check(None).await.unwrap(), ServingStatus::Serving);
+}
+
+#[tokio::test]
+async fn test_watch_streams_changes() {
+    let health = HealthReporter::new();
+    let client = spawn(health.clone()).await;
+
+    let mut watch = client.watch_health(Some("echo.v1.Echo")).await.unwrap();
+    assert_eq!(watch.next().await.unwrap().unwrap(), ServingStatus::Serving);
+
+    health.set_serving("", false);
+    assert_eq!(watch.next().await.unwrap().unwrap(), ServingStatus::NotServing);
+    health.set_serving("", true);
+    assert_eq!(watch.next().await.unwrap().unwrap(), ServingStatus::Serving);
+
+    // Unknown services are reported rather than refused
+    let mut unknown = client.watch_health(Some("later.v1.Later")).await.unwrap();
+    assert_eq!(unknown.next().await.unwrap().unwrap(), ServingStatus::ServiceUnknown);
+    health.set_serving("later.v1.Later", true);
+    assert_eq!(unknown.next().await.unwrap().unwrap(), ServingStatus::Serving);
+}
+
+#[tokio::test]
+async fn test_collector_health_is_reported() {
+    let collector = ObservabilityCollector::new();
+    let client = spawn(collector.health_reporter()).await;
+
+    collector.update_health(false, HashMap::new()).await;
+    assert_eq!(client.health_check(None).await.unwrap(), ServingStatus::NotServing);
+    collector.update_health(true, HashMap::new()).await;
+    assert_eq!(client.health_check(None).await.unwrap(), ServingStatus::Serving);
+}
@@ -0,0 +1,51 @@
+//! End-to-end tests for the connection I/O backends
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_server::{IoBackend, QuillServer};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Wait until something accepts connections on `addr`
+async fn wait_until_listening(addr: SocketAddr) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while tokio::net::TcpStream::connect(addr).await.is_err() {
+        assert!(tokio::time::Instant::now() < deadline, "nothing listening on {}", addr);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+async fn spawn(backend: IoBackend, acceptors: usize) -> String {
+    let addr: SocketAddr = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    };
+    let server = QuillServer::builder()
+        .register("test.Io/Echo", |req: Bytes| async move { Ok(req) })
+        .reuse_port(acceptors)
+        .tcp_nodelay(true)
+        .io_backend(backend)
+        .build();
+    tokio::spawn(async move {
+        let _ = server.serve(addr).await;
+    });
+    wait_until_listening(addr).await;
+    format!("http://{}", addr)
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_io_uring_backend_serves_calls() {
+    // Served over io_uring where available, otherwise after falling back to tokio
+    let url = spawn(IoBackend::IoUring, 2).await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+
+    let small = Bytes::from_static(b"hello");
+    assert_eq!(client.call("test.Io", "Echo", small.clone()).await.unwrap(), small);
+
+    // Larger than one read buffer, so it takes several reads and writes
+    let large = Bytes::from(vec![7u8; 300 * 1024]);
+    for _ in 0..4 {
+        assert_eq!(client.call("test.Io", "Echo", large.clone()).await.unwrap(), large);
+    }
+}
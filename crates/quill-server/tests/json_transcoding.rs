@@ -0,0 +1,138 @@
+//! End-to-end tests for JSON transcoding of described methods
+
+use bytes::Bytes;
+use prost::Message;
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto,
+    ServiceDescriptorProto,
+};
+use quill_client::QuillClient;
+use quill_server::QuillServer;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Hello {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(int32, tag = "2")]
+    count: i32,
+}
+
+fn descriptor_set() -> Vec<u8> {
+    let field = |name: &str, number, kind: Type| FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(kind as i32),
+        json_name: Some(name.to_string()),
+        ..Default::default()
+    };
+    let file = FileDescriptorProto {
+        name: Some("greeter.proto".to_string()),
+        package: Some("greeter.v1".to_string()),
+        syntax: Some("proto3".to_string()),
+        message_type: vec![DescriptorProto {
+            name: Some("Hello".to_string()),
+            field: vec![field("name", 1, Type::String), field("count", 2, Type::Int32)],
+            ..Default::default()
+        }],
+        service: vec![ServiceDescriptorProto {
+            name: Some("Greeter".to_string()),
+            method: vec![MethodDescriptorProto {
+                name: Some("SayHello".to_string()),
+                input_type: Some(".greeter.v1.Hello".to_string()),
+                output_type: Some(".greeter.v1.Hello".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    FileDescriptorSet { file: vec![file] }.encode_to_vec()
+}
+
+async fn spawn() -> SocketAddr {
+    let server = QuillServer::builder()
+        .register("greeter.v1.Greeter/SayHello", |req: Bytes| async move {
+            let hello = Hello::decode(req).map_err(|e| quill_core::QuillError::Rpc(e.to_string()))?;
+            let reply = Hello {
+                name: format!("Hello, {}", hello.name),
+                count: hello.count + 1,
+            };
+            Ok(Bytes::from(reply.encode_to_vec()))
+        })
+        .register("raw.v1.Raw/Echo", |req: Bytes| async move { Ok(req) })
+        .file_descriptor_set(&descriptor_set())
+        .unwrap()
+        .build();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = server.serve_with_listener(listener).await;
+    });
+    addr
+}
+
+/// POST `body` as JSON over plain HTTP/1.1, like curl does
+async fn post_json(addr: SocketAddr, path: &str, body: &str) -> (u16, String, String) {
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        addr,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    let content_type = head
+        .lines()
+        .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-type: ").map(str::to_string))
+        .unwrap_or_default();
+    (status, content_type, body.to_string())
+}
+
+#[tokio::test]
+async fn test_described_method_accepts_json() {
+    let addr = spawn().await;
+
+    let (status, content_type, body) =
+        post_json(addr, "/greeter.v1.Greeter/SayHello", r#"{"name": "Ada", "count": 1}"#).await;
+    assert_eq!(status, 200);
+    assert_eq!(content_type, "application/json");
+    assert_eq!(serde_json::from_str::<Value>(&body).unwrap(), json!({"name": "Hello, Ada", "count": 2}));
+
+    let (status, _, body) = post_json(addr, "/greeter.v1.Greeter/SayHello", r#"{"name": 1}"#).await;
+    assert_eq!(status, 400);
+    assert!(body.contains("greeter.v1.Hello"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_protobuf_and_undescribed_methods_are_unchanged() {
+    let addr = spawn().await;
+    let client = QuillClient::new(format!("http://{}", addr));
+
+    let request = Hello {
+        name: "Ada".to_string(),
+        count: 0,
+    };
+    let reply = client
+        .call("greeter.v1.Greeter", "SayHello", Bytes::from(request.encode_to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(Hello::decode(reply).unwrap().name, "Hello, Ada");
+
+    // Without descriptors the handler gets the JSON itself
+    let (status, content_type, body) = post_json(addr, "/raw.v1.Raw/Echo", r#"{"raw": true}"#).await;
+    assert_eq!(status, 200);
+    assert_eq!(content_type, "application/proto");
+    assert_eq!(body, r#"{"raw": true}"#);
+}
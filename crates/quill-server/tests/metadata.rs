@@ -0,0 +1,47 @@
+//! End-to-end tests for per-call metadata
+
+use bytes::Bytes;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::Metadata;
+use quill_server::{QuillServer, RequestContext, RpcRouter};
+use std::time::Duration;
+
+/// Answers with the metadata the handler received, one `key=value` per line
+fn router() -> RpcRouter {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Meta/Echo", |_req: Bytes| async move {
+        let ctx = RequestContext::current().expect("handlers run in a request context");
+        let mut lines: Vec<String> =
+            ctx.metadata().iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        lines.sort();
+        Ok(Bytes::from(lines.join("\n")))
+    });
+    router
+}
+
+async fn spawn() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router()).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_metadata_reaches_handler() {
+    let url = spawn().await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+
+    let mut metadata = Metadata::new().with("x-request-id", "req-42").unwrap();
+    metadata.append("x-tag", "blue").unwrap();
+    metadata.append("x-tag", "green").unwrap();
+    let options = RequestOptions::new().metadata(metadata).timeout(Duration::from_secs(5));
+
+    let echoed = client.call_with_options("test.Meta", "Echo", Bytes::new(), options).await.unwrap();
+    assert_eq!(echoed, Bytes::from("x-request-id=req-42\nx-tag=blue\nx-tag=green"));
+
+    // Protocol headers such as content-type and the timeout are not metadata
+    let echoed = client.call("test.Meta", "Echo", Bytes::new()).await.unwrap();
+    assert!(echoed.is_empty(), "{:?}", echoed);
+}
@@ -0,0 +1,98 @@
+//! End-to-end tests for the Prometheus metrics endpoint
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::QuillError;
+use quill_server::QuillServer;
+use quill_tensor::{DType, Tensor, TensorMeta, TensorSender};
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_stream::StreamExt;
+
+async fn spawn() -> (QuillClient, SocketAddr) {
+    let server = QuillServer::builder()
+        .enable_metrics("/metrics")
+        .register("test.Echo/Echo", |req: Bytes| async move { Ok(req) })
+        .register_tensor_streaming("test.Tensors/Download", |_req: Bytes| async move {
+            let values: Vec<f32> = (0..64).map(|i| i as f32).collect();
+            let tensor = Tensor::from_f32(&TensorMeta::new(vec![8, 8], DType::Float32), &values);
+            let frames = TensorSender::with_chunk_size(64).encode_tensor(&tensor);
+            Ok(tokio_stream::iter(frames.into_iter().map(Ok::<_, QuillError>)))
+        })
+        .build();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = server.serve_with_listener(listener).await;
+    });
+    (QuillClient::new(format!("http://{}", addr)), addr)
+}
+
+/// Scrape `path` like Prometheus does, over plain HTTP/1.1
+async fn scrape(addr: SocketAddr, path: &str) -> String {
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let request = format!("GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", path, addr);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+fn metric(scrape: &str, name: &str) -> u64 {
+    scrape
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("metric {} missing", name))
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_reports_calls() {
+    let (client, addr) = spawn().await;
+
+    client.call("test.Echo", "Echo", Bytes::from_static(b"hi")).await.unwrap();
+    assert!(client.call("test.Echo", "Missing", Bytes::new()).await.is_err());
+    let frames: Vec<_> = client
+        .call_server_streaming("test.Tensors", "Download", Bytes::new())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert!(!frames.is_empty());
+
+    let response = scrape(addr, "/metrics").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("content-type: text/plain; version=0.0.4"));
+
+    assert_eq!(metric(&response, "quill_requests_total"), 3);
+    assert_eq!(metric(&response, "quill_requests_failed_total"), 1);
+    assert_eq!(
+        metric(&response, "quill_responses_total{endpoint=\"test.Echo/Missing\",code=\"404\"}"),
+        1
+    );
+    assert_eq!(
+        metric(&response, "quill_request_duration_seconds_count{endpoint=\"test.Echo/Echo\"}"),
+        1
+    );
+    assert!(metric(&response, "quill_stream_frames_total") >= 3);
+    assert!(metric(&response, "quill_tensor_bytes_total") >= 64 * 4);
+    assert_eq!(metric(&response, "quill_streams_active"), 0);
+    // The scrape's own connection is open while it is served
+    assert!(metric(&response, "quill_connections_active") >= 1);
+    assert!(metric(&response, "quill_connections_total") >= 2);
+}
+
+#[tokio::test]
+async fn test_scrapes_are_not_recorded() {
+    let (_client, addr) = spawn().await;
+
+    scrape(addr, "/metrics").await;
+    let response = scrape(addr, "/metrics").await;
+    assert_eq!(metric(&response, "quill_requests_total"), 0);
+
+    // Other paths are still answered as RPCs
+    let response = scrape(addr, "/other").await;
+    assert!(response.starts_with("HTTP/1.1 405"));
+}
@@ -0,0 +1,85 @@
+//! End-to-end tests for deadline-bounded partial results
+
+use bytes::Bytes;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::QuillError;
+use quill_server::{PartialResponse, QuillServer, RpcRouter};
+use std::time::Duration;
+use tokio_stream::{Stream, StreamExt};
+
+/// Streams `count` results, one every 50ms
+fn slow_results(count: u64) -> impl Stream<Item = Result<Bytes, QuillError>> + Send {
+    tokio_stream::iter(0..count).then(|i| async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        Ok(Bytes::from(format!("doc-{}", i)))
+    })
+}
+
+async fn spawn() -> QuillClient {
+    let mut router = RpcRouter::new();
+    router.register("test.Search/Query", |req: Bytes| async move {
+        let count = req[0] as u64;
+        Ok(PartialResponse::with_budget(Duration::from_millis(120))
+            .expected(count)
+            .stream(slow_results(count)))
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_deadline_returns_partial_results() {
+    let client = spawn().await;
+    let mut stream = client
+        .call_server_streaming_partial("test.Search", "Query", Bytes::from_static(&[10]), RequestOptions::new())
+        .await
+        .unwrap();
+
+    let mut results = Vec::new();
+    while let Some(item) = stream.next().await {
+        results.push(item.unwrap());
+    }
+
+    assert_eq!(results, vec![Bytes::from("doc-0"), Bytes::from("doc-1")]);
+    assert!(stream.is_partial());
+    let stats = stream.partial().unwrap();
+    assert_eq!(stats.emitted, 2);
+    assert_eq!(stats.expected, Some(10));
+    assert_eq!(stats.budget_ms, 120);
+    assert_eq!(stats.completeness(), Some(0.2));
+}
+
+#[tokio::test]
+async fn test_fast_response_is_complete() {
+    let client = spawn().await;
+    let mut stream = client
+        .call_server_streaming_partial("test.Search", "Query", Bytes::from_static(&[1]), RequestOptions::new())
+        .await
+        .unwrap();
+
+    let mut count = 0;
+    while let Some(item) = stream.next().await {
+        item.unwrap();
+        count += 1;
+    }
+    assert_eq!(count, 1);
+    assert!(!stream.is_partial());
+}
+
+#[tokio::test]
+async fn test_plain_streaming_client_sees_normal_end() {
+    let client = spawn().await;
+    let stream = client
+        .call_server_streaming("test.Search", "Query", Bytes::from_static(&[10]))
+        .await
+        .unwrap();
+
+    let results: Vec<_> = stream.collect().await;
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r.is_ok()));
+}
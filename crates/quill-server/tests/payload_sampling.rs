@@ -0,0 +1,75 @@
+//! End-to-end tests for payload sampling
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_server::{PayloadDirection, PayloadSample, PayloadSamplingConfig, QuillServer, RpcRouter};
+use std::sync::{Arc, Mutex};
+
+async fn spawn(config: PayloadSamplingConfig) -> QuillClient {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Users/Login", |_req: Bytes| async move {
+        Ok(Bytes::from_static(br#"{"token":"abc123","user":"ada"}"#))
+    });
+    router.register_unary("test.Users/Avatar", |_req: Bytes| async move { Ok(Bytes::from(vec![0xffu8; 1024])) });
+    router.enable_payload_sampling(config);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_sampled_calls_record_redacted_request_and_response() {
+    let samples = Arc::new(Mutex::new(Vec::<PayloadSample>::new()));
+    let recorded = Arc::clone(&samples);
+    let client = spawn(
+        PayloadSamplingConfig::new(0.0)
+            .method_rate("test.Users/Login", 0.5)
+            .on_sample(move |sample| recorded.lock().unwrap().push(sample.clone())),
+    )
+    .await;
+
+    for _ in 0..4 {
+        let response = client
+            .call("test.Users", "Login", Bytes::from_static(br#"{"user":"ada","password":"hunter2"}"#))
+            .await
+            .unwrap();
+        // Sampling never alters what the caller receives
+        assert_eq!(response, Bytes::from_static(br#"{"token":"abc123","user":"ada"}"#));
+    }
+    client.call("test.Users", "Avatar", Bytes::new()).await.unwrap();
+
+    let samples = samples.lock().unwrap();
+    assert_eq!(samples.len(), 4, "{:#?}", samples);
+    for pair in samples.chunks(2) {
+        assert_eq!(pair[0].direction, PayloadDirection::Request);
+        assert_eq!(pair[0].payload, r#"{"password":"[redacted]","user":"ada"}"#);
+        assert_eq!(pair[1].direction, PayloadDirection::Response);
+        assert_eq!(pair[1].payload, r#"{"token":"[redacted]","user":"ada"}"#);
+        assert!(pair.iter().all(|sample| sample.method == "test.Users/Login"));
+    }
+}
+
+#[tokio::test]
+async fn test_sampled_payloads_are_size_capped() {
+    let samples = Arc::new(Mutex::new(Vec::<PayloadSample>::new()));
+    let recorded = Arc::clone(&samples);
+    let client = spawn(
+        PayloadSamplingConfig::new(1.0)
+            .max_payload_bytes(64)
+            .on_sample(move |sample| recorded.lock().unwrap().push(sample.clone())),
+    )
+    .await;
+
+    client.call("test.Users", "Avatar", Bytes::new()).await.unwrap();
+
+    let samples = samples.lock().unwrap();
+    let response = &samples[1];
+    assert_eq!(response.size, 1024);
+    assert!(response.truncated);
+    assert!(response.payload.starts_with("hex:ff"));
+    assert!(response.payload.len() <= 64);
+}
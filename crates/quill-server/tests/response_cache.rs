@@ -0,0 +1,105 @@
+//! End-to-end tests for the response cache
+
+use bytes::Bytes;
+use http::{HeaderName, HeaderValue};
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::QuillError;
+use quill_server::{CachedResponse, QuillServer, ResponseCache, ResponseCacheConfig, RpcResponse, RpcRouter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Serve a metadata method that caches its own response on first call
+async fn spawn(cache: impl Into<ResponseCacheConfig>, calls: Arc<AtomicUsize>) -> String {
+    let config = cache.into();
+    let cache = config.cache().clone();
+    let mut router = RpcRouter::new();
+    let handler_cache = cache.clone();
+    router.register("test.Models/Metadata", move |_req: Bytes| {
+        let cache = handler_cache.clone();
+        let calls = Arc::clone(&calls);
+        async move {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            let metadata = Bytes::from(format!("metadata-v{}-{}", call, "x".repeat(2048)));
+            cache.insert(
+                "test.Models/Metadata",
+                CachedResponse::new(metadata.clone()).with_zstd(3)?,
+            );
+            Ok(RpcResponse::unary(metadata))
+        }
+    });
+    router.enable_response_cache(config);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_cached_response_skips_handler_until_invalidated() {
+    let cache = ResponseCache::new();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let config = ResponseCacheConfig::new(cache.clone()).remote_invalidation(|_| true);
+    let url = spawn(config, Arc::clone(&calls)).await;
+    let client = QuillClient::new(url);
+
+    let first = client.call("test.Models", "Metadata", Bytes::new()).await.unwrap();
+    let second = client.call("test.Models", "Metadata", Bytes::new()).await.unwrap();
+    assert_eq!(first, second);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    assert_eq!(client.invalidate_response_cache(Some("test.Models")).await.unwrap(), 1);
+    assert!(cache.is_empty());
+    let third = client.call("test.Models", "Metadata", Bytes::new()).await.unwrap();
+    assert!(third.starts_with(b"metadata-v1"));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    assert_eq!(client.invalidate_response_cache(None).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_pre_compressed_response_is_decoded() {
+    let cache = ResponseCache::new();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let url = spawn(cache.clone(), Arc::clone(&calls)).await;
+    let client = QuillClient::builder().base_url(url).enable_compression(true).build().unwrap();
+
+    let plain = QuillClient::new(client.base_url().to_string());
+    let expected = plain.call("test.Models", "Metadata", Bytes::new()).await.unwrap();
+
+    // The zstd body is sent as compressed once and decoded by the client
+    let cached = client.call("test.Models", "Metadata", Bytes::new()).await.unwrap();
+    assert_eq!(cached, expected);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_remote_invalidation_is_opt_in_and_authorized() {
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    // Not served without remote invalidation
+    let url = spawn(ResponseCache::new(), Arc::clone(&calls)).await;
+    let client = QuillClient::new(url);
+    client.call("test.Models", "Metadata", Bytes::new()).await.unwrap();
+    assert!(client.invalidate_response_cache(None).await.is_err());
+
+    let cache = ResponseCache::new();
+    let config = ResponseCacheConfig::new(cache.clone())
+        .remote_invalidation(|headers| headers.get("x-role").is_some_and(|role| role == "admin"));
+    let url = spawn(config, Arc::clone(&calls)).await;
+    let client = QuillClient::new(url);
+    client.call("test.Models", "Metadata", Bytes::new()).await.unwrap();
+
+    let err = client.invalidate_response_cache(None).await.unwrap_err();
+    assert!(matches!(err, QuillError::ProblemDetails(ref pd) if pd.status == 403), "{:?}", err);
+    assert_eq!(cache.len(), 1);
+
+    let options = RequestOptions::new().header(HeaderName::from_static("x-role"), HeaderValue::from_static("admin"));
+    client
+        .call_with_options("quill.cache.v1.ResponseCache", "Invalidate", Bytes::new(), options)
+        .await
+        .unwrap();
+    assert!(cache.is_empty());
+}
@@ -0,0 +1,108 @@
+//! End-to-end tests for runtime and socket tuning
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_server::{CorePinning, InferenceRuntime, IoBackend, QuillServer, ServerBuilder};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Reports the name of the thread that ran the "model"
+fn builder(inference: &InferenceRuntime) -> ServerBuilder {
+    let handler_runtime = inference.clone();
+    QuillServer::builder()
+        .register("test.Model/Infer", move |_req: Bytes| {
+            let inference = handler_runtime.clone();
+            async move {
+                let thread = inference
+                    .run_blocking(|| std::thread::current().name().unwrap_or_default().to_string())
+                    .await?;
+                Ok(Bytes::from(thread))
+            }
+        })
+        .inference_runtime(inference.clone())
+}
+
+fn free_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap()
+}
+
+/// Wait until something accepts connections on `addr`
+async fn wait_until_listening(addr: SocketAddr) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while tokio::net::TcpStream::connect(addr).await.is_err() {
+        assert!(tokio::time::Instant::now() < deadline, "nothing listening on {}", addr);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_reuse_port_acceptors_serve_calls() {
+    let inference = InferenceRuntime::new(2).unwrap();
+    let server = builder(&inference)
+        .reuse_port(4)
+        .listen_backlog(128)
+        .tcp_nodelay(true)
+        .socket_buffer_sizes(256 * 1024, 256 * 1024)
+        .build();
+    assert!(server.inference_runtime().is_some());
+
+    let addr = free_addr();
+    tokio::spawn(async move {
+        let _ = server.serve(addr).await;
+    });
+    wait_until_listening(addr).await;
+
+    // Separate clients open separate connections, spread over the acceptors
+    for _ in 0..8 {
+        let client = QuillClient::builder().base_url(format!("http://{}", addr)).build().unwrap();
+        let thread = client.call("test.Model", "Infer", Bytes::new()).await.unwrap();
+        assert_eq!(thread, Bytes::from("quill-inference"));
+    }
+}
+
+#[tokio::test]
+async fn test_run_builds_configured_runtime() {
+    let inference = InferenceRuntime::new(1).unwrap();
+    let server = builder(&inference).worker_threads(2).core_pinning(CorePinning::Cores(vec![0])).build();
+
+    let addr = free_addr();
+    std::thread::spawn(move || server.run(addr).map_err(|e| e.to_string()));
+    wait_until_listening(addr).await;
+
+    let client = QuillClient::builder().base_url(format!("http://{}", addr)).build().unwrap();
+    let thread = client.call("test.Model", "Infer", Bytes::new()).await.unwrap();
+    assert_eq!(thread, Bytes::from("quill-inference"));
+}
+
+#[tokio::test]
+async fn test_serve_with_listener_applies_socket_config() {
+    let inference = InferenceRuntime::new(1).unwrap();
+    let server = builder(&inference)
+        .listen_backlog(128)
+        .tcp_nodelay(true)
+        .socket_buffer_sizes(256 * 1024, 256 * 1024)
+        .io_backend(IoBackend::IoUring)
+        .build();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = server.serve_with_listener(listener).await;
+    });
+
+    let client = QuillClient::builder().base_url(format!("http://{}", addr)).build().unwrap();
+    let thread = client.call("test.Model", "Infer", Bytes::new()).await.unwrap();
+    assert_eq!(thread, Bytes::from("quill-inference"));
+}
+
+#[tokio::test]
+async fn test_serve_with_listener_rejects_reuse_port() {
+    let inference = InferenceRuntime::new(1).unwrap();
+    let server = builder(&inference).reuse_port(2).build();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let err = server.serve_with_listener(listener).await.unwrap_err();
+    assert!(err.to_string().contains("reuse_port"), "{}", err);
+}
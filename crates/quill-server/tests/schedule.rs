@@ -0,0 +1,107 @@
+//! End-to-end tests for scheduled method invocation
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::QuillError;
+use quill_server::{OverlapPolicy, QuillServer, RpcRouter, ScheduledJob};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Default)]
+struct Counters {
+    runs: AtomicUsize,
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+async fn spawn(router: RpcRouter) -> QuillClient {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+fn router(counters: Arc<Counters>, work: Duration) -> RpcRouter {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Jobs/Refresh", move |_req: Bytes| {
+        let counters = Arc::clone(&counters);
+        async move {
+            let now = counters.current.fetch_add(1, Ordering::SeqCst) + 1;
+            counters.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(work).await;
+            counters.current.fetch_sub(1, Ordering::SeqCst);
+            counters.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(Bytes::new())
+        }
+    });
+    router.register_unary("test.Jobs/Fail", |_req: Bytes| async move {
+        Err::<Bytes, _>(QuillError::Rpc("cache backend down".to_string()))
+    });
+    router
+}
+
+async fn history(client: &QuillClient) -> Vec<serde_json::Value> {
+    let body = client
+        .call("quill.scheduler.v1.Scheduler", "History", Bytes::new())
+        .await
+        .unwrap();
+    let history: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    history["runs"].as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn test_jobs_run_and_are_recorded() {
+    let counters = Arc::new(Counters::default());
+    let mut router = router(Arc::clone(&counters), Duration::ZERO);
+    router.schedule(ScheduledJob::every("refresh", "test.Jobs/Refresh", Duration::from_millis(40)));
+    router.schedule(ScheduledJob::every("fail", "/test.Jobs/Fail", Duration::from_millis(40)));
+    let client = spawn(router).await;
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(counters.runs.load(Ordering::SeqCst) >= 3);
+
+    let runs = history(&client).await;
+    let refresh = runs.iter().find(|run| run["job"] == "refresh").unwrap();
+    assert_eq!(refresh["status"], "succeeded");
+    assert_eq!(refresh["path"], "test.Jobs/Refresh");
+
+    let fail = runs.iter().find(|run| run["job"] == "fail").unwrap();
+    assert_eq!(fail["status"], "failed");
+    assert_eq!(fail["code"], 500);
+    assert!(fail["detail"].as_str().unwrap().contains("cache backend down"));
+}
+
+#[tokio::test]
+async fn test_overlapping_runs_are_skipped() {
+    let counters = Arc::new(Counters::default());
+    let mut router = router(Arc::clone(&counters), Duration::from_millis(100));
+    router.schedule(
+        ScheduledJob::every("refresh", "test.Jobs/Refresh", Duration::from_millis(30))
+            .overlap(OverlapPolicy::Skip),
+    );
+    let client = spawn(router).await;
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(counters.peak.load(Ordering::SeqCst), 1);
+    let runs = history(&client).await;
+    assert!(runs.iter().any(|run| run["status"] == "skipped"));
+    assert!(runs.iter().any(|run| run["status"] == "succeeded"));
+}
+
+#[tokio::test]
+async fn test_overlapping_runs_allowed() {
+    let counters = Arc::new(Counters::default());
+    let mut router = router(Arc::clone(&counters), Duration::from_millis(100));
+    router.schedule(
+        ScheduledJob::every("refresh", "test.Jobs/Refresh", Duration::from_millis(30))
+            .overlap(OverlapPolicy::Allow)
+            .jitter(Duration::from_millis(5)),
+    );
+    let _client = spawn(router).await;
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(counters.peak.load(Ordering::SeqCst) > 1);
+}
@@ -0,0 +1,160 @@
+//! Deterministic tests over a simulated network on a paused clock
+
+use bytes::Bytes;
+use quill_client::retry::retry_with_policy;
+use quill_client::{FlowControlConfig, QuillClient, RetryPolicy};
+use quill_core::{ErrorCode, QuillError};
+use quill_server::{QuillServer, RpcResponse, RpcRouter};
+use quill_transport::sim::{LinkConfig, SimNetwork};
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_stream::StreamExt;
+
+const ADDR: &str = "echo:80";
+
+fn serve(network: &SimNetwork, flow_control: bool) {
+    let mut router = RpcRouter::new();
+    router.register_unary("echo.v1.Echo/Echo", |req: Bytes| async move { Ok(req) });
+    router.register("echo.v1.Echo/Count", |_req: Bytes| async move {
+        let messages = (0..20).map(|i| Ok::<_, QuillError>(Bytes::from(format!("msg{}", i))));
+        Ok(RpcResponse::streaming(tokio_stream::iter(messages)))
+    });
+    if flow_control {
+        router.enable_flow_control();
+    }
+    let listener = network.listen(ADDR);
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_sim(listener).await;
+    });
+}
+
+fn client(network: &SimNetwork) -> quill_client::client::ClientBuilder {
+    QuillClient::builder()
+        .base_url(format!("http://{}", ADDR))
+        .sim_network(network.clone())
+}
+
+fn link(one_way_ms: u64) -> LinkConfig {
+    LinkConfig::new().latency(Duration::from_millis(one_way_ms))
+}
+
+async fn count_stream(client: &QuillClient) -> usize {
+    client
+        .call_server_streaming("echo.v1.Echo", "Count", Bytes::new())
+        .await
+        .unwrap()
+        .map(|message| message.unwrap())
+        .collect::<Vec<_>>()
+        .await
+        .len()
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_unary_call_takes_one_round_trip_once_connected() {
+    let network = SimNetwork::new(1).with_link(link(25));
+    serve(&network, false);
+    let client = client(&network).build().unwrap();
+
+    let start = Instant::now();
+    client.call("echo.v1.Echo", "Echo", Bytes::from_static(b"hi")).await.unwrap();
+    let first = start.elapsed();
+    // Connect, then the request
+    assert!(first >= Duration::from_millis(100), "first call took {:?}", first);
+
+    let start = Instant::now();
+    let reply = client.call("echo.v1.Echo", "Echo", Bytes::from_static(b"hi")).await.unwrap();
+    assert_eq!(reply, Bytes::from_static(b"hi"));
+    assert_eq!(start.elapsed(), Duration::from_millis(50));
+    assert_eq!(network.stats().connections, 1);
+}
+
+async fn lossy_run(seed: u64) -> (Duration, quill_transport::sim::SimStats) {
+    let network = SimNetwork::new(seed).with_link(
+        link(10)
+            .jitter(Duration::from_millis(10))
+            .loss(0.1)
+            .retransmit_timeout(Duration::from_millis(100)),
+    );
+    serve(&network, false);
+    let client = client(&network).build().unwrap();
+
+    let start = Instant::now();
+    for _ in 0..10 {
+        client.call("echo.v1.Echo", "Echo", Bytes::from(vec![7u8; 8192])).await.unwrap();
+    }
+    (start.elapsed(), network.stats())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_lossy_runs_replay_with_same_seed() {
+    let (elapsed, stats) = lossy_run(7).await;
+    assert!(stats.retransmissions > 0);
+    assert_eq!(lossy_run(7).await, (elapsed, stats));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_credit_flow_control_paces_stream_by_round_trips() {
+    let rtt = Duration::from_millis(100);
+
+    let network = SimNetwork::new(1).with_link(link(50));
+    serve(&network, false);
+    let unlimited = client(&network).build().unwrap();
+    unlimited.call("echo.v1.Echo", "Echo", Bytes::new()).await.unwrap();
+    let start = Instant::now();
+    assert_eq!(count_stream(&unlimited).await, 20);
+    let unlimited_elapsed = start.elapsed();
+    assert!(unlimited_elapsed < rtt * 2, "unlimited stream took {:?}", unlimited_elapsed);
+
+    let network = SimNetwork::new(1).with_link(link(50));
+    serve(&network, true);
+    let limited = client(&network)
+        .flow_control(FlowControlConfig::new(4).refill_threshold(4))
+        .build()
+        .unwrap();
+    limited.call("echo.v1.Echo", "Echo", Bytes::new()).await.unwrap();
+    let start = Instant::now();
+    assert_eq!(count_stream(&limited).await, 20);
+    // Four messages per credit round trip
+    let limited_elapsed = start.elapsed();
+    assert!(limited_elapsed >= rtt * 5, "flow-controlled stream took {:?}", limited_elapsed);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_policy_backs_off_through_refused_connects() {
+    let network = SimNetwork::new(1).with_link(link(5));
+    serve(&network, false);
+    network.refuse_connects(ADDR, 2);
+    let client = client(&network).build().unwrap();
+    let policy = RetryPolicy::new()
+        .max_attempts(3)
+        .initial_backoff(Duration::from_millis(100))
+        .jitter(0.0);
+
+    let start = Instant::now();
+    let reply = retry_with_policy(&policy, || client.call("echo.v1.Echo", "Echo", Bytes::from_static(b"hi")))
+        .await
+        .unwrap();
+    assert_eq!(reply, Bytes::from_static(b"hi"));
+    // Backoffs of 200ms and 400ms before the third attempt
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(600), "took {:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(700), "took {:?}", elapsed);
+    assert_eq!(network.stats().refused, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_partition_times_out_calls_until_healed() {
+    let network = SimNetwork::new(1).with_link(link(5));
+    serve(&network, false);
+    let client = client(&network).timeout(Duration::from_millis(300)).build().unwrap();
+    client.call("echo.v1.Echo", "Echo", Bytes::new()).await.unwrap();
+
+    network.partition(ADDR);
+    let start = Instant::now();
+    let err = client.call("echo.v1.Echo", "Echo", Bytes::new()).await.unwrap_err();
+    assert_eq!(err.code(), ErrorCode::DeadlineExceeded);
+    assert_eq!(start.elapsed(), Duration::from_millis(300));
+
+    network.heal(ADDR);
+    client.call("echo.v1.Echo", "Echo", Bytes::new()).await.unwrap();
+}
@@ -0,0 +1,102 @@
+//! End-to-end tests for cursor streams resumed from a persisted cursor
+
+use bytes::Bytes;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{QuillError, StreamCursor};
+use quill_server::{QuillServer, RpcRouter};
+use tokio_stream::StreamExt;
+
+/// Lists the numbers below the limit in the request, after the cursor if any
+fn router() -> RpcRouter {
+    let mut router = RpcRouter::new();
+    router.register_cursor_streaming("test.Numbers/List", 4, |req: Bytes, cursor: Option<StreamCursor>| async move {
+        let limit: u32 = std::str::from_utf8(&req)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| QuillError::Rpc("invalid limit".to_string()))?;
+        let start = match cursor {
+            Some(cursor) => u32::from_be_bytes(cursor.as_bytes()[..].try_into().unwrap()) + 1,
+            None => 0,
+        };
+        Ok(tokio_stream::iter(start..limit).map(|n| {
+            Ok((Bytes::from(n.to_string()), StreamCursor::new(n.to_be_bytes().to_vec())))
+        }))
+    });
+    router
+}
+
+async fn spawn(router: RpcRouter) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+fn numbers(messages: &[Bytes]) -> Vec<u32> {
+    messages
+        .iter()
+        .map(|m| std::str::from_utf8(m).unwrap().parse().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_resume_from_persisted_cursor() {
+    let url = spawn(router()).await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+
+    // Stop reading after the first cursor frame and persist it
+    let mut stream = client
+        .call_cursor_stream("test.Numbers", "List", Bytes::from("10"), None, RequestOptions::default())
+        .await
+        .unwrap();
+    let mut received = Vec::new();
+    while stream.cursor().is_none() {
+        received.push(stream.next().await.unwrap().unwrap());
+    }
+    let cursor = stream.cursor().cloned().unwrap();
+    drop(stream);
+    assert_eq!(numbers(&received), vec![0, 1, 2, 3, 4]);
+    assert_eq!(cursor.as_bytes()[..], 3u32.to_be_bytes());
+
+    // Resuming replays nothing before the cursor
+    let stream = client
+        .call_cursor_stream("test.Numbers", "List", Bytes::from("10"), Some(&cursor), RequestOptions::default())
+        .await
+        .unwrap();
+    let mut stream = stream.decode_with(|bytes| Ok(numbers(&[bytes])[0]));
+    let mut resumed = Vec::new();
+    while let Some(n) = stream.next().await {
+        resumed.push(n.unwrap());
+    }
+    assert_eq!(resumed, vec![4, 5, 6, 7, 8, 9]);
+    assert_eq!(stream.cursor().unwrap().as_bytes()[..], 9u32.to_be_bytes());
+
+    // Plain streaming calls skip cursor frames
+    let plain: Vec<Bytes> = client
+        .call_server_streaming("test.Numbers", "List", Bytes::from("6"))
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(numbers(&plain), vec![0, 1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn test_invalid_resume_token_is_rejected() {
+    let url = spawn(router()).await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+
+    let options = RequestOptions::new().header(
+        http::HeaderName::from_static(quill_core::RESUME_TOKEN_HEADER),
+        http::HeaderValue::from_static("not-hex"),
+    );
+    let error = client
+        .call_cursor_stream("test.Numbers", "List", Bytes::from("10"), None, options)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(error, QuillError::ProblemDetails(pd) if pd.status == 400));
+}
@@ -0,0 +1,85 @@
+//! End-to-end tests for idle stream collection
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::QuillError;
+use quill_server::{
+    QuillServer, RpcResponse, RpcRouter, StreamGcConfig, StreamLeak, STREAM_GC_LEAKS_METHOD,
+    STREAM_GC_SERVICE,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+async fn spawn(config: StreamGcConfig) -> QuillClient {
+    let mut router = RpcRouter::new();
+    // Sends one message, then hangs without ending the stream
+    router.register("test.Feed/Watch", |_req: Bytes| async move {
+        let first = tokio_stream::iter(vec![Ok::<_, QuillError>(Bytes::from_static(b"first"))]);
+        Ok(RpcResponse::streaming(first.chain(tokio_stream::pending())))
+    });
+    router.enable_stream_gc(config);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_leaks_endpoint_lists_stalled_stream() {
+    let client = spawn(
+        StreamGcConfig::new()
+            .scan_interval(Duration::from_millis(20))
+            .suspect_after(Duration::from_millis(50))
+            .cancel_after(Duration::ZERO),
+    )
+    .await;
+
+    let mut stream =
+        client.call_server_streaming("test.Feed", "Watch", Bytes::new()).await.unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"first"));
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    let body = client.call(STREAM_GC_SERVICE, STREAM_GC_LEAKS_METHOD, Bytes::new()).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["active"], 1);
+    assert_eq!(json["suspected_total"], 1);
+    assert_eq!(json["cancelled_total"], 0);
+    let leak = &json["leaks"][0];
+    assert_eq!(leak["method"], "test.Feed/Watch");
+    assert_eq!(leak["side"], "response");
+    assert!(leak["idle_ms"].as_u64().unwrap() >= 50);
+}
+
+#[tokio::test]
+async fn test_stalled_stream_is_cancelled() {
+    let cancelled = Arc::new(Mutex::new(Vec::<StreamLeak>::new()));
+    let recorded = Arc::clone(&cancelled);
+    let client = spawn(
+        StreamGcConfig::new()
+            .scan_interval(Duration::from_millis(20))
+            .suspect_after(Duration::from_millis(50))
+            .cancel_after(Duration::from_millis(100))
+            .on_cancel(move |leak| recorded.lock().unwrap().push(leak.clone())),
+    )
+    .await;
+
+    let mut stream =
+        client.call_server_streaming("test.Feed", "Watch", Bytes::new()).await.unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"first"));
+
+    let error = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("stalled stream was not cancelled")
+        .unwrap()
+        .unwrap_err();
+    assert!(error.is_stream_idle_timeout(), "{:?}", error);
+
+    let cancelled = cancelled.lock().unwrap();
+    assert_eq!(cancelled.len(), 1);
+    assert_eq!(cancelled[0].method, "test.Feed/Watch");
+    assert_eq!(cancelled[0].bytes, 5);
+}
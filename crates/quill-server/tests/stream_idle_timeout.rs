@@ -0,0 +1,85 @@
+//! End-to-end tests for stream idle timeouts
+
+use bytes::Bytes;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::QuillError;
+use quill_server::{QuillServer, RpcResponse, RpcRouter, StreamIdleConfig, StreamIdleEvent, StreamSide};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+async fn spawn(idle: Option<StreamIdleConfig>) -> QuillClient {
+    let mut router = RpcRouter::new();
+    // Sends one message, then hangs without ending the stream
+    router.register("test.Feed/Watch", |_req: Bytes| async move {
+        let first = tokio_stream::iter(vec![Ok::<_, QuillError>(Bytes::from_static(b"first"))]);
+        Ok(RpcResponse::streaming(first.chain(tokio_stream::pending())))
+    });
+    if let Some(config) = idle {
+        router.enable_stream_idle_timeout(config);
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_server_cancels_idle_stream() {
+    let events = Arc::new(Mutex::new(Vec::<StreamIdleEvent>::new()));
+    let recorded = Arc::clone(&events);
+    let client = spawn(Some(
+        StreamIdleConfig::new(Duration::from_millis(100))
+            .on_idle_timeout(move |event| recorded.lock().unwrap().push(event.clone())),
+    ))
+    .await;
+
+    let mut stream = client
+        .call_server_streaming("test.Feed", "Watch", Bytes::new())
+        .await
+        .unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"first"));
+
+    let error = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("idle stream was not cancelled")
+        .unwrap()
+        .unwrap_err();
+    assert!(error.is_stream_idle_timeout(), "{:?}", error);
+    match error {
+        QuillError::ProblemDetails(pd) => assert_eq!(pd.status, 408),
+        other => panic!("expected problem details, got {:?}", other),
+    }
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].method, "test.Feed/Watch");
+    assert_eq!(events[0].side, StreamSide::Response);
+}
+
+#[tokio::test]
+async fn test_client_times_out_idle_stream() {
+    let client = spawn(None).await;
+
+    let mut stream = client
+        .call_server_streaming_with_options(
+            "test.Feed",
+            "Watch",
+            Bytes::new(),
+            RequestOptions::new().stream_idle_timeout(Duration::from_millis(100)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"first"));
+
+    let error = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("client idle timeout did not fire")
+        .unwrap()
+        .unwrap_err();
+    assert!(error.is_stream_idle_timeout(), "{:?}", error);
+    assert!(stream.next().await.is_none());
+}
@@ -0,0 +1,159 @@
+//! End-to-end tests for per-tenant stream and bandwidth limits
+
+use bytes::Bytes;
+use http::{HeaderName, HeaderValue};
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{BatchEntry, QuillError};
+use quill_server::middleware::{ApiKeyValidator, AuthLayer, AuthScheme};
+use quill_server::{
+    BatchRpcConfig, QuillServer, RpcResponse, RpcRouter, TenantIsolationConfig, TenantLimits,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_stream::StreamExt;
+
+async fn spawn(config: impl FnOnce(TenantIsolationConfig) -> TenantIsolationConfig) -> QuillClient {
+    let mut router = RpcRouter::new();
+    // Sends one message, then hangs without ending the stream
+    router.register("test.Model/Generate", |_req: Bytes| async move {
+        let first = tokio_stream::iter(vec![Ok::<_, QuillError>(Bytes::from_static(b"token"))]);
+        Ok(RpcResponse::streaming(first.chain(tokio_stream::pending())))
+    });
+    router.register_unary("test.Model/Embed", |_req: Bytes| async move {
+        Ok(Bytes::from(vec![0u8; 1000]))
+    });
+
+    let validator = ApiKeyValidator::new()
+        .with_key("key-a".to_string(), "tenant-a".to_string())
+        .with_key("key-b".to_string(), "tenant-b".to_string());
+    let auth = AuthLayer::new(
+        AuthScheme::ApiKey {
+            header_name: "x-api-key".to_string(),
+        },
+        Arc::new(validator),
+    );
+    router.enable_tenant_isolation(config(TenantIsolationConfig::new(auth)));
+    router.enable_batch(BatchRpcConfig::default());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+fn as_tenant(key: &'static str) -> RequestOptions {
+    RequestOptions::new().header(HeaderName::from_static("x-api-key"), HeaderValue::from_static(key))
+}
+
+fn status(error: QuillError) -> u16 {
+    match error {
+        QuillError::ProblemDetails(pd) => pd.status,
+        other => panic!("expected problem details, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_noisy_tenant_is_limited_alone() {
+    let client = spawn(|config| {
+        config.default_limits(TenantLimits::default().max_concurrent_streams(1))
+    })
+    .await;
+
+    let mut stream = client
+        .call_server_streaming_with_options("test.Model", "Generate", Bytes::new(), as_tenant("key-a"))
+        .await
+        .unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"token"));
+
+    // A second connection does not give the tenant more streams
+    let other_connection = QuillClient::new(client.base_url());
+    let error = other_connection
+        .call_server_streaming_with_options("test.Model", "Generate", Bytes::new(), as_tenant("key-a"))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(status(error), 429);
+
+    // Another tenant is unaffected
+    let response = client
+        .call_with_options("test.Model", "Embed", Bytes::new(), as_tenant("key-b"))
+        .await
+        .unwrap();
+    assert_eq!(response.len(), 1000);
+
+    // The slot is returned once the stream is dropped
+    drop(stream);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let _stream = other_connection
+        .call_server_streaming_with_options("test.Model", "Generate", Bytes::new(), as_tenant("key-a"))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_rejects_unauthenticated_calls() {
+    let client = spawn(|config| config).await;
+
+    let error = client
+        .call_with_options("test.Model", "Embed", Bytes::new(), as_tenant("wrong-key"))
+        .await
+        .unwrap_err();
+    assert_eq!(status(error), 401);
+}
+
+#[tokio::test]
+async fn test_batches_are_admitted_like_calls() {
+    let client = spawn(|config| {
+        config.default_limits(TenantLimits::default().max_concurrent_streams(1))
+    })
+    .await;
+    let entries = vec![BatchEntry::new("test.Model", "Embed", Bytes::new())];
+
+    let error = client.call_batch(&entries).await.unwrap_err();
+    assert_eq!(status(error), 401);
+
+    // A batch takes a stream slot of its tenant like any other call
+    let mut stream = client
+        .call_server_streaming_with_options("test.Model", "Generate", Bytes::new(), as_tenant("key-a"))
+        .await
+        .unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap(), Bytes::from_static(b"token"));
+    let error = client
+        .call_batch_with_options(&entries, as_tenant("key-a"))
+        .await
+        .unwrap_err();
+    assert_eq!(status(error), 429);
+
+    let results = client.call_batch_with_options(&entries, as_tenant("key-b")).await.unwrap();
+    assert_eq!(results[0].payload.len(), 1000);
+}
+
+#[tokio::test]
+async fn test_bandwidth_shared_across_calls() {
+    let client = spawn(|config| {
+        config.tenant("tenant-a", TenantLimits::default().max_bytes_per_second(2000))
+    })
+    .await;
+
+    // The first 2000 bytes fit the burst; the next 2000 wait for one second of budget
+    let started = Instant::now();
+    for _ in 0..4 {
+        client
+            .call_with_options("test.Model", "Embed", Bytes::new(), as_tenant("key-a"))
+            .await
+            .unwrap();
+    }
+    assert!(started.elapsed() >= Duration::from_millis(900), "{:?}", started.elapsed());
+
+    // Tenants without a bandwidth limit are not paced
+    let started = Instant::now();
+    for _ in 0..4 {
+        client
+            .call_with_options("test.Model", "Embed", Bytes::new(), as_tenant("key-b"))
+            .await
+            .unwrap();
+    }
+    assert!(started.elapsed() < Duration::from_millis(500), "{:?}", started.elapsed());
+}
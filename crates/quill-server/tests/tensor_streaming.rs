@@ -0,0 +1,144 @@
+//! End-to-end tests for tensor streaming handlers
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::QuillError;
+use quill_server::{tensor_channel, QuillServer, RpcResponse, RpcRouter};
+use quill_tensor::stream::ReceiverEvent;
+use quill_tensor::{DType, Tensor, TensorMeta, TensorPassthrough, TensorReceiver, TensorSender};
+use std::sync::Arc;
+use tokio_stream::StreamExt;
+
+fn sample_tensor() -> Tensor {
+    let values: Vec<f32> = (0..64).map(|i| i as f32).collect();
+    Tensor::from_f32(&TensorMeta::new(vec![8, 8], DType::Float32), &values)
+}
+
+fn large_tensor() -> Tensor {
+    let values: Vec<f32> = (0..128 * 128).map(|i| i as f32).collect();
+    Tensor::from_f32(&TensorMeta::new(vec![128, 128], DType::Float32), &values)
+}
+
+async fn serve(server: QuillServer) -> QuillClient {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = server.serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+async fn spawn() -> QuillClient {
+    let server = QuillServer::builder()
+        .register_tensor_streaming("test.Tensors/Frames", |_req: Bytes| async move {
+            let frames = TensorSender::with_chunk_size(64).encode_tensor(&sample_tensor());
+            Ok(tokio_stream::iter(frames.into_iter().map(Ok::<_, QuillError>)))
+        })
+        .register_tensor_streaming("test.Tensors/Sink", |_req: Bytes| async move {
+            let (sink, frames) = tensor_channel(TensorSender::with_chunk_size(100), 4);
+            tokio::spawn(async move {
+                let tensor = sample_tensor();
+                sink.send_meta(&tensor.meta).await?;
+                sink.send_payload(tensor.data.clone()).await?;
+                sink.send_end().await
+            });
+            Ok(frames)
+        })
+        .register_tensor_streaming("test.Tensors/Large", |_req: Bytes| async move {
+            let frames = TensorSender::with_chunk_size(32 * 1024).encode_tensor(&large_tensor());
+            Ok(tokio_stream::iter(frames.into_iter().map(Ok::<_, QuillError>)))
+        })
+        .build();
+    serve(server).await
+}
+
+/// Relay that forwards every message of `upstream`'s Large method as received
+///
+/// Its Limited method relays the same stream, but only tensors up to 1 KB.
+async fn spawn_relay(upstream: QuillClient) -> QuillClient {
+    let upstream = Arc::new(upstream);
+    let mut router = RpcRouter::new();
+    for (method, limit) in [("Large", None), ("Limited", Some(1024))] {
+        let upstream = Arc::clone(&upstream);
+        router.register(format!("test.Tensors/{}", method), move |req: Bytes| {
+            let upstream = Arc::clone(&upstream);
+            async move {
+                let messages = upstream.call_server_streaming("test.Tensors", "Large", req).await?;
+                let passthrough = match limit {
+                    Some(limit) => TensorPassthrough::new().with_max_tensor_bytes(limit),
+                    None => TensorPassthrough::new(),
+                };
+                Ok(RpcResponse::tensor_passthrough(messages, passthrough))
+            }
+        });
+    }
+    serve(QuillServer::new(router)).await
+}
+
+/// Receive a tensor, returning it with the number of messages it took
+async fn receive(client: &QuillClient, method: &str) -> (Tensor, usize) {
+    let mut stream = client
+        .call_server_streaming("test.Tensors", method, Bytes::new())
+        .await
+        .unwrap();
+    let mut receiver = TensorReceiver::new();
+    let mut messages = 0;
+    while let Some(message) = stream.next().await {
+        receiver.feed_bytes(message.unwrap());
+        messages += 1;
+        loop {
+            match receiver.poll().unwrap() {
+                ReceiverEvent::NeedMoreData | ReceiverEvent::End => break,
+                _ => {}
+            }
+        }
+    }
+    (receiver.take_tensor().unwrap(), messages)
+}
+
+#[tokio::test]
+async fn test_tensor_frames_are_sent_incrementally() {
+    let client = spawn().await;
+
+    let (tensor, messages) = receive(&client, "Frames").await;
+    assert_eq!(tensor.as_f32(), sample_tensor().as_f32());
+    // TENSOR_META, four 64-byte payload chunks and END_STREAM
+    assert_eq!(messages, 6);
+}
+
+#[tokio::test]
+async fn test_tensor_sink_streams_payload() {
+    let client = spawn().await;
+
+    let (tensor, messages) = receive(&client, "Sink").await;
+    assert_eq!(tensor.shape(), &[8, 8]);
+    assert_eq!(tensor.as_f32(), sample_tensor().as_f32());
+    assert_eq!(messages, 5);
+}
+
+#[tokio::test]
+async fn test_relay_forwards_large_tensor_chunks() {
+    let relay = spawn_relay(spawn().await).await;
+
+    let (tensor, messages) = receive(&relay, "Large").await;
+    assert_eq!(tensor.as_f32(), large_tensor().as_f32());
+    // TENSOR_META, two 32KB payload chunks and END_STREAM
+    assert_eq!(messages, 4);
+}
+
+#[tokio::test]
+async fn test_relay_rejects_tensor_over_limit() {
+    let relay = spawn_relay(spawn().await).await;
+
+    let mut stream = relay
+        .call_server_streaming("test.Tensors", "Limited", Bytes::new())
+        .await
+        .unwrap();
+    // The metadata is checked before any payload is forwarded
+    let error = match stream.next().await {
+        Some(Err(error)) => error,
+        other => panic!("expected the relay to fail the stream, got {:?}", other),
+    };
+    assert!(error.to_string().contains("tensor too large"), "{}", error);
+    assert!(error.to_string().contains("502"), "{}", error);
+}
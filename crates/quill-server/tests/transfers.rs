@@ -0,0 +1,86 @@
+//! End-to-end tests for progress-reporting uploads and downloads
+
+use bytes::Bytes;
+use quill_client::{ChunkedUpload, QuillClient, RequestOptions, TransferState};
+use quill_core::QuillError;
+use quill_server::{ChunkedUploadConfig, QuillServer, RpcResponse, RpcRouter};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+const FILE_CHUNKS: usize = 10;
+const FILE_CHUNK_SIZE: usize = 1000;
+
+async fn spawn() -> Arc<QuillClient> {
+    let mut router = RpcRouter::new();
+    router.enable_chunked_upload(ChunkedUploadConfig::default());
+    router.register_unary("test.Files/Put", |req: Bytes| async move {
+        Ok(Bytes::from(req.len().to_string()))
+    });
+    router.register("test.Files/Get", |_req: Bytes| async move {
+        let chunks = tokio_stream::iter(0..FILE_CHUNKS).then(|i| async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok::<_, QuillError>(Bytes::from(vec![i as u8; FILE_CHUNK_SIZE]))
+        });
+        Ok(RpcResponse::streaming(chunks))
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    Arc::new(QuillClient::new(format!("http://{}", addr)))
+}
+
+#[tokio::test]
+async fn test_upload_reports_progress() {
+    let client = spawn().await;
+    let body = Bytes::from(vec![7u8; 64 * 1024]);
+
+    let options = RequestOptions::new().chunked_upload(ChunkedUpload::new().chunk_size(8 * 1024));
+    let mut handle = client.upload("test.Files", "Put", body.clone(), options);
+    assert_eq!(handle.progress().total_bytes, Some(body.len() as u64));
+
+    let response = (&mut handle).await.unwrap();
+    assert_eq!(response, Bytes::from(body.len().to_string()));
+
+    let progress = handle.progress();
+    assert_eq!(progress.state, TransferState::Completed);
+    assert_eq!(progress.bytes_transferred, body.len() as u64);
+    assert_eq!(progress.fraction(), Some(1.0));
+}
+
+#[tokio::test]
+async fn test_download_pause_resume() {
+    let client = spawn().await;
+    let total = (FILE_CHUNKS * FILE_CHUNK_SIZE) as u64;
+    let handle = client.download("test.Files", "Get", Bytes::new(), RequestOptions::new(), Some(total));
+
+    tokio::time::sleep(Duration::from_millis(70)).await;
+    assert!(handle.pause());
+    // The message in flight when pausing may still be counted
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    let paused = handle.progress();
+    assert_eq!(paused.state, TransferState::Paused);
+    assert!(paused.bytes_transferred < total);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(handle.progress().bytes_transferred, paused.bytes_transferred);
+
+    assert!(handle.resume());
+    let body = handle.await.unwrap();
+    assert_eq!(body.len() as u64, total);
+    assert_eq!(&body[..FILE_CHUNK_SIZE], &[0u8; FILE_CHUNK_SIZE][..]);
+}
+
+#[tokio::test]
+async fn test_download_cancel() {
+    let client = spawn().await;
+    let handle = client.download("test.Files", "Get", Bytes::new(), RequestOptions::new(), None);
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(handle.cancel());
+    assert_eq!(handle.state(), TransferState::Cancelled);
+    assert!(matches!(handle.await, Err(QuillError::Rpc(msg)) if msg == "Transfer cancelled"));
+}
@@ -0,0 +1,100 @@
+//! End-to-end tests for serving over a Unix domain socket
+#![cfg(unix)]
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quill_core::QuillError;
+use quill_server::{QuillServer, RpcResponse, RpcRouter};
+use std::path::Path;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+fn router() -> RpcRouter {
+    let mut router = RpcRouter::new();
+    router.register_unary("test.Echo/Unary", |req: Bytes| async move { Ok(req) });
+    router.register("test.Echo/Repeat", |req: Bytes| async move {
+        let messages = (0..3).map(move |i| Ok(Bytes::from(format!("{}-{}", String::from_utf8_lossy(&req), i))));
+        Ok(RpcResponse::streaming(tokio_stream::iter(messages)))
+    });
+    router.register_client_streaming("test.Echo/Concat", |stream| async move {
+        let parts: Vec<Bytes> = stream.collect::<Result<_, _>>().await?;
+        Ok(RpcResponse::unary(Bytes::from(parts.concat())))
+    });
+    router.register_bidi_streaming("test.Echo/Upper", |stream| async move {
+        Ok(RpcResponse::streaming(
+            stream.map(|message| message.map(|m| Bytes::from(m.to_ascii_uppercase()))),
+        ))
+    });
+    router
+}
+
+async fn spawn(path: &Path) {
+    let socket = path.to_path_buf();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router()).serve_uds(socket).await;
+    });
+
+    // Wait until the server accepts on the socket
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while tokio::net::UnixStream::connect(path).await.is_err() {
+        assert!(tokio::time::Instant::now() < deadline, "nothing listening on {}", path.display());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+fn messages(items: &[&'static str]) -> std::pin::Pin<Box<dyn tokio_stream::Stream<Item = Result<Bytes, QuillError>> + Send>> {
+    let items: Vec<_> = items.iter().map(|m| Ok(Bytes::from_static(m.as_bytes()))).collect();
+    Box::pin(tokio_stream::iter(items))
+}
+
+#[tokio::test]
+async fn test_all_streaming_modes_over_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("quill.sock");
+    spawn(&socket).await;
+    let client = QuillClient::builder().uds_path(&socket).build().unwrap();
+
+    let response = client.call("test.Echo", "Unary", Bytes::from("ping")).await.unwrap();
+    assert_eq!(response, Bytes::from("ping"));
+
+    let repeated: Vec<Bytes> = client
+        .call_server_streaming("test.Echo", "Repeat", Bytes::from("tick"))
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(repeated, vec!["tick-0", "tick-1", "tick-2"]);
+
+    let concatenated = client
+        .call_client_streaming("test.Echo", "Concat", messages(&["a", "b", "c"]))
+        .await
+        .unwrap();
+    assert_eq!(concatenated, Bytes::from("abc"));
+
+    let upper: Vec<Bytes> = client
+        .call_bidi_streaming("test.Echo", "Upper", messages(&["x", "y"]))
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(upper, vec!["X", "Y"]);
+}
+
+#[tokio::test]
+async fn test_stale_socket_is_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("quill.sock");
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+    assert!(socket.exists());
+
+    spawn(&socket).await;
+    let client = QuillClient::builder().uds_path(&socket).build().unwrap();
+    let response = client.call("test.Echo", "Unary", Bytes::from("again")).await.unwrap();
+    assert_eq!(response, Bytes::from("again"));
+
+    // A missing socket is a transport error
+    let missing = QuillClient::builder().uds_path(dir.path().join("missing.sock")).build().unwrap();
+    assert!(missing.call("test.Echo", "Unary", Bytes::new()).await.is_err());
+}
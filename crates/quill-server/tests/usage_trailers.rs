@@ -0,0 +1,76 @@
+//! End-to-end tests for usage trailers
+
+use bytes::Bytes;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{QuillError, Usage};
+use quill_server::{QuillServer, RequestContext, RpcResponse, RpcRouter};
+use tokio_stream::StreamExt;
+
+async fn spawn() -> QuillClient {
+    let mut router = RpcRouter::new();
+    router.register("test.Llm/Generate", |req: Bytes| async move {
+        // Input is counted up front, output as the stream is consumed
+        let usage = RequestContext::current().unwrap_or_default().usage().clone();
+        usage.record_input(req.len() as u64);
+        let tokens = tokio_stream::iter(0..req[0]).map(move |i| {
+            usage.record_output(1);
+            Ok::<_, QuillError>(Bytes::from(format!("tok-{}", i)))
+        });
+        Ok(RpcResponse::streaming(tokens))
+    });
+    router.register("test.Llm/Unmetered", |_req: Bytes| async move {
+        Ok(RpcResponse::streaming(tokio_stream::iter([Ok(Bytes::from("done"))])))
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = QuillServer::new(router).serve_with_listener(listener).await;
+    });
+    QuillClient::new(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_usage_recorded_by_the_stream_is_reported() {
+    let client = spawn().await;
+    let mut stream = client
+        .call_server_streaming_usage("test.Llm", "Generate", Bytes::from_static(&[4, 0, 0]), RequestOptions::new())
+        .await
+        .unwrap();
+
+    assert!(stream.usage().is_none());
+    let mut tokens = Vec::new();
+    while let Some(item) = stream.next().await {
+        tokens.push(item.unwrap());
+    }
+
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(stream.usage(), Some(&Usage::new(3, 4)));
+}
+
+#[tokio::test]
+async fn test_unmetered_stream_has_no_usage() {
+    let client = spawn().await;
+    let mut stream = client
+        .call_server_streaming_usage("test.Llm", "Unmetered", Bytes::new(), RequestOptions::new())
+        .await
+        .unwrap();
+
+    while let Some(item) = stream.next().await {
+        item.unwrap();
+    }
+    assert!(stream.usage().is_none());
+}
+
+#[tokio::test]
+async fn test_plain_streaming_client_skips_usage() {
+    let client = spawn().await;
+    let stream = client
+        .call_server_streaming("test.Llm", "Generate", Bytes::from_static(&[2]))
+        .await
+        .unwrap();
+
+    let tokens: Vec<_> = stream.collect().await;
+    assert_eq!(tokens.len(), 2);
+    assert!(tokens.iter().all(|t| t.is_ok()));
+}
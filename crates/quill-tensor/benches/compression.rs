@@ -0,0 +1,125 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use quill_tensor::{f16, DType, Tensor, TensorCodec, TensorMeta, TensorReceiver, TensorSender};
+
+/// 1M elements of each sample tensor
+const NUMEL: usize = 1 << 20;
+
+/// Deterministic xorshift noise in [0, 1)
+struct Noise(u64);
+
+impl Noise {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Samples resembling the tensors worth compressing, and one that isn't
+fn samples() -> Vec<(&'static str, Tensor)> {
+    let mut noise = Noise(0x9E3779B97F4A7C15);
+
+    // Roughly normal f16 values, like embeddings
+    let embeddings: Vec<u8> = (0..NUMEL)
+        .flat_map(|_| {
+            let sum: f32 = (0..4).map(|_| noise.next()).sum();
+            f16::from_f32((sum - 2.0) * 0.5).to_le_bytes()
+        })
+        .collect();
+    let embeddings =
+        Tensor::new(TensorMeta::new(vec![1024, 1024], DType::Float16), embeddings.into());
+
+    // Mostly-zero f32 activations, like padded KV cache blocks
+    let sparse: Vec<f32> = (0..NUMEL)
+        .map(|_| {
+            let x = noise.next();
+            if x < 0.9 {
+                0.0
+            } else {
+                x
+            }
+        })
+        .collect();
+    let sparse = Tensor::from_f32(&TensorMeta::new(vec![1024, 1024], DType::Float32), &sparse);
+
+    // Uniform noise, the worst case
+    let random: Vec<f32> = (0..NUMEL).map(|_| noise.next()).collect();
+    let random = Tensor::from_f32(&TensorMeta::new(vec![1024, 1024], DType::Float32), &random);
+
+    vec![("embeddings_f16", embeddings), ("sparse_f32", sparse), ("random_f32", random)]
+}
+
+fn codecs() -> Vec<(&'static str, Option<TensorCodec>)> {
+    vec![
+        ("none", None),
+        ("lz4", Some(TensorCodec::Lz4)),
+        ("zstd1", Some(TensorCodec::Zstd(1))),
+        ("zstd3", Some(TensorCodec::Zstd(3))),
+    ]
+}
+
+fn sender(codec: Option<TensorCodec>) -> TensorSender {
+    match codec {
+        Some(codec) => TensorSender::new().with_compression(codec),
+        None => TensorSender::new(),
+    }
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tensor_compression_encode");
+    group.sample_size(20);
+
+    for (name, tensor) in samples() {
+        group.throughput(Throughput::Bytes(tensor.byte_size() as u64));
+        for (codec_name, codec) in codecs() {
+            let sender = sender(codec);
+
+            // Bandwidth side of the tradeoff
+            let wire: usize = sender.encode_tensor(&tensor).iter().map(|f| f.encoded_size()).sum();
+            println!(
+                "{}/{}: {} -> {} bytes ({:.2}x)",
+                name,
+                codec_name,
+                tensor.byte_size(),
+                wire,
+                tensor.byte_size() as f64 / wire as f64
+            );
+
+            group.bench_with_input(BenchmarkId::new(name, codec_name), &tensor, |b, tensor| {
+                b.iter(|| black_box(sender.encode_tensor(black_box(tensor))))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tensor_compression_decode");
+    group.sample_size(20);
+
+    for (name, tensor) in samples() {
+        group.throughput(Throughput::Bytes(tensor.byte_size() as u64));
+        for (codec_name, codec) in codecs() {
+            let frames: Vec<_> =
+                sender(codec).encode_tensor(&tensor).iter().map(|f| f.encode()).collect();
+
+            group.bench_with_input(BenchmarkId::new(name, codec_name), &frames, |b, frames| {
+                b.iter(|| {
+                    let mut receiver = TensorReceiver::new();
+                    for frame in frames {
+                        receiver.feed_bytes(frame.clone());
+                        receiver.poll().unwrap();
+                    }
+                    black_box(receiver.take_tensor().unwrap())
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode);
+criterion_main!(benches);
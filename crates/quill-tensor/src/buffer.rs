@@ -212,6 +212,11 @@ impl CudaBuffer {
         self.device_id
     }
 
+    /// Returns the device memory, e.g. for copies into part of the buffer.
+    pub(crate) fn storage_mut(&mut self) -> &mut CudaSlice<u8> {
+        &mut self.storage
+    }
+
     /// Copies data from host memory to this GPU buffer.
     ///
     /// # Arguments
@@ -266,9 +271,13 @@ impl CudaBuffer {
     /// The returned pointer is only valid for the lifetime of this buffer
     /// and must only be used for CUDA operations on the same device.
     pub fn device_ptr(&self) -> *const u8 {
-        // Note: cudarc's CudaSlice doesn't expose raw pointer directly
-        // This would need unsafe implementation
-        std::ptr::null()
+        self.as_device_ptr() as *const u8
+    }
+
+    /// Returns the device address of the buffer, e.g. for DLPack or the
+    /// CUDA Array Interface.
+    pub fn as_device_ptr(&self) -> u64 {
+        *DevicePtr::device_ptr(&self.storage)
     }
 }
 
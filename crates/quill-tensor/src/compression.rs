@@ -0,0 +1,208 @@
+//! Compression of TENSOR_PAYLOAD frames.
+//!
+//! Embeddings and KV caches often compress well, so a [`TensorSender`] can
+//! compress each payload chunk on its own with a [`TensorCodec`]. A
+//! compressed frame is marked in its reserved bytes:
+//!
+//! ```text
+//! reserved[0] |= COMPRESSED
+//! reserved[1]  = codec id (1 = LZ4, 2 = zstd)
+//! payload      = original length (u32 LE) ++ compressed bytes
+//! ```
+//!
+//! Chunks that don't get smaller are sent as-is, so a stream may mix
+//! compressed and raw frames. Receivers in this crate decompress
+//! transparently; peers that predate compression would take compressed
+//! payloads for tensor data, so only enable it towards receivers that
+//! support it.
+//!
+//! [`TensorSender`]: crate::TensorSender
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::frame::{reserved_flags, FrameType, TensorFrame};
+use crate::stream::TensorStreamError;
+
+/// Size of the original-length prefix of a compressed payload.
+pub(crate) const LENGTH_PREFIX_SIZE: usize = 4;
+
+/// Codec for compressing TENSOR_PAYLOAD chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TensorCodec {
+    /// LZ4 block compression: fast, with modest ratios.
+    Lz4,
+    /// Zstandard at the given level: slower, with better ratios.
+    Zstd(i32),
+}
+
+impl TensorCodec {
+    /// Zstandard at its default level.
+    pub const ZSTD_DEFAULT: Self = Self::Zstd(zstd::DEFAULT_COMPRESSION_LEVEL);
+
+    /// Returns the codec id carried in a compressed frame.
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Lz4 => 1,
+            Self::Zstd(_) => 2,
+        }
+    }
+
+    /// Returns the codec for a frame's codec id.
+    ///
+    /// The level of zstd only matters when compressing.
+    pub const fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Lz4),
+            2 => Some(Self::ZSTD_DEFAULT),
+            _ => None,
+        }
+    }
+
+    /// Returns a human-readable name for this codec.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Lz4 => "lz4",
+            Self::Zstd(_) => "zstd",
+        }
+    }
+
+    /// Creates a TENSOR_PAYLOAD frame for `chunk`, compressed if that makes it smaller.
+    pub fn payload_frame(&self, chunk: Bytes) -> TensorFrame {
+        let Ok(len) = u32::try_from(chunk.len()) else {
+            return TensorFrame::tensor_payload(chunk);
+        };
+        let compressed = match self {
+            Self::Lz4 => lz4::block::compress(&chunk, None, false),
+            Self::Zstd(level) => zstd::bulk::compress(&chunk, *level),
+        };
+        match compressed {
+            Ok(compressed) if LENGTH_PREFIX_SIZE + compressed.len() < chunk.len() => {
+                let mut payload = BytesMut::with_capacity(LENGTH_PREFIX_SIZE + compressed.len());
+                payload.put_u32_le(len);
+                payload.put_slice(&compressed);
+                TensorFrame::with_reserved(
+                    FrameType::TensorPayload,
+                    [reserved_flags::COMPRESSED, self.id(), 0, 0],
+                    payload.freeze(),
+                )
+            }
+            _ => TensorFrame::tensor_payload(chunk),
+        }
+    }
+
+    fn decompress(&self, data: &[u8], len: usize) -> std::io::Result<Vec<u8>> {
+        match self {
+            // Fits: the length came from a u32 and was checked against the limit
+            Self::Lz4 => lz4::block::decompress(data, Some(len as i32)),
+            Self::Zstd(_) => zstd::bulk::decompress(data, len),
+        }
+    }
+}
+
+/// Returns the codec a TENSOR_PAYLOAD frame was compressed with, if any.
+pub fn payload_codec(frame: &TensorFrame) -> Option<u8> {
+    (frame.frame_type == FrameType::TensorPayload
+        && frame.reserved[0] & reserved_flags::COMPRESSED != 0)
+        .then_some(frame.reserved[1])
+}
+
+/// Returns the tensor data carried by a TENSOR_PAYLOAD frame.
+///
+/// Raw payloads are returned as they are. Compressed ones are decompressed,
+/// failing if they would exceed `limit` bytes, e.g. the part of the tensor
+/// still expected.
+pub fn decode_payload(frame: &TensorFrame, limit: usize) -> Result<Bytes, TensorStreamError> {
+    let Some(id) = payload_codec(frame) else {
+        return Ok(frame.payload.clone());
+    };
+    let codec = TensorCodec::from_id(id)
+        .ok_or_else(|| TensorStreamError::Compression(format!("unknown codec id: {}", id)))?;
+    if frame.payload.len() < LENGTH_PREFIX_SIZE {
+        return Err(TensorStreamError::Compression("compressed payload too short".to_string()));
+    }
+    let (prefix, data) = frame.payload.split_at(LENGTH_PREFIX_SIZE);
+    let len = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+    if len > limit || len > i32::MAX as usize {
+        return Err(TensorStreamError::SizeMismatch { expected: limit, actual: len });
+    }
+    let decompressed = codec
+        .decompress(data, len)
+        .map_err(|e| TensorStreamError::Compression(format!("{}: {}", codec.name(), e)))?;
+    if decompressed.len() != len {
+        return Err(TensorStreamError::Compression(format!(
+            "{}: decompressed {} bytes, expected {}",
+            codec.name(),
+            decompressed.len(),
+            len
+        )));
+    }
+    Ok(Bytes::from(decompressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compressible(len: usize) -> Bytes {
+        (0..len).map(|i| (i % 16) as u8).collect::<Vec<_>>().into()
+    }
+
+    #[test]
+    fn test_codecs_round_trip() {
+        let chunk = compressible(64 * 1024);
+        for codec in [TensorCodec::Lz4, TensorCodec::Zstd(3)] {
+            let frame = codec.payload_frame(chunk.clone());
+            assert_eq!(payload_codec(&frame), Some(codec.id()));
+            assert!(frame.payload.len() < chunk.len() / 4, "{} did not compress", codec.name());
+
+            let (decoded, _) = TensorFrame::decode(&frame.encode()).unwrap();
+            assert_eq!(decode_payload(&decoded, chunk.len()).unwrap(), chunk);
+        }
+    }
+
+    #[test]
+    fn test_incompressible_chunk_is_sent_raw() {
+        // xorshift noise doesn't compress
+        let mut state = 0x2545F4914F6CDD1Du64;
+        let noise: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let chunk = Bytes::from(noise);
+
+        let frame = TensorCodec::Lz4.payload_frame(chunk.clone());
+        assert_eq!(payload_codec(&frame), None);
+        assert_eq!(frame.payload, chunk);
+        assert_eq!(decode_payload(&frame, chunk.len()).unwrap().as_ptr(), chunk.as_ptr());
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_payloads() {
+        let chunk = compressible(8192);
+        let frame = TensorCodec::ZSTD_DEFAULT.payload_frame(chunk.clone());
+
+        // More than the receiver still expects
+        assert!(matches!(
+            decode_payload(&frame, chunk.len() - 1),
+            Err(TensorStreamError::SizeMismatch { .. })
+        ));
+
+        let (frame_type, mut reserved, payload) = frame.into_parts();
+        let corrupt = TensorFrame::with_reserved(frame_type, reserved, payload.slice(..20));
+        assert!(matches!(
+            decode_payload(&corrupt, chunk.len()),
+            Err(TensorStreamError::Compression(_))
+        ));
+
+        reserved[1] = 0x7F;
+        let unknown = TensorFrame::with_reserved(frame_type, reserved, payload);
+        assert!(matches!(
+            decode_payload(&unknown, chunk.len()),
+            Err(TensorStreamError::Compression(_))
+        ));
+    }
+}
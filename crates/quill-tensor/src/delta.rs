@@ -0,0 +1,540 @@
+//! Delta streaming for repeatedly-sent similar tensors.
+//!
+//! Successive KV-cache or LoRA tensors often differ in only a few bytes. In
+//! delta mode the sender keeps a rolling reference per tensor name and sends
+//! only the XOR of the changed byte ranges, with a full keyframe every
+//! `keyframe_interval` tensors (or whenever the shape changes).
+//!
+//! # Wire Format
+//!
+//! A keyframe is an ordinary tensor stream:
+//!
+//! ```text
+//! TENSOR_META → TENSOR_PAYLOAD* → END_STREAM
+//! ```
+//!
+//! A delta replaces the payload frames with TENSOR_DELTA frames:
+//!
+//! ```text
+//! TENSOR_META → TENSOR_DELTA* → END_STREAM
+//! ```
+//!
+//! Each TENSOR_DELTA frame carries the reference generation in its reserved
+//! bytes (big-endian u32) and a sequence of runs:
+//!
+//! ```text
+//! ┌────────────┬────────────┬──────────────────┐
+//! │   Offset   │   Length   │   XOR bytes      │
+//! │  (8 bytes) │  (4 bytes) │  (Length bytes)  │
+//! └────────────┴────────────┴──────────────────┘
+//! ```
+//!
+//! Delta mode is negotiated with the `quill-tensor-delta` header. When the
+//! peer does not support it, the sender emits keyframes only, which any
+//! [`TensorReceiver`](crate::stream::TensorReceiver) can decode.
+
+use std::collections::HashMap;
+
+use bytes::{Bytes, BytesMut};
+
+use crate::frame::{FrameType, TensorFrame, TensorFrameParser};
+use crate::stream::{decode_tensor_meta, TensorSender, TensorStreamError};
+use crate::tensor::{Tensor, TensorMeta};
+
+/// Header used to negotiate delta streaming.
+pub const DELTA_ENCODING_HEADER: &str = "quill-tensor-delta";
+
+/// Delta encoding identifier advertised in [`DELTA_ENCODING_HEADER`].
+pub const DELTA_ENCODING_XOR_V1: &str = "xor-v1";
+
+/// Size of a run header (offset + length) in bytes.
+const RUN_HEADER_SIZE: usize = 12;
+
+/// Unchanged gaps shorter than this are folded into the surrounding run.
+const RUN_MERGE_GAP: usize = RUN_HEADER_SIZE;
+
+/// Returns whether a peer's `quill-tensor-delta` header value accepts delta mode.
+pub fn accepts_delta(header_value: Option<&str>) -> bool {
+    header_value
+        .map(|v| v.split(',').any(|item| item.trim() == DELTA_ENCODING_XOR_V1))
+        .unwrap_or(false)
+}
+
+/// Configuration for delta streaming.
+#[derive(Debug, Clone)]
+pub struct DeltaConfig {
+    /// Whether delta frames may be sent (false = keyframes only).
+    pub enabled: bool,
+    /// Send a full keyframe after this many consecutive deltas.
+    pub keyframe_interval: u32,
+    /// Fall back to a keyframe when the delta exceeds this fraction of the tensor size.
+    pub max_delta_ratio: f64,
+}
+
+impl Default for DeltaConfig {
+    fn default() -> Self {
+        Self { enabled: true, keyframe_interval: 32, max_delta_ratio: 0.5 }
+    }
+}
+
+/// Rolling reference kept per tensor name.
+#[derive(Debug, Clone)]
+struct Reference {
+    meta: TensorMeta,
+    data: Bytes,
+    generation: u32,
+    deltas_since_keyframe: u32,
+}
+
+/// Sender that encodes tensors as keyframes or deltas against a rolling reference.
+pub struct DeltaSender {
+    sender: TensorSender,
+    config: DeltaConfig,
+    references: HashMap<String, Reference>,
+}
+
+impl DeltaSender {
+    /// Creates a new delta sender with default configuration.
+    pub fn new() -> Self {
+        Self::with_config(DeltaConfig::default())
+    }
+
+    /// Creates a delta sender with custom configuration.
+    pub fn with_config(config: DeltaConfig) -> Self {
+        Self { sender: TensorSender::new(), config, references: HashMap::new() }
+    }
+
+    /// Sets the chunk size used for payload and delta frames.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.sender = TensorSender::with_chunk_size(chunk_size);
+        self
+    }
+
+    /// Enables or disables delta frames (e.g. after negotiation).
+    ///
+    /// Disabling clears all references so the next tensors are keyframes.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.config.enabled = enabled;
+        if !enabled {
+            self.references.clear();
+        }
+    }
+
+    /// Returns whether delta frames may be sent.
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Drops the reference for a tensor name, forcing a keyframe next time.
+    pub fn reset(&mut self, name: &str) {
+        self.references.remove(name);
+    }
+
+    /// Encodes a tensor as either a keyframe or a delta stream.
+    ///
+    /// Unnamed tensors are always sent as keyframes, since references are
+    /// tracked by name. Strided tensors are diffed in row-major order.
+    pub fn encode_tensor(&mut self, tensor: &Tensor) -> Vec<TensorFrame> {
+        let tensor = &tensor.to_contiguous();
+        let Some(name) = tensor.meta.name.clone().filter(|_| self.config.enabled) else {
+            return self.sender.encode_tensor(tensor);
+        };
+
+        if let Some(frames) = self.try_encode_delta(&name, tensor) {
+            return frames;
+        }
+
+        let generation =
+            self.references.get(&name).map(|r| r.generation.wrapping_add(1)).unwrap_or(0);
+        self.references.insert(
+            name,
+            Reference {
+                meta: tensor.meta.clone(),
+                data: tensor.data.clone(),
+                generation,
+                deltas_since_keyframe: 0,
+            },
+        );
+        self.sender.encode_tensor(tensor)
+    }
+
+    fn try_encode_delta(&mut self, name: &str, tensor: &Tensor) -> Option<Vec<TensorFrame>> {
+        let reference = self.references.get_mut(name)?;
+        if reference.meta.shape != tensor.meta.shape
+            || reference.meta.dtype != tensor.meta.dtype
+            || reference.data.len() != tensor.data.len()
+            || reference.deltas_since_keyframe >= self.config.keyframe_interval
+        {
+            return None;
+        }
+
+        let runs = diff_runs(&reference.data, &tensor.data);
+        let delta_size: usize = runs.iter().map(|r| RUN_HEADER_SIZE + r.len()).sum();
+        if delta_size as f64 > tensor.data.len() as f64 * self.config.max_delta_ratio {
+            return None;
+        }
+
+        let reserved = reference.generation.to_be_bytes();
+        let mut frames = vec![TensorFrame::tensor_meta(self.sender.encode_meta(&tensor.meta))];
+
+        let mut payload = BytesMut::new();
+        for run in runs {
+            if !payload.is_empty()
+                && payload.len() + RUN_HEADER_SIZE + run.len() > self.sender.chunk_size()
+            {
+                frames.push(TensorFrame::with_reserved(
+                    FrameType::TensorDelta,
+                    reserved,
+                    std::mem::take(&mut payload).freeze(),
+                ));
+            }
+            payload.extend_from_slice(&(run.start as u64).to_le_bytes());
+            payload.extend_from_slice(&(run.len() as u32).to_le_bytes());
+            payload.extend(
+                reference.data[run.clone()].iter().zip(&tensor.data[run]).map(|(a, b)| a ^ b),
+            );
+        }
+        if !payload.is_empty() {
+            frames.push(TensorFrame::with_reserved(
+                FrameType::TensorDelta,
+                reserved,
+                payload.freeze(),
+            ));
+        }
+        frames.push(TensorFrame::end_stream());
+
+        reference.data = tensor.data.clone();
+        reference.meta = tensor.meta.clone();
+        reference.deltas_since_keyframe += 1;
+        Some(frames)
+    }
+}
+
+impl Default for DeltaSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Computes the byte ranges that differ between two equal-length buffers.
+fn diff_runs(old: &[u8], new: &[u8]) -> Vec<std::ops::Range<usize>> {
+    let mut runs: Vec<std::ops::Range<usize>> = Vec::new();
+    let mut i = 0;
+    while i < new.len() {
+        if old[i] == new[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < new.len() && old[i] != new[i] {
+            i += 1;
+        }
+        match runs.last_mut() {
+            Some(last) if start - last.end < RUN_MERGE_GAP => last.end = i,
+            _ => runs.push(start..i),
+        }
+    }
+    runs
+}
+
+/// Events produced by the delta receiver.
+#[derive(Debug)]
+pub enum DeltaEvent {
+    /// Tensor metadata received.
+    Metadata(TensorMeta),
+    /// Keyframe payload chunk received (offset, size).
+    Keyframe { offset: usize, size: usize },
+    /// Delta frame applied (number of runs, patched bytes).
+    Delta { runs: usize, bytes: usize },
+    /// Tensor fully reconstructed.
+    Complete(Tensor),
+    /// Stream was cancelled.
+    Cancelled(String),
+    /// Need more data to parse next frame.
+    NeedMoreData,
+}
+
+/// Receiver that reconstructs tensors from keyframe and delta streams.
+pub struct DeltaReceiver {
+    parser: TensorFrameParser,
+    references: HashMap<String, (u32, Bytes)>,
+    meta: Option<TensorMeta>,
+    buffer: BytesMut,
+    received_size: usize,
+    is_delta: bool,
+}
+
+impl DeltaReceiver {
+    /// Creates a new delta receiver.
+    pub fn new() -> Self {
+        Self {
+            parser: TensorFrameParser::new(),
+            references: HashMap::new(),
+            meta: None,
+            buffer: BytesMut::new(),
+            received_size: 0,
+            is_delta: false,
+        }
+    }
+
+    /// Feeds raw bytes into the receiver.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.parser.feed(data);
+    }
+
+    /// Feeds a Bytes buffer into the receiver.
+    pub fn feed_bytes(&mut self, data: Bytes) {
+        self.parser.feed_bytes(data);
+    }
+
+    /// Processes the next available frame.
+    pub fn poll(&mut self) -> Result<DeltaEvent, TensorStreamError> {
+        match self.parser.parse_frame()? {
+            None => Ok(DeltaEvent::NeedMoreData),
+            Some(frame) => self.handle_frame(frame),
+        }
+    }
+
+    /// Drops the reference for a tensor name.
+    pub fn reset(&mut self, name: &str) {
+        self.references.remove(name);
+    }
+
+    fn handle_frame(&mut self, frame: TensorFrame) -> Result<DeltaEvent, TensorStreamError> {
+        match frame.frame_type {
+            FrameType::TensorMeta => {
+                let meta = decode_tensor_meta(&frame.payload)?;
+                self.buffer = BytesMut::with_capacity(meta.byte_size());
+                self.received_size = 0;
+                self.is_delta = false;
+                self.meta = Some(meta.clone());
+                Ok(DeltaEvent::Metadata(meta))
+            }
+            FrameType::TensorPayload => {
+                if self.meta.is_none() {
+                    return Err(TensorStreamError::MissingMetadata);
+                }
+                let offset = self.received_size;
+                self.buffer.extend_from_slice(&frame.payload);
+                self.received_size += frame.payload.len();
+                Ok(DeltaEvent::Keyframe { offset, size: frame.payload.len() })
+            }
+            FrameType::TensorDelta => self.apply_delta(frame),
+            FrameType::EndStream => self.finish(),
+            FrameType::Cancel => {
+                let reason = String::from_utf8_lossy(&frame.payload).into_owned();
+                Ok(DeltaEvent::Cancelled(reason))
+            }
+            _ => Err(TensorStreamError::UnexpectedFrame {
+                expected: "TENSOR_META, TENSOR_PAYLOAD, TENSOR_DELTA, END_STREAM, or CANCEL",
+                actual: frame.frame_type.name(),
+            }),
+        }
+    }
+
+    fn apply_delta(&mut self, frame: TensorFrame) -> Result<DeltaEvent, TensorStreamError> {
+        let meta = self.meta.as_ref().ok_or(TensorStreamError::MissingMetadata)?;
+        let name = meta.name.as_deref().ok_or_else(|| {
+            TensorStreamError::Internal("TENSOR_DELTA for unnamed tensor".to_string())
+        })?;
+        let generation = u32::from_be_bytes(frame.reserved);
+
+        if !self.is_delta {
+            let (ref_generation, reference) = self.references.get(name).ok_or_else(|| {
+                TensorStreamError::Internal(format!("no delta reference for tensor '{}'", name))
+            })?;
+            if *ref_generation != generation {
+                return Err(TensorStreamError::Internal(format!(
+                    "delta reference generation mismatch for '{}': have {}, got {}",
+                    name, ref_generation, generation
+                )));
+            }
+            if reference.len() != meta.byte_size() {
+                return Err(TensorStreamError::SizeMismatch {
+                    expected: meta.byte_size(),
+                    actual: reference.len(),
+                });
+            }
+            self.buffer = BytesMut::from(&reference[..]);
+            self.received_size = reference.len();
+            self.is_delta = true;
+        }
+
+        let payload = &frame.payload[..];
+        let mut pos = 0;
+        let mut runs = 0;
+        let mut bytes = 0;
+        while pos < payload.len() {
+            if payload.len() - pos < RUN_HEADER_SIZE {
+                return Err(TensorStreamError::Internal("truncated delta run".to_string()));
+            }
+            let offset = u64::from_le_bytes(payload[pos..pos + 8].try_into().unwrap()) as usize;
+            let len = u32::from_le_bytes(payload[pos + 8..pos + 12].try_into().unwrap()) as usize;
+            pos += RUN_HEADER_SIZE;
+
+            if payload.len() - pos < len || offset + len > self.buffer.len() {
+                return Err(TensorStreamError::Internal("delta run out of bounds".to_string()));
+            }
+            for (dst, x) in
+                self.buffer[offset..offset + len].iter_mut().zip(&payload[pos..pos + len])
+            {
+                *dst ^= x;
+            }
+            pos += len;
+            runs += 1;
+            bytes += len;
+        }
+
+        Ok(DeltaEvent::Delta { runs, bytes })
+    }
+
+    fn finish(&mut self) -> Result<DeltaEvent, TensorStreamError> {
+        let meta = self.meta.take().ok_or(TensorStreamError::MissingMetadata)?;
+        if self.received_size != meta.byte_size() {
+            return Err(TensorStreamError::SizeMismatch {
+                expected: meta.byte_size(),
+                actual: self.received_size,
+            });
+        }
+
+        let data = std::mem::take(&mut self.buffer).freeze();
+        if let Some(name) = meta.name.clone() {
+            let generation = match self.references.get(&name) {
+                Some((generation, _)) if self.is_delta => *generation,
+                Some((generation, _)) => generation.wrapping_add(1),
+                None => 0,
+            };
+            self.references.insert(name, (generation, data.clone()));
+        }
+
+        self.received_size = 0;
+        self.is_delta = false;
+        Ok(DeltaEvent::Complete(Tensor::new(meta, data)))
+    }
+}
+
+impl Default for DeltaReceiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dtype::DType;
+    use crate::stream::{ReceiverEvent, TensorReceiver};
+
+    fn named_tensor(values: &[f32]) -> Tensor {
+        let meta = TensorMeta::new(vec![values.len()], DType::Float32).with_name("kv.layer0");
+        Tensor::from_f32(&meta, values)
+    }
+
+    fn receive_all(receiver: &mut DeltaReceiver, frames: &[TensorFrame]) -> Tensor {
+        for frame in frames {
+            receiver.feed(&frame.encode());
+        }
+        loop {
+            match receiver.poll().unwrap() {
+                DeltaEvent::Complete(tensor) => return tensor,
+                DeltaEvent::NeedMoreData => panic!("stream ended without a tensor"),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_accepts_delta() {
+        assert!(accepts_delta(Some("xor-v1")));
+        assert!(accepts_delta(Some("zstd, xor-v1")));
+        assert!(!accepts_delta(Some("xor-v2")));
+        assert!(!accepts_delta(None));
+    }
+
+    #[test]
+    fn test_diff_runs_merges_small_gaps() {
+        let old = vec![0u8; 64];
+        let mut new = old.clone();
+        new[2] = 1;
+        new[5] = 1;
+        new[40] = 1;
+
+        let runs = diff_runs(&old, &new);
+        assert_eq!(runs, vec![2..6, 40..41]);
+    }
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let mut sender = DeltaSender::new();
+        let mut receiver = DeltaReceiver::new();
+
+        let mut values: Vec<f32> = (0..1024).map(|i| i as f32).collect();
+        let first = named_tensor(&values);
+        let frames = sender.encode_tensor(&first);
+        assert!(frames.iter().all(|f| f.frame_type != FrameType::TensorDelta));
+        assert_eq!(receive_all(&mut receiver, &frames).data, first.data);
+
+        values[10] = -1.0;
+        values[500] = -2.0;
+        let second = named_tensor(&values);
+        let frames = sender.encode_tensor(&second);
+        assert!(frames.iter().any(|f| f.frame_type == FrameType::TensorDelta));
+
+        let encoded: usize = frames.iter().map(|f| f.encoded_size()).sum();
+        assert!(encoded < second.byte_size() / 10);
+        assert_eq!(receive_all(&mut receiver, &frames).data, second.data);
+    }
+
+    #[test]
+    fn test_keyframe_interval_and_shape_change() {
+        let mut sender =
+            DeltaSender::with_config(DeltaConfig { keyframe_interval: 1, ..Default::default() });
+        let is_delta =
+            |frames: &[TensorFrame]| frames.iter().any(|f| f.frame_type == FrameType::TensorDelta);
+
+        let mut values = vec![1.0f32; 256];
+        assert!(!is_delta(&sender.encode_tensor(&named_tensor(&values))));
+        values[0] = 2.0;
+        assert!(is_delta(&sender.encode_tensor(&named_tensor(&values))));
+        values[0] = 3.0;
+        // Interval reached, so a keyframe is forced
+        assert!(!is_delta(&sender.encode_tensor(&named_tensor(&values))));
+        // Shape change always forces a keyframe
+        assert!(!is_delta(&sender.encode_tensor(&named_tensor(&values[..128]))));
+    }
+
+    #[test]
+    fn test_disabled_falls_back_to_plain_stream() {
+        let mut sender = DeltaSender::new();
+        sender.set_enabled(false);
+
+        let values = vec![0.5f32; 64];
+        sender.encode_tensor(&named_tensor(&values));
+        let frames = sender.encode_tensor(&named_tensor(&values));
+
+        // A regular receiver can decode keyframe-only output
+        let mut receiver = TensorReceiver::new();
+        for frame in &frames {
+            receiver.feed(&frame.encode());
+        }
+        while !matches!(receiver.poll().unwrap(), ReceiverEvent::End) {}
+        assert_eq!(receiver.take_tensor().unwrap().as_f32(), &values[..]);
+    }
+
+    #[test]
+    fn test_delta_without_reference_is_rejected() {
+        let mut sender = DeltaSender::new();
+        let mut values = vec![0.0f32; 256];
+        sender.encode_tensor(&named_tensor(&values));
+        values[3] = 1.0;
+        let frames = sender.encode_tensor(&named_tensor(&values));
+
+        // A fresh receiver never saw the keyframe
+        let mut receiver = DeltaReceiver::new();
+        for frame in &frames {
+            receiver.feed(&frame.encode());
+        }
+        assert!(matches!(receiver.poll().unwrap(), DeltaEvent::Metadata(_)));
+        assert!(receiver.poll().is_err());
+    }
+}
@@ -35,6 +35,7 @@
 
 use std::ffi::c_void;
 use std::ptr;
+use std::sync::Arc;
 
 use bytes::Bytes;
 
@@ -151,6 +152,10 @@ pub enum DLDataTypeCode {
     Complex = 5,
     /// Boolean
     Bool = 6,
+    /// 8-bit float, E4M3 without infinities (DLPack 1.1)
+    Float8E4M3Fn = 10,
+    /// 8-bit float, E5M2 (DLPack 1.1)
+    Float8E5M2 = 12,
 }
 
 /// DLPack data type descriptor.
@@ -214,6 +219,26 @@ impl DLDataType {
                 bits: 8,
                 lanes: 1,
             },
+            DType::Float8E4M3 => Self {
+                code: DLDataTypeCode::Float8E4M3Fn,
+                bits: 8,
+                lanes: 1,
+            },
+            DType::Float8E5M2 => Self {
+                code: DLDataTypeCode::Float8E5M2,
+                bits: 8,
+                lanes: 1,
+            },
+            DType::Int4 => Self {
+                code: DLDataTypeCode::Int,
+                bits: 4,
+                lanes: 1,
+            },
+            DType::UInt4 => Self {
+                code: DLDataTypeCode::UInt,
+                bits: 4,
+                lanes: 1,
+            },
         }
     }
 
@@ -229,6 +254,10 @@ impl DLDataType {
             (DLDataTypeCode::Int, 64) => Ok(DType::Int64),
             (DLDataTypeCode::UInt, 8) => Ok(DType::UInt8),
             (DLDataTypeCode::Bool, 8) | (DLDataTypeCode::Bool, 1) => Ok(DType::Bool),
+            (DLDataTypeCode::Float8E4M3Fn, 8) => Ok(DType::Float8E4M3),
+            (DLDataTypeCode::Float8E5M2, 8) => Ok(DType::Float8E5M2),
+            (DLDataTypeCode::Int, 4) => Ok(DType::Int4),
+            (DLDataTypeCode::UInt, 4) => Ok(DType::UInt4),
             _ => Err(DLPackError::UnsupportedDataType {
                 code: self.code as u8,
                 bits: self.bits,
@@ -330,6 +359,10 @@ pub enum DLPackError {
     /// Non-contiguous tensor (strides not supported yet).
     #[error("Non-contiguous tensors not supported")]
     NonContiguous,
+
+    /// Invalid shape (negative dimension count or size).
+    #[error("Invalid DLPack tensor: negative dimension {0}")]
+    NegativeDimension(i64),
 }
 
 /// Internal context for managing DLPack tensor lifetime.
@@ -339,7 +372,7 @@ struct DLPackContext {
     /// Strides array (kept alive for DLTensor.strides)
     _strides: Option<Vec<i64>>,
     /// Data buffer
-    buffer: TensorBuffer,
+    buffer: Arc<TensorBuffer>,
 }
 
 /// A DLPack capsule that can be exchanged with other frameworks.
@@ -358,12 +391,19 @@ impl DLPackCapsule {
     /// The returned capsule owns the tensor data and will clean it up
     /// when dropped (unless ownership is transferred via `into_raw`).
     pub fn from_tensor(tensor: &Tensor) -> GpuResult<Self> {
-        // Convert tensor data to TensorBuffer
-        let buffer = TensorBuffer::cpu(tensor.data.clone());
+        let buffer = Arc::new(TensorBuffer::cpu(tensor.data.clone()));
+        Self::from_buffer(buffer, &tensor.meta)
+    }
 
+    /// Creates a DLPack capsule sharing a CPU or GPU buffer.
+    ///
+    /// The capsule keeps `buffer` alive until its deleter runs, so GPU
+    /// memory is handed out without copying. The DLPack device is taken
+    /// from the buffer, not from `meta.device`.
+    pub fn from_buffer(buffer: Arc<TensorBuffer>, meta: &TensorMeta) -> GpuResult<Self> {
         // Build context
-        let shape: Vec<i64> = tensor.meta.shape.iter().map(|&x| x as i64).collect();
-        let strides: Option<Vec<i64>> = tensor.meta.strides.as_ref().map(|s| {
+        let shape: Vec<i64> = meta.shape.iter().map(|&x| x as i64).collect();
+        let strides: Option<Vec<i64>> = meta.strides.as_ref().map(|s| {
             s.iter().map(|&x| x as i64).collect()
         });
 
@@ -377,19 +417,22 @@ impl DLPackCapsule {
         let shape_ptr = ctx._shape.as_ptr() as *mut i64;
         let strides_ptr = ctx._strides.as_ref().map(|s| s.as_ptr() as *mut i64).unwrap_or(ptr::null_mut());
 
-        // Get data pointer
-        let data_ptr = match &ctx.buffer {
-            TensorBuffer::Cpu(bytes) => bytes.as_ptr() as *mut c_void,
+        // Get data pointer and the device it lives on
+        let (data_ptr, device) = match &*ctx.buffer {
+            TensorBuffer::Cpu(bytes) => (bytes.as_ptr() as *mut c_void, DLDevice::cpu()),
             #[cfg(feature = "cuda")]
-            TensorBuffer::Cuda(cuda_buf) => cuda_buf.as_device_ptr() as *mut c_void,
+            TensorBuffer::Cuda(cuda_buf) => (
+                cuda_buf.as_device_ptr() as *mut c_void,
+                DLDevice::cuda(cuda_buf.device_id() as i32),
+            ),
         };
 
         // Create DLTensor
         let dl_tensor = DLTensor {
             data: data_ptr,
-            device: DLDevice::from_device(tensor.meta.device, 0),
-            ndim: tensor.meta.shape.len() as i32,
-            dtype: DLDataType::from_dtype(tensor.meta.dtype),
+            device,
+            ndim: meta.shape.len() as i32,
+            dtype: DLDataType::from_dtype(meta.dtype),
             shape: shape_ptr,
             strides: strides_ptr,
             byte_offset: 0,
@@ -410,7 +453,12 @@ impl DLPackCapsule {
 
     /// Converts a DLPack capsule back to a Quill tensor.
     ///
-    /// This consumes the capsule and takes ownership of the data.
+    /// This consumes the capsule and takes ownership of the data. Host
+    /// memory (CPU or CUDA pinned) is shared without copying, and the
+    /// capsule's deleter runs once the tensor's data is dropped. CUDA device
+    /// memory is copied to the host (with the `cuda` feature) and released
+    /// right away. Strides are accepted only when they describe a compact
+    /// row-major layout.
     pub fn to_tensor(self) -> Result<Tensor, DLPackError> {
         if self.ptr.is_null() {
             return Err(DLPackError::NullData);
@@ -419,48 +467,64 @@ impl DLPackCapsule {
         let managed = unsafe { &*self.ptr };
         let dl_tensor = &managed.dl_tensor;
 
-        // Validate
-        if dl_tensor.data.is_null() {
-            return Err(DLPackError::NullData);
-        }
-        if dl_tensor.shape.is_null() {
-            return Err(DLPackError::NullShape);
-        }
-
-        // Check for non-contiguous tensors
-        if !dl_tensor.strides.is_null() {
-            // TODO: Support non-contiguous tensors
-            return Err(DLPackError::NonContiguous);
-        }
-
-        // Convert dtype
-        let dtype = dl_tensor.dtype.to_dtype()?;
+        // Enum fields come from foreign code, so read them as plain integers
+        let device_type =
+            unsafe { ptr::addr_of!(dl_tensor.device.device_type).cast::<i32>().read() };
+        let type_code = unsafe { ptr::addr_of!(dl_tensor.dtype.code).cast::<u8>().read() };
+        let dtype = raw_dtype(type_code, dl_tensor.dtype.bits, dl_tensor.dtype.lanes)?;
 
         // Convert shape
-        let shape: Vec<usize> = unsafe {
-            std::slice::from_raw_parts(dl_tensor.shape, dl_tensor.ndim as usize)
-                .iter()
-                .map(|&x| x as usize)
-                .collect()
+        let ndim = usize::try_from(dl_tensor.ndim)
+            .map_err(|_| DLPackError::NegativeDimension(dl_tensor.ndim as i64))?;
+        if ndim > 0 && dl_tensor.shape.is_null() {
+            return Err(DLPackError::NullShape);
+        }
+        let dims: &[i64] = match ndim {
+            0 => &[],
+            _ => unsafe { std::slice::from_raw_parts(dl_tensor.shape, ndim) },
         };
+        let shape = dims
+            .iter()
+            .map(|&dim| usize::try_from(dim).map_err(|_| DLPackError::NegativeDimension(dim)))
+            .collect::<Result<Vec<usize>, _>>()?;
+
+        // Strided tensors must still be laid out contiguously
+        if ndim > 0 && !dl_tensor.strides.is_null() {
+            let strides = unsafe { std::slice::from_raw_parts(dl_tensor.strides, ndim) };
+            if !is_compact(&shape, strides) {
+                return Err(DLPackError::NonContiguous);
+            }
+        }
 
-        // Convert device
-        let device = Device::from(dl_tensor.device.device_type);
-
-        // Calculate size
-        let meta = TensorMeta::new(shape.clone(), dtype).with_device(device);
+        let meta = TensorMeta::new(shape, dtype);
         let byte_size = meta.byte_size();
-
-        // Copy data
-        let data = unsafe {
-            let data_ptr = (dl_tensor.data as *const u8).add(dl_tensor.byte_offset as usize);
-            std::slice::from_raw_parts(data_ptr, byte_size).to_vec()
+        if byte_size == 0 {
+            return Ok(Tensor::new(meta, Bytes::new()));
+        }
+        if dl_tensor.data.is_null() {
+            return Err(DLPackError::NullData);
+        }
+        let data_ptr = unsafe { (dl_tensor.data as *const u8).add(dl_tensor.byte_offset as usize) };
+
+        let data = match device_type {
+            d if d == DLDeviceType::Cpu as i32 || d == DLDeviceType::CudaHost as i32 => {
+                Bytes::from_owner(DLPackOwner {
+                    _capsule: self,
+                    data: data_ptr,
+                    len: byte_size,
+                })
+            }
+            d if d == DLDeviceType::Cuda as i32 || d == DLDeviceType::CudaManaged as i32 => {
+                let device_id = dl_tensor.device.device_id;
+                let host = copy_from_device(data_ptr, byte_size, device_id)?;
+                // The deleter runs here, releasing the device memory
+                drop(self);
+                Bytes::from(host)
+            }
+            other => return Err(DLPackError::UnsupportedDevice(other)),
         };
 
-        // Don't drop self normally - we consumed the data
-        std::mem::forget(self);
-
-        Ok(Tensor::new(meta, Bytes::from(data)))
+        Ok(Tensor::new(meta, data))
     }
 
     /// Returns the raw pointer to the DLManagedTensor.
@@ -501,6 +565,78 @@ impl Drop for DLPackCapsule {
     }
 }
 
+/// Imported host memory, released through the capsule's deleter on drop
+struct DLPackOwner {
+    _capsule: DLPackCapsule,
+    data: *const u8,
+    len: usize,
+}
+
+// DLPack deleters may be called from any thread
+unsafe impl Send for DLPackOwner {}
+
+impl AsRef<[u8]> for DLPackOwner {
+    fn as_ref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.data, self.len) }
+    }
+}
+
+/// Converts a raw DLPack data type to a Quill DType.
+fn raw_dtype(code: u8, bits: u8, lanes: u16) -> Result<DType, DLPackError> {
+    let code = match code {
+        0 => DLDataTypeCode::Int,
+        1 => DLDataTypeCode::UInt,
+        2 => DLDataTypeCode::Float,
+        4 => DLDataTypeCode::Bfloat,
+        6 => DLDataTypeCode::Bool,
+        10 => DLDataTypeCode::Float8E4M3Fn,
+        12 => DLDataTypeCode::Float8E5M2,
+        _ => return Err(DLPackError::UnsupportedDataType { code, bits }),
+    };
+    if lanes != 1 {
+        return Err(DLPackError::UnsupportedDataType { code: code as u8, bits });
+    }
+    DLDataType { code, bits, lanes }.to_dtype()
+}
+
+/// Whether `strides` (in elements) describe a compact row-major layout.
+///
+/// Dimensions of size one may have any stride.
+fn is_compact(shape: &[usize], strides: &[i64]) -> bool {
+    let mut expected: i64 = 1;
+    for (&dim, &stride) in shape.iter().zip(strides).rev() {
+        if dim != 1 && stride != expected {
+            return false;
+        }
+        expected = expected.saturating_mul(dim as i64);
+    }
+    true
+}
+
+/// Copies CUDA device memory to the host.
+#[cfg(feature = "cuda")]
+fn copy_from_device(data: *const u8, len: usize, device_id: i32) -> Result<Vec<u8>, DLPackError> {
+    use cudarc::driver::{result, CudaDevice};
+
+    let device = CudaDevice::new(device_id as usize).map_err(|e| {
+        GpuError::DriverNotAvailable(format!("Failed to open device {}: {}", device_id, e))
+    })?;
+    device
+        .bind_to_thread()
+        .map_err(|e| GpuError::DriverNotAvailable(format!("Failed to bind device: {}", e)))?;
+    let mut host = vec![0u8; len];
+    unsafe { result::memcpy_dtoh_sync(&mut host, data as u64) }.map_err(|e| {
+        GpuError::TransferFailed(format!("Device-to-host copy failed: {}", e))
+    })?;
+    Ok(host)
+}
+
+/// Copies CUDA device memory to the host (non-CUDA version always fails).
+#[cfg(not(feature = "cuda"))]
+fn copy_from_device(_data: *const u8, _len: usize, _device_id: i32) -> Result<Vec<u8>, DLPackError> {
+    Err(GpuError::NotCompiled.into())
+}
+
 /// Deleter function for DLManagedTensor created by Quill.
 unsafe extern "C" fn dlpack_deleter(tensor: *mut DLManagedTensor) {
     if tensor.is_null() {
@@ -537,8 +673,13 @@ pub struct CudaArrayInterface {
 
 impl CudaArrayInterface {
     /// Creates a CUDA Array Interface descriptor from a TensorBuffer.
+    ///
+    /// Returns `None` for packed dtypes, which the interface can't describe.
     #[cfg(feature = "cuda")]
     pub fn from_buffer(buffer: &TensorBuffer, meta: &TensorMeta) -> Option<Self> {
+        if meta.dtype.is_packed() {
+            return None;
+        }
         match buffer {
             TensorBuffer::Cuda(cuda_buf) => {
                 let typestr = dtype_to_typestr(meta.dtype);
@@ -564,6 +705,8 @@ impl CudaArrayInterface {
 }
 
 /// Converts a DType to a numpy typestring.
+///
+/// Types numpy lacks are described as raw bytes of their element size.
 pub fn dtype_to_typestr(dtype: DType) -> String {
     // Little-endian format codes
     match dtype {
@@ -576,6 +719,8 @@ pub fn dtype_to_typestr(dtype: DType) -> String {
         DType::Int64 => "<i8".to_string(),
         DType::UInt8 => "|u1".to_string(),
         DType::Bool => "|b1".to_string(),
+        DType::Float8E4M3 | DType::Float8E5M2 => "|V1".to_string(),
+        DType::Int4 | DType::UInt4 => "|V1".to_string(),
     }
 }
 
@@ -602,6 +747,8 @@ pub fn typestr_to_dtype(typestr: &str) -> Result<DType, DLPackError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     #[test]
     fn test_dl_device_cpu() {
@@ -655,6 +802,22 @@ mod tests {
         assert_eq!(imported.as_f32(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
     }
 
+    #[test]
+    fn test_dlpack_shares_buffer() {
+        // Host data is described as CPU memory even for CUDA metadata
+        let meta = TensorMeta::new(vec![4], DType::UInt8).with_device(Device::Cuda);
+        let buffer = Arc::new(TensorBuffer::cpu_from_slice(&[1, 2, 3, 4]));
+        let capsule = DLPackCapsule::from_buffer(buffer.clone(), &meta).unwrap();
+        assert_eq!(Arc::strong_count(&buffer), 2);
+
+        let dl_tensor = unsafe { &(*capsule.as_ptr()).dl_tensor };
+        assert_eq!(dl_tensor.device, DLDevice::cpu());
+        assert_eq!(dl_tensor.data as *const u8, buffer.as_cpu().unwrap().as_ptr());
+
+        drop(capsule);
+        assert_eq!(Arc::strong_count(&buffer), 1);
+    }
+
     #[test]
     fn test_dlpack_i64_tensor() {
         let meta = TensorMeta::new(vec![4], DType::Int64);
@@ -668,6 +831,96 @@ mod tests {
         assert_eq!(imported.as_i64(), &[100, 200, 300, 400]);
     }
 
+    /// Externally produced tensor recording when its deleter runs
+    struct Foreign {
+        shape: Vec<i64>,
+        strides: Vec<i64>,
+        data: Vec<f32>,
+        deleted: Arc<AtomicUsize>,
+    }
+
+    unsafe extern "C" fn foreign_deleter(managed: *mut DLManagedTensor) {
+        let managed = Box::from_raw(managed);
+        let foreign = Box::from_raw(managed.manager_ctx as *mut Foreign);
+        foreign.deleted.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn foreign_capsule(
+        shape: Vec<i64>,
+        strides: Vec<i64>,
+        data: Vec<f32>,
+        deleted: &Arc<AtomicUsize>,
+    ) -> DLPackCapsule {
+        let mut foreign = Box::new(Foreign { shape, strides, data, deleted: deleted.clone() });
+        let dl_tensor = DLTensor {
+            data: foreign.data.as_mut_ptr() as *mut c_void,
+            device: DLDevice::cpu(),
+            ndim: foreign.shape.len() as i32,
+            dtype: DLDataType::from_dtype(DType::Float32),
+            shape: foreign.shape.as_mut_ptr(),
+            strides: foreign.strides.as_mut_ptr(),
+            byte_offset: 0,
+        };
+        let managed = Box::new(DLManagedTensor {
+            dl_tensor,
+            manager_ctx: Box::into_raw(foreign) as *mut c_void,
+            deleter: Some(foreign_deleter),
+        });
+        unsafe { DLPackCapsule::from_raw(Box::into_raw(managed)) }
+    }
+
+    #[test]
+    fn test_dlpack_import_shares_memory_until_dropped() {
+        let deleted = Arc::new(AtomicUsize::new(0));
+        let data = vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let data_ptr = data.as_ptr() as *const u8;
+
+        // Explicit compact strides, with any stride for the unit dimension
+        let capsule = foreign_capsule(vec![2, 1, 3], vec![3, 42, 1], data, &deleted);
+        let imported = capsule.to_tensor().unwrap();
+        assert_eq!(imported.meta.shape, vec![2, 1, 3]);
+        assert_eq!(imported.as_f32(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(imported.data.as_ptr(), data_ptr);
+        assert_eq!(deleted.load(Ordering::SeqCst), 0);
+
+        let shared = imported.data.clone();
+        drop(imported);
+        assert_eq!(deleted.load(Ordering::SeqCst), 0);
+        drop(shared);
+        assert_eq!(deleted.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_dlpack_import_rejects_non_contiguous() {
+        let deleted = Arc::new(AtomicUsize::new(0));
+
+        // A transposed 2x3 view of a 3x2 buffer
+        let capsule = foreign_capsule(vec![2, 3], vec![1, 2], vec![0.0; 6], &deleted);
+        assert!(matches!(capsule.to_tensor(), Err(DLPackError::NonContiguous)));
+        assert_eq!(deleted.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_dlpack_import_rejects_negative_dimension() {
+        let deleted = Arc::new(AtomicUsize::new(0));
+        let capsule = foreign_capsule(vec![-1], vec![1], vec![], &deleted);
+        assert!(matches!(capsule.to_tensor(), Err(DLPackError::NegativeDimension(-1))));
+        assert_eq!(deleted.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(not(feature = "cuda"))]
+    #[test]
+    fn test_dlpack_import_cuda_requires_feature() {
+        let deleted = Arc::new(AtomicUsize::new(0));
+        let capsule = foreign_capsule(vec![2], vec![1], vec![1.0, 2.0], &deleted);
+        unsafe { (*capsule.as_ptr()).dl_tensor.device = DLDevice::cuda(0) };
+        assert!(matches!(
+            capsule.to_tensor(),
+            Err(DLPackError::Gpu(GpuError::NotCompiled))
+        ));
+        assert_eq!(deleted.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_typestr_conversion() {
         assert_eq!(typestr_to_dtype("<f4").unwrap(), DType::Float32);
@@ -681,6 +934,19 @@ mod tests {
         assert_eq!(dtype_to_typestr(DType::Float32), "<f4");
         assert_eq!(dtype_to_typestr(DType::Int64), "<i8");
         assert_eq!(dtype_to_typestr(DType::UInt8), "|u1");
+        assert_eq!(dtype_to_typestr(DType::Float8E4M3), "|V1");
+    }
+
+    #[test]
+    fn test_dl_dtype_quantized() {
+        for dtype in [DType::Float8E4M3, DType::Float8E5M2, DType::Int4, DType::UInt4] {
+            let dl = DLDataType::from_dtype(dtype);
+            assert_eq!(dl.bits as usize, dtype.bit_width());
+            assert_eq!(dl.to_dtype().unwrap(), dtype);
+            assert_eq!(raw_dtype(dl.code as u8, dl.bits, dl.lanes).unwrap(), dtype);
+        }
+        assert_eq!(DLDataType::from_dtype(DType::Float8E4M3).code as u8, 10);
+        assert_eq!(DLDataType::from_dtype(DType::Float8E5M2).code as u8, 12);
     }
 
     #[test]
@@ -1,6 +1,16 @@
 //! Data types for tensor elements.
 //!
-//! Supports standard ML data types including half-precision floats (f16, bf16).
+//! Supports standard ML data types including half-precision floats (f16, bf16),
+//! FP8 (E4M3 and E5M2) and packed 4-bit integers.
+//!
+//! # Packed 4-bit types
+//!
+//! [`DType::Int4`] and [`DType::UInt4`] store two elements per byte: element
+//! `2i` in the low nibble of byte `i` and element `2i + 1` in its high
+//! nibble. A tensor with an odd number of elements leaves the high nibble of
+//! its last byte zero. Use [`DType::byte_size`] rather than
+//! [`DType::element_size`] to size buffers, and [`pack_int4`] /
+//! [`unpack_int4`] (or their unsigned variants) to convert values.
 
 use half::{bf16, f16};
 
@@ -28,11 +38,22 @@ pub enum DType {
     UInt8 = 8,
     /// Boolean (1 byte per element)
     Bool = 9,
+    /// 8-bit floating point with 4 exponent and 3 mantissa bits (OCP E4M3FN)
+    Float8E4M3 = 10,
+    /// 8-bit floating point with 5 exponent and 2 mantissa bits (OCP E5M2)
+    Float8E5M2 = 11,
+    /// 4-bit signed integer, two per byte
+    Int4 = 12,
+    /// 4-bit unsigned integer, two per byte
+    UInt4 = 13,
 }
 
 impl DType {
     /// Returns the size in bytes of a single element of this data type.
     ///
+    /// Packed 4-bit types report 1, the byte that holds two of their
+    /// elements; size their buffers with [`byte_size`](Self::byte_size).
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -49,9 +70,41 @@ impl DType {
             DType::Float32 | DType::Int32 => 4,
             DType::Float16 | DType::BFloat16 => 2,
             DType::Int8 | DType::UInt8 | DType::Bool => 1,
+            DType::Float8E4M3 | DType::Float8E5M2 => 1,
+            DType::Int4 | DType::UInt4 => 1,
+        }
+    }
+
+    /// Returns the number of bits of a single element.
+    #[inline]
+    pub const fn bit_width(&self) -> usize {
+        match self {
+            DType::Int4 | DType::UInt4 => 4,
+            _ => self.element_size() * 8,
         }
     }
 
+    /// Returns whether several elements share each byte.
+    #[inline]
+    pub const fn is_packed(&self) -> bool {
+        self.bit_width() < 8
+    }
+
+    /// Returns the number of bytes holding `numel` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quill_tensor::DType;
+    ///
+    /// assert_eq!(DType::Float32.byte_size(3), 12);
+    /// assert_eq!(DType::Int4.byte_size(3), 2);
+    /// ```
+    #[inline]
+    pub const fn byte_size(&self, numel: usize) -> usize {
+        (numel * self.bit_width()).div_ceil(8)
+    }
+
     /// Returns a human-readable name for this data type.
     #[inline]
     pub const fn name(&self) -> &'static str {
@@ -65,6 +118,10 @@ impl DType {
             DType::Int64 => "int64",
             DType::UInt8 => "uint8",
             DType::Bool => "bool",
+            DType::Float8E4M3 => "float8_e4m3",
+            DType::Float8E5M2 => "float8_e5m2",
+            DType::Int4 => "int4",
+            DType::UInt4 => "uint4",
         }
     }
 
@@ -73,7 +130,12 @@ impl DType {
     pub const fn is_floating_point(&self) -> bool {
         matches!(
             self,
-            DType::Float32 | DType::Float16 | DType::BFloat16 | DType::Float64
+            DType::Float32
+                | DType::Float16
+                | DType::BFloat16
+                | DType::Float64
+                | DType::Float8E4M3
+                | DType::Float8E5M2
         )
     }
 
@@ -85,27 +147,19 @@ impl DType {
             DType::Int8
                 | DType::Int32
                 | DType::Int64
+                | DType::Int4
                 | DType::Float32
                 | DType::Float16
                 | DType::BFloat16
                 | DType::Float64
+                | DType::Float8E4M3
+                | DType::Float8E5M2
         )
     }
 
     /// Converts from protobuf DType enum value.
     pub fn from_proto(value: i32) -> Option<Self> {
-        match value {
-            1 => Some(DType::Float32),
-            2 => Some(DType::Float16),
-            3 => Some(DType::BFloat16),
-            4 => Some(DType::Float64),
-            5 => Some(DType::Int8),
-            6 => Some(DType::Int32),
-            7 => Some(DType::Int64),
-            8 => Some(DType::UInt8),
-            9 => Some(DType::Bool),
-            _ => None,
-        }
+        u8::try_from(value).ok().and_then(|value| Self::try_from(value).ok())
     }
 
     /// Converts to protobuf DType enum value.
@@ -135,11 +189,65 @@ impl TryFrom<u8> for DType {
             7 => Ok(DType::Int64),
             8 => Ok(DType::UInt8),
             9 => Ok(DType::Bool),
+            10 => Ok(DType::Float8E4M3),
+            11 => Ok(DType::Float8E5M2),
+            12 => Ok(DType::Int4),
+            13 => Ok(DType::UInt4),
             _ => Err(()),
         }
     }
 }
 
+/// Packs 4-bit signed values two per byte, low nibble first.
+///
+/// Values are truncated to their low 4 bits, so they should lie in `-8..=7`.
+pub fn pack_int4(values: &[i8]) -> Vec<u8> {
+    pack_nibbles(values.iter().map(|&v| v as u8))
+}
+
+/// Unpacks `numel` 4-bit signed values, sign-extending each.
+///
+/// # Panics
+///
+/// Panics if `packed` holds fewer than `numel` values.
+pub fn unpack_int4(packed: &[u8], numel: usize) -> Vec<i8> {
+    unpack_nibbles(packed, numel).map(|v| ((v << 4) as i8) >> 4).collect()
+}
+
+/// Packs 4-bit unsigned values two per byte, low nibble first.
+///
+/// Values are truncated to their low 4 bits, so they should lie in `0..=15`.
+pub fn pack_uint4(values: &[u8]) -> Vec<u8> {
+    pack_nibbles(values.iter().copied())
+}
+
+/// Unpacks `numel` 4-bit unsigned values.
+///
+/// # Panics
+///
+/// Panics if `packed` holds fewer than `numel` values.
+pub fn unpack_uint4(packed: &[u8], numel: usize) -> Vec<u8> {
+    unpack_nibbles(packed, numel).collect()
+}
+
+fn pack_nibbles(values: impl ExactSizeIterator<Item = u8>) -> Vec<u8> {
+    let mut packed = vec![0u8; DType::UInt4.byte_size(values.len())];
+    for (i, value) in values.enumerate() {
+        packed[i / 2] |= (value & 0x0F) << (4 * (i % 2));
+    }
+    packed
+}
+
+fn unpack_nibbles(packed: &[u8], numel: usize) -> impl Iterator<Item = u8> + '_ {
+    assert!(
+        packed.len() >= DType::UInt4.byte_size(numel),
+        "{} bytes hold fewer than {} packed values",
+        packed.len(),
+        numel
+    );
+    (0..numel).map(move |i| (packed[i / 2] >> (4 * (i % 2))) & 0x0F)
+}
+
 /// Trait for types that can be used as tensor elements.
 pub trait Element: Copy + Send + Sync + 'static {
     /// The DType corresponding to this element type.
@@ -221,6 +329,41 @@ mod tests {
         assert_eq!(DType::Bool.element_size(), 1);
     }
 
+    #[test]
+    fn test_quantized_dtypes() {
+        assert_eq!(DType::Float8E4M3.element_size(), 1);
+        assert_eq!(DType::Float8E5M2.byte_size(5), 5);
+        assert!(DType::Float8E5M2.is_floating_point());
+        assert!(!DType::Float8E4M3.is_packed());
+
+        assert_eq!(DType::Int4.bit_width(), 4);
+        assert!(DType::UInt4.is_packed());
+        assert_eq!(DType::Int4.byte_size(0), 0);
+        assert_eq!(DType::Int4.byte_size(7), 4);
+        assert!(DType::Int4.is_signed());
+        assert!(!DType::UInt4.is_signed());
+
+        for dtype in [DType::Float8E4M3, DType::Float8E5M2, DType::Int4, DType::UInt4] {
+            assert_eq!(DType::try_from(dtype as u8), Ok(dtype));
+            assert_eq!(DType::from_proto(dtype.to_proto()), Some(dtype));
+        }
+        assert_eq!(DType::from_proto(-1), None);
+        assert_eq!(DType::from_proto(256 + 1), None);
+    }
+
+    #[test]
+    fn test_int4_packing() {
+        let values = [-8i8, 7, -1, 0, 3];
+        let packed = pack_int4(&values);
+        assert_eq!(packed, vec![0x78, 0x0F, 0x03]);
+        assert_eq!(unpack_int4(&packed, values.len()), values);
+
+        let values = [0u8, 15, 9];
+        let packed = pack_uint4(&values);
+        assert_eq!(packed, vec![0xF0, 0x09]);
+        assert_eq!(unpack_uint4(&packed, values.len()), values);
+    }
+
     #[test]
     fn test_dtype_names() {
         assert_eq!(DType::Float32.name(), "float32");
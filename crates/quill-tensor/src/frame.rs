@@ -11,6 +11,12 @@
 //!
 //! This format enables zero-copy tensor streaming by separating metadata
 //! from payload, allowing receivers to pre-allocate buffers.
+//!
+//! Reserved bytes 2-3 of TENSOR_META, TENSOR_PAYLOAD and END_STREAM frames
+//! carry a big-endian tensor ID, so several tensors (e.g. logits, hidden
+//! states and attention maps) can be interleaved on one stream. Each tensor
+//! runs from its TENSOR_META to its END_STREAM. Frames of single-tensor
+//! streams leave the ID at [`DEFAULT_TENSOR_ID`].
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use thiserror::Error;
@@ -21,6 +27,9 @@ pub const TENSOR_FRAME_HEADER_SIZE: usize = 9;
 /// Maximum payload size (4 GB - 1).
 pub const MAX_PAYLOAD_SIZE: u32 = u32::MAX;
 
+/// Tensor ID of frames that don't set one.
+pub const DEFAULT_TENSOR_ID: u16 = 0;
+
 /// Frame types for the tensor streaming protocol.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -51,6 +60,11 @@ pub enum FrameType {
     /// into pre-allocated memory without parsing.
     TensorPayload = 0x11,
 
+    /// Tensor delta frame.
+    /// Contains XOR runs against a previously received tensor of the same
+    /// name. The reserved bytes carry the reference generation.
+    TensorDelta = 0x12,
+
     /// Token batch frame for LLM streaming.
     /// Contains a batch of tokens with optional logprobs.
     TokenBatch = 0x20,
@@ -66,13 +80,17 @@ impl FrameType {
             FrameType::Credit => "CREDIT",
             FrameType::TensorMeta => "TENSOR_META",
             FrameType::TensorPayload => "TENSOR_PAYLOAD",
+            FrameType::TensorDelta => "TENSOR_DELTA",
             FrameType::TokenBatch => "TOKEN_BATCH",
         }
     }
 
     /// Returns whether this frame type carries tensor data.
     pub const fn is_tensor_frame(&self) -> bool {
-        matches!(self, FrameType::TensorMeta | FrameType::TensorPayload)
+        matches!(
+            self,
+            FrameType::TensorMeta | FrameType::TensorPayload | FrameType::TensorDelta
+        )
     }
 
     /// Returns whether this frame type signals stream end or cancellation.
@@ -92,6 +110,7 @@ impl TryFrom<u8> for FrameType {
             0x08 => Ok(FrameType::Credit),
             0x10 => Ok(FrameType::TensorMeta),
             0x11 => Ok(FrameType::TensorPayload),
+            0x12 => Ok(FrameType::TensorDelta),
             0x20 => Ok(FrameType::TokenBatch),
             _ => Err(TensorFrameError::UnknownFrameType(value)),
         }
@@ -129,7 +148,8 @@ pub enum TensorFrameError {
 /// └────────────┴────────────┴────────────┴─────────────────┘
 /// ```
 ///
-/// The reserved bytes can be used for future extensions like:
+/// The reserved bytes hold [`reserved_flags`], the compression codec and
+/// the tensor ID; they can be used for future extensions like:
 /// - Compression hints
 /// - Checksum flags
 /// - Version information
@@ -162,11 +182,34 @@ impl TensorFrame {
         }
     }
 
+    /// Takes the frame apart into its type, reserved bytes and payload.
+    ///
+    /// [`with_reserved`](Self::with_reserved) puts the parts back together
+    /// without copying the payload, e.g. when a relay rewrites the type or
+    /// reserved bytes before forwarding.
+    pub fn into_parts(self) -> (FrameType, [u8; 4], Bytes) {
+        (self.frame_type, self.reserved, self.payload)
+    }
+
     /// Creates a PROTO_MSG frame.
     pub fn proto_msg(payload: Bytes) -> Self {
         Self::new(FrameType::ProtoMsg, payload)
     }
 
+    /// Returns the ID of the tensor this frame belongs to.
+    ///
+    /// Meaningful for TENSOR_META, TENSOR_PAYLOAD and END_STREAM frames;
+    /// [`DEFAULT_TENSOR_ID`] for frames of single-tensor streams.
+    pub fn tensor_id(&self) -> u16 {
+        u16::from_be_bytes([self.reserved[2], self.reserved[3]])
+    }
+
+    /// Returns this frame tagged with tensor ID `id`.
+    pub fn with_tensor_id(mut self, id: u16) -> Self {
+        self.reserved[2..4].copy_from_slice(&id.to_be_bytes());
+        self
+    }
+
     /// Creates a TENSOR_META frame.
     pub fn tensor_meta(payload: Bytes) -> Self {
         Self::new(FrameType::TensorMeta, payload)
@@ -187,6 +230,29 @@ impl TensorFrame {
         Self::new(FrameType::EndStream, Bytes::new())
     }
 
+    /// Creates an END_STREAM frame carrying a CRC32 of the tensor data.
+    ///
+    /// Sets [`reserved_flags::HAS_CHECKSUM`]; receivers that don't check
+    /// the checksum treat it as a plain END_STREAM.
+    pub fn end_stream_with_checksum(crc32: u32) -> Self {
+        Self::with_reserved(
+            FrameType::EndStream,
+            [reserved_flags::HAS_CHECKSUM, 0, 0, 0],
+            Bytes::copy_from_slice(&crc32.to_le_bytes()),
+        )
+    }
+
+    /// Returns the CRC32 carried by an END_STREAM frame, if present.
+    pub fn end_stream_checksum(&self) -> Option<u32> {
+        if self.frame_type != FrameType::EndStream
+            || self.reserved[0] & reserved_flags::HAS_CHECKSUM == 0
+            || self.payload.len() != 4
+        {
+            return None;
+        }
+        Some(u32::from_le_bytes(self.payload[..4].try_into().unwrap()))
+    }
+
     /// Creates a CANCEL frame with optional reason.
     pub fn cancel(reason: Option<&str>) -> Self {
         let payload = reason.map(|r| Bytes::copy_from_slice(r.as_bytes())).unwrap_or_default();
@@ -285,41 +351,62 @@ impl TensorFrame {
 /// Parser for streaming tensor frames.
 ///
 /// Handles partial frame data and buffers until complete frames
-/// can be parsed.
+/// can be parsed. Parsed payloads share the parser's buffers, and chunks
+/// given to [`feed_bytes`](Self::feed_bytes) are only copied when a frame
+/// straddles them and the next chunk.
 #[derive(Debug, Default)]
 pub struct TensorFrameParser {
     buffer: BytesMut,
+    /// Chunk fed as `Bytes` and not yet parsed; set only while `buffer` is empty.
+    chunk: Bytes,
 }
 
 impl TensorFrameParser {
     /// Creates a new parser.
     pub fn new() -> Self {
-        Self {
-            buffer: BytesMut::new(),
-        }
+        Self::default()
     }
 
     /// Creates a new parser with the specified buffer capacity.
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             buffer: BytesMut::with_capacity(capacity),
+            chunk: Bytes::new(),
         }
     }
 
     /// Feeds data into the parser.
     pub fn feed(&mut self, data: &[u8]) {
+        self.spill();
         self.buffer.extend_from_slice(data);
     }
 
-    /// Feeds a Bytes buffer into the parser.
+    /// Feeds a Bytes buffer into the parser, keeping its frames' payloads in place.
     pub fn feed_bytes(&mut self, data: Bytes) {
-        self.buffer.extend_from_slice(&data);
+        if self.buffer.is_empty() && self.chunk.is_empty() {
+            self.chunk = data;
+        } else {
+            self.spill();
+            self.buffer.extend_from_slice(&data);
+        }
     }
 
     /// Attempts to parse the next frame.
     ///
     /// Returns `Ok(None)` if there isn't enough data for a complete frame.
     pub fn parse_frame(&mut self) -> Result<Option<TensorFrame>, TensorFrameError> {
+        if !self.chunk.is_empty() {
+            return match TensorFrame::decode_from_bytes(&mut self.chunk) {
+                Ok(frame) => Ok(Some(frame)),
+                Err(TensorFrameError::Incomplete(_)) => {
+                    // The frame continues in a later chunk
+                    self.spill();
+                    Ok(None)
+                }
+                Err(e) => Err(e),
+            };
+        }
+
         if self.buffer.len() < TENSOR_FRAME_HEADER_SIZE {
             return Ok(None);
         }
@@ -347,8 +434,8 @@ impl TensorFrameParser {
         ];
 
         // Split off the frame data
-        let frame_data = self.buffer.split_to(total_size);
-        let payload = Bytes::copy_from_slice(&frame_data[TENSOR_FRAME_HEADER_SIZE..]);
+        let frame_data = self.buffer.split_to(total_size).freeze();
+        let payload = frame_data.slice(TENSOR_FRAME_HEADER_SIZE..);
 
         Ok(Some(TensorFrame {
             frame_type,
@@ -360,18 +447,27 @@ impl TensorFrameParser {
     /// Returns the number of buffered bytes.
     #[inline]
     pub fn buffered_len(&self) -> usize {
-        self.buffer.len()
+        self.buffer.len() + self.chunk.len()
     }
 
     /// Returns whether the buffer is empty.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.buffer.is_empty()
+        self.buffer.is_empty() && self.chunk.is_empty()
     }
 
     /// Clears the internal buffer.
     pub fn clear(&mut self) {
         self.buffer.clear();
+        self.chunk = Bytes::new();
+    }
+
+    /// Moves the unparsed chunk into the buffer, so later data can follow it.
+    fn spill(&mut self) {
+        if !self.chunk.is_empty() {
+            self.buffer.extend_from_slice(&self.chunk);
+            self.chunk = Bytes::new();
+        }
     }
 }
 
@@ -461,6 +557,36 @@ mod tests {
         assert_eq!(parsed.frame_type, FrameType::TensorPayload);
     }
 
+    #[test]
+    fn test_fed_bytes_are_parsed_in_place() {
+        let meta = TensorFrame::tensor_meta(Bytes::from_static(b"meta"));
+        let data = TensorFrame::tensor_payload(Bytes::from(vec![5u8; 4096]));
+        let mut buf = BytesMut::new();
+        meta.encode_into(&mut buf);
+        data.encode_into(&mut buf);
+        let wire = buf.freeze();
+
+        let mut parser = TensorFrameParser::new();
+        parser.feed_bytes(wire.clone());
+        assert_eq!(parser.parse_frame().unwrap().unwrap().payload, meta.payload);
+        let parsed = parser.parse_frame().unwrap().unwrap();
+        assert_eq!(parsed.payload, data.payload);
+        assert_eq!(parsed.payload.as_ptr(), wire[wire.len() - 4096..].as_ptr());
+        assert!(parser.is_empty());
+
+        // Frames split across chunks fall back to the buffer
+        parser.feed_bytes(wire.slice(..20));
+        assert_eq!(parser.parse_frame().unwrap().unwrap().payload, meta.payload);
+        assert!(parser.parse_frame().unwrap().is_none());
+        parser.feed_bytes(wire.slice(20..));
+        assert_eq!(parser.parse_frame().unwrap().unwrap().payload, data.payload);
+
+        // Taken apart and rebuilt, the payload is still the received one
+        let (frame_type, reserved, payload) = parsed.into_parts();
+        let rebuilt = TensorFrame::with_reserved(frame_type, reserved, payload);
+        assert_eq!(rebuilt.payload.as_ptr(), wire[wire.len() - 4096..].as_ptr());
+    }
+
     #[test]
     fn test_credit_frame() {
         let credit = TensorFrame::credit(1024 * 1024);
@@ -477,6 +603,7 @@ mod tests {
     fn test_frame_type_properties() {
         assert!(FrameType::TensorMeta.is_tensor_frame());
         assert!(FrameType::TensorPayload.is_tensor_frame());
+        assert!(FrameType::TensorDelta.is_tensor_frame());
         assert!(!FrameType::ProtoMsg.is_tensor_frame());
 
         assert!(FrameType::EndStream.is_terminal());
@@ -484,6 +611,28 @@ mod tests {
         assert!(!FrameType::TensorPayload.is_terminal());
     }
 
+    #[test]
+    fn test_end_stream_checksum() {
+        let frame = TensorFrame::end_stream_with_checksum(0xDEADBEEF);
+        let (decoded, _) = TensorFrame::decode(&frame.encode()).unwrap();
+        assert_eq!(decoded.frame_type, FrameType::EndStream);
+        assert_eq!(decoded.end_stream_checksum(), Some(0xDEADBEEF));
+
+        assert_eq!(TensorFrame::end_stream().end_stream_checksum(), None);
+    }
+
+    #[test]
+    fn test_tensor_id() {
+        assert_eq!(TensorFrame::end_stream().tensor_id(), DEFAULT_TENSOR_ID);
+
+        // The ID leaves the checksum flag and codec bytes alone
+        let frame = TensorFrame::end_stream_with_checksum(0xDEADBEEF).with_tensor_id(0x0102);
+        let (decoded, _) = TensorFrame::decode(&frame.encode()).unwrap();
+        assert_eq!(decoded.tensor_id(), 0x0102);
+        assert_eq!(decoded.end_stream_checksum(), Some(0xDEADBEEF));
+        assert_eq!(decoded.reserved[2..], [0x01, 0x02]);
+    }
+
     #[test]
     fn test_cancel_with_reason() {
         let frame = TensorFrame::cancel(Some("timeout"));
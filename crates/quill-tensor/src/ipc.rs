@@ -0,0 +1,254 @@
+//! CUDA IPC handle exchange for same-host tensor passing.
+//!
+//! When sender and receiver run on the same host, streaming a GPU tensor's
+//! bytes over loopback means a device-to-host copy, a socket round trip and
+//! a host-to-device copy. In CUDA IPC mode the sender instead puts a CUDA IPC
+//! memory handle for its allocation into the TENSOR_META frame and sends no
+//! payload frames:
+//!
+//! ```text
+//! TENSOR_META (+ IPC handle) → END_STREAM
+//! ```
+//!
+//! [`TensorReceiver`] and [`GpuTensorReceiver`] open the handle and copy the
+//! tensor straight from the sender's allocation, device-to-device for GPU
+//! buffers. Other receivers reject such metadata.
+//!
+//! # Negotiation
+//!
+//! A receiver advertises the mode with the `quill-tensor-cuda-ipc` header,
+//! whose value from [`cuda_ipc_header_value`] names the host it runs on. The
+//! sender checks it with [`accepts_cuda_ipc`] and passes the result to
+//! [`TensorSender::encode_buffer`], which falls back to byte streaming for
+//! other hosts, CPU buffers, or when no handle can be exported.
+//!
+//! The sender must keep the buffer alive until the receiver has taken the
+//! tensor, e.g. until the call completes.
+//!
+//! [`TensorReceiver`]: crate::TensorReceiver
+//! [`GpuTensorReceiver`]: crate::GpuTensorReceiver
+//! [`TensorSender::encode_buffer`]: crate::TensorSender::encode_buffer
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::buffer::{GpuError, GpuResult, TensorBuffer};
+use crate::tensor::Device;
+
+#[cfg(feature = "cuda")]
+use crate::buffer::CudaBuffer;
+#[cfg(feature = "cuda")]
+use cudarc::driver::{result, sys, CudaDevice, DevicePtrMut};
+
+/// Header used to negotiate CUDA IPC mode.
+pub const CUDA_IPC_HEADER: &str = "quill-tensor-cuda-ipc";
+
+/// Protocol version advertised in [`CUDA_IPC_HEADER`].
+pub const CUDA_IPC_V1: &str = "v1";
+
+/// Size of a CUDA IPC memory handle in bytes.
+pub const CUDA_IPC_HANDLE_SIZE: usize = 64;
+
+/// A CUDA IPC memory handle and the device the memory lives on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CudaIpcHandle {
+    device_id: u32,
+    handle: [u8; CUDA_IPC_HANDLE_SIZE],
+}
+
+impl CudaIpcHandle {
+    /// Creates a handle from its raw bytes.
+    pub fn new(device_id: u32, handle: [u8; CUDA_IPC_HANDLE_SIZE]) -> Self {
+        Self { device_id, handle }
+    }
+
+    /// Returns the sender's device ordinal.
+    pub fn device_id(&self) -> u32 {
+        self.device_id
+    }
+
+    /// Returns the raw handle bytes.
+    pub fn as_bytes(&self) -> &[u8; CUDA_IPC_HANDLE_SIZE] {
+        &self.handle
+    }
+}
+
+impl fmt::Debug for CudaIpcHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CudaIpcHandle")
+            .field("device_id", &self.device_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns an identifier of this host and boot, if the platform has one.
+///
+/// IPC handles are only meaningful between processes sharing a host, so
+/// peers compare this before using them.
+pub fn host_id() -> Option<&'static str> {
+    static HOST_ID: OnceLock<Option<String>> = OnceLock::new();
+    HOST_ID
+        .get_or_init(|| {
+            std::fs::read_to_string("/proc/sys/kernel/random/boot_id")
+                .ok()
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+        })
+        .as_deref()
+}
+
+/// Returns the `quill-tensor-cuda-ipc` header value a receiver sends.
+///
+/// Returns `None` without the `cuda` feature or a host ID, in which case
+/// the header should be left out.
+pub fn cuda_ipc_header_value() -> Option<String> {
+    if !cfg!(feature = "cuda") {
+        return None;
+    }
+    host_id().map(|host| format!("{}; host={}", CUDA_IPC_V1, host))
+}
+
+/// Returns whether a peer's `quill-tensor-cuda-ipc` header value allows
+/// sending IPC handles, i.e. it runs on this host.
+pub fn accepts_cuda_ipc(header_value: Option<&str>) -> bool {
+    let (Some(value), Some(local)) = (header_value, host_id()) else {
+        return false;
+    };
+    let mut parts = value.split(';').map(str::trim);
+    if parts.next() != Some(CUDA_IPC_V1) {
+        return false;
+    }
+    cfg!(feature = "cuda") && parts.any(|part| part.strip_prefix("host=") == Some(local))
+}
+
+/// Exports an IPC handle for a buffer, if it lives on the GPU.
+#[cfg(feature = "cuda")]
+pub(crate) fn export(buffer: &TensorBuffer) -> GpuResult<CudaIpcHandle> {
+    let TensorBuffer::Cuda(buffer) = buffer else {
+        return Err(GpuError::TransferFailed("CPU buffers have no IPC handle".to_string()));
+    };
+    bind(buffer.device_id())?;
+    let mut handle = std::mem::MaybeUninit::<sys::CUipcMemHandle>::uninit();
+    unsafe { sys::lib().cuIpcGetMemHandle(handle.as_mut_ptr(), buffer.as_device_ptr()) }
+        .result()
+        .map_err(|e| GpuError::TransferFailed(format!("Failed to export IPC handle: {}", e)))?;
+    let handle = unsafe { handle.assume_init() };
+    Ok(CudaIpcHandle::new(
+        buffer.device_id() as u32,
+        handle.reserved.map(|b| b as u8),
+    ))
+}
+
+/// Exports an IPC handle for a buffer (non-CUDA version always fails).
+#[cfg(not(feature = "cuda"))]
+pub(crate) fn export(_buffer: &TensorBuffer) -> GpuResult<CudaIpcHandle> {
+    Err(GpuError::NotCompiled)
+}
+
+/// Copies `len` bytes from the allocation behind `handle`.
+///
+/// CUDA tensors are copied device-to-device into a buffer on `device_id`,
+/// falling back to CPU memory if the allocation fails; others are copied to
+/// the host.
+#[cfg(feature = "cuda")]
+pub(crate) fn import(
+    handle: &CudaIpcHandle,
+    device: Device,
+    device_id: usize,
+    len: usize,
+) -> GpuResult<TensorBuffer> {
+    let target = if device == Device::Cuda {
+        match CudaBuffer::allocate(len, device_id) {
+            Ok(buffer) => Some(buffer),
+            Err(e) => {
+                tracing::warn!("GPU allocation failed ({}), copying IPC tensor to CPU", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // Open the handle in the context the copy runs in
+    let context = target.as_ref().map_or(handle.device_id as usize, CudaBuffer::device_id);
+    bind(context)?;
+
+    let raw = sys::CUipcMemHandle {
+        reserved: handle.handle.map(|b| b as std::ffi::c_char),
+    };
+    let mut remote: sys::CUdeviceptr = 0;
+    unsafe {
+        sys::lib().cuIpcOpenMemHandle_v2(
+            &mut remote,
+            raw,
+            sys::CUipcMem_flags::CU_IPC_MEM_LAZY_ENABLE_PEER_ACCESS as std::ffi::c_uint,
+        )
+    }
+    .result()
+    .map_err(|e| GpuError::TransferFailed(format!("Failed to open IPC handle: {}", e)))?;
+
+    let copied = match target {
+        Some(mut buffer) => {
+            let dst = *buffer.storage_mut().device_ptr_mut();
+            unsafe { result::memcpy_dtod_sync(dst, remote, len) }
+                .map(|()| TensorBuffer::Cuda(buffer))
+                .map_err(|e| {
+                    GpuError::TransferFailed(format!("Device-to-device copy failed: {}", e))
+                })
+        }
+        None => {
+            let mut host = vec![0u8; len];
+            unsafe { result::memcpy_dtoh_sync(&mut host, remote) }
+                .map(|()| TensorBuffer::cpu(host.into()))
+                .map_err(|e| {
+                    GpuError::TransferFailed(format!("Device-to-host copy failed: {}", e))
+                })
+        }
+    };
+    let _ = unsafe { sys::lib().cuIpcCloseMemHandle(remote) };
+    copied
+}
+
+/// Copies from an IPC handle (non-CUDA version always fails).
+#[cfg(not(feature = "cuda"))]
+pub(crate) fn import(
+    _handle: &CudaIpcHandle,
+    _device: Device,
+    _device_id: usize,
+    _len: usize,
+) -> GpuResult<TensorBuffer> {
+    Err(GpuError::NotCompiled)
+}
+
+#[cfg(feature = "cuda")]
+fn bind(device_id: usize) -> GpuResult<()> {
+    let device = CudaDevice::new(device_id).map_err(|e| {
+        GpuError::DriverNotAvailable(format!("Failed to open device {}: {}", device_id, e))
+    })?;
+    device
+        .bind_to_thread()
+        .map_err(|e| GpuError::DriverNotAvailable(format!("Failed to bind device: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts_cuda_ipc() {
+        assert!(!accepts_cuda_ipc(None));
+        assert!(!accepts_cuda_ipc(Some("v1; host=some-other-host")));
+        assert!(!accepts_cuda_ipc(Some("v2")));
+
+        if let Some(host) = host_id() {
+            // Same host, but only usable with the cuda feature
+            let value = format!("v1; host={}", host);
+            assert_eq!(accepts_cuda_ipc(Some(&value)), cfg!(feature = "cuda"));
+            assert_eq!(cuda_ipc_header_value().is_some(), cfg!(feature = "cuda"));
+        }
+    }
+
+    #[test]
+    fn test_cpu_buffers_not_exported() {
+        assert!(export(&TensorBuffer::cpu_zeros(16)).is_err());
+    }
+}
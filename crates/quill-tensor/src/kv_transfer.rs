@@ -0,0 +1,690 @@
+//! KV-cache transfer adapters for disaggregated serving.
+//!
+//! Disaggregated inference stacks run prefill and decode on different
+//! engines and move each request's KV cache between them. Engine-side
+//! connectors (vLLM's KV connector, NIXL agents) reduce this to three calls
+//! on the destination: reserve blocks, write blocks into them, and signal
+//! that a request's cache is complete. [`KvTransferAgent`] is that interface;
+//! [`KvTransferSender`] and [`KvTransferReceiver`] carry it over Quill tensor
+//! frames, so Quill can be the network layer between engines.
+//!
+//! | Agent call    | vLLM connector          | NIXL                          |
+//! |---------------|-------------------------|-------------------------------|
+//! | `allocate`    | allocate slots for load | prepare transfer descriptors  |
+//! | `write_block` | save/load KV layer      | WRITE transfer                |
+//! | `notify`      | request finished        | notification message          |
+//!
+//! # Wire Format
+//!
+//! A request's transfer is a PROTO_MSG control frame, one tensor stream per
+//! block, and a closing control frame:
+//!
+//! ```text
+//! PROTO_MSG(allocate) → (TENSOR_META → TENSOR_PAYLOAD* → END_STREAM)* → PROTO_MSG(notify)
+//! ```
+//!
+//! Control frames hold a tag byte (1 = allocate, 2 = notify), the request
+//! ID (u16 length + UTF-8) and, for allocate, the block count (u32). Block
+//! tensors are named `{request_id}/{index}` and shaped by the
+//! [`KvBlockLayout`] both sides agree on. Indexes are the sender's; the
+//! receiver maps them to the block IDs its agent allocated.
+
+use std::collections::HashMap;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use thiserror::Error;
+
+use crate::dtype::DType;
+use crate::frame::{FrameType, TensorFrame, TensorFrameParser};
+use crate::stream::{decode_tensor_meta, TensorSender, TensorStreamError};
+use crate::tensor::{Tensor, TensorMeta};
+
+/// Control frame tag reserving blocks for a request.
+const TAG_ALLOCATE: u8 = 1;
+
+/// Control frame tag completing a request.
+const TAG_NOTIFY: u8 = 2;
+
+/// Errors from KV-cache transfers.
+#[derive(Debug, Error)]
+pub enum KvTransferError {
+    /// Tensor stream error.
+    #[error(transparent)]
+    Stream(#[from] TensorStreamError),
+
+    /// Block tensor does not match the agreed layout.
+    #[error("block layout mismatch: expected {expected}, got {actual}")]
+    LayoutMismatch { expected: String, actual: String },
+
+    /// Block or notify for a request that was never allocated.
+    #[error("unknown request: {0}")]
+    UnknownRequest(String),
+
+    /// Block index beyond the blocks allocated for the request.
+    #[error("block {index} out of range for request {request_id} ({allocated} allocated)")]
+    BlockOutOfRange { request_id: String, index: usize, allocated: usize },
+
+    /// Request notified before all of its blocks were written.
+    #[error("request {request_id} incomplete: {written} of {allocated} blocks written")]
+    Incomplete { request_id: String, written: usize, allocated: usize },
+
+    /// Destination has no free blocks left.
+    #[error("out of KV blocks: requested {requested}, {free} free")]
+    OutOfBlocks { requested: usize, free: usize },
+
+    /// Malformed control frame or block name.
+    #[error("invalid KV transfer frame: {0}")]
+    Invalid(String),
+}
+
+/// Shape of one KV-cache block, shared by sender and receiver.
+///
+/// A block holds keys and values of `block_size` tokens for every layer,
+/// laid out as `[num_layers, 2, block_size, num_kv_heads, head_dim]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvBlockLayout {
+    /// Transformer layers.
+    pub num_layers: usize,
+    /// Tokens per block.
+    pub block_size: usize,
+    /// Key/value heads per layer.
+    pub num_kv_heads: usize,
+    /// Dimension of each head.
+    pub head_dim: usize,
+    /// Element type of the cache.
+    pub dtype: DType,
+}
+
+impl KvBlockLayout {
+    /// Creates a layout.
+    pub fn new(
+        num_layers: usize,
+        block_size: usize,
+        num_kv_heads: usize,
+        head_dim: usize,
+        dtype: DType,
+    ) -> Self {
+        Self { num_layers, block_size, num_kv_heads, head_dim, dtype }
+    }
+
+    /// Returns the tensor shape of one block.
+    pub fn block_shape(&self) -> Vec<usize> {
+        vec![self.num_layers, 2, self.block_size, self.num_kv_heads, self.head_dim]
+    }
+
+    /// Returns the size of one block in bytes.
+    pub fn block_bytes(&self) -> usize {
+        self.block_meta().byte_size()
+    }
+
+    fn block_meta(&self) -> TensorMeta {
+        TensorMeta::new(self.block_shape(), self.dtype)
+    }
+}
+
+/// Destination of KV-cache transfers, implemented by an engine's cache manager.
+///
+/// Calls for one request arrive in order: `allocate`, then `write_block`
+/// once per allocated block, then `notify`.
+pub trait KvTransferAgent {
+    /// Reserves `num_blocks` blocks for a request and returns their IDs.
+    fn allocate(
+        &mut self,
+        request_id: &str,
+        num_blocks: usize,
+    ) -> Result<Vec<u32>, KvTransferError>;
+
+    /// Writes one block of a request into an allocated block.
+    fn write_block(
+        &mut self,
+        request_id: &str,
+        block_id: u32,
+        data: Bytes,
+    ) -> Result<(), KvTransferError>;
+
+    /// Signals that all blocks of a request have been written.
+    fn notify(&mut self, request_id: &str) -> Result<(), KvTransferError>;
+}
+
+/// Encodes KV-cache transfers as tensor frames.
+pub struct KvTransferSender {
+    layout: KvBlockLayout,
+    sender: TensorSender,
+}
+
+impl KvTransferSender {
+    /// Creates a sender for blocks of the given layout.
+    pub fn new(layout: KvBlockLayout) -> Self {
+        Self { layout, sender: TensorSender::new() }
+    }
+
+    /// Sets the chunk size used for block payload frames.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.sender = TensorSender::with_chunk_size(chunk_size);
+        self
+    }
+
+    /// Returns the block layout.
+    pub fn layout(&self) -> &KvBlockLayout {
+        &self.layout
+    }
+
+    /// Encodes the frame reserving `num_blocks` blocks for a request.
+    pub fn encode_allocate(&self, request_id: &str, num_blocks: usize) -> TensorFrame {
+        let mut payload = control_payload(TAG_ALLOCATE, request_id);
+        payload.put_u32_le(num_blocks as u32);
+        TensorFrame::proto_msg(payload.freeze())
+    }
+
+    /// Encodes block `index` of a request.
+    pub fn encode_block(
+        &self,
+        request_id: &str,
+        index: usize,
+        data: Bytes,
+    ) -> Result<Vec<TensorFrame>, KvTransferError> {
+        let meta = self.layout.block_meta().with_name(format!("{}/{}", request_id, index));
+        if data.len() != meta.byte_size() {
+            return Err(TensorStreamError::SizeMismatch {
+                expected: meta.byte_size(),
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(self.sender.encode_tensor(&Tensor::new(meta, data)))
+    }
+
+    /// Encodes the frame completing a request.
+    pub fn encode_notify(&self, request_id: &str) -> TensorFrame {
+        TensorFrame::proto_msg(control_payload(TAG_NOTIFY, request_id).freeze())
+    }
+
+    /// Encodes a whole transfer: allocate, every block in order, notify.
+    pub fn encode_request(
+        &self,
+        request_id: &str,
+        blocks: &[Bytes],
+    ) -> Result<Vec<TensorFrame>, KvTransferError> {
+        let mut frames = vec![self.encode_allocate(request_id, blocks.len())];
+        for (index, block) in blocks.iter().enumerate() {
+            frames.extend(self.encode_block(request_id, index, block.clone())?);
+        }
+        frames.push(self.encode_notify(request_id));
+        Ok(frames)
+    }
+}
+
+fn control_payload(tag: u8, request_id: &str) -> BytesMut {
+    let mut payload = BytesMut::with_capacity(1 + 2 + request_id.len() + 4);
+    payload.put_u8(tag);
+    payload.put_u16_le(request_id.len() as u16);
+    payload.put_slice(request_id.as_bytes());
+    payload
+}
+
+/// Events produced by the KV transfer receiver.
+#[derive(Debug, PartialEq, Eq)]
+pub enum KvTransferEvent {
+    /// Blocks reserved for a request.
+    Allocated { request_id: String, block_ids: Vec<u32> },
+    /// Block `index` of a request written to `block_id`.
+    BlockWritten { request_id: String, index: usize, block_id: u32 },
+    /// All blocks of a request written and the agent notified.
+    Completed { request_id: String },
+    /// Stream was cancelled.
+    Cancelled(String),
+    /// Need more data to parse next frame.
+    NeedMoreData,
+}
+
+/// Allocation and progress of one in-flight request.
+struct Transfer {
+    block_ids: Vec<u32>,
+    written: Vec<bool>,
+}
+
+/// Block being received.
+struct PendingBlock {
+    request_id: String,
+    index: usize,
+    buffer: BytesMut,
+}
+
+/// Decodes KV-cache transfers from tensor frames and drives an agent.
+pub struct KvTransferReceiver<A> {
+    parser: TensorFrameParser,
+    layout: KvBlockLayout,
+    agent: A,
+    transfers: HashMap<String, Transfer>,
+    block: Option<PendingBlock>,
+}
+
+impl<A: KvTransferAgent> KvTransferReceiver<A> {
+    /// Creates a receiver writing blocks of the given layout to `agent`.
+    pub fn new(layout: KvBlockLayout, agent: A) -> Self {
+        Self {
+            parser: TensorFrameParser::new(),
+            layout,
+            agent,
+            transfers: HashMap::new(),
+            block: None,
+        }
+    }
+
+    /// Feeds raw bytes into the receiver.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.parser.feed(data);
+    }
+
+    /// Feeds a Bytes buffer into the receiver.
+    pub fn feed_bytes(&mut self, data: Bytes) {
+        self.parser.feed_bytes(data);
+    }
+
+    /// Processes the next available frame.
+    pub fn poll(&mut self) -> Result<KvTransferEvent, KvTransferError> {
+        loop {
+            let Some(frame) = self.parser.parse_frame().map_err(TensorStreamError::from)? else {
+                return Ok(KvTransferEvent::NeedMoreData);
+            };
+            if let Some(event) = self.handle_frame(frame)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Returns the agent.
+    pub fn agent(&self) -> &A {
+        &self.agent
+    }
+
+    /// Returns the agent mutably.
+    pub fn agent_mut(&mut self) -> &mut A {
+        &mut self.agent
+    }
+
+    /// Returns the number of requests allocated but not yet completed.
+    pub fn in_flight(&self) -> usize {
+        self.transfers.len()
+    }
+
+    /// Consumes the receiver, returning the agent.
+    pub fn into_agent(self) -> A {
+        self.agent
+    }
+
+    fn handle_frame(
+        &mut self,
+        frame: TensorFrame,
+    ) -> Result<Option<KvTransferEvent>, KvTransferError> {
+        match frame.frame_type {
+            FrameType::ProtoMsg => self.handle_control(frame.payload).map(Some),
+            FrameType::TensorMeta => {
+                self.start_block(&frame.payload)?;
+                Ok(None)
+            }
+            FrameType::TensorPayload => {
+                let block = self.block.as_mut().ok_or(TensorStreamError::MissingMetadata)?;
+                block.buffer.extend_from_slice(&frame.payload);
+                Ok(None)
+            }
+            FrameType::EndStream => self.finish_block(&frame).map(Some),
+            FrameType::Cancel => {
+                self.block = None;
+                let reason = String::from_utf8_lossy(&frame.payload).into_owned();
+                Ok(Some(KvTransferEvent::Cancelled(reason)))
+            }
+            _ => Err(TensorStreamError::UnexpectedFrame {
+                expected: "PROTO_MSG, TENSOR_META, TENSOR_PAYLOAD, END_STREAM, or CANCEL",
+                actual: frame.frame_type.name(),
+            }
+            .into()),
+        }
+    }
+
+    fn handle_control(&mut self, mut payload: Bytes) -> Result<KvTransferEvent, KvTransferError> {
+        let truncated = || KvTransferError::Invalid("truncated control frame".to_string());
+        if payload.remaining() < 3 {
+            return Err(truncated());
+        }
+        let tag = payload.get_u8();
+        let len = payload.get_u16_le() as usize;
+        if payload.remaining() < len {
+            return Err(truncated());
+        }
+        let request_id = String::from_utf8(payload.split_to(len).to_vec())
+            .map_err(|_| KvTransferError::Invalid("request ID is not UTF-8".to_string()))?;
+
+        match tag {
+            TAG_ALLOCATE => {
+                if payload.remaining() < 4 {
+                    return Err(truncated());
+                }
+                let num_blocks = payload.get_u32_le() as usize;
+                let block_ids = self.agent.allocate(&request_id, num_blocks)?;
+                self.transfers.insert(
+                    request_id.clone(),
+                    Transfer { block_ids: block_ids.clone(), written: vec![false; num_blocks] },
+                );
+                Ok(KvTransferEvent::Allocated { request_id, block_ids })
+            }
+            TAG_NOTIFY => {
+                let transfer = self
+                    .transfers
+                    .get(&request_id)
+                    .ok_or_else(|| KvTransferError::UnknownRequest(request_id.clone()))?;
+                let written = transfer.written.iter().filter(|w| **w).count();
+                if written < transfer.written.len() {
+                    return Err(KvTransferError::Incomplete {
+                        request_id,
+                        written,
+                        allocated: transfer.written.len(),
+                    });
+                }
+                self.agent.notify(&request_id)?;
+                self.transfers.remove(&request_id);
+                Ok(KvTransferEvent::Completed { request_id })
+            }
+            _ => Err(KvTransferError::Invalid(format!("unknown control tag {}", tag))),
+        }
+    }
+
+    fn start_block(&mut self, payload: &[u8]) -> Result<(), KvTransferError> {
+        let mut meta = decode_tensor_meta(payload)?;
+        let name = meta.name.take().unwrap_or_default();
+        let expected = self.layout.block_meta();
+        if meta.shape != expected.shape || meta.dtype != expected.dtype {
+            return Err(KvTransferError::LayoutMismatch {
+                expected: format!("{:?} {:?}", expected.dtype, expected.shape),
+                actual: format!("{:?} {:?}", meta.dtype, meta.shape),
+            });
+        }
+
+        let (request_id, index) = name
+            .rsplit_once('/')
+            .and_then(|(request_id, index)| Some((request_id, index.parse::<usize>().ok()?)))
+            .ok_or_else(|| KvTransferError::Invalid(format!("bad block name '{}'", name)))?;
+        let transfer = self
+            .transfers
+            .get(request_id)
+            .ok_or_else(|| KvTransferError::UnknownRequest(request_id.to_string()))?;
+        if index >= transfer.block_ids.len() {
+            return Err(KvTransferError::BlockOutOfRange {
+                request_id: request_id.to_string(),
+                index,
+                allocated: transfer.block_ids.len(),
+            });
+        }
+
+        self.block = Some(PendingBlock {
+            request_id: request_id.to_string(),
+            index,
+            buffer: BytesMut::with_capacity(expected.byte_size()),
+        });
+        Ok(())
+    }
+
+    fn finish_block(&mut self, frame: &TensorFrame) -> Result<KvTransferEvent, KvTransferError> {
+        let block = self.block.take().ok_or(TensorStreamError::MissingMetadata)?;
+        let expected = self.layout.block_bytes();
+        if block.buffer.len() != expected {
+            return Err(
+                TensorStreamError::SizeMismatch { expected, actual: block.buffer.len() }.into()
+            );
+        }
+        if let Some(expected) = frame.end_stream_checksum() {
+            let actual = crc32fast::hash(&block.buffer);
+            if actual != expected {
+                return Err(TensorStreamError::ChecksumMismatch { expected, actual }.into());
+            }
+        }
+
+        let transfer = self
+            .transfers
+            .get_mut(&block.request_id)
+            .ok_or_else(|| KvTransferError::UnknownRequest(block.request_id.clone()))?;
+        let block_id = transfer.block_ids[block.index];
+        self.agent.write_block(&block.request_id, block_id, block.buffer.freeze())?;
+        transfer.written[block.index] = true;
+        Ok(KvTransferEvent::BlockWritten {
+            request_id: block.request_id,
+            index: block.index,
+            block_id,
+        })
+    }
+}
+
+/// In-memory KV-cache agent with a fixed pool of blocks.
+///
+/// Serves as a host-side staging cache and as the reference agent for
+/// connector tests.
+pub struct HostKvCache {
+    block_bytes: usize,
+    blocks: Vec<Option<Bytes>>,
+    free: Vec<u32>,
+    allocated: HashMap<String, Vec<u32>>,
+    ready: HashMap<String, Vec<u32>>,
+}
+
+impl HostKvCache {
+    /// Creates a cache of `capacity` blocks of the given layout.
+    pub fn new(layout: &KvBlockLayout, capacity: usize) -> Self {
+        Self {
+            block_bytes: layout.block_bytes(),
+            blocks: vec![None; capacity],
+            free: (0..capacity as u32).rev().collect(),
+            allocated: HashMap::new(),
+            ready: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of free blocks.
+    pub fn free_blocks(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Returns the block IDs of a completed request, in sender order.
+    pub fn ready(&self, request_id: &str) -> Option<&[u32]> {
+        self.ready.get(request_id).map(Vec::as_slice)
+    }
+
+    /// Returns the contents of a written block.
+    pub fn read_block(&self, block_id: u32) -> Option<&Bytes> {
+        self.blocks.get(block_id as usize)?.as_ref()
+    }
+
+    /// Frees the blocks of a request, completed or not.
+    pub fn release(&mut self, request_id: &str) {
+        let ids = self.ready.remove(request_id).or_else(|| self.allocated.remove(request_id));
+        for id in ids.into_iter().flatten() {
+            self.blocks[id as usize] = None;
+            self.free.push(id);
+        }
+    }
+}
+
+impl KvTransferAgent for HostKvCache {
+    fn allocate(
+        &mut self,
+        request_id: &str,
+        num_blocks: usize,
+    ) -> Result<Vec<u32>, KvTransferError> {
+        if num_blocks > self.free.len() {
+            return Err(KvTransferError::OutOfBlocks {
+                requested: num_blocks,
+                free: self.free.len(),
+            });
+        }
+        self.release(request_id);
+        let ids =
+            self.free.split_off(self.free.len() - num_blocks).into_iter().rev().collect::<Vec<_>>();
+        self.allocated.insert(request_id.to_string(), ids.clone());
+        Ok(ids)
+    }
+
+    fn write_block(
+        &mut self,
+        request_id: &str,
+        block_id: u32,
+        data: Bytes,
+    ) -> Result<(), KvTransferError> {
+        let owned = self.allocated.get(request_id).is_some_and(|ids| ids.contains(&block_id));
+        if !owned {
+            return Err(KvTransferError::Invalid(format!(
+                "block {} is not allocated to request {}",
+                block_id, request_id
+            )));
+        }
+        if data.len() != self.block_bytes {
+            return Err(TensorStreamError::SizeMismatch {
+                expected: self.block_bytes,
+                actual: data.len(),
+            }
+            .into());
+        }
+        self.blocks[block_id as usize] = Some(data);
+        Ok(())
+    }
+
+    fn notify(&mut self, request_id: &str) -> Result<(), KvTransferError> {
+        let ids = self
+            .allocated
+            .remove(request_id)
+            .ok_or_else(|| KvTransferError::UnknownRequest(request_id.to_string()))?;
+        self.ready.insert(request_id.to_string(), ids);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> KvBlockLayout {
+        KvBlockLayout::new(2, 4, 2, 8, DType::Float16)
+    }
+
+    fn block(fill: u8) -> Bytes {
+        Bytes::from(vec![fill; layout().block_bytes()])
+    }
+
+    fn receive_all<A: KvTransferAgent>(
+        receiver: &mut KvTransferReceiver<A>,
+        frames: &[TensorFrame],
+    ) -> Result<Vec<KvTransferEvent>, KvTransferError> {
+        for frame in frames {
+            receiver.feed_bytes(frame.encode());
+        }
+        let mut events = Vec::new();
+        loop {
+            match receiver.poll()? {
+                KvTransferEvent::NeedMoreData => return Ok(events),
+                event => events.push(event),
+            }
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = layout();
+        assert_eq!(layout.block_shape(), vec![2, 2, 4, 2, 8]);
+        assert_eq!(layout.block_bytes(), 2 * 2 * 4 * 2 * 8 * 2);
+    }
+
+    #[test]
+    fn test_transfer_roundtrip() {
+        let sender = KvTransferSender::new(layout()).with_chunk_size(100);
+        let frames = sender.encode_request("req/1", &[block(1), block(2), block(3)]).unwrap();
+
+        let mut receiver = KvTransferReceiver::new(layout(), HostKvCache::new(&layout(), 8));
+        let events = receive_all(&mut receiver, &frames).unwrap();
+
+        let KvTransferEvent::Allocated { block_ids, .. } = &events[0] else {
+            panic!("expected allocation, got {:?}", events[0]);
+        };
+        let block_ids = block_ids.clone();
+        assert_eq!(events.len(), 5);
+        assert_eq!(
+            events[2],
+            KvTransferEvent::BlockWritten {
+                request_id: "req/1".into(),
+                index: 1,
+                block_id: block_ids[1]
+            }
+        );
+        assert_eq!(events[4], KvTransferEvent::Completed { request_id: "req/1".into() });
+        assert_eq!(receiver.in_flight(), 0);
+
+        let mut cache = receiver.into_agent();
+        assert_eq!(cache.ready("req/1"), Some(&block_ids[..]));
+        for (fill, id) in (1..).zip(&block_ids) {
+            assert_eq!(cache.read_block(*id), Some(&block(fill)));
+        }
+        assert_eq!(cache.free_blocks(), 5);
+        cache.release("req/1");
+        assert_eq!(cache.free_blocks(), 8);
+    }
+
+    #[test]
+    fn test_interleaved_requests_and_checksums() {
+        let sender = KvTransferSender::new(layout());
+        let mut frames = vec![sender.encode_allocate("a", 1), sender.encode_allocate("b", 1)];
+        let checksummed = TensorSender::new().enable_checksum(true);
+        let meta = layout().block_meta().with_name("b/0");
+        frames.extend(checksummed.encode_tensor(&Tensor::new(meta, block(7))));
+        frames.extend(sender.encode_block("a", 0, block(9)).unwrap());
+        frames.push(sender.encode_notify("b"));
+        frames.push(sender.encode_notify("a"));
+
+        let mut receiver = KvTransferReceiver::new(layout(), HostKvCache::new(&layout(), 2));
+        let events = receive_all(&mut receiver, &frames).unwrap();
+        assert_eq!(events.len(), 6);
+
+        let cache = receiver.agent();
+        let a = cache.ready("a").unwrap()[0];
+        let b = cache.ready("b").unwrap()[0];
+        assert_eq!(cache.read_block(a), Some(&block(9)));
+        assert_eq!(cache.read_block(b), Some(&block(7)));
+    }
+
+    #[test]
+    fn test_rejects_invalid_transfers() {
+        let sender = KvTransferSender::new(layout());
+        assert!(sender.encode_block("r", 0, Bytes::from_static(b"short")).is_err());
+
+        // Each case follows an allocation of two blocks for "r"
+        let receive_after_allocate = |frames: Vec<TensorFrame>| {
+            let mut receiver = KvTransferReceiver::new(layout(), HostKvCache::new(&layout(), 4));
+            let mut all = vec![sender.encode_allocate("r", 2)];
+            all.extend(frames);
+            receive_all(&mut receiver, &all).unwrap_err()
+        };
+
+        // Notify before all blocks arrived
+        let error = receive_after_allocate(vec![sender.encode_notify("r")]);
+        assert!(matches!(error, KvTransferError::Incomplete { written: 0, allocated: 2, .. }));
+
+        // Block for an unallocated request
+        let error = receive_after_allocate(sender.encode_block("other", 0, block(1)).unwrap());
+        assert!(matches!(error, KvTransferError::UnknownRequest(id) if id == "other"));
+
+        // Block beyond the allocation
+        let error = receive_after_allocate(sender.encode_block("r", 2, block(1)).unwrap());
+        assert!(matches!(error, KvTransferError::BlockOutOfRange { index: 2, allocated: 2, .. }));
+
+        // Block in a different layout
+        let other = KvTransferSender::new(KvBlockLayout::new(2, 4, 2, 8, DType::Float32));
+        let error =
+            receive_after_allocate(other.encode_block("r", 0, Bytes::from(vec![0; 1024])).unwrap());
+        assert!(matches!(error, KvTransferError::LayoutMismatch { .. }));
+    }
+
+    #[test]
+    fn test_out_of_blocks() {
+        let sender = KvTransferSender::new(layout());
+        let mut receiver = KvTransferReceiver::new(layout(), HostKvCache::new(&layout(), 2));
+        let error = receive_all(&mut receiver, &[sender.encode_allocate("r", 3)]).unwrap_err();
+        assert!(matches!(error, KvTransferError::OutOfBlocks { requested: 3, free: 2 }));
+        assert_eq!(receiver.in_flight(), 0);
+    }
+}
@@ -0,0 +1,282 @@
+//! Token-level latency of generation streams.
+//!
+//! Producers stamp each token with its generation time, in microseconds
+//! since the stream started (see
+//! [`TokenBatchBuilder::with_timestamps`](crate::TokenBatchBuilder::with_timestamps)).
+//! Since the stamps are taken on the producer's monotonic clock when each
+//! token is generated, clients measure time-to-first-token and inter-token
+//! latency precisely, whatever the batching or network delays:
+//!
+//! - [`TokenLatencyTracker`] accumulates the tokens of one stream into a
+//!   [`TokenLatencySummary`]
+//! - [`TokenLatencyMetrics`] aggregates the summaries of many streams
+//! - [`TokenStream`](crate::TokenStream) does both as batches arrive and
+//!   reports the summary once the stream completes
+//!
+//! Tokens without a timestamp are ignored. Stamped tokens need decoders
+//! that know the timestamp flag, so only enable stamping once clients are
+//! updated. A tracker follows one sequence; use one per `sequence_id` when
+//! generating several sequences at once.
+//!
+//! # Example
+//!
+//! ```rust
+//! use quill_tensor::latency::TokenLatencyTracker;
+//! use quill_tensor::{Token, TokenBatch};
+//! use std::time::Duration;
+//!
+//! let mut tracker = TokenLatencyTracker::new();
+//! tracker.record(&TokenBatch::with_tokens(vec![
+//!     Token::new(1, 0).with_timestamp_us(120_000),
+//!     Token::new(2, 1).with_timestamp_us(140_000),
+//!     Token::new(3, 2).with_timestamp_us(170_000),
+//! ]));
+//!
+//! let summary = tracker.summary();
+//! assert_eq!(summary.time_to_first_token, Some(Duration::from_millis(120)));
+//! assert_eq!(summary.mean_inter_token, Some(Duration::from_millis(25)));
+//! assert_eq!(summary.max_inter_token, Some(Duration::from_millis(30)));
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::token::{Token, TokenBatch};
+
+/// Callback invoked with the summary of a completed stream.
+pub type LatencyListener = Arc<dyn Fn(&TokenLatencySummary) + Send + Sync>;
+
+/// Latency of the tokens of one stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenLatencySummary {
+    /// Number of timestamped tokens.
+    pub tokens: usize,
+    /// Generation time of the first token, since the stream started.
+    pub time_to_first_token: Option<Duration>,
+    /// Generation time of the last token, since the stream started.
+    pub generation_time: Option<Duration>,
+    /// Mean time between two consecutive tokens.
+    pub mean_inter_token: Option<Duration>,
+    /// Median time between two consecutive tokens.
+    pub p50_inter_token: Option<Duration>,
+    /// 99th percentile of the time between two consecutive tokens.
+    pub p99_inter_token: Option<Duration>,
+    /// Longest time between two consecutive tokens.
+    pub max_inter_token: Option<Duration>,
+}
+
+impl TokenLatencySummary {
+    /// Returns the decoding rate after the first token, in tokens per second.
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let mean = self.mean_inter_token?.as_secs_f64();
+        (mean > 0.0).then(|| 1.0 / mean)
+    }
+}
+
+/// Accumulates token timestamps of one stream.
+#[derive(Debug, Clone, Default)]
+pub struct TokenLatencyTracker {
+    first_us: Option<u64>,
+    last_us: Option<u64>,
+    /// Microseconds between consecutive tokens.
+    gaps_us: Vec<u64>,
+    tokens: usize,
+}
+
+impl TokenLatencyTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the timestamped tokens of a batch.
+    pub fn record(&mut self, batch: &TokenBatch) {
+        batch.iter().for_each(|token| self.record_token(token));
+    }
+
+    /// Records one token, if it is timestamped.
+    pub fn record_token(&mut self, token: &Token) {
+        let Some(timestamp_us) = token.timestamp_us else {
+            return;
+        };
+        if let Some(last_us) = self.last_us {
+            self.gaps_us.push(timestamp_us.saturating_sub(last_us));
+        }
+        self.first_us.get_or_insert(timestamp_us);
+        self.last_us = Some(timestamp_us);
+        self.tokens += 1;
+    }
+
+    /// Returns the latency of the tokens recorded so far.
+    pub fn summary(&self) -> TokenLatencySummary {
+        let mut gaps = self.gaps_us.clone();
+        gaps.sort_unstable();
+        let percentile = |p: usize| {
+            let last = gaps.len().checked_sub(1)?;
+            Some(Duration::from_micros(gaps[last * p / 100]))
+        };
+        let mean = (!gaps.is_empty())
+            .then(|| Duration::from_micros(gaps.iter().sum::<u64>() / gaps.len() as u64));
+
+        TokenLatencySummary {
+            tokens: self.tokens,
+            time_to_first_token: self.first_us.map(Duration::from_micros),
+            generation_time: self.last_us.map(Duration::from_micros),
+            mean_inter_token: mean,
+            p50_inter_token: percentile(50),
+            p99_inter_token: percentile(99),
+            max_inter_token: gaps.last().copied().map(Duration::from_micros),
+        }
+    }
+}
+
+/// Aggregate latency of the streams recorded into [`TokenLatencyMetrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenLatencyStats {
+    /// Number of streams with at least one timestamped token.
+    pub streams: u64,
+    /// Number of timestamped tokens.
+    pub tokens: u64,
+    /// Mean time-to-first-token across streams.
+    pub mean_time_to_first_token: Option<Duration>,
+    /// Longest time-to-first-token of any stream.
+    pub max_time_to_first_token: Option<Duration>,
+    /// Mean time between two consecutive tokens across streams.
+    pub mean_inter_token: Option<Duration>,
+    /// Longest time between two consecutive tokens of any stream.
+    pub max_inter_token: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    streams: u64,
+    tokens: u64,
+    ttft_sum_us: u128,
+    ttft_max: Option<Duration>,
+    gaps: u64,
+    gap_sum_us: u128,
+    gap_max: Option<Duration>,
+}
+
+/// Token latency aggregated across streams.
+///
+/// Share one instance (behind an `Arc`) between the streams of a client,
+/// e.g. to export time-to-first-token next to its other metrics.
+#[derive(Debug, Default)]
+pub struct TokenLatencyMetrics {
+    totals: Mutex<Totals>,
+}
+
+impl TokenLatencyMetrics {
+    /// Creates empty metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the summary of a completed stream.
+    pub fn record(&self, summary: &TokenLatencySummary) {
+        let Some(ttft) = summary.time_to_first_token else {
+            return;
+        };
+        let mut totals = self.totals.lock().unwrap();
+        totals.streams += 1;
+        totals.tokens += summary.tokens as u64;
+        totals.ttft_sum_us += ttft.as_micros();
+        totals.ttft_max = totals.ttft_max.max(Some(ttft));
+        if let Some(mean) = summary.mean_inter_token {
+            let gaps = summary.tokens.saturating_sub(1) as u64;
+            totals.gaps += gaps;
+            totals.gap_sum_us += mean.as_micros() * gaps as u128;
+            totals.gap_max = totals.gap_max.max(summary.max_inter_token);
+        }
+    }
+
+    /// Returns a snapshot of the aggregate latency.
+    pub fn stats(&self) -> TokenLatencyStats {
+        let totals = self.totals.lock().unwrap();
+        let mean = |sum: u128, count: u64| {
+            (count > 0).then(|| Duration::from_micros((sum / count as u128) as u64))
+        };
+        TokenLatencyStats {
+            streams: totals.streams,
+            tokens: totals.tokens,
+            mean_time_to_first_token: mean(totals.ttft_sum_us, totals.streams),
+            max_time_to_first_token: totals.ttft_max,
+            mean_inter_token: mean(totals.gap_sum_us, totals.gaps),
+            max_inter_token: totals.gap_max,
+        }
+    }
+
+    /// Discards everything recorded.
+    pub fn reset(&self) {
+        *self.totals.lock().unwrap() = Totals::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamped(timestamps_ms: &[u64]) -> TokenBatch {
+        let tokens = timestamps_ms
+            .iter()
+            .enumerate()
+            .map(|(i, ms)| Token::new(i as u32, i as u32).with_timestamp_us(ms * 1000))
+            .collect();
+        TokenBatch::with_tokens(tokens)
+    }
+
+    #[test]
+    fn test_summary_across_batches() {
+        let mut tracker = TokenLatencyTracker::new();
+        assert_eq!(tracker.summary(), TokenLatencySummary::default());
+
+        tracker.record(&stamped(&[100]));
+        let first = tracker.summary();
+        assert_eq!(first.time_to_first_token, Some(Duration::from_millis(100)));
+        assert_eq!(first.mean_inter_token, None);
+        assert_eq!(first.tokens_per_second(), None);
+
+        // Gaps span batch boundaries; unstamped tokens are skipped
+        let mut batch = stamped(&[110, 120, 130]);
+        batch.push(Token::new(9, 9));
+        tracker.record(&batch);
+        tracker.record(&stamped(&[230]));
+
+        let summary = tracker.summary();
+        assert_eq!(summary.tokens, 5);
+        assert_eq!(summary.generation_time, Some(Duration::from_millis(230)));
+        assert_eq!(summary.mean_inter_token, Some(Duration::from_micros(32_500)));
+        assert_eq!(summary.p50_inter_token, Some(Duration::from_millis(10)));
+        assert_eq!(summary.p99_inter_token, Some(Duration::from_millis(10)));
+        assert_eq!(summary.max_inter_token, Some(Duration::from_millis(100)));
+        let rate = summary.tokens_per_second().unwrap();
+        assert!((rate - 1.0 / 0.0325).abs() < 1e-6, "{}", rate);
+    }
+
+    #[test]
+    fn test_metrics_aggregate_streams() {
+        let metrics = TokenLatencyMetrics::new();
+        let summary = |timestamps_ms: &[u64]| {
+            let mut tracker = TokenLatencyTracker::new();
+            tracker.record(&stamped(timestamps_ms));
+            tracker.summary()
+        };
+
+        metrics.record(&summary(&[100, 110, 120]));
+        metrics.record(&summary(&[300, 340]));
+        // Streams without timestamps are not counted
+        metrics.record(&TokenLatencySummary::default());
+
+        let stats = metrics.stats();
+        assert_eq!(stats.streams, 2);
+        assert_eq!(stats.tokens, 5);
+        assert_eq!(stats.mean_time_to_first_token, Some(Duration::from_millis(200)));
+        assert_eq!(stats.max_time_to_first_token, Some(Duration::from_millis(300)));
+        assert_eq!(stats.mean_inter_token, Some(Duration::from_millis(20)));
+        assert_eq!(stats.max_inter_token, Some(Duration::from_millis(40)));
+
+        metrics.reset();
+        assert_eq!(metrics.stats(), TokenLatencyStats::default());
+    }
+}
@@ -6,10 +6,21 @@
 //! # Features
 //!
 //! - **Zero-copy streaming**: Pre-allocate buffers based on tensor metadata
-//! - **ML data types**: f32, f16, bf16, i8, i32, i64, u8, bool
+//! - **ML data types**: f32, f16, bf16, i8, i32, i64, u8, bool, FP8 (E4M3/E5M2), packed int4/uint4
 //! - **Tensor streaming**: Chunk large tensors for efficient transfer
+//! - **Multi-tensor streams**: Interleave several tensors on one stream by tensor ID
+//! - **Payload compression**: Optional per-chunk LZ4 or zstd compression
+//! - **Relay passthrough**: Validate and forward tensor streams without assembling them
+//! - **Memory-mapped receive**: Stream tensors larger than RAM straight into a file
+//! - **Multi-stream reassembly**: Reorder chunks from parallel streams within a memory bound
+//! - **Delta streaming**: Send XOR deltas for repeatedly-streamed similar tensors
+//! - **KV-cache transfer**: Adapters for vLLM/NIXL-style disaggregated serving
 //! - **Token batching**: Efficient LLM token generation streaming
+//! - **Token latency**: Time-to-first-token and inter-token latency from per-token timestamps
+//! - **Token post-processing**: Stop sequences, max tokens and banned-text filters on the client
 //! - **GPU support**: Optional CUDA GPU memory via `cuda` feature
+//! - **Staged GPU upload**: Overlap receive and host-to-device copies through pinned buffers
+//! - **CUDA IPC**: Pass GPU tensors between processes on one host by memory handle
 //!
 //! # GPU Support
 //!
@@ -38,24 +49,52 @@
 //! ```
 
 pub mod buffer;
+pub mod compression;
+pub mod delta;
 pub mod dlpack;
 pub mod dtype;
 pub mod frame;
+pub mod ipc;
+pub mod kv_transfer;
+pub mod latency;
+pub mod mmap;
+pub mod passthrough;
 pub mod pool;
+pub mod postprocess;
+pub mod reorder;
+pub mod staging;
 pub mod stream;
 pub mod tensor;
 pub mod token;
 
 pub use buffer::{GpuError, GpuResult, GpuStatus, TensorBuffer};
+pub use compression::TensorCodec;
+pub use delta::{
+    accepts_delta, DeltaConfig, DeltaEvent, DeltaReceiver, DeltaSender, DELTA_ENCODING_HEADER,
+    DELTA_ENCODING_XOR_V1,
+};
 pub use dlpack::{
     CudaArrayInterface, DLDataType, DLDevice, DLDeviceType, DLManagedTensor, DLPackCapsule,
     DLPackError, DLTensor,
 };
-pub use dtype::DType;
-pub use frame::{FrameType, TensorFrame, TensorFrameError, TensorFrameParser};
+pub use dtype::{pack_int4, pack_uint4, unpack_int4, unpack_uint4, DType};
+pub use frame::{FrameType, TensorFrame, TensorFrameError, TensorFrameParser, DEFAULT_TENSOR_ID};
+pub use ipc::{accepts_cuda_ipc, cuda_ipc_header_value, CudaIpcHandle, CUDA_IPC_HEADER};
+pub use kv_transfer::{
+    HostKvCache, KvBlockLayout, KvTransferAgent, KvTransferError, KvTransferEvent,
+    KvTransferReceiver, KvTransferSender,
+};
+pub use latency::{
+    TokenLatencyMetrics, TokenLatencyStats, TokenLatencySummary, TokenLatencyTracker,
+};
+pub use mmap::MmapTensorReceiver;
+pub use passthrough::TensorPassthrough;
 pub use pool::{
     GpuMemoryPool, PinnedMemoryPool, PoolConfig, PoolStats, PooledBuffer, PooledGpuBuffer,
 };
+pub use postprocess::{PostProcessedStream, TokenPostProcessor, TokenRuleEvent};
+pub use reorder::{ReorderBuffer, ReorderConfig, ReorderStats};
+pub use staging::StagingConfig;
 pub use stream::{
     GpuReceiverEvent, GpuTensorReceiver, PooledGpuReceiver, PooledTensorBuffer, TensorChunk,
     TensorReceiver, TensorSender, TensorStream,
@@ -0,0 +1,319 @@
+//! Memory-mapped tensor receive mode.
+//!
+//! [`MmapTensorReceiver`] writes incoming TENSOR_PAYLOAD chunks directly into
+//! a pre-sized memory-mapped file instead of a heap buffer. This lets a
+//! process receive tensors larger than available RAM (e.g. model shards):
+//! pages are written back to disk by the OS as needed.
+//!
+//! On END_STREAM the mapping is flushed and the file fsynced, then the data
+//! is verified against a CRC32 — either one supplied up front with
+//! [`MmapTensorReceiver::with_expected_checksum`] or one carried by the
+//! END_STREAM frame. The completed [`Tensor`] is backed by a read-only
+//! mapping of the file, so no copy into memory is made.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use quill_tensor::mmap::MmapTensorReceiver;
+//! use quill_tensor::stream::ReceiverEvent;
+//!
+//! let mut receiver = MmapTensorReceiver::new("/data/shard-00001.bin");
+//! for chunk in incoming {
+//!     receiver.feed_bytes(chunk);
+//!     while let ReceiverEvent::Metadata(_) | ReceiverEvent::Data(_) = receiver.poll()? {}
+//! }
+//! let tensor = receiver.take_tensor().expect("stream complete");
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use memmap2::MmapMut;
+
+use crate::compression::decode_payload;
+use crate::frame::{FrameType, TensorFrame, TensorFrameParser};
+use crate::stream::{decode_tensor_meta, ReceiverEvent, TensorChunk, TensorStreamError};
+use crate::tensor::{Tensor, TensorMeta};
+
+/// Receiver that streams tensor data into a memory-mapped file.
+pub struct MmapTensorReceiver {
+    parser: TensorFrameParser,
+    path: PathBuf,
+    expected_checksum: Option<u32>,
+    meta: Option<TensorMeta>,
+    file: Option<File>,
+    mmap: Option<MmapMut>,
+    hasher: crc32fast::Hasher,
+    expected_size: usize,
+    received_size: usize,
+    tensor: Option<Tensor>,
+}
+
+impl MmapTensorReceiver {
+    /// Creates a receiver that writes the tensor to `path`.
+    ///
+    /// The file is created (or truncated) when TENSOR_META arrives.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            parser: TensorFrameParser::new(),
+            path: path.into(),
+            expected_checksum: None,
+            meta: None,
+            file: None,
+            mmap: None,
+            hasher: crc32fast::Hasher::new(),
+            expected_size: 0,
+            received_size: 0,
+            tensor: None,
+        }
+    }
+
+    /// Sets the CRC32 the received data must match.
+    ///
+    /// Takes precedence over a checksum carried by the END_STREAM frame.
+    pub fn with_expected_checksum(mut self, crc32: u32) -> Self {
+        self.expected_checksum = Some(crc32);
+        self
+    }
+
+    /// Returns the path of the backing file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the tensor metadata if received.
+    pub fn meta(&self) -> Option<&TensorMeta> {
+        self.meta.as_ref()
+    }
+
+    /// Returns the number of payload bytes written so far.
+    pub fn received_size(&self) -> usize {
+        self.received_size
+    }
+
+    /// Feeds raw bytes into the receiver.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.parser.feed(data);
+    }
+
+    /// Feeds a Bytes buffer into the receiver.
+    pub fn feed_bytes(&mut self, data: Bytes) {
+        self.parser.feed_bytes(data);
+    }
+
+    /// Processes available frames and returns the next event.
+    pub fn poll(&mut self) -> Result<ReceiverEvent, TensorStreamError> {
+        match self.parser.parse_frame()? {
+            None => Ok(ReceiverEvent::NeedMoreData),
+            Some(frame) => self.handle_frame(frame),
+        }
+    }
+
+    /// Takes the completed, verified tensor.
+    ///
+    /// Returns None until END_STREAM has been processed successfully.
+    pub fn take_tensor(&mut self) -> Option<Tensor> {
+        self.tensor.take()
+    }
+
+    fn handle_frame(&mut self, frame: TensorFrame) -> Result<ReceiverEvent, TensorStreamError> {
+        match frame.frame_type {
+            FrameType::TensorMeta => {
+                let meta = decode_tensor_meta(&frame.payload)?;
+                self.allocate(&meta)?;
+                self.meta = Some(meta.clone());
+                Ok(ReceiverEvent::Metadata(meta))
+            }
+            FrameType::TensorPayload => {
+                if self.meta.is_none() {
+                    return Err(TensorStreamError::MissingMetadata);
+                }
+                let offset = self.received_size;
+                let payload = decode_payload(&frame, self.expected_size.saturating_sub(offset))?;
+                let end = offset + payload.len();
+                if end > self.expected_size {
+                    return Err(TensorStreamError::SizeMismatch {
+                        expected: self.expected_size,
+                        actual: end,
+                    });
+                }
+                if let Some(mmap) = self.mmap.as_mut() {
+                    mmap[offset..end].copy_from_slice(&payload);
+                }
+                self.hasher.update(&payload);
+                self.received_size = end;
+                Ok(ReceiverEvent::Data(TensorChunk::new(offset, payload)))
+            }
+            FrameType::EndStream => {
+                self.finish(frame.end_stream_checksum())?;
+                Ok(ReceiverEvent::End)
+            }
+            FrameType::Cancel => {
+                self.discard();
+                let reason = String::from_utf8_lossy(&frame.payload).into_owned();
+                Ok(ReceiverEvent::Cancelled(reason))
+            }
+            _ => Err(TensorStreamError::UnexpectedFrame {
+                expected: "TENSOR_META, TENSOR_PAYLOAD, END_STREAM, or CANCEL",
+                actual: frame.frame_type.name(),
+            }),
+        }
+    }
+
+    /// Creates the backing file at the full tensor size and maps it.
+    fn allocate(&mut self, meta: &TensorMeta) -> Result<(), TensorStreamError> {
+        let size = meta.storage_byte_size();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.path)?;
+        file.set_len(size as u64)?;
+
+        // Zero-length mappings are rejected by the OS
+        self.mmap = if size > 0 {
+            // SAFETY: the file was just created and sized by us; the mapping is
+            // only accessed through this receiver until it is made read-only.
+            Some(unsafe { MmapMut::map_mut(&file)? })
+        } else {
+            None
+        };
+        self.file = Some(file);
+        self.hasher = crc32fast::Hasher::new();
+        self.expected_size = size;
+        self.received_size = 0;
+        self.tensor = None;
+        Ok(())
+    }
+
+    /// Flushes and fsyncs the file, verifies the data and builds the tensor.
+    fn finish(&mut self, stream_checksum: Option<u32>) -> Result<(), TensorStreamError> {
+        let meta = self.meta.take().ok_or(TensorStreamError::MissingMetadata)?;
+        if self.received_size != self.expected_size {
+            self.discard();
+            return Err(TensorStreamError::SizeMismatch {
+                expected: self.expected_size,
+                actual: self.received_size,
+            });
+        }
+
+        if let Some(mmap) = self.mmap.as_ref() {
+            mmap.flush()?;
+        }
+        if let Some(file) = self.file.take() {
+            file.sync_all()?;
+        }
+
+        let actual = std::mem::take(&mut self.hasher).finalize();
+        if let Some(expected) = self.expected_checksum.or(stream_checksum) {
+            if expected != actual {
+                self.discard();
+                return Err(TensorStreamError::ChecksumMismatch { expected, actual });
+            }
+        }
+
+        let data = match self.mmap.take() {
+            Some(mmap) => Bytes::from_owner(mmap.make_read_only()?),
+            None => Bytes::new(),
+        };
+        self.tensor = Some(Tensor::new(meta, data));
+        Ok(())
+    }
+
+    /// Drops the mapping and removes the partially written file.
+    fn discard(&mut self) {
+        self.mmap = None;
+        self.file = None;
+        self.meta = None;
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dtype::DType;
+    use crate::stream::TensorSender;
+
+    fn sample_tensor() -> Tensor {
+        let meta = TensorMeta::new(vec![64, 32], DType::Float32).with_name("shard");
+        let values: Vec<f32> = (0..64 * 32).map(|i| i as f32 * 0.5).collect();
+        Tensor::from_f32(&meta, &values)
+    }
+
+    fn drain(receiver: &mut MmapTensorReceiver) -> Result<(), TensorStreamError> {
+        loop {
+            match receiver.poll()? {
+                ReceiverEvent::End | ReceiverEvent::NeedMoreData => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_receive_into_mmap() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tensor.bin");
+        let tensor = sample_tensor();
+
+        let mut receiver = MmapTensorReceiver::new(&path);
+        for frame in TensorSender::with_chunk_size(1000).enable_checksum(true).encode_tensor(&tensor)
+        {
+            receiver.feed(&frame.encode());
+        }
+        drain(&mut receiver).unwrap();
+
+        let received = receiver.take_tensor().unwrap();
+        assert_eq!(received.meta.shape, vec![64, 32]);
+        assert_eq!(received.as_f32(), tensor.as_f32());
+        assert_eq!(std::fs::read(&path).unwrap(), &tensor.data[..]);
+    }
+
+    #[test]
+    fn test_checksum_mismatch_removes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tensor.bin");
+        let tensor = sample_tensor();
+
+        let mut receiver = MmapTensorReceiver::new(&path).with_expected_checksum(0x1234_5678);
+        for frame in TensorSender::new().encode_tensor(&tensor) {
+            receiver.feed(&frame.encode());
+        }
+
+        let err = drain(&mut receiver).unwrap_err();
+        assert!(matches!(err, TensorStreamError::ChecksumMismatch { expected: 0x1234_5678, .. }));
+        assert!(receiver.take_tensor().is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_truncated_stream_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tensor.bin");
+        let tensor = sample_tensor();
+
+        let mut frames = TensorSender::with_chunk_size(1024).encode_tensor(&tensor);
+        frames.remove(1);
+
+        let mut receiver = MmapTensorReceiver::new(&path);
+        for frame in frames {
+            receiver.feed(&frame.encode());
+        }
+        assert!(matches!(drain(&mut receiver), Err(TensorStreamError::SizeMismatch { .. })));
+    }
+
+    #[test]
+    fn test_empty_tensor() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = TensorMeta::new(vec![0], DType::Float32);
+
+        let mut receiver = MmapTensorReceiver::new(dir.path().join("empty.bin"));
+        for frame in TensorSender::new().encode_tensor(&Tensor::zeros(meta)) {
+            receiver.feed(&frame.encode());
+        }
+        drain(&mut receiver).unwrap();
+        assert_eq!(receiver.take_tensor().unwrap().byte_size(), 0);
+    }
+}
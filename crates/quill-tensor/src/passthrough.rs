@@ -0,0 +1,415 @@
+//! Streaming passthrough of tensor frames.
+//!
+//! A relay or proxy forwarding tensor streams between connections doesn't
+//! need the tensors themselves. [`TensorPassthrough`] follows the frames of
+//! a stream as its bytes go by and checks them like a receiver would, but
+//! never assembles the tensor or copies payload bytes: each message comes
+//! back as the same [`Bytes`], ready to be sent on.
+//!
+//! Only TENSOR_META payloads and the length prefix of compressed chunks are
+//! looked at. Payload sizes are checked against the metadata, and compressed
+//! chunks against the size they announce; decompressing and checksums are
+//! left to the final receiver. Frames may be split across messages or
+//! several may share one. Tensors interleaved on one stream are checked
+//! separately by tensor ID.
+//!
+//! On a GPU host this keeps relayed tensors off the device entirely, unlike
+//! terminating the stream with a [`GpuTensorReceiver`] and sending it again.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use quill_tensor::TensorPassthrough;
+//!
+//! let mut passthrough = TensorPassthrough::new().with_max_tensor_bytes(1 << 30);
+//! while let Some(message) = upstream.next().await {
+//!     downstream.send(passthrough.forward(message?)?).await?;
+//! }
+//! ```
+//!
+//! [`GpuTensorReceiver`]: crate::GpuTensorReceiver
+
+use bytes::Bytes;
+use std::collections::HashMap;
+
+use crate::compression::{TensorCodec, LENGTH_PREFIX_SIZE};
+use crate::frame::{
+    reserved_flags, FrameType, TensorFrameError, DEFAULT_TENSOR_ID, TENSOR_FRAME_HEADER_SIZE,
+};
+use crate::stream::{decode_tensor_meta, TensorStreamError};
+use crate::tensor::TensorMeta;
+
+/// Largest TENSOR_META payload accepted, as it is buffered to be decoded.
+pub const MAX_META_SIZE: usize = 64 * 1024;
+
+/// Frame whose payload is passing through.
+struct FrameState {
+    frame_type: FrameType,
+    compressed: bool,
+    codec: u8,
+    /// Payload bytes still to come.
+    remaining: usize,
+    /// Leading payload bytes kept for validation.
+    kept: Vec<u8>,
+    keep: usize,
+}
+
+/// Progress of one tensor of the stream.
+#[derive(Default)]
+struct TensorProgress {
+    meta: Option<TensorMeta>,
+    expected_size: usize,
+    received_size: usize,
+}
+
+/// Validates a tensor frame stream while forwarding its bytes unchanged.
+pub struct TensorPassthrough {
+    header: [u8; TENSOR_FRAME_HEADER_SIZE],
+    header_len: usize,
+    frame: Option<FrameState>,
+    max_tensor_bytes: Option<usize>,
+    tensors: HashMap<u16, TensorProgress>,
+    /// Tensor the current frame belongs to.
+    current: u16,
+    forwarded_bytes: u64,
+}
+
+impl TensorPassthrough {
+    /// Creates a passthrough accepting tensors of any size.
+    pub fn new() -> Self {
+        Self {
+            header: [0; TENSOR_FRAME_HEADER_SIZE],
+            header_len: 0,
+            frame: None,
+            max_tensor_bytes: None,
+            tensors: HashMap::new(),
+            current: DEFAULT_TENSOR_ID,
+            forwarded_bytes: 0,
+        }
+    }
+
+    /// Rejects tensors whose storage exceeds `limit` bytes.
+    ///
+    /// Checked on TENSOR_META, before any of the tensor's payload is forwarded.
+    pub fn with_max_tensor_bytes(mut self, limit: usize) -> Self {
+        self.max_tensor_bytes = Some(limit);
+        self
+    }
+
+    /// Returns the metadata of the current tensor, if received.
+    ///
+    /// The current tensor is the one the last frame belonged to.
+    pub fn meta(&self) -> Option<&TensorMeta> {
+        self.progress()?.meta.as_ref()
+    }
+
+    /// Returns the number of the current tensor's data bytes announced so far.
+    ///
+    /// Compressed chunks count with their uncompressed size.
+    pub fn received_bytes(&self) -> usize {
+        self.progress().map_or(0, |tensor| tensor.received_size)
+    }
+
+    /// Returns the current tensor's data size announced by the metadata.
+    pub fn expected_bytes(&self) -> usize {
+        self.progress().map_or(0, |tensor| tensor.expected_size)
+    }
+
+    /// Returns the total number of bytes forwarded.
+    pub fn forwarded_bytes(&self) -> u64 {
+        self.forwarded_bytes
+    }
+
+    /// Returns whether the stream ends on a frame boundary.
+    pub fn at_frame_boundary(&self) -> bool {
+        self.frame.is_none() && self.header_len == 0
+    }
+
+    /// Checks the frames (or parts of frames) in `data` and returns it as is.
+    ///
+    /// An error means `data` must not be forwarded; the stream can't be
+    /// continued after one.
+    pub fn forward(&mut self, data: Bytes) -> Result<Bytes, TensorStreamError> {
+        let mut rest: &[u8] = &data;
+        while !rest.is_empty() {
+            match self.frame.as_mut() {
+                None => {
+                    let take = (TENSOR_FRAME_HEADER_SIZE - self.header_len).min(rest.len());
+                    self.header[self.header_len..self.header_len + take]
+                        .copy_from_slice(&rest[..take]);
+                    self.header_len += take;
+                    rest = &rest[take..];
+                    if self.header_len == TENSOR_FRAME_HEADER_SIZE {
+                        self.header_len = 0;
+                        self.start_frame()?;
+                    }
+                }
+                Some(frame) => {
+                    let take = frame.remaining.min(rest.len());
+                    let wanted = (frame.keep - frame.kept.len()).min(take);
+                    frame.kept.extend_from_slice(&rest[..wanted]);
+                    frame.remaining -= take;
+                    rest = &rest[take..];
+                    if frame.compressed && frame.kept.len() == LENGTH_PREFIX_SIZE && wanted > 0 {
+                        self.check_compressed_chunk()?;
+                    }
+                    if self.frame.as_ref().is_some_and(|frame| frame.remaining == 0) {
+                        self.finish_frame()?;
+                    }
+                }
+            }
+        }
+        self.forwarded_bytes += data.len() as u64;
+        Ok(data)
+    }
+
+    /// Validates a frame header and starts passing its payload.
+    fn start_frame(&mut self) -> Result<(), TensorStreamError> {
+        let header = self.header;
+        let frame_type = FrameType::try_from(header[0])?;
+        let length = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) as usize;
+        let compressed = header[1] & reserved_flags::COMPRESSED != 0;
+        if matches!(frame_type, FrameType::TensorMeta | FrameType::TensorPayload | FrameType::EndStream) {
+            self.current = u16::from_be_bytes([header[3], header[4]]);
+        }
+
+        let keep = match frame_type {
+            FrameType::TensorMeta => {
+                if length > MAX_META_SIZE {
+                    return Err(TensorFrameError::Invalid(format!(
+                        "TENSOR_META of {} bytes exceeds {} bytes",
+                        length, MAX_META_SIZE
+                    ))
+                    .into());
+                }
+                length
+            }
+            FrameType::TensorPayload => {
+                if self.meta().is_none() {
+                    return Err(TensorStreamError::MissingMetadata);
+                }
+                if compressed {
+                    LENGTH_PREFIX_SIZE
+                } else {
+                    self.add_received(length)?;
+                    0
+                }
+            }
+            FrameType::EndStream | FrameType::Cancel | FrameType::TokenBatch => 0,
+            other => {
+                return Err(TensorStreamError::UnexpectedFrame {
+                    expected: "TENSOR_META, TENSOR_PAYLOAD, TOKEN_BATCH, END_STREAM, or CANCEL",
+                    actual: other.name(),
+                })
+            }
+        };
+
+        self.frame = Some(FrameState {
+            frame_type,
+            compressed: frame_type == FrameType::TensorPayload && compressed,
+            codec: header[2],
+            remaining: length,
+            kept: Vec::with_capacity(keep),
+            keep,
+        });
+        if length == 0 {
+            self.finish_frame()?;
+        }
+        Ok(())
+    }
+
+    /// Accounts for a compressed chunk once its length prefix has passed.
+    fn check_compressed_chunk(&mut self) -> Result<(), TensorStreamError> {
+        let frame = self.frame.as_ref().expect("compressed chunk in progress");
+        if TensorCodec::from_id(frame.codec).is_none() {
+            return Err(TensorStreamError::Compression(format!(
+                "unknown codec id: {}",
+                frame.codec
+            )));
+        }
+        let len = u32::from_le_bytes(frame.kept[..LENGTH_PREFIX_SIZE].try_into().unwrap());
+        self.add_received(len as usize)
+    }
+
+    /// Validates a frame whose payload has passed completely.
+    fn finish_frame(&mut self) -> Result<(), TensorStreamError> {
+        let frame = self.frame.take().expect("frame in progress");
+        match frame.frame_type {
+            FrameType::TensorMeta => {
+                let meta = decode_tensor_meta(&frame.kept)?;
+                let size = meta.storage_byte_size();
+                if let Some(limit) = self.max_tensor_bytes.filter(|&limit| size > limit) {
+                    return Err(TensorStreamError::TooLarge { size, limit });
+                }
+                self.tensors.insert(
+                    self.current,
+                    TensorProgress {
+                        meta: Some(meta),
+                        expected_size: size,
+                        received_size: 0,
+                    },
+                );
+            }
+            FrameType::TensorPayload if frame.compressed && frame.kept.len() < frame.keep => {
+                return Err(TensorStreamError::Compression(
+                    "compressed payload too short".to_string(),
+                ));
+            }
+            FrameType::EndStream
+                if self.meta().is_some() && self.received_bytes() != self.expected_bytes() =>
+            {
+                return Err(TensorStreamError::SizeMismatch {
+                    expected: self.expected_bytes(),
+                    actual: self.received_bytes(),
+                });
+            }
+            FrameType::Cancel => self.tensors.clear(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn add_received(&mut self, len: usize) -> Result<(), TensorStreamError> {
+        let tensor = self.tensors.entry(self.current).or_default();
+        let received = tensor.received_size + len;
+        if received > tensor.expected_size {
+            return Err(TensorStreamError::SizeMismatch {
+                expected: tensor.expected_size,
+                actual: received,
+            });
+        }
+        tensor.received_size = received;
+        Ok(())
+    }
+
+    fn progress(&self) -> Option<&TensorProgress> {
+        self.tensors.get(&self.current)
+    }
+}
+
+impl Default for TensorPassthrough {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dtype::DType;
+    use crate::frame::TensorFrame;
+    use crate::stream::{ReceiverEvent, TensorReceiver, TensorSender};
+    use crate::tensor::Tensor;
+    use crate::TensorCodec;
+
+    fn sample_tensor() -> Tensor {
+        let values: Vec<f32> = (0..256).map(|i| (i % 8) as f32).collect();
+        Tensor::from_f32(&TensorMeta::new(vec![16, 16], DType::Float32), &values)
+    }
+
+    fn encoded(sender: &TensorSender, tensor: &Tensor) -> Vec<Bytes> {
+        sender.encode_tensor(tensor).iter().map(|frame| frame.encode()).collect()
+    }
+
+    #[test]
+    fn test_forwards_messages_unchanged() {
+        let tensor = sample_tensor();
+        let mut passthrough = TensorPassthrough::new();
+        let mut receiver = TensorReceiver::new();
+        for message in encoded(&TensorSender::with_chunk_size(100), &tensor) {
+            let forwarded = passthrough.forward(message.clone()).unwrap();
+            // The same buffer, not a copy
+            assert_eq!(forwarded.as_ptr(), message.as_ptr());
+            receiver.feed_bytes(forwarded);
+            while !matches!(receiver.poll().unwrap(), ReceiverEvent::NeedMoreData) {}
+        }
+        assert_eq!(passthrough.meta().unwrap().shape, vec![16, 16]);
+        assert_eq!(passthrough.received_bytes(), tensor.byte_size());
+        assert!(passthrough.at_frame_boundary());
+        assert_eq!(receiver.take_tensor().unwrap().as_f32(), tensor.as_f32());
+    }
+
+    #[test]
+    fn test_frames_split_across_messages() {
+        let tensor = sample_tensor();
+        let sender = TensorSender::with_chunk_size(256).with_compression(TensorCodec::Lz4);
+        let stream: Vec<u8> = encoded(&sender, &tensor).concat();
+
+        // Split at every few bytes, cutting through headers and length prefixes
+        let mut passthrough = TensorPassthrough::new();
+        for piece in stream.chunks(3) {
+            passthrough.forward(Bytes::copy_from_slice(piece)).unwrap();
+        }
+        assert_eq!(passthrough.received_bytes(), tensor.byte_size());
+        assert_eq!(passthrough.forwarded_bytes(), stream.len() as u64);
+        assert!(passthrough.at_frame_boundary());
+    }
+
+    #[test]
+    fn test_interleaved_tensors_checked_separately() {
+        let tensor = sample_tensor();
+        let sender = TensorSender::with_chunk_size(512);
+        let first = sender.encode_tensor_with_id(&tensor, 1);
+        let second = sender.encode_tensor_with_id(&tensor, 2);
+
+        let mut passthrough = TensorPassthrough::new();
+        for (a, b) in first.iter().zip(&second) {
+            passthrough.forward(a.encode()).unwrap();
+            passthrough.forward(b.encode()).unwrap();
+        }
+        assert_eq!(passthrough.received_bytes(), tensor.byte_size());
+
+        // A tensor ID without metadata of its own
+        let orphan = second[1].clone().with_tensor_id(3);
+        assert!(matches!(
+            passthrough.forward(orphan.encode()),
+            Err(TensorStreamError::MissingMetadata)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_streams() {
+        let tensor = sample_tensor();
+        let messages = encoded(&TensorSender::with_chunk_size(512), &tensor);
+
+        // Too large for the configured limit
+        let mut passthrough = TensorPassthrough::new().with_max_tensor_bytes(1000);
+        assert!(matches!(
+            passthrough.forward(messages[0].clone()),
+            Err(TensorStreamError::TooLarge { size: 1024, limit: 1000 })
+        ));
+
+        // Payload before metadata
+        let mut passthrough = TensorPassthrough::new();
+        assert!(matches!(
+            passthrough.forward(messages[1].clone()),
+            Err(TensorStreamError::MissingMetadata)
+        ));
+
+        // More payload than the metadata announced
+        let mut passthrough = TensorPassthrough::new();
+        for message in &messages[..3] {
+            passthrough.forward(message.clone()).unwrap();
+        }
+        assert!(matches!(
+            passthrough.forward(messages[1].clone()),
+            Err(TensorStreamError::SizeMismatch { expected: 1024, actual: 1536 })
+        ));
+
+        // Ended early
+        let mut passthrough = TensorPassthrough::new();
+        passthrough.forward(messages[0].clone()).unwrap();
+        passthrough.forward(messages[1].clone()).unwrap();
+        assert!(matches!(
+            passthrough.forward(TensorFrame::end_stream().encode()),
+            Err(TensorStreamError::SizeMismatch { expected: 1024, actual: 512 })
+        ));
+
+        // Not part of a tensor stream
+        let mut passthrough = TensorPassthrough::new();
+        assert!(matches!(
+            passthrough.forward(TensorFrame::proto_msg(Bytes::from_static(b"x")).encode()),
+            Err(TensorStreamError::UnexpectedFrame { .. })
+        ));
+    }
+}
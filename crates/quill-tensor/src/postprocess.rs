@@ -0,0 +1,616 @@
+//! Client-side post-processing of token streams.
+//!
+//! [`TokenPostProcessor`] applies a pipeline of rules to generated tokens
+//! as they arrive, so every UI does not have to reimplement them on top of
+//! raw [`TokenBatch`]es:
+//!
+//! 1. **Banned substrings and patterns**: a token whose text completes a
+//!    banned substring or regex match (looking back over the text already
+//!    passed) is dropped.
+//! 2. **Stop sequences**: output is truncated right before the first stop
+//!    sequence, which may span several tokens. Tokens that could start a
+//!    stop sequence are held back until it is clear they do not.
+//! 3. **Max tokens**: output ends after a number of tokens.
+//!
+//! Each rule that triggers is reported as a [`TokenRuleEvent`]. A rule that
+//! ends generation also invokes the [`on_cancel`](TokenPostProcessor::on_cancel)
+//! hook, and [`PostProcessedStream`] drops the upstream stream, so the
+//! server stops generating tokens nobody will see.
+//!
+//! A processor tracks one sequence; use one per `sequence_id` when
+//! generating several sequences at once.
+//!
+//! # Example
+//!
+//! ```rust
+//! use quill_tensor::postprocess::TokenPostProcessor;
+//! use quill_tensor::{Token, TokenBatch};
+//!
+//! let mut processor = TokenPostProcessor::new().stop_sequence("\n\n").max_tokens(100);
+//! let batch = TokenBatch::with_tokens(vec![
+//!     Token::with_text(1, "Hello", 0),
+//!     Token::with_text(2, "\n", 1),
+//!     Token::with_text(3, "\nBye", 2),
+//! ]);
+//!
+//! let out = processor.process(batch);
+//! assert_eq!(out.tokens.len(), 1);
+//! assert!(out.is_final);
+//! assert!(processor.is_stopped());
+//! ```
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use regex::Regex;
+
+use crate::token::{Token, TokenBatch};
+
+/// Bytes of passed text kept to match banned substrings and patterns across tokens.
+pub const FILTER_CONTEXT_BYTES: usize = 256;
+
+/// Callback invoked when a rule triggers.
+pub type TokenRuleListener = Arc<dyn Fn(&TokenRuleEvent) + Send + Sync>;
+
+/// Callback invoked to cancel generation upstream.
+pub type CancelHook = Arc<dyn Fn() + Send + Sync>;
+
+/// A rule that triggered while post-processing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenRuleEvent {
+    /// Output was truncated before a stop sequence.
+    StopSequence {
+        /// The stop sequence found.
+        sequence: String,
+        /// Position of the token completing it.
+        position: u32,
+    },
+    /// Output reached the token limit.
+    MaxTokens {
+        /// The limit.
+        limit: usize,
+    },
+    /// A token completing a banned substring was dropped.
+    BannedSubstring {
+        /// The banned substring.
+        substring: String,
+        /// Position of the dropped token.
+        position: u32,
+    },
+    /// A token completing a banned pattern match was dropped.
+    BannedPattern {
+        /// The banned pattern.
+        pattern: String,
+        /// Text the pattern matched.
+        matched: String,
+        /// Position of the dropped token.
+        position: u32,
+    },
+}
+
+impl TokenRuleEvent {
+    /// Returns whether this rule ended generation.
+    pub fn ends_generation(&self) -> bool {
+        matches!(self, Self::StopSequence { .. } | Self::MaxTokens { .. })
+    }
+}
+
+/// Composable pipeline of rules applied to a token stream.
+#[derive(Clone, Default)]
+pub struct TokenPostProcessor {
+    stop_sequences: Vec<String>,
+    banned_substrings: Vec<String>,
+    banned_patterns: Vec<Regex>,
+    max_tokens: Option<usize>,
+    listener: Option<TokenRuleListener>,
+    cancel: Option<CancelHook>,
+    /// Tail of the text passed by the filters.
+    context: String,
+    /// Tokens that may be the start of a stop sequence.
+    held: Vec<Token>,
+    emitted: usize,
+    stopped: bool,
+    finished: bool,
+}
+
+impl TokenPostProcessor {
+    /// Creates a processor without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Truncates output before `sequence`.
+    pub fn stop_sequence(mut self, sequence: impl Into<String>) -> Self {
+        let sequence = sequence.into();
+        if !sequence.is_empty() {
+            self.stop_sequences.push(sequence);
+        }
+        self
+    }
+
+    /// Drops tokens completing `substring`.
+    pub fn ban_substring(mut self, substring: impl Into<String>) -> Self {
+        let substring = substring.into();
+        if !substring.is_empty() {
+            self.banned_substrings.push(substring);
+        }
+        self
+    }
+
+    /// Drops tokens completing a match of the regex `pattern`.
+    ///
+    /// Matches are looked for in the last [`FILTER_CONTEXT_BYTES`] of
+    /// passed text plus the new token.
+    pub fn ban_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.banned_patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Ends output after `limit` tokens.
+    pub fn max_tokens(mut self, limit: usize) -> Self {
+        self.max_tokens = Some(limit);
+        self
+    }
+
+    /// Registers a listener for triggered rules.
+    pub fn on_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&TokenRuleEvent) + Send + Sync + 'static,
+    {
+        self.listener = Some(Arc::new(f));
+        self
+    }
+
+    /// Registers a hook asking the server to stop generating, e.g. by sending CANCEL.
+    ///
+    /// Invoked once, when a stop sequence or the token limit ends output.
+    pub fn on_cancel<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.cancel = Some(Arc::new(f));
+        self
+    }
+
+    /// Returns whether a rule ended generation.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Returns the number of tokens output so far.
+    pub fn emitted_tokens(&self) -> usize {
+        self.emitted
+    }
+
+    /// Applies the rules to a received batch.
+    ///
+    /// The returned batch holds the tokens ready for display and keeps the
+    /// sequence ID. It is final once a rule ended generation or `batch` was
+    /// the final one, in which case held-back tokens are released.
+    pub fn process(&mut self, batch: TokenBatch) -> TokenBatch {
+        let mut out = Vec::new();
+        for token in batch.tokens {
+            if self.finished {
+                break;
+            }
+            self.push(token, &mut out);
+        }
+        if batch.is_final && !self.finished {
+            out.extend(self.finish());
+        }
+
+        let mut processed = TokenBatch::with_tokens(out);
+        processed.sequence_id = batch.sequence_id;
+        processed.is_final = self.finished;
+        processed
+    }
+
+    /// Releases held-back tokens at the end of the stream.
+    pub fn finish(&mut self) -> Vec<Token> {
+        if self.finished {
+            return Vec::new();
+        }
+        self.finished = true;
+        let held = std::mem::take(&mut self.held);
+        let mut out = Vec::new();
+        self.release(held, &mut out);
+        out
+    }
+
+    fn push(&mut self, token: Token, out: &mut Vec<Token>) {
+        let text = token.text.clone().unwrap_or_default();
+        if let Some(event) = self.banned(&text, token.position) {
+            self.emit(&event);
+            return;
+        }
+        self.context.push_str(&text);
+        if self.context.len() > FILTER_CONTEXT_BYTES {
+            let mut start = self.context.len() - FILTER_CONTEXT_BYTES;
+            while !self.context.is_char_boundary(start) {
+                start += 1;
+            }
+            self.context.drain(..start);
+        }
+
+        self.held.push(token);
+        let held_text: String = self.held.iter().filter_map(|token| token.text.as_deref()).collect();
+        let found = self
+            .stop_sequences
+            .iter()
+            .filter_map(|sequence| held_text.find(sequence.as_str()).map(|index| (index, sequence)))
+            .min_by_key(|(index, _)| *index);
+
+        let held = std::mem::take(&mut self.held);
+        match found {
+            Some((index, sequence)) => {
+                let event = TokenRuleEvent::StopSequence {
+                    sequence: sequence.clone(),
+                    position: held.last().map(|token| token.position).unwrap_or_default(),
+                };
+                let kept = truncate_at(held, index);
+                self.release(kept, out);
+                if !self.finished {
+                    self.stop(&event);
+                }
+            }
+            None => {
+                // Hold back the tokens that could start a stop sequence
+                let split = held_text.len() - self.partial_stop_len(&held_text);
+                let mut start = 0;
+                let mut ready = Vec::new();
+                let mut held = held.into_iter().peekable();
+                while let Some(token) = held.next_if(|token| {
+                    start + token.text.as_deref().map_or(0, str::len) <= split
+                }) {
+                    start += token.text.as_deref().map_or(0, str::len);
+                    ready.push(token);
+                }
+                self.held = held.collect();
+                self.release(ready, out);
+            }
+        }
+    }
+
+    /// Outputs `tokens`, enforcing the token limit.
+    fn release(&mut self, tokens: Vec<Token>, out: &mut Vec<Token>) {
+        for token in tokens {
+            if self.max_tokens.is_some_and(|limit| self.emitted >= limit) {
+                break;
+            }
+            out.push(token);
+            self.emitted += 1;
+        }
+        if let Some(limit) = self.max_tokens.filter(|limit| self.emitted >= *limit) {
+            if !self.finished {
+                self.held.clear();
+                self.stop(&TokenRuleEvent::MaxTokens { limit });
+            }
+        }
+    }
+
+    /// The event of a banned substring or pattern completed by `text`, if any.
+    fn banned(&self, text: &str, position: u32) -> Option<TokenRuleEvent> {
+        if text.is_empty() {
+            return None;
+        }
+        let window = format!("{}{}", self.context, text);
+        let passed = self.context.len();
+
+        // Only matches ending in the new token count; earlier ones already passed
+        for substring in &self.banned_substrings {
+            let found = window
+                .match_indices(substring.as_str())
+                .any(|(index, _)| index + substring.len() > passed);
+            if found {
+                return Some(TokenRuleEvent::BannedSubstring {
+                    substring: substring.clone(),
+                    position,
+                });
+            }
+        }
+        for pattern in &self.banned_patterns {
+            if let Some(found) = pattern.find_iter(&window).find(|found| found.end() > passed) {
+                return Some(TokenRuleEvent::BannedPattern {
+                    pattern: pattern.as_str().to_string(),
+                    matched: found.as_str().to_string(),
+                    position,
+                });
+            }
+        }
+        None
+    }
+
+    /// Length of the longest suffix of `text` that starts a stop sequence.
+    fn partial_stop_len(&self, text: &str) -> usize {
+        self.stop_sequences
+            .iter()
+            .flat_map(|sequence| {
+                (1..sequence.len())
+                    .rev()
+                    .filter(|len| sequence.is_char_boundary(*len))
+                    .find(|len| text.ends_with(&sequence[..*len]))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn stop(&mut self, event: &TokenRuleEvent) {
+        self.stopped = true;
+        self.finished = true;
+        self.emit(event);
+        if let Some(cancel) = &self.cancel {
+            cancel();
+        }
+    }
+
+    fn emit(&self, event: &TokenRuleEvent) {
+        tracing::debug!("Token rule triggered: {:?}", event);
+        if let Some(listener) = &self.listener {
+            listener(event);
+        }
+    }
+}
+
+impl fmt::Debug for TokenPostProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenPostProcessor")
+            .field("stop_sequences", &self.stop_sequences)
+            .field("banned_substrings", &self.banned_substrings)
+            .field("banned_patterns", &self.banned_patterns)
+            .field("max_tokens", &self.max_tokens)
+            .field("emitted", &self.emitted)
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
+/// Keeps the text of `tokens` before byte `index` of their concatenated text.
+fn truncate_at(tokens: Vec<Token>, index: usize) -> Vec<Token> {
+    let mut start = 0;
+    let mut kept = Vec::new();
+    for mut token in tokens {
+        let len = token.text.as_deref().map_or(0, str::len);
+        if start + len <= index {
+            start += len;
+            kept.push(token);
+            continue;
+        }
+        if start < index {
+            if let Some(text) = token.text.as_mut() {
+                text.truncate(index - start);
+            }
+            kept.push(token);
+        }
+        break;
+    }
+    kept
+}
+
+/// Token stream with a [`TokenPostProcessor`] applied.
+///
+/// Ends after the final batch. When a rule ends generation, the upstream
+/// stream is dropped right away, which cancels the underlying call.
+pub struct PostProcessedStream<S> {
+    inner: Option<S>,
+    processor: TokenPostProcessor,
+}
+
+impl<S> PostProcessedStream<S> {
+    /// Applies `processor` to the batches of `inner`.
+    pub fn new(inner: S, processor: TokenPostProcessor) -> Self {
+        Self {
+            inner: Some(inner),
+            processor,
+        }
+    }
+
+    /// Returns the processor, e.g. to check whether a rule stopped output.
+    pub fn processor(&self) -> &TokenPostProcessor {
+        &self.processor
+    }
+}
+
+impl<S, E> Stream for PostProcessedStream<S>
+where
+    S: Stream<Item = Result<TokenBatch, E>> + Unpin,
+{
+    type Item = Result<TokenBatch, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let Some(inner) = this.inner.as_mut() else {
+                return Poll::Ready(None);
+            };
+            let batch = match Pin::new(inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(batch))) => this.processor.process(batch),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => {
+                    this.inner = None;
+                    if this.processor.finished {
+                        return Poll::Ready(None);
+                    }
+                    TokenBatch::final_batch(this.processor.finish())
+                }
+                Poll::Pending => return Poll::Pending,
+            };
+            if batch.is_final {
+                this.inner = None;
+            } else if batch.is_empty() {
+                continue;
+            }
+            return Poll::Ready(Some(Ok(batch)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    fn tokens(texts: &[&str]) -> Vec<Token> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Token::with_text(i as u32, *text, i as u32))
+            .collect()
+    }
+
+    fn text(tokens: &[Token]) -> String {
+        tokens.iter().filter_map(|token| token.text.as_deref()).collect()
+    }
+
+    /// Feeds one token per batch, returning the output text
+    fn run(processor: &mut TokenPostProcessor, texts: &[&str]) -> String {
+        let mut out = Vec::new();
+        for token in tokens(texts) {
+            out.extend(processor.process(TokenBatch::with_tokens(vec![token])).tokens);
+        }
+        out.extend(processor.finish());
+        text(&out)
+    }
+
+    #[test]
+    fn test_stop_sequence_across_tokens() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let cancels = Arc::new(AtomicUsize::new(0));
+        let cancelled = Arc::clone(&cancels);
+        let mut processor = TokenPostProcessor::new()
+            .stop_sequence("</answer>")
+            .on_event(move |event| recorded.lock().unwrap().push(event.clone()))
+            .on_cancel(move || {
+                cancelled.fetch_add(1, Ordering::SeqCst);
+            });
+
+        let mut out = Vec::new();
+        for (i, token) in tokens(&["The ", "answer", " is 4", "</", "ans", "wer> ignored"]).into_iter().enumerate() {
+            let batch = processor.process(TokenBatch::with_tokens(vec![token]));
+            // "</" and "ans" are held back until the stop sequence is complete
+            if i == 3 || i == 4 {
+                assert!(batch.is_empty());
+            }
+            out.extend(batch.tokens);
+        }
+
+        assert_eq!(text(&out), "The answer is 4");
+        assert!(processor.is_stopped());
+        assert_eq!(cancels.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            events.lock().unwrap()[..],
+            [TokenRuleEvent::StopSequence {
+                sequence: "</answer>".to_string(),
+                position: 5
+            }]
+        );
+    }
+
+    #[test]
+    fn test_false_start_of_stop_sequence_is_released() {
+        let mut processor = TokenPostProcessor::new().stop_sequence("STOP");
+        assert_eq!(run(&mut processor, &["a ST", "AR", " b ST"]), "a STAR b ST");
+        assert!(!processor.is_stopped());
+    }
+
+    #[test]
+    fn test_stop_sequence_inside_token() {
+        let mut processor = TokenPostProcessor::new().stop_sequence("\n\n");
+        assert_eq!(run(&mut processor, &["one", " two\n\nthree", "four"]), "one two");
+    }
+
+    #[test]
+    fn test_max_tokens() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let mut processor = TokenPostProcessor::new()
+            .max_tokens(3)
+            .on_event(move |event| recorded.lock().unwrap().push(event.clone()));
+
+        let batch = processor.process(TokenBatch::with_tokens(tokens(&["a", "b", "c", "d", "e"])));
+        assert_eq!(text(&batch.tokens), "abc");
+        assert!(batch.is_final);
+        assert_eq!(events.lock().unwrap()[..], [TokenRuleEvent::MaxTokens { limit: 3 }]);
+        assert!(processor.process(TokenBatch::with_tokens(tokens(&["f"]))).is_empty());
+    }
+
+    #[test]
+    fn test_banned_substrings_and_patterns() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let mut processor = TokenPostProcessor::new()
+            .ban_substring("darn")
+            .ban_pattern(r"\d{3}-\d{4}")
+            .unwrap()
+            .on_event(move |event| recorded.lock().unwrap().push(event.clone()));
+
+        let out = run(&mut processor, &["oh ", "da", "rn", " call 555", "-1234", " now"]);
+        assert_eq!(out, "oh da call 555 now");
+        assert!(!processor.is_stopped());
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[0],
+            TokenRuleEvent::BannedSubstring {
+                substring: "darn".to_string(),
+                position: 2
+            }
+        );
+        assert!(matches!(&events[1], TokenRuleEvent::BannedPattern { matched, position: 4, .. } if matched == "555-1234"));
+        assert!(events.iter().all(|event| !event.ends_generation()));
+    }
+
+    #[test]
+    fn test_final_batch_releases_held_tokens() {
+        let mut processor = TokenPostProcessor::new().stop_sequence("###");
+        let batch = processor.process(TokenBatch::final_batch(tokens(&["done", "#"])).with_sequence_id(7));
+        assert_eq!(text(&batch.tokens), "done#");
+        assert_eq!(batch.sequence_id, Some(7));
+        assert!(batch.is_final);
+    }
+
+    #[tokio::test]
+    async fn test_stream_drops_upstream_when_stopped() {
+        use futures::StreamExt;
+
+        struct Upstream {
+            batches: std::vec::IntoIter<TokenBatch>,
+            dropped: Arc<AtomicUsize>,
+        }
+        impl Stream for Upstream {
+            type Item = Result<TokenBatch, String>;
+            fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                Poll::Ready(self.batches.next().map(Ok))
+            }
+        }
+        impl Drop for Upstream {
+            fn drop(&mut self) {
+                self.dropped.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let upstream = Upstream {
+            batches: vec![
+                TokenBatch::with_tokens(tokens(&["a", "b"])),
+                TokenBatch::with_tokens(tokens(&["c"])),
+                TokenBatch::with_tokens(tokens(&["d"])),
+            ]
+            .into_iter(),
+            dropped: Arc::clone(&dropped),
+        };
+        let mut stream = PostProcessedStream::new(upstream, TokenPostProcessor::new().max_tokens(3));
+
+        assert_eq!(text(&stream.next().await.unwrap().unwrap().tokens), "ab");
+        let last = stream.next().await.unwrap().unwrap();
+        assert_eq!(text(&last.tokens), "c");
+        assert!(last.is_final);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        assert!(stream.next().await.is_none());
+        assert!(stream.processor().is_stopped());
+    }
+}
@@ -0,0 +1,373 @@
+//! Bounded reordering buffer for multi-stream tensor reassembly.
+//!
+//! When a tensor is transferred over several streams in parallel, its
+//! chunks arrive out of order. [`ReorderBuffer`] accepts chunks at any
+//! offset and hands them back strictly in order, so they can be written to
+//! a [`TensorBuffer`](crate::TensorBuffer), a file or a hasher as if they
+//! had arrived on a single stream.
+//!
+//! Chunks waiting for a gap to be filled are held in memory up to
+//! [`ReorderConfig::max_buffered_bytes`]. Past that bound, chunks are
+//! spilled to a temporary file in [`ReorderConfig::spill_dir`] and read back
+//! when their turn comes; without a spill directory they are rejected with
+//! [`TensorStreamError::ReorderBufferFull`] so the sender can be throttled.
+//! The chunk at the read position is always accepted, since it can be
+//! taken right away.
+//!
+//! # Example
+//!
+//! ```rust
+//! use bytes::Bytes;
+//! use quill_tensor::reorder::{ReorderBuffer, ReorderConfig};
+//! use quill_tensor::TensorChunk;
+//!
+//! let mut buffer = ReorderBuffer::new(6, ReorderConfig::new(1024));
+//! buffer.insert(TensorChunk::new(3, Bytes::from_static(b"def"))).unwrap();
+//! assert!(buffer.pop().unwrap().is_none());
+//!
+//! buffer.insert(TensorChunk::new(0, Bytes::from_static(b"abc"))).unwrap();
+//! assert_eq!(buffer.pop().unwrap().unwrap().offset, 0);
+//! assert_eq!(buffer.pop().unwrap().unwrap().offset, 3);
+//! assert!(buffer.is_complete());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bytes::Bytes;
+
+use crate::stream::{TensorChunk, TensorStreamError};
+
+/// Default bytes held in memory while waiting for missing chunks.
+pub const DEFAULT_MAX_BUFFERED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Distinguishes spill files of one process.
+static SPILL_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// Configuration of a [`ReorderBuffer`].
+#[derive(Debug, Clone)]
+pub struct ReorderConfig {
+    /// Bytes held in memory while waiting for missing chunks.
+    pub max_buffered_bytes: usize,
+    /// Directory for chunks beyond the memory bound, or None to reject them.
+    pub spill_dir: Option<PathBuf>,
+}
+
+impl Default for ReorderConfig {
+    fn default() -> Self {
+        Self {
+            max_buffered_bytes: DEFAULT_MAX_BUFFERED_BYTES,
+            spill_dir: None,
+        }
+    }
+}
+
+impl ReorderConfig {
+    /// Creates a configuration holding at most `max_buffered_bytes` in memory.
+    pub fn new(max_buffered_bytes: usize) -> Self {
+        Self {
+            max_buffered_bytes,
+            spill_dir: None,
+        }
+    }
+
+    /// Spills chunks beyond the memory bound to a file in `dir`.
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = Some(dir.into());
+        self
+    }
+}
+
+/// Buffer usage of a [`ReorderBuffer`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReorderStats {
+    /// Bytes currently held in memory.
+    pub buffered_bytes: usize,
+    /// Largest number of bytes held in memory at once.
+    pub peak_buffered_bytes: usize,
+    /// Bytes currently spilled to disk.
+    pub spilled_bytes: usize,
+    /// Chunks spilled to disk so far.
+    pub spilled_chunks: u64,
+}
+
+/// A chunk waiting for the chunks before it.
+enum Pending {
+    Memory(Bytes),
+    /// Spilled to the same offset of the spill file.
+    Spilled(usize),
+}
+
+impl Pending {
+    fn len(&self) -> usize {
+        match self {
+            Pending::Memory(data) => data.len(),
+            Pending::Spilled(len) => *len,
+        }
+    }
+}
+
+/// Temporary file holding spilled chunks, removed on drop.
+struct SpillFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Reorders tensor chunks arriving out of order into offset order.
+pub struct ReorderBuffer {
+    config: ReorderConfig,
+    total_size: usize,
+    next_offset: usize,
+    pending: BTreeMap<usize, Pending>,
+    spill: Option<SpillFile>,
+    stats: ReorderStats,
+}
+
+impl ReorderBuffer {
+    /// Creates a buffer for a tensor of `total_size` bytes.
+    pub fn new(total_size: usize, config: ReorderConfig) -> Self {
+        Self {
+            config,
+            total_size,
+            next_offset: 0,
+            pending: BTreeMap::new(),
+            spill: None,
+            stats: ReorderStats::default(),
+        }
+    }
+
+    /// Returns the offset of the next chunk [`pop`](Self::pop) will return.
+    pub fn next_offset(&self) -> usize {
+        self.next_offset
+    }
+
+    /// Returns whether every byte has been taken in order.
+    pub fn is_complete(&self) -> bool {
+        self.next_offset == self.total_size
+    }
+
+    /// Returns current and peak buffer usage.
+    pub fn stats(&self) -> ReorderStats {
+        self.stats
+    }
+
+    /// Adds a chunk received at any offset.
+    ///
+    /// Fails if the chunk lies outside the tensor, overlaps data already
+    /// received, or does not fit in memory and spilling is disabled.
+    pub fn insert(&mut self, chunk: TensorChunk) -> Result<(), TensorStreamError> {
+        let end = chunk.offset + chunk.len();
+        if end > self.total_size {
+            return Err(TensorStreamError::SizeMismatch {
+                expected: self.total_size,
+                actual: end,
+            });
+        }
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        if self.overlaps(chunk.offset, end) {
+            return Err(TensorStreamError::OverlappingChunk {
+                offset: chunk.offset,
+                len: chunk.len(),
+            });
+        }
+
+        let fits = self.stats.buffered_bytes + chunk.len() <= self.config.max_buffered_bytes;
+        let pending = if fits || chunk.offset == self.next_offset {
+            self.stats.buffered_bytes += chunk.len();
+            self.stats.peak_buffered_bytes = self.stats.peak_buffered_bytes.max(self.stats.buffered_bytes);
+            Pending::Memory(chunk.data)
+        } else if self.config.spill_dir.is_some() {
+            self.spill(chunk.offset, &chunk.data)?;
+            Pending::Spilled(chunk.len())
+        } else {
+            return Err(TensorStreamError::ReorderBufferFull {
+                limit: self.config.max_buffered_bytes,
+            });
+        };
+        self.pending.insert(chunk.offset, pending);
+        Ok(())
+    }
+
+    /// Takes the chunk at the read position, if it has arrived.
+    ///
+    /// Spilled chunks are read back from disk.
+    pub fn pop(&mut self) -> Result<Option<TensorChunk>, TensorStreamError> {
+        let offset = self.next_offset;
+        let Some(pending) = self.pending.remove(&offset) else {
+            return Ok(None);
+        };
+        let data = match pending {
+            Pending::Memory(data) => {
+                self.stats.buffered_bytes -= data.len();
+                data
+            }
+            Pending::Spilled(len) => {
+                self.stats.spilled_bytes -= len;
+                self.read_spilled(offset, len)?
+            }
+        };
+        self.next_offset += data.len();
+        if self.stats.spilled_bytes == 0 {
+            // Nothing left on disk
+            self.spill = None;
+        }
+        Ok(Some(TensorChunk::new(offset, data)))
+    }
+
+    /// Whether `[offset, end)` overlaps data taken or waiting.
+    fn overlaps(&self, offset: usize, end: usize) -> bool {
+        if offset < self.next_offset {
+            return true;
+        }
+        let before = self
+            .pending
+            .range(..=offset)
+            .next_back()
+            .is_some_and(|(start, pending)| start + pending.len() > offset);
+        let after = self.pending.range(offset..end).next().is_some();
+        before || after
+    }
+
+    fn spill(&mut self, offset: usize, data: &[u8]) -> Result<(), TensorStreamError> {
+        let spill = match &mut self.spill {
+            Some(spill) => spill,
+            None => {
+                let dir = self.config.spill_dir.as_ref().expect("spilling is enabled");
+                let path = dir.join(format!(
+                    "quill-reorder-{}-{}.spill",
+                    std::process::id(),
+                    SPILL_FILES.fetch_add(1, Ordering::Relaxed)
+                ));
+                let file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create_new(true)
+                    .open(&path)?;
+                self.spill.insert(SpillFile { path, file })
+            }
+        };
+        // Chunks keep their tensor offset, leaving the file sparse
+        spill.file.seek(SeekFrom::Start(offset as u64))?;
+        spill.file.write_all(data)?;
+        self.stats.spilled_bytes += data.len();
+        self.stats.spilled_chunks += 1;
+        Ok(())
+    }
+
+    fn read_spilled(&mut self, offset: usize, len: usize) -> Result<Bytes, TensorStreamError> {
+        let spill = self
+            .spill
+            .as_mut()
+            .ok_or_else(|| TensorStreamError::Internal("spill file missing".to_string()))?;
+        let mut data = vec![0u8; len];
+        spill.file.seek(SeekFrom::Start(offset as u64))?;
+        spill.file.read_exact(&mut data)?;
+        Ok(Bytes::from(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(offset: usize, len: usize) -> TensorChunk {
+        let data: Vec<u8> = (offset..offset + len).map(|i| i as u8).collect();
+        TensorChunk::new(offset, Bytes::from(data))
+    }
+
+    fn drain(buffer: &mut ReorderBuffer) -> Vec<u8> {
+        let mut out = Vec::new();
+        while let Some(chunk) = buffer.pop().unwrap() {
+            out.extend_from_slice(&chunk.data);
+        }
+        out
+    }
+
+    fn expected(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn test_reorders_interleaved_streams() {
+        // Four streams sending every fourth chunk, received stream by stream
+        let mut buffer = ReorderBuffer::new(1600, ReorderConfig::default());
+        let mut out = Vec::new();
+        for stream in (0..4).rev() {
+            for i in (stream..16).step_by(4) {
+                buffer.insert(chunk(i * 100, 100)).unwrap();
+                out.extend(drain(&mut buffer));
+            }
+        }
+
+        assert!(buffer.is_complete());
+        assert_eq!(out, expected(1600));
+        assert_eq!(buffer.stats().buffered_bytes, 0);
+        assert_eq!(buffer.stats().peak_buffered_bytes, 1300);
+    }
+
+    #[test]
+    fn test_rejects_when_full_without_spill() {
+        let mut buffer = ReorderBuffer::new(400, ReorderConfig::new(150));
+        buffer.insert(chunk(100, 100)).unwrap();
+        let err = buffer.insert(chunk(200, 100)).unwrap_err();
+        assert!(matches!(err, TensorStreamError::ReorderBufferFull { limit: 150 }));
+
+        // The chunk at the read position is always accepted
+        buffer.insert(chunk(0, 100)).unwrap();
+        assert_eq!(drain(&mut buffer), expected(200));
+        buffer.insert(chunk(200, 100)).unwrap();
+    }
+
+    #[test]
+    fn test_spills_beyond_memory_bound() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut buffer = ReorderBuffer::new(1000, ReorderConfig::new(250).with_spill_dir(dir.path()));
+        for i in (1..10).rev() {
+            buffer.insert(chunk(i * 100, 100)).unwrap();
+        }
+
+        let stats = buffer.stats();
+        assert_eq!(stats.buffered_bytes, 200);
+        assert_eq!(stats.spilled_bytes, 700);
+        assert_eq!(stats.spilled_chunks, 7);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        buffer.insert(chunk(0, 100)).unwrap();
+        assert_eq!(drain(&mut buffer), expected(1000));
+        assert!(buffer.is_complete());
+        assert_eq!(buffer.stats().spilled_bytes, 0);
+        // The spill file is removed once nothing is left on disk
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_rejects_invalid_chunks() {
+        let mut buffer = ReorderBuffer::new(300, ReorderConfig::default());
+        buffer.insert(chunk(100, 100)).unwrap();
+
+        for (offset, len) in [(150, 100), (50, 100), (100, 10)] {
+            let err = buffer.insert(chunk(offset, len)).unwrap_err();
+            assert!(matches!(err, TensorStreamError::OverlappingChunk { .. }), "{}+{}", offset, len);
+        }
+        assert!(matches!(
+            buffer.insert(chunk(250, 100)),
+            Err(TensorStreamError::SizeMismatch { expected: 300, actual: 350 })
+        ));
+
+        buffer.insert(chunk(0, 100)).unwrap();
+        drain(&mut buffer);
+        // Data already taken counts as received
+        assert!(matches!(buffer.insert(chunk(0, 10)), Err(TensorStreamError::OverlappingChunk { .. })));
+    }
+}
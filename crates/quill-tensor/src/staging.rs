@@ -0,0 +1,402 @@
+//! Chunk-wise upload of received tensors to GPU memory.
+//!
+//! By default [`GpuTensorReceiver`] collects the whole tensor on the host and
+//! copies it to the device once END_STREAM arrives, so host memory peaks at
+//! the tensor's size and the upload only starts after the last byte was
+//! received. With [`GpuTensorReceiver::with_staging`], payload chunks are
+//! gathered in a few fixed-size staging buffers instead. Each buffer is
+//! uploaded as soon as it fills, asynchronously on its own CUDA stream, while
+//! the next one takes in network data:
+//!
+//! ```text
+//! network ─▶ [buffer 0] ─H2D (stream 0)─┐
+//!            [buffer 1] ─H2D (stream 1)─┼─▶ CudaBuffer
+//!            [buffer 0] ─H2D (stream 0)─┘   (chunk by chunk)
+//! ```
+//!
+//! A buffer is only refilled once its previous upload completed, so host
+//! memory stays at `buffers * chunk_size` whatever the tensor's size. With
+//! the `cuda` feature the staging buffers are page-locked, which lets the
+//! copies run as DMA transfers. Without a usable GPU the chunks are copied
+//! into host memory instead, like the receiver's regular CPU fallback.
+//!
+//! [`GpuTensorReceiver`]: crate::GpuTensorReceiver
+//! [`GpuTensorReceiver::with_staging`]: crate::GpuTensorReceiver::with_staging
+
+use bytes::BytesMut;
+use tracing::warn;
+
+use crate::buffer::{GpuResult, TensorBuffer};
+use crate::tensor::Device;
+
+#[cfg(feature = "cuda")]
+use crate::buffer::{CudaBuffer, GpuError};
+#[cfg(feature = "cuda")]
+use cudarc::driver::{result, sys, CudaDevice, CudaStream, DevicePtrMut};
+#[cfg(feature = "cuda")]
+use std::sync::Arc;
+
+/// Default size of each staging buffer (4 MB).
+pub const DEFAULT_STAGING_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Default number of staging buffers (double buffering).
+pub const DEFAULT_STAGING_BUFFERS: usize = 2;
+
+/// Configuration of chunk-wise uploads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StagingConfig {
+    /// Size of each staging buffer in bytes.
+    pub chunk_size: usize,
+    /// Number of staging buffers; uploads of all but one can be in flight.
+    pub buffers: usize,
+}
+
+impl StagingConfig {
+    /// Sets the size of each staging buffer.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Sets the number of staging buffers (at least 2).
+    pub fn with_buffers(mut self, buffers: usize) -> Self {
+        self.buffers = buffers.max(2);
+        self
+    }
+
+    /// Returns the host memory used for staging.
+    pub fn staging_bytes(&self) -> usize {
+        self.chunk_size * self.buffers
+    }
+}
+
+impl Default for StagingConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_STAGING_CHUNK_SIZE,
+            buffers: DEFAULT_STAGING_BUFFERS,
+        }
+    }
+}
+
+/// Host memory a chunk is gathered in before its upload.
+enum HostMemory {
+    Pageable(Box<[u8]>),
+    #[cfg(feature = "cuda")]
+    Pinned(PinnedMemory),
+}
+
+impl HostMemory {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            HostMemory::Pageable(data) => data,
+            #[cfg(feature = "cuda")]
+            HostMemory::Pinned(memory) => memory.as_slice(),
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        match self {
+            HostMemory::Pageable(data) => data,
+            #[cfg(feature = "cuda")]
+            HostMemory::Pinned(memory) => memory.as_mut_slice(),
+        }
+    }
+}
+
+/// Page-locked host memory, freed on drop.
+#[cfg(feature = "cuda")]
+struct PinnedMemory {
+    ptr: *mut u8,
+    len: usize,
+}
+
+// The memory is owned exclusively and only accessed through `&`/`&mut self`.
+#[cfg(feature = "cuda")]
+unsafe impl Send for PinnedMemory {}
+
+#[cfg(feature = "cuda")]
+impl PinnedMemory {
+    fn allocate(device: &CudaDevice, len: usize) -> GpuResult<Self> {
+        device.bind_to_thread().map_err(|e| {
+            GpuError::DriverNotAvailable(format!("Failed to bind device: {}", e))
+        })?;
+        let mut ptr = std::ptr::null_mut();
+        unsafe { sys::lib().cuMemAllocHost_v2(&mut ptr, len) }
+            .result()
+            .map_err(|e| {
+                GpuError::AllocationFailed(format!(
+                    "Failed to allocate {} bytes of pinned memory: {}",
+                    len, e
+                ))
+            })?;
+        Ok(Self {
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+#[cfg(feature = "cuda")]
+impl Drop for PinnedMemory {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = sys::lib().cuMemFreeHost(self.ptr.cast());
+        }
+    }
+}
+
+/// Stream a staging buffer is uploaded on.
+#[cfg(feature = "cuda")]
+struct UploadStream(CudaStream);
+
+// Streams are only used by the upload owning them, one call at a time.
+#[cfg(feature = "cuda")]
+unsafe impl Send for UploadStream {}
+
+/// A staging buffer and how much of it is filled.
+struct Slot {
+    memory: HostMemory,
+    filled: usize,
+}
+
+/// Where staged chunks are uploaded to.
+enum Target {
+    /// Host memory, when no GPU is usable.
+    Host(BytesMut),
+    /// Device memory, with one stream per staging buffer.
+    #[cfg(feature = "cuda")]
+    Cuda {
+        device: Arc<CudaDevice>,
+        buffer: CudaBuffer,
+        streams: Vec<UploadStream>,
+    },
+}
+
+impl Target {
+    /// Starts copying the chunk staged in `slot` to `offset`.
+    ///
+    /// The chunk must not change until [`wait`](Self::wait) returned for
+    /// the same slot.
+    #[cfg_attr(not(feature = "cuda"), allow(unused_variables))]
+    fn upload(&mut self, slot: usize, offset: usize, chunk: &[u8]) -> GpuResult<()> {
+        match self {
+            Target::Host(data) => {
+                data[offset..offset + chunk.len()].copy_from_slice(chunk);
+                Ok(())
+            }
+            #[cfg(feature = "cuda")]
+            Target::Cuda {
+                device,
+                buffer,
+                streams,
+            } => {
+                device.bind_to_thread().map_err(|e| {
+                    GpuError::DriverNotAvailable(format!("Failed to bind device: {}", e))
+                })?;
+                let mut view = buffer.storage_mut().slice_mut(offset..offset + chunk.len());
+                unsafe { result::memcpy_htod_async(*view.device_ptr_mut(), chunk, streams[slot].0.stream) }
+                    .map_err(|e| {
+                        GpuError::TransferFailed(format!("Host-to-device copy failed: {}", e))
+                    })
+            }
+        }
+    }
+
+    /// Waits for the upload from `slot` to complete.
+    #[cfg_attr(not(feature = "cuda"), allow(unused_variables))]
+    fn wait(&mut self, slot: usize) -> GpuResult<()> {
+        match self {
+            Target::Host(_) => Ok(()),
+            #[cfg(feature = "cuda")]
+            Target::Cuda { streams, .. } => unsafe { result::stream::synchronize(streams[slot].0.stream) }
+                .map_err(|e| GpuError::SyncFailed(format!("Stream synchronization failed: {}", e))),
+        }
+    }
+
+    fn into_buffer(self) -> TensorBuffer {
+        match self {
+            Target::Host(data) => TensorBuffer::cpu(data.freeze()),
+            #[cfg(feature = "cuda")]
+            Target::Cuda { buffer, .. } => TensorBuffer::Cuda(buffer),
+        }
+    }
+}
+
+/// Upload of one tensor through a ring of staging buffers.
+pub(crate) struct StagedUpload {
+    slots: Vec<Slot>,
+    /// Slot being filled.
+    current: usize,
+    /// Bytes handed to the target so far.
+    uploaded: usize,
+    target: Target,
+}
+
+impl StagedUpload {
+    /// Allocates the target buffer and staging buffers for `size` bytes.
+    ///
+    /// CUDA tensors fall back to host memory if the GPU isn't usable.
+    pub(crate) fn new(
+        config: StagingConfig,
+        device: Device,
+        size: usize,
+        device_id: usize,
+    ) -> GpuResult<Self> {
+        let chunk_size = config.chunk_size.min(size).max(1);
+        let buffers = config.buffers.max(2);
+
+        #[cfg(feature = "cuda")]
+        if device == Device::Cuda {
+            match Self::cuda(chunk_size, buffers, size, device_id) {
+                Ok(upload) => return Ok(upload),
+                Err(e) => warn!("Staged GPU upload unavailable ({}), receiving into CPU memory", e),
+            }
+        }
+        #[cfg(not(feature = "cuda"))]
+        if device == Device::Cuda {
+            let _ = device_id;
+            warn!("CUDA feature not compiled, receiving {} bytes into CPU memory", size);
+        }
+
+        let slots = (0..buffers)
+            .map(|_| Slot {
+                memory: HostMemory::Pageable(vec![0; chunk_size].into_boxed_slice()),
+                filled: 0,
+            })
+            .collect();
+        let mut data = BytesMut::with_capacity(size);
+        data.resize(size, 0);
+        Ok(Self::with_target(slots, Target::Host(data)))
+    }
+
+    #[cfg(feature = "cuda")]
+    fn cuda(chunk_size: usize, buffers: usize, size: usize, device_id: usize) -> GpuResult<Self> {
+        let device = CudaDevice::new(device_id).map_err(|e| {
+            GpuError::DriverNotAvailable(format!("Failed to open device {}: {}", device_id, e))
+        })?;
+        let buffer = CudaBuffer::allocate(size, device_id)?;
+        let mut slots = Vec::with_capacity(buffers);
+        let mut streams = Vec::with_capacity(buffers);
+        for _ in 0..buffers {
+            slots.push(Slot {
+                memory: HostMemory::Pinned(PinnedMemory::allocate(&device, chunk_size)?),
+                filled: 0,
+            });
+            let stream = device.fork_default_stream().map_err(|e| {
+                GpuError::DriverNotAvailable(format!("Failed to create stream: {}", e))
+            })?;
+            streams.push(UploadStream(stream));
+        }
+        Ok(Self::with_target(
+            slots,
+            Target::Cuda {
+                device,
+                buffer,
+                streams,
+            },
+        ))
+    }
+
+    fn with_target(slots: Vec<Slot>, target: Target) -> Self {
+        Self {
+            slots,
+            current: 0,
+            uploaded: 0,
+            target,
+        }
+    }
+
+    /// Stages `data`, uploading every staging buffer it fills.
+    pub(crate) fn write(&mut self, mut data: &[u8]) -> GpuResult<()> {
+        while !data.is_empty() {
+            let slot = &mut self.slots[self.current];
+            let memory = slot.memory.as_mut_slice();
+            let take = (memory.len() - slot.filled).min(data.len());
+            memory[slot.filled..slot.filled + take].copy_from_slice(&data[..take]);
+            slot.filled += take;
+            data = &data[take..];
+            if slot.filled == memory.len() {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Uploads the current staging buffer and moves on to the next one.
+    fn flush(&mut self) -> GpuResult<()> {
+        let slot = &mut self.slots[self.current];
+        if slot.filled == 0 {
+            return Ok(());
+        }
+        let chunk = &slot.memory.as_slice()[..slot.filled];
+        self.target.upload(self.current, self.uploaded, chunk)?;
+        self.uploaded += slot.filled;
+        slot.filled = 0;
+
+        // The next buffer may still be uploading from its last round
+        self.current = (self.current + 1) % self.slots.len();
+        self.target.wait(self.current)
+    }
+
+    /// Uploads what is left and returns the buffer once all copies completed.
+    pub(crate) fn finish(mut self) -> GpuResult<TensorBuffer> {
+        self.flush()?;
+        for slot in 0..self.slots.len() {
+            self.target.wait(slot)?;
+        }
+        let target = std::mem::replace(&mut self.target, Target::Host(BytesMut::new()));
+        Ok(target.into_buffer())
+    }
+}
+
+impl Drop for StagedUpload {
+    fn drop(&mut self) {
+        // Staging buffers must outlive copies still reading from them
+        for slot in 0..self.slots.len() {
+            let _ = self.target.wait(slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn staging_bytes(upload: &StagedUpload) -> usize {
+        upload.slots.iter().map(|slot| slot.memory.as_slice().len()).sum()
+    }
+
+    #[test]
+    fn test_staged_upload_in_chunks() {
+        let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let config = StagingConfig::default().with_chunk_size(64).with_buffers(3);
+        let mut upload = StagedUpload::new(config, Device::Cuda, data.len(), 0).unwrap();
+        assert_eq!(staging_bytes(&upload), 192);
+
+        // Writes straddling staging buffers, and a short last chunk
+        for piece in data.chunks(100) {
+            upload.write(piece).unwrap();
+        }
+        assert_eq!(upload.uploaded, 960);
+
+        let buffer = upload.finish().unwrap();
+        assert_eq!(buffer.to_host().unwrap(), data);
+    }
+
+    #[test]
+    fn test_staging_smaller_than_chunk() {
+        let mut upload = StagedUpload::new(StagingConfig::default(), Device::Cpu, 10, 0).unwrap();
+        assert_eq!(staging_bytes(&upload), 20);
+        upload.write(b"0123456789").unwrap();
+        assert_eq!(upload.finish().unwrap().to_host().unwrap(), &b"0123456789"[..]);
+    }
+}
@@ -27,6 +27,7 @@
 //! ```
 
 use bytes::{Bytes, BytesMut};
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -34,8 +35,11 @@ use futures_core::Stream;
 use pin_project_lite::pin_project;
 
 use crate::buffer::{GpuError, TensorBuffer};
-use crate::frame::{FrameType, TensorFrame, TensorFrameError, TensorFrameParser};
+use crate::compression::{decode_payload, TensorCodec};
+use crate::frame::{FrameType, TensorFrame, TensorFrameError, TensorFrameParser, DEFAULT_TENSOR_ID};
+use crate::ipc::{self, CudaIpcHandle, CUDA_IPC_HANDLE_SIZE};
 use crate::pool::{GpuMemoryPool, PinnedMemoryPool, PooledBuffer, PooledGpuBuffer};
+use crate::staging::{StagedUpload, StagingConfig};
 use crate::tensor::{Device, Tensor, TensorMeta};
 
 /// Error type for tensor streaming operations.
@@ -60,6 +64,18 @@ pub enum TensorStreamError {
     #[error("tensor size mismatch: expected {expected} bytes, got {actual}")]
     SizeMismatch { expected: usize, actual: usize },
 
+    /// Tensor exceeds a configured size limit.
+    #[error("tensor too large: {size} bytes (limit {limit})")]
+    TooLarge { size: usize, limit: usize },
+
+    /// Chunk overlaps data already received.
+    #[error("chunk at offset {offset} ({len} bytes) overlaps data already received")]
+    OverlappingChunk { offset: usize, len: usize },
+
+    /// Reordering buffer is full and spilling is disabled.
+    #[error("reorder buffer full: more than {limit} bytes waiting for missing chunks")]
+    ReorderBufferFull { limit: usize },
+
     /// Stream was cancelled.
     #[error("stream cancelled: {0}")]
     Cancelled(String),
@@ -68,6 +84,18 @@ pub enum TensorStreamError {
     #[error("GPU error: {0}")]
     Gpu(#[from] GpuError),
 
+    /// Compressed payload could not be decoded.
+    #[error("compression error: {0}")]
+    Compression(String),
+
+    /// Tensor data failed its integrity check.
+    #[error("tensor checksum mismatch: expected {expected:#010x}, got {actual:#010x}")]
+    ChecksumMismatch { expected: u32, actual: u32 },
+
+    /// I/O error (e.g. writing a memory-mapped tensor file).
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     /// Internal error.
     #[error("internal error: {0}")]
     Internal(String),
@@ -151,6 +179,9 @@ where
 /// Encodes tensor data as frames for efficient transfer.
 pub struct TensorSender {
     chunk_size: usize,
+    checksum: bool,
+    preserve_strides: bool,
+    codec: Option<TensorCodec>,
 }
 
 impl TensorSender {
@@ -161,21 +192,80 @@ impl TensorSender {
     pub fn new() -> Self {
         Self {
             chunk_size: Self::DEFAULT_CHUNK_SIZE,
+            checksum: false,
+            preserve_strides: false,
+            codec: None,
         }
     }
 
     /// Creates a sender with custom chunk size.
     pub fn with_chunk_size(chunk_size: usize) -> Self {
-        Self { chunk_size }
+        Self {
+            chunk_size,
+            checksum: false,
+            preserve_strides: false,
+            codec: None,
+        }
+    }
+
+    /// Appends a CRC32 of the tensor data to the END_STREAM frame.
+    pub fn enable_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Sends strided tensors as-is instead of copying them to row-major order.
+    ///
+    /// By default [`encode_tensor`](Self::encode_tensor) materializes a
+    /// contiguous copy of non-contiguous tensors. With this enabled, the
+    /// strides travel in the TENSOR_META frame and the payload is the
+    /// tensor's backing storage, which avoids the copy and can be smaller
+    /// (e.g. for broadcasts). Receivers get a strided [`Tensor`] and may call
+    /// [`Tensor::to_contiguous`].
+    pub fn preserve_strides(mut self, enabled: bool) -> Self {
+        self.preserve_strides = enabled;
+        self
+    }
+
+    /// Compresses each payload chunk with `codec`.
+    ///
+    /// Chunks that don't get smaller are sent uncompressed. Only enable this
+    /// towards receivers that understand compressed payloads (see
+    /// [`compression`](crate::compression)); the checksum still covers the
+    /// uncompressed data.
+    pub fn with_compression(mut self, codec: TensorCodec) -> Self {
+        self.codec = Some(codec);
+        self
+    }
+
+    /// Returns the codec payload chunks are compressed with, if any.
+    pub fn compression(&self) -> Option<TensorCodec> {
+        self.codec
+    }
+
+    /// Returns the chunk size used for payload frames.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
     }
 
     /// Encodes a tensor as a sequence of frames.
     ///
     /// Returns:
     /// 1. TENSOR_META frame with tensor metadata
-    /// 2. One or more TENSOR_PAYLOAD frames with raw data
-    /// 3. END_STREAM frame
+    /// 2. One or more TENSOR_PAYLOAD frames with raw (or, with
+    ///    [`with_compression`](Self::with_compression), compressed) data
+    /// 3. END_STREAM frame (carrying a CRC32 if checksums are enabled)
+    ///
+    /// Non-contiguous tensors are copied to row-major order first unless
+    /// [`preserve_strides`](Self::preserve_strides) is enabled.
     pub fn encode_tensor(&self, tensor: &Tensor) -> Vec<TensorFrame> {
+        let contiguous;
+        let tensor = if self.preserve_strides || tensor.is_contiguous() {
+            tensor
+        } else {
+            contiguous = tensor.to_contiguous();
+            &contiguous
+        };
         let mut frames = Vec::new();
 
         // Encode metadata as protobuf-like format
@@ -188,16 +278,34 @@ impl TensorSender {
         while offset < data.len() {
             let end = std::cmp::min(offset + self.chunk_size, data.len());
             let chunk = data.slice(offset..end);
-            frames.push(TensorFrame::tensor_payload(chunk));
+            frames.push(match &self.codec {
+                Some(codec) => codec.payload_frame(chunk),
+                None => TensorFrame::tensor_payload(chunk),
+            });
             offset = end;
         }
 
         // End stream
-        frames.push(TensorFrame::end_stream());
+        if self.checksum {
+            frames.push(TensorFrame::end_stream_with_checksum(crc32fast::hash(data)));
+        } else {
+            frames.push(TensorFrame::end_stream());
+        }
 
         frames
     }
 
+    /// Encodes a tensor as frames tagged with tensor ID `id`.
+    ///
+    /// Frames of tensors with different IDs may be interleaved on one
+    /// stream; a [`TensorReceiver`] reassembles each tensor separately.
+    pub fn encode_tensor_with_id(&self, tensor: &Tensor, id: u16) -> Vec<TensorFrame> {
+        self.encode_tensor(tensor)
+            .into_iter()
+            .map(|frame| frame.with_tensor_id(id))
+            .collect()
+    }
+
     /// Encodes tensor metadata to bytes.
     ///
     /// Simple binary format (not protobuf for efficiency):
@@ -205,26 +313,102 @@ impl TensorSender {
     /// - shape: [u64; ndim]
     /// - dtype: u8
     /// - device: u8
-    /// - byte_size: u64
+    /// - byte_size: u64 (of the payload, see [`TensorMeta::storage_byte_size`])
     /// - name_len: u16
     /// - name: [u8; name_len] (optional)
-    fn encode_meta(&self, meta: &TensorMeta) -> Bytes {
-        let name_bytes = meta.name.as_ref().map(|n| n.as_bytes()).unwrap_or(&[]);
-        let capacity = 1 + meta.shape.len() * 8 + 1 + 1 + 8 + 2 + name_bytes.len();
-        let mut buf = BytesMut::with_capacity(capacity);
-
-        buf.extend_from_slice(&[meta.shape.len() as u8]);
-        for &dim in &meta.shape {
-            buf.extend_from_slice(&(dim as u64).to_le_bytes());
+    ///
+    /// followed, for tensors that require grad, have a non-contiguous
+    /// layout or are passed by CUDA IPC handle, by a versioned extension:
+    /// - version: u8 (currently 1)
+    /// - flags: u8 (0x01 requires_grad, 0x02 strides follow, 0x04 IPC handle follows)
+    /// - strides: [u64; ndim]
+    /// - IPC device: u32, IPC handle: [u8; 64] (see [`crate::ipc`])
+    ///
+    /// Later versions only append fields, and decoders ignore fields and
+    /// flags they don't know. Older decoders ignore the whole extension.
+    pub fn encode_meta(&self, meta: &TensorMeta) -> Bytes {
+        encode_tensor_meta(meta, None)
+    }
+
+    /// Encodes a tensor held in a CPU or GPU buffer.
+    ///
+    /// With `cuda_ipc` set, i.e. after the receiver's header passed
+    /// [`ipc::accepts_cuda_ipc`], a GPU buffer is handed over as a CUDA IPC
+    /// handle in the TENSOR_META frame instead of as payload frames. The
+    /// buffer must then stay alive until the receiver has taken the tensor.
+    /// Otherwise, or if no handle can be exported, the data is copied to the
+    /// host and streamed like [`encode_tensor`](Self::encode_tensor).
+    pub fn encode_buffer(
+        &self,
+        meta: &TensorMeta,
+        buffer: &TensorBuffer,
+        cuda_ipc: bool,
+    ) -> Result<Vec<TensorFrame>, TensorStreamError> {
+        if buffer.len() != meta.storage_byte_size() {
+            return Err(TensorStreamError::SizeMismatch {
+                expected: meta.storage_byte_size(),
+                actual: buffer.len(),
+            });
         }
-        buf.extend_from_slice(&[meta.dtype as u8]);
-        buf.extend_from_slice(&[meta.device as u8]);
-        buf.extend_from_slice(&(meta.byte_size() as u64).to_le_bytes());
-        buf.extend_from_slice(&(name_bytes.len() as u16).to_le_bytes());
-        buf.extend_from_slice(name_bytes);
+        if cuda_ipc && buffer.is_gpu() {
+            match ipc::export(buffer) {
+                Ok(handle) => {
+                    return Ok(vec![
+                        TensorFrame::tensor_meta(encode_tensor_meta(meta, Some(&handle))),
+                        TensorFrame::end_stream(),
+                    ]);
+                }
+                Err(e) => tracing::warn!("CUDA IPC export failed ({}), streaming tensor bytes", e),
+            }
+        }
+        let data = buffer.to_host()?;
+        Ok(self.encode_tensor(&Tensor::new(meta.clone(), data)))
+    }
+}
 
-        buf.freeze()
+/// Encodes tensor metadata, with an IPC handle standing in for the payload.
+fn encode_tensor_meta(meta: &TensorMeta, ipc: Option<&CudaIpcHandle>) -> Bytes {
+    let name_bytes = meta.name.as_ref().map(|n| n.as_bytes()).unwrap_or(&[]);
+    let strides = meta.strides.as_ref().filter(|_| !meta.is_contiguous());
+    let mut flags = 0;
+    if meta.requires_grad {
+        flags |= meta_flags::REQUIRES_GRAD;
+    }
+    if strides.is_some() {
+        flags |= meta_flags::HAS_STRIDES;
+    }
+    if ipc.is_some() {
+        flags |= meta_flags::CUDA_IPC;
     }
+    let extension_len = if flags == 0 {
+        0
+    } else {
+        2 + strides.map_or(0, |s| s.len() * 8) + ipc.map_or(0, |_| 4 + CUDA_IPC_HANDLE_SIZE)
+    };
+    let capacity = 1 + meta.shape.len() * 8 + 1 + 1 + 8 + 2 + name_bytes.len() + extension_len;
+    let mut buf = BytesMut::with_capacity(capacity);
+
+    buf.extend_from_slice(&[meta.shape.len() as u8]);
+    for &dim in &meta.shape {
+        buf.extend_from_slice(&(dim as u64).to_le_bytes());
+    }
+    buf.extend_from_slice(&[meta.dtype as u8]);
+    buf.extend_from_slice(&[meta.device as u8]);
+    buf.extend_from_slice(&(meta.storage_byte_size() as u64).to_le_bytes());
+    buf.extend_from_slice(&(name_bytes.len() as u16).to_le_bytes());
+    buf.extend_from_slice(name_bytes);
+    if flags != 0 {
+        buf.extend_from_slice(&[META_EXTENSION_VERSION, flags]);
+        for &stride in strides.into_iter().flatten() {
+            buf.extend_from_slice(&(stride as u64).to_le_bytes());
+        }
+        if let Some(handle) = ipc {
+            buf.extend_from_slice(&handle.device_id().to_le_bytes());
+            buf.extend_from_slice(handle.as_bytes());
+        }
+    }
+
+    buf.freeze()
 }
 
 impl Default for TensorSender {
@@ -236,12 +420,48 @@ impl Default for TensorSender {
 /// Receiver for streaming tensor data.
 ///
 /// Decodes frames and assembles tensor data with zero-copy where possible.
+/// Tensors interleaved on one stream are reassembled separately by tensor
+/// ID; [`meta`](Self::meta), [`is_complete`](Self::is_complete) and
+/// [`take_tensor`](Self::take_tensor) refer to the tensor with
+/// [`DEFAULT_TENSOR_ID`], and [`poll_tensor`](Self::poll_tensor) waits for
+/// any other.
 pub struct TensorReceiver {
     parser: TensorFrameParser,
+    tensors: HashMap<u16, Assembly>,
+    /// Tensor the last frame belonged to.
+    current: u16,
+}
+
+/// Reassembly state of one tensor.
+#[derive(Default)]
+struct Assembly {
     meta: Option<TensorMeta>,
     buffer: BytesMut,
     expected_size: usize,
     received_size: usize,
+    /// Whether the tensor's END_STREAM was received.
+    ended: bool,
+}
+
+impl Assembly {
+    fn with_meta(meta: TensorMeta) -> Self {
+        let byte_size = meta.storage_byte_size();
+        Self {
+            meta: Some(meta),
+            buffer: BytesMut::with_capacity(byte_size),
+            expected_size: byte_size,
+            received_size: 0,
+            ended: false,
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.expected_size > 0 && self.received_size >= self.expected_size
+    }
+
+    fn into_tensor(self) -> Option<Tensor> {
+        Some(Tensor::new(self.meta?, self.buffer.freeze()))
+    }
 }
 
 impl TensorReceiver {
@@ -249,23 +469,16 @@ impl TensorReceiver {
     pub fn new() -> Self {
         Self {
             parser: TensorFrameParser::new(),
-            meta: None,
-            buffer: BytesMut::new(),
-            expected_size: 0,
-            received_size: 0,
+            tensors: HashMap::new(),
+            current: DEFAULT_TENSOR_ID,
         }
     }
 
     /// Creates a receiver with known metadata (enables pre-allocation).
     pub fn with_meta(meta: TensorMeta) -> Self {
-        let byte_size = meta.byte_size();
-        Self {
-            parser: TensorFrameParser::new(),
-            meta: Some(meta),
-            buffer: BytesMut::with_capacity(byte_size),
-            expected_size: byte_size,
-            received_size: 0,
-        }
+        let mut receiver = Self::new();
+        receiver.tensors.insert(DEFAULT_TENSOR_ID, Assembly::with_meta(meta));
+        receiver
     }
 
     /// Feeds raw bytes into the receiver.
@@ -279,6 +492,9 @@ impl TensorReceiver {
     }
 
     /// Processes available frames and returns the next event.
+    ///
+    /// [`current_tensor_id`](Self::current_tensor_id) tells which tensor
+    /// the event belongs to.
     pub fn poll(&mut self) -> Result<ReceiverEvent, TensorStreamError> {
         match self.parser.parse_frame()? {
             None => Ok(ReceiverEvent::NeedMoreData),
@@ -286,14 +502,36 @@ impl TensorReceiver {
         }
     }
 
+    /// Processes available frames until tensor `id` has ended and returns it.
+    ///
+    /// Returns `None` if more data is needed first. Frames of other tensors
+    /// are reassembled along the way and stay available to later calls.
+    pub fn poll_tensor(&mut self, id: u16) -> Result<Option<Tensor>, TensorStreamError> {
+        loop {
+            if self.tensors.get(&id).is_some_and(|tensor| tensor.ended) {
+                return Ok(self.tensors.remove(&id).and_then(Assembly::into_tensor));
+            }
+            match self.poll()? {
+                ReceiverEvent::NeedMoreData => return Ok(None),
+                ReceiverEvent::Cancelled(reason) => return Err(TensorStreamError::Cancelled(reason)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the ID of the tensor the last polled frame belonged to.
+    pub fn current_tensor_id(&self) -> u16 {
+        self.current
+    }
+
     /// Returns the tensor metadata if received.
     pub fn meta(&self) -> Option<&TensorMeta> {
-        self.meta.as_ref()
+        self.tensors.get(&DEFAULT_TENSOR_ID)?.meta.as_ref()
     }
 
     /// Returns whether all expected data has been received.
     pub fn is_complete(&self) -> bool {
-        self.expected_size > 0 && self.received_size >= self.expected_size
+        self.tensors.get(&DEFAULT_TENSOR_ID).is_some_and(Assembly::is_complete)
     }
 
     /// Takes the completed tensor, returning None if not complete.
@@ -301,50 +539,53 @@ impl TensorReceiver {
         if !self.is_complete() {
             return None;
         }
-
-        let meta = self.meta.take()?;
-        let data = std::mem::take(&mut self.buffer).freeze();
-        self.received_size = 0;
-        self.expected_size = 0;
-
-        Some(Tensor::new(meta, data))
+        self.tensors.remove(&DEFAULT_TENSOR_ID)?.into_tensor()
     }
 
     fn handle_frame(&mut self, frame: TensorFrame) -> Result<ReceiverEvent, TensorStreamError> {
+        if frame.frame_type == FrameType::Cancel {
+            let reason = String::from_utf8_lossy(&frame.payload).into_owned();
+            return Ok(ReceiverEvent::Cancelled(reason));
+        }
+        self.current = frame.tensor_id();
+        let tensor = self.tensors.entry(self.current).or_default();
         match frame.frame_type {
             FrameType::TensorMeta => {
-                let meta = self.decode_meta(&frame.payload)?;
-                self.expected_size = meta.byte_size();
-                self.buffer = BytesMut::with_capacity(self.expected_size);
-                self.received_size = 0;
-                self.meta = Some(meta.clone());
+                let (meta, ipc) = decode_tensor_meta_with_ipc(&frame.payload)?;
+                *tensor = Assembly::with_meta(meta.clone());
+                if let Some(handle) = ipc {
+                    // The data is copied from the sender's allocation at once
+                    let size = tensor.expected_size;
+                    let data = ipc::import(&handle, Device::Cpu, 0, size)?.to_host()?;
+                    tensor.buffer.extend_from_slice(&data);
+                    tensor.received_size = size;
+                }
                 Ok(ReceiverEvent::Metadata(meta))
             }
             FrameType::TensorPayload => {
-                if self.meta.is_none() {
+                if tensor.meta.is_none() {
                     return Err(TensorStreamError::MissingMetadata);
                 }
-                let chunk_size = frame.payload.len();
-                self.buffer.extend_from_slice(&frame.payload);
-                self.received_size += chunk_size;
+                let remaining = tensor.expected_size.saturating_sub(tensor.received_size);
+                let payload = decode_payload(&frame, remaining)?;
+                let chunk_size = payload.len();
+                tensor.buffer.extend_from_slice(&payload);
+                tensor.received_size += chunk_size;
                 Ok(ReceiverEvent::Data(TensorChunk::new(
-                    self.received_size - chunk_size,
-                    frame.payload,
+                    tensor.received_size - chunk_size,
+                    payload,
                 )))
             }
             FrameType::EndStream => {
-                if self.expected_size > 0 && self.received_size != self.expected_size {
+                if tensor.expected_size > 0 && tensor.received_size != tensor.expected_size {
                     return Err(TensorStreamError::SizeMismatch {
-                        expected: self.expected_size,
-                        actual: self.received_size,
+                        expected: tensor.expected_size,
+                        actual: tensor.received_size,
                     });
                 }
+                tensor.ended = true;
                 Ok(ReceiverEvent::End)
             }
-            FrameType::Cancel => {
-                let reason = String::from_utf8_lossy(&frame.payload).into_owned();
-                Ok(ReceiverEvent::Cancelled(reason))
-            }
             _ => Err(TensorStreamError::UnexpectedFrame {
                 expected: "TENSOR_META, TENSOR_PAYLOAD, END_STREAM, or CANCEL",
                 actual: frame.frame_type.name(),
@@ -352,63 +593,9 @@ impl TensorReceiver {
         }
     }
 
-    fn decode_meta(&self, data: &[u8]) -> Result<TensorMeta, TensorStreamError> {
-        if data.is_empty() {
-            return Err(TensorStreamError::Internal("empty metadata".to_string()));
-        }
-
-        let ndim = data[0] as usize;
-        let mut offset = 1;
-
-        if data.len() < offset + ndim * 8 + 1 + 1 + 8 + 2 {
-            return Err(TensorStreamError::Internal("metadata too short".to_string()));
-        }
-
-        let mut shape = Vec::with_capacity(ndim);
-        for _ in 0..ndim {
-            let dim = u64::from_le_bytes([
-                data[offset],
-                data[offset + 1],
-                data[offset + 2],
-                data[offset + 3],
-                data[offset + 4],
-                data[offset + 5],
-                data[offset + 6],
-                data[offset + 7],
-            ]) as usize;
-            shape.push(dim);
-            offset += 8;
-        }
-
-        let dtype = crate::dtype::DType::try_from(data[offset]).map_err(|_| {
-            TensorStreamError::Internal(format!("unknown dtype: {}", data[offset]))
-        })?;
-        offset += 1;
-
-        let device = crate::tensor::Device::from_proto(data[offset] as i32)
-            .ok_or_else(|| TensorStreamError::Internal(format!("unknown device: {}", data[offset])))?;
-        offset += 1;
-
-        // Skip byte_size (we compute it from shape)
-        offset += 8;
-
-        let name_len = u16::from_le_bytes([data[offset], data[offset + 1]]) as usize;
-        offset += 2;
-
-        let name = if name_len > 0 && data.len() >= offset + name_len {
-            Some(String::from_utf8_lossy(&data[offset..offset + name_len]).into_owned())
-        } else {
-            None
-        };
-
-        Ok(TensorMeta {
-            shape,
-            dtype,
-            device,
-            strides: None,
-            name,
-            requires_grad: false,
-        })
+    /// Decodes a TENSOR_META payload written by [`TensorSender::encode_meta`].
+    pub fn decode_meta(data: &[u8]) -> Result<TensorMeta, TensorStreamError> {
+        decode_tensor_meta(data)
     }
 }
 
@@ -445,12 +632,20 @@ impl Default for TensorReceiver {
 /// let (meta, buffer) = receiver.take()?;
 /// assert!(buffer.is_gpu());
 /// ```
+///
+/// By default the whole tensor is collected on the host before it is copied
+/// to the device. Use [`with_staging`](Self::with_staging) to upload large
+/// tensors chunk by chunk while they are received.
 pub struct GpuTensorReceiver {
     parser: TensorFrameParser,
     meta: TensorMeta,
     device_id: usize,
     /// CPU staging buffer for accumulating incoming data
     staging: BytesMut,
+    /// Chunk-wise upload configuration, if enabled
+    staging_config: Option<StagingConfig>,
+    /// Chunk-wise upload of the current tensor
+    upload: Option<StagedUpload>,
     /// Target buffer (CPU or GPU)
     buffer: Option<TensorBuffer>,
     expected_size: usize,
@@ -472,7 +667,7 @@ impl GpuTensorReceiver {
     /// A receiver that will allocate on the appropriate device.
     /// For `Device::Cuda`, allocation falls back to CPU if GPU is unavailable.
     pub fn new(meta: TensorMeta, device_id: usize) -> Result<Self, TensorStreamError> {
-        let expected_size = meta.byte_size();
+        let expected_size = meta.storage_byte_size();
 
         // Allocate staging buffer for incoming network data
         let staging = BytesMut::with_capacity(expected_size);
@@ -482,6 +677,8 @@ impl GpuTensorReceiver {
             meta,
             device_id,
             staging,
+            staging_config: None,
+            upload: None,
             buffer: None,
             expected_size,
             received_size: 0,
@@ -498,6 +695,19 @@ impl GpuTensorReceiver {
         Self::new(meta, device_id)
     }
 
+    /// Uploads CUDA tensors chunk-wise through pinned staging buffers.
+    ///
+    /// Host memory then stays at [`StagingConfig::staging_bytes`] instead of
+    /// the tensor's size, and uploads overlap with receiving. CPU tensors
+    /// are received as before.
+    pub fn with_staging(mut self, config: StagingConfig) -> Self {
+        self.staging_config = Some(config);
+        if self.meta.device == Device::Cuda {
+            self.staging = BytesMut::new();
+        }
+        self
+    }
+
     /// Returns the tensor metadata.
     pub fn meta(&self) -> &TensorMeta {
         &self.meta
@@ -582,16 +792,44 @@ impl GpuTensorReceiver {
         match frame.frame_type {
             FrameType::TensorMeta => {
                 // Update metadata if received dynamically
-                let new_meta = decode_tensor_meta(&frame.payload)?;
+                let (new_meta, ipc) = decode_tensor_meta_with_ipc(&frame.payload)?;
                 self.meta = new_meta.clone();
-                self.expected_size = new_meta.byte_size();
-                self.staging = BytesMut::with_capacity(self.expected_size);
+                self.expected_size = new_meta.storage_byte_size();
+                self.upload = None;
+                self.buffer = None;
+                self.staging = if self.is_staged() || ipc.is_some() {
+                    BytesMut::new()
+                } else {
+                    BytesMut::with_capacity(self.expected_size)
+                };
                 self.received_size = 0;
+                if let Some(handle) = ipc {
+                    // Copied device-to-device from the sender's allocation
+                    let buffer =
+                        ipc::import(&handle, self.meta.device, self.device_id, self.expected_size)?;
+                    self.buffer = Some(buffer);
+                    self.received_size = self.expected_size;
+                }
                 Ok(GpuReceiverEvent::Metadata(new_meta))
             }
             FrameType::TensorPayload => {
-                let chunk_size = frame.payload.len();
-                self.staging.extend_from_slice(&frame.payload);
+                let remaining = self.expected_size.saturating_sub(self.received_size);
+                let payload = decode_payload(&frame, remaining)?;
+                let chunk_size = payload.len();
+                if let Some(config) = self.staging_config.filter(|_| self.is_staged()) {
+                    let upload = match self.upload.as_mut() {
+                        Some(upload) => upload,
+                        None => self.upload.insert(StagedUpload::new(
+                            config,
+                            self.meta.device,
+                            self.expected_size,
+                            self.device_id,
+                        )?),
+                    };
+                    upload.write(&payload)?;
+                } else {
+                    self.staging.extend_from_slice(&payload);
+                }
                 self.received_size += chunk_size;
 
                 Ok(GpuReceiverEvent::Data {
@@ -624,12 +862,27 @@ impl GpuTensorReceiver {
         }
     }
 
+    /// Returns whether payloads go through a chunk-wise upload.
+    fn is_staged(&self) -> bool {
+        self.staging_config.is_some() && self.meta.device == Device::Cuda
+    }
+
     /// Finalizes the transfer by moving data to the target device.
     fn finalize_transfer(&mut self) -> Result<(), TensorStreamError> {
         if self.buffer.is_some() {
             return Ok(()); // Already transferred
         }
 
+        if let Some(config) = self.staging_config.filter(|_| self.is_staged()) {
+            // Chunks were uploaded as they arrived; wait for the last ones
+            let upload = match self.upload.take() {
+                Some(upload) => upload,
+                None => StagedUpload::new(config, self.meta.device, self.expected_size, self.device_id)?,
+            };
+            self.buffer = Some(upload.finish()?);
+            return Ok(());
+        }
+
         // Allocate on target device
         let mut buffer = self.meta.device.allocate_buffer(self.expected_size, self.device_id)?;
 
@@ -725,7 +978,7 @@ impl PooledGpuReceiver {
         pinned_pool: PinnedMemoryPool,
         gpu_pool: Option<GpuMemoryPool>,
     ) -> Result<Self, TensorStreamError> {
-        let expected_size = meta.byte_size();
+        let expected_size = meta.storage_byte_size();
 
         // Acquire staging buffer from pool
         let staging = pinned_pool.acquire(expected_size)?;
@@ -835,7 +1088,7 @@ impl PooledGpuReceiver {
             FrameType::TensorMeta => {
                 let new_meta = decode_tensor_meta(&frame.payload)?;
                 self.meta = new_meta.clone();
-                self.expected_size = new_meta.byte_size();
+                self.expected_size = new_meta.storage_byte_size();
                 self.received_size = 0;
                 self.staging_offset = 0;
 
@@ -845,11 +1098,13 @@ impl PooledGpuReceiver {
                 Ok(GpuReceiverEvent::Metadata(new_meta))
             }
             FrameType::TensorPayload => {
-                let chunk_size = frame.payload.len();
+                let remaining = self.expected_size.saturating_sub(self.received_size);
+                let payload = decode_payload(&frame, remaining)?;
+                let chunk_size = payload.len();
 
                 // Write to staging buffer
                 if let Some(ref mut staging) = self.staging {
-                    staging.extend_from_slice(&frame.payload);
+                    staging.extend_from_slice(&payload);
                 }
                 self.staging_offset += chunk_size;
                 self.received_size += chunk_size;
@@ -951,7 +1206,20 @@ impl PooledTensorBuffer {
 }
 
 /// Decodes tensor metadata from bytes.
-fn decode_tensor_meta(data: &[u8]) -> Result<TensorMeta, TensorStreamError> {
+pub(crate) fn decode_tensor_meta(data: &[u8]) -> Result<TensorMeta, TensorStreamError> {
+    match decode_tensor_meta_with_ipc(data)? {
+        (meta, None) => Ok(meta),
+        // Payload frames won't follow, so only IPC-aware receivers may accept it
+        (_, Some(_)) => Err(TensorStreamError::Internal(
+            "tensor passed by CUDA IPC handle is not supported by this receiver".to_string(),
+        )),
+    }
+}
+
+/// Decodes TENSOR_META, along with the CUDA IPC handle it may carry.
+pub(crate) fn decode_tensor_meta_with_ipc(
+    data: &[u8],
+) -> Result<(TensorMeta, Option<CudaIpcHandle>), TensorStreamError> {
     if data.is_empty() {
         return Err(TensorStreamError::Internal("empty metadata".to_string()));
     }
@@ -999,15 +1267,69 @@ fn decode_tensor_meta(data: &[u8]) -> Result<TensorMeta, TensorStreamError> {
     } else {
         None
     };
+    offset += name_len;
+
+    // Versioned extension; absent for plain contiguous tensors
+    let mut strides = None;
+    let mut requires_grad = false;
+    let mut ipc = None;
+    if let Some(&version) = data.get(offset) {
+        let Some(&flags) = data.get(offset + 1).filter(|_| version >= 1) else {
+            return Err(TensorStreamError::Internal(format!(
+                "invalid metadata extension version {}",
+                version
+            )));
+        };
+        offset += 2;
+        requires_grad = flags & meta_flags::REQUIRES_GRAD != 0;
+        if flags & meta_flags::HAS_STRIDES != 0 {
+            if data.len() < offset + ndim * 8 {
+                return Err(TensorStreamError::Internal(format!(
+                    "metadata too short for {} strides",
+                    ndim
+                )));
+            }
+            strides = Some(
+                data[offset..offset + ndim * 8]
+                    .chunks_exact(8)
+                    .map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize)
+                    .collect(),
+            );
+            offset += ndim * 8;
+        }
+        if flags & meta_flags::CUDA_IPC != 0 {
+            let Some(field) = data.get(offset..offset + 4 + CUDA_IPC_HANDLE_SIZE) else {
+                return Err(TensorStreamError::Internal(
+                    "metadata too short for CUDA IPC handle".to_string(),
+                ));
+            };
+            let device_id = u32::from_le_bytes(field[..4].try_into().unwrap());
+            ipc = Some(CudaIpcHandle::new(device_id, field[4..].try_into().unwrap()));
+        }
+    }
 
-    Ok(TensorMeta {
+    let meta = TensorMeta {
         shape,
         dtype,
         device,
-        strides: None,
+        strides,
         name,
-        requires_grad: false,
-    })
+        requires_grad,
+    };
+    Ok((meta, ipc))
+}
+
+/// Version of the TENSOR_META extension written by [`TensorSender::encode_meta`].
+const META_EXTENSION_VERSION: u8 = 1;
+
+/// Flags of the TENSOR_META extension.
+mod meta_flags {
+    /// The tensor requires grad.
+    pub const REQUIRES_GRAD: u8 = 0x01;
+    /// Strides for every dimension follow the flags.
+    pub const HAS_STRIDES: u8 = 0x02;
+    /// A CUDA IPC handle follows and no payload frames are sent.
+    pub const CUDA_IPC: u8 = 0x04;
 }
 
 /// Events produced by the tensor receiver.
@@ -1110,6 +1432,39 @@ mod tests {
         assert_eq!(received.as_f32(), &[1.0, 2.0, 3.0, 4.0]);
     }
 
+    #[test]
+    fn test_receiver_interleaved_tensors() {
+        let logits = Tensor::from_f32(&TensorMeta::new(vec![64], DType::Float32), &[1.0; 64]);
+        let hidden = Tensor::from_f32(&TensorMeta::new(vec![32], DType::Float32), &[2.0; 32]);
+        let sender = TensorSender::with_chunk_size(64);
+        let logits_frames = sender.encode_tensor_with_id(&logits, 1);
+        let hidden_frames = sender.encode_tensor_with_id(&hidden, 2);
+        assert!(logits_frames.len() > hidden_frames.len());
+
+        // Alternate frames, so each tensor's chunks arrive between the other's
+        let mut receiver = TensorReceiver::new();
+        let mut logits_frames = logits_frames.into_iter();
+        for frame in hidden_frames {
+            receiver.feed(&logits_frames.next().unwrap().encode());
+            receiver.feed(&frame.encode());
+        }
+        assert!(matches!(receiver.poll().unwrap(), ReceiverEvent::Metadata(_)));
+        assert_eq!(receiver.current_tensor_id(), 1);
+
+        // Hidden states are complete, logits still need their last frames
+        assert!(receiver.poll_tensor(1).unwrap().is_none());
+        let received = receiver.poll_tensor(2).unwrap().unwrap();
+        assert_eq!(received.as_f32(), &[2.0; 32]);
+        assert!(receiver.take_tensor().is_none());
+
+        for frame in logits_frames {
+            receiver.feed(&frame.encode());
+        }
+        let received = receiver.poll_tensor(1).unwrap().unwrap();
+        assert_eq!(received.as_f32(), &[1.0; 64]);
+        assert!(receiver.poll_tensor(1).unwrap().is_none());
+    }
+
     #[test]
     fn test_receiver_with_prealloc() {
         let meta = TensorMeta::new(vec![100], DType::Float32);
@@ -1221,6 +1576,29 @@ mod tests {
         assert_eq!(host_data.len(), 16);
     }
 
+    #[test]
+    fn test_gpu_receiver_staged_upload() {
+        let meta = TensorMeta::new(vec![1000], DType::Float32).with_device(Device::Cuda);
+        let values: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+        let tensor = Tensor::from_f32(&meta, &values);
+
+        let sender = TensorSender::with_chunk_size(300);
+        let frames = sender.encode_tensor(&tensor);
+
+        let config = StagingConfig::default().with_chunk_size(512);
+        let mut receiver = GpuTensorReceiver::new(meta, 0).unwrap().with_staging(config);
+        for frame in frames {
+            receiver.feed(&frame.encode());
+        }
+        while !matches!(receiver.poll().unwrap(), GpuReceiverEvent::End) {}
+
+        // Only the staging buffers were held, not the whole tensor
+        assert_eq!(receiver.staging.capacity(), 0);
+
+        let received = receiver.take_tensor().unwrap();
+        assert_eq!(received.data, tensor.data);
+    }
+
     #[test]
     fn test_gpu_receiver_take_tensor() {
         // Test the take_tensor() convenience method
@@ -1305,6 +1683,177 @@ mod tests {
         assert_eq!(decoded.name, Some("test_tensor".to_string()));
     }
 
+    #[test]
+    fn test_sender_materializes_strided_tensor() {
+        // A 3x2 transposed view of the row-major 2x3 matrix [[1, 2, 3], [4, 5, 6]]
+        let storage = TensorMeta::new(vec![2, 3], DType::Float32);
+        let original = Tensor::from_f32(&storage, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let meta = TensorMeta::new(vec![3, 2], DType::Float32).with_strides(vec![1, 3]);
+        let view = Tensor::new(meta, original.data);
+
+        let mut receiver = TensorReceiver::new();
+        for frame in TensorSender::new().encode_tensor(&view) {
+            receiver.feed(&frame.encode());
+        }
+        while !matches!(receiver.poll().unwrap(), ReceiverEvent::End) {}
+
+        let received = receiver.take_tensor().unwrap();
+        assert!(received.is_contiguous());
+        assert_eq!(received.meta.strides, None);
+        assert_eq!(received.as_f32(), &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn test_sender_preserves_strides() {
+        // Broadcast a row of 4 to 1000x4 with a zero stride
+        let row = Tensor::from_i32(&TensorMeta::new(vec![4], DType::Int32), &[1, 2, 3, 4]);
+        let meta = TensorMeta::new(vec![1000, 4], DType::Int32)
+            .with_strides(vec![0, 1])
+            .with_name("bias");
+        let broadcast = Tensor::new(meta, row.data);
+
+        let frames = TensorSender::new().preserve_strides(true).encode_tensor(&broadcast);
+        // Only the 16 bytes of storage are sent
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].payload.len(), 16);
+
+        let mut receiver = TensorReceiver::new();
+        for frame in frames {
+            receiver.feed(&frame.encode());
+        }
+        let ReceiverEvent::Metadata(meta) = receiver.poll().unwrap() else {
+            panic!("expected metadata");
+        };
+        assert_eq!(meta.strides, Some(vec![0, 1]));
+        assert_eq!(meta.name.as_deref(), Some("bias"));
+        while !matches!(receiver.poll().unwrap(), ReceiverEvent::End) {}
+
+        let received = receiver.take_tensor().unwrap();
+        assert!(!received.is_contiguous());
+        let contiguous = received.to_contiguous();
+        assert_eq!(contiguous.numel(), 4000);
+        assert_eq!(&contiguous.as_i32()[..8], &[1, 2, 3, 4, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_compressed_tensor_round_trip() {
+        let meta = TensorMeta::new(vec![64, 256], DType::Float32);
+        let values: Vec<f32> = (0..64 * 256).map(|i| (i % 7) as f32).collect();
+        let tensor = Tensor::from_f32(&meta, &values);
+
+        for codec in [TensorCodec::Lz4, TensorCodec::ZSTD_DEFAULT] {
+            let sender = TensorSender::with_chunk_size(8192)
+                .with_compression(codec)
+                .enable_checksum(true);
+            let frames = sender.encode_tensor(&tensor);
+            assert_eq!(frames.len(), 2 + tensor.byte_size() / 8192);
+            let wire: usize = frames.iter().map(|f| f.payload.len()).sum();
+            assert!(wire < tensor.byte_size() / 4);
+
+            let mut receiver = TensorReceiver::new();
+            let mut offsets = Vec::new();
+            for frame in &frames {
+                receiver.feed(&frame.encode());
+                if let ReceiverEvent::Data(chunk) = receiver.poll().unwrap() {
+                    assert_eq!(chunk.len(), 8192);
+                    offsets.push(chunk.offset);
+                }
+            }
+            assert_eq!(offsets, (0..8).map(|i| i * 8192).collect::<Vec<_>>());
+            let received = receiver.take_tensor().unwrap();
+            assert_eq!(received.as_f32(), tensor.as_f32());
+            assert_eq!(
+                frames.last().unwrap().end_stream_checksum(),
+                Some(crc32fast::hash(&received.data))
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_meta_rejects_bad_strides() {
+        let meta = TensorMeta::new(vec![2, 3], DType::Float32).with_strides(vec![1, 2]);
+        let mut payload = TensorSender::new().encode_meta(&meta).to_vec();
+        payload.truncate(payload.len() - 4);
+        assert!(decode_tensor_meta(&payload).is_err());
+
+        // Version 0 is not a valid extension
+        let plain = TensorSender::new().encode_meta(&TensorMeta::new(vec![2], DType::Int8));
+        let mut payload = plain.to_vec();
+        payload.extend_from_slice(&[0, meta_flags::REQUIRES_GRAD]);
+        assert!(decode_tensor_meta(&payload).is_err());
+    }
+
+    #[test]
+    fn test_meta_extension_round_trip() {
+        let sender = TensorSender::new();
+
+        // Plain tensors keep the original layout
+        let plain = TensorMeta::new(vec![2, 3], DType::Float32).with_name("w");
+        let payload = sender.encode_meta(&plain);
+        assert_eq!(payload.len(), 1 + 2 * 8 + 1 + 1 + 8 + 2 + 1);
+        assert_eq!(decode_tensor_meta(&payload).unwrap(), plain);
+
+        let meta = TensorMeta::new(vec![2, 3], DType::Float32)
+            .with_strides(vec![1, 2])
+            .with_requires_grad(true)
+            .with_name("w");
+        let decoded = decode_tensor_meta(&sender.encode_meta(&meta)).unwrap();
+        assert_eq!(decoded, meta);
+
+        let grad_only = TensorMeta::new(vec![4], DType::BFloat16).with_requires_grad(true);
+        let payload = sender.encode_meta(&grad_only);
+        assert_eq!(decode_tensor_meta(&payload).unwrap(), grad_only);
+
+        // Fields and flags of later versions are skipped
+        let mut future = payload.to_vec();
+        let flags = future.len() - 1;
+        future[flags - 1] = 2;
+        future[flags] |= 0x80;
+        future.extend_from_slice(&[0xAB; 12]);
+        assert_eq!(decode_tensor_meta(&future).unwrap(), grad_only);
+    }
+
+    #[test]
+    fn test_meta_cuda_ipc_handle() {
+        let meta = TensorMeta::new(vec![2, 3], DType::Float32)
+            .with_device(Device::Cuda)
+            .with_strides(vec![1, 2]);
+        let handle = CudaIpcHandle::new(1, [7; CUDA_IPC_HANDLE_SIZE]);
+        let payload = encode_tensor_meta(&meta, Some(&handle));
+        assert_eq!(decode_tensor_meta_with_ipc(&payload).unwrap(), (meta, Some(handle)));
+
+        // Receivers expecting payload frames refuse it
+        assert!(decode_tensor_meta(&payload).is_err());
+        assert!(decode_tensor_meta_with_ipc(&payload[..payload.len() - 1]).is_err());
+
+        // Without a GPU the handle can't be opened
+        let mut receiver = TensorReceiver::new();
+        receiver.feed(&TensorFrame::tensor_meta(payload).encode());
+        assert!(matches!(receiver.poll(), Err(TensorStreamError::Gpu(_))));
+    }
+
+    #[test]
+    fn test_encode_buffer_falls_back_to_bytes() {
+        let meta = TensorMeta::new(vec![4], DType::Float32);
+        let tensor = Tensor::from_f32(&meta, &[1.0, 2.0, 3.0, 4.0]);
+        let buffer = TensorBuffer::cpu(tensor.data.clone());
+
+        // CPU buffers are streamed even if the peer accepts IPC handles
+        let sender = TensorSender::new();
+        let frames = sender.encode_buffer(&meta, &buffer, true).unwrap();
+        assert_eq!(frames.len(), sender.encode_tensor(&tensor).len());
+
+        let mut receiver = TensorReceiver::new();
+        for frame in frames {
+            receiver.feed(&frame.encode());
+        }
+        while !matches!(receiver.poll().unwrap(), ReceiverEvent::End) {}
+        assert_eq!(receiver.take_tensor().unwrap().data, tensor.data);
+
+        let short = TensorBuffer::cpu_zeros(8);
+        assert!(sender.encode_buffer(&meta, &short, false).is_err());
+    }
+
     #[test]
     fn test_pooled_receiver_cpu_tensor() {
         use crate::pool::{PinnedMemoryPool, PoolConfig};
@@ -23,7 +23,7 @@
 use bytes::{Bytes, BytesMut};
 
 use crate::buffer::{GpuResult, TensorBuffer};
-use crate::dtype::{DType, Element};
+use crate::dtype::{pack_int4, pack_uint4, unpack_int4, unpack_uint4, DType, Element};
 
 /// Device where the tensor data is located.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -155,9 +155,11 @@ impl TensorMeta {
     }
 
     /// Returns the total size in bytes of the tensor data.
+    ///
+    /// Packed dtypes round up to whole bytes.
     #[inline]
     pub fn byte_size(&self) -> usize {
-        self.numel() * self.dtype.element_size()
+        self.dtype.byte_size(self.numel())
     }
 
     /// Returns the number of dimensions.
@@ -167,6 +169,8 @@ impl TensorMeta {
     }
 
     /// Returns whether the tensor has a contiguous memory layout.
+    ///
+    /// Dimensions of size one may have any stride.
     pub fn is_contiguous(&self) -> bool {
         match &self.strides {
             None => true,
@@ -176,8 +180,8 @@ impl TensorMeta {
                 }
                 // Check if strides match row-major (C) order
                 let mut expected_stride = 1;
-                for (i, &dim) in self.shape.iter().enumerate().rev() {
-                    if strides[i] != expected_stride {
+                for (&dim, &stride) in self.shape.iter().zip(strides).rev() {
+                    if dim != 1 && stride != expected_stride {
                         return false;
                     }
                     expected_stride *= dim;
@@ -187,6 +191,29 @@ impl TensorMeta {
         }
     }
 
+    /// Returns the size in bytes of the storage the layout spans.
+    ///
+    /// Equals [`byte_size`](Self::byte_size) for contiguous tensors. Strided
+    /// views span up to their furthest element, so a transpose needs as many
+    /// bytes as the original and a broadcast (stride 0) fewer.
+    pub fn storage_byte_size(&self) -> usize {
+        match &self.strides {
+            Some(strides) if !self.is_contiguous() => {
+                if self.numel() == 0 {
+                    return 0;
+                }
+                let last: usize = self
+                    .shape
+                    .iter()
+                    .zip(strides)
+                    .map(|(&dim, &stride)| (dim - 1) * stride)
+                    .sum();
+                self.dtype.byte_size(last + 1)
+            }
+            _ => self.byte_size(),
+        }
+    }
+
     /// Computes default row-major (C-order) strides for this shape.
     pub fn default_strides(&self) -> Vec<usize> {
         let mut strides = vec![1; self.shape.len()];
@@ -223,7 +250,9 @@ impl TensorMeta {
 /// A tensor with owned data.
 ///
 /// The data is stored in row-major (C) order as raw bytes, allowing
-/// zero-copy transfer over the wire.
+/// zero-copy transfer over the wire. When the metadata carries
+/// non-contiguous strides, the data is the backing storage of a strided
+/// view instead; use [`Tensor::to_contiguous`] to get row-major data.
 #[derive(Debug, Clone)]
 pub struct Tensor {
     /// Tensor metadata
@@ -235,16 +264,28 @@ pub struct Tensor {
 impl Tensor {
     /// Creates a new tensor from metadata and raw bytes.
     ///
+    /// For strided metadata, `data` is the storage the strides index into.
+    ///
     /// # Panics
     ///
-    /// Panics if the data length doesn't match the expected byte size.
+    /// Panics if the data length doesn't match the expected byte size, or
+    /// if the strides don't have one entry per dimension.
     pub fn new(meta: TensorMeta, data: Bytes) -> Self {
+        if let Some(strides) = &meta.strides {
+            assert_eq!(
+                strides.len(),
+                meta.shape.len(),
+                "Strides {:?} don't match shape {:?}",
+                strides,
+                meta.shape
+            );
+        }
         assert_eq!(
             data.len(),
-            meta.byte_size(),
+            meta.storage_byte_size(),
             "Data length {} doesn't match expected byte size {}",
             data.len(),
-            meta.byte_size()
+            meta.storage_byte_size()
         );
         Self { meta, data }
     }
@@ -313,9 +354,43 @@ impl Tensor {
         }
     }
 
+    /// Creates a new Int4 tensor, packing two values per byte.
+    ///
+    /// Values are truncated to 4 bits, so they should lie in `-8..=7`.
+    pub fn from_int4(meta: &TensorMeta, data: &[i8]) -> Self {
+        assert_eq!(meta.dtype, DType::Int4, "Metadata dtype must be Int4");
+        assert_eq!(
+            data.len(),
+            meta.numel(),
+            "Data length doesn't match tensor shape"
+        );
+
+        Self {
+            meta: meta.clone(),
+            data: Bytes::from(pack_int4(data)),
+        }
+    }
+
+    /// Creates a new UInt4 tensor, packing two values per byte.
+    ///
+    /// Values are truncated to 4 bits, so they should lie in `0..=15`.
+    pub fn from_uint4(meta: &TensorMeta, data: &[u8]) -> Self {
+        assert_eq!(meta.dtype, DType::UInt4, "Metadata dtype must be UInt4");
+        assert_eq!(
+            data.len(),
+            meta.numel(),
+            "Data length doesn't match tensor shape"
+        );
+
+        Self {
+            meta: meta.clone(),
+            data: Bytes::from(pack_uint4(data)),
+        }
+    }
+
     /// Creates a new tensor filled with zeros.
     pub fn zeros(meta: TensorMeta) -> Self {
-        let data = Bytes::from(vec![0u8; meta.byte_size()]);
+        let data = Bytes::from(vec![0u8; meta.storage_byte_size()]);
         Self { meta, data }
     }
 
@@ -343,6 +418,77 @@ impl Tensor {
         self.meta.dtype
     }
 
+    /// Returns whether the data is laid out in row-major (C) order.
+    #[inline]
+    pub fn is_contiguous(&self) -> bool {
+        self.meta.is_contiguous()
+    }
+
+    /// Returns the tensor with its elements in row-major (C) order.
+    ///
+    /// Contiguous tensors share their data; strided views are copied
+    /// element by element (whole rows at a time when the innermost
+    /// dimension is dense, one nibble at a time for packed dtypes). The
+    /// result has no strides set.
+    pub fn to_contiguous(&self) -> Tensor {
+        let mut meta = self.meta.clone();
+        meta.strides = None;
+        let strides = match &self.meta.strides {
+            Some(strides) if !self.is_contiguous() => strides,
+            _ => {
+                return Self {
+                    meta,
+                    data: self.data.clone(),
+                }
+            }
+        };
+
+        let numel = meta.numel();
+        let packed = meta.dtype.is_packed();
+        let mut data = Vec::with_capacity(if packed { numel } else { meta.byte_size() });
+        if numel > 0 {
+            let shape = &meta.shape;
+            let element_size = meta.dtype.element_size();
+            let ndim = shape.len();
+
+            // Copy the innermost dimension in one go when it is dense and
+            // elements are whole bytes
+            let (outer, run) = match strides.last() {
+                Some(1) if !packed => (ndim - 1, shape[ndim - 1]),
+                _ => (ndim, 1),
+            };
+            let run_bytes = run * element_size;
+
+            let mut index = vec![0usize; outer];
+            for _ in 0..numel / run {
+                let element: usize = index.iter().zip(strides).map(|(&i, &s)| i * s).sum();
+                if packed {
+                    // Gather unpacked nibbles, packed again below
+                    data.push(self.data[element / 2] >> (4 * (element % 2)));
+                } else {
+                    let start = element * element_size;
+                    data.extend_from_slice(&self.data[start..start + run_bytes]);
+                }
+
+                for dim in (0..outer).rev() {
+                    index[dim] += 1;
+                    if index[dim] < shape[dim] {
+                        break;
+                    }
+                    index[dim] = 0;
+                }
+            }
+        }
+        if packed {
+            data = pack_uint4(&data);
+        }
+
+        Self {
+            meta,
+            data: Bytes::from(data),
+        }
+    }
+
     /// Returns a view of the tensor data as the specified element type.
     ///
     /// # Safety
@@ -392,6 +538,26 @@ impl Tensor {
         unsafe { self.as_slice::<i64>() }
     }
 
+    /// Returns the unpacked values of an Int4 tensor in row-major order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if dtype is not Int4.
+    pub fn to_int4(&self) -> Vec<i8> {
+        assert_eq!(self.meta.dtype, DType::Int4, "Tensor dtype must be Int4");
+        unpack_int4(&self.to_contiguous().data, self.meta.numel())
+    }
+
+    /// Returns the unpacked values of a UInt4 tensor in row-major order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if dtype is not UInt4.
+    pub fn to_uint4(&self) -> Vec<u8> {
+        assert_eq!(self.meta.dtype, DType::UInt4, "Tensor dtype must be UInt4");
+        unpack_uint4(&self.to_contiguous().data, self.meta.numel())
+    }
+
     /// Splits this tensor into chunks for streaming.
     ///
     /// Each chunk will be at most `max_chunk_bytes` in size.
@@ -641,6 +807,76 @@ mod tests {
         assert_eq!(strides, vec![12, 4, 1]);
     }
 
+    #[test]
+    fn test_is_contiguous() {
+        let meta = TensorMeta::new(vec![2, 1, 3], DType::Float32);
+        assert!(meta.is_contiguous());
+        assert!(meta.clone().with_strides(vec![3, 3, 1]).is_contiguous());
+        // The stride of a size-one dimension doesn't matter
+        assert!(meta.clone().with_strides(vec![3, 7, 1]).is_contiguous());
+        assert!(!meta.clone().with_strides(vec![1, 1, 2]).is_contiguous());
+        assert_eq!(meta.with_strides(vec![1, 1, 2]).storage_byte_size(), 24);
+    }
+
+    #[test]
+    fn test_to_contiguous_transpose() {
+        // A 3x2 transposed view of the row-major 2x3 matrix [[1, 2, 3], [4, 5, 6]]
+        let storage = TensorMeta::new(vec![2, 3], DType::Float32);
+        let original = Tensor::from_f32(&storage, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let view_meta = TensorMeta::new(vec![3, 2], DType::Float32).with_strides(vec![1, 3]);
+        let view = Tensor::new(view_meta, original.data.clone());
+        assert!(!view.is_contiguous());
+
+        let contiguous = view.to_contiguous();
+        assert!(contiguous.is_contiguous());
+        assert_eq!(contiguous.meta.strides, None);
+        assert_eq!(contiguous.as_f32(), &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+
+        // Contiguous tensors share their data
+        let same = original.to_contiguous();
+        assert_eq!(same.data.as_ptr(), original.data.as_ptr());
+    }
+
+    #[test]
+    fn test_to_contiguous_broadcast_and_slice() {
+        // Broadcast a row of 3 to 2x3 with a zero stride
+        let meta = TensorMeta::new(vec![2, 3], DType::Int32).with_strides(vec![0, 1]);
+        assert_eq!(meta.storage_byte_size(), 12);
+        let row = Tensor::from_i32(&TensorMeta::new(vec![3], DType::Int32), &[7, 8, 9]);
+        let broadcast = Tensor::new(meta, row.data);
+        assert_eq!(broadcast.to_contiguous().as_i32(), &[7, 8, 9, 7, 8, 9]);
+
+        // Every other column of a 2x4 matrix
+        let meta = TensorMeta::new(vec![2, 2], DType::Int32).with_strides(vec![4, 2]);
+        let matrix = Tensor::from_i32(
+            &TensorMeta::new(vec![8], DType::Int32),
+            &[0, 1, 2, 3, 4, 5, 6, 7],
+        );
+        // The view spans up to element 6, so 7 elements of storage
+        let columns = Tensor::new(meta, matrix.data.slice(..28));
+        assert_eq!(columns.to_contiguous().as_i32(), &[0, 2, 4, 6]);
+    }
+
+    #[test]
+    fn test_packed_int4_tensor() {
+        let meta = TensorMeta::new(vec![2, 3], DType::Int4);
+        assert_eq!(meta.byte_size(), 3);
+        let tensor = Tensor::from_int4(&meta, &[-8, -1, 0, 1, 2, 7]);
+        assert_eq!(tensor.data.len(), 3);
+        assert_eq!(tensor.to_int4(), vec![-8, -1, 0, 1, 2, 7]);
+
+        // Odd element counts round up to whole bytes
+        let odd = Tensor::from_uint4(&TensorMeta::new(vec![3], DType::UInt4), &[1, 2, 15]);
+        assert_eq!(odd.data.as_ref(), &[0x21, 0x0F]);
+
+        // Transposing a packed tensor moves individual nibbles
+        let view_meta = TensorMeta::new(vec![3, 2], DType::Int4).with_strides(vec![1, 3]);
+        assert_eq!(view_meta.storage_byte_size(), 3);
+        let view = Tensor::new(view_meta, tensor.data.clone());
+        assert_eq!(view.to_int4(), vec![-8, 1, -1, 2, 0, 7]);
+        assert_eq!(view.to_contiguous().data.len(), 3);
+    }
+
     #[test]
     fn test_tensor_meta_builder() {
         let meta = TensorMeta::new(vec![32, 768], DType::Float16)
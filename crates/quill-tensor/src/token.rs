@@ -4,11 +4,17 @@
 
 use bytes::{BufMut, Bytes, BytesMut};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 use futures_core::Stream;
 use pin_project_lite::pin_project;
 
+use crate::latency::{
+    LatencyListener, TokenLatencyMetrics, TokenLatencySummary, TokenLatencyTracker,
+};
+
 /// A single token from a language model.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
@@ -22,6 +28,11 @@ pub struct Token {
     pub position: u32,
     /// Whether this is a special token (BOS, EOS, PAD, etc.).
     pub is_special: bool,
+    /// Optional generation time in microseconds since the stream started.
+    ///
+    /// Measured on the producer's monotonic clock, so it is unaffected by
+    /// batching and network delays. See [`crate::latency`].
+    pub timestamp_us: Option<u64>,
 }
 
 impl Token {
@@ -33,6 +44,7 @@ impl Token {
             logprob: None,
             position,
             is_special: false,
+            timestamp_us: None,
         }
     }
 
@@ -44,6 +56,7 @@ impl Token {
             logprob: None,
             position,
             is_special: false,
+            timestamp_us: None,
         }
     }
 
@@ -59,13 +72,21 @@ impl Token {
         self
     }
 
+    /// Sets the generation time in microseconds since the stream started.
+    pub fn with_timestamp_us(mut self, timestamp_us: u64) -> Self {
+        self.timestamp_us = Some(timestamp_us);
+        self
+    }
+
     /// Encodes this token to bytes.
     ///
     /// Wire format:
     /// - id: u32 (4 bytes)
     /// - position: u32 (4 bytes)
-    /// - flags: u8 (1 byte) - bit 0: has_text, bit 1: has_logprob, bit 2: is_special
+    /// - flags: u8 (1 byte) - bit 0: has_text, bit 1: has_logprob, bit 2: is_special,
+    ///   bit 3: has_timestamp
     /// - logprob: f32 (4 bytes, optional)
+    /// - timestamp_us: u64 (8 bytes, optional)
     /// - text_len: u16 (2 bytes, optional)
     /// - text: [u8; text_len] (optional)
     pub fn encode(&self) -> Bytes {
@@ -76,13 +97,18 @@ impl Token {
 
         let flags = (self.text.is_some() as u8)
             | ((self.logprob.is_some() as u8) << 1)
-            | ((self.is_special as u8) << 2);
+            | ((self.is_special as u8) << 2)
+            | ((self.timestamp_us.is_some() as u8) << 3);
         buf.put_u8(flags);
 
         if let Some(logprob) = self.logprob {
             buf.put_f32(logprob);
         }
 
+        if let Some(timestamp_us) = self.timestamp_us {
+            buf.put_u64(timestamp_us);
+        }
+
         if let Some(ref text) = self.text {
             let text_bytes = text.as_bytes();
             buf.put_u16(text_bytes.len() as u16);
@@ -105,6 +131,7 @@ impl Token {
         let has_text = (flags & 0x01) != 0;
         let has_logprob = (flags & 0x02) != 0;
         let is_special = (flags & 0x04) != 0;
+        let has_timestamp = (flags & 0x08) != 0;
 
         let mut offset = 9;
 
@@ -124,6 +151,14 @@ impl Token {
             None
         };
 
+        let timestamp_us = if has_timestamp {
+            let bytes = data.get(offset..offset + 8)?;
+            offset += 8;
+            Some(u64::from_be_bytes(bytes.try_into().ok()?))
+        } else {
+            None
+        };
+
         let text = if has_text {
             if data.len() < offset + 2 {
                 return None;
@@ -148,6 +183,7 @@ impl Token {
                 logprob,
                 position,
                 is_special,
+                timestamp_us,
             },
             offset,
         ))
@@ -317,16 +353,50 @@ impl<'a> IntoIterator for &'a TokenBatch {
 
 pin_project! {
     /// A stream of token batches for LLM generation.
+    ///
+    /// Tracks the latency of timestamped tokens as batches pass through (see
+    /// [`crate::latency`]); the summary is reported once, when the inner
+    /// stream ends or yields its final batch.
     pub struct TokenStream<S> {
         #[pin]
         inner: S,
+        latency: TokenLatencyTracker,
+        metrics: Option<Arc<TokenLatencyMetrics>>,
+        on_complete: Option<LatencyListener>,
+        completed: bool,
     }
 }
 
 impl<S> TokenStream<S> {
     /// Creates a new token stream from an inner stream.
     pub fn new(inner: S) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            latency: TokenLatencyTracker::new(),
+            metrics: None,
+            on_complete: None,
+            completed: false,
+        }
+    }
+
+    /// Records the latency summary into `metrics` on completion.
+    pub fn with_metrics(mut self, metrics: Arc<TokenLatencyMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Calls `listener` with the latency summary on completion.
+    pub fn on_complete<F>(mut self, listener: F) -> Self
+    where
+        F: Fn(&TokenLatencySummary) + Send + Sync + 'static,
+    {
+        self.on_complete = Some(Arc::new(listener));
+        self
+    }
+
+    /// Returns the latency of the tokens received so far.
+    pub fn latency(&self) -> TokenLatencySummary {
+        self.latency.summary()
     }
 
     /// Consumes this wrapper and returns the inner stream.
@@ -342,34 +412,61 @@ where
     type Item = Result<TokenBatch, E>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.project().inner.poll_next(cx)
+        let this = self.project();
+        let item = futures_core::ready!(this.inner.poll_next(cx));
+        let done = match &item {
+            Some(Ok(batch)) => {
+                this.latency.record(batch);
+                batch.is_final
+            }
+            Some(Err(_)) => false,
+            None => true,
+        };
+        if done && !*this.completed {
+            *this.completed = true;
+            let summary = this.latency.summary();
+            if let Some(metrics) = this.metrics.as_ref() {
+                metrics.record(&summary);
+            }
+            if let Some(listener) = this.on_complete.as_ref() {
+                listener(&summary);
+            }
+        }
+        Poll::Ready(item)
     }
 }
 
 /// Builder for creating token batches incrementally.
+///
+/// Batches are flushed when they reach the max size, when their oldest
+/// token reaches the max age (see [`with_max_age`](Self::with_max_age)),
+/// or on an explicit [`flush`](Self::flush). Nothing wakes the builder up
+/// by itself: async producers should wait until [`deadline`](Self::deadline)
+/// alongside the next token and then call [`flush_due`](Self::flush_due).
 #[derive(Debug, Default)]
 pub struct TokenBatchBuilder {
     tokens: Vec<Token>,
     sequence_id: Option<u32>,
     max_size: usize,
+    max_age: Option<Duration>,
+    immediate_first_token: bool,
+    stream_start: Option<Instant>,
+    oldest: Option<Instant>,
+    flushed: bool,
 }
 
 impl TokenBatchBuilder {
     /// Creates a new builder with default max batch size.
     pub fn new() -> Self {
-        Self {
-            tokens: Vec::new(),
-            sequence_id: None,
-            max_size: 32,
-        }
+        Self::with_max_size(32)
     }
 
     /// Creates a builder with the specified max batch size.
     pub fn with_max_size(max_size: usize) -> Self {
         Self {
             tokens: Vec::with_capacity(max_size),
-            sequence_id: None,
             max_size,
+            ..Self::default()
         }
     }
 
@@ -379,11 +476,44 @@ impl TokenBatchBuilder {
         self
     }
 
-    /// Adds a token, returning a batch if the max size is reached.
-    pub fn push(&mut self, token: Token) -> Option<TokenBatch> {
+    /// Flushes a batch once its oldest token has waited `max_age`.
+    ///
+    /// Bounds the latency batching adds when generation is slow.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Emits the first token of the stream as a batch of its own.
+    ///
+    /// Keeps time-to-first-token unaffected by batching.
+    pub fn with_immediate_first_token(mut self, enabled: bool) -> Self {
+        self.immediate_first_token = enabled;
+        self
+    }
+
+    /// Stamps each pushed token with its generation time since `stream_start`.
+    ///
+    /// Use the time the request arrived, so clients can measure
+    /// time-to-first-token and inter-token latency (see [`crate::latency`]).
+    /// Tokens that already carry a timestamp keep it.
+    pub fn with_timestamps(mut self, stream_start: Instant) -> Self {
+        self.stream_start = Some(stream_start);
+        self
+    }
+
+    /// Adds a token, returning a batch if the max size or age is reached.
+    pub fn push(&mut self, mut token: Token) -> Option<TokenBatch> {
+        let now = Instant::now();
+        if let (Some(start), None) = (self.stream_start, token.timestamp_us) {
+            token.timestamp_us = Some(now.saturating_duration_since(start).as_micros() as u64);
+        }
         self.tokens.push(token);
+        let oldest = *self.oldest.get_or_insert(now);
 
-        if self.tokens.len() >= self.max_size {
+        let first = self.immediate_first_token && !self.flushed;
+        let expired = self.max_age.is_some_and(|age| now.duration_since(oldest) >= age);
+        if first || expired || self.tokens.len() >= self.max_size {
             Some(self.flush())
         } else {
             None
@@ -392,6 +522,8 @@ impl TokenBatchBuilder {
 
     /// Flushes accumulated tokens into a batch.
     pub fn flush(&mut self) -> TokenBatch {
+        self.oldest = None;
+        self.flushed = true;
         TokenBatch {
             tokens: std::mem::take(&mut self.tokens),
             sequence_id: self.sequence_id,
@@ -399,6 +531,24 @@ impl TokenBatchBuilder {
         }
     }
 
+    /// Returns when the pending tokens reach the max age, if ever.
+    pub fn deadline(&self) -> Option<Instant> {
+        Some(self.oldest? + self.max_age?)
+    }
+
+    /// Flushes the pending tokens if they reached the max age.
+    pub fn flush_due(&mut self) -> Option<TokenBatch> {
+        self.flush_due_at(Instant::now())
+    }
+
+    /// Flushes the pending tokens if they reached the max age by `now`.
+    pub fn flush_due_at(&mut self, now: Instant) -> Option<TokenBatch> {
+        match self.deadline() {
+            Some(deadline) if deadline <= now => Some(self.flush()),
+            _ => None,
+        }
+    }
+
     /// Creates the final batch with remaining tokens.
     pub fn finish(mut self) -> TokenBatch {
         TokenBatch {
@@ -451,6 +601,19 @@ mod tests {
         assert_eq!(decoded.is_special, token.is_special);
     }
 
+    #[test]
+    fn test_token_timestamp_encode_decode() {
+        let token = Token::with_text(7, "hi", 3).with_logprob(-0.25).with_timestamp_us(1_500_042);
+        let encoded = token.encode();
+        let (decoded, consumed) = Token::decode(&encoded).unwrap();
+        assert_eq!(decoded, token);
+        assert_eq!(consumed, encoded.len());
+
+        // Truncated timestamps are rejected
+        assert!(Token::decode(&encoded[..15]).is_none());
+        assert_eq!(Token::decode(&Token::new(7, 3).encode()).unwrap().0.timestamp_us, None);
+    }
+
     #[test]
     fn test_token_batch() {
         let mut batch = TokenBatch::new();
@@ -501,4 +664,89 @@ mod tests {
         assert!(final_batch.is_final);
         assert_eq!(final_batch.len(), 1);
     }
+
+    #[test]
+    fn test_batch_builder_max_age() {
+        let mut builder = TokenBatchBuilder::with_max_size(8)
+            .with_max_age(Duration::from_millis(50))
+            .with_sequence_id(3);
+        assert!(builder.deadline().is_none());
+
+        assert!(builder.push(Token::new(1, 0)).is_none());
+        let deadline = builder.deadline().unwrap();
+        assert!(builder.push(Token::new(2, 1)).is_none());
+        // The deadline follows the oldest pending token
+        assert_eq!(builder.deadline(), Some(deadline));
+
+        assert!(builder.flush_due_at(deadline - Duration::from_millis(1)).is_none());
+        let batch = builder.flush_due_at(deadline).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.sequence_id, Some(3));
+        assert!(!batch.is_final);
+        assert!(builder.deadline().is_none());
+        assert!(builder.flush_due_at(deadline + Duration::from_secs(1)).is_none());
+    }
+
+    #[test]
+    fn test_batch_builder_expired_on_push() {
+        let mut builder = TokenBatchBuilder::with_max_size(8).with_max_age(Duration::ZERO);
+        let batch = builder.push(Token::new(1, 0)).unwrap();
+        assert_eq!(batch.len(), 1);
+    }
+
+    #[test]
+    fn test_batch_builder_timestamps() {
+        let start = Instant::now() - Duration::from_millis(20);
+        let mut builder = TokenBatchBuilder::with_max_size(2).with_timestamps(start);
+        builder.push(Token::new(1, 0));
+        let batch = builder.push(Token::new(2, 1).with_timestamp_us(5)).unwrap();
+
+        assert!(batch.tokens[0].timestamp_us.unwrap() >= 20_000);
+        assert_eq!(batch.tokens[1].timestamp_us, Some(5));
+        assert_eq!(TokenBatchBuilder::new().finish().tokens.len(), 0);
+        let mut plain = TokenBatchBuilder::new();
+        plain.push(Token::new(1, 0));
+        assert_eq!(plain.finish().tokens[0].timestamp_us, None);
+    }
+
+    #[test]
+    fn test_batch_builder_immediate_first_token() {
+        let mut builder = TokenBatchBuilder::with_max_size(2).with_immediate_first_token(true);
+
+        assert_eq!(builder.push(Token::new(1, 0)).unwrap().len(), 1);
+        assert!(builder.push(Token::new(2, 1)).is_none());
+        assert_eq!(builder.push(Token::new(3, 2)).unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_token_stream_reports_latency_on_completion() {
+        use crate::latency::TokenLatencyMetrics;
+        use futures::StreamExt;
+        use std::sync::Mutex;
+
+        let stamped = |id: u32, ms: u64| Token::new(id, id).with_timestamp_us(ms * 1000);
+        let batches: Vec<Result<TokenBatch, String>> = vec![
+            Ok(TokenBatch::with_tokens(vec![stamped(0, 50), stamped(1, 70)])),
+            Ok(TokenBatch::final_batch(vec![stamped(2, 100)])),
+        ];
+        let metrics = Arc::new(TokenLatencyMetrics::new());
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reported);
+        let mut stream = TokenStream::new(futures::stream::iter(batches))
+            .with_metrics(Arc::clone(&metrics))
+            .on_complete(move |summary| sink.lock().unwrap().push(*summary));
+
+        stream.next().await.unwrap().unwrap();
+        assert_eq!(stream.latency().time_to_first_token, Some(Duration::from_millis(50)));
+        assert!(reported.lock().unwrap().is_empty());
+
+        // Reported at the final batch, and not again when the stream ends
+        stream.next().await.unwrap().unwrap();
+        assert!(stream.next().await.is_none());
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].tokens, 3);
+        assert_eq!(reported[0].mean_inter_token, Some(Duration::from_millis(25)));
+        assert_eq!(metrics.stats().streams, 1);
+    }
 }
@@ -5,12 +5,16 @@
 //! - 0-RTT connection resumption
 //! - HTTP/3 datagrams for unreliable messaging
 //! - Connection migration
+//! - Per-call transport statistics (congestion window, RTT, loss)
+//! - UDP segmentation offload (GSO/GRO) and receive batching
 
 #[cfg(feature = "http3")]
 use bytes::Bytes;
 #[cfg(feature = "http3")]
 use http::{Request, Response, StatusCode};
 #[cfg(feature = "http3")]
+use crate::reload::ConfigSource;
+#[cfg(feature = "http3")]
 use quill_core::PrismProfile;
 #[cfg(feature = "http3")]
 use std::future::Future;
@@ -19,9 +23,11 @@ use std::net::SocketAddr;
 #[cfg(feature = "http3")]
 use std::pin::Pin;
 #[cfg(feature = "http3")]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(feature = "http3")]
 use std::sync::Arc;
 #[cfg(feature = "http3")]
-use std::time::Duration;
+use std::time::{Duration, Instant};
 #[cfg(feature = "http3")]
 use thiserror::Error;
 #[cfg(feature = "http3")]
@@ -56,6 +62,18 @@ pub struct HyperConfig {
     pub keep_alive_interval_ms: u64,
     /// Idle timeout (milliseconds)
     pub idle_timeout_ms: u64,
+    /// Advertise CRC32 checksums on datagrams (used only if the peer agrees)
+    pub enable_datagram_checksum: bool,
+    /// Send several packets per syscall with GSO where the host supports it
+    pub enable_segmentation_offload: bool,
+    /// Largest UDP payload accepted (1200-65527 bytes)
+    ///
+    /// Sizes the receive buffers: each syscall reads up to
+    /// [`UdpOffloadSupport::recv_batch_size`] datagrams of this size, times
+    /// the GRO segments. Raise it on loopback or jumbo-frame links.
+    pub max_udp_payload_size: u16,
+    /// Largest request body a server reads; larger requests get `413`
+    pub max_request_body_size: usize,
 }
 
 #[cfg(feature = "http3")]
@@ -69,7 +87,113 @@ impl Default for HyperConfig {
             max_datagram_size: 65536,
             keep_alive_interval_ms: 30000,
             idle_timeout_ms: 60000,
+            enable_datagram_checksum: false,
+            enable_segmentation_offload: true,
+            max_udp_payload_size: 1472, // Ethernet MTU minus IP and UDP headers
+            max_request_body_size: quill_core::framing::MAX_FRAME_SIZE,
+        }
+    }
+}
+
+#[cfg(feature = "http3")]
+impl HyperConfig {
+    /// quinn endpoint settings
+    pub(crate) fn endpoint_config(&self) -> Result<quinn::EndpointConfig, HyperError> {
+        let mut config = quinn::EndpointConfig::default();
+        config.max_udp_payload_size(self.max_udp_payload_size).map_err(|_| {
+            HyperError::Config(format!(
+                "max_udp_payload_size must be between 1200 and 65527, got {}",
+                self.max_udp_payload_size
+            ))
+        })?;
+        Ok(config)
+    }
+
+    /// Bind a QUIC client endpoint on an ephemeral port
+    pub(crate) fn client_endpoint(&self) -> Result<quinn::Endpoint, HyperError> {
+        let endpoint_config = self.endpoint_config()?;
+        std::net::UdpSocket::bind("0.0.0.0:0")
+            .and_then(|socket| {
+                let runtime = quinn::default_runtime()
+                    .ok_or_else(|| std::io::Error::other("no async runtime found"))?;
+                quinn::Endpoint::new(endpoint_config, None, socket, runtime)
+            })
+            .map_err(|e| HyperError::QuicConnection(format!("Failed to create endpoint: {}", e)))
+    }
+}
+
+/// UDP offload and batching support of the host, as quinn uses it
+#[cfg(feature = "http3")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UdpOffloadSupport {
+    /// Packets sent per syscall with GSO (1 = unsupported)
+    pub max_gso_segments: usize,
+    /// Packets coalesced per received datagram with GRO (1 = unsupported)
+    pub gro_segments: usize,
+    /// Datagrams read per receive syscall
+    pub recv_batch_size: usize,
+}
+
+#[cfg(feature = "http3")]
+impl UdpOffloadSupport {
+    /// Probe the host with a loopback UDP socket
+    pub fn detect() -> std::io::Result<Self> {
+        let socket = std::net::UdpSocket::bind((std::net::Ipv4Addr::LOCALHOST, 0))?;
+        let state = quinn::udp::UdpSocketState::new((&socket).into())?;
+        Ok(Self {
+            max_gso_segments: state.max_gso_segments(),
+            gro_segments: state.gro_segments(),
+            recv_batch_size: quinn::udp::BATCH_SIZE,
+        })
+    }
+
+    /// Whether outgoing packets can be segmented by the kernel
+    pub fn gso(&self) -> bool {
+        self.max_gso_segments > 1
+    }
+
+    /// Whether incoming packets can be coalesced by the kernel
+    pub fn gro(&self) -> bool {
+        self.gro_segments > 1
+    }
+
+    /// Most bytes one receive syscall can return with `max_udp_payload_size`
+    pub fn recv_batch_bytes(&self, max_udp_payload_size: u16) -> usize {
+        max_udp_payload_size as usize * self.gro_segments * self.recv_batch_size
+    }
+}
+
+#[cfg(feature = "http3")]
+impl std::fmt::Display for UdpOffloadSupport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let segments = |n: usize| {
+            if n > 1 {
+                format!("{} segments", n)
+            } else {
+                "unsupported".to_string()
+            }
+        };
+        write!(
+            f,
+            "GSO {}, GRO {}, {} datagrams per receive",
+            segments(self.max_gso_segments),
+            segments(self.gro_segments),
+            self.recv_batch_size
+        )
+    }
+}
+
+/// Log the host's UDP offload support next to the configuration using it
+#[cfg(feature = "http3")]
+pub(crate) fn log_udp_offload(config: &HyperConfig) {
+    match UdpOffloadSupport::detect() {
+        Ok(support) => {
+            info!("UDP offload: {}", support);
+            if config.enable_segmentation_offload && !support.gso() {
+                info!("GSO unavailable; QUIC packets are sent one per syscall");
+            }
         }
+        Err(e) => debug!("Could not probe UDP offload support: {}", e),
     }
 }
 
@@ -151,6 +275,317 @@ impl Datagram {
             })
         }
     }
+
+    /// Encode the datagram with a trailing checksum
+    ///
+    /// Format: [flow_id (optional varint)][payload][crc32 (4 bytes, big-endian)]
+    ///
+    /// The checksum covers the flow_id as well, so a corrupted flow_id is
+    /// detected rather than silently misrouted.
+    pub fn encode_with_checksum(&self) -> Bytes {
+        append_checksum(self.encode())
+    }
+
+    /// Decode a datagram that carries a trailing checksum
+    ///
+    /// Returns `HyperError::Datagram` if the checksum does not match.
+    pub fn decode_with_checksum(data: Bytes, expect_flow_id: bool) -> Result<Self, HyperError> {
+        let data = verify_checksum(data)?;
+        Self::decode(data, expect_flow_id)
+    }
+}
+
+/// Size of the datagram checksum trailer in bytes
+#[cfg(feature = "http3")]
+pub const DATAGRAM_CHECKSUM_LEN: usize = 4;
+
+/// Header used to exchange datagram capabilities between peers
+#[cfg(feature = "http3")]
+pub const DATAGRAM_CAPABILITIES_HEADER: &str = "Quill-Datagram-Caps";
+
+/// Append a CRC32 checksum to an encoded datagram
+#[cfg(feature = "http3")]
+fn append_checksum(data: Bytes) -> Bytes {
+    let checksum = crc32fast::hash(&data);
+    let mut buf = Vec::with_capacity(data.len() + DATAGRAM_CHECKSUM_LEN);
+    buf.extend_from_slice(&data);
+    buf.extend_from_slice(&checksum.to_be_bytes());
+    Bytes::from(buf)
+}
+
+/// Verify and strip the CRC32 checksum trailer from a received datagram
+#[cfg(feature = "http3")]
+fn verify_checksum(data: Bytes) -> Result<Bytes, HyperError> {
+    if data.len() < DATAGRAM_CHECKSUM_LEN {
+        return Err(HyperError::Datagram(format!(
+            "Datagram too short for checksum: {} bytes",
+            data.len()
+        )));
+    }
+
+    let split = data.len() - DATAGRAM_CHECKSUM_LEN;
+    let mut expected = [0u8; DATAGRAM_CHECKSUM_LEN];
+    expected.copy_from_slice(&data[split..]);
+    let expected = u32::from_be_bytes(expected);
+    let actual = crc32fast::hash(&data[..split]);
+
+    if actual != expected {
+        return Err(HyperError::Datagram(format!(
+            "Datagram checksum mismatch: expected {:08x}, got {:08x}",
+            expected, actual
+        )));
+    }
+
+    Ok(data.slice(..split))
+}
+
+/// Datagram capabilities advertised by a peer
+///
+/// Exchanged via the `Quill-Datagram-Caps` header, e.g. `checksum=crc32`.
+/// Optional features are only used when both peers advertise them.
+#[cfg(feature = "http3")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DatagramCapabilities {
+    /// Peer can send and verify CRC32 checksums
+    pub checksum: bool,
+}
+
+#[cfg(feature = "http3")]
+impl DatagramCapabilities {
+    /// Capabilities advertised by a local configuration
+    pub fn from_config(config: &HyperConfig) -> Self {
+        Self {
+            checksum: config.enable_datagrams && config.enable_datagram_checksum,
+        }
+    }
+
+    /// Format as a header value
+    pub fn to_header_value(&self) -> String {
+        let mut caps = Vec::new();
+        if self.checksum {
+            caps.push("checksum=crc32");
+        }
+        caps.join(", ")
+    }
+
+    /// Parse from a header value
+    ///
+    /// Unknown capabilities are ignored.
+    pub fn from_header_value(value: &str) -> Self {
+        let mut caps = Self::default();
+        for item in value.split(',') {
+            if item.trim() == "checksum=crc32" {
+                caps.checksum = true;
+            }
+        }
+        caps
+    }
+
+    /// Capabilities usable by both sides
+    pub fn negotiate(&self, peer: &DatagramCapabilities) -> Self {
+        Self {
+            checksum: self.checksum && peer.checksum,
+        }
+    }
+}
+
+/// Datagram receive statistics for a connection
+#[cfg(feature = "http3")]
+#[derive(Debug, Clone, Default)]
+pub struct DatagramStats {
+    /// Number of datagrams received
+    pub received: u64,
+    /// Number of datagrams whose checksum was verified
+    pub verified: u64,
+    /// Number of datagrams dropped due to a checksum mismatch
+    pub checksum_failures: u64,
+}
+
+/// Snapshot of QUIC transport statistics for a connection
+#[cfg(feature = "http3")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransportStats {
+    /// Time since the call started when the snapshot was taken
+    pub elapsed: Duration,
+    /// Smoothed round-trip time estimate
+    pub rtt: Duration,
+    /// Congestion window in bytes
+    pub cwnd: u64,
+    /// Number of congestion events
+    pub congestion_events: u64,
+    /// Packets sent
+    pub sent_packets: u64,
+    /// Packets declared lost
+    pub lost_packets: u64,
+    /// Bytes declared lost
+    pub lost_bytes: u64,
+    /// Largest UDP payload the path currently supports
+    pub current_mtu: u16,
+    /// DATAGRAM frames sent
+    pub datagrams_sent: u64,
+    /// DATAGRAM frames received
+    pub datagrams_received: u64,
+    /// Received datagrams dropped by the receiver (failed checksum)
+    pub datagrams_dropped: u64,
+}
+
+#[cfg(feature = "http3")]
+impl TransportStats {
+    /// Build a snapshot from quinn connection statistics
+    pub fn from_connection_stats(stats: &quinn::ConnectionStats) -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            rtt: stats.path.rtt,
+            cwnd: stats.path.cwnd,
+            congestion_events: stats.path.congestion_events,
+            sent_packets: stats.path.sent_packets,
+            lost_packets: stats.path.lost_packets,
+            lost_bytes: stats.path.lost_bytes,
+            current_mtu: stats.path.current_mtu,
+            datagrams_sent: stats.frame_tx.datagram,
+            datagrams_received: stats.frame_rx.datagram,
+            datagrams_dropped: 0,
+        }
+    }
+
+    /// Include drops recorded by the datagram receiver
+    pub fn with_datagram_stats(mut self, stats: &DatagramStats) -> Self {
+        self.datagrams_dropped = stats.checksum_failures;
+        self
+    }
+
+    /// Pacing rate implied by the congestion window, in bytes per second
+    ///
+    /// quinn paces at roughly `cwnd / rtt`; `None` before an RTT sample exists.
+    pub fn pacing_rate(&self) -> Option<u64> {
+        let rtt = self.rtt.as_secs_f64();
+        (rtt > 0.0).then(|| (self.cwnd as f64 / rtt) as u64)
+    }
+
+    /// Fraction of sent packets that were lost
+    pub fn loss_rate(&self) -> f64 {
+        if self.sent_packets == 0 {
+            0.0
+        } else {
+            self.lost_packets as f64 / self.sent_packets as f64
+        }
+    }
+}
+
+/// Callback receiving transport statistics snapshots during a call
+#[cfg(feature = "http3")]
+pub type TransportStatsCallback = Arc<dyn Fn(&TransportStats) + Send + Sync>;
+
+/// Periodic transport statistics sampling for a call
+#[cfg(feature = "http3")]
+#[derive(Clone)]
+pub struct StatsSampler {
+    interval: Duration,
+    callback: TransportStatsCallback,
+}
+
+#[cfg(feature = "http3")]
+impl StatsSampler {
+    /// Sample every `interval` while the call is in flight
+    pub fn new<F>(interval: Duration, callback: F) -> Self
+    where
+        F: Fn(&TransportStats) + Send + Sync + 'static,
+    {
+        Self {
+            interval: interval.max(Duration::from_millis(1)),
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Sampling interval
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Spawn a task sampling `conn` until the returned handle is aborted
+    fn spawn(&self, conn: quinn::Connection, start: Instant) -> tokio::task::JoinHandle<()> {
+        let sampler = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(sampler.interval);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let mut stats = TransportStats::from_connection_stats(&conn.stats());
+                stats.elapsed = start.elapsed();
+                (sampler.callback)(&stats);
+            }
+        })
+    }
+}
+
+#[cfg(feature = "http3")]
+impl std::fmt::Debug for StatsSampler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StatsSampler")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "http3")]
+#[derive(Default)]
+struct DatagramStatsAtomic {
+    received: AtomicU64,
+    verified: AtomicU64,
+    checksum_failures: AtomicU64,
+}
+
+#[cfg(feature = "http3")]
+impl DatagramStatsAtomic {
+    fn to_stats(&self) -> DatagramStats {
+        DatagramStats {
+            received: self.received.load(Ordering::Relaxed),
+            verified: self.verified.load(Ordering::Relaxed),
+            checksum_failures: self.checksum_failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Per-connection datagram state shared by the sender and receive task
+#[cfg(feature = "http3")]
+#[derive(Clone, Default)]
+struct DatagramState {
+    checksum: Arc<AtomicBool>,
+    stats: Arc<DatagramStatsAtomic>,
+}
+
+#[cfg(feature = "http3")]
+impl DatagramState {
+    fn checksum_enabled(&self) -> bool {
+        self.checksum.load(Ordering::Relaxed)
+    }
+
+    fn set_checksum(&self, enabled: bool) {
+        self.checksum.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Account for a received datagram and strip its checksum if enabled
+    ///
+    /// Returns `None` if the datagram failed verification and should be dropped.
+    fn receive(&self, data: Bytes) -> Option<Bytes> {
+        self.stats.received.fetch_add(1, Ordering::Relaxed);
+        if !self.checksum_enabled() {
+            return Some(data);
+        }
+
+        match verify_checksum(data) {
+            Ok(data) => {
+                self.stats.verified.fetch_add(1, Ordering::Relaxed);
+                Some(data)
+            }
+            Err(e) => {
+                self.stats.checksum_failures.fetch_add(1, Ordering::Relaxed);
+                warn!("Dropping datagram: {}", e);
+                None
+            }
+        }
+    }
 }
 
 /// Encode a u64 as a variable-length integer (QUIC varint format)
@@ -250,20 +685,30 @@ impl DatagramReceiver {
 pub struct DatagramSender {
     conn: quinn::Connection,
     max_size: usize,
+    state: DatagramState,
 }
 
 #[cfg(feature = "http3")]
 impl DatagramSender {
     /// Create a new datagram sender
-    fn new(conn: quinn::Connection, max_size: usize) -> Self {
-        Self { conn, max_size }
+    fn new(conn: quinn::Connection, max_size: usize, state: DatagramState) -> Self {
+        Self {
+            conn,
+            max_size,
+            state,
+        }
     }
 
     /// Send a datagram
     ///
+    /// If checksums were negotiated for the connection, a CRC32 trailer is appended.
     /// Returns an error if the datagram is too large or the connection is closed
     pub fn send(&self, datagram: Datagram) -> Result<(), HyperError> {
-        let encoded = datagram.encode();
+        let encoded = if self.state.checksum_enabled() {
+            datagram.encode_with_checksum()
+        } else {
+            datagram.encode()
+        };
         if encoded.len() > self.max_size {
             return Err(HyperError::Datagram(format!(
                 "Datagram too large: {} > {} bytes",
@@ -278,6 +723,11 @@ impl DatagramSender {
 
     /// Send raw bytes as a datagram
     pub fn send_bytes(&self, data: Bytes) -> Result<(), HyperError> {
+        let data = if self.state.checksum_enabled() {
+            append_checksum(data)
+        } else {
+            data
+        };
         if data.len() > self.max_size {
             return Err(HyperError::Datagram(format!(
                 "Datagram too large: {} > {} bytes",
@@ -294,6 +744,11 @@ impl DatagramSender {
     pub fn max_size(&self) -> usize {
         self.max_size
     }
+
+    /// Check if checksums are appended to outgoing datagrams
+    pub fn checksum_enabled(&self) -> bool {
+        self.state.checksum_enabled()
+    }
 }
 
 /// A persistent HTTP/3 connection with datagram support
@@ -302,6 +757,7 @@ pub struct H3Connection {
     conn: quinn::Connection,
     datagram_sender: DatagramSender,
     datagram_rx: Option<mpsc::Receiver<Datagram>>,
+    datagram_state: DatagramState,
     config: Arc<HyperConfig>,
 }
 
@@ -334,11 +790,40 @@ impl H3Connection {
         self.config.enable_datagrams
     }
 
+    /// Datagram capabilities to advertise to the peer
+    pub fn datagram_capabilities(&self) -> DatagramCapabilities {
+        DatagramCapabilities::from_config(&self.config)
+    }
+
+    /// Apply the capabilities advertised by the peer
+    ///
+    /// Checksums are enabled only if both sides advertise them. Returns the
+    /// negotiated capabilities.
+    pub fn apply_datagram_capabilities(
+        &self,
+        peer: &DatagramCapabilities,
+    ) -> DatagramCapabilities {
+        let negotiated = self.datagram_capabilities().negotiate(peer);
+        self.datagram_state.set_checksum(negotiated.checksum);
+        negotiated
+    }
+
+    /// Get datagram receive statistics
+    pub fn datagram_stats(&self) -> DatagramStats {
+        self.datagram_state.stats.to_stats()
+    }
+
     /// Get connection statistics
     pub fn stats(&self) -> quinn::ConnectionStats {
         self.conn.stats()
     }
 
+    /// Get a transport statistics snapshot including datagram drops
+    pub fn transport_stats(&self) -> TransportStats {
+        TransportStats::from_connection_stats(&self.conn.stats())
+            .with_datagram_stats(&self.datagram_stats())
+    }
+
     /// Close the connection gracefully
     pub fn close(&self, code: u32, reason: &str) {
         self.conn.close(
@@ -398,10 +883,26 @@ impl Default for HyperTransport {
 #[cfg(feature = "http3")]
 pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
 
+/// Response body of an [`H3Service`]
+///
+/// Chunks are sent on the request stream as they are produced. An error
+/// resets the stream, since the response headers are already out.
+#[cfg(feature = "http3")]
+pub type H3Body = Pin<Box<dyn futures::Stream<Item = Result<Bytes, HyperError>> + Send>>;
+
+/// Response body sending `bytes` as a single chunk
+#[cfg(feature = "http3")]
+pub fn full_body(bytes: Bytes) -> H3Body {
+    Box::pin(futures::stream::once(std::future::ready(Ok(bytes))))
+}
+
 /// HTTP/3 service trait for handling requests
+///
+/// The server reads the whole request body, up to
+/// [`HyperConfig::max_request_body_size`], before calling the service.
 #[cfg(feature = "http3")]
 pub trait H3Service: Clone + Send + 'static {
-    fn call(&self, req: Request<()>) -> BoxFuture<Result<Response<Bytes>, StatusCode>>;
+    fn call(&self, req: Request<Bytes>) -> BoxFuture<Result<Response<H3Body>, StatusCode>>;
 }
 
 /// Trait for handling incoming datagrams on the server
@@ -446,13 +947,23 @@ where
 pub struct ServerConnection {
     conn: quinn::Connection,
     config: Arc<HyperConfig>,
+    datagram_state: DatagramState,
 }
 
 #[cfg(feature = "http3")]
 impl ServerConnection {
     /// Get a datagram sender for this connection
     pub fn datagram_sender(&self) -> DatagramSender {
-        DatagramSender::new(self.conn.clone(), self.config.max_datagram_size)
+        DatagramSender::new(
+            self.conn.clone(),
+            self.config.max_datagram_size,
+            self.datagram_state.clone(),
+        )
+    }
+
+    /// Get datagram receive statistics
+    pub fn datagram_stats(&self) -> DatagramStats {
+        self.datagram_state.stats.to_stats()
     }
 
     /// Get the remote address
@@ -464,6 +975,206 @@ impl ServerConnection {
     pub fn stats(&self) -> quinn::ConnectionStats {
         self.conn.stats()
     }
+
+    /// Get a transport statistics snapshot including datagram drops
+    pub fn transport_stats(&self) -> TransportStats {
+        TransportStats::from_connection_stats(&self.conn.stats())
+            .with_datagram_stats(&self.datagram_stats())
+    }
+}
+
+/// PEM data given inline or read from a file when the server starts
+#[cfg(feature = "http3")]
+#[derive(Debug, Clone)]
+enum PemSource {
+    Path(std::path::PathBuf),
+    Bytes(Vec<u8>),
+}
+
+#[cfg(feature = "http3")]
+impl PemSource {
+    fn read(&self) -> Result<std::borrow::Cow<'_, [u8]>, HyperError> {
+        match self {
+            PemSource::Path(path) => std::fs::read(path)
+                .map(std::borrow::Cow::Owned)
+                .map_err(|e| HyperError::Tls(format!("Failed to read {}: {}", path.display(), e))),
+            PemSource::Bytes(bytes) => Ok(std::borrow::Cow::Borrowed(bytes)),
+        }
+    }
+
+    fn certs(&self) -> Result<Vec<rustls::pki_types::CertificateDer<'static>>, HyperError> {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::CertificateDer;
+
+        let certs = CertificateDer::pem_slice_iter(&self.read()?)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| HyperError::Tls(format!("Invalid certificate PEM: {}", e)))?;
+        if certs.is_empty() {
+            return Err(HyperError::Tls("No certificates found in PEM".to_string()));
+        }
+        Ok(certs)
+    }
+
+    fn private_key(&self) -> Result<rustls::pki_types::PrivateKeyDer<'static>, HyperError> {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::PrivateKeyDer;
+
+        PrivateKeyDer::from_pem_slice(&self.read()?)
+            .map_err(|e| HyperError::Tls(format!("Invalid private key PEM: {}", e)))
+    }
+}
+
+/// Certificates of an HTTP/3 server
+///
+/// Without a certificate and key the server generates a self-signed
+/// certificate for `localhost`, which is only suitable for testing. With
+/// client CA certificates the server verifies client certificates (mTLS).
+#[cfg(feature = "http3")]
+#[derive(Debug, Clone, Default)]
+pub struct H3TlsConfig {
+    cert: Option<PemSource>,
+    key: Option<PemSource>,
+    client_ca: Option<PemSource>,
+    client_auth_optional: bool,
+}
+
+#[cfg(feature = "http3")]
+impl H3TlsConfig {
+    /// Load the certificate chain from a PEM file
+    pub fn cert_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.cert = Some(PemSource::Path(path.into()));
+        self
+    }
+
+    /// Load the private key from a PEM file
+    pub fn key_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.key = Some(PemSource::Path(path.into()));
+        self
+    }
+
+    /// Use a PEM-encoded certificate chain
+    pub fn cert_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.cert = Some(PemSource::Bytes(pem.into()));
+        self
+    }
+
+    /// Use a PEM-encoded private key
+    pub fn key_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.key = Some(PemSource::Bytes(pem.into()));
+        self
+    }
+
+    /// Verify client certificates against CA certificates from a PEM file
+    pub fn client_ca_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.client_ca = Some(PemSource::Path(path.into()));
+        self
+    }
+
+    /// Verify client certificates against PEM-encoded CA certificates
+    pub fn client_ca_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.client_ca = Some(PemSource::Bytes(pem.into()));
+        self
+    }
+
+    /// Accept clients without a certificate; presented ones are still verified
+    pub fn client_auth_optional(mut self, optional: bool) -> Self {
+        self.client_auth_optional = optional;
+        self
+    }
+
+    /// Whether a certificate and key were configured
+    pub fn has_certificate(&self) -> bool {
+        self.cert.is_some() && self.key.is_some()
+    }
+
+    /// Whether client certificates are verified
+    pub fn verifies_clients(&self) -> bool {
+        self.client_ca.is_some()
+    }
+
+    /// Reload these certificates whenever their PEM files change
+    ///
+    /// Pass the source to [`H3ServerBuilder::tls_source`]. Inline PEM data
+    /// stays as given; changed files are only used once they all load.
+    pub fn watch(self) -> ConfigSource<H3TlsConfig> {
+        let paths: Vec<_> = [&self.cert, &self.key, &self.client_ca]
+            .into_iter()
+            .flatten()
+            .filter_map(|source| match source {
+                PemSource::Path(path) => Some(path.clone()),
+                PemSource::Bytes(_) => None,
+            })
+            .collect();
+        ConfigSource::files(paths, move || {
+            self.validate().map_err(|e| e.to_string())?;
+            self.server_config().map_err(|e| e.to_string())?;
+            Ok(self.clone())
+        })
+    }
+
+    fn validate(&self) -> Result<(), HyperError> {
+        match (&self.cert, &self.key) {
+            (Some(_), None) => Err(HyperError::Config("TLS certificate given without a private key".to_string())),
+            (None, Some(_)) => Err(HyperError::Config("TLS private key given without a certificate".to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Build the rustls configuration, loading certificates and keys
+    fn server_config(&self) -> Result<rustls::ServerConfig, HyperError> {
+        let (cert_chain, key) = match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => (cert.certs()?, key.private_key()?),
+            _ => Self::self_signed()?,
+        };
+
+        let builder = rustls::ServerConfig::builder();
+        let builder = match &self.client_ca {
+            Some(ca) => {
+                let mut roots = rustls::RootCertStore::empty();
+                for cert in ca.certs()? {
+                    roots
+                        .add(cert)
+                        .map_err(|e| HyperError::Tls(format!("Invalid client CA certificate: {}", e)))?;
+                }
+                let mut verifier = rustls::server::WebPkiClientVerifier::builder(Arc::new(roots));
+                if self.client_auth_optional {
+                    verifier = verifier.allow_unauthenticated();
+                }
+                let verifier = verifier
+                    .build()
+                    .map_err(|e| HyperError::Tls(format!("Failed to build client verifier: {}", e)))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        builder
+            .with_single_cert(cert_chain, key)
+            .map_err(|e| HyperError::Tls(format!("Certificate error: {}", e)))
+    }
+
+    /// Generate a self-signed certificate for testing
+    #[allow(clippy::type_complexity)]
+    fn self_signed() -> Result<
+        (
+            Vec<rustls::pki_types::CertificateDer<'static>>,
+            rustls::pki_types::PrivateKeyDer<'static>,
+        ),
+        HyperError,
+    > {
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
+            .map_err(|e| HyperError::Tls(format!("Failed to generate certificate: {}", e)))?;
+
+        let cert_der = cert.serialize_der()
+            .map_err(|e| HyperError::Tls(format!("Failed to serialize certificate: {}", e)))?;
+        let key_der = cert.serialize_private_key_der();
+
+        let key = PrivateKeyDer::try_from(key_der)
+            .map_err(|_| HyperError::Tls("Failed to parse private key".to_string()))?;
+        Ok((vec![CertificateDer::from(cert_der)], key))
+    }
 }
 
 /// HTTP/3 server builder
@@ -471,57 +1182,205 @@ impl ServerConnection {
 pub struct H3ServerBuilder {
     config: HyperConfig,
     bind_addr: SocketAddr,
+    tls: H3TlsConfig,
+    tls_source: Option<ConfigSource<H3TlsConfig>>,
+    udp_buffer_sizes: UdpBufferSizes,
 }
 
-#[cfg(feature = "http3")]
-impl H3ServerBuilder {
-    /// Create a new HTTP/3 server builder
-    pub fn new(bind_addr: SocketAddr) -> Self {
-        Self {
-            config: HyperConfig::default(),
-            bind_addr,
-        }
+#[cfg(feature = "http3")]
+impl H3ServerBuilder {
+    /// Create a new HTTP/3 server builder
+    pub fn new(bind_addr: SocketAddr) -> Self {
+        Self {
+            config: HyperConfig::default(),
+            bind_addr,
+            tls: H3TlsConfig::default(),
+            tls_source: None,
+            udp_buffer_sizes: UdpBufferSizes::default(),
+        }
+    }
+
+    /// Enable 0-RTT
+    pub fn enable_zero_rtt(mut self, enable: bool) -> Self {
+        self.config.enable_zero_rtt = enable;
+        self
+    }
+
+    /// Enable datagrams
+    pub fn enable_datagrams(mut self, enable: bool) -> Self {
+        self.config.enable_datagrams = enable;
+        self
+    }
+
+    /// Advertise CRC32 checksums on datagrams
+    pub fn enable_datagram_checksum(mut self, enable: bool) -> Self {
+        self.config.enable_datagram_checksum = enable;
+        self
+    }
+
+    /// Set max concurrent streams
+    pub fn max_concurrent_streams(mut self, max: u64) -> Self {
+        self.config.max_concurrent_streams = max;
+        self
+    }
+
+    /// Set idle timeout
+    pub fn idle_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.config.idle_timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Send several packets per syscall with GSO where supported (default on)
+    pub fn enable_segmentation_offload(mut self, enable: bool) -> Self {
+        self.config.enable_segmentation_offload = enable;
+        self
+    }
+
+    /// Set the largest UDP payload accepted, which sizes the receive buffers
+    pub fn max_udp_payload_size(mut self, size: u16) -> Self {
+        self.config.max_udp_payload_size = size;
+        self
+    }
+
+    /// Set the largest request body read; larger requests get `413`
+    pub fn max_request_body_size(mut self, size: usize) -> Self {
+        self.config.max_request_body_size = size;
+        self
+    }
+
+    /// Set the kernel receive buffer size of the UDP socket
+    ///
+    /// OS defaults are often too small for QUIC at high throughput, causing
+    /// dropped packets and retransmits.
+    pub fn udp_recv_buffer_size(mut self, size: usize) -> Self {
+        self.udp_buffer_sizes.recv = Some(size);
+        self
+    }
+
+    /// Set the kernel send buffer size of the UDP socket
+    pub fn udp_send_buffer_size(mut self, size: usize) -> Self {
+        self.udp_buffer_sizes.send = Some(size);
+        self
+    }
+
+    /// Set the server certificates
+    pub fn tls(mut self, tls: H3TlsConfig) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Swap in new server certificates whenever `source` yields them
+    ///
+    /// The server starts with the certificates set by [`tls`](Self::tls) and
+    /// the `tls_*` methods. New handshakes use the latest certificates;
+    /// established connections keep theirs. Certificates that fail to load
+    /// are logged and the current ones stay in use.
+    pub fn tls_source(mut self, source: ConfigSource<H3TlsConfig>) -> Self {
+        self.tls_source = Some(source);
+        self
+    }
+
+    /// Load the certificate chain from a PEM file
+    pub fn tls_cert_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.tls = self.tls.cert_path(path);
+        self
+    }
+
+    /// Load the private key from a PEM file
+    pub fn tls_key_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.tls = self.tls.key_path(path);
+        self
     }
 
-    /// Enable 0-RTT
-    pub fn enable_zero_rtt(mut self, enable: bool) -> Self {
-        self.config.enable_zero_rtt = enable;
+    /// Use a PEM-encoded certificate chain
+    pub fn tls_cert_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls = self.tls.cert_pem(pem);
         self
     }
 
-    /// Enable datagrams
-    pub fn enable_datagrams(mut self, enable: bool) -> Self {
-        self.config.enable_datagrams = enable;
+    /// Use a PEM-encoded private key
+    pub fn tls_key_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls = self.tls.key_pem(pem);
         self
     }
 
-    /// Set max concurrent streams
-    pub fn max_concurrent_streams(mut self, max: u64) -> Self {
-        self.config.max_concurrent_streams = max;
+    /// Require client certificates signed by CAs from a PEM file
+    pub fn tls_client_ca_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.tls = self.tls.client_ca_path(path);
         self
     }
 
-    /// Set idle timeout
-    pub fn idle_timeout_ms(mut self, timeout_ms: u64) -> Self {
-        self.config.idle_timeout_ms = timeout_ms;
+    /// Require client certificates signed by PEM-encoded CAs
+    pub fn tls_client_ca_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls = self.tls.client_ca_pem(pem);
         self
     }
 
     /// Build the HTTP/3 server
+    ///
+    /// Fails if only one of the certificate and private key was given.
     pub fn build(self) -> Result<H3Server, HyperError> {
+        self.tls.validate()?;
         Ok(H3Server {
             config: self.config,
             bind_addr: self.bind_addr,
+            tls: self.tls,
+            tls_source: self.tls_source,
+            udp_buffer_sizes: self.udp_buffer_sizes,
             endpoint: None,
         })
     }
 }
 
+/// Kernel buffer sizes of a UDP socket (None = OS default)
+#[cfg(feature = "http3")]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct UdpBufferSizes {
+    recv: Option<usize>,
+    send: Option<usize>,
+}
+
+#[cfg(feature = "http3")]
+impl UdpBufferSizes {
+    /// Bind a QUIC server endpoint, sizing the socket buffers if configured
+    pub(crate) fn bind_endpoint(
+        &self,
+        endpoint_config: quinn::EndpointConfig,
+        server_config: quinn::ServerConfig,
+        addr: SocketAddr,
+    ) -> std::io::Result<quinn::Endpoint> {
+        let runtime = quinn::default_runtime()
+            .ok_or_else(|| std::io::Error::other("no async runtime found"))?;
+        if self.recv.is_none() && self.send.is_none() {
+            let socket = std::net::UdpSocket::bind(addr)?;
+            return quinn::Endpoint::new(endpoint_config, Some(server_config), socket, runtime);
+        }
+
+        let socket = socket2::Socket::new(
+            socket2::Domain::for_address(addr),
+            socket2::Type::DGRAM,
+            Some(socket2::Protocol::UDP),
+        )?;
+        if let Some(size) = self.recv {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = self.send {
+            socket.set_send_buffer_size(size)?;
+        }
+        socket.bind(&addr.into())?;
+
+        quinn::Endpoint::new(endpoint_config, Some(server_config), socket.into(), runtime)
+    }
+}
+
 /// HTTP/3 server
 #[cfg(feature = "http3")]
 pub struct H3Server {
     config: HyperConfig,
     bind_addr: SocketAddr,
+    tls: H3TlsConfig,
+    tls_source: Option<ConfigSource<H3TlsConfig>>,
+    udp_buffer_sizes: UdpBufferSizes,
     endpoint: Option<quinn::Endpoint>,
 }
 
@@ -537,6 +1396,11 @@ impl H3Server {
         &self.config
     }
 
+    /// Get the TLS configuration
+    pub fn tls(&self) -> &H3TlsConfig {
+        &self.tls
+    }
+
     /// Start the HTTP/3 server and accept connections
     ///
     /// # Arguments
@@ -547,42 +1411,19 @@ impl H3Server {
     {
         info!("Starting HTTP/3 server on {}", self.bind_addr);
 
-        // Create rustls server configuration
-        let tls_config = self.create_server_tls_config()?;
-
-        // Wrap in QuicServerConfig
-        let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(tls_config)
-            .map_err(|e| HyperError::Tls(format!("Failed to create QUIC server config: {}", e)))?;
-
-        // Create quinn server configuration
-        let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
-
-        // Configure transport
-        let mut transport_config = quinn::TransportConfig::default();
-
-        let max_streams = quinn::VarInt::from_u32(self.config.max_concurrent_streams as u32);
-        transport_config.max_concurrent_bidi_streams(max_streams);
-        transport_config.max_concurrent_uni_streams(max_streams);
-
-        transport_config.max_idle_timeout(Some(
-            quinn::IdleTimeout::try_from(Duration::from_millis(self.config.idle_timeout_ms))
-                .map_err(|_| HyperError::Config("Invalid idle timeout".to_string()))?
-        ));
-        transport_config.keep_alive_interval(Some(Duration::from_millis(self.config.keep_alive_interval_ms)));
-
-        if self.config.enable_datagrams {
-            transport_config.datagram_receive_buffer_size(Some(self.config.max_datagram_size));
-            transport_config.datagram_send_buffer_size(self.config.max_datagram_size);
-        }
-
-        server_config.transport_config(Arc::new(transport_config));
+        let server_config = Self::quic_server_config(&self.config, &self.tls, self.config.enable_datagrams)?;
 
         // Create and bind endpoint
-        let endpoint = quinn::Endpoint::server(server_config, self.bind_addr)
+        let endpoint_config = self.config.endpoint_config()?;
+        log_udp_offload(&self.config);
+        let endpoint = self
+            .udp_buffer_sizes
+            .bind_endpoint(endpoint_config, server_config, self.bind_addr)
             .map_err(|e| HyperError::QuicConnection(format!("Failed to bind endpoint: {}", e)))?;
 
         info!("HTTP/3 server listening on {}", endpoint.local_addr().unwrap());
         self.endpoint = Some(endpoint.clone());
+        let _reload = self.spawn_tls_reload(&endpoint, self.config.enable_datagrams);
 
         // Accept connections
         while let Some(conn) = endpoint.accept().await {
@@ -635,41 +1476,19 @@ impl H3Server {
     {
         info!("Starting HTTP/3 server with datagram support on {}", self.bind_addr);
 
-        // Create rustls server configuration
-        let tls_config = self.create_server_tls_config()?;
-
-        // Wrap in QuicServerConfig
-        let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(tls_config)
-            .map_err(|e| HyperError::Tls(format!("Failed to create QUIC server config: {}", e)))?;
-
-        // Create quinn server configuration
-        let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
-
-        // Configure transport
-        let mut transport_config = quinn::TransportConfig::default();
-
-        let max_streams = quinn::VarInt::from_u32(self.config.max_concurrent_streams as u32);
-        transport_config.max_concurrent_bidi_streams(max_streams);
-        transport_config.max_concurrent_uni_streams(max_streams);
-
-        transport_config.max_idle_timeout(Some(
-            quinn::IdleTimeout::try_from(Duration::from_millis(self.config.idle_timeout_ms))
-                .map_err(|_| HyperError::Config("Invalid idle timeout".to_string()))?
-        ));
-        transport_config.keep_alive_interval(Some(Duration::from_millis(self.config.keep_alive_interval_ms)));
-
-        // Enable datagrams
-        transport_config.datagram_receive_buffer_size(Some(self.config.max_datagram_size));
-        transport_config.datagram_send_buffer_size(self.config.max_datagram_size);
-
-        server_config.transport_config(Arc::new(transport_config));
+        let server_config = Self::quic_server_config(&self.config, &self.tls, true)?;
 
         // Create and bind endpoint
-        let endpoint = quinn::Endpoint::server(server_config, self.bind_addr)
+        let endpoint_config = self.config.endpoint_config()?;
+        log_udp_offload(&self.config);
+        let endpoint = self
+            .udp_buffer_sizes
+            .bind_endpoint(endpoint_config, server_config, self.bind_addr)
             .map_err(|e| HyperError::QuicConnection(format!("Failed to bind endpoint: {}", e)))?;
 
         info!("HTTP/3 server with datagrams listening on {}", endpoint.local_addr().unwrap());
         self.endpoint = Some(endpoint.clone());
+        let _reload = self.spawn_tls_reload(&endpoint, true);
 
         let config = Arc::new(self.config);
 
@@ -715,11 +1534,14 @@ impl H3Server {
         debug!("Connection established with {}", remote_addr);
 
         // Spawn datagram handler task
+        let datagram_state = DatagramState::default();
+        let local_caps = DatagramCapabilities::from_config(&config);
         let datagram_conn = quinn_conn.clone();
         let dg_handler = datagram_handler.clone();
         let dg_config = config.clone();
+        let dg_state = datagram_state.clone();
         tokio::spawn(async move {
-            Self::datagram_handler_task(datagram_conn, dg_handler, dg_config).await;
+            Self::datagram_handler_task(datagram_conn, dg_handler, dg_config, dg_state).await;
         });
 
         // Create h3 connection for HTTP/3 streams
@@ -732,10 +1554,25 @@ impl H3Server {
             match h3_conn.accept().await {
                 Ok(Some(resolver)) => {
                     let service = service.clone();
+                    let datagram_state = datagram_state.clone();
+                    let max_body_size = config.max_request_body_size;
                     tokio::spawn(async move {
                         match resolver.resolve_request().await {
                             Ok((req, stream)) => {
-                                if let Err(e) = Self::handle_request(req, stream, service).await {
+                                let negotiated = Self::negotiate_datagram_capabilities(
+                                    &req,
+                                    &local_caps,
+                                    &datagram_state,
+                                );
+                                if let Err(e) = Self::handle_request(
+                                    req,
+                                    stream,
+                                    service,
+                                    negotiated,
+                                    max_body_size,
+                                )
+                                .await
+                                {
                                     error!("Request error: {}", e);
                                 }
                             }
@@ -759,20 +1596,45 @@ impl H3Server {
         Ok(())
     }
 
+    /// Negotiate datagram capabilities from the request's capability header
+    ///
+    /// Returns the negotiated capabilities if the client advertised any, so they
+    /// can be echoed back in the response.
+    fn negotiate_datagram_capabilities(
+        req: &Request<()>,
+        local: &DatagramCapabilities,
+        state: &DatagramState,
+    ) -> Option<DatagramCapabilities> {
+        let peer = req
+            .headers()
+            .get(DATAGRAM_CAPABILITIES_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(DatagramCapabilities::from_header_value)?;
+
+        let negotiated = local.negotiate(&peer);
+        state.set_checksum(negotiated.checksum);
+        debug!("Negotiated datagram capabilities: {:?}", negotiated);
+        Some(negotiated)
+    }
+
     /// Task that handles incoming datagrams for a connection
     async fn datagram_handler_task<D>(
         conn: quinn::Connection,
         handler: D,
         config: Arc<HyperConfig>,
+        state: DatagramState,
     )
     where
         D: DatagramHandler,
     {
-        let sender = DatagramSender::new(conn.clone(), config.max_datagram_size);
+        let sender = DatagramSender::new(conn.clone(), config.max_datagram_size, state.clone());
 
         loop {
             match conn.read_datagram().await {
                 Ok(data) => {
+                    let Some(data) = state.receive(data) else {
+                        continue;
+                    };
                     let datagram = Datagram::new(data);
                     handler.handle(datagram, sender.clone());
                 }
@@ -798,7 +1660,7 @@ impl H3Server {
     async fn handle_connection<S>(
         conn: quinn::Incoming,
         service: S,
-        _config: HyperConfig,
+        config: HyperConfig,
     ) -> Result<(), HyperError>
     where
         S: H3Service,
@@ -822,11 +1684,14 @@ impl H3Server {
             match h3_conn.accept().await {
                 Ok(Some(resolver)) => {
                     let service = service.clone();
+                    let max_body_size = config.max_request_body_size;
                     tokio::spawn(async move {
                         // Resolve the request headers
                         match resolver.resolve_request().await {
                             Ok((req, stream)) => {
-                                if let Err(e) = Self::handle_request(req, stream, service).await {
+                                if let Err(e) =
+                                    Self::handle_request(req, stream, service, None, max_body_size).await
+                                {
                                     error!("Request error: {}", e);
                                 }
                             }
@@ -855,81 +1720,157 @@ impl H3Server {
         req: Request<()>,
         mut stream: h3::server::RequestStream<B, Bytes>,
         service: S,
+        datagram_caps: Option<DatagramCapabilities>,
+        max_body_size: usize,
     ) -> Result<(), HyperError>
     where
         S: H3Service,
         B: quic::BidiStream<Bytes>,
     {
+        use bytes::Buf;
+        use futures::StreamExt;
+
         debug!("Handling request: {} {}", req.method(), req.uri());
 
-        // Call the service
-        let response = service.call(req).await;
-
-        // Send response
-        match response {
-            Ok(resp) => {
-                let (parts, body) = resp.into_parts();
-                let resp = Response::from_parts(parts, ());
-
-                stream
-                    .send_response(resp)
-                    .await
-                    .map_err(|e| HyperError::H3Stream(format!("Failed to send response: {}", e)))?;
-
-                stream
-                    .send_data(body)
-                    .await
-                    .map_err(|e| HyperError::H3Stream(format!("Failed to send body: {}", e)))?;
-
-                stream
-                    .finish()
-                    .await
-                    .map_err(|e| HyperError::H3Stream(format!("Failed to finish stream: {}", e)))?;
-
-                debug!("Response sent successfully");
+        // Read the request body, refusing it once it outgrows the limit
+        let mut body = bytes::BytesMut::new();
+        while let Some(mut chunk) = stream
+            .recv_data()
+            .await
+            .map_err(|e| HyperError::H3Stream(format!("Failed to receive request body: {}", e)))?
+        {
+            if body.len() + chunk.remaining() > max_body_size {
+                debug!("Request body exceeds {} bytes", max_body_size);
+                // The rest of the request is not needed to answer
+                stream.stop_sending(h3::error::Code::H3_NO_ERROR);
+                return Self::send_status(stream, StatusCode::PAYLOAD_TOO_LARGE).await;
             }
-            Err(status) => {
-                let resp = Response::builder()
-                    .status(status)
-                    .body(())
-                    .unwrap();
-
-                stream
-                    .send_response(resp)
-                    .await
-                    .map_err(|e| HyperError::H3Stream(format!("Failed to send error response: {}", e)))?;
-
-                stream
-                    .finish()
-                    .await
-                    .map_err(|e| HyperError::H3Stream(format!("Failed to finish stream: {}", e)))?;
+            body.extend_from_slice(chunk.chunk());
+            chunk.advance(chunk.remaining());
+        }
+        let req = req.map(|()| body.freeze());
+
+        // Call the service
+        let (mut parts, mut body) = match service.call(req).await {
+            Ok(resp) => resp.into_parts(),
+            Err(status) => return Self::send_status(stream, status).await,
+        };
+        if let Some(caps) = datagram_caps {
+            if let Ok(value) = http::HeaderValue::from_str(&caps.to_header_value()) {
+                parts.headers.insert(DATAGRAM_CAPABILITIES_HEADER, value);
             }
         }
 
+        stream
+            .send_response(Response::from_parts(parts, ()))
+            .await
+            .map_err(|e| HyperError::H3Stream(format!("Failed to send response: {}", e)))?;
+
+        // Send the body as it is produced
+        while let Some(chunk) = body.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    stream.stop_stream(h3::error::Code::H3_INTERNAL_ERROR);
+                    return Err(e);
+                }
+            };
+            stream
+                .send_data(chunk)
+                .await
+                .map_err(|e| HyperError::H3Stream(format!("Failed to send body: {}", e)))?;
+        }
+
+        stream
+            .finish()
+            .await
+            .map_err(|e| HyperError::H3Stream(format!("Failed to finish stream: {}", e)))?;
+
+        debug!("Response sent successfully");
         Ok(())
     }
 
-    /// Create server TLS configuration
-    fn create_server_tls_config(&self) -> Result<rustls::ServerConfig, HyperError> {
-        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    /// Answer a request with `status` and no body
+    async fn send_status<B>(
+        mut stream: h3::server::RequestStream<B, Bytes>,
+        status: StatusCode,
+    ) -> Result<(), HyperError>
+    where
+        B: quic::BidiStream<Bytes>,
+    {
+        let resp = Response::builder()
+            .status(status)
+            .body(())
+            .unwrap();
 
-        // TODO: Load certificates from configuration
-        // For now, create a self-signed certificate for testing
-        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
-            .map_err(|e| HyperError::Tls(format!("Failed to generate certificate: {}", e)))?;
+        stream
+            .send_response(resp)
+            .await
+            .map_err(|e| HyperError::H3Stream(format!("Failed to send error response: {}", e)))?;
 
-        let cert_der = cert.serialize_der()
-            .map_err(|e| HyperError::Tls(format!("Failed to serialize certificate: {}", e)))?;
-        let key_der = cert.serialize_private_key_der();
+        stream
+            .finish()
+            .await
+            .map_err(|e| HyperError::H3Stream(format!("Failed to finish stream: {}", e)))
+    }
 
-        let cert_chain = vec![CertificateDer::from(cert_der)];
-        let key = PrivateKeyDer::try_from(key_der)
-            .map_err(|_| HyperError::Tls("Failed to parse private key".to_string()))?;
+    /// Build the QUIC server configuration from certificates and settings
+    fn quic_server_config(
+        config: &HyperConfig,
+        tls: &H3TlsConfig,
+        datagrams: bool,
+    ) -> Result<quinn::ServerConfig, HyperError> {
+        // Wrap the rustls configuration in QuicServerConfig
+        let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(Self::create_server_tls_config(tls)?)
+            .map_err(|e| HyperError::Tls(format!("Failed to create QUIC server config: {}", e)))?;
+        let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
 
-        let mut tls_config = rustls::ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(cert_chain, key)
-            .map_err(|e| HyperError::Tls(format!("Certificate error: {}", e)))?;
+        // Configure transport
+        let mut transport_config = quinn::TransportConfig::default();
+
+        let max_streams = quinn::VarInt::from_u32(config.max_concurrent_streams as u32);
+        transport_config.max_concurrent_bidi_streams(max_streams);
+        transport_config.max_concurrent_uni_streams(max_streams);
+
+        transport_config.max_idle_timeout(Some(
+            quinn::IdleTimeout::try_from(Duration::from_millis(config.idle_timeout_ms))
+                .map_err(|_| HyperError::Config("Invalid idle timeout".to_string()))?
+        ));
+        transport_config.keep_alive_interval(Some(Duration::from_millis(config.keep_alive_interval_ms)));
+        transport_config.enable_segmentation_offload(config.enable_segmentation_offload);
+
+        if datagrams {
+            transport_config.datagram_receive_buffer_size(Some(config.max_datagram_size));
+            transport_config.datagram_send_buffer_size(config.max_datagram_size);
+        }
+
+        server_config.transport_config(Arc::new(transport_config));
+        Ok(server_config)
+    }
+
+    /// Swap the endpoint's certificates with each value of the TLS source
+    ///
+    /// The task stops when the returned guard is dropped.
+    fn spawn_tls_reload(&mut self, endpoint: &quinn::Endpoint, datagrams: bool) -> Option<TaskGuard> {
+        let mut source = self.tls_source.take()?;
+        let endpoint = endpoint.clone();
+        let config = self.config.clone();
+        Some(TaskGuard(tokio::spawn(async move {
+            while let Some(tls) = source.next().await {
+                match Self::quic_server_config(&config, &tls, datagrams) {
+                    Ok(server_config) => {
+                        endpoint.set_server_config(Some(server_config));
+                        info!("Reloaded HTTP/3 server certificates");
+                    }
+                    Err(e) => error!("Keeping current HTTP/3 server certificates: {}", e),
+                }
+            }
+        })))
+    }
+
+    /// Create server TLS configuration
+    fn create_server_tls_config(tls: &H3TlsConfig) -> Result<rustls::ServerConfig, HyperError> {
+        let mut tls_config = tls.server_config()?;
 
         tls_config.alpn_protocols = vec![b"h3".to_vec()];
         // Note: 0-RTT is controlled at the QUIC layer via max_early_data_size
@@ -965,6 +1906,24 @@ impl H3ClientBuilder {
         self
     }
 
+    /// Advertise CRC32 checksums on datagrams
+    pub fn enable_datagram_checksum(mut self, enable: bool) -> Self {
+        self.config.enable_datagram_checksum = enable;
+        self
+    }
+
+    /// Send several packets per syscall with GSO where supported (default on)
+    pub fn enable_segmentation_offload(mut self, enable: bool) -> Self {
+        self.config.enable_segmentation_offload = enable;
+        self
+    }
+
+    /// Set the largest UDP payload accepted, which sizes the receive buffers
+    pub fn max_udp_payload_size(mut self, size: u16) -> Self {
+        self.config.max_udp_payload_size = size;
+        self
+    }
+
     /// Build the HTTP/3 client
     pub fn build(self) -> Result<H3Client, HyperError> {
         H3Client::new(self.config)
@@ -978,6 +1937,17 @@ impl Default for H3ClientBuilder {
     }
 }
 
+/// Stops a background task (stats sampling, certificate reloads) when dropped
+#[cfg(feature = "http3")]
+struct TaskGuard(tokio::task::JoinHandle<()>);
+
+#[cfg(feature = "http3")]
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
 /// HTTP/3 client
 #[cfg(feature = "http3")]
 pub struct H3Client {
@@ -1016,6 +1986,7 @@ impl H3Client {
                 .map_err(|_| HyperError::Config("Invalid idle timeout".to_string()))?
         ));
         transport_config.keep_alive_interval(Some(Duration::from_millis(config.keep_alive_interval_ms)));
+        transport_config.enable_segmentation_offload(config.enable_segmentation_offload);
 
         if config.enable_datagrams {
             transport_config.datagram_receive_buffer_size(Some(config.max_datagram_size));
@@ -1025,8 +1996,7 @@ impl H3Client {
         client_config.transport_config(Arc::new(transport_config));
 
         // Create endpoint
-        let mut endpoint = quinn::Endpoint::client("0.0.0.0:0".parse().unwrap())
-            .map_err(|e| HyperError::QuicConnection(format!("Failed to create endpoint: {}", e)))?;
+        let mut endpoint = config.client_endpoint()?;
 
         endpoint.set_default_client_config(client_config);
 
@@ -1049,6 +2019,22 @@ impl H3Client {
         addr: SocketAddr,
         req: Request<Bytes>,
     ) -> Result<Response<Bytes>, HyperError> {
+        let (resp, _stats) = self.send_request_with_stats(addr, req, None).await?;
+        Ok(resp)
+    }
+
+    /// Send an HTTP/3 request and report transport statistics
+    ///
+    /// The returned snapshot is taken once the response body has been
+    /// received. With a sampler, snapshots are also delivered periodically
+    /// while the request is in flight.
+    pub async fn send_request_with_stats(
+        &self,
+        addr: SocketAddr,
+        req: Request<Bytes>,
+        sampler: Option<&StatsSampler>,
+    ) -> Result<(Response<Bytes>, TransportStats), HyperError> {
+        let start = Instant::now();
         info!("Connecting to {}", addr);
 
         // Connect to server
@@ -1061,6 +2047,9 @@ impl H3Client {
 
         debug!("QUIC connection established");
 
+        let stats_conn = conn.clone();
+        let sampling = sampler.map(|sampler| TaskGuard(sampler.spawn(conn.clone(), start)));
+
         // Create h3 connection
         let quinn_conn = h3_quinn::Connection::new(conn);
         let (mut driver, mut send_request) = h3::client::new(quinn_conn)
@@ -1116,7 +2105,11 @@ impl H3Client {
 
         debug!("Response received: {} bytes", body_data.len());
 
-        Ok(resp.map(|_| Bytes::from(body_data)))
+        drop(sampling);
+        let mut stats = TransportStats::from_connection_stats(&stats_conn.stats());
+        stats.elapsed = start.elapsed();
+
+        Ok((resp.map(|_| Bytes::from(body_data)), stats))
     }
 
     /// Establish a persistent connection with datagram support
@@ -1165,21 +2158,26 @@ impl H3Client {
         // Create datagram channel
         let (datagram_tx, datagram_rx) = mpsc::channel(256);
 
+        let datagram_state = DatagramState::default();
+
         // Spawn datagram receiver task if datagrams are enabled
         if self.config.enable_datagrams {
             let conn_clone = conn.clone();
+            let state = datagram_state.clone();
             tokio::spawn(async move {
-                Self::datagram_receiver_task(conn_clone, datagram_tx).await;
+                Self::datagram_receiver_task(conn_clone, datagram_tx, state).await;
             });
         }
 
         let max_datagram_size = self.config.max_datagram_size;
-        let datagram_sender = DatagramSender::new(conn.clone(), max_datagram_size);
+        let datagram_sender =
+            DatagramSender::new(conn.clone(), max_datagram_size, datagram_state.clone());
 
         Ok(H3Connection {
             conn,
             datagram_sender,
             datagram_rx: Some(datagram_rx),
+            datagram_state,
             config: self.config.clone(),
         })
     }
@@ -1188,10 +2186,14 @@ impl H3Client {
     async fn datagram_receiver_task(
         conn: quinn::Connection,
         tx: mpsc::Sender<Datagram>,
+        state: DatagramState,
     ) {
         loop {
             match conn.read_datagram().await {
                 Ok(data) => {
+                    let Some(data) = state.receive(data) else {
+                        continue;
+                    };
                     let datagram = Datagram::new(data);
                     if tx.send(datagram).await.is_err() {
                         debug!("Datagram receiver channel closed");
@@ -1375,6 +2377,10 @@ mod tests {
             max_datagram_size: 32768,
             keep_alive_interval_ms: 15000,
             idle_timeout_ms: 30000,
+            enable_datagram_checksum: false,
+            enable_segmentation_offload: false,
+            max_udp_payload_size: 9000,
+            max_request_body_size: 1024,
         };
 
         let transport = HyperTransport::with_config(config);
@@ -1396,6 +2402,118 @@ mod tests {
         assert_eq!(server.bind_addr(), addr);
         assert!(server.config().enable_zero_rtt);
         assert_eq!(server.config().max_concurrent_streams, 150);
+        assert_eq!(server.config().max_request_body_size, quill_core::framing::MAX_FRAME_SIZE);
+    }
+
+    /// Echoes the request body back one byte per chunk
+    #[derive(Clone)]
+    struct ChunkedEcho;
+
+    impl H3Service for ChunkedEcho {
+        fn call(&self, req: Request<Bytes>) -> BoxFuture<Result<Response<H3Body>, StatusCode>> {
+            let chunks: Vec<_> = req.body().chunks(1).map(|byte| Ok(Bytes::copy_from_slice(byte))).collect();
+            let body: H3Body = Box::pin(futures::stream::iter(chunks));
+            Box::pin(async move { Ok(Response::new(body)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_streams_responses_and_limits_requests() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let addr = std::net::UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let server = H3ServerBuilder::new(addr).max_request_body_size(1024).build().unwrap();
+        tokio::spawn(server.serve(ChunkedEcho));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let client = H3ClientBuilder::new().build().unwrap();
+        let request = |body: Bytes| Request::post("https://localhost/echo").body(body).unwrap();
+        let response = client.send_request(addr, request(Bytes::from_static(b"hello"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.into_body(), Bytes::from_static(b"hello"));
+
+        let response = client.send_request(addr, request(Bytes::from(vec![0u8; 4096]))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    fn test_cert_pem() -> (String, String) {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        (cert.serialize_pem().unwrap(), cert.serialize_private_key_pem())
+    }
+
+    #[test]
+    fn test_server_tls_from_pem_and_files() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let addr = "127.0.0.1:4433".parse().unwrap();
+        let (cert, key) = test_cert_pem();
+
+        let server = H3ServerBuilder::new(addr)
+            .tls_cert_pem(cert.clone())
+            .tls_key_pem(key.clone())
+            .tls_client_ca_pem(cert.clone())
+            .build()
+            .unwrap();
+        assert!(server.tls().has_certificate());
+        assert!(server.tls().verifies_clients());
+        assert!(H3Server::create_server_tls_config(server.tls()).is_ok());
+
+        let dir = std::env::temp_dir().join(format!("quill-h3-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cert.pem"), &cert).unwrap();
+        std::fs::write(dir.join("key.pem"), &key).unwrap();
+        let server = H3ServerBuilder::new(addr)
+            .tls_cert_path(dir.join("cert.pem"))
+            .tls_key_path(dir.join("key.pem"))
+            .build()
+            .unwrap();
+        assert!(H3Server::create_server_tls_config(server.tls()).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_server_tls_errors() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let addr = "127.0.0.1:4433".parse().unwrap();
+        let (cert, _) = test_cert_pem();
+
+        // A certificate needs its key
+        assert!(matches!(
+            H3ServerBuilder::new(addr).tls_cert_pem(cert).build(),
+            Err(HyperError::Config(_))
+        ));
+
+        let server = H3ServerBuilder::new(addr)
+            .tls_cert_pem("not a certificate")
+            .tls_key_path("/nonexistent/key.pem")
+            .build()
+            .unwrap();
+        assert!(matches!(H3Server::create_server_tls_config(server.tls()), Err(HyperError::Tls(_))));
+    }
+
+    #[tokio::test]
+    async fn test_tls_watch_reloads_rotated_files() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let dir = std::env::temp_dir().join(format!("quill-h3-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = test_cert_pem();
+        std::fs::write(dir.join("cert.pem"), &cert).unwrap();
+        std::fs::write(dir.join("key.pem"), &key).unwrap();
+
+        let mut source = H3TlsConfig::default()
+            .cert_path(dir.join("cert.pem"))
+            .key_path(dir.join("key.pem"))
+            .watch()
+            .poll_interval(Duration::from_millis(10));
+        assert_eq!(source.paths().len(), 2);
+
+        // A new certificate is not used until its key matches
+        let (new_cert, new_key) = test_cert_pem();
+        std::fs::write(dir.join("cert.pem"), &new_cert).unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(100), source.next()).await.is_err());
+
+        std::fs::write(dir.join("key.pem"), &new_key).unwrap();
+        let tls = tokio::time::timeout(Duration::from_secs(5), source.next()).await.unwrap().unwrap();
+        assert!(H3Server::create_server_tls_config(&tls).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
     #[tokio::test]
@@ -1526,5 +2644,119 @@ mod tests {
 
         assert!(config.enable_datagrams);
         assert_eq!(config.max_datagram_size, 65536);
+        assert!(!config.enable_datagram_checksum);
+    }
+
+    #[test]
+    fn test_datagram_checksum_roundtrip() {
+        let original = Datagram::with_flow_id(Bytes::from("hello"), 42);
+        let encoded = original.encode_with_checksum();
+        assert_eq!(encoded.len(), original.encode().len() + DATAGRAM_CHECKSUM_LEN);
+
+        let decoded = Datagram::decode_with_checksum(encoded, true).unwrap();
+        assert_eq!(decoded.payload, original.payload);
+        assert_eq!(decoded.flow_id, Some(42));
+    }
+
+    #[test]
+    fn test_datagram_checksum_detects_corruption() {
+        let encoded = Datagram::with_flow_id(Bytes::from("hello"), 42).encode_with_checksum();
+
+        // Flip a bit in the flow_id
+        let mut corrupted = encoded.to_vec();
+        corrupted[0] ^= 0x01;
+        assert!(Datagram::decode_with_checksum(Bytes::from(corrupted), true).is_err());
+
+        // Too short to carry a checksum
+        assert!(Datagram::decode_with_checksum(Bytes::from_static(b"ab"), false).is_err());
+    }
+
+    #[test]
+    fn test_datagram_state_counters() {
+        let state = DatagramState::default();
+
+        // Without checksums, datagrams pass through untouched
+        assert_eq!(state.receive(Bytes::from("raw")), Some(Bytes::from("raw")));
+
+        state.set_checksum(true);
+        let good = Datagram::new(Bytes::from("good")).encode_with_checksum();
+        assert_eq!(state.receive(good), Some(Bytes::from("good")));
+        assert_eq!(state.receive(Bytes::from("bad-checksum")), None);
+
+        let stats = state.stats.to_stats();
+        assert_eq!(stats.received, 3);
+        assert_eq!(stats.verified, 1);
+        assert_eq!(stats.checksum_failures, 1);
+    }
+
+    #[test]
+    fn test_datagram_capabilities_negotiation() {
+        let config = HyperConfig {
+            enable_datagram_checksum: true,
+            ..Default::default()
+        };
+        let local = DatagramCapabilities::from_config(&config);
+        assert_eq!(local.to_header_value(), "checksum=crc32");
+
+        let peer = DatagramCapabilities::from_header_value("future-cap, checksum=crc32");
+        assert!(local.negotiate(&peer).checksum);
+
+        // Falls back cleanly when the peer does not advertise checksums
+        let legacy = DatagramCapabilities::from_header_value("");
+        assert!(!local.negotiate(&legacy).checksum);
+        assert!(!DatagramCapabilities::default().negotiate(&peer).checksum);
+    }
+
+    #[test]
+    fn test_transport_stats_from_connection_stats() {
+        let mut quinn_stats = quinn::ConnectionStats::default();
+        quinn_stats.path.rtt = Duration::from_millis(20);
+        quinn_stats.path.cwnd = 120_000;
+        quinn_stats.path.sent_packets = 200;
+        quinn_stats.path.lost_packets = 4;
+        quinn_stats.frame_rx.datagram = 9;
+
+        let datagrams = DatagramStats {
+            received: 9,
+            verified: 7,
+            checksum_failures: 2,
+        };
+        let stats = TransportStats::from_connection_stats(&quinn_stats).with_datagram_stats(&datagrams);
+        assert_eq!(stats.rtt, Duration::from_millis(20));
+        assert_eq!(stats.cwnd, 120_000);
+        assert_eq!(stats.datagrams_received, 9);
+        assert_eq!(stats.datagrams_dropped, 2);
+        assert_eq!(stats.pacing_rate(), Some(6_000_000));
+        assert!((stats.loss_rate() - 0.02).abs() < 1e-9);
+        assert_eq!(TransportStats::default().pacing_rate(), None);
+    }
+
+    #[test]
+    fn test_stats_sampler_interval_floor() {
+        let sampler = StatsSampler::new(Duration::ZERO, |_| {});
+        assert_eq!(sampler.interval(), Duration::from_millis(1));
+    }
+    #[tokio::test]
+    async fn test_udp_offload_settings() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let support = UdpOffloadSupport::detect().unwrap();
+        assert!(support.max_gso_segments >= 1 && support.gro_segments >= 1);
+        assert_eq!(
+            support.recv_batch_bytes(1472),
+            1472 * support.gro_segments * support.recv_batch_size
+        );
+        assert!(support.to_string().contains("per receive"));
+
+        let client = H3ClientBuilder::new()
+            .enable_segmentation_offload(false)
+            .max_udp_payload_size(9000)
+            .build()
+            .unwrap();
+        assert!(!client.config().enable_segmentation_offload);
+
+        // quinn only accepts payload sizes a QUIC packet fits in
+        let result = H3ClientBuilder::new().max_udp_payload_size(1000).build();
+        assert!(matches!(result, Err(HyperError::Config(_))));
     }
 }
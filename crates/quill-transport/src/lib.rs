@@ -5,24 +5,35 @@
 //! - Turbo: Full HTTP/2
 //! - Hyper: HTTP/3 over QUIC
 //! - WebTransport: Browser-compatible HTTP/3 with streams and datagrams
+//!
+//! With the `test-util` feature, [`sim`] provides a simulated network for
+//! deterministic tests.
 
 pub mod classic;
 pub mod hyper;
 pub mod negotiation;
+pub mod reload;
 pub mod turbo;
 
+#[cfg(any(test, feature = "test-util"))]
+pub mod sim;
 #[cfg(feature = "webtransport")]
 pub mod webtransport;
 
 pub use classic::ClassicTransport;
 pub use negotiation::{negotiate_profile, ProfileNegotiator};
+pub use reload::ConfigSource;
+#[cfg(any(test, feature = "test-util"))]
+pub use sim::{LinkConfig, SimListener, SimNetwork, SimStats, SimStream};
 pub use turbo::TurboTransport;
 
 #[cfg(feature = "http3")]
 pub use hyper::{
-    BoxFuture, Datagram, DatagramHandler, DatagramReceiver, DatagramSender, FnDatagramHandler,
-    H3Client, H3ClientBuilder, H3Connection, H3Server, H3ServerBuilder, H3Service, HyperConfig,
-    HyperError, HyperTransport, ServerConnection,
+    full_body, BoxFuture, Datagram, DatagramCapabilities, DatagramHandler, DatagramReceiver,
+    DatagramSender, DatagramStats, FnDatagramHandler, H3Body, H3Client, H3ClientBuilder,
+    H3Connection, H3Server, H3ServerBuilder, H3Service, H3TlsConfig, HyperConfig, HyperError,
+    HyperTransport, ServerConnection, StatsSampler, TransportStats, TransportStatsCallback,
+    UdpOffloadSupport, DATAGRAM_CAPABILITIES_HEADER, DATAGRAM_CHECKSUM_LEN,
 };
 
 #[cfg(feature = "webtransport")]
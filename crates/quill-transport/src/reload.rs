@@ -0,0 +1,257 @@
+//! Hot reloading of configuration
+//!
+//! This module provides:
+//! - A configuration source backed by files, a channel or a fixed value
+//! - Polling of files for changes, reloading them once they parse
+//!
+//! Long-running servers use a [`ConfigSource`] to swap certificates, rate
+//! limits or credentials without a restart: the consumer awaits
+//! [`ConfigSource::next`] in a background task and atomically replaces its
+//! configuration with each value. New connections and requests see the new
+//! configuration; work already in flight keeps the old one.
+//!
+//! File sources compare each file's modification time and length every
+//! poll interval, which also catches the symlink swaps Kubernetes uses to
+//! update mounted secrets. A change is only reported once the loader
+//! accepts the files, so a certificate written before its key is picked up
+//! when both are in place; rejected contents are logged once per change.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tracing::warn;
+
+/// Default interval between checks of watched files
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+type Loader<T> = Arc<dyn Fn() -> Result<T, String> + Send + Sync>;
+
+/// Modification time and length of a file (None = missing)
+type Fingerprint = Option<(SystemTime, u64)>;
+
+/// Where reloadable configuration comes from
+pub struct ConfigSource<T> {
+    kind: SourceKind<T>,
+}
+
+enum SourceKind<T> {
+    Files {
+        paths: Vec<PathBuf>,
+        poll_interval: Duration,
+        load: Loader<T>,
+        seen: Vec<Fingerprint>,
+        rejected: Option<Vec<Fingerprint>>,
+    },
+    Channel(watch::Receiver<T>),
+    Fixed(T),
+}
+
+impl<T: Clone> ConfigSource<T> {
+    /// Reload with `load` whenever one of `paths` changes
+    ///
+    /// Changes are measured from the files as they are now.
+    pub fn files<I, P, F>(paths: I, load: F) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+        F: Fn() -> Result<T, String> + Send + Sync + 'static,
+    {
+        let paths: Vec<PathBuf> = paths.into_iter().map(Into::into).collect();
+        let seen = fingerprints(&paths);
+        Self {
+            kind: SourceKind::Files {
+                paths,
+                poll_interval: DEFAULT_POLL_INTERVAL,
+                load: Arc::new(load),
+                seen,
+                rejected: None,
+            },
+        }
+    }
+
+    /// Take each value sent on `receiver` after the current one
+    pub fn channel(receiver: watch::Receiver<T>) -> Self {
+        Self {
+            kind: SourceKind::Channel(receiver),
+        }
+    }
+
+    /// Always `value`; never changes
+    pub fn fixed(value: T) -> Self {
+        Self {
+            kind: SourceKind::Fixed(value),
+        }
+    }
+
+    /// Whether this source never changes
+    pub fn is_fixed(&self) -> bool {
+        matches!(self.kind, SourceKind::Fixed(_))
+    }
+
+    /// Set how often watched files are checked (file sources only)
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        if let SourceKind::Files { poll_interval, .. } = &mut self.kind {
+            *poll_interval = interval.max(Duration::from_millis(1));
+        }
+        self
+    }
+
+    /// Files watched by this source (empty for other sources)
+    pub fn paths(&self) -> &[PathBuf] {
+        match &self.kind {
+            SourceKind::Files { paths, .. } => paths,
+            SourceKind::Channel(_) | SourceKind::Fixed(_) => &[],
+        }
+    }
+
+    /// Load the configuration as it is now
+    pub fn current(&self) -> Result<T, String> {
+        match &self.kind {
+            SourceKind::Files { load, .. } => load(),
+            SourceKind::Channel(receiver) => Ok(receiver.borrow().clone()),
+            SourceKind::Fixed(value) => Ok(value.clone()),
+        }
+    }
+
+    /// Wait for the next configuration
+    ///
+    /// Returns `None` once a channel's sender is dropped, and at once for
+    /// fixed sources; file sources never end.
+    pub async fn next(&mut self) -> Option<T> {
+        match &mut self.kind {
+            SourceKind::Files {
+                paths,
+                poll_interval,
+                load,
+                seen,
+                rejected,
+            } => loop {
+                tokio::time::sleep(*poll_interval).await;
+                let current = fingerprints(paths);
+                if current == *seen || rejected.as_ref() == Some(&current) {
+                    continue;
+                }
+                match load() {
+                    Ok(value) => {
+                        *seen = current;
+                        *rejected = None;
+                        return Some(value);
+                    }
+                    Err(e) => {
+                        warn!("Ignoring changed configuration in {}: {}", display_paths(paths), e);
+                        *rejected = Some(current);
+                    }
+                }
+            },
+            SourceKind::Channel(receiver) => {
+                receiver.changed().await.ok()?;
+                Some(receiver.borrow_and_update().clone())
+            }
+            SourceKind::Fixed(_) => None,
+        }
+    }
+}
+
+impl<T: Clone> Clone for ConfigSource<T> {
+    fn clone(&self) -> Self {
+        let kind = match &self.kind {
+            SourceKind::Files {
+                paths,
+                poll_interval,
+                load,
+                seen,
+                rejected,
+            } => SourceKind::Files {
+                paths: paths.clone(),
+                poll_interval: *poll_interval,
+                load: Arc::clone(load),
+                seen: seen.clone(),
+                rejected: rejected.clone(),
+            },
+            SourceKind::Channel(receiver) => SourceKind::Channel(receiver.clone()),
+            SourceKind::Fixed(value) => SourceKind::Fixed(value.clone()),
+        };
+        Self { kind }
+    }
+}
+
+impl<T> fmt::Debug for ConfigSource<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            SourceKind::Files { paths, poll_interval, .. } => f
+                .debug_struct("ConfigSource::Files")
+                .field("paths", paths)
+                .field("poll_interval", poll_interval)
+                .finish(),
+            SourceKind::Channel(_) => f.write_str("ConfigSource::Channel"),
+            SourceKind::Fixed(_) => f.write_str("ConfigSource::Fixed"),
+        }
+    }
+}
+
+fn fingerprints(paths: &[PathBuf]) -> Vec<Fingerprint> {
+    paths
+        .iter()
+        .map(|path| {
+            let meta = std::fs::metadata(path).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        })
+        .collect()
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_number(path: PathBuf) -> impl Fn() -> Result<u32, String> {
+        move || {
+            let text = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            text.trim().parse().map_err(|e| format!("{}", e))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_source_reloads_valid_changes() {
+        let dir = std::env::temp_dir().join(format!("quill-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("limit");
+        std::fs::write(&path, "10").unwrap();
+
+        let mut source = ConfigSource::files([&path], read_number(path.clone())).poll_interval(Duration::from_millis(10));
+        assert_eq!(source.current(), Ok(10));
+        assert_eq!(source.paths(), std::slice::from_ref(&path));
+
+        // Unparseable contents are skipped until fixed
+        std::fs::write(&path, "not a number").unwrap();
+        let next = tokio::time::timeout(Duration::from_millis(100), source.next()).await;
+        assert!(next.is_err());
+
+        std::fs::write(&path, "250").unwrap();
+        let next = tokio::time::timeout(Duration::from_secs(5), source.next()).await;
+        assert_eq!(next.unwrap(), Some(250));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_channel_and_fixed_sources() {
+        let (sender, receiver) = watch::channel(1u32);
+        let mut source = ConfigSource::channel(receiver);
+        assert_eq!(source.current(), Ok(1));
+
+        sender.send(2).unwrap();
+        assert_eq!(source.next().await, Some(2));
+        drop(sender);
+        assert_eq!(source.next().await, None);
+
+        let mut fixed = ConfigSource::fixed(3u32);
+        assert!(fixed.is_fixed());
+        assert_eq!(fixed.current(), Ok(3));
+        assert_eq!(fixed.next().await, None);
+    }
+}
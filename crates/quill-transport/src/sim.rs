@@ -0,0 +1,715 @@
+//! Deterministic network simulation for tests
+//!
+//! This module provides:
+//! - An in-memory network connecting clients and servers in one process
+//! - Scripted latency, jitter, bandwidth and packet loss per network
+//! - Partitions, connection resets and refused connects on demand
+//! - Seeded randomness, so a run with the same seed replays exactly
+//!
+//! Time comes from tokio's clock. With the clock paused
+//! (`#[tokio::test(start_paused = true)]`, which needs tokio's `test-util`
+//! feature) the runtime jumps ahead whenever every task waits on a timer:
+//! a simulated minute passes in milliseconds, and on a current-thread
+//! runtime the same seed always produces the same schedule.
+//!
+//! Connections carry bytes like TCP. Writes are cut into segments of at
+//! most [`LinkConfig::mss`] bytes, each delivered in order after the link's
+//! serialization delay, latency and jitter. A lost segment is retransmitted
+//! after [`LinkConfig::retransmit_timeout`], so loss shows up as
+//! head-of-line delay, never as a corrupted stream. While a listener is
+//! partitioned, connects to it hang and segments in either direction are
+//! held back until it is healed.
+//!
+//! `quill-server` serves a [`SimListener`] with `QuillServer::serve_sim`,
+//! and `quill-client` dials a [`SimNetwork`] when built with
+//! `ClientBuilder::sim_network`; both need their `test-util` feature.
+//!
+//! ```ignore
+//! let network = SimNetwork::new(7).with_link(LinkConfig::new().latency(Duration::from_millis(40)));
+//! tokio::spawn(server.serve_sim(network.listen("echo:80")));
+//! let client = QuillClient::builder().base_url("http://echo:80").sim_network(network).build()?;
+//! ```
+
+use bytes::{Buf, Bytes};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::{mpsc, Notify};
+use tokio::time::{Instant, Sleep};
+
+/// Default maximum segment size, that of Ethernet
+pub const DEFAULT_MSS: usize = 1460;
+
+/// Behavior of the links of a simulated network
+///
+/// Applies to both directions of every connection. Changes with
+/// [`SimNetwork::set_link`] affect segments written afterwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkConfig {
+    /// One-way delay of every segment
+    pub latency: Duration,
+    /// Extra one-way delay drawn uniformly from `0..=jitter` per segment
+    pub jitter: Duration,
+    /// Probability in `[0, 1]` that a segment is lost and retransmitted
+    pub loss: f64,
+    /// Delay added by each retransmission of a lost segment
+    pub retransmit_timeout: Duration,
+    /// Link capacity in bytes per second (None = unlimited)
+    pub bandwidth: Option<u64>,
+    /// Largest segment written to the link
+    pub mss: usize,
+}
+
+impl LinkConfig {
+    /// Create an instant, lossless link
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the one-way latency
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Set the maximum extra one-way delay per segment
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set the segment loss probability, clamped to `[0, 1)`
+    ///
+    /// A loss of 1 would retransmit forever; partition the listener instead.
+    pub fn loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Set the delay added by each retransmission
+    pub fn retransmit_timeout(mut self, timeout: Duration) -> Self {
+        self.retransmit_timeout = timeout;
+        self
+    }
+
+    /// Limit the link to `bytes_per_sec`
+    pub fn bandwidth(mut self, bytes_per_sec: u64) -> Self {
+        self.bandwidth = Some(bytes_per_sec.max(1));
+        self
+    }
+
+    /// Set the maximum segment size (at least 1 byte)
+    pub fn mss(mut self, mss: usize) -> Self {
+        self.mss = mss.max(1);
+        self
+    }
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            loss: 0.0,
+            retransmit_timeout: Duration::from_millis(200),
+            bandwidth: None,
+            mss: DEFAULT_MSS,
+        }
+    }
+}
+
+/// Counters of a simulated network
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimStats {
+    /// Connections established
+    pub connections: u64,
+    /// Connects refused, by a missing listener or [`SimNetwork::refuse_connects`]
+    pub refused: u64,
+    /// Connections reset with [`SimNetwork::reset`]
+    pub resets: u64,
+    /// Segments written to links
+    pub segments: u64,
+    /// Transmissions lost and retried
+    pub retransmissions: u64,
+    /// Payload bytes written to links
+    pub bytes: u64,
+}
+
+/// Small deterministic generator (SplitMix64)
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    incoming: mpsc::UnboundedSender<SimStream>,
+    partitioned: bool,
+    refuse: u32,
+    connections: Vec<Arc<Connection>>,
+}
+
+#[derive(Debug)]
+struct NetState {
+    link: LinkConfig,
+    rng: Rng,
+    endpoints: HashMap<String, Endpoint>,
+    stats: SimStats,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<NetState>,
+    healed: Notify,
+}
+
+/// Seeded in-memory network of listeners and connections
+///
+/// Clones share the same network.
+#[derive(Clone)]
+pub struct SimNetwork {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for SimNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.shared.state.lock().unwrap();
+        f.debug_struct("SimNetwork")
+            .field("link", &state.link)
+            .field("listeners", &state.endpoints.len())
+            .field("stats", &state.stats)
+            .finish()
+    }
+}
+
+impl SimNetwork {
+    /// Create a network with instant links, drawing randomness from `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(NetState {
+                    link: LinkConfig::default(),
+                    rng: Rng(seed),
+                    endpoints: HashMap::new(),
+                    stats: SimStats::default(),
+                }),
+                healed: Notify::new(),
+            }),
+        }
+    }
+
+    /// Use `link` for every connection
+    pub fn with_link(self, link: LinkConfig) -> Self {
+        self.set_link(link);
+        self
+    }
+
+    /// Change the links for segments written from now on
+    pub fn set_link(&self, link: LinkConfig) {
+        self.shared.state.lock().unwrap().link = link;
+    }
+
+    /// Current link behavior
+    pub fn link(&self) -> LinkConfig {
+        self.shared.state.lock().unwrap().link
+    }
+
+    /// Listen at `addr` (e.g. `"echo:80"`), replacing an earlier listener there
+    pub fn listen(&self, addr: impl Into<String>) -> SimListener {
+        let addr = addr.into();
+        let (incoming, accepted) = mpsc::unbounded_channel();
+        self.shared.state.lock().unwrap().endpoints.insert(
+            addr.clone(),
+            Endpoint {
+                incoming,
+                partitioned: false,
+                refuse: 0,
+                connections: Vec::new(),
+            },
+        );
+        SimListener { addr, accepted }
+    }
+
+    /// Connect to the listener at `addr`
+    ///
+    /// Takes one round trip. Fails with `ConnectionRefused` when nothing
+    /// listens there or the connect is scripted to be refused, and waits
+    /// while the listener is partitioned.
+    pub async fn connect(&self, addr: &str) -> io::Result<SimStream> {
+        loop {
+            let healed = self.shared.healed.notified();
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                let NetState { endpoints, stats, .. } = &mut *state;
+                let Some(endpoint) = endpoints.get_mut(addr) else {
+                    stats.refused += 1;
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionRefused,
+                        format!("nothing listens at {}", addr),
+                    ));
+                };
+                if endpoint.refuse > 0 {
+                    endpoint.refuse -= 1;
+                    stats.refused += 1;
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionRefused,
+                        format!("connect to {} refused", addr),
+                    ));
+                }
+                if !endpoint.partitioned {
+                    break;
+                }
+            }
+            healed.await;
+        }
+
+        // SYN and SYN-ACK
+        let handshake = self.link().latency * 2;
+        tokio::time::sleep(handshake).await;
+
+        let connection = Arc::new(Connection {
+            addr: addr.to_string(),
+            to_server: Mutex::new(Pipe::default()),
+            to_client: Mutex::new(Pipe::default()),
+        });
+        let client = SimStream::new(self.clone(), Arc::clone(&connection), Side::Client);
+        let server = SimStream::new(self.clone(), Arc::clone(&connection), Side::Server);
+
+        let mut state = self.shared.state.lock().unwrap();
+        let NetState { endpoints, stats, .. } = &mut *state;
+        let endpoint = endpoints
+            .get_mut(addr)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::ConnectionRefused, format!("{} stopped listening", addr)))?;
+        endpoint
+            .incoming
+            .send(server)
+            .map_err(|_| io::Error::new(io::ErrorKind::ConnectionRefused, format!("{} stopped listening", addr)))?;
+        endpoint.connections.retain(|c| Arc::strong_count(c) > 1);
+        endpoint.connections.push(connection);
+        stats.connections += 1;
+        Ok(client)
+    }
+
+    /// Cut `addr` off: connects hang and segments are held until [`heal`](Self::heal)
+    pub fn partition(&self, addr: &str) {
+        if let Some(endpoint) = self.shared.state.lock().unwrap().endpoints.get_mut(addr) {
+            endpoint.partitioned = true;
+        }
+    }
+
+    /// Reconnect `addr`, releasing held connects and segments
+    pub fn heal(&self, addr: &str) {
+        let connections = {
+            let mut state = self.shared.state.lock().unwrap();
+            match state.endpoints.get_mut(addr) {
+                Some(endpoint) => {
+                    endpoint.partitioned = false;
+                    endpoint.connections.clone()
+                }
+                None => Vec::new(),
+            }
+        };
+        self.shared.healed.notify_waiters();
+        for connection in connections {
+            connection.wake_all();
+        }
+    }
+
+    /// Refuse the next `count` connects to `addr`
+    pub fn refuse_connects(&self, addr: &str, count: u32) {
+        if let Some(endpoint) = self.shared.state.lock().unwrap().endpoints.get_mut(addr) {
+            endpoint.refuse += count;
+        }
+    }
+
+    /// Reset every open connection to `addr`
+    ///
+    /// Both ends then fail reads and writes with `ConnectionReset`.
+    pub fn reset(&self, addr: &str) {
+        let connections = {
+            let mut state = self.shared.state.lock().unwrap();
+            let NetState { endpoints, stats, .. } = &mut *state;
+            match endpoints.get_mut(addr) {
+                Some(endpoint) => {
+                    let connections = std::mem::take(&mut endpoint.connections);
+                    stats.resets += connections.len() as u64;
+                    connections
+                }
+                None => Vec::new(),
+            }
+        };
+        for connection in connections {
+            for pipe in [&connection.to_server, &connection.to_client] {
+                let mut pipe = pipe.lock().unwrap();
+                pipe.reset = true;
+                pipe.wake();
+            }
+        }
+    }
+
+    /// Counters since the network was created
+    pub fn stats(&self) -> SimStats {
+        self.shared.state.lock().unwrap().stats
+    }
+
+    fn is_partitioned(&self, addr: &str) -> bool {
+        let state = self.shared.state.lock().unwrap();
+        state.endpoints.get(addr).is_some_and(|endpoint| endpoint.partitioned)
+    }
+
+    /// Schedule `data` on `pipe`, returning the bytes taken
+    fn transmit(&self, pipe: &mut Pipe, data: &[u8]) -> usize {
+        let mut state = self.shared.state.lock().unwrap();
+        let link = state.link;
+        let len = data.len().min(link.mss);
+        let now = Instant::now();
+
+        // Serialization on the sender's link, then propagation
+        let start = pipe.link_free_at.map_or(now, |free_at| free_at.max(now));
+        let serialization = match link.bandwidth {
+            Some(rate) => Duration::from_secs_f64(len as f64 / rate as f64),
+            None => Duration::ZERO,
+        };
+        let sent_at = start + serialization;
+        pipe.link_free_at = Some(sent_at);
+
+        let mut delay = link.latency;
+        if !link.jitter.is_zero() {
+            delay += link.jitter.mul_f64(state.rng.next_f64());
+        }
+        while link.loss > 0.0 && state.rng.next_f64() < link.loss {
+            delay += link.retransmit_timeout;
+            state.stats.retransmissions += 1;
+        }
+        state.stats.segments += 1;
+        state.stats.bytes += len as u64;
+
+        // Segments arrive in order, as TCP delivers them
+        let arrival = sent_at + delay;
+        let deliver_at = pipe.last_delivery.map_or(arrival, |last| last.max(arrival));
+        pipe.last_delivery = Some(deliver_at);
+        pipe.segments.push_back((deliver_at, Bytes::copy_from_slice(&data[..len])));
+        pipe.wake();
+        len
+    }
+}
+
+/// Accepts connections made to one address of a [`SimNetwork`]
+#[derive(Debug)]
+pub struct SimListener {
+    addr: String,
+    accepted: mpsc::UnboundedReceiver<SimStream>,
+}
+
+impl SimListener {
+    /// Wait for the next connection
+    ///
+    /// Fails once a later [`SimNetwork::listen`] took over the address.
+    pub async fn accept(&mut self) -> io::Result<SimStream> {
+        self.accepted
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, format!("{} is no longer listened on", self.addr)))
+    }
+
+    /// Address connections are made to
+    pub fn local_addr(&self) -> &str {
+        &self.addr
+    }
+}
+
+/// Bytes travelling one way along a connection
+#[derive(Debug, Default)]
+struct Pipe {
+    segments: VecDeque<(Instant, Bytes)>,
+    link_free_at: Option<Instant>,
+    last_delivery: Option<Instant>,
+    /// The writer shut down; EOF after the last segment
+    closed: bool,
+    /// The reader is gone; writes fail
+    abandoned: bool,
+    reset: bool,
+    reader: Option<Waker>,
+}
+
+impl Pipe {
+    fn wake(&mut self) {
+        if let Some(waker) = self.reader.take() {
+            waker.wake();
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Connection {
+    addr: String,
+    to_server: Mutex<Pipe>,
+    to_client: Mutex<Pipe>,
+}
+
+impl Connection {
+    fn wake_all(&self) {
+        self.to_server.lock().unwrap().wake();
+        self.to_client.lock().unwrap().wake();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Client,
+    Server,
+}
+
+/// One end of a simulated connection
+pub struct SimStream {
+    network: SimNetwork,
+    connection: Arc<Connection>,
+    side: Side,
+    timer: Option<Pin<Box<Sleep>>>,
+}
+
+impl fmt::Debug for SimStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimStream")
+            .field("addr", &self.connection.addr)
+            .field("side", &self.side)
+            .finish()
+    }
+}
+
+impl SimStream {
+    fn new(network: SimNetwork, connection: Arc<Connection>, side: Side) -> Self {
+        Self {
+            network,
+            connection,
+            side,
+            timer: None,
+        }
+    }
+
+    /// Address of the listener this connection was made to
+    pub fn peer_addr(&self) -> &str {
+        &self.connection.addr
+    }
+
+    fn incoming(&self) -> &Mutex<Pipe> {
+        match self.side {
+            Side::Client => &self.connection.to_client,
+            Side::Server => &self.connection.to_server,
+        }
+    }
+
+    fn outgoing(&self) -> &Mutex<Pipe> {
+        match self.side {
+            Side::Client => &self.connection.to_server,
+            Side::Server => &self.connection.to_client,
+        }
+    }
+}
+
+fn reset_error() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionReset, "simulated connection reset")
+}
+
+impl AsyncRead for SimStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let partitioned = this.network.is_partitioned(&this.connection.addr);
+        let mut pipe = this.incoming().lock().unwrap();
+        if pipe.reset {
+            return Poll::Ready(Err(reset_error()));
+        }
+        if partitioned {
+            // Woken when healed
+            pipe.reader = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let now = Instant::now();
+        let mut read = false;
+        while buf.remaining() > 0 {
+            let Some((deliver_at, segment)) = pipe.segments.front_mut() else {
+                break;
+            };
+            if *deliver_at > now {
+                break;
+            }
+            let len = segment.len().min(buf.remaining());
+            buf.put_slice(&segment[..len]);
+            segment.advance(len);
+            if segment.is_empty() {
+                pipe.segments.pop_front();
+            }
+            read = true;
+        }
+        if read || (pipe.closed && pipe.segments.is_empty()) {
+            return Poll::Ready(Ok(()));
+        }
+
+        pipe.reader = Some(cx.waker().clone());
+        if let Some(&(deliver_at, _)) = pipe.segments.front() {
+            drop(pipe);
+            let timer = this.timer.get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deliver_at)));
+            timer.as_mut().reset(deliver_at);
+            if timer.as_mut().poll(cx).is_ready() {
+                cx.waker().wake_by_ref();
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl AsyncWrite for SimStream {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let mut pipe = this.outgoing().lock().unwrap();
+        if pipe.reset {
+            return Poll::Ready(Err(reset_error()));
+        }
+        if pipe.abandoned || pipe.closed {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, "simulated connection closed")));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        Poll::Ready(Ok(this.network.transmit(&mut pipe, buf)))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut pipe = self.outgoing().lock().unwrap();
+        pipe.closed = true;
+        pipe.wake();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for SimStream {
+    fn drop(&mut self) {
+        let mut outgoing = self.outgoing().lock().unwrap();
+        outgoing.closed = true;
+        outgoing.wake();
+        drop(outgoing);
+        self.incoming().lock().unwrap().abandoned = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn pair(network: &SimNetwork) -> (SimStream, SimStream) {
+        let mut listener = network.listen("echo:80");
+        let client = network.connect("echo:80").await.unwrap();
+        let server = listener.accept().await.unwrap();
+        (client, server)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_latency_delays_delivery() {
+        let network = SimNetwork::new(1).with_link(LinkConfig::new().latency(Duration::from_millis(50)));
+        let start = Instant::now();
+        let (mut client, mut server) = pair(&network).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        assert_eq!(start.elapsed(), Duration::from_millis(150));
+
+        client.shutdown().await.unwrap();
+        assert_eq!(server.read(&mut buf).await.unwrap(), 0);
+    }
+
+    async fn transfer_time(seed: u64) -> (Duration, SimStats) {
+        let link = LinkConfig::new()
+            .latency(Duration::from_millis(10))
+            .jitter(Duration::from_millis(5))
+            .loss(0.2)
+            .mss(100);
+        let network = SimNetwork::new(seed).with_link(link);
+        let (mut client, mut server) = pair(&network).await;
+        let start = Instant::now();
+        let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+        client.write_all(&data).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, data);
+        (start.elapsed(), network.stats())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_loss_is_deterministic_per_seed() {
+        let (elapsed, stats) = transfer_time(42).await;
+        assert_eq!(stats.segments, 100);
+        assert!(stats.retransmissions > 0);
+        assert!(elapsed >= Duration::from_millis(200), "elapsed = {:?}", elapsed);
+        assert_eq!(transfer_time(42).await, (elapsed, stats));
+        assert_ne!(transfer_time(43).await.1, stats);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_partition_holds_and_reset_fails() {
+        let network = SimNetwork::new(1).with_link(LinkConfig::new().latency(Duration::from_millis(5)));
+        let (mut client, mut server) = pair(&network).await;
+
+        network.partition("echo:80");
+        client.write_all(b"held").await.unwrap();
+        let mut buf = [0u8; 4];
+        let read = tokio::time::timeout(Duration::from_secs(10), server.read_exact(&mut buf)).await;
+        assert!(read.is_err());
+
+        let healer = network.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            healer.heal("echo:80");
+        });
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"held");
+
+        network.reset("echo:80");
+        let err = server.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(client.write_all(b"x").await.unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(network.stats().resets, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_refused_connects() {
+        let network = SimNetwork::new(1);
+        let err = network.connect("missing:80").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+
+        let _listener = network.listen("echo:80");
+        network.refuse_connects("echo:80", 1);
+        assert!(network.connect("echo:80").await.is_err());
+        assert!(network.connect("echo:80").await.is_ok());
+        assert_eq!(network.stats().refused, 2);
+        assert_eq!(network.stats().connections, 1);
+    }
+}
@@ -40,7 +40,7 @@ use std::time::Duration;
 use thiserror::Error;
 use tracing::{debug, error, info};
 
-use crate::hyper::{HyperConfig, HyperError};
+use crate::hyper::{log_udp_offload, HyperConfig, HyperError, UdpBufferSizes};
 
 /// WebTransport-specific errors
 #[derive(Debug, Error)]
@@ -323,6 +323,7 @@ impl WebTransportServer {
             ))
             .map_err(|_| WebTransportError::Config("Invalid idle timeout".to_string()))?,
         ));
+        transport_config.enable_segmentation_offload(self.config.http3.enable_segmentation_offload);
 
         if self.config.enable_datagrams {
             transport_config.datagram_receive_buffer_size(Some(self.config.max_datagram_size));
@@ -332,7 +333,10 @@ impl WebTransportServer {
         server_config.transport_config(Arc::new(transport_config));
 
         // Create endpoint
-        let endpoint = quinn::Endpoint::server(server_config, self.bind_addr)
+        let endpoint_config = self.config.http3.endpoint_config()?;
+        log_udp_offload(&self.config.http3);
+        let endpoint = UdpBufferSizes::default()
+            .bind_endpoint(endpoint_config, server_config, self.bind_addr)
             .map_err(|e| WebTransportError::Connection(format!("Failed to bind: {}", e)))?;
 
         info!(
@@ -483,6 +487,7 @@ impl WebTransportClientBuilder {
             quinn::VarInt::from_u32(self.config.http3.max_concurrent_streams as u32);
         transport_config.max_concurrent_bidi_streams(max_streams);
         transport_config.max_concurrent_uni_streams(max_streams);
+        transport_config.enable_segmentation_offload(self.config.http3.enable_segmentation_offload);
 
         if self.config.enable_datagrams {
             transport_config.datagram_receive_buffer_size(Some(self.config.max_datagram_size));
@@ -492,8 +497,7 @@ impl WebTransportClientBuilder {
         client_config.transport_config(Arc::new(transport_config));
 
         // Create endpoint
-        let mut endpoint = quinn::Endpoint::client("0.0.0.0:0".parse().unwrap())
-            .map_err(|e| WebTransportError::Connection(format!("Failed to create endpoint: {}", e)))?;
+        let mut endpoint = self.config.http3.client_endpoint()?;
 
         endpoint.set_default_client_config(client_config);
 
@@ -0,0 +1,115 @@
+//! Quill RPC framework
+//!
+//! This crate gathers the Quill crates behind one dependency, with a cargo
+//! feature per subsystem:
+//! - `client` (default): the client SDK, as `quill::client`
+//! - `server` (default): the server SDK, as `quill::server`
+//! - `tensor`: tensor and token streaming types, as `quill::tensor`
+//! - `h3`: HTTP/3 (the Hyper profile) for the enabled client and server
+//! - `gateway`: the REST gateway, as `quill::gateway` (implies `client`)
+//! - `codegen`: stub generation for build scripts, as `quill::codegen`
+//! - `test-util`: a simulated network for deterministic tests, as `quill::sim`
+//! - `full`: everything but `test-util`
+//!
+//! The core types (framing, errors, metadata) are always available, as
+//! [`core`] and at the crate root. The types most applications need,
+//! including the client and server builders, are re-exported at the root
+//! too, and [`prelude`] brings them into scope at once:
+//!
+//! ```no_run
+//! use quill::prelude::*;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = QuillServer::builder()
+//!     .register("echo.v1.Echo/Echo", |req: Bytes| async move { Ok(req) })
+//!     .build();
+//! let addr = "127.0.0.1:8080".parse()?;
+//! tokio::spawn(async move {
+//!     let _ = server.serve(addr).await;
+//! });
+//!
+//! let client = QuillClient::new("http://127.0.0.1:8080");
+//! let reply = client.call("echo.v1.Echo", "Echo", Bytes::from("hi")).await?;
+//! assert_eq!(reply, Bytes::from("hi"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Stubs generated by `quill::codegen::compile_protos` name this crate's
+//! `core`, `client` and `server` modules, alongside `prost`, `bytes` and
+//! `futures`, so crates compiling protos need no other Quill dependency.
+
+pub use bytes::Bytes;
+pub use quill_core as core;
+pub use quill_core::{
+    Deadline, ErrorCode, Frame, FrameFlags, FrameParser, FrameStream, Metadata, PrismProfile,
+    ProblemDetails, ProfilePreference, QuillError, ServingStatus, StreamWriter,
+};
+
+#[cfg(feature = "client")]
+pub use quill_client as client;
+#[cfg(feature = "client")]
+pub use quill_client::client::ClientBuilder;
+#[cfg(feature = "client")]
+pub use quill_client::{CircuitBreakerConfig, FlowControlConfig, QuillClient, RequestOptions, RetryPolicy};
+#[cfg(all(feature = "client", feature = "h3"))]
+pub use quill_client::{H3ClientBuilder, QuillH3Client};
+
+#[cfg(feature = "server")]
+pub use quill_server as server;
+#[cfg(feature = "server")]
+pub use quill_server::{HealthReporter, QuillServer, RequestContext, RpcResponse, RpcRouter, ServerBuilder};
+#[cfg(all(feature = "server", feature = "h3"))]
+pub use quill_server::{H3ServerBuilder, QuillH3Server};
+
+#[cfg(feature = "tensor")]
+pub use quill_tensor as tensor;
+#[cfg(feature = "tensor")]
+pub use quill_tensor::{DType, Tensor, TensorFrame, TensorMeta, Token, TokenBatch, TokenBatchBuilder};
+
+#[cfg(feature = "gateway")]
+pub use quill_rest_gateway as gateway;
+#[cfg(feature = "gateway")]
+pub use quill_rest_gateway::{RestGateway, RestGatewayBuilder};
+
+/// Code generation for build scripts, naming this crate in generated stubs
+#[cfg(feature = "codegen")]
+pub mod codegen {
+    pub use quill_codegen::*;
+
+    /// Generate Quill RPC code from protobuf files
+    ///
+    /// Like [`quill_codegen::compile_protos`], with stubs naming the modules
+    /// of the `quill` crate.
+    pub fn compile_protos(
+        protos: &[impl AsRef<std::path::Path>],
+        includes: &[impl AsRef<std::path::Path>],
+        config: QuillConfig,
+    ) -> std::io::Result<()> {
+        quill_codegen::compile_protos(protos, includes, config.with_crate_paths(CratePaths::Facade))
+    }
+}
+
+#[cfg(feature = "test-util")]
+pub use quill_transport::sim;
+
+/// The common types of the enabled subsystems
+///
+/// ```
+/// use quill::prelude::*;
+/// ```
+pub mod prelude {
+    pub use crate::{Bytes, ErrorCode, Frame, Metadata, ProblemDetails, QuillError};
+
+    #[cfg(feature = "client")]
+    pub use crate::{ClientBuilder, QuillClient, RequestOptions, RetryPolicy};
+
+    #[cfg(feature = "server")]
+    pub use crate::{QuillServer, RequestContext, RpcResponse, RpcRouter, ServerBuilder};
+
+    #[cfg(feature = "tensor")]
+    pub use crate::{DType, Tensor, TensorFrame, Token, TokenBatch};
+
+    #[cfg(feature = "gateway")]
+    pub use crate::{RestGateway, RestGatewayBuilder};
+}
@@ -0,0 +1,45 @@
+//! Metrics and health endpoints
+//!
+//! This module provides:
+//! - `GET /metrics`: Prometheus text exposition of request metrics
+//! - `GET /healthz`: liveness; answers `200` while the process runs
+//! - `GET /readyz`: readiness; `503` while the health status is unhealthy
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use quill_server::{ObservabilityCollector, PROMETHEUS_CONTENT_TYPE};
+use serde_json::json;
+
+/// Router serving the admin endpoints from `collector`
+pub fn router(collector: ObservabilityCollector) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .route("/healthz", get(|| async { "ok" }))
+        .route("/readyz", get(ready))
+        .with_state(collector)
+}
+
+async fn metrics(State(collector): State<ObservabilityCollector>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        collector.export_prometheus().await,
+    )
+}
+
+async fn ready(State(collector): State<ObservabilityCollector>) -> impl IntoResponse {
+    let health = collector.get_health().await;
+    let status = if health.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let dependencies: serde_json::Map<String, serde_json::Value> = health
+        .dependencies
+        .iter()
+        .map(|(name, dep)| (name.clone(), json!({ "healthy": dep.healthy, "error": dep.error })))
+        .collect();
+    (status, Json(json!({ "healthy": health.healthy, "dependencies": dependencies })))
+}
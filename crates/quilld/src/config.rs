@@ -0,0 +1,486 @@
+//! Daemon configuration
+//!
+//! This module provides:
+//! - The YAML configuration file format
+//! - Loading of protobuf descriptor sets named by the configuration
+//! - Validation reporting every problem at once, for `quilld check-config`
+//!
+//! ```yaml
+//! server:
+//!   listen: 0.0.0.0:8080
+//!   http_version: auto        # auto | http1 | http2
+//! descriptors:
+//!   - protos/users.pb         # FileDescriptorSet, relative to this file
+//! reflection: true
+//! admin:
+//!   listen: 0.0.0.0:9090      # /metrics, /healthz, /readyz
+//! gateway:
+//!   listen: 0.0.0.0:8081
+//!   base_path: /api
+//!   routes:
+//!     - service: users.v1.UserService
+//!       method: GetUser
+//!       http: GET /users/{id}
+//! plugins:
+//!   reload_interval_ms: 2000  # 0 disables hot reload
+//!   modules:
+//!     - name: echo
+//!       path: plugins/echo.wasm
+//!       methods: [echo.v1.EchoService/Echo]
+//!       streaming_methods: [echo.v1.EchoService/Tail]
+//! ```
+
+use crate::error::{QuilldError, QuilldResult};
+use prost_reflect::DescriptorPool;
+use quill_server::HttpVersion;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// Default configuration file path
+pub const DEFAULT_CONFIG_PATH: &str = "quilld.yaml";
+
+/// Daemon configuration
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuilldConfig {
+    /// RPC server settings
+    #[serde(default)]
+    pub server: ServerSection,
+    /// FileDescriptorSet files describing the served services
+    #[serde(default)]
+    pub descriptors: Vec<PathBuf>,
+    /// Serve the built-in reflection service
+    #[serde(default = "default_true")]
+    pub reflection: bool,
+    /// Metrics and health endpoints (disabled when absent)
+    #[serde(default)]
+    pub admin: Option<AdminSection>,
+    /// REST gateway (disabled when absent)
+    #[serde(default)]
+    pub gateway: Option<GatewaySection>,
+    /// WASM handler plugins (disabled when absent)
+    #[serde(default)]
+    pub plugins: Option<PluginsSection>,
+}
+
+/// RPC server settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerSection {
+    /// Address to listen on
+    #[serde(default = "default_server_listen")]
+    pub listen: SocketAddr,
+    /// HTTP versions to accept
+    #[serde(default)]
+    pub http_version: HttpVersionSetting,
+}
+
+impl Default for ServerSection {
+    fn default() -> Self {
+        Self {
+            listen: default_server_listen(),
+            http_version: HttpVersionSetting::default(),
+        }
+    }
+}
+
+/// HTTP versions accepted by the RPC server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpVersionSetting {
+    /// Negotiate HTTP/1.1 or HTTP/2
+    #[default]
+    Auto,
+    /// HTTP/1.1 only
+    Http1,
+    /// HTTP/2 only
+    Http2,
+}
+
+impl From<HttpVersionSetting> for HttpVersion {
+    fn from(setting: HttpVersionSetting) -> Self {
+        match setting {
+            HttpVersionSetting::Auto => HttpVersion::Auto,
+            HttpVersionSetting::Http1 => HttpVersion::Http1Only,
+            HttpVersionSetting::Http2 => HttpVersion::Http2Only,
+        }
+    }
+}
+
+/// Metrics and health endpoint settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminSection {
+    /// Address to listen on
+    #[serde(default = "default_admin_listen")]
+    pub listen: SocketAddr,
+}
+
+/// REST gateway settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GatewaySection {
+    /// Address to listen on
+    #[serde(default = "default_gateway_listen")]
+    pub listen: SocketAddr,
+    /// Path prefix of every route
+    #[serde(default = "default_base_path")]
+    pub base_path: String,
+    /// API title in the OpenAPI document
+    #[serde(default = "default_title")]
+    pub title: String,
+    /// API version in the OpenAPI document
+    #[serde(default = "default_api_version")]
+    pub version: String,
+    /// REST routes
+    #[serde(default)]
+    pub routes: Vec<RouteSection>,
+}
+
+/// A REST route mapped to an RPC method
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteSection {
+    /// Fully-qualified service name (e.g. `users.v1.UserService`)
+    pub service: String,
+    /// Method name
+    pub method: String,
+    /// HTTP method and URL template (e.g. `GET /users/{id}`)
+    pub http: String,
+}
+
+/// WASM handler plugin settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginsSection {
+    /// How often to check module files for changes; `0` disables hot reload
+    #[serde(default = "default_reload_interval_ms")]
+    pub reload_interval_ms: u64,
+    /// Largest linear memory of one plugin instance in MiB
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: usize,
+    /// Fuel available to one call; unlimited if absent
+    #[serde(default = "default_fuel")]
+    pub fuel: Option<u64>,
+    /// Plugin modules
+    #[serde(default)]
+    pub modules: Vec<PluginModuleSection>,
+}
+
+/// A WASM module serving RPC methods
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginModuleSection {
+    /// Plugin name
+    pub name: String,
+    /// Module file (`.wasm`, or `.wat` text)
+    pub path: PathBuf,
+    /// Unary methods (`pkg.Service/Method`)
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Server-streaming methods (`pkg.Service/Method`)
+    #[serde(default)]
+    pub streaming_methods: Vec<String>,
+}
+
+impl RouteSection {
+    /// Split `http` into its HTTP method and URL template
+    pub fn http_parts(&self) -> Option<(&str, &str)> {
+        let (method, template) = self.http.trim().split_once(char::is_whitespace)?;
+        let template = template.trim();
+        template.starts_with('/').then_some((method, template))
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_server_listen() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 8080))
+}
+
+fn default_admin_listen() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 9090))
+}
+
+fn default_gateway_listen() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 8081))
+}
+
+fn default_base_path() -> String {
+    "/api".to_string()
+}
+
+fn default_title() -> String {
+    "Quill API".to_string()
+}
+
+fn default_api_version() -> String {
+    "1.0.0".to_string()
+}
+
+fn default_reload_interval_ms() -> u64 {
+    2000
+}
+
+fn default_max_memory_mb() -> usize {
+    64
+}
+
+fn default_fuel() -> Option<u64> {
+    Some(1_000_000_000)
+}
+
+impl QuilldConfig {
+    /// Parse a configuration from YAML
+    pub fn from_yaml(yaml: &str) -> QuilldResult<Self> {
+        serde_yaml::from_str(yaml).map_err(|e| QuilldError::Config(e.to_string()))
+    }
+
+    /// Load a configuration file
+    ///
+    /// Relative descriptor and plugin paths are resolved against the file's directory.
+    pub fn load(path: &Path) -> QuilldResult<Self> {
+        let yaml = std::fs::read_to_string(path).map_err(|source| QuilldError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut config = Self::from_yaml(&yaml)?;
+        if let Some(dir) = path.parent() {
+            for descriptor in &mut config.descriptors {
+                if descriptor.is_relative() {
+                    *descriptor = dir.join(&*descriptor);
+                }
+            }
+            for module in config.plugins.iter_mut().flat_map(|plugins| &mut plugins.modules) {
+                if module.path.is_relative() {
+                    module.path = dir.join(&module.path);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Load every configured descriptor set into one pool
+    pub fn descriptor_pool(&self) -> QuilldResult<DescriptorPool> {
+        let mut pool = DescriptorPool::new();
+        for path in &self.descriptors {
+            let bytes = std::fs::read(path).map_err(|source| QuilldError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            pool.decode_file_descriptor_set(bytes.as_slice())
+                .map_err(|e| QuilldError::Descriptor {
+                    path: path.clone(),
+                    message: e.to_string(),
+                })?;
+        }
+        Ok(pool)
+    }
+
+    /// Every problem with the configuration; empty if it is valid
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let mut listeners = vec![("server", self.server.listen)];
+        listeners.extend(self.admin.as_ref().map(|admin| ("admin", admin.listen)));
+        listeners.extend(self.gateway.as_ref().map(|gateway| ("gateway", gateway.listen)));
+        let mut seen = HashSet::new();
+        for (name, addr) in &listeners {
+            if addr.port() != 0 && !seen.insert(addr.port()) {
+                problems.push(format!("{} listens on port {}, which is already in use by another section", name, addr.port()));
+            }
+        }
+
+        let pool = match self.descriptor_pool() {
+            Ok(pool) => Some(pool),
+            Err(e) => {
+                problems.push(e.to_string());
+                None
+            }
+        };
+
+        if let Some(gateway) = &self.gateway {
+            if !cfg!(feature = "gateway") {
+                problems.push("gateway section requires quilld built with the `gateway` feature".to_string());
+            }
+            if !gateway.base_path.is_empty() && !gateway.base_path.starts_with('/') {
+                problems.push(format!("gateway base_path '{}' must start with '/'", gateway.base_path));
+            }
+            for (i, route) in gateway.routes.iter().enumerate() {
+                validate_route(i, route, pool.as_ref().filter(|_| !self.descriptors.is_empty()), &mut problems);
+            }
+        }
+
+        if let Some(plugins) = &self.plugins {
+            if !cfg!(feature = "plugins") {
+                problems.push("plugins section requires quilld built with the `plugins` feature".to_string());
+            }
+            validate_plugins(plugins, &mut problems);
+        }
+
+        problems
+    }
+}
+
+fn validate_plugins(plugins: &PluginsSection, problems: &mut Vec<String>) {
+    let mut names = HashSet::new();
+    let mut methods = HashSet::new();
+    for module in &plugins.modules {
+        if !names.insert(module.name.as_str()) {
+            problems.push(format!("plugin '{}' is defined more than once", module.name));
+        }
+        if !module.path.is_file() {
+            problems.push(format!("plugin '{}': module {} does not exist", module.name, module.path.display()));
+        }
+        if module.methods.is_empty() && module.streaming_methods.is_empty() {
+            problems.push(format!("plugin '{}' serves no methods", module.name));
+        }
+        for method in module.methods.iter().chain(&module.streaming_methods) {
+            if quill_server::parse_rpc_path(method).is_none() {
+                problems.push(format!("plugin '{}': method '{}' must be '<service>/<method>'", module.name, method));
+            } else if !methods.insert(method.as_str()) {
+                problems.push(format!("plugin '{}': method '{}' is served more than once", module.name, method));
+            }
+        }
+    }
+}
+
+fn validate_route(index: usize, route: &RouteSection, pool: Option<&DescriptorPool>, problems: &mut Vec<String>) {
+    let name = format!("gateway route {} ({}/{})", index, route.service, route.method);
+    match route.http_parts() {
+        None => problems.push(format!("{}: http '{}' must be '<METHOD> /<path>'", name, route.http)),
+        Some((method, template)) => {
+            if !matches!(method, "GET" | "POST" | "PUT" | "PATCH" | "DELETE") {
+                problems.push(format!("{}: unsupported HTTP method '{}'", name, method));
+            }
+            #[cfg(feature = "gateway")]
+            if let Err(e) = quill_rest_gateway::UrlTemplate::new(template) {
+                problems.push(format!("{}: {}", name, e));
+            }
+            #[cfg(not(feature = "gateway"))]
+            let _ = template;
+        }
+    }
+
+    if let Some(pool) = pool {
+        match pool.get_service_by_name(&route.service) {
+            None => problems.push(format!("{}: service is not in any descriptor set", name)),
+            Some(service) => {
+                if !service.methods().any(|method| method.name() == route.method) {
+                    problems.push(format!("{}: service has no method '{}'", name, route.method));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let config = QuilldConfig::from_yaml("{}").unwrap();
+        assert_eq!(config.server.listen, default_server_listen());
+        assert_eq!(config.server.http_version, HttpVersionSetting::Auto);
+        assert!(config.reflection);
+        assert!(config.admin.is_none() && config.gateway.is_none() && config.plugins.is_none());
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_full_config() {
+        let config = QuilldConfig::from_yaml(
+            r#"
+server:
+  listen: 127.0.0.1:7000
+  http_version: http2
+reflection: false
+admin:
+  listen: 127.0.0.1:7001
+gateway:
+  listen: 127.0.0.1:7002
+  routes:
+    - service: users.v1.UserService
+      method: GetUser
+      http: GET /users/{id}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.server.http_version, HttpVersionSetting::Http2);
+        assert!(!config.reflection);
+        let gateway = config.gateway.as_ref().unwrap();
+        assert_eq!(gateway.base_path, "/api");
+        assert_eq!(gateway.routes[0].http_parts(), Some(("GET", "/users/{id}")));
+        assert!(config.validate().is_empty(), "{:?}", config.validate());
+    }
+
+    #[test]
+    fn test_plugin_validation() {
+        let config = QuilldConfig::from_yaml(
+            r#"
+plugins:
+  modules:
+    - name: echo
+      path: missing.wasm
+      methods: [echo.v1.EchoService/Echo, Echo]
+    - name: echo
+      path: missing.wasm
+      streaming_methods: [echo.v1.EchoService/Echo]
+"#,
+        )
+        .unwrap();
+
+        let plugins = config.plugins.as_ref().unwrap();
+        assert_eq!(plugins.reload_interval_ms, 2000);
+        assert_eq!(plugins.fuel, Some(1_000_000_000));
+
+        let problems = config.validate();
+        assert_eq!(problems.len(), 5, "{:#?}", problems);
+        assert!(problems[0].contains("missing.wasm does not exist"));
+        assert!(problems[1].contains("'Echo' must be"));
+        assert!(problems[2].contains("defined more than once"));
+        assert!(problems[4].contains("served more than once"));
+    }
+
+    #[test]
+    fn test_unknown_fields_rejected() {
+        assert!(QuilldConfig::from_yaml("sever:\n  listen: 127.0.0.1:1\n").is_err());
+    }
+
+    #[test]
+    fn test_validation_reports_every_problem() {
+        let config = QuilldConfig::from_yaml(
+            r#"
+server:
+  listen: 127.0.0.1:7000
+admin:
+  listen: 127.0.0.1:7000
+descriptors: [does-not-exist.pb]
+gateway:
+  base_path: api
+  routes:
+    - service: users.v1.UserService
+      method: GetUser
+      http: FETCH /users
+    - service: users.v1.UserService
+      method: ListUsers
+      http: users
+"#,
+        )
+        .unwrap();
+
+        let problems = config.validate();
+        assert_eq!(problems.len(), 5, "{:#?}", problems);
+        assert!(problems[0].contains("port 7000"));
+        assert!(problems[1].contains("does-not-exist.pb"));
+        assert!(problems[2].contains("base_path"));
+        assert!(problems[3].contains("unsupported HTTP method 'FETCH'"));
+        assert!(problems[4].contains("must be '<METHOD> /<path>'"));
+    }
+}
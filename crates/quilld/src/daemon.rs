@@ -0,0 +1,266 @@
+//! Daemon composition
+//!
+//! This module provides:
+//! - `Daemon`, which builds the RPC router, reflection service, plugins,
+//!   admin endpoints and REST gateway from a [`QuilldConfig`]
+//! - `Daemon::run`, serving every configured listener until shutdown
+
+use crate::admin;
+use crate::config::QuilldConfig;
+use crate::error::{QuilldError, QuilldResult};
+use prost_reflect::DescriptorPool;
+use quill_server::{ObservabilityCollector, QuillServer, RpcRouter, ServerConfig};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::net::TcpListener;
+use tracing::info;
+
+#[cfg(feature = "plugins")]
+use quill_server::PluginHost;
+#[cfg(feature = "plugins")]
+use std::sync::Arc;
+
+#[cfg(feature = "gateway")]
+use quill_rest_gateway::{HttpMethod, MessageConverter, RestGateway, RestGatewayBuilder, RouteMapping};
+
+/// A configured Quill daemon
+pub struct Daemon {
+    config: QuilldConfig,
+    pool: DescriptorPool,
+    router: RpcRouter,
+    collector: ObservabilityCollector,
+    #[cfg(feature = "plugins")]
+    plugins: Option<Arc<PluginHost>>,
+}
+
+impl Daemon {
+    /// Build a daemon from a validated configuration
+    pub fn new(config: QuilldConfig) -> QuilldResult<Self> {
+        let problems = config.validate();
+        if !problems.is_empty() {
+            return Err(QuilldError::Config(problems.join("; ")));
+        }
+
+        let pool = config.descriptor_pool()?;
+        let collector = ObservabilityCollector::new();
+        let mut router = RpcRouter::new();
+        router.set_observability(collector.clone());
+        if config.reflection {
+            router
+                .add_file_descriptor_set(&pool.encode_to_vec())
+                .map_err(|e| QuilldError::Config(format!("descriptors unusable for reflection: {}", e)))?;
+            router.enable_reflection();
+        }
+        #[cfg(feature = "plugins")]
+        let plugins = match &config.plugins {
+            Some(section) => Some(crate::plugins::load(section, &mut router)?),
+            None => None,
+        };
+
+        Ok(Self {
+            config,
+            pool,
+            router,
+            collector,
+            #[cfg(feature = "plugins")]
+            plugins,
+        })
+    }
+
+    /// The daemon configuration
+    pub fn config(&self) -> &QuilldConfig {
+        &self.config
+    }
+
+    /// Descriptors of the served services
+    pub fn descriptor_pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    /// The RPC router, for registering service handlers before `run`
+    pub fn router_mut(&mut self) -> &mut RpcRouter {
+        &mut self.router
+    }
+
+    /// Metrics and health state served by the admin endpoints
+    pub fn collector(&self) -> &ObservabilityCollector {
+        &self.collector
+    }
+
+    /// Host running the configured WASM plugins
+    #[cfg(feature = "plugins")]
+    pub fn plugin_host(&self) -> Option<&Arc<PluginHost>> {
+        self.plugins.as_ref()
+    }
+
+    /// Build the REST gateway, if one is configured
+    #[cfg(feature = "gateway")]
+    pub fn gateway(&self) -> QuilldResult<Option<RestGateway>> {
+        let Some(section) = &self.config.gateway else {
+            return Ok(None);
+        };
+
+        let client = quill_client::QuillClient::new(format!("http://{}", loopback(self.config.server.listen)));
+        let mut builder = RestGatewayBuilder::new(client)
+            .title(&section.title)
+            .version(&section.version)
+            .base_path(&section.base_path);
+        if !self.config.descriptors.is_empty() {
+            builder = builder.with_converter(MessageConverter::new(self.pool.clone()));
+        }
+
+        for route in &section.routes {
+            let (method, template) = route
+                .http_parts()
+                .ok_or_else(|| QuilldError::Gateway(format!("invalid route http '{}'", route.http)))?;
+            let method = HttpMethod::from_str(method)
+                .ok_or_else(|| QuilldError::Gateway(format!("unsupported HTTP method '{}'", method)))?;
+            let mapping = RouteMapping::new(&route.service, &route.method)
+                .add_mapping(method, template)
+                .map_err(|e| QuilldError::Gateway(e.to_string()))?;
+            builder = builder.route(mapping);
+        }
+
+        Ok(Some(builder.build()))
+    }
+
+    /// The gateway's OpenAPI document, if a gateway is configured
+    #[cfg(feature = "gateway")]
+    pub fn openapi_json(&self) -> QuilldResult<Option<String>> {
+        match self.gateway()? {
+            Some(gateway) => gateway
+                .openapi_json()
+                .map(Some)
+                .map_err(|e| QuilldError::Gateway(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Serve every configured listener until `shutdown` completes or one fails
+    pub async fn run(self, shutdown: impl Future<Output = ()>) -> QuilldResult<()> {
+        #[cfg(feature = "gateway")]
+        let gateway = match (&self.config.gateway, self.gateway()?) {
+            (Some(section), Some(gateway)) => Some((section.listen, gateway.router())),
+            _ => None,
+        };
+
+        let admin = match &self.config.admin {
+            Some(section) => Some((bind(section.listen).await?, admin::router(self.collector.clone()))),
+            None => None,
+        };
+
+        let server_config = ServerConfig {
+            http_version: self.config.server.http_version.into(),
+            ..ServerConfig::default()
+        };
+        let server_addr = self.config.server.listen;
+        let server = QuillServer::with_config(self.router, server_config);
+        info!("quilld serving RPC on {}", server_addr);
+
+        let admin_task = async move {
+            match admin {
+                Some((listener, router)) => {
+                    info!("quilld serving admin endpoints on {}", listener.local_addr().map_err(server_error)?);
+                    axum::serve(listener, router).await.map_err(server_error)
+                }
+                None => std::future::pending().await,
+            }
+        };
+
+        #[cfg(feature = "gateway")]
+        let gateway_task = async move {
+            match gateway {
+                Some((addr, router)) => {
+                    let listener = bind(addr).await?;
+                    info!("quilld serving REST gateway on {}", addr);
+                    axum::serve(listener, router).await.map_err(server_error)
+                }
+                None => std::future::pending().await,
+            }
+        };
+        #[cfg(not(feature = "gateway"))]
+        let gateway_task = std::future::pending::<QuilldResult<()>>();
+
+        #[cfg(feature = "plugins")]
+        let plugin_watcher = {
+            let (plugins, section) = (self.plugins, self.config.plugins);
+            async move {
+                match (plugins, section) {
+                    (Some(host), Some(section)) => crate::plugins::watch(host, section).await,
+                    _ => std::future::pending().await,
+                }
+            }
+        };
+        #[cfg(not(feature = "plugins"))]
+        let plugin_watcher = std::future::pending::<()>();
+
+        tokio::select! {
+            result = server.serve(server_addr) => result.map_err(|e| QuilldError::Server(e.to_string())),
+            result = admin_task => result,
+            result = gateway_task => result,
+            _ = plugin_watcher => Ok(()),
+            _ = shutdown => {
+                info!("quilld shutting down");
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn bind(addr: SocketAddr) -> QuilldResult<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .map_err(|e| QuilldError::Server(format!("failed to bind {}: {}", addr, e)))
+}
+
+fn server_error(e: std::io::Error) -> QuilldError {
+    QuilldError::Server(e.to_string())
+}
+
+/// Address for reaching a listener from the same host
+#[cfg_attr(not(feature = "gateway"), allow(dead_code))]
+fn loopback(addr: SocketAddr) -> SocketAddr {
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port()),
+        IpAddr::V6(ip) if ip.is_unspecified() => SocketAddr::new(Ipv6Addr::LOCALHOST.into(), addr.port()),
+        _ => addr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loopback() {
+        assert_eq!(loopback("0.0.0.0:8080".parse().unwrap()), "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(loopback("[::]:8080".parse().unwrap()), "[::1]:8080".parse().unwrap());
+        assert_eq!(loopback("10.0.0.1:8080".parse().unwrap()), "10.0.0.1:8080".parse().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_config_rejected() {
+        let config = QuilldConfig::from_yaml("descriptors: [missing.pb]").unwrap();
+        assert!(matches!(Daemon::new(config), Err(QuilldError::Config(_))));
+    }
+
+    #[cfg(feature = "gateway")]
+    #[test]
+    fn test_openapi_lists_routes() {
+        let config = QuilldConfig::from_yaml(
+            r#"
+gateway:
+  title: Users
+  routes:
+    - service: users.v1.UserService
+      method: GetUser
+      http: GET /users/{id}
+"#,
+        )
+        .unwrap();
+        let daemon = Daemon::new(config).unwrap();
+        let json = daemon.openapi_json().unwrap().unwrap();
+        assert!(json.contains("/users/{id}"), "{}", json);
+        assert!(json.contains("Users"));
+    }
+}
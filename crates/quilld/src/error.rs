@@ -0,0 +1,33 @@
+//! Error types for the Quill daemon
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Daemon errors
+#[derive(Debug, Error)]
+pub enum QuilldError {
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("Invalid descriptor set {path}: {message}")]
+    Descriptor { path: PathBuf, message: String },
+
+    #[error("Gateway error: {0}")]
+    Gateway(String),
+
+    #[error("Plugin error: {0}")]
+    Plugin(String),
+
+    #[error("Server error: {0}")]
+    Server(String),
+}
+
+/// Result type for daemon operations
+pub type QuilldResult<T> = Result<T, QuilldError>;
@@ -0,0 +1,26 @@
+//! Quill daemon
+//!
+//! `quilld` composes the Quill server, REST gateway, metrics, health checks
+//! and reflection into a single deployable process driven by a YAML file.
+//!
+//! This module provides:
+//! - Configuration loading and validation
+//! - Reflection over the configured descriptors
+//! - Admin endpoints (`/metrics`, `/healthz`, `/readyz`)
+//! - Hot-loaded WASM handler plugins (with `plugins` feature)
+//! - `Daemon`, which serves every configured listener
+
+pub mod admin;
+pub mod config;
+pub mod daemon;
+pub mod error;
+#[cfg(feature = "plugins")]
+pub mod plugins;
+
+pub use config::{
+    AdminSection, GatewaySection, HttpVersionSetting, PluginModuleSection, PluginsSection, QuilldConfig, RouteSection,
+    ServerSection,
+};
+pub use daemon::Daemon;
+pub use error::{QuilldError, QuilldResult};
+pub use quill_core::{REFLECTION_METHOD, REFLECTION_PATH, REFLECTION_SERVICE};
@@ -0,0 +1,120 @@
+//! Deployable Quill daemon.
+//!
+//! Provides commands for:
+//! - serve: Run the server, gateway and admin endpoints
+//! - check-config: Validate a configuration file
+//! - print-openapi: Print the gateway's OpenAPI document
+
+use clap::{Parser, Subcommand};
+use quilld::config::DEFAULT_CONFIG_PATH;
+use quilld::{Daemon, QuilldConfig, QuilldResult};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser)]
+#[command(name = "quilld")]
+#[command(about = "Deployable Quill daemon", long_about = None)]
+#[command(version)]
+struct Cli {
+    /// Configuration file
+    #[arg(short, long, global = true, default_value = DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Run the server, gateway and admin endpoints
+    Serve,
+    /// Validate the configuration file
+    CheckConfig,
+    /// Print the gateway's OpenAPI document
+    PrintOpenapi,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Commands::Serve => serve(&cli.config).await,
+        Commands::CheckConfig => check_config(&cli.config),
+        Commands::PrintOpenapi => print_openapi(&cli.config),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn serve(path: &Path) -> QuilldResult<ExitCode> {
+    let daemon = Daemon::new(QuilldConfig::load(path)?)?;
+    daemon
+        .run(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn check_config(path: &Path) -> QuilldResult<ExitCode> {
+    let config = QuilldConfig::load(path)?;
+    let problems = config.validate();
+    if !problems.is_empty() {
+        eprintln!("{}: {} problem(s)", path.display(), problems.len());
+        for problem in &problems {
+            eprintln!("  - {}", problem);
+        }
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let services = config.descriptor_pool()?.services().count();
+    println!("{}: ok", path.display());
+    println!("  server:   {} ({:?})", config.server.listen, config.server.http_version);
+    println!("  services: {} from {} descriptor set(s)", services, config.descriptors.len());
+    println!("  reflection: {}", if config.reflection { "enabled" } else { "disabled" });
+    match &config.admin {
+        Some(admin) => println!("  admin:    {}", admin.listen),
+        None => println!("  admin:    disabled"),
+    }
+    match &config.gateway {
+        Some(gateway) => println!("  gateway:  {} ({} route(s))", gateway.listen, gateway.routes.len()),
+        None => println!("  gateway:  disabled"),
+    }
+    match &config.plugins {
+        Some(plugins) => println!("  plugins:  {} module(s)", plugins.modules.len()),
+        None => println!("  plugins:  disabled"),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(feature = "gateway")]
+fn print_openapi(path: &Path) -> QuilldResult<ExitCode> {
+    let daemon = Daemon::new(QuilldConfig::load(path)?)?;
+    match daemon.openapi_json()? {
+        Some(json) => {
+            println!("{}", json);
+            Ok(ExitCode::SUCCESS)
+        }
+        None => {
+            eprintln!("{}: no gateway section configured", path.display());
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+#[cfg(not(feature = "gateway"))]
+fn print_openapi(_path: &Path) -> QuilldResult<ExitCode> {
+    eprintln!("quilld was built without the `gateway` feature");
+    Ok(ExitCode::FAILURE)
+}
@@ -0,0 +1,83 @@
+//! WASM plugin loading and hot reload
+//!
+//! This module provides:
+//! - Loading the configured plugin modules and registering their methods
+//! - A watcher reloading a module when its file changes
+//!
+//! Reloads swap the module behind already registered methods; adding or
+//! removing methods requires a restart.
+
+use crate::config::PluginsSection;
+use crate::error::{QuilldError, QuilldResult};
+use quill_server::{PluginConfig, PluginHost, RpcRouter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+/// Load every configured module into a new host and register its methods on `router`
+pub fn load(section: &PluginsSection, router: &mut RpcRouter) -> QuilldResult<Arc<PluginHost>> {
+    let host = PluginHost::new(PluginConfig {
+        max_memory_bytes: section.max_memory_mb * 1024 * 1024,
+        fuel: section.fuel,
+        inherit_stdio: true,
+    })
+    .map_err(|e| QuilldError::Plugin(e.to_string()))?;
+    let host = Arc::new(host);
+
+    for module in &section.modules {
+        host.load(&module.name, &module.path)
+            .map_err(|e| QuilldError::Plugin(format!("{}: {}", module.name, e)))?;
+        for method in &module.methods {
+            host.register_unary(router, method, &module.name);
+        }
+        for method in &module.streaming_methods {
+            host.register_streaming(router, method, &module.name);
+        }
+        info!(
+            "loaded plugin '{}' from {} ({} method(s))",
+            module.name,
+            module.path.display(),
+            module.methods.len() + module.streaming_methods.len()
+        );
+    }
+    Ok(host)
+}
+
+/// Reload modules whose files change, until the returned future is dropped
+///
+/// Never completes; pending forever when hot reload is disabled.
+pub async fn watch(host: Arc<PluginHost>, section: PluginsSection) {
+    if section.reload_interval_ms == 0 {
+        return std::future::pending().await;
+    }
+
+    let mut modules: Vec<(String, PathBuf, Option<SystemTime>)> = section
+        .modules
+        .into_iter()
+        .map(|module| {
+            let modified = modified(&module.path);
+            (module.name, module.path, modified)
+        })
+        .collect();
+
+    let mut interval = tokio::time::interval(Duration::from_millis(section.reload_interval_ms));
+    loop {
+        interval.tick().await;
+        for (name, path, last) in &mut modules {
+            let current = modified(path);
+            if current.is_none() || current == *last {
+                continue;
+            }
+            *last = current;
+            match host.reload(name) {
+                Ok(generation) => info!("reloaded plugin '{}' (generation {})", name, generation),
+                Err(e) => warn!("failed to reload plugin '{}', keeping previous version: {}", name, e),
+            }
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,126 @@
+//! End-to-end tests for the Quill daemon
+
+use bytes::Bytes;
+use prost::Message;
+use prost_types::{FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto};
+use quill_client::QuillClient;
+use quilld::{Daemon, QuilldConfig, REFLECTION_METHOD, REFLECTION_SERVICE};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+
+fn free_addr() -> SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+/// Wait until something accepts connections on `addr`
+async fn wait_until_listening(addr: SocketAddr) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while tokio::net::TcpStream::connect(addr).await.is_err() {
+        assert!(tokio::time::Instant::now() < deadline, "nothing listening on {}", addr);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+fn descriptor_set() -> Vec<u8> {
+    let file = FileDescriptorProto {
+        name: Some("echo.proto".to_string()),
+        package: Some("echo.v1".to_string()),
+        syntax: Some("proto3".to_string()),
+        message_type: vec![prost_types::DescriptorProto {
+            name: Some("Message".to_string()),
+            ..Default::default()
+        }],
+        service: vec![ServiceDescriptorProto {
+            name: Some("EchoService".to_string()),
+            method: vec![MethodDescriptorProto {
+                name: Some("Echo".to_string()),
+                input_type: Some(".echo.v1.Message".to_string()),
+                output_type: Some(".echo.v1.Message".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    FileDescriptorSet { file: vec![file] }.encode_to_vec()
+}
+
+async fn http_get(addr: SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!("GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", path, addr);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_daemon_serves_rpc_reflection_and_admin() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("echo.pb"), descriptor_set()).unwrap();
+
+    let server_addr = free_addr();
+    let admin_addr = free_addr();
+    let config_path = dir.path().join("quilld.yaml");
+    let mut file = std::fs::File::create(&config_path).unwrap();
+    write!(
+        file,
+        "server:\n  listen: {}\nadmin:\n  listen: {}\ndescriptors: [echo.pb]\n",
+        server_addr, admin_addr
+    )
+    .unwrap();
+
+    let config = QuilldConfig::load(&config_path).unwrap();
+    assert!(config.validate().is_empty());
+
+    let mut daemon = Daemon::new(config).unwrap();
+    daemon
+        .router_mut()
+        .register_unary("echo.v1.EchoService/Echo", |req: Bytes| async move { Ok(req) });
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let handle = tokio::spawn(daemon.run(async {
+        let _ = stopped.await;
+    }));
+    wait_until_listening(server_addr).await;
+    wait_until_listening(admin_addr).await;
+
+    let client = QuillClient::new(format!("http://{}", server_addr));
+    let echoed = client
+        .call("echo.v1.EchoService", "Echo", Bytes::from_static(b"hello"))
+        .await
+        .unwrap();
+    assert_eq!(echoed, Bytes::from_static(b"hello"));
+
+    let descriptors = client.call(REFLECTION_SERVICE, REFLECTION_METHOD, Bytes::new()).await.unwrap();
+    let set = FileDescriptorSet::decode(descriptors).unwrap();
+    assert_eq!(set.file.len(), 1);
+    assert_eq!(set.file[0].package.as_deref(), Some("echo.v1"));
+    assert_eq!(set.file[0].service[0].name.as_deref(), Some("EchoService"));
+
+    let missing = client
+        .call(REFLECTION_SERVICE, REFLECTION_METHOD, Bytes::from_static(b"missing.v1.Missing"))
+        .await;
+    assert!(missing.is_err());
+
+    let health = http_get(admin_addr, "/healthz").await;
+    assert!(health.starts_with("HTTP/1.1 200"), "{}", health);
+
+    let ready = http_get(admin_addr, "/readyz").await;
+    assert!(ready.starts_with("HTTP/1.1 200"), "{}", ready);
+
+    let metrics = http_get(admin_addr, "/metrics").await;
+    assert!(metrics.starts_with("HTTP/1.1 200"), "{}", metrics);
+    assert!(metrics.contains("echo.v1.EchoService/Echo"), "{}", metrics);
+
+    stop.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,148 @@
+//! End-to-end tests for WASM handler plugins served by the daemon
+#![cfg(feature = "plugins")]
+
+use bytes::Bytes;
+use quill_client::QuillClient;
+use quilld::{Daemon, QuilldConfig};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio_stream::StreamExt;
+
+/// Echoes the request
+const ECHO_V1: &str = r#"
+    (module
+      (import "quill" "emit" (func $emit (param i32 i32)))
+      (memory (export "memory") 1)
+      (func (export "quill_alloc") (param i32) (result i32) (i32.const 1024))
+      (func (export "quill_handle") (param i32 i32 i32 i32) (result i32)
+        (call $emit (local.get 2) (local.get 3))
+        (i32.const 0)))
+"#;
+
+/// Answers every request with `v2`
+const ECHO_V2: &str = r#"
+    (module
+      (import "quill" "emit" (func $emit (param i32 i32)))
+      (memory (export "memory") 1)
+      (data (i32.const 0) "v2")
+      (func (export "quill_alloc") (param i32) (result i32) (i32.const 1024))
+      (func (export "quill_handle") (param i32 i32 i32 i32) (result i32)
+        (call $emit (i32.const 0) (i32.const 2))
+        (i32.const 0)))
+"#;
+
+/// Streams each request byte as its own message
+const SPLIT: &str = r#"
+    (module
+      (import "quill" "emit" (func $emit (param i32 i32)))
+      (memory (export "memory") 1)
+      (global $next (mut i32) (i32.const 1024))
+      (func (export "quill_alloc") (param $len i32) (result i32)
+        (local $ptr i32)
+        (local.set $ptr (global.get $next))
+        (global.set $next (i32.add (global.get $next) (local.get $len)))
+        (local.get $ptr))
+      (func (export "quill_handle") (param i32 i32) (param $req i32) (param $len i32) (result i32)
+        (local $i i32)
+        (block $done
+          (loop $next
+            (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+            (call $emit (i32.add (local.get $req) (local.get $i)) (i32.const 1))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $next)))
+        (i32.const 0)))
+"#;
+
+fn free_addr() -> SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+/// Wait until something accepts connections on `addr`
+async fn wait_until_listening(addr: SocketAddr) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while tokio::net::TcpStream::connect(addr).await.is_err() {
+        assert!(tokio::time::Instant::now() < deadline, "nothing listening on {}", addr);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_plugins_serve_and_hot_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("echo.wat"), ECHO_V1).unwrap();
+    std::fs::write(dir.path().join("split.wat"), SPLIT).unwrap();
+
+    let server_addr = free_addr();
+    let config_path = dir.path().join("quilld.yaml");
+    std::fs::write(
+        &config_path,
+        format!(
+            r#"
+server:
+  listen: {}
+plugins:
+  reload_interval_ms: 20
+  modules:
+    - name: echo
+      path: echo.wat
+      methods: [echo.v1.EchoService/Echo]
+    - name: split
+      path: split.wat
+      streaming_methods: [echo.v1.EchoService/Split]
+"#,
+            server_addr
+        ),
+    )
+    .unwrap();
+
+    let daemon = Daemon::new(QuilldConfig::load(&config_path).unwrap()).unwrap();
+    let host = daemon.plugin_host().unwrap().clone();
+    assert_eq!(host.names(), vec!["echo", "split"]);
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let handle = tokio::spawn(daemon.run(async {
+        let _ = stopped.await;
+    }));
+    wait_until_listening(server_addr).await;
+
+    let client = QuillClient::new(format!("http://{}", server_addr));
+    let echoed = client
+        .call("echo.v1.EchoService", "Echo", Bytes::from_static(b"hello"))
+        .await
+        .unwrap();
+    assert_eq!(echoed, Bytes::from_static(b"hello"));
+
+    let stream = client
+        .call_server_streaming("echo.v1.EchoService", "Split", Bytes::from_static(b"abc"))
+        .await
+        .unwrap();
+    let messages: Vec<Bytes> = stream.map(|message| message.unwrap()).collect().await;
+    assert_eq!(messages, vec![Bytes::from_static(b"a"), Bytes::from_static(b"b"), Bytes::from_static(b"c")]);
+
+    // Broken modules are rejected and the previous version keeps serving
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    std::fs::write(dir.path().join("echo.wat"), "(module").unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(host.generation("echo"), Some(1));
+
+    std::fs::write(dir.path().join("echo.wat"), ECHO_V2).unwrap();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while host.generation("echo") != Some(2) {
+        assert!(tokio::time::Instant::now() < deadline, "plugin was not reloaded");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let echoed = client
+        .call("echo.v1.EchoService", "Echo", Bytes::from_static(b"hello"))
+        .await
+        .unwrap();
+    assert_eq!(echoed, Bytes::from_static(b"v2"));
+
+    stop.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,12 @@
+use quill_codegen::{compile_protos, QuillConfig};
+
+fn main() -> std::io::Result<()> {
+    let config = QuillConfig::new()
+        .with_package_prefix("example")
+        .with_serde(true)
+        .with_reflection(true);
+
+    compile_protos(&["proto/chat.proto"], &["proto"], config)?;
+
+    Ok(())
+}
@@ -0,0 +1,319 @@
+//! Reference chat service exercising the full Quill stack
+//!
+//! Unlike the smaller examples, this service is wired the way a production
+//! service would be, and its tests double as end-to-end tests of the
+//! library subsystems it relies on:
+//!
+//! - Generated client and server stubs from `proto/chat.proto`
+//! - Token-by-token streaming replies fed by a producer task
+//! - Cancellation: dropping the reply stream fires a [`CancelGuard`] and
+//!   stops generation mid-reply
+//! - Conversation state in a [`SessionStore`], keyed by session ID
+//! - Usage recorded on the call's [`UsageRecorder`] and sent in a USAGE
+//!   trailer, and billed to the session
+//! - REST exposure through the gateway, streaming replies as SSE
+//! - The same router served over HTTP/2 and HTTP/3
+//!
+//! The "model" is deterministic: it answers with the user's words, so tests
+//! can predict every token.
+//!
+//! ## Running the Example
+//!
+//! ```bash
+//! cargo test -p chat-service-example
+//! ```
+//!
+//! [`UsageRecorder`]: quill_server::UsageRecorder
+
+use futures::Stream;
+use quill_client::QuillClient;
+use quill_core::{ProblemDetails, QuillError, Usage};
+use quill_rest_gateway::{GatewayResult, HttpMethod, RestGateway, RestGatewayBuilder, RouteMapping};
+use quill_server::{
+    new_session_id, session_id, CancelGuard, InMemorySessionStore, QuillH3Server, QuillServer,
+    RequestContext, ServerBuilder, SessionStore,
+};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+// Include the generated protobuf code
+pub mod chat {
+    include!(concat!(env!("OUT_DIR"), "/chat.v1.rs"));
+}
+
+pub use chat::{GetSessionRequest, ReplyToken, SendRequest, SessionInfo};
+
+use chat::chat_service_server::{add_service, ChatService as ChatServiceApi};
+
+/// Tokens generated when a request doesn't set `max_tokens`
+pub const DEFAULT_MAX_TOKENS: u32 = 64;
+
+/// Delay between two generated tokens
+pub const DEFAULT_TOKEN_INTERVAL: Duration = Duration::from_millis(5);
+
+/// One exchange of a conversation
+#[derive(Debug, Clone)]
+pub struct Turn {
+    pub message: String,
+    /// Reply tokens the caller received
+    pub reply: String,
+    /// Whether the caller cancelled before the reply finished
+    pub cancelled: bool,
+}
+
+/// Conversation state kept between calls
+#[derive(Debug, Clone, Default)]
+pub struct ChatSession {
+    pub turns: Vec<Turn>,
+    /// Usage billed to the session across all its calls
+    pub usage: Usage,
+}
+
+impl ChatSession {
+    /// Tokens of context the model reads before the next message
+    fn context_tokens(&self) -> u64 {
+        self.turns
+            .iter()
+            .map(|turn| count_tokens(&turn.message) + count_tokens(&turn.reply))
+            .sum()
+    }
+
+    fn info(&self, session_id: &str) -> SessionInfo {
+        SessionInfo {
+            session_id: session_id.to_string(),
+            turns: self.turns.len() as u32,
+            cancelled_turns: self.turns.iter().filter(|turn| turn.cancelled).count() as u32,
+            input_tokens: self.usage.input_tokens,
+            output_tokens: self.usage.output_tokens,
+        }
+    }
+}
+
+fn count_tokens(text: &str) -> u64 {
+    text.split_whitespace().count() as u64
+}
+
+/// Reply of the deterministic model, one entry per token
+fn generate_reply(session: &ChatSession, message: &str, max_tokens: usize) -> Vec<String> {
+    let turn = session.turns.len() + 1;
+    let mut words = vec![format!("[{}]", turn), "You".to_string(), "said:".to_string()];
+    words.extend(message.split_whitespace().map(str::to_string));
+    words
+        .into_iter()
+        .take(max_tokens)
+        .enumerate()
+        .map(|(i, word)| if i == 0 { word } else { format!(" {}", word) })
+        .collect()
+}
+
+/// Chat service backed by a session store
+#[derive(Clone)]
+pub struct ChatService {
+    sessions: Arc<dyn SessionStore<ChatSession>>,
+    token_interval: Duration,
+}
+
+impl ChatService {
+    /// Create a service keeping sessions in `sessions`
+    pub fn new(sessions: Arc<dyn SessionStore<ChatSession>>) -> Self {
+        Self {
+            sessions,
+            token_interval: DEFAULT_TOKEN_INTERVAL,
+        }
+    }
+
+    /// Create a service keeping sessions in memory for an hour
+    pub fn in_memory() -> Self {
+        let store = InMemorySessionStore::new().with_ttl(Duration::from_secs(3600));
+        Self::new(Arc::new(store))
+    }
+
+    /// Set the delay between two generated tokens
+    pub fn with_token_interval(mut self, interval: Duration) -> Self {
+        self.token_interval = interval;
+        self
+    }
+
+    /// Session store the service reads and writes
+    pub fn sessions(&self) -> &Arc<dyn SessionStore<ChatSession>> {
+        &self.sessions
+    }
+}
+
+#[async_trait::async_trait]
+impl ChatServiceApi for ChatService {
+    async fn send(
+        &self,
+        request: SendRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<ReplyToken, QuillError>> + Send>>, QuillError> {
+        if request.message.trim().is_empty() {
+            return Err(QuillError::ProblemDetails(
+                ProblemDetails::new(http::StatusCode::BAD_REQUEST, "Empty message")
+                    .with_detail("message must contain at least one word"),
+            ));
+        }
+
+        // The context is only visible here, not inside the producer task
+        let ctx = RequestContext::current().unwrap_or_default();
+        let id = match request.session_id.as_str() {
+            "" => session_id(&ctx).map(str::to_string).unwrap_or_else(new_session_id),
+            id => id.to_string(),
+        };
+        let session = self.sessions.load(&id).unwrap_or_default();
+
+        let max_tokens = match request.max_tokens {
+            0 => DEFAULT_MAX_TOKENS,
+            max => max,
+        };
+        let reply = generate_reply(&session, &request.message, max_tokens as usize);
+        let input_tokens = session.context_tokens() + count_tokens(&request.message);
+        let usage = ctx.usage().clone();
+        usage.record_input(input_tokens);
+
+        let (tx, rx) = mpsc::channel(8);
+        let (guard, signal) = CancelGuard::new();
+        let sessions = Arc::clone(&self.sessions);
+        let interval = self.token_interval;
+        tokio::spawn(async move {
+            let mut sent = String::new();
+            let mut output_tokens = 0;
+            for (index, text) in reply.iter().enumerate() {
+                tokio::select! {
+                    () = signal.cancelled() => break,
+                    () = tokio::time::sleep(interval) => {}
+                }
+                let token = ReplyToken {
+                    session_id: id.clone(),
+                    text: text.clone(),
+                    index: index as u32,
+                };
+                if tx.send(Ok(token)).await.is_err() {
+                    break;
+                }
+                // Tokens are billed once queued for the caller
+                usage.record_output(1);
+                output_tokens += 1;
+                sent.push_str(text);
+            }
+
+            let turn = Turn {
+                message: request.message,
+                reply: sent,
+                cancelled: output_tokens < reply.len() as u64,
+            };
+            sessions.update(&id, &mut |session: &mut ChatSession| {
+                session.turns.push(turn.clone());
+                session.usage.add(&Usage::new(input_tokens, output_tokens));
+            });
+            // The reply stream ends once the session is saved
+            drop(tx);
+        });
+
+        Ok(Box::pin(ReplyStream {
+            tokens: ReceiverStream::new(rx),
+            _cancel: guard,
+        }))
+    }
+
+    async fn get_session(&self, request: GetSessionRequest) -> Result<SessionInfo, QuillError> {
+        match self.sessions.load(&request.session_id) {
+            Some(session) => Ok(session.info(&request.session_id)),
+            None => Err(QuillError::ProblemDetails(
+                ProblemDetails::new(http::StatusCode::NOT_FOUND, "Session not found")
+                    .with_detail(format!("No session '{}'", request.session_id)),
+            )),
+        }
+    }
+}
+
+/// Reply tokens, cancelling generation when dropped early
+struct ReplyStream {
+    tokens: ReceiverStream<Result<ReplyToken, QuillError>>,
+    _cancel: CancelGuard,
+}
+
+impl Stream for ReplyStream {
+    type Item = Result<ReplyToken, QuillError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.tokens).poll_next(cx)
+    }
+}
+
+/// Register the chat service, with reflection, on `builder`
+pub fn add_chat_service(builder: ServerBuilder, service: ChatService) -> ServerBuilder {
+    let builder = builder
+        .enable_reflection()
+        .file_descriptor_set(chat::FILE_DESCRIPTOR_SET)
+        .expect("generated descriptor set is valid");
+    add_service(builder, service)
+}
+
+/// Create an HTTP/2 server for `service`
+pub fn create_server(service: ChatService) -> QuillServer {
+    add_chat_service(QuillServer::builder(), service).build()
+}
+
+/// Create an HTTP/3 server for `service` bound to `addr`
+pub fn create_h3_server(service: ChatService, addr: SocketAddr) -> QuillH3Server {
+    let router = add_chat_service(QuillServer::builder(), service).into_router();
+    QuillH3Server::new(router, addr)
+}
+
+/// Create a REST gateway in front of a chat server
+///
+/// - `POST /v1/chat` streams a reply as SSE (or NDJSON), ending with a
+///   `usage` event
+/// - `GET /v1/sessions/{session_id}` describes a session
+///
+/// Request metadata is forwarded, so a `session-id` header selects the
+/// session too.
+pub fn create_gateway(client: QuillClient) -> GatewayResult<RestGateway> {
+    let send = RouteMapping::new("ChatService", "send")
+        .add_mapping(HttpMethod::Post, "/v1/chat")?
+        .server_streaming();
+    let get_session = RouteMapping::new("ChatService", "get_session")
+        .add_mapping(HttpMethod::Get, "/v1/sessions/{session_id}")?;
+
+    Ok(RestGatewayBuilder::new(client)
+        .base_path("")
+        .title("Chat API")
+        .with_converter(chat::message_converter()?)
+        .forward_metadata(true)
+        .route(send)
+        .route(get_session)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reply_echoes_the_message() {
+        let reply = generate_reply(&ChatSession::default(), "hello  there", 64);
+        assert_eq!(reply.concat(), "[1] You said: hello there");
+        assert_eq!(reply.len(), 5);
+
+        let truncated = generate_reply(&ChatSession::default(), "hello there", 2);
+        assert_eq!(truncated, vec!["[1]", " You"]);
+    }
+
+    #[test]
+    fn test_context_grows_with_turns() {
+        let mut session = ChatSession::default();
+        assert_eq!(session.context_tokens(), 0);
+        session.turns.push(Turn {
+            message: "hi there".to_string(),
+            reply: "[1] You said: hi there".to_string(),
+            cancelled: false,
+        });
+        assert_eq!(session.context_tokens(), 7);
+        assert_eq!(generate_reply(&session, "again", 64)[0], "[2]");
+    }
+}
@@ -0,0 +1,108 @@
+//! The chat service behind the REST gateway, streaming replies as SSE
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use chat_service_example::{create_gateway, create_server, ChatService};
+use http_body_util::BodyExt;
+use quill_client::QuillClient;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+async fn gateway() -> axum::Router {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = create_server(ChatService::in_memory()).serve_with_listener(listener).await;
+    });
+    let client = QuillClient::builder().base_url(format!("http://{}", addr)).build().unwrap();
+    create_gateway(client).unwrap().router()
+}
+
+/// Parse an SSE body into (event type, data) pairs
+fn parse_sse(body: &str) -> Vec<(Option<String>, Value)> {
+    body.split("\n\n")
+        .filter(|event| !event.trim().is_empty())
+        .map(|event| {
+            let mut event_type = None;
+            let mut data = Value::Null;
+            for line in event.lines() {
+                if let Some(value) = line.strip_prefix("event: ") {
+                    event_type = Some(value.to_string());
+                } else if let Some(value) = line.strip_prefix("data: ") {
+                    data = serde_json::from_str(value).unwrap();
+                }
+            }
+            (event_type, data)
+        })
+        .collect()
+}
+
+async fn post_chat(router: axum::Router, accept: &str, session: Option<&str>, body: Value) -> (StatusCode, String) {
+    let mut request = Request::builder()
+        .method("POST")
+        .uri("/v1/chat")
+        .header("content-type", "application/json")
+        .header("accept", accept);
+    if let Some(session) = session {
+        request = request.header("session-id", session);
+    }
+    let response = router
+        .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_reply_streams_as_sse_with_usage() {
+    let router = gateway().await;
+    let (status, body) =
+        post_chat(router, "text/event-stream", None, json!({"message": "hello gateway"})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let events = parse_sse(&body);
+    let (usage_type, usage) = events.last().unwrap();
+    assert_eq!(usage_type.as_deref(), Some("usage"));
+    assert_eq!(usage["input_tokens"], 2);
+    assert_eq!(usage["output_tokens"], 5);
+
+    let tokens = &events[..events.len() - 1];
+    assert_eq!(tokens.len(), 5);
+    assert!(tokens.iter().all(|(event_type, _)| event_type.is_none()));
+    let text: String = tokens.iter().map(|(_, token)| token["text"].as_str().unwrap()).collect();
+    assert_eq!(text, "[1] You said: hello gateway");
+}
+
+#[tokio::test]
+async fn test_session_header_selects_the_session() {
+    let router = gateway().await;
+    let (_, body) = post_chat(router.clone(), "text/event-stream", Some("web-1"), json!({"message": "one"})).await;
+    let events = parse_sse(&body);
+    assert!(events[..events.len() - 1].iter().all(|(_, token)| token["sessionId"] == "web-1"));
+
+    // The second turn is streamed as NDJSON instead
+    let (_, body) =
+        post_chat(router.clone(), "application/x-ndjson", Some("web-1"), json!({"message": "two"})).await;
+    let lines: Vec<Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines[0]["text"], "[2]");
+    assert!(lines.last().unwrap()["usage"]["output_tokens"].is_number());
+
+    let request = Request::builder().uri("/v1/sessions/web-1").body(Body::empty()).unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let info: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(info["sessionId"], "web-1");
+    assert_eq!(info["turns"], 2);
+}
+
+#[tokio::test]
+async fn test_rejected_message_is_a_problem() {
+    let router = gateway().await;
+    let (status, body) = post_chat(router, "text/event-stream", None, json!({"message": ""})).await;
+    // Upstream failures surface as gateway errors carrying the upstream problem
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(body.contains("Empty message"));
+}
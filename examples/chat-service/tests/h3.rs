@@ -0,0 +1,50 @@
+//! The same chat router served over HTTP/3
+
+use bytes::Bytes;
+use chat_service_example::{create_h3_server, ChatService, ReplyToken, SendRequest};
+use futures::StreamExt;
+use prost::Message;
+use quill_client::QuillH3Client;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_reply_streams_over_http3() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let addr: SocketAddr = {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.local_addr().unwrap()
+    };
+    let service = ChatService::in_memory();
+    let sessions = service.sessions().clone();
+    let server = create_h3_server(service, addr);
+    let server_handle = tokio::spawn(async move {
+        if let Err(e) = server.serve().await {
+            eprintln!("HTTP/3 server error: {}", e);
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let client = QuillH3Client::builder(addr).build().unwrap();
+    let request = SendRequest {
+        session_id: "h3-session".to_string(),
+        message: "over quic".to_string(),
+        max_tokens: 0,
+    };
+    let stream = client
+        .call_server_streaming("ChatService", "send", Bytes::from(request.encode_to_vec()))
+        .await
+        .unwrap();
+
+    // The usage trailer is skipped by receivers that don't read it
+    let tokens: Vec<ReplyToken> = stream.map(|token| ReplyToken::decode(token.unwrap()).unwrap()).collect().await;
+    let text: String = tokens.iter().map(|token| token.text.as_str()).collect();
+    assert_eq!(text, "[1] You said: over quic");
+
+    let session = sessions.load("h3-session").unwrap();
+    assert_eq!(session.turns.len(), 1);
+    assert_eq!(session.usage.output_tokens, 5);
+
+    server_handle.abort();
+}
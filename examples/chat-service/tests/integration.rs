@@ -0,0 +1,165 @@
+//! End-to-end tests of the chat service over HTTP/2 with the generated client
+
+use bytes::Bytes;
+use chat_service_example::chat::chat_service_client::ChatServiceClient;
+use chat_service_example::{create_server, ChatService, GetSessionRequest, ReplyToken, SendRequest};
+use futures::StreamExt;
+use prost::Message;
+use quill_client::{QuillClient, RequestOptions};
+use quill_core::{QuillError, Usage};
+use std::time::Duration;
+
+async fn spawn(service: ChatService) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = create_server(service).serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+fn send_request(session_id: &str, message: &str) -> SendRequest {
+    SendRequest {
+        session_id: session_id.to_string(),
+        message: message.to_string(),
+        max_tokens: 0,
+    }
+}
+
+async fn collect_reply(client: &ChatServiceClient, request: &SendRequest) -> Vec<ReplyToken> {
+    client
+        .send(request)
+        .await
+        .unwrap()
+        .map(|token| token.unwrap())
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn test_reply_streams_token_by_token() {
+    let client = ChatServiceClient::connect(spawn(ChatService::in_memory()).await).unwrap();
+
+    let tokens = collect_reply(&client, &send_request("", "hello quill")).await;
+    let text: String = tokens.iter().map(|token| token.text.as_str()).collect();
+    assert_eq!(text, "[1] You said: hello quill");
+    assert!(tokens.iter().enumerate().all(|(i, token)| token.index == i as u32));
+
+    // A new session was started and named in every token
+    let session_id = tokens[0].session_id.clone();
+    assert_eq!(session_id.len(), 32);
+    assert!(tokens.iter().all(|token| token.session_id == session_id));
+}
+
+#[tokio::test]
+async fn test_session_carries_context_between_calls() {
+    let client = ChatServiceClient::connect(spawn(ChatService::in_memory()).await).unwrap();
+
+    let first = collect_reply(&client, &send_request("", "hi there")).await;
+    let session_id = first[0].session_id.clone();
+    let second = collect_reply(&client, &send_request(&session_id, "again")).await;
+    assert_eq!(second[0].text, "[2]");
+
+    let info = client
+        .get_session(&GetSessionRequest { session_id: session_id.clone() })
+        .await
+        .unwrap();
+    assert_eq!(info.turns, 2);
+    assert_eq!(info.cancelled_turns, 0);
+    // 2 message tokens, then 1 more on top of the 7 tokens of the first turn
+    assert_eq!(info.input_tokens, 2 + 8);
+    assert_eq!(info.output_tokens, 5 + 4);
+}
+
+#[tokio::test]
+async fn test_usage_trailer_matches_the_reply() {
+    let url = spawn(ChatService::in_memory()).await;
+    let client = QuillClient::new(url);
+
+    let request = send_request("", "count these four words").encode_to_vec();
+    let mut stream = client
+        .call_server_streaming_usage("ChatService", "send", Bytes::from(request), RequestOptions::new())
+        .await
+        .unwrap();
+    let mut tokens = 0;
+    while let Some(token) = stream.next().await {
+        ReplyToken::decode(token.unwrap()).unwrap();
+        tokens += 1;
+    }
+
+    assert_eq!(tokens, 7);
+    assert_eq!(stream.usage(), Some(&Usage::new(4, 7)));
+}
+
+#[tokio::test]
+async fn test_max_tokens_truncates_the_reply() {
+    let client = ChatServiceClient::connect(spawn(ChatService::in_memory()).await).unwrap();
+
+    let mut request = send_request("", "a long message that will not fit");
+    request.max_tokens = 3;
+    let tokens = collect_reply(&client, &request).await;
+    assert_eq!(tokens.len(), 3);
+
+    // Truncation is not cancellation
+    let info = client
+        .get_session(&GetSessionRequest { session_id: tokens[0].session_id.clone() })
+        .await
+        .unwrap();
+    assert_eq!(info.cancelled_turns, 0);
+}
+
+#[tokio::test]
+async fn test_dropping_the_stream_cancels_generation() {
+    let service = ChatService::in_memory().with_token_interval(Duration::from_millis(20));
+    let sessions = service.sessions().clone();
+    let client = ChatServiceClient::connect(spawn(service).await).unwrap();
+
+    let words = vec!["word"; 40].join(" ");
+    let mut stream = client.send(&send_request("cancel-me", &words)).await.unwrap();
+    for _ in 0..2 {
+        stream.next().await.unwrap().unwrap();
+    }
+    drop(stream);
+
+    // The producer stops well before the 43 tokens it would have sent
+    let mut session = None;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        session = sessions.load("cancel-me");
+        if session.is_some() {
+            break;
+        }
+    }
+    let session = session.expect("cancelled turn is saved");
+    assert_eq!(session.turns.len(), 1);
+    assert!(session.turns[0].cancelled);
+    assert!(session.usage.output_tokens < 43);
+}
+
+#[tokio::test]
+async fn test_empty_message_is_rejected() {
+    let client = ChatServiceClient::connect(spawn(ChatService::in_memory()).await).unwrap();
+
+    let err = match client.send(&send_request("", "   ")).await {
+        Ok(_) => panic!("empty message was accepted"),
+        Err(err) => err,
+    };
+    match err {
+        QuillError::ProblemDetails(pd) => assert_eq!(pd.status, 400),
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[tokio::test]
+async fn test_unknown_session_is_not_found() {
+    let client = ChatServiceClient::connect(spawn(ChatService::in_memory()).await).unwrap();
+
+    let err = client
+        .get_session(&GetSessionRequest { session_id: "missing".to_string() })
+        .await
+        .unwrap_err();
+    match err {
+        QuillError::ProblemDetails(pd) => assert_eq!(pd.status, 404),
+        other => panic!("unexpected error: {other}"),
+    }
+}
@@ -5,6 +5,9 @@
 //! - Generate client and server code using quill-codegen
 //! - Implement the generated server trait
 //! - Use the generated client
+//!
+//! The service covers all four streaming modes: unary, server streaming,
+//! client streaming and bidirectional streaming.
 
 use bytes::Bytes;
 use quill_core::QuillError;
@@ -50,11 +53,42 @@ impl Greeter for GreeterService {
 
         Ok(Box::pin(stream))
     }
+
+    async fn say_hello_to_all(
+        &self,
+        mut request_stream: Pin<Box<dyn Stream<Item = Result<HelloRequest, QuillError>> + Send>>,
+    ) -> Result<HelloReply, QuillError> {
+        use futures::StreamExt;
+
+        let mut names = Vec::new();
+        while let Some(request) = request_stream.next().await {
+            names.push(request?.name);
+        }
+
+        Ok(HelloReply { message: format!("Hello, {}!", names.join(" and ")) })
+    }
+
+    async fn chat(
+        &self,
+        request_stream: Pin<Box<dyn Stream<Item = Result<HelloRequest, QuillError>> + Send>>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<HelloReply, QuillError>> + Send>>, QuillError> {
+        use futures::StreamExt;
+
+        let replies = request_stream.map(|request| {
+            request.map(|request| HelloReply { message: format!("Hello, {}!", request.name) })
+        });
+
+        Ok(Box::pin(replies))
+    }
 }
 
 /// Create a server with the greeter service
 pub fn create_server() -> quill_server::QuillServer {
-    let builder = quill_server::QuillServer::builder();
+    // Describe the service to tools that discover it at runtime
+    let builder = quill_server::QuillServer::builder()
+        .enable_reflection()
+        .file_descriptor_set(greeter::FILE_DESCRIPTOR_SET)
+        .expect("generated descriptor set is valid");
     let service = GreeterService;
     add_service(builder, service).build()
 }
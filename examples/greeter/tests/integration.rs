@@ -0,0 +1,79 @@
+//! End-to-end tests of the generated client and server in every streaming mode
+
+use futures::{stream, StreamExt};
+use greeter_example::greeter::greeter_client::GreeterClient;
+use greeter_example::{create_server, HelloRequest};
+use quill_core::QuillError;
+use std::time::Duration;
+
+async fn connect() -> GreeterClient {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = create_server().serve_with_listener(listener).await;
+    });
+    GreeterClient::connect(format!("http://{}", addr)).unwrap()
+}
+
+fn requests(names: &[&str]) -> impl futures::Stream<Item = Result<HelloRequest, QuillError>> {
+    let requests: Vec<_> =
+        names.iter().map(|name| Ok(HelloRequest { name: name.to_string() })).collect();
+    stream::iter(requests)
+}
+
+#[tokio::test]
+async fn test_unary() {
+    let client = connect().await;
+    let reply = client.say_hello(&HelloRequest { name: "Alice".to_string() }).await.unwrap();
+    assert_eq!(reply.message, "Hello, Alice!");
+}
+
+#[tokio::test]
+async fn test_server_streaming() {
+    let client = connect().await;
+    let replies: Vec<_> = client
+        .say_hello_stream(&HelloRequest { name: "Bob".to_string() })
+        .await
+        .unwrap()
+        .map(|reply| reply.unwrap().message)
+        .collect()
+        .await;
+    assert_eq!(replies.len(), 4);
+    assert_eq!(replies[0], "Hello, Bob!");
+}
+
+#[tokio::test]
+async fn test_client_streaming() {
+    let client = connect().await;
+    let reply = client.say_hello_to_all(requests(&["Ann", "Ben", "Cy"])).await.unwrap();
+    assert_eq!(reply.message, "Hello, Ann and Ben and Cy!");
+}
+
+#[tokio::test]
+async fn test_bidi_streaming() {
+    let client = connect().await;
+    let replies: Vec<_> = client
+        .chat(requests(&["Dee", "Eve"]))
+        .await
+        .unwrap()
+        .map(|reply| reply.unwrap().message)
+        .collect()
+        .await;
+    assert_eq!(replies, vec!["Hello, Dee!", "Hello, Eve!"]);
+}
+
+#[tokio::test]
+async fn test_bidi_streaming_replies_while_sending() {
+    let client = connect().await;
+    let (sender, receiver) = futures::channel::mpsc::unbounded();
+    let mut replies = client.chat(receiver).await.unwrap();
+
+    // Each reply arrives before the next request is sent
+    for name in ["Fay", "Gus"] {
+        sender.unbounded_send(Ok(HelloRequest { name: name.to_string() })).unwrap();
+        let reply = tokio::time::timeout(Duration::from_secs(5), replies.next()).await.unwrap();
+        assert_eq!(reply.unwrap().unwrap().message, format!("Hello, {}!", name));
+    }
+    drop(sender);
+    assert!(replies.next().await.is_none());
+}
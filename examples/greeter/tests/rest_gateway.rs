@@ -0,0 +1,93 @@
+//! REST gateway in front of the greeter, using the generated JSON support
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use greeter_example::greeter::message_converter;
+use greeter_example::{create_server, HelloReply};
+use http_body_util::BodyExt;
+use quill_client::QuillClient;
+use quill_rest_gateway::{HttpMethod, MessageConverter, RestGatewayBuilder, RouteMapping};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+async fn spawn() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let _ = create_server().serve_with_listener(listener).await;
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_gateway_maps_json_with_generated_converter() {
+    let url = spawn().await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+
+    let route = RouteMapping::new("Greeter", "say_hello")
+        .add_mapping(HttpMethod::Post, "/v1/greetings")
+        .unwrap();
+    let router = RestGatewayBuilder::new(client)
+        .base_path("")
+        .with_converter(message_converter().unwrap())
+        .route(route)
+        .build()
+        .router();
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/greetings")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({"name": "Ada"}).to_string()))
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!({"message": "Hello, Ada!"}));
+
+    // The messages themselves derive serde too
+    let reply: HelloReply = serde_json::from_value(body).unwrap();
+    assert_eq!(reply.message, "Hello, Ada!");
+}
+
+#[tokio::test]
+async fn test_gateway_discovers_schema_by_reflection() {
+    let url = spawn().await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+    let converter = MessageConverter::from_reflection(&client).await.unwrap();
+    assert!(converter.get_input_descriptor("greeter.v1.Greeter", "SayHelloToAll").is_ok());
+
+    let route =
+        RouteMapping::new("Greeter", "say_hello").add_mapping(HttpMethod::Post, "/v1/greetings").unwrap();
+    let router = RestGatewayBuilder::new(client)
+        .base_path("")
+        .with_converter(converter)
+        .route(route)
+        .build()
+        .router();
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/greetings")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({"name": "Grace"}).to_string()))
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!({"message": "Hello, Grace!"}));
+}
+
+#[tokio::test]
+async fn test_reflection_of_unknown_service() {
+    let url = spawn().await;
+    let client = QuillClient::builder().base_url(&url).build().unwrap();
+
+    let descriptors = client.file_descriptor_set(Some("Greeter")).await.unwrap();
+    assert!(!descriptors.is_empty());
+    assert!(client.file_descriptor_set(Some("billing.v1.Billing")).await.is_err());
+}
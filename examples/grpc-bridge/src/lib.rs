@@ -78,6 +78,7 @@ impl EchoServiceBridge {
             quill_base_url: quill_base_url.to_string(),
             enable_logging: true,
             forward_metadata: true,
+            grpc_backend_url: "http://localhost:50051".to_string(),
         };
 
         let bridge = quill_grpc_bridge::GrpcBridge::new(config)?;
@@ -147,6 +148,7 @@ mod tests {
             quill_base_url: "http://localhost:8080".to_string(),
             enable_logging: true,
             forward_metadata: true,
+            grpc_backend_url: "http://localhost:50051".to_string(),
         };
 
         let bridge = GrpcBridge::new(config);
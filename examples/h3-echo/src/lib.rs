@@ -62,14 +62,9 @@ mod tests {
 
     /// Test HTTP/3 echo integration
     ///
-    /// This test demonstrates the HTTP/3 server and client setup.
-    /// Note: Full end-to-end testing requires the H3Service trait to pass
-    /// request bodies to handlers, which is planned for a future update.
-    ///
-    /// For now, this test validates the configuration and startup of both
-    /// the HTTP/3 server and client.
+    /// The request body reaches the registered handler and its response
+    /// comes back over the same QUIC connection.
     #[tokio::test]
-    #[ignore = "Full end-to-end HTTP/3 requires H3Service body handling (WIP)"]
     async fn test_h3_echo_integration() {
         // Install rustls crypto provider
         let _ = rustls::crypto::ring::default_provider().install_default();
@@ -172,7 +167,9 @@ mod tests {
     /// by using a simple echo service implementation.
     #[tokio::test]
     async fn test_h3_transport_layer() {
-        use quill_transport::{H3ClientBuilder, H3ServerBuilder, BoxFuture, H3Service};
+        use quill_transport::{
+            full_body, BoxFuture, H3Body, H3ClientBuilder, H3ServerBuilder, H3Service,
+        };
         use http::{Request, Response, StatusCode};
 
         // Install rustls crypto provider
@@ -186,14 +183,16 @@ mod tests {
         struct SimpleEchoService;
 
         impl H3Service for SimpleEchoService {
-            fn call(&self, req: Request<()>) -> BoxFuture<Result<Response<Bytes>, StatusCode>> {
+            fn call(&self, req: Request<Bytes>) -> BoxFuture<Result<Response<H3Body>, StatusCode>> {
                 let path = req.uri().path().to_string();
+                let body = req.into_body();
                 Box::pin(async move {
-                    // Return the path as the response body
+                    // Return the path and request body as the response body
+                    let echo = format!("Echo: {} {}", path, String::from_utf8_lossy(&body));
                     Ok(Response::builder()
                         .status(StatusCode::OK)
                         .header("content-type", "text/plain")
-                        .body(Bytes::from(format!("Echo: {}", path)))
+                        .body(full_body(Bytes::from(echo)))
                         .unwrap())
                 })
             }
@@ -240,6 +239,7 @@ mod tests {
                 assert_eq!(resp.status(), StatusCode::OK);
                 let body = resp.into_body();
                 assert!(body.starts_with(b"Echo: "));
+                assert!(body.ends_with(b" Hello"));
                 tracing::info!("H3 transport test passed!");
             }
             Err(e) => {
@@ -116,7 +116,9 @@ pub async fn handle_tail(request: Bytes) -> Result<Bytes, QuillError> {
 mod tests {
     use super::*;
     use prost::Message;
-    use quill_transport::{BoxFuture, H3ClientBuilder, H3ServerBuilder, H3Service};
+    use quill_transport::{
+        full_body, BoxFuture, H3Body, H3ClientBuilder, H3ServerBuilder, H3Service,
+    };
     use http::{Request, Response, StatusCode};
     use std::net::SocketAddr;
     use tokio::time::{sleep, Duration};
@@ -216,7 +218,7 @@ mod tests {
         struct StreamingLogService;
 
         impl H3Service for StreamingLogService {
-            fn call(&self, req: Request<()>) -> BoxFuture<Result<Response<Bytes>, StatusCode>> {
+            fn call(&self, req: Request<Bytes>) -> BoxFuture<Result<Response<H3Body>, StatusCode>> {
                 let path = req.uri().path().to_string();
                 Box::pin(async move {
                     if path.contains("Tail") {
@@ -226,12 +228,12 @@ mod tests {
                             .status(StatusCode::OK)
                             .header("content-type", "application/proto")
                             .header("x-quill-streaming", "true")
-                            .body(stream_data)
+                            .body(full_body(stream_data))
                             .unwrap())
                     } else {
                         Ok(Response::builder()
                             .status(StatusCode::NOT_FOUND)
-                            .body(Bytes::from("Not found"))
+                            .body(full_body(Bytes::from("Not found")))
                             .unwrap())
                     }
                 })
@@ -320,14 +322,17 @@ mod tests {
         struct LargeStreamService;
 
         impl H3Service for LargeStreamService {
-            fn call(&self, _req: Request<()>) -> BoxFuture<Result<Response<Bytes>, StatusCode>> {
+            fn call(
+                &self,
+                _req: Request<Bytes>,
+            ) -> BoxFuture<Result<Response<H3Body>, StatusCode>> {
                 Box::pin(async move {
                     // Generate a large streaming response
                     let stream_data = generate_log_stream(100);
                     Ok(Response::builder()
                         .status(StatusCode::OK)
                         .header("content-type", "application/proto")
-                        .body(stream_data)
+                        .body(full_body(stream_data))
                         .unwrap())
                 })
             }
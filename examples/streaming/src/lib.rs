@@ -79,7 +79,7 @@ mod tests {
             RpcResponse::Streaming(_) => {
                 // Expected
             }
-            RpcResponse::Unary(_) => panic!("Expected streaming response"),
+            _ => panic!("Expected streaming response"),
         }
     }
 }